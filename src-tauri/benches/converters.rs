@@ -0,0 +1,89 @@
+// Benchmarks for the streaming JSONL converters
+//
+// Run with `cargo bench --bench converters`. Compares reading a large
+// synthetic Claude Code session line by line against loading it whole, and
+// measures a full streaming conversion to canonical JSONL.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use guidemode_desktop::providers::canonical::converter::ToCanonical;
+use guidemode_desktop::providers::claude::types::ClaudeEntry;
+use guidemode_desktop::providers::common::{CanonicalWriter, JsonlReader};
+use serde_json::json;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+/// Write a Claude Code session of `messages` alternating user/assistant lines
+fn write_session(path: &Path, messages: usize) {
+    let mut file = std::io::BufWriter::new(fs::File::create(path).unwrap());
+    let text = "Refactor the parser so it streams its input. ".repeat(20);
+    for i in 0..messages {
+        let role = if i % 2 == 0 { "user" } else { "assistant" };
+        let line = json!({
+            "type": role,
+            "uuid": format!("msg-{}", i),
+            "parentUuid": (i > 0).then(|| format!("msg-{}", i - 1)),
+            "sessionId": "bench-session",
+            "timestamp": "2025-01-01T10:00:00.000Z",
+            "cwd": "/tmp/bench",
+            "message": {"role": role, "content": [{"type": "text", "text": text}]}
+        });
+        writeln!(file, "{}", line).unwrap();
+    }
+}
+
+fn bench_converters(c: &mut Criterion) {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let mut group = c.benchmark_group("claude_jsonl");
+    group.sample_size(10);
+
+    for messages in [1_000, 10_000] {
+        let source = temp_dir.path().join(format!("session-{}.jsonl", messages));
+        write_session(&source, messages);
+        group.throughput(Throughput::Bytes(fs::metadata(&source).unwrap().len()));
+
+        group.bench_with_input(
+            BenchmarkId::new("read_to_string", messages),
+            &source,
+            |b, path| {
+                b.iter(|| {
+                    let content = fs::read_to_string(path).unwrap();
+                    content
+                        .lines()
+                        .filter(|line| serde_json::from_str::<ClaudeEntry>(line).is_ok())
+                        .count()
+                })
+            },
+        );
+
+        group.bench_with_input(BenchmarkId::new("stream", messages), &source, |b, path| {
+            b.iter(|| {
+                let mut reader = JsonlReader::open(path).unwrap();
+                let mut parsed = 0;
+                while let Some((_, line)) = reader.next_line().unwrap() {
+                    parsed += serde_json::from_str::<ClaudeEntry>(line).is_ok() as usize;
+                }
+                parsed
+            })
+        });
+
+        group.bench_with_input(BenchmarkId::new("convert", messages), &source, |b, path| {
+            b.iter(|| {
+                let mut reader = JsonlReader::open(path).unwrap();
+                let mut writer = CanonicalWriter::create(temp_dir.path()).unwrap();
+                while let Some((_, line)) = reader.next_line().unwrap() {
+                    let entry: ClaudeEntry = serde_json::from_str(line).unwrap();
+                    if let Ok(Some(message)) = entry.to_canonical() {
+                        writer.write(&message).unwrap();
+                    }
+                }
+                writer.message_count()
+            })
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_converters);
+criterion_main!(benches);
@@ -0,0 +1,10 @@
+#![no_main]
+
+//! Canonical JSONL as the validator reads it after every conversion
+
+use guidemode_desktop::providers::canonical::validation::validate_jsonl;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    validate_jsonl(&String::from_utf8_lossy(data));
+});
@@ -0,0 +1,29 @@
+#![no_main]
+
+//! A Codex rollout, converted line by line as the Codex watcher converts it
+
+use guidemode_desktop::providers::canonical::converter::ToCanonical;
+use guidemode_desktop::providers::codex::converter::apply_turn_model;
+use guidemode_desktop::providers::codex::CodexMessage;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let rollout = String::from_utf8_lossy(data);
+    let mut turn_model: Option<String> = None;
+
+    for line in rollout.lines() {
+        let Ok(message) = serde_json::from_str::<CodexMessage>(line) else {
+            continue;
+        };
+        message.get_session_id();
+        message.get_cwd();
+        message.get_git_branch();
+        message.get_version();
+        if let Some(model) = message.get_model() {
+            turn_model = Some(model);
+        }
+        if let Ok(Some(mut canonical)) = message.to_canonical() {
+            apply_turn_model(&mut canonical, turn_model.as_deref());
+        }
+    }
+});
@@ -0,0 +1,24 @@
+#![no_main]
+
+//! A blob from a Cursor session database, decoded and converted as the
+//! Cursor watcher does
+
+use guidemode_desktop::providers::cursor::converter::CursorMessageWithRaw;
+use guidemode_desktop::providers::cursor::protobuf::CursorMessage;
+use guidemode_desktop::providers::cursor::timing::SessionTiming;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(message) = CursorMessage::decode_from_bytes(data) else {
+        return;
+    };
+    message.get_role();
+    message.get_id();
+
+    let timing = SessionTiming {
+        created_at: 1_730_000_000_000,
+        updated_at: Some(1_730_000_600_000),
+        total_messages: 8,
+    };
+    let _ = CursorMessageWithRaw::new(&message, data, &timing, 3).to_canonical_split();
+});
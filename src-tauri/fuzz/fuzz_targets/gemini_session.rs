@@ -0,0 +1,16 @@
+#![no_main]
+
+//! A Gemini session file, parsed and converted as the Gemini watcher does
+
+use guidemode_desktop::providers::gemini::converter::convert_session_to_canonical;
+use guidemode_desktop::providers::gemini::parser::GeminiSession;
+use guidemode_desktop::providers::gemini::utils::infer_cwd_from_session;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok((session, _skipped)) = GeminiSession::from_reader_lenient(data) else {
+        return;
+    };
+    let cwd = infer_cwd_from_session(&session, &session.project_hash);
+    let _ = convert_session_to_canonical(&session, cwd);
+});
@@ -0,0 +1,9 @@
+-- Cache of verified Cursor project hash → CWD mappings
+-- Cursor names chat directories with the MD5 of the workspace path, which
+-- cannot be reversed reliably from the projects folder name alone
+CREATE TABLE IF NOT EXISTS cursor_project_paths (
+    hash TEXT PRIMARY KEY,
+    cwd TEXT NOT NULL,
+    source TEXT NOT NULL,
+    verified_at INTEGER NOT NULL
+);
@@ -0,0 +1,17 @@
+-- Per-session tool usage, computed locally from canonical JSONL
+-- One row per (session, tool); aggregated across sessions for the dashboard
+CREATE TABLE IF NOT EXISTS tool_usage_stats (
+    session_id TEXT NOT NULL,
+    provider TEXT NOT NULL,
+    project_name TEXT NOT NULL,
+    tool_name TEXT NOT NULL,
+    invocation_count INTEGER NOT NULL DEFAULT 0,
+    result_count INTEGER NOT NULL DEFAULT 0,
+    error_count INTEGER NOT NULL DEFAULT 0,
+    total_result_bytes INTEGER NOT NULL DEFAULT 0,
+    updated_at INTEGER NOT NULL,
+    PRIMARY KEY (session_id, tool_name)
+);
+
+CREATE INDEX IF NOT EXISTS tool_usage_stats_tool_idx ON tool_usage_stats(tool_name);
+CREATE INDEX IF NOT EXISTS tool_usage_stats_project_idx ON tool_usage_stats(project_name);
@@ -0,0 +1,5 @@
+-- Context window utilization over the whole session, computed locally
+-- context_utilization_percent only describes the most recent request
+ALTER TABLE session_metrics ADD COLUMN peak_context_utilization_percent REAL;
+ALTER TABLE session_metrics ADD COLUMN avg_context_utilization_percent REAL;
+ALTER TABLE session_metrics ADD COLUMN context_ceiling_hits INTEGER; -- times utilization rose to 90%+
@@ -0,0 +1,58 @@
+-- Each session's contribution to the usage rollups. Re-ingesting a session
+-- swaps its previous contribution for the new one instead of adding to it.
+-- day/week are local dates (YYYY-MM-DD); weeks start on Monday
+CREATE TABLE IF NOT EXISTS session_usage (
+    session_id TEXT PRIMARY KEY,
+    provider TEXT NOT NULL,
+    project_name TEXT NOT NULL,
+    day TEXT NOT NULL,
+    week TEXT NOT NULL,
+    input_tokens INTEGER NOT NULL DEFAULT 0,
+    output_tokens INTEGER NOT NULL DEFAULT 0,
+    cache_created INTEGER NOT NULL DEFAULT 0,
+    cache_read INTEGER NOT NULL DEFAULT 0,
+    estimated_cost REAL NOT NULL DEFAULT 0, -- USD at list price
+    updated_at INTEGER NOT NULL
+);
+
+-- Sessions, tokens and cost per day/week, provider and project
+CREATE TABLE IF NOT EXISTS usage_rollups (
+    granularity TEXT NOT NULL, -- 'day' or 'week'
+    period_start TEXT NOT NULL,
+    provider TEXT NOT NULL,
+    project_name TEXT NOT NULL,
+    session_count INTEGER NOT NULL DEFAULT 0,
+    input_tokens INTEGER NOT NULL DEFAULT 0,
+    output_tokens INTEGER NOT NULL DEFAULT 0,
+    cache_created INTEGER NOT NULL DEFAULT 0,
+    cache_read INTEGER NOT NULL DEFAULT 0,
+    estimated_cost REAL NOT NULL DEFAULT 0,
+    PRIMARY KEY (granularity, period_start, provider, project_name)
+);
+
+-- Backfill from existing sessions; costs are filled in as sessions are recomputed
+INSERT OR IGNORE INTO session_usage (
+    session_id, provider, project_name, day, week,
+    input_tokens, output_tokens, cache_created, cache_read, estimated_cost, updated_at
+)
+SELECT
+    s.session_id, s.provider, s.project_name,
+    date(COALESCE(s.session_start_time, s.created_at) / 1000, 'unixepoch', 'localtime'),
+    date(COALESCE(s.session_start_time, s.created_at) / 1000, 'unixepoch', 'localtime', 'weekday 0', '-6 days'),
+    COALESCE(m.total_input_tokens, 0), COALESCE(m.total_output_tokens, 0),
+    COALESCE(m.total_cache_created, 0), COALESCE(m.total_cache_read, 0),
+    0, CAST(strftime('%s', 'now') AS INTEGER) * 1000
+FROM agent_sessions s
+LEFT JOIN session_metrics m ON m.session_id = s.session_id;
+
+INSERT OR IGNORE INTO usage_rollups
+SELECT 'day', day, provider, project_name, COUNT(*),
+       SUM(input_tokens), SUM(output_tokens), SUM(cache_created), SUM(cache_read), SUM(estimated_cost)
+FROM session_usage
+GROUP BY day, provider, project_name;
+
+INSERT OR IGNORE INTO usage_rollups
+SELECT 'week', week, provider, project_name, COUNT(*),
+       SUM(input_tokens), SUM(output_tokens), SUM(cache_created), SUM(cache_read), SUM(estimated_cost)
+FROM session_usage
+GROUP BY week, provider, project_name;
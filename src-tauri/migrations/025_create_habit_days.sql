@@ -0,0 +1,23 @@
+-- Per-day habits for local streak tracking, one row per local day with sessions
+-- Refreshed from session_usage/session_metrics whenever a session on that day changes
+CREATE TABLE IF NOT EXISTS habit_days (
+    day TEXT PRIMARY KEY, -- YYYY-MM-DD, local time
+    session_count INTEGER NOT NULL DEFAULT 0,
+    plan_mode_sessions INTEGER NOT NULL DEFAULT 0,
+    user_messages INTEGER NOT NULL DEFAULT 0,
+    interruptions INTEGER NOT NULL DEFAULT 0,
+    updated_at INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS session_usage_day_idx ON session_usage(day);
+
+-- Backfill from existing sessions
+INSERT OR REPLACE INTO habit_days
+SELECT u.day, COUNT(*),
+       SUM(COALESCE(m.used_plan_mode, 0)),
+       SUM(COALESCE(m.total_user_messages, 0)),
+       SUM(COALESCE(m.total_interruptions, 0)),
+       CAST(strftime('%s', 'now') AS INTEGER) * 1000
+FROM session_usage u
+LEFT JOIN session_metrics m ON m.session_id = u.session_id
+GROUP BY u.day;
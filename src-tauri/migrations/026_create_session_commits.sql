@@ -0,0 +1,12 @@
+-- Commits authored during a session on its branch, linked from the local git repository
+CREATE TABLE IF NOT EXISTS session_commits (
+    session_id TEXT NOT NULL,
+    commit_hash TEXT NOT NULL,
+    summary TEXT NOT NULL,
+    author_name TEXT NOT NULL,
+    author_email TEXT NOT NULL,
+    authored_at INTEGER NOT NULL, -- Unix milliseconds
+    PRIMARY KEY (session_id, commit_hash)
+);
+
+CREATE INDEX IF NOT EXISTS session_commits_hash_idx ON session_commits(commit_hash);
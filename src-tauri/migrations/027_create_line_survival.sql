@@ -0,0 +1,13 @@
+-- Lines added by a session's commits and how many survive window_days after it ended
+-- One row per (session, window); the rest of the lines were rewritten or removed (churn)
+CREATE TABLE IF NOT EXISTS line_survival (
+    session_id TEXT NOT NULL,
+    window_days INTEGER NOT NULL,
+    project_name TEXT NOT NULL,
+    lines_added INTEGER NOT NULL DEFAULT 0,
+    lines_surviving INTEGER NOT NULL DEFAULT 0,
+    computed_at INTEGER NOT NULL,
+    PRIMARY KEY (session_id, window_days)
+);
+
+CREATE INDEX IF NOT EXISTS line_survival_project_idx ON line_survival(project_name);
@@ -0,0 +1,15 @@
+-- Cached diffs between two commits (immutable, so never stale)
+-- Bounded by total size; least recently accessed entries are evicted first
+CREATE TABLE IF NOT EXISTS git_diff_cache (
+    repo_path TEXT NOT NULL, -- git directory of the checkout
+    from_commit TEXT NOT NULL,
+    to_commit TEXT NOT NULL,
+    filters TEXT NOT NULL, -- diff options the entry was computed with
+    diff_json TEXT NOT NULL,
+    size_bytes INTEGER NOT NULL,
+    created_at INTEGER NOT NULL,
+    last_accessed_at INTEGER NOT NULL,
+    PRIMARY KEY (repo_path, from_commit, to_commit, filters)
+);
+
+CREATE INDEX IF NOT EXISTS git_diff_cache_accessed_idx ON git_diff_cache(last_accessed_at);
@@ -0,0 +1,11 @@
+-- Working tree changes left behind by sessions that never committed, captured at session end
+CREATE TABLE IF NOT EXISTS uncommitted_snapshots (
+    session_id TEXT PRIMARY KEY,
+    head_commit TEXT NOT NULL,
+    files_changed INTEGER NOT NULL,
+    lines_added INTEGER NOT NULL,
+    lines_removed INTEGER NOT NULL,
+    patch TEXT NOT NULL,
+    patch_truncated INTEGER NOT NULL DEFAULT 0,
+    captured_at INTEGER NOT NULL -- Unix milliseconds
+);
@@ -0,0 +1,16 @@
+-- Versions of context files (CLAUDE.md, AGENTS.md, GEMINI.md), recorded when a scan finds new content
+CREATE TABLE IF NOT EXISTS context_file_snapshots (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    cwd TEXT NOT NULL, -- Directory that was scanned
+    file_path TEXT NOT NULL, -- Absolute path
+    relative_path TEXT NOT NULL,
+    content_hash TEXT NOT NULL, -- SHA256 of content
+    content TEXT NOT NULL,
+    size INTEGER NOT NULL,
+    token_count INTEGER NOT NULL,
+    modified_at INTEGER NOT NULL, -- File modification time, Unix milliseconds
+    captured_at INTEGER NOT NULL -- Unix milliseconds
+);
+
+CREATE INDEX IF NOT EXISTS context_file_snapshots_file_idx
+    ON context_file_snapshots(file_path, modified_at);
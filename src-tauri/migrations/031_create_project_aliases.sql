@@ -0,0 +1,9 @@
+-- User-defined project name aliases: sessions and uploads named after an alias
+-- are attributed to the canonical project name instead
+CREATE TABLE IF NOT EXISTS project_aliases (
+    alias TEXT PRIMARY KEY COLLATE NOCASE,
+    canonical_name TEXT NOT NULL,
+    created_at INTEGER NOT NULL -- Unix milliseconds
+);
+
+CREATE INDEX IF NOT EXISTS project_aliases_canonical_idx ON project_aliases(canonical_name);
@@ -0,0 +1,12 @@
+-- What historical scans produced for each provider source file, so rescans
+-- can skip files that haven't changed
+CREATE TABLE IF NOT EXISTS scan_cache (
+    provider TEXT NOT NULL,
+    source_path TEXT NOT NULL,
+    size INTEGER NOT NULL,
+    mtime_ms INTEGER NOT NULL,
+    content_hash TEXT NOT NULL, -- SHA256 of the source file
+    session TEXT NOT NULL, -- JSON of the scanned session
+    scanned_at INTEGER NOT NULL, -- Unix milliseconds
+    PRIMARY KEY (provider, source_path)
+);
@@ -0,0 +1,12 @@
+-- Content hashes of uploaded sessions, so restarts don't re-upload files the
+-- server already has. Only the latest hash per session is kept; earlier ones
+-- are superseded by it.
+CREATE TABLE IF NOT EXISTS uploaded_hashes (
+    provider TEXT NOT NULL,
+    session_id TEXT NOT NULL,
+    hash TEXT NOT NULL, -- SHA256 of the uploaded file or content
+    uploaded_at INTEGER NOT NULL, -- Unix milliseconds
+    PRIMARY KEY (provider, session_id, hash)
+);
+
+CREATE INDEX IF NOT EXISTS idx_uploaded_hashes_hash ON uploaded_hashes(hash);
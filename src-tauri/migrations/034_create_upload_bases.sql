@@ -0,0 +1,10 @@
+-- The transcript the server last received for each session, so a session that
+-- grew since can be uploaded as a delta of its appended bytes
+CREATE TABLE IF NOT EXISTS upload_bases (
+    provider TEXT NOT NULL,
+    session_id TEXT NOT NULL,
+    content_hash TEXT NOT NULL, -- SHA256 of the uploaded file bytes
+    content_length INTEGER NOT NULL, -- Length of the uploaded file in bytes
+    uploaded_at INTEGER NOT NULL, -- Unix milliseconds
+    PRIMARY KEY (provider, session_id)
+);
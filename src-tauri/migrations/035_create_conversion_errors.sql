@@ -0,0 +1,16 @@
+-- Source lines converters couldn't parse or convert, kept so they can be
+-- inspected and the session converted again once the app is updated
+CREATE TABLE IF NOT EXISTS conversion_errors (
+    provider TEXT NOT NULL,
+    session_id TEXT NOT NULL,
+    source_path TEXT NOT NULL,
+    line_number INTEGER NOT NULL, -- 1-based line in the source file
+    raw_line TEXT NOT NULL, -- Truncated to a few kilobytes
+    error TEXT NOT NULL,
+    app_version TEXT NOT NULL, -- App version that failed to convert the line
+    provider_version TEXT, -- Provider CLI version detected at the time, if known
+    created_at INTEGER NOT NULL, -- Unix milliseconds
+    PRIMARY KEY (provider, session_id, line_number)
+);
+
+CREATE INDEX IF NOT EXISTS conversion_errors_app_version_idx ON conversion_errors(app_version);
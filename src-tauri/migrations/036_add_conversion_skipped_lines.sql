@@ -0,0 +1,3 @@
+-- Source lines left out of the session's canonical file because they couldn't
+-- be converted; metrics of sessions with skipped lines are incomplete
+ALTER TABLE agent_sessions ADD COLUMN conversion_skipped_lines INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,7 @@
+-- Session length excluding idle gaps between messages, next to the wall-clock
+-- session_length_minutes; filled in as sessions are recomputed
+ALTER TABLE session_metrics ADD COLUMN active_length_minutes REAL;
+ALTER TABLE session_metrics ADD COLUMN idle_gap_count INTEGER;
+
+ALTER TABLE session_usage ADD COLUMN active_minutes REAL NOT NULL DEFAULT 0;
+ALTER TABLE usage_rollups ADD COLUMN active_minutes REAL NOT NULL DEFAULT 0;
@@ -0,0 +1,6 @@
+-- Each session's place in the upload queue, so queued items show when they
+-- were queued and how often they were tried instead of guessed values
+ALTER TABLE agent_sessions ADD COLUMN upload_queued_at INTEGER; -- Unix milliseconds, cleared once synced
+ALTER TABLE agent_sessions ADD COLUMN upload_attempts INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE agent_sessions ADD COLUMN upload_last_attempt_at INTEGER; -- Unix milliseconds
+ALTER TABLE agent_sessions ADD COLUMN upload_priority INTEGER NOT NULL DEFAULT 0; -- Higher uploads first
@@ -0,0 +1,3 @@
+-- Projects excluded from syncing, and the sessions skipped because of it
+ALTER TABLE projects ADD COLUMN sync_excluded INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE agent_sessions ADD COLUMN sync_skipped INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,5 @@
+-- When a synced session's rating or assessment last changed locally; the
+-- change is sent as a metadata patch instead of a full re-upload
+ALTER TABLE agent_sessions ADD COLUMN metadata_dirty_at INTEGER; -- Unix milliseconds, cleared once patched
+
+CREATE INDEX IF NOT EXISTS agent_sessions_metadata_dirty_idx ON agent_sessions(metadata_dirty_at);
@@ -0,0 +1,3 @@
+-- When a synced session's file changed (the conversation was resumed) and it
+-- went back to pending to upload the new version
+ALTER TABLE agent_sessions ADD COLUMN upload_superseded_at INTEGER; -- Unix milliseconds, cleared once synced
@@ -0,0 +1,6 @@
+-- Sessions that resume an earlier conversation (`claude --resume`, OpenCode
+-- forks) and repeat it before continuing
+ALTER TABLE agent_sessions ADD COLUMN resumed_from TEXT; -- session_id of the session it continues
+ALTER TABLE agent_sessions ADD COLUMN first_message_uuid TEXT;
+
+CREATE INDEX IF NOT EXISTS agent_sessions_first_message_uuid_idx ON agent_sessions(first_message_uuid);
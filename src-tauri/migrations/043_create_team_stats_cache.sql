@@ -0,0 +1,10 @@
+-- Team aggregates fetched from the server, so the dashboard can compare
+-- against the team offline and without refetching on every view
+CREATE TABLE IF NOT EXISTS team_stats_cache (
+    tenant_id TEXT NOT NULL,
+    period_start TEXT NOT NULL, -- YYYY-MM-DD, local
+    period_end TEXT NOT NULL,
+    stats_json TEXT NOT NULL, -- server response
+    fetched_at INTEGER NOT NULL,
+    PRIMARY KEY (tenant_id, period_start, period_end)
+);
@@ -0,0 +1,5 @@
+-- The tenant each session belongs to, so switching accounts can't upload one
+-- organization's sessions to another. NULL until a tenant is logged in to.
+ALTER TABLE agent_sessions ADD COLUMN tenant_id TEXT;
+
+CREATE INDEX IF NOT EXISTS agent_sessions_tenant_idx ON agent_sessions(tenant_id);
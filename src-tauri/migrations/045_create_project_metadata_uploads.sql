@@ -0,0 +1,11 @@
+-- Project metadata last uploaded to each server, so unchanged metadata
+-- isn't sent again for every session of the project
+CREATE TABLE IF NOT EXISTS project_metadata_uploads (
+    server_url TEXT NOT NULL,
+    tenant_id TEXT NOT NULL DEFAULT '', -- empty when not logged in to a tenant
+    project_name TEXT NOT NULL,
+    body_hash TEXT NOT NULL, -- SHA-256 of the request body
+    etag TEXT, -- server's ETag for the project, if it sent one
+    uploaded_at INTEGER NOT NULL,
+    PRIMARY KEY (server_url, tenant_id, project_name)
+);
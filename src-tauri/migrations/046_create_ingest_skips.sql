@@ -0,0 +1,12 @@
+-- Sessions found on disk that scans or syncs left out, and why, so a session
+-- that isn't showing up can be explained. A file's row is cleared once it
+-- makes it through
+CREATE TABLE IF NOT EXISTS ingest_skips (
+    provider TEXT NOT NULL,
+    source_path TEXT NOT NULL,
+    session_id TEXT, -- NULL when the source couldn't be parsed
+    reason TEXT NOT NULL, -- no_timestamps, too_large, filtered_project or failed
+    detail TEXT NOT NULL,
+    skipped_at INTEGER NOT NULL, -- Unix milliseconds
+    PRIMARY KEY (provider, source_path)
+);
@@ -0,0 +1,3 @@
+-- Why a session was skipped, when the quality gate skipped it rather than a
+-- project exclusion
+ALTER TABLE agent_sessions ADD COLUMN sync_skip_reason TEXT;
@@ -0,0 +1,9 @@
+-- Working directories of projects merged into another, so sessions found
+-- there later join the project they were merged into
+CREATE TABLE IF NOT EXISTS project_merges (
+    cwd TEXT PRIMARY KEY,
+    project_id TEXT NOT NULL,
+    merged_at INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS project_merges_project_id_idx ON project_merges(project_id);
@@ -0,0 +1,10 @@
+-- Sessions moved to an archive file, so scans and watchers don't save them
+-- again until the archive is restored
+CREATE TABLE IF NOT EXISTS archived_sessions (
+    session_id TEXT PRIMARY KEY,
+    provider TEXT NOT NULL,
+    archive_path TEXT NOT NULL,
+    archived_at INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS archived_sessions_archive_path_idx ON archived_sessions(archive_path);
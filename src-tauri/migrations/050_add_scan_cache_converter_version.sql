@@ -0,0 +1,4 @@
+-- Cached scans are only reused by the converter that produced them, and only
+-- while the sidechain files merged into the session are unchanged
+ALTER TABLE scan_cache ADD COLUMN converter_version TEXT NOT NULL DEFAULT '';
+ALTER TABLE scan_cache ADD COLUMN sidechains TEXT NOT NULL DEFAULT '[]'; -- JSON of each merged file's path, size and mtime
@@ -0,0 +1,3 @@
+-- When a session synced with its metrics only because the server couldn't
+-- take its transcript; it goes back to pending once the server can
+ALTER TABLE agent_sessions ADD COLUMN transcript_deferred_at INTEGER; -- Unix milliseconds
@@ -0,0 +1,6 @@
+-- Uploaded hashes belong to the tenant they were uploaded for, so one
+-- tenant's uploads never count as another's, even once their session is gone
+ALTER TABLE uploaded_hashes ADD COLUMN tenant_id TEXT;
+UPDATE uploaded_hashes SET tenant_id = (
+    SELECT s.tenant_id FROM agent_sessions s WHERE s.session_id = uploaded_hashes.session_id
+);
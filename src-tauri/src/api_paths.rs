@@ -0,0 +1,191 @@
+//! Server API endpoint paths
+//!
+//! Endpoints are requested at `{server_url}{base path}/{endpoint}`, where
+//! `endpoint` is the path under the API (e.g. `agent-sessions/upload-v2`).
+//! The base path is `/api` unless `apiBasePath` in config.json sets another,
+//! for deployments that mount the API under a prefix.
+//!
+//! A server can also describe its layout in a discovery document at
+//! `/.well-known/guidemode.json`, fetched with each health check:
+//!
+//! ```json
+//! {"apiBasePath": "/guidemode/api", "endpoints": {"agent-sessions/upload-v2": "/ingest/v2"}}
+//! ```
+//!
+//! An endpoint listed there is used as given, relative to the server URL.
+//! Otherwise the configured base path wins over the discovered one.
+
+use crate::config::load_config;
+use crate::logging::log_info;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+pub const DEFAULT_API_BASE_PATH: &str = "/api";
+
+pub const HEALTH: &str = "health";
+pub const CHECK_HASH: &str = "agent-sessions/check-hash";
+pub const SESSION_UPLOAD: &str = "agent-sessions/upload";
+pub const SESSION_UPLOAD_V2: &str = "agent-sessions/upload-v2";
+pub const SESSION_UPLOAD_DELTA: &str = "agent-sessions/upload-delta";
+pub const SESSION_METADATA: &str = "agent-sessions/metadata";
+pub const METRICS_UPLOAD: &str = "session-metrics/upload";
+pub const PROJECTS: &str = "projects";
+pub const PROJECT_MERGES: &str = "projects/merge";
+
+const DISCOVERY_PATH: &str = "/.well-known/guidemode.json";
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Discovery {
+    pub api_base_path: Option<String>,
+    /// Endpoint -> path relative to the server URL
+    #[serde(default)]
+    pub endpoints: HashMap<String, String>,
+}
+
+/// Discovery document of the server it was fetched from
+static DISCOVERED: Mutex<Option<(String, Discovery)>> = Mutex::new(None);
+
+/// `path` with a leading slash and no trailing one; empty stays empty
+fn normalize(path: &str) -> String {
+    let path = path.trim().trim_end_matches('/');
+    if path.is_empty() || path.starts_with('/') {
+        path.to_string()
+    } else {
+        format!("/{}", path)
+    }
+}
+
+/// Path of `endpoint` on a server, relative to its URL
+fn resolve(endpoint: &str, configured_base: Option<&str>, discovery: Option<&Discovery>) -> String {
+    if let Some(path) = discovery.and_then(|discovery| discovery.endpoints.get(endpoint)) {
+        return normalize(path);
+    }
+    let base = configured_base
+        .or_else(|| discovery.and_then(|discovery| discovery.api_base_path.as_deref()))
+        .unwrap_or(DEFAULT_API_BASE_PATH);
+    format!("{}/{}", normalize(base), endpoint.trim_start_matches('/'))
+}
+
+fn discovered(server_url: &str) -> Option<Discovery> {
+    let discovered = DISCOVERED.lock().ok()?;
+    match discovered.as_ref() {
+        Some((url, discovery)) if url == server_url => Some(discovery.clone()),
+        _ => None,
+    }
+}
+
+/// Path of `endpoint` on `server_url`, relative to it
+pub fn api_path(server_url: &str, endpoint: &str) -> String {
+    let configured_base = load_config().ok().and_then(|config| config.api_base_path);
+    resolve(
+        endpoint,
+        configured_base.as_deref(),
+        discovered(server_url).as_ref(),
+    )
+}
+
+/// Full URL of `endpoint` on `server_url`
+pub fn api_url(server_url: &str, endpoint: &str) -> String {
+    format!(
+        "{}{}",
+        server_url.trim_end_matches('/'),
+        api_path(server_url, endpoint)
+    )
+}
+
+/// Fetch `server_url`'s discovery document and use it for later requests
+///
+/// Servers without one keep the default paths.
+pub async fn discover(server_url: &str) {
+    let discovery = fetch_discovery(server_url).await.unwrap_or_default();
+    let Ok(mut discovered) = DISCOVERED.lock() else {
+        return;
+    };
+    let changed = discovered
+        .as_ref()
+        .is_none_or(|(url, previous)| url != server_url || *previous != discovery);
+    if changed && discovery != Discovery::default() {
+        log_info(
+            "server",
+            &format!(
+                "✓ Using API layout from {}'s discovery document (base path {})",
+                server_url,
+                discovery
+                    .api_base_path
+                    .as_deref()
+                    .unwrap_or(DEFAULT_API_BASE_PATH)
+            ),
+        )
+        .unwrap_or_default();
+    }
+    *discovered = Some((server_url.to_string(), discovery));
+}
+
+async fn fetch_discovery(server_url: &str) -> Option<Discovery> {
+    let client = reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .ok()?;
+    let response = client
+        .get(format!(
+            "{}{}",
+            server_url.trim_end_matches('/'),
+            DISCOVERY_PATH
+        ))
+        .send()
+        .await
+        .ok()?;
+    if !response.status().is_success() {
+        return None;
+    }
+    response.json().await.ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_and_configured_base_paths() {
+        assert_eq!(
+            resolve(SESSION_UPLOAD_V2, None, None),
+            "/api/agent-sessions/upload-v2"
+        );
+        assert_eq!(
+            resolve(SESSION_UPLOAD_V2, Some("guidemode/api/"), None),
+            "/guidemode/api/agent-sessions/upload-v2"
+        );
+        assert_eq!(resolve(HEALTH, Some(""), None), "/health");
+    }
+
+    #[test]
+    fn test_discovered_paths() {
+        let discovery: Discovery = serde_json::from_str(
+            r#"{"apiBasePath": "/guidemode/api", "endpoints": {"agent-sessions/upload-v2": "/ingest/v2"}}"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            resolve(SESSION_UPLOAD_V2, None, Some(&discovery)),
+            "/ingest/v2"
+        );
+        assert_eq!(
+            resolve(METRICS_UPLOAD, None, Some(&discovery)),
+            "/guidemode/api/session-metrics/upload"
+        );
+        // Configured base paths win over discovered ones, not over listed endpoints
+        assert_eq!(
+            resolve(METRICS_UPLOAD, Some("/custom"), Some(&discovery)),
+            "/custom/session-metrics/upload"
+        );
+        assert_eq!(
+            resolve(SESSION_UPLOAD_V2, Some("/custom"), Some(&discovery)),
+            "/ingest/v2"
+        );
+    }
+}
@@ -10,6 +10,18 @@ use warp::Filter;
 /// Type alias for the result sender used in auth callbacks
 type ResultSender = Arc<Mutex<Option<oneshot::Sender<Result<AuthCallbackData, AuthError>>>>>;
 
+/// The login waiting for a callback, so a `guidemode://auth/callback` deep link
+/// can complete it instead of the localhost server
+static PENDING_LOGIN: std::sync::Mutex<Option<PendingLogin>> = std::sync::Mutex::new(None);
+
+/// A login in progress: the random `state` sent with its auth URL, which its
+/// callback must echo back, and where to send the callback's result
+#[derive(Clone)]
+struct PendingLogin {
+    state: String,
+    result_tx: ResultSender,
+}
+
 #[derive(Debug, Clone)]
 pub struct AuthCallbackData {
     pub api_key: String,
@@ -22,6 +34,8 @@ pub enum AuthError {
     ServerStartFailed(String),
     TimeoutError,
     CallbackError(String),
+    /// The callback's `state` is missing or belongs to another login
+    StateMismatch,
     PortUnavailable,
 }
 
@@ -31,6 +45,9 @@ impl std::fmt::Display for AuthError {
             AuthError::ServerStartFailed(msg) => write!(f, "Failed to start auth server: {}", msg),
             AuthError::TimeoutError => write!(f, "Authentication timed out"),
             AuthError::CallbackError(msg) => write!(f, "Authentication failed: {}", msg),
+            AuthError::StateMismatch => {
+                write!(f, "Authentication callback doesn't match this login")
+            }
             AuthError::PortUnavailable => write!(f, "Unable to find available port"),
         }
     }
@@ -46,7 +63,10 @@ pub struct AuthServer {
 }
 
 impl AuthServer {
+    /// Start the callback server for a login whose auth URL carries `state`;
+    /// callbacks that don't echo it back are rejected
     pub async fn start(
+        state: &str,
     ) -> Result<(Self, oneshot::Receiver<Result<AuthCallbackData, AuthError>>), AuthError> {
         let port = Self::find_available_port().await?;
         let callback_url = format!("http://localhost:{}/callback", port);
@@ -54,7 +74,7 @@ impl AuthServer {
         let (shutdown_tx, shutdown_rx) = oneshot::channel();
         let (result_tx, result_rx) = oneshot::channel();
 
-        let server_handle = Self::start_server(port, shutdown_rx, result_tx).await?;
+        let server_handle = Self::start_server(port, state, shutdown_rx, result_tx).await?;
 
         let auth_server = AuthServer {
             server_handle: Some(server_handle),
@@ -86,15 +106,22 @@ impl AuthServer {
 
     async fn start_server(
         port: u16,
+        state: &str,
         shutdown_rx: oneshot::Receiver<()>,
         result_tx: oneshot::Sender<Result<AuthCallbackData, AuthError>>,
     ) -> Result<tokio::task::JoinHandle<()>, AuthError> {
-        let result_tx = Arc::new(Mutex::new(Some(result_tx)));
-        let result_tx_filter = warp::any().map(move || result_tx.clone());
+        let login = PendingLogin {
+            state: state.to_string(),
+            result_tx: Arc::new(Mutex::new(Some(result_tx))),
+        };
+        if let Ok(mut pending) = PENDING_LOGIN.lock() {
+            *pending = Some(login.clone());
+        }
+        let login_filter = warp::any().map(move || login.clone());
 
         let callback_route = warp::path("callback")
             .and(warp::query::<HashMap<String, String>>())
-            .and(result_tx_filter)
+            .and(login_filter)
             .and_then(Self::handle_callback);
 
         let routes = callback_route.recover(Self::handle_rejection);
@@ -118,27 +145,11 @@ impl AuthServer {
 
     async fn handle_callback(
         params: HashMap<String, String>,
-        result_tx: ResultSender,
+        login: PendingLogin,
     ) -> Result<impl warp::Reply, Infallible> {
-        use tracing::info;
+        use tracing::{info, warn};
         info!(params_count = params.len(), "Received auth callback");
-        let result = if let Some(error) = params.get("error") {
-            Err(AuthError::CallbackError(error.clone()))
-        } else if let (Some(api_key), Some(tenant_id), Some(tenant_name)) = (
-            params.get("key"),
-            params.get("tenant_id"),
-            params.get("tenant_name"),
-        ) {
-            Ok(AuthCallbackData {
-                api_key: api_key.clone(),
-                tenant_id: tenant_id.clone(),
-                tenant_name: tenant_name.clone(),
-            })
-        } else {
-            Err(AuthError::CallbackError(
-                "Missing required parameters".to_string(),
-            ))
-        };
+        let result = Self::parse_callback(&params, &login.state);
 
         // Return JavaScript to automatically close the window
         let html = match &result {
@@ -195,8 +206,14 @@ impl AuthServer {
         // Create the HTTP response
         let response = warp::reply::html(html);
 
+        // A forged callback must not complete or cancel the login in progress
+        if let Err(AuthError::StateMismatch) = result {
+            warn!("Ignoring auth callback with a missing or mismatched state");
+            return Ok(response);
+        }
+
         // Schedule the result sending after a small delay to ensure HTTP response is sent
-        let result_tx_clone = result_tx.clone();
+        let result_tx_clone = login.result_tx.clone();
         let result_clone = result.clone();
         tokio::spawn(async move {
             tokio::time::sleep(Duration::from_millis(500)).await;
@@ -208,6 +225,53 @@ impl AuthServer {
         Ok(response)
     }
 
+    fn parse_callback(
+        params: &HashMap<String, String>,
+        expected_state: &str,
+    ) -> Result<AuthCallbackData, AuthError> {
+        if params.get("state").map(String::as_str) != Some(expected_state) {
+            Err(AuthError::StateMismatch)
+        } else if let Some(error) = params.get("error") {
+            Err(AuthError::CallbackError(error.clone()))
+        } else if let (Some(api_key), Some(tenant_id), Some(tenant_name)) = (
+            params.get("key"),
+            params.get("tenant_id"),
+            params.get("tenant_name"),
+        ) {
+            Ok(AuthCallbackData {
+                api_key: api_key.clone(),
+                tenant_id: tenant_id.clone(),
+                tenant_name: tenant_name.clone(),
+            })
+        } else {
+            Err(AuthError::CallbackError(
+                "Missing required parameters".to_string(),
+            ))
+        }
+    }
+
+    /// Complete the waiting login with a deep link's callback parameters
+    ///
+    /// Returns false when no login is waiting for a callback, or the callback's
+    /// state doesn't match the waiting login's.
+    pub async fn complete_from_deep_link(params: &HashMap<String, String>) -> bool {
+        let pending = PENDING_LOGIN
+            .lock()
+            .ok()
+            .and_then(|pending| pending.clone());
+        let Some(login) = pending else {
+            return false;
+        };
+        let result = Self::parse_callback(params, &login.state);
+        if let Err(AuthError::StateMismatch) = result {
+            return false;
+        }
+        let Some(tx) = login.result_tx.lock().await.take() else {
+            return false;
+        };
+        tx.send(result).is_ok()
+    }
+
     async fn handle_rejection(_err: warp::Rejection) -> Result<impl warp::Reply, Infallible> {
         let html = r#"<!DOCTYPE html>
 <html>
@@ -252,6 +316,10 @@ impl AuthServer {
     }
 
     pub async fn shutdown(mut self) {
+        if let Ok(mut pending) = PENDING_LOGIN.lock() {
+            *pending = None;
+        }
+
         // Send shutdown signal
         if let Some(shutdown_tx) = self.shutdown_tx.take() {
             let _ = shutdown_tx.send(());
@@ -281,6 +349,9 @@ impl Drop for AuthServer {
 mod tests {
     use super::*;
 
+    /// Servers register themselves as the pending login, so tests that start one run in turn
+    static SERVER_TEST: Mutex<()> = Mutex::const_new(());
+
     #[tokio::test]
     async fn test_port_availability() {
         // Test that port checking works
@@ -290,7 +361,10 @@ mod tests {
 
     #[tokio::test]
     async fn test_server_lifecycle() {
-        let (server, _result_rx) = AuthServer::start().await.expect("Failed to start server");
+        let _serial = SERVER_TEST.lock().await;
+        let (server, _result_rx) = AuthServer::start("state-1")
+            .await
+            .expect("Failed to start server");
         let port = server.port;
 
         // Server should be running
@@ -303,4 +377,39 @@ mod tests {
         tokio::time::sleep(Duration::from_millis(200)).await;
         assert!(AuthServer::is_port_available(port).await);
     }
+
+    #[tokio::test]
+    async fn test_deep_link_completes_pending_login() {
+        let _serial = SERVER_TEST.lock().await;
+        let (server, result_rx) = AuthServer::start("state-1")
+            .await
+            .expect("Failed to start server");
+
+        let callback = |state: Option<&str>| -> HashMap<String, String> {
+            [
+                ("key", Some("gm_key")),
+                ("tenant_id", Some("t1")),
+                ("tenant_name", Some("Acme")),
+                ("state", state),
+            ]
+            .iter()
+            .filter_map(|(k, v)| v.map(|v| (k.to_string(), v.to_string())))
+            .collect()
+        };
+        // Callbacks from another login, or forged ones, leave the login waiting
+        assert!(!AuthServer::complete_from_deep_link(&callback(None)).await);
+        assert!(!AuthServer::complete_from_deep_link(&callback(Some("state-2"))).await);
+
+        let params = callback(Some("state-1"));
+        assert!(AuthServer::complete_from_deep_link(&params).await);
+
+        let data = AuthServer::wait_for_callback_with_timeout(result_rx, Duration::from_secs(1))
+            .await
+            .unwrap();
+        assert_eq!(data.api_key, "gm_key");
+        assert_eq!(data.tenant_name, "Acme");
+
+        server.shutdown().await;
+        assert!(!AuthServer::complete_from_deep_link(&params).await);
+    }
 }
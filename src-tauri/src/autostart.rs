@@ -0,0 +1,101 @@
+//! Launching at login
+//!
+//! `tauri-plugin-autostart` registers the app with the OS: a launch agent on
+//! macOS, a `Run` registry key on Windows and an XDG autostart entry on Linux.
+//! Those launches pass `--autostart`, so with `startMinimized` set the app
+//! starts hidden in the tray instead of opening its window over whatever the
+//! user is doing. Launches by the user always show the window.
+//!
+//! The preferences live in `GuideModeConfig`; the OS registration follows them
+//! at startup and whenever they change.
+
+use crate::config::{load_config, save_config, GuideModeConfig};
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+use tauri_plugin_autostart::ManagerExt;
+
+/// Argument the OS passes to launches at login
+pub const AUTOSTART_ARG: &str = "--autostart";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StartupSettings {
+    pub launch_at_login: bool,
+    pub start_minimized: bool,
+}
+
+pub fn load_settings() -> Result<StartupSettings, String> {
+    let config = load_config().map_err(|e| format!("Failed to load config: {}", e))?;
+    Ok(StartupSettings {
+        launch_at_login: config.launch_at_login,
+        start_minimized: config.start_minimized,
+    })
+}
+
+/// Save the preferences and update the OS registration to match
+pub fn save_settings(app: &AppHandle, settings: &StartupSettings) -> Result<(), String> {
+    let mut config = load_config().map_err(|e| format!("Failed to load config: {}", e))?;
+    config.launch_at_login = settings.launch_at_login;
+    config.start_minimized = settings.start_minimized;
+    save_config(&config).map_err(|e| format!("Failed to save config: {}", e))?;
+
+    sync_registration(app, settings.launch_at_login)
+}
+
+/// Register or unregister the app with the OS, if it doesn't match `enabled`
+pub fn sync_registration(app: &AppHandle, enabled: bool) -> Result<(), String> {
+    let autolaunch = app.autolaunch();
+    let registered = autolaunch
+        .is_enabled()
+        .map_err(|e| format!("Failed to check launch at login: {}", e))?;
+
+    let result = match (enabled, registered) {
+        (true, false) => autolaunch.enable(),
+        (false, true) => autolaunch.disable(),
+        _ => return Ok(()),
+    };
+    result.map_err(|e| format!("Failed to update launch at login: {}", e))
+}
+
+/// Whether this launch should keep the window hidden in the tray
+pub fn start_hidden<I: IntoIterator<Item = String>>(config: &GuideModeConfig, args: I) -> bool {
+    config.launch_at_login
+        && config.start_minimized
+        && args.into_iter().any(|arg| arg == AUTOSTART_ARG)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn test_start_hidden_only_when_launched_at_login() {
+        let config = GuideModeConfig {
+            launch_at_login: true,
+            start_minimized: true,
+            ..Default::default()
+        };
+        assert!(start_hidden(&config, args(&["guidemode", AUTOSTART_ARG])));
+        assert!(!start_hidden(&config, args(&["guidemode"])));
+
+        let shown = GuideModeConfig {
+            start_minimized: false,
+            ..config.clone()
+        };
+        assert!(!start_hidden(&shown, args(&["guidemode", AUTOSTART_ARG])));
+
+        // A stale registration from a disabled preference shows the window
+        let disabled = GuideModeConfig {
+            launch_at_login: false,
+            ..config
+        };
+        assert!(!start_hidden(
+            &disabled,
+            args(&["guidemode", AUTOSTART_ARG])
+        ));
+    }
+}
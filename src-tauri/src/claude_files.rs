@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use std::collections::HashMap;
 use std::fs;
 use std::path::Path;
@@ -9,10 +10,23 @@ use std::path::Path;
 pub enum ClaudeFileType {
     Command,
     Skill,
+    Agent,
     Config,
     Other,
 }
 
+impl ClaudeFileType {
+    fn sort_order(&self) -> u8 {
+        match self {
+            ClaudeFileType::Command => 0,
+            ClaudeFileType::Skill => 1,
+            ClaudeFileType::Agent => 2,
+            ClaudeFileType::Config => 3,
+            ClaudeFileType::Other => 4,
+        }
+    }
+}
+
 /// Metadata parsed from frontmatter
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ClaudeMetadata {
@@ -32,6 +46,65 @@ pub struct ClaudeFile {
     pub metadata: Option<ClaudeMetadata>,
 }
 
+/// A hook command from a settings file
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct HookConfig {
+    pub event: String,           // e.g., "PreToolUse"
+    pub matcher: Option<String>, // Tool name pattern, if the event supports one
+    pub hook_type: String,       // e.g., "command"
+    pub command: Option<String>,
+    pub timeout: Option<u64>, // Seconds
+    pub source: String,       // Settings file, relative to the project
+}
+
+/// An MCP server configured for the project
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct McpServer {
+    pub name: String,
+    pub transport: String, // "stdio", "http" or "sse"
+    pub command: Option<String>,
+    pub args: Vec<String>,
+    pub url: Option<String>,
+    /// Names of environment variables passed to the server; values may be secrets
+    pub env_keys: Vec<String>,
+    pub source: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum PermissionBehavior {
+    Allow,
+    Deny,
+    Ask,
+}
+
+/// A permission rule from a settings file, e.g. `Bash(npm run test:*)`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PermissionRule {
+    pub behavior: PermissionBehavior,
+    pub rule: String,
+    pub source: String,
+}
+
+/// Everything that configures an agent in a project, grouped by category
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AgentSetup {
+    pub commands: Vec<ClaudeFile>,
+    pub skills: Vec<ClaudeFile>,
+    pub agents: Vec<ClaudeFile>,
+    pub hooks: Vec<HookConfig>,
+    pub mcp_servers: Vec<McpServer>,
+    pub permissions: Vec<PermissionRule>,
+    /// Remaining config and markdown files in .claude
+    pub other_files: Vec<ClaudeFile>,
+}
+
+/// Settings files read for hooks and permissions, relative to the project
+const SETTINGS_FILES: &[&str] = &[".claude/settings.json", ".claude/settings.local.json"];
+
+/// Project-scoped MCP server configuration, relative to the project
+const MCP_CONFIG_FILE: &str = ".mcp.json";
+
 /// Scan .claude directory for all configuration files
 pub fn scan_claude_files(cwd: &str) -> Result<Vec<ClaudeFile>, String> {
     let cwd_path = Path::new(cwd);
@@ -51,7 +124,13 @@ pub fn scan_claude_files(cwd: &str) -> Result<Vec<ClaudeFile>, String> {
     // Scan commands directory
     let commands_dir = claude_dir.join("commands");
     if commands_dir.exists() && commands_dir.is_dir() {
-        scan_commands(&commands_dir, &claude_dir, &mut claude_files)?;
+        scan_markdown_files(&commands_dir, &claude_dir, ClaudeFileType::Command, &mut claude_files)?;
+    }
+
+    // Scan agents directory
+    let agents_dir = claude_dir.join("agents");
+    if agents_dir.exists() && agents_dir.is_dir() {
+        scan_markdown_files(&agents_dir, &claude_dir, ClaudeFileType::Agent, &mut claude_files)?;
     }
 
     // Scan skills directory
@@ -68,31 +147,167 @@ pub fn scan_claude_files(cwd: &str) -> Result<Vec<ClaudeFile>, String> {
 
     // Sort by file type, then by relative path
     claude_files.sort_by(|a, b| {
-        match (&a.file_type, &b.file_type) {
-            (ClaudeFileType::Command, ClaudeFileType::Command) => a.relative_path.cmp(&b.relative_path),
-            (ClaudeFileType::Command, _) => std::cmp::Ordering::Less,
-            (_, ClaudeFileType::Command) => std::cmp::Ordering::Greater,
-            (ClaudeFileType::Skill, ClaudeFileType::Skill) => a.relative_path.cmp(&b.relative_path),
-            (ClaudeFileType::Skill, _) => std::cmp::Ordering::Less,
-            (_, ClaudeFileType::Skill) => std::cmp::Ordering::Greater,
-            (ClaudeFileType::Config, ClaudeFileType::Config) => a.relative_path.cmp(&b.relative_path),
-            (ClaudeFileType::Config, _) => std::cmp::Ordering::Less,
-            (_, ClaudeFileType::Config) => std::cmp::Ordering::Greater,
-            _ => a.relative_path.cmp(&b.relative_path),
-        }
+        a.file_type
+            .sort_order()
+            .cmp(&b.file_type.sort_order())
+            .then_with(|| a.relative_path.cmp(&b.relative_path))
     });
 
     Ok(claude_files)
 }
 
-/// Scan commands directory for command files
-fn scan_commands(
-    commands_dir: &Path,
+/// Scan a project's full agent setup: .claude files, hooks, MCP servers and
+/// permission rules
+pub fn scan_agent_setup(cwd: &str) -> Result<AgentSetup, String> {
+    let cwd_path = Path::new(cwd);
+    let mut setup = AgentSetup::default();
+
+    for file in scan_claude_files(cwd)? {
+        match file.file_type {
+            ClaudeFileType::Command => setup.commands.push(file),
+            ClaudeFileType::Skill => setup.skills.push(file),
+            ClaudeFileType::Agent => setup.agents.push(file),
+            ClaudeFileType::Config | ClaudeFileType::Other => setup.other_files.push(file),
+        }
+    }
+
+    for source in SETTINGS_FILES {
+        let Some(settings) = read_json(&cwd_path.join(source)) else {
+            continue;
+        };
+        setup.hooks.extend(parse_hooks(&settings, source));
+        setup.permissions.extend(parse_permissions(&settings, source));
+    }
+
+    if let Some(mcp_config) = read_json(&cwd_path.join(MCP_CONFIG_FILE)) {
+        setup.mcp_servers = parse_mcp_servers(&mcp_config, MCP_CONFIG_FILE);
+    }
+
+    Ok(setup)
+}
+
+/// Read a JSON file, skipping missing or malformed files
+fn read_json(path: &Path) -> Option<Value> {
+    let content = fs::read_to_string(path).ok()?;
+    match serde_json::from_str(&content) {
+        Ok(value) => Some(value),
+        Err(e) => {
+            eprintln!("Failed to parse {:?}: {}", path, e);
+            None
+        }
+    }
+}
+
+/// Parse `hooks` from settings: event → [{ matcher, hooks: [{ type, command, timeout }] }]
+fn parse_hooks(settings: &Value, source: &str) -> Vec<HookConfig> {
+    let Some(events) = settings.get("hooks").and_then(Value::as_object) else {
+        return Vec::new();
+    };
+
+    let mut hooks = Vec::new();
+    for (event, matchers) in events {
+        for matcher in matchers.as_array().into_iter().flatten() {
+            let pattern = matcher
+                .get("matcher")
+                .and_then(Value::as_str)
+                .filter(|pattern| !pattern.is_empty())
+                .map(str::to_string);
+
+            for hook in matcher.get("hooks").and_then(Value::as_array).into_iter().flatten() {
+                hooks.push(HookConfig {
+                    event: event.clone(),
+                    matcher: pattern.clone(),
+                    hook_type: hook
+                        .get("type")
+                        .and_then(Value::as_str)
+                        .unwrap_or("command")
+                        .to_string(),
+                    command: hook.get("command").and_then(Value::as_str).map(str::to_string),
+                    timeout: hook.get("timeout").and_then(Value::as_u64),
+                    source: source.to_string(),
+                });
+            }
+        }
+    }
+    hooks
+}
+
+/// Parse `permissions.allow`, `permissions.deny` and `permissions.ask` from settings
+fn parse_permissions(settings: &Value, source: &str) -> Vec<PermissionRule> {
+    let Some(permissions) = settings.get("permissions") else {
+        return Vec::new();
+    };
+
+    let mut rules = Vec::new();
+    for (key, behavior) in [
+        ("allow", PermissionBehavior::Allow),
+        ("deny", PermissionBehavior::Deny),
+        ("ask", PermissionBehavior::Ask),
+    ] {
+        for rule in permissions.get(key).and_then(Value::as_array).into_iter().flatten() {
+            if let Some(rule) = rule.as_str() {
+                rules.push(PermissionRule {
+                    behavior: behavior.clone(),
+                    rule: rule.to_string(),
+                    source: source.to_string(),
+                });
+            }
+        }
+    }
+    rules
+}
+
+/// Parse `mcpServers` from an MCP config: name → { type, command, args, url, env }
+fn parse_mcp_servers(config: &Value, source: &str) -> Vec<McpServer> {
+    let Some(servers) = config.get("mcpServers").and_then(Value::as_object) else {
+        return Vec::new();
+    };
+
+    let mut mcp_servers: Vec<McpServer> = servers
+        .iter()
+        .map(|(name, server)| {
+            let url = server.get("url").and_then(Value::as_str).map(str::to_string);
+            // Servers without an explicit type are stdio, unless they only have a URL
+            let transport = server
+                .get("type")
+                .and_then(Value::as_str)
+                .map(str::to_string)
+                .unwrap_or_else(|| if url.is_some() { "http" } else { "stdio" }.to_string());
+
+            McpServer {
+                name: name.clone(),
+                transport,
+                command: server.get("command").and_then(Value::as_str).map(str::to_string),
+                args: server
+                    .get("args")
+                    .and_then(Value::as_array)
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|arg| arg.as_str().map(str::to_string))
+                    .collect(),
+                url,
+                env_keys: server
+                    .get("env")
+                    .and_then(Value::as_object)
+                    .map(|env| env.keys().cloned().collect())
+                    .unwrap_or_default(),
+                source: source.to_string(),
+            }
+        })
+        .collect();
+    mcp_servers.sort_by(|a, b| a.name.cmp(&b.name));
+    mcp_servers
+}
+
+/// Scan a directory for markdown files of one type (commands, agents)
+fn scan_markdown_files(
+    dir: &Path,
     claude_root: &Path,
+    file_type: ClaudeFileType,
     files: &mut Vec<ClaudeFile>,
 ) -> Result<(), String> {
-    let entries = fs::read_dir(commands_dir)
-        .map_err(|e| format!("Failed to read commands directory: {}", e))?;
+    let entries = fs::read_dir(dir)
+        .map_err(|e| format!("Failed to read directory {:?}: {}", dir, e))?;
 
     for entry in entries {
         let entry = entry.map_err(|e| format!("Failed to read directory entry: {}", e))?;
@@ -103,10 +318,10 @@ fn scan_commands(
             continue;
         }
 
-        match read_claude_file(&path, claude_root, ClaudeFileType::Command) {
+        match read_claude_file(&path, claude_root, file_type.clone()) {
             Ok(file) => files.push(file),
             Err(e) => {
-                eprintln!("Failed to read command file {:?}: {}", path, e);
+                eprintln!("Failed to read file {:?}: {}", path, e);
             }
         }
     }
@@ -231,8 +446,11 @@ fn read_claude_file(
         .to_string_lossy()
         .to_string();
 
-    // Parse frontmatter for commands and skills
-    let parsed_metadata = if file_type == ClaudeFileType::Command || file_type == ClaudeFileType::Skill {
+    // Parse frontmatter for commands, skills and agents
+    let parsed_metadata = if matches!(
+        file_type,
+        ClaudeFileType::Command | ClaudeFileType::Skill | ClaudeFileType::Agent
+    ) {
         parse_frontmatter(&content)
     } else {
         None
@@ -378,6 +596,101 @@ This is a test skill."#;
         assert!(files[0].metadata.is_none());
     }
 
+    #[test]
+    fn test_scan_with_agent() {
+        let temp_dir = TempDir::new().unwrap();
+        let temp_path = temp_dir.path();
+        let agents_dir = temp_path.join(".claude").join("agents");
+        fs::create_dir_all(&agents_dir).unwrap();
+
+        fs::write(
+            agents_dir.join("reviewer.md"),
+            "---\nname: reviewer\ndescription: Reviews code\ntools: Read, Grep\n---\nReview.",
+        )
+        .unwrap();
+
+        let files = scan_claude_files(temp_path.to_str().unwrap()).unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].file_type, ClaudeFileType::Agent);
+        assert_eq!(files[0].relative_path, Path::new("agents").join("reviewer.md").to_string_lossy());
+        assert_eq!(
+            files[0].metadata.as_ref().unwrap().name.as_deref(),
+            Some("reviewer")
+        );
+    }
+
+    #[test]
+    fn test_scan_agent_setup() {
+        let temp_dir = TempDir::new().unwrap();
+        let temp_path = temp_dir.path();
+        let claude_dir = temp_path.join(".claude");
+        fs::create_dir_all(claude_dir.join("agents")).unwrap();
+        fs::write(claude_dir.join("agents").join("planner.md"), "Plan").unwrap();
+
+        fs::write(
+            claude_dir.join("settings.json"),
+            r#"{
+                "permissions": {
+                    "allow": ["Bash(npm run test:*)", "Read"],
+                    "deny": ["Read(./.env)"]
+                },
+                "hooks": {
+                    "PostToolUse": [
+                        {
+                            "matcher": "Edit|Write",
+                            "hooks": [{ "type": "command", "command": "npm run lint", "timeout": 30 }]
+                        }
+                    ],
+                    "Stop": [{ "hooks": [{ "type": "command", "command": "notify" }] }]
+                }
+            }"#,
+        )
+        .unwrap();
+        fs::write(
+            claude_dir.join("settings.local.json"),
+            r#"{ "permissions": { "ask": ["Bash(git push:*)"] } }"#,
+        )
+        .unwrap();
+        fs::write(
+            temp_path.join(".mcp.json"),
+            r#"{
+                "mcpServers": {
+                    "github": {
+                        "command": "npx",
+                        "args": ["-y", "@modelcontextprotocol/server-github"],
+                        "env": { "GITHUB_TOKEN": "secret" }
+                    },
+                    "docs": { "type": "sse", "url": "https://example.com/sse" }
+                }
+            }"#,
+        )
+        .unwrap();
+
+        let setup = scan_agent_setup(temp_path.to_str().unwrap()).unwrap();
+
+        assert_eq!(setup.agents.len(), 1);
+        assert_eq!(setup.other_files.len(), 2);
+
+        assert_eq!(setup.hooks.len(), 2);
+        let lint = setup.hooks.iter().find(|hook| hook.event == "PostToolUse").unwrap();
+        assert_eq!(lint.matcher.as_deref(), Some("Edit|Write"));
+        assert_eq!(lint.command.as_deref(), Some("npm run lint"));
+        assert_eq!(lint.timeout, Some(30));
+        assert_eq!(lint.source, ".claude/settings.json");
+
+        assert_eq!(setup.permissions.len(), 4);
+        assert_eq!(setup.permissions[2].behavior, PermissionBehavior::Deny);
+        assert_eq!(setup.permissions[3].behavior, PermissionBehavior::Ask);
+        assert_eq!(setup.permissions[3].source, ".claude/settings.local.json");
+
+        assert_eq!(setup.mcp_servers.len(), 2);
+        assert_eq!(setup.mcp_servers[0].name, "docs");
+        assert_eq!(setup.mcp_servers[0].transport, "sse");
+        assert_eq!(setup.mcp_servers[1].transport, "stdio");
+        assert_eq!(setup.mcp_servers[1].args.len(), 2);
+        assert_eq!(setup.mcp_servers[1].env_keys, vec!["GITHUB_TOKEN"]);
+    }
+
     #[test]
     fn test_parse_frontmatter() {
         let content = r#"---
@@ -1,15 +1,26 @@
 use crate::auth_server::{AuthError, AuthServer};
+use crate::canonical_migration::{MigrationReport, VerificationReport};
 use crate::config::{
     clear_config, delete_provider_config, ensure_logs_dir, load_config, load_provider_config,
     save_config, save_provider_config, ActivityLogEntry, GuideModeConfig, ProjectInfo,
     ProviderConfig,
 };
+use crate::error::GuideModeError;
+use crate::events::{OperationKind, Progress, ProgressPhase};
 use crate::logging::{read_provider_logs, LogEntry};
+use crate::metrics::{
+    ContextUsage, HabitDay, HabitStreaks, LineSurvivalStats, RollupGranularity, ToolUsageStats, UsageRange,
+    UsageRollup,
+};
+use crate::providers::canonical::validation::ValidationReport;
+use crate::providers::common::access::{self, Access};
+use crate::providers::common::expand_home;
 use crate::providers::{
     ClaudeWatcher, ClaudeWatcherStatus, CodexWatcher, CodexWatcherStatus,
     CopilotWatcher, CopilotWatcherStatus, CursorWatcher, CursorWatcherStatus, GeminiWatcher,
     GeminiWatcherStatus, OpenCodeWatcher, OpenCodeWatcherStatus, SessionInfo,
 };
+use crate::server_health::ServerHealth;
 use crate::upload_queue::{QueueItems, UploadQueue, UploadStatus};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -19,18 +30,18 @@ use std::time::Duration;
 use tauri::State;
 
 #[tauri::command]
-pub async fn load_config_command() -> Result<GuideModeConfig, String> {
-    load_config().map_err(|e| e.to_string())
+pub async fn load_config_command() -> Result<GuideModeConfig, GuideModeError> {
+    load_config().map_err(|e| GuideModeError::Config(e.to_string()))
 }
 
 #[tauri::command]
-pub async fn save_config_command(config: GuideModeConfig) -> Result<(), String> {
-    save_config(&config).map_err(|e| e.to_string())
+pub async fn save_config_command(config: GuideModeConfig) -> Result<(), GuideModeError> {
+    save_config(&config).map_err(|e| GuideModeError::Config(e.to_string()))
 }
 
 #[tauri::command]
-pub async fn clear_config_command() -> Result<(), String> {
-    clear_config().map_err(|e| e.to_string())
+pub async fn clear_config_command() -> Result<(), GuideModeError> {
+    clear_config().map_err(|e| GuideModeError::Config(e.to_string()))
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -49,19 +60,26 @@ struct UserInfo {
 #[tauri::command]
 pub async fn login_command(
     server_url: String,
-    _app: tauri::AppHandle,
+    app: tauri::AppHandle,
     state: State<'_, AppState>,
-) -> Result<(), String> {
+) -> Result<(), GuideModeError> {
+    // Callbacks must echo this back, so other sites can't log the app into their account
+    let login_state = uuid::Uuid::new_v4().simple().to_string();
+
     // Start the auth server - this handles automatic port selection and cleanup
-    let (auth_server, result_rx) = AuthServer::start()
+    let (auth_server, result_rx) = AuthServer::start(&login_state)
         .await
         .map_err(|e| format!("Failed to start authentication server: {}", e))?;
 
     let callback_url = &auth_server.callback_url;
+    // The deep link lets the server redirect to the app when the browser
+    // can't reach the localhost callback
     let auth_url = format!(
-        "{}/auth/desktop?redirect_uri={}",
+        "{}/auth/desktop?redirect_uri={}&fallback_redirect_uri={}&state={}",
         server_url,
-        urlencoding::encode(callback_url)
+        urlencoding::encode(callback_url),
+        urlencoding::encode(crate::deep_link::AUTH_CALLBACK_URL),
+        login_state
     );
 
     // Log server details for debugging
@@ -106,6 +124,7 @@ pub async fn login_command(
             avatar_url: user_info.avatar_url.clone(),
             tenant_id: Some(auth_data.tenant_id.clone()),
             tenant_name: Some(auth_data.tenant_name.clone()),
+            ..load_config().unwrap_or_default()
         };
 
         info!("Saving authentication configuration");
@@ -116,7 +135,10 @@ pub async fn login_command(
         state.upload_queue.set_config(config);
         info!("Upload queue configuration updated");
 
-        Ok::<(), String>(())
+        // Gate uploads on what the server supports from the start
+        crate::server_health::check_and_publish(&app, &server_url).await;
+
+        Ok::<(), GuideModeError>(())
     }
     .await;
 
@@ -167,7 +189,7 @@ async fn verify_session(
 }
 
 #[tauri::command]
-pub async fn logout_command(state: State<'_, AppState>) -> Result<(), String> {
+pub async fn logout_command(state: State<'_, AppState>) -> Result<(), GuideModeError> {
     clear_config_command().await?;
 
     // Clear upload queue config by setting an empty config
@@ -179,36 +201,44 @@ pub async fn logout_command(state: State<'_, AppState>) -> Result<(), String> {
         avatar_url: None,
         tenant_id: None,
         tenant_name: None,
+        ..Default::default()
     };
     state.upload_queue.set_config(empty_config);
     use tracing::info;
     info!("Upload queue configuration cleared");
 
+    // Team stats belong to the account that fetched them
+    if let Err(e) = crate::team_stats::clear_team_stats_cache() {
+        tracing::warn!("{}", e);
+    }
+
     Ok(())
 }
 
 // Provider config commands
 #[tauri::command]
-pub async fn load_provider_config_command(provider_id: String) -> Result<ProviderConfig, String> {
-    load_provider_config(&provider_id).map_err(|e| e.to_string())
+pub async fn load_provider_config_command(
+    provider_id: String,
+) -> Result<ProviderConfig, GuideModeError> {
+    load_provider_config(&provider_id).map_err(|e| GuideModeError::Config(e.to_string()))
 }
 
 #[tauri::command]
 pub async fn save_provider_config_command(
     provider_id: String,
     config: ProviderConfig,
-) -> Result<(), String> {
-    save_provider_config(&provider_id, &config).map_err(|e| e.to_string())
+) -> Result<(), GuideModeError> {
+    save_provider_config(&provider_id, &config).map_err(|e| GuideModeError::Config(e.to_string()))
 }
 
 #[tauri::command]
-pub async fn delete_provider_config_command(provider_id: String) -> Result<(), String> {
-    delete_provider_config(&provider_id).map_err(|e| e.to_string())
+pub async fn delete_provider_config_command(provider_id: String) -> Result<(), GuideModeError> {
+    delete_provider_config(&provider_id).map_err(|e| GuideModeError::Config(e.to_string()))
 }
 
 // Setup instructions command
 #[tauri::command]
-pub async fn load_setup_instructions_command(file_name: String) -> Result<String, String> {
+pub async fn load_setup_instructions_command(file_name: String) -> Result<String, GuideModeError> {
     // Setup instructions are bundled as resources at compile time using include_str! macro
     // This embeds the files directly into the binary at build time
     match file_name.as_str() {
@@ -220,7 +250,10 @@ pub async fn load_setup_instructions_command(file_name: String) -> Result<String
         "opencode.md" => Ok(include_str!("../../setup-instructions/opencode.md").to_string()),
         "codex.md" => Ok(include_str!("../../setup-instructions/codex.md").to_string()),
         "cursor.md" => Ok(include_str!("../../setup-instructions/cursor.md").to_string()),
-        _ => Err(format!("Unknown setup instructions file: {}", file_name)),
+        _ => Err(GuideModeError::NotFound(format!(
+            "Unknown setup instructions file: {}",
+            file_name
+        ))),
     }
 }
 
@@ -229,23 +262,24 @@ pub async fn load_setup_instructions_command(file_name: String) -> Result<String
 pub async fn scan_projects_command(
     provider_id: String,
     directory: String,
-) -> Result<Vec<ProjectInfo>, String> {
+) -> Result<Vec<ProjectInfo>, GuideModeError> {
     crate::providers::scan_projects(&provider_id, &directory)
 }
 
 // Directory validation command
 #[tauri::command]
-pub async fn check_directory_exists(path: String) -> Result<bool, String> {
+pub async fn check_directory_exists(path: String) -> Result<bool, GuideModeError> {
     use std::path::Path;
 
     // Expand ~ and environment variables
-    let expanded = shellexpand::tilde(&path).to_string();
-    Ok(Path::new(&expanded).exists())
+    let expanded = expand_home(&path).display().to_string();
+    // A directory GuideMode may not read still exists
+    Ok(access::probe(Path::new(&expanded)) != Access::Missing)
 }
 
 // Activity logging commands
 #[tauri::command]
-pub async fn add_activity_log_command(entry: ActivityLogEntry) -> Result<(), String> {
+pub async fn add_activity_log_command(entry: ActivityLogEntry) -> Result<(), GuideModeError> {
     ensure_logs_dir().map_err(|e| e.to_string())?;
 
     let logs_dir = crate::config::get_logs_dir().map_err(|e| e.to_string())?;
@@ -273,7 +307,7 @@ pub async fn add_activity_log_command(entry: ActivityLogEntry) -> Result<(), Str
 #[tauri::command]
 pub async fn get_activity_logs_command(
     limit: Option<usize>,
-) -> Result<Vec<ActivityLogEntry>, String> {
+) -> Result<Vec<ActivityLogEntry>, GuideModeError> {
     let logs_dir = crate::config::get_logs_dir().map_err(|e| e.to_string())?;
 
     if !logs_dir.exists() {
@@ -341,10 +375,97 @@ impl Watcher {
             Watcher::Cursor(watcher) => { let _ = watcher.stop(); },
         }
     }
+
+    /// Whether the watcher is still running; a watcher that hit a fatal error
+    /// stays registered but stopped
+    pub fn is_running(&self) -> bool {
+        match self {
+            Watcher::Claude(watcher) => watcher.get_status().is_running,
+            Watcher::Copilot(watcher) => watcher.get_status().is_running,
+            Watcher::OpenCode(watcher) => watcher.get_status().is_running,
+            Watcher::Codex(watcher) => watcher.get_status().is_running,
+            Watcher::Gemini(watcher) => watcher.get_status().is_running,
+            Watcher::Cursor(watcher) => watcher.get_status().map(|s| s.is_running).unwrap_or(false),
+        }
+    }
+}
+
+/// Running watchers by provider ID
+///
+/// Uses tokio's `RwLock`, which can't be poisoned: a command that panics
+/// while holding it no longer locks every other watcher command out.
+/// Starting a running watcher is an error and stopping a stopped one a no-op.
+#[derive(Default)]
+pub struct WatcherRegistry {
+    watchers: tokio::sync::RwLock<HashMap<String, Watcher>>,
+}
+
+impl WatcherRegistry {
+    /// Start a provider's watcher with `create` unless one is already running
+    ///
+    /// The lock is held while the watcher is created, so two concurrent
+    /// starts can't both create one.
+    pub async fn start(
+        &self,
+        provider_id: &str,
+        create: impl FnOnce() -> Result<Watcher, String>,
+    ) -> Result<(), String> {
+        let mut watchers = self.watchers.write().await;
+        Self::insert(&mut watchers, provider_id, create)
+    }
+
+    /// `start` for synchronous startup code, before any command can hold the lock
+    pub fn start_now(
+        &self,
+        provider_id: &str,
+        create: impl FnOnce() -> Result<Watcher, String>,
+    ) -> Result<(), String> {
+        let mut watchers = self
+            .watchers
+            .try_write()
+            .map_err(|_| "Watcher state is busy".to_string())?;
+        Self::insert(&mut watchers, provider_id, create)
+    }
+
+    fn insert(
+        watchers: &mut HashMap<String, Watcher>,
+        provider_id: &str,
+        create: impl FnOnce() -> Result<Watcher, String>,
+    ) -> Result<(), String> {
+        if let Some(existing) = watchers.get(provider_id) {
+            if existing.is_running() {
+                return Err(format!("The {} watcher is already running", provider_id));
+            }
+            // Replace a watcher that stopped on its own
+            existing.stop();
+        }
+        watchers.insert(provider_id.to_string(), create()?);
+        Ok(())
+    }
+
+    /// Stop a provider's watcher if it is running
+    pub async fn stop(&self, provider_id: &str) {
+        let watcher = self.watchers.write().await.remove(provider_id);
+        if let Some(watcher) = watcher {
+            watcher.stop();
+        }
+    }
+
+    /// Stop every watcher, e.g. when the app quits
+    pub async fn stop_all(&self) {
+        let watchers = std::mem::take(&mut *self.watchers.write().await);
+        for watcher in watchers.into_values() {
+            watcher.stop();
+        }
+    }
+
+    pub async fn read(&self) -> tokio::sync::RwLockReadGuard<'_, HashMap<String, Watcher>> {
+        self.watchers.read().await
+    }
 }
 
 pub struct AppState {
-    pub watchers: Arc<Mutex<HashMap<String, Watcher>>>,
+    pub watchers: Arc<WatcherRegistry>,
     pub upload_queue: Arc<UploadQueue>,
     pub event_bus: crate::events::EventBus,
 }
@@ -354,16 +475,23 @@ impl AppState {
         let upload_queue = Arc::new(UploadQueue::new());
 
         // Start the upload queue processor
-        if let Err(e) = upload_queue.start_processing() {
+        if let Err(e) = upload_queue.start_processing(&event_bus) {
             eprintln!("Failed to start upload queue processor: {}", e);
         }
 
         Self {
-            watchers: Arc::new(Mutex::new(HashMap::new())),
+            watchers: Arc::new(WatcherRegistry::default()),
             upload_queue,
             event_bus,
         }
     }
+
+    /// Stop watchers so no new uploads are queued, then let in-flight uploads
+    /// finish within `upload_timeout`
+    pub async fn shutdown(&self, upload_timeout: std::time::Duration) {
+        self.watchers.stop_all().await;
+        self.upload_queue.shutdown(upload_timeout).await;
+    }
 }
 
 // Claude watcher commands
@@ -371,18 +499,19 @@ impl AppState {
 pub async fn start_claude_watcher(
     state: State<'_, AppState>,
     projects: Vec<String>,
-) -> Result<(), String> {
+) -> Result<(), GuideModeError> {
     // Load provider config to check home directory
     let provider_config = load_provider_config("claude-code")
         .map_err(|e| format!("Failed to load provider config: {}", e))?;
 
     // Check if home directory exists before starting watcher
-    let expanded_path = shellexpand::tilde(&provider_config.home_directory).to_string();
+    let expanded_path = expand_home(&provider_config.home_directory).display().to_string();
+    access::ensure_readable("claude-code", std::path::Path::new(&expanded_path))?;
     if !std::path::Path::new(&expanded_path).exists() {
-        return Err(format!(
+        return Err(GuideModeError::Config(format!(
             "Cannot start watcher: directory '{}' does not exist. Please install Claude Code or configure a valid directory.",
             provider_config.home_directory
-        ));
+        )));
     }
 
     // Update upload queue with current config
@@ -390,49 +519,42 @@ pub async fn start_claude_watcher(
         state.upload_queue.set_config(config);
     }
 
-    // Create new watcher
-    let watcher = ClaudeWatcher::new(
-        projects,
-        Arc::clone(&state.upload_queue),
-        state.event_bus.clone(),
-    )
-    .map_err(|e| format!("Failed to create Claude watcher: {}", e))?;
-
-    // Store watcher in state
-    if let Ok(mut watchers) = state.watchers.lock() {
-        watchers.insert("claude-code".to_string(), Watcher::Claude(watcher));
-    }
-
-    Ok(())
+    // Create and store the watcher, unless one is already running
+    state
+        .watchers
+        .start("claude-code", || {
+            ClaudeWatcher::new(
+                projects,
+                Arc::clone(&state.upload_queue),
+                state.event_bus.clone(),
+            )
+            .map(Watcher::Claude)
+            .map_err(|e| format!("Failed to create Claude watcher: {}", e))
+        })
+        .await
+        .map_err(GuideModeError::from)
 }
 
 #[tauri::command]
-pub async fn stop_claude_watcher(state: State<'_, AppState>) -> Result<(), String> {
-    if let Ok(mut watchers) = state.watchers.lock() {
-        if let Some(watcher) = watchers.remove("claude-code") {
-            watcher.stop();
-        }
-    }
+pub async fn stop_claude_watcher(state: State<'_, AppState>) -> Result<(), GuideModeError> {
+    state.watchers.stop("claude-code").await;
     Ok(())
 }
 
 #[tauri::command]
 pub async fn get_claude_watcher_status(
     state: State<'_, AppState>,
-) -> Result<ClaudeWatcherStatus, String> {
-    if let Ok(watchers) = state.watchers.lock() {
-        if let Some(Watcher::Claude(watcher)) = watchers.get("claude-code") {
-            Ok(watcher.get_status())
-        } else {
-            Ok(ClaudeWatcherStatus {
-                is_running: false,
-                pending_uploads: 0,
-                processing_uploads: 0,
-                failed_uploads: 0,
-            })
-        }
-    } else {
-        Err("Failed to access watcher state".to_string())
+) -> Result<ClaudeWatcherStatus, GuideModeError> {
+    match state.watchers.read().await.get("claude-code") {
+        Some(Watcher::Claude(watcher)) => Ok(watcher.get_status()),
+        _ => Ok(ClaudeWatcherStatus {
+            is_running: false,
+            pending_uploads: 0,
+            processing_uploads: 0,
+            failed_uploads: 0,
+            access_denied: access::denied_path("claude-code"),
+            watch_limit: None,
+        }),
     }
 }
 
@@ -441,18 +563,19 @@ pub async fn get_claude_watcher_status(
 pub async fn start_opencode_watcher(
     state: State<'_, AppState>,
     projects: Vec<String>,
-) -> Result<(), String> {
+) -> Result<(), GuideModeError> {
     // Load provider config to check home directory
     let provider_config = load_provider_config("opencode")
         .map_err(|e| format!("Failed to load provider config: {}", e))?;
 
     // Check if home directory exists before starting watcher
-    let expanded_path = shellexpand::tilde(&provider_config.home_directory).to_string();
+    let expanded_path = expand_home(&provider_config.home_directory).display().to_string();
+    access::ensure_readable("opencode", std::path::Path::new(&expanded_path))?;
     if !std::path::Path::new(&expanded_path).exists() {
-        return Err(format!(
+        return Err(GuideModeError::Config(format!(
             "Cannot start watcher: directory '{}' does not exist. Please install OpenCode or configure a valid directory.",
             provider_config.home_directory
-        ));
+        )));
     }
 
     // Update upload queue with current config
@@ -460,49 +583,42 @@ pub async fn start_opencode_watcher(
         state.upload_queue.set_config(config);
     }
 
-    // Create new watcher
-    let watcher = OpenCodeWatcher::new(
-        projects,
-        Arc::clone(&state.upload_queue),
-        state.event_bus.clone(),
-    )
-    .map_err(|e| format!("Failed to create OpenCode watcher: {}", e))?;
-
-    // Store watcher in state
-    if let Ok(mut watchers) = state.watchers.lock() {
-        watchers.insert("opencode".to_string(), Watcher::OpenCode(watcher));
-    }
-
-    Ok(())
+    // Create and store the watcher, unless one is already running
+    state
+        .watchers
+        .start("opencode", || {
+            OpenCodeWatcher::new(
+                projects,
+                Arc::clone(&state.upload_queue),
+                state.event_bus.clone(),
+            )
+            .map(Watcher::OpenCode)
+            .map_err(|e| format!("Failed to create OpenCode watcher: {}", e))
+        })
+        .await
+        .map_err(GuideModeError::from)
 }
 
 #[tauri::command]
-pub async fn stop_opencode_watcher(state: State<'_, AppState>) -> Result<(), String> {
-    if let Ok(mut watchers) = state.watchers.lock() {
-        if let Some(watcher) = watchers.remove("opencode") {
-            watcher.stop();
-        }
-    }
+pub async fn stop_opencode_watcher(state: State<'_, AppState>) -> Result<(), GuideModeError> {
+    state.watchers.stop("opencode").await;
     Ok(())
 }
 
 #[tauri::command]
 pub async fn get_opencode_watcher_status(
     state: State<'_, AppState>,
-) -> Result<OpenCodeWatcherStatus, String> {
-    if let Ok(watchers) = state.watchers.lock() {
-        if let Some(Watcher::OpenCode(watcher)) = watchers.get("opencode") {
-            Ok(watcher.get_status())
-        } else {
-            Ok(OpenCodeWatcherStatus {
-                is_running: false,
-                pending_uploads: 0,
-                processing_uploads: 0,
-                failed_uploads: 0,
-            })
-        }
-    } else {
-        Err("Failed to access watcher state".to_string())
+) -> Result<OpenCodeWatcherStatus, GuideModeError> {
+    match state.watchers.read().await.get("opencode") {
+        Some(Watcher::OpenCode(watcher)) => Ok(watcher.get_status()),
+        _ => Ok(OpenCodeWatcherStatus {
+            is_running: false,
+            pending_uploads: 0,
+            processing_uploads: 0,
+            failed_uploads: 0,
+            access_denied: access::denied_path("opencode"),
+            watch_limit: None,
+        }),
     }
 }
 
@@ -511,18 +627,19 @@ pub async fn get_opencode_watcher_status(
 pub async fn start_codex_watcher(
     state: State<'_, AppState>,
     projects: Vec<String>,
-) -> Result<(), String> {
+) -> Result<(), GuideModeError> {
     // Load provider config to check home directory
     let provider_config = load_provider_config("codex")
         .map_err(|e| format!("Failed to load provider config: {}", e))?;
 
     // Check if home directory exists before starting watcher
-    let expanded_path = shellexpand::tilde(&provider_config.home_directory).to_string();
+    let expanded_path = expand_home(&provider_config.home_directory).display().to_string();
+    access::ensure_readable("codex", std::path::Path::new(&expanded_path))?;
     if !std::path::Path::new(&expanded_path).exists() {
-        return Err(format!(
+        return Err(GuideModeError::Config(format!(
             "Cannot start watcher: directory '{}' does not exist. Please install Codex or configure a valid directory.",
             provider_config.home_directory
-        ));
+        )));
     }
 
     // Update upload queue with current config
@@ -530,49 +647,42 @@ pub async fn start_codex_watcher(
         state.upload_queue.set_config(config);
     }
 
-    // Create new watcher
-    let watcher = CodexWatcher::new(
-        projects,
-        Arc::clone(&state.upload_queue),
-        state.event_bus.clone(),
-    )
-    .map_err(|e| format!("Failed to create Codex watcher: {}", e))?;
-
-    // Store watcher in state
-    if let Ok(mut watchers) = state.watchers.lock() {
-        watchers.insert("codex".to_string(), Watcher::Codex(watcher));
-    }
-
-    Ok(())
+    // Create and store the watcher, unless one is already running
+    state
+        .watchers
+        .start("codex", || {
+            CodexWatcher::new(
+                projects,
+                Arc::clone(&state.upload_queue),
+                state.event_bus.clone(),
+            )
+            .map(Watcher::Codex)
+            .map_err(|e| format!("Failed to create Codex watcher: {}", e))
+        })
+        .await
+        .map_err(GuideModeError::from)
 }
 
 #[tauri::command]
-pub async fn stop_codex_watcher(state: State<'_, AppState>) -> Result<(), String> {
-    if let Ok(mut watchers) = state.watchers.lock() {
-        if let Some(watcher) = watchers.remove("codex") {
-            watcher.stop();
-        }
-    }
+pub async fn stop_codex_watcher(state: State<'_, AppState>) -> Result<(), GuideModeError> {
+    state.watchers.stop("codex").await;
     Ok(())
 }
 
 #[tauri::command]
 pub async fn get_codex_watcher_status(
     state: State<'_, AppState>,
-) -> Result<CodexWatcherStatus, String> {
-    if let Ok(watchers) = state.watchers.lock() {
-        if let Some(Watcher::Codex(watcher)) = watchers.get("codex") {
-            Ok(watcher.get_status())
-        } else {
-            Ok(CodexWatcherStatus {
-                is_running: false,
-                pending_uploads: 0,
-                processing_uploads: 0,
-                failed_uploads: 0,
-            })
-        }
-    } else {
-        Err("Failed to access watcher state".to_string())
+) -> Result<CodexWatcherStatus, GuideModeError> {
+    match state.watchers.read().await.get("codex") {
+        Some(Watcher::Codex(watcher)) => Ok(watcher.get_status()),
+        _ => Ok(CodexWatcherStatus {
+            is_running: false,
+            pending_uploads: 0,
+            processing_uploads: 0,
+            failed_uploads: 0,
+            access_denied: access::denied_path("codex"),
+            watch_limit: None,
+        }),
     }
 }
 
@@ -581,52 +691,47 @@ pub async fn get_codex_watcher_status(
 pub async fn start_cursor_watcher(
     state: State<'_, AppState>,
     projects: Vec<String>,
-) -> Result<(), String> {
+) -> Result<(), GuideModeError> {
     // Check if chats directory exists
-    let chats_path = shellexpand::tilde("~/.cursor/chats").to_string();
+    let chats_path = expand_home("~/.cursor/chats").display().to_string();
+    access::ensure_readable("cursor", std::path::Path::new(&chats_path))?;
     if !std::path::Path::new(&chats_path).exists() {
-        return Err(format!(
+        return Err(GuideModeError::Config(format!(
             "Cannot start watcher: Cursor chats directory '{}' does not exist. Please install Cursor or create a session.",
             chats_path
-        ));
+        )));
     }
 
     let upload_queue = Arc::clone(&state.upload_queue);
     let event_bus = state.event_bus.clone();
 
-    match CursorWatcher::new(projects, upload_queue, event_bus) {
-        Ok(watcher) => {
-            if let Ok(mut watchers) = state.watchers.lock() {
-                watchers.insert("cursor".to_string(), Watcher::Cursor(watcher));
-            }
-            Ok(())
-        }
-        Err(e) => Err(format!("Failed to start Cursor watcher: {}", e)),
-    }
+    state
+        .watchers
+        .start("cursor", || {
+            CursorWatcher::new(projects, upload_queue, event_bus)
+                .map(Watcher::Cursor)
+                .map_err(|e| format!("Failed to start Cursor watcher: {}", e))
+        })
+        .await
+        .map_err(GuideModeError::from)
 }
 
 #[tauri::command]
-pub async fn stop_cursor_watcher(state: State<'_, AppState>) -> Result<(), String> {
-    if let Ok(mut watchers) = state.watchers.lock() {
-        if let Some(watcher) = watchers.remove("cursor") {
-            watcher.stop();
-        }
-    }
+pub async fn stop_cursor_watcher(state: State<'_, AppState>) -> Result<(), GuideModeError> {
+    state.watchers.stop("cursor").await;
     Ok(())
 }
 
 #[tauri::command]
 pub async fn get_cursor_watcher_status(
     state: State<'_, AppState>,
-) -> Result<CursorWatcherStatus, String> {
-    if let Ok(watchers) = state.watchers.lock() {
-        if let Some(Watcher::Cursor(watcher)) = watchers.get("cursor") {
-            watcher.get_status()
-        } else {
-            Ok(CursorWatcherStatus::default())
-        }
-    } else {
-        Err("Failed to access watcher state".to_string())
+) -> Result<CursorWatcherStatus, GuideModeError> {
+    match state.watchers.read().await.get("cursor") {
+        Some(Watcher::Cursor(watcher)) => watcher.get_status().map_err(GuideModeError::from),
+        _ => Ok(CursorWatcherStatus {
+            access_denied: access::denied_path("cursor"),
+            ..Default::default()
+        }),
     }
 }
 
@@ -635,18 +740,19 @@ pub async fn get_cursor_watcher_status(
 pub async fn start_copilot_watcher(
     state: State<'_, AppState>,
     projects: Vec<String>,
-) -> Result<(), String> {
+) -> Result<(), GuideModeError> {
     // Load provider config to check home directory
     let provider_config = load_provider_config("github-copilot")
         .map_err(|e| format!("Failed to load provider config: {}", e))?;
 
     // Check if home directory exists before starting watcher
-    let expanded_path = shellexpand::tilde(&provider_config.home_directory).to_string();
+    let expanded_path = expand_home(&provider_config.home_directory).display().to_string();
+    access::ensure_readable("github-copilot", std::path::Path::new(&expanded_path))?;
     if !std::path::Path::new(&expanded_path).exists() {
-        return Err(format!(
+        return Err(GuideModeError::Config(format!(
             "Cannot start watcher: directory '{}' does not exist. Please install GitHub Copilot or configure a valid directory.",
             provider_config.home_directory
-        ));
+        )));
     }
 
     // Update upload queue with current config
@@ -654,49 +760,42 @@ pub async fn start_copilot_watcher(
         state.upload_queue.set_config(config);
     }
 
-    // Create new watcher
-    let watcher = CopilotWatcher::new(
-        projects,
-        Arc::clone(&state.upload_queue),
-        state.event_bus.clone(),
-    )
-    .map_err(|e| format!("Failed to create Copilot watcher: {}", e))?;
-
-    // Store watcher in state
-    if let Ok(mut watchers) = state.watchers.lock() {
-        watchers.insert("github-copilot".to_string(), Watcher::Copilot(watcher));
-    }
-
-    Ok(())
+    // Create and store the watcher, unless one is already running
+    state
+        .watchers
+        .start("github-copilot", || {
+            CopilotWatcher::new(
+                projects,
+                Arc::clone(&state.upload_queue),
+                state.event_bus.clone(),
+            )
+            .map(Watcher::Copilot)
+            .map_err(|e| format!("Failed to create Copilot watcher: {}", e))
+        })
+        .await
+        .map_err(GuideModeError::from)
 }
 
 #[tauri::command]
-pub async fn stop_copilot_watcher(state: State<'_, AppState>) -> Result<(), String> {
-    if let Ok(mut watchers) = state.watchers.lock() {
-        if let Some(watcher) = watchers.remove("github-copilot") {
-            watcher.stop();
-        }
-    }
+pub async fn stop_copilot_watcher(state: State<'_, AppState>) -> Result<(), GuideModeError> {
+    state.watchers.stop("github-copilot").await;
     Ok(())
 }
 
 #[tauri::command]
 pub async fn get_copilot_watcher_status(
     state: State<'_, AppState>,
-) -> Result<CopilotWatcherStatus, String> {
-    if let Ok(watchers) = state.watchers.lock() {
-        if let Some(Watcher::Copilot(watcher)) = watchers.get("github-copilot") {
-            Ok(watcher.get_status())
-        } else {
-            Ok(CopilotWatcherStatus {
-                is_running: false,
-                pending_uploads: 0,
-                processing_uploads: 0,
-                failed_uploads: 0,
-            })
-        }
-    } else {
-        Err("Failed to access watcher state".to_string())
+) -> Result<CopilotWatcherStatus, GuideModeError> {
+    match state.watchers.read().await.get("github-copilot") {
+        Some(Watcher::Copilot(watcher)) => Ok(watcher.get_status()),
+        _ => Ok(CopilotWatcherStatus {
+            is_running: false,
+            pending_uploads: 0,
+            processing_uploads: 0,
+            failed_uploads: 0,
+            access_denied: access::denied_path("github-copilot"),
+            watch_limit: None,
+        }),
     }
 }
 
@@ -705,18 +804,19 @@ pub async fn get_copilot_watcher_status(
 pub async fn start_gemini_watcher(
     state: State<'_, AppState>,
     projects: Vec<String>,
-) -> Result<(), String> {
+) -> Result<(), GuideModeError> {
     // Load provider config to check home directory
     let provider_config = load_provider_config("gemini-code")
         .map_err(|e| format!("Failed to load provider config: {}", e))?;
 
     // Check if home directory exists before starting watcher
-    let expanded_path = shellexpand::tilde(&provider_config.home_directory).to_string();
+    let expanded_path = expand_home(&provider_config.home_directory).display().to_string();
+    access::ensure_readable("gemini-code", std::path::Path::new(&expanded_path))?;
     if !std::path::Path::new(&expanded_path).exists() {
-        return Err(format!(
+        return Err(GuideModeError::Config(format!(
             "Cannot start watcher: directory '{}' does not exist. Please install Gemini Code or configure a valid directory.",
             provider_config.home_directory
-        ));
+        )));
     }
 
     // Update upload queue with current config
@@ -724,71 +824,69 @@ pub async fn start_gemini_watcher(
         state.upload_queue.set_config(config);
     }
 
-    // Create new watcher - projects parameter now contains hashes (not CWDs)
-    let watcher = GeminiWatcher::new(
-        projects,
-        Arc::clone(&state.upload_queue),
-        state.event_bus.clone(),
-    )
-    .map_err(|e| format!("Failed to create Gemini watcher: {}", e))?;
-
-    // Store watcher in state
-    if let Ok(mut watchers) = state.watchers.lock() {
-        watchers.insert("gemini-code".to_string(), Watcher::Gemini(watcher));
-    }
-
-    Ok(())
+    // Create and store the watcher, unless one is already running
+    // (projects parameter now contains hashes, not CWDs)
+    state
+        .watchers
+        .start("gemini-code", || {
+            GeminiWatcher::new(
+                projects,
+                Arc::clone(&state.upload_queue),
+                state.event_bus.clone(),
+            )
+            .map(Watcher::Gemini)
+            .map_err(|e| format!("Failed to create Gemini watcher: {}", e))
+        })
+        .await
+        .map_err(GuideModeError::from)
 }
 
 #[tauri::command]
-pub async fn stop_gemini_watcher(state: State<'_, AppState>) -> Result<(), String> {
-    if let Ok(mut watchers) = state.watchers.lock() {
-        if let Some(watcher) = watchers.remove("gemini-code") {
-            watcher.stop();
-        }
-    }
+pub async fn stop_gemini_watcher(state: State<'_, AppState>) -> Result<(), GuideModeError> {
+    state.watchers.stop("gemini-code").await;
     Ok(())
 }
 
 #[tauri::command]
 pub async fn get_gemini_watcher_status(
     state: State<'_, AppState>,
-) -> Result<GeminiWatcherStatus, String> {
-    if let Ok(watchers) = state.watchers.lock() {
-        if let Some(Watcher::Gemini(watcher)) = watchers.get("gemini-code") {
-            Ok(watcher.get_status())
-        } else {
-            Ok(GeminiWatcherStatus {
-                is_running: false,
-                pending_uploads: 0,
-                processing_uploads: 0,
-                failed_uploads: 0,
-            })
-        }
-    } else {
-        Err("Failed to access watcher state".to_string())
+) -> Result<GeminiWatcherStatus, GuideModeError> {
+    match state.watchers.read().await.get("gemini-code") {
+        Some(Watcher::Gemini(watcher)) => Ok(watcher.get_status()),
+        _ => Ok(GeminiWatcherStatus {
+            is_running: false,
+            pending_uploads: 0,
+            processing_uploads: 0,
+            failed_uploads: 0,
+            access_denied: access::denied_path("gemini-code"),
+            watch_limit: None,
+        }),
     }
 }
 
 #[tauri::command]
-pub async fn get_upload_queue_status(state: State<'_, AppState>) -> Result<UploadStatus, String> {
+pub async fn get_upload_queue_status(
+    state: State<'_, AppState>,
+) -> Result<UploadStatus, GuideModeError> {
     Ok(state.upload_queue.get_status())
 }
 
 #[tauri::command]
-pub async fn retry_failed_uploads(state: State<'_, AppState>) -> Result<(), String> {
+pub async fn retry_failed_uploads(state: State<'_, AppState>) -> Result<(), GuideModeError> {
     state.upload_queue.retry_failed();
     Ok(())
 }
 
 #[tauri::command]
-pub async fn clear_failed_uploads(state: State<'_, AppState>) -> Result<(), String> {
+pub async fn clear_failed_uploads(state: State<'_, AppState>) -> Result<(), GuideModeError> {
     state.upload_queue.clear_failed();
     Ok(())
 }
 
 #[tauri::command]
-pub async fn get_upload_queue_items(state: State<'_, AppState>) -> Result<QueueItems, String> {
+pub async fn get_upload_queue_items(
+    state: State<'_, AppState>,
+) -> Result<QueueItems, GuideModeError> {
     Ok(state.upload_queue.get_all_items())
 }
 
@@ -796,21 +894,67 @@ pub async fn get_upload_queue_items(state: State<'_, AppState>) -> Result<QueueI
 pub async fn retry_single_upload(
     state: State<'_, AppState>,
     item_id: String,
-) -> Result<(), String> {
-    state.upload_queue.retry_item(&item_id)
+) -> Result<(), GuideModeError> {
+    state.upload_queue.retry_item(&item_id).map_err(GuideModeError::from)
+}
+
+#[tauri::command]
+pub async fn remove_queue_item(
+    state: State<'_, AppState>,
+    item_id: String,
+) -> Result<(), GuideModeError> {
+    state.upload_queue.remove_item(&item_id).map_err(GuideModeError::from)
+}
+
+/// Exclude a project from syncing or include it again, returning how many sessions changed
+#[tauri::command]
+pub async fn set_project_sync_excluded(
+    state: State<'_, AppState>,
+    project_id: String,
+    excluded: bool,
+) -> Result<usize, GuideModeError> {
+    state
+        .upload_queue
+        .set_project_sync_excluded(&project_id, excluded)
+        .map_err(GuideModeError::from)
+}
+
+/// Set a queue item's priority; higher priorities upload first
+#[tauri::command]
+pub async fn set_queue_item_priority(
+    state: State<'_, AppState>,
+    item_id: String,
+    priority: i64,
+) -> Result<(), GuideModeError> {
+    state.upload_queue.set_item_priority(&item_id, priority).map_err(GuideModeError::from)
+}
+
+/// Move a misattributed queue item to another project before retrying it
+#[tauri::command]
+pub async fn set_queue_item_project(
+    state: State<'_, AppState>,
+    item_id: String,
+    project_name: String,
+) -> Result<String, GuideModeError> {
+    state.upload_queue.set_item_project(&item_id, &project_name).map_err(GuideModeError::from)
 }
 
+/// Change a queue item's working directory before retrying it
 #[tauri::command]
-pub async fn remove_queue_item(state: State<'_, AppState>, item_id: String) -> Result<(), String> {
-    state.upload_queue.remove_item(&item_id)
+pub async fn set_queue_item_cwd(
+    state: State<'_, AppState>,
+    item_id: String,
+    cwd: Option<String>,
+) -> Result<(), GuideModeError> {
+    state.upload_queue.set_item_cwd(&item_id, cwd.as_deref()).map_err(GuideModeError::from)
 }
 
 #[tauri::command]
 pub async fn get_provider_logs(
     provider: String,
     max_lines: Option<usize>,
-) -> Result<Vec<LogEntry>, String> {
-    read_provider_logs(&provider, max_lines).map_err(|e| e.to_string())
+) -> Result<Vec<LogEntry>, GuideModeError> {
+    read_provider_logs(&provider, max_lines).map_err(GuideModeError::from)
 }
 
 // Session sync state for tracking progress
@@ -863,9 +1007,9 @@ where
 pub async fn scan_historical_sessions(
     app_handle: tauri::AppHandle,
     provider_id: String,
-) -> Result<Vec<SessionInfo>, String> {
+    operation_id: Option<String>,
+) -> Result<Vec<SessionInfo>, GuideModeError> {
     use crate::logging::{log_info, log_warn};
-    use tauri::Emitter;
 
     // Log start of scan
     if let Err(e) = log_info(
@@ -875,17 +1019,24 @@ pub async fn scan_historical_sessions(
         eprintln!("Logging error: {}", e);
     }
 
-    // Emit initial progress event
-    let _ = app_handle.emit(
-        "rescan-progress",
-        serde_json::json!({
-            "provider": provider_id,
-            "phase": "starting",
-            "current": 0,
-            "total": 0,
-            "message": "Starting scan..."
-        }),
+    let scan_progress = Progress::start(
+        &app_handle,
+        OperationKind::Rescan,
+        operation_id,
+        Some(&provider_id),
     );
+    scan_progress.report(ProgressPhase::Starting, 0, 0, "Starting scan...");
+
+    // A cancelled scan stops between batches, so the sessions it inserted are
+    // stored whole and still get their metrics
+    let check_cancelled = |current: usize, total: usize| {
+        scan_progress.check_cancelled(current, total).inspect_err(|_| {
+            update_sync_progress_for_provider(&provider_id, |progress| {
+                progress.is_scanning = false;
+            })
+            .ok();
+        })
+    };
 
     // Update progress
     update_sync_progress_for_provider(&provider_id, |progress| {
@@ -905,7 +1056,7 @@ pub async fn scan_historical_sessions(
         if let Err(e) = log_warn(&provider_id, &format!("⚠ {}", err_msg)) {
             eprintln!("Logging error: {}", e);
         }
-        return Err(err_msg);
+        return Err(GuideModeError::Config(err_msg));
     }
 
     if let Err(e) = log_info(
@@ -915,16 +1066,11 @@ pub async fn scan_historical_sessions(
         eprintln!("Logging error: {}", e);
     }
 
-    // Emit scanning phase
-    let _ = app_handle.emit(
-        "rescan-progress",
-        serde_json::json!({
-            "provider": provider_id,
-            "phase": "scanning",
-            "current": 0,
-            "total": 0,
-            "message": format!("Scanning directory: {}", config.home_directory)
-        }),
+    scan_progress.report(
+        ProgressPhase::Scanning,
+        0,
+        0,
+        format!("Scanning directory: {}", config.home_directory),
     );
 
     // Prepare project filter (pass to scanner for early filtering)
@@ -951,10 +1097,12 @@ pub async fn scan_historical_sessions(
     };
 
     // Scan for sessions with early filtering (avoids scanning/processing unselected projects)
-    let sessions = crate::providers::scan_all_sessions_filtered(
+    let scan_started = std::time::Instant::now();
+    let report = crate::providers::scan_all_sessions_filtered(
         &provider_id,
         &config.home_directory,
         selected_projects_filter,
+        scan_progress.token(),
     )
     .map_err(|e| {
         // Log the error
@@ -968,66 +1116,99 @@ pub async fn scan_historical_sessions(
             progress.is_scanning = false;
         })
         .ok();
+        scan_progress.report(ProgressPhase::Failed, 0, 0, format!("Scan failed: {}", e));
         e
     })?;
 
+    let sessions = report.sessions;
+
     if let Err(e) = log_info(
         &provider_id,
-        &format!("✓ Scan complete: found {} sessions", sessions.len()),
+        &format!(
+            "✓ Scan complete: found {} sessions ({} unchanged) in {}ms",
+            sessions.len(),
+            report.unchanged.len(),
+            scan_started.elapsed().as_millis()
+        ),
     ) {
         eprintln!("Logging error: {}", e);
     }
 
-    // Emit found sessions count
-    let _ = app_handle.emit(
-        "rescan-progress",
-        serde_json::json!({
-            "provider": provider_id,
-            "phase": "processing",
-            "current": 0,
-            "total": sessions.len(),
-            "message": format!("Found {} sessions, inserting into database...", sessions.len())
-        }),
+    check_cancelled(0, sessions.len())?;
+
+    // Emit found sessions count with how long each project took to scan
+    let mut found = scan_progress.event(
+        ProgressPhase::Processing,
+        0,
+        sessions.len(),
+        format!("Found {} sessions, inserting into database...", sessions.len()),
     );
+    found.project_timings = Some(report.project_timings);
+    found.unchanged = Some(report.unchanged.len());
+    scan_progress.emit(&found);
+
+    // Sessions whose source file hasn't changed only need inserting if they're missing
+    let stored_session_ids =
+        crate::database::get_session_ids_for_provider(&provider_id).unwrap_or_default();
 
-    // Insert all sessions into the database (just like file watcher does)
-    // The upload queue poller will handle uploading them
+    // Insert all sessions into the database in batches, one transaction and one
+    // progress event each instead of events per session. The upload queue
+    // picks them up from there
     let mut inserted_count = 0;
-    for (index, session) in sessions.iter().enumerate() {
-        // Emit progress every 10 sessions or on last session
-        if index % 10 == 0 || index == sessions.len() - 1 {
-            let _ = app_handle.emit(
-                "rescan-progress",
-                serde_json::json!({
-                    "provider": provider_id,
-                    "phase": "processing",
-                    "current": index + 1,
-                    "total": sessions.len(),
-                    "message": format!("Processing session {} of {}...", index + 1, sessions.len())
-                }),
-            );
+    let mut completed_session_ids = Vec::new();
+    let to_save: Vec<&SessionInfo> = sessions
+        .iter()
+        .filter(|session| {
+            !(report.unchanged.contains(&session.session_id)
+                && stored_session_ids.contains(&session.session_id))
+        })
+        .collect();
+    inserted_count += sessions.len() - to_save.len();
+    let mut cancelled = None;
+    for batch in to_save.chunks(crate::providers::common::db_helpers::HISTORICAL_BATCH_SIZE) {
+        if let Err(e) = check_cancelled(inserted_count, sessions.len()) {
+            cancelled = Some(e);
+            break;
         }
-        match crate::providers::common::db_helpers::insert_session_immediately(
+        match crate::providers::common::db_helpers::insert_historical_sessions(
             &provider_id,
-            &session.project_name,
-            &session.session_id,
-            &session.file_path,
-            session.file_size,
-            None, // Hash will be calculated during upload
-            true, // is_historical - rescan discovers historical sessions
+            batch,
+            &stored_session_ids,
         ) {
-            Ok(_) => {
-                inserted_count += 1;
+            Ok(saved) => {
+                inserted_count += saved.len();
+                completed_session_ids.extend(
+                    saved
+                        .into_iter()
+                        .filter(|(_, saved)| saved.completed)
+                        .map(|(session_id, _)| session_id),
+                );
             }
             Err(e) => {
-                if let Err(log_err) = log_warn(
-                    &provider_id,
-                    &format!("⚠ Failed to insert session {}: {}", session.session_id, e),
-                ) {
+                if let Err(log_err) = log_warn(&provider_id, &format!("⚠ {}", e)) {
                     eprintln!("Logging error: {}", log_err);
                 }
             }
         }
+        scan_progress.report(
+            ProgressPhase::Processing,
+            inserted_count,
+            sessions.len(),
+            format!("Processed {} of {} sessions...", inserted_count, sessions.len()),
+        );
+    }
+
+    // Without per-session completion events the frontend doesn't compute
+    // metrics for these, so compute them here
+    if !completed_session_ids.is_empty() {
+        tauri::async_runtime::spawn_blocking(move || {
+            for session_id in completed_session_ids {
+                if let Err(e) = crate::metrics::compute_session_metrics(&session_id) {
+                    log_warn("metrics", &format!("⚠ Failed to compute core metrics for {}: {}", session_id, e))
+                        .unwrap_or_default();
+                }
+            }
+        });
     }
 
     if let Err(e) = log_info(
@@ -1036,17 +1217,15 @@ pub async fn scan_historical_sessions(
     ) {
         eprintln!("Logging error: {}", e);
     }
+    if let Some(e) = cancelled {
+        return Err(e);
+    }
 
-    // Emit completion event
-    let _ = app_handle.emit(
-        "rescan-progress",
-        serde_json::json!({
-            "provider": provider_id,
-            "phase": "complete",
-            "current": sessions.len(),
-            "total": sessions.len(),
-            "message": format!("Scan complete! Found and inserted {} sessions.", inserted_count)
-        }),
+    scan_progress.report(
+        ProgressPhase::Complete,
+        sessions.len(),
+        sessions.len(),
+        format!("Scan complete! Found and inserted {} sessions.", inserted_count),
     );
 
     // Update progress
@@ -1062,9 +1241,11 @@ pub async fn scan_historical_sessions(
 
 #[tauri::command]
 pub async fn sync_historical_sessions(
+    app_handle: tauri::AppHandle,
     state: State<'_, AppState>,
     provider_id: String,
-) -> Result<(), String> {
+    operation_id: Option<String>,
+) -> Result<(), GuideModeError> {
     use crate::logging::{log_error, log_info, log_warn};
 
     if let Err(e) = log_info(
@@ -1087,7 +1268,7 @@ pub async fn sync_historical_sessions(
         if let Err(e) = log_warn(&provider_id, &format!("⚠ {}", err_msg)) {
             eprintln!("Logging error: {}", e);
         }
-        return Err(err_msg);
+        return Err(GuideModeError::Config(err_msg));
     }
 
     // Update upload queue with current config
@@ -1117,7 +1298,7 @@ pub async fn sync_historical_sessions(
         if let Err(e) = log_warn(&provider_id, &format!("⚠ {}", err_msg)) {
             eprintln!("Logging error: {}", e);
         }
-        return Err(err_msg);
+        return Err(GuideModeError::Validation(err_msg));
     }
 
     if let Err(e) = log_info(
@@ -1130,10 +1311,33 @@ pub async fn sync_historical_sessions(
     // Track initial upload queue status to calculate completion
     let _initial_status = state.upload_queue.get_status();
 
+    let sync_progress = Progress::start(
+        &app_handle,
+        OperationKind::HistoricalSync,
+        operation_id,
+        Some(&provider_id),
+    );
+    sync_progress.report(
+        ProgressPhase::Starting,
+        0,
+        sessions.len(),
+        format!("Queueing {} sessions for upload...", sessions.len()),
+    );
+
     // Add all sessions to upload queue
     let mut queued_count = 0;
     let mut error_count = 0;
+    let mut cancelled = None;
     for (index, session) in sessions.iter().enumerate() {
+        // Sessions queued before a cancel still upload, and are tracked below
+        if let Err(e) = sync_progress.check_cancelled(index, sessions.len()) {
+            cancelled = Some(e);
+            break;
+        }
+        sync_progress.report_item(index + 1, sessions.len(), || {
+            format!("Queueing session {} of {}...", index + 1, sessions.len())
+        });
+
         // Update current progress
         update_sync_progress_for_provider(&provider_id, |progress| {
             progress.current_project = session.project_name.clone();
@@ -1181,6 +1385,14 @@ pub async fn sync_historical_sessions(
     ) {
         eprintln!("Logging error: {}", e);
     }
+    if cancelled.is_none() {
+        sync_progress.report(
+            ProgressPhase::Complete,
+            sessions.len(),
+            sessions.len(),
+            format!("Queued {} sessions for upload", queued_count),
+        );
+    }
 
     // Store initial queue size for progress calculation
     let final_status = state.upload_queue.get_status();
@@ -1202,14 +1414,17 @@ pub async fn sync_historical_sessions(
         eprintln!("Logging error: {}", e);
     }
 
-    Ok(())
+    match cancelled {
+        Some(e) => Err(e),
+        None => Ok(()),
+    }
 }
 
 #[tauri::command]
 pub async fn get_session_sync_progress(
     state: State<'_, AppState>,
     provider_id: String,
-) -> Result<SessionSyncProgress, String> {
+) -> Result<SessionSyncProgress, GuideModeError> {
     let mut progress = get_sync_progress_for_provider(&provider_id)?;
 
     // If we're tracking upload progress, calculate real progress from upload queue
@@ -1237,63 +1452,31 @@ pub async fn get_session_sync_progress(
 pub async fn reset_session_sync_progress(
     state: State<'_, AppState>,
     provider_id: String,
-) -> Result<(), String> {
+) -> Result<(), GuideModeError> {
     // Clear the sync progress state
     if let Ok(mut progress_map) = get_sync_progress_map().lock() {
         progress_map.remove(&provider_id);
     } else {
-        return Err("Failed to reset sync progress".to_string());
+        return Err(GuideModeError::LockPoisoned(
+            "Failed to reset sync progress".to_string(),
+        ));
     }
 
     // Clear uploaded hashes to allow re-syncing the same files
-    state.upload_queue.clear_uploaded_hashes();
+    state.upload_queue.clear_uploaded_hashes(&provider_id);
 
     Ok(())
 }
 
 #[tauri::command]
-pub async fn execute_sql(
-    sql: String,
-    params: Vec<serde_json::Value>,
-) -> Result<Vec<serde_json::Value>, String> {
-    crate::database::execute_sql_query(&sql, params).map_err(|e| e.to_string())
-}
-
-#[tauri::command]
-pub async fn clear_all_sessions() -> Result<String, String> {
+pub async fn clear_all_sessions() -> Result<String, GuideModeError> {
     use crate::logging::log_info;
 
-    // Get counts before deleting
-    let metrics_count =
-        crate::database::execute_sql_query("SELECT COUNT(*) as count FROM session_metrics", vec![])
-            .map_err(|e| e.to_string())?;
-
-    let sessions_count =
-        crate::database::execute_sql_query("SELECT COUNT(*) as count FROM agent_sessions", vec![])
-            .map_err(|e| e.to_string())?;
-
-    let metrics_num = metrics_count
-        .first()
-        .and_then(|r| r.get("count"))
-        .and_then(|v| v.as_i64())
-        .unwrap_or(0);
-
-    let sessions_num = sessions_count
-        .first()
-        .and_then(|r| r.get("count"))
-        .and_then(|v| v.as_i64())
-        .unwrap_or(0);
-
-    // Clear both tables
-    crate::database::execute_sql_query("DELETE FROM session_metrics", vec![])
-        .map_err(|e| e.to_string())?;
-
-    crate::database::execute_sql_query("DELETE FROM agent_sessions", vec![])
-        .map_err(|e| e.to_string())?;
+    let cleared = crate::database::clear_sessions(None).map_err(|e| e.to_string())?;
 
     let message = format!(
         "Cleared {} session metrics and {} sessions from database",
-        metrics_num, sessions_num
+        cleared.session_metrics, cleared.sessions
     );
 
     let _ = log_info("system", &message);
@@ -1303,54 +1486,15 @@ pub async fn clear_all_sessions() -> Result<String, String> {
 }
 
 #[tauri::command]
-pub async fn clear_provider_sessions(provider_id: String) -> Result<String, String> {
+pub async fn clear_provider_sessions(provider_id: String) -> Result<String, GuideModeError> {
     use crate::logging::log_info;
 
-    // Get counts before deleting
-    let metrics_count = crate::database::execute_sql_query(
-        "SELECT COUNT(*) as count FROM session_metrics sm
-         JOIN agent_sessions s ON sm.session_id = s.session_id
-         WHERE s.provider = ?",
-        vec![serde_json::Value::String(provider_id.clone())],
-    )
-    .map_err(|e| e.to_string())?;
-
-    let sessions_count = crate::database::execute_sql_query(
-        "SELECT COUNT(*) as count FROM agent_sessions WHERE provider = ?",
-        vec![serde_json::Value::String(provider_id.clone())],
-    )
-    .map_err(|e| e.to_string())?;
-
-    let metrics_num = metrics_count
-        .first()
-        .and_then(|r| r.get("count"))
-        .and_then(|v| v.as_i64())
-        .unwrap_or(0);
-
-    let sessions_num = sessions_count
-        .first()
-        .and_then(|r| r.get("count"))
-        .and_then(|v| v.as_i64())
-        .unwrap_or(0);
-
-    // Delete metrics for sessions from this provider
-    crate::database::execute_sql_query(
-        "DELETE FROM session_metrics WHERE session_id IN
-         (SELECT session_id FROM agent_sessions WHERE provider = ?)",
-        vec![serde_json::Value::String(provider_id.clone())],
-    )
-    .map_err(|e| e.to_string())?;
-
-    // Delete sessions from this provider
-    crate::database::execute_sql_query(
-        "DELETE FROM agent_sessions WHERE provider = ?",
-        vec![serde_json::Value::String(provider_id.clone())],
-    )
-    .map_err(|e| e.to_string())?;
+    let cleared =
+        crate::database::clear_sessions(Some(&provider_id)).map_err(|e| e.to_string())?;
 
     let message = format!(
         "Cleared {} session metrics and {} sessions for provider '{}'",
-        metrics_num, sessions_num, provider_id
+        cleared.session_metrics, cleared.sessions, provider_id
     );
 
     let _ = log_info(&provider_id, &message);
@@ -1364,7 +1508,7 @@ pub async fn get_session_content(
     provider: String,
     file_path: String,
     _session_id: String,
-) -> Result<String, String> {
+) -> Result<String, GuideModeError> {
     use std::path::PathBuf;
     use tracing::debug;
 
@@ -1385,36 +1529,216 @@ pub async fn get_session_content(
     Ok(content)
 }
 
-// Autostart function for watchers
-pub fn start_enabled_watchers(app_state: &AppState) {
-    use tracing::{error, info};
+/// A page of parsed canonical messages, for viewing large sessions without
+/// loading the whole file
+#[tauri::command]
+pub async fn get_session_messages(
+    session_id: String,
+    offset: usize,
+    limit: Option<usize>,
+) -> Result<crate::session_messages::MessagePage, GuideModeError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        crate::session_messages::get_session_messages(&session_id, offset, limit)
+    })
+    .await
+    .map_err(|e| format!("Failed to read session messages: {}", e))?
+    .map_err(GuideModeError::from)
+}
 
-    // Load and set the configuration on upload queue first
-    if let Ok(config) = load_config() {
-        app_state.upload_queue.set_config(config);
-        info!("Configuration loaded and set for upload queue");
-    } else {
-        error!("Failed to load configuration for upload queue");
-    }
+/// Stream messages after `offset` to `on_page` as a live session grows.
+/// Returns a stream id for `stop_session_stream`.
+#[tauri::command]
+pub async fn stream_session_messages(
+    state: State<'_, AppState>,
+    session_id: String,
+    offset: usize,
+    on_page: tauri::ipc::Channel<crate::session_messages::MessagePage>,
+) -> Result<String, GuideModeError> {
+    crate::session_messages::stream_session_messages(
+        &state.event_bus,
+        &session_id,
+        offset,
+        move |page| on_page.send(page).is_ok(),
+    )
+    .map_err(GuideModeError::from)
+}
 
-    // Try to start Claude Code watcher if enabled
-    if let Ok(claude_config) = load_provider_config("claude-code") {
-        if claude_config.enabled {
-            // Check if home directory exists before starting watcher
-            let expanded_path = shellexpand::tilde(&claude_config.home_directory).to_string();
-            if !std::path::Path::new(&expanded_path).exists() {
-                error!(
-                    provider = "claude-code",
-                    directory = %claude_config.home_directory,
-                    "Cannot start watcher: directory does not exist"
-                );
-                // Continue without starting this watcher
-                // No need to return error - just skip this provider
-            } else {
-                // Scan for projects
-                match crate::providers::scan_projects("claude-code", &claude_config.home_directory)
-                {
-                    Ok(projects) => {
+/// Stop a message stream; false if it had already ended
+#[tauri::command]
+pub async fn stop_session_stream(stream_id: String) -> Result<bool, GuideModeError> {
+    Ok(crate::session_messages::stop_session_stream(&stream_id))
+}
+
+/// Find the messages of a session that mention `query`
+#[tauri::command]
+pub async fn search_in_session(
+    session_id: String,
+    query: String,
+) -> Result<crate::session_search::SessionSearchResults, GuideModeError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        crate::session_search::search_in_session(&session_id, &query)
+    })
+    .await
+    .map_err(|e| format!("Failed to search session: {}", e))?
+    .map_err(GuideModeError::from)
+}
+
+#[tauri::command]
+pub async fn load_summarizer_config_command(
+) -> Result<crate::config::SummarizerConfig, GuideModeError> {
+    crate::config::load_summarizer_config()
+        .map_err(|e| GuideModeError::Config(format!("Failed to load summarizer config: {}", e)))
+}
+
+#[tauri::command]
+pub async fn save_summarizer_config_command(
+    config: crate::config::SummarizerConfig,
+) -> Result<(), GuideModeError> {
+    crate::config::save_summarizer_config(&config)
+        .map_err(|e| GuideModeError::Config(format!("Failed to save summarizer config: {}", e)))
+}
+
+/// Summarize a session now; `force` replaces a summary that is up to date
+#[tauri::command]
+pub async fn summarize_session(
+    session_id: String,
+    force: Option<bool>,
+) -> Result<Option<crate::session_summary::SessionSummary>, GuideModeError> {
+    crate::session_summary::summarize_session(&session_id, force.unwrap_or(false))
+        .await
+        .map_err(GuideModeError::from)
+}
+
+#[tauri::command]
+pub async fn load_assessor_config_command(
+) -> Result<crate::config::AssessorConfig, GuideModeError> {
+    crate::config::load_assessor_config()
+        .map_err(|e| GuideModeError::Config(format!("Failed to load assessor config: {}", e)))
+}
+
+#[tauri::command]
+pub async fn save_assessor_config_command(
+    config: crate::config::AssessorConfig,
+) -> Result<(), GuideModeError> {
+    crate::config::save_assessor_config(&config)
+        .map_err(|e| GuideModeError::Config(format!("Failed to save assessor config: {}", e)))
+}
+
+/// Assess a session with the local assessor now; `force` assesses it again
+/// even if it hasn't changed since its last local assessment
+#[tauri::command]
+pub async fn assess_session(
+    session_id: String,
+    force: Option<bool>,
+) -> Result<Option<crate::session_assessment::LocalAssessment>, GuideModeError> {
+    crate::session_assessment::assess_session(&session_id, force.unwrap_or(false))
+        .await
+        .map_err(GuideModeError::from)
+}
+
+#[tauri::command]
+pub async fn check_server_health(app: tauri::AppHandle) -> Result<ServerHealth, GuideModeError> {
+    let server_url = load_config()
+        .map_err(|e| format!("Failed to load config: {}", e))?
+        .server_url
+        .ok_or("No server URL configured")?;
+    Ok(crate::server_health::check_and_publish(&app, &server_url).await)
+}
+
+#[tauri::command]
+pub async fn open_session_window(
+    app: tauri::AppHandle,
+    session_id: String,
+) -> Result<(), GuideModeError> {
+    crate::windows::open_session_window(&app, &session_id).map_err(GuideModeError::from)
+}
+
+#[tauri::command]
+pub async fn get_startup_settings() -> Result<crate::autostart::StartupSettings, GuideModeError> {
+    crate::autostart::load_settings().map_err(GuideModeError::from)
+}
+
+#[tauri::command]
+pub async fn set_startup_settings(
+    app: tauri::AppHandle,
+    settings: crate::autostart::StartupSettings,
+) -> Result<(), GuideModeError> {
+    crate::autostart::save_settings(&app, &settings).map_err(GuideModeError::from)
+}
+
+#[tauri::command]
+pub async fn get_disk_usage() -> Result<crate::session_cache::DiskUsage, GuideModeError> {
+    crate::session_cache::disk_usage().map_err(GuideModeError::from)
+}
+
+#[tauri::command]
+pub async fn get_cache_settings() -> Result<crate::session_cache::CacheSettings, GuideModeError> {
+    crate::session_cache::load_settings().map_err(GuideModeError::from)
+}
+
+/// Save the cache settings, moving the cache if its directory changed, and
+/// prune it to the new size limit
+#[tauri::command]
+pub async fn set_cache_settings(
+    settings: crate::session_cache::CacheSettings,
+    state: State<'_, AppState>,
+) -> Result<crate::session_cache::PruneReport, GuideModeError> {
+    // Watchers would keep converting into the old directory while it moves
+    let moves_cache = crate::session_cache::moves_cache(&settings)?;
+    if moves_cache {
+        state.watchers.stop_all().await;
+    }
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        crate::session_cache::save_settings(&settings)
+    })
+    .await
+    .map_err(|e| format!("Failed to save cache settings: {}", e))?;
+    if moves_cache {
+        start_enabled_watchers(&state);
+    }
+    result.map_err(GuideModeError::from)
+}
+
+#[tauri::command]
+pub async fn prune_session_cache() -> Result<crate::session_cache::PruneReport, GuideModeError> {
+    crate::session_cache::prune_to_limit().map_err(GuideModeError::from)
+}
+
+#[tauri::command]
+pub async fn take_deep_link_route() -> Result<Option<String>, GuideModeError> {
+    Ok(crate::deep_link::take_launch_route())
+}
+
+// Autostart function for watchers
+pub fn start_enabled_watchers(app_state: &AppState) {
+    use tracing::{error, info};
+
+    // Load and set the configuration on upload queue first
+    if let Ok(config) = load_config() {
+        app_state.upload_queue.set_config(config);
+        info!("Configuration loaded and set for upload queue");
+    } else {
+        error!("Failed to load configuration for upload queue");
+    }
+
+    // Try to start Claude Code watcher if enabled
+    if let Ok(claude_config) = load_provider_config("claude-code") {
+        if claude_config.enabled {
+            // Check if home directory exists before starting watcher
+            let expanded_path = expand_home(&claude_config.home_directory).display().to_string();
+            if !std::path::Path::new(&expanded_path).exists() {
+                error!(
+                    provider = "claude-code",
+                    directory = %claude_config.home_directory,
+                    "Cannot start watcher: directory does not exist"
+                );
+                // Continue without starting this watcher
+                // No need to return error - just skip this provider
+            } else {
+                // Scan for projects
+                match crate::providers::scan_projects("claude-code", &claude_config.home_directory)
+                {
+                    Ok(projects) => {
                         let projects_to_watch = if claude_config.project_selection == "ALL" {
                             projects.iter().map(|p| p.name.clone()).collect()
                         } else {
@@ -1422,19 +1746,17 @@ pub fn start_enabled_watchers(app_state: &AppState) {
                         };
 
                         if !projects_to_watch.is_empty() {
-                            match ClaudeWatcher::new(
-                                projects_to_watch,
-                                Arc::clone(&app_state.upload_queue),
-                                app_state.event_bus.clone(),
-                            ) {
-                                Ok(watcher) => {
-                                    if let Ok(mut watchers) = app_state.watchers.lock() {
-                                        watchers.insert(
-                                            "claude-code".to_string(),
-                                            Watcher::Claude(watcher),
-                                        );
-                                        info!("Claude Code watcher started automatically");
-                                    }
+                            match app_state.watchers.start_now("claude-code", || {
+                                ClaudeWatcher::new(
+                                    projects_to_watch,
+                                    Arc::clone(&app_state.upload_queue),
+                                    app_state.event_bus.clone(),
+                                )
+                                .map(Watcher::Claude)
+                                .map_err(|e| e.to_string())
+                            }) {
+                                Ok(()) => {
+                                    info!("Claude Code watcher started automatically");
                                 }
                                 Err(e) => {
                                     error!(error = %e, "Failed to start Claude Code watcher");
@@ -1454,7 +1776,7 @@ pub fn start_enabled_watchers(app_state: &AppState) {
     if let Ok(opencode_config) = load_provider_config("opencode") {
         if opencode_config.enabled {
             // Check if home directory exists before starting watcher
-            let expanded_path = shellexpand::tilde(&opencode_config.home_directory).to_string();
+            let expanded_path = expand_home(&opencode_config.home_directory).display().to_string();
             if !std::path::Path::new(&expanded_path).exists() {
                 error!(
                     provider = "opencode",
@@ -1472,19 +1794,17 @@ pub fn start_enabled_watchers(app_state: &AppState) {
                         };
 
                         if !projects_to_watch.is_empty() {
-                            match OpenCodeWatcher::new(
-                                projects_to_watch,
-                                Arc::clone(&app_state.upload_queue),
-                                app_state.event_bus.clone(),
-                            ) {
-                                Ok(watcher) => {
-                                    if let Ok(mut watchers) = app_state.watchers.lock() {
-                                        watchers.insert(
-                                            "opencode".to_string(),
-                                            Watcher::OpenCode(watcher),
-                                        );
-                                        info!("OpenCode watcher started automatically");
-                                    }
+                            match app_state.watchers.start_now("opencode", || {
+                                OpenCodeWatcher::new(
+                                    projects_to_watch,
+                                    Arc::clone(&app_state.upload_queue),
+                                    app_state.event_bus.clone(),
+                                )
+                                .map(Watcher::OpenCode)
+                                .map_err(|e| e.to_string())
+                            }) {
+                                Ok(()) => {
+                                    info!("OpenCode watcher started automatically");
                                 }
                                 Err(e) => {
                                     error!(error = %e, "Failed to start OpenCode watcher");
@@ -1513,16 +1833,17 @@ pub fn start_enabled_watchers(app_state: &AppState) {
                     };
 
                     if !projects_to_watch.is_empty() {
-                        match CodexWatcher::new(
-                            projects_to_watch,
-                            Arc::clone(&app_state.upload_queue),
-                            app_state.event_bus.clone(),
-                        ) {
-                            Ok(watcher) => {
-                                if let Ok(mut watchers) = app_state.watchers.lock() {
-                                    watchers.insert("codex".to_string(), Watcher::Codex(watcher));
-                                    info!("Codex watcher started automatically");
-                                }
+                        match app_state.watchers.start_now("codex", || {
+                            CodexWatcher::new(
+                                projects_to_watch,
+                                Arc::clone(&app_state.upload_queue),
+                                app_state.event_bus.clone(),
+                            )
+                            .map(Watcher::Codex)
+                            .map_err(|e| e.to_string())
+                        }) {
+                            Ok(()) => {
+                                info!("Codex watcher started automatically");
                             }
                             Err(e) => {
                                 error!(error = %e, "Failed to start Codex watcher");
@@ -1551,19 +1872,17 @@ pub fn start_enabled_watchers(app_state: &AppState) {
                     };
 
                     if !projects_to_watch.is_empty() {
-                        match CopilotWatcher::new(
-                            projects_to_watch,
-                            Arc::clone(&app_state.upload_queue),
-                            app_state.event_bus.clone(),
-                        ) {
-                            Ok(watcher) => {
-                                if let Ok(mut watchers) = app_state.watchers.lock() {
-                                    watchers.insert(
-                                        "github-copilot".to_string(),
-                                        Watcher::Copilot(watcher),
-                                    );
-                                    info!("GitHub Copilot watcher started automatically");
-                                }
+                        match app_state.watchers.start_now("github-copilot", || {
+                            CopilotWatcher::new(
+                                projects_to_watch,
+                                Arc::clone(&app_state.upload_queue),
+                                app_state.event_bus.clone(),
+                            )
+                            .map(Watcher::Copilot)
+                            .map_err(|e| e.to_string())
+                        }) {
+                            Ok(()) => {
+                                info!("GitHub Copilot watcher started automatically");
                             }
                             Err(e) => {
                                 error!(error = %e, "Failed to start GitHub Copilot watcher");
@@ -1582,7 +1901,7 @@ pub fn start_enabled_watchers(app_state: &AppState) {
     if let Ok(cursor_config) = load_provider_config("cursor") {
         if cursor_config.enabled {
             // Check if chats directory exists
-            let chats_path = shellexpand::tilde("~/.cursor/chats").to_string();
+            let chats_path = expand_home("~/.cursor/chats").display().to_string();
             if !std::path::Path::new(&chats_path).exists() {
                 error!(
                     provider = "cursor",
@@ -1600,16 +1919,17 @@ pub fn start_enabled_watchers(app_state: &AppState) {
                         };
 
                         if !projects_to_watch.is_empty() {
-                            match CursorWatcher::new(
-                                projects_to_watch,
-                                Arc::clone(&app_state.upload_queue),
-                                app_state.event_bus.clone(),
-                            ) {
-                                Ok(watcher) => {
-                                    if let Ok(mut watchers) = app_state.watchers.lock() {
-                                        watchers.insert("cursor".to_string(), Watcher::Cursor(watcher));
-                                        info!("Cursor watcher started automatically");
-                                    }
+                            match app_state.watchers.start_now("cursor", || {
+                                CursorWatcher::new(
+                                    projects_to_watch,
+                                    Arc::clone(&app_state.upload_queue),
+                                    app_state.event_bus.clone(),
+                                )
+                                .map(Watcher::Cursor)
+                                .map_err(|e| e.to_string())
+                            }) {
+                                Ok(()) => {
+                                    info!("Cursor watcher started automatically");
                                 }
                                 Err(e) => {
                                     error!(error = %e, "Failed to start Cursor watcher");
@@ -1640,19 +1960,17 @@ pub fn start_enabled_watchers(app_state: &AppState) {
                     };
 
                     if !projects_to_watch.is_empty() {
-                        match GeminiWatcher::new(
-                            projects_to_watch,
-                            Arc::clone(&app_state.upload_queue),
-                            app_state.event_bus.clone(),
-                        ) {
-                            Ok(watcher) => {
-                                if let Ok(mut watchers) = app_state.watchers.lock() {
-                                    watchers.insert(
-                                        "gemini-code".to_string(),
-                                        Watcher::Gemini(watcher),
-                                    );
-                                    info!("Gemini Code watcher started automatically");
-                                }
+                        match app_state.watchers.start_now("gemini-code", || {
+                            GeminiWatcher::new(
+                                projects_to_watch,
+                                Arc::clone(&app_state.upload_queue),
+                                app_state.event_bus.clone(),
+                            )
+                            .map(Watcher::Gemini)
+                            .map_err(|e| e.to_string())
+                        }) {
+                            Ok(()) => {
+                                info!("Gemini Code watcher started automatically");
                             }
                             Err(e) => {
                                 error!(error = %e, "Failed to start Gemini Code watcher");
@@ -1670,7 +1988,7 @@ pub fn start_enabled_watchers(app_state: &AppState) {
 
 /// Get all projects with session counts
 #[tauri::command]
-pub async fn get_all_projects() -> Result<Vec<serde_json::Value>, String> {
+pub async fn get_all_projects() -> Result<Vec<serde_json::Value>, GuideModeError> {
     use crate::database::get_all_projects;
 
     let projects = get_all_projects().map_err(|e| format!("Failed to get projects: {}", e))?;
@@ -1688,6 +2006,7 @@ pub async fn get_all_projects() -> Result<Vec<serde_json::Value>, String> {
                 "createdAt": p.created_at,
                 "updatedAt": p.updated_at,
                 "sessionCount": p.session_count,
+                "syncExcluded": p.sync_excluded,
             })
         })
         .collect();
@@ -1697,7 +2016,9 @@ pub async fn get_all_projects() -> Result<Vec<serde_json::Value>, String> {
 
 /// Get a single project by ID
 #[tauri::command]
-pub async fn get_project_by_id(project_id: String) -> Result<Option<serde_json::Value>, String> {
+pub async fn get_project_by_id(
+    project_id: String,
+) -> Result<Option<serde_json::Value>, GuideModeError> {
     use crate::database::get_project_by_id;
 
     let project =
@@ -1713,13 +2034,187 @@ pub async fn get_project_by_id(project_id: String) -> Result<Option<serde_json::
             "createdAt": p.created_at,
             "updatedAt": p.updated_at,
             "sessionCount": p.session_count,
+            "syncExcluded": p.sync_excluded,
         })
     }))
 }
 
+/// List project name aliases
+#[tauri::command]
+pub async fn list_project_aliases(
+) -> Result<Vec<crate::project_aliases::ProjectAlias>, GuideModeError> {
+    crate::project_aliases::list_project_aliases().map_err(GuideModeError::from)
+}
+
+/// Merge a project name into a canonical one, across providers and uploads
+#[tauri::command]
+pub async fn set_project_alias(
+    alias: String,
+    canonical_name: String,
+) -> Result<crate::project_aliases::ProjectAlias, GuideModeError> {
+    crate::project_aliases::set_project_alias(&alias, &canonical_name)
+}
+
+/// Remove a project name alias
+#[tauri::command]
+pub async fn remove_project_alias(alias: String) -> Result<bool, GuideModeError> {
+    crate::project_aliases::remove_project_alias(&alias).map_err(GuideModeError::from)
+}
+
+/// Merge duplicate projects into one, telling the server too when `inform_server` is set
+///
+/// The local merge stands even if the server can't be told; `serverError` says why.
+#[tauri::command]
+pub async fn merge_projects(
+    state: State<'_, AppState>,
+    source_ids: Vec<String>,
+    target_id: String,
+    inform_server: Option<bool>,
+) -> Result<crate::project_merge::ProjectMerge, GuideModeError> {
+    let mut merge = state.upload_queue.merge_projects(&source_ids, &target_id)?;
+    if inform_server.unwrap_or(false) {
+        let config = load_config().map_err(|e| GuideModeError::Config(e.to_string()))?;
+        match crate::project_merge::notify_server(&merge, &config).await {
+            Ok(()) => merge.server_notified = true,
+            Err(e) => {
+                crate::logging::log_warn(
+                    "projects",
+                    &format!("⚠ Merged projects locally, but the server wasn't told: {}", e),
+                )
+                .unwrap_or_default();
+                merge.server_error = Some(e.to_string());
+            }
+        }
+    }
+    Ok(merge)
+}
+
+/// Import sessions from a directory or zip archive of canonical JSONL, or of
+/// raw provider session files when `provider` is given
+#[tauri::command]
+pub async fn import_sessions(
+    app: tauri::AppHandle,
+    path: String,
+    provider: Option<String>,
+    operation_id: Option<String>,
+) -> Result<crate::session_import::ImportReport, GuideModeError> {
+    let progress = Progress::start(&app, OperationKind::Import, operation_id, provider.as_deref());
+    tauri::async_runtime::spawn_blocking(move || {
+        crate::session_import::import_sessions(&path, provider.as_deref(), &progress)
+    })
+    .await
+    .map_err(|e| format!("Session import failed: {}", e))?
+}
+
+/// Export the sessions matching `filters` to a zip archive at `destination`
+#[tauri::command]
+pub async fn export_sessions(
+    app: tauri::AppHandle,
+    filters: Option<crate::session_export::ExportFilters>,
+    destination: String,
+    operation_id: Option<String>,
+) -> Result<crate::session_export::ExportReport, GuideModeError> {
+    let progress = Progress::start(&app, OperationKind::Export, operation_id, None);
+    tauri::async_runtime::spawn_blocking(move || {
+        crate::session_export::export_sessions(&filters.unwrap_or_default(), &destination, &progress)
+    })
+    .await
+    .map_err(|e| format!("Session export failed: {}", e))?
+}
+
+/// Move the sessions matching `filters` to a compressed archive and out of the
+/// live database
+#[tauri::command]
+pub async fn archive_sessions(
+    app: tauri::AppHandle,
+    filters: crate::session_export::ExportFilters,
+    operation_id: Option<String>,
+) -> Result<crate::session_archive::ArchiveReport, GuideModeError> {
+    let progress = Progress::start(&app, OperationKind::Archive, operation_id, None);
+    tauri::async_runtime::spawn_blocking(move || {
+        crate::session_archive::archive_sessions(&filters, &progress)
+    })
+    .await
+    .map_err(|e| format!("Session archive failed: {}", e))?
+}
+
+/// Restore the sessions of an archive made by `archive_sessions`
+#[tauri::command]
+pub async fn restore_session_archive(
+    app: tauri::AppHandle,
+    path: String,
+    operation_id: Option<String>,
+) -> Result<crate::session_import::ImportReport, GuideModeError> {
+    let progress = Progress::start(&app, OperationKind::Archive, operation_id, None);
+    tauri::async_runtime::spawn_blocking(move || {
+        crate::session_archive::restore_archive(&path, &progress)
+    })
+    .await
+    .map_err(|e| format!("Archive restore failed: {}", e))?
+}
+
+/// Ask a long-running operation to stop, by the operation ID its command was
+/// given; false if it isn't running
+#[tauri::command]
+pub async fn cancel_operation(operation_id: String) -> Result<bool, GuideModeError> {
+    Ok(crate::events::cancel_operation(&operation_id))
+}
+
+/// Stop a provider's running historical scan and sync; false if neither is
+/// running
+#[tauri::command]
+pub async fn cancel_historical_operation(provider_id: String) -> Result<bool, GuideModeError> {
+    let cancelled = crate::events::cancel_historical_operation(&provider_id);
+    if cancelled {
+        crate::logging::log_info(&provider_id, "⏹ Cancelling historical scan and sync")
+            .unwrap_or_default();
+    }
+    Ok(cancelled)
+}
+
+/// Write a session's transcript as Markdown or standalone HTML
+#[tauri::command]
+pub async fn export_session_transcript(
+    session_id: String,
+    format: crate::transcript::TranscriptFormat,
+    path: String,
+    include_thinking: Option<bool>,
+) -> Result<(), GuideModeError> {
+    let options = crate::transcript::TranscriptOptions {
+        include_thinking: include_thinking.unwrap_or(false),
+    };
+    tauri::async_runtime::spawn_blocking(move || {
+        crate::transcript::export_session_transcript(&session_id, format, &path, options)
+            .map_err(GuideModeError::from)
+    })
+    .await
+    .map_err(|e| format!("Transcript export failed: {}", e))?
+}
+
+/// Link to a session on the server, uploading it first if needed
+#[tauri::command]
+pub async fn share_session_link(
+    state: State<'_, AppState>,
+    session_id: String,
+) -> Result<String, GuideModeError> {
+    crate::session_share::share_session_link(&state.upload_queue, &session_id)
+        .await
+        .map_err(GuideModeError::from)
+}
+
+/// Sanitized Markdown excerpt of a session, for the clipboard
+#[tauri::command]
+pub async fn get_session_markdown_excerpt(
+    session_id: String,
+    max_chars: Option<usize>,
+) -> Result<crate::session_share::SessionExcerpt, GuideModeError> {
+    crate::session_share::session_markdown_excerpt(&session_id, max_chars)
+        .map_err(GuideModeError::from)
+}
+
 /// Open a folder in the OS file manager (Finder on macOS, Explorer on Windows, etc.)
 #[tauri::command]
-pub async fn open_folder_in_os(path: String) -> Result<(), String> {
+pub async fn open_folder_in_os(path: String) -> Result<(), GuideModeError> {
     use std::process::Command;
 
     #[cfg(target_os = "macos")]
@@ -1749,23 +2244,107 @@ pub async fn open_folder_in_os(path: String) -> Result<(), String> {
     Ok(())
 }
 
+/// Check a provider's directory again, e.g. after access was granted;
+/// returns the directory still denied, if any
+#[tauri::command]
+pub async fn check_provider_access(provider_id: String) -> Result<Option<String>, GuideModeError> {
+    let provider_config = load_provider_config(&provider_id)
+        .map_err(|e| GuideModeError::Config(e.to_string()))?;
+    access::check(&provider_id, &expand_home(&provider_config.home_directory));
+    Ok(access::denied_path(&provider_id))
+}
+
+/// Open the system settings where GuideMode is allowed to read other apps'
+/// data (Full Disk Access on macOS)
+#[tauri::command]
+pub async fn open_privacy_settings() -> Result<(), GuideModeError> {
+    #[cfg(target_os = "macos")]
+    {
+        std::process::Command::new("open")
+            .arg("x-apple.systempreferences:com.apple.preference.security?Privacy_AllFiles")
+            .spawn()
+            .map_err(|e| format!("Failed to open System Settings: {}", e))?;
+        Ok(())
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    Err(GuideModeError::Validation(
+        "Access is granted through the folder's permissions on this platform".to_string(),
+    ))
+}
+
+/// Dev command: write `count` synthetic sessions for `provider` into a sample
+/// home directory, returned so the provider can be pointed at it for a demo.
+/// Only registered in debug builds.
+#[cfg(debug_assertions)]
+#[tauri::command]
+pub async fn generate_sample_sessions(
+    provider: String,
+    count: usize,
+) -> Result<crate::sample_sessions::SampleSessions, GuideModeError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        crate::sample_sessions::generate(&provider, count, &crate::sample_sessions::default_root())
+    })
+    .await
+    .map_err(|e| format!("Sample session generation failed: {}", e))?
+}
+
 /// Quick rate a session
 #[tauri::command]
-pub async fn quick_rate_session(session_id: String, rating: String) -> Result<(), String> {
+pub async fn quick_rate_session(session_id: String, rating: String) -> Result<(), GuideModeError> {
     use crate::database::quick_rate_session;
 
-    quick_rate_session(&session_id, &rating).map_err(|e| format!("Failed to save rating: {}", e))
+    quick_rate_session(&session_id, &rating).map_err(GuideModeError::from)
+}
+
+/// Clear a session's sync failure so the upload queue tries it again
+#[tauri::command]
+pub async fn clear_session_sync_failure(session_id: String) -> Result<(), GuideModeError> {
+    crate::database::clear_sync_failure(&session_id)?;
+    crate::upload_queue::request_db_poll();
+    Ok(())
+}
+
+/// Store the frontend processors' metrics for a session
+#[tauri::command]
+pub async fn store_processor_metrics(
+    session_id: String,
+    provider: String,
+    metrics: crate::metrics::store::ProcessorMetrics,
+) -> Result<(), GuideModeError> {
+    crate::metrics::store::store_processor_metrics(&session_id, &provider, &metrics)
+        .map_err(GuideModeError::from)
+}
+
+/// Store a session's AI processing results
+#[tauri::command]
+pub async fn store_ai_results(
+    session_id: String,
+    results: crate::database::AiResults,
+) -> Result<(), GuideModeError> {
+    crate::database::store_ai_results(&session_id, &results)?;
+    Ok(())
+}
+
+/// Mark a session's AI processing as failed
+#[tauri::command]
+pub async fn fail_ai_processing(session_id: String) -> Result<(), GuideModeError> {
+    crate::database::fail_ai_processing(&session_id)?;
+    Ok(())
 }
 
 /// Get assessment rating for a session
 #[tauri::command]
-pub async fn get_session_rating(session_id: String) -> Result<Option<String>, String> {
+pub async fn get_session_rating(session_id: String) -> Result<Option<String>, GuideModeError> {
     use crate::database::get_session_rating;
 
-    get_session_rating(&session_id).map_err(|e| format!("Failed to get rating: {}", e))
+    get_session_rating(&session_id).map_err(GuideModeError::from)
 }
 
 /// Get git diff between two commits for a session with timestamp filtering
+///
+/// Diffs between two commits are cached locally; `force_refresh` bypasses
+/// and replaces the cached entry.
 #[tauri::command]
 pub async fn get_session_git_diff(
     cwd: String,
@@ -1774,15 +2353,78 @@ pub async fn get_session_git_diff(
     is_active: bool,
     session_start_time: Option<i64>,
     session_end_time: Option<i64>,
-) -> Result<Vec<crate::git_diff::FileDiff>, String> {
-    crate::git_diff::get_commit_diff(
+    force_refresh: Option<bool>,
+) -> Result<Vec<crate::git_diff::FileDiff>, GuideModeError> {
+    crate::git_diff::get_commit_diff_cached(
         &cwd,
         &first_commit_hash,
         &latest_commit_hash,
         is_active,
         session_start_time,
         session_end_time,
+        force_refresh.unwrap_or(false),
     )
+    .map_err(GuideModeError::from)
+}
+
+/// List a page of the files changed in a session, without hunks
+///
+/// Use with `get_session_git_diff_file` for sessions that touch many files.
+#[tauri::command]
+pub async fn get_session_git_diff_files(
+    cwd: String,
+    first_commit_hash: String,
+    latest_commit_hash: String,
+    is_active: bool,
+    session_end_time: Option<i64>,
+    offset: Option<usize>,
+    limit: Option<usize>,
+) -> Result<crate::git_diff::DiffFilePage, GuideModeError> {
+    crate::git_diff::list_diff_files(
+        &cwd,
+        &first_commit_hash,
+        &latest_commit_hash,
+        is_active,
+        session_end_time,
+        offset.unwrap_or(0),
+        limit.unwrap_or(crate::git_diff::DEFAULT_DIFF_PAGE_SIZE),
+    )
+    .map_err(GuideModeError::from)
+}
+
+/// Get the diff of one file changed in a session
+#[tauri::command]
+pub async fn get_session_git_diff_file(
+    cwd: String,
+    first_commit_hash: String,
+    latest_commit_hash: String,
+    is_active: bool,
+    session_end_time: Option<i64>,
+    path: String,
+) -> Result<Option<crate::git_diff::FileDiff>, GuideModeError> {
+    crate::git_diff::get_diff_file(
+        &cwd,
+        &first_commit_hash,
+        &latest_commit_hash,
+        is_active,
+        session_end_time,
+        &path,
+    )
+    .map_err(GuideModeError::from)
+}
+
+/// Get the commits created during a session, newest first
+///
+/// Links are refreshed from the session's repository first; if it can't be
+/// read anymore, the commits linked earlier are returned.
+#[tauri::command]
+pub async fn get_session_commits(
+    session_id: String,
+) -> Result<Vec<crate::git_diff::SessionCommit>, GuideModeError> {
+    match crate::git_diff::link_session_commits(&session_id) {
+        Ok(Some(commits)) => Ok(commits),
+        _ => crate::database::get_session_commits(&session_id).map_err(GuideModeError::from),
+    }
 }
 
 /// Scan a directory for context files (CLAUDE.md, AGENTS.md, GEMINI.md)
@@ -1790,16 +2432,165 @@ pub async fn get_session_git_diff(
 #[tauri::command]
 pub async fn scan_context_files(
     cwd: String,
-) -> Result<Vec<crate::context_files::ContextFile>, String> {
-    crate::context_files::scan_context_files(&cwd)
+) -> Result<Vec<crate::context_files::ContextFile>, GuideModeError> {
+    let files = crate::context_files::scan_context_files(&cwd)?;
+
+    // History is a side record; a failure shouldn't hide the files
+    if let Err(e) = crate::context_history::record_context_file_snapshots(&cwd, &files) {
+        crate::logging::log_warn("context-files", &format!("⚠ {}", e)).unwrap_or_default();
+    }
+
+    Ok(files)
+}
+
+/// Lint a directory's context files for broken imports, duplicate sections,
+/// oversized files and contradictory instructions
+#[tauri::command]
+pub async fn lint_context_files(
+    cwd: String,
+) -> Result<Vec<crate::context_lint::LintFinding>, GuideModeError> {
+    crate::context_lint::lint_context_files(&cwd).map_err(GuideModeError::from)
+}
+
+/// Get the recorded versions of a context file with the quality of sessions
+/// that ran while each was current
+#[tauri::command]
+pub async fn get_context_file_history(
+    file_path: String,
+) -> Result<Vec<crate::context_history::ContextFileVersion>, GuideModeError> {
+    crate::context_history::get_context_file_history(&file_path).map_err(GuideModeError::from)
+}
+
+/// Get a unified diff between two context file snapshots
+#[tauri::command]
+pub async fn diff_context_file_snapshots(
+    from_id: i64,
+    to_id: i64,
+) -> Result<String, GuideModeError> {
+    crate::context_history::diff_context_file_snapshots(from_id, to_id)
+        .map_err(GuideModeError::from)
+}
+
+/// Report estimated tokens of a directory's context files and their @imports
+#[tauri::command]
+pub async fn get_context_token_report(
+    cwd: String,
+    options: Option<crate::context_files::ContextTokenOptions>,
+) -> Result<crate::context_files::ContextTokenReport, GuideModeError> {
+    crate::context_files::context_token_report(&cwd, &options.unwrap_or_default())
+        .map_err(GuideModeError::from)
 }
 
 /// Scan .claude directory for commands, skills, and config files
 #[tauri::command]
 pub async fn scan_claude_files(
     cwd: String,
-) -> Result<Vec<crate::claude_files::ClaudeFile>, String> {
-    crate::claude_files::scan_claude_files(&cwd)
+) -> Result<Vec<crate::claude_files::ClaudeFile>, GuideModeError> {
+    crate::claude_files::scan_claude_files(&cwd).map_err(GuideModeError::from)
+}
+
+/// Scan a project's full agent setup: commands, skills, subagents, hooks,
+/// MCP servers and permission rules
+#[tauri::command]
+pub async fn scan_agent_setup(
+    cwd: String,
+) -> Result<crate::claude_files::AgentSetup, GuideModeError> {
+    crate::claude_files::scan_agent_setup(&cwd).map_err(GuideModeError::from)
+}
+
+/// Probe for every supported agent so onboarding can preconfigure the
+/// installed ones
+#[tauri::command]
+pub async fn detect_installed_agents(
+) -> Result<Vec<crate::providers::detection::DetectedAgent>, GuideModeError> {
+    tauri::async_runtime::spawn_blocking(crate::providers::detection::detect_installed_agents)
+        .await
+        .map_err(|e| format!("Agent detection failed: {}", e))
+        .map_err(GuideModeError::from)
+}
+
+/// Detected CLI version of each provider and whether its converter was
+/// tested with it; `recheck` detects the versions again
+#[tauri::command]
+pub async fn get_provider_versions(
+    recheck: Option<bool>,
+) -> Result<Vec<crate::providers::versions::ProviderVersion>, GuideModeError> {
+    if !recheck.unwrap_or(false) {
+        return Ok(crate::providers::versions::load());
+    }
+    tauri::async_runtime::spawn_blocking(crate::providers::versions::check_all)
+        .await
+        .map_err(|e| format!("Provider version check failed: {}", e))
+        .map_err(GuideModeError::from)
+}
+
+/// Source lines the converters quarantined, newest first
+#[tauri::command]
+pub async fn list_conversion_errors(
+    provider: Option<String>,
+) -> Result<Vec<crate::providers::common::quarantine::ConversionError>, GuideModeError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        crate::providers::common::quarantine::list_conversion_errors(provider.as_deref())
+    })
+    .await
+    .map_err(|e| format!("Failed to load conversion errors: {}", e))?
+    .map_err(GuideModeError::from)
+}
+
+/// How many of a provider's sessions were found, converted, skipped and
+/// failed, with the reasons for the latest skips, to explain a session that
+/// isn't showing up
+#[tauri::command]
+pub async fn get_provider_ingest_stats(
+    provider_id: String,
+) -> Result<crate::providers::common::ingest_log::ProviderIngestStats, GuideModeError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        crate::providers::common::ingest_log::provider_stats(&provider_id)
+    })
+    .await
+    .map_err(|e| format!("Failed to load ingest stats: {}", e))?
+    .map_err(GuideModeError::from)
+}
+
+/// Convert quarantined sessions again from their source files
+#[tauri::command]
+pub async fn retry_conversion_errors(
+    app: tauri::AppHandle,
+    provider: Option<String>,
+    operation_id: Option<String>,
+) -> Result<crate::conversion_retry::RetryReport, GuideModeError> {
+    let progress = Progress::start(&app, OperationKind::Maintenance, operation_id, provider.as_deref());
+    tauri::async_runtime::spawn_blocking(move || {
+        crate::conversion_retry::retry_quarantined(provider.as_deref(), &progress)
+    })
+    .await
+    .map_err(|e| format!("Conversion retry failed: {}", e))?
+}
+
+/// Scan, convert and validate a provider's sessions without storing or
+/// uploading them, to check what a sync would do
+#[tauri::command]
+pub async fn preview_sync(
+    provider_id: String,
+) -> Result<crate::sync_preview::SyncPreview, GuideModeError> {
+    tauri::async_runtime::spawn_blocking(move || crate::sync_preview::preview_sync(&provider_id))
+        .await
+        .map_err(|e| format!("Sync preview failed: {}", e))?
+        .map_err(GuideModeError::from)
+}
+
+/// Build the request bodies uploading a session would send, with the
+/// transcript summarized, to check what leaves the machine
+#[tauri::command]
+pub async fn inspect_upload_payload(
+    session_id: String,
+) -> Result<crate::upload_queue::UploadPayloadInspection, GuideModeError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        crate::upload_queue::inspect_upload_payload(&session_id)
+    })
+    .await
+    .map_err(|e| format!("Upload payload inspection failed: {}", e))?
+    .map_err(GuideModeError::from)
 }
 
 /// Log updater events from the frontend
@@ -1808,19 +2599,8 @@ pub async fn log_updater_event_command(
     level: String,
     message: String,
     details: Option<serde_json::Value>,
-) -> Result<(), String> {
-    crate::logging::log_updater_event(&level, &message, details).map_err(|e| e.to_string())
-}
-
-/// Migration report for canonical format migration
-#[derive(Debug, Serialize, Deserialize)]
-pub struct MigrationReport {
-    pub provider: String,
-    pub total_sessions: usize,
-    pub successful: usize,
-    pub failed: usize,
-    pub errors: Vec<String>,
-    pub dry_run: bool,
+) -> Result<(), GuideModeError> {
+    crate::logging::log_updater_event(&level, &message, details).map_err(GuideModeError::from)
 }
 
 /// Migrate provider sessions to canonical format
@@ -1829,13 +2609,19 @@ pub struct MigrationReport {
 /// Supports dry-run mode for validation before actual migration.
 ///
 /// # Arguments
-/// * `provider` - Provider name ("codex", "gemini-code", "github-copilot", "opencode")
-/// * `dry_run` - If true, validates conversion without writing files
+/// * `provider` - Provider name ("codex", "gemini-code", "github-copilot", "opencode"),
+///   or "all" for every provider installed
+/// * `dry_run` - If true, compares the conversion with the existing canonical files
+///   without writing them
+/// * `operation_id` - ID to report progress under and cancel the migration by
 #[tauri::command]
 pub async fn migrate_to_canonical_command(
+    app: tauri::AppHandle,
     provider: String,
     dry_run: bool,
-) -> Result<MigrationReport, String> {
+    operation_id: Option<String>,
+) -> Result<Vec<MigrationReport>, GuideModeError> {
+    use crate::canonical_migration::{for_all_providers, migrate, ALL_PROVIDERS};
     use tracing::info;
 
     info!(
@@ -1844,160 +2630,258 @@ pub async fn migrate_to_canonical_command(
         "Starting migration to canonical format"
     );
 
-    match provider.as_str() {
-        "codex" => migrate_codex(dry_run).await,
-        "gemini-code" | "github-copilot" | "opencode" => {
-            Err(format!("Provider '{}' migration not yet implemented. Coming in Phase 1.", provider))
+    let all = provider == ALL_PROVIDERS;
+    let progress = Progress::start(
+        &app,
+        OperationKind::Migration,
+        operation_id,
+        (!all).then_some(provider.as_str()),
+    );
+    tauri::async_runtime::spawn_blocking(move || {
+        if all {
+            for_all_providers(|provider| migrate(provider, dry_run, &progress))
+        } else {
+            migrate(&provider, dry_run, &progress).map(|report| vec![report])
         }
-        other => Err(format!("Unsupported provider: {}", other)),
-    }
+    })
+    .await
+    .map_err(|e| format!("Migration failed: {}", e))?
 }
 
-async fn migrate_codex(dry_run: bool) -> Result<MigrationReport, String> {
-    use std::path::PathBuf;
-    use tracing::{error, info};
-
-    let home = std::env::var("HOME").map_err(|_| "HOME environment variable not set")?;
-    let sessions_dir = PathBuf::from(&home).join(".codex/sessions");
-    let cache_dir = PathBuf::from(&home).join(".guidemode/cache/canonical/codex");
-
-    if !sessions_dir.exists() {
-        return Err(format!(
-            "Codex sessions directory not found: {}",
-            sessions_dir.display()
-        ));
-    }
-
-    // Create cache directory if not in dry-run mode
-    if !dry_run {
-        fs::create_dir_all(&cache_dir).map_err(|e| {
-            format!(
-                "Failed to create canonical cache directory: {}",
-                e
-            )
-        })?;
-        info!(path = %cache_dir.display(), "Created canonical cache directory");
-    }
-
-    let mut report = MigrationReport {
-        provider: "codex".to_string(),
-        total_sessions: 0,
-        successful: 0,
-        failed: 0,
-        errors: Vec::new(),
-        dry_run,
-    };
-
-    // Find all Codex JSONL files recursively
-    let walker = walkdir::WalkDir::new(&sessions_dir)
-        .into_iter()
-        .filter_map(|e| e.ok())
-        .filter(|e| {
-            e.path()
-                .extension()
-                .and_then(|s| s.to_str())
-                == Some("jsonl")
-        });
-
-    for entry in walker {
-        let path = entry.path();
-        report.total_sessions += 1;
-
-        match convert_codex_session(path, &cache_dir, dry_run) {
-            Ok(session_id) => {
-                report.successful += 1;
-                info!(
-                    session_id = %session_id,
-                    path = %path.display(),
-                    "Successfully converted session"
-                );
-            }
-            Err(e) => {
-                report.failed += 1;
-                let error_msg = format!("{}: {}", path.display(), e);
-                report.errors.push(error_msg.clone());
-                error!(error = %e, path = %path.display(), "Failed to convert session");
-            }
-        }
-    }
-
-    info!(
-        total = report.total_sessions,
-        successful = report.successful,
-        failed = report.failed,
-        dry_run = report.dry_run,
-        "Migration completed"
+/// Compare a provider's canonical files with their sources
+///
+/// Checks message counts, roles and timestamp ranges of each canonical file
+/// against a fresh conversion of its source, without writing anything.
+///
+/// # Arguments
+/// * `provider` - Provider name, or "all" for every provider installed
+/// * `operation_id` - ID to report progress under and cancel the check by
+#[tauri::command]
+pub async fn verify_canonical_migration(
+    app: tauri::AppHandle,
+    provider: String,
+    operation_id: Option<String>,
+) -> Result<Vec<VerificationReport>, GuideModeError> {
+    use crate::canonical_migration::{for_all_providers, verify, ALL_PROVIDERS};
+
+    let all = provider == ALL_PROVIDERS;
+    let progress = Progress::start(
+        &app,
+        OperationKind::Migration,
+        operation_id,
+        (!all).then_some(provider.as_str()),
     );
-
-    Ok(report)
+    tauri::async_runtime::spawn_blocking(move || {
+        if all {
+            for_all_providers(|provider| verify(provider, &progress))
+        } else {
+            verify(&provider, &progress).map(|report| vec![report])
+        }
+    })
+    .await
+    .map_err(|e| format!("Verification failed: {}", e))?
 }
 
-fn convert_codex_session(
-    source: &std::path::Path,
-    cache_dir: &std::path::Path,
-    dry_run: bool,
-) -> Result<String, String> {
-    use crate::providers::canonical::converter::ToCanonical;
-    use crate::providers::codex::CodexMessage;
+/// Rewrite a provider's canonical cache from its sources
+///
+/// For when verification fails: removes other copies of each session in the
+/// cache, rewrites it, and verifies the result. Canonical files whose source
+/// is gone are kept.
+///
+/// # Arguments
+/// * `provider` - Provider name, or "all" for every provider installed
+/// * `operation_id` - ID to report progress under and cancel the rewrite by
+#[tauri::command]
+pub async fn regenerate_canonical_cache(
+    app: tauri::AppHandle,
+    provider: String,
+    operation_id: Option<String>,
+) -> Result<Vec<MigrationReport>, GuideModeError> {
+    use crate::canonical_migration::{for_all_providers, regenerate, ALL_PROVIDERS};
+    use tracing::info;
 
-    // Read source file
-    let content = fs::read_to_string(source)
-        .map_err(|e| format!("Failed to read file: {}", e))?;
+    info!(provider = %provider, "Regenerating canonical cache");
 
-    if content.trim().is_empty() {
-        return Err("Empty session file".to_string());
-    }
+    let all = provider == ALL_PROVIDERS;
+    let progress = Progress::start(
+        &app,
+        OperationKind::Migration,
+        operation_id,
+        (!all).then_some(provider.as_str()),
+    );
+    tauri::async_runtime::spawn_blocking(move || {
+        if all {
+            for_all_providers(|provider| regenerate(provider, &progress))
+        } else {
+            regenerate(&provider, &progress).map(|report| vec![report])
+        }
+    })
+    .await
+    .map_err(|e| format!("Regeneration failed: {}", e))?
+}
 
-    // Parse and convert each line
-    let mut canonical_lines = Vec::new();
-    let mut session_id = String::new();
+/// Validate a canonical JSONL file and report structural problems
+///
+/// Debugging aid for sessions that render or upload incorrectly. Checks every
+/// message against the invariants in `providers::canonical::validation`.
+///
+/// # Arguments
+/// * `file_path` - Path to a canonical session file under ~/.guidemode
+#[tauri::command]
+pub async fn validate_canonical_file(
+    file_path: String,
+) -> Result<ValidationReport, GuideModeError> {
+    let path = crate::validation::validate_file_path(std::path::Path::new(&file_path))
+        .map_err(|e| e.to_string())?;
 
-    for (line_num, line) in content.lines().enumerate() {
-        if line.trim().is_empty() {
-            continue;
-        }
+    crate::providers::canonical::validation::validate_file(&path).map_err(GuideModeError::from)
+}
 
-        // Parse Codex message
-        let codex_msg: CodexMessage = serde_json::from_str(line).map_err(|e| {
-            format!("Failed to parse line {}: {}", line_num + 1, e)
-        })?;
+/// Tool usage statistics for the dashboard
+///
+/// Aggregates the per-session tool counts computed by the local metrics
+/// engine: invocations, failure rate and average result size per tool, plus
+/// the most used tools in each project.
+///
+/// # Arguments
+/// * `project_name` - Limit the statistics to one project
+/// * `top_n` - Number of tools listed per project (default 5)
+#[tauri::command]
+pub async fn get_tool_usage_stats(
+    project_name: Option<String>,
+    top_n: Option<usize>,
+) -> Result<ToolUsageStats, GuideModeError> {
+    crate::metrics::store::get_tool_usage_stats(project_name.as_deref(), top_n.unwrap_or(5))
+        .map_err(GuideModeError::from)
+}
 
-        // Extract session ID from first session_meta message
-        if session_id.is_empty() {
-            if let Some(id) = codex_msg.get_session_id() {
-                session_id = id;
-            }
-        }
+/// The improvement tips that recur most across sessions
+///
+/// Tips from session metrics are grouped by category and text similarity,
+/// counted per project and ranked by how many sessions they came up in.
+///
+/// # Arguments
+/// * `project_name` - Limit the report to one project
+/// * `limit` - Number of tip groups returned (default 20)
+#[tauri::command]
+pub async fn get_improvement_tips_report(
+    project_name: Option<String>,
+    limit: Option<usize>,
+) -> Result<crate::metrics::TipsReport, GuideModeError> {
+    crate::metrics::store::get_tips_report(project_name.as_deref(), limit.unwrap_or(20))
+        .map_err(GuideModeError::from)
+}
 
-        // Convert to canonical format
-        let canonical = codex_msg
-            .to_canonical()
-            .map_err(|e| format!("Failed to convert line {}: {}", line_num + 1, e))?;
+/// The user's metrics next to their team's medians and quartiles
+///
+/// Team aggregates come from the server for the logged-in tenant and are
+/// cached for an hour; cached stats are returned, marked stale, while the
+/// server can't be reached.
+///
+/// # Arguments
+/// * `range` - Local days to compare
+/// * `refresh` - Fetch from the server even if the cached stats are fresh
+#[tauri::command]
+pub async fn get_team_comparison(
+    range: UsageRange,
+    refresh: Option<bool>,
+) -> Result<crate::team_stats::TeamComparison, GuideModeError> {
+    crate::team_stats::get_team_comparison(range, refresh.unwrap_or(false))
+        .await
+        .map_err(GuideModeError::from)
+}
 
-        // Serialize to JSON
-        let json = serde_json::to_string(&canonical)
-            .map_err(|e| format!("Failed to serialize line {}: {}", line_num + 1, e))?;
+/// Context window growth curve for a session
+///
+/// Computed on demand from the canonical file, one sample per model request.
+#[tauri::command]
+pub async fn get_context_growth(session_id: String) -> Result<ContextUsage, GuideModeError> {
+    crate::metrics::session_context_usage(&session_id).map_err(GuideModeError::from)
+}
 
-        canonical_lines.push(json);
-    }
+/// Sessions, tokens and estimated cost per day or week
+///
+/// Reads the rollup tables maintained as sessions are ingested, broken down
+/// by provider and project.
+///
+/// # Arguments
+/// * `range` - Inclusive local date range, e.g. `{ "start": "2025-01-01", "end": "2025-01-31" }`
+/// * `granularity` - `"day"` or `"week"` (weeks start on Monday)
+#[tauri::command]
+pub async fn get_usage_rollup(
+    range: UsageRange,
+    granularity: RollupGranularity,
+) -> Result<UsageRollup, GuideModeError> {
+    crate::metrics::store::get_usage_rollup(range, granularity)
+        .map_err(GuideModeError::from)
+}
 
-    // Use session ID from content, or derive from filename
-    if session_id.is_empty() {
-        session_id = source
-            .file_stem()
-            .and_then(|s| s.to_str())
-            .ok_or("Invalid filename")?
-            .to_string();
-    }
+/// Export session metrics and usage rollups to a directory for pandas/DuckDB
+///
+/// # Arguments
+/// * `format` - `"csv"` or `"parquet"`
+/// * `range` - Inclusive local date range; everything when omitted
+/// * `path` - Directory to write the files and `SCHEMA.md` into
+#[tauri::command]
+pub async fn export_metrics(
+    format: crate::metrics::export::MetricsExportFormat,
+    range: Option<UsageRange>,
+    path: String,
+) -> Result<crate::metrics::export::MetricsExportReport, GuideModeError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        crate::metrics::export::export_metrics(format, range, &path)
+    })
+    .await
+    .map_err(|e| format!("Metrics export failed: {}", e))?
+    .map_err(GuideModeError::from)
+}
 
-    // Write canonical JSONL if not dry-run
-    if !dry_run {
-        let target = cache_dir.join(format!("{}.jsonl", session_id));
-        let canonical_content = canonical_lines.join("\n");
+/// Current and longest streaks: days with sessions, days using plan mode and
+/// days with few interruptions
+///
+/// # Arguments
+/// * `interruption_threshold` - Highest interruption rate (% of prompts) for a
+///   low-interruption day (default 10)
+#[tauri::command]
+pub async fn get_habit_streaks(
+    interruption_threshold: Option<f64>,
+) -> Result<HabitStreaks, GuideModeError> {
+    crate::metrics::store::get_habit_streaks(
+        interruption_threshold
+            .unwrap_or(crate::metrics::habits::DEFAULT_INTERRUPTION_THRESHOLD_PERCENT),
+    )
+    .map_err(GuideModeError::from)
+}
 
-        fs::write(&target, canonical_content)
-            .map_err(|e| format!("Failed to write canonical file: {}", e))?;
-    }
+/// How many lines added by sessions' commits survive after a number of days
+///
+/// Sessions whose window has passed since the last call are analyzed first
+/// (blaming the files their commits touched), then the stored results are
+/// returned per session and project.
+///
+/// # Arguments
+/// * `project_name` - Limit the results to one project
+/// * `window_days` - Days after the session ended to check (default 14)
+#[tauri::command]
+pub async fn get_line_survival(
+    project_name: Option<String>,
+    window_days: Option<u32>,
+) -> Result<LineSurvivalStats, GuideModeError> {
+    use crate::metrics::survival::{analyze_pending_sessions, DEFAULT_SURVIVAL_WINDOW_DAYS};
+
+    let window_days = window_days.unwrap_or(DEFAULT_SURVIVAL_WINDOW_DAYS);
+    tauri::async_runtime::spawn_blocking(move || {
+        analyze_pending_sessions(window_days)?;
+        crate::metrics::store::get_line_survival(project_name.as_deref(), window_days)
+            .map_err(GuideModeError::from)
+    })
+    .await
+    .map_err(|e| format!("Line survival analysis failed: {}", e))?
+}
 
-    Ok(session_id)
+/// Per-day habits within a date range, for calendar views
+#[tauri::command]
+pub async fn get_habit_days(range: UsageRange) -> Result<Vec<HabitDay>, GuideModeError> {
+    crate::metrics::store::get_habit_days(range)
+        .map_err(GuideModeError::from)
 }
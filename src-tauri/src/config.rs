@@ -1,6 +1,9 @@
+use crate::project_metadata::MonorepoGranularity;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
 use std::path::PathBuf;
+use std::time::SystemTime;
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct GuideModeConfig {
@@ -16,6 +19,98 @@ pub struct GuideModeConfig {
     pub tenant_id: Option<String>,
     #[serde(rename = "tenantName")]
     pub tenant_name: Option<String>,
+    /// Start the app when the user logs in to their computer
+    #[serde(rename = "launchAtLogin", default)]
+    pub launch_at_login: bool,
+    /// Keep the window hidden in the tray when launched at login
+    #[serde(rename = "startMinimized", default)]
+    pub start_minimized: bool,
+    /// Where canonical session files are cached, instead of ~/.guidemode/sessions
+    #[serde(rename = "cacheDir", default, skip_serializing_if = "Option::is_none")]
+    pub cache_dir: Option<String>,
+    /// Size the cache is pruned back to by removing synced sessions, unlimited if unset
+    #[serde(rename = "cacheMaxMb", default, skip_serializing_if = "Option::is_none")]
+    pub cache_max_mb: Option<u64>,
+    /// Gaps between messages longer than this don't count toward a session's
+    /// active length, 15 minutes if unset
+    #[serde(
+        rename = "idleThresholdMinutes",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub idle_threshold_minutes: Option<u64>,
+    /// Most uploads run at once, 3 if unset; lowered automatically while the
+    /// server is struggling
+    #[serde(
+        rename = "maxConcurrentUploads",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub max_concurrent_uploads: Option<usize>,
+    /// Seconds between database polls for sessions to upload, 10 if unset;
+    /// polls that find nothing back off from it
+    #[serde(
+        rename = "dbPollIntervalSecs",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub db_poll_interval_secs: Option<u64>,
+    /// Path the server's API is mounted under, `/api` if unset
+    #[serde(
+        rename = "apiBasePath",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub api_base_path: Option<String>,
+    /// Default policies for sessions whose cwd is under a root, first match wins
+    #[serde(
+        rename = "workspaceRoots",
+        default,
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub workspace_roots: Vec<WorkspaceRoot>,
+    /// Gitignore-style patterns of projects and paths never captured
+    #[serde(
+        rename = "ignorePatterns",
+        default,
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub ignore_patterns: Vec<String>,
+    /// Free disk space below which session files and archives aren't written
+    #[serde(
+        rename = "minFreeDiskMb",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub min_free_disk_mb: Option<u64>,
+}
+
+/// Directories matching a glob (`~/work/**`) and the policy their sessions get
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkspaceRoot {
+    /// `*` matches within a path component, `**` any number of components
+    pub pattern: String,
+    /// Overrides the provider's sync mode
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sync_mode: Option<String>,
+    #[serde(default)]
+    pub redaction: RedactionLevel,
+    /// Sent with the sessions' uploads
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+}
+
+/// What is scrubbed from transcripts before they upload
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RedactionLevel {
+    #[default]
+    None,
+    /// API keys, tokens and private keys
+    Secrets,
+    /// Secrets, and the home directory in paths
+    Strict,
 }
 
 pub fn get_config_dir() -> Result<PathBuf, Box<dyn std::error::Error>> {
@@ -30,6 +125,12 @@ pub fn get_config_file_path() -> Result<PathBuf, Box<dyn std::error::Error>> {
     Ok(get_config_dir()?.join("config.json"))
 }
 
+/// Size and modification time of config.json, for caches of what it holds
+pub fn config_file_stamp() -> Option<(u64, SystemTime)> {
+    let metadata = fs::metadata(get_config_file_path().ok()?).ok()?;
+    Some((metadata.len(), metadata.modified().ok()?))
+}
+
 pub fn ensure_config_dir() -> Result<(), Box<dyn std::error::Error>> {
     let config_dir = get_config_dir()?;
     if !config_dir.exists() {
@@ -69,6 +170,8 @@ pub fn save_config(config: &GuideModeConfig) -> Result<(), Box<dyn std::error::E
     let content = serde_json::to_string_pretty(config)?;
 
     fs::write(&config_file, content)?;
+    crate::tenant::forget_active_tenant();
+    crate::ignore_list::forget_ignore_list();
 
     // Set permissions to 600 (read/write for owner only) on Unix systems
     #[cfg(unix)]
@@ -84,7 +187,21 @@ pub fn save_config(config: &GuideModeConfig) -> Result<(), Box<dyn std::error::E
 }
 
 pub fn clear_config() -> Result<(), Box<dyn std::error::Error>> {
-    let default_config = GuideModeConfig::default();
+    // Startup preferences belong to the machine, not the account
+    let current = load_config().unwrap_or_default();
+    let default_config = GuideModeConfig {
+        launch_at_login: current.launch_at_login,
+        start_minimized: current.start_minimized,
+        cache_dir: current.cache_dir,
+        cache_max_mb: current.cache_max_mb,
+        idle_threshold_minutes: current.idle_threshold_minutes,
+        max_concurrent_uploads: current.max_concurrent_uploads,
+        db_poll_interval_secs: current.db_poll_interval_secs,
+        workspace_roots: current.workspace_roots,
+        ignore_patterns: current.ignore_patterns,
+        min_free_disk_mb: current.min_free_disk_mb,
+        ..Default::default()
+    };
     save_config(&default_config)
 }
 
@@ -101,6 +218,81 @@ pub struct ProviderConfig {
     pub last_scanned: Option<String>,
     #[serde(rename = "syncMode", default = "default_sync_mode")]
     pub sync_mode: String, // "Nothing", "Metrics Only", or "Transcript and Metrics"
+    /// User-defined path → project name overrides for session attribution
+    #[serde(
+        rename = "projectMappings",
+        default,
+        skip_serializing_if = "HashMap::is_empty"
+    )]
+    pub project_mappings: HashMap<String, String>,
+    /// Include the uncommitted-change patch of sessions that never committed in uploads
+    #[serde(rename = "attachUncommittedPatch", default)]
+    pub attach_uncommitted_patch: bool,
+    /// Monorepo root path → how its sessions are attributed to projects
+    /// (roots without an entry attribute sessions to packages)
+    #[serde(
+        rename = "monorepoGranularity",
+        default,
+        skip_serializing_if = "HashMap::is_empty"
+    )]
+    pub monorepo_granularity: HashMap<String, MonorepoGranularity>,
+    /// Overrides of the checks sessions must pass to be queued for upload
+    #[serde(
+        rename = "enqueueValidation",
+        default,
+        skip_serializing_if = "EnqueueValidation::is_default"
+    )]
+    pub enqueue_validation: EnqueueValidation,
+    #[serde(
+        rename = "qualityGate",
+        default,
+        skip_serializing_if = "QualityGate::is_default"
+    )]
+    pub quality_gate: QualityGate,
+}
+
+/// Overrides of a provider's enqueue validation; unset fields keep the defaults
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EnqueueValidation {
+    /// Fields at least one message must have
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub required_fields: Option<Vec<String>>,
+    /// Largest session queued, in bytes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_size_bytes: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_messages: Option<usize>,
+    /// Queue sessions without checking them, for debugging
+    #[serde(default)]
+    pub bypass: bool,
+}
+
+impl EnqueueValidation {
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Thresholds below which a provider's sessions are too trivial to upload;
+/// unset fields keep the defaults
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QualityGate {
+    /// Skip low-signal sessions instead of uploading them
+    #[serde(default)]
+    pub enabled: bool,
+    /// Fewest user and assistant messages, meta messages aside
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_messages: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_assistant_messages: Option<usize>,
+}
+
+impl QualityGate {
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
 }
 
 fn default_sync_mode() -> String {
@@ -116,6 +308,11 @@ impl Default for ProviderConfig {
             selected_projects: Vec::new(),
             last_scanned: None,
             sync_mode: "Nothing".to_string(),
+            project_mappings: HashMap::new(),
+            attach_uncommitted_patch: false,
+            monorepo_granularity: HashMap::new(),
+            enqueue_validation: EnqueueValidation::default(),
+            quality_gate: QualityGate::default(),
         }
     }
 }
@@ -146,6 +343,15 @@ pub fn get_logs_dir() -> Result<PathBuf, Box<dyn std::error::Error>> {
     Ok(get_config_dir()?.join("logs"))
 }
 
+/// Root of the canonical session cache: `cacheDir` if configured, otherwise
+/// ~/.guidemode/sessions. Files are laid out as {provider}/{project}/{session_id}.jsonl.
+pub fn get_sessions_dir() -> Result<PathBuf, Box<dyn std::error::Error>> {
+    match load_config().ok().and_then(|config| config.cache_dir) {
+        Some(cache_dir) => Ok(PathBuf::from(cache_dir)),
+        None => Ok(get_config_dir()?.join("sessions")),
+    }
+}
+
 pub fn ensure_providers_dir() -> Result<(), Box<dyn std::error::Error>> {
     let providers_dir = get_providers_dir()?;
     if !providers_dir.exists() {
@@ -180,7 +386,19 @@ pub fn ensure_logs_dir() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+/// Where a provider's config is saved: in the active tenant's directory when
+/// logged in to a tenant, so each tenant keeps its own settings
 pub fn get_provider_config_path(provider_id: &str) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let providers_dir = get_providers_dir()?;
+    let dir = match crate::tenant::active_tenant_dir() {
+        Some(tenant_dir) => providers_dir.join(tenant_dir),
+        None => providers_dir,
+    };
+    Ok(dir.join(format!("{}.json", provider_id)))
+}
+
+/// The provider config shared by tenants without their own
+fn get_shared_provider_config_path(provider_id: &str) -> Result<PathBuf, Box<dyn std::error::Error>> {
     Ok(get_providers_dir()?.join(format!("{}.json", provider_id)))
 }
 
@@ -189,7 +407,10 @@ pub fn load_provider_config(
 ) -> Result<ProviderConfig, Box<dyn std::error::Error>> {
     ensure_providers_dir()?;
 
-    let config_file = get_provider_config_path(provider_id)?;
+    let mut config_file = get_provider_config_path(provider_id)?;
+    if !config_file.exists() {
+        config_file = get_shared_provider_config_path(provider_id)?;
+    }
 
     if config_file.exists() {
         let content = fs::read_to_string(config_file)?;
@@ -207,6 +428,15 @@ pub fn save_provider_config(
     ensure_providers_dir()?;
 
     let config_file = get_provider_config_path(provider_id)?;
+    if let Some(tenant_dir) = config_file.parent().filter(|dir| !dir.exists()) {
+        fs::create_dir_all(tenant_dir)?;
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(tenant_dir, fs::Permissions::from_mode(0o700))?;
+        }
+    }
     let content = serde_json::to_string_pretty(config)?;
 
     fs::write(&config_file, content)?;
@@ -233,6 +463,185 @@ pub fn delete_provider_config(provider_id: &str) -> Result<(), Box<dyn std::erro
     Ok(())
 }
 
+/// Local summarization of completed sessions. The endpoint speaks the OpenAI
+/// chat completions API, which local servers (Ollama, LM Studio, llama.cpp)
+/// and hosted providers both offer.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SummarizerConfig {
+    pub enabled: bool,
+    pub endpoint: String,
+    pub model: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_key: Option<String>,
+    /// Longest transcript sent to the endpoint; longer ones keep their end
+    #[serde(default = "default_summarizer_max_chars")]
+    pub max_transcript_chars: usize,
+}
+
+fn default_summarizer_max_chars() -> usize {
+    24_000
+}
+
+impl Default for SummarizerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            endpoint: "http://localhost:11434/v1/chat/completions".to_string(),
+            model: "llama3.2".to_string(),
+            api_key: None,
+            max_transcript_chars: default_summarizer_max_chars(),
+        }
+    }
+}
+
+pub fn get_summarizer_config_path() -> Result<PathBuf, Box<dyn std::error::Error>> {
+    Ok(get_config_dir()?.join("summarizer.json"))
+}
+
+pub fn load_summarizer_config() -> Result<SummarizerConfig, Box<dyn std::error::Error>> {
+    ensure_config_dir()?;
+
+    let config_file = get_summarizer_config_path()?;
+
+    if config_file.exists() {
+        let content = fs::read_to_string(config_file)?;
+        let config: SummarizerConfig = serde_json::from_str(&content)?;
+        Ok(config)
+    } else {
+        Ok(SummarizerConfig::default())
+    }
+}
+
+pub fn save_summarizer_config(config: &SummarizerConfig) -> Result<(), Box<dyn std::error::Error>> {
+    ensure_config_dir()?;
+
+    let config_file = get_summarizer_config_path()?;
+    let content = serde_json::to_string_pretty(config)?;
+
+    fs::write(&config_file, content)?;
+
+    // May hold an API key, so owner-only like config.json
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let metadata = fs::metadata(&config_file)?;
+        let mut permissions = metadata.permissions();
+        permissions.set_mode(0o600);
+        fs::set_permissions(&config_file, permissions)?;
+    }
+
+    Ok(())
+}
+
+/// Local assessments of sessions, filling in a rating and per-category
+/// improvement tips without the server. Uses the same kind of chat
+/// completions endpoint as the summarizer.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AssessorConfig {
+    pub enabled: bool,
+    pub endpoint: String,
+    pub model: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_key: Option<String>,
+    #[serde(default = "default_summarizer_max_chars")]
+    pub max_transcript_chars: usize,
+    /// Most assessment requests sent in a minute
+    #[serde(default = "default_assessor_requests_per_minute")]
+    pub requests_per_minute: u32,
+}
+
+fn default_assessor_requests_per_minute() -> u32 {
+    6
+}
+
+impl Default for AssessorConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            endpoint: "http://localhost:11434/v1/chat/completions".to_string(),
+            model: "llama3.2".to_string(),
+            api_key: None,
+            max_transcript_chars: default_summarizer_max_chars(),
+            requests_per_minute: default_assessor_requests_per_minute(),
+        }
+    }
+}
+
+pub fn get_assessor_config_path() -> Result<PathBuf, Box<dyn std::error::Error>> {
+    Ok(get_config_dir()?.join("assessor.json"))
+}
+
+pub fn load_assessor_config() -> Result<AssessorConfig, Box<dyn std::error::Error>> {
+    ensure_config_dir()?;
+
+    let config_file = get_assessor_config_path()?;
+
+    if config_file.exists() {
+        let content = fs::read_to_string(config_file)?;
+        let config: AssessorConfig = serde_json::from_str(&content)?;
+        Ok(config)
+    } else {
+        Ok(AssessorConfig::default())
+    }
+}
+
+pub fn save_assessor_config(config: &AssessorConfig) -> Result<(), Box<dyn std::error::Error>> {
+    ensure_config_dir()?;
+
+    let config_file = get_assessor_config_path()?;
+    let content = serde_json::to_string_pretty(config)?;
+
+    fs::write(&config_file, content)?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let metadata = fs::metadata(&config_file)?;
+        let mut permissions = metadata.permissions();
+        permissions.set_mode(0o600);
+        fs::set_permissions(&config_file, permissions)?;
+    }
+
+    Ok(())
+}
+
+/// Identifies this installation in uploads, so sessions read from a provider
+/// directory that is cloud-synced between machines can be told apart.
+/// Generated on first use and kept in ~/.guidemode/machine-id.
+pub fn get_machine_id() -> Result<String, Box<dyn std::error::Error>> {
+    ensure_config_dir()?;
+
+    let id_file = get_config_dir()?.join("machine-id");
+    let id = uuid::Uuid::new_v4().to_string();
+    if let Ok(existing) = fs::read_to_string(&id_file) {
+        if existing.trim().is_empty() {
+            // Left empty by an interrupted first run
+            fs::write(&id_file, &id)?;
+            return Ok(id);
+        }
+        return Ok(existing.trim().to_string());
+    }
+
+    // create_new so two uploads racing on first use agree on one ID
+    match fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&id_file)
+    {
+        Ok(mut file) => {
+            use std::io::Write;
+            file.write_all(id.as_bytes())?;
+            Ok(id)
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+            Ok(fs::read_to_string(&id_file)?.trim().to_string())
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
 /// Determines whether a project should be included based on the provider configuration.
 ///
 /// Returns `true` if:
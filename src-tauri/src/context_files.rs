@@ -1,7 +1,8 @@
 use ignore::WalkBuilder;
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 /// Context file information returned to the frontend
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -11,6 +12,90 @@ pub struct ContextFile {
     pub relative_path: String, // Path relative to cwd
     pub content: String,       // File contents
     pub size: u64,             // File size in bytes
+    pub token_count: u64,      // Estimated tokens, excluding imports
+}
+
+/// How tokens are estimated
+///
+/// No provider tokenizer is bundled, so both are approximations; they're
+/// meant for spotting oversized files, not for exact accounting.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Tokenizer {
+    /// About 4 characters per token, the usual estimate for English and code
+    #[default]
+    Characters,
+    /// About 3 words per 4 tokens, closer for prose-heavy files
+    Words,
+}
+
+impl Tokenizer {
+    pub fn count_tokens(&self, text: &str) -> u64 {
+        match self {
+            Tokenizer::Characters => (text.chars().count() as u64).div_ceil(4),
+            Tokenizer::Words => (text.split_whitespace().count() as u64 * 4).div_ceil(3),
+        }
+    }
+}
+
+/// Default token count above which a single context file is flagged
+pub const DEFAULT_FILE_TOKEN_THRESHOLD: u64 = 5_000;
+
+/// Default token count above which all context files together are flagged
+pub const DEFAULT_TOTAL_TOKEN_THRESHOLD: u64 = 20_000;
+
+/// Imports nested deeper than this are not followed (Claude Code's limit)
+const MAX_IMPORT_DEPTH: usize = 5;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ContextTokenOptions {
+    pub tokenizer: Tokenizer,
+    pub file_threshold: u64,
+    pub total_threshold: u64,
+}
+
+impl Default for ContextTokenOptions {
+    fn default() -> Self {
+        Self {
+            tokenizer: Tokenizer::default(),
+            file_threshold: DEFAULT_FILE_TOKEN_THRESHOLD,
+            total_threshold: DEFAULT_TOTAL_TOKEN_THRESHOLD,
+        }
+    }
+}
+
+/// A file pulled into a context file with an `@path` import
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContextImport {
+    pub import: String,    // As written, without the @
+    pub file_path: String, // Absolute path
+    pub depth: usize,      // 1 for direct imports
+    pub token_count: u64,
+}
+
+/// Token budget of one context file and everything it imports
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContextFileTokens {
+    pub relative_path: String,
+    pub file_path: String,
+    pub token_count: u64,
+    pub import_token_count: u64,
+    pub total_token_count: u64,
+    pub imports: Vec<ContextImport>,
+    pub exceeds_threshold: bool,
+}
+
+/// Token budget of all context files in a directory
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContextTokenReport {
+    pub tokenizer: Tokenizer,
+    pub files: Vec<ContextFileTokens>,
+    /// Each file counted once, even when imported by several context files
+    pub total_token_count: u64,
+    pub file_threshold: u64,
+    pub total_threshold: u64,
+    pub exceeds_total_threshold: bool,
 }
 
 /// Context file names to search for (case-insensitive)
@@ -18,6 +103,14 @@ const CONTEXT_FILE_NAMES: &[&str] = &["CLAUDE.md", "AGENTS.md", "GEMINI.md"];
 
 /// Scan a directory for context files, honoring .gitignore patterns
 pub fn scan_context_files(cwd: &str) -> Result<Vec<ContextFile>, String> {
+    scan_context_files_with_tokenizer(cwd, Tokenizer::default())
+}
+
+/// Scan a directory for context files, counting tokens with `tokenizer`
+pub fn scan_context_files_with_tokenizer(
+    cwd: &str,
+    tokenizer: Tokenizer,
+) -> Result<Vec<ContextFile>, String> {
     let cwd_path = Path::new(cwd);
 
     // Check if directory exists
@@ -56,7 +149,7 @@ pub fn scan_context_files(cwd: &str) -> Result<Vec<ContextFile>, String> {
                         .any(|name| name.to_lowercase() == file_name_lower);
 
                     if is_context_file {
-                        match read_context_file(path, cwd_path) {
+                        match read_context_file(path, cwd_path, tokenizer) {
                             Ok(context_file) => context_files.push(context_file),
                             Err(e) => {
                                 // Log error but continue scanning
@@ -79,8 +172,144 @@ pub fn scan_context_files(cwd: &str) -> Result<Vec<ContextFile>, String> {
     Ok(context_files)
 }
 
+/// Report the token budget of a directory's context files and their imports
+pub fn context_token_report(
+    cwd: &str,
+    options: &ContextTokenOptions,
+) -> Result<ContextTokenReport, String> {
+    let context_files = scan_context_files_with_tokenizer(cwd, options.tokenizer)?;
+
+    let mut counted: HashSet<String> = HashSet::new();
+    let mut total_token_count = 0;
+    let mut files = Vec::new();
+
+    for context_file in context_files {
+        let path = PathBuf::from(&context_file.file_path);
+        let mut seen = HashSet::from([path.clone()]);
+        let mut imports = Vec::new();
+        collect_imports(
+            &path,
+            &context_file.content,
+            1,
+            options.tokenizer,
+            &mut seen,
+            &mut imports,
+        );
+
+        if counted.insert(context_file.file_path.clone()) {
+            total_token_count += context_file.token_count;
+        }
+        for import in &imports {
+            if counted.insert(import.file_path.clone()) {
+                total_token_count += import.token_count;
+            }
+        }
+
+        let import_token_count: u64 = imports.iter().map(|import| import.token_count).sum();
+        let total = context_file.token_count + import_token_count;
+        files.push(ContextFileTokens {
+            relative_path: context_file.relative_path,
+            file_path: context_file.file_path,
+            token_count: context_file.token_count,
+            import_token_count,
+            total_token_count: total,
+            imports,
+            exceeds_threshold: total > options.file_threshold,
+        });
+    }
+
+    Ok(ContextTokenReport {
+        tokenizer: options.tokenizer,
+        files,
+        total_token_count,
+        file_threshold: options.file_threshold,
+        total_threshold: options.total_threshold,
+        exceeds_total_threshold: total_token_count > options.total_threshold,
+    })
+}
+
+/// Follow `@path` imports in a context file, depth first, each file once
+fn collect_imports(
+    path: &Path,
+    content: &str,
+    depth: usize,
+    tokenizer: Tokenizer,
+    seen: &mut HashSet<PathBuf>,
+    imports: &mut Vec<ContextImport>,
+) {
+    if depth > MAX_IMPORT_DEPTH {
+        return;
+    }
+    let base_dir = path.parent().unwrap_or(Path::new(""));
+
+    for (_, import) in parse_imports(content) {
+        // Unresolvable imports are usually @mentions rather than paths
+        let Some(resolved) = resolve_import(base_dir, &import) else {
+            continue;
+        };
+        let Ok(canonical) = resolved.canonicalize() else {
+            continue;
+        };
+        if !canonical.is_file() || !seen.insert(canonical.clone()) {
+            continue;
+        }
+        let Ok(imported) = fs::read_to_string(&canonical) else {
+            continue;
+        };
+
+        imports.push(ContextImport {
+            import,
+            file_path: canonical.to_string_lossy().to_string(),
+            depth,
+            token_count: tokenizer.count_tokens(&imported),
+        });
+        collect_imports(&canonical, &imported, depth + 1, tokenizer, seen, imports);
+    }
+}
+
+/// `@path` imports in markdown with their 1-based line numbers, skipping code
+/// blocks and inline code
+pub(crate) fn parse_imports(content: &str) -> Vec<(usize, String)> {
+    let mut imports = Vec::new();
+    let mut in_code_block = false;
+
+    for (line_index, line) in content.lines().enumerate() {
+        if line.trim_start().starts_with("```") {
+            in_code_block = !in_code_block;
+            continue;
+        }
+        if in_code_block {
+            continue;
+        }
+
+        // Odd segments between backticks are inline code
+        for (index, segment) in line.split('`').enumerate() {
+            if index % 2 == 1 {
+                continue;
+            }
+            for word in segment.split_whitespace() {
+                if let Some(import) = word.strip_prefix('@') {
+                    let import = import.trim_end_matches([',', '.', ';', ':', ')', '!', '?']);
+                    if !import.is_empty() {
+                        imports.push((line_index + 1, import.to_string()));
+                    }
+                }
+            }
+        }
+    }
+
+    imports
+}
+
+pub(crate) fn resolve_import(base_dir: &Path, import: &str) -> Option<PathBuf> {
+    if let Some(rest) = import.strip_prefix("~/") {
+        return dirs::home_dir().map(|home| home.join(rest));
+    }
+    Some(base_dir.join(import))
+}
+
 /// Read a context file and return its information
-fn read_context_file(path: &Path, cwd: &Path) -> Result<ContextFile, String> {
+fn read_context_file(path: &Path, cwd: &Path, tokenizer: Tokenizer) -> Result<ContextFile, String> {
     // Read file contents
     let content =
         fs::read_to_string(path).map_err(|e| format!("Failed to read file {:?}: {}", path, e))?;
@@ -114,6 +343,7 @@ fn read_context_file(path: &Path, cwd: &Path) -> Result<ContextFile, String> {
         file_name,
         file_path,
         relative_path,
+        token_count: tokenizer.count_tokens(&content),
         content,
         size: metadata.len(),
     })
@@ -180,6 +410,76 @@ mod tests {
         assert!(files[0].relative_path.contains("src"));
     }
 
+    #[test]
+    fn test_count_tokens() {
+        assert_eq!(Tokenizer::Characters.count_tokens(""), 0);
+        assert_eq!(Tokenizer::Characters.count_tokens("abcdefgh"), 2);
+        assert_eq!(Tokenizer::Characters.count_tokens("abcdefghi"), 3);
+        assert_eq!(Tokenizer::Words.count_tokens("one two three"), 4);
+    }
+
+    #[test]
+    fn test_parse_imports() {
+        let content = "See @docs/style.md, and @~/notes.md.\n\
+                       Email me at dev@example.com or `@not/this.md`\n\
+                       ```\n@not/code.md\n```\n";
+
+        assert_eq!(
+            parse_imports(content),
+            vec![
+                (1, "docs/style.md".to_string()),
+                (1, "~/notes.md".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_context_token_report() {
+        let temp_dir = TempDir::new().unwrap();
+        let temp_path = temp_dir.path();
+
+        fs::create_dir(temp_path.join("docs")).unwrap();
+        fs::write(
+            temp_path.join("CLAUDE.md"),
+            "Follow @docs/style.md and @docs/missing.md",
+        )
+        .unwrap();
+        fs::write(temp_path.join("AGENTS.md"), "Also @docs/style.md").unwrap();
+        // Imports resolve relative to the importing file, and cycles stop
+        fs::write(
+            temp_path.join("docs/style.md"),
+            "x".repeat(400) + " @rules.md",
+        )
+        .unwrap();
+        fs::write(
+            temp_path.join("docs/rules.md"),
+            "y".repeat(40) + " @style.md",
+        )
+        .unwrap();
+
+        let options = ContextTokenOptions {
+            file_threshold: 100,
+            ..Default::default()
+        };
+        let report = context_token_report(temp_path.to_str().unwrap(), &options).unwrap();
+
+        let claude = report
+            .files
+            .iter()
+            .find(|file| file.relative_path == "CLAUDE.md")
+            .unwrap();
+        assert_eq!(claude.imports.len(), 2);
+        assert_eq!(claude.imports[0].depth, 1);
+        assert_eq!(claude.imports[1].depth, 2);
+        assert_eq!(claude.token_count, 11);
+        assert_eq!(claude.import_token_count, 103 + 13);
+        assert!(claude.exceeds_threshold);
+
+        // Shared imports count once towards the total
+        assert_eq!(report.total_token_count, 11 + 5 + 103 + 13);
+        assert!(!report.exceeds_total_threshold);
+    }
+
     #[test]
     fn test_gitignore_respected() {
         let temp_dir = TempDir::new().unwrap();
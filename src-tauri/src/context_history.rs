@@ -0,0 +1,306 @@
+//! History of context files
+//!
+//! Every scan of a project's context files records a snapshot of each file
+//! whose content hash changed since its last snapshot. Each version is
+//! reported with the quality metrics of the sessions that ran in the project
+//! while it was current, so edits to CLAUDE.md or AGENTS.md can be compared
+//! against how sessions went before and after.
+
+use crate::context_files::ContextFile;
+use crate::database::with_connection_mut;
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, Result};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::Path;
+
+/// One recorded version of a context file
+#[derive(Debug, Clone, Serialize)]
+pub struct ContextFileVersion {
+    pub id: i64,
+    pub file_path: String,
+    pub relative_path: String,
+    pub content_hash: String,
+    pub size: i64,
+    pub token_count: i64,
+    pub modified_at: i64, // Unix milliseconds
+    pub captured_at: i64, // Unix milliseconds
+    /// Sessions started in the project while this version was current
+    pub sessions: VersionSessions,
+}
+
+/// Quality of the sessions that ran with a context file version
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct VersionSessions {
+    pub session_count: i64,
+    pub avg_process_quality_score: Option<f64>,
+    pub avg_task_success_rate: Option<f64>,
+    pub avg_interruption_rate: Option<f64>,
+}
+
+/// Record a snapshot if the file's content changed since its last one
+fn record(
+    conn: &Connection,
+    cwd: &str,
+    file: &ContextFile,
+    modified_at: i64,
+    now: i64,
+) -> Result<bool> {
+    let content_hash = format!("{:x}", Sha256::digest(file.content.as_bytes()));
+    let latest_hash = conn
+        .query_row(
+            "SELECT content_hash FROM context_file_snapshots
+             WHERE file_path = ?
+             ORDER BY modified_at DESC, id DESC
+             LIMIT 1",
+            params![file.file_path],
+            |row| row.get::<_, String>(0),
+        )
+        .ok();
+    if latest_hash.as_deref() == Some(content_hash.as_str()) {
+        return Ok(false);
+    }
+
+    conn.execute(
+        "INSERT INTO context_file_snapshots (
+            cwd, file_path, relative_path, content_hash, content,
+            size, token_count, modified_at, captured_at
+        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        params![
+            cwd,
+            file.file_path,
+            file.relative_path,
+            content_hash,
+            file.content,
+            file.size as i64,
+            file.token_count as i64,
+            modified_at,
+            now,
+        ],
+    )?;
+    Ok(true)
+}
+
+/// Escape `%`, `_` and the escape character itself for a `LIKE ... ESCAPE '\'` pattern
+fn escape_like(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '\\' | '%' | '_') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Quality of sessions started under `cwd` in `[start, end)`
+fn version_sessions(
+    conn: &Connection,
+    cwd: &str,
+    start: i64,
+    end: Option<i64>,
+) -> Result<VersionSessions> {
+    conn.query_row(
+        "SELECT COUNT(*), AVG(m.process_quality_score), AVG(m.task_success_rate),
+                AVG(m.interruption_rate)
+         FROM agent_sessions s
+         LEFT JOIN session_metrics m ON m.session_id = s.session_id
+         WHERE (s.cwd = ?1 OR s.cwd LIKE ?4 ESCAPE '\\')
+           AND s.session_start_time >= ?2
+           AND (?3 IS NULL OR s.session_start_time < ?3)",
+        params![cwd, start, end, format!("{}/%", escape_like(cwd))],
+        |row| {
+            Ok(VersionSessions {
+                session_count: row.get(0)?,
+                avg_process_quality_score: row.get(1)?,
+                avg_task_success_rate: row.get(2)?,
+                avg_interruption_rate: row.get(3)?,
+            })
+        },
+    )
+}
+
+/// Versions of a file, oldest first
+fn history(conn: &Connection, file_path: &str) -> Result<Vec<ContextFileVersion>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, cwd, file_path, relative_path, content_hash, size, token_count,
+                modified_at, captured_at
+         FROM context_file_snapshots
+         WHERE file_path = ?
+         ORDER BY modified_at, id",
+    )?;
+    let rows = stmt
+        .query_map(params![file_path], |row| {
+            Ok((
+                row.get::<_, String>(1)?,
+                ContextFileVersion {
+                    id: row.get(0)?,
+                    file_path: row.get(2)?,
+                    relative_path: row.get(3)?,
+                    content_hash: row.get(4)?,
+                    size: row.get(5)?,
+                    token_count: row.get(6)?,
+                    modified_at: row.get(7)?,
+                    captured_at: row.get(8)?,
+                    sessions: VersionSessions::default(),
+                },
+            ))
+        })?
+        .collect::<Result<Vec<_>>>()?;
+
+    // A version is current from its modification until the next version's
+    let next_modified: Vec<Option<i64>> = rows
+        .iter()
+        .skip(1)
+        .map(|(_, version)| Some(version.modified_at))
+        .chain(std::iter::once(None))
+        .collect();
+
+    rows.into_iter()
+        .zip(next_modified)
+        .map(|((cwd, mut version), end)| {
+            version.sessions = version_sessions(conn, &cwd, version.modified_at, end)?;
+            Ok(version)
+        })
+        .collect()
+}
+
+/// Record snapshots of scanned context files that changed since the last scan
+///
+/// Returns the number of new snapshots.
+pub fn record_context_file_snapshots(cwd: &str, files: &[ContextFile]) -> Result<usize, String> {
+    let now = Utc::now().timestamp_millis();
+
+    with_connection_mut(|conn| {
+        let mut recorded = 0;
+        for file in files {
+            // Modification time dates the edit better than the scan that noticed it
+            let modified_at = fs::metadata(&file.file_path)
+                .and_then(|metadata| metadata.modified())
+                .map(|modified| DateTime::<Utc>::from(modified).timestamp_millis())
+                .unwrap_or(now);
+            if record(conn, cwd, file, modified_at, now)? {
+                recorded += 1;
+            }
+        }
+        Ok(recorded)
+    })
+    .map_err(|e| format!("Failed to record context file snapshots: {}", e))
+}
+
+/// Get the recorded versions of a context file, oldest first
+pub fn get_context_file_history(file_path: &str) -> Result<Vec<ContextFileVersion>, String> {
+    with_connection_mut(|conn| history(conn, file_path))
+        .map_err(|e| format!("Failed to get context file history: {}", e))
+}
+
+/// Unified diff between two snapshots
+pub fn diff_context_file_snapshots(from_id: i64, to_id: i64) -> Result<String, String> {
+    let load = |id: i64| {
+        with_connection_mut(|conn| {
+            conn.query_row(
+                "SELECT relative_path, content FROM context_file_snapshots WHERE id = ?",
+                params![id],
+                |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)),
+            )
+        })
+        .map_err(|e| format!("Failed to load context file snapshot {}: {}", id, e))
+    };
+    let (old_path, old_content) = load(from_id)?;
+    let (new_path, new_content) = load(to_id)?;
+
+    let mut patch = git2::Patch::from_buffers(
+        old_content.as_bytes(),
+        Some(Path::new(&old_path)),
+        new_content.as_bytes(),
+        Some(Path::new(&new_path)),
+        None,
+    )
+    .map_err(|e| format!("Failed to diff snapshots: {}", e))?;
+    let buf = patch
+        .to_buf()
+        .map_err(|e| format!("Failed to print snapshot diff: {}", e))?;
+
+    Ok(String::from_utf8_lossy(&buf).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context_file(content: &str) -> ContextFile {
+        ContextFile {
+            file_name: "CLAUDE.md".to_string(),
+            file_path: "/work/app/CLAUDE.md".to_string(),
+            relative_path: "CLAUDE.md".to_string(),
+            content: content.to_string(),
+            size: content.len() as u64,
+            token_count: 1,
+        }
+    }
+
+    fn session(conn: &Connection, id: &str, cwd: &str, start: i64, quality: i64) {
+        conn.execute(
+            "INSERT INTO agent_sessions (
+                id, provider, project_name, session_id, file_name, file_path, file_size,
+                session_start_time, cwd, created_at, uploaded_at
+            ) VALUES (?1, 'claude-code', 'app', ?1, 'f', 'f', 0, ?2, ?3, 0, 0)",
+            params![id, start, cwd],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO session_metrics (id, session_id, provider, timestamp, process_quality_score, created_at)
+             VALUES (?1, ?1, 'claude-code', 0, ?2, 0)",
+            params![id, quality],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_records_only_changed_content() {
+        let conn = crate::database::test_connection();
+
+        assert!(record(&conn, "/work/app", &context_file("v1"), 100, 150).unwrap());
+        assert!(!record(&conn, "/work/app", &context_file("v1"), 100, 250).unwrap());
+        assert!(record(&conn, "/work/app", &context_file("v2"), 300, 350).unwrap());
+
+        let versions = history(&conn, "/work/app/CLAUDE.md").unwrap();
+        assert_eq!(versions.len(), 2);
+        assert_eq!(versions[0].modified_at, 100);
+        assert_eq!(versions[1].modified_at, 300);
+    }
+
+    #[test]
+    fn test_history_attributes_sessions_to_versions() {
+        let conn = crate::database::test_connection();
+        record(&conn, "/work/app", &context_file("v1"), 100, 100).unwrap();
+        record(&conn, "/work/app", &context_file("v2"), 300, 300).unwrap();
+
+        session(&conn, "before", "/work/app", 50, 10);
+        session(&conn, "s1", "/work/app", 150, 60);
+        session(&conn, "s2", "/work/app/src", 200, 80);
+        session(&conn, "s3", "/work/app", 400, 90);
+        session(&conn, "other", "/work/application", 400, 10);
+
+        let versions = history(&conn, "/work/app/CLAUDE.md").unwrap();
+
+        assert_eq!(versions[0].sessions.session_count, 2);
+        assert_eq!(versions[0].sessions.avg_process_quality_score, Some(70.0));
+        assert_eq!(versions[1].sessions.session_count, 1);
+        assert_eq!(versions[1].sessions.avg_process_quality_score, Some(90.0));
+    }
+
+    #[test]
+    fn test_history_treats_wildcards_in_cwd_literally() {
+        let conn = crate::database::test_connection();
+        record(&conn, "/work/my_app", &context_file("v1"), 100, 100).unwrap();
+
+        session(&conn, "s1", "/work/my_app/src", 150, 60);
+        session(&conn, "other", "/work/myXapp/src", 150, 10);
+
+        let versions = history(&conn, "/work/my_app/CLAUDE.md").unwrap();
+        assert_eq!(versions[0].sessions.session_count, 1);
+        assert_eq!(escape_like(r"C:\50%_off"), r"C:\\50\%\_off");
+    }
+}
@@ -0,0 +1,317 @@
+//! Lint pass over context files (CLAUDE.md, AGENTS.md, GEMINI.md)
+//!
+//! Checks are heuristics meant to point at likely problems, not a grammar:
+//! `@imports` that don't resolve, headings repeated within a file, files over
+//! the token threshold, and "always"/"never" instructions that contradict
+//! each other, within a file or across files.
+
+use crate::context_files::{
+    parse_imports, resolve_import, scan_context_files, ContextFile, DEFAULT_FILE_TOKEN_THRESHOLD,
+};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LintSeverity {
+    Error,
+    Warning,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LintRule {
+    BrokenImport,
+    DuplicateSection,
+    OversizedFile,
+    ContradictoryInstructions,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LintFinding {
+    pub rule: LintRule,
+    pub severity: LintSeverity,
+    pub relative_path: String,
+    pub line: Option<usize>, // 1-based
+    pub message: String,
+}
+
+/// Instruction phrases shorter than this are too vague to compare
+const MIN_INSTRUCTION_WORDS: usize = 2;
+
+/// Words of an instruction compared for contradictions
+const MAX_INSTRUCTION_WORDS: usize = 5;
+
+/// Prefixes that negate an instruction, checked before the positive ones
+const NEGATIVE_MARKERS: &[&str] = &["never ", "don't ", "do not ", "must not ", "should not "];
+
+const POSITIVE_MARKERS: &[&str] = &["always ", "must ", "should "];
+
+/// Lint the context files found under `cwd`
+pub fn lint_context_files(cwd: &str) -> Result<Vec<LintFinding>, String> {
+    let files = scan_context_files(cwd)?;
+
+    let mut findings = Vec::new();
+    for file in &files {
+        findings.extend(check_imports(file));
+        findings.extend(check_duplicate_sections(file));
+        findings.extend(check_size(file, DEFAULT_FILE_TOKEN_THRESHOLD));
+    }
+    findings.extend(check_contradictions(&files));
+
+    Ok(findings)
+}
+
+/// Lines outside fenced code blocks, with 1-based line numbers
+fn prose_lines(content: &str) -> Vec<(usize, &str)> {
+    let mut in_code_block = false;
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| {
+            if line.trim_start().starts_with("```") {
+                in_code_block = !in_code_block;
+                return false;
+            }
+            !in_code_block
+        })
+        .map(|(index, line)| (index + 1, line))
+        .collect()
+}
+
+fn check_imports(file: &ContextFile) -> Vec<LintFinding> {
+    let base_dir = Path::new(&file.file_path).parent().unwrap_or(Path::new(""));
+
+    parse_imports(&file.content)
+        .into_iter()
+        // Bare @names are usually mentions, not imports
+        .filter(|(_, import)| import.contains('/') || import.contains('.'))
+        .filter(|(_, import)| !resolve_import(base_dir, import).is_some_and(|path| path.is_file()))
+        .map(|(line, import)| LintFinding {
+            rule: LintRule::BrokenImport,
+            severity: LintSeverity::Error,
+            relative_path: file.relative_path.clone(),
+            line: Some(line),
+            message: format!("@{} does not resolve to a file", import),
+        })
+        .collect()
+}
+
+fn check_duplicate_sections(file: &ContextFile) -> Vec<LintFinding> {
+    let mut first_seen: HashMap<(usize, String), usize> = HashMap::new();
+    let mut findings = Vec::new();
+
+    for (line, text) in prose_lines(&file.content) {
+        let trimmed = text.trim_start();
+        let level = trimmed.chars().take_while(|c| *c == '#').count();
+        if level == 0 || !trimmed[level..].starts_with(' ') {
+            continue;
+        }
+        let heading = trimmed[level..].trim().to_lowercase();
+
+        match first_seen.get(&(level, heading.clone())) {
+            Some(first_line) => findings.push(LintFinding {
+                rule: LintRule::DuplicateSection,
+                severity: LintSeverity::Warning,
+                relative_path: file.relative_path.clone(),
+                line: Some(line),
+                message: format!(
+                    "Section \"{}\" already appears on line {}",
+                    trimmed[level..].trim(),
+                    first_line
+                ),
+            }),
+            None => {
+                first_seen.insert((level, heading), line);
+            }
+        }
+    }
+    findings
+}
+
+fn check_size(file: &ContextFile, threshold: u64) -> Option<LintFinding> {
+    (file.token_count > threshold).then(|| LintFinding {
+        rule: LintRule::OversizedFile,
+        severity: LintSeverity::Warning,
+        relative_path: file.relative_path.clone(),
+        line: None,
+        message: format!(
+            "About {} tokens, over the {} token budget; it is loaded into every session",
+            file.token_count, threshold
+        ),
+    })
+}
+
+/// An "always"/"never" style instruction
+struct Instruction<'a> {
+    file: &'a ContextFile,
+    line: usize,
+    negative: bool,
+    words: Vec<String>,
+}
+
+/// Extract the instruction a line gives, if it starts with a directive marker
+fn parse_instruction(line: &str) -> Option<(bool, Vec<String>)> {
+    let text = line
+        .trim_start()
+        .trim_start_matches(['-', '*', '+', ' '])
+        .to_lowercase()
+        .replace('\u{2019}', "'");
+
+    let (negative, rest) = NEGATIVE_MARKERS
+        .iter()
+        .find_map(|marker| text.strip_prefix(marker).map(|rest| (true, rest)))
+        .or_else(|| {
+            POSITIVE_MARKERS
+                .iter()
+                .find_map(|marker| text.strip_prefix(marker).map(|rest| (false, rest)))
+        })?;
+
+    // The instruction ends at the first clause break
+    let clause = rest.split(['.', ',', ';', ':', '(']).next().unwrap_or("");
+    let words: Vec<String> = clause
+        .split_whitespace()
+        .take(MAX_INSTRUCTION_WORDS)
+        .map(|word| {
+            word.trim_matches(|c: char| !c.is_alphanumeric())
+                .to_string()
+        })
+        .filter(|word| !word.is_empty())
+        .collect();
+
+    (words.len() >= MIN_INSTRUCTION_WORDS).then_some((negative, words))
+}
+
+/// Whether one instruction's words start the other's ("use tabs" / "use tabs for indentation")
+fn same_subject(a: &[String], b: &[String]) -> bool {
+    let shorter = a.len().min(b.len());
+    a[..shorter] == b[..shorter]
+}
+
+fn check_contradictions(files: &[ContextFile]) -> Vec<LintFinding> {
+    let instructions: Vec<Instruction> = files
+        .iter()
+        .flat_map(|file| {
+            prose_lines(&file.content)
+                .into_iter()
+                .filter_map(move |(line, text)| {
+                    parse_instruction(text).map(|(negative, words)| Instruction {
+                        file,
+                        line,
+                        negative,
+                        words,
+                    })
+                })
+        })
+        .collect();
+
+    let mut findings = Vec::new();
+    for (index, later) in instructions.iter().enumerate() {
+        let conflict = instructions[..index].iter().find(|earlier| {
+            earlier.negative != later.negative && same_subject(&earlier.words, &later.words)
+        });
+        if let Some(earlier) = conflict {
+            findings.push(LintFinding {
+                rule: LintRule::ContradictoryInstructions,
+                severity: LintSeverity::Warning,
+                relative_path: later.file.relative_path.clone(),
+                line: Some(later.line),
+                message: format!(
+                    "\"{}\" conflicts with {}:{}",
+                    later.words.join(" "),
+                    earlier.file.relative_path,
+                    earlier.line
+                ),
+            });
+        }
+    }
+    findings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_parse_instruction() {
+        assert_eq!(
+            parse_instruction("- Always use tabs for indentation."),
+            Some((
+                false,
+                vec!["use", "tabs", "for", "indentation"]
+                    .into_iter()
+                    .map(String::from)
+                    .collect()
+            ))
+        );
+        assert_eq!(
+            parse_instruction("Don\u{2019}t use tabs"),
+            Some((true, vec!["use".to_string(), "tabs".to_string()]))
+        );
+        assert_eq!(parse_instruction("Never guess"), None);
+        assert_eq!(parse_instruction("Use tabs"), None);
+    }
+
+    #[test]
+    fn test_lint_context_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let temp_path = temp_dir.path();
+
+        fs::create_dir(temp_path.join("docs")).unwrap();
+        fs::write(temp_path.join("docs/style.md"), "Style").unwrap();
+        fs::write(
+            temp_path.join("CLAUDE.md"),
+            "# Setup\n\
+             See @docs/style.md and @docs/gone.md, ask @alice\n\
+             - Always use tabs for indentation\n\
+             ```\n\
+             # Setup\n\
+             ```\n\
+             # Setup\n",
+        )
+        .unwrap();
+        fs::write(temp_path.join("AGENTS.md"), "- Never use tabs\n").unwrap();
+
+        let findings = lint_context_files(temp_path.to_str().unwrap()).unwrap();
+
+        let broken = findings
+            .iter()
+            .find(|finding| finding.rule == LintRule::BrokenImport)
+            .unwrap();
+        assert_eq!(broken.line, Some(2));
+        assert!(broken.message.contains("docs/gone.md"));
+
+        let duplicate = findings
+            .iter()
+            .find(|finding| finding.rule == LintRule::DuplicateSection)
+            .unwrap();
+        assert_eq!(duplicate.line, Some(7));
+
+        // AGENTS.md sorts first, so the CLAUDE.md instruction is reported
+        let contradiction = findings
+            .iter()
+            .find(|finding| finding.rule == LintRule::ContradictoryInstructions)
+            .unwrap();
+        assert_eq!(contradiction.relative_path, "CLAUDE.md");
+        assert_eq!(contradiction.line, Some(3));
+        assert!(contradiction.message.contains("AGENTS.md:1"));
+
+        assert_eq!(findings.len(), 3);
+    }
+
+    #[test]
+    fn test_oversized_file() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("CLAUDE.md"), "word ".repeat(100)).unwrap();
+        let files = scan_context_files(temp_dir.path().to_str().unwrap()).unwrap();
+
+        assert!(check_size(&files[0], 1_000).is_none());
+        assert_eq!(
+            check_size(&files[0], 100).map(|finding| finding.rule),
+            Some(LintRule::OversizedFile)
+        );
+    }
+}
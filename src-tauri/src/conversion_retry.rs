@@ -0,0 +1,144 @@
+//! Converting quarantined sessions again
+//!
+//! Sessions with quarantined lines are converted again from their source
+//! files on the first launch after an app update, and on request from the
+//! conversion errors list. Each conversion replaces the session's quarantined
+//! lines, so lines the updated converters handle drop out and those they
+//! still can't are recorded against the new version, which keeps them from
+//! being retried again until the next update.
+
+use crate::error::GuideModeError;
+use crate::events::{OperationKind, Progress, ProgressPhase};
+use crate::logging::{log_info, log_warn};
+use crate::providers::common::quarantine::{self, QuarantinedSession};
+use crate::session_import::reconvert_raw;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::path::Path;
+
+#[derive(Debug, Default, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RetryReport {
+    pub retried: usize,
+    /// Sessions that now convert without quarantined lines
+    pub recovered: usize,
+    pub still_failing: usize,
+    /// Sessions whose source file is gone or couldn't be converted at all
+    pub failed: usize,
+}
+
+/// Convert every quarantined session again, optionally for one provider
+pub fn retry_quarantined(
+    provider: Option<&str>,
+    progress: &Progress,
+) -> Result<RetryReport, GuideModeError> {
+    retry(quarantine::quarantined_sessions(provider)?, progress)
+}
+
+/// Retry sessions quarantined by an earlier app version in the background
+pub fn retry_after_update() {
+    tauri::async_runtime::spawn_blocking(|| {
+        let sessions = match quarantine::sessions_to_retry() {
+            Ok(sessions) => sessions,
+            Err(e) => {
+                log_warn("conversion", &e).unwrap_or_default();
+                return;
+            }
+        };
+        if sessions.is_empty() {
+            return;
+        }
+
+        log_info(
+            "conversion",
+            &format!(
+                "🔄 Retrying {} sessions quarantined before GuideMode {}",
+                sessions.len(),
+                quarantine::APP_VERSION
+            ),
+        )
+        .unwrap_or_default();
+        let progress = Progress::silent(OperationKind::Maintenance, None);
+        let report = match retry(sessions, &progress) {
+            Ok(report) => report,
+            Err(e) => {
+                log_warn("conversion", &e.to_string()).unwrap_or_default();
+                return;
+            }
+        };
+        log_info(
+            "conversion",
+            &format!(
+                "✓ Retried quarantined sessions: {} recovered, {} still failing, {} failed",
+                report.recovered, report.still_failing, report.failed
+            ),
+        )
+        .unwrap_or_default();
+    });
+}
+
+fn retry(
+    sessions: Vec<QuarantinedSession>,
+    progress: &Progress,
+) -> Result<RetryReport, GuideModeError> {
+    let mut report = RetryReport::default();
+    let mut converted = Vec::new();
+    let total = sessions.len();
+    progress.report(
+        ProgressPhase::Starting,
+        0,
+        total,
+        format!("Converting {} quarantined sessions again...", total),
+    );
+
+    for (index, session) in sessions.into_iter().enumerate() {
+        progress.check_cancelled(index, total)?;
+        progress.report_item(index + 1, total, || {
+            format!("Converting session {} of {}...", index + 1, total)
+        });
+        report.retried += 1;
+        let source = Path::new(&session.source_path);
+        let result = if source.exists() {
+            reconvert_raw(source, &session.provider).map(|_| ())
+        } else {
+            Err(format!(
+                "Source file {} no longer exists",
+                session.source_path
+            ))
+        };
+        match result {
+            Ok(()) => converted.push(session),
+            Err(e) => {
+                log_warn(
+                    &session.provider,
+                    &format!(
+                        "Failed to convert quarantined session {} again: {}",
+                        session.session_id, e
+                    ),
+                )
+                .unwrap_or_default();
+                report.failed += 1;
+            }
+        }
+    }
+
+    let remaining: HashSet<(String, String)> = quarantine::quarantined_sessions(None)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|session| (session.provider, session.session_id))
+        .collect();
+    for session in converted {
+        if remaining.contains(&(session.provider, session.session_id)) {
+            report.still_failing += 1;
+        } else {
+            report.recovered += 1;
+        }
+    }
+    progress.report(
+        ProgressPhase::Complete,
+        total,
+        total,
+        format!("{} of {} sessions recovered", report.recovered, total),
+    );
+    Ok(report)
+}
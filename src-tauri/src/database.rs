@@ -1,11 +1,15 @@
 use crate::logging::{log_debug, log_info};
-use base64::{engine::general_purpose, Engine as _};
 use chrono::{DateTime, Utc};
 use lazy_static::lazy_static;
-use rusqlite::{params, Connection, Result};
+use r2d2::PooledConnection;
+use r2d2_sqlite::SqliteConnectionManager;
+use rusqlite::{params, Connection, Result, TransactionBehavior};
 use serde::{Deserialize, Serialize};
-use std::sync::Mutex;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
 use tauri::Emitter;
+use crate::windows::emit_session_event;
+use crate::upload_queue::quality_gate::LOW_SIGNAL;
 use uuid::Uuid;
 
 /// Type alias for session data tuple returned from database queries
@@ -18,43 +22,58 @@ type SessionDataTuple = (
     Option<String>, // latest_commit
 );
 
+/// Connections kept open for Rust-side access
+const POOL_SIZE: u32 = 4;
+
+/// How long to wait for a free pooled connection
+const POOL_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long a statement waits on a lock held by another connection,
+/// including the Tauri SQL plugin's, before failing with SQLITE_BUSY
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+type ConnectionPool = r2d2::Pool<SqliteConnectionManager>;
+
 lazy_static! {
-    static ref DB_CONNECTION: Mutex<Option<Connection>> = Mutex::new(None);
     static ref APP_HANDLE: Mutex<Option<tauri::AppHandle>> = Mutex::new(None);
 }
 
-/// Helper function to get database connection with retry logic
-/// Retries up to 3 times with 100ms delay between attempts to handle temporary lock contention
-fn get_db_connection() -> Result<std::sync::MutexGuard<'static, Option<Connection>>, rusqlite::Error>
-{
-    const MAX_RETRIES: u32 = 3;
-    const RETRY_DELAY: std::time::Duration = std::time::Duration::from_millis(100);
-
-    for attempt in 0..MAX_RETRIES {
-        match DB_CONNECTION.lock() {
-            Ok(guard) => return Ok(guard),
-            Err(_) if attempt < MAX_RETRIES - 1 => {
-                std::thread::sleep(RETRY_DELAY);
-                continue;
-            }
-            Err(_) => return Err(rusqlite::Error::InvalidQuery),
-        }
-    }
+static DB_POOL: OnceLock<ConnectionPool> = OnceLock::new();
 
-    unreachable!()
+/// Settings every pooled connection is opened with
+fn configure_connection(conn: &mut Connection) -> Result<()> {
+    conn.busy_timeout(BUSY_TIMEOUT)?;
+    conn.pragma_update_and_check(None, "journal_mode", "WAL", |_| Ok(()))?;
+    // Transactions take the write lock when they begin, so two read-then-write
+    // transactions queue on busy_timeout instead of one failing mid-way
+    conn.set_transaction_behavior(TransactionBehavior::Immediate);
+    Ok(())
+}
+
+/// A connection from the pool
+/// Returns an error if the database isn't initialized or no connection frees
+/// up within `POOL_TIMEOUT`
+fn connection() -> Result<PooledConnection<SqliteConnectionManager>> {
+    pooled_connection(DB_POOL.get().ok_or(rusqlite::Error::InvalidQuery)?)
+}
+
+fn pooled_connection(pool: &ConnectionPool) -> Result<PooledConnection<SqliteConnectionManager>> {
+    pool.get().map_err(|e| {
+        rusqlite::Error::SqliteFailure(
+            rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_BUSY),
+            Some(format!("Failed to get a database connection: {}", e)),
+        )
+    })
 }
 
-/// Helper function to get the active database connection with mutable access
-/// Returns an error if the connection is not initialized
+/// Helper function to get a pooled database connection with mutable access
+/// Returns an error if the database isn't initialized
 pub fn with_connection_mut<F, T>(f: F) -> Result<T, rusqlite::Error>
 where
     F: FnOnce(&mut Connection) -> Result<T, rusqlite::Error>,
 {
-    let mut db_conn = get_db_connection()?;
-    let conn = db_conn
-        .as_mut()
-        .ok_or_else(|| rusqlite::Error::InvalidQuery)?;
-    f(conn)
+    let mut conn = connection()?;
+    f(&mut conn)
 }
 
 /// Set the app handle for event emission
@@ -64,7 +83,7 @@ pub fn set_app_handle(app_handle: tauri::AppHandle) {
     }
 }
 
-/// Initialize the database connection
+/// Initialize the database connection pool
 /// Note: Migrations are handled by tauri-plugin-sql
 pub fn init_database() -> Result<()> {
     let db_path = get_db_path()?;
@@ -75,22 +94,82 @@ pub fn init_database() -> Result<()> {
             .map_err(|_e| rusqlite::Error::InvalidPath(parent.to_path_buf()))?;
     }
 
-    // Open connection to existing database (migrations handled by plugin)
-    let conn = Connection::open(&db_path)?;
+    if DB_POOL.get().is_some() {
+        return Ok(());
+    }
 
-    // Store connection
-    let mut db_conn = DB_CONNECTION.lock().unwrap();
-    *db_conn = Some(conn);
+    // Open connections to existing database (migrations handled by plugin)
+    let pool = build_pool(
+        r2d2::Pool::builder().max_size(POOL_SIZE),
+        SqliteConnectionManager::file(&db_path),
+    )?;
+    let _ = DB_POOL.set(pool);
 
     log_info(
         "database",
-        &format!("✓ Database connection established at {:?}", db_path),
+        &format!("✓ Database connection pool established at {:?}", db_path),
     )
     .unwrap_or_default();
 
     Ok(())
 }
 
+/// Initialize an empty in-memory database with every migration applied
+///
+/// For tests that run the app's database code without a file on disk. The
+/// database lives in a single connection that's never recycled, so it lasts
+/// as long as the process.
+#[allow(dead_code)] // Used by integration tests
+pub fn init_in_memory_database() -> Result<()> {
+    if DB_POOL.get().is_some() {
+        return Ok(());
+    }
+
+    let pool = build_pool(
+        r2d2::Pool::builder()
+            .max_size(1)
+            .idle_timeout(None)
+            .max_lifetime(None),
+        SqliteConnectionManager::memory(),
+    )?;
+    let conn = pooled_connection(&pool)?;
+    for migration in crate::migrations::migrations() {
+        conn.execute_batch(migration.sql)?;
+    }
+    drop(conn);
+    let _ = DB_POOL.set(pool);
+
+    Ok(())
+}
+
+/// In-memory connection with every migration applied, for unit tests
+///
+/// Tests that need tables get the real schema from here rather than picking
+/// migration files by hand.
+#[cfg(test)]
+pub fn test_connection() -> Connection {
+    let conn = Connection::open_in_memory().unwrap();
+    for migration in crate::migrations::migrations() {
+        conn.execute_batch(migration.sql).unwrap();
+    }
+    conn
+}
+
+fn build_pool(
+    builder: r2d2::Builder<SqliteConnectionManager>,
+    manager: SqliteConnectionManager,
+) -> Result<ConnectionPool> {
+    builder
+        .connection_timeout(POOL_TIMEOUT)
+        .build(manager.with_init(configure_connection))
+        .map_err(|e| {
+            rusqlite::Error::SqliteFailure(
+                rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_CANTOPEN),
+                Some(format!("Failed to open database pool: {}", e)),
+            )
+        })
+}
+
 /// Get the database file path (same location as tauri-plugin-sql uses)
 fn get_db_path() -> Result<std::path::PathBuf> {
     // Use Tauri's app data directory (same as plugin)
@@ -104,6 +183,26 @@ fn get_db_path() -> Result<std::path::PathBuf> {
     Ok(app_dir.join("guidemode.db"))
 }
 
+/// A session's row, as written by `insert_session`, `update_session` and
+/// `save_sessions`
+#[derive(Debug, Clone, Default)]
+pub struct SessionRecord<'a> {
+    pub provider: &'a str,
+    pub project_name: &'a str,
+    pub session_id: &'a str,
+    pub file_name: &'a str,
+    pub file_path: &'a str,
+    pub file_size: u64,
+    pub file_hash: Option<&'a str>,
+    pub session_start_time: Option<DateTime<Utc>>,
+    pub session_end_time: Option<DateTime<Utc>>,
+    pub duration_ms: Option<i64>,
+    pub cwd: Option<&'a str>,
+    pub git_branch: Option<&'a str>,
+    pub first_commit_hash: Option<&'a str>,
+    pub latest_commit_hash: Option<&'a str>,
+}
+
 /// Insert a session into the database
 #[allow(clippy::too_many_arguments)]
 pub fn insert_session(
@@ -122,46 +221,27 @@ pub fn insert_session(
     first_commit_hash: Option<&str>,
     latest_commit_hash: Option<&str>,
 ) -> Result<String> {
-    let db_conn = DB_CONNECTION.lock().unwrap();
-    let conn = db_conn
-        .as_ref()
-        .ok_or_else(|| rusqlite::Error::InvalidQuery)?;
-
-    let id = Uuid::new_v4().to_string();
-    let now = Utc::now().timestamp_millis();
+    let record = SessionRecord {
+        provider,
+        project_name,
+        session_id,
+        file_name,
+        file_path,
+        file_size,
+        file_hash,
+        session_start_time,
+        session_end_time,
+        duration_ms,
+        cwd,
+        git_branch,
+        first_commit_hash,
+        latest_commit_hash,
+    };
+    let id = with_connection_mut(|conn| insert_session_row(conn, &record))?;
 
     // Check if session is complete (has end time)
     let session_completed = session_end_time.is_some();
 
-    conn.execute(
-        "INSERT INTO agent_sessions (
-            id, provider, project_name, session_id, file_name, file_path, file_size, file_hash,
-            session_start_time, session_end_time, duration_ms, cwd,
-            git_branch, first_commit_hash, latest_commit_hash,
-            processing_status, synced_to_server,
-            created_at, uploaded_at
-        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, 'pending', 0, ?, ?)",
-        params![
-            id,
-            provider,
-            project_name,
-            session_id,
-            file_name,
-            file_path,
-            file_size as i64,
-            file_hash,
-            session_start_time.map(|t| t.timestamp_millis()),
-            session_end_time.map(|t| t.timestamp_millis()),
-            duration_ms,
-            cwd,
-            git_branch,
-            first_commit_hash,
-            latest_commit_hash,
-            now,
-            now,
-        ],
-    )?;
-
     log_info(
         "database",
         &format!("✓ Inserted session {} into local database", session_id),
@@ -171,11 +251,11 @@ pub fn insert_session(
     // Emit event to frontend
     if let Ok(app_handle_guard) = APP_HANDLE.lock() {
         if let Some(ref app_handle) = *app_handle_guard {
-            let _ = app_handle.emit("session-updated", session_id);
+            emit_session_event(app_handle, "session-updated", session_id, session_id);
 
             // Emit session-completed event if session already has end time
             if session_completed {
-                let _ = app_handle.emit("session-completed", session_id);
+                emit_session_event(app_handle, "session-completed", session_id, session_id);
                 log_info(
                     "database",
                     &format!(
@@ -191,6 +271,45 @@ pub fn insert_session(
     Ok(id)
 }
 
+fn insert_session_row(conn: &Connection, record: &SessionRecord) -> Result<String> {
+    let id = Uuid::new_v4().to_string();
+    let now = Utc::now().timestamp_millis();
+    let project_name = crate::project_aliases::resolve(conn, record.project_name);
+    let tenant_id = crate::tenant::active_tenant_id();
+
+    conn.execute(
+        "INSERT INTO agent_sessions (
+            id, provider, project_name, session_id, file_name, file_path, file_size, file_hash,
+            session_start_time, session_end_time, duration_ms, cwd,
+            git_branch, first_commit_hash, latest_commit_hash,
+            processing_status, synced_to_server,
+            created_at, uploaded_at, tenant_id
+        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, 'pending', 0, ?, ?, ?)",
+        params![
+            id,
+            record.provider,
+            project_name,
+            record.session_id,
+            record.file_name,
+            record.file_path,
+            record.file_size as i64,
+            record.file_hash,
+            record.session_start_time.map(|t| t.timestamp_millis()),
+            record.session_end_time.map(|t| t.timestamp_millis()),
+            record.duration_ms,
+            record.cwd,
+            record.git_branch,
+            record.first_commit_hash,
+            record.latest_commit_hash,
+            now,
+            now,
+            tenant_id,
+        ],
+    )?;
+
+    Ok(id)
+}
+
 /// Update an existing session with new activity (file size, timestamp)
 /// Uses a transaction to prevent race conditions during read-modify-write
 #[allow(clippy::too_many_arguments)]
@@ -206,101 +325,29 @@ pub fn update_session(
     git_branch: Option<&str>,
     latest_commit_hash: Option<&str>,
 ) -> Result<()> {
+    // Hash the file so a synced session only goes back to pending when it changed
+    let file_hash = file_hash
+        .map(str::to_string)
+        .or_else(|| synced_file_hash(session_id, file_path, file_size));
+
+    let record = SessionRecord {
+        session_id,
+        file_name,
+        file_path,
+        file_size,
+        file_hash: file_hash.as_deref(),
+        session_start_time,
+        session_end_time,
+        cwd,
+        git_branch,
+        latest_commit_hash,
+        ..Default::default()
+    };
+
     with_connection_mut(|conn| {
         // Use a transaction to make read-modify-write atomic
         let tx = conn.transaction()?;
-
-        let now = Utc::now().timestamp_millis();
-
-        // Get the existing start time, end time, cwd, and git fields from database
-        // Query by session_id only since providers like OpenCode have multiple files per session
-        let (existing_start_time_ms, existing_end_time_ms, existing_cwd, existing_git_branch, existing_first_commit, existing_latest_commit): SessionDataTuple = tx.query_row(
-            "SELECT session_start_time, session_end_time, cwd, git_branch, first_commit_hash, latest_commit_hash FROM agent_sessions WHERE session_id = ?",
-            params![session_id],
-            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?, row.get(5)?)),
-        ).ok().unwrap_or((None, None, None, None, None, None));
-
-        // Use new start time if provided and existing is null, otherwise keep existing
-        let final_start_time_ms = match (existing_start_time_ms, session_start_time) {
-            (None, Some(new_start)) => Some(new_start.timestamp_millis()), // Database has null, use new value
-            (Some(existing), _) => Some(existing), // Keep existing non-null value
-            (None, None) => None,                  // Both null, stay null
-        };
-
-        // Use new cwd if provided and existing is null, otherwise keep existing
-        let final_cwd = match (existing_cwd, cwd) {
-            (None, Some(new_cwd)) => Some(new_cwd.to_string()), // Database has null, use new value
-            (Some(existing), _) => Some(existing),              // Keep existing non-null value
-            (None, None) => None,                               // Both null, stay null
-        };
-
-        // Always update git_branch if provided (allows tracking branch switches during session)
-        // This ensures sessions are associated with the branch where work is actually done
-        let final_git_branch = match git_branch {
-            Some(new_branch) => Some(new_branch.to_string()),
-            None => existing_git_branch,
-        };
-
-        // Use new commit as first_commit_hash if existing is null and we have a commit, otherwise keep existing
-        let final_first_commit = match (existing_first_commit, latest_commit_hash) {
-            (None, Some(new_commit)) => Some(new_commit.to_string()), // Database has null, use new value
-            (Some(existing), _) => Some(existing), // Keep existing non-null value
-            (None, None) => None,                  // Both null, stay null
-        };
-
-        // Always update latest_commit_hash if provided (this is expected to change)
-        let final_latest_commit = match latest_commit_hash {
-            Some(new_commit) => Some(new_commit.to_string()),
-            None => existing_latest_commit,
-        };
-
-        // Calculate duration if we have both start and end times
-        let duration_ms = if let (Some(start), Some(end)) = (final_start_time_ms, session_end_time)
-        {
-            Some((end.timestamp_millis() - start).max(0))
-        } else {
-            None
-        };
-
-        // Detect if session is being completed (first time getting end time)
-        let session_completed = existing_end_time_ms.is_none() && session_end_time.is_some();
-
-        // Update by session_id only since providers like OpenCode have multiple files per session
-        // Reset core_metrics_status and processing_status to 'pending' since file content has changed
-        tx.execute(
-            "UPDATE agent_sessions
-             SET file_name = ?,
-                 file_path = ?,
-                 file_size = ?,
-                 file_hash = ?,
-                 session_start_time = ?,
-                 session_end_time = ?,
-                 duration_ms = ?,
-                 cwd = ?,
-                 git_branch = ?,
-                 first_commit_hash = ?,
-                 latest_commit_hash = ?,
-                 uploaded_at = ?,
-                 synced_to_server = 0,
-                 core_metrics_status = 'pending',
-                 processing_status = 'pending'
-             WHERE session_id = ?",
-            params![
-                file_name,
-                file_path,
-                file_size as i64,
-                file_hash,
-                final_start_time_ms,
-                session_end_time.map(|t| t.timestamp_millis()),
-                duration_ms,
-                final_cwd,
-                final_git_branch,
-                final_first_commit,
-                final_latest_commit,
-                now,
-                session_id,
-            ],
-        )?;
+        let change = update_session_row(&tx, &record)?;
 
         // Commit transaction before emitting events (events are outside transaction)
         tx.commit()?;
@@ -317,11 +364,11 @@ pub fn update_session(
         // Emit event to frontend (after transaction committed)
         if let Ok(app_handle_guard) = APP_HANDLE.lock() {
             if let Some(ref app_handle) = *app_handle_guard {
-                let _ = app_handle.emit("session-updated", session_id);
+                emit_session_event(app_handle, "session-updated", session_id, session_id);
 
                 // Emit session-completed event if this is the first time the session got an end time
-                if session_completed {
-                    let _ = app_handle.emit("session-completed", session_id);
+                if change.completed {
+                    emit_session_event(app_handle, "session-completed", session_id, session_id);
                     log_info(
                         "database",
                         &format!(
@@ -338,33 +385,244 @@ pub fn update_session(
     })
 }
 
+/// Hash of an updated session file, when it decides whether a synced session changed
+///
+/// Unsynced sessions go back to pending either way, and a new size already
+/// shows a synced one changed, so neither is hashed. A synced file of the same
+/// size not modified since it synced keeps its stored hash.
+fn synced_file_hash(session_id: &str, file_path: &str, file_size: u64) -> Option<String> {
+    if file_path.is_empty() {
+        return None;
+    }
+    let (synced, stored_hash, stored_size, synced_at): (bool, Option<String>, i64, Option<i64>) =
+        with_connection_mut(|conn| {
+            conn.query_row(
+                "SELECT COALESCE(synced_to_server, 0) = 1, file_hash, file_size, synced_at
+                 FROM agent_sessions WHERE session_id = ?",
+                params![session_id],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+            )
+        })
+        .ok()?;
+    if !synced || stored_size != file_size as i64 {
+        return None;
+    }
+
+    let path = std::path::Path::new(file_path);
+    let modified_ms = std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| modified.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|since_epoch| since_epoch.as_millis() as i64);
+    match (stored_hash, modified_ms, synced_at) {
+        (Some(hash), Some(modified_ms), Some(synced_at)) if modified_ms <= synced_at => Some(hash),
+        _ => crate::upload_queue::hashing::calculate_file_hash_sha256(&path.to_path_buf()).ok(),
+    }
+}
+
+/// What updating a session's row changed
+#[derive(Debug, Clone, Copy)]
+struct SessionChange {
+    /// The session got its end time
+    completed: bool,
+}
+
+fn update_session_row(conn: &Connection, record: &SessionRecord) -> Result<SessionChange> {
+    let SessionRecord {
+        session_id,
+        file_name,
+        file_path,
+        file_size,
+        file_hash,
+        session_start_time,
+        session_end_time,
+        cwd,
+        git_branch,
+        latest_commit_hash,
+        ..
+    } = *record;
+
+    let now = Utc::now().timestamp_millis();
+
+    // Get the existing start time, end time, cwd, and git fields from database
+    // Query by session_id only since providers like OpenCode have multiple files per session
+    let (existing_start_time_ms, existing_end_time_ms, existing_cwd, existing_git_branch, existing_first_commit, existing_latest_commit): SessionDataTuple = conn.query_row(
+        "SELECT session_start_time, session_end_time, cwd, git_branch, first_commit_hash, latest_commit_hash FROM agent_sessions WHERE session_id = ?",
+        params![session_id],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?, row.get(5)?)),
+    ).ok().unwrap_or((None, None, None, None, None, None));
+
+    // Use new start time if provided and existing is null, otherwise keep existing
+    let final_start_time_ms = match (existing_start_time_ms, session_start_time) {
+        (None, Some(new_start)) => Some(new_start.timestamp_millis()), // Database has null, use new value
+        (Some(existing), _) => Some(existing), // Keep existing non-null value
+        (None, None) => None,                  // Both null, stay null
+    };
+
+    // Use new cwd if provided and existing is null, otherwise keep existing
+    let final_cwd = match (existing_cwd, cwd) {
+        (None, Some(new_cwd)) => Some(new_cwd.to_string()), // Database has null, use new value
+        (Some(existing), _) => Some(existing),              // Keep existing non-null value
+        (None, None) => None,                               // Both null, stay null
+    };
+
+    // Always update git_branch if provided (allows tracking branch switches during session)
+    // This ensures sessions are associated with the branch where work is actually done
+    let final_git_branch = match git_branch {
+        Some(new_branch) => Some(new_branch.to_string()),
+        None => existing_git_branch,
+    };
+
+    // Use new commit as first_commit_hash if existing is null and we have a commit, otherwise keep existing
+    let final_first_commit = match (existing_first_commit, latest_commit_hash) {
+        (None, Some(new_commit)) => Some(new_commit.to_string()), // Database has null, use new value
+        (Some(existing), _) => Some(existing), // Keep existing non-null value
+        (None, None) => None,                  // Both null, stay null
+    };
+
+    // Always update latest_commit_hash if provided (this is expected to change)
+    let final_latest_commit = match latest_commit_hash {
+        Some(new_commit) => Some(new_commit.to_string()),
+        None => existing_latest_commit,
+    };
+
+    // Calculate duration if we have both start and end times
+    let duration_ms = if let (Some(start), Some(end)) = (final_start_time_ms, session_end_time)
+    {
+        Some((end.timestamp_millis() - start).max(0))
+    } else {
+        None
+    };
+
+    // Detect if session is being completed (first time getting end time)
+    let session_completed = existing_end_time_ms.is_none() && session_end_time.is_some();
+
+    // A synced session whose file is unchanged stays synced; one that changed
+    // (the conversation was resumed) supersedes its upload. Without a stored
+    // hash, an unchanged size counts as unchanged since resumed sessions grow
+    let (synced, existing_hash, existing_size): (bool, Option<String>, i64) = conn
+        .query_row(
+            "SELECT COALESCE(synced_to_server, 0) = 1, file_hash, file_size FROM agent_sessions WHERE session_id = ?",
+            params![session_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .unwrap_or((false, None, 0));
+    let unchanged = synced
+        && match (&existing_hash, &file_hash) {
+            (Some(existing), Some(new)) => existing == new,
+            _ => file_size > 0 && existing_size == file_size as i64,
+        };
+    let superseded = synced && !unchanged;
+    // A session the quality gate skipped is gated again once its file changes
+    let regate = match (&existing_hash, &file_hash) {
+        (Some(existing), Some(new)) => existing != new,
+        _ => existing_size != file_size as i64,
+    };
+
+    // Update by session_id only since providers like OpenCode have multiple files per session
+    // Reset core_metrics_status and processing_status to 'pending' unless the file is unchanged
+    conn.execute(
+        "UPDATE agent_sessions
+         SET file_name = ?,
+             file_path = ?,
+             file_size = ?,
+             file_hash = ?,
+             session_start_time = ?,
+             session_end_time = ?,
+             duration_ms = ?,
+             cwd = ?,
+             git_branch = ?,
+             first_commit_hash = ?,
+             latest_commit_hash = ?,
+             uploaded_at = ?,
+             synced_to_server = CASE WHEN ? THEN synced_to_server ELSE 0 END,
+             core_metrics_status = CASE WHEN ? THEN core_metrics_status ELSE 'pending' END,
+             processing_status = CASE WHEN ? THEN processing_status ELSE 'pending' END,
+             upload_superseded_at = CASE WHEN ? THEN ? ELSE upload_superseded_at END,
+             sync_skipped = CASE WHEN ? AND sync_skip_reason = ? THEN 0 ELSE sync_skipped END,
+             sync_skip_reason = CASE WHEN ? AND sync_skip_reason = ? THEN NULL ELSE sync_skip_reason END
+         WHERE session_id = ?",
+        params![
+            file_name,
+            file_path,
+            file_size as i64,
+            file_hash,
+            final_start_time_ms,
+            session_end_time.map(|t| t.timestamp_millis()),
+            duration_ms,
+            final_cwd,
+            final_git_branch,
+            final_first_commit,
+            final_latest_commit,
+            now,
+            unchanged,
+            unchanged,
+            unchanged,
+            superseded,
+            now,
+            regate,
+            LOW_SIGNAL,
+            regate,
+            LOW_SIGNAL,
+            session_id,
+        ],
+    )?;
+
+    if superseded {
+        log_info(
+            "database",
+            &format!(
+                "↻ Session {} changed after it synced, queued to upload the new version",
+                session_id
+            ),
+        )
+        .unwrap_or_default();
+    }
+
+    Ok(SessionChange {
+        completed: session_completed,
+    })
+}
+
 /// Get all unsynced sessions (for upload queue)
-/// Only returns sessions that have both start and end times, no sync failure,
+/// Only returns sessions of the active tenant that have both start and end times, no sync failure,
 /// and where the provider's sync mode is set to "Transcript and Metrics" or "Metrics Only"
 /// For "Metrics Only" mode, requires core_metrics_status = 'completed' (uploads twice: first with core metrics, then with AI)
 pub fn get_unsynced_sessions() -> Result<Vec<UnsyncedSession>> {
-    use crate::config::load_provider_config;
+    query_unsynced_sessions(None)
+}
+
+/// The session, if it's ready to upload like those of `get_unsynced_sessions`
+pub fn get_unsynced_session(session_id: &str) -> Result<Option<UnsyncedSession>> {
+    Ok(query_unsynced_sessions(Some(session_id))?.into_iter().next())
+}
+
+fn query_unsynced_sessions(session_id: Option<&str>) -> Result<Vec<UnsyncedSession>> {
+    use crate::workspace_roots::effective_sync_mode;
 
-    let db_conn = DB_CONNECTION.lock().unwrap();
-    let conn = db_conn
-        .as_ref()
-        .ok_or_else(|| rusqlite::Error::InvalidQuery)?;
+    let tenant_id = crate::tenant::active_tenant_id();
+    let conn = connection()?;
 
     let mut stmt = conn.prepare(
         "SELECT id, provider, project_name, session_id, file_name, file_path, file_size, cwd,
                 session_start_time, session_end_time,
                 COALESCE(core_metrics_status, 'pending') as core_metrics_status,
-                COALESCE(processing_status, 'pending') as processing_status
+                COALESCE(processing_status, 'pending') as processing_status,
+                COALESCE(upload_queued_at, created_at), upload_attempts, upload_last_attempt_at,
+                upload_priority, upload_superseded_at
          FROM agent_sessions
          WHERE synced_to_server = 0
            AND session_start_time IS NOT NULL
            AND session_end_time IS NOT NULL
            AND sync_failed_reason IS NULL
-         ORDER BY created_at ASC",
+           AND sync_skipped = 0
+           AND tenant_id IS ?1
+           AND (?2 IS NULL OR session_id = ?2)
+         ORDER BY upload_priority DESC, created_at ASC",
     )?;
 
     let all_sessions = stmt
-        .query_map([], |row| {
+        .query_map(params![tenant_id, session_id], |row| {
             Ok((
                 UnsyncedSession {
                     id: row.get(0)?,
@@ -377,6 +635,13 @@ pub fn get_unsynced_sessions() -> Result<Vec<UnsyncedSession>> {
                     cwd: row.get(7)?,
                     session_start_time: row.get(8)?,
                     session_end_time: row.get(9)?,
+                    upload: UploadQueueState {
+                        queued_at: row.get(12)?,
+                        attempts: row.get(13)?,
+                        last_attempt_at: row.get(14)?,
+                        priority: row.get(15)?,
+                        superseded_at: row.get(16)?,
+                    },
                 },
                 row.get::<_, String>(10)?, // core_metrics_status
                 row.get::<_, String>(11)?, // processing_status
@@ -386,15 +651,19 @@ pub fn get_unsynced_sessions() -> Result<Vec<UnsyncedSession>> {
 
     // Filter to include sessions with sync mode "Transcript and Metrics" or "Metrics Only"
     // For "Metrics Only", require core_metrics_status = 'completed' (will upload twice: first with core, then with AI)
+    // Sessions saved before their project was ignored never upload
+    let ignore_list = crate::ignore_list::IgnoreList::current();
     let sessions = all_sessions
         .into_iter()
+        .filter(|(session, _, _)| !ignore_list.is_ignored(&session.project_name, session.cwd.as_deref()))
         .filter_map(|(session, core_metrics_status, _processing_status)| {
-            match load_provider_config(&session.provider) {
-                Ok(config) => {
-                    if config.sync_mode == "Transcript and Metrics" {
+            // A workspace root's sync mode overrides the provider's
+            match effective_sync_mode(&session.provider, session.cwd.as_deref()) {
+                Some(sync_mode) => {
+                    if sync_mode == "Transcript and Metrics" {
                         // Transcript mode: upload anytime after session ends
                         Some(session)
-                    } else if config.sync_mode == "Metrics Only" {
+                    } else if sync_mode == "Metrics Only" {
                         // Metrics Only: wait for core metrics to complete (uploads immediately after core metrics)
                         // Will upload again later when AI processing completes (server upserts)
                         if core_metrics_status == "completed" {
@@ -407,7 +676,7 @@ pub fn get_unsynced_sessions() -> Result<Vec<UnsyncedSession>> {
                         None
                     }
                 }
-                Err(_) => {
+                None => {
                     // If we can't load config, default to not syncing (safe default)
                     None
                 }
@@ -420,16 +689,16 @@ pub fn get_unsynced_sessions() -> Result<Vec<UnsyncedSession>> {
 
 /// Mark a session as synced
 pub fn mark_session_synced(session_id: &str, server_session_id: Option<&str>) -> Result<()> {
-    let db_conn = DB_CONNECTION.lock().unwrap();
-    let conn = db_conn
-        .as_ref()
-        .ok_or_else(|| rusqlite::Error::InvalidQuery)?;
+    let conn = connection()?;
 
     let now = Utc::now().timestamp_millis();
 
     conn.execute(
         "UPDATE agent_sessions
-         SET synced_to_server = 1, synced_at = ?, server_session_id = ?, sync_failed_reason = NULL
+         SET synced_to_server = 1, synced_at = ?, server_session_id = ?, sync_failed_reason = NULL,
+             upload_queued_at = NULL, upload_attempts = 0, upload_last_attempt_at = NULL,
+             upload_priority = 0, metadata_dirty_at = NULL, upload_superseded_at = NULL,
+             transcript_deferred_at = NULL
          WHERE session_id = ?",
         params![now, server_session_id, session_id],
     )?;
@@ -443,12 +712,52 @@ pub fn mark_session_synced(session_id: &str, server_session_id: Option<&str>) ->
     Ok(())
 }
 
+/// Mark a session synced whose metrics went up without its transcript, so
+/// `requeue_deferred_transcripts` can send the transcript later
+pub fn mark_session_transcript_deferred(session_id: &str) -> Result<()> {
+    mark_session_synced(session_id, None)?;
+    connection()?.execute(
+        "UPDATE agent_sessions SET transcript_deferred_at = ? WHERE session_id = ?",
+        params![Utc::now().timestamp_millis(), session_id],
+    )?;
+    Ok(())
+}
+
+/// Put sessions whose transcript was deferred back in the upload queue, now
+/// that the server takes transcripts; how many there were
+pub fn requeue_deferred_transcripts() -> Result<usize> {
+    connection()?.execute(
+        "UPDATE agent_sessions SET synced_to_server = 0, transcript_deferred_at = NULL
+         WHERE transcript_deferred_at IS NOT NULL",
+        [],
+    )
+}
+
+fn skip_session_sync(conn: &Connection, session_id: &str, reason: &str) -> Result<usize> {
+    conn.execute(
+        "UPDATE agent_sessions SET sync_skipped = 1, sync_skip_reason = ?
+         WHERE session_id = ? AND synced_to_server = 0",
+        params![reason, session_id],
+    )
+}
+
+/// Leave a session out of syncing, recording why
+pub fn mark_session_sync_skipped(session_id: &str, reason: &str) -> Result<()> {
+    let conn = connection()?;
+    skip_session_sync(&conn, session_id, reason)?;
+
+    log_info(
+        "database",
+        &format!("⊘ Skipped syncing session {}: {}", session_id, reason),
+    )
+    .unwrap_or_default();
+
+    Ok(())
+}
+
 /// Mark a session as sync failed with reason
 pub fn mark_session_sync_failed(session_id: &str, reason: &str) -> Result<()> {
-    let db_conn = DB_CONNECTION.lock().unwrap();
-    let conn = db_conn
-        .as_ref()
-        .ok_or_else(|| rusqlite::Error::InvalidQuery)?;
+    let conn = connection()?;
 
     conn.execute(
         "UPDATE agent_sessions
@@ -477,24 +786,37 @@ pub struct FailedSession {
     pub file_size: i64,
     pub cwd: Option<String>,
     pub sync_failed_reason: String,
+    pub upload: UploadQueueState,
+}
+
+/// A session's place in the upload queue
+#[derive(Debug, Clone)]
+pub struct UploadQueueState {
+    /// When the session was first queued, or created if it hasn't been yet
+    pub queued_at: i64,
+    pub attempts: u32,
+    pub last_attempt_at: Option<i64>,
+    pub priority: i64,
+    /// When the session changed after it synced, if this upload replaces that one
+    pub superseded_at: Option<i64>,
 }
 
 /// Get all failed sessions (for upload queue display)
 pub fn get_failed_sessions() -> Result<Vec<FailedSession>> {
-    let db_conn = DB_CONNECTION.lock().unwrap();
-    let conn = db_conn
-        .as_ref()
-        .ok_or_else(|| rusqlite::Error::InvalidQuery)?;
+    let tenant_id = crate::tenant::active_tenant_id();
+    let conn = connection()?;
 
     let mut stmt = conn.prepare(
-        "SELECT id, provider, project_name, session_id, file_name, file_path, file_size, cwd, sync_failed_reason
+        "SELECT id, provider, project_name, session_id, file_name, file_path, file_size, cwd, sync_failed_reason,
+                COALESCE(upload_queued_at, created_at), upload_attempts, upload_last_attempt_at,
+                upload_priority, upload_superseded_at
          FROM agent_sessions
-         WHERE sync_failed_reason IS NOT NULL
+         WHERE sync_failed_reason IS NOT NULL AND tenant_id IS ?
          ORDER BY created_at DESC"
     )?;
 
     let sessions = stmt
-        .query_map([], |row| {
+        .query_map(params![tenant_id], |row| {
             Ok(FailedSession {
                 id: row.get(0)?,
                 provider: row.get(1)?,
@@ -505,6 +827,13 @@ pub fn get_failed_sessions() -> Result<Vec<FailedSession>> {
                 file_size: row.get(6)?,
                 cwd: row.get(7)?,
                 sync_failed_reason: row.get(8)?,
+                upload: UploadQueueState {
+                    queued_at: row.get(9)?,
+                    attempts: row.get(10)?,
+                    last_attempt_at: row.get(11)?,
+                    priority: row.get(12)?,
+                    superseded_at: row.get(13)?,
+                },
             })
         })?
         .collect::<Result<Vec<_>>>()?;
@@ -519,10 +848,7 @@ pub fn get_upload_stats() -> Result<UploadStats> {
     let unsynced = get_unsynced_sessions()?;
     let pending = unsynced.len();
 
-    let db_conn = DB_CONNECTION.lock().unwrap();
-    let conn = db_conn
-        .as_ref()
-        .ok_or_else(|| rusqlite::Error::InvalidQuery)?;
+    let conn = connection()?;
 
     // Count synced sessions
     let synced: i64 = conn.query_row(
@@ -564,6 +890,7 @@ pub struct UnsyncedSession {
     pub session_start_time: Option<i64>,
     #[allow(dead_code)]
     pub session_end_time: Option<i64>,
+    pub upload: UploadQueueState,
 }
 
 /// Insert or get a project by CWD (upsert)
@@ -577,74 +904,98 @@ pub fn insert_or_get_project(
     with_connection_mut(|conn| {
         // Use a transaction for atomic upsert
         let tx = conn.transaction()?;
+        let (project_id, created) = upsert_project(&tx, name, github_repo, cwd, project_type)?;
 
-        let now = Utc::now().timestamp_millis();
+        // Commit transaction
+        tx.commit()?;
 
-        // Try to get existing project by CWD
-        let existing: Option<String> = tx
-            .query_row(
-                "SELECT id FROM projects WHERE cwd = ?",
-                params![cwd],
-                |row| row.get(0),
-            )
-            .ok();
+        // Emit event to frontend
+        if created {
+            if let Ok(app_handle_guard) = APP_HANDLE.lock() {
+                if let Some(ref app_handle) = *app_handle_guard {
+                    let _ = app_handle.emit("project-updated", &project_id);
+                }
+            }
+        }
 
-        let project_id = if let Some(project_id) = existing {
-            // Update existing project
-            tx.execute(
-                "UPDATE projects SET name = ?, github_repo = ?, type = ?, updated_at = ? WHERE id = ?",
-                params![name, github_repo, project_type, now, project_id],
-            )?;
+        Ok(project_id)
+    })
+}
 
-            log_debug(
-                "database",
-                &format!("↻ Updated project {} ({})", name, project_id),
-            )
-            .unwrap_or_default();
+/// The project's ID, and whether it was created
+fn upsert_project(
+    conn: &Connection,
+    name: &str,
+    github_repo: Option<&str>,
+    cwd: &str,
+    project_type: &str,
+) -> Result<(String, bool)> {
+    let now = Utc::now().timestamp_millis();
+    let name = crate::project_aliases::resolve(conn, name);
 
-            project_id
-        } else {
-            // Insert new project
-            let id = Uuid::new_v4().to_string();
-            tx.execute(
-                "INSERT INTO projects (id, name, github_repo, cwd, type, created_at, updated_at)
-                 VALUES (?, ?, ?, ?, ?, ?, ?)",
-                params![id, name, github_repo, cwd, project_type, now, now],
-            )?;
+    // Try to get existing project by CWD, or the project it was merged into
+    let existing: Option<String> = conn
+        .query_row(
+            "SELECT id FROM projects WHERE cwd = ?1
+             UNION ALL
+             SELECT project_id FROM project_merges WHERE cwd = ?1",
+            params![cwd],
+            |row| row.get(0),
+        )
+        .ok();
 
-            log_info(
-                "database",
-                &format!("✓ Inserted project {} ({})", name, &id),
-            )
-            .unwrap_or_default();
+    let project = if let Some(project_id) = existing {
+        // Update existing project
+        conn.execute(
+            "UPDATE projects SET name = ?, github_repo = ?, type = ?, updated_at = ? WHERE id = ?",
+            params![name, github_repo, project_type, now, project_id],
+        )?;
 
-            // Emit event to frontend
-            if let Ok(app_handle_guard) = APP_HANDLE.lock() {
-                if let Some(ref app_handle) = *app_handle_guard {
-                    let _ = app_handle.emit("project-updated", &id);
-                }
-            }
+        log_debug(
+            "database",
+            &format!("↻ Updated project {} ({})", name, project_id),
+        )
+        .unwrap_or_default();
 
-            id
-        };
+        (project_id, false)
+    } else {
+        // Insert new project
+        let id = Uuid::new_v4().to_string();
+        conn.execute(
+            "INSERT INTO projects (id, name, github_repo, cwd, type, created_at, updated_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?)",
+            params![id, name, github_repo, cwd, project_type, now, now],
+        )?;
 
-        // Commit transaction
-        tx.commit()?;
+        log_info(
+            "database",
+            &format!("✓ Inserted project {} ({})", name, &id),
+        )
+        .unwrap_or_default();
 
-        Ok(project_id)
-    })
+        (id, true)
+    };
+
+    Ok(project)
+}
+
+/// Working directories of known projects, including those merged into another
+pub fn get_project_roots() -> Result<Vec<String>> {
+    let conn = connection()?;
+    let mut stmt = conn.prepare("SELECT cwd FROM projects UNION SELECT cwd FROM project_merges")?;
+    let roots = stmt
+        .query_map([], |row| row.get(0))?
+        .collect::<Result<Vec<String>>>()?;
+    Ok(roots)
 }
 
 /// Get all projects with session counts
 pub fn get_all_projects() -> Result<Vec<ProjectWithCount>> {
-    let db_conn = DB_CONNECTION.lock().unwrap();
-    let conn = db_conn
-        .as_ref()
-        .ok_or_else(|| rusqlite::Error::InvalidQuery)?;
+    let conn = connection()?;
 
     let mut stmt = conn.prepare(
         "SELECT p.id, p.name, p.github_repo, p.cwd, p.type, p.created_at, p.updated_at,
-                COUNT(s.id) as session_count
+                COUNT(s.id) as session_count, p.sync_excluded
          FROM projects p
          LEFT JOIN agent_sessions s ON p.id = s.project_id
          GROUP BY p.id
@@ -662,6 +1013,7 @@ pub fn get_all_projects() -> Result<Vec<ProjectWithCount>> {
                 created_at: row.get(5)?,
                 updated_at: row.get(6)?,
                 session_count: row.get(7)?,
+                sync_excluded: row.get(8)?,
             })
         })?
         .collect::<Result<Vec<_>>>()?;
@@ -671,15 +1023,12 @@ pub fn get_all_projects() -> Result<Vec<ProjectWithCount>> {
 
 /// Get a single project by ID
 pub fn get_project_by_id(project_id: &str) -> Result<Option<ProjectWithCount>> {
-    let db_conn = DB_CONNECTION.lock().unwrap();
-    let conn = db_conn
-        .as_ref()
-        .ok_or_else(|| rusqlite::Error::InvalidQuery)?;
+    let conn = connection()?;
 
     let project: Option<ProjectWithCount> = conn
         .query_row(
             "SELECT p.id, p.name, p.github_repo, p.cwd, p.type, p.created_at, p.updated_at,
-                COUNT(s.id) as session_count
+                COUNT(s.id) as session_count, p.sync_excluded
          FROM projects p
          LEFT JOIN agent_sessions s ON p.id = s.project_id
          WHERE p.id = ?
@@ -695,6 +1044,7 @@ pub fn get_project_by_id(project_id: &str) -> Result<Option<ProjectWithCount>> {
                     created_at: row.get(5)?,
                     updated_at: row.get(6)?,
                     session_count: row.get(7)?,
+                    sync_excluded: row.get(8)?,
                 })
             },
         )
@@ -705,15 +1055,13 @@ pub fn get_project_by_id(project_id: &str) -> Result<Option<ProjectWithCount>> {
 
 /// Attach a session to a project
 pub fn attach_session_to_project(session_id: &str, project_id: &str) -> Result<()> {
-    let db_conn = DB_CONNECTION.lock().unwrap();
-    let conn = db_conn
-        .as_ref()
-        .ok_or_else(|| rusqlite::Error::InvalidQuery)?;
+    let conn = connection()?;
 
     conn.execute(
         "UPDATE agent_sessions SET project_id = ? WHERE session_id = ?",
         params![project_id, session_id],
     )?;
+    crate::project_sync::skip_if_excluded(&conn, session_id)?;
 
     log_debug(
         "database",
@@ -731,6 +1079,7 @@ pub fn attach_session_to_project(session_id: &str, project_id: &str) -> Result<(
 /// Used when linking a session to a project to sync the project_name field
 pub fn update_session_project_name(session_id: &str, project_name: &str) -> Result<()> {
     with_connection_mut(|conn| {
+        let project_name = crate::project_aliases::resolve(conn, project_name);
         conn.execute(
             "UPDATE agent_sessions SET project_name = ? WHERE session_id = ?",
             params![project_name, session_id],
@@ -749,6 +1098,131 @@ pub fn update_session_project_name(session_id: &str, project_name: &str) -> Resu
     })
 }
 
+/// The project a session saved by `save_sessions` belongs to
+#[derive(Debug, Clone)]
+pub struct ProjectRecord<'a> {
+    pub name: &'a str,
+    pub github_repo: Option<&'a str>,
+    pub cwd: &'a str,
+    pub project_type: &'a str,
+}
+
+/// How saving one session with `save_sessions` went
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SavedSession {
+    /// Inserted rather than updated
+    pub inserted: bool,
+    /// The session has an end time it didn't have before
+    pub completed: bool,
+}
+
+/// Insert or update sessions and link them to their projects in a single
+/// transaction, for bulk saves like historical scans
+///
+/// Unlike `insert_session` and `update_session`, no per-session events are
+/// emitted to the frontend. A session that fails doesn't stop the others.
+pub fn save_sessions(
+    sessions: &[(SessionRecord, Option<ProjectRecord>)],
+) -> Result<Vec<std::result::Result<SavedSession, String>>> {
+    with_connection_mut(|conn| save_session_rows(conn, sessions))
+}
+
+fn save_session_rows(
+    conn: &mut Connection,
+    sessions: &[(SessionRecord, Option<ProjectRecord>)],
+) -> Result<Vec<std::result::Result<SavedSession, String>>> {
+    let tx = conn.transaction()?;
+    let mut results = Vec::with_capacity(sessions.len());
+    for (record, project) in sessions {
+        // A failed statement only rolls back itself, not the transaction
+        let saved = match insert_session_row(&tx, record) {
+            Ok(_) => Ok(SavedSession {
+                inserted: true,
+                completed: record.session_end_time.is_some(),
+            }),
+            Err(e) if e.to_string().contains("UNIQUE constraint") => {
+                update_session_row(&tx, record).map(|change| SavedSession {
+                    inserted: false,
+                    completed: change.completed,
+                })
+            }
+            Err(e) => Err(e),
+        };
+        if let (Ok(_), Some(project)) = (&saved, project) {
+            let linked = upsert_project(
+                &tx,
+                project.name,
+                project.github_repo,
+                project.cwd,
+                project.project_type,
+            )
+            .and_then(|(project_id, _)| {
+                tx.execute(
+                    "UPDATE agent_sessions SET project_id = ?, project_name = ? WHERE session_id = ?",
+                    params![
+                        project_id,
+                        crate::project_aliases::resolve(&tx, project.name),
+                        record.session_id
+                    ],
+                )?;
+                crate::project_sync::skip_if_excluded(&tx, record.session_id)
+            });
+            if let Err(e) = linked {
+                log_debug(
+                    "database",
+                    &format!(
+                        "⚠ Failed to link session {} to project {}: {}",
+                        record.session_id, project.name, e
+                    ),
+                )
+                .unwrap_or_default();
+            }
+        }
+        results.push(saved.map_err(|e| e.to_string()));
+    }
+    tx.commit()?;
+    Ok(results)
+}
+
+/// Get a cached Cursor project hash → CWD mapping
+pub fn get_cursor_project_path(hash: &str) -> Result<Option<String>> {
+    let conn = connection()?;
+
+    let cwd: Option<String> = conn
+        .query_row(
+            "SELECT cwd FROM cursor_project_paths WHERE hash = ?",
+            params![hash],
+            |row| row.get(0),
+        )
+        .ok();
+
+    Ok(cwd)
+}
+
+/// Cache a verified Cursor project hash → CWD mapping
+/// `source` records how the mapping was resolved (e.g. "metadata", "candidate")
+pub fn save_cursor_project_path(hash: &str, cwd: &str, source: &str) -> Result<()> {
+    let conn = connection()?;
+
+    let now = Utc::now().timestamp_millis();
+
+    conn.execute(
+        "INSERT INTO cursor_project_paths (hash, cwd, source, verified_at)
+         VALUES (?, ?, ?, ?)
+         ON CONFLICT(hash) DO UPDATE SET cwd = excluded.cwd, source = excluded.source,
+             verified_at = excluded.verified_at",
+        params![hash, cwd, source, now],
+    )?;
+
+    log_debug(
+        "database",
+        &format!("✓ Cached Cursor project path {} → {}", hash, cwd),
+    )
+    .unwrap_or_default();
+
+    Ok(())
+}
+
 #[derive(Debug, Clone)]
 pub struct ProjectWithCount {
     pub id: String,
@@ -759,70 +1233,115 @@ pub struct ProjectWithCount {
     pub created_at: i64,
     pub updated_at: i64,
     pub session_count: i64,
+    pub sync_excluded: bool,
 }
 
-/// Execute a raw SQL query and return results as JSON
-/// This is used by the React frontend to query the database dynamically
-pub fn execute_sql_query(
-    sql: &str,
-    params: Vec<serde_json::Value>,
-) -> Result<Vec<serde_json::Value>> {
-    let db_conn = DB_CONNECTION.lock().unwrap();
-    let conn = db_conn
-        .as_ref()
-        .ok_or_else(|| rusqlite::Error::InvalidQuery)?;
-
-    let mut stmt = conn.prepare(sql)?;
-
-    // Convert JSON params to rusqlite params
-    let rusqlite_params: Vec<Box<dyn rusqlite::ToSql>> = params
-        .iter()
-        .map(|p| match p {
-            serde_json::Value::String(s) => Box::new(s.clone()) as Box<dyn rusqlite::ToSql>,
-            serde_json::Value::Number(n) => {
-                if let Some(i) = n.as_i64() {
-                    Box::new(i) as Box<dyn rusqlite::ToSql>
-                } else if let Some(f) = n.as_f64() {
-                    Box::new(f) as Box<dyn rusqlite::ToSql>
-                } else {
-                    Box::new(rusqlite::types::Null) as Box<dyn rusqlite::ToSql>
-                }
-            }
-            serde_json::Value::Bool(b) => Box::new(*b) as Box<dyn rusqlite::ToSql>,
-            serde_json::Value::Null => Box::new(rusqlite::types::Null) as Box<dyn rusqlite::ToSql>,
-            _ => Box::new(rusqlite::types::Null) as Box<dyn rusqlite::ToSql>,
-        })
-        .collect();
+/// What `clear_sessions` removed
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClearedSessions {
+    pub session_metrics: i64,
+    pub sessions: i64,
+}
 
-    let param_refs: Vec<&dyn rusqlite::ToSql> =
-        rusqlite_params.iter().map(|p| p.as_ref()).collect();
-
-    let rows = stmt.query_map(param_refs.as_slice(), |row| {
-        let mut map = serde_json::Map::new();
-        let column_count = row.as_ref().column_count();
-
-        for i in 0..column_count {
-            let column_name = row.as_ref().column_name(i)?.to_string();
-            let value: serde_json::Value = match row.get_ref(i)? {
-                rusqlite::types::ValueRef::Null => serde_json::Value::Null,
-                rusqlite::types::ValueRef::Integer(i) => serde_json::Value::Number(i.into()),
-                rusqlite::types::ValueRef::Real(f) => serde_json::Number::from_f64(f)
-                    .map(serde_json::Value::Number)
-                    .unwrap_or(serde_json::Value::Null),
-                rusqlite::types::ValueRef::Text(s) => {
-                    serde_json::Value::String(String::from_utf8_lossy(s).to_string())
-                }
-                rusqlite::types::ValueRef::Blob(b) => {
-                    serde_json::Value::String(general_purpose::STANDARD.encode(b))
-                }
-            };
-            map.insert(column_name, value);
-        }
+/// Tables with a `session_id` column that aren't data computed from a session
+const NOT_SESSION_DATA: &[&str] = &["agent_sessions", "archived_sessions"];
 
-        Ok(serde_json::Value::Object(map))
-    })?;
+/// A table holding per-session data
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct SessionDataTable {
+    pub name: String,
+    pub has_session_id: bool,
+    pub has_provider: bool,
+}
 
-    rows.collect()
+/// Tables holding per-session data, read from the schema so tables added by
+/// later migrations are covered: those with a `session_id` or `provider` column
+pub(crate) fn session_data_tables(conn: &Connection) -> Result<Vec<SessionDataTable>> {
+    let mut stmt = conn.prepare(
+        "SELECT m.name, SUM(c.name = 'session_id'), SUM(c.name = 'provider')
+         FROM sqlite_master m JOIN pragma_table_info(m.name) c
+         WHERE m.type = 'table' AND c.name IN ('session_id', 'provider')
+         GROUP BY m.name ORDER BY m.name",
+    )?;
+    let tables = stmt
+        .query_map([], |row| {
+            Ok(SessionDataTable {
+                name: row.get(0)?,
+                has_session_id: row.get::<_, i64>(1)? > 0,
+                has_provider: row.get::<_, i64>(2)? > 0,
+            })
+        })?
+        .collect::<Result<Vec<_>>>()?;
+    Ok(tables
+        .into_iter()
+        .filter(|table| !NOT_SESSION_DATA.contains(&table.name.as_str()))
+        .collect())
+}
+
+fn count_sessions(conn: &Connection, provider: Option<&str>) -> Result<i64> {
+    conn.query_row(
+        "SELECT COUNT(*) FROM agent_sessions WHERE ?1 IS NULL OR provider = ?1",
+        params![provider],
+        |row| row.get(0),
+    )
+}
+
+fn count_session_metrics(conn: &Connection, provider: Option<&str>) -> Result<i64> {
+    conn.query_row(
+        "SELECT COUNT(*) FROM session_metrics
+         WHERE ?1 IS NULL
+            OR session_id IN (SELECT session_id FROM agent_sessions WHERE provider = ?1)",
+        params![provider],
+        |row| row.get(0),
+    )
+}
+
+/// Delete a provider's sessions (all sessions if `None`) and everything
+/// computed from them, in one transaction
+fn delete_sessions(conn: &mut Connection, provider: Option<&str>) -> Result<ClearedSessions> {
+    let tx = conn.transaction()?;
+    let cleared = ClearedSessions {
+        session_metrics: count_session_metrics(&tx, provider)?,
+        sessions: count_sessions(&tx, provider)?,
+    };
+
+    for table in session_data_tables(&tx)? {
+        let sql = if table.has_provider {
+            format!(
+                "DELETE FROM {} WHERE ?1 IS NULL OR provider = ?1",
+                table.name
+            )
+        } else {
+            format!(
+                "DELETE FROM {} WHERE ?1 IS NULL
+                    OR session_id IN (SELECT session_id FROM agent_sessions WHERE provider = ?1)",
+                table.name
+            )
+        };
+        tx.execute(&sql, params![provider])?;
+    }
+    // Habit days span providers, so recompute them from what's left
+    crate::metrics::store::rebuild_habit_days(&tx)?;
+    tx.execute(
+        "DELETE FROM agent_sessions WHERE ?1 IS NULL OR provider = ?1",
+        params![provider],
+    )?;
+    // Context file snapshots are kept per working directory
+    tx.execute(
+        "DELETE FROM context_file_snapshots
+         WHERE cwd NOT IN (SELECT cwd FROM agent_sessions WHERE cwd IS NOT NULL)",
+        [],
+    )?;
+
+    tx.commit()?;
+    Ok(cleared)
+}
+
+/// Delete a provider's sessions, or all sessions if `provider` is `None`,
+/// along with everything stored about them
+pub fn clear_sessions(provider: Option<&str>) -> Result<ClearedSessions> {
+    let mut conn = connection()?;
+    delete_sessions(&mut conn, provider)
 }
 
 /// Quick rate a session with thumbs up/meh/thumbs down
@@ -833,10 +1352,7 @@ pub fn quick_rate_session(session_id: &str, rating: &str) -> Result<()> {
     )
     .unwrap_or_default();
 
-    let db_conn = DB_CONNECTION.lock().unwrap();
-    let conn = db_conn
-        .as_ref()
-        .ok_or_else(|| rusqlite::Error::InvalidQuery)?;
+    let conn = connection()?;
 
     let now = Utc::now().timestamp_millis();
 
@@ -852,9 +1368,11 @@ pub fn quick_rate_session(session_id: &str, rating: &str) -> Result<()> {
     log_debug("database", &format!("Existing assessment: {:?}", existing)).unwrap_or_default();
 
     if let Some(id) = existing {
-        // Update existing assessment with new rating
+        // Update existing assessment with new rating, which makes a local
+        // assessment the user's own
         conn.execute(
-            "UPDATE session_assessments SET rating = ? WHERE id = ?",
+            "UPDATE session_assessments
+             SET rating = ?, survey_type = NULLIF(survey_type, 'local') WHERE id = ?",
             params![rating, id],
         )?;
 
@@ -888,32 +1406,92 @@ pub fn quick_rate_session(session_id: &str, rating: &str) -> Result<()> {
     }
 
     // Update agent_sessions assessment_status to 'rating_only' and set completed time
-    // ALSO reset synced_to_server to trigger re-upload with the new rating
+    // A synced session gets its new rating through a metadata patch; one that
+    // hasn't synced yet sends it with its upload
     conn.execute(
-        "UPDATE agent_sessions SET assessment_status = 'rating_only', assessment_completed_at = ?, synced_to_server = 0 WHERE session_id = ?",
-        params![now, session_id],
+        "UPDATE agent_sessions
+         SET assessment_status = 'rating_only', assessment_completed_at = ?,
+             metadata_dirty_at = CASE WHEN synced_to_server = 1 THEN ? ELSE metadata_dirty_at END
+         WHERE session_id = ?",
+        params![now, now, session_id],
     )?;
+    crate::upload_queue::request_db_poll();
 
     // Emit event to frontend
     if let Ok(app_handle_guard) = APP_HANDLE.lock() {
         if let Some(ref app_handle) = *app_handle_guard {
-            let _ = app_handle.emit("session-updated", session_id);
+            emit_session_event(app_handle, "session-updated", session_id, session_id);
         }
     }
 
     Ok(())
 }
 
+/// A synced session whose rating or assessment changed since it was uploaded
+#[derive(Debug, Clone)]
+pub struct DirtyMetadataSession {
+    pub session_id: String,
+    pub server_session_id: Option<String>,
+    /// When the metadata last changed, in Unix milliseconds
+    pub dirty_at: i64,
+}
+
+/// Get synced sessions with metadata changes to send, oldest change first
+pub fn get_dirty_metadata_sessions(limit: usize) -> Result<Vec<DirtyMetadataSession>> {
+    let tenant_id = crate::tenant::active_tenant_id();
+    with_connection_mut(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT session_id, server_session_id, metadata_dirty_at
+             FROM agent_sessions
+             WHERE metadata_dirty_at IS NOT NULL AND synced_to_server = 1 AND tenant_id IS ?
+             ORDER BY metadata_dirty_at ASC
+             LIMIT ?",
+        )?;
+        let sessions = stmt
+            .query_map(params![tenant_id, limit as i64], |row| {
+                Ok(DirtyMetadataSession {
+                    session_id: row.get(0)?,
+                    server_session_id: row.get(1)?,
+                    dirty_at: row.get(2)?,
+                })
+            })?
+            .collect::<Result<Vec<_>>>()?;
+        Ok(sessions)
+    })
+}
+
+/// Clear a session's metadata changes once patched, unless it changed again meanwhile
+pub fn clear_metadata_dirty(session_id: &str, dirty_at: i64) -> Result<bool> {
+    with_connection_mut(|conn| {
+        conn.execute(
+            "UPDATE agent_sessions SET metadata_dirty_at = NULL
+             WHERE session_id = ? AND metadata_dirty_at = ?",
+            params![session_id, dirty_at],
+        )
+    })
+    .map(|rows| rows > 0)
+}
+
+/// Send a session's metadata changes with a full upload instead of a patch
+pub fn requeue_dirty_metadata(session_id: &str) -> Result<()> {
+    with_connection_mut(|conn| {
+        conn.execute(
+            "UPDATE agent_sessions SET synced_to_server = 0, metadata_dirty_at = NULL
+             WHERE session_id = ?",
+            params![session_id],
+        )
+    })?;
+    Ok(())
+}
+
 /// Get the rating for a session
 pub fn get_session_rating(session_id: &str) -> Result<Option<String>> {
-    let db_conn = DB_CONNECTION.lock().unwrap();
-    let conn = db_conn
-        .as_ref()
-        .ok_or_else(|| rusqlite::Error::InvalidQuery)?;
+    let conn = connection()?;
 
     let rating: Option<String> = conn
         .query_row(
-            "SELECT rating FROM session_assessments WHERE session_id = ?",
+            "SELECT rating FROM session_assessments
+             WHERE session_id = ? AND survey_type IS NOT 'local'",
             params![session_id],
             |row| row.get(0),
         )
@@ -922,6 +1500,40 @@ pub fn get_session_rating(session_id: &str) -> Result<Option<String>> {
     Ok(rating)
 }
 
+/// IDs of the sessions stored for a provider
+pub fn get_session_ids_for_provider(provider: &str) -> Result<std::collections::HashSet<String>> {
+    let conn = connection()?;
+
+    let mut stmt = conn.prepare("SELECT session_id FROM agent_sessions WHERE provider = ?")?;
+    let session_ids = stmt
+        .query_map(params![provider], |row| row.get(0))?
+        .collect::<Result<_>>()?;
+
+    Ok(session_ids)
+}
+
+/// IDs of a provider's sessions moved to an archive
+pub fn get_archived_session_ids(provider: &str) -> Result<std::collections::HashSet<String>> {
+    let conn = connection()?;
+
+    let mut stmt = conn.prepare("SELECT session_id FROM archived_sessions WHERE provider = ?")?;
+    let session_ids = stmt
+        .query_map(params![provider], |row| row.get(0))?
+        .collect::<Result<_>>()?;
+
+    Ok(session_ids)
+}
+
+/// Whether a session was moved to an archive
+pub fn is_session_archived(session_id: &str) -> Result<bool> {
+    let conn = connection()?;
+    conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM archived_sessions WHERE session_id = ?)",
+        params![session_id],
+        |row| row.get(0),
+    )
+}
+
 /// Full session data structure for metrics-only sync
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FullSessionData {
@@ -948,14 +1560,14 @@ pub struct FullSessionData {
     pub git_branch: Option<String>,
     pub first_commit_hash: Option<String>,
     pub latest_commit_hash: Option<String>,
+    pub cwd: Option<String>,
+    /// The session this one resumes, if it continues an earlier conversation
+    pub resumed_from: Option<String>,
 }
 
 /// Get full session data by session ID (for metrics-only sync)
 pub fn get_full_session_by_id(session_id: &str) -> Result<Option<FullSessionData>> {
-    let db_conn = DB_CONNECTION.lock().unwrap();
-    let conn = db_conn
-        .as_ref()
-        .ok_or_else(|| rusqlite::Error::InvalidQuery)?;
+    let conn = connection()?;
 
     let session: Option<FullSessionData> = conn
         .query_row(
@@ -966,7 +1578,7 @@ pub fn get_full_session_by_id(session_id: &str) -> Result<Option<FullSessionData
                     core_metrics_processed_at,
                     assessment_status, assessment_completed_at,
                     ai_model_summary, ai_model_quality_score, ai_model_metadata, ai_model_phase_analysis,
-                    git_branch, first_commit_hash, latest_commit_hash
+                    git_branch, first_commit_hash, latest_commit_hash, cwd, resumed_from
              FROM agent_sessions
              WHERE session_id = ?",
             params![session_id],
@@ -995,6 +1607,8 @@ pub fn get_full_session_by_id(session_id: &str) -> Result<Option<FullSessionData
                     git_branch: row.get(20)?,
                     first_commit_hash: row.get(21)?,
                     latest_commit_hash: row.get(22)?,
+                    cwd: row.get(23)?,
+                    resumed_from: row.get(24)?,
                 })
             },
         )
@@ -1003,6 +1617,136 @@ pub fn get_full_session_by_id(session_id: &str) -> Result<Option<FullSessionData
     Ok(session)
 }
 
+/// Replace the commits linked to a session
+pub fn replace_session_commits(
+    session_id: &str,
+    commits: &[crate::git_diff::SessionCommit],
+) -> Result<()> {
+    with_connection_mut(|conn| {
+        let tx = conn.transaction()?;
+
+        tx.execute(
+            "DELETE FROM session_commits WHERE session_id = ?",
+            params![session_id],
+        )?;
+
+        for commit in commits {
+            tx.execute(
+                "INSERT INTO session_commits (
+                    session_id, commit_hash, summary, author_name, author_email, authored_at
+                ) VALUES (?, ?, ?, ?, ?, ?)",
+                params![
+                    session_id,
+                    commit.commit_hash,
+                    commit.summary,
+                    commit.author_name,
+                    commit.author_email,
+                    commit.authored_at,
+                ],
+            )?;
+        }
+
+        tx.commit()
+    })
+}
+
+/// Get the commits linked to a session, newest first
+pub fn get_session_commits(session_id: &str) -> Result<Vec<crate::git_diff::SessionCommit>> {
+    with_connection_mut(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT commit_hash, summary, author_name, author_email, authored_at
+             FROM session_commits
+             WHERE session_id = ?
+             ORDER BY authored_at DESC",
+        )?;
+        let commits = stmt
+            .query_map(params![session_id], |row| {
+                Ok(crate::git_diff::SessionCommit {
+                    commit_hash: row.get(0)?,
+                    summary: row.get(1)?,
+                    author_name: row.get(2)?,
+                    author_email: row.get(3)?,
+                    authored_at: row.get(4)?,
+                })
+            })?
+            .collect::<Result<Vec<_>>>()?;
+        Ok(commits)
+    })
+}
+
+/// Store a session's uncommitted-change snapshot and its git diff stats
+pub fn store_uncommitted_snapshot(
+    session_id: &str,
+    snapshot: &crate::git_diff::UncommittedSnapshot,
+) -> Result<()> {
+    with_connection_mut(|conn| {
+        let tx = conn.transaction()?;
+
+        tx.execute(
+            "INSERT OR REPLACE INTO uncommitted_snapshots (
+                session_id, head_commit, files_changed, lines_added, lines_removed,
+                patch, patch_truncated, captured_at
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+            params![
+                session_id,
+                snapshot.head_commit,
+                snapshot.files_changed,
+                snapshot.lines_added,
+                snapshot.lines_removed,
+                snapshot.patch,
+                snapshot.patch_truncated,
+                snapshot.captured_at,
+            ],
+        )?;
+
+        tx.execute(
+            "UPDATE session_metrics SET
+                git_total_files_changed = ?,
+                git_lines_added = ?,
+                git_lines_removed = ?,
+                git_net_lines_changed = ?
+             WHERE session_id = ?",
+            params![
+                snapshot.files_changed,
+                snapshot.lines_added,
+                snapshot.lines_removed,
+                snapshot.lines_added - snapshot.lines_removed,
+                session_id,
+            ],
+        )?;
+
+        tx.commit()
+    })
+}
+
+/// Get the uncommitted-change snapshot captured when a session ended
+pub fn get_uncommitted_snapshot(
+    session_id: &str,
+) -> Result<Option<crate::git_diff::UncommittedSnapshot>> {
+    with_connection_mut(|conn| {
+        Ok(conn
+            .query_row(
+                "SELECT head_commit, files_changed, lines_added, lines_removed,
+                        patch, patch_truncated, captured_at
+                 FROM uncommitted_snapshots
+                 WHERE session_id = ?",
+                params![session_id],
+                |row| {
+                    Ok(crate::git_diff::UncommittedSnapshot {
+                        head_commit: row.get(0)?,
+                        files_changed: row.get(1)?,
+                        lines_added: row.get(2)?,
+                        lines_removed: row.get(3)?,
+                        patch: row.get(4)?,
+                        patch_truncated: row.get(5)?,
+                        captured_at: row.get(6)?,
+                    })
+                },
+            )
+            .ok())
+    })
+}
+
 /// Session metrics structure
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SessionMetrics {
@@ -1029,6 +1773,8 @@ pub struct SessionMetrics {
     pub session_length_minutes: Option<f64>,
     pub total_interruptions: Option<i64>,
     pub engagement_total_responses: Option<i64>,
+    pub active_length_minutes: Option<f64>,
+    pub idle_gap_count: Option<i64>,
     // Quality metrics
     pub task_success_rate: Option<f64>,
     pub iteration_count: Option<i64>,
@@ -1067,14 +1813,21 @@ pub struct SessionMetrics {
     pub messages_until_first_compact: Option<i64>,
     pub avg_tokens_per_message: Option<f64>,
     pub context_improvement_tips: Option<String>, // JSON array
+    pub peak_context_utilization_percent: Option<f64>,
+    pub avg_context_utilization_percent: Option<f64>,
+    pub context_ceiling_hits: Option<i64>,
+}
+
+impl SessionMetrics {
+    /// Session time without idle gaps, in milliseconds
+    pub fn active_duration_ms(&self) -> Option<i64> {
+        self.active_length_minutes.map(|minutes| (minutes * 60_000.0).round() as i64)
+    }
 }
 
 /// Clear all failed sessions from the database
 pub fn clear_failed_sessions() -> Result<()> {
-    let db_conn = DB_CONNECTION.lock().unwrap();
-    let conn = db_conn
-        .as_ref()
-        .ok_or_else(|| rusqlite::Error::InvalidQuery)?;
+    let conn = connection()?;
 
     conn.execute(
         "DELETE FROM agent_sessions WHERE sync_failed_reason IS NOT NULL",
@@ -1088,10 +1841,7 @@ pub fn clear_failed_sessions() -> Result<()> {
 
 /// Retry all failed sessions by resetting their sync status
 pub fn retry_failed_sessions() -> Result<()> {
-    let db_conn = DB_CONNECTION.lock().unwrap();
-    let conn = db_conn
-        .as_ref()
-        .ok_or_else(|| rusqlite::Error::InvalidQuery)?;
+    let conn = connection()?;
 
     conn.execute(
         "UPDATE agent_sessions
@@ -1107,10 +1857,16 @@ pub fn retry_failed_sessions() -> Result<()> {
 
 /// Remove a session from the database by ID
 pub fn remove_session_by_id(session_id: &str) -> Result<usize> {
-    let db_conn = DB_CONNECTION.lock().unwrap();
-    let conn = db_conn
-        .as_ref()
-        .ok_or_else(|| rusqlite::Error::InvalidQuery)?;
+    let conn = connection()?;
+
+    // Remove the session's contribution to the usage rollups first
+    if let Ok(agent_session_id) = conn.query_row(
+        "SELECT session_id FROM agent_sessions WHERE id = ?",
+        params![session_id],
+        |row| row.get::<_, String>(0),
+    ) {
+        crate::metrics::store::remove_session_usage(&conn, &agent_session_id)?;
+    }
 
     let rows_affected = conn.execute(
         "DELETE FROM agent_sessions WHERE id = ?",
@@ -1130,10 +1886,7 @@ pub fn remove_session_by_id(session_id: &str) -> Result<usize> {
 
 /// Retry a single failed session by resetting its sync status
 pub fn retry_session_by_id(session_id: &str) -> Result<usize> {
-    let db_conn = DB_CONNECTION.lock().unwrap();
-    let conn = db_conn
-        .as_ref()
-        .ok_or_else(|| rusqlite::Error::InvalidQuery)?;
+    let conn = connection()?;
 
     let rows_affected = conn.execute(
         "UPDATE agent_sessions
@@ -1149,12 +1902,64 @@ pub fn retry_session_by_id(session_id: &str) -> Result<usize> {
     Ok(rows_affected)
 }
 
+/// Clear a session's sync failure so the upload queue tries it again
+pub fn clear_sync_failure(session_id: &str) -> Result<usize> {
+    with_connection_mut(|conn| {
+        conn.execute(
+            "UPDATE agent_sessions SET sync_failed_reason = NULL WHERE session_id = ?",
+            params![session_id],
+        )
+    })
+}
+
+/// A session's AI summary and analysis
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AiResults {
+    pub summary: Option<String>,
+    pub quality_score: Option<f64>,
+    /// JSON object
+    pub metadata: Option<String>,
+    /// JSON object
+    pub phase_analysis: Option<String>,
+}
+
+/// Store a session's AI results, completing its processing and flagging it
+/// for re-sync so they're uploaded
+pub fn store_ai_results(session_id: &str, results: &AiResults) -> Result<usize> {
+    let now = Utc::now().timestamp_millis();
+    with_connection_mut(|conn| {
+        conn.execute(
+            "UPDATE agent_sessions
+             SET ai_model_summary = ?, ai_model_quality_score = ?, ai_model_metadata = ?,
+                 ai_model_phase_analysis = ?, processing_status = 'completed', processed_at = ?,
+                 synced_to_server = 0
+             WHERE session_id = ?",
+            params![
+                results.summary,
+                results.quality_score,
+                results.metadata,
+                results.phase_analysis,
+                now,
+                session_id
+            ],
+        )
+    })
+}
+
+/// Mark a session's AI processing as failed
+pub fn fail_ai_processing(session_id: &str) -> Result<usize> {
+    with_connection_mut(|conn| {
+        conn.execute(
+            "UPDATE agent_sessions SET processing_status = 'failed' WHERE session_id = ?",
+            params![session_id],
+        )
+    })
+}
+
 /// Get session metrics by session ID
 pub fn get_session_metrics(session_id: &str) -> Result<Option<SessionMetrics>> {
-    let db_conn = DB_CONNECTION.lock().unwrap();
-    let conn = db_conn
-        .as_ref()
-        .ok_or_else(|| rusqlite::Error::InvalidQuery)?;
+    let conn = connection()?;
 
     let metrics: Option<SessionMetrics> = conn
         .query_row(
@@ -1173,7 +1978,9 @@ pub fn get_session_metrics(session_id: &str) -> Result<Option<SessionMetrics>> {
                     total_input_tokens, total_output_tokens, total_cache_created, total_cache_read,
                     context_length, context_window_size, context_utilization_percent,
                     compact_event_count, compact_event_steps, messages_until_first_compact,
-                    avg_tokens_per_message, context_improvement_tips
+                    avg_tokens_per_message, context_improvement_tips,
+                    peak_context_utilization_percent, avg_context_utilization_percent,
+                    context_ceiling_hits, active_length_minutes, idle_gap_count
              FROM session_metrics
              WHERE session_id = ?
              ORDER BY created_at DESC
@@ -1235,6 +2042,11 @@ pub fn get_session_metrics(session_id: &str) -> Result<Option<SessionMetrics>> {
                     messages_until_first_compact: row.get(51)?,
                     avg_tokens_per_message: row.get(52)?,
                     context_improvement_tips: row.get(53)?,
+                    peak_context_utilization_percent: row.get(54)?,
+                    avg_context_utilization_percent: row.get(55)?,
+                    context_ceiling_hits: row.get(56)?,
+                    active_length_minutes: row.get(57)?,
+                    idle_gap_count: row.get(58)?,
                 })
             },
         )
@@ -1242,3 +2054,253 @@ pub fn get_session_metrics(session_id: &str) -> Result<Option<SessionMetrics>> {
 
     Ok(metrics)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(session_id: &str, end_time: Option<DateTime<Utc>>) -> SessionRecord<'_> {
+        SessionRecord {
+            provider: "claude-code",
+            project_name: "app",
+            session_id,
+            file_name: "f.jsonl",
+            file_path: "/f.jsonl",
+            file_size: 10,
+            session_start_time: Some(Utc::now()),
+            session_end_time: end_time,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_save_sessions_inserts_updates_and_links_projects() {
+        let mut conn = test_connection();
+        let project = ProjectRecord {
+            name: "app",
+            github_repo: None,
+            cwd: "/work/app",
+            project_type: "node",
+        };
+
+        let saved = save_session_rows(
+            &mut conn,
+            &[
+                (record("s1", None), Some(project.clone())),
+                (record("s2", Some(Utc::now())), None),
+            ],
+        )
+        .unwrap();
+        assert_eq!(
+            saved,
+            [
+                Ok(SavedSession {
+                    inserted: true,
+                    completed: false
+                }),
+                Ok(SavedSession {
+                    inserted: true,
+                    completed: true
+                }),
+            ]
+        );
+
+        // Saving again updates; s1 completes now, s2 already had its end time
+        let saved = save_session_rows(
+            &mut conn,
+            &[
+                (record("s1", Some(Utc::now())), Some(project)),
+                (record("s2", Some(Utc::now())), None),
+            ],
+        )
+        .unwrap();
+        assert_eq!(
+            saved,
+            [
+                Ok(SavedSession {
+                    inserted: false,
+                    completed: true
+                }),
+                Ok(SavedSession {
+                    inserted: false,
+                    completed: false
+                }),
+            ]
+        );
+
+        let (sessions, projects, linked): (i64, i64, i64) = conn
+            .query_row(
+                "SELECT (SELECT COUNT(*) FROM agent_sessions),
+                        (SELECT COUNT(*) FROM projects),
+                        (SELECT COUNT(*) FROM agent_sessions WHERE project_id IS NOT NULL)",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .unwrap();
+        assert_eq!((sessions, projects, linked), (2, 1, 1));
+    }
+
+    #[test]
+    fn test_low_signal_sessions_are_gated_again_once_changed() {
+        let mut conn = test_connection();
+        let saved = |conn: &mut Connection, file_hash: &str| {
+            let record = SessionRecord {
+                file_hash: Some(file_hash),
+                ..record("s1", Some(Utc::now()))
+            };
+            save_session_rows(conn, &[(record, None)]).unwrap();
+        };
+        let skipped = |conn: &Connection| -> (bool, Option<String>) {
+            conn.query_row(
+                "SELECT sync_skipped, sync_skip_reason FROM agent_sessions",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap()
+        };
+
+        saved(&mut conn, "h1");
+        skip_session_sync(&conn, "s1", LOW_SIGNAL).unwrap();
+        saved(&mut conn, "h1");
+        assert_eq!(skipped(&conn), (true, Some(LOW_SIGNAL.to_string())));
+
+        saved(&mut conn, "h2");
+        assert_eq!(skipped(&conn), (false, None));
+    }
+
+    #[test]
+    fn test_pooled_connections_use_wal_and_immediate_transactions() {
+        let dir = tempfile::tempdir().unwrap();
+        let manager = SqliteConnectionManager::file(dir.path().join("test.db"))
+            .with_init(configure_connection);
+        let pool = r2d2::Pool::builder().max_size(2).build(manager).unwrap();
+
+        let mut writer = pool.get().unwrap();
+        let journal_mode: String = writer
+            .query_row("PRAGMA journal_mode", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(journal_mode, "wal");
+        writer.execute_batch("CREATE TABLE t (x INTEGER)").unwrap();
+
+        // An open transaction already holds the write lock, so the other
+        // connection can read but not start writing
+        let tx = writer.transaction().unwrap();
+        let mut other = pool.get().unwrap();
+        other.busy_timeout(Duration::from_millis(10)).unwrap();
+        let count: i64 = other
+            .query_row("SELECT COUNT(*) FROM t", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 0);
+        assert!(other.transaction().is_err());
+        tx.commit().unwrap();
+    }
+
+    #[test]
+    fn test_clear_sessions_by_provider_then_all() {
+        let mut conn = test_connection();
+        for (session_id, provider) in [
+            ("s1", "claude-code"),
+            ("s2", "claude-code"),
+            ("s3", "codex"),
+        ] {
+            insert_session_row(
+                &conn,
+                &SessionRecord {
+                    provider,
+                    ..record(session_id, None)
+                },
+            )
+            .unwrap();
+            conn.execute(
+                "INSERT INTO session_metrics (id, session_id, provider, timestamp, created_at)
+                 VALUES (?1, ?1, ?2, 0, 0)",
+                params![session_id, provider],
+            )
+            .unwrap();
+            conn.execute(
+                "INSERT INTO session_usage (session_id, provider, project_name, day, week, updated_at)
+                 VALUES (?1, ?2, 'app', '2026-10-12', '2026-10-12', 0)",
+                params![session_id, provider],
+            )
+            .unwrap();
+            conn.execute(
+                "INSERT INTO uploaded_hashes (provider, session_id, hash, uploaded_at)
+                 VALUES (?2, ?1, ?1, 0)",
+                params![session_id, provider],
+            )
+            .unwrap();
+        }
+        conn.execute(
+            "INSERT INTO context_file_snapshots (
+                cwd, file_path, relative_path, content_hash, content, size, token_count,
+                modified_at, captured_at
+            ) VALUES ('/gone', '/gone/CLAUDE.md', 'CLAUDE.md', 'h', '', 0, 0, 0, 0)",
+            [],
+        )
+        .unwrap();
+        let count = |conn: &Connection, table: &str| -> i64 {
+            conn.query_row(&format!("SELECT COUNT(*) FROM {}", table), [], |row| {
+                row.get(0)
+            })
+            .unwrap()
+        };
+        crate::metrics::store::rebuild_habit_days(&conn).unwrap();
+        let habit_sessions = |conn: &Connection| -> i64 {
+            conn.query_row(
+                "SELECT COALESCE(SUM(session_count), 0) FROM habit_days",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap()
+        };
+        assert_eq!(habit_sessions(&conn), 3);
+
+        assert_eq!(
+            delete_sessions(&mut conn, Some("claude-code")).unwrap(),
+            ClearedSessions {
+                session_metrics: 2,
+                sessions: 2
+            }
+        );
+        assert_eq!(count_sessions(&conn, None).unwrap(), 1);
+        assert_eq!(count_session_metrics(&conn, Some("codex")).unwrap(), 1);
+        assert_eq!(count(&conn, "uploaded_hashes"), 1);
+        assert_eq!(count(&conn, "context_file_snapshots"), 0);
+        assert_eq!(habit_sessions(&conn), 1);
+
+        assert_eq!(
+            delete_sessions(&mut conn, None).unwrap(),
+            ClearedSessions {
+                session_metrics: 1,
+                sessions: 1
+            }
+        );
+        assert_eq!(count(&conn, "session_usage"), 0);
+        assert_eq!(count(&conn, "uploaded_hashes"), 0);
+        assert_eq!(habit_sessions(&conn), 0);
+    }
+
+    #[test]
+    fn test_session_data_tables_come_from_the_schema() {
+        let conn = test_connection();
+        let names: Vec<String> = session_data_tables(&conn)
+            .unwrap()
+            .into_iter()
+            .map(|table| table.name)
+            .collect();
+        for table in [
+            "conversion_errors",
+            "ingest_skips",
+            "scan_cache",
+            "session_assessments",
+            "session_metrics",
+            "upload_bases",
+            "uploaded_hashes",
+        ] {
+            assert!(names.iter().any(|name| name == table), "{} missing", table);
+        }
+        assert!(!names
+            .iter()
+            .any(|name| NOT_SESSION_DATA.contains(&name.as_str())));
+    }
+}
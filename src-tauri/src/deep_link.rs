@@ -0,0 +1,179 @@
+//! `guidemode://` deep links
+//!
+//! Links from the web dashboard or chat tools open the app on an entity:
+//!
+//! - `guidemode://session/<id>` and `guidemode://project/<id>` focus the main
+//!   window and navigate it to that session or project
+//! - `guidemode://auth/callback?key=…&tenant_id=…&tenant_name=…` completes a
+//!   login waiting in `login_command`, for when the browser can't reach the
+//!   localhost auth server
+//!
+//! The scheme is registered by `tauri-plugin-deep-link`. On Windows and Linux
+//! a link starts a second process; `tauri-plugin-single-instance` forwards its
+//! URL to the running app instead.
+//!
+//! Links that launched the app arrive before the frontend listens for
+//! `navigate` events, so their route is kept for the frontend to take once
+//! it has loaded.
+
+use crate::auth_server::AuthServer;
+use crate::logging::{log_info, log_warn};
+use crate::windows::{focus_main_window, MAIN_WINDOW};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter};
+use url::Url;
+
+pub const SCHEME: &str = "guidemode";
+
+/// Redirect target the server can use instead of the localhost callback
+pub const AUTH_CALLBACK_URL: &str = "guidemode://auth/callback";
+
+/// Route of the link that launched the app, until the frontend takes it
+static LAUNCH_ROUTE: Mutex<Option<String>> = Mutex::new(None);
+
+#[derive(Debug, PartialEq)]
+pub enum DeepLink {
+    Session(String),
+    Project(String),
+    AuthCallback(HashMap<String, String>),
+}
+
+impl DeepLink {
+    /// Frontend route the link navigates to
+    fn route(&self) -> Option<String> {
+        match self {
+            DeepLink::Session(id) => Some(format!("/sessions/{}", urlencoding::encode(id))),
+            DeepLink::Project(id) => Some(format!("/projects/{}", urlencoding::encode(id))),
+            DeepLink::AuthCallback(_) => None,
+        }
+    }
+}
+
+/// Parse a `guidemode://` URL, or `None` if it isn't a link this app handles
+pub fn parse(url: &Url) -> Option<DeepLink> {
+    if url.scheme() != SCHEME {
+        return None;
+    }
+
+    let segments: Vec<String> = url
+        .path_segments()
+        .into_iter()
+        .flatten()
+        .filter(|segment| !segment.is_empty())
+        .map(|segment| {
+            urlencoding::decode(segment)
+                .map(|s| s.into_owned())
+                .unwrap_or_else(|_| segment.to_string())
+        })
+        .collect();
+
+    match (url.host_str()?, segments.as_slice()) {
+        ("session", [id]) => Some(DeepLink::Session(id.clone())),
+        ("project", [id]) => Some(DeepLink::Project(id.clone())),
+        ("auth", [callback]) if callback == "callback" => Some(DeepLink::AuthCallback(
+            url.query_pairs().into_owned().collect(),
+        )),
+        _ => None,
+    }
+}
+
+/// Handle links opened while the app is running
+pub fn handle_urls(app: &AppHandle, urls: Vec<Url>) {
+    for url in urls {
+        match parse(&url) {
+            Some(DeepLink::AuthCallback(params)) => {
+                focus_main_window(app);
+                tauri::async_runtime::spawn(async move {
+                    if !AuthServer::complete_from_deep_link(&params).await {
+                        log_warn(
+                            "deep-link",
+                            "⚠ Ignoring auth callback: no login in progress or its state doesn't match",
+                        )
+                        .unwrap_or_default();
+                    }
+                });
+            }
+            Some(link) => {
+                if let Some(route) = link.route() {
+                    log_info("deep-link", &format!("🔗 Opening {}", route)).unwrap_or_default();
+                    focus_main_window(app);
+                    let _ = app.emit_to(MAIN_WINDOW, "navigate", route);
+                }
+            }
+            None => {
+                log_warn(
+                    "deep-link",
+                    &format!("⚠ Ignoring unrecognised link {}", url),
+                )
+                .unwrap_or_default();
+            }
+        }
+    }
+}
+
+/// Keep the route of the links that launched the app for the frontend
+///
+/// No login can be waiting yet, so auth callbacks among them are ignored.
+pub fn handle_launch_urls(urls: Vec<Url>) {
+    let route = urls
+        .iter()
+        .filter_map(|url| parse(url)?.route())
+        .next_back();
+    if let Some(route) = route {
+        log_info("deep-link", &format!("🔗 Launched with {}", route)).unwrap_or_default();
+        if let Ok(mut launch_route) = LAUNCH_ROUTE.lock() {
+            *launch_route = Some(route);
+        }
+    }
+}
+
+/// Take the route of the link that launched the app, if any
+pub fn take_launch_route() -> Option<String> {
+    LAUNCH_ROUTE.lock().ok()?.take()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn link(url: &str) -> Option<DeepLink> {
+        parse(&Url::parse(url).unwrap())
+    }
+
+    #[test]
+    fn test_parse_entity_links() {
+        assert_eq!(
+            link("guidemode://session/0199a3f2-7c1e"),
+            Some(DeepLink::Session("0199a3f2-7c1e".to_string()))
+        );
+        assert_eq!(
+            link("guidemode://project/42/"),
+            Some(DeepLink::Project("42".to_string()))
+        );
+        assert_eq!(
+            link("guidemode://session/chat%2F2025").and_then(|l| l.route()),
+            Some("/sessions/chat%2F2025".to_string())
+        );
+
+        assert_eq!(link("guidemode://session"), None);
+        assert_eq!(link("guidemode://session/a/b"), None);
+        assert_eq!(link("guidemode://settings/x"), None);
+        assert_eq!(link("https://session/abc"), None);
+    }
+
+    #[test]
+    fn test_parse_auth_callback() {
+        let Some(DeepLink::AuthCallback(params)) =
+            link("guidemode://auth/callback?key=gm_1&tenant_id=t1&tenant_name=Acme%20Inc")
+        else {
+            panic!("expected an auth callback");
+        };
+        assert_eq!(params.get("key").map(String::as_str), Some("gm_1"));
+        assert_eq!(
+            params.get("tenant_name").map(String::as_str),
+            Some("Acme Inc")
+        );
+        assert_eq!(link("guidemode://auth/other"), None);
+    }
+}
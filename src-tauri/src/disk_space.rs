@@ -0,0 +1,170 @@
+//! Disk space guard
+//!
+//! Canonical session files and archives are only written while the disk they
+//! go to has more free space than the configured minimum (`minFreeDiskMb`,
+//! 500 MB by default). Below it, `get_canonical_path` refuses, which pauses
+//! the scanners' and watchers' cache writes, and archives aren't written.
+//! The frontend is told with a `disk-space` event whenever writes pause or
+//! resume; the space is checked on each write and every minute, so writes
+//! resume once space is freed.
+
+use crate::config::{get_sessions_dir, load_config};
+use crate::logging::{log_info, log_warn};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+/// Free space below which writes pause, when the config doesn't set one
+pub const DEFAULT_MIN_FREE_MB: u64 = 500;
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+static APP: OnceLock<AppHandle> = OnceLock::new();
+
+/// Whether writes are paused for lack of space
+static PAUSED: Mutex<bool> = Mutex::new(false);
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiskSpace {
+    pub path: PathBuf,
+    pub available_bytes: u64,
+    pub min_free_bytes: u64,
+    /// Writes are paused until space is freed
+    pub low: bool,
+    pub checked_at: DateTime<Utc>,
+}
+
+impl DiskSpace {
+    fn of(path: &Path, available_bytes: u64, min_free_bytes: u64) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            available_bytes,
+            min_free_bytes,
+            low: available_bytes < min_free_bytes,
+            checked_at: Utc::now(),
+        }
+    }
+
+    /// Why a write to this disk is refused
+    pub fn refusal(&self, what: &str) -> String {
+        format!(
+            "Not writing {}: only {} MB free on the disk of {}, below the {} MB minimum",
+            what,
+            self.available_bytes / 1_000_000,
+            self.path.display(),
+            self.min_free_bytes / 1_000_000
+        )
+    }
+}
+
+fn min_free_bytes() -> u64 {
+    load_config()
+        .ok()
+        .and_then(|config| config.min_free_disk_mb)
+        .unwrap_or(DEFAULT_MIN_FREE_MB)
+        * 1_000_000
+}
+
+/// The nearest existing ancestor of `path`, whose disk it will be written to
+fn existing_ancestor(path: &Path) -> Option<&Path> {
+    path.ancestors().find(|ancestor| ancestor.exists())
+}
+
+/// Free space on the disk `path` is (or would be) on
+pub fn check(path: &Path) -> Option<DiskSpace> {
+    let available = fs2::available_space(existing_ancestor(path)?).ok()?;
+    Some(DiskSpace::of(path, available, min_free_bytes()))
+}
+
+/// Remember whether writes are paused, logging and telling the frontend
+/// when that changes
+fn record(space: &DiskSpace) {
+    let Ok(mut paused) = PAUSED.lock() else {
+        return;
+    };
+    if *paused == space.low {
+        return;
+    }
+    *paused = space.low;
+    drop(paused);
+
+    if space.low {
+        log_warn(
+            "disk-space",
+            &format!("⏸ {}", space.refusal("session files")),
+        )
+        .unwrap_or_default();
+    } else {
+        log_info(
+            "disk-space",
+            &format!(
+                "▶ {} MB free again, resuming session file writes",
+                space.available_bytes / 1_000_000
+            ),
+        )
+        .unwrap_or_default();
+    }
+    if let Some(app) = APP.get() {
+        let _ = app.emit("disk-space", space);
+    }
+}
+
+/// Refuse to write `what` to `path` while its disk is low on space
+///
+/// Space that can't be measured doesn't block the write.
+pub fn ensure_space(path: &Path, what: &str) -> Result<(), String> {
+    let Some(space) = check(path) else {
+        return Ok(());
+    };
+    record(&space);
+    if space.low {
+        return Err(space.refusal(what));
+    }
+    Ok(())
+}
+
+/// Check the session cache's disk now and then every `CHECK_INTERVAL`, so
+/// the frontend learns when writes pause or resume even without writes
+pub fn start_periodic_checks(app: AppHandle) {
+    let _ = APP.set(app);
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+            let space = get_sessions_dir()
+                .ok()
+                .and_then(|sessions_dir| check(&sessions_dir));
+            if let Some(space) = space {
+                record(&space);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_low_space() {
+        let space = DiskSpace::of(Path::new("/data/sessions"), 200_000_000, 500_000_000);
+        assert!(space.low);
+        assert_eq!(
+            space.refusal("archive"),
+            "Not writing archive: only 200 MB free on the disk of /data/sessions, below the 500 MB minimum"
+        );
+        assert!(!DiskSpace::of(Path::new("/data"), 500_000_000, 500_000_000).low);
+    }
+
+    #[test]
+    fn test_measures_nearest_existing_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let missing = dir.path().join("sessions/claude-code/app");
+        assert_eq!(existing_ancestor(&missing), Some(dir.path()));
+        assert!(check(&missing).is_some_and(|space| space.available_bytes > 0));
+    }
+}
@@ -1,6 +1,11 @@
+use serde::ser::SerializeStruct;
 use thiserror::Error;
 
 /// GuideMode Desktop application errors
+///
+/// Commands return these to the frontend serialized as
+/// `{ code, message, retryable }`, with `status` added for HTTP status
+/// errors, so the UI can branch on `code` rather than on message text.
 #[derive(Debug, Error)]
 pub enum GuideModeError {
     /// Database-related errors
@@ -31,9 +36,49 @@ pub enum GuideModeError {
     #[error("JSON error: {0}")]
     Json(#[from] serde_json::Error),
 
-    /// HTTP request errors
-    #[error("HTTP error: {0}")]
-    Http(#[from] reqwest::Error),
+    /// Requests that never got a response (connection, timeout, TLS, body)
+    #[error("Network error: {0}")]
+    Network(#[from] reqwest::Error),
+
+    /// The server answered `action` with a non-success status
+    #[error("{action} failed with status {status}: {body}")]
+    Status {
+        action: String,
+        status: reqwest::StatusCode,
+        body: String,
+    },
+
+    /// The server received different content than was uploaded
+    #[error(
+        "Upload checksum mismatch for {session_id}: the server received content hashing to {received}, but {sent} was sent"
+    )]
+    ChecksumMismatch {
+        session_id: String,
+        received: String,
+        sent: String,
+    },
+
+    /// A session, file or record that doesn't exist
+    #[error("Not found: {0}")]
+    NotFound(String),
+
+    /// Errors reading or converting a provider's sessions
+    #[error("{provider}: {message}")]
+    Provider { provider: String, message: String },
+
+    /// The operating system denied reading a provider's sessions, e.g. macOS
+    /// privacy protection without Full Disk Access
+    #[error("{provider}: access to {path} was denied. Grant GuideMode access to it (on macOS: System Settings > Privacy & Security > Full Disk Access)")]
+    AccessDenied { provider: String, path: String },
+
+    /// A path built from session metadata that leads out of the provider's
+    /// directory, e.g. through `..` or a symlink
+    #[error("{provider}: refused to read {path}, which is outside its directory")]
+    OutsideRoot { provider: String, path: String },
+
+    /// A long-running operation stopped because the user cancelled it
+    #[error("{0} was cancelled")]
+    Cancelled(String),
 
     /// Mutex poison error
     #[error("Lock poisoned: {0}")]
@@ -44,14 +89,140 @@ pub enum GuideModeError {
     Other(String),
 }
 
-/// Convert GuideModeError to String for Tauri commands
-/// Tauri commands can only return String errors to the frontend
+impl GuideModeError {
+    /// A non-success response to `action`, reading its body for the message
+    pub async fn from_response(action: &str, response: reqwest::Response) -> Self {
+        let status = response.status();
+        let body = response
+            .text()
+            .await
+            .unwrap_or_else(|_| "Unknown error".to_string());
+        GuideModeError::Status {
+            action: action.to_string(),
+            status,
+            body,
+        }
+    }
+
+    /// Stable identifier of the kind of error, for the frontend
+    pub fn code(&self) -> &'static str {
+        match self {
+            GuideModeError::Database(_) => "database",
+            GuideModeError::Config(_) => "config",
+            GuideModeError::Upload(_) => "upload",
+            GuideModeError::Auth(_) => "auth",
+            GuideModeError::Validation(_) => "validation",
+            GuideModeError::Io(_) => "io",
+            GuideModeError::Json(_) => "json",
+            GuideModeError::Network(_) => "network",
+            GuideModeError::Status { status, .. } => match status.as_u16() {
+                401 | 403 => "unauthorized",
+                404 => "not_found",
+                409 => "conflict",
+                429 => "rate_limited",
+                _ => "http_status",
+            },
+            GuideModeError::ChecksumMismatch { .. } => "checksum_mismatch",
+            GuideModeError::NotFound(_) => "not_found",
+            GuideModeError::Provider { .. } => "provider",
+            GuideModeError::AccessDenied { .. } => "access_denied",
+            GuideModeError::OutsideRoot { .. } => "outside_root",
+            GuideModeError::Cancelled(_) => "cancelled",
+            GuideModeError::LockPoisoned(_) => "lock_poisoned",
+            GuideModeError::Other(_) => "other",
+        }
+    }
+
+    /// HTTP status the server answered with, if that is what failed
+    pub fn status(&self) -> Option<reqwest::StatusCode> {
+        match self {
+            GuideModeError::Status { status, .. } => Some(*status),
+            GuideModeError::Network(e) => e.status(),
+            _ => None,
+        }
+    }
+
+    /// Whether trying the same thing again later may succeed
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            GuideModeError::Network(_) | GuideModeError::ChecksumMismatch { .. } => true,
+            GuideModeError::Status { status, .. } => {
+                status.is_server_error()
+                    || *status == reqwest::StatusCode::REQUEST_TIMEOUT
+                    || *status == reqwest::StatusCode::TOO_MANY_REQUESTS
+            }
+            GuideModeError::Database(rusqlite::Error::SqliteFailure(e, _)) => matches!(
+                e.code,
+                rusqlite::ErrorCode::DatabaseBusy | rusqlite::ErrorCode::DatabaseLocked
+            ),
+            GuideModeError::Io(e) => matches!(
+                e.kind(),
+                std::io::ErrorKind::Interrupted
+                    | std::io::ErrorKind::TimedOut
+                    | std::io::ErrorKind::WouldBlock
+            ),
+            _ => false,
+        }
+    }
+
+    fn with_context(self, msg: &str) -> Self {
+        match self {
+            GuideModeError::Other(s) => GuideModeError::Other(format!("{}: {}", msg, s)),
+            GuideModeError::Config(s) => GuideModeError::Config(format!("{}: {}", msg, s)),
+            GuideModeError::Upload(s) => GuideModeError::Upload(format!("{}: {}", msg, s)),
+            GuideModeError::Auth(s) => GuideModeError::Auth(format!("{}: {}", msg, s)),
+            GuideModeError::Validation(s) => GuideModeError::Validation(format!("{}: {}", msg, s)),
+            GuideModeError::NotFound(s) => GuideModeError::NotFound(format!("{}: {}", msg, s)),
+            GuideModeError::Provider { provider, message } => GuideModeError::Provider {
+                provider,
+                message: format!("{}: {}", msg, message),
+            },
+            GuideModeError::LockPoisoned(s) => {
+                GuideModeError::LockPoisoned(format!("{}: {}", msg, s))
+            }
+            // Wrapped and structured errors keep their source
+            err => err,
+        }
+    }
+}
+
+/// Commands reject with `{ code, message, retryable }`, plus `status` for
+/// HTTP status errors
+impl serde::Serialize for GuideModeError {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let status = self.status();
+        let fields = if status.is_some() { 4 } else { 3 };
+        let mut state = serializer.serialize_struct("GuideModeError", fields)?;
+        state.serialize_field("code", self.code())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.serialize_field("retryable", &self.is_retryable())?;
+        if let Some(status) = status {
+            state.serialize_field("status", &status.as_u16())?;
+        }
+        state.end()
+    }
+}
+
+/// Convert GuideModeError to String for code that still reports errors as text
 impl From<GuideModeError> for String {
     fn from(err: GuideModeError) -> String {
         err.to_string()
     }
 }
 
+/// Errors still reported as text are wrapped as `Other`
+impl From<String> for GuideModeError {
+    fn from(err: String) -> Self {
+        GuideModeError::Other(err)
+    }
+}
+
+impl From<&str> for GuideModeError {
+    fn from(err: &str) -> Self {
+        GuideModeError::Other(err.to_string())
+    }
+}
+
 /// Helper to convert Box<dyn std::error::Error> to GuideModeError
 impl From<Box<dyn std::error::Error>> for GuideModeError {
     fn from(err: Box<dyn std::error::Error>) -> Self {
@@ -59,6 +230,18 @@ impl From<Box<dyn std::error::Error>> for GuideModeError {
     }
 }
 
+impl From<Box<dyn std::error::Error + Send + Sync>> for GuideModeError {
+    fn from(err: Box<dyn std::error::Error + Send + Sync>) -> Self {
+        GuideModeError::Other(err.to_string())
+    }
+}
+
+impl<T> From<std::sync::PoisonError<T>> for GuideModeError {
+    fn from(err: std::sync::PoisonError<T>) -> Self {
+        GuideModeError::LockPoisoned(err.to_string())
+    }
+}
+
 /// Helper trait for adding context to errors
 #[allow(dead_code)]
 pub trait ErrorContext<T> {
@@ -67,23 +250,7 @@ pub trait ErrorContext<T> {
 
 impl<T, E: Into<GuideModeError>> ErrorContext<T> for Result<T, E> {
     fn context(self, msg: &str) -> Result<T, GuideModeError> {
-        self.map_err(|e| {
-            let err: GuideModeError = e.into();
-            match err {
-                GuideModeError::Other(s) => GuideModeError::Other(format!("{}: {}", msg, s)),
-                GuideModeError::Database(e) => GuideModeError::Database(e),
-                GuideModeError::Config(s) => GuideModeError::Config(format!("{}: {}", msg, s)),
-                GuideModeError::Upload(s) => GuideModeError::Upload(format!("{}: {}", msg, s)),
-                GuideModeError::Auth(s) => GuideModeError::Auth(format!("{}: {}", msg, s)),
-                GuideModeError::Validation(s) => GuideModeError::Validation(format!("{}: {}", msg, s)),
-                GuideModeError::Io(e) => GuideModeError::Io(e),
-                GuideModeError::Json(e) => GuideModeError::Json(e),
-                GuideModeError::Http(e) => GuideModeError::Http(e),
-                GuideModeError::LockPoisoned(s) => {
-                    GuideModeError::LockPoisoned(format!("{}: {}", msg, s))
-                }
-            }
-        })
+        self.map_err(|e| e.into().with_context(msg))
     }
 }
 
@@ -116,4 +283,70 @@ mod tests {
         let err_msg = result.unwrap_err().to_string();
         assert!(err_msg.contains("I/O error"));
     }
+
+    #[test]
+    fn test_status_error_display_and_code() {
+        let err = GuideModeError::Status {
+            action: "Upload v2".to_string(),
+            status: reqwest::StatusCode::TOO_MANY_REQUESTS,
+            body: "slow down".to_string(),
+        };
+        assert_eq!(
+            err.to_string(),
+            "Upload v2 failed with status 429 Too Many Requests: slow down"
+        );
+        assert_eq!(err.code(), "rate_limited");
+        assert!(err.is_retryable());
+    }
+
+    #[test]
+    fn test_retryable() {
+        let status = |code: u16| GuideModeError::Status {
+            action: "Upload".to_string(),
+            status: reqwest::StatusCode::from_u16(code).unwrap(),
+            body: String::new(),
+        };
+        assert!(status(503).is_retryable());
+        assert!(!status(400).is_retryable());
+        assert!(GuideModeError::ChecksumMismatch {
+            session_id: "s1".to_string(),
+            received: "aa".to_string(),
+            sent: "bb".to_string(),
+        }
+        .is_retryable());
+        assert!(!GuideModeError::Validation("bad".to_string()).is_retryable());
+        assert!(!GuideModeError::from("plain text").is_retryable());
+    }
+
+    #[test]
+    fn test_serializes_code_and_message() {
+        let err = GuideModeError::NotFound("session s1".to_string());
+        let value = serde_json::to_value(&err).unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "code": "not_found",
+                "message": "Not found: session s1",
+                "retryable": false,
+            })
+        );
+
+        let err = GuideModeError::Status {
+            action: "Hash check".to_string(),
+            status: reqwest::StatusCode::BAD_GATEWAY,
+            body: "Unknown error".to_string(),
+        };
+        let value = serde_json::to_value(&err).unwrap();
+        assert_eq!(value["code"], "http_status");
+        assert_eq!(value["status"], 502);
+        assert_eq!(value["retryable"], true);
+
+        let err = GuideModeError::AccessDenied {
+            provider: "cursor".to_string(),
+            path: "/Users/dev/.cursor".to_string(),
+        };
+        let value = serde_json::to_value(&err).unwrap();
+        assert_eq!(value["code"], "access_denied");
+        assert_eq!(value["retryable"], false);
+    }
 }
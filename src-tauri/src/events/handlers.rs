@@ -2,7 +2,7 @@ use super::{EventBus, SessionEvent, SessionEventPayload};
 use crate::database;
 use crate::logging::{log_error, log_info};
 use crate::shutdown::ShutdownCoordinator;
-use tauri::Emitter;
+use crate::windows::emit_session_event;
 use tokio::sync::broadcast;
 
 /// Handler that writes events to database
@@ -75,6 +75,7 @@ impl DatabaseEventHandler {
                     false, // is_historical - watcher events are for live sessions
                 )
                 .map_err(|e| e.to_string())?;
+                self.publish_saved(&event.provider, session_id)?;
             }
 
             SessionEventPayload::Completed {
@@ -97,16 +98,31 @@ impl DatabaseEventHandler {
                     None,
                 )
                 .map_err(|e| e.to_string())?;
+                self.publish_saved(&event.provider, session_id)?;
             }
 
             SessionEventPayload::Failed { session_id, reason } => {
                 database::mark_session_sync_failed(session_id, reason)
                     .map_err(|e| e.to_string())?;
             }
+
+            // Warnings and live messages are only surfaced to the frontend
+            SessionEventPayload::ContextCeilingWarning { .. }
+            | SessionEventPayload::MessagesAppended { .. }
+            | SessionEventPayload::Saved { .. } => {}
         }
 
         Ok(())
     }
+
+    /// Tell the upload processor the session's row is written, so it can
+    /// queue the session without waiting for a database poll
+    fn publish_saved(&self, provider: &str, session_id: &str) -> Result<(), String> {
+        let payload = SessionEventPayload::Saved {
+            session_id: session_id.to_string(),
+        };
+        self.event_bus.publish(provider, payload).map(|_| ())
+    }
 }
 
 /// Handler that emits events to frontend
@@ -142,11 +158,19 @@ impl FrontendEventHandler {
                                 // Emit different events based on payload type
                                 match &event.payload {
                                     SessionEventPayload::SessionChanged { session_id, .. } => {
-                                        let _ = self.app_handle.emit("session-updated", session_id);
+                                        emit_session_event(&self.app_handle, "session-updated", session_id, session_id);
                                     }
 
                                     SessionEventPayload::Completed { session_id, .. } => {
-                                        let _ = self.app_handle.emit("session-completed", session_id);
+                                        emit_session_event(&self.app_handle, "session-completed", session_id, session_id);
+                                    }
+
+                                    SessionEventPayload::ContextCeilingWarning { session_id, .. } => {
+                                        emit_session_event(&self.app_handle, "context-ceiling-warning", session_id, &event.payload);
+                                    }
+
+                                    SessionEventPayload::MessagesAppended { session_id, .. } => {
+                                        emit_session_event(&self.app_handle, "session-messages-appended", session_id, &event.payload);
                                     }
 
                                     _ => {}
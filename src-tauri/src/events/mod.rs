@@ -1,7 +1,12 @@
 mod bus;
 mod handlers;
+mod progress;
 mod types;
 
-pub use bus::EventBus;
+pub use bus::{EventBus, EventReceiver};
 pub use handlers::{DatabaseEventHandler, FrontendEventHandler};
+pub use progress::{
+    cancel_historical_operation, cancel_operation, CancellationToken, OperationKind, Progress,
+    ProgressPhase,
+};
 pub use types::{SessionEvent, SessionEventPayload};
@@ -0,0 +1,363 @@
+//! Progress of long-running operations.
+//!
+//! Scans, historical syncs, migrations, exports, imports and maintenance
+//! report what they are doing as `ProgressEvent`s. Each run has an operation
+//! ID, picked by the frontend so it can cancel the run before the command
+//! returns, or generated. `cancel_operation` flips the run's
+//! `CancellationToken`; the operation checks it between items and stops
+//! with `GuideModeError::Cancelled`, after reporting a `cancelled` phase.
+//! A provider's historical scan and sync can also be cancelled by provider,
+//! with `cancel_historical_operation`.
+
+use crate::error::GuideModeError;
+use crate::providers::common::scan_cache::ProjectScanTiming;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use tauri::Emitter;
+
+/// Long-running operations that report progress
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OperationKind {
+    Rescan,
+    HistoricalSync,
+    Migration,
+    Export,
+    Import,
+    Archive,
+    Maintenance,
+}
+
+impl OperationKind {
+    /// Event the operation's progress is emitted as
+    ///
+    /// Rescans keep the event the provider settings already listen to.
+    pub fn event_name(self) -> &'static str {
+        match self {
+            OperationKind::Rescan => "rescan-progress",
+            _ => "operation-progress",
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            OperationKind::Rescan => "Scan",
+            OperationKind::HistoricalSync => "Sync",
+            OperationKind::Migration => "Migration",
+            OperationKind::Export => "Export",
+            OperationKind::Import => "Import",
+            OperationKind::Archive => "Archive",
+            OperationKind::Maintenance => "Maintenance",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProgressPhase {
+    Starting,
+    Scanning,
+    Processing,
+    Complete,
+    Cancelled,
+    Failed,
+}
+
+/// A step of a long-running operation, `current` of `total` items done
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProgressEvent {
+    pub operation_id: String,
+    pub kind: OperationKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub provider: Option<String>,
+    pub phase: ProgressPhase,
+    pub current: usize,
+    pub total: usize,
+    pub message: String,
+    /// How long each project took to scan, once a rescan has scanned
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub project_timings: Option<Vec<ProjectScanTiming>>,
+    /// Sessions a rescan found unchanged since the last scan
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unchanged: Option<usize>,
+}
+
+/// Shared flag telling an operation to stop
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+/// Items between `Progress::report_item` events
+const ITEM_REPORT_INTERVAL: usize = 25;
+
+struct RunningOperation {
+    kind: OperationKind,
+    provider: Option<String>,
+    token: CancellationToken,
+}
+
+/// The operations running now, by operation ID
+static RUNNING: OnceLock<Mutex<HashMap<String, RunningOperation>>> = OnceLock::new();
+
+fn running() -> &'static Mutex<HashMap<String, RunningOperation>> {
+    RUNNING.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Cancel the running operations matching `matches`, returning how many
+fn cancel_matching(matches: impl Fn(&str, &RunningOperation) -> bool) -> usize {
+    let Ok(running) = running().lock() else {
+        return 0;
+    };
+    running
+        .iter()
+        .filter(|(id, operation)| matches(id, operation))
+        .inspect(|(_, operation)| operation.token.cancel())
+        .count()
+}
+
+/// Ask a running operation to stop; false if no operation has that ID
+pub fn cancel_operation(operation_id: &str) -> bool {
+    cancel_matching(|id, _| id == operation_id) > 0
+}
+
+/// Ask a provider's running historical scan and sync to stop; false if
+/// neither is running
+pub fn cancel_historical_operation(provider_id: &str) -> bool {
+    cancel_matching(|_, operation| {
+        matches!(
+            operation.kind,
+            OperationKind::Rescan | OperationKind::HistoricalSync
+        ) && operation.provider.as_deref() == Some(provider_id)
+    }) > 0
+}
+
+type ProgressSink = Box<dyn Fn(&ProgressEvent) + Send + Sync>;
+
+/// Reports one run of an operation and holds its cancellation token
+///
+/// The run can be cancelled by ID until the reporter is dropped.
+pub struct Progress {
+    operation_id: String,
+    kind: OperationKind,
+    provider: Option<String>,
+    token: CancellationToken,
+    sink: Option<ProgressSink>,
+}
+
+impl Progress {
+    /// Register a run, emitting its progress to the frontend
+    pub fn start(
+        app: &tauri::AppHandle,
+        kind: OperationKind,
+        operation_id: Option<String>,
+        provider: Option<&str>,
+    ) -> Self {
+        let app = app.clone();
+        Self::with_sink(kind, operation_id, provider, move |event| {
+            let _ = app.emit(event.kind.event_name(), event);
+        })
+    }
+
+    /// Register a run, passing its progress to `sink`
+    pub fn with_sink(
+        kind: OperationKind,
+        operation_id: Option<String>,
+        provider: Option<&str>,
+        sink: impl Fn(&ProgressEvent) + Send + Sync + 'static,
+    ) -> Self {
+        let mut progress = Self::register(kind, operation_id, provider);
+        progress.sink = Some(Box::new(sink));
+        progress
+    }
+
+    /// Register a run that reports nowhere, for background work
+    pub fn silent(kind: OperationKind, operation_id: Option<String>) -> Self {
+        Self::register(kind, operation_id, None)
+    }
+
+    fn register(kind: OperationKind, operation_id: Option<String>, provider: Option<&str>) -> Self {
+        let operation_id = operation_id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+        let provider = provider.map(str::to_string);
+        let token = CancellationToken::default();
+        if let Ok(mut running) = running().lock() {
+            running.insert(
+                operation_id.clone(),
+                RunningOperation {
+                    kind,
+                    provider: provider.clone(),
+                    token: token.clone(),
+                },
+            );
+        }
+        Self {
+            operation_id,
+            kind,
+            provider,
+            token,
+            sink: None,
+        }
+    }
+
+    /// Token to pass to work that checks for cancellation itself
+    pub fn token(&self) -> &CancellationToken {
+        &self.token
+    }
+
+    /// The operation's progress at `phase`, for adding details before `emit`
+    pub fn event(
+        &self,
+        phase: ProgressPhase,
+        current: usize,
+        total: usize,
+        message: impl Into<String>,
+    ) -> ProgressEvent {
+        ProgressEvent {
+            operation_id: self.operation_id.clone(),
+            kind: self.kind,
+            provider: self.provider.clone(),
+            phase,
+            current,
+            total,
+            message: message.into(),
+            project_timings: None,
+            unchanged: None,
+        }
+    }
+
+    pub fn emit(&self, event: &ProgressEvent) {
+        if let Some(sink) = &self.sink {
+            sink(event);
+        }
+    }
+
+    pub fn report(
+        &self,
+        phase: ProgressPhase,
+        current: usize,
+        total: usize,
+        message: impl Into<String>,
+    ) {
+        self.emit(&self.event(phase, current, total, message));
+    }
+
+    /// Report `current` of `total` items processed, every few items and at
+    /// the last so large runs don't flood the frontend
+    pub fn report_item(&self, current: usize, total: usize, message: impl FnOnce() -> String) {
+        if current.is_multiple_of(ITEM_REPORT_INTERVAL) || current == total {
+            self.report(ProgressPhase::Processing, current, total, message());
+        }
+    }
+
+    /// Stop if the run was cancelled, reporting how far it got
+    pub fn check_cancelled(&self, current: usize, total: usize) -> Result<(), GuideModeError> {
+        if !self.token.is_cancelled() {
+            return Ok(());
+        }
+        let label = self.kind.label();
+        self.report(
+            ProgressPhase::Cancelled,
+            current,
+            total,
+            format!("{} cancelled after {} of {}", label, current, total),
+        );
+        Err(GuideModeError::Cancelled(label.to_string()))
+    }
+}
+
+impl Drop for Progress {
+    fn drop(&mut self) {
+        if let Ok(mut running) = running().lock() {
+            running.remove(&self.operation_id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn recording(
+        kind: OperationKind,
+        operation_id: &str,
+    ) -> (Progress, Arc<Mutex<Vec<ProgressEvent>>>) {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sink_events = Arc::clone(&events);
+        let progress = Progress::with_sink(
+            kind,
+            Some(operation_id.to_string()),
+            Some("codex"),
+            move |event| sink_events.lock().unwrap().push(event.clone()),
+        );
+        (progress, events)
+    }
+
+    #[test]
+    fn test_cancel_by_operation_id() {
+        let (progress, events) = recording(OperationKind::Export, "op-cancel");
+        assert!(progress.check_cancelled(0, 3).is_ok());
+
+        assert!(cancel_operation("op-cancel"));
+        let error = progress.check_cancelled(1, 3).unwrap_err();
+        assert_eq!(error.code(), "cancelled");
+        assert_eq!(error.to_string(), "Export was cancelled");
+
+        let events = events.lock().unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].phase, ProgressPhase::Cancelled);
+        assert_eq!(events[0].current, 1);
+    }
+
+    #[test]
+    fn test_finished_operations_cannot_be_cancelled() {
+        let (progress, _) = recording(OperationKind::Import, "op-done");
+        drop(progress);
+        assert!(!cancel_operation("op-done"));
+        assert!(!cancel_operation("op-unknown"));
+    }
+
+    #[test]
+    fn test_cancel_historical_operation_by_provider() {
+        let (scan, _) = recording(OperationKind::Rescan, "op-scan");
+        let (export, _) = recording(OperationKind::Export, "op-export");
+
+        assert!(!cancel_historical_operation("claude-code"));
+        assert!(cancel_historical_operation("codex"));
+        assert!(scan.token().is_cancelled());
+        assert!(!export.token().is_cancelled());
+    }
+
+    #[test]
+    fn test_event_serialization() {
+        let (progress, _) = recording(OperationKind::Rescan, "op-json");
+        let mut event = progress.event(ProgressPhase::Processing, 2, 5, "Processed 2 of 5");
+        event.unchanged = Some(1);
+
+        let value = serde_json::to_value(&event).unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "operationId": "op-json",
+                "kind": "rescan",
+                "provider": "codex",
+                "phase": "processing",
+                "current": 2,
+                "total": 5,
+                "message": "Processed 2 of 5",
+                "unchanged": 1,
+            })
+        );
+        assert_eq!(event.kind.event_name(), "rescan-progress");
+    }
+}
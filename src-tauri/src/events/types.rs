@@ -1,3 +1,4 @@
+use crate::providers::canonical::CanonicalMessage;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
@@ -34,8 +35,28 @@ pub enum SessionEventPayload {
         duration_ms: i64,
     },
 
+    /// Session written to the local database, after it changed or completed
+    Saved { session_id: String },
+
     /// Session processing failed
     Failed { session_id: String, reason: String },
+
+    /// Session repeatedly filled its context window
+    ContextCeilingWarning {
+        session_id: String,
+        ceiling_hits: i64,
+        peak_utilization_percent: f64,
+    },
+
+    /// Messages added to a live session's canonical file since the last batch
+    MessagesAppended {
+        session_id: String,
+        /// Line of the first message in the batch
+        offset: usize,
+        /// Line the next batch starts from
+        next_offset: usize,
+        messages: Vec<CanonicalMessage>,
+    },
 }
 
 impl SessionEvent {
@@ -47,7 +68,10 @@ impl SessionEvent {
         match &self.payload {
             SessionEventPayload::SessionChanged { session_id, .. } => session_id,
             SessionEventPayload::Completed { session_id, .. } => session_id,
+            SessionEventPayload::Saved { session_id } => session_id,
             SessionEventPayload::Failed { session_id, .. } => session_id,
+            SessionEventPayload::ContextCeilingWarning { session_id, .. } => session_id,
+            SessionEventPayload::MessagesAppended { session_id, .. } => session_id,
         }
     }
 
@@ -59,7 +83,10 @@ impl SessionEvent {
         match &self.payload {
             SessionEventPayload::SessionChanged { .. } => "session_changed",
             SessionEventPayload::Completed { .. } => "completed",
+            SessionEventPayload::Saved { .. } => "saved",
             SessionEventPayload::Failed { .. } => "failed",
+            SessionEventPayload::ContextCeilingWarning { .. } => "context_ceiling_warning",
+            SessionEventPayload::MessagesAppended { .. } => "messages_appended",
         }
     }
 }
@@ -1,6 +1,67 @@
-use git2::{Diff, DiffFormat, DiffOptions, Repository};
+use crate::database::{
+    get_full_session_by_id, replace_session_commits, store_uncommitted_snapshot,
+};
+use crate::git_diff_cache::{get_cached_diff, store_cached_diff, DiffCacheKey};
+use crate::logging::log_warn;
+use chrono::Utc;
+use git2::{
+    BlameOptions, BranchType, Delta, Diff, DiffFile, DiffFormat, DiffOptions, Oid, Patch,
+    Repository, RepositoryOpenFlags, Revwalk, Sort, Tree,
+};
 use serde::{Deserialize, Serialize};
-use std::path::Path;
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Most commits walked back from the branch tip when linking a session
+const MAX_COMMITS_SCANNED: usize = 5_000;
+
+/// Lines of context around each change
+const DIFF_CONTEXT_LINES: u32 = 3;
+
+/// Most hunk text kept per file; the rest is replaced by a truncation marker
+pub const MAX_FILE_DIFF_BYTES: usize = 1024 * 1024;
+
+/// Largest file whose full content is loaded for highlighting
+pub const MAX_FILE_CONTENT_BYTES: u64 = 1024 * 1024;
+
+/// Largest patch kept in an uncommitted-change snapshot
+pub const MAX_SNAPSHOT_PATCH_BYTES: usize = 256 * 1024;
+
+/// How long after a session ends its working tree is assumed to still hold
+/// the session's changes
+const SNAPSHOT_WINDOW_MS: i64 = 60 * 60 * 1000;
+
+/// Files per page when listing a session's changed files
+pub const DEFAULT_DIFF_PAGE_SIZE: usize = 200;
+pub const MAX_DIFF_PAGE_SIZE: usize = 1_000;
+
+/// Most line survival results kept in memory before the cache is reset
+const MAX_SURVIVAL_CACHE_ENTRIES: usize = 512;
+
+/// Line survival by repository, blamed commit and session commits
+///
+/// Blame at a fixed commit never changes, so repeated analyses of the same
+/// commits (other windows, re-runs after a failure) reuse the result.
+static SURVIVAL_CACHE: Mutex<BTreeMap<String, LineSurvival>> = Mutex::new(BTreeMap::new());
+
+/// How long a file list that includes the working directory is reused
+const WORKDIR_FILE_LIST_TTL: Duration = Duration::from_secs(30);
+
+/// Most session file lists kept in memory before the cache is reset
+const MAX_CACHED_FILE_LISTS: usize = 32;
+
+/// A session's changed files, shared by every page
+struct CachedFileList {
+    files: Arc<Vec<FileDiffSummary>>,
+    /// `None` for diffs between two commits, which never change
+    expires_at: Option<Instant>,
+}
+
+/// File lists by repository, resolved commits and whether the working
+/// directory is included
+static DIFF_FILE_LISTS: Mutex<BTreeMap<String, CachedFileList>> = Mutex::new(BTreeMap::new());
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FileDiff {
@@ -13,6 +74,29 @@ pub struct FileDiff {
     pub is_binary: bool,
     pub old_content: Option<String>,
     pub new_content: Option<String>,
+    /// Hunks or contents were cut off at the size caps
+    #[serde(default)]
+    pub truncated: bool,
+}
+
+/// A changed file without its hunks or contents
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileDiffSummary {
+    pub old_path: String,
+    pub new_path: String,
+    pub change_type: String,
+    pub language: Option<String>,
+    pub stats: DiffStats,
+    pub is_binary: bool,
+}
+
+/// One page of a session's changed files
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiffFilePage {
+    pub files: Vec<FileDiffSummary>,
+    pub offset: usize,
+    pub total_files: usize,
+    pub has_more: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -21,6 +105,60 @@ pub struct DiffStats {
     pub deletions: u32,
 }
 
+/// Lines added by a session's commits and how many of them are still there
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LineSurvival {
+    pub lines_added: i64,
+    pub lines_surviving: i64,
+}
+
+/// A commit authored during a session
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionCommit {
+    pub commit_hash: String,
+    pub summary: String,
+    pub author_name: String,
+    pub author_email: String,
+    pub authored_at: i64, // Unix milliseconds
+}
+
+/// Working tree changes relative to HEAD, captured when a session that never
+/// committed ends
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UncommittedSnapshot {
+    pub head_commit: String,
+    pub files_changed: i64,
+    pub lines_added: i64,
+    pub lines_removed: i64,
+    /// Unified diff, cut off at [`MAX_SNAPSHOT_PATCH_BYTES`]
+    pub patch: String,
+    pub patch_truncated: bool,
+    pub captured_at: i64, // Unix milliseconds
+}
+
+/// Open the repository a session's working directory belongs to
+///
+/// Sessions may run in a subdirectory, a linked worktree (`.git` is a file
+/// pointing into the main repository's `worktrees/`) or a submodule (`.git`
+/// points into the superproject's `modules/`). Discovery walks up from `cwd`
+/// to the nearest `.git` and follows it, so diffs use that checkout's HEAD,
+/// index and object store. The walk doesn't continue above the home
+/// directory, so a dotfiles repository there isn't picked up for unrelated
+/// projects.
+pub fn open_repository(cwd: &str) -> Result<Repository, String> {
+    let ceiling_dirs: Vec<PathBuf> = dirs::home_dir().into_iter().collect();
+    let repo = Repository::open_ext(cwd, RepositoryOpenFlags::empty(), &ceiling_dirs)
+        .map_err(|e| format!("Failed to open git repository at {}: {}", cwd, e))?;
+
+    if repo.is_bare() {
+        return Err(format!(
+            "Git repository at {} has no working directory",
+            cwd
+        ));
+    }
+    Ok(repo)
+}
+
 /// Get diff between two commits in a repository, with optional timestamp filtering
 ///
 /// This function implements smart diff logic based on session state and timestamps:
@@ -29,7 +167,7 @@ pub struct DiffStats {
 /// - If session is inactive with different hashes: Shows all changes between commits (filtered by time if provided)
 ///
 /// # Arguments
-/// * `cwd` - Working directory path
+/// * `cwd` - Working directory path (anywhere inside the checkout)
 /// * `first_commit_hash` - Starting commit hash
 /// * `latest_commit_hash` - Ending commit hash (can be same as first)
 /// * `is_active` - Whether the session is currently active
@@ -44,14 +182,52 @@ pub fn get_commit_diff(
     session_end_time: Option<i64>,
 ) -> Result<Vec<FileDiff>, String> {
     // Open repository
-    let repo = Repository::open(cwd)
-        .map_err(|e| format!("Failed to open git repository at {}: {}", cwd, e))?;
+    let repo = open_repository(cwd)?;
+    let session_diff = build_session_diff(
+        &repo,
+        first_commit_hash,
+        latest_commit_hash,
+        is_active,
+        session_end_time,
+        None,
+    )?;
+
+    // Parse diff into FileDiff structures
+    parse_diff(
+        &repo,
+        session_diff.diff,
+        Some(&session_diff.old_tree),
+        session_diff.new_tree.as_ref(),
+    )
+}
 
+/// A session's diff with the trees file contents are read from
+struct SessionDiff<'repo> {
+    diff: Diff<'repo>,
+    old_tree: Tree<'repo>,
+    /// `None` when the new side is the working directory
+    new_tree: Option<Tree<'repo>>,
+}
+
+/// Build the diff for a session, optionally limited to a single path
+///
+/// See [`get_commit_diff`] for how the session state selects what is compared.
+fn build_session_diff<'repo>(
+    repo: &'repo Repository,
+    first_commit_hash: &str,
+    latest_commit_hash: &str,
+    is_active: bool,
+    session_end_time: Option<i64>,
+    path: Option<&str>,
+) -> Result<SessionDiff<'repo>, String> {
     // Create diff options
     let mut diff_opts = DiffOptions::new();
-    diff_opts.context_lines(3); // Standard 3 lines of context
+    diff_opts.context_lines(DIFF_CONTEXT_LINES); // Standard 3 lines of context
     diff_opts.include_untracked(true); // Include untracked files
     diff_opts.recurse_untracked_dirs(true); // Recurse into untracked directories
+    if let Some(path) = path {
+        diff_opts.pathspec(path).disable_pathspec_match(true);
+    }
 
     // Get the first commit object
     let first_oid = repo
@@ -75,24 +251,22 @@ pub fn get_commit_diff(
         // For sessions with same hash, we look at what exists in the branch now
         // This could be the working directory (if active) or HEAD (if inactive)
         // Either way, we show the diff from the session's starting point to the current state
-        if is_active {
+        let diff = if is_active {
             // Active: show working directory changes
-            let diff = repo
-                .diff_tree_to_workdir_with_index(Some(&first_tree), Some(&mut diff_opts))
-                .map_err(|e| format!("Failed to create diff to working directory: {}", e))?;
-
-            let result = parse_diff(&repo, diff, Some(&first_tree), None, cwd);
-            return result;
+            repo.diff_tree_to_workdir_with_index(Some(&first_tree), Some(&mut diff_opts))
+                .map_err(|e| format!("Failed to create diff to working directory: {}", e))?
         } else {
             // Inactive: show diff from first_commit to current working directory
             // Even though the session ended, we want to show what changes exist that were made during that time
-            let diff = repo
-                .diff_tree_to_workdir_with_index(Some(&first_tree), Some(&mut diff_opts))
-                .map_err(|e| format!("Failed to create tree to workdir diff: {}", e))?;
+            repo.diff_tree_to_workdir_with_index(Some(&first_tree), Some(&mut diff_opts))
+                .map_err(|e| format!("Failed to create tree to workdir diff: {}", e))?
+        };
 
-            let result = parse_diff(&repo, diff, Some(&first_tree), None, cwd);
-            return result;
-        }
+        return Ok(SessionDiff {
+            diff,
+            old_tree: first_tree,
+            new_tree: None,
+        });
     }
 
     // Different commits - show all changes between them (no timestamp filtering)
@@ -132,18 +306,567 @@ pub fn get_commit_diff(
             .map_err(|e| format!("Failed to merge diffs: {}", e))?;
     }
 
-    // Parse diff into FileDiff structures
-    parse_diff(
-        &repo,
+    Ok(SessionDiff {
         diff,
-        Some(&first_tree),
-        if include_uncommitted {
+        old_tree: first_tree,
+        new_tree: if include_uncommitted {
             None
         } else {
-            Some(&latest_tree)
+            Some(latest_tree)
         },
-        cwd,
-    )
+    })
+}
+
+/// Walk a local branch newest first, falling back to HEAD when the branch
+/// is unknown or no longer exists
+fn branch_revwalk<'repo>(
+    repo: &'repo Repository,
+    branch: Option<&str>,
+) -> Result<Revwalk<'repo>, String> {
+    let mut revwalk = repo
+        .revwalk()
+        .map_err(|e| format!("Failed to walk commits: {}", e))?;
+    revwalk
+        .set_sorting(Sort::TIME)
+        .map_err(|e| format!("Failed to sort commits: {}", e))?;
+
+    let branch_ref = branch
+        .filter(|name| repo.find_branch(name, BranchType::Local).is_ok())
+        .map(|name| format!("refs/heads/{}", name));
+    match branch_ref {
+        Some(reference) => revwalk.push_ref(&reference),
+        None => revwalk.push_head(),
+    }
+    .map_err(|e| format!("Failed to find branch tip: {}", e))?;
+
+    Ok(revwalk)
+}
+
+/// Get a session's diff, serving diffs between two commits from the local cache
+///
+/// Only diffs of an inactive session between two different commits are
+/// cached: they never change. Diffs that include the working directory are
+/// always recomputed. `force_refresh` recomputes and replaces a cached diff.
+///
+/// Arguments are the same as for [`get_commit_diff`].
+pub fn get_commit_diff_cached(
+    cwd: &str,
+    first_commit_hash: &str,
+    latest_commit_hash: &str,
+    is_active: bool,
+    session_start_time: Option<i64>,
+    session_end_time: Option<i64>,
+    force_refresh: bool,
+) -> Result<Vec<FileDiff>, String> {
+    let compute = || {
+        get_commit_diff(
+            cwd,
+            first_commit_hash,
+            latest_commit_hash,
+            is_active,
+            session_start_time,
+            session_end_time,
+        )
+    };
+    if is_active || first_commit_hash == latest_commit_hash {
+        return compute();
+    }
+
+    let repo = open_repository(cwd)?;
+    // Refs like HEAD or a branch name move; key on the commits they point at now
+    let from_commit = resolve_commit_oid(&repo, first_commit_hash)?;
+    let to_commit = resolve_commit_oid(&repo, latest_commit_hash)?;
+    if from_commit == to_commit {
+        return compute();
+    }
+
+    let repo_path = repo.path().to_string_lossy();
+    let filters = format!(
+        "context={};untracked;max_bytes={}",
+        DIFF_CONTEXT_LINES, MAX_FILE_DIFF_BYTES
+    );
+    let key = DiffCacheKey {
+        repo_path: &repo_path,
+        from_commit: &from_commit,
+        to_commit: &to_commit,
+        filters: &filters,
+    };
+
+    if !force_refresh {
+        if let Some(diffs) = get_cached_diff(&key) {
+            return Ok(diffs);
+        }
+    }
+
+    let diffs = compute()?;
+    if let Err(e) = store_cached_diff(&key, &diffs) {
+        log_warn("git", &format!("⚠ {}", e)).unwrap_or_default();
+    }
+    Ok(diffs)
+}
+
+/// Full object ID of the commit a revision (hash, branch or `HEAD`) points at
+fn resolve_commit_oid(repo: &Repository, revision: &str) -> Result<String, String> {
+    repo.revparse_single(revision)
+        .and_then(|object| object.peel_to_commit())
+        .map(|commit| commit.id().to_string())
+        .map_err(|e| format!("Failed to find commit {}: {}", revision, e))
+}
+
+/// List one page of the files changed in a session, without hunks or contents
+///
+/// For sessions touching thousands of files the UI lists files first and
+/// loads each file's hunks with [`get_diff_file`] when it is opened. The
+/// full file list is built once per (repository, resolved commits) and later
+/// pages are served from memory; lists that include the working directory
+/// expire after [`WORKDIR_FILE_LIST_TTL`]. `limit` is clamped to
+/// [`MAX_DIFF_PAGE_SIZE`].
+pub fn list_diff_files(
+    cwd: &str,
+    first_commit_hash: &str,
+    latest_commit_hash: &str,
+    is_active: bool,
+    session_end_time: Option<i64>,
+    offset: usize,
+    limit: usize,
+) -> Result<DiffFilePage, String> {
+    let repo = open_repository(cwd)?;
+    let from_commit = resolve_commit_oid(&repo, first_commit_hash)?;
+    let to_commit = resolve_commit_oid(&repo, latest_commit_hash)?;
+    let includes_workdir = is_active || first_commit_hash == latest_commit_hash;
+    let scope = if includes_workdir {
+        "workdir"
+    } else {
+        "commits"
+    };
+    let key = format!(
+        "{}:{}:{}:{}",
+        repo.path().to_string_lossy(),
+        from_commit,
+        to_commit,
+        scope
+    );
+
+    let files = match cached_file_list(&key) {
+        Some(files) => files,
+        None => {
+            let session_diff = build_session_diff(
+                &repo,
+                first_commit_hash,
+                latest_commit_hash,
+                is_active,
+                session_end_time,
+                None,
+            )?;
+            let files = Arc::new(summarize_diff_files(&repo, &session_diff.diff)?);
+            store_file_list(key, includes_workdir, files.clone());
+            files
+        }
+    };
+
+    let total_files = files.len();
+    let start = offset.min(total_files);
+    let end = offset
+        .saturating_add(limit.clamp(1, MAX_DIFF_PAGE_SIZE))
+        .min(total_files);
+
+    Ok(DiffFilePage {
+        files: files[start..end].to_vec(),
+        offset,
+        total_files,
+        has_more: end < total_files,
+    })
+}
+
+/// A session's file list, unless it is missing or expired
+fn cached_file_list(key: &str) -> Option<Arc<Vec<FileDiffSummary>>> {
+    let cache = DIFF_FILE_LISTS.lock().ok()?;
+    let entry = cache.get(key)?;
+    let fresh = entry
+        .expires_at
+        .is_none_or(|expires_at| Instant::now() < expires_at);
+    fresh.then(|| entry.files.clone())
+}
+
+fn store_file_list(key: String, includes_workdir: bool, files: Arc<Vec<FileDiffSummary>>) {
+    let Ok(mut cache) = DIFF_FILE_LISTS.lock() else {
+        return;
+    };
+    let now = Instant::now();
+    cache.retain(|_, entry| entry.expires_at.is_none_or(|expires_at| now < expires_at));
+    if cache.len() >= MAX_CACHED_FILE_LISTS {
+        cache.clear();
+    }
+    cache.insert(
+        key,
+        CachedFileList {
+            files,
+            expires_at: includes_workdir.then(|| now + WORKDIR_FILE_LIST_TTL),
+        },
+    );
+}
+
+/// Summaries with line stats for every file in a diff
+fn summarize_diff_files(repo: &Repository, diff: &Diff) -> Result<Vec<FileDiffSummary>, String> {
+    let workdir = repo.workdir().unwrap_or(Path::new(""));
+    let mut files = Vec::with_capacity(diff.deltas().len());
+
+    for (index, delta) in diff.deltas().enumerate() {
+        let old_path = diff_file_path(delta.old_file());
+        let new_path = diff_file_path(delta.new_file());
+
+        let (additions, deletions) = if delta.status() == Delta::Untracked {
+            // Untracked files have no patch; every line is an addition
+            let additions = get_file_content_from_workdir(workdir, &new_path)
+                .ok()
+                .flatten()
+                .map(|content| content.lines().count())
+                .unwrap_or(0);
+            (additions, 0)
+        } else {
+            Patch::from_diff(diff, index)
+                .map_err(|e| format!("Failed to diff {}: {}", new_path, e))?
+                .and_then(|patch| patch.line_stats().ok())
+                .map(|(_, additions, deletions)| (additions, deletions))
+                .unwrap_or((0, 0))
+        };
+
+        files.push(FileDiffSummary {
+            old_path: if delta.status() == Delta::Untracked {
+                String::new()
+            } else {
+                old_path
+            },
+            change_type: change_type(delta.status()).to_string(),
+            language: detect_language(&new_path),
+            new_path,
+            stats: DiffStats {
+                additions: additions as u32,
+                deletions: deletions as u32,
+            },
+            is_binary: delta.new_file().is_binary(),
+        });
+    }
+
+    Ok(files)
+}
+
+/// Get the diff of a single file changed in a session
+///
+/// Returns `None` if the file didn't change. Arguments are the same as for
+/// [`list_diff_files`]; `path` is relative to the checkout root.
+pub fn get_diff_file(
+    cwd: &str,
+    first_commit_hash: &str,
+    latest_commit_hash: &str,
+    is_active: bool,
+    session_end_time: Option<i64>,
+    path: &str,
+) -> Result<Option<FileDiff>, String> {
+    let repo = open_repository(cwd)?;
+    let session_diff = build_session_diff(
+        &repo,
+        first_commit_hash,
+        latest_commit_hash,
+        is_active,
+        session_end_time,
+        Some(path),
+    )?;
+
+    let diffs = parse_diff(
+        &repo,
+        session_diff.diff,
+        Some(&session_diff.old_tree),
+        session_diff.new_tree.as_ref(),
+    )?;
+    Ok(diffs
+        .into_iter()
+        .find(|diff| diff.new_path == path || diff.old_path == path))
+}
+
+fn diff_file_path(file: DiffFile) -> String {
+    file.path()
+        .unwrap_or(Path::new(""))
+        .to_string_lossy()
+        .to_string()
+}
+
+/// Find commits authored within a session's time window on its branch
+///
+/// Walks the branch newest first (HEAD when the branch is unknown or no longer
+/// exists) and keeps commits whose author date falls within
+/// `[session_start_time, session_end_time]`. The walk stops at the first
+/// commit committed before the session started.
+///
+/// # Arguments
+/// * `repo` - Repository the session worked in
+/// * `branch` - Local branch the session was on
+/// * `session_start_time` - Session start timestamp (Unix milliseconds)
+/// * `session_end_time` - Session end timestamp (Unix milliseconds)
+pub fn find_session_commits(
+    repo: &Repository,
+    branch: Option<&str>,
+    session_start_time: i64,
+    session_end_time: i64,
+) -> Result<Vec<SessionCommit>, String> {
+    let mut commits = Vec::new();
+    for oid in branch_revwalk(repo, branch)?.take(MAX_COMMITS_SCANNED) {
+        let oid = oid.map_err(|e| format!("Failed to walk commits: {}", e))?;
+        let commit = repo
+            .find_commit(oid)
+            .map_err(|e| format!("Failed to find commit {}: {}", oid, e))?;
+
+        if commit.time().seconds() * 1000 < session_start_time {
+            break;
+        }
+
+        let author = commit.author();
+        let authored_at = author.when().seconds() * 1000;
+        if (session_start_time..=session_end_time).contains(&authored_at) {
+            commits.push(SessionCommit {
+                commit_hash: oid.to_string(),
+                summary: commit.summary().unwrap_or_default().to_string(),
+                author_name: author.name().unwrap_or_default().to_string(),
+                author_email: author.email().unwrap_or_default().to_string(),
+                authored_at,
+            });
+        }
+    }
+
+    Ok(commits)
+}
+
+/// Count how many lines added by a set of commits survive at a point in time
+///
+/// The branch is checked as it was at `as_of_time`: each file the commits
+/// touched is blamed at the newest commit committed by then, and lines still
+/// attributed to one of the commits count as surviving. Lines later rewritten
+/// by one of the same commits still count. Returns `None` when the branch
+/// has no commit that old.
+///
+/// # Arguments
+/// * `repo` - Repository the commits were made in
+/// * `commit_hashes` - Commits whose added lines are tracked
+/// * `branch` - Local branch the commits were made on
+/// * `as_of_time` - Point in time to check (Unix milliseconds)
+pub fn compute_line_survival(
+    repo: &Repository,
+    commit_hashes: &[String],
+    branch: Option<&str>,
+    as_of_time: i64,
+) -> Result<Option<LineSurvival>, String> {
+    let mut as_of = None;
+    for oid in branch_revwalk(repo, branch)?.take(MAX_COMMITS_SCANNED) {
+        let oid = oid.map_err(|e| format!("Failed to walk commits: {}", e))?;
+        let commit = repo
+            .find_commit(oid)
+            .map_err(|e| format!("Failed to find commit {}: {}", oid, e))?;
+        if commit.time().seconds() * 1000 <= as_of_time {
+            as_of = Some(oid);
+            break;
+        }
+    }
+    let Some(as_of) = as_of else {
+        return Ok(None);
+    };
+
+    let mut sorted_hashes = commit_hashes.to_vec();
+    sorted_hashes.sort();
+    let cache_key = format!(
+        "{}:{}:{}",
+        repo.path().to_string_lossy(),
+        as_of,
+        sorted_hashes.join(",")
+    );
+    if let Some(survival) = SURVIVAL_CACHE
+        .lock()
+        .ok()
+        .and_then(|cache| cache.get(&cache_key).copied())
+    {
+        return Ok(Some(survival));
+    }
+
+    let mut survival = LineSurvival::default();
+    let mut session_commits: HashSet<Oid> = HashSet::new();
+    let mut touched_paths: HashSet<String> = HashSet::new();
+
+    for hash in commit_hashes {
+        // Commits rewritten or dropped since they were linked add nothing
+        let Ok(commit) = Oid::from_str(hash).and_then(|oid| repo.find_commit(oid)) else {
+            continue;
+        };
+        let tree = commit
+            .tree()
+            .map_err(|e| format!("Failed to get commit tree: {}", e))?;
+        let parent_tree = match commit.parent(0) {
+            Ok(parent) => Some(
+                parent
+                    .tree()
+                    .map_err(|e| format!("Failed to get parent tree: {}", e))?,
+            ),
+            Err(_) => None,
+        };
+
+        let diff = repo
+            .diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), None)
+            .map_err(|e| format!("Failed to create diff: {}", e))?;
+        let stats = diff
+            .stats()
+            .map_err(|e| format!("Failed to get diff stats: {}", e))?;
+        survival.lines_added += stats.insertions() as i64;
+
+        for delta in diff.deltas() {
+            if delta.status() != git2::Delta::Deleted {
+                if let Some(path) = delta.new_file().path() {
+                    touched_paths.insert(path.to_string_lossy().to_string());
+                }
+            }
+        }
+        session_commits.insert(commit.id());
+    }
+
+    for path in &touched_paths {
+        let mut opts = BlameOptions::new();
+        opts.newest_commit(as_of);
+        // Files deleted by then have no surviving lines
+        let Ok(blame) = repo.blame_file(Path::new(path), Some(&mut opts)) else {
+            continue;
+        };
+        survival.lines_surviving += blame
+            .iter()
+            .filter(|hunk| session_commits.contains(&hunk.final_commit_id()))
+            .map(|hunk| hunk.lines_in_hunk() as i64)
+            .sum::<i64>();
+    }
+    survival.lines_surviving = survival.lines_surviving.min(survival.lines_added);
+
+    if let Ok(mut cache) = SURVIVAL_CACHE.lock() {
+        if cache.len() >= MAX_SURVIVAL_CACHE_ENTRIES {
+            cache.clear();
+        }
+        cache.insert(cache_key, survival);
+    }
+
+    Ok(Some(survival))
+}
+
+/// Link a session to the commits made during it, replacing its stored links
+///
+/// Returns `None` when there's nothing to link: the session has no working
+/// directory or start time, or the directory isn't a git repository. Active
+/// sessions are linked up to now.
+pub fn link_session_commits(session_id: &str) -> Result<Option<Vec<SessionCommit>>, String> {
+    let session = get_full_session_by_id(session_id)
+        .map_err(|e| format!("Failed to get session data: {}", e))?
+        .ok_or_else(|| format!("Session {} not found in database", session_id))?;
+
+    let (Some(cwd), Some(start_time)) = (session.cwd.as_deref(), session.session_start_time) else {
+        return Ok(None);
+    };
+    let Ok(repo) = open_repository(cwd) else {
+        return Ok(None);
+    };
+
+    let end_time = session
+        .session_end_time
+        .unwrap_or_else(|| Utc::now().timestamp_millis());
+    let commits = find_session_commits(&repo, session.git_branch.as_deref(), start_time, end_time)?;
+
+    replace_session_commits(session_id, &commits)
+        .map_err(|e| format!("Failed to store session commits: {}", e))?;
+
+    Ok(Some(commits))
+}
+
+/// Diff the working tree against HEAD, like `git diff HEAD` plus untracked files
+pub fn capture_uncommitted_snapshot(repo: &Repository) -> Result<UncommittedSnapshot, String> {
+    let head = repo
+        .head()
+        .and_then(|head| head.peel_to_commit())
+        .map_err(|e| format!("Failed to resolve HEAD: {}", e))?;
+    let head_tree = head
+        .tree()
+        .map_err(|e| format!("Failed to get HEAD tree: {}", e))?;
+
+    let mut diff_opts = DiffOptions::new();
+    diff_opts
+        .context_lines(DIFF_CONTEXT_LINES)
+        .include_untracked(true)
+        .recurse_untracked_dirs(true)
+        .show_untracked_content(true);
+    let diff = repo
+        .diff_tree_to_workdir_with_index(Some(&head_tree), Some(&mut diff_opts))
+        .map_err(|e| format!("Failed to create diff to working directory: {}", e))?;
+    let stats = diff
+        .stats()
+        .map_err(|e| format!("Failed to get diff stats: {}", e))?;
+
+    let mut patch = String::new();
+    let mut patch_truncated = false;
+    let printed = diff.print(DiffFormat::Patch, |_delta, _hunk, line| {
+        let content = String::from_utf8_lossy(line.content());
+        if patch.len() + content.len() + 1 > MAX_SNAPSHOT_PATCH_BYTES {
+            patch_truncated = true;
+            return false;
+        }
+        if matches!(line.origin(), '+' | '-' | ' ') {
+            patch.push(line.origin());
+        }
+        patch.push_str(&content);
+        true
+    });
+    // Stopping at the cap makes print report an error
+    if let Err(e) = printed {
+        if !patch_truncated {
+            return Err(format!("Failed to print diff: {}", e));
+        }
+    }
+
+    Ok(UncommittedSnapshot {
+        head_commit: head.id().to_string(),
+        files_changed: stats.files_changed() as i64,
+        lines_added: stats.insertions() as i64,
+        lines_removed: stats.deletions() as i64,
+        patch,
+        patch_truncated,
+        captured_at: Utc::now().timestamp_millis(),
+    })
+}
+
+/// Snapshot the uncommitted changes of a session that ended without committing
+///
+/// Returns `None` when the session has no repository, ended too long ago for
+/// the working tree to still reflect it, or HEAD has moved since.
+pub fn snapshot_uncommitted_changes(
+    session_id: &str,
+) -> Result<Option<UncommittedSnapshot>, String> {
+    let session = get_full_session_by_id(session_id)
+        .map_err(|e| format!("Failed to get session data: {}", e))?
+        .ok_or_else(|| format!("Session {} not found in database", session_id))?;
+
+    let (Some(cwd), Some(end_time)) = (session.cwd.as_deref(), session.session_end_time) else {
+        return Ok(None);
+    };
+    if Utc::now().timestamp_millis() - end_time > SNAPSHOT_WINDOW_MS {
+        return Ok(None);
+    }
+    let Ok(repo) = open_repository(cwd) else {
+        return Ok(None);
+    };
+
+    let snapshot = capture_uncommitted_snapshot(&repo)?;
+    if session
+        .latest_commit_hash
+        .as_deref()
+        .is_some_and(|hash| hash != snapshot.head_commit)
+    {
+        return Ok(None);
+    }
+
+    store_uncommitted_snapshot(session_id, &snapshot)
+        .map_err(|e| format!("Failed to store uncommitted snapshot: {}", e))?;
+
+    Ok(Some(snapshot))
 }
 
 /// Parse git2 Diff into structured FileDiff objects
@@ -152,13 +875,15 @@ fn parse_diff(
     diff: Diff,
     old_tree: Option<&git2::Tree>,
     new_tree: Option<&git2::Tree>,
-    cwd: &str,
 ) -> Result<Vec<FileDiff>, String> {
+    // Diff paths are relative to the checkout root, which may be above the session's cwd
+    let workdir = repo.workdir().unwrap_or(Path::new(""));
     let mut file_diffs: Vec<FileDiff> = Vec::new();
     let mut current_file: Option<FileDiff> = None;
     let mut current_file_content = String::new();
     let mut current_hunk_header: Option<String> = None;
     let mut file_headers_added = false;
+    let mut omitted_lines: u32 = 0;
 
     // First, handle untracked files separately (they won't appear in print output)
     for delta in diff.deltas() {
@@ -171,7 +896,9 @@ fn parse_diff(
                 .to_string();
 
             // Read the entire file content from working directory
-            let file_content = get_file_content_from_workdir(cwd, &new_path).ok();
+            let loaded = get_file_content_from_workdir(workdir, &new_path);
+            let truncated = matches!(loaded, Ok(None));
+            let file_content = loaded.ok().flatten();
 
             // Create hunks that show the entire file as added
             let mut hunk_content = String::new();
@@ -193,6 +920,8 @@ fn parse_diff(
                     hunk_content.push_str(line);
                 }
                 hunk_content.push('\n');
+            } else if truncated {
+                hunk_content.push_str("\\ File too large to show\n");
             }
 
             file_diffs.push(FileDiff {
@@ -211,6 +940,7 @@ fn parse_diff(
                 is_binary: delta.new_file().is_binary(),
                 old_content: None,
                 new_content: file_content,
+                truncated,
             });
         }
     }
@@ -233,29 +963,20 @@ fn parse_diff(
         // Detect file change
         if current_file.is_none() || current_file.as_ref().unwrap().new_path != new_path {
             // Save previous file if exists
-            if let Some(mut file) = current_file.take() {
-                if !current_file_content.is_empty() {
-                    // Store the entire file diff as a single string
-                    file.hunks.push(current_file_content.clone());
-                    current_file_content.clear();
-                }
-                file_diffs.push(file);
+            if let Some(file) = current_file.take() {
+                // Store the entire file diff as a single string
+                file_diffs.push(finish_file(
+                    file,
+                    &mut current_file_content,
+                    &mut omitted_lines,
+                ));
             }
 
             // Start new file
-            let change_type = match delta.status() {
-                git2::Delta::Added => "added",
-                git2::Delta::Untracked => "added", // Treat untracked files as added
-                git2::Delta::Deleted => "deleted",
-                git2::Delta::Modified => "modified",
-                git2::Delta::Renamed => "renamed",
-                _ => "modified",
-            };
-
             current_file = Some(FileDiff {
                 old_path: old_path.clone(),
                 new_path: new_path.clone(),
-                change_type: change_type.to_string(),
+                change_type: change_type(delta.status()).to_string(),
                 language: detect_language(&new_path),
                 hunks: Vec::new(),
                 stats: DiffStats {
@@ -265,6 +986,7 @@ fn parse_diff(
                 is_binary: delta.new_file().is_binary(),
                 old_content: None,
                 new_content: None,
+                truncated: false,
             });
             current_hunk_header = None;
             file_headers_added = false;
@@ -299,7 +1021,10 @@ fn parse_diff(
             );
 
             // Check if this is a NEW hunk (different header than current)
-            if current_hunk_header.as_ref() != Some(&header) {
+            if current_hunk_header.as_ref() != Some(&header)
+                && current_file_content.len() < MAX_FILE_DIFF_BYTES
+                && omitted_lines == 0
+            {
                 // Start new hunk
                 if !current_file_content.is_empty() && !current_file_content.ends_with('\n') {
                     current_file_content.push('\n');
@@ -315,9 +1040,14 @@ fn parse_diff(
 
         match origin {
             '+' | '-' | ' ' => {
-                current_file_content.push('\n');
-                current_file_content.push(origin);
-                current_file_content.push_str(content.trim_end_matches('\n'));
+                // Past the cap, lines are only counted
+                if current_file_content.len() < MAX_FILE_DIFF_BYTES && omitted_lines == 0 {
+                    current_file_content.push('\n');
+                    current_file_content.push(origin);
+                    current_file_content.push_str(content.trim_end_matches('\n'));
+                } else {
+                    omitted_lines += 1;
+                }
 
                 // Update stats
                 if let Some(ref mut file) = current_file {
@@ -336,11 +1066,12 @@ fn parse_diff(
     .map_err(|e| format!("Failed to print diff: {}", e))?;
 
     // Save last file
-    if let Some(mut file) = current_file.take() {
-        if !current_file_content.is_empty() {
-            file.hunks.push(current_file_content);
-        }
-        file_diffs.push(file);
+    if let Some(file) = current_file.take() {
+        file_diffs.push(finish_file(
+            file,
+            &mut current_file_content,
+            &mut omitted_lines,
+        ));
     }
 
     // Extract file contents for syntax highlighting
@@ -352,34 +1083,67 @@ fn parse_diff(
         // Get old file content
         if !file_diff.old_path.is_empty() && file_diff.change_type != "added" {
             if let Some(tree) = old_tree {
-                file_diff.old_content =
-                    get_file_content_from_tree(repo, tree, &file_diff.old_path).ok();
+                let loaded = get_file_content_from_tree(repo, tree, &file_diff.old_path);
+                file_diff.truncated |= matches!(loaded, Ok(None));
+                file_diff.old_content = loaded.ok().flatten();
             }
         }
 
         // Get new file content
         if !file_diff.new_path.is_empty() && file_diff.change_type != "deleted" {
-            if let Some(tree) = new_tree {
+            let loaded = if let Some(tree) = new_tree {
                 // From tree (committed)
-                file_diff.new_content =
-                    get_file_content_from_tree(repo, tree, &file_diff.new_path).ok();
+                get_file_content_from_tree(repo, tree, &file_diff.new_path)
             } else {
                 // From working directory (uncommitted changes)
-                file_diff.new_content =
-                    get_file_content_from_workdir(cwd, &file_diff.new_path).ok();
-            }
+                get_file_content_from_workdir(workdir, &file_diff.new_path)
+            };
+            file_diff.truncated |= matches!(loaded, Ok(None));
+            file_diff.new_content = loaded.ok().flatten();
         }
     }
 
     Ok(file_diffs)
 }
 
-/// Get file content from a git tree
+/// Attach the hunk text collected for a file, ending it with a marker when
+/// lines were dropped at the size cap
+///
+/// The marker uses the `\` prefix unified diffs reserve for annotations, so
+/// diff viewers skip it instead of treating it as a changed line.
+fn finish_file(mut file: FileDiff, content: &mut String, omitted_lines: &mut u32) -> FileDiff {
+    if *omitted_lines > 0 {
+        content.push_str(&format!(
+            "\n\\ Diff truncated, {} more lines not shown\n",
+            omitted_lines
+        ));
+        file.truncated = true;
+        *omitted_lines = 0;
+    }
+    if !content.is_empty() {
+        file.hunks.push(std::mem::take(content));
+    }
+    file
+}
+
+/// Map a delta status to the change type shown in the UI
+fn change_type(status: Delta) -> &'static str {
+    match status {
+        Delta::Added => "added",
+        Delta::Untracked => "added", // Treat untracked files as added
+        Delta::Deleted => "deleted",
+        Delta::Modified => "modified",
+        Delta::Renamed => "renamed",
+        _ => "modified",
+    }
+}
+
+/// Get file content from a git tree, `None` if it exceeds [`MAX_FILE_CONTENT_BYTES`]
 fn get_file_content_from_tree(
     repo: &Repository,
     tree: &git2::Tree,
     path: &str,
-) -> Result<String, String> {
+) -> Result<Option<String>, String> {
     let entry = tree
         .get_path(Path::new(path))
         .map_err(|e| format!("File not found in tree: {}", e))?;
@@ -391,17 +1155,27 @@ fn get_file_content_from_tree(
     let blob = object
         .as_blob()
         .ok_or_else(|| "Object is not a blob".to_string())?;
+    if blob.size() as u64 > MAX_FILE_CONTENT_BYTES {
+        return Ok(None);
+    }
 
     let content = String::from_utf8(blob.content().to_vec())
         .map_err(|_| "File content is not valid UTF-8".to_string())?;
 
-    Ok(content)
+    Ok(Some(content))
 }
 
-/// Get file content from working directory
-fn get_file_content_from_workdir(cwd: &str, path: &str) -> Result<String, String> {
-    let full_path = Path::new(cwd).join(path);
+/// Get file content from working directory, `None` if it exceeds [`MAX_FILE_CONTENT_BYTES`]
+fn get_file_content_from_workdir(workdir: &Path, path: &str) -> Result<Option<String>, String> {
+    let full_path = workdir.join(path);
+    let metadata = std::fs::metadata(&full_path)
+        .map_err(|e| format!("Failed to read file from working directory: {}", e))?;
+    if metadata.len() > MAX_FILE_CONTENT_BYTES {
+        return Ok(None);
+    }
+
     std::fs::read_to_string(&full_path)
+        .map(Some)
         .map_err(|e| format!("Failed to read file from working directory: {}", e))
 }
 
@@ -445,6 +1219,94 @@ fn detect_language(file_path: &str) -> Option<String> {
 mod tests {
     use super::*;
 
+    use git2::{Signature, Time};
+
+    fn commit_at(repo: &Repository, message: &str, authored: i64, committed: i64) {
+        let author = Signature::new("Dev", "dev@example.com", &Time::new(authored, 0)).unwrap();
+        let committer = Signature::new("Dev", "dev@example.com", &Time::new(committed, 0)).unwrap();
+        let tree_id = repo.index().unwrap().write_tree().unwrap();
+        let tree = repo.find_tree(tree_id).unwrap();
+        let parent = repo.head().ok().and_then(|head| head.peel_to_commit().ok());
+        let parents: Vec<&git2::Commit> = parent.iter().collect();
+        repo.commit(Some("HEAD"), &author, &committer, message, &tree, &parents)
+            .unwrap();
+    }
+
+    fn commit_file(repo: &Repository, path: &str, content: &str, time: i64) -> String {
+        let workdir = repo.workdir().unwrap();
+        std::fs::write(workdir.join(path), content).unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new(path)).unwrap();
+        index.write().unwrap();
+        commit_at(repo, path, time, time);
+        repo.head().unwrap().target().unwrap().to_string()
+    }
+
+    #[test]
+    fn test_resolve_commit_oid_follows_refs() {
+        let temp = tempfile::tempdir().unwrap();
+        let repo = Repository::init(temp.path()).unwrap();
+        let first = commit_file(&repo, "a.txt", "one\n", 1_700_000_000);
+        assert_eq!(resolve_commit_oid(&repo, "HEAD").unwrap(), first);
+        assert_eq!(resolve_commit_oid(&repo, &first[..8]).unwrap(), first);
+
+        let second = commit_file(&repo, "a.txt", "two\n", 1_700_000_100);
+        assert_eq!(resolve_commit_oid(&repo, "HEAD").unwrap(), second);
+    }
+
+    #[test]
+    fn test_compute_line_survival() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+
+        let session_commits = vec![commit_file(&repo, "lib.rs", "a\nb\nc\nd\n", 1_000)];
+        // Two days later one line is rewritten and another removed
+        commit_file(&repo, "lib.rs", "a\nB\nc\n", 1_000 + 2 * 86_400);
+
+        let survival = |as_of: i64| {
+            compute_line_survival(&repo, &session_commits, None, as_of * 1000).unwrap()
+        };
+
+        assert_eq!(
+            survival(1_000 + 86_400),
+            Some(LineSurvival {
+                lines_added: 4,
+                lines_surviving: 4
+            })
+        );
+        assert_eq!(
+            survival(1_000 + 7 * 86_400),
+            Some(LineSurvival {
+                lines_added: 4,
+                lines_surviving: 2
+            })
+        );
+        assert_eq!(survival(500), None);
+    }
+
+    #[test]
+    fn test_find_session_commits() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+
+        commit_at(&repo, "Before session", 1_000, 1_000);
+        commit_at(&repo, "Fix login", 2_000, 2_000);
+        // Rebased: authored during the session, committed after it
+        commit_at(&repo, "Add tests", 2_500, 9_000);
+        commit_at(&repo, "After session", 5_000, 10_000);
+
+        let commits = find_session_commits(&repo, None, 1_500_000, 3_000_000).unwrap();
+
+        let summaries: Vec<&str> = commits.iter().map(|c| c.summary.as_str()).collect();
+        assert_eq!(summaries, vec!["Add tests", "Fix login"]);
+        assert_eq!(commits[1].authored_at, 2_000_000);
+        assert_eq!(commits[1].author_email, "dev@example.com");
+
+        // Unknown branches fall back to HEAD
+        let commits = find_session_commits(&repo, Some("gone"), 1_500_000, 3_000_000).unwrap();
+        assert_eq!(commits.len(), 2);
+    }
+
     #[test]
     fn test_detect_language() {
         assert_eq!(detect_language("main.rs"), Some("rust".to_string()));
@@ -0,0 +1,153 @@
+//! Local cache for commit-to-commit diffs
+//!
+//! A diff between two commits never changes, so it is cached in SQLite by
+//! repository, commit pair and diff options. Diffs that include the working
+//! directory are always recomputed. The cache is bounded by total size and
+//! evicts the least recently viewed diffs first.
+
+use crate::database::with_connection_mut;
+use crate::git_diff::FileDiff;
+use chrono::Utc;
+use rusqlite::{params, Connection, Result};
+
+/// Upper bound for the total size of cached diffs
+const MAX_DIFF_CACHE_BYTES: i64 = 64 * 1024 * 1024;
+
+/// Identifies a cached diff
+pub struct DiffCacheKey<'a> {
+    /// Git directory of the checkout
+    pub repo_path: &'a str,
+    pub from_commit: &'a str,
+    pub to_commit: &'a str,
+    /// Diff options the entry was computed with
+    pub filters: &'a str,
+}
+
+fn load(conn: &Connection, key: &DiffCacheKey, now: i64) -> Result<Option<String>> {
+    let diff_json = conn
+        .query_row(
+            "SELECT diff_json FROM git_diff_cache
+             WHERE repo_path = ? AND from_commit = ? AND to_commit = ? AND filters = ?",
+            params![key.repo_path, key.from_commit, key.to_commit, key.filters],
+            |row| row.get::<_, String>(0),
+        )
+        .ok();
+
+    if diff_json.is_some() {
+        conn.execute(
+            "UPDATE git_diff_cache SET last_accessed_at = ?
+             WHERE repo_path = ? AND from_commit = ? AND to_commit = ? AND filters = ?",
+            params![
+                now,
+                key.repo_path,
+                key.from_commit,
+                key.to_commit,
+                key.filters
+            ],
+        )?;
+    }
+    Ok(diff_json)
+}
+
+fn store(conn: &Connection, key: &DiffCacheKey, diff_json: &str, now: i64) -> Result<()> {
+    conn.execute(
+        "INSERT OR REPLACE INTO git_diff_cache (
+            repo_path, from_commit, to_commit, filters,
+            diff_json, size_bytes, created_at, last_accessed_at
+        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+        params![
+            key.repo_path,
+            key.from_commit,
+            key.to_commit,
+            key.filters,
+            diff_json,
+            diff_json.len() as i64,
+            now,
+            now,
+        ],
+    )?;
+    Ok(())
+}
+
+/// Evict least recently accessed entries until the cache fits `max_bytes`
+///
+/// Returns the number of evicted entries.
+fn evict(conn: &Connection, max_bytes: i64) -> Result<usize> {
+    let mut stmt = conn.prepare(
+        "SELECT rowid, size_bytes FROM git_diff_cache ORDER BY last_accessed_at DESC, rowid DESC",
+    )?;
+    let entries = stmt
+        .query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?)))?
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut total_bytes = 0;
+    let mut evicted = 0;
+    for (rowid, size_bytes) in entries {
+        total_bytes += size_bytes;
+        if total_bytes > max_bytes {
+            conn.execute("DELETE FROM git_diff_cache WHERE rowid = ?", params![rowid])?;
+            evicted += 1;
+        }
+    }
+    Ok(evicted)
+}
+
+/// Get a cached diff, marking it as recently accessed
+pub fn get_cached_diff(key: &DiffCacheKey) -> Option<Vec<FileDiff>> {
+    let now = Utc::now().timestamp_millis();
+    let diff_json = with_connection_mut(|conn| load(conn, key, now)).ok()??;
+    serde_json::from_str(&diff_json).ok()
+}
+
+/// Cache a diff, evicting old entries if the cache grew too large
+pub fn store_cached_diff(key: &DiffCacheKey, diffs: &[FileDiff]) -> Result<(), String> {
+    let diff_json =
+        serde_json::to_string(diffs).map_err(|e| format!("Failed to serialize diff: {}", e))?;
+    let now = Utc::now().timestamp_millis();
+
+    with_connection_mut(|conn| {
+        store(conn, key, &diff_json, now)?;
+        evict(conn, MAX_DIFF_CACHE_BYTES)?;
+        Ok(())
+    })
+    .map_err(|e| format!("Failed to cache diff: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key<'a>(to_commit: &'a str) -> DiffCacheKey<'a> {
+        DiffCacheKey {
+            repo_path: "/repo/.git",
+            from_commit: "a1",
+            to_commit,
+            filters: "context=3",
+        }
+    }
+
+    #[test]
+    fn test_store_and_load() {
+        let conn = crate::database::test_connection();
+        store(&conn, &key("b2"), "[]", 1).unwrap();
+
+        assert_eq!(load(&conn, &key("b2"), 2).unwrap().as_deref(), Some("[]"));
+        assert_eq!(load(&conn, &key("c3"), 2).unwrap(), None);
+    }
+
+    #[test]
+    fn test_evicts_least_recently_accessed() {
+        let conn = crate::database::test_connection();
+        let diff_json = "x".repeat(100);
+        store(&conn, &key("b1"), &diff_json, 1).unwrap();
+        store(&conn, &key("b2"), &diff_json, 2).unwrap();
+        store(&conn, &key("b3"), &diff_json, 3).unwrap();
+        // Viewing the oldest entry keeps it
+        load(&conn, &key("b1"), 4).unwrap();
+
+        assert_eq!(evict(&conn, 250).unwrap(), 1);
+        assert!(load(&conn, &key("b1"), 5).unwrap().is_some());
+        assert!(load(&conn, &key("b2"), 5).unwrap().is_none());
+        assert!(load(&conn, &key("b3"), 5).unwrap().is_some());
+    }
+}
@@ -0,0 +1,173 @@
+//! Ignore list for projects and paths
+//!
+//! Sessions of ignored projects are never captured: scans and watchers skip
+//! them before converting them into the session cache, so they're never saved
+//! to the database and never upload. Sessions saved before their project was
+//! ignored stay in the database but don't upload.
+//!
+//! Patterns follow `.gitignore`: one per entry, `#` starts a comment, `!`
+//! re-includes what an earlier pattern ignored and the last matching pattern
+//! wins. A pattern with a `/` (or starting with `~`) is matched against the
+//! session's working directory and ignores everything beneath it; one
+//! without matches the project name, or any directory of the working
+//! directory. `*` and `**` work as in workspace roots.
+//!
+//! Every session a scan or watcher sees is checked, so the list is cached
+//! until config.json is saved or changes on disk.
+
+use crate::config::{config_file_stamp, load_config};
+use crate::providers::common::canonical_path::SessionPath;
+use crate::workspace_roots::{components, fold_case, matches, matches_component};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+/// The list, and the size and modification time of the config file it was
+/// read from
+type CachedList = (Option<(u64, SystemTime)>, Arc<IgnoreList>);
+
+static CURRENT: Mutex<Option<CachedList>> = Mutex::new(None);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Rule {
+    pattern: String,
+    negated: bool,
+    /// Matched against the whole working directory rather than names
+    anchored: bool,
+}
+
+impl Rule {
+    fn parse(line: &str) -> Option<Self> {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            return None;
+        }
+        let (negated, pattern) = match line.strip_prefix('!') {
+            Some(pattern) => (true, pattern),
+            None => (false, line),
+        };
+        let pattern = pattern.trim_end_matches(['/', '\\']);
+        if pattern.is_empty() {
+            return None;
+        }
+        Some(Self {
+            pattern: pattern.to_string(),
+            negated,
+            anchored: pattern.starts_with('~') || pattern.contains(['/', '\\']),
+        })
+    }
+
+    fn matches(&self, project_name: &str, cwd: Option<&str>) -> bool {
+        if self.anchored {
+            return cwd.is_some_and(|cwd| {
+                matches(&self.pattern, cwd) || matches(&format!("{}/**", self.pattern), cwd)
+            });
+        }
+        matches_component(&self.pattern, project_name)
+            || cwd.is_some_and(|cwd| {
+                let windows = SessionPath::parse(cwd).is_windows();
+                let pattern = if windows {
+                    self.pattern.to_lowercase()
+                } else {
+                    self.pattern.clone()
+                };
+                fold_case(components(cwd), windows)
+                    .iter()
+                    .any(|component| matches_component(&pattern, component))
+            })
+    }
+}
+
+/// The configured ignore patterns, parsed
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IgnoreList {
+    rules: Vec<Rule>,
+}
+
+impl IgnoreList {
+    pub fn new(patterns: &[String]) -> Self {
+        Self {
+            rules: patterns
+                .iter()
+                .filter_map(|line| Rule::parse(line))
+                .collect(),
+        }
+    }
+
+    /// The list in the config, empty when it can't be read
+    fn load() -> Self {
+        load_config()
+            .map(|config| Self::new(&config.ignore_patterns))
+            .unwrap_or_default()
+    }
+
+    /// The list in the config, read again only once the config changed
+    pub fn current() -> Arc<Self> {
+        let stamp = config_file_stamp();
+        let Ok(mut cached) = CURRENT.lock() else {
+            return Arc::new(Self::load());
+        };
+        match cached.as_ref() {
+            Some((cached_stamp, list)) if stamp.is_some() && *cached_stamp == stamp => {
+                Arc::clone(list)
+            }
+            _ => {
+                let list = Arc::new(Self::load());
+                *cached = Some((stamp, Arc::clone(&list)));
+                list
+            }
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Whether sessions of `project_name`, run in `cwd`, are ignored
+    pub fn is_ignored(&self, project_name: &str, cwd: Option<&str>) -> bool {
+        self.rules
+            .iter()
+            .rev()
+            .find(|rule| rule.matches(project_name, cwd))
+            .is_some_and(|rule| !rule.negated)
+    }
+}
+
+/// Read the list from config.json again on next use
+pub fn forget_ignore_list() {
+    if let Ok(mut cached) = CURRENT.lock() {
+        *cached = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn list(patterns: &[&str]) -> IgnoreList {
+        IgnoreList::new(&patterns.iter().map(|p| p.to_string()).collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn test_paths_and_names() {
+        let ignored = list(&["# client work", "", "/work/acme/", "secret-*"]);
+        assert!(ignored.is_ignored("api", Some("/work/acme")));
+        assert!(ignored.is_ignored("api", Some("/work/acme/api/src")));
+        assert!(!ignored.is_ignored("api", Some("/work/acme-oss")));
+
+        // Names match the project, or any directory of the cwd
+        assert!(ignored.is_ignored("secret-api", None));
+        assert!(ignored.is_ignored("web", Some("/src/secret-labs/web")));
+        assert!(!ignored.is_ignored("web", Some("/src/labs/web")));
+        assert!(ignored.is_ignored("web", Some(r"C:\Src\Secret-Labs\web")));
+        assert!(!IgnoreList::default().is_ignored("anything", Some("/")));
+    }
+
+    #[test]
+    fn test_last_match_wins() {
+        let ignored = list(&["/work/**", "!/work/oss", "/work/oss/private"]);
+        assert!(ignored.is_ignored("app", Some("/work/client/app")));
+        assert!(!ignored.is_ignored("lib", Some("/work/oss/lib")));
+        assert!(ignored.is_ignored("private", Some("/work/oss/private")));
+        assert!(!ignored.is_ignored("app", Some("/home/dev/app")));
+    }
+}
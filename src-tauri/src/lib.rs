@@ -3,14 +3,30 @@
 
 #![recursion_limit = "256"]
 
+pub mod api_paths;
 pub mod claude_files;
 pub mod config;
 pub mod database;
+pub mod disk_space;
 pub mod error;
 pub mod events;
+pub mod git_diff;
+pub mod git_diff_cache;
+pub mod ignore_list;
 pub mod logging;
+pub mod metrics;
+pub mod migrations;
+pub mod project_aliases;
+pub mod project_merge;
 pub mod project_metadata;
+pub mod project_sync;
 pub mod providers;
+pub mod redaction;
+pub mod sample_sessions;
+pub mod server_health;
 pub mod shutdown;
+pub mod tenant;
 pub mod upload_queue;
 pub mod validation;
+pub mod windows;
+pub mod workspace_roots;
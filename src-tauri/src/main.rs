@@ -2,171 +2,85 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 #![recursion_limit = "256"]
 
+mod api_paths;
 mod auth_server;
+mod autostart;
+mod canonical_migration;
 mod claude_files;
 mod commands;
 mod config;
 mod context_files;
+mod context_history;
+mod context_lint;
+mod conversion_retry;
 mod database;
+mod deep_link;
+mod disk_space;
 mod error;
 mod events;
 mod file_watcher;
 mod git_diff;
+mod git_diff_cache;
+mod ignore_list;
 mod logging;
+mod metrics;
+mod migrations;
+mod project_aliases;
+mod project_merge;
 mod project_metadata;
+mod project_sync;
 mod providers;
+mod redaction;
+mod recovery;
+#[cfg(debug_assertions)]
+mod sample_sessions;
+mod server_health;
+mod session_archive;
+mod session_assessment;
+mod session_cache;
+mod session_export;
+mod session_import;
+mod session_messages;
+mod session_search;
+mod session_share;
+mod session_summary;
 mod shutdown;
+mod sync_preview;
+mod team_stats;
+mod tenant;
+mod transcript;
+mod tray;
 mod types;
 mod upload_queue;
 mod validation;
+mod windows;
+mod workspace_roots;
 
 use commands::{start_enabled_watchers, AppState};
 use events::{DatabaseEventHandler, EventBus, FrontendEventHandler};
 use file_watcher::start_config_file_watcher;
+use metrics::CoreMetricsHandler;
 use shutdown::ShutdownCoordinator;
 use tauri::Manager;
 
 fn main() {
     tauri::Builder::default()
+        // Must come first so a second launch hands its deep link to this instance
+        .plugin(tauri_plugin_single_instance::init(|app, _argv, _cwd| {
+            windows::focus_main_window(app);
+        }))
+        .plugin(tauri_plugin_deep_link::init())
+        .plugin(tauri_plugin_autostart::init(
+            tauri_plugin_autostart::MacosLauncher::LaunchAgent,
+            Some(vec![autostart::AUTOSTART_ARG]),
+        ))
         .plugin(tauri_plugin_updater::Builder::new().build())
         .plugin(tauri_plugin_process::init())
         .plugin(tauri_plugin_http::init())
         .plugin(tauri_plugin_dialog::init())
         .plugin(
             tauri_plugin_sql::Builder::new()
-                .add_migrations(
-                    "sqlite:guidemode.db",
-                    vec![
-                        tauri_plugin_sql::Migration {
-                            version: 1,
-                            description: "create_agent_sessions",
-                            sql: include_str!("../migrations/001_create_agent_sessions.sql"),
-                            kind: tauri_plugin_sql::MigrationKind::Up,
-                        },
-                        tauri_plugin_sql::Migration {
-                            version: 2,
-                            description: "create_session_metrics",
-                            sql: include_str!("../migrations/002_create_session_metrics.sql"),
-                            kind: tauri_plugin_sql::MigrationKind::Up,
-                        },
-                        tauri_plugin_sql::Migration {
-                            version: 3,
-                            description: "add_cwd_column",
-                            sql: include_str!("../migrations/003_add_cwd_column.sql"),
-                            kind: tauri_plugin_sql::MigrationKind::Up,
-                        },
-                        tauri_plugin_sql::Migration {
-                            version: 4,
-                            description: "add_sync_failed_reason",
-                            sql: include_str!("../migrations/004_add_sync_failed_reason.sql"),
-                            kind: tauri_plugin_sql::MigrationKind::Up,
-                        },
-                        tauri_plugin_sql::Migration {
-                            version: 5,
-                            description: "unique_session_id",
-                            sql: include_str!("../migrations/005_unique_session_id.sql"),
-                            kind: tauri_plugin_sql::MigrationKind::Up,
-                        },
-                        tauri_plugin_sql::Migration {
-                            version: 6,
-                            description: "unique_session_metrics",
-                            sql: include_str!("../migrations/006_unique_session_id.sql"),
-                            kind: tauri_plugin_sql::MigrationKind::Up,
-                        },
-                        tauri_plugin_sql::Migration {
-                            version: 7,
-                            description: "create_projects",
-                            sql: include_str!("../migrations/007_create_projects.sql"),
-                            kind: tauri_plugin_sql::MigrationKind::Up,
-                        },
-                        tauri_plugin_sql::Migration {
-                            version: 8,
-                            description: "add_project_foreign_key",
-                            sql: include_str!("../migrations/008_add_project_foreign_key.sql"),
-                            kind: tauri_plugin_sql::MigrationKind::Up,
-                        },
-                        tauri_plugin_sql::Migration {
-                            version: 9,
-                            description: "create_session_assessments",
-                            sql: include_str!("../migrations/009_create_session_assessments.sql"),
-                            kind: tauri_plugin_sql::MigrationKind::Up,
-                        },
-                        tauri_plugin_sql::Migration {
-                            version: 10,
-                            description: "add_phase_analysis",
-                            sql: include_str!("../migrations/010_add_phase_analysis.sql"),
-                            kind: tauri_plugin_sql::MigrationKind::Up,
-                        },
-                        tauri_plugin_sql::Migration {
-                            version: 11,
-                            description: "add_core_metrics_tracking",
-                            sql: include_str!("../migrations/011_add_core_metrics_tracking.sql"),
-                            kind: tauri_plugin_sql::MigrationKind::Up,
-                        },
-                        tauri_plugin_sql::Migration {
-                            version: 12,
-                            description: "add_category_improvement_tips",
-                            sql: include_str!(
-                                "../migrations/012_add_category_improvement_tips.sql"
-                            ),
-                            kind: tauri_plugin_sql::MigrationKind::Up,
-                        },
-                        tauri_plugin_sql::Migration {
-                            version: 13,
-                            description: "add_file_hash",
-                            sql: include_str!("../migrations/013_add_file_hash.sql"),
-                            kind: tauri_plugin_sql::MigrationKind::Up,
-                        },
-                        tauri_plugin_sql::Migration {
-                            version: 14,
-                            description: "add_git_tracking",
-                            sql: include_str!("../migrations/014_add_git_tracking.sql"),
-                            kind: tauri_plugin_sql::MigrationKind::Up,
-                        },
-                        tauri_plugin_sql::Migration {
-                            version: 15,
-                            description: "add_git_diff_metrics",
-                            sql: include_str!("../migrations/015_add_git_diff_metrics.sql"),
-                            kind: tauri_plugin_sql::MigrationKind::Up,
-                        },
-                        tauri_plugin_sql::Migration {
-                            version: 16,
-                            description: "add_git_diff_improvement_tips",
-                            sql: include_str!(
-                                "../migrations/016_add_git_diff_improvement_tips.sql"
-                            ),
-                            kind: tauri_plugin_sql::MigrationKind::Up,
-                        },
-                        tauri_plugin_sql::Migration {
-                            version: 17,
-                            description: "add_context_management_metrics",
-                            sql: include_str!(
-                                "../migrations/017_add_context_management_metrics.sql"
-                            ),
-                            kind: tauri_plugin_sql::MigrationKind::Up,
-                        },
-                        tauri_plugin_sql::Migration {
-                            version: 18,
-                            description: "update_context_metrics_structure",
-                            sql: include_str!(
-                                "../migrations/018_update_context_metrics_structure.sql"
-                            ),
-                            kind: tauri_plugin_sql::MigrationKind::Up,
-                        },
-                        tauri_plugin_sql::Migration {
-                            version: 19,
-                            description: "remove_per_message_tokens",
-                            sql: include_str!("../migrations/019_remove_per_message_tokens.sql"),
-                            kind: tauri_plugin_sql::MigrationKind::Up,
-                        },
-                        tauri_plugin_sql::Migration {
-                            version: 20,
-                            description: "remove_peak_context_tokens",
-                            sql: include_str!("../migrations/020_remove_peak_context_tokens.sql"),
-                            kind: tauri_plugin_sql::MigrationKind::Up,
-                        },
-                    ],
-                )
+                .add_migrations("sqlite:guidemode.db", migrations::migrations())
                 .build(),
         )
         .plugin(tauri_plugin_shell::init())
@@ -203,22 +117,88 @@ fn main() {
             );
             frontend_handler.start();
 
+            let metrics_handler = CoreMetricsHandler::new(event_bus.clone(), shutdown.clone());
+            metrics_handler.start();
+
+            let live_tail_handler =
+                session_messages::LiveTailHandler::new(event_bus.clone(), shutdown.clone());
+            live_tail_handler.start();
+
+            let summary_handler =
+                session_summary::SummaryHandler::new(event_bus.clone(), shutdown.clone());
+            summary_handler.start();
+
+            let assessment_handler =
+                session_assessment::AssessmentHandler::new(event_bus.clone(), shutdown.clone());
+            assessment_handler.start();
+
             // Initialize application state with event bus
             let app_state = AppState::new(event_bus);
 
             // Set app handle on upload queue for event emission
             app_state.upload_queue.set_app_handle(app.handle().clone());
 
+            // Repair state a crash left behind, before watchers start converting
+            recovery::run();
+
+            // Keep the session cache within its size limit
+            session_cache::prune_in_background();
+
             // Start enabled file watchers
             start_enabled_watchers(&app_state);
 
+            // Warn about agent versions newer than the converters were tested with
+            providers::versions::check_and_publish(app.handle().clone());
+
+            // Convert sessions quarantined by an earlier version again
+            conversion_retry::retry_after_update();
+
+            // Check the connected server's version and capabilities
+            server_health::start_periodic_checks(app.handle().clone());
+
+            // Pause session file writes while the disk is nearly full
+            disk_space::start_periodic_checks(app.handle().clone());
+
             app.manage(app_state);
+            app.manage(shutdown);
+
+            // Open guidemode:// links, including the one that launched the app
+            {
+                use tauri_plugin_deep_link::DeepLinkExt;
+
+                #[cfg(any(windows, target_os = "linux"))]
+                if let Err(e) = app.deep_link().register_all() {
+                    warn!("Failed to register deep link scheme: {}", e);
+                }
+
+                if let Ok(Some(urls)) = app.deep_link().get_current() {
+                    deep_link::handle_launch_urls(urls);
+                }
+
+                let handle = app.handle().clone();
+                app.deep_link().on_open_url(move |event| {
+                    deep_link::handle_urls(&handle, event.urls());
+                });
+            }
 
             // Get reference to main window for config file watcher
             let main_window = app
                 .get_webview_window("main")
                 .ok_or("Main window not found")?;
 
+            // Keep launch at login in step with the config, and stay in the
+            // tray when launched at login with a minimized start
+            if let Err(e) = tray::create(app) {
+                warn!("Failed to create tray icon: {}", e);
+            }
+            let config = config::load_config().unwrap_or_default();
+            if let Err(e) = autostart::sync_registration(app.handle(), config.launch_at_login) {
+                warn!("{}", e);
+            }
+            if autostart::start_hidden(&config, std::env::args()) {
+                let _ = main_window.hide();
+            }
+
             // Start config file watcher with main window for event emission
             match start_config_file_watcher(main_window.as_ref().window()) {
                 Ok(_watcher) => {
@@ -276,21 +256,105 @@ fn main() {
             commands::sync_historical_sessions,
             commands::get_session_sync_progress,
             commands::reset_session_sync_progress,
-            commands::execute_sql,
             commands::get_session_content,
+            commands::get_session_messages,
+            commands::stream_session_messages,
+            commands::search_in_session,
+            commands::load_summarizer_config_command,
+            commands::save_summarizer_config_command,
+            commands::load_assessor_config_command,
+            commands::save_assessor_config_command,
+            commands::assess_session,
+            commands::summarize_session,
+            commands::stop_session_stream,
             commands::clear_all_sessions,
             commands::clear_provider_sessions,
             commands::get_all_projects,
             commands::get_project_by_id,
+            commands::list_project_aliases,
+            commands::set_project_alias,
+            commands::remove_project_alias,
+            commands::merge_projects,
+            commands::import_sessions,
+            commands::cancel_operation,
+            commands::cancel_historical_operation,
+            commands::export_sessions,
+            commands::archive_sessions,
+            commands::restore_session_archive,
+            commands::export_session_transcript,
+            commands::share_session_link,
+            commands::get_session_markdown_excerpt,
             commands::open_folder_in_os,
+            commands::check_provider_access,
+            commands::open_privacy_settings,
+            #[cfg(debug_assertions)]
+            commands::generate_sample_sessions,
             commands::quick_rate_session,
             commands::get_session_rating,
+            commands::clear_session_sync_failure,
+            commands::store_processor_metrics,
+            commands::store_ai_results,
+            commands::fail_ai_processing,
             commands::get_session_git_diff,
+            commands::get_session_git_diff_files,
+            commands::get_session_git_diff_file,
+            commands::get_session_commits,
             commands::scan_context_files,
+            commands::get_context_token_report,
+            commands::lint_context_files,
+            commands::get_context_file_history,
+            commands::diff_context_file_snapshots,
             commands::scan_claude_files,
+            commands::scan_agent_setup,
             commands::log_updater_event_command,
-            commands::migrate_to_canonical_command
+            commands::migrate_to_canonical_command,
+            commands::verify_canonical_migration,
+            commands::regenerate_canonical_cache,
+            commands::validate_canonical_file,
+            commands::get_tool_usage_stats,
+            commands::get_context_growth,
+            commands::get_usage_rollup,
+            commands::export_metrics,
+            commands::get_habit_streaks,
+            commands::get_habit_days,
+            commands::get_line_survival,
+            commands::check_server_health,
+            commands::open_session_window,
+            commands::take_deep_link_route,
+            commands::get_startup_settings,
+            commands::set_startup_settings,
+            commands::get_disk_usage,
+            commands::get_cache_settings,
+            commands::set_cache_settings,
+            commands::prune_session_cache,
+            commands::detect_installed_agents,
+            commands::get_provider_versions,
+            commands::list_conversion_errors,
+            commands::get_provider_ingest_stats,
+            commands::retry_conversion_errors,
+            commands::preview_sync,
+            commands::inspect_upload_payload,
+            commands::set_queue_item_priority,
+            commands::set_queue_item_project,
+            commands::set_queue_item_cwd,
+            commands::set_project_sync_excluded,
+            commands::get_improvement_tips_report,
+            commands::get_team_comparison
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app_handle, event| {
+            if let tauri::RunEvent::Exit = event {
+                // Stop event handlers, then watchers and the upload processor,
+                // letting in-flight uploads finish before the process exits
+                if let Some(shutdown) = app_handle.try_state::<ShutdownCoordinator>() {
+                    shutdown.shutdown();
+                }
+                if let Some(app_state) = app_handle.try_state::<AppState>() {
+                    tauri::async_runtime::block_on(app_state.shutdown(
+                        std::time::Duration::from_secs(upload_queue::SHUTDOWN_UPLOAD_TIMEOUT_SECS),
+                    ));
+                }
+            }
+        });
 }
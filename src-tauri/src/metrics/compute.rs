@@ -0,0 +1,500 @@
+//! Core metrics computed from canonical JSONL
+//!
+//! A pass over the session's messages produces the performance, usage, error,
+//! engagement, quality, token and context columns of `session_metrics`.
+//! Scores that need an AI model (input clarity, process quality) are left
+//! empty.
+
+use super::context::{compute_context_usage, ContextUsage};
+use crate::providers::canonical::{CanonicalMessage, ContentBlock, ContentValue, MessageType};
+use crate::providers::common::models::{model_pricing, normalize_model};
+use chrono::{DateTime, Duration, Utc};
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+/// Tools that read files or search the workspace
+const READ_TOOLS: &[&str] = &[
+    "read",
+    "glob",
+    "grep",
+    "ls",
+    "notebookread",
+    "read_file",
+    "read_many_files",
+    "list_directory",
+    "search_file_content",
+    "view",
+];
+
+/// Tools that modify files
+const WRITE_TOOLS: &[&str] = &[
+    "write",
+    "edit",
+    "multiedit",
+    "notebookedit",
+    "write_file",
+    "replace",
+    "apply_patch",
+    "str_replace_editor",
+    "create",
+];
+
+/// Tools that maintain a todo list
+const TODO_TOOLS: &[&str] = &["todowrite", "todo_write", "update_plan"];
+
+const EXIT_PLAN_MODE_TOOL: &str = "exitplanmode";
+
+/// Marker Claude Code inserts when the user interrupts a response
+const INTERRUPTION_MARKER: &str = "[Request interrupted by user";
+
+/// Sycophantic openers counted as over-the-top affirmations
+const AFFIRMATION_PHRASES: &[&str] = &[
+    "you're absolutely right",
+    "you are absolutely right",
+    "great question",
+    "excellent question",
+    "perfect!",
+];
+
+/// Longest error message kept in `last_error_message`
+const MAX_ERROR_MESSAGE_CHARS: usize = 200;
+
+/// Gaps between messages longer than this count as idle unless configured
+pub const DEFAULT_IDLE_THRESHOLD_MINUTES: u64 = 15;
+
+/// Core metrics for one session, mirroring the `session_metrics` columns
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CoreMetrics {
+    // Performance
+    pub response_latency_ms: Option<f64>,
+    pub task_completion_time_ms: Option<f64>,
+    pub performance_total_responses: i64,
+
+    // Usage
+    pub read_write_ratio: Option<f64>,
+    pub read_operations: i64,
+    pub write_operations: i64,
+    pub total_user_messages: i64,
+
+    // Errors
+    pub error_count: i64,
+    pub error_types: Vec<String>,
+    pub last_error_message: Option<String>,
+    pub recovery_attempts: i64,
+    pub fatal_errors: i64,
+
+    // Engagement
+    pub interruption_rate: Option<f64>,
+    pub session_length_minutes: Option<f64>,
+    /// Session length without the gaps longer than the idle threshold
+    pub active_length_minutes: Option<f64>,
+    pub idle_gap_count: i64,
+    pub total_interruptions: i64,
+
+    // Quality
+    pub task_success_rate: Option<f64>,
+    pub iteration_count: i64,
+    pub used_plan_mode: bool,
+    pub used_todo_tracking: bool,
+    pub over_top_affirmations: i64,
+    pub over_top_affirmations_phrases: Vec<String>,
+    pub successful_operations: i64,
+    pub total_operations: i64,
+    pub exit_plan_mode_count: i64,
+    pub todo_write_count: i64,
+
+    // Tokens
+    pub total_input_tokens: i64,
+    pub total_output_tokens: i64,
+    pub total_cache_created: i64,
+    pub total_cache_read: i64,
+    /// List-price estimate; requests on unknown models count as free
+    pub estimated_cost_usd: f64,
+
+    // Context window
+    pub context: ContextUsage,
+}
+
+fn is_tool(name: &str, tools: &[&str]) -> bool {
+    tools.contains(&name.to_lowercase().as_str())
+}
+
+fn parse_timestamp(timestamp: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(timestamp)
+        .ok()
+        .map(|dt| dt.with_timezone(&Utc))
+}
+
+fn blocks(message: &CanonicalMessage) -> &[ContentBlock] {
+    match &message.message.content {
+        ContentValue::Structured(blocks) => blocks,
+        ContentValue::Text(_) => &[],
+    }
+}
+
+fn text_content(message: &CanonicalMessage) -> String {
+    match &message.message.content {
+        ContentValue::Text(text) => text.clone(),
+        ContentValue::Structured(blocks) => blocks
+            .iter()
+            .filter_map(|block| match block {
+                ContentBlock::Text { text } => Some(text.as_str()),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .join("\n"),
+    }
+}
+
+/// A user message typed by a person, as opposed to a tool result
+fn is_prompt(message: &CanonicalMessage) -> bool {
+    message.message_type == MessageType::User
+        && message.is_meta != Some(true)
+        && !blocks(message)
+            .iter()
+            .any(|block| matches!(block, ContentBlock::ToolResult { .. }))
+}
+
+fn percent(part: i64, whole: i64) -> Option<f64> {
+    (whole > 0).then(|| part as f64 / whole as f64 * 100.0)
+}
+
+/// Time covered by sorted timestamps, leaving out gaps longer than `idle_threshold`
+///
+/// Returns the active time and the number of idle gaps left out.
+fn active_time(timestamps: &[DateTime<Utc>], idle_threshold: Duration) -> (Duration, i64) {
+    let mut active = Duration::zero();
+    let mut idle_gaps = 0;
+    for pair in timestamps.windows(2) {
+        let gap = pair[1] - pair[0];
+        if gap > idle_threshold {
+            idle_gaps += 1;
+        } else {
+            active += gap;
+        }
+    }
+    (active, idle_gaps)
+}
+
+/// Parse canonical JSONL content, skipping lines that aren't canonical messages
+pub fn parse_messages(content: &str) -> Vec<CanonicalMessage> {
+    content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect()
+}
+
+/// Compute core metrics from parsed canonical messages (in file order)
+///
+/// Gaps between messages longer than `idle_threshold` are left out of the
+/// active session length.
+pub fn compute_from_messages(
+    messages: &[CanonicalMessage],
+    idle_threshold: Duration,
+) -> CoreMetrics {
+    let mut metrics = CoreMetrics::default();
+
+    let mut tool_names: HashMap<&str, &str> = HashMap::new();
+    let mut error_types: BTreeSet<String> = BTreeSet::new();
+    let mut affirmations: BTreeSet<String> = BTreeSet::new();
+    // Tools whose last call failed and haven't been retried yet
+    let mut awaiting_retry: HashSet<String> = HashSet::new();
+    let mut counted_requests: HashSet<&str> = HashSet::new();
+
+    let mut timestamps: Vec<DateTime<Utc>> = Vec::new();
+    let mut first_prompt_at: Option<DateTime<Utc>> = None;
+    let mut last_response_at: Option<DateTime<Utc>> = None;
+    let mut pending_prompt_at: Option<DateTime<Utc>> = None;
+    let mut latencies: Vec<f64> = Vec::new();
+
+    for message in messages {
+        let timestamp = parse_timestamp(&message.timestamp);
+        timestamps.extend(timestamp);
+
+        match message.message_type {
+            MessageType::User if is_prompt(message) => {
+                metrics.total_user_messages += 1;
+                if text_content(message).contains(INTERRUPTION_MARKER) {
+                    metrics.total_interruptions += 1;
+                } else if let Some(ts) = timestamp {
+                    first_prompt_at.get_or_insert(ts);
+                    pending_prompt_at = Some(ts);
+                }
+            }
+            MessageType::Assistant => {
+                metrics.performance_total_responses += 1;
+                if let Some(ts) = timestamp {
+                    last_response_at = Some(ts);
+                    if let Some(prompt_at) = pending_prompt_at.take() {
+                        latencies.push((ts - prompt_at).num_milliseconds().max(0) as f64);
+                    }
+                }
+
+                let text = text_content(message).to_lowercase();
+                for phrase in AFFIRMATION_PHRASES {
+                    if text.contains(phrase) {
+                        metrics.over_top_affirmations += 1;
+                        affirmations.insert(phrase.to_string());
+                    }
+                }
+
+                // Providers repeat usage on every message split from one response
+                let first_of_request = message
+                    .request_id
+                    .as_deref()
+                    .is_none_or(|request_id| counted_requests.insert(request_id));
+                if let (Some(usage), true) = (&message.message.usage, first_of_request) {
+                    let input = usage.input_tokens.unwrap_or(0);
+                    let output = usage.output_tokens.unwrap_or(0);
+                    let cache_created = usage.cache_creation_input_tokens.unwrap_or(0);
+                    let cache_read = usage.cache_read_input_tokens.unwrap_or(0);
+
+                    metrics.total_input_tokens += i64::from(input);
+                    metrics.total_output_tokens += i64::from(output);
+                    metrics.total_cache_created += i64::from(cache_created);
+                    metrics.total_cache_read += i64::from(cache_read);
+
+                    let pricing = message
+                        .message
+                        .model
+                        .as_deref()
+                        .and_then(|model| model_pricing(&normalize_model(model)));
+                    if let Some(pricing) = pricing {
+                        metrics.estimated_cost_usd += pricing.cost(
+                            input.into(),
+                            output.into(),
+                            cache_created.into(),
+                            cache_read.into(),
+                        );
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        for block in blocks(message) {
+            match block {
+                ContentBlock::ToolUse { id, name, .. } => {
+                    tool_names.insert(id.as_str(), name.as_str());
+
+                    if is_tool(name, READ_TOOLS) {
+                        metrics.read_operations += 1;
+                    } else if is_tool(name, WRITE_TOOLS) {
+                        metrics.write_operations += 1;
+                    }
+                    if is_tool(name, TODO_TOOLS) {
+                        metrics.todo_write_count += 1;
+                    }
+                    if name.to_lowercase() == EXIT_PLAN_MODE_TOOL {
+                        metrics.exit_plan_mode_count += 1;
+                    }
+                    if awaiting_retry.remove(name.as_str()) {
+                        metrics.recovery_attempts += 1;
+                    }
+                }
+                ContentBlock::ToolResult {
+                    tool_use_id,
+                    content,
+                    is_error,
+                } => {
+                    metrics.total_operations += 1;
+                    if *is_error == Some(true) {
+                        metrics.error_count += 1;
+                        let tool = tool_names
+                            .get(tool_use_id.as_str())
+                            .copied()
+                            .unwrap_or("unknown");
+                        error_types.insert(tool.to_string());
+                        awaiting_retry.insert(tool.to_string());
+                        let first_line = content.lines().next().unwrap_or_default();
+                        metrics.last_error_message =
+                            Some(first_line.chars().take(MAX_ERROR_MESSAGE_CHARS).collect());
+                    } else {
+                        metrics.successful_operations += 1;
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    metrics.error_types = error_types.into_iter().collect();
+    metrics.over_top_affirmations_phrases = affirmations.into_iter().collect();
+    // Failures that were never retried
+    metrics.fatal_errors = awaiting_retry.len() as i64;
+
+    metrics.response_latency_ms =
+        (!latencies.is_empty()).then(|| latencies.iter().sum::<f64>() / latencies.len() as f64);
+    metrics.task_completion_time_ms = match (first_prompt_at, last_response_at) {
+        (Some(start), Some(end)) if end >= start => Some((end - start).num_milliseconds() as f64),
+        _ => None,
+    };
+    timestamps.sort();
+    if let (Some(start), Some(end)) = (timestamps.first(), timestamps.last()) {
+        let (active, idle_gaps) = active_time(&timestamps, idle_threshold);
+        metrics.session_length_minutes = Some((*end - *start).num_milliseconds() as f64 / 60_000.0);
+        metrics.active_length_minutes = Some(active.num_milliseconds() as f64 / 60_000.0);
+        metrics.idle_gap_count = idle_gaps;
+    }
+
+    metrics.read_write_ratio = (metrics.read_operations + metrics.write_operations > 0)
+        .then(|| metrics.read_operations as f64 / metrics.write_operations.max(1) as f64);
+    metrics.interruption_rate = percent(metrics.total_interruptions, metrics.total_user_messages);
+    metrics.task_success_rate = percent(metrics.successful_operations, metrics.total_operations);
+    // Every prompt after the first is another iteration on the task
+    metrics.iteration_count =
+        (metrics.total_user_messages - metrics.total_interruptions - 1).max(0);
+    metrics.used_plan_mode = metrics.exit_plan_mode_count > 0;
+    metrics.used_todo_tracking = metrics.todo_write_count > 0;
+    metrics.context = compute_context_usage(messages);
+
+    metrics
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn line(value: serde_json::Value) -> String {
+        let mut value = value;
+        value["sessionId"] = json!("s1");
+        value["provider"] = json!("claude-code");
+        value.to_string()
+    }
+
+    fn compute(content: &str) -> CoreMetrics {
+        compute_from_messages(
+            &parse_messages(content),
+            Duration::minutes(DEFAULT_IDLE_THRESHOLD_MINUTES as i64),
+        )
+    }
+
+    fn sample_session() -> String {
+        [
+            line(json!({
+                "uuid": "u1", "timestamp": "2025-01-01T10:00:00Z", "type": "user",
+                "message": {"role": "user", "content": "Fix the login bug"}
+            })),
+            line(json!({
+                "uuid": "a1", "timestamp": "2025-01-01T10:00:04Z", "type": "assistant",
+                "requestId": "r1",
+                "message": {"role": "assistant", "content": [
+                    {"type": "text", "text": "You're absolutely right, let me look."},
+                    {"type": "tool_use", "id": "t1", "name": "Read", "input": {}}
+                ], "usage": {"input_tokens": 100, "output_tokens": 20}}
+            })),
+            line(json!({
+                "uuid": "a2", "timestamp": "2025-01-01T10:00:05Z", "type": "assistant",
+                "requestId": "r1",
+                "message": {"role": "assistant", "content": [
+                    {"type": "tool_use", "id": "t2", "name": "Edit", "input": {}}
+                ], "usage": {"input_tokens": 100, "output_tokens": 20}}
+            })),
+            line(json!({
+                "uuid": "r1", "timestamp": "2025-01-01T10:00:06Z", "type": "user",
+                "message": {"role": "user", "content": [
+                    {"type": "tool_result", "tool_use_id": "t1", "content": "file contents"},
+                    {"type": "tool_result", "tool_use_id": "t2", "content": "old_string not found\nmore", "is_error": true}
+                ]}
+            })),
+            line(json!({
+                "uuid": "a3", "timestamp": "2025-01-01T10:00:08Z", "type": "assistant",
+                "message": {"role": "assistant", "content": [
+                    {"type": "tool_use", "id": "t3", "name": "Edit", "input": {}},
+                    {"type": "tool_use", "id": "t4", "name": "TodoWrite", "input": {}}
+                ]}
+            })),
+            line(json!({
+                "uuid": "r2", "timestamp": "2025-01-01T10:00:09Z", "type": "user",
+                "message": {"role": "user", "content": [
+                    {"type": "tool_result", "tool_use_id": "t3", "content": "ok"},
+                    {"type": "tool_result", "tool_use_id": "t4", "content": "ok"}
+                ]}
+            })),
+            line(json!({
+                "uuid": "u2", "timestamp": "2025-01-01T10:01:00Z", "type": "user",
+                "message": {"role": "user", "content": "[Request interrupted by user]"}
+            })),
+            line(json!({
+                "uuid": "u3", "timestamp": "2025-01-01T10:02:00Z", "type": "user",
+                "message": {"role": "user", "content": "Now add a test"}
+            })),
+            line(json!({
+                "uuid": "a4", "timestamp": "2025-01-01T10:02:10Z", "type": "assistant",
+                "message": {"role": "assistant", "content": "Done."}
+            })),
+        ]
+        .join("\n")
+    }
+
+    #[test]
+    fn test_usage_and_engagement() {
+        let metrics = compute(&sample_session());
+
+        assert_eq!(metrics.total_user_messages, 3);
+        assert_eq!(metrics.total_interruptions, 1);
+        assert_eq!(metrics.iteration_count, 1);
+        assert_eq!(metrics.read_operations, 1);
+        assert_eq!(metrics.write_operations, 2);
+        assert_eq!(metrics.read_write_ratio, Some(0.5));
+        assert_eq!(metrics.performance_total_responses, 4);
+        assert_eq!(metrics.session_length_minutes, Some(130.0 / 60.0));
+        // 4s for the first prompt, 10s for the last
+        assert_eq!(metrics.response_latency_ms, Some(7000.0));
+        assert_eq!(metrics.task_completion_time_ms, Some(130_000.0));
+    }
+
+    #[test]
+    fn test_errors_and_quality() {
+        let metrics = compute(&sample_session());
+
+        assert_eq!(metrics.total_operations, 4);
+        assert_eq!(metrics.successful_operations, 3);
+        assert_eq!(metrics.task_success_rate, Some(75.0));
+        assert_eq!(metrics.error_count, 1);
+        assert_eq!(metrics.error_types, vec!["Edit".to_string()]);
+        assert_eq!(
+            metrics.last_error_message.as_deref(),
+            Some("old_string not found")
+        );
+        assert_eq!(metrics.recovery_attempts, 1);
+        assert_eq!(metrics.fatal_errors, 0);
+        assert!(metrics.used_todo_tracking);
+        assert!(!metrics.used_plan_mode);
+        assert_eq!(metrics.over_top_affirmations, 1);
+    }
+
+    #[test]
+    fn test_tokens_counted_once_per_request() {
+        let metrics = compute(&sample_session());
+
+        assert_eq!(metrics.total_input_tokens, 100);
+        assert_eq!(metrics.total_output_tokens, 20);
+    }
+
+    #[test]
+    fn test_active_length_excludes_idle_gaps() {
+        let metrics =
+            compute_from_messages(&parse_messages(&sample_session()), Duration::seconds(30));
+
+        assert_eq!(metrics.session_length_minutes, Some(130.0 / 60.0));
+        // The 51s and 60s gaps around the interruption are idle
+        assert_eq!(metrics.active_length_minutes, Some(19.0 / 60.0));
+        assert_eq!(metrics.idle_gap_count, 2);
+
+        let metrics = compute(&sample_session());
+        assert_eq!(
+            metrics.active_length_minutes,
+            metrics.session_length_minutes
+        );
+        assert_eq!(metrics.idle_gap_count, 0);
+    }
+
+    #[test]
+    fn test_empty_content() {
+        let metrics = compute("not json\n");
+
+        assert_eq!(metrics, CoreMetrics::default());
+    }
+}
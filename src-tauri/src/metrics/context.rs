@@ -0,0 +1,287 @@
+//! Context window utilization
+//!
+//! Builds a context growth curve from the token usage reported on assistant
+//! messages. Providers with prompt caching (Claude) report the full context of
+//! each request as `input + cache_read + cache_creation`; for the others the
+//! context is approximated by cumulative input and output tokens.
+//!
+//! Compactions are detected from the summary message a provider inserts after
+//! compacting, or from the context shrinking sharply after it was well filled.
+
+use crate::providers::canonical::{CanonicalMessage, ContentBlock, ContentValue, MessageType};
+use crate::providers::common::models::{
+    context_window_tokens, normalize_model, DEFAULT_CONTEXT_WINDOW,
+};
+use serde::Serialize;
+use std::collections::HashSet;
+
+/// Utilization at which a request counts as hitting the context ceiling
+pub const CONTEXT_CEILING_PERCENT: f64 = 90.0;
+
+/// Ceiling hits after which a session is reported as repeatedly hitting the limit
+pub const REPEATED_CEILING_HITS: i64 = 2;
+
+/// Text providers put at the start of a conversation summary after compacting
+const COMPACTION_MARKERS: &[&str] = &[
+    "This session is being continued from a previous conversation",
+    "Conversation compacted",
+];
+
+/// A drop below this fraction of the previous context counts as a compaction...
+const COMPACTION_DROP_RATIO: f64 = 0.5;
+
+/// ...if the previous request used at least this much of the window
+const COMPACTION_MIN_UTILIZATION_PERCENT: f64 = 50.0;
+
+/// Context size of one request
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContextSample {
+    /// 1-based index of the message in the session
+    pub step: usize,
+    pub timestamp: String,
+    pub context_tokens: u64,
+    pub utilization_percent: f64,
+}
+
+/// Context window usage over a session
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContextUsage {
+    /// Growth curve, one sample per model request
+    pub samples: Vec<ContextSample>,
+    /// Context size of the most recent request
+    pub context_length: Option<u64>,
+    pub context_window_size: Option<u64>,
+    /// Utilization of the most recent request
+    pub context_utilization_percent: Option<f64>,
+    pub peak_utilization_percent: Option<f64>,
+    pub avg_utilization_percent: Option<f64>,
+    /// Average input + output tokens per request
+    pub avg_tokens_per_message: Option<f64>,
+    /// Times utilization rose to the ceiling
+    pub ceiling_hits: i64,
+    /// Steps at which the context was compacted
+    pub compact_event_steps: Vec<usize>,
+    pub messages_until_first_compact: Option<usize>,
+}
+
+impl ContextUsage {
+    pub fn compact_event_count(&self) -> i64 {
+        self.compact_event_steps.len() as i64
+    }
+
+    /// Whether the session kept running into the context limit
+    pub fn repeatedly_hits_ceiling(&self) -> bool {
+        self.ceiling_hits >= REPEATED_CEILING_HITS
+    }
+}
+
+fn is_compaction_summary(message: &CanonicalMessage) -> bool {
+    if message.message_type == MessageType::Assistant {
+        return false;
+    }
+    let starts_with_marker = |text: &str| {
+        COMPACTION_MARKERS
+            .iter()
+            .any(|marker| text.trim_start().starts_with(marker))
+    };
+
+    match &message.message.content {
+        ContentValue::Text(text) => starts_with_marker(text),
+        ContentValue::Structured(blocks) => blocks.iter().any(|block| match block {
+            ContentBlock::Text { text } => starts_with_marker(text),
+            _ => false,
+        }),
+    }
+}
+
+/// Compute context usage from canonical messages (in file order)
+pub fn compute_context_usage(messages: &[CanonicalMessage]) -> ContextUsage {
+    let mut usage = ContextUsage::default();
+
+    let mut counted_requests: HashSet<&str> = HashSet::new();
+    let mut cumulative_tokens: u64 = 0;
+    let mut request_tokens: Vec<u64> = Vec::new();
+    let mut window = DEFAULT_CONTEXT_WINDOW;
+    let mut above_ceiling = false;
+    // Set by an explicit summary so the following drop isn't counted again
+    let mut compacted_since_sample = false;
+
+    for (index, message) in messages.iter().enumerate() {
+        let step = index + 1;
+
+        if is_compaction_summary(message) {
+            usage.compact_event_steps.push(step);
+            cumulative_tokens = 0;
+            compacted_since_sample = true;
+            continue;
+        }
+
+        if message.message_type != MessageType::Assistant {
+            continue;
+        }
+        let Some(tokens) = &message.message.usage else {
+            continue;
+        };
+        // Providers repeat usage on every message split from one response
+        if let Some(request_id) = message.request_id.as_deref() {
+            if !counted_requests.insert(request_id) {
+                continue;
+            }
+        }
+
+        if let Some(model) = message.message.model.as_deref() {
+            window = context_window_tokens(&normalize_model(model));
+        }
+
+        let input = u64::from(tokens.input_tokens.unwrap_or(0));
+        let output = u64::from(tokens.output_tokens.unwrap_or(0));
+        let context_tokens = if tokens.cache_read_input_tokens.is_some()
+            || tokens.cache_creation_input_tokens.is_some()
+        {
+            input
+                + u64::from(tokens.cache_read_input_tokens.unwrap_or(0))
+                + u64::from(tokens.cache_creation_input_tokens.unwrap_or(0))
+        } else {
+            cumulative_tokens += input + output;
+            cumulative_tokens
+        };
+        request_tokens.push(input + output);
+
+        let utilization_percent = context_tokens as f64 / window as f64 * 100.0;
+
+        if let Some(previous) = usage.samples.last() {
+            let dropped =
+                (context_tokens as f64) < previous.context_tokens as f64 * COMPACTION_DROP_RATIO;
+            if dropped
+                && previous.utilization_percent >= COMPACTION_MIN_UTILIZATION_PERCENT
+                && !compacted_since_sample
+            {
+                usage.compact_event_steps.push(step);
+            }
+        }
+        compacted_since_sample = false;
+
+        let at_ceiling = utilization_percent >= CONTEXT_CEILING_PERCENT;
+        if at_ceiling && !above_ceiling {
+            usage.ceiling_hits += 1;
+        }
+        above_ceiling = at_ceiling;
+
+        usage.samples.push(ContextSample {
+            step,
+            timestamp: message.timestamp.clone(),
+            context_tokens,
+            utilization_percent,
+        });
+    }
+
+    if let Some(last) = usage.samples.last() {
+        usage.context_length = Some(last.context_tokens);
+        usage.context_window_size = Some(window);
+        usage.context_utilization_percent = Some(last.utilization_percent);
+
+        let utilizations = usage
+            .samples
+            .iter()
+            .map(|sample| sample.utilization_percent);
+        usage.peak_utilization_percent = utilizations.clone().reduce(f64::max);
+        usage.avg_utilization_percent =
+            Some(utilizations.sum::<f64>() / usage.samples.len() as f64);
+        usage.avg_tokens_per_message =
+            Some(request_tokens.iter().sum::<u64>() as f64 / request_tokens.len() as f64);
+    }
+    usage.messages_until_first_compact = usage.compact_event_steps.first().map(|step| step - 1);
+
+    usage
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::compute::parse_messages;
+    use serde_json::json;
+
+    fn assistant(uuid: &str, usage: serde_json::Value) -> String {
+        json!({
+            "uuid": uuid, "timestamp": "2025-01-01T10:00:00Z", "type": "assistant",
+            "sessionId": "s1", "provider": "claude-code",
+            "message": {
+                "role": "assistant", "content": "ok",
+                "model": "claude-sonnet-4-5-20250929", "usage": usage
+            }
+        })
+        .to_string()
+    }
+
+    fn user(uuid: &str, text: &str) -> String {
+        json!({
+            "uuid": uuid, "timestamp": "2025-01-01T10:00:00Z", "type": "user",
+            "sessionId": "s1", "provider": "claude-code",
+            "message": {"role": "user", "content": text}
+        })
+        .to_string()
+    }
+
+    fn cached(input: u64, cache_read: u64) -> serde_json::Value {
+        json!({"input_tokens": input, "output_tokens": 100, "cache_read_input_tokens": cache_read})
+    }
+
+    #[test]
+    fn test_growth_and_ceiling_hits() {
+        let content = [
+            user("u1", "start"),
+            assistant("a1", cached(1_000, 49_000)),
+            assistant("a2", cached(1_000, 184_000)),
+            // Drop without a summary: implicit compaction
+            assistant("a3", cached(1_000, 19_000)),
+            assistant("a4", cached(1_000, 189_000)),
+        ]
+        .join("\n");
+
+        let usage = compute_context_usage(&parse_messages(&content));
+
+        assert_eq!(usage.samples.len(), 4);
+        assert_eq!(usage.samples[0].context_tokens, 50_000);
+        assert_eq!(usage.samples[0].utilization_percent, 25.0);
+        assert_eq!(usage.context_length, Some(190_000));
+        assert_eq!(usage.context_window_size, Some(200_000));
+        assert_eq!(usage.peak_utilization_percent, Some(95.0));
+        assert_eq!(usage.ceiling_hits, 2);
+        assert!(usage.repeatedly_hits_ceiling());
+        assert_eq!(usage.compact_event_steps, vec![4]);
+        assert_eq!(usage.messages_until_first_compact, Some(3));
+    }
+
+    #[test]
+    fn test_summary_message_counts_once() {
+        let content = [
+            assistant("a1", cached(1_000, 150_000)),
+            user("u1", "This session is being continued from a previous conversation that ran out of context."),
+            assistant("a2", cached(1_000, 20_000)),
+        ]
+        .join("\n");
+
+        let usage = compute_context_usage(&parse_messages(&content));
+
+        assert_eq!(usage.compact_event_steps, vec![2]);
+        assert_eq!(usage.compact_event_count(), 1);
+        assert_eq!(usage.ceiling_hits, 0);
+    }
+
+    #[test]
+    fn test_cumulative_tokens_without_cache_fields() {
+        let content = [
+            assistant("a1", json!({"input_tokens": 1_000, "output_tokens": 500})),
+            assistant("a2", json!({"input_tokens": 2_000, "output_tokens": 500})),
+        ]
+        .join("\n");
+
+        let usage = compute_context_usage(&parse_messages(&content));
+
+        let tokens: Vec<u64> = usage.samples.iter().map(|s| s.context_tokens).collect();
+        assert_eq!(tokens, vec![1_500, 4_000]);
+        assert_eq!(usage.avg_tokens_per_message, Some(2_000.0));
+    }
+}
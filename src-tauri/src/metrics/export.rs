@@ -0,0 +1,488 @@
+//! Export session metrics and usage rollups for analysis tools
+//!
+//! Writes one CSV or Parquet file per table into a directory, next to a
+//! `SCHEMA.md` describing every column, so the bundle can be loaded straight
+//! into pandas or DuckDB. Columns keep their SQLite types: integers, doubles
+//! and UTF-8 text, all nullable.
+
+use super::rollup::{RollupGranularity, UsageRange};
+use crate::database::with_connection_mut;
+use crate::logging::log_info;
+use chrono::Utc;
+use parquet::basic::Compression;
+use parquet::data_type::{ByteArray, ByteArrayType, DoubleType, Int64Type};
+use parquet::file::properties::WriterProperties;
+use parquet::file::writer::SerializedFileWriter;
+use parquet::schema::parser::parse_message_type;
+use rusqlite::types::Value;
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MetricsExportFormat {
+    Csv,
+    Parquet,
+}
+
+impl MetricsExportFormat {
+    fn extension(&self) -> &'static str {
+        match self {
+            MetricsExportFormat::Csv => "csv",
+            MetricsExportFormat::Parquet => "parquet",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportedTable {
+    pub file: String,
+    pub row_count: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MetricsExportReport {
+    pub path: String,
+    pub tables: Vec<ExportedTable>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ColumnType {
+    Integer,
+    Real,
+    Text,
+}
+
+impl ColumnType {
+    fn from_declared(declared: Option<&str>) -> Self {
+        match declared.map(|t| t.to_ascii_uppercase()).as_deref() {
+            Some("INTEGER") => ColumnType::Integer,
+            Some("REAL") => ColumnType::Real,
+            _ => ColumnType::Text,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            ColumnType::Integer => "INT64",
+            ColumnType::Real => "DOUBLE",
+            ColumnType::Text => "UTF8",
+        }
+    }
+
+    /// Parquet schema field for a nullable column of this type
+    fn parquet_field(&self, name: &str) -> String {
+        match self {
+            ColumnType::Integer => format!("OPTIONAL INT64 {};", name),
+            ColumnType::Real => format!("OPTIONAL DOUBLE {};", name),
+            ColumnType::Text => format!("OPTIONAL BYTE_ARRAY {} (UTF8);", name),
+        }
+    }
+}
+
+struct Table {
+    name: &'static str,
+    description: &'static str,
+    columns: Vec<(String, ColumnType)>,
+    rows: Vec<Vec<Value>>,
+}
+
+fn query_table(
+    conn: &Connection,
+    name: &'static str,
+    description: &'static str,
+    sql: &str,
+    params: impl rusqlite::Params,
+) -> rusqlite::Result<Table> {
+    let mut stmt = conn.prepare(sql)?;
+    let columns: Vec<(String, ColumnType)> = stmt
+        .columns()
+        .iter()
+        .map(|column| {
+            (
+                column.name().to_string(),
+                ColumnType::from_declared(column.decl_type()),
+            )
+        })
+        .collect();
+    let column_count = columns.len();
+    let rows = stmt
+        .query_map(params, |row| {
+            (0..column_count)
+                .map(|index| row.get::<_, Value>(index))
+                .collect::<rusqlite::Result<Vec<_>>>()
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    Ok(Table {
+        name,
+        description,
+        columns,
+        rows,
+    })
+}
+
+fn query_tables(conn: &Connection, range: Option<UsageRange>) -> rusqlite::Result<Vec<Table>> {
+    let start = range.map(|range| range.start.to_string());
+    let week_start = range.map(|range| {
+        RollupGranularity::Week
+            .period_start(range.start)
+            .to_string()
+    });
+    let end = range.map(|range| range.end.to_string());
+
+    let session_metrics = query_table(
+        conn,
+        "session_metrics",
+        "One row per session with computed core metrics, joined with the session's \
+         project and start time. Metrics of sessions with conversion_skipped_lines \
+         above zero miss the source lines that couldn't be converted.",
+        "SELECT s.project_name, s.session_start_time, s.conversion_skipped_lines, m.*
+         FROM session_metrics m
+         JOIN agent_sessions s ON s.session_id = m.session_id
+         WHERE ?1 IS NULL
+            OR date(COALESCE(s.session_start_time, s.created_at) / 1000, 'unixepoch', 'localtime')
+               BETWEEN ?1 AND ?2
+         ORDER BY s.session_start_time, m.session_id",
+        params![start, end],
+    )?;
+    let usage_rollups = query_table(
+        conn,
+        "usage_rollups",
+        "Sessions, active minutes (without idle gaps), tokens and estimated cost \
+         (USD at list price) per day or week, provider and project. Weeks start on Monday.",
+        "SELECT * FROM usage_rollups
+         WHERE ?1 IS NULL
+            OR (granularity = 'day' AND period_start BETWEEN ?1 AND ?3)
+            OR (granularity = 'week' AND period_start BETWEEN ?2 AND ?3)
+         ORDER BY granularity, period_start, provider, project_name",
+        params![start, week_start, end],
+    )?;
+
+    Ok(vec![session_metrics, usage_rollups])
+}
+
+fn csv_field(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::Integer(i) => i.to_string(),
+        Value::Real(f) => f.to_string(),
+        Value::Text(text) => text.clone(),
+        Value::Blob(bytes) => hex::encode(bytes),
+    }
+}
+
+fn write_csv(table: &Table, path: &Path) -> Result<(), String> {
+    let mut writer =
+        csv::Writer::from_path(path).map_err(|e| format!("Failed to create CSV: {}", e))?;
+    writer
+        .write_record(table.columns.iter().map(|(name, _)| name))
+        .map_err(|e| format!("Failed to write CSV: {}", e))?;
+    for row in &table.rows {
+        writer
+            .write_record(row.iter().map(csv_field))
+            .map_err(|e| format!("Failed to write CSV: {}", e))?;
+    }
+    writer
+        .flush()
+        .map_err(|e| format!("Failed to write CSV: {}", e))
+}
+
+/// Non-null values of a column with their definition levels. SQLite doesn't
+/// enforce declared types, so values are coerced to the column's type.
+fn column_values<T>(
+    table: &Table,
+    index: usize,
+    convert: impl Fn(&Value) -> Option<T>,
+) -> (Vec<T>, Vec<i16>) {
+    let mut values = Vec::new();
+    let mut definition_levels = Vec::with_capacity(table.rows.len());
+    for row in &table.rows {
+        match convert(&row[index]) {
+            Some(value) => {
+                values.push(value);
+                definition_levels.push(1);
+            }
+            None => definition_levels.push(0),
+        }
+    }
+    (values, definition_levels)
+}
+
+fn write_parquet(table: &Table, path: &Path) -> Result<(), parquet::errors::ParquetError> {
+    let fields: String = table
+        .columns
+        .iter()
+        .map(|(name, column_type)| column_type.parquet_field(name) + "\n")
+        .collect();
+    let schema = Arc::new(parse_message_type(&format!(
+        "message {} {{\n{}}}",
+        table.name, fields
+    ))?);
+    let properties = Arc::new(
+        WriterProperties::builder()
+            .set_compression(Compression::SNAPPY)
+            .build(),
+    );
+
+    let file = fs::File::create(path)?;
+    let mut writer = SerializedFileWriter::new(file, schema, properties)?;
+    let mut row_group = writer.next_row_group()?;
+    let mut index = 0;
+    while let Some(mut column) = row_group.next_column()? {
+        match table.columns[index].1 {
+            ColumnType::Integer => {
+                let (values, levels) = column_values(table, index, |value| match value {
+                    Value::Integer(i) => Some(*i),
+                    Value::Real(f) => Some(*f as i64),
+                    _ => None,
+                });
+                column
+                    .typed::<Int64Type>()
+                    .write_batch(&values, Some(&levels), None)?;
+            }
+            ColumnType::Real => {
+                let (values, levels) = column_values(table, index, |value| match value {
+                    Value::Integer(i) => Some(*i as f64),
+                    Value::Real(f) => Some(*f),
+                    _ => None,
+                });
+                column
+                    .typed::<DoubleType>()
+                    .write_batch(&values, Some(&levels), None)?;
+            }
+            ColumnType::Text => {
+                let (values, levels) = column_values(table, index, |value| match value {
+                    Value::Null => None,
+                    value => Some(ByteArray::from(csv_field(value).into_bytes())),
+                });
+                column
+                    .typed::<ByteArrayType>()
+                    .write_batch(&values, Some(&levels), None)?;
+            }
+        }
+        column.close()?;
+        index += 1;
+    }
+    row_group.close()?;
+    writer.close()?;
+    Ok(())
+}
+
+fn schema_document(
+    tables: &[Table],
+    format: MetricsExportFormat,
+    range: Option<UsageRange>,
+) -> String {
+    let mut doc = String::from("# GuideMode metrics export\n\n");
+    let _ = writeln!(doc, "Exported at {}.", Utc::now().to_rfc3339());
+    if let Some(range) = range {
+        let _ = writeln!(
+            doc,
+            "Sessions from {} to {} (local dates).",
+            range.start, range.end
+        );
+    }
+    doc.push_str(
+        "\nTimestamps (`*_time`, `timestamp`, `created_at`) are Unix milliseconds. \
+         `error_types`, `custom_metrics`, `compact_event_steps`, \
+         `over_top_affirmations_phrases` and the `*_tips` columns hold JSON text. \
+         Flags such as `used_plan_mode` are 0 or 1.\n",
+    );
+
+    for table in tables {
+        let _ = write!(
+            doc,
+            "\n## {}.{}\n\n{}\n\n| Column | Type |\n| --- | --- |\n",
+            table.name,
+            format.extension(),
+            table.description
+        );
+        for (name, column_type) in &table.columns {
+            let _ = writeln!(doc, "| {} | {} |", name, column_type.name());
+        }
+    }
+
+    let first = &tables[0];
+    let _ = write!(
+        doc,
+        "\n## Loading\n\n```python\nimport pandas as pd\ndf = pd.read_{reader}(\"{file}\")\n```\n\n\
+         ```sql\nSELECT * FROM '{file}';  -- DuckDB\n```\n",
+        reader = format.extension(),
+        file = format!("{}.{}", first.name, format.extension())
+    );
+
+    doc
+}
+
+/// Export session metrics and usage rollups within `range` (all when None)
+/// into the directory at `path`
+pub fn export_metrics(
+    format: MetricsExportFormat,
+    range: Option<UsageRange>,
+    path: &str,
+) -> Result<MetricsExportReport, String> {
+    let tables = with_connection_mut(|conn| query_tables(conn, range))
+        .map_err(|e| format!("Failed to query metrics: {}", e))?;
+    write_bundle(&tables, format, range, Path::new(path))?;
+
+    log_info(
+        "metrics",
+        &format!("✓ Exported metrics as {} to {}", format.extension(), path),
+    )
+    .unwrap_or_default();
+
+    Ok(MetricsExportReport {
+        path: path.to_string(),
+        tables: tables
+            .iter()
+            .map(|table| ExportedTable {
+                file: format!("{}.{}", table.name, format.extension()),
+                row_count: table.rows.len(),
+            })
+            .collect(),
+    })
+}
+
+fn write_bundle(
+    tables: &[Table],
+    format: MetricsExportFormat,
+    range: Option<UsageRange>,
+    directory: &Path,
+) -> Result<(), String> {
+    fs::create_dir_all(directory)
+        .map_err(|e| format!("Failed to create {}: {}", directory.display(), e))?;
+
+    for table in tables {
+        let file = directory.join(format!("{}.{}", table.name, format.extension()));
+        match format {
+            MetricsExportFormat::Csv => write_csv(table, &file)?,
+            MetricsExportFormat::Parquet => write_parquet(table, &file)
+                .map_err(|e| format!("Failed to write {}: {}", file.display(), e))?,
+        }
+    }
+
+    fs::write(
+        directory.join("SCHEMA.md"),
+        schema_document(tables, format, range),
+    )
+    .map_err(|e| format!("Failed to write schema: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    use parquet::file::reader::{FileReader, SerializedFileReader};
+
+    fn database() -> Connection {
+        let conn = crate::database::test_connection();
+        conn.execute_batch(
+            "-- 2025-01-08 and 2025-02-03 (UTC noon, same local date in any timezone within ±11h)
+             INSERT INTO agent_sessions (
+                id, provider, project_name, session_id, file_name, file_path, file_size,
+                session_start_time, created_at, uploaded_at, conversion_skipped_lines
+             ) VALUES
+                ('s1', 'claude-code', 'app', 's1', 's1', '/s1', 0, 1736337600000, 0, 0, 0),
+                ('s2', 'codex', 'app', 's2', 's2', '/s2', 0, 1738584000000, 0, 0, 2);
+             INSERT INTO session_metrics (
+                id, session_id, provider, timestamp, created_at,
+                response_latency_ms, read_operations, error_types
+             ) VALUES
+                ('m1', 's1', 'claude-code', 0, 0, 120.5, 3, '[\"timeout\"]'),
+                ('m2', 's2', 'codex', 0, 0, NULL, 1, NULL);
+             INSERT INTO usage_rollups (
+                granularity, period_start, provider, project_name, session_count, estimated_cost
+             ) VALUES
+                ('day', '2025-01-08', 'claude-code', 'app', 1, 0.5),
+                ('week', '2025-01-06', 'claude-code', 'app', 1, 0.5),
+                ('day', '2025-02-03', 'codex', 'app', 1, 0.25);",
+        )
+        .unwrap();
+        conn
+    }
+
+    fn january() -> Option<UsageRange> {
+        Some(UsageRange {
+            start: NaiveDate::from_ymd_opt(2025, 1, 8).unwrap(),
+            end: NaiveDate::from_ymd_opt(2025, 1, 31).unwrap(),
+        })
+    }
+
+    #[test]
+    fn test_query_tables_applies_range() {
+        let conn = database();
+
+        let tables = query_tables(&conn, january()).unwrap();
+        assert_eq!(tables[0].rows.len(), 1);
+        assert_eq!(tables[1].rows.len(), 2);
+        assert_eq!(
+            tables[0].columns[..6],
+            [
+                ("project_name".to_string(), ColumnType::Text),
+                ("session_start_time".to_string(), ColumnType::Integer),
+                ("conversion_skipped_lines".to_string(), ColumnType::Integer),
+                ("id".to_string(), ColumnType::Text),
+                ("session_id".to_string(), ColumnType::Text),
+                ("provider".to_string(), ColumnType::Text),
+            ]
+        );
+
+        let tables = query_tables(&conn, None).unwrap();
+        assert_eq!(tables[0].rows.len(), 2);
+        assert_eq!(tables[1].rows.len(), 3);
+    }
+
+    #[test]
+    fn test_write_bundle() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let tables = query_tables(&database(), None).unwrap();
+
+        let csv_dir = temp_dir.path().join("csv");
+        write_bundle(&tables, MetricsExportFormat::Csv, None, &csv_dir).unwrap();
+        let csv = fs::read_to_string(csv_dir.join("session_metrics.csv")).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        let header: Vec<&str> = lines[0].split(',').collect();
+        assert_eq!(
+            header[..4],
+            ["project_name", "session_start_time", "conversion_skipped_lines", "id"]
+        );
+        let field = |line: usize, column: &str| {
+            let index = header.iter().position(|name| *name == column).unwrap();
+            lines[line].split(',').nth(index).unwrap().to_string()
+        };
+        assert_eq!(field(1, "session_start_time"), "1736337600000");
+        assert_eq!(field(1, "provider"), "claude-code");
+        assert_eq!(field(1, "response_latency_ms"), "120.5");
+        assert_eq!(field(1, "read_operations"), "3");
+        assert_eq!(field(1, "error_types"), "\"[\"\"timeout\"\"]\"");
+        assert_eq!(field(2, "conversion_skipped_lines"), "2");
+        assert_eq!(field(2, "response_latency_ms"), "");
+        assert_eq!(field(2, "error_types"), "");
+        assert!(fs::read_to_string(csv_dir.join("SCHEMA.md"))
+            .unwrap()
+            .contains("| response_latency_ms | DOUBLE |"));
+
+        let parquet_dir = temp_dir.path().join("parquet");
+        write_bundle(&tables, MetricsExportFormat::Parquet, None, &parquet_dir).unwrap();
+        let reader = SerializedFileReader::new(
+            fs::File::open(parquet_dir.join("usage_rollups.parquet")).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(reader.metadata().file_metadata().num_rows(), 3);
+        assert_eq!(
+            reader
+                .metadata()
+                .file_metadata()
+                .schema_descr()
+                .num_columns(),
+            11
+        );
+    }
+}
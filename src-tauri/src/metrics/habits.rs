@@ -0,0 +1,176 @@
+//! Streak and habit tracking
+//!
+//! `habit_days` keeps one row per local day with sessions: how many there
+//! were, how many used plan mode and how often the user interrupted. It is
+//! refreshed alongside the usage rollups whenever a session on that day is
+//! ingested or its metrics change. Streaks are derived from it on request.
+
+use chrono::{Days, NaiveDate};
+use serde::Serialize;
+
+/// Interruption rate (interruptions per prompt, %) a day may have to count
+/// as a low-interruption day
+pub const DEFAULT_INTERRUPTION_THRESHOLD_PERCENT: f64 = 10.0;
+
+/// Activity on one local day
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HabitDay {
+    /// Local date, YYYY-MM-DD
+    pub day: String,
+    pub session_count: i64,
+    pub plan_mode_sessions: i64,
+    pub user_messages: i64,
+    pub interruptions: i64,
+}
+
+impl HabitDay {
+    pub fn date(&self) -> Option<NaiveDate> {
+        self.day.parse().ok()
+    }
+
+    pub fn interruption_rate(&self) -> f64 {
+        if self.user_messages > 0 {
+            self.interruptions as f64 / self.user_messages as f64 * 100.0
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Consecutive days meeting a goal
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Streak {
+    /// Run ending today, or yesterday if today has no qualifying day yet
+    pub current: i64,
+    pub longest: i64,
+    pub last_day: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HabitStreaks {
+    /// Days with at least one session
+    pub activity: Streak,
+    /// Days with at least one session in plan mode
+    pub plan_mode: Streak,
+    /// Days with sessions and an interruption rate at or below the threshold
+    pub low_interruption: Streak,
+    pub interruption_threshold_percent: f64,
+}
+
+/// Streak over qualifying days, sorted oldest first
+fn streak(days: &[NaiveDate], today: NaiveDate) -> Streak {
+    let mut longest = 0;
+    let mut run = 0;
+    let mut previous: Option<NaiveDate> = None;
+
+    for &day in days {
+        run = match previous {
+            Some(previous) if previous.checked_add_days(Days::new(1)) == Some(day) => run + 1,
+            _ => 1,
+        };
+        longest = longest.max(run);
+        previous = Some(day);
+    }
+
+    let yesterday = today.checked_sub_days(Days::new(1)).unwrap_or(today);
+    let current = match previous {
+        Some(last) if last >= yesterday => run,
+        _ => 0,
+    };
+
+    Streak {
+        current,
+        longest,
+        last_day: previous.map(|day| day.to_string()),
+    }
+}
+
+/// Compute streaks from habit days sorted oldest first
+pub fn compute_streaks(
+    days: &[HabitDay],
+    today: NaiveDate,
+    interruption_threshold_percent: f64,
+) -> HabitStreaks {
+    let qualifying = |goal: &dyn Fn(&HabitDay) -> bool| -> Vec<NaiveDate> {
+        days.iter()
+            .filter(|day| day.session_count > 0 && goal(day))
+            .filter_map(HabitDay::date)
+            .collect()
+    };
+
+    HabitStreaks {
+        activity: streak(&qualifying(&|_| true), today),
+        plan_mode: streak(&qualifying(&|day| day.plan_mode_sessions > 0), today),
+        low_interruption: streak(
+            &qualifying(&|day| day.interruption_rate() <= interruption_threshold_percent),
+            today,
+        ),
+        interruption_threshold_percent,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(day: &str, plan_mode_sessions: i64, interruptions: i64) -> HabitDay {
+        HabitDay {
+            day: day.to_string(),
+            session_count: 2,
+            plan_mode_sessions,
+            user_messages: 10,
+            interruptions,
+        }
+    }
+
+    fn date(value: &str) -> NaiveDate {
+        value.parse().unwrap()
+    }
+
+    #[test]
+    fn test_compute_streaks() {
+        let days = [
+            day("2025-01-01", 1, 0),
+            day("2025-01-02", 1, 5),
+            day("2025-01-03", 1, 0),
+            // 2025-01-04 has no sessions
+            day("2025-01-05", 0, 1),
+            day("2025-01-06", 1, 0),
+        ];
+
+        let streaks = compute_streaks(&days, date("2025-01-06"), 10.0);
+
+        assert_eq!(streaks.activity.current, 2);
+        assert_eq!(streaks.activity.longest, 3);
+        assert_eq!(streaks.activity.last_day.as_deref(), Some("2025-01-06"));
+        assert_eq!(streaks.plan_mode.current, 1);
+        assert_eq!(streaks.plan_mode.longest, 3);
+        assert_eq!(streaks.low_interruption.current, 2);
+        assert_eq!(streaks.low_interruption.longest, 2);
+    }
+
+    #[test]
+    fn test_current_streak_allows_idle_today() {
+        let days = [day("2025-01-01", 0, 0), day("2025-01-02", 0, 0)];
+
+        assert_eq!(
+            compute_streaks(&days, date("2025-01-03"), 10.0)
+                .activity
+                .current,
+            2
+        );
+        assert_eq!(
+            compute_streaks(&days, date("2025-01-04"), 10.0)
+                .activity
+                .current,
+            0
+        );
+        assert_eq!(
+            compute_streaks(&[], date("2025-01-04"), 10.0).activity,
+            Streak::default()
+        );
+    }
+}
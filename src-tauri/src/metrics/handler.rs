@@ -0,0 +1,126 @@
+use super::compute_session_metrics;
+use crate::events::{EventBus, SessionEventPayload};
+use crate::logging::{log_error, log_info, log_warn};
+use crate::shutdown::ShutdownCoordinator;
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast;
+
+/// Quiet period after the last change before a session's metrics are computed
+const METRICS_DEBOUNCE: Duration = Duration::from_secs(10);
+
+/// How often pending sessions are checked
+const CHECK_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Sessions remembered for context ceiling warnings; the oldest are forgotten first
+const MAX_WARNED_SESSIONS: usize = 1000;
+
+/// Handler that computes core metrics once a session stops changing
+pub struct CoreMetricsHandler {
+    event_bus: EventBus,
+    shutdown: ShutdownCoordinator,
+}
+
+impl CoreMetricsHandler {
+    pub fn new(event_bus: EventBus, shutdown: ShutdownCoordinator) -> Self {
+        Self {
+            event_bus,
+            shutdown,
+        }
+    }
+
+    pub fn start(self) {
+        tauri::async_runtime::spawn(async move {
+            let mut rx = self.event_bus.subscribe();
+            let mut shutdown_rx = self.shutdown.subscribe();
+            let mut interval = tokio::time::interval(CHECK_INTERVAL);
+
+            // Session ID -> (provider, time of its most recent change)
+            let mut pending: HashMap<String, (String, Instant)> = HashMap::new();
+            // Session ID -> ceiling hits already warned about, in first-warned order
+            let mut warned: HashMap<String, i64> = HashMap::new();
+            let mut warned_order: VecDeque<String> = VecDeque::new();
+
+            loop {
+                tokio::select! {
+                    result = rx.recv() => {
+                        match result {
+                            Ok(event) => {
+                                if let SessionEventPayload::SessionChanged { session_id, .. } = &event.payload {
+                                    pending.insert(session_id.clone(), (event.provider.clone(), Instant::now()));
+                                }
+                            }
+                            Err(broadcast::error::RecvError::Closed) => {
+                                log_info("metrics", "Core metrics handler stopped (event bus closed)").unwrap_or_default();
+                                break;
+                            }
+                            Err(broadcast::error::RecvError::Lagged(n)) => {
+                                log_error("metrics", &format!("Core metrics handler lagged {} events", n))
+                                    .unwrap_or_default();
+                            }
+                        }
+                    }
+                    _ = interval.tick() => {
+                        let ready: Vec<String> = pending
+                            .iter()
+                            .filter(|(_, (_, changed_at))| changed_at.elapsed() >= METRICS_DEBOUNCE)
+                            .map(|(session_id, _)| session_id.clone())
+                            .collect();
+
+                        for session_id in ready {
+                            let Some((provider, _)) = pending.remove(&session_id) else {
+                                continue;
+                            };
+                            let result = tokio::task::spawn_blocking({
+                                let session_id = session_id.clone();
+                                move || compute_session_metrics(&session_id)
+                            })
+                            .await;
+
+                            match result {
+                                Ok(Ok(Some(metrics))) => {
+                                    let context = &metrics.context;
+                                    let already_warned = warned.get(&session_id).copied().unwrap_or(0);
+                                    if context.repeatedly_hits_ceiling() && context.ceiling_hits > already_warned {
+                                        if warned.insert(session_id.clone(), context.ceiling_hits).is_none() {
+                                            warned_order.push_back(session_id.clone());
+                                            while warned_order.len() > MAX_WARNED_SESSIONS {
+                                                if let Some(oldest) = warned_order.pop_front() {
+                                                    warned.remove(&oldest);
+                                                }
+                                            }
+                                        }
+                                        log_warn(
+                                            "metrics",
+                                            &format!("Session {} hit the context ceiling {} times", session_id, context.ceiling_hits),
+                                        )
+                                        .unwrap_or_default();
+
+                                        let payload = SessionEventPayload::ContextCeilingWarning {
+                                            session_id: session_id.clone(),
+                                            ceiling_hits: context.ceiling_hits,
+                                            peak_utilization_percent: context.peak_utilization_percent.unwrap_or(0.0),
+                                        };
+                                        if let Err(e) = self.event_bus.publish(&provider, payload) {
+                                            log_error("metrics", &format!("Failed to publish context warning: {}", e))
+                                                .unwrap_or_default();
+                                        }
+                                    }
+                                }
+                                Ok(Err(e)) => {
+                                    log_error("metrics", &format!("Failed to compute core metrics for {}: {}", session_id, e))
+                                        .unwrap_or_default();
+                                }
+                                _ => {}
+                            }
+                        }
+                    }
+                    _ = shutdown_rx.recv() => {
+                        log_info("metrics", "Core metrics handler gracefully shutting down").unwrap_or_default();
+                        break;
+                    }
+                }
+            }
+        });
+    }
+}
@@ -0,0 +1,134 @@
+//! Local core metrics engine
+//!
+//! Computes the core `session_metrics` columns (latency, read/write ratio,
+//! errors, interruptions, tool usage, tokens) directly from the canonical
+//! JSONL file on disk. Metrics are recomputed shortly after a session stops
+//! changing, so "Metrics Only" sync has complete metrics to upload without
+//! the transcript ever leaving the machine.
+//!
+//! Architecture:
+//! - `compute` - single pass over canonical messages producing `CoreMetrics`
+//! - `context` - context growth curve, utilization and compaction detection
+//! - `export` - CSV/Parquet export of metrics and rollups with a schema document
+//! - `tools` - per-tool invocation, failure and result size counts, and the
+//!   file paths in tool inputs
+//! - `rollup` - daily/weekly sessions, tokens and cost per provider and project
+//! - `habits` - per-day habits and streaks (activity, plan mode, interruptions)
+//! - `survival` - how many lines from a session's commits survive N days later
+//! - `tips` - improvement tips grouped by similarity and ranked by recurrence
+//! - `store` - upsert into `session_metrics` and mark core metrics completed
+//! - `handler` - event bus subscriber that debounces session changes
+
+pub mod compute;
+pub mod context;
+pub mod export;
+pub mod habits;
+pub mod handler;
+pub mod rollup;
+pub mod store;
+pub mod survival;
+pub mod tips;
+pub mod tools;
+
+pub use compute::CoreMetrics;
+pub use context::ContextUsage;
+pub use habits::{HabitDay, HabitStreaks};
+pub use handler::CoreMetricsHandler;
+pub use rollup::{RollupGranularity, UsageRange, UsageRollup};
+pub use survival::LineSurvivalStats;
+pub use tips::TipsReport;
+pub use tools::ToolUsageStats;
+
+use crate::config::load_config;
+use crate::database::get_full_session_by_id;
+use crate::logging::{log_info, log_warn};
+use std::fs;
+
+/// Gap between messages after which a session counts as idle, from the config
+fn idle_threshold() -> chrono::Duration {
+    let minutes = load_config()
+        .ok()
+        .and_then(|config| config.idle_threshold_minutes)
+        .filter(|minutes| *minutes > 0)
+        .unwrap_or(compute::DEFAULT_IDLE_THRESHOLD_MINUTES);
+    chrono::Duration::minutes(minutes as i64)
+}
+
+/// Compute and store core metrics for a session from its canonical file
+///
+/// Sessions without an end time are skipped; they are picked up again on
+/// their next change. Returns the computed metrics when they were stored.
+pub fn compute_session_metrics(session_id: &str) -> Result<Option<CoreMetrics>, String> {
+    let session = get_full_session_by_id(session_id)
+        .map_err(|e| format!("Failed to get session data: {}", e))?
+        .ok_or_else(|| format!("Session {} not found in database", session_id))?;
+
+    if session.session_end_time.is_none() {
+        return Ok(None);
+    }
+
+    let content = fs::read_to_string(&session.file_path)
+        .map_err(|e| format!("Failed to read {}: {}", session.file_path, e))?;
+
+    let messages = compute::parse_messages(&content);
+    let metrics = compute::compute_from_messages(&messages, idle_threshold());
+    store::store_core_metrics(session_id, &session.provider, &metrics)
+        .map_err(|e| format!("Failed to store core metrics: {}", e))?;
+
+    let tool_usage = tools::compute_tool_usage(&messages);
+    store::store_tool_usage(
+        session_id,
+        &session.provider,
+        &session.project_name,
+        &tool_usage,
+    )
+    .map_err(|e| format!("Failed to store tool usage: {}", e))?;
+
+    store::record_session_usage(session_id, Some(rollup::UsageTotals::from(&metrics)))
+        .map_err(|e| format!("Failed to update usage rollups: {}", e))?;
+
+    // Commit links are a convenience for session detail; don't fail metrics over them
+    match crate::git_diff::link_session_commits(session_id) {
+        // Sessions that never commit leave their work in the working tree
+        Ok(Some(commits)) if commits.is_empty() => {
+            if let Err(e) = crate::git_diff::snapshot_uncommitted_changes(session_id) {
+                log_warn(
+                    "metrics",
+                    &format!("⚠ Failed to snapshot changes for {}: {}", session_id, e),
+                )
+                .unwrap_or_default();
+            }
+        }
+        Ok(_) => {}
+        Err(e) => log_warn(
+            "metrics",
+            &format!("⚠ Failed to link commits for {}: {}", session_id, e),
+        )
+        .unwrap_or_default(),
+    }
+
+    log_info(
+        "metrics",
+        &format!(
+            "✓ Computed core metrics for {} ({} responses, {} tool results)",
+            session_id, metrics.performance_total_responses, metrics.total_operations
+        ),
+    )
+    .unwrap_or_default();
+
+    Ok(Some(metrics))
+}
+
+/// Context growth curve for a session, read from its canonical file
+pub fn session_context_usage(session_id: &str) -> Result<ContextUsage, String> {
+    let session = get_full_session_by_id(session_id)
+        .map_err(|e| format!("Failed to get session data: {}", e))?
+        .ok_or_else(|| format!("Session {} not found in database", session_id))?;
+
+    let content = fs::read_to_string(&session.file_path)
+        .map_err(|e| format!("Failed to read {}: {}", session.file_path, e))?;
+
+    Ok(context::compute_context_usage(&compute::parse_messages(
+        &content,
+    )))
+}
@@ -0,0 +1,224 @@
+//! Daily and weekly usage rollups
+//!
+//! `usage_rollups` keeps sessions, active time, tokens and estimated cost per
+//! period, provider and project, so the dashboard can chart usage without
+//! grouping raw sessions. Each session's contribution is recorded in `session_usage`;
+//! when a session is ingested again its previous contribution is subtracted
+//! before the new one is added (see `store::record_session_usage`).
+
+use super::compute::CoreMetrics;
+use chrono::{DateTime, Datelike, Days, Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+/// Granularities maintained in `usage_rollups`
+pub const ROLLUP_GRANULARITIES: [RollupGranularity; 2] =
+    [RollupGranularity::Day, RollupGranularity::Week];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RollupGranularity {
+    Day,
+    /// Weeks start on Monday
+    Week,
+}
+
+impl RollupGranularity {
+    /// Value stored in `usage_rollups.granularity`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RollupGranularity::Day => "day",
+            RollupGranularity::Week => "week",
+        }
+    }
+
+    /// First day of the period containing `date`
+    pub fn period_start(&self, date: NaiveDate) -> NaiveDate {
+        match self {
+            RollupGranularity::Day => date,
+            RollupGranularity::Week => date
+                .checked_sub_days(Days::new(date.weekday().num_days_from_monday().into()))
+                .unwrap_or(date),
+        }
+    }
+}
+
+/// Local calendar day of a millisecond timestamp
+pub fn local_date(timestamp_ms: i64) -> Option<NaiveDate> {
+    DateTime::from_timestamp_millis(timestamp_ms)
+        .map(|timestamp| timestamp.with_timezone(&Local).date_naive())
+}
+
+/// Tokens, cost and active time a session contributes to its periods
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct UsageTotals {
+    pub active_minutes: f64,
+    pub input_tokens: i64,
+    pub output_tokens: i64,
+    pub cache_created: i64,
+    pub cache_read: i64,
+    pub estimated_cost_usd: f64,
+}
+
+impl From<&CoreMetrics> for UsageTotals {
+    fn from(metrics: &CoreMetrics) -> Self {
+        Self {
+            active_minutes: metrics.active_length_minutes.unwrap_or(0.0),
+            input_tokens: metrics.total_input_tokens,
+            output_tokens: metrics.total_output_tokens,
+            cache_created: metrics.total_cache_created,
+            cache_read: metrics.total_cache_read,
+            estimated_cost_usd: metrics.estimated_cost_usd,
+        }
+    }
+}
+
+/// Inclusive range of local dates
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct UsageRange {
+    pub start: NaiveDate,
+    pub end: NaiveDate,
+}
+
+/// Aggregated counts for a period (or part of one)
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageCounts {
+    pub session_count: i64,
+    /// Session time without idle gaps
+    pub active_minutes: f64,
+    pub input_tokens: i64,
+    pub output_tokens: i64,
+    pub cache_created: i64,
+    pub cache_read: i64,
+    pub estimated_cost_usd: f64,
+}
+
+impl UsageCounts {
+    fn add(&mut self, other: &UsageCounts) {
+        self.session_count += other.session_count;
+        self.active_minutes += other.active_minutes;
+        self.input_tokens += other.input_tokens;
+        self.output_tokens += other.output_tokens;
+        self.cache_created += other.cache_created;
+        self.cache_read += other.cache_read;
+        self.estimated_cost_usd += other.estimated_cost_usd;
+    }
+}
+
+/// Usage of one provider and project in a period
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageRollupRow {
+    pub period_start: String,
+    pub provider: String,
+    pub project_name: String,
+    #[serde(flatten)]
+    pub counts: UsageCounts,
+}
+
+/// Usage across providers and projects in a period
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UsagePeriod {
+    pub period_start: String,
+    #[serde(flatten)]
+    pub counts: UsageCounts,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageRollup {
+    pub granularity: RollupGranularity,
+    /// Totals per period, oldest first
+    pub periods: Vec<UsagePeriod>,
+    /// Per provider and project breakdown, ordered by period
+    pub rows: Vec<UsageRollupRow>,
+}
+
+impl UsageRollup {
+    /// Build a rollup from rows ordered by period
+    pub fn from_rows(granularity: RollupGranularity, rows: Vec<UsageRollupRow>) -> Self {
+        let mut periods: Vec<UsagePeriod> = Vec::new();
+        for row in &rows {
+            match periods.last_mut() {
+                Some(period) if period.period_start == row.period_start => {
+                    period.counts.add(&row.counts)
+                }
+                _ => periods.push(UsagePeriod {
+                    period_start: row.period_start.clone(),
+                    counts: row.counts.clone(),
+                }),
+            }
+        }
+
+        Self {
+            granularity,
+            periods,
+            rows,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(value: &str) -> NaiveDate {
+        value.parse().unwrap()
+    }
+
+    fn row(period_start: &str, provider: &str, sessions: i64, cost: f64) -> UsageRollupRow {
+        UsageRollupRow {
+            period_start: period_start.to_string(),
+            provider: provider.to_string(),
+            project_name: "app".to_string(),
+            counts: UsageCounts {
+                session_count: sessions,
+                input_tokens: sessions * 100,
+                estimated_cost_usd: cost,
+                ..Default::default()
+            },
+        }
+    }
+
+    #[test]
+    fn test_period_start() {
+        // 2025-01-08 is a Wednesday
+        assert_eq!(
+            RollupGranularity::Day.period_start(date("2025-01-08")),
+            date("2025-01-08")
+        );
+        assert_eq!(
+            RollupGranularity::Week.period_start(date("2025-01-08")),
+            date("2025-01-06")
+        );
+        assert_eq!(
+            RollupGranularity::Week.period_start(date("2025-01-12")),
+            date("2025-01-06")
+        );
+        assert_eq!(
+            RollupGranularity::Week.period_start(date("2025-01-06")),
+            date("2025-01-06")
+        );
+    }
+
+    #[test]
+    fn test_rollup_period_totals() {
+        let rollup = UsageRollup::from_rows(
+            RollupGranularity::Day,
+            vec![
+                row("2025-01-06", "claude-code", 2, 0.5),
+                row("2025-01-06", "codex", 1, 0.25),
+                row("2025-01-07", "claude-code", 3, 1.0),
+            ],
+        );
+
+        assert_eq!(rollup.periods.len(), 2);
+        assert_eq!(rollup.periods[0].period_start, "2025-01-06");
+        assert_eq!(rollup.periods[0].counts.session_count, 3);
+        assert_eq!(rollup.periods[0].counts.input_tokens, 300);
+        assert_eq!(rollup.periods[0].counts.estimated_cost_usd, 0.75);
+        assert_eq!(rollup.periods[1].counts.session_count, 3);
+        assert_eq!(rollup.rows.len(), 3);
+    }
+}
@@ -0,0 +1,850 @@
+//! Persisting locally computed core metrics
+
+use super::compute::CoreMetrics;
+use super::habits::{compute_streaks, HabitDay, HabitStreaks};
+use super::tips::{cluster_tips, split_tips, SessionTip, TipsReport, TIP_COLUMNS};
+use super::survival::{
+    survival_rate, LineSurvivalStats, PendingSurvivalSession, ProjectLineSurvival,
+    SessionLineSurvival,
+};
+use super::rollup::{
+    local_date, RollupGranularity, UsageCounts, UsageRange, UsageRollup, UsageRollupRow,
+    UsageTotals, ROLLUP_GRANULARITIES,
+};
+use super::tools::{ProjectToolStats, SessionToolUsage, ToolStat, ToolUsageStats};
+use crate::database::with_connection_mut;
+use crate::git_diff::LineSurvival;
+use chrono::{Local, Utc};
+use rusqlite::{params, Connection, Result, Row};
+use serde::Deserialize;
+use uuid::Uuid;
+
+/// Store core metrics for a session and mark its core metrics as completed
+///
+/// Only the columns computed locally are written; AI-derived columns and
+/// improvement tips from an earlier run are kept. The session is flagged for
+/// re-sync so Metrics Only uploads pick up the new values.
+pub fn store_core_metrics(session_id: &str, provider: &str, metrics: &CoreMetrics) -> Result<()> {
+    let now = Utc::now().timestamp_millis();
+    let context = &metrics.context;
+
+    with_connection_mut(|conn| {
+        let tx = conn.transaction()?;
+
+        tx.execute(
+            "INSERT INTO session_metrics (
+                id, session_id, provider, timestamp,
+                response_latency_ms, task_completion_time_ms, performance_total_responses,
+                read_write_ratio, read_operations, write_operations, total_user_messages,
+                error_count, error_types, last_error_message, recovery_attempts, fatal_errors,
+                interruption_rate, session_length_minutes, total_interruptions, engagement_total_responses,
+                active_length_minutes, idle_gap_count,
+                task_success_rate, iteration_count,
+                used_plan_mode, used_todo_tracking, over_top_affirmations,
+                successful_operations, total_operations, exit_plan_mode_count, todo_write_count,
+                over_top_affirmations_phrases,
+                total_input_tokens, total_output_tokens, total_cache_created, total_cache_read,
+                context_length, context_window_size, context_utilization_percent,
+                compact_event_count, compact_event_steps, messages_until_first_compact,
+                avg_tokens_per_message, peak_context_utilization_percent,
+                avg_context_utilization_percent, context_ceiling_hits,
+                created_at
+            ) VALUES (
+                ?, ?, ?, ?,
+                ?, ?, ?,
+                ?, ?, ?, ?,
+                ?, ?, ?, ?, ?,
+                ?, ?, ?, ?,
+                ?, ?,
+                ?, ?,
+                ?, ?, ?,
+                ?, ?, ?, ?,
+                ?,
+                ?, ?, ?, ?,
+                ?, ?, ?,
+                ?, ?, ?,
+                ?, ?,
+                ?, ?,
+                ?
+            )
+            ON CONFLICT(session_id) DO UPDATE SET
+                provider = excluded.provider,
+                timestamp = excluded.timestamp,
+                response_latency_ms = excluded.response_latency_ms,
+                task_completion_time_ms = excluded.task_completion_time_ms,
+                performance_total_responses = excluded.performance_total_responses,
+                read_write_ratio = excluded.read_write_ratio,
+                read_operations = excluded.read_operations,
+                write_operations = excluded.write_operations,
+                total_user_messages = excluded.total_user_messages,
+                error_count = excluded.error_count,
+                error_types = excluded.error_types,
+                last_error_message = excluded.last_error_message,
+                recovery_attempts = excluded.recovery_attempts,
+                fatal_errors = excluded.fatal_errors,
+                interruption_rate = excluded.interruption_rate,
+                session_length_minutes = excluded.session_length_minutes,
+                total_interruptions = excluded.total_interruptions,
+                engagement_total_responses = excluded.engagement_total_responses,
+                active_length_minutes = excluded.active_length_minutes,
+                idle_gap_count = excluded.idle_gap_count,
+                task_success_rate = excluded.task_success_rate,
+                iteration_count = excluded.iteration_count,
+                used_plan_mode = excluded.used_plan_mode,
+                used_todo_tracking = excluded.used_todo_tracking,
+                over_top_affirmations = excluded.over_top_affirmations,
+                successful_operations = excluded.successful_operations,
+                total_operations = excluded.total_operations,
+                exit_plan_mode_count = excluded.exit_plan_mode_count,
+                todo_write_count = excluded.todo_write_count,
+                over_top_affirmations_phrases = excluded.over_top_affirmations_phrases,
+                total_input_tokens = excluded.total_input_tokens,
+                total_output_tokens = excluded.total_output_tokens,
+                total_cache_created = excluded.total_cache_created,
+                total_cache_read = excluded.total_cache_read,
+                context_length = excluded.context_length,
+                context_window_size = excluded.context_window_size,
+                context_utilization_percent = excluded.context_utilization_percent,
+                compact_event_count = excluded.compact_event_count,
+                compact_event_steps = excluded.compact_event_steps,
+                messages_until_first_compact = excluded.messages_until_first_compact,
+                avg_tokens_per_message = excluded.avg_tokens_per_message,
+                peak_context_utilization_percent = excluded.peak_context_utilization_percent,
+                avg_context_utilization_percent = excluded.avg_context_utilization_percent,
+                context_ceiling_hits = excluded.context_ceiling_hits",
+            params![
+                Uuid::new_v4().to_string(),
+                session_id,
+                provider,
+                now,
+                metrics.response_latency_ms,
+                metrics.task_completion_time_ms,
+                metrics.performance_total_responses,
+                metrics.read_write_ratio,
+                metrics.read_operations,
+                metrics.write_operations,
+                metrics.total_user_messages,
+                metrics.error_count,
+                metrics.error_types.join(","),
+                metrics.last_error_message,
+                metrics.recovery_attempts,
+                metrics.fatal_errors,
+                metrics.interruption_rate,
+                metrics.session_length_minutes,
+                metrics.total_interruptions,
+                metrics.performance_total_responses,
+                metrics.active_length_minutes,
+                metrics.idle_gap_count,
+                metrics.task_success_rate,
+                metrics.iteration_count,
+                metrics.used_plan_mode as i64,
+                metrics.used_todo_tracking as i64,
+                metrics.over_top_affirmations,
+                metrics.successful_operations,
+                metrics.total_operations,
+                metrics.exit_plan_mode_count,
+                metrics.todo_write_count,
+                metrics.over_top_affirmations_phrases.join(","),
+                metrics.total_input_tokens,
+                metrics.total_output_tokens,
+                metrics.total_cache_created,
+                metrics.total_cache_read,
+                context.context_length.map(|tokens| tokens as i64),
+                context.context_window_size.map(|tokens| tokens as i64),
+                context.context_utilization_percent,
+                context.compact_event_count(),
+                serde_json::to_string(&context.compact_event_steps).ok(),
+                context.messages_until_first_compact.map(|count| count as i64),
+                context.avg_tokens_per_message,
+                context.peak_utilization_percent,
+                context.avg_utilization_percent,
+                context.ceiling_hits,
+                now,
+            ],
+        )?;
+
+        tx.execute(
+            "UPDATE agent_sessions
+             SET core_metrics_status = 'completed',
+                 core_metrics_processed_at = ?,
+                 synced_to_server = 0
+             WHERE session_id = ?",
+            params![now, session_id],
+        )?;
+
+        tx.commit()
+    })
+}
+
+/// Metrics only the frontend processors compute, keyed like their
+/// `session_metrics` columns
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ProcessorMetrics {
+    pub input_clarity_score: Option<f64>,
+    pub process_quality_score: Option<f64>,
+    pub usage_improvement_tips: Option<String>,
+    pub error_improvement_tips: Option<String>,
+    pub engagement_improvement_tips: Option<String>,
+    pub quality_improvement_tips: Option<String>,
+    pub performance_improvement_tips: Option<String>,
+    pub improvement_tips: Option<String>,
+    pub custom_metrics: Option<String>,
+    pub git_total_files_changed: Option<i64>,
+    pub git_lines_added: Option<i64>,
+    pub git_lines_removed: Option<i64>,
+    pub git_lines_modified: Option<i64>,
+    pub git_net_lines_changed: Option<i64>,
+    pub git_lines_read_per_line_changed: Option<f64>,
+    pub git_reads_per_file_changed: Option<f64>,
+    pub git_lines_changed_per_minute: Option<f64>,
+    pub git_lines_changed_per_tool_use: Option<f64>,
+    pub total_lines_read: Option<i64>,
+    pub git_diff_improvement_tips: Option<String>,
+    pub context_improvement_tips: Option<String>,
+}
+
+/// Store the frontend processors' metrics for a session
+///
+/// Core metrics from `store_core_metrics` are kept. The session is flagged
+/// for re-sync so the new tips are uploaded.
+pub fn store_processor_metrics(
+    session_id: &str,
+    provider: &str,
+    metrics: &ProcessorMetrics,
+) -> Result<()> {
+    let now = Utc::now().timestamp_millis();
+
+    with_connection_mut(|conn| {
+        let tx = conn.transaction()?;
+
+        tx.execute(
+            "INSERT INTO session_metrics (
+                id, session_id, provider, timestamp,
+                input_clarity_score, process_quality_score,
+                usage_improvement_tips, error_improvement_tips, engagement_improvement_tips,
+                quality_improvement_tips, performance_improvement_tips, improvement_tips,
+                custom_metrics,
+                git_total_files_changed, git_lines_added, git_lines_removed, git_lines_modified,
+                git_net_lines_changed, git_lines_read_per_line_changed, git_reads_per_file_changed,
+                git_lines_changed_per_minute, git_lines_changed_per_tool_use, total_lines_read,
+                git_diff_improvement_tips, context_improvement_tips,
+                created_at
+            ) VALUES (
+                ?, ?, ?, ?,
+                ?, ?,
+                ?, ?, ?,
+                ?, ?, ?,
+                ?,
+                ?, ?, ?, ?,
+                ?, ?, ?,
+                ?, ?, ?,
+                ?, ?,
+                ?
+            )
+            ON CONFLICT(session_id) DO UPDATE SET
+                input_clarity_score = excluded.input_clarity_score,
+                process_quality_score = excluded.process_quality_score,
+                usage_improvement_tips = excluded.usage_improvement_tips,
+                error_improvement_tips = excluded.error_improvement_tips,
+                engagement_improvement_tips = excluded.engagement_improvement_tips,
+                quality_improvement_tips = excluded.quality_improvement_tips,
+                performance_improvement_tips = excluded.performance_improvement_tips,
+                improvement_tips = excluded.improvement_tips,
+                custom_metrics = excluded.custom_metrics,
+                git_total_files_changed = excluded.git_total_files_changed,
+                git_lines_added = excluded.git_lines_added,
+                git_lines_removed = excluded.git_lines_removed,
+                git_lines_modified = excluded.git_lines_modified,
+                git_net_lines_changed = excluded.git_net_lines_changed,
+                git_lines_read_per_line_changed = excluded.git_lines_read_per_line_changed,
+                git_reads_per_file_changed = excluded.git_reads_per_file_changed,
+                git_lines_changed_per_minute = excluded.git_lines_changed_per_minute,
+                git_lines_changed_per_tool_use = excluded.git_lines_changed_per_tool_use,
+                total_lines_read = excluded.total_lines_read,
+                git_diff_improvement_tips = excluded.git_diff_improvement_tips,
+                context_improvement_tips = excluded.context_improvement_tips",
+            params![
+                Uuid::new_v4().to_string(),
+                session_id,
+                provider,
+                now,
+                metrics.input_clarity_score,
+                metrics.process_quality_score,
+                metrics.usage_improvement_tips,
+                metrics.error_improvement_tips,
+                metrics.engagement_improvement_tips,
+                metrics.quality_improvement_tips,
+                metrics.performance_improvement_tips,
+                metrics.improvement_tips,
+                metrics.custom_metrics,
+                metrics.git_total_files_changed,
+                metrics.git_lines_added,
+                metrics.git_lines_removed,
+                metrics.git_lines_modified,
+                metrics.git_net_lines_changed,
+                metrics.git_lines_read_per_line_changed,
+                metrics.git_reads_per_file_changed,
+                metrics.git_lines_changed_per_minute,
+                metrics.git_lines_changed_per_tool_use,
+                metrics.total_lines_read,
+                metrics.git_diff_improvement_tips,
+                metrics.context_improvement_tips,
+                now,
+            ],
+        )?;
+
+        tx.execute(
+            "UPDATE agent_sessions SET synced_to_server = 0 WHERE session_id = ?",
+            params![session_id],
+        )?;
+
+        tx.commit()
+    })
+}
+
+/// Replace a session's tool usage rows
+pub fn store_tool_usage(
+    session_id: &str,
+    provider: &str,
+    project_name: &str,
+    usage: &[SessionToolUsage],
+) -> Result<()> {
+    let now = Utc::now().timestamp_millis();
+
+    with_connection_mut(|conn| {
+        let tx = conn.transaction()?;
+
+        tx.execute(
+            "DELETE FROM tool_usage_stats WHERE session_id = ?",
+            params![session_id],
+        )?;
+
+        for tool in usage {
+            tx.execute(
+                "INSERT INTO tool_usage_stats (
+                    session_id, provider, project_name, tool_name,
+                    invocation_count, result_count, error_count, total_result_bytes, updated_at
+                ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
+                params![
+                    session_id,
+                    provider,
+                    project_name,
+                    tool.tool_name,
+                    tool.invocation_count,
+                    tool.result_count,
+                    tool.error_count,
+                    tool.total_result_bytes,
+                    now,
+                ],
+            )?;
+        }
+
+        tx.commit()
+    })
+}
+
+/// Aggregate columns shared by the tool usage queries
+const TOOL_STAT_COLUMNS: &str = "tool_name, SUM(invocation_count), SUM(result_count),
+     SUM(error_count), SUM(total_result_bytes), COUNT(DISTINCT session_id)";
+
+fn tool_stat_from_row(row: &Row, offset: usize) -> Result<ToolStat> {
+    Ok(ToolStat::new(
+        row.get(offset)?,
+        row.get(offset + 1)?,
+        row.get(offset + 2)?,
+        row.get(offset + 3)?,
+        row.get(offset + 4)?,
+        row.get(offset + 5)?,
+    ))
+}
+
+/// Tool usage across sessions, optionally limited to one project
+///
+/// `top_n` caps the number of tools listed per project.
+pub fn get_tool_usage_stats(project_name: Option<&str>, top_n: usize) -> Result<ToolUsageStats> {
+    with_connection_mut(|conn| {
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {}
+             FROM tool_usage_stats
+             WHERE ?1 IS NULL OR project_name = ?1
+             GROUP BY tool_name
+             ORDER BY SUM(invocation_count) DESC, tool_name",
+            TOOL_STAT_COLUMNS
+        ))?;
+        let tools = stmt
+            .query_map(params![project_name], |row| tool_stat_from_row(row, 0))?
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut stmt = conn.prepare(&format!(
+            "SELECT project_name, {}
+             FROM tool_usage_stats
+             WHERE ?1 IS NULL OR project_name = ?1
+             GROUP BY project_name, tool_name
+             ORDER BY project_name, SUM(invocation_count) DESC, tool_name",
+            TOOL_STAT_COLUMNS
+        ))?;
+        let rows = stmt.query_map(params![project_name], |row| {
+            Ok((row.get::<_, String>(0)?, tool_stat_from_row(row, 1)?))
+        })?;
+
+        let mut top_tools_by_project: Vec<ProjectToolStats> = Vec::new();
+        for row in rows {
+            let (project, stat) = row?;
+            match top_tools_by_project.last_mut() {
+                Some(last) if last.project_name == project => {
+                    if last.tools.len() < top_n {
+                        last.tools.push(stat);
+                    }
+                }
+                _ => top_tools_by_project.push(ProjectToolStats {
+                    project_name: project,
+                    tools: vec![stat],
+                }),
+            }
+        }
+
+        Ok(ToolUsageStats {
+            tools,
+            top_tools_by_project,
+        })
+    })
+}
+
+/// A session's recorded contribution to the usage rollups
+struct SessionUsage {
+    provider: String,
+    project_name: String,
+    day: String,
+    week: String,
+    totals: UsageTotals,
+}
+
+impl SessionUsage {
+    fn period_start(&self, granularity: RollupGranularity) -> &str {
+        match granularity {
+            RollupGranularity::Day => &self.day,
+            RollupGranularity::Week => &self.week,
+        }
+    }
+}
+
+/// Add (`sign` = 1) or subtract (`sign` = -1) a session's usage from its periods
+fn apply_to_rollups(conn: &Connection, usage: &SessionUsage, sign: i64) -> Result<()> {
+    let totals = &usage.totals;
+
+    for granularity in ROLLUP_GRANULARITIES {
+        conn.execute(
+            "INSERT INTO usage_rollups (
+                granularity, period_start, provider, project_name, session_count, active_minutes,
+                input_tokens, output_tokens, cache_created, cache_read, estimated_cost
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT(granularity, period_start, provider, project_name) DO UPDATE SET
+                session_count = session_count + excluded.session_count,
+                active_minutes = active_minutes + excluded.active_minutes,
+                input_tokens = input_tokens + excluded.input_tokens,
+                output_tokens = output_tokens + excluded.output_tokens,
+                cache_created = cache_created + excluded.cache_created,
+                cache_read = cache_read + excluded.cache_read,
+                estimated_cost = estimated_cost + excluded.estimated_cost",
+            params![
+                granularity.as_str(),
+                usage.period_start(granularity),
+                usage.provider,
+                usage.project_name,
+                sign,
+                sign as f64 * totals.active_minutes,
+                sign * totals.input_tokens,
+                sign * totals.output_tokens,
+                sign * totals.cache_created,
+                sign * totals.cache_read,
+                sign as f64 * totals.estimated_cost_usd,
+            ],
+        )?;
+    }
+
+    if sign < 0 {
+        conn.execute("DELETE FROM usage_rollups WHERE session_count <= 0", [])?;
+    }
+    Ok(())
+}
+
+/// Remove a session's contribution from the usage rollups
+///
+/// Returns the totals it contributed, if it was recorded.
+pub fn remove_session_usage(conn: &Connection, session_id: &str) -> Result<Option<UsageTotals>> {
+    let usage = conn
+        .query_row(
+            "SELECT provider, project_name, day, week,
+                    input_tokens, output_tokens, cache_created, cache_read, estimated_cost,
+                    active_minutes
+             FROM session_usage
+             WHERE session_id = ?",
+            params![session_id],
+            |row| {
+                Ok(SessionUsage {
+                    provider: row.get(0)?,
+                    project_name: row.get(1)?,
+                    day: row.get(2)?,
+                    week: row.get(3)?,
+                    totals: UsageTotals {
+                        input_tokens: row.get(4)?,
+                        output_tokens: row.get(5)?,
+                        cache_created: row.get(6)?,
+                        cache_read: row.get(7)?,
+                        estimated_cost_usd: row.get(8)?,
+                        active_minutes: row.get(9)?,
+                    },
+                })
+            },
+        )
+        .ok();
+
+    let Some(usage) = usage else {
+        return Ok(None);
+    };
+
+    apply_to_rollups(conn, &usage, -1)?;
+    conn.execute(
+        "DELETE FROM session_usage WHERE session_id = ?",
+        params![session_id],
+    )?;
+    refresh_habit_day(conn, &usage.day)?;
+
+    Ok(Some(usage.totals))
+}
+
+/// Record a session in the usage rollups, replacing its previous contribution
+///
+/// Sessions are bucketed by their local start date. `totals` is `None` when
+/// only the session row changed; the tokens from the last metrics run are kept.
+pub fn record_session_usage(session_id: &str, totals: Option<UsageTotals>) -> Result<()> {
+    let now = Utc::now().timestamp_millis();
+
+    with_connection_mut(|conn| {
+        let tx = conn.transaction()?;
+
+        let session = tx
+            .query_row(
+                "SELECT provider, project_name, COALESCE(session_start_time, created_at)
+                 FROM agent_sessions
+                 WHERE session_id = ?",
+                params![session_id],
+                |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get(2)?)),
+            )
+            .ok();
+        let Some((provider, project_name, day)) =
+            session.and_then(|(provider, project_name, started_at)| {
+                Some((provider, project_name, local_date(started_at)?))
+            })
+        else {
+            return Ok(());
+        };
+
+        let previous = remove_session_usage(&tx, session_id)?;
+        let usage = SessionUsage {
+            provider,
+            project_name,
+            day: day.to_string(),
+            week: RollupGranularity::Week.period_start(day).to_string(),
+            totals: totals.or(previous).unwrap_or_default(),
+        };
+
+        tx.execute(
+            "INSERT INTO session_usage (
+                session_id, provider, project_name, day, week, active_minutes,
+                input_tokens, output_tokens, cache_created, cache_read, estimated_cost, updated_at
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            params![
+                session_id,
+                usage.provider,
+                usage.project_name,
+                usage.day,
+                usage.week,
+                usage.totals.active_minutes,
+                usage.totals.input_tokens,
+                usage.totals.output_tokens,
+                usage.totals.cache_created,
+                usage.totals.cache_read,
+                usage.totals.estimated_cost_usd,
+                now,
+            ],
+        )?;
+        apply_to_rollups(&tx, &usage, 1)?;
+        refresh_habit_day(&tx, &usage.day)?;
+
+        tx.commit()
+    })
+}
+
+/// Usage per period, provider and project within a date range
+///
+/// The range is widened to the start of the period containing `range.start`.
+pub fn get_usage_rollup(range: UsageRange, granularity: RollupGranularity) -> Result<UsageRollup> {
+    with_connection_mut(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT period_start, provider, project_name, session_count,
+                    input_tokens, output_tokens, cache_created, cache_read, estimated_cost,
+                    active_minutes
+             FROM usage_rollups
+             WHERE granularity = ? AND period_start BETWEEN ? AND ?
+             ORDER BY period_start, provider, project_name",
+        )?;
+        let rows = stmt
+            .query_map(
+                params![
+                    granularity.as_str(),
+                    granularity.period_start(range.start).to_string(),
+                    range.end.to_string(),
+                ],
+                |row| {
+                    Ok(UsageRollupRow {
+                        period_start: row.get(0)?,
+                        provider: row.get(1)?,
+                        project_name: row.get(2)?,
+                        counts: UsageCounts {
+                            session_count: row.get(3)?,
+                            input_tokens: row.get(4)?,
+                            output_tokens: row.get(5)?,
+                            cache_created: row.get(6)?,
+                            cache_read: row.get(7)?,
+                            estimated_cost_usd: row.get(8)?,
+                            active_minutes: row.get(9)?,
+                        },
+                    })
+                },
+            )?
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(UsageRollup::from_rows(granularity, rows))
+    })
+}
+
+/// Aggregate `habit_days` columns over `session_usage` joined with metrics
+const HABIT_DAY_SELECT: &str = "SELECT u.day, COUNT(*),
+        SUM(COALESCE(m.used_plan_mode, 0)),
+        SUM(COALESCE(m.total_user_messages, 0)),
+        SUM(COALESCE(m.total_interruptions, 0)),
+        ?
+     FROM session_usage u
+     LEFT JOIN session_metrics m ON m.session_id = u.session_id";
+
+/// Recompute one day's habits from its sessions
+fn refresh_habit_day(conn: &Connection, day: &str) -> Result<()> {
+    conn.execute("DELETE FROM habit_days WHERE day = ?", params![day])?;
+    conn.execute(
+        &format!(
+            "INSERT INTO habit_days {} WHERE u.day = ? GROUP BY u.day",
+            HABIT_DAY_SELECT
+        ),
+        params![Utc::now().timestamp_millis(), day],
+    )?;
+    Ok(())
+}
+
+/// Recompute all habit days, e.g. after a provider's sessions were cleared
+pub(crate) fn rebuild_habit_days(conn: &Connection) -> Result<()> {
+    conn.execute("DELETE FROM habit_days", [])?;
+    conn.execute(
+        &format!("INSERT INTO habit_days {} GROUP BY u.day", HABIT_DAY_SELECT),
+        params![Utc::now().timestamp_millis()],
+    )?;
+    Ok(())
+}
+
+fn habit_day_from_row(row: &Row) -> Result<HabitDay> {
+    Ok(HabitDay {
+        day: row.get(0)?,
+        session_count: row.get(1)?,
+        plan_mode_sessions: row.get(2)?,
+        user_messages: row.get(3)?,
+        interruptions: row.get(4)?,
+    })
+}
+
+/// Habit days within a date range, oldest first
+pub fn get_habit_days(range: UsageRange) -> Result<Vec<HabitDay>> {
+    with_connection_mut(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT day, session_count, plan_mode_sessions, user_messages, interruptions
+             FROM habit_days
+             WHERE day BETWEEN ? AND ?
+             ORDER BY day",
+        )?;
+        let days = stmt
+            .query_map(
+                params![range.start.to_string(), range.end.to_string()],
+                habit_day_from_row,
+            )?
+            .collect::<Result<Vec<_>>>()?;
+        Ok(days)
+    })
+}
+
+/// Current and longest streaks as of today
+pub fn get_habit_streaks(interruption_threshold_percent: f64) -> Result<HabitStreaks> {
+    with_connection_mut(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT day, session_count, plan_mode_sessions, user_messages, interruptions
+             FROM habit_days
+             ORDER BY day",
+        )?;
+        let days = stmt
+            .query_map([], habit_day_from_row)?
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(compute_streaks(
+            &days,
+            Local::now().date_naive(),
+            interruption_threshold_percent,
+        ))
+    })
+}
+
+/// Tips recurring across sessions, optionally limited to one project,
+/// ranked and capped at `limit`
+pub fn get_tips_report(project_name: Option<&str>, limit: usize) -> Result<TipsReport> {
+    with_connection_mut(|conn| {
+        let columns: Vec<&str> = TIP_COLUMNS.iter().map(|(_, column)| *column).collect();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT m.session_id, s.project_name, {}
+             FROM session_metrics m
+             JOIN agent_sessions s ON s.session_id = m.session_id
+             WHERE ?1 IS NULL OR s.project_name = ?1",
+            columns.join(", ")
+        ))?;
+        let mut rows = stmt.query(params![project_name])?;
+
+        let mut tips = Vec::new();
+        while let Some(row) = rows.next()? {
+            let session_id: String = row.get(0)?;
+            let project_name: String = row.get(1)?;
+            for (index, (category, _)) in TIP_COLUMNS.iter().enumerate() {
+                let Some(value) = row.get::<_, Option<String>>(index + 2)? else {
+                    continue;
+                };
+                tips.extend(split_tips(&value).into_iter().map(|text| SessionTip {
+                    session_id: session_id.clone(),
+                    project_name: project_name.clone(),
+                    category,
+                    text,
+                }));
+            }
+        }
+
+        Ok(cluster_tips(&tips, limit))
+    })
+}
+
+/// Ended sessions with linked commits, not yet analyzed for `window_days`
+///
+/// Only sessions that ended before `ended_before` (Unix milliseconds) are
+/// returned, so the whole window has passed.
+pub fn get_pending_survival_sessions(
+    window_days: u32,
+    ended_before: i64,
+) -> Result<Vec<PendingSurvivalSession>> {
+    with_connection_mut(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT s.session_id, s.project_name, s.cwd, s.git_branch, s.session_end_time
+             FROM agent_sessions s
+             WHERE s.session_end_time IS NOT NULL
+               AND s.session_end_time <= ?1
+               AND s.cwd IS NOT NULL
+               AND EXISTS (SELECT 1 FROM session_commits c WHERE c.session_id = s.session_id)
+               AND NOT EXISTS (
+                   SELECT 1 FROM line_survival l
+                   WHERE l.session_id = s.session_id AND l.window_days = ?2
+               )",
+        )?;
+        let sessions = stmt
+            .query_map(params![ended_before, window_days], |row| {
+                Ok(PendingSurvivalSession {
+                    session_id: row.get(0)?,
+                    project_name: row.get(1)?,
+                    cwd: row.get(2)?,
+                    git_branch: row.get(3)?,
+                    session_end_time: row.get(4)?,
+                })
+            })?
+            .collect::<Result<Vec<_>>>()?;
+        Ok(sessions)
+    })
+}
+
+/// Store a session's line survival for a window
+pub fn store_line_survival(
+    session_id: &str,
+    project_name: &str,
+    window_days: u32,
+    survival: &LineSurvival,
+) -> Result<()> {
+    with_connection_mut(|conn| {
+        conn.execute(
+            "INSERT OR REPLACE INTO line_survival (
+                session_id, window_days, project_name, lines_added, lines_surviving, computed_at
+            ) VALUES (?, ?, ?, ?, ?, ?)",
+            params![
+                session_id,
+                window_days,
+                project_name,
+                survival.lines_added,
+                survival.lines_surviving,
+                Utc::now().timestamp_millis(),
+            ],
+        )?;
+        Ok(())
+    })
+}
+
+/// Line survival per session and project, optionally limited to one project
+pub fn get_line_survival(project_name: Option<&str>, window_days: u32) -> Result<LineSurvivalStats> {
+    with_connection_mut(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT l.session_id, l.project_name, l.lines_added, l.lines_surviving
+             FROM line_survival l
+             LEFT JOIN agent_sessions s ON s.session_id = l.session_id
+             WHERE l.window_days = ?1 AND (?2 IS NULL OR l.project_name = ?2)
+             ORDER BY s.session_end_time DESC",
+        )?;
+        let sessions = stmt
+            .query_map(params![window_days, project_name], |row| {
+                let lines_added: i64 = row.get(2)?;
+                let lines_surviving: i64 = row.get(3)?;
+                Ok(SessionLineSurvival {
+                    session_id: row.get(0)?,
+                    project_name: row.get(1)?,
+                    lines_added,
+                    lines_surviving,
+                    survival_rate: survival_rate(lines_added, lines_surviving),
+                })
+            })?
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut stmt = conn.prepare(
+            "SELECT project_name, COUNT(*), SUM(lines_added), SUM(lines_surviving)
+             FROM line_survival
+             WHERE window_days = ?1 AND (?2 IS NULL OR project_name = ?2)
+             GROUP BY project_name
+             ORDER BY SUM(lines_added) DESC, project_name",
+        )?;
+        let projects = stmt
+            .query_map(params![window_days, project_name], |row| {
+                let lines_added: i64 = row.get(2)?;
+                let lines_surviving: i64 = row.get(3)?;
+                Ok(ProjectLineSurvival {
+                    project_name: row.get(0)?,
+                    session_count: row.get(1)?,
+                    lines_added,
+                    lines_surviving,
+                    survival_rate: survival_rate(lines_added, lines_surviving),
+                })
+            })?
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(LineSurvivalStats {
+            window_days,
+            projects,
+            sessions,
+        })
+    })
+}
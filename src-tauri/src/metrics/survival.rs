@@ -0,0 +1,130 @@
+//! Survival of AI-assisted lines
+//!
+//! Once a session's linked commits are `window_days` old, the lines they added
+//! are blamed on the branch as it was `window_days` after the session ended.
+//! The share still attributed to the session's commits is its survival rate;
+//! the rest was rewritten or removed (churn). Results are stored per session
+//! and window in `line_survival`.
+
+use super::store;
+use crate::database::get_session_commits;
+use crate::git_diff::{compute_line_survival, open_repository};
+use crate::logging::log_info;
+use chrono::Utc;
+use serde::Serialize;
+
+/// Window used when none is requested
+pub const DEFAULT_SURVIVAL_WINDOW_DAYS: u32 = 14;
+
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+
+/// Ended session with linked commits that hasn't been analyzed for a window
+pub struct PendingSurvivalSession {
+    pub session_id: String,
+    pub project_name: String,
+    pub cwd: String,
+    pub git_branch: Option<String>,
+    pub session_end_time: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionLineSurvival {
+    pub session_id: String,
+    pub project_name: String,
+    pub lines_added: i64,
+    pub lines_surviving: i64,
+    /// Percentage of added lines still present
+    pub survival_rate: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectLineSurvival {
+    pub project_name: String,
+    pub session_count: i64,
+    pub lines_added: i64,
+    pub lines_surviving: i64,
+    pub survival_rate: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LineSurvivalStats {
+    pub window_days: u32,
+    /// Projects with the most added lines first
+    pub projects: Vec<ProjectLineSurvival>,
+    /// Most recently ended sessions first
+    pub sessions: Vec<SessionLineSurvival>,
+}
+
+/// Percentage of added lines that survived
+pub fn survival_rate(lines_added: i64, lines_surviving: i64) -> Option<f64> {
+    (lines_added > 0).then(|| lines_surviving as f64 / lines_added as f64 * 100.0)
+}
+
+/// Analyze ended sessions whose window has passed; returns how many were stored
+///
+/// Sessions whose repository can't be opened are left pending and retried on
+/// the next run.
+pub fn analyze_pending_sessions(window_days: u32) -> Result<usize, String> {
+    let window_ms = i64::from(window_days) * DAY_MS;
+    let pending = store::get_pending_survival_sessions(
+        window_days,
+        Utc::now().timestamp_millis() - window_ms,
+    )
+    .map_err(|e| format!("Failed to get sessions to analyze: {}", e))?;
+
+    let mut analyzed = 0;
+    for session in pending {
+        let Ok(repo) = open_repository(&session.cwd) else {
+            continue;
+        };
+        let commits: Vec<String> = get_session_commits(&session.session_id)
+            .map_err(|e| format!("Failed to get session commits: {}", e))?
+            .into_iter()
+            .map(|commit| commit.commit_hash)
+            .collect();
+
+        let survival = compute_line_survival(
+            &repo,
+            &commits,
+            session.git_branch.as_deref(),
+            session.session_end_time + window_ms,
+        )?;
+        if let Some(survival) = survival {
+            store::store_line_survival(
+                &session.session_id,
+                &session.project_name,
+                window_days,
+                &survival,
+            )
+            .map_err(|e| format!("Failed to store line survival: {}", e))?;
+            analyzed += 1;
+        }
+    }
+
+    if analyzed > 0 {
+        log_info(
+            "metrics",
+            &format!(
+                "✓ Analyzed line survival for {} sessions ({} day window)",
+                analyzed, window_days
+            ),
+        )
+        .unwrap_or_default();
+    }
+
+    Ok(analyzed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_survival_rate() {
+        assert_eq!(survival_rate(200, 150), Some(75.0));
+        assert_eq!(survival_rate(0, 0), None);
+    }
+}
@@ -0,0 +1,292 @@
+//! Recurring improvement tips
+//!
+//! Tips are stored per session in the `*_improvement_tips` columns of
+//! `session_metrics`. To show which ones keep coming back, tips are grouped
+//! within their category by text similarity: each tip is reduced to its
+//! significant words and joins the first group whose words overlap its own
+//! enough (Jaccard similarity). Groups are ranked by the number of sessions
+//! they recur in, then by the number of projects.
+
+use serde::Serialize;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// Categories and the `session_metrics` columns their tips are stored in.
+/// Context tips are a JSON array, the others one tip per line.
+pub const TIP_COLUMNS: [(&str, &str); 7] = [
+    ("usage", "usage_improvement_tips"),
+    ("error", "error_improvement_tips"),
+    ("engagement", "engagement_improvement_tips"),
+    ("quality", "quality_improvement_tips"),
+    ("performance", "performance_improvement_tips"),
+    ("git_diff", "git_diff_improvement_tips"),
+    ("context", "context_improvement_tips"),
+];
+
+/// Share of significant words two tips must have in common to be grouped
+const SIMILARITY_THRESHOLD: f64 = 0.5;
+
+/// Words that say nothing about what a tip is about
+const STOP_WORDS: [&str; 24] = [
+    "the", "and", "for", "you", "your", "with", "that", "this", "are", "was", "can", "from",
+    "into", "when", "before", "after", "more", "less", "use", "try", "consider", "make", "could",
+    "should",
+];
+
+/// One tip of one session
+#[derive(Debug, Clone)]
+pub struct SessionTip {
+    pub session_id: String,
+    pub project_name: String,
+    pub category: &'static str,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectTipCount {
+    pub project_name: String,
+    pub session_count: i64,
+}
+
+/// Similar tips of one category
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TipCluster {
+    pub category: String,
+    /// The wording seen most often in the group
+    pub tip: String,
+    /// Other wordings grouped with it
+    pub variants: Vec<String>,
+    pub session_count: i64,
+    /// Sessions per project, most first
+    pub projects: Vec<ProjectTipCount>,
+}
+
+/// Ranked "top things to fix" across sessions
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TipsReport {
+    /// Sessions that have any tips
+    pub sessions_with_tips: i64,
+    pub clusters: Vec<TipCluster>,
+}
+
+/// Split a tips column into its tips
+pub fn split_tips(value: &str) -> Vec<String> {
+    let tips = match serde_json::from_str::<Vec<String>>(value) {
+        Ok(tips) => tips,
+        Err(_) => value.lines().map(str::to_string).collect(),
+    };
+    tips.into_iter()
+        .map(|tip| {
+            tip.trim()
+                .trim_start_matches(['-', '*', '•'])
+                .trim()
+                .to_string()
+        })
+        .filter(|tip| !tip.is_empty())
+        .collect()
+}
+
+/// Significant words of a tip, lowercased and without plural endings
+fn words(text: &str) -> BTreeSet<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .map(str::to_lowercase)
+        .filter(|word| word.len() >= 3 && !STOP_WORDS.contains(&word.as_str()))
+        .map(|word| match word.strip_suffix('s') {
+            Some(stem) if stem.len() >= 3 && !word.ends_with("ss") => stem.to_string(),
+            _ => word,
+        })
+        .collect()
+}
+
+fn similarity(a: &BTreeSet<String>, b: &BTreeSet<String>) -> f64 {
+    let union = a.union(b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(b).count() as f64 / union as f64
+}
+
+#[derive(Default)]
+struct Group {
+    words: BTreeSet<String>,
+    /// Wording -> times seen, in the order first seen
+    wordings: Vec<(String, usize)>,
+    /// Project -> sessions
+    sessions: BTreeMap<String, BTreeSet<String>>,
+}
+
+/// Group similar tips and rank the groups, keeping the first `limit`
+pub fn cluster_tips(tips: &[SessionTip], limit: usize) -> TipsReport {
+    let mut groups: HashMap<&str, Vec<Group>> = HashMap::new();
+    for tip in tips {
+        let tip_words = words(&tip.text);
+        let category = groups.entry(tip.category).or_default();
+        let index = match category.iter().position(|group| {
+            if tip_words.is_empty() {
+                group.wordings.iter().any(|(text, _)| text == &tip.text)
+            } else {
+                similarity(&group.words, &tip_words) >= SIMILARITY_THRESHOLD
+            }
+        }) {
+            Some(index) => index,
+            None => {
+                category.push(Group {
+                    words: tip_words,
+                    ..Default::default()
+                });
+                category.len() - 1
+            }
+        };
+
+        let group = &mut category[index];
+        match group
+            .wordings
+            .iter_mut()
+            .find(|(text, _)| text == &tip.text)
+        {
+            Some((_, count)) => *count += 1,
+            None => group.wordings.push((tip.text.clone(), 1)),
+        }
+        group
+            .sessions
+            .entry(tip.project_name.clone())
+            .or_default()
+            .insert(tip.session_id.clone());
+    }
+
+    let mut clusters: Vec<TipCluster> = groups
+        .into_iter()
+        .flat_map(|(category, groups)| groups.into_iter().map(move |group| (category, group)))
+        .map(|(category, group)| {
+            let mut wordings = group.wordings;
+            // Stable, so ties keep the wording seen first
+            wordings.sort_by(|(_, a), (_, b)| b.cmp(a));
+            let mut wordings = wordings.into_iter().map(|(text, _)| text);
+            let tip = wordings.next().unwrap_or_default();
+
+            let mut projects: Vec<ProjectTipCount> = group
+                .sessions
+                .into_iter()
+                .map(|(project_name, sessions)| ProjectTipCount {
+                    project_name,
+                    session_count: sessions.len() as i64,
+                })
+                .collect();
+            projects.sort_by_key(|project| Reverse(project.session_count));
+
+            TipCluster {
+                category: category.to_string(),
+                tip,
+                variants: wordings.collect(),
+                session_count: projects.iter().map(|project| project.session_count).sum(),
+                projects,
+            }
+        })
+        .collect();
+    clusters.sort_by(|a, b| {
+        b.session_count
+            .cmp(&a.session_count)
+            .then(b.projects.len().cmp(&a.projects.len()))
+            .then_with(|| a.category.cmp(&b.category))
+            .then_with(|| a.tip.cmp(&b.tip))
+    });
+    clusters.truncate(limit);
+
+    let sessions_with_tips = tips
+        .iter()
+        .map(|tip| tip.session_id.as_str())
+        .collect::<BTreeSet<_>>()
+        .len() as i64;
+
+    TipsReport {
+        sessions_with_tips,
+        clusters,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tip(session_id: &str, project_name: &str, category: &'static str, text: &str) -> SessionTip {
+        SessionTip {
+            session_id: session_id.to_string(),
+            project_name: project_name.to_string(),
+            category,
+            text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_split_tips() {
+        assert_eq!(
+            split_tips("- Add tests\n\n• Run the linter \n"),
+            ["Add tests", "Run the linter"]
+        );
+        assert_eq!(
+            split_tips(r#"["Compact earlier", " "]"#),
+            ["Compact earlier"]
+        );
+    }
+
+    #[test]
+    fn test_similar_tips_are_clustered_and_ranked() {
+        let tips = [
+            tip("s1", "api", "quality", "Add tests for edge cases"),
+            tip("s2", "api", "quality", "Add more tests for the edge cases"),
+            tip("s3", "web", "quality", "Add tests for edge cases"),
+            // Same words in another category stay apart
+            tip("s4", "web", "error", "Add tests for edge cases"),
+            tip(
+                "s1",
+                "api",
+                "usage",
+                "Break large requests into smaller steps",
+            ),
+            tip(
+                "s2",
+                "api",
+                "usage",
+                "Break large requests into smaller steps",
+            ),
+        ];
+        let report = cluster_tips(&tips, 10);
+
+        assert_eq!(report.sessions_with_tips, 4);
+        let summary: Vec<(&str, &str, i64)> = report
+            .clusters
+            .iter()
+            .map(|c| (c.category.as_str(), c.tip.as_str(), c.session_count))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("quality", "Add tests for edge cases", 3),
+                ("usage", "Break large requests into smaller steps", 2),
+                ("error", "Add tests for edge cases", 1),
+            ]
+        );
+        assert_eq!(
+            report.clusters[0].variants,
+            ["Add more tests for the edge cases"]
+        );
+        assert_eq!(
+            report.clusters[0].projects,
+            [
+                ProjectTipCount {
+                    project_name: "api".to_string(),
+                    session_count: 2
+                },
+                ProjectTipCount {
+                    project_name: "web".to_string(),
+                    session_count: 1
+                },
+            ]
+        );
+
+        assert_eq!(cluster_tips(&tips, 1).clusters.len(), 1);
+    }
+}
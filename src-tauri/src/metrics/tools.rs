@@ -0,0 +1,250 @@
+//! Per-tool usage statistics
+//!
+//! Every `tool_use` block is counted against its tool name, and the matching
+//! `tool_result` contributes its outcome (`is_error`) and size. Results whose
+//! `tool_use` isn't in the session are attributed to "unknown".
+
+use crate::providers::canonical::{CanonicalMessage, ContentBlock, ContentValue};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+
+/// Tool name used for results without a matching tool_use
+const UNKNOWN_TOOL: &str = "unknown";
+
+/// Tool input fields that hold a file path
+const PATH_KEYS: [&str; 5] = [
+    "file_path",
+    "filePath",
+    "path",
+    "notebook_path",
+    "target_file",
+];
+
+/// Usage of one tool within a session
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SessionToolUsage {
+    pub tool_name: String,
+    pub invocation_count: i64,
+    pub result_count: i64,
+    pub error_count: i64,
+    pub total_result_bytes: i64,
+}
+
+/// Aggregated usage of one tool across sessions
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolStat {
+    pub tool_name: String,
+    pub invocation_count: i64,
+    pub error_count: i64,
+    /// Percentage of results reported as errors
+    pub failure_rate: Option<f64>,
+    /// Average tool_result content size in bytes
+    pub avg_result_bytes: Option<f64>,
+    pub session_count: i64,
+}
+
+impl ToolStat {
+    pub fn new(
+        tool_name: String,
+        invocation_count: i64,
+        result_count: i64,
+        error_count: i64,
+        total_result_bytes: i64,
+        session_count: i64,
+    ) -> Self {
+        let per_result =
+            |value: i64| (result_count > 0).then(|| value as f64 / result_count as f64);
+
+        Self {
+            tool_name,
+            invocation_count,
+            error_count,
+            failure_rate: per_result(error_count).map(|rate| rate * 100.0),
+            avg_result_bytes: per_result(total_result_bytes),
+            session_count,
+        }
+    }
+}
+
+/// Most used tools for a project
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectToolStats {
+    pub project_name: String,
+    pub tools: Vec<ToolStat>,
+}
+
+/// Tool usage across all (or one project's) sessions
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolUsageStats {
+    /// All tools, most invoked first
+    pub tools: Vec<ToolStat>,
+    /// Top tools for each project, most invoked first
+    pub top_tools_by_project: Vec<ProjectToolStats>,
+}
+
+fn stats_for<'a>(
+    usage: &'a mut BTreeMap<String, SessionToolUsage>,
+    name: &str,
+) -> &'a mut SessionToolUsage {
+    usage
+        .entry(name.to_string())
+        .or_insert_with(|| SessionToolUsage {
+            tool_name: name.to_string(),
+            ..Default::default()
+        })
+}
+
+/// Count tool invocations and results in a session, ordered by tool name
+pub fn compute_tool_usage(messages: &[CanonicalMessage]) -> Vec<SessionToolUsage> {
+    let mut usage: BTreeMap<String, SessionToolUsage> = BTreeMap::new();
+    let mut tool_names: HashMap<&str, &str> = HashMap::new();
+
+    for message in messages {
+        let ContentValue::Structured(blocks) = &message.message.content else {
+            continue;
+        };
+
+        for block in blocks {
+            match block {
+                ContentBlock::ToolUse { id, name, .. } => {
+                    tool_names.insert(id.as_str(), name.as_str());
+                    stats_for(&mut usage, name).invocation_count += 1;
+                }
+                ContentBlock::ToolResult {
+                    tool_use_id,
+                    content,
+                    is_error,
+                } => {
+                    let name = tool_names
+                        .get(tool_use_id.as_str())
+                        .copied()
+                        .unwrap_or(UNKNOWN_TOOL);
+                    let stats = stats_for(&mut usage, name);
+                    stats.result_count += 1;
+                    stats.total_result_bytes += content.len() as i64;
+                    if *is_error == Some(true) {
+                        stats.error_count += 1;
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    usage.into_values().collect()
+}
+
+/// File paths in a tool's input, at any depth
+pub fn input_paths<'a>(input: &'a Value, paths: &mut Vec<&'a str>) {
+    match input {
+        Value::Object(map) => {
+            for (key, value) in map {
+                match value {
+                    Value::String(path) if PATH_KEYS.contains(&key.as_str()) => paths.push(path),
+                    _ => input_paths(value, paths),
+                }
+            }
+        }
+        Value::Array(values) => values.iter().for_each(|value| input_paths(value, paths)),
+        _ => {}
+    }
+}
+
+/// File paths named by tool_use inputs, in order of first use
+pub fn touched_file_paths(messages: &[CanonicalMessage]) -> Vec<String> {
+    let mut paths: Vec<String> = Vec::new();
+
+    for message in messages {
+        let ContentValue::Structured(blocks) = &message.message.content else {
+            continue;
+        };
+
+        for block in blocks {
+            let ContentBlock::ToolUse { input, .. } = block else {
+                continue;
+            };
+            let mut tool_paths = Vec::new();
+            input_paths(input, &mut tool_paths);
+            for path in tool_paths {
+                if !path.is_empty() && !paths.iter().any(|seen| seen == path) {
+                    paths.push(path.to_string());
+                }
+            }
+        }
+    }
+
+    paths
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::compute::parse_messages;
+    use serde_json::json;
+
+    #[test]
+    fn test_compute_tool_usage() {
+        let content = [
+            json!({
+                "uuid": "a1", "timestamp": "2025-01-01T10:00:00Z", "type": "assistant",
+                "sessionId": "s1", "provider": "claude-code",
+                "message": {"role": "assistant", "content": [
+                    {"type": "tool_use", "id": "t1", "name": "Bash", "input": {}},
+                    {"type": "tool_use", "id": "t2", "name": "Bash", "input": {}},
+                    {"type": "tool_use", "id": "t3", "name": "Read", "input": {}}
+                ]}
+            }),
+            json!({
+                "uuid": "r1", "timestamp": "2025-01-01T10:00:01Z", "type": "user",
+                "sessionId": "s1", "provider": "claude-code",
+                "message": {"role": "user", "content": [
+                    {"type": "tool_result", "tool_use_id": "t1", "content": "ok"},
+                    {"type": "tool_result", "tool_use_id": "t2", "content": "command failed", "is_error": true},
+                    {"type": "tool_result", "tool_use_id": "t3", "content": "0123456789"},
+                    {"type": "tool_result", "tool_use_id": "missing", "content": "?"}
+                ]}
+            }),
+        ]
+        .map(|value| value.to_string())
+        .join("\n");
+
+        let usage = compute_tool_usage(&parse_messages(&content));
+        let names: Vec<&str> = usage.iter().map(|u| u.tool_name.as_str()).collect();
+        assert_eq!(names, vec!["Bash", "Read", "unknown"]);
+
+        assert_eq!(usage[0].invocation_count, 2);
+        assert_eq!(usage[0].result_count, 2);
+        assert_eq!(usage[0].error_count, 1);
+        assert_eq!(usage[0].total_result_bytes, 16);
+        assert_eq!(usage[2].invocation_count, 0);
+        assert_eq!(usage[2].result_count, 1);
+
+        let stat = ToolStat::new("Bash".to_string(), 2, 2, 1, 16, 1);
+        assert_eq!(stat.failure_rate, Some(50.0));
+        assert_eq!(stat.avg_result_bytes, Some(8.0));
+    }
+
+    #[test]
+    fn test_touched_file_paths() {
+        let content = json!({
+            "uuid": "a1", "timestamp": "2025-01-01T10:00:00Z", "type": "assistant",
+            "sessionId": "s1", "provider": "claude-code",
+            "message": {"role": "assistant", "content": [
+                {"type": "tool_use", "id": "t1", "name": "Edit", "input": {"file_path": "/repo/a.rs"}},
+                {"type": "tool_use", "id": "t2", "name": "Grep", "input": {"pattern": "x", "path": "/repo/lib"}},
+                {"type": "tool_use", "id": "t3", "name": "Read", "input": {"file_path": "/repo/a.rs"}},
+                {"type": "tool_use", "id": "t4", "name": "Bash", "input": {"command": "ls"}}
+            ]}
+        })
+        .to_string();
+
+        assert_eq!(
+            touched_file_paths(&parse_messages(&content)),
+            vec!["/repo/a.rs", "/repo/lib"]
+        );
+    }
+}
@@ -0,0 +1,324 @@
+//! Database schema migrations
+//!
+//! Applied by tauri-plugin-sql when the app starts, and by
+//! `database::init_in_memory_database` for tests.
+
+use tauri_plugin_sql::{Migration, MigrationKind};
+
+/// Every migration, oldest first
+pub fn migrations() -> Vec<Migration> {
+    vec![
+        Migration {
+            version: 1,
+            description: "create_agent_sessions",
+            sql: include_str!("../migrations/001_create_agent_sessions.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 2,
+            description: "create_session_metrics",
+            sql: include_str!("../migrations/002_create_session_metrics.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 3,
+            description: "add_cwd_column",
+            sql: include_str!("../migrations/003_add_cwd_column.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 4,
+            description: "add_sync_failed_reason",
+            sql: include_str!("../migrations/004_add_sync_failed_reason.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 5,
+            description: "unique_session_id",
+            sql: include_str!("../migrations/005_unique_session_id.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 6,
+            description: "unique_session_metrics",
+            sql: include_str!("../migrations/006_unique_session_id.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 7,
+            description: "create_projects",
+            sql: include_str!("../migrations/007_create_projects.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 8,
+            description: "add_project_foreign_key",
+            sql: include_str!("../migrations/008_add_project_foreign_key.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 9,
+            description: "create_session_assessments",
+            sql: include_str!("../migrations/009_create_session_assessments.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 10,
+            description: "add_phase_analysis",
+            sql: include_str!("../migrations/010_add_phase_analysis.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 11,
+            description: "add_core_metrics_tracking",
+            sql: include_str!("../migrations/011_add_core_metrics_tracking.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 12,
+            description: "add_category_improvement_tips",
+            sql: include_str!("../migrations/012_add_category_improvement_tips.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 13,
+            description: "add_file_hash",
+            sql: include_str!("../migrations/013_add_file_hash.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 14,
+            description: "add_git_tracking",
+            sql: include_str!("../migrations/014_add_git_tracking.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 15,
+            description: "add_git_diff_metrics",
+            sql: include_str!("../migrations/015_add_git_diff_metrics.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 16,
+            description: "add_git_diff_improvement_tips",
+            sql: include_str!("../migrations/016_add_git_diff_improvement_tips.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 17,
+            description: "add_context_management_metrics",
+            sql: include_str!("../migrations/017_add_context_management_metrics.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 18,
+            description: "update_context_metrics_structure",
+            sql: include_str!("../migrations/018_update_context_metrics_structure.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 19,
+            description: "remove_per_message_tokens",
+            sql: include_str!("../migrations/019_remove_per_message_tokens.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 20,
+            description: "remove_peak_context_tokens",
+            sql: include_str!("../migrations/020_remove_peak_context_tokens.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 21,
+            description: "create_cursor_project_paths",
+            sql: include_str!("../migrations/021_create_cursor_project_paths.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 22,
+            description: "create_tool_usage_stats",
+            sql: include_str!("../migrations/022_create_tool_usage_stats.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 23,
+            description: "add_context_utilization_tracking",
+            sql: include_str!("../migrations/023_add_context_utilization_tracking.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 24,
+            description: "create_usage_rollups",
+            sql: include_str!("../migrations/024_create_usage_rollups.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 25,
+            description: "create_habit_days",
+            sql: include_str!("../migrations/025_create_habit_days.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 26,
+            description: "create_session_commits",
+            sql: include_str!("../migrations/026_create_session_commits.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 27,
+            description: "create_line_survival",
+            sql: include_str!("../migrations/027_create_line_survival.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 28,
+            description: "create_git_diff_cache",
+            sql: include_str!("../migrations/028_create_git_diff_cache.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 29,
+            description: "create_uncommitted_snapshots",
+            sql: include_str!("../migrations/029_create_uncommitted_snapshots.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 30,
+            description: "create_context_file_snapshots",
+            sql: include_str!("../migrations/030_create_context_file_snapshots.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 31,
+            description: "create_project_aliases",
+            sql: include_str!("../migrations/031_create_project_aliases.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 32,
+            description: "create_scan_cache",
+            sql: include_str!("../migrations/032_create_scan_cache.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 33,
+            description: "create_uploaded_hashes",
+            sql: include_str!("../migrations/033_create_uploaded_hashes.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 34,
+            description: "create_upload_bases",
+            sql: include_str!("../migrations/034_create_upload_bases.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 35,
+            description: "create_conversion_errors",
+            sql: include_str!("../migrations/035_create_conversion_errors.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 36,
+            description: "add_conversion_skipped_lines",
+            sql: include_str!("../migrations/036_add_conversion_skipped_lines.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 37,
+            description: "add_active_duration",
+            sql: include_str!("../migrations/037_add_active_duration.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 38,
+            description: "add_upload_queue_state",
+            sql: include_str!("../migrations/038_add_upload_queue_state.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 39,
+            description: "add_project_sync_exclusion",
+            sql: include_str!("../migrations/039_add_project_sync_exclusion.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 40,
+            description: "add_metadata_dirty",
+            sql: include_str!("../migrations/040_add_metadata_dirty.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 41,
+            description: "add_upload_superseded",
+            sql: include_str!("../migrations/041_add_upload_superseded.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 42,
+            description: "add_session_resumption",
+            sql: include_str!("../migrations/042_add_session_resumption.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 43,
+            description: "create_team_stats_cache",
+            sql: include_str!("../migrations/043_create_team_stats_cache.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 44,
+            description: "add_session_tenant",
+            sql: include_str!("../migrations/044_add_session_tenant.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 45,
+            description: "create_project_metadata_uploads",
+            sql: include_str!("../migrations/045_create_project_metadata_uploads.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 46,
+            description: "create_ingest_skips",
+            sql: include_str!("../migrations/046_create_ingest_skips.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 47,
+            description: "add_sync_skip_reason",
+            sql: include_str!("../migrations/047_add_sync_skip_reason.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 48,
+            description: "create_project_merges",
+            sql: include_str!("../migrations/048_create_project_merges.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 49,
+            description: "create_archived_sessions",
+            sql: include_str!("../migrations/049_create_archived_sessions.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 50,
+            description: "add_scan_cache_converter_version",
+            sql: include_str!("../migrations/050_add_scan_cache_converter_version.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 51,
+            description: "add_transcript_deferred",
+            sql: include_str!("../migrations/051_add_transcript_deferred.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 52,
+            description: "add_uploaded_hashes_tenant",
+            sql: include_str!("../migrations/052_add_uploaded_hashes_tenant.sql"),
+            kind: MigrationKind::Up,
+        },
+    ]
+}
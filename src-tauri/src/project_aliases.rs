@@ -0,0 +1,256 @@
+//! Project name aliases
+//!
+//! Providers derive project names differently (folder name, git remote, a
+//! user mapping), so one repository can show up under several names. An alias
+//! maps one of those names to a canonical name: sessions are stored, linked to
+//! projects and uploaded under the canonical name. Adding an alias also renames
+//! what is already stored under it; removing one only affects later sessions.
+
+use crate::database::with_connection_mut;
+use crate::error::GuideModeError;
+use chrono::Utc;
+use rusqlite::{params, Connection, Result};
+use serde::Serialize;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectAlias {
+    pub alias: String,
+    pub canonical_name: String,
+    pub created_at: i64, // Unix milliseconds
+}
+
+/// Tables that store a project name per session or per project
+const PROJECT_NAME_TABLES: [(&str, &str); 5] = [
+    ("agent_sessions", "project_name"),
+    ("projects", "name"),
+    ("tool_usage_stats", "project_name"),
+    ("line_survival", "project_name"),
+    ("session_usage", "project_name"),
+];
+
+/// The canonical name for a project name, or the name itself if it isn't an alias
+pub(crate) fn resolve(conn: &Connection, name: &str) -> String {
+    conn.query_row(
+        "SELECT canonical_name FROM project_aliases WHERE alias = ?",
+        params![name],
+        |row| row.get(0),
+    )
+    .unwrap_or_else(|_| name.to_string())
+}
+
+fn set(
+    conn: &mut Connection,
+    alias: &str,
+    canonical_name: &str,
+) -> std::result::Result<ProjectAlias, GuideModeError> {
+    if alias.eq_ignore_ascii_case(&resolve(conn, canonical_name)) {
+        return Err(GuideModeError::Validation(format!(
+            "\"{}\" can't be an alias of itself",
+            alias
+        )));
+    }
+
+    let tx = conn.transaction()?;
+    let alias = add(&tx, alias, canonical_name)?;
+    tx.commit()?;
+    Ok(alias)
+}
+
+/// Add an alias and rename what is stored under it, within the caller's transaction
+pub(crate) fn add(conn: &Connection, alias: &str, canonical_name: &str) -> Result<ProjectAlias> {
+    // Aliases always point at a canonical name, never at another alias
+    let canonical_name = resolve(conn, canonical_name);
+    if alias.eq_ignore_ascii_case(&canonical_name) {
+        return Err(rusqlite::Error::InvalidParameterName(format!(
+            "\"{}\" can't be an alias of itself",
+            alias
+        )));
+    }
+
+    let now = Utc::now().timestamp_millis();
+    conn.execute(
+        "INSERT OR REPLACE INTO project_aliases (alias, canonical_name, created_at)
+         VALUES (?, ?, ?)",
+        params![alias, canonical_name, now],
+    )?;
+    // Aliases of the alias now point at its canonical name too
+    conn.execute(
+        "UPDATE project_aliases SET canonical_name = ? WHERE canonical_name = ? COLLATE NOCASE",
+        params![canonical_name, alias],
+    )?;
+
+    for (table, column) in PROJECT_NAME_TABLES {
+        conn.execute(
+            &format!(
+                "UPDATE {table} SET {column} = ?1 WHERE {column} = ?2 COLLATE NOCASE",
+                table = table,
+                column = column
+            ),
+            params![canonical_name, alias],
+        )?;
+    }
+    rebuild_usage_rollups(conn, &canonical_name)?;
+
+    Ok(ProjectAlias {
+        alias: alias.to_string(),
+        canonical_name,
+        created_at: now,
+    })
+}
+
+/// Recompute the usage rollups of a project from its sessions' contributions,
+/// merging the rows that were kept under its aliases
+fn rebuild_usage_rollups(conn: &Connection, project_name: &str) -> Result<()> {
+    conn.execute(
+        "DELETE FROM usage_rollups
+         WHERE project_name = ?1
+            OR project_name IN (SELECT alias FROM project_aliases WHERE canonical_name = ?1)",
+        params![project_name],
+    )?;
+    for (granularity, period) in [("day", "day"), ("week", "week")] {
+        conn.execute(
+            &format!(
+                "INSERT INTO usage_rollups
+                 SELECT ?1, {period}, provider, project_name, COUNT(*),
+                        SUM(input_tokens), SUM(output_tokens), SUM(cache_created),
+                        SUM(cache_read), SUM(estimated_cost), SUM(active_minutes)
+                 FROM session_usage
+                 WHERE project_name = ?2
+                 GROUP BY {period}, provider, project_name",
+                period = period
+            ),
+            params![granularity, project_name],
+        )?;
+    }
+    Ok(())
+}
+
+fn list(conn: &Connection) -> Result<Vec<ProjectAlias>> {
+    let mut stmt = conn.prepare(
+        "SELECT alias, canonical_name, created_at
+         FROM project_aliases
+         ORDER BY canonical_name, alias",
+    )?;
+    let aliases = stmt
+        .query_map([], |row| {
+            Ok(ProjectAlias {
+                alias: row.get(0)?,
+                canonical_name: row.get(1)?,
+                created_at: row.get(2)?,
+            })
+        })?
+        .collect::<Result<Vec<_>>>()?;
+    Ok(aliases)
+}
+
+/// The canonical name for a project name
+pub fn canonical_project_name(name: &str) -> String {
+    with_connection_mut(|conn| Ok(resolve(conn, name))).unwrap_or_else(|_| name.to_string())
+}
+
+/// Make `alias` an alias of `canonical_name`, renaming what is stored under it
+pub fn set_project_alias(
+    alias: &str,
+    canonical_name: &str,
+) -> std::result::Result<ProjectAlias, GuideModeError> {
+    let alias = alias.trim();
+    let canonical_name = canonical_name.trim();
+    if alias.is_empty() || canonical_name.is_empty() {
+        return Err(GuideModeError::Validation(
+            "Alias and canonical project name must not be empty".to_string(),
+        ));
+    }
+
+    with_connection_mut(|conn| Ok(set(conn, alias, canonical_name)))?
+}
+
+/// Remove an alias; sessions already renamed keep the canonical name
+pub fn remove_project_alias(alias: &str) -> Result<bool, String> {
+    with_connection_mut(|conn| {
+        conn.execute(
+            "DELETE FROM project_aliases WHERE alias = ?",
+            params![alias],
+        )
+    })
+    .map(|deleted| deleted > 0)
+    .map_err(|e| format!("Failed to remove project alias: {}", e))
+}
+
+/// All aliases, grouped by canonical name
+pub fn list_project_aliases() -> Result<Vec<ProjectAlias>, String> {
+    with_connection_mut(|conn| list(conn))
+        .map_err(|e| format!("Failed to list project aliases: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session_usage(conn: &Connection, session_id: &str, project_name: &str, tokens: i64) {
+        conn.execute(
+            "INSERT INTO session_usage (
+                session_id, provider, project_name, day, week, input_tokens, updated_at
+            ) VALUES (?, 'claude-code', ?, '2025-01-08', '2025-01-06', ?, 0)",
+            params![session_id, project_name, tokens],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_aliases_resolve_to_canonical_name() {
+        let mut conn = crate::database::test_connection();
+
+        set(&mut conn, "desktop", "guidemode-desktop").unwrap();
+        // Pointing the canonical name elsewhere carries its aliases along
+        set(&mut conn, "guidemode-desktop", "work-guidemode").unwrap();
+        // Targets that are aliases resolve to their canonical name
+        let alias = set(&mut conn, "gm", "desktop").unwrap();
+
+        assert_eq!(alias.canonical_name, "work-guidemode");
+        assert_eq!(resolve(&conn, "Desktop"), "work-guidemode");
+        assert_eq!(resolve(&conn, "guidemode-desktop"), "work-guidemode");
+        assert_eq!(resolve(&conn, "other"), "other");
+        assert_eq!(list(&conn).unwrap().len(), 3);
+
+        assert!(matches!(
+            set(&mut conn, "work-guidemode", "gm"),
+            Err(GuideModeError::Validation(_))
+        ));
+    }
+
+    #[test]
+    fn test_alias_merges_stored_usage() {
+        let mut conn = crate::database::test_connection();
+        session_usage(&conn, "s1", "desktop", 100);
+        session_usage(&conn, "s2", "guidemode-desktop", 50);
+        conn.execute_batch(
+            "INSERT INTO usage_rollups (granularity, period_start, provider, project_name, session_count, input_tokens)
+             VALUES ('day', '2025-01-08', 'claude-code', 'desktop', 1, 100),
+                    ('day', '2025-01-08', 'claude-code', 'guidemode-desktop', 1, 50)",
+        )
+        .unwrap();
+
+        set(&mut conn, "desktop", "guidemode-desktop").unwrap();
+
+        let rollups: Vec<(String, String, i64, i64)> = conn
+            .prepare(
+                "SELECT granularity, project_name, session_count, input_tokens
+                 FROM usage_rollups ORDER BY granularity",
+            )
+            .unwrap()
+            .query_map([], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+            })
+            .unwrap()
+            .collect::<Result<_>>()
+            .unwrap();
+        assert_eq!(
+            rollups,
+            vec![
+                ("day".to_string(), "guidemode-desktop".to_string(), 2, 150),
+                ("week".to_string(), "guidemode-desktop".to_string(), 2, 150),
+            ]
+        );
+    }
+}
@@ -0,0 +1,298 @@
+//! Merging duplicate projects
+//!
+//! Projects are keyed by working directory, so a repository checked out in
+//! two places, or renamed, accumulates near-duplicates over time. Merging
+//! moves the duplicates' sessions into one target project and deletes the
+//! duplicates. Their names become aliases of the target's, so stored usage
+//! and later sessions follow, and their working directories are remembered
+//! in `project_merges`, so sessions found there later join the target instead
+//! of recreating a duplicate. The server can be told about the merge too, so
+//! the sessions it already has move with them.
+
+use crate::api_paths;
+use crate::config::GuideModeConfig;
+use crate::database::with_connection_mut;
+use crate::error::GuideModeError;
+use crate::project_aliases;
+use chrono::Utc;
+use rusqlite::{params, Connection, Result};
+use serde::Serialize;
+
+struct Project {
+    id: String,
+    name: String,
+    github_repo: Option<String>,
+    cwd: String,
+    created_at: i64,
+}
+
+/// What a merge moved into the target project
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectMerge {
+    pub target_id: String,
+    pub target_name: String,
+    pub github_repo: Option<String>,
+    /// Names of the projects merged in, now aliases of the target's name
+    pub merged_names: Vec<String>,
+    pub moved_sessions: Vec<String>,
+    /// Moved sessions the server already has under a merged project's name
+    pub synced_sessions: usize,
+    pub server_notified: bool,
+    /// Why the server couldn't be told, when it was asked to be
+    pub server_error: Option<String>,
+}
+
+fn project(conn: &Connection, project_id: &str) -> Result<Project> {
+    conn.query_row(
+        "SELECT id, name, github_repo, cwd, created_at FROM projects WHERE id = ?",
+        params![project_id],
+        |row| {
+            Ok(Project {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                github_repo: row.get(2)?,
+                cwd: row.get(3)?,
+                created_at: row.get(4)?,
+            })
+        },
+    )
+}
+
+fn merge(conn: &mut Connection, source_ids: &[String], target_id: &str) -> Result<ProjectMerge> {
+    if source_ids.is_empty() || source_ids.iter().any(|id| id == target_id) {
+        return Err(rusqlite::Error::InvalidParameterName(
+            "Pick at least one project to merge, other than the target".to_string(),
+        ));
+    }
+
+    let tx = conn.transaction()?;
+    let target = project(&tx, target_id)?;
+    let now = Utc::now().timestamp_millis();
+    let mut merged = ProjectMerge {
+        target_id: target.id.clone(),
+        target_name: target.name.clone(),
+        github_repo: target.github_repo.clone(),
+        merged_names: Vec::new(),
+        moved_sessions: Vec::new(),
+        synced_sessions: 0,
+        server_notified: false,
+        server_error: None,
+    };
+
+    for source_id in source_ids {
+        let source = project(&tx, source_id)?;
+        if !source.name.eq_ignore_ascii_case(&target.name) {
+            project_aliases::add(&tx, &source.name, &target.name)?;
+            tx.execute(
+                "DELETE FROM project_metadata_uploads WHERE project_name = ?",
+                params![source.name],
+            )?;
+        }
+
+        let sessions = {
+            let mut stmt = tx.prepare(
+                "SELECT session_id, synced_to_server FROM agent_sessions WHERE project_id = ?",
+            )?;
+            let sessions = stmt
+                .query_map(params![source.id], |row| {
+                    Ok((row.get::<_, String>(0)?, row.get::<_, bool>(1)?))
+                })?
+                .collect::<Result<Vec<_>>>()?;
+            sessions
+        };
+        tx.execute(
+            "UPDATE agent_sessions SET project_id = ?, project_name = ? WHERE project_id = ?",
+            params![target.id, target.name, source.id],
+        )?;
+
+        // Directories merged into the source now lead to the target, as does its own
+        tx.execute(
+            "UPDATE project_merges SET project_id = ? WHERE project_id = ?",
+            params![target.id, source.id],
+        )?;
+        tx.execute(
+            "INSERT OR REPLACE INTO project_merges (cwd, project_id, merged_at) VALUES (?, ?, ?)",
+            params![source.cwd, target.id, now],
+        )?;
+        tx.execute(
+            "UPDATE projects
+             SET github_repo = COALESCE(github_repo, ?), created_at = MIN(created_at, ?)
+             WHERE id = ?",
+            params![source.github_repo, source.created_at, target.id],
+        )?;
+        tx.execute("DELETE FROM projects WHERE id = ?", params![source.id])?;
+
+        merged.github_repo = merged.github_repo.or(source.github_repo);
+        merged.synced_sessions += sessions.iter().filter(|(_, synced)| *synced).count();
+        merged
+            .moved_sessions
+            .extend(sessions.into_iter().map(|(session_id, _)| session_id));
+        merged.merged_names.push(source.name);
+    }
+
+    // Moved sessions that haven't synced follow the target's sync exclusion
+    tx.execute(
+        "UPDATE agent_sessions
+         SET sync_skipped = (SELECT sync_excluded FROM projects WHERE id = ?1)
+         WHERE project_id = ?1 AND synced_to_server = 0 AND sync_skip_reason IS NULL",
+        params![target.id],
+    )?;
+    tx.execute(
+        "UPDATE projects SET updated_at = ? WHERE id = ?",
+        params![now, target.id],
+    )?;
+    tx.commit()?;
+    Ok(merged)
+}
+
+/// Merge projects into `target_id`, moving their sessions and deleting them
+pub fn merge_projects(source_ids: &[String], target_id: &str) -> Result<ProjectMerge, String> {
+    with_connection_mut(|conn| merge(conn, source_ids, target_id)).map_err(|e| match e {
+        rusqlite::Error::QueryReturnedNoRows => "Project not found".to_string(),
+        e => format!("Failed to merge projects: {}", e),
+    })
+}
+
+/// Tell the server which project names were merged into the target, so it
+/// can move the sessions it already has
+pub async fn notify_server(
+    merge: &ProjectMerge,
+    config: &GuideModeConfig,
+) -> Result<(), GuideModeError> {
+    let api_key = config.api_key.as_ref().ok_or("No API key configured")?;
+    let server_url = config
+        .server_url
+        .as_ref()
+        .ok_or("No server URL configured")?;
+
+    let response = reqwest::Client::new()
+        .post(api_paths::api_url(server_url, api_paths::PROJECT_MERGES))
+        .header("Authorization", format!("Bearer {}", api_key))
+        .json(&serde_json::json!({
+            "targetName": merge.target_name,
+            "gitRemoteUrl": merge.github_repo,
+            "sourceNames": merge.merged_names,
+        }))
+        .send()
+        .await?;
+    if !response.status().is_success() {
+        return Err(GuideModeError::from_response("Project merge", response).await);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn database() -> Connection {
+        let conn = crate::database::test_connection();
+        for (id, name, github_repo, cwd, created_at) in [
+            ("p1", "guidemode", None, "/work/guidemode", 20),
+            (
+                "p2",
+                "desktop",
+                Some("guidemode/desktop"),
+                "/old/desktop",
+                10,
+            ),
+            ("p3", "guidemode", None, "/tmp/guidemode", 30),
+        ] {
+            conn.execute(
+                "INSERT INTO projects (id, name, github_repo, cwd, type, created_at, updated_at)
+                 VALUES (?, ?, ?, ?, 'node', ?, 0)",
+                params![id, name, github_repo, cwd, created_at],
+            )
+            .unwrap();
+        }
+        conn
+    }
+
+    fn session(conn: &Connection, session_id: &str, project_id: &str, synced: bool) {
+        conn.execute(
+            "INSERT INTO agent_sessions (
+                id, provider, project_name, session_id, file_name, file_path, file_size,
+                created_at, uploaded_at, synced_to_server, project_id
+            ) SELECT ?1, 'claude-code', name, ?1, 'f.jsonl', '/f', 10, 0, 0, ?2, id
+              FROM projects WHERE id = ?3",
+            params![session_id, synced, project_id],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_merge_moves_sessions_and_metadata() {
+        let mut conn = database();
+        session(&conn, "s1", "p1", false);
+        session(&conn, "s2", "p2", true);
+        session(&conn, "s3", "p3", false);
+        conn.execute("UPDATE projects SET sync_excluded = 1 WHERE id = 'p1'", [])
+            .unwrap();
+
+        let merged = merge(&mut conn, &["p2".to_string(), "p3".to_string()], "p1").unwrap();
+        assert_eq!(merged.merged_names, vec!["desktop", "guidemode"]);
+        assert_eq!(merged.moved_sessions, vec!["s2", "s3"]);
+        assert_eq!(merged.synced_sessions, 1);
+        assert_eq!(merged.github_repo.as_deref(), Some("guidemode/desktop"));
+
+        let (projects, github_repo, created_at): (i64, Option<String>, i64) = conn
+            .query_row(
+                "SELECT COUNT(*), MAX(github_repo), MIN(created_at) FROM projects",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .unwrap();
+        assert_eq!(
+            (projects, github_repo.as_deref(), created_at),
+            (1, Some("guidemode/desktop"), 10)
+        );
+
+        // Sessions moved, renamed, and skipped like the rest of the excluded target
+        let sessions: Vec<(String, String, bool)> = conn
+            .prepare(
+                "SELECT session_id, project_name, sync_skipped FROM agent_sessions
+                 WHERE project_id = 'p1' ORDER BY session_id",
+            )
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+            .unwrap()
+            .collect::<Result<_>>()
+            .unwrap();
+        assert_eq!(
+            sessions,
+            [
+                ("s1".to_string(), "guidemode".to_string(), true),
+                ("s2".to_string(), "guidemode".to_string(), false),
+                ("s3".to_string(), "guidemode".to_string(), true),
+            ]
+        );
+
+        assert_eq!(project_aliases::resolve(&conn, "desktop"), "guidemode");
+        let merged_cwds: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM project_merges WHERE project_id = 'p1'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(merged_cwds, 2);
+    }
+
+    #[test]
+    fn test_invalid_merges() {
+        let mut conn = database();
+        assert!(merge(&mut conn, &[], "p1").is_err());
+        assert!(merge(&mut conn, &["p1".to_string()], "p1").is_err());
+        assert!(matches!(
+            merge(&mut conn, &["p2".to_string(), "missing".to_string()], "p1"),
+            Err(rusqlite::Error::QueryReturnedNoRows)
+        ));
+
+        // Nothing was merged
+        let projects: i64 = conn
+            .query_row("SELECT COUNT(*) FROM projects", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(projects, 3);
+    }
+}
@@ -1,7 +1,9 @@
-use git2::Repository;
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProjectMetadata {
@@ -9,11 +11,112 @@ pub struct ProjectMetadata {
     pub git_remote_url: Option<String>,
     pub cwd: String,
     pub detected_project_type: String,
+    /// Frameworks and build tools found in the project's manifests
+    #[serde(default)]
+    pub frameworks: Vec<String>,
 }
 
+/// Share of a project's source bytes written in one language
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LanguageShare {
+    pub language: String,
+    pub percentage: f64,
+}
+
+/// Source file extensions and the language they are written in
+const LANGUAGE_EXTENSIONS: &[(&str, &str)] = &[
+    ("rs", "Rust"),
+    ("ts", "TypeScript"),
+    ("tsx", "TypeScript"),
+    ("js", "JavaScript"),
+    ("jsx", "JavaScript"),
+    ("mjs", "JavaScript"),
+    ("cjs", "JavaScript"),
+    ("py", "Python"),
+    ("go", "Go"),
+    ("java", "Java"),
+    ("kt", "Kotlin"),
+    ("kts", "Kotlin"),
+    ("scala", "Scala"),
+    ("groovy", "Groovy"),
+    ("tf", "HCL"),
+    ("hcl", "HCL"),
+    ("dart", "Dart"),
+    ("swift", "Swift"),
+    ("rb", "Ruby"),
+    ("php", "PHP"),
+    ("c", "C"),
+    ("h", "C"),
+    ("cpp", "C++"),
+    ("cc", "C++"),
+    ("hpp", "C++"),
+    ("cs", "C#"),
+    ("vue", "Vue"),
+    ("svelte", "Svelte"),
+    ("css", "CSS"),
+    ("scss", "SCSS"),
+    ("html", "HTML"),
+    ("sh", "Shell"),
+    ("sql", "SQL"),
+];
+
+/// Files counted towards language percentages, so huge trees stay cheap
+const MAX_LANGUAGE_SCAN_FILES: usize = 5000;
+
+/// Larger files are usually generated or vendored and would skew percentages
+const MAX_LANGUAGE_FILE_BYTES: u64 = 1024 * 1024;
+
+/// Walked entries of any kind, so trees with few source files stay cheap too
+const MAX_LANGUAGE_SCAN_ENTRIES: usize = 50_000;
+
+/// Languages under this share are left out of the breakdown
+const MIN_LANGUAGE_PERCENTAGE: f64 = 1.0;
+
+/// How long a project's language breakdown is reused between uploads
+const LANGUAGE_CACHE_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// Most projects whose language breakdown is kept in memory
+const MAX_LANGUAGE_CACHE_ENTRIES: usize = 128;
+
+/// Language breakdowns by project directory, with when they were detected
+static LANGUAGES: Mutex<BTreeMap<String, (Instant, Vec<LanguageShare>)>> =
+    Mutex::new(BTreeMap::new());
+
+/// Dependency names that identify a framework, per manifest
+const NODE_FRAMEWORKS: &[(&str, &str)] = &[
+    ("next", "nextjs"),
+    ("react", "react"),
+    ("vue", "vue"),
+    ("svelte", "svelte"),
+    ("@angular/core", "angular"),
+    ("express", "express"),
+    ("vite", "vite"),
+    ("electron", "electron"),
+    ("@tauri-apps/api", "tauri"),
+];
+const RUST_FRAMEWORKS: &[(&str, &str)] = &[
+    ("tauri", "tauri"),
+    ("axum", "axum"),
+    ("actix-web", "actix-web"),
+    ("rocket", "rocket"),
+    ("bevy", "bevy"),
+];
+const PYTHON_FRAMEWORKS: &[(&str, &str)] = &[
+    ("django", "django"),
+    ("flask", "flask"),
+    ("fastapi", "fastapi"),
+];
+const GO_FRAMEWORKS: &[(&str, &str)] = &[
+    ("github.com/gin-gonic/gin", "gin"),
+    ("github.com/labstack/echo", "echo"),
+    ("github.com/gofiber/fiber", "fiber"),
+];
+
 /// Extract project metadata from a directory
 pub fn extract_project_metadata(cwd: &str) -> Result<ProjectMetadata, String> {
-    let path = Path::new(cwd);
+    // Windows paths past `MAX_PATH` only open with the long-path prefix
+    let native = crate::providers::common::canonical_path::SessionPath::parse(cwd).to_native();
+    let path = native.as_path();
 
     // Validate that directory exists
     if !path.exists() {
@@ -42,6 +145,7 @@ pub fn extract_project_metadata(cwd: &str) -> Result<ProjectMetadata, String> {
         git_remote_url,
         cwd: cwd.to_string(),
         detected_project_type,
+        frameworks: detect_frameworks(path),
     })
 }
 
@@ -55,11 +159,24 @@ fn detect_project_type_and_name(path: &Path) -> Result<(String, String), String>
         }
     }
 
-    // Check for Rust project (Cargo.toml)
+    // Check for Rust project (Cargo.toml): a workspace, or a single crate
     let cargo_toml = path.join("Cargo.toml");
     if cargo_toml.exists() {
-        if let Ok(project_name) = extract_rust_project_name(&cargo_toml) {
-            return Ok((project_name, "rust".to_string()));
+        if let Ok(manifest) = read_toml(&cargo_toml) {
+            let package_name = manifest
+                .get("package")
+                .and_then(|package| package.get("name"))
+                .and_then(|name| name.as_str());
+            if manifest.get("workspace").is_some() {
+                let project_name = match package_name {
+                    Some(name) => name.to_string(),
+                    None => directory_name(path)?,
+                };
+                return Ok((project_name, "rust-workspace".to_string()));
+            }
+            if let Some(name) = package_name {
+                return Ok((name.to_string(), "rust".to_string()));
+            }
         }
     }
 
@@ -71,6 +188,11 @@ fn detect_project_type_and_name(path: &Path) -> Result<(String, String), String>
         }
     }
 
+    // Python projects without pyproject.toml
+    if path.join("requirements.txt").exists() || path.join("setup.py").exists() {
+        return Ok((directory_name(path)?, "python".to_string()));
+    }
+
     // Check for Go project (go.mod)
     let go_mod = path.join("go.mod");
     if go_mod.exists() {
@@ -79,14 +201,305 @@ fn detect_project_type_and_name(path: &Path) -> Result<(String, String), String>
         }
     }
 
+    // Check for Flutter or Dart project (pubspec.yaml)
+    let pubspec = path.join("pubspec.yaml");
+    if let Ok(content) = fs::read_to_string(&pubspec) {
+        let project_name = yaml_top_level_value(&content, "name")
+            .map(Ok)
+            .unwrap_or_else(|| directory_name(path))?;
+        let project_type = if uses_flutter(&content) {
+            "flutter"
+        } else {
+            "dart"
+        };
+        return Ok((project_name, project_type.to_string()));
+    }
+
+    // Check for JVM project (Gradle or Maven)
+    if let Some(build_tool) = jvm_build_tool(path) {
+        let project_name = match build_tool {
+            "gradle" => extract_gradle_project_name(path),
+            _ => extract_maven_project_name(&path.join("pom.xml")),
+        };
+        let project_name = project_name
+            .map(Ok)
+            .unwrap_or_else(|| directory_name(path))?;
+        return Ok((project_name, "jvm".to_string()));
+    }
+
+    // Check for Terraform configuration (*.tf)
+    if has_file_with_extension(path, "tf") {
+        return Ok((directory_name(path)?, "terraform".to_string()));
+    }
+
     // Fallback: use directory name
-    let dir_name = path
-        .file_name()
+    Ok((directory_name(path)?, "generic".to_string()))
+}
+
+fn directory_name(path: &Path) -> Result<String, String> {
+    path.file_name()
         .and_then(|n| n.to_str())
-        .ok_or("Invalid directory name")?
-        .to_string();
+        .map(String::from)
+        .ok_or_else(|| "Invalid directory name".to_string())
+}
+
+fn read_toml(path: &Path) -> Result<toml::Value, String> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    toml::from_str(&content).map_err(|e| format!("Failed to parse {}: {}", path.display(), e))
+}
+
+/// Value of a top-level `key: value` line in a YAML file
+fn yaml_top_level_value(content: &str, key: &str) -> Option<String> {
+    content.lines().find_map(|line| {
+        let value = line.strip_prefix(key)?.strip_prefix(':')?;
+        let value = value.split(" #").next()?.trim();
+        let value = value.trim_matches(|c| c == '"' || c == '\'');
+        (!value.is_empty()).then(|| value.to_string())
+    })
+}
+
+/// Whether a pubspec.yaml depends on the Flutter SDK
+fn uses_flutter(pubspec: &str) -> bool {
+    pubspec.lines().any(|line| line.trim() == "sdk: flutter")
+}
+
+/// "gradle" or "maven" if the directory has their build files
+fn jvm_build_tool(path: &Path) -> Option<&'static str> {
+    const GRADLE_FILES: [&str; 4] = [
+        "build.gradle",
+        "build.gradle.kts",
+        "settings.gradle",
+        "settings.gradle.kts",
+    ];
+
+    if GRADLE_FILES.iter().any(|file| path.join(file).exists()) {
+        Some("gradle")
+    } else if path.join("pom.xml").exists() {
+        Some("maven")
+    } else {
+        None
+    }
+}
 
-    Ok((dir_name, "generic".to_string()))
+/// `rootProject.name` from settings.gradle(.kts)
+fn extract_gradle_project_name(path: &Path) -> Option<String> {
+    ["settings.gradle.kts", "settings.gradle"]
+        .iter()
+        .filter_map(|file| fs::read_to_string(path.join(file)).ok())
+        .find_map(|content| {
+            content.lines().find_map(|line| {
+                let value = line.trim().strip_prefix("rootProject.name")?;
+                let value = value.trim().strip_prefix('=')?.trim();
+                let name = value.trim_matches(|c| c == '"' || c == '\'');
+                (!name.is_empty()).then(|| name.to_string())
+            })
+        })
+}
+
+/// The project's own `<artifactId>` from pom.xml (the first one outside `<parent>`)
+fn extract_maven_project_name(pom_xml: &Path) -> Option<String> {
+    let content = fs::read_to_string(pom_xml).ok()?;
+    let content = match (content.find("<parent>"), content.find("</parent>")) {
+        (Some(start), Some(end)) if start < end => {
+            format!("{}{}", &content[..start], &content[end..])
+        }
+        _ => content,
+    };
+
+    let start = content.find("<artifactId>")? + "<artifactId>".len();
+    let end = start + content[start..].find("</artifactId>")?;
+    let name = content[start..end].trim();
+    (!name.is_empty()).then(|| name.to_string())
+}
+
+fn has_file_with_extension(path: &Path, extension: &str) -> bool {
+    fs::read_dir(path)
+        .map(|entries| {
+            entries.filter_map(|entry| entry.ok()).any(|entry| {
+                entry.path().extension().and_then(|ext| ext.to_str()) == Some(extension)
+            })
+        })
+        .unwrap_or(false)
+}
+
+/// Frameworks and build tools named in the project's manifests
+fn detect_frameworks(path: &Path) -> Vec<String> {
+    let mut frameworks: Vec<String> = Vec::new();
+    let mut add = |framework: &str| {
+        if !frameworks.iter().any(|seen| seen == framework) {
+            frameworks.push(framework.to_string());
+        }
+    };
+
+    if let Ok(content) = fs::read_to_string(path.join("package.json")) {
+        if let Ok(json) = serde_json::from_str::<serde_json::Value>(&content) {
+            let has_dependency = |name: &str| {
+                ["dependencies", "devDependencies"]
+                    .iter()
+                    .any(|field| json.get(field).and_then(|deps| deps.get(name)).is_some())
+            };
+            for (dependency, framework) in NODE_FRAMEWORKS {
+                if has_dependency(dependency) {
+                    add(framework);
+                }
+            }
+        }
+    }
+
+    if let Ok(manifest) = read_toml(&path.join("Cargo.toml")) {
+        let has_dependency = |name: &str| {
+            let in_table =
+                |table: Option<&toml::Value>| table.and_then(|deps| deps.get(name)).is_some();
+            in_table(manifest.get("dependencies"))
+                || in_table(
+                    manifest
+                        .get("workspace")
+                        .and_then(|workspace| workspace.get("dependencies")),
+                )
+        };
+        for (dependency, framework) in RUST_FRAMEWORKS {
+            if has_dependency(dependency) {
+                add(framework);
+            }
+        }
+    }
+
+    if let Ok(content) = fs::read_to_string(path.join("pyproject.toml")) {
+        if content.contains("[tool.poetry]") || path.join("poetry.lock").exists() {
+            add("poetry");
+        }
+        if content.contains("[tool.uv]") || path.join("uv.lock").exists() {
+            add("uv");
+        }
+        for (dependency, framework) in PYTHON_FRAMEWORKS {
+            if lists_dependency(&content, dependency) {
+                add(framework);
+            }
+        }
+    } else if path.join("uv.lock").exists() {
+        add("uv");
+    }
+    if let Ok(content) = fs::read_to_string(path.join("requirements.txt")) {
+        for (dependency, framework) in PYTHON_FRAMEWORKS {
+            if lists_dependency(&content, dependency) {
+                add(framework);
+            }
+        }
+    }
+
+    if let Ok(content) = fs::read_to_string(path.join("go.mod")) {
+        for (module, framework) in GO_FRAMEWORKS {
+            if content.contains(module) {
+                add(framework);
+            }
+        }
+    }
+
+    if let Some(build_tool) = jvm_build_tool(path) {
+        add(build_tool);
+        let spring_boot = ["build.gradle", "build.gradle.kts", "pom.xml"]
+            .iter()
+            .filter_map(|file| fs::read_to_string(path.join(file)).ok())
+            .any(|content| content.contains("org.springframework.boot"));
+        if spring_boot {
+            add("spring-boot");
+        }
+    }
+
+    frameworks
+}
+
+/// Whether a line of a Python manifest starts with the dependency's name,
+/// e.g. `fastapi>=0.100`, `"django~=5.0",` or `flask = "^3.0"`
+fn lists_dependency(content: &str, name: &str) -> bool {
+    content.lines().any(|line| {
+        let line = line.trim().trim_start_matches(['"', '\'']).to_lowercase();
+        line.strip_prefix(name).is_some_and(|rest| {
+            !rest.starts_with(|c: char| c.is_alphanumeric() || c == '-' || c == '_')
+        })
+    })
+}
+
+/// Share of source bytes per language under `cwd`, largest first
+///
+/// Respects .gitignore, skips dependency directories and stops after
+/// `MAX_LANGUAGE_SCAN_FILES` source files. Results are reused for an hour.
+pub fn detect_languages(cwd: &str) -> Vec<LanguageShare> {
+    if let Ok(cache) = LANGUAGES.lock() {
+        if let Some((detected_at, languages)) = cache.get(cwd) {
+            if detected_at.elapsed() < LANGUAGE_CACHE_TTL {
+                return languages.clone();
+            }
+        }
+    }
+
+    let languages = scan_languages(cwd);
+
+    if let Ok(mut cache) = LANGUAGES.lock() {
+        if cache.len() >= MAX_LANGUAGE_CACHE_ENTRIES {
+            cache.clear();
+        }
+        cache.insert(cwd.to_string(), (Instant::now(), languages.clone()));
+    }
+    languages
+}
+
+fn scan_languages(cwd: &str) -> Vec<LanguageShare> {
+    let walker = ignore::WalkBuilder::new(cwd)
+        .standard_filters(true)
+        .follow_links(false)
+        .filter_entry(|entry| entry.depth() == 0 || !is_skipped_dir(entry.path()))
+        .build();
+
+    let mut bytes_by_language: HashMap<&str, u64> = HashMap::new();
+    let mut scanned = 0;
+    for entry in walker
+        .take(MAX_LANGUAGE_SCAN_ENTRIES)
+        .filter_map(|entry| entry.ok())
+    {
+        if scanned >= MAX_LANGUAGE_SCAN_FILES {
+            break;
+        }
+        let Some(extension) = entry.path().extension().and_then(|ext| ext.to_str()) else {
+            continue;
+        };
+        let Some((_, language)) = LANGUAGE_EXTENSIONS
+            .iter()
+            .find(|(known, _)| known.eq_ignore_ascii_case(extension))
+        else {
+            continue;
+        };
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        if !metadata.is_file() || metadata.len() > MAX_LANGUAGE_FILE_BYTES {
+            continue;
+        }
+
+        *bytes_by_language.entry(language).or_default() += metadata.len();
+        scanned += 1;
+    }
+
+    let total: u64 = bytes_by_language.values().sum();
+    if total == 0 {
+        return Vec::new();
+    }
+
+    let mut languages: Vec<LanguageShare> = bytes_by_language
+        .into_iter()
+        .map(|(language, bytes)| LanguageShare {
+            language: language.to_string(),
+            percentage: (bytes as f64 / total as f64 * 1000.0).round() / 10.0,
+        })
+        .filter(|share| share.percentage >= MIN_LANGUAGE_PERCENTAGE)
+        .collect();
+    languages.sort_by(|a, b| {
+        b.percentage
+            .total_cmp(&a.percentage)
+            .then_with(|| a.language.cmp(&b.language))
+    });
+    languages
 }
 
 /// Extract project name from package.json
@@ -103,27 +516,6 @@ fn extract_nodejs_project_name(package_json: &Path) -> Result<String, String> {
         .ok_or_else(|| "No 'name' field in package.json".to_string())
 }
 
-/// Extract project name from Cargo.toml
-fn extract_rust_project_name(cargo_toml: &Path) -> Result<String, String> {
-    let content =
-        fs::read_to_string(cargo_toml).map_err(|e| format!("Failed to read Cargo.toml: {}", e))?;
-
-    // Simple parser for TOML [package] name field
-    for line in content.lines() {
-        let trimmed = line.trim();
-        if trimmed.starts_with("name") && trimmed.contains('=') {
-            if let Some(value_part) = trimmed.split('=').nth(1) {
-                let name = value_part.trim().trim_matches('"').trim_matches('\'');
-                if !name.is_empty() {
-                    return Ok(name.to_string());
-                }
-            }
-        }
-    }
-
-    Err("No 'name' field found in Cargo.toml".to_string())
-}
-
 /// Extract project name from pyproject.toml
 fn extract_python_project_name(pyproject_toml: &Path) -> Result<String, String> {
     let content = fs::read_to_string(pyproject_toml)
@@ -189,6 +581,417 @@ fn extract_go_project_name(go_mod: &Path) -> Result<String, String> {
     Err("No 'module' directive found in go.mod".to_string())
 }
 
+/// Workspace tool that makes a directory a monorepo
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MonorepoKind {
+    Pnpm,
+    Cargo,
+    Go,
+    Nx,
+}
+
+/// How sessions in a monorepo are attributed to projects
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MonorepoGranularity {
+    /// The whole monorepo is one project
+    Repo,
+    /// Each workspace package is its own project
+    #[default]
+    Package,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Monorepo {
+    pub kind: MonorepoKind,
+    pub root: PathBuf,
+    /// Package directories, sorted
+    pub packages: Vec<PathBuf>,
+}
+
+/// Directories never searched for workspace packages
+const SKIPPED_DIRS: [&str; 3] = ["node_modules", "target", "vendor"];
+
+/// How deep `**` patterns and nx project discovery search below their base
+const MAX_PACKAGE_SEARCH_DEPTH: usize = 6;
+
+/// Find the monorepo containing `cwd`, searching up to (not including) the home directory
+pub fn detect_monorepo(cwd: &str) -> Option<Monorepo> {
+    let home_dir = dirs::home_dir();
+
+    Path::new(cwd)
+        .ancestors()
+        .take_while(|dir| home_dir.as_deref() != Some(*dir))
+        .find_map(detect_monorepo_root)
+}
+
+/// Detect a monorepo rooted exactly at `root`
+fn detect_monorepo_root(root: &Path) -> Option<Monorepo> {
+    let (kind, packages) = if root.join("pnpm-workspace.yaml").is_file() {
+        let content = fs::read_to_string(root.join("pnpm-workspace.yaml")).ok()?;
+        let patterns = parse_pnpm_workspace(&content);
+        (
+            MonorepoKind::Pnpm,
+            expand_members(root, &patterns, &[], "package.json"),
+        )
+    } else if let Some((members, exclude)) = read_cargo_workspace(&root.join("Cargo.toml")) {
+        (
+            MonorepoKind::Cargo,
+            expand_members(root, &members, &exclude, "Cargo.toml"),
+        )
+    } else if root.join("go.work").is_file() {
+        let content = fs::read_to_string(root.join("go.work")).ok()?;
+        let patterns = parse_go_work(&content);
+        (
+            MonorepoKind::Go,
+            expand_members(root, &patterns, &[], "go.mod"),
+        )
+    } else if root.join("nx.json").is_file() {
+        (MonorepoKind::Nx, find_nx_projects(root))
+    } else {
+        return None;
+    };
+
+    (!packages.is_empty()).then(|| Monorepo {
+        kind,
+        root: root.to_path_buf(),
+        packages,
+    })
+}
+
+/// Package patterns listed under `packages:` in pnpm-workspace.yaml
+fn parse_pnpm_workspace(content: &str) -> Vec<String> {
+    let mut patterns = Vec::new();
+    let mut in_packages = false;
+
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        // A new top-level key ends the list
+        if !line.starts_with([' ', '\t', '-']) {
+            in_packages = trimmed == "packages:";
+            continue;
+        }
+        if let Some(item) = trimmed.strip_prefix('-').filter(|_| in_packages) {
+            let pattern = item.trim().trim_matches(|c| c == '"' || c == '\'');
+            if !pattern.is_empty() {
+                patterns.push(pattern.to_string());
+            }
+        }
+    }
+
+    patterns
+}
+
+/// `members` and `exclude` of a Cargo.toml `[workspace]` table
+fn read_cargo_workspace(cargo_toml: &Path) -> Option<(Vec<String>, Vec<String>)> {
+    let content = fs::read_to_string(cargo_toml).ok()?;
+    let manifest: toml::Value = toml::from_str(&content).ok()?;
+    let workspace = manifest.get("workspace")?;
+
+    let strings = |key: &str| -> Vec<String> {
+        workspace
+            .get(key)
+            .and_then(|value| value.as_array())
+            .map(|values| {
+                values
+                    .iter()
+                    .filter_map(|value| value.as_str().map(String::from))
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+
+    Some((strings("members"), strings("exclude")))
+}
+
+/// Module directories named by `use` directives in go.work
+fn parse_go_work(content: &str) -> Vec<String> {
+    let mut directories = Vec::new();
+    let mut in_use_block = false;
+
+    for line in content.lines() {
+        let line = line.split("//").next().unwrap_or("").trim();
+        if in_use_block {
+            if line == ")" {
+                in_use_block = false;
+            } else if !line.is_empty() {
+                directories.push(line.to_string());
+            }
+        } else if let Some(rest) = line.strip_prefix("use") {
+            let rest = rest.trim();
+            if rest == "(" {
+                in_use_block = true;
+            } else if !rest.is_empty() && line.starts_with("use ") {
+                directories.push(rest.to_string());
+            }
+        }
+    }
+
+    directories
+}
+
+/// nx projects: directories below the root with a project.json
+fn find_nx_projects(root: &Path) -> Vec<PathBuf> {
+    let mut projects: Vec<PathBuf> = walkdir::WalkDir::new(root)
+        .min_depth(1)
+        .max_depth(MAX_PACKAGE_SEARCH_DEPTH)
+        .into_iter()
+        .filter_entry(|entry| !is_skipped_dir(entry.path()))
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_dir() && entry.path().join("project.json").is_file())
+        .map(|entry| entry.into_path())
+        .collect();
+    projects.sort();
+    projects
+}
+
+fn is_skipped_dir(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.starts_with('.') || SKIPPED_DIRS.contains(&name))
+}
+
+/// Expand workspace member patterns into the package directories containing `manifest`
+///
+/// Patterns may use `*` within a path segment and `**` for any depth; patterns
+/// starting with `!` and those in `exclude` remove directories.
+fn expand_members(
+    root: &Path,
+    patterns: &[String],
+    exclude: &[String],
+    manifest: &str,
+) -> Vec<PathBuf> {
+    let expand = |pattern: &str| -> Vec<PathBuf> {
+        let pattern = pattern.trim_start_matches("./").trim_end_matches('/');
+        let segments: Vec<&str> = pattern.split('/').filter(|s| !s.is_empty()).collect();
+        expand_segments(root, &segments, 0)
+    };
+
+    let mut excluded: Vec<PathBuf> = exclude.iter().flat_map(|pattern| expand(pattern)).collect();
+    excluded.extend(
+        patterns
+            .iter()
+            .filter_map(|pattern| pattern.strip_prefix('!'))
+            .flat_map(expand),
+    );
+
+    let mut packages: Vec<PathBuf> = patterns
+        .iter()
+        .filter(|pattern| !pattern.starts_with('!'))
+        .flat_map(|pattern| expand(pattern))
+        .filter(|dir| dir.join(manifest).is_file() && !excluded.contains(dir))
+        .collect();
+    packages.sort();
+    packages.dedup();
+    packages
+}
+
+/// Directories under `dir` matching the remaining pattern segments
+fn expand_segments(dir: &Path, segments: &[&str], depth: usize) -> Vec<PathBuf> {
+    let Some((segment, rest)) = segments.split_first() else {
+        return vec![dir.to_path_buf()];
+    };
+    if depth > MAX_PACKAGE_SEARCH_DEPTH {
+        return Vec::new();
+    }
+
+    if !segment.contains('*') {
+        let child = dir.join(segment);
+        return if child.is_dir() {
+            expand_segments(&child, rest, depth + 1)
+        } else {
+            Vec::new()
+        };
+    }
+
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let children: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir() && !is_skipped_dir(path))
+        .collect();
+
+    let mut matches = Vec::new();
+    if *segment == "**" {
+        // `**` matches zero or more directories
+        matches.extend(expand_segments(dir, rest, depth + 1));
+        for child in &children {
+            matches.extend(expand_segments(child, segments, depth + 1));
+        }
+    } else {
+        for child in &children {
+            let name = child.file_name().and_then(|n| n.to_str()).unwrap_or("");
+            if wildcard_match(segment, name) {
+                matches.extend(expand_segments(child, rest, depth + 1));
+            }
+        }
+    }
+    matches
+}
+
+/// Match a name against a pattern where `*` matches any run of characters
+fn wildcard_match(pattern: &str, name: &str) -> bool {
+    match pattern.split_once('*') {
+        None => pattern == name,
+        Some((prefix, rest)) => {
+            let Some(remaining) = name.strip_prefix(prefix) else {
+                return false;
+            };
+            (0..=remaining.len())
+                .filter(|&i| remaining.is_char_boundary(i))
+                .any(|i| wildcard_match(rest, &remaining[i..]))
+        }
+    }
+}
+
+/// The package a session worked in: the one containing most of the files its
+/// tools touched, or the one containing its cwd if it touched none
+///
+/// Relative file paths are resolved against `cwd`. Ties go to the package
+/// touched first.
+pub fn attribute_package<'a>(
+    monorepo: &'a Monorepo,
+    cwd: &str,
+    file_paths: &[String],
+) -> Option<&'a Path> {
+    // Nested packages: the deepest one containing the path wins
+    let package_of = |path: &Path| {
+        monorepo
+            .packages
+            .iter()
+            .filter(|package| path.starts_with(package))
+            .max_by_key(|package| package.components().count())
+    };
+
+    let mut counts: Vec<(&PathBuf, usize)> = Vec::new();
+    for file_path in file_paths {
+        let path = Path::new(cwd).join(file_path);
+        if let Some(package) = package_of(&path) {
+            match counts.iter_mut().find(|(seen, _)| *seen == package) {
+                Some((_, count)) => *count += 1,
+                None => counts.push((package, 1)),
+            }
+        }
+    }
+
+    let mut best: Option<(&PathBuf, usize)> = None;
+    for (package, count) in counts {
+        if best.is_none_or(|(_, best_count)| count > best_count) {
+            best = Some((package, count));
+        }
+    }
+
+    best.map(|(package, _)| package)
+        .or_else(|| package_of(Path::new(cwd)))
+        .map(PathBuf::as_path)
+}
+
+/// Extract project metadata for a session, attributing monorepo sessions to
+/// the package they worked in
+///
+/// `granularity` is keyed by monorepo root path; roots without an entry use
+/// package granularity. `touched_files` is only called inside a monorepo.
+pub fn extract_session_project_metadata(
+    cwd: &str,
+    granularity: &HashMap<String, MonorepoGranularity>,
+    touched_files: impl FnOnce() -> Vec<String>,
+) -> Result<ProjectMetadata, String> {
+    let Some(monorepo) = detect_monorepo(cwd) else {
+        return extract_project_metadata(cwd);
+    };
+
+    let root = monorepo.root.to_string_lossy().to_string();
+    let project_dir = match granularity.get(&root).copied().unwrap_or_default() {
+        MonorepoGranularity::Repo => monorepo.root.as_path(),
+        MonorepoGranularity::Package => {
+            attribute_package(&monorepo, cwd, &touched_files()).unwrap_or(&monorepo.root)
+        }
+    };
+
+    let mut metadata = extract_project_metadata(&project_dir.to_string_lossy())?;
+    // Packages share the repository's remote
+    if metadata.git_remote_url.is_none() {
+        metadata.git_remote_url = extract_git_remote_url(&monorepo.root);
+    }
+    Ok(metadata)
+}
+
+/// Most session files whose touched paths are kept in memory before the
+/// cache is reset
+const MAX_TOUCHED_FILE_CACHE_ENTRIES: usize = 256;
+
+/// The files a session's tools touched, as of one version of its file
+struct TouchedFiles {
+    size: u64,
+    modified: Option<SystemTime>,
+    paths: Vec<String>,
+}
+
+/// Touched files by session file
+///
+/// Each upload attempt of a session asks for its project again; the session
+/// file is only parsed again once its size or modification time changes.
+static TOUCHED_FILES: Mutex<BTreeMap<PathBuf, TouchedFiles>> = Mutex::new(BTreeMap::new());
+
+/// File paths named by the tool calls of a session file
+fn session_touched_files(session_file: &Path) -> Vec<String> {
+    let Ok(file_metadata) = fs::metadata(session_file) else {
+        return Vec::new();
+    };
+    let (size, modified) = (file_metadata.len(), file_metadata.modified().ok());
+
+    if let Ok(cache) = TOUCHED_FILES.lock() {
+        if let Some(cached) = cache.get(session_file) {
+            if cached.size == size && cached.modified == modified {
+                return cached.paths.clone();
+            }
+        }
+    }
+
+    let paths = fs::read_to_string(session_file)
+        .map(|content| {
+            crate::metrics::tools::touched_file_paths(&crate::metrics::compute::parse_messages(
+                &content,
+            ))
+        })
+        .unwrap_or_default();
+
+    if let Ok(mut cache) = TOUCHED_FILES.lock() {
+        if cache.len() >= MAX_TOUCHED_FILE_CACHE_ENTRIES {
+            cache.clear();
+        }
+        cache.insert(
+            session_file.to_path_buf(),
+            TouchedFiles {
+                size,
+                modified,
+                paths: paths.clone(),
+            },
+        );
+    }
+    paths
+}
+
+/// Extract project metadata for a provider's session file, using the provider's
+/// monorepo granularity and the files the session's tools touched
+pub fn extract_session_file_project_metadata(
+    provider_id: &str,
+    cwd: &str,
+    session_file: &Path,
+) -> Result<ProjectMetadata, String> {
+    let granularity = crate::config::load_provider_config(provider_id)
+        .map(|config| config.monorepo_granularity)
+        .unwrap_or_default();
+
+    extract_session_project_metadata(cwd, &granularity, || session_touched_files(session_file))
+}
+
 /// Convert SSH Git URL to HTTPS URL for GitHub
 fn convert_ssh_to_https(url: &str) -> String {
     // Check if it's a GitHub SSH URL (git@github.com:owner/repo.git)
@@ -260,10 +1063,10 @@ fn extract_git_remote_url(path: &Path) -> Option<String> {
     None
 }
 
-/// Extract current git branch from working directory
+/// Extract current git branch from working directory (or its worktree/submodule checkout)
 /// Returns None if not a git repository or if there's an error
 pub fn extract_git_branch(cwd: &str) -> Option<String> {
-    let repo = Repository::open(cwd).ok()?;
+    let repo = crate::git_diff::open_repository(cwd).ok()?;
     let head = repo.head().ok()?;
     head.shorthand().map(String::from)
 }
@@ -271,7 +1074,7 @@ pub fn extract_git_branch(cwd: &str) -> Option<String> {
 /// Extract current git commit hash from working directory
 /// Returns None if not a git repository or if there's an error
 pub fn extract_git_commit_hash(cwd: &str) -> Option<String> {
-    let repo = Repository::open(cwd).ok()?;
+    let repo = crate::git_diff::open_repository(cwd).ok()?;
     let head = repo.head().ok()?;
     let oid = head.target()?;
     Some(oid.to_string())
@@ -425,4 +1228,268 @@ mod tests {
             Some("https://github.com/guidemode/guidemode.git".to_string())
         );
     }
+
+    fn write(root: &Path, path: &str, content: &str) {
+        let full_path = root.join(path);
+        fs::create_dir_all(full_path.parent().unwrap()).unwrap();
+        fs::write(full_path, content).unwrap();
+    }
+
+    #[test]
+    fn test_detect_pnpm_monorepo() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path();
+        write(
+            root,
+            "pnpm-workspace.yaml",
+            "packages:\n  - 'packages/*'\n  - \"apps/**\"\n  - '!packages/legacy'\ncatalog:\n  - ignored\n",
+        );
+        write(root, "packages/ui/package.json", r#"{"name": "@acme/ui"}"#);
+        write(
+            root,
+            "packages/legacy/package.json",
+            r#"{"name": "legacy"}"#,
+        );
+        write(root, "packages/notes/README.md", "no manifest");
+        write(root, "apps/web/site/package.json", r#"{"name": "site"}"#);
+        write(root, "apps/web/site/node_modules/dep/package.json", "{}");
+
+        let monorepo = detect_monorepo(root.join("packages/ui").to_str().unwrap()).unwrap();
+
+        assert_eq!(monorepo.kind, MonorepoKind::Pnpm);
+        assert_eq!(monorepo.root, root);
+        assert_eq!(
+            monorepo.packages,
+            vec![root.join("apps/web/site"), root.join("packages/ui")]
+        );
+    }
+
+    #[test]
+    fn test_detect_cargo_and_go_workspaces() {
+        let temp_dir = tempdir().unwrap();
+        let cargo_root = temp_dir.path().join("cargo");
+        write(
+            &cargo_root,
+            "Cargo.toml",
+            "[workspace]\nmembers = [\"crates/*\", \"cli\"]\nexclude = [\"crates/scratch\"]\n",
+        );
+        write(
+            &cargo_root,
+            "crates/core/Cargo.toml",
+            "[package]\nname = \"core\"",
+        );
+        write(
+            &cargo_root,
+            "crates/scratch/Cargo.toml",
+            "[package]\nname = \"x\"",
+        );
+        write(&cargo_root, "cli/Cargo.toml", "[package]\nname = \"cli\"");
+
+        let cargo = detect_monorepo(cargo_root.to_str().unwrap()).unwrap();
+        assert_eq!(cargo.kind, MonorepoKind::Cargo);
+        assert_eq!(
+            cargo.packages,
+            vec![cargo_root.join("cli"), cargo_root.join("crates/core")]
+        );
+
+        let go_root = temp_dir.path().join("go");
+        write(
+            &go_root,
+            "go.work",
+            "go 1.22\n\nuse (\n\t./api // service\n\t./tools\n)\nuse ./web\n",
+        );
+        for module in ["api", "tools", "web"] {
+            write(&go_root, &format!("{}/go.mod", module), "module x");
+        }
+
+        let go = detect_monorepo(go_root.join("api").to_str().unwrap()).unwrap();
+        assert_eq!(go.kind, MonorepoKind::Go);
+        assert_eq!(go.packages.len(), 3);
+
+        // A plain crate is not a monorepo
+        write(
+            temp_dir.path(),
+            "plain/Cargo.toml",
+            "[package]\nname = \"plain\"",
+        );
+        assert!(detect_monorepo(temp_dir.path().join("plain").to_str().unwrap()).is_none());
+    }
+
+    #[test]
+    fn test_attribute_package() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path();
+        write(root, "nx.json", "{}");
+        write(root, "apps/web/project.json", "{}");
+        write(root, "libs/auth/project.json", "{}");
+        write(root, "libs/auth/package.json", r#"{"name": "auth"}"#);
+
+        let monorepo = detect_monorepo(root.to_str().unwrap()).unwrap();
+        assert_eq!(monorepo.kind, MonorepoKind::Nx);
+
+        let cwd = root.to_str().unwrap();
+        let touched = vec![
+            "apps/web/main.ts".to_string(),
+            root.join("libs/auth/a.ts").to_string_lossy().to_string(),
+            "libs/auth/b.ts".to_string(),
+            "README.md".to_string(),
+        ];
+        assert_eq!(
+            attribute_package(&monorepo, cwd, &touched),
+            Some(root.join("libs/auth").as_path())
+        );
+
+        // Without touched files, the package containing the cwd is used
+        let web_cwd = root.join("apps/web/src");
+        assert_eq!(
+            attribute_package(&monorepo, web_cwd.to_str().unwrap(), &[]),
+            Some(root.join("apps/web").as_path())
+        );
+        assert_eq!(attribute_package(&monorepo, cwd, &[]), None);
+
+        let metadata =
+            extract_session_project_metadata(cwd, &HashMap::new(), || touched.clone()).unwrap();
+        assert_eq!(metadata.project_name, "auth");
+
+        let granularity = HashMap::from([(cwd.to_string(), MonorepoGranularity::Repo)]);
+        let metadata = extract_session_project_metadata(cwd, &granularity, || {
+            panic!("touched files are not needed at repo granularity")
+        })
+        .unwrap();
+        assert_eq!(metadata.cwd, cwd);
+    }
+
+    #[test]
+    fn test_wildcard_match() {
+        assert!(wildcard_match("*", "anything"));
+        assert!(wildcard_match("app-*", "app-web"));
+        assert!(wildcard_match("*-service", "billing-service"));
+        assert!(!wildcard_match("app-*", "lib-web"));
+        assert!(wildcard_match("exact", "exact"));
+    }
+
+    #[test]
+    fn test_rust_workspace_vs_crate() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path();
+        write(
+            root,
+            "Cargo.toml",
+            "[workspace]\nmembers = [\"app\"]\n\n[workspace.dependencies]\naxum = \"0.7\"\n",
+        );
+        write(
+            root,
+            "app/Cargo.toml",
+            "[package]\nname = \"app\"\n\n[dependencies]\ntauri = \"2\"\n",
+        );
+
+        let workspace = extract_project_metadata(root.to_str().unwrap()).unwrap();
+        assert_eq!(workspace.detected_project_type, "rust-workspace");
+        assert_eq!(workspace.frameworks, vec!["axum"]);
+
+        let app = extract_project_metadata(root.join("app").to_str().unwrap()).unwrap();
+        assert_eq!(app.project_name, "app");
+        assert_eq!(app.detected_project_type, "rust");
+        assert_eq!(app.frameworks, vec!["tauri"]);
+    }
+
+    #[test]
+    fn test_python_tooling() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path();
+        write(
+            root,
+            "pyproject.toml",
+            "[project]\nname = \"api\"\ndependencies = [\n  \"fastapi>=0.110\",\n  \"flask-cors\",\n]\n\n[tool.uv]\ndev-dependencies = []\n",
+        );
+
+        let metadata = extract_project_metadata(root.to_str().unwrap()).unwrap();
+        assert_eq!(metadata.detected_project_type, "python");
+        assert_eq!(metadata.frameworks, vec!["uv", "fastapi"]);
+
+        let poetry_dir = tempdir().unwrap();
+        write(
+            poetry_dir.path(),
+            "pyproject.toml",
+            "[tool.poetry]\nname = \"svc\"\n\n[tool.poetry.dependencies]\ndjango = \"^5.0\"\n",
+        );
+        let metadata = extract_project_metadata(poetry_dir.path().to_str().unwrap()).unwrap();
+        assert_eq!(metadata.project_name, "svc");
+        assert_eq!(metadata.frameworks, vec!["poetry", "django"]);
+    }
+
+    #[test]
+    fn test_jvm_terraform_and_flutter_projects() {
+        let gradle_dir = tempdir().unwrap();
+        write(
+            gradle_dir.path(),
+            "settings.gradle.kts",
+            "rootProject.name = \"billing\"\ninclude(\"core\")\n",
+        );
+        write(
+            gradle_dir.path(),
+            "build.gradle.kts",
+            "plugins { id(\"org.springframework.boot\") version \"3.2.0\" }\n",
+        );
+        let gradle = extract_project_metadata(gradle_dir.path().to_str().unwrap()).unwrap();
+        assert_eq!(gradle.project_name, "billing");
+        assert_eq!(gradle.detected_project_type, "jvm");
+        assert_eq!(gradle.frameworks, vec!["gradle", "spring-boot"]);
+
+        let maven_dir = tempdir().unwrap();
+        write(
+            maven_dir.path(),
+            "pom.xml",
+            "<project><parent><artifactId>parent-pom</artifactId></parent>\n<artifactId>orders</artifactId></project>",
+        );
+        let maven = extract_project_metadata(maven_dir.path().to_str().unwrap()).unwrap();
+        assert_eq!(maven.project_name, "orders");
+        assert_eq!(maven.frameworks, vec!["maven"]);
+
+        let terraform_dir = tempdir().unwrap();
+        write(terraform_dir.path(), "main.tf", "provider \"aws\" {}\n");
+        let terraform = extract_project_metadata(terraform_dir.path().to_str().unwrap()).unwrap();
+        assert_eq!(terraform.detected_project_type, "terraform");
+
+        let flutter_dir = tempdir().unwrap();
+        write(
+            flutter_dir.path(),
+            "pubspec.yaml",
+            "name: shop_app\ndependencies:\n  flutter:\n    sdk: flutter\n",
+        );
+        let flutter = extract_project_metadata(flutter_dir.path().to_str().unwrap()).unwrap();
+        assert_eq!(flutter.project_name, "shop_app");
+        assert_eq!(flutter.detected_project_type, "flutter");
+    }
+
+    #[test]
+    fn test_detect_languages() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir(root.join(".git")).unwrap();
+        write(root, ".gitignore", "dist/\n");
+        write(root, "src/main.rs", &"x".repeat(600));
+        write(root, "web/app.ts", &"x".repeat(300));
+        write(root, "web/app.tsx", &"x".repeat(95));
+        write(root, "scripts/run.sh", &"x".repeat(5));
+        write(root, "dist/bundle.js", &"x".repeat(10_000));
+        write(root, "node_modules/dep/index.js", &"x".repeat(10_000));
+        write(root, "README.md", &"x".repeat(10_000));
+
+        let languages = detect_languages(root.to_str().unwrap());
+
+        assert_eq!(
+            languages,
+            vec![
+                LanguageShare {
+                    language: "Rust".to_string(),
+                    percentage: 60.0
+                },
+                LanguageShare {
+                    language: "TypeScript".to_string(),
+                    percentage: 39.5
+                },
+            ]
+        );
+    }
 }
@@ -0,0 +1,138 @@
+//! Excluding projects from sync
+//!
+//! A project is excluded in the `projects` table, whichever providers its
+//! sessions come from. Excluding one marks its sessions that haven't synced
+//! yet as skipped (`sync_skipped`), and sessions linked to it later are
+//! skipped as they're linked, so the upload queue never picks them up.
+//! Including the project again lets its skipped sessions sync.
+
+use crate::database::with_connection_mut;
+use rusqlite::{params, Connection, Result};
+
+fn set(conn: &mut Connection, project_id: &str, excluded: bool) -> Result<Vec<String>> {
+    let tx = conn.transaction()?;
+    let rows = tx.execute(
+        "UPDATE projects SET sync_excluded = ? WHERE id = ?",
+        params![excluded, project_id],
+    )?;
+    if rows == 0 {
+        return Err(rusqlite::Error::QueryReturnedNoRows);
+    }
+
+    let session_ids = {
+        let mut stmt = tx.prepare(
+            "SELECT session_id FROM agent_sessions
+             WHERE project_id = ? AND synced_to_server = 0 AND sync_skipped = ?",
+        )?;
+        let ids = stmt
+            .query_map(params![project_id, !excluded], |row| row.get(0))?
+            .collect::<Result<Vec<String>>>()?;
+        ids
+    };
+    tx.execute(
+        "UPDATE agent_sessions SET sync_skipped = ?
+         WHERE project_id = ? AND synced_to_server = 0",
+        params![excluded, project_id],
+    )?;
+    tx.commit()?;
+    Ok(session_ids)
+}
+
+/// Skip a session that was just linked to an excluded project
+pub(crate) fn skip_if_excluded(conn: &Connection, session_id: &str) -> Result<usize> {
+    conn.execute(
+        "UPDATE agent_sessions SET sync_skipped = 1
+         WHERE session_id = ? AND synced_to_server = 0
+           AND project_id IN (SELECT id FROM projects WHERE sync_excluded = 1)",
+        params![session_id],
+    )
+}
+
+/// Exclude a project from syncing, or include it again
+///
+/// Returns the sessions that were skipped or let through again.
+pub fn set_project_sync_excluded(project_id: &str, excluded: bool) -> Result<Vec<String>, String> {
+    with_connection_mut(|conn| set(conn, project_id, excluded)).map_err(|e| match e {
+        rusqlite::Error::QueryReturnedNoRows => format!("Project {} not found", project_id),
+        e => format!("Failed to update project sync: {}", e),
+    })
+}
+
+/// Whether the project at `cwd` is excluded from syncing
+pub fn is_cwd_excluded(cwd: &str) -> bool {
+    with_connection_mut(|conn| {
+        conn.query_row(
+            "SELECT sync_excluded FROM projects WHERE cwd = ?",
+            params![cwd],
+            |row| row.get(0),
+        )
+    })
+    .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn database() -> Connection {
+        let conn = crate::database::test_connection();
+        conn.execute(
+            "INSERT INTO projects (id, name, cwd, type, created_at, updated_at)
+             VALUES ('p1', 'app', '/app', 'node', 0, 0)",
+            [],
+        )
+        .unwrap();
+        conn
+    }
+
+    fn session(conn: &Connection, session_id: &str, synced: bool) {
+        conn.execute(
+            "INSERT INTO agent_sessions (
+                id, provider, project_name, session_id, file_name, file_path, file_size,
+                created_at, uploaded_at, synced_to_server, project_id
+            ) VALUES (?1, 'claude-code', 'app', ?1, 'f.jsonl', '/f', 10, 0, 0, ?2, 'p1')",
+            params![session_id, synced],
+        )
+        .unwrap();
+    }
+
+    fn skipped(conn: &Connection) -> Vec<String> {
+        let mut stmt = conn
+            .prepare("SELECT session_id FROM agent_sessions WHERE sync_skipped = 1 ORDER BY 1")
+            .unwrap();
+        stmt.query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<Result<_>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_excluding_skips_unsynced_sessions() {
+        let mut conn = database();
+        session(&conn, "s1", false);
+        session(&conn, "s2", true);
+
+        assert_eq!(set(&mut conn, "p1", true).unwrap(), vec!["s1"]);
+        assert_eq!(skipped(&conn), vec!["s1"]);
+
+        // Sessions linked later are skipped too
+        session(&conn, "s3", false);
+        skip_if_excluded(&conn, "s3").unwrap();
+        assert_eq!(skipped(&conn), vec!["s1", "s3"]);
+
+        assert_eq!(set(&mut conn, "p1", false).unwrap(), vec!["s1", "s3"]);
+        assert!(skipped(&conn).is_empty());
+        session(&conn, "s4", false);
+        skip_if_excluded(&conn, "s4").unwrap();
+        assert!(skipped(&conn).is_empty());
+    }
+
+    #[test]
+    fn test_unknown_project() {
+        let mut conn = database();
+        assert!(matches!(
+            set(&mut conn, "missing", true),
+            Err(rusqlite::Error::QueryReturnedNoRows)
+        ));
+    }
+}
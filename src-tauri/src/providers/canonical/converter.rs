@@ -70,7 +70,9 @@ pub fn to_jsonl<T: ToCanonical>(messages: Vec<T>) -> Result<String> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::providers::canonical::{ContentValue, MessageContent, MessageType};
+    use crate::providers::canonical::{
+        ContentValue, MessageContent, MessageType, CANONICAL_SCHEMA_VERSION,
+    };
 
     // Mock implementation for testing
     struct MockMessage {
@@ -81,6 +83,7 @@ mod tests {
     impl ToCanonical for MockMessage {
         fn to_canonical(&self) -> Result<Option<CanonicalMessage>> {
             Ok(Some(CanonicalMessage {
+                schema_version: CANONICAL_SCHEMA_VERSION,
                 uuid: self.id.clone(),
                 timestamp: "2025-01-01T00:00:00.000Z".to_string(),
                 message_type: MessageType::User,
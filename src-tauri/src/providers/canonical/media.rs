@@ -0,0 +1,243 @@
+//! Image and attachment blocks for canonical messages
+//!
+//! Providers embed attachments as base64 data, data URLs or references to
+//! local files. Canonical messages never carry the raw bytes: an attachment is
+//! reduced to its media type, a SHA256 hash of its content and, when known,
+//! the local path it came from. Blocks with an `image/*` media type become
+//! `ContentBlock::Image`, everything else `ContentBlock::Attachment`.
+
+use super::ContentBlock;
+use base64::Engine;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::Path;
+
+/// Local files larger than this are referenced by path only, without a hash
+const MAX_HASHED_FILE_BYTES: u64 = 50 * 1024 * 1024;
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+fn is_image(media_type: Option<&str>) -> bool {
+    media_type.is_some_and(|media_type| media_type.starts_with("image/"))
+}
+
+/// Guess a media type from a file extension
+pub fn media_type_from_path(path: &str) -> Option<String> {
+    let extension = Path::new(path).extension()?.to_str()?.to_lowercase();
+    let media_type = match extension.as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        "pdf" => "application/pdf",
+        "txt" | "md" => "text/plain",
+        "json" => "application/json",
+        _ => return None,
+    };
+    Some(media_type.to_string())
+}
+
+fn build_block(
+    media_type: Option<String>,
+    hash: Option<String>,
+    size_bytes: Option<u64>,
+    name: Option<&str>,
+    path: Option<&str>,
+) -> ContentBlock {
+    let path = path.map(str::to_string);
+
+    if is_image(media_type.as_deref()) {
+        ContentBlock::Image {
+            media_type: media_type.unwrap_or_default(),
+            hash,
+            size_bytes,
+            path,
+        }
+    } else {
+        ContentBlock::Attachment {
+            name: name
+                .map(str::to_string)
+                .or_else(|| path.as_deref().and_then(file_name)),
+            media_type,
+            hash,
+            size_bytes,
+            path,
+        }
+    }
+}
+
+fn file_name(path: &str) -> Option<String> {
+    Path::new(path)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .map(str::to_string)
+}
+
+/// Build a block from in-memory content
+pub fn block_from_bytes(
+    media_type: &str,
+    bytes: &[u8],
+    name: Option<&str>,
+    path: Option<&str>,
+) -> ContentBlock {
+    build_block(
+        Some(media_type.to_string()),
+        Some(sha256_hex(bytes)),
+        Some(bytes.len() as u64),
+        name,
+        path,
+    )
+}
+
+/// Build a block from base64-encoded content
+///
+/// Data that is not valid base64 is hashed as-is so the block still
+/// identifies its content.
+pub fn block_from_base64(
+    media_type: &str,
+    data: &str,
+    name: Option<&str>,
+    path: Option<&str>,
+) -> ContentBlock {
+    match base64::engine::general_purpose::STANDARD.decode(data.trim()) {
+        Ok(bytes) => block_from_bytes(media_type, &bytes, name, path),
+        Err(_) => block_from_bytes(media_type, data.as_bytes(), name, path),
+    }
+}
+
+/// Build a block referencing a local file, hashing it when it is readable
+pub fn block_from_path(path: &str, media_type: Option<&str>, name: Option<&str>) -> ContentBlock {
+    let media_type = media_type
+        .map(str::to_string)
+        .or_else(|| media_type_from_path(path));
+
+    let size_bytes = fs::metadata(path).ok().map(|metadata| metadata.len());
+    let hash = size_bytes
+        .filter(|size| *size <= MAX_HASHED_FILE_BYTES)
+        .and_then(|_| fs::read(path).ok())
+        .map(|bytes| sha256_hex(&bytes));
+
+    build_block(media_type, hash, size_bytes, name, Some(path))
+}
+
+/// Build a block from a URL: `data:` URLs are hashed, `file://` URLs and
+/// plain paths are treated as local files, anything else is kept by name only
+pub fn block_from_url(url: &str, media_type: Option<&str>, name: Option<&str>) -> ContentBlock {
+    if let Some(rest) = url.strip_prefix("data:") {
+        if let Some((header, data)) = rest.split_once(',') {
+            let url_media_type = header.split(';').next().filter(|m| !m.is_empty());
+            let media_type = media_type
+                .or(url_media_type)
+                .unwrap_or("application/octet-stream");
+            if header.ends_with(";base64") {
+                return block_from_base64(media_type, data, name, None);
+            }
+            let decoded = urlencoding::decode(data)
+                .map(|d| d.into_owned())
+                .unwrap_or_else(|_| data.to_string());
+            return block_from_bytes(media_type, decoded.as_bytes(), name, None);
+        }
+    }
+
+    if let Some(path) = url.strip_prefix("file://") {
+        return block_from_path(path, media_type, name);
+    }
+    if url.starts_with('/') {
+        return block_from_path(url, media_type, name);
+    }
+
+    build_block(
+        media_type
+            .map(str::to_string)
+            .or_else(|| media_type_from_path(url)),
+        None,
+        None,
+        name.or(Some(url)),
+        None,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn test_image_from_base64_is_hashed() {
+        // "hello" base64-encoded
+        let block = block_from_base64("image/png", "aGVsbG8=", None, None);
+
+        match block {
+            ContentBlock::Image {
+                media_type,
+                hash,
+                size_bytes,
+                path,
+            } => {
+                assert_eq!(media_type, "image/png");
+                assert_eq!(hash.as_deref(), Some(sha256_hex(b"hello").as_str()));
+                assert_eq!(size_bytes, Some(5));
+                assert_eq!(path, None);
+            }
+            other => panic!("Expected image block, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_data_url_and_file_url() {
+        let block = block_from_url(
+            "data:application/pdf;base64,aGVsbG8=",
+            None,
+            Some("doc.pdf"),
+        );
+        match block {
+            ContentBlock::Attachment {
+                name, media_type, ..
+            } => {
+                assert_eq!(name.as_deref(), Some("doc.pdf"));
+                assert_eq!(media_type.as_deref(), Some("application/pdf"));
+            }
+            other => panic!("Expected attachment block, got {:?}", other),
+        }
+
+        let mut file = tempfile::Builder::new().suffix(".png").tempfile().unwrap();
+        file.write_all(b"png bytes").unwrap();
+        let path = file.path().to_str().unwrap().to_string();
+
+        match block_from_url(&format!("file://{}", path), None, None) {
+            ContentBlock::Image {
+                media_type,
+                hash,
+                path: block_path,
+                ..
+            } => {
+                assert_eq!(media_type, "image/png");
+                assert_eq!(hash.as_deref(), Some(sha256_hex(b"png bytes").as_str()));
+                assert_eq!(block_path.as_deref(), Some(path.as_str()));
+            }
+            other => panic!("Expected image block, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_missing_file_keeps_path_without_hash() {
+        match block_from_path("/does/not/exist/report.txt", None, None) {
+            ContentBlock::Attachment {
+                name,
+                media_type,
+                hash,
+                path,
+                ..
+            } => {
+                assert_eq!(name.as_deref(), Some("report.txt"));
+                assert_eq!(media_type.as_deref(), Some("text/plain"));
+                assert_eq!(hash, None);
+                assert_eq!(path.as_deref(), Some("/does/not/exist/report.txt"));
+            }
+            other => panic!("Expected attachment block, got {:?}", other),
+        }
+    }
+}
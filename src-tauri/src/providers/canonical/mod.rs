@@ -2,16 +2,32 @@ use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
 pub mod converter;
+pub mod media;
+pub mod validation;
 
 #[cfg(test)]
 mod tests;
 
+/// Current version of the canonical message schema
+///
+/// Bump when the meaning or shape of canonical fields changes, so consumers
+/// can tell files written by older converters apart.
+pub const CANONICAL_SCHEMA_VERSION: u32 = 1;
+
+fn default_schema_version() -> u32 {
+    CANONICAL_SCHEMA_VERSION
+}
+
 /// Canonical JSONL message format (based on Claude Code)
 ///
 /// This is the unified format that all providers convert to for consistent processing.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct CanonicalMessage {
+    /// Canonical schema version (files written before versioning read as version 1)
+    #[serde(default = "default_schema_version")]
+    pub schema_version: u32,
+
     /// Unique message identifier
     pub uuid: String,
 
@@ -135,6 +151,33 @@ pub enum ContentBlock {
     Thinking {
         thinking: String,
     },
+    /// Image referenced by content hash (raw bytes are never embedded)
+    Image {
+        media_type: String,
+        /// SHA256 of the image bytes
+        #[serde(skip_serializing_if = "Option::is_none")]
+        hash: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        size_bytes: Option<u64>,
+        /// Local file the image was read from, if known
+        #[serde(skip_serializing_if = "Option::is_none")]
+        path: Option<String>,
+    },
+    /// Non-image file attached to a message (documents, referenced files)
+    Attachment {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        name: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        media_type: Option<String>,
+        /// SHA256 of the file content
+        #[serde(skip_serializing_if = "Option::is_none")]
+        hash: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        size_bytes: Option<u64>,
+        /// Local file path, if known
+        #[serde(skip_serializing_if = "Option::is_none")]
+        path: Option<String>,
+    },
 }
 
 /// Token usage statistics
@@ -170,6 +213,7 @@ impl CanonicalMessage {
         text: String,
     ) -> Self {
         Self {
+            schema_version: CANONICAL_SCHEMA_VERSION,
             uuid,
             timestamp,
             message_type,
@@ -206,6 +250,7 @@ impl CanonicalMessage {
         blocks: Vec<ContentBlock>,
     ) -> Self {
         Self {
+            schema_version: CANONICAL_SCHEMA_VERSION,
             uuid,
             timestamp,
             message_type,
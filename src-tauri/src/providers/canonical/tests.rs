@@ -136,6 +136,7 @@ fn test_deserialize_tool_result() {
 #[test]
 fn test_serialize_with_provider_metadata() {
     let msg = CanonicalMessage {
+        schema_version: CANONICAL_SCHEMA_VERSION,
         uuid: "test-uuid".to_string(),
         timestamp: "2025-01-01T00:00:00.000Z".to_string(),
         message_type: MessageType::Assistant,
@@ -193,6 +194,7 @@ fn test_serialize_with_provider_metadata() {
 #[test]
 fn test_serialize_with_token_usage() {
     let msg = CanonicalMessage {
+        schema_version: CANONICAL_SCHEMA_VERSION,
         uuid: "test-uuid".to_string(),
         timestamp: "2025-01-01T00:00:00.000Z".to_string(),
         message_type: MessageType::Assistant,
@@ -0,0 +1,501 @@
+//! Structural validation of canonical JSONL output
+//!
+//! Every converter writes canonical messages that the upload and metrics
+//! pipelines assume are well formed. This module checks the invariants those
+//! consumers rely on so a bad conversion is noticed when it is written rather
+//! than when a session fails to render:
+//!
+//! - every line parses as a `CanonicalMessage` with a non-empty uuid and session ID
+//! - non-meta messages carry some content
+//! - timestamps are RFC 3339 and do not go backwards
+//! - every `tool_result` answers an earlier `tool_use`, and every `tool_use` is answered
+//!
+//! Structural breakage is reported as an error; things that real sessions can
+//! legitimately contain (an interrupted tool call, clock skew) are warnings.
+
+use super::{CanonicalMessage, ContentBlock, ContentValue, MessageType, CANONICAL_SCHEMA_VERSION};
+use crate::logging::{log_error, log_warn};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+/// How serious a validation issue is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// A single problem found in a canonical file
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ValidationIssue {
+    /// 1-based line number in the JSONL file
+    pub line: usize,
+    /// UUID of the offending message, when it could be parsed
+    pub uuid: Option<String>,
+    pub severity: Severity,
+    /// Stable identifier for the kind of issue (e.g. "orphan_tool_result")
+    pub code: &'static str,
+    pub message: String,
+}
+
+/// Result of validating a canonical file
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ValidationReport {
+    pub total_messages: usize,
+    pub issues: Vec<ValidationIssue>,
+}
+
+impl ValidationReport {
+    /// True when there are no errors (warnings are allowed)
+    pub fn is_valid(&self) -> bool {
+        self.errors().next().is_none()
+    }
+
+    pub fn errors(&self) -> impl Iterator<Item = &ValidationIssue> {
+        self.issues
+            .iter()
+            .filter(|issue| issue.severity == Severity::Error)
+    }
+
+    pub fn warnings(&self) -> impl Iterator<Item = &ValidationIssue> {
+        self.issues
+            .iter()
+            .filter(|issue| issue.severity == Severity::Warning)
+    }
+
+    /// One-line summary suitable for the provider log
+    pub fn summary(&self) -> String {
+        let mut codes: Vec<&str> = self.issues.iter().map(|issue| issue.code).collect();
+        codes.sort_unstable();
+        codes.dedup();
+
+        format!(
+            "{} messages, {} errors, {} warnings ({})",
+            self.total_messages,
+            self.errors().count(),
+            self.warnings().count(),
+            codes.join(", ")
+        )
+    }
+}
+
+/// Accumulates issues while walking messages in file order
+#[derive(Default)]
+struct Validator {
+    report: ValidationReport,
+    last_timestamp: Option<DateTime<Utc>>,
+    tool_uses: HashMap<String, (usize, String)>,
+    answered: HashSet<String>,
+}
+
+impl Validator {
+    fn issue(
+        &mut self,
+        line: usize,
+        uuid: Option<&str>,
+        severity: Severity,
+        code: &'static str,
+        message: String,
+    ) {
+        self.report.issues.push(ValidationIssue {
+            line,
+            uuid: uuid.map(str::to_string),
+            severity,
+            code,
+            message,
+        });
+    }
+
+    fn check(&mut self, line: usize, message: &CanonicalMessage) {
+        self.report.total_messages += 1;
+        let uuid = Some(message.uuid.as_str()).filter(|uuid| !uuid.is_empty());
+
+        if message.schema_version > CANONICAL_SCHEMA_VERSION {
+            self.issue(
+                line,
+                uuid,
+                Severity::Warning,
+                "unknown_schema_version",
+                format!(
+                    "schema version {} is newer than supported version {}",
+                    message.schema_version, CANONICAL_SCHEMA_VERSION
+                ),
+            );
+        }
+
+        if message.uuid.is_empty() {
+            self.issue(
+                line,
+                None,
+                Severity::Error,
+                "missing_uuid",
+                "message has an empty uuid".to_string(),
+            );
+        }
+
+        if message.session_id.is_empty() {
+            self.issue(
+                line,
+                uuid,
+                Severity::Error,
+                "missing_session_id",
+                "message has an empty sessionId".to_string(),
+            );
+        }
+
+        let is_meta = message.is_meta == Some(true) || message.message_type == MessageType::Meta;
+        if !is_meta && is_empty_content(&message.message.content) {
+            self.issue(
+                line,
+                uuid,
+                Severity::Error,
+                "empty_content",
+                "non-meta message has no content".to_string(),
+            );
+        }
+
+        self.check_timestamp(line, uuid, &message.timestamp);
+
+        if let ContentValue::Structured(blocks) = &message.message.content {
+            for block in blocks {
+                self.check_block(line, uuid, block);
+            }
+        }
+    }
+
+    fn check_timestamp(&mut self, line: usize, uuid: Option<&str>, timestamp: &str) {
+        let parsed = match DateTime::parse_from_rfc3339(timestamp) {
+            Ok(parsed) => parsed.with_timezone(&Utc),
+            Err(_) => {
+                self.issue(
+                    line,
+                    uuid,
+                    Severity::Error,
+                    "invalid_timestamp",
+                    format!("timestamp '{}' is not RFC 3339", timestamp),
+                );
+                return;
+            }
+        };
+
+        if let Some(previous) = self.last_timestamp {
+            if parsed < previous {
+                self.issue(
+                    line,
+                    uuid,
+                    Severity::Warning,
+                    "non_monotonic_timestamp",
+                    format!(
+                        "timestamp {} is earlier than the previous message ({})",
+                        timestamp,
+                        previous.to_rfc3339()
+                    ),
+                );
+            }
+        }
+
+        self.last_timestamp = Some(self.last_timestamp.map_or(parsed, |prev| prev.max(parsed)));
+    }
+
+    fn check_block(&mut self, line: usize, uuid: Option<&str>, block: &ContentBlock) {
+        match block {
+            ContentBlock::ToolUse { id, name, .. } => {
+                if self.tool_uses.contains_key(id) {
+                    self.issue(
+                        line,
+                        uuid,
+                        Severity::Warning,
+                        "duplicate_tool_use",
+                        format!("tool_use id '{}' was already used", id),
+                    );
+                } else {
+                    self.tool_uses.insert(id.clone(), (line, name.clone()));
+                }
+            }
+            ContentBlock::ToolResult { tool_use_id, .. } => {
+                if self.tool_uses.contains_key(tool_use_id) {
+                    self.answered.insert(tool_use_id.clone());
+                } else {
+                    self.issue(
+                        line,
+                        uuid,
+                        Severity::Warning,
+                        "orphan_tool_result",
+                        format!("tool_result for unknown tool_use '{}'", tool_use_id),
+                    );
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn finish(mut self) -> ValidationReport {
+        let mut unanswered: Vec<(usize, String, String)> = self
+            .tool_uses
+            .iter()
+            .filter(|(id, _)| !self.answered.contains(*id))
+            .map(|(id, (line, name))| (*line, id.clone(), name.clone()))
+            .collect();
+        unanswered.sort();
+
+        for (line, id, name) in unanswered {
+            self.issue(
+                line,
+                None,
+                Severity::Warning,
+                "unmatched_tool_use",
+                format!("tool_use '{}' ({}) has no tool_result", id, name),
+            );
+        }
+
+        self.report.issues.sort_by_key(|issue| issue.line);
+        self.report
+    }
+}
+
+fn is_empty_content(content: &ContentValue) -> bool {
+    match content {
+        ContentValue::Text(text) => text.trim().is_empty(),
+        ContentValue::Structured(blocks) => blocks.is_empty(),
+    }
+}
+
+/// Validate already-parsed messages, numbering them as consecutive lines
+pub fn validate_messages(messages: &[CanonicalMessage]) -> ValidationReport {
+    let mut validator = Validator::default();
+    for (index, message) in messages.iter().enumerate() {
+        validator.check(index + 1, message);
+    }
+    validator.finish()
+}
+
+/// Validate canonical JSONL content
+pub fn validate_jsonl(content: &str) -> ValidationReport {
+    let mut validator = Validator::default();
+
+    for (index, line) in content.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        match serde_json::from_str::<CanonicalMessage>(line) {
+            Ok(message) => validator.check(index + 1, &message),
+            Err(e) => {
+                validator.report.total_messages += 1;
+                validator.issue(
+                    index + 1,
+                    None,
+                    Severity::Error,
+                    "invalid_json",
+                    format!("line is not a canonical message: {}", e),
+                );
+            }
+        }
+    }
+
+    validator.finish()
+}
+
+/// Validate a canonical JSONL file on disk
+pub fn validate_file(path: &Path) -> Result<ValidationReport, String> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    Ok(validate_jsonl(&content))
+}
+
+/// Validate freshly converted messages as they are written out, and log a
+/// summary if anything is wrong
+///
+/// Used by the converters while writing a canonical file. Validation never
+/// blocks a conversion; issues are only logged so bad sessions can be found
+/// and inspected with the `validate_canonical_file` command.
+#[derive(Default)]
+pub struct OutputCheck {
+    validator: Validator,
+}
+
+impl OutputCheck {
+    pub fn check(&mut self, message: &CanonicalMessage) {
+        let line = self.validator.report.total_messages + 1;
+        self.validator.check(line, message);
+    }
+
+    pub fn log(self, provider_id: &str, session_id: &str) {
+        log_report(provider_id, session_id, &self.validator.finish());
+    }
+}
+
+/// Same as `OutputCheck`, re-reading a canonical file that was appended to
+pub fn check_converted_file(provider_id: &str, session_id: &str, path: &Path) {
+    match validate_file(path) {
+        Ok(report) => log_report(provider_id, session_id, &report),
+        Err(e) => {
+            if let Err(log_err) = log_warn(provider_id, &e) {
+                eprintln!("Logging error: {}", log_err);
+            }
+        }
+    }
+}
+
+fn log_report(provider_id: &str, session_id: &str, report: &ValidationReport) {
+    if report.issues.is_empty() {
+        return;
+    }
+
+    let mut msg = format!(
+        "Canonical validation for session {}: {}",
+        session_id,
+        report.summary()
+    );
+    if !report.is_valid() {
+        if let Some(note) = crate::providers::versions::compatibility_note(provider_id) {
+            msg = format!("{} ({})", msg, note);
+        }
+    }
+    let logged = if report.is_valid() {
+        log_warn(provider_id, &msg)
+    } else {
+        log_error(provider_id, &msg)
+    };
+    if let Err(e) = logged {
+        eprintln!("Logging error: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn message(uuid: &str, timestamp: &str, content: ContentValue) -> CanonicalMessage {
+        let mut message = CanonicalMessage::new_text_message(
+            uuid.to_string(),
+            timestamp.to_string(),
+            MessageType::Assistant,
+            "session-1".to_string(),
+            "test".to_string(),
+            "assistant".to_string(),
+            String::new(),
+        );
+        message.message.content = content;
+        message
+    }
+
+    fn text(uuid: &str, timestamp: &str, body: &str) -> CanonicalMessage {
+        message(uuid, timestamp, ContentValue::Text(body.to_string()))
+    }
+
+    fn codes(report: &ValidationReport) -> Vec<&str> {
+        report.issues.iter().map(|issue| issue.code).collect()
+    }
+
+    #[test]
+    fn test_valid_session() {
+        let messages = vec![
+            text("u1", "2025-01-01T00:00:00Z", "hello"),
+            message(
+                "u2",
+                "2025-01-01T00:00:01Z",
+                ContentValue::Structured(vec![ContentBlock::ToolUse {
+                    id: "call_1".to_string(),
+                    name: "Read".to_string(),
+                    input: json!({}),
+                }]),
+            ),
+            message(
+                "u3",
+                "2025-01-01T00:00:02Z",
+                ContentValue::Structured(vec![ContentBlock::ToolResult {
+                    tool_use_id: "call_1".to_string(),
+                    content: "ok".to_string(),
+                    is_error: None,
+                }]),
+            ),
+        ];
+
+        let report = validate_messages(&messages);
+        assert!(report.issues.is_empty(), "{:?}", report.issues);
+        assert_eq!(report.total_messages, 3);
+    }
+
+    #[test]
+    fn test_structural_errors() {
+        let mut meta = text("u3", "2025-01-01T00:00:02Z", "");
+        meta.is_meta = Some(true);
+
+        let messages = vec![
+            text("u1", "2025-01-01T00:00:00Z", "  "),
+            text("", "yesterday", "hi"),
+            meta,
+        ];
+
+        let report = validate_messages(&messages);
+        assert!(!report.is_valid());
+        assert_eq!(
+            codes(&report),
+            vec!["empty_content", "missing_uuid", "invalid_timestamp"]
+        );
+    }
+
+    #[test]
+    fn test_tool_pairing_and_timestamp_warnings() {
+        let messages = vec![
+            message(
+                "u1",
+                "2025-01-01T00:00:05Z",
+                ContentValue::Structured(vec![ContentBlock::ToolUse {
+                    id: "call_1".to_string(),
+                    name: "Bash".to_string(),
+                    input: json!({}),
+                }]),
+            ),
+            message(
+                "u2",
+                "2025-01-01T00:00:01Z",
+                ContentValue::Structured(vec![ContentBlock::ToolResult {
+                    tool_use_id: "call_2".to_string(),
+                    content: "?".to_string(),
+                    is_error: None,
+                }]),
+            ),
+        ];
+
+        let report = validate_messages(&messages);
+        assert!(report.is_valid());
+        assert_eq!(
+            codes(&report),
+            vec![
+                "unmatched_tool_use",
+                "non_monotonic_timestamp",
+                "orphan_tool_result"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validate_jsonl_reports_bad_lines() {
+        let good = serde_json::to_string(&text("u1", "2025-01-01T00:00:00Z", "hi")).unwrap();
+        let content = format!("{}\n\nnot json\n", good);
+
+        let report = validate_jsonl(&content);
+        assert_eq!(report.total_messages, 2);
+        assert_eq!(report.issues.len(), 1);
+        assert_eq!(report.issues[0].line, 3);
+        assert_eq!(report.issues[0].code, "invalid_json");
+    }
+
+    #[test]
+    fn test_missing_schema_version_reads_as_v1() {
+        let mut value = serde_json::to_value(text("u1", "2025-01-01T00:00:00Z", "hi")).unwrap();
+        value.as_object_mut().unwrap().remove("schemaVersion");
+
+        let message: CanonicalMessage = serde_json::from_value(value).unwrap();
+        assert_eq!(message.schema_version, 1);
+    }
+}
@@ -1,9 +1,12 @@
 //! Converter implementation for Claude Code to canonical format
 
+use crate::providers::canonical::media::{block_from_base64, block_from_bytes, block_from_url};
 use crate::providers::canonical::{
-    converter::ToCanonical, CanonicalMessage, MessageContent, MessageType,
+    converter::ToCanonical, CanonicalMessage, ContentBlock, MessageContent, MessageType,
+    CANONICAL_SCHEMA_VERSION,
 };
 use anyhow::{Context, Result};
+use serde_json::Value;
 
 use super::types::{ClaudeEntry, ClaudeEntryType};
 
@@ -62,7 +65,10 @@ impl ToCanonical for ClaudeEntry {
 
         // 5. Extract and clean message content
         let message: MessageContent = if let Some(msg_value) = &self.message {
-            let mut msg: MessageContent = serde_json::from_value(msg_value.clone())
+            let mut msg_value = msg_value.clone();
+            normalize_media_blocks(&mut msg_value);
+
+            let mut msg: MessageContent = serde_json::from_value(msg_value)
                 .context("Failed to parse message content")?;
 
             // Fix empty tool_result content (canonical schema requires non-empty content)
@@ -95,6 +101,7 @@ impl ToCanonical for ClaudeEntry {
 
         // 7. Build canonical message
         Ok(Some(CanonicalMessage {
+            schema_version: CANONICAL_SCHEMA_VERSION,
             uuid,
             timestamp,
             message_type,
@@ -131,6 +138,86 @@ impl ToCanonical for ClaudeEntry {
     }
 }
 
+/// Replace Claude image and document blocks with canonical image/attachment blocks
+///
+/// Embedded base64 data is reduced to a hash. Tool results whose content is a
+/// block array (e.g. a Read of a screenshot) are flattened to text, with any
+/// images lifted into blocks that follow the result.
+fn normalize_media_blocks(message: &mut Value) {
+    let Some(blocks) = message.get_mut("content").and_then(Value::as_array_mut) else {
+        return;
+    };
+
+    let mut normalized = Vec::with_capacity(blocks.len());
+    for mut block in blocks.drain(..) {
+        match block.get("type").and_then(Value::as_str) {
+            Some("image") | Some("document") => normalized.push(media_block_value(&block)),
+            Some("tool_result") => {
+                let lifted = flatten_tool_result(&mut block);
+                normalized.push(block);
+                normalized.extend(lifted);
+            }
+            _ => normalized.push(block),
+        }
+    }
+
+    *blocks = normalized;
+}
+
+/// Flatten array tool_result content to text, returning any media blocks it contained
+fn flatten_tool_result(block: &mut Value) -> Vec<Value> {
+    let Some(items) = block.get("content").and_then(Value::as_array) else {
+        return Vec::new();
+    };
+
+    let mut texts = Vec::new();
+    let mut media = Vec::new();
+    for item in items {
+        match item.get("type").and_then(Value::as_str) {
+            Some("text") => texts.push(item["text"].as_str().unwrap_or_default().to_string()),
+            Some("image") | Some("document") => media.push(media_block_value(item)),
+            _ => {}
+        }
+    }
+
+    block["content"] = Value::String(texts.join("\n"));
+    media
+}
+
+fn media_block_value(block: &Value) -> Value {
+    serde_json::to_value(media_block(block)).unwrap_or_else(|_| block.clone())
+}
+
+/// Convert a Claude image/document block using its `source`
+fn media_block(block: &Value) -> ContentBlock {
+    let source = &block["source"];
+    let media_type = source["media_type"].as_str();
+    let name = block["title"].as_str();
+
+    match source["type"].as_str() {
+        Some("base64") => block_from_base64(
+            media_type.unwrap_or("application/octet-stream"),
+            source["data"].as_str().unwrap_or_default(),
+            name,
+            None,
+        ),
+        Some("url") => block_from_url(source["url"].as_str().unwrap_or_default(), media_type, name),
+        Some("text") => block_from_bytes(
+            media_type.unwrap_or("text/plain"),
+            source["data"].as_str().unwrap_or_default().as_bytes(),
+            name,
+            None,
+        ),
+        // Unknown sources (e.g. uploaded file IDs) are identified by the source itself
+        _ => block_from_bytes(
+            media_type.unwrap_or("application/octet-stream"),
+            source.to_string().as_bytes(),
+            name.or(source["file_id"].as_str()),
+            None,
+        ),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -252,6 +339,52 @@ mod tests {
         assert!(result.is_none(), "system compact_boundary should be filtered out");
     }
 
+    #[test]
+    fn test_images_replaced_by_hashed_blocks() {
+        let json = r#"{
+            "type": "user",
+            "uuid": "uuid-4",
+            "timestamp": "2025-10-20T07:46:00.000Z",
+            "sessionId": "abc-123",
+            "message": {
+                "role": "user",
+                "content": [
+                    {"type": "text", "text": "What is in this screenshot?"},
+                    {"type": "image", "source": {"type": "base64", "media_type": "image/png", "data": "aGVsbG8="}},
+                    {"type": "tool_result", "tool_use_id": "tool-1", "content": [
+                        {"type": "text", "text": "Read image"},
+                        {"type": "image", "source": {"type": "base64", "media_type": "image/jpeg", "data": "d29ybGQ="}}
+                    ]}
+                ]
+            }
+        }"#;
+
+        let entry: ClaudeEntry = serde_json::from_str(json).unwrap();
+        let canonical = entry.to_canonical().unwrap().unwrap();
+
+        let serialized = serde_json::to_string(&canonical).unwrap();
+        assert!(!serialized.contains("aGVsbG8="), "raw image data must not be kept");
+
+        match canonical.message.content {
+            ContentValue::Structured(blocks) => {
+                assert_eq!(blocks.len(), 4);
+                assert!(matches!(
+                    &blocks[1],
+                    ContentBlock::Image { media_type, hash: Some(_), .. } if media_type == "image/png"
+                ));
+                assert!(matches!(
+                    &blocks[2],
+                    ContentBlock::ToolResult { content, .. } if content == "Read image"
+                ));
+                assert!(matches!(
+                    &blocks[3],
+                    ContentBlock::Image { media_type, .. } if media_type == "image/jpeg"
+                ));
+            }
+            _ => panic!("Expected structured content"),
+        }
+    }
+
     #[test]
     fn test_convert_with_structured_content() {
         let json = r#"{
@@ -3,20 +3,21 @@
 use crate::logging::log_debug;
 use crate::providers::canonical::converter::ToCanonical;
 use crate::providers::claude::types::ClaudeEntry;
-use crate::providers::common::get_canonical_path;
-use std::fs;
+use crate::providers::common::{
+    annotate_model, CanonicalWriter, ConvertResult, ConvertedFile, JsonlReader,
+};
 use std::path::{Path, PathBuf};
 
 /// Convert a Claude Code session file to canonical format
 ///
 /// This function:
-/// 1. Reads the native Claude JSONL
+/// 1. Streams the native Claude JSONL line by line
 /// 2. Parses each line as ClaudeEntry
 /// 3. Filters out system events (file-history-snapshot, summary, etc.)
 /// 4. Adds `provider: "claude-code"` field
 /// 5. Fixes empty tool_result content
 /// 6. Merges agent sidechain files
-/// 7. Writes canonical JSONL to cache
+/// 7. Writes canonical JSONL to a temporary file as it goes
 ///
 /// # Arguments
 /// * `claude_file` - Path to native Claude session file
@@ -24,29 +25,25 @@ use std::path::{Path, PathBuf};
 /// * `cwd` - Optional working directory (will be extracted if not provided)
 ///
 /// # Returns
-/// Path to the canonical JSONL file
-pub fn convert_to_canonical_file(
+/// The converted session, ready to persist into the session cache
+pub fn convert_file(
     claude_file: &Path,
     session_id: &str,
     cwd: Option<&str>,
-) -> Result<PathBuf, Box<dyn std::error::Error + Send + Sync>> {
-    // Read native Claude Code JSONL
-    let content = fs::read_to_string(claude_file)?;
+) -> ConvertResult<ConvertedFile> {
+    // Stream native Claude Code JSONL
+    let mut reader = JsonlReader::open(claude_file)?;
 
     // Get source directory for finding agent files
     let source_dir = claude_file
         .parent()
         .ok_or("Source file has no parent directory")?;
 
-    let mut canonical_lines = Vec::new();
+    let mut writer = CanonicalWriter::for_source("claude-code", claude_file)?;
     let mut cwd_value: Option<String> = cwd.map(|s| s.to_string());
 
     // Parse and convert each line independently
-    for (line_num, line) in content.lines().enumerate() {
-        if line.trim().is_empty() {
-            continue;
-        }
-
+    while let Some((line_num, line)) = reader.next_line()? {
         match serde_json::from_str::<ClaudeEntry>(line) {
             Ok(claude_entry) => {
                 // Extract CWD from first entry that has it (if not provided)
@@ -59,57 +56,56 @@ pub fn convert_to_canonical_file(
                     Ok(Some(mut canonical_msg)) => {
                         // Ensure session_id is set correctly
                         canonical_msg.session_id = session_id.to_string();
+                        annotate_model(&mut canonical_msg);
 
-                        canonical_lines.push(serde_json::to_string(&canonical_msg)?);
+                        writer.write(&canonical_msg)?;
 
                         // Check if this message has an agent sidechain
                         if let Some(agent_id) = extract_agent_id_from_tool_use_result(&claude_entry)
                         {
-                            // Load and convert agent messages
-                            if let Ok(agent_lines) =
-                                load_and_convert_agent_messages(source_dir, &agent_id, session_id)
-                            {
-                                canonical_lines.extend(agent_lines);
-                            }
+                            // Merge agent messages; an unreadable agent file is skipped
+                            let _ = append_agent_messages(
+                                source_dir,
+                                &agent_id,
+                                session_id,
+                                &mut writer,
+                            );
                         }
                     }
                     Ok(None) => {
                         // Message was filtered out (e.g., file-history-snapshot)
                     }
                     Err(e) => {
-                        // Log parsing errors but continue processing
+                        // Quarantine the line but continue processing
                         if let Err(log_err) = log_debug(
                             "claude-code",
-                            &format!("Failed to convert line {}: {}", line_num + 1, e),
+                            &format!("Failed to convert line {}: {}", line_num, e),
                         ) {
                             eprintln!("Logging error: {}", log_err);
                         }
+                        writer.quarantine(line_num, line, e);
                     }
                 }
             }
             Err(e) => {
-                // Log parsing errors but continue processing
+                // Quarantine the line but continue processing
                 if let Err(log_err) = log_debug(
                     "claude-code",
-                    &format!("Failed to parse line {}: {}", line_num + 1, e),
+                    &format!("Failed to parse line {}: {}", line_num, e),
                 ) {
                     eprintln!("Logging error: {}", log_err);
                 }
+                writer.quarantine(line_num, line, e);
             }
         }
     }
 
-    // Get project-organized canonical path
-    // Uses ~/.guidemode/sessions/{provider}/{project}/{session_id}.jsonl
-    let canonical_path = get_canonical_path("claude-code", cwd_value.as_deref(), session_id)?;
-
-    // Write converted canonical JSONL
-    if let Some(parent) = canonical_path.parent() {
-        fs::create_dir_all(parent)?;
-    }
-    fs::write(&canonical_path, canonical_lines.join("\n"))?;
-
-    Ok(canonical_path)
+    Ok(ConvertedFile::new(
+        "claude-code",
+        session_id.to_string(),
+        cwd_value,
+        writer,
+    ))
 }
 
 /// Extract agent ID from a Claude entry's toolUseResult
@@ -122,38 +118,59 @@ fn extract_agent_id_from_tool_use_result(entry: &ClaudeEntry) -> Option<String>
         .map(|s| s.to_string())
 }
 
-/// Load and convert agent messages from agent-*.jsonl file
-fn load_and_convert_agent_messages(
+/// The agent-*.jsonl sidechain files a session merges, whether or not they
+/// exist yet
+pub fn sidechain_files(claude_file: &Path) -> Vec<PathBuf> {
+    let (Some(source_dir), Ok(mut reader)) = (claude_file.parent(), JsonlReader::open(claude_file))
+    else {
+        return Vec::new();
+    };
+
+    let mut files = Vec::new();
+    while let Ok(Some((_, line))) = reader.next_line() {
+        // Only lines mentioning an agent can carry one
+        if !line.contains("agentId") {
+            continue;
+        }
+        let Ok(entry) = serde_json::from_str::<ClaudeEntry>(line) else {
+            continue;
+        };
+        if let Some(agent_id) = extract_agent_id_from_tool_use_result(&entry) {
+            let file = source_dir.join(format!("agent-{}.jsonl", agent_id));
+            if !files.contains(&file) {
+                files.push(file);
+            }
+        }
+    }
+    files
+}
+
+/// Convert messages from an agent-*.jsonl file and append them to `writer`
+fn append_agent_messages(
     source_dir: &Path,
     agent_id: &str,
     session_id: &str,
-) -> Result<Vec<String>, Box<dyn std::error::Error + Send + Sync>> {
+    writer: &mut CanonicalWriter,
+) -> std::io::Result<()> {
     let agent_file = source_dir.join(format!("agent-{}.jsonl", agent_id));
 
     if !agent_file.exists() {
         // Agent file may not exist yet during partial writes
-        return Ok(Vec::new());
+        return Ok(());
     }
 
-    let agent_content = fs::read_to_string(&agent_file)?;
-    let mut agent_lines = Vec::new();
-
-    for line in agent_content.lines() {
-        if line.trim().is_empty() {
-            continue;
-        }
-
+    let mut reader = JsonlReader::open(&agent_file)?;
+    while let Some((_, line)) = reader.next_line()? {
         if let Ok(agent_entry) = serde_json::from_str::<ClaudeEntry>(line) {
             if let Ok(Some(mut canonical_msg)) = agent_entry.to_canonical() {
                 // Ensure session_id is set correctly
                 canonical_msg.session_id = session_id.to_string();
+                annotate_model(&mut canonical_msg);
 
-                if let Ok(json) = serde_json::to_string(&canonical_msg) {
-                    agent_lines.push(json);
-                }
+                writer.write(&canonical_msg)?;
             }
         }
     }
 
-    Ok(agent_lines)
+    Ok(())
 }
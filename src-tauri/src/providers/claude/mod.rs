@@ -16,5 +16,5 @@ pub mod types;
 pub mod watcher;
 
 // Re-export main types
-pub use converter_utils::convert_to_canonical_file;
+pub use converter_utils::convert_file;
 pub use scanner::scan_projects;
@@ -1,12 +1,15 @@
 use crate::config::ProjectInfo;
-use crate::logging::{log_debug, log_info, log_warn};
-use crate::providers::common::SessionInfo;
+use crate::events::CancellationToken;
+use crate::ignore_list::IgnoreList;
+use crate::logging::log_debug;
+use crate::providers::common::scan_cache::scan_project_dirs;
+use crate::providers::common::{is_agent_file, JsonlReader, ScanReport, SessionInfo};
 use crate::providers::sort_projects_by_modified;
 use chrono::{DateTime, Utc};
 use serde::Deserialize;
-use shellexpand::tilde;
+use crate::providers::common::expand_home;
 use std::fs;
-use std::path::{Path, PathBuf};
+use std::path::Path;
 
 #[derive(Debug, Deserialize)]
 struct ClaudeLogEntry {
@@ -16,8 +19,7 @@ struct ClaudeLogEntry {
 }
 
 pub fn scan_projects(home_directory: &str) -> Result<Vec<ProjectInfo>, String> {
-    let expanded = tilde(home_directory);
-    let primary_base = PathBuf::from(expanded.into_owned());
+    let primary_base = expand_home(home_directory);
 
     // Build candidate paths with fallbacks for cross-platform support
     let mut base_candidates = Vec::new();
@@ -83,16 +85,17 @@ pub fn scan_projects(home_directory: &str) -> Result<Vec<ProjectInfo>, String> {
 pub fn scan_sessions_filtered(
     base_path: &Path,
     selected_projects: Option<&[String]>,
-) -> Result<Vec<SessionInfo>, String> {
+    cancel: &CancellationToken,
+) -> Result<ScanReport, String> {
     let projects_path = base_path.join("projects");
     if !projects_path.exists() {
-        return Ok(Vec::new());
+        return Ok(ScanReport::default());
     }
 
-    let mut sessions = Vec::new();
     let entries = fs::read_dir(&projects_path)
         .map_err(|e| format!("Failed to read Claude projects directory: {}", e))?;
 
+    let mut projects = Vec::new();
     for entry in entries.flatten() {
         let project_path = entry.path();
         if !project_path.is_dir() {
@@ -110,78 +113,116 @@ pub fn scan_sessions_filtered(
             }
         }
 
-        // Find all .jsonl files in this project
-        if let Ok(project_entries) = fs::read_dir(&project_path) {
-            for project_entry in project_entries.flatten() {
-                let file_path = project_entry.path();
-                if file_path.extension().and_then(|ext| ext.to_str()) == Some("jsonl") {
-                    // Skip agent files - they will be merged when processing the main session
-                    if let Some(filename) = file_path.file_name().and_then(|n| n.to_str()) {
-                        use super::super::common::is_agent_file;
-                        if is_agent_file(filename) {
-                            continue;
-                        }
-                    }
-
-                    match parse_claude_session(&file_path, project_name) {
-                        Ok(mut session_info) => {
-                            session_info.provider = "claude-code".to_string();
-                            sessions.push(session_info);
-                        }
-                        Err(e) => {
-                            if let Err(log_err) = log_warn(
-                                "claude-code",
-                                &format!("Failed to parse session {}: {}", file_path.display(), e),
-                            ) {
-                                eprintln!("Logging error: {}", log_err);
-                            }
-                        }
-                    }
-                }
-            }
-        }
+        projects.push((project_name.to_string(), project_path));
     }
 
-    if let Err(e) = log_info(
+    Ok(scan_project_dirs(
         "claude-code",
-        &format!("📊 Found {} Claude Code sessions", sessions.len()),
-    ) {
-        eprintln!("Logging error: {}", e);
+        projects,
+        cancel,
+        |project_path| {
+            // Find all .jsonl files in this project
+            let Ok(project_entries) = fs::read_dir(project_path) else {
+                return Vec::new();
+            };
+            project_entries
+                .flatten()
+                .map(|project_entry| project_entry.path())
+                .filter(|file_path| {
+                    file_path.extension().and_then(|ext| ext.to_str()) == Some("jsonl")
+                })
+                // Skip agent files - they will be merged when processing the main session
+                .filter(|file_path| {
+                    file_path
+                        .file_name()
+                        .and_then(|n| n.to_str())
+                        .is_none_or(|filename| !is_agent_file(filename))
+                })
+                .collect()
+        },
+        |file_path, project_name| {
+            let Some(mut session_info) = parse_claude_session(file_path, project_name)? else {
+                return Ok(None);
+            };
+            session_info.provider = "claude-code".to_string();
+            Ok(Some(session_info))
+        },
+    ))
+}
+
+/// The cwd recorded on a session line, if any
+fn line_cwd(line: &str) -> Option<String> {
+    let value = serde_json::from_str::<serde_json::Value>(line).ok()?;
+    value
+        .get("cwd")
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string())
+}
+
+/// The lines of a session file the scanner needs
+struct SessionBounds {
+    first: String,
+    last: String,
+    /// First cwd in the first 50 lines
+    cwd: Option<String>,
+}
+
+/// Read a session's first and last lines and its cwd in one streaming pass
+fn read_session_bounds(file_path: &Path) -> Result<SessionBounds, String> {
+    let mut reader =
+        JsonlReader::open(file_path).map_err(|e| format!("Failed to read file: {}", e))?;
+    let mut bounds: Option<SessionBounds> = None;
+
+    while let Some((line_number, line)) = reader
+        .next_line()
+        .map_err(|e| format!("Failed to read file: {}", e))?
+    {
+        let bounds = bounds.get_or_insert_with(|| SessionBounds {
+            first: line.to_string(),
+            last: String::new(),
+            cwd: None,
+        });
+        if bounds.cwd.is_none() && line_number <= 50 {
+            bounds.cwd = line_cwd(line);
+        }
+        bounds.last.clear();
+        bounds.last.push_str(line);
     }
 
-    Ok(sessions)
+    bounds.ok_or_else(|| "File is empty".to_string())
 }
 
-fn extract_cwd_from_claude_session(content: &str) -> Option<String> {
-    // Look through the first 50 lines for a cwd field
-    for line in content.lines().take(50) {
-        if let Ok(value) = serde_json::from_str::<serde_json::Value>(line) {
-            if let Some(cwd) = value.get("cwd").and_then(|v| v.as_str()) {
-                return Some(cwd.to_string());
-            }
+/// The cwd recorded in the first 50 lines of a session file
+pub(crate) fn session_cwd(file_path: &Path) -> Option<String> {
+    let mut reader = JsonlReader::open(file_path).ok()?;
+    while let Ok(Some((line_number, line))) = reader.next_line() {
+        if line_number > 50 {
+            break;
+        }
+        if let Some(cwd) = line_cwd(line) {
+            return Some(cwd);
         }
     }
     None
 }
 
-fn parse_claude_session(file_path: &Path, project_name: &str) -> Result<SessionInfo, String> {
-    let content =
-        fs::read_to_string(file_path).map_err(|e| format!("Failed to read file: {}", e))?;
-
-    let lines: Vec<&str> = content
-        .lines()
-        .filter(|line| !line.trim().is_empty())
-        .collect();
-    if lines.is_empty() {
-        return Err("File is empty".to_string());
+/// Parse a session and convert it into the canonical cache; None when its
+/// project is ignored
+pub(crate) fn parse_claude_session(
+    file_path: &Path,
+    project_name: &str,
+) -> Result<Option<SessionInfo>, String> {
+    let bounds = read_session_bounds(file_path)?;
+    if IgnoreList::current().is_ignored(project_name, bounds.cwd.as_deref()) {
+        return Ok(None);
     }
 
     // Parse first line for session start
-    let first_entry: ClaudeLogEntry =
-        serde_json::from_str(lines[0]).map_err(|e| format!("Failed to parse first line: {}", e))?;
+    let first_entry: ClaudeLogEntry = serde_json::from_str(&bounds.first)
+        .map_err(|e| format!("Failed to parse first line: {}", e))?;
 
     // Parse last line for session end
-    let last_entry: ClaudeLogEntry = serde_json::from_str(lines[lines.len() - 1])
+    let last_entry: ClaudeLogEntry = serde_json::from_str(&bounds.last)
         .map_err(|e| format!("Failed to parse last line: {}", e))?;
 
     // Extract session ID (prefer from first entry, fallback to filename)
@@ -222,8 +263,8 @@ fn parse_claude_session(file_path: &Path, project_name: &str) -> Result<SessionI
         .unwrap_or("unknown.jsonl")
         .to_string();
 
-    // Try to extract CWD from session content (look for cwd in early entries)
-    let cwd = extract_cwd_from_claude_session(&content);
+    // CWD from the session's early entries
+    let cwd = bounds.cwd;
 
     if cwd.is_none() {
         if let Err(e) = log_debug(
@@ -249,9 +290,10 @@ fn parse_claude_session(file_path: &Path, project_name: &str) -> Result<SessionI
     // 2. Add provider field
     // 3. Fix empty tool_result content
     // 4. Merge agent sidechain files
-    use super::converter_utils::convert_to_canonical_file;
+    use super::converter_utils::convert_file;
 
-    let cache_path = convert_to_canonical_file(file_path, &session_id, cwd.as_deref())
+    let cache_path = convert_file(file_path, &session_id, cwd.as_deref())
+        .and_then(|converted| converted.persist())
         .map_err(|e| format!("Failed to convert session: {}", e))?;
 
     // Update file size from cache file
@@ -261,7 +303,7 @@ fn parse_claude_session(file_path: &Path, project_name: &str) -> Result<SessionI
     // The real project name will be derived from CWD later during upload
     // This ensures filtering works correctly with user-selected projects
 
-    Ok(SessionInfo {
+    Ok(Some(SessionInfo {
         provider: "claude-code".to_string(),
         project_name: project_name.to_string(), // Use Claude Code folder name for filtering
         session_id,
@@ -274,5 +316,5 @@ fn parse_claude_session(file_path: &Path, project_name: &str) -> Result<SessionI
         content: None,      // Claude Code sessions use files directly
         cwd,                // CWD will be used to derive real project name during upload
         project_hash: None, // Not used for Claude Code
-    })
+    }))
 }
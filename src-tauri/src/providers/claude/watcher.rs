@@ -1,14 +1,16 @@
 use crate::config::load_provider_config;
 use crate::events::{EventBus, SessionEventPayload};
+use crate::ignore_list::IgnoreList;
 use crate::logging::{log_debug, log_error, log_info, log_warn};
+use crate::providers::claude::scanner::session_cwd;
 use crate::providers::common::{
-    extract_session_id_from_filename,
-    get_file_size, has_extension, should_skip_file, SessionStateManager, WatcherStatus,
-    EVENT_TIMEOUT, FILE_WATCH_POLL_INTERVAL, MIN_SIZE_CHANGE_BYTES,
+    access, expand_home, extract_session_id_from_filename, file_watcher, get_file_size,
+    has_extension, should_skip_file, FileWatcher, SessionStateManager, WatcherStatus, EVENT_TIMEOUT,
+    FILE_WATCH_POLL_INTERVAL, MIN_SIZE_CHANGE_BYTES,
 };
+use crate::providers::convert_file_to_canonical;
 use crate::upload_queue::UploadQueue;
-use notify::{Config, Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
-use shellexpand::tilde;
+use notify::{Config, Event, EventKind, RecursiveMode};
 use std::path::{Path, PathBuf};
 use std::sync::{mpsc, Arc, Mutex};
 use std::thread;
@@ -25,7 +27,7 @@ pub struct FileChangeEvent {
 
 #[derive(Debug)]
 pub struct ClaudeWatcher {
-    _watcher: RecommendedWatcher,
+    _watcher: FileWatcher,
     _thread_handle: thread::JoinHandle<()>,
     upload_queue: Arc<UploadQueue>,
     is_running: Arc<Mutex<bool>>,
@@ -50,8 +52,8 @@ impl ClaudeWatcher {
         }
 
         let home_directory = config.home_directory;
-        let expanded_home = tilde(&home_directory);
-        let base_path = Path::new(expanded_home.as_ref());
+        let expanded_home = expand_home(&home_directory);
+        let base_path = expanded_home.as_path();
 
         if !base_path.exists() {
             return Err(format!(
@@ -107,7 +109,9 @@ impl ClaudeWatcher {
         let (tx, rx) = mpsc::channel();
 
         // Create the file watcher
-        let mut watcher = RecommendedWatcher::new(
+        let watcher = FileWatcher::new(
+            PROVIDER_ID,
+            base_path,
             tx,
             Config::default().with_poll_interval(FILE_WATCH_POLL_INTERVAL),
         )?;
@@ -140,10 +144,13 @@ impl ClaudeWatcher {
         let projects_path_clone = projects_path.clone();
         let event_bus_clone = event_bus.clone();
 
+        let file_watcher = watcher.clone();
+
         // Start background thread to handle file events
         let thread_handle = thread::spawn(move || {
             Self::file_event_processor(
                 rx,
+                file_watcher,
                 projects_path_clone,
                 upload_queue_clone,
                 event_bus_clone,
@@ -180,6 +187,7 @@ impl ClaudeWatcher {
 
     fn file_event_processor(
         rx: mpsc::Receiver<Result<Event, notify::Error>>,
+        file_watcher: FileWatcher,
         projects_path: PathBuf,
         _upload_queue: Arc<UploadQueue>,
         event_bus: EventBus,
@@ -262,10 +270,12 @@ impl ClaudeWatcher {
                     }
                 }
                 Ok(Err(error)) => {
-                    if let Err(e) =
-                        log_error(PROVIDER_ID, &format!("File watcher error: {:?}", error))
-                    {
-                        eprintln!("Logging error: {}", e);
+                    if !file_watcher.handle_error(&error) {
+                        if let Err(e) =
+                            log_error(PROVIDER_ID, &format!("File watcher error: {:?}", error))
+                        {
+                            eprintln!("Logging error: {}", e);
+                        }
                     }
                 }
                 Err(mpsc::RecvTimeoutError::Timeout) => {
@@ -287,12 +297,9 @@ impl ClaudeWatcher {
 
     fn convert_to_canonical_file(
         claude_file: &Path,
-        session_id: &str,
     ) -> Result<PathBuf, Box<dyn std::error::Error + Send + Sync>> {
-        use crate::providers::claude::convert_to_canonical_file as convert;
-
         // Use shared conversion function
-        convert(claude_file, session_id, None)
+        convert_file_to_canonical(PROVIDER_ID, claude_file)?.persist()
     }
 
     fn process_file_event(event: &Event, projects_path: &Path) -> Option<FileChangeEvent> {
@@ -323,11 +330,17 @@ impl ClaudeWatcher {
                         // Extract session ID
                         let session_id = extract_session_id_from_filename(path);
 
+                        // Ignored projects never reach the canonical cache
+                        let ignore_list = IgnoreList::current();
+                        if !ignore_list.is_empty()
+                            && ignore_list.is_ignored(&project_name, session_cwd(path).as_deref())
+                        {
+                            continue;
+                        }
+
                         // Copy to canonical cache for consistency
-                        let canonical_path = match Self::convert_to_canonical_file(path, &session_id) {
-                            Ok(cache_path) => {
-                                cache_path
-                            }
+                        let canonical_path = match Self::convert_to_canonical_file(path) {
+                            Ok(cache_path) => cache_path,
                             Err(e) => {
                                 // Check if this is expected (partial file without CWD)
                                 let error_msg = e.to_string();
@@ -407,6 +420,8 @@ impl ClaudeWatcher {
             pending_uploads: upload_status.pending,
             processing_uploads: upload_status.processing,
             failed_uploads: upload_status.failed,
+            access_denied: access::denied_path(PROVIDER_ID),
+            watch_limit: file_watcher::watch_limit_hint(PROVIDER_ID),
         }
     }
 }
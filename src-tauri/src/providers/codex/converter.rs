@@ -1,7 +1,9 @@
+use crate::providers::canonical::media::block_from_url;
 use crate::providers::canonical::{
     converter::ToCanonical, CanonicalMessage, ContentBlock, ContentValue, MessageContent,
-    MessageType, TokenUsage,
+    MessageType, TokenUsage, CANONICAL_SCHEMA_VERSION,
 };
+use crate::providers::common::annotate_model;
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
@@ -10,6 +12,15 @@ use serde_json::Value;
 ///
 /// Codex messages have a consistent wrapper with timestamp, type, and payload.
 /// The payload structure varies based on type.
+///
+/// Two rollout formats are supported:
+/// - v1: flat `session_meta` payload (`id`, `cwd`, `cli_version`, ...)
+/// - v2: `session_meta` fields nested under `meta` with renamed keys
+///   (`session_id`, `working_directory`, `version`), plus new `response_item`
+///   types (`custom_tool_call`, `local_shell_call`, `web_search_call`, ...)
+///
+/// Anything unrecognised is kept as `CodexPayload::Unknown` so a single new
+/// line type never causes the whole session to be skipped.
 #[derive(Debug, Clone, Serialize)]
 pub struct CodexMessage {
     pub timestamp: String,
@@ -39,29 +50,29 @@ impl<'de> Deserialize<'de> for CodexMessage {
         let payload_value = value.get("payload")
             .ok_or_else(|| D::Error::missing_field("payload"))?;
 
-        // Deserialize payload based on message_type
-        let payload = match message_type.as_str() {
+        // Deserialize payload based on message_type, falling back to passthrough
+        let parsed = match message_type.as_str() {
             "session_meta" => {
-                CodexPayload::SessionMeta(serde_json::from_value(payload_value.clone())
-                    .map_err(D::Error::custom)?)
+                serde_json::from_value(normalize_session_meta(payload_value))
+                    .map(CodexPayload::SessionMeta)
             }
             "response_item" => {
-                CodexPayload::ResponseItem(serde_json::from_value(payload_value.clone())
-                    .map_err(D::Error::custom)?)
+                serde_json::from_value(payload_value.clone()).map(CodexPayload::ResponseItem)
             }
             "event_msg" => {
-                CodexPayload::EventMsg(serde_json::from_value(payload_value.clone())
-                    .map_err(D::Error::custom)?)
+                serde_json::from_value(payload_value.clone()).map(CodexPayload::EventMsg)
             }
             "turn_context" => {
-                CodexPayload::TurnContext(serde_json::from_value(payload_value.clone())
-                    .map_err(D::Error::custom)?)
-            }
-            _ => {
-                return Err(D::Error::custom(format!("Unknown message type: {}", message_type)));
+                serde_json::from_value(payload_value.clone()).map(CodexPayload::TurnContext)
             }
+            _ => Ok(CodexPayload::Unknown(payload_value.clone())),
         };
 
+        let payload = parsed.unwrap_or_else(|e| {
+            tracing::debug!("Unrecognised Codex {} payload, passing through: {}", message_type, e);
+            CodexPayload::Unknown(payload_value.clone())
+        });
+
         Ok(CodexMessage {
             timestamp,
             message_type,
@@ -70,6 +81,28 @@ impl<'de> Deserialize<'de> for CodexMessage {
     }
 }
 
+/// Flatten a v2 `session_meta` payload (`{"meta": {...}, "git": {...}}`) into the v1 shape
+///
+/// Top-level keys win over nested ones; v1 payloads are returned unchanged.
+fn normalize_session_meta(payload: &Value) -> Value {
+    let Some(obj) = payload.as_object() else {
+        return payload.clone();
+    };
+    let Some(meta) = obj.get("meta").and_then(|m| m.as_object()) else {
+        return payload.clone();
+    };
+
+    let mut merged = meta.clone();
+    for (key, value) in obj {
+        if key != "meta" {
+            merged.insert(key.clone(), value.clone());
+        }
+    }
+    merged.insert("format_version".to_string(), Value::from(2));
+
+    Value::Object(merged)
+}
+
 /// Codex payload wrapper
 ///
 /// The payload structure is polymorphic based on parent message_type
@@ -83,29 +116,43 @@ pub enum CodexPayload {
     EventMsg(EventMsgPayload),
     /// Turn context (type: "turn_context")
     TurnContext(TurnContextPayload),
+    /// Unrecognised line type or payload shape, preserved as-is
+    Unknown(Value),
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct SessionMetaPayload {
+    #[serde(alias = "session_id", alias = "sessionId")]
     pub id: String,
-    pub timestamp: String,
+    #[serde(default)]
+    pub timestamp: Option<String>,
+    #[serde(alias = "working_directory", alias = "workdir")]
     pub cwd: String,
-    pub originator: String,
+    #[serde(default)]
+    pub originator: Option<String>,
+    #[serde(default, alias = "version")]
     pub cli_version: Option<String>,
+    #[serde(default)]
     pub git: Option<GitInfo>,
+    /// Remaining fields (instructions, model_provider, source, format_version, ...)
+    #[serde(flatten)]
+    pub other: serde_json::Map<String, Value>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct GitInfo {
+    #[serde(default, alias = "commit")]
     pub commit_hash: Option<String>,
+    #[serde(default)]
     pub branch: Option<String>,
+    #[serde(default, alias = "remote_url")]
     pub repository_url: Option<String>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct ResponseItemPayload {
     #[serde(rename = "type")]
-    pub item_type: String, // "message", "function_call", "function_call_output", "reasoning"
+    pub item_type: String, // "message", "function_call", "function_call_output", "reasoning", ...
     #[serde(flatten)]
     pub data: Value,
 }
@@ -120,6 +167,7 @@ pub struct EventMsgPayload {
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct TurnContextPayload {
+    #[serde(alias = "working_directory")]
     pub cwd: String,
     pub model: Option<String>,
     #[serde(flatten)]
@@ -161,6 +209,26 @@ impl CodexMessage {
             _ => None,
         }
     }
+
+    /// Extract the model selected for the following turn
+    pub fn get_model(&self) -> Option<String> {
+        match &self.payload {
+            CodexPayload::TurnContext(ctx) => ctx.model.clone(),
+            _ => None,
+        }
+    }
+}
+
+/// Attribute an assistant message to the model of the current turn
+///
+/// Codex only reports the model in `turn_context` lines, so messages are
+/// filled from the most recent one before their model is normalized.
+pub fn apply_turn_model(canonical_msg: &mut CanonicalMessage, turn_model: Option<&str>) {
+    if canonical_msg.message_type == MessageType::Assistant && canonical_msg.message.model.is_none()
+    {
+        canonical_msg.message.model = turn_model.map(str::to_string);
+    }
+    annotate_model(canonical_msg);
 }
 
 impl ToCanonical for CodexMessage {
@@ -176,6 +244,7 @@ impl ToCanonical for CodexMessage {
             CodexPayload::SessionMeta(_) => {
                 // Session meta becomes a meta message
                 Ok(Some(CanonicalMessage {
+                    schema_version: CANONICAL_SCHEMA_VERSION,
                     uuid,
                     timestamp: self.timestamp.clone(),
                     message_type: MessageType::Meta,
@@ -199,9 +268,22 @@ impl ToCanonical for CodexMessage {
                     tool_use_result: None,
                 }))
             }
+            CodexPayload::Unknown(payload) => {
+                // Unknown line type - keep it as a meta message so nothing is lost
+                Ok(Some(self.passthrough_message(
+                    &uuid,
+                    &session_id,
+                    serde_json::json!({
+                        "codex_type": self.message_type,
+                        "payload": payload,
+                        "warning": "unknown codex message type",
+                    }),
+                )))
+            }
             CodexPayload::TurnContext(_) => {
                 // Turn context is metadata - preserve full payload
                 Ok(Some(CanonicalMessage {
+                    schema_version: CANONICAL_SCHEMA_VERSION,
                     uuid,
                     timestamp: self.timestamp.clone(),
                     message_type: MessageType::Meta,
@@ -274,7 +356,32 @@ impl CodexMessage {
                     String::new()
                 };
 
+                // input_image items carry data: URLs; keep only their hash
+                let images: Vec<ContentBlock> = content
+                    .as_array()
+                    .map(|items| {
+                        items
+                            .iter()
+                            .filter(|c| c["type"] == "input_image")
+                            .filter_map(|c| c["image_url"].as_str())
+                            .map(|url| block_from_url(url, None, None))
+                            .collect()
+                    })
+                    .unwrap_or_default();
+
+                let content_value = if images.is_empty() {
+                    ContentValue::Text(content_text)
+                } else {
+                    let mut blocks = Vec::with_capacity(images.len() + 1);
+                    if !content_text.is_empty() {
+                        blocks.push(ContentBlock::Text { text: content_text });
+                    }
+                    blocks.extend(images);
+                    ContentValue::Structured(blocks)
+                };
+
                 Ok(Some(CanonicalMessage {
+                    schema_version: CANONICAL_SCHEMA_VERSION,
                     uuid: uuid.to_string(),
                     timestamp: self.timestamp.clone(),
                     message_type: if role == "user" {
@@ -292,7 +399,7 @@ impl CodexMessage {
                     user_type: Some("external".to_string()),
                     message: MessageContent {
                         role: role.to_string(),
-                        content: ContentValue::Text(content_text),
+                        content: content_value,
                         model: None,
                         usage: None,
                     },
@@ -326,6 +433,7 @@ impl CodexMessage {
                 };
 
                 Ok(Some(CanonicalMessage {
+                    schema_version: CANONICAL_SCHEMA_VERSION,
                     uuid: uuid.to_string(),
                     timestamp: self.timestamp.clone(),
                     message_type: MessageType::Assistant,
@@ -355,11 +463,8 @@ impl CodexMessage {
             "function_call_output" => {
                 let call_id = item.data["call_id"].as_str().unwrap_or(uuid);
 
-                // Simple output extraction to match TypeScript
-                let output = item.data["output"]
-                    .as_str()
-                    .unwrap_or("")
-                    .to_string();
+                // v1 stores output as a string, v2 as {"content": ..., "success": ...}
+                let (output, is_error) = extract_tool_output(&item.data["output"]);
 
                 // Validate we have required data for tool_result
                 // Don't create empty tool_result blocks (causes parsing issues)
@@ -374,10 +479,11 @@ impl CodexMessage {
                 let block = ContentBlock::ToolResult {
                     tool_use_id: call_id.to_string(),
                     content: output,
-                    is_error: Some(false),
+                    is_error: Some(is_error),
                 };
 
                 Ok(Some(CanonicalMessage {
+                    schema_version: CANONICAL_SCHEMA_VERSION,
                     uuid: uuid.to_string(),
                     timestamp: self.timestamp.clone(),
                     message_type: MessageType::User,  // Tool results are USER messages
@@ -420,6 +526,7 @@ impl CodexMessage {
                 };
 
                 Ok(Some(CanonicalMessage {
+                    schema_version: CANONICAL_SCHEMA_VERSION,
                     uuid: uuid.to_string(),
                     timestamp: self.timestamp.clone(),
                     message_type: MessageType::Assistant,
@@ -446,39 +553,155 @@ impl CodexMessage {
                     tool_use_result: None,
                 }))
             }
+            "custom_tool_call" | "local_shell_call" | "web_search_call" => {
+                let (call_id, name, input) = match item.item_type.as_str() {
+                    "custom_tool_call" => {
+                        let name = item.data["name"].as_str().context("Missing tool name")?;
+                        // Custom tool input is free-form text, often (but not always) JSON
+                        let input = match &item.data["input"] {
+                            Value::String(raw) => serde_json::from_str(raw)
+                                .unwrap_or_else(|_| serde_json::json!({ "input": raw })),
+                            other => other.clone(),
+                        };
+                        (item.data["call_id"].as_str(), name.to_string(), input)
+                    }
+                    "local_shell_call" => (
+                        item.data["call_id"].as_str().or(item.data["id"].as_str()),
+                        "local_shell".to_string(),
+                        item.data["action"].clone(),
+                    ),
+                    _ => (
+                        item.data["id"].as_str(),
+                        "web_search".to_string(),
+                        item.data["action"].clone(),
+                    ),
+                };
+
+                let block = ContentBlock::ToolUse {
+                    id: call_id.unwrap_or(uuid).to_string(),
+                    name,
+                    input,
+                };
+
+                Ok(Some(self.structured_message(
+                    uuid,
+                    session_id,
+                    MessageType::Assistant,
+                    "assistant",
+                    block,
+                    &item.item_type,
+                )))
+            }
+            "custom_tool_call_output" => {
+                let call_id = item.data["call_id"].as_str().unwrap_or(uuid);
+                let (output, is_error) = extract_tool_output(&item.data["output"]);
+
+                if call_id.is_empty() || output.is_empty() {
+                    return Err(anyhow::anyhow!(
+                        "Tool result missing required fields: call_id='{}', output length={}",
+                        call_id,
+                        output.len()
+                    ));
+                }
+
+                let block = ContentBlock::ToolResult {
+                    tool_use_id: call_id.to_string(),
+                    content: output,
+                    is_error: Some(is_error),
+                };
+
+                Ok(Some(self.structured_message(
+                    uuid,
+                    session_id,
+                    MessageType::User, // Tool results are USER messages
+                    "user",
+                    block,
+                    &item.item_type,
+                )))
+            }
             _ => {
                 // Unknown type, preserve full payload for future analysis
-                Ok(Some(CanonicalMessage {
-                    uuid: uuid.to_string(),
-                    timestamp: self.timestamp.clone(),
-                    message_type: MessageType::Assistant,
-                    session_id: session_id.to_string(),
-                    provider: self.provider_name().to_string(),
-                    cwd: self.extract_cwd(),
-                    git_branch: self.extract_git_branch(),
-                    version: None,
-                    parent_uuid: None,
-                    is_sidechain: None,
-                    user_type: Some("external".to_string()),
-                    message: MessageContent {
-                        role: "assistant".to_string(),
-                        content: ContentValue::Text(String::new()),
-                        model: None,
-                        usage: None,
-                    },
-                    provider_metadata: Some(serde_json::json!({
+                Ok(Some(self.passthrough_message(
+                    uuid,
+                    session_id,
+                    serde_json::json!({
                         "codex_type": "response_item",
                         "item_type": item.item_type,
+                        "payload": item.data,
                         "warning": "unknown response_item type",
-                    })),
-                    is_meta: None,
-                    request_id: None,
-                    tool_use_result: None,
-                }))
+                    }),
+                )))
             }
         }
     }
 
+    /// Build a single-block tool message from a response_item
+    fn structured_message(
+        &self,
+        uuid: &str,
+        session_id: &str,
+        message_type: MessageType,
+        role: &str,
+        block: ContentBlock,
+        item_type: &str,
+    ) -> CanonicalMessage {
+        CanonicalMessage {
+            schema_version: CANONICAL_SCHEMA_VERSION,
+            uuid: uuid.to_string(),
+            timestamp: self.timestamp.clone(),
+            message_type,
+            session_id: session_id.to_string(),
+            provider: self.provider_name().to_string(),
+            cwd: self.extract_cwd(),
+            git_branch: self.extract_git_branch(),
+            version: None,
+            parent_uuid: None,
+            is_sidechain: None,
+            user_type: Some("external".to_string()),
+            message: MessageContent {
+                role: role.to_string(),
+                content: ContentValue::Structured(vec![block]),
+                model: None,
+                usage: None,
+            },
+            provider_metadata: Some(serde_json::json!({
+                "codex_type": "response_item",
+                "item_type": item_type,
+            })),
+            is_meta: None,
+            request_id: None,
+            tool_use_result: None,
+        }
+    }
+
+    /// Build a meta message that carries an unrecognised payload in provider_metadata
+    fn passthrough_message(&self, uuid: &str, session_id: &str, metadata: Value) -> CanonicalMessage {
+        CanonicalMessage {
+            schema_version: CANONICAL_SCHEMA_VERSION,
+            uuid: uuid.to_string(),
+            timestamp: self.timestamp.clone(),
+            message_type: MessageType::Meta,
+            session_id: session_id.to_string(),
+            provider: self.provider_name().to_string(),
+            cwd: self.extract_cwd(),
+            git_branch: None,
+            version: None,
+            parent_uuid: None,
+            is_sidechain: None,
+            user_type: Some("external".to_string()),
+            message: MessageContent {
+                role: "assistant".to_string(),
+                content: ContentValue::Text(String::new()),
+                model: None,
+                usage: None,
+            },
+            provider_metadata: Some(metadata),
+            is_meta: Some(true),
+            request_id: None,
+            tool_use_result: None,
+        }
+    }
+
     fn convert_event_msg(
         &self,
         event: &EventMsgPayload,
@@ -505,6 +728,7 @@ impl CodexMessage {
                 };
 
                 Ok(Some(CanonicalMessage {
+                    schema_version: CANONICAL_SCHEMA_VERSION,
                     uuid: uuid.to_string(),
                     timestamp: self.timestamp.clone(),
                     message_type: MessageType::Meta,
@@ -549,6 +773,7 @@ impl CodexMessage {
             _ => {
                 // Unknown event type - preserve type for debugging
                 Ok(Some(CanonicalMessage {
+                    schema_version: CANONICAL_SCHEMA_VERSION,
                     uuid: uuid.to_string(),
                     timestamp: self.timestamp.clone(),
                     message_type: MessageType::Meta,
@@ -580,6 +805,30 @@ impl CodexMessage {
     }
 }
 
+/// Extract tool output text and error flag
+///
+/// v1 rollouts store the output as a plain string; v2 stores
+/// `{"content": "...", "success": bool}` (or a list of content items).
+fn extract_tool_output(output: &Value) -> (String, bool) {
+    match output {
+        Value::String(text) => (text.clone(), false),
+        Value::Object(obj) => {
+            let content = match obj.get("content") {
+                Some(Value::String(text)) => text.clone(),
+                Some(Value::Array(items)) => items
+                    .iter()
+                    .filter_map(|item| item["text"].as_str())
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+                _ => String::new(),
+            };
+            let is_error = obj.get("success").and_then(|v| v.as_bool()) == Some(false);
+            (content, is_error)
+        }
+        _ => (String::new(), false),
+    }
+}
+
 /// Generate a deterministic UUID from Codex timestamp and session ID
 fn generate_uuid_from_codex(timestamp: &str, session_id: &str) -> String {
     use std::collections::hash_map::DefaultHasher;
@@ -655,6 +904,36 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_convert_user_message_with_image() {
+        let json = r#"{
+            "timestamp": "2025-10-20T06:46:47.990Z",
+            "type": "response_item",
+            "payload": {
+                "type": "message",
+                "role": "user",
+                "content": [
+                    {"type": "input_text", "text": "What does this show?"},
+                    {"type": "input_image", "image_url": "data:image/png;base64,aGVsbG8="}
+                ]
+            }
+        }"#;
+
+        let msg: CodexMessage = serde_json::from_str(json).unwrap();
+        let canonical = msg.to_canonical().unwrap().unwrap();
+
+        match canonical.message.content {
+            ContentValue::Structured(blocks) => {
+                assert_eq!(blocks.len(), 2);
+                assert!(matches!(
+                    &blocks[1],
+                    ContentBlock::Image { media_type, hash: Some(_), .. } if media_type == "image/png"
+                ));
+            }
+            _ => panic!("Expected structured content"),
+        }
+    }
+
     #[test]
     fn test_convert_function_call() {
         let json = r#"{
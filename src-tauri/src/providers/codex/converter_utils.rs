@@ -0,0 +1,142 @@
+//! Shared utilities for converting Codex rollouts to canonical format
+
+use crate::logging::log_error;
+use crate::providers::canonical::converter::ToCanonical;
+use crate::providers::codex::converter::{apply_turn_model, CodexMessage};
+use crate::providers::codex::scanner::session_id_from_filename;
+use crate::providers::common::{CanonicalWriter, ConvertResult, ConvertedFile, JsonlReader};
+use chrono::{DateTime, Utc};
+use std::io;
+use std::path::Path;
+
+const PROVIDER_ID: &str = "codex";
+
+/// Number of leading lines searched for session metadata
+const SESSION_META_SEARCH_LINES: usize = 10;
+
+/// When a rollout's first and last lines were written
+#[derive(Debug, Default)]
+pub struct RolloutSpan {
+    pub start: Option<DateTime<Utc>>,
+    pub end: Option<DateTime<Utc>>,
+}
+
+/// The messages that parse among a rollout's leading lines, where its session
+/// metadata is (not always on the first line)
+pub fn leading_messages(codex_file: &Path) -> io::Result<Vec<CodexMessage>> {
+    let mut reader = JsonlReader::open(codex_file)?;
+    let mut messages = Vec::new();
+    let mut line_count = 0;
+    while let Some((_, line)) = reader.next_line()? {
+        if let Ok(message) = serde_json::from_str::<CodexMessage>(line) {
+            messages.push(message);
+        }
+        line_count += 1;
+        if line_count == SESSION_META_SEARCH_LINES {
+            break;
+        }
+    }
+    Ok(messages)
+}
+
+/// Convert a Codex rollout to canonical format
+///
+/// Every message takes the session ID from the rollout's metadata, falling
+/// back to the one in its filename, and assistant messages take the model of
+/// the turn they belong to.
+pub fn convert_file(codex_file: &Path) -> ConvertResult<ConvertedFile> {
+    convert_rollout(codex_file).map(|(converted, _)| converted)
+}
+
+/// Convert a Codex rollout to canonical format, also returning when its first
+/// and last lines were written
+pub fn convert_rollout(codex_file: &Path) -> ConvertResult<(ConvertedFile, RolloutSpan)> {
+    let session_id = leading_messages(codex_file)?
+        .iter()
+        .find_map(|message| message.get_session_id())
+        .or_else(|| session_id_from_filename(codex_file))
+        .ok_or("No session ID in session metadata or filename")?;
+
+    // Stream original Codex JSONL
+    let mut reader = JsonlReader::open(codex_file)?;
+
+    let mut writer = CanonicalWriter::for_source(PROVIDER_ID, codex_file)?;
+    let mut turn_model: Option<String> = None;
+    let mut cwd: Option<String> = None;
+    let mut span = RolloutSpan::default();
+    let mut last_line = String::new();
+
+    // Parse and convert each line independently (no aggregation needed)
+    while let Some((line_num, line)) = reader.next_line()? {
+        // Session timing comes from the first and last lines
+        if last_line.is_empty() {
+            span.start = line_timestamp(line);
+        }
+        last_line.clear();
+        last_line.push_str(line);
+
+        match serde_json::from_str::<CodexMessage>(line) {
+            Ok(codex_msg) => {
+                if let Some(model) = codex_msg.get_model() {
+                    turn_model = Some(model);
+                }
+
+                // Convert to canonical format
+                match codex_msg.to_canonical() {
+                    Ok(Some(mut canonical_msg)) => {
+                        // Only the session_meta message carries the session ID
+                        canonical_msg.session_id = session_id.clone();
+                        apply_turn_model(&mut canonical_msg, turn_model.as_deref());
+
+                        // CWD from the first canonical messages
+                        if cwd.is_none() && writer.message_count() < 50 {
+                            cwd = canonical_msg.cwd.clone();
+                        }
+
+                        writer.write(&canonical_msg)?;
+                    }
+                    Ok(None) => {
+                        // Message was skipped (e.g., duplicate event_msg)
+                    }
+                    Err(e) => {
+                        if let Err(log_err) = log_error(
+                            PROVIDER_ID,
+                            &format!(
+                                "Failed to convert Codex message at line {}: {}",
+                                line_num, e
+                            ),
+                        ) {
+                            eprintln!("Logging error: {}", log_err);
+                        }
+                        // Quarantine the line and continue processing other lines
+                        writer.quarantine(line_num, line, e);
+                    }
+                }
+            }
+            Err(e) => {
+                if let Err(log_err) = log_error(
+                    PROVIDER_ID,
+                    &format!("Failed to parse Codex message at line {}: {}", line_num, e),
+                ) {
+                    eprintln!("Logging error: {}", log_err);
+                }
+                // Quarantine the line and continue processing other lines
+                writer.quarantine(line_num, line, e);
+            }
+        }
+    }
+    span.end = line_timestamp(&last_line);
+
+    let converted = ConvertedFile::new(PROVIDER_ID, session_id, cwd, writer);
+    Ok((converted, span))
+}
+
+/// Read the top-level timestamp of a rollout line
+fn line_timestamp(line: &str) -> Option<DateTime<Utc>> {
+    serde_json::from_str::<serde_json::Value>(line)
+        .ok()?
+        .get("timestamp")?
+        .as_str()
+        .and_then(|ts| DateTime::parse_from_rfc3339(ts).ok())
+        .map(|dt| dt.with_timezone(&Utc))
+}
@@ -2,18 +2,20 @@ use super::sort_projects_by_modified;
 use crate::config::ProjectInfo;
 use chrono::{DateTime, Utc};
 use serde::Deserialize;
-use shellexpand::tilde;
+use crate::providers::common::expand_home;
 use std::collections::HashMap;
 use std::fs;
-use std::path::{Path, PathBuf};
+use std::path::Path;
 use std::time::SystemTime;
 use toml::Value;
 
 pub mod converter;
+pub mod converter_utils;
 pub mod scanner;
 pub mod watcher;
 
 pub use converter::CodexMessage;
+pub use converter_utils::convert_file;
 
 #[derive(Debug, Deserialize, Default)]
 struct CodexConfig {
@@ -22,8 +24,7 @@ struct CodexConfig {
 }
 
 pub fn scan_projects(home_directory: &str) -> Result<Vec<ProjectInfo>, String> {
-    let expanded = tilde(home_directory);
-    let primary_base = PathBuf::from(expanded.into_owned());
+    let primary_base = expand_home(home_directory);
 
     // Build candidate paths with fallbacks for cross-platform support
     let mut base_candidates = Vec::new();
@@ -1,118 +1,99 @@
 //! Codex session scanner - discovers and parses Codex sessions from ~/.codex/sessions/
 
-use crate::logging::{log_info, log_warn};
-use crate::providers::common::SessionInfo;
-use chrono::{DateTime, Utc};
-use serde::Deserialize;
+use crate::events::CancellationToken;
+use crate::ignore_list::IgnoreList;
+use crate::providers::common::scan_cache::scan_project_dirs;
+use crate::providers::common::{ScanReport, SessionInfo};
 use std::fs;
 use std::path::{Path, PathBuf};
 
-#[derive(Debug, Deserialize)]
-struct CodexLogEntry {
-    timestamp: Option<String>,
-    payload: Option<CodexPayload>,
-}
-
-#[derive(Debug, Deserialize)]
-struct CodexPayload {
-    id: Option<String>,
-    cwd: Option<String>,
-}
-
 /// Scan all Codex sessions from the base path
 pub fn scan_sessions_filtered(
     base_path: &Path,
     selected_projects: Option<&[String]>,
-) -> Result<Vec<SessionInfo>, String> {
+    cancel: &CancellationToken,
+) -> Result<ScanReport, String> {
     // Codex uses ~/.codex/sessions/YYYY/MM/DD/*.jsonl structure
     let sessions_path = base_path.join("sessions");
     if !sessions_path.exists() {
-        return Ok(Vec::new());
+        return Ok(ScanReport::default());
     }
 
-    let mut sessions = Vec::new();
-
-    // Recursively find all .jsonl files in the sessions directory
-    fn find_session_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<(), String> {
+    // Recursively find the directories holding .jsonl files; sessions aren't
+    // grouped by project on disk, so each day directory is scanned as one
+    fn find_session_dirs(dir: &Path, dirs: &mut Vec<PathBuf>) -> Result<(), String> {
         let entries = fs::read_dir(dir)
             .map_err(|e| format!("Failed to read directory {}: {}", dir.display(), e))?;
 
+        let mut has_sessions = false;
         for entry in entries.flatten() {
             let path = entry.path();
             if path.is_dir() {
-                find_session_files(&path, files)?;
+                find_session_dirs(&path, dirs)?;
             } else if path.extension().and_then(|ext| ext.to_str()) == Some("jsonl") {
-                files.push(path);
+                has_sessions = true;
             }
         }
+        if has_sessions {
+            dirs.push(dir.to_path_buf());
+        }
         Ok(())
     }
 
-    let mut session_files = Vec::new();
-    find_session_files(&sessions_path, &mut session_files)?;
-
-    for file_path in session_files {
-        match parse_codex_session(&file_path, selected_projects) {
-            Ok(Some(session_info)) => {
-                sessions.push(session_info);
-            }
-            Ok(None) => {
-                // Session filtered out - skipped
-            }
-            Err(e) => {
-                if let Err(log_err) = log_warn(
-                    "codex",
-                    &format!(
-                        "Failed to parse Codex session {}: {}",
-                        file_path.display(),
-                        e
-                    ),
-                ) {
-                    eprintln!("Logging error: {}", log_err);
-                }
-            }
-        }
-    }
+    let mut session_dirs = Vec::new();
+    find_session_dirs(&sessions_path, &mut session_dirs)?;
+    let groups = session_dirs
+        .into_iter()
+        .map(|dir| {
+            let label = dir
+                .strip_prefix(&sessions_path)
+                .map(|relative| relative.to_string_lossy().to_string())
+                .unwrap_or_default();
+            (label, dir)
+        })
+        .collect();
 
-    if let Err(e) = log_info(
+    let mut report = scan_project_dirs(
         "codex",
-        &format!("📊 Found {} Codex sessions", sessions.len()),
-    ) {
-        eprintln!("Logging error: {}", e);
+        groups,
+        cancel,
+        |dir| {
+            fs::read_dir(dir)
+                .map(|entries| {
+                    entries
+                        .flatten()
+                        .map(|entry| entry.path())
+                        .filter(|path| {
+                            path.extension().and_then(|ext| ext.to_str()) == Some("jsonl")
+                        })
+                        .collect()
+                })
+                .unwrap_or_default()
+        },
+        |file_path, _| parse_codex_session(file_path, selected_projects),
+    );
+
+    // Cached sessions skip parsing, and with it the project filter
+    if let Some(selected) = selected_projects {
+        report.retain(|session| selected.contains(&session.project_name));
     }
 
-    Ok(sessions)
+    Ok(report)
 }
 
-fn parse_codex_session(
+pub(crate) fn parse_codex_session(
     file_path: &Path,
     selected_projects: Option<&[String]>,
 ) -> Result<Option<SessionInfo>, String> {
-    use super::super::canonical::converter::ToCanonical;
-    use super::super::common::{extract_cwd_from_canonical_content, get_canonical_path};
-    use super::CodexMessage;
-
-    let content =
-        fs::read_to_string(file_path).map_err(|e| format!("Failed to read file: {}", e))?;
-
-    // Parse first line for session metadata
-    let lines: Vec<&str> = content
-        .lines()
-        .filter(|line| !line.trim().is_empty())
-        .collect();
-    if lines.is_empty() {
-        return Err("File is empty".to_string());
-    }
+    use super::converter_utils::{convert_rollout, leading_messages};
 
-    let first_entry: CodexLogEntry =
-        serde_json::from_str(lines[0]).map_err(|e| format!("Failed to parse first line: {}", e))?;
-
-    // Extract session info from first line metadata
-    let payload = first_entry
-        .payload
-        .ok_or("No payload in session metadata")?;
-    let session_id = payload.id.ok_or("No session ID in payload")?;
-    let cwd = payload.cwd.ok_or("No cwd in payload")?;
+    // Find session metadata near the top of the file (not always the first line)
+    let leading_messages =
+        leading_messages(file_path).map_err(|e| format!("Failed to read file: {}", e))?;
+    let cwd = leading_messages
+        .iter()
+        .find_map(|msg| msg.get_cwd())
+        .ok_or("No cwd in session metadata")?;
 
     // Extract project name from cwd path
     let project_name = Path::new(&cwd)
@@ -127,60 +108,23 @@ fn parse_codex_session(
             return Ok(None); // Skip this session
         }
     }
-
-    // Convert Codex JSONL to canonical format - simple 1-to-1 conversion
-    // The watcher uses MessageAggregator for real-time processing, but the scanner
-    // reads complete files that are already in final form, so just convert directly
-    let mut canonical_lines = Vec::new();
-
-    for line in lines.iter() {
-        if let Ok(codex_msg) = serde_json::from_str::<CodexMessage>(line) {
-            match codex_msg.to_canonical() {
-                Ok(Some(mut canonical_msg)) => {
-                    // Fix session_id for all messages (not just session_meta)
-                    canonical_msg.session_id = session_id.clone();
-
-                    if let Ok(serialized) = serde_json::to_string(&canonical_msg) {
-                        canonical_lines.push(serialized);
-                    }
-                }
-                Ok(None) => {
-                    // Message was skipped (e.g., duplicate event_msg)
-                }
-                Err(e) => {
-                    // Log error but continue processing
-                    eprintln!("Failed to convert Codex message: {}", e);
-                }
-            }
-        }
+    if IgnoreList::current().is_ignored(&project_name, Some(&cwd)) {
+        return Ok(None);
     }
 
-    let canonical_content = canonical_lines.join("\n");
+    // Convert the way the watcher does; lines are streamed so large rollouts
+    // never sit in memory
+    let (converted, span) =
+        convert_rollout(file_path).map_err(|e| format!("Failed to convert session: {}", e))?;
+    let session_id = converted.session_id.clone();
 
-    // Extract CWD from canonical content (should match original)
-    let canonical_cwd = extract_cwd_from_canonical_content(&canonical_content);
-
-    // Get project-organized canonical path
-    let cache_path = get_canonical_path("codex", canonical_cwd.as_deref(), &session_id)
-        .map_err(|e| format!("Failed to get canonical path: {}", e))?;
-
-    // Write canonical JSONL to project-organized path
-    fs::write(&cache_path, &canonical_content)
+    // Move canonical JSONL to project-organized path
+    let cache_path = converted
+        .persist()
         .map_err(|e| format!("Failed to write canonical JSONL: {}", e))?;
 
-    // Parse session timing from first and last lines
-    let session_start_time = first_entry
-        .timestamp
-        .and_then(|ts| DateTime::parse_from_rfc3339(&ts).ok())
-        .map(|dt| dt.with_timezone(&Utc));
-
-    let last_entry: CodexLogEntry = serde_json::from_str(lines[lines.len() - 1])
-        .map_err(|e| format!("Failed to parse last line: {}", e))?;
-
-    let session_end_time = last_entry
-        .timestamp
-        .and_then(|ts| DateTime::parse_from_rfc3339(&ts).ok())
-        .map(|dt| dt.with_timezone(&Utc));
+    let session_start_time = span.start;
+    let session_end_time = span.end;
 
     // Calculate duration
     let duration_ms = match (session_start_time, session_end_time) {
@@ -213,6 +157,21 @@ fn parse_codex_session(
     }))
 }
 
+/// Extract the session UUID from a rollout filename
+/// (rollout-2025-10-06T22-15-35-{SESSION_ID}.jsonl)
+pub(crate) fn session_id_from_filename(file_path: &Path) -> Option<String> {
+    let stem = file_path.file_stem()?.to_str()?;
+    let candidate = stem.get(stem.len().checked_sub(36)?..)?;
+
+    let is_uuid = candidate
+        .split('-')
+        .map(|part| part.len())
+        .eq([8, 4, 4, 4, 12])
+        && candidate.chars().all(|c| c.is_ascii_hexdigit() || c == '-');
+
+    is_uuid.then(|| candidate.to_string())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
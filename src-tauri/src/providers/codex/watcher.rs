@@ -1,17 +1,16 @@
 use crate::config::load_provider_config;
 use crate::events::{EventBus, SessionEventPayload};
+use crate::ignore_list::IgnoreList;
 use crate::logging::{log_error, log_info};
-use crate::providers::canonical::converter::ToCanonical;
 use crate::providers::codex::converter::CodexMessage;
+use crate::providers::common::file_access::ProviderRoot;
 use crate::providers::common::{
-    extract_cwd_from_canonical_content, get_canonical_path, get_file_size, has_extension,
-    should_skip_file, SessionStateManager, WatcherStatus, EVENT_TIMEOUT, FILE_WATCH_POLL_INTERVAL,
-    MIN_SIZE_CHANGE_BYTES,
+    access, expand_home, get_file_size, has_extension, should_skip_file, SessionStateManager,
+    WatcherStatus, EVENT_TIMEOUT, FILE_WATCH_POLL_INTERVAL, MIN_SIZE_CHANGE_BYTES,
 };
+use crate::providers::convert_file_to_canonical;
 use crate::upload_queue::UploadQueue;
 use notify::{Config, Event, EventKind, PollWatcher, RecursiveMode, Watcher};
-use shellexpand::tilde;
-use std::fs;
 use std::path::{Path, PathBuf};
 use std::sync::{mpsc, Arc, Mutex};
 use std::thread;
@@ -53,8 +52,8 @@ impl CodexWatcher {
         }
 
         let home_directory = config.home_directory;
-        let expanded_home = tilde(&home_directory);
-        let base_path = Path::new(expanded_home.as_ref());
+        let expanded_home = expand_home(&home_directory);
+        let base_path = expanded_home.as_path();
 
         if !base_path.exists() {
             return Err(format!(
@@ -228,83 +227,6 @@ impl CodexWatcher {
         Ok(subdirs)
     }
 
-    fn convert_to_canonical_file(
-        codex_file: &Path,
-        session_id: &str,
-    ) -> Result<PathBuf, Box<dyn std::error::Error + Send + Sync>> {
-        // Read original Codex JSONL
-        let content = fs::read_to_string(codex_file)?;
-
-        let mut canonical_lines = Vec::new();
-
-        // Parse and convert each line independently (no aggregation needed)
-        for (line_num, line) in content.lines().enumerate() {
-            if line.trim().is_empty() {
-                continue;
-            }
-
-            match serde_json::from_str::<CodexMessage>(line) {
-                Ok(codex_msg) => {
-                    // Convert to canonical format
-                    match codex_msg.to_canonical() {
-                        Ok(Some(mut canonical_msg)) => {
-                            // Update session_id from the known session ID (from filename)
-                            // This ensures all messages have the correct session_id,
-                            // not just the session_meta message
-                            canonical_msg.session_id = session_id.to_string();
-
-                            canonical_lines.push(serde_json::to_string(&canonical_msg)?);
-                        }
-                        Ok(None) => {
-                            // Message was skipped (e.g., duplicate event_msg)
-                        }
-                        Err(e) => {
-                            if let Err(log_err) = log_error(
-                                PROVIDER_ID,
-                                &format!(
-                                    "Failed to convert Codex message at line {}: {}",
-                                    line_num + 1,
-                                    e
-                                ),
-                            ) {
-                                eprintln!("Logging error: {}", log_err);
-                            }
-                            // Continue processing other lines
-                        }
-                    }
-                }
-                Err(e) => {
-                    if let Err(log_err) = log_error(
-                        PROVIDER_ID,
-                        &format!(
-                            "Failed to parse Codex message at line {}: {}",
-                            line_num + 1,
-                            e
-                        ),
-                    ) {
-                        eprintln!("Logging error: {}", log_err);
-                    }
-                    // Continue processing other lines
-                }
-            }
-        }
-
-        // Join canonical lines into content
-        let canonical_content = canonical_lines.join("\n");
-
-        // Extract CWD from canonical content
-        let cwd = extract_cwd_from_canonical_content(&canonical_content);
-
-        // Get project-organized canonical path
-        // Uses ~/.guidemode/sessions/{provider}/{project}/{session_id}.jsonl
-        let canonical_path = get_canonical_path(PROVIDER_ID, cwd.as_deref(), session_id)?;
-
-        // Write to project-organized path
-        fs::write(&canonical_path, canonical_content)?;
-
-        Ok(canonical_path)
-    }
-
     fn file_event_processor(
         rx: mpsc::Receiver<Result<Event, notify::Error>>,
         sessions_path: PathBuf,
@@ -341,7 +263,9 @@ impl CodexWatcher {
                         );
 
                         // Convert to canonical format
-                        match Self::convert_to_canonical_file(&file_event.path, &file_event.session_id) {
+                        match convert_file_to_canonical(PROVIDER_ID, &file_event.path)
+                            .and_then(|converted| converted.persist())
+                        {
                             Ok(canonical_path) => {
                                 // Get size of canonical file
                                 let canonical_size = get_file_size(&canonical_path).unwrap_or(0);
@@ -466,7 +390,13 @@ impl CodexWatcher {
                     // Extract session ID from filename (always succeeds unless malformed)
                     if let Some(session_id) = Self::extract_session_id_from_filename(path) {
                         // Extract project name from file content (fallback to "unknown")
-                        let project_name = Self::extract_project_name_from_file(path);
+                        let (project_name, cwd) =
+                            Self::extract_project_from_file(path, sessions_path);
+
+                        // Ignored projects never reach the canonical cache
+                        if IgnoreList::current().is_ignored(&project_name, cwd.as_deref()) {
+                            continue;
+                        }
 
                         // Get file size
                         let file_size = get_file_size(path).unwrap_or(0);
@@ -550,11 +480,11 @@ impl CodexWatcher {
                     if let Some(session_id) = entry.get("sessionId").and_then(|v| v.as_str()) {
                         return Some(session_id.to_string());
                     }
-                    // Also check payload.id (alternative format)
-                    if let Some(payload) = entry.get("payload") {
-                        if let Some(id) = payload.get("id").and_then(|v| v.as_str()) {
-                            return Some(id.to_string());
-                        }
+                }
+                // Also check session_meta payload (v1 and v2 rollout formats)
+                if let Ok(message) = serde_json::from_str::<CodexMessage>(&line_content) {
+                    if let Some(id) = message.get_session_id() {
+                        return Some(id);
                     }
                 }
             }
@@ -572,37 +502,44 @@ impl CodexWatcher {
         None
     }
 
-    fn extract_project_name_from_file(file_path: &Path) -> String {
+    /// The session's project name and cwd
+    fn extract_project_from_file(
+        file_path: &Path,
+        sessions_path: &Path,
+    ) -> (String, Option<String>) {
         // Try to read first line to get project name from cwd
-        use std::fs::File;
         use std::io::{BufRead, BufReader};
 
-        if let Ok(file) = File::open(file_path) {
+        if let Ok(file) = ProviderRoot::new(PROVIDER_ID, sessions_path).open(file_path) {
             let reader = BufReader::new(file);
             // Check first few lines (in case session_meta isn't first)
             for line_content in reader.lines().take(10).flatten() {
                 if let Ok(entry) = serde_json::from_str::<serde_json::Value>(&line_content) {
                     // Try to find CWD from various locations in the JSON
-                    let cwd = entry.get("cwd").and_then(|v| v.as_str()).or_else(|| {
-                        entry
-                            .get("payload")
-                            .and_then(|p| p.get("cwd"))
-                            .and_then(|v| v.as_str())
-                    });
+                    let cwd = entry
+                        .get("cwd")
+                        .and_then(|v| v.as_str())
+                        .map(|v| v.to_string())
+                        .or_else(|| {
+                            serde_json::from_value::<CodexMessage>(entry)
+                                .ok()
+                                .and_then(|message| message.get_cwd())
+                        });
 
                     if let Some(cwd_path) = cwd {
-                        return Path::new(cwd_path)
+                        let project_name = Path::new(&cwd_path)
                             .file_name()
                             .and_then(|name| name.to_str())
                             .unwrap_or("unknown")
                             .to_string();
+                        return (project_name, Some(cwd_path));
                     }
                 }
             }
         }
 
         // Fallback to "unknown" if we can't read the file or find CWD
-        "unknown".to_string()
+        ("unknown".to_string(), None)
     }
 
     pub fn stop(&self) {
@@ -629,6 +566,9 @@ impl CodexWatcher {
             pending_uploads: upload_status.pending,
             processing_uploads: upload_status.processing,
             failed_uploads: upload_status.failed,
+            access_denied: access::denied_path(PROVIDER_ID),
+            // Codex always polls, so it never runs out of watches
+            watch_limit: None,
         }
     }
 }
@@ -0,0 +1,132 @@
+//! Read access to providers' session directories
+//!
+//! On macOS 14+ the privacy framework (TCC) can deny reading another app's
+//! data, e.g. under `~/Library/Application Support`, until GuideMode is
+//! granted Full Disk Access. The denial looks like an empty directory, so a
+//! scan would silently find nothing. Scans and watchers check access first:
+//! a denial is remembered per provider and reported in its watcher status,
+//! and the UI offers to open the privacy settings where access is granted.
+
+use crate::error::GuideModeError;
+use crate::logging::{log_info, log_warn};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::Mutex;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    Readable,
+    Missing,
+    /// The operating system refused to let GuideMode read it
+    Denied,
+}
+
+/// The directory each provider was last denied access to
+static DENIED: Mutex<BTreeMap<String, String>> = Mutex::new(BTreeMap::new());
+
+/// Whether an I/O error is the operating system refusing access (`EACCES`,
+/// or the `EPERM` macOS returns for privacy denials)
+pub fn is_permission_denied(error: &io::Error) -> bool {
+    error.kind() == io::ErrorKind::PermissionDenied
+}
+
+/// Whether `path` can be read, telling a missing path from a denied one
+pub fn probe(path: &Path) -> Access {
+    // Listing a protected directory is what's denied; its metadata may not be
+    let result = match fs::metadata(path) {
+        Ok(metadata) if metadata.is_dir() => fs::read_dir(path).map(drop),
+        Ok(_) => fs::File::open(path).map(drop),
+        Err(e) => Err(e),
+    };
+    match result {
+        Ok(()) => Access::Readable,
+        Err(e) if is_permission_denied(&e) => Access::Denied,
+        Err(_) => Access::Missing,
+    }
+}
+
+/// Remember whether `provider_id` may read `path`, logging when that changes
+fn record(provider_id: &str, path: &Path, access: Access) {
+    let Ok(mut denied) = DENIED.lock() else {
+        return;
+    };
+    if access == Access::Denied {
+        let path = path.display().to_string();
+        if denied.get(provider_id) != Some(&path) {
+            log_warn(
+                provider_id,
+                &format!(
+                    "🔒 Access to {} was denied; grant GuideMode Full Disk Access to capture these sessions",
+                    path
+                ),
+            )
+            .unwrap_or_default();
+            denied.insert(provider_id.to_string(), path);
+        }
+    } else if denied.remove(provider_id).is_some() {
+        log_info(
+            provider_id,
+            &format!("🔓 Access to {} granted", path.display()),
+        )
+        .unwrap_or_default();
+    }
+}
+
+/// Probe `path` for `provider_id`, remembering a denial for its status
+pub fn check(provider_id: &str, path: &Path) -> Access {
+    let access = probe(path);
+    record(provider_id, path, access);
+    access
+}
+
+/// The directory `provider_id` was last denied access to, if it still is
+pub fn denied_path(provider_id: &str) -> Option<String> {
+    DENIED.lock().ok()?.get(provider_id).cloned()
+}
+
+/// Fail when `provider_id` is denied access to `path`; a missing path is left
+/// to the caller
+pub fn ensure_readable(provider_id: &str, path: &Path) -> Result<(), GuideModeError> {
+    match check(provider_id, path) {
+        Access::Denied => Err(GuideModeError::AccessDenied {
+            provider: provider_id.to_string(),
+            path: path.display().to_string(),
+        }),
+        Access::Readable | Access::Missing => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_probe() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("session.jsonl");
+        fs::write(&file, "{}").unwrap();
+        assert_eq!(probe(dir.path()), Access::Readable);
+        assert_eq!(probe(&file), Access::Readable);
+        assert_eq!(probe(&dir.path().join("missing")), Access::Missing);
+
+        assert!(is_permission_denied(&io::Error::from(
+            io::ErrorKind::PermissionDenied
+        )));
+        assert!(!is_permission_denied(&io::Error::from(
+            io::ErrorKind::NotFound
+        )));
+        #[cfg(unix)]
+        assert!(is_permission_denied(&io::Error::from_raw_os_error(1)));
+    }
+
+    #[test]
+    fn test_denials_are_remembered_until_access_returns() {
+        let path = Path::new("/Users/dev/Library/Application Support/Cursor");
+        record("access-test", path, Access::Denied);
+        assert_eq!(denied_path("access-test"), Some(path.display().to_string()));
+        record("access-test", path, Access::Missing);
+        assert_eq!(denied_path("access-test"), None);
+    }
+}
@@ -1,6 +1,10 @@
+use std::fmt;
 use std::fs;
 use std::path::PathBuf;
 
+/// Longest path Windows opens without the `\\?\` prefix
+const WINDOWS_MAX_PATH: usize = 260;
+
 /// Extract CWD from canonical JSONL content
 /// Returns the session start directory (not mid-session directory changes)
 /// Scans first 50 lines, skipping snapshot messages to find the initial CWD
@@ -45,20 +49,40 @@ pub fn sanitize_project_name(name: &str) -> String {
 }
 
 /// Get canonical path for a session file, organized by project
-/// Path format: ~/.guidemode/sessions/{provider}/{project}/{session_id}.jsonl
+/// Path format: ~/.guidemode/sessions/{provider}/{project}/{session_id}.jsonl,
+/// or under the configured cache directory
 ///
 /// If CWD is provided, attempts to extract project name using project_metadata.
 /// Falls back to "unknown" if CWD is None or project extraction fails.
+/// Fails while the disk is below its free space minimum (see `disk_space`).
 pub fn get_canonical_path(
     provider_id: &str,
     cwd: Option<&str>,
     session_id: &str,
+) -> Result<PathBuf, Box<dyn std::error::Error + Send + Sync>> {
+    let session_path = canonical_path_for(provider_id, cwd, session_id)?;
+
+    // Cache writes pause while the disk is nearly full
+    crate::disk_space::ensure_space(&session_path, "session files")?;
+
+    // Ensure parent directories exist
+    if let Some(project_dir) = session_path.parent() {
+        fs::create_dir_all(project_dir)?;
+    }
+
+    Ok(session_path)
+}
+
+/// Canonical path for a session, like `get_canonical_path`, without creating
+/// its project directory
+pub fn canonical_path_for(
+    provider_id: &str,
+    cwd: Option<&str>,
+    session_id: &str,
 ) -> Result<PathBuf, Box<dyn std::error::Error + Send + Sync>> {
     // Get base sessions directory
-    let sessions_base = dirs::home_dir()
-        .ok_or("Failed to get home directory")?
-        .join(".guidemode")
-        .join("sessions")
+    let sessions_base = crate::config::get_sessions_dir()
+        .map_err(|e| e.to_string())?
         .join(provider_id);
 
     // Determine project name from CWD
@@ -78,14 +102,268 @@ pub fn get_canonical_path(
 
     // Create full path with project subdirectory
     let project_dir = sessions_base.join(&project_name);
+    Ok(project_dir.join(format!("{}.jsonl", session_id)))
+}
 
-    // Ensure parent directories exist
-    fs::create_dir_all(&project_dir)?;
+/// Where a session path is rooted
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathRoot {
+    /// `/`
+    Unix,
+    /// A drive letter, upper-cased: `C:\`
+    Drive(char),
+    /// A network share: `\\server\share\`
+    Unc {
+        server: String,
+        share: String,
+    },
+    Relative,
+}
 
-    // Create session file path
-    let session_path = project_dir.join(format!("{}.jsonl", session_id));
+/// A path recorded by a provider, parsed the same way on every platform
+///
+/// Sessions recorded on Windows name directories with drive letters, `\`
+/// separators and sometimes the `\\?\` long-path prefix, which `/` string
+/// handling gets wrong. `.` and `..` are resolved, and Windows paths compare
+/// case-insensitively like their file systems.
+#[derive(Debug, Clone)]
+pub struct SessionPath {
+    root: PathRoot,
+    components: Vec<String>,
+}
 
-    Ok(session_path)
+fn is_separator(c: char) -> bool {
+    c == '/' || c == '\\'
+}
+
+fn has_drive(path: &str) -> bool {
+    match path.as_bytes() {
+        [drive, b':'] => drive.is_ascii_alphabetic(),
+        [drive, b':', separator, ..] => {
+            drive.is_ascii_alphabetic() && matches!(separator, b'/' | b'\\')
+        }
+        _ => false,
+    }
+}
+
+/// Whether `path` is absolute on Unix or Windows, whatever platform runs this
+pub fn is_absolute_path(path: &str) -> bool {
+    path.starts_with(is_separator) || has_drive(path)
+}
+
+/// Expand a leading `~` to the home directory, joining the rest component by
+/// component so the result only uses the platform's separators
+pub fn expand_home(path: &str) -> PathBuf {
+    let rest = match path.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with(std::path::is_separator) => rest,
+        _ => return PathBuf::from(path),
+    };
+    let Some(home) = dirs::home_dir() else {
+        return PathBuf::from(path);
+    };
+    rest.split(std::path::is_separator)
+        .filter(|component| !component.is_empty())
+        .fold(home, |path, component| path.join(component))
+}
+
+impl SessionPath {
+    pub fn parse(path: &str) -> Self {
+        // `\\?\C:\x` is `C:\x`, and `\\?\UNC\server\share` is `\\server\share`
+        let (path, unc) = match path
+            .strip_prefix(r"\\?\")
+            .or_else(|| path.strip_prefix(r"\\.\"))
+        {
+            Some(rest) => match rest.get(..4) {
+                Some(prefix) if prefix.eq_ignore_ascii_case(r"UNC\") => (&rest[4..], true),
+                _ => (rest, false),
+            },
+            None => match path.strip_prefix(r"\\").or_else(|| path.strip_prefix("//")) {
+                Some(rest) => (rest, true),
+                None => (path, false),
+            },
+        };
+
+        let mut parts = path
+            .split(is_separator)
+            .filter(|part| !part.is_empty() && *part != ".");
+        let root = if unc {
+            match (parts.next(), parts.next()) {
+                (Some(server), Some(share)) => PathRoot::Unc {
+                    server: server.to_string(),
+                    share: share.to_string(),
+                },
+                _ => PathRoot::Unix,
+            }
+        } else if has_drive(path) {
+            // The drive is the first part; `C:` alone has no separator after it
+            parts.next();
+            PathRoot::Drive(path.as_bytes()[0].to_ascii_uppercase() as char)
+        } else if path.starts_with(is_separator) {
+            PathRoot::Unix
+        } else {
+            PathRoot::Relative
+        };
+
+        let mut components: Vec<String> = Vec::new();
+        for part in parts {
+            match part {
+                ".." if root != PathRoot::Relative => {
+                    components.pop();
+                }
+                ".." if components.last().is_some_and(|last| last != "..") => {
+                    components.pop();
+                }
+                _ => components.push(part.to_string()),
+            }
+        }
+        Self { root, components }
+    }
+
+    pub fn components(&self) -> &[String] {
+        &self.components
+    }
+
+    /// Whether this is a Windows path, compared case-insensitively
+    pub fn is_windows(&self) -> bool {
+        matches!(self.root, PathRoot::Drive(_) | PathRoot::Unc { .. })
+    }
+
+    pub fn file_name(&self) -> Option<&str> {
+        self.components.last().map(String::as_str)
+    }
+
+    pub fn parent(&self) -> Option<Self> {
+        let (_, components) = self.components.split_last()?;
+        Some(Self {
+            root: self.root.clone(),
+            components: components.to_vec(),
+        })
+    }
+
+    /// This path, then each of its parents up to the root
+    pub fn ancestors(&self) -> impl Iterator<Item = Self> {
+        std::iter::successors(Some(self.clone()), Self::parent)
+    }
+
+    fn same_name(&self, a: &str, b: &str) -> bool {
+        if self.is_windows() {
+            a.to_lowercase() == b.to_lowercase()
+        } else {
+            a == b
+        }
+    }
+
+    fn same_root(&self, other: &Self) -> bool {
+        match (&self.root, &other.root) {
+            (
+                PathRoot::Unc { server, share },
+                PathRoot::Unc {
+                    server: other_server,
+                    share: other_share,
+                },
+            ) => self.same_name(server, other_server) && self.same_name(share, other_share),
+            (root, other_root) => root == other_root,
+        }
+    }
+
+    /// Whether `base` is this path or one of its parents
+    pub fn starts_with(&self, base: &Self) -> bool {
+        self.same_root(base)
+            && base.components.len() <= self.components.len()
+            && self
+                .components
+                .iter()
+                .zip(&base.components)
+                .all(|(a, b)| self.same_name(a, b))
+    }
+
+    /// The drive or share, as components, followed by the path's components
+    pub fn rooted_components(&self) -> Vec<String> {
+        let root = match &self.root {
+            PathRoot::Drive(drive) => vec![format!("{}:", drive)],
+            PathRoot::Unc { server, share } => vec![server.clone(), share.clone()],
+            PathRoot::Unix | PathRoot::Relative => Vec::new(),
+        };
+        root.into_iter()
+            .chain(self.components.iter().cloned())
+            .collect()
+    }
+
+    fn render(&self, separator: char, drive: Option<char>) -> String {
+        let mut path = match &self.root {
+            PathRoot::Unix => separator.to_string(),
+            PathRoot::Drive(letter) => format!("{}:{}", drive.unwrap_or(*letter), separator),
+            PathRoot::Unc { server, share } => {
+                format!("{0}{0}{1}{0}{2}", separator, server, share)
+            }
+            PathRoot::Relative => String::new(),
+        };
+        for (index, component) in self.components.iter().enumerate() {
+            if index > 0 || matches!(self.root, PathRoot::Unc { .. }) {
+                path.push(separator);
+            }
+            path.push_str(component);
+        }
+        path
+    }
+
+    /// The ways a tool may have spelled this path, for providers that hash it
+    ///
+    /// Windows paths come with either separator and drive letter case.
+    pub fn spellings(&self) -> Vec<String> {
+        let PathRoot::Drive(letter) = self.root else {
+            return vec![self.to_string()];
+        };
+        let mut spellings = Vec::new();
+        for drive in [letter, letter.to_ascii_lowercase()] {
+            for separator in ['\\', '/'] {
+                spellings.push(self.render(separator, Some(drive)));
+            }
+        }
+        spellings
+    }
+
+    /// This path with the `\\?\` prefix Windows needs past `MAX_PATH`, when
+    /// it's that long
+    fn long_path(&self) -> Option<String> {
+        let path = self.to_string();
+        if path.len() < WINDOWS_MAX_PATH {
+            return None;
+        }
+        match self.root {
+            PathRoot::Drive(_) => Some(format!(r"\\?\{}", path)),
+            PathRoot::Unc { .. } => Some(format!(r"\\?\UNC\{}", &path[2..])),
+            PathRoot::Unix | PathRoot::Relative => None,
+        }
+    }
+
+    /// This path for the file system of the platform running this
+    pub fn to_native(&self) -> PathBuf {
+        if cfg!(windows) {
+            if let Some(long_path) = self.long_path() {
+                return PathBuf::from(long_path);
+            }
+        }
+        PathBuf::from(self.to_string())
+    }
+}
+
+impl PartialEq for SessionPath {
+    fn eq(&self, other: &Self) -> bool {
+        self.components.len() == other.components.len() && self.starts_with(other)
+    }
+}
+
+impl Eq for SessionPath {}
+
+impl fmt::Display for SessionPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.root == PathRoot::Relative && self.components.is_empty() {
+            return f.write_str(".");
+        }
+        let separator = if self.is_windows() { '\\' } else { '/' };
+        f.write_str(&self.render(separator, None))
+    }
 }
 
 #[cfg(test)]
@@ -166,4 +444,109 @@ mod tests {
         assert!(result.is_err());
         assert!(result.unwrap_err().to_string().contains("No CWD available"));
     }
+
+    #[test]
+    fn test_windows_paths() {
+        let path = SessionPath::parse(r"c:\Users\Dev\repo\");
+        assert_eq!(path.root, PathRoot::Drive('C'));
+        assert_eq!(path.components(), ["Users", "Dev", "repo"]);
+        assert_eq!(path.to_string(), r"C:\Users\Dev\repo");
+        assert_eq!(path.file_name(), Some("repo"));
+
+        // Long-path prefixes, either separator and case don't make a different path
+        assert_eq!(path, SessionPath::parse(r"\\?\C:\users\dev\REPO"));
+        assert_eq!(path, SessionPath::parse("C:/Users/Dev/./src/../repo"));
+        assert_ne!(path, SessionPath::parse(r"D:\Users\Dev\repo"));
+        assert!(path.starts_with(&SessionPath::parse(r"C:\USERS")));
+        assert!(
+            !SessionPath::parse(r"C:\Users\Devs").starts_with(&SessionPath::parse(r"C:\Users\Dev"))
+        );
+
+        let share = SessionPath::parse(r"\\?\UNC\nas\Work\app");
+        assert_eq!(
+            share.root,
+            PathRoot::Unc {
+                server: "nas".to_string(),
+                share: "Work".to_string()
+            }
+        );
+        assert_eq!(share.to_string(), r"\\nas\Work\app");
+        assert_eq!(share, SessionPath::parse("//NAS/work/App"));
+        assert_eq!(share.rooted_components(), ["nas", "Work", "app"]);
+        assert_eq!(
+            share
+                .ancestors()
+                .map(|path| path.to_string())
+                .collect::<Vec<_>>(),
+            [r"\\nas\Work\app", r"\\nas\Work"]
+        );
+    }
+
+    #[test]
+    fn test_unix_paths_stay_case_sensitive() {
+        let path = SessionPath::parse("/home/dev/Repo/");
+        assert_eq!(path.root, PathRoot::Unix);
+        assert_eq!(path.to_string(), "/home/dev/Repo");
+        assert_ne!(path, SessionPath::parse("/home/dev/repo"));
+        assert_eq!(path.spellings(), ["/home/dev/Repo"]);
+        assert_eq!(SessionPath::parse("src/../../lib").root, PathRoot::Relative);
+        assert_eq!(SessionPath::parse("src/../../lib").to_string(), "../lib");
+
+        assert!(is_absolute_path("/tmp"));
+        assert!(is_absolute_path(r"D:\tmp"));
+        assert!(is_absolute_path(r"\\nas\share"));
+        assert!(!is_absolute_path("C:tmp"));
+        assert!(!is_absolute_path("tmp/a:1"));
+    }
+
+    #[test]
+    fn test_windows_spellings() {
+        assert_eq!(
+            SessionPath::parse(r"\\?\C:\repo").spellings(),
+            [r"C:\repo", "C:/repo", r"c:\repo", "c:/repo"]
+        );
+    }
+
+    #[test]
+    fn test_long_windows_paths_get_the_prefix() {
+        let deep = format!(r"C:\{}", ["directory"; 30].join(r"\"));
+        assert_eq!(
+            SessionPath::parse(&deep).long_path(),
+            Some(format!(r"\\?\{}", deep))
+        );
+        let share = format!(r"\\nas\share\{}", ["directory"; 30].join(r"\"));
+        assert_eq!(
+            SessionPath::parse(&share).long_path(),
+            Some(format!(r"\\?\UNC\{}", &share[2..]))
+        );
+        assert_eq!(SessionPath::parse(r"C:\short").long_path(), None);
+        assert_eq!(
+            SessionPath::parse(&format!("/{}", "a".repeat(300))).long_path(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_expand_home() {
+        let home = dirs::home_dir().unwrap();
+        assert_eq!(expand_home("~"), home);
+        assert_eq!(
+            expand_home("~/.claude/projects"),
+            home.join(".claude").join("projects")
+        );
+        assert_eq!(expand_home("~other/x"), PathBuf::from("~other/x"));
+        assert_eq!(expand_home("/opt/claude"), PathBuf::from("/opt/claude"));
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_native_windows_paths() {
+        let home = dirs::home_dir().unwrap();
+        assert_eq!(expand_home(r"~\.claude"), home.join(".claude"));
+        let deep = format!(r"C:\{}", ["directory"; 30].join(r"\"));
+        assert!(SessionPath::parse(&deep)
+            .to_native()
+            .to_string_lossy()
+            .starts_with(r"\\?\C:\"));
+    }
 }
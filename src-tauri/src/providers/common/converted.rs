@@ -0,0 +1,82 @@
+//! A session converted to canonical JSONL, before it reaches the session cache
+//!
+//! Each provider converts a session file into a `ConvertedFile`, and
+//! `providers::convert_file_to_canonical` picks the provider's converter.
+//! Watchers and scans persist the result into the cache; the canonical format
+//! migration and the converter golden tests read it first, and only the
+//! migration persists it, when it changed.
+
+use crate::providers::common::get_canonical_path;
+use crate::providers::common::jsonl_stream::CanonicalWriter;
+use crate::providers::common::quarantine::QuarantinedLine;
+use chrono::{DateTime, Utc};
+use std::error::Error;
+use std::io;
+use std::path::{Path, PathBuf};
+
+pub type ConvertResult<T> = Result<T, Box<dyn Error + Send + Sync>>;
+
+/// Canonical JSONL for one session, still in its temporary file
+///
+/// Dropping it without persisting discards the conversion.
+pub struct ConvertedFile {
+    pub provider_id: &'static str,
+    pub session_id: String,
+    /// Working directory the session started in, which picks its cache directory
+    pub cwd: Option<String>,
+    writer: CanonicalWriter,
+}
+
+impl ConvertedFile {
+    pub fn new(
+        provider_id: &'static str,
+        session_id: String,
+        cwd: Option<String>,
+        writer: CanonicalWriter,
+    ) -> Self {
+        Self {
+            provider_id,
+            session_id,
+            cwd,
+            writer,
+        }
+    }
+
+    pub fn message_count(&self) -> usize {
+        self.writer.message_count()
+    }
+
+    /// Source lines that couldn't be parsed or converted
+    pub fn skipped_lines(&self) -> &[QuarantinedLine] {
+        self.writer.quarantined()
+    }
+
+    /// Time of the latest main-thread message, for conversions that append to
+    /// the session later
+    pub fn last_timestamp(&self) -> Option<DateTime<Utc>> {
+        self.writer.last_timestamp()
+    }
+
+    pub fn content(&mut self) -> io::Result<String> {
+        self.writer.content()
+    }
+
+    /// Where the session belongs in the session cache
+    /// (~/.guidemode/sessions/{provider}/{project}/{session_id}.jsonl)
+    pub fn canonical_path(&self) -> ConvertResult<PathBuf> {
+        get_canonical_path(self.provider_id, self.cwd.as_deref(), &self.session_id)
+    }
+
+    /// Move the canonical JSONL into the session cache, returning its path
+    pub fn persist(self) -> ConvertResult<PathBuf> {
+        let canonical_path = self.canonical_path()?;
+        self.persist_to(&canonical_path)?;
+        Ok(canonical_path)
+    }
+
+    /// Move the canonical JSONL to `destination`, recording its quarantined lines
+    pub fn persist_to(self, destination: &Path) -> io::Result<()> {
+        self.writer
+            .persist(self.provider_id, &self.session_id, destination)
+    }
+}
@@ -1,6 +1,13 @@
-use crate::database::{insert_session, update_session};
+use crate::database::{
+    get_archived_session_ids, insert_session, save_sessions, update_session, ProjectRecord,
+    SavedSession, SessionRecord,
+};
+use crate::ignore_list::IgnoreList;
 use crate::logging::{log_debug, log_info, log_warn};
+use crate::providers::common::session_info::SessionInfo;
+use crate::project_metadata::ProjectMetadata;
 use chrono::{DateTime, Utc};
+use std::collections::HashSet;
 use std::path::PathBuf;
 
 /// Type alias for timing data tuple returned from JSONL parsing
@@ -39,9 +46,26 @@ pub fn insert_session_immediately(
         .and_then(|n| n.to_str())
         .unwrap_or("unknown.jsonl");
 
+    // Archived sessions stay out until their archive is restored
+    if crate::database::is_session_archived(session_id).unwrap_or(false) {
+        let _ = log_debug(
+            provider_id,
+            &format!("Session {} is archived, not saving it", session_id),
+        );
+        return Ok(());
+    }
+
     // Extract CWD from file
     let cwd = extract_cwd_from_file(provider_id, file_path);
 
+    if IgnoreList::current().is_ignored(project_name, cwd.as_deref()) {
+        let _ = log_debug(
+            provider_id,
+            &format!("Session {} is in an ignored project, not saving it", session_id),
+        );
+        return Ok(());
+    }
+
     // Determine git info based on whether this is a historical scan
     let (git_branch, first_commit, latest_commit) = if is_historical {
         // For historical sessions, check if we already have git data in the database
@@ -108,6 +132,11 @@ pub fn insert_session_immediately(
     match insert_result {
         Ok(_) => {
             // Insert succeeded - this is a new session
+            // Its conversion quarantined lines before the row existed
+            if let Err(e) = crate::providers::common::quarantine::sync_skipped_lines(provider_id, session_id) {
+                let _ = log_warn(provider_id, &format!("⚠ {}", e));
+            }
+
             let timing_info = match (start_time, end_time, duration) {
                 (Some(start), Some(end), Some(dur)) => format!(
                     " | Start: {}, End: {}, Duration: {}ms",
@@ -167,9 +196,28 @@ pub fn insert_session_immediately(
         }
     }
 
+    // Link a session that resumes an earlier conversation to it
+    match crate::providers::common::resumption::link_resumed_session(provider_id, session_id, file_path) {
+        Ok(Some(parent)) => {
+            let _ = log_debug(
+                provider_id,
+                &format!("↪ Session {} resumes session {}", session_id, parent),
+            );
+        }
+        Ok(None) => {}
+        Err(e) => {
+            let _ = log_warn(provider_id, &format!("⚠ {}", e));
+        }
+    }
+
     // Extract and link project if CWD is available
     if let Some(ref cwd_path) = cwd {
-        match crate::project_metadata::extract_project_metadata(cwd_path) {
+        // Monorepo sessions are attributed to the package their tools touched
+        match crate::project_metadata::extract_session_file_project_metadata(
+            provider_id,
+            cwd_path,
+            file_path,
+        ) {
             Ok(metadata) => {
                 // Insert or update project
                 match crate::database::insert_or_get_project(
@@ -231,9 +279,173 @@ pub fn insert_session_immediately(
         }
     }
 
+    // Count the session in the usage rollups (tokens are added by the metrics engine)
+    if let Err(e) = crate::metrics::store::record_session_usage(session_id, None) {
+        let _ = log_warn(
+            provider_id,
+            &format!("⚠ Failed to update usage rollups: {}", e),
+        );
+    }
+
     Ok(())
 }
 
+/// Historical sessions saved to the database per transaction
+pub const HISTORICAL_BATCH_SIZE: usize = 200;
+
+/// A session's file-derived fields, read before its batch is written
+struct PreparedSession {
+    file_name: String,
+    file_path: String,
+    file_hash: Option<String>,
+    timing: (Option<DateTime<Utc>>, Option<DateTime<Utc>>, Option<i64>),
+    cwd: Option<String>,
+    project: Option<ProjectMetadata>,
+}
+
+/// Insert or update sessions found by a historical scan, like
+/// `insert_session_immediately` with `is_historical` but in one transaction
+/// and without per-session frontend events
+///
+/// `stored` are the session IDs already in the database; only their files
+/// are hashed, to tell whether a synced session changed. Returns the saved
+/// sessions; failures are logged.
+pub fn insert_historical_sessions(
+    provider_id: &str,
+    sessions: &[&SessionInfo],
+    stored: &HashSet<String>,
+) -> Result<Vec<(String, SavedSession)>, String> {
+    // Archived sessions stay out until their archive is restored
+    let archived = get_archived_session_ids(provider_id).unwrap_or_default();
+    let sessions: Vec<&SessionInfo> = sessions
+        .iter()
+        .copied()
+        .filter(|session| !archived.contains(&session.session_id))
+        .collect();
+
+    // File reads happen before the database is locked
+    let prepared: Vec<PreparedSession> = sessions
+        .iter()
+        .map(|session| {
+            let file_path = &session.file_path;
+            let cwd = extract_cwd_from_file(provider_id, file_path);
+            let timing = extract_session_timing(provider_id, file_path).unwrap_or_else(|e| {
+                let _ = log_warn(
+                    provider_id,
+                    &format!("⚠ Could not extract session timing: {} - will save session without timing data", e)
+                );
+                (None, None, None)
+            });
+            let project = cwd.as_deref().and_then(|cwd_path| {
+                crate::project_metadata::extract_session_file_project_metadata(
+                    provider_id,
+                    cwd_path,
+                    file_path,
+                )
+                .ok()
+            });
+            let file_hash = stored
+                .contains(&session.session_id)
+                .then(|| crate::upload_queue::hashing::calculate_file_hash_sha256(file_path).ok())
+                .flatten();
+            PreparedSession {
+                file_name: file_path
+                    .file_name()
+                    .and_then(|n| n.to_str())
+                    .unwrap_or("unknown.jsonl")
+                    .to_string(),
+                file_path: file_path.to_string_lossy().to_string(),
+                file_hash,
+                timing,
+                cwd,
+                project,
+            }
+        })
+        .collect();
+
+    // Sessions the scan couldn't place before their cwd was read
+    let ignore_list = IgnoreList::current();
+    let (sessions, prepared): (Vec<&SessionInfo>, Vec<PreparedSession>) = sessions
+        .iter()
+        .copied()
+        .zip(prepared)
+        .filter(|(session, prepared)| {
+            !ignore_list.is_ignored(&session.project_name, prepared.cwd.as_deref())
+        })
+        .unzip();
+
+    // Git state isn't captured: it's the current state, not the session's.
+    // Updates keep what was recorded while the session was live
+    let rows: Vec<(SessionRecord, Option<ProjectRecord>)> = sessions
+        .iter()
+        .zip(&prepared)
+        .map(|(session, prepared)| {
+            let (session_start_time, session_end_time, duration_ms) = prepared.timing;
+            let record = SessionRecord {
+                provider: provider_id,
+                project_name: &session.project_name,
+                session_id: &session.session_id,
+                file_name: &prepared.file_name,
+                file_path: &prepared.file_path,
+                file_size: session.file_size,
+                file_hash: prepared.file_hash.as_deref(),
+                session_start_time,
+                session_end_time,
+                duration_ms,
+                cwd: prepared.cwd.as_deref(),
+                ..Default::default()
+            };
+            let project = prepared.project.as_ref().map(|metadata| ProjectRecord {
+                name: &metadata.project_name,
+                github_repo: metadata.git_remote_url.as_deref(),
+                cwd: &metadata.cwd,
+                project_type: &metadata.detected_project_type,
+            });
+            (record, project)
+        })
+        .collect();
+
+    let results =
+        save_sessions(&rows).map_err(|e| format!("Failed to save sessions: {}", e))?;
+
+    let mut saved = Vec::with_capacity(results.len());
+    for (session, result) in sessions.iter().zip(results) {
+        let session_id = &session.session_id;
+        let result = match result {
+            Ok(result) => result,
+            Err(e) => {
+                let _ = log_warn(
+                    provider_id,
+                    &format!("⚠ Failed to insert session {}: {}", session_id, e),
+                );
+                continue;
+            }
+        };
+        if result.inserted {
+            if let Err(e) = crate::providers::common::quarantine::sync_skipped_lines(provider_id, session_id) {
+                let _ = log_warn(provider_id, &format!("⚠ {}", e));
+            }
+        }
+        if let Err(e) = crate::providers::common::resumption::link_resumed_session(provider_id, session_id, &session.file_path) {
+            let _ = log_warn(provider_id, &format!("⚠ {}", e));
+        }
+        if let Err(e) = crate::metrics::store::record_session_usage(session_id, None) {
+            let _ = log_warn(
+                provider_id,
+                &format!("⚠ Failed to update usage rollups: {}", e),
+            );
+        }
+        saved.push((session_id.clone(), result));
+    }
+
+    // The upload queue picks them up on its next tick
+    if !saved.is_empty() {
+        crate::upload_queue::request_db_poll();
+    }
+
+    Ok(saved)
+}
+
 /// Extract session timing from JSONL file (works for all providers)
 /// Extract timing information from session file (start time, end time, duration)
 /// All providers now use JSONL format (including github-copilot snapshots)
@@ -307,7 +519,7 @@ fn extract_session_timing(_provider_id: &str, file_path: &PathBuf) -> TimingResu
 }
 
 /// Extract CWD from session file (provider-specific logic)
-fn extract_cwd_from_file(_provider_id: &str, file_path: &PathBuf) -> Option<String> {
+pub(crate) fn extract_cwd_from_file(provider_id: &str, file_path: &PathBuf) -> Option<String> {
     use std::fs;
 
     // Read file content
@@ -316,4 +528,17 @@ fn extract_cwd_from_file(_provider_id: &str, file_path: &PathBuf) -> Option<Stri
     // Use shared utility to extract CWD from canonical content
     // (All providers now use canonical format with cwd at top level)
     crate::providers::common::canonical_path::extract_cwd_from_canonical_content(&content)
+        .or_else(|| {
+            // Sessions without one are attributed by the files their tools touched
+            let cwd = crate::providers::common::project_detection::infer_cwd(&content)?;
+            let _ = log_debug(
+                provider_id,
+                &format!(
+                    "📁 Inferred project {} for {} from tool activity",
+                    cwd,
+                    file_path.display()
+                ),
+            );
+            Some(cwd)
+        })
 }
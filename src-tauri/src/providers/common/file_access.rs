@@ -0,0 +1,167 @@
+//! Read-only access to provider directories
+//!
+//! GuideMode only ever reads what providers write, and paths into their
+//! directories are often built from session metadata: project and session
+//! IDs, project hashes, selected project names. A crafted value such as
+//! `../../.ssh/id_rsa` must not make GuideMode read outside the provider's
+//! directory. Provider files are opened read-only through this module, and
+//! paths built from metadata are checked against the provider's configured
+//! root; attempts to leave it are refused and logged. The check is made again
+//! on the open file, so a path swapped for a symlink in between is refused
+//! too.
+
+use crate::error::GuideModeError;
+use crate::logging::log_warn;
+use same_file::Handle;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read};
+use std::path::{Component, Path, PathBuf};
+
+/// Open `path` for reading only
+pub fn open_read_only(path: &Path) -> io::Result<File> {
+    OpenOptions::new().read(true).open(path)
+}
+
+/// Read all of `path` through a read-only handle
+pub fn read_to_string(path: &Path) -> io::Result<String> {
+    let mut content = String::new();
+    open_read_only(path)?.read_to_string(&mut content)?;
+    Ok(content)
+}
+
+/// Where `path` really points: symlinks and `..` are resolved through its
+/// longest existing ancestor, and the rest of the path is applied lexically
+fn resolve(path: &Path) -> PathBuf {
+    let components: Vec<Component> = path.components().collect();
+    for existing in (1..=components.len()).rev() {
+        let Ok(mut resolved) = fs::canonicalize(components[..existing].iter().collect::<PathBuf>())
+        else {
+            continue;
+        };
+        for component in &components[existing..] {
+            match component {
+                Component::ParentDir => {
+                    resolved.pop();
+                }
+                Component::Normal(name) => resolved.push(name),
+                _ => {}
+            }
+        }
+        return resolved;
+    }
+    path.to_path_buf()
+}
+
+/// A provider's configured directory, which every path it reads must stay under
+#[derive(Debug, Clone)]
+pub struct ProviderRoot {
+    provider_id: String,
+    root: PathBuf,
+}
+
+impl ProviderRoot {
+    pub fn new(provider_id: &str, root: &Path) -> Self {
+        ProviderRoot {
+            provider_id: provider_id.to_string(),
+            root: resolve(root),
+        }
+    }
+
+    /// Whether `path` stays under the root once symlinks and `..` are resolved
+    pub fn contains(&self, path: &Path) -> bool {
+        resolve(path).starts_with(&self.root)
+    }
+
+    /// Refuse, and log, a path that leaves the root
+    pub fn check(&self, path: &Path) -> Result<(), GuideModeError> {
+        if self.contains(path) {
+            return Ok(());
+        }
+        Err(self.refuse(path))
+    }
+
+    fn refuse(&self, path: &Path) -> GuideModeError {
+        log_warn(
+            &self.provider_id,
+            &format!(
+                "🚫 Refused to read {} outside {}",
+                path.display(),
+                self.root.display()
+            ),
+        )
+        .unwrap_or_default();
+        GuideModeError::OutsideRoot {
+            provider: self.provider_id.clone(),
+            path: path.display().to_string(),
+        }
+    }
+
+    /// Open `path` read-only, refusing it unless the file actually opened
+    /// sits under the root
+    ///
+    /// `path` is checked before opening so nothing outside the root is ever
+    /// opened by name, then its canonical path is checked against the root and
+    /// against the open handle: if `path` was swapped in between, the handle
+    /// and the canonical path name different files.
+    pub fn open(&self, path: &Path) -> Result<File, GuideModeError> {
+        self.check(path)?;
+        let file = open_read_only(path)?;
+        let canonical = fs::canonicalize(path)?;
+        if !canonical.starts_with(&self.root)
+            || Handle::from_file(file.try_clone()?)? != Handle::from_path(&canonical)?
+        {
+            return Err(self.refuse(path));
+        }
+        Ok(file)
+    }
+
+    /// Read all of `path` through a handle opened by [`ProviderRoot::open`]
+    pub fn read_to_string(&self, path: &Path) -> Result<String, GuideModeError> {
+        let mut content = String::new();
+        self.open(path)?.read_to_string(&mut content)?;
+        Ok(content)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_paths_must_stay_under_root() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = dir.path().join("storage");
+        fs::create_dir_all(storage.join("project")).unwrap();
+        fs::write(storage.join("project").join("p1.json"), "{}").unwrap();
+        fs::write(dir.path().join("secret.json"), "{}").unwrap();
+        let root = ProviderRoot::new("file-access-test", &storage);
+
+        let project = storage.join("project").join("p1.json");
+        assert!(root.contains(&project));
+        assert_eq!(root.read_to_string(&project).unwrap(), "{}");
+        // Paths that don't exist yet are still checked
+        assert!(root.contains(&storage.join("session").join("new.json")));
+
+        let escaped = storage.join("project").join("../../secret.json");
+        assert!(!root.contains(&escaped));
+        let err = root.read_to_string(&escaped).unwrap_err();
+        assert_eq!(err.code(), "outside_root");
+        assert!(!root.contains(&storage.join("missing/../../../secret.json")));
+        assert!(!root.contains(Path::new("/etc/passwd")));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlinks_out_of_root_are_refused() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = dir.path().join("storage");
+        fs::create_dir(&storage).unwrap();
+        fs::write(dir.path().join("secret.json"), "{}").unwrap();
+        std::os::unix::fs::symlink(dir.path().join("secret.json"), storage.join("link.json"))
+            .unwrap();
+        let root = ProviderRoot::new("file-access-test", &storage);
+        assert!(!root.contains(&storage.join("link.json")));
+        let err = root.open(&storage.join("link.json")).unwrap_err();
+        assert_eq!(err.code(), "outside_root");
+    }
+}
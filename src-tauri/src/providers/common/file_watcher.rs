@@ -0,0 +1,248 @@
+//! File watching that survives running out of inotify watches
+//!
+//! On Linux every watched directory costs an inotify watch, and recursively
+//! watching hundreds of projects can exhaust `fs.inotify.max_user_watches`.
+//! The kernel then refuses new watches with `ENOSPC` and changes in the
+//! unwatched directories are silently missed. `FileWatcher` notices that,
+//! switches the provider to polling the same paths and remembers the
+//! fallback so the watcher status can explain how to raise the limit.
+//! Watched paths must stay under the provider's directory.
+
+use crate::logging::{log_info, log_warn};
+use crate::providers::common::file_access::ProviderRoot;
+use notify::{Config, ErrorKind, Event, PollWatcher, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::Sender;
+use std::sync::{Arc, Mutex};
+
+/// `errno` for "no space left on device", which inotify returns when the
+/// per-user watch limit is reached
+const ENOSPC: i32 = 28;
+
+/// Where Linux exposes the per-user inotify watch limit
+const MAX_USER_WATCHES: &str = "/proc/sys/fs/inotify/max_user_watches";
+
+/// Providers whose watcher fell back to polling
+static POLLING: Mutex<BTreeSet<String>> = Mutex::new(BTreeSet::new());
+
+/// Whether `error` means the operating system ran out of file watches
+pub fn is_watch_limit(error: &notify::Error) -> bool {
+    match &error.kind {
+        ErrorKind::MaxFilesWatch => true,
+        ErrorKind::Io(e) => cfg!(target_os = "linux") && e.raw_os_error() == Some(ENOSPC),
+        _ => false,
+    }
+}
+
+/// How to stop `provider_id` from polling, if its watcher fell back to it
+pub fn watch_limit_hint(provider_id: &str) -> Option<String> {
+    if !POLLING.lock().ok()?.contains(provider_id) {
+        return None;
+    }
+    let current = fs::read_to_string(MAX_USER_WATCHES)
+        .ok()
+        .map(|limit| format!(" (currently {})", limit.trim()))
+        .unwrap_or_default();
+    Some(format!(
+        "The system ran out of inotify watches{}, so session files are polled instead. \
+         Raise the limit with `sudo sysctl fs.inotify.max_user_watches=524288`, add \
+         `fs.inotify.max_user_watches=524288` to /etc/sysctl.conf to keep it, then restart the watcher.",
+        current
+    ))
+}
+
+fn record_polling(provider_id: &str, polling: bool) {
+    if let Ok(mut providers) = POLLING.lock() {
+        if polling {
+            providers.insert(provider_id.to_string());
+        } else {
+            providers.remove(provider_id);
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Backend {
+    Native(RecommendedWatcher),
+    Polling(PollWatcher),
+}
+
+impl Backend {
+    fn watch(&mut self, path: &Path, mode: RecursiveMode) -> notify::Result<()> {
+        match self {
+            Backend::Native(watcher) => watcher.watch(path, mode),
+            Backend::Polling(watcher) => watcher.watch(path, mode),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Inner {
+    provider_id: String,
+    root: ProviderRoot,
+    tx: Sender<notify::Result<Event>>,
+    config: Config,
+    backend: Backend,
+    watched: Vec<(PathBuf, RecursiveMode)>,
+}
+
+impl Inner {
+    /// Replace the native watcher with a poller over everything watched so far
+    fn fall_back_to_polling(&mut self) -> notify::Result<()> {
+        if matches!(self.backend, Backend::Polling(_)) {
+            return Ok(());
+        }
+        let mut poller = PollWatcher::new(self.tx.clone(), self.config)?;
+        for (path, mode) in &self.watched {
+            poller.watch(path, *mode)?;
+        }
+        // Dropping the native watcher hands its watches back to the system
+        self.backend = Backend::Polling(poller);
+        record_polling(&self.provider_id, true);
+        log_warn(
+            &self.provider_id,
+            &format!(
+                "⚠️  Ran out of inotify watches; polling {} path(s) every {:?} instead",
+                self.watched.len(),
+                self.config.poll_interval_v2().unwrap_or_default()
+            ),
+        )
+        .unwrap_or_default();
+        Ok(())
+    }
+}
+
+/// A provider's file watcher, native where possible and polling once the
+/// system runs out of watches. Clones share the same watcher.
+#[derive(Debug, Clone)]
+pub struct FileWatcher {
+    inner: Arc<Mutex<Inner>>,
+}
+
+impl FileWatcher {
+    /// A native watcher for paths under `root`, sending events to `tx`;
+    /// `config`'s poll interval is used if it has to fall back to polling
+    pub fn new(
+        provider_id: &str,
+        root: &Path,
+        tx: Sender<notify::Result<Event>>,
+        config: Config,
+    ) -> notify::Result<Self> {
+        let backend = Backend::Native(RecommendedWatcher::new(tx.clone(), config)?);
+        // A restarted watcher gets another chance, e.g. after the limit was raised
+        if watch_limit_hint(provider_id).is_some() {
+            record_polling(provider_id, false);
+            log_info(provider_id, "🔁 Retrying native file watching").unwrap_or_default();
+        }
+        Ok(FileWatcher {
+            inner: Arc::new(Mutex::new(Inner {
+                provider_id: provider_id.to_string(),
+                root: ProviderRoot::new(provider_id, root),
+                tx,
+                config,
+                backend,
+                watched: Vec::new(),
+            })),
+        })
+    }
+
+    fn lock(&self) -> notify::Result<std::sync::MutexGuard<'_, Inner>> {
+        self.inner
+            .lock()
+            .map_err(|_| notify::Error::generic("file watcher lock poisoned"))
+    }
+
+    /// Watch `path`, switching to polling if the system is out of watches
+    pub fn watch(&self, path: &Path, mode: RecursiveMode) -> notify::Result<()> {
+        let mut inner = self.lock()?;
+        inner
+            .root
+            .check(path)
+            .map_err(|e| notify::Error::generic(&e.to_string()).add_path(path.to_path_buf()))?;
+        inner.watched.push((path.to_path_buf(), mode));
+        match inner.backend.watch(path, mode) {
+            Err(e) if is_watch_limit(&e) => inner.fall_back_to_polling(),
+            result => result,
+        }
+    }
+
+    /// Handle an error reported while watching; returns whether it was the
+    /// watch limit, in which case the watcher now polls
+    pub fn handle_error(&self, error: &notify::Error) -> bool {
+        if !is_watch_limit(error) {
+            return false;
+        }
+        let result = self
+            .lock()
+            .and_then(|mut inner| inner.fall_back_to_polling());
+        if let Err(e) = result {
+            let provider_id = self
+                .lock()
+                .map(|i| i.provider_id.clone())
+                .unwrap_or_default();
+            log_warn(
+                &provider_id,
+                &format!("Failed to fall back to polling: {:?}", e),
+            )
+            .unwrap_or_default();
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io;
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    #[test]
+    fn test_is_watch_limit() {
+        assert!(is_watch_limit(&notify::Error::new(
+            ErrorKind::MaxFilesWatch
+        )));
+        assert!(!is_watch_limit(&notify::Error::path_not_found()));
+        assert!(!is_watch_limit(&notify::Error::io(io::Error::from(
+            io::ErrorKind::NotFound
+        ))));
+        #[cfg(target_os = "linux")]
+        assert!(is_watch_limit(&notify::Error::io(
+            io::Error::from_raw_os_error(ENOSPC)
+        )));
+    }
+
+    #[test]
+    fn test_falls_back_to_polling_on_watch_limit() {
+        let dir = tempfile::tempdir().unwrap();
+        let (tx, rx) = mpsc::channel();
+        let config = Config::default().with_poll_interval(Duration::from_millis(50));
+        let watcher = FileWatcher::new("file-watcher-test", dir.path(), tx, config).unwrap();
+        watcher.watch(dir.path(), RecursiveMode::Recursive).unwrap();
+        assert!(watch_limit_hint("file-watcher-test").is_none());
+        // Paths outside the provider's directory aren't watched
+        assert!(watcher
+            .watch(&dir.path().join(".."), RecursiveMode::Recursive)
+            .is_err());
+
+        assert!(!watcher.handle_error(&notify::Error::generic("other")));
+        assert!(watcher.handle_error(&notify::Error::new(ErrorKind::MaxFilesWatch)));
+        let hint = watch_limit_hint("file-watcher-test").unwrap();
+        assert!(hint.contains("fs.inotify.max_user_watches"));
+
+        // The poller picks up changes in the already watched directory
+        while rx.try_recv().is_ok() {}
+        let nested = dir.path().join("project");
+        fs::create_dir(&nested).unwrap();
+        fs::write(nested.join("session.jsonl"), "{}").unwrap();
+        let event = rx.recv_timeout(Duration::from_secs(5)).unwrap().unwrap();
+        assert!(event.paths.iter().all(|p| p.starts_with(dir.path())));
+
+        // Restarting the watcher clears the fallback
+        let (tx, _rx) = mpsc::channel();
+        FileWatcher::new("file-watcher-test", dir.path(), tx, config).unwrap();
+        assert!(watch_limit_hint("file-watcher-test").is_none());
+    }
+}
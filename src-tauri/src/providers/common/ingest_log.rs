@@ -0,0 +1,424 @@
+//! Why sessions found on disk didn't make it into the app
+//!
+//! Historical scans drop source files they can't parse, and syncs leave out
+//! sessions without timestamps or in a project excluded from sync, the
+//! quality gate skips low-signal sessions before they upload, and syncs send
+//! only the head and tail of sessions larger than the upload limit. Each of
+//! those is recorded in `ingest_skips`, one row per file, and cleared once
+//! the file makes it through whole, so `provider_stats` can explain why a
+//! session someone expects isn't showing up, or only partly.
+
+use crate::database::with_connection_mut;
+use crate::providers::common::preview::is_previewing;
+use chrono::{DateTime, TimeZone, Utc};
+use rusqlite::{params, Connection, Result};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+/// Skipped sessions listed in `ProviderIngestStats::recent_skips`
+const RECENT_SKIPS: usize = 50;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SkipReason {
+    /// No line of the session has a timestamp
+    NoTimestamps,
+    /// Larger than the upload limit, so uploaded truncated
+    TooLarge,
+    /// The session's project is excluded from sync
+    FilteredProject,
+    /// Failed the provider's enqueue validation for another reason
+    Invalid,
+    /// Under the provider's quality gate thresholds
+    LowSignal,
+    /// The source couldn't be parsed
+    Failed,
+}
+
+impl SkipReason {
+    fn as_str(self) -> &'static str {
+        match self {
+            SkipReason::NoTimestamps => "no_timestamps",
+            SkipReason::TooLarge => "too_large",
+            SkipReason::FilteredProject => "filtered_project",
+            SkipReason::Invalid => "invalid",
+            SkipReason::LowSignal => "low_signal",
+            SkipReason::Failed => "failed",
+        }
+    }
+
+    fn parse(reason: &str) -> Option<Self> {
+        match reason {
+            "no_timestamps" => Some(SkipReason::NoTimestamps),
+            "too_large" => Some(SkipReason::TooLarge),
+            "filtered_project" => Some(SkipReason::FilteredProject),
+            "invalid" => Some(SkipReason::Invalid),
+            "low_signal" => Some(SkipReason::LowSignal),
+            "failed" => Some(SkipReason::Failed),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SkippedSession {
+    pub source_path: String,
+    pub session_id: Option<String>,
+    pub reason: SkipReason,
+    pub detail: String,
+    pub skipped_at: DateTime<Utc>,
+}
+
+/// Sessions a sync left out or truncated, by reason
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SkipCounts {
+    pub no_timestamps: usize,
+    pub too_large: usize,
+    pub filtered_project: usize,
+    pub invalid: usize,
+    pub low_signal: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderIngestStats {
+    pub provider: String,
+    /// Source files scans found, or sessions stored if watchers found more
+    pub discovered: usize,
+    /// Sessions stored in the database
+    pub converted: usize,
+    /// Stored sessions with source lines the converter had to leave out
+    pub partially_converted: usize,
+    pub skipped: SkipCounts,
+    /// Source files scans couldn't parse
+    pub failed: usize,
+    pub synced: usize,
+    /// Stored sessions whose upload failed
+    pub sync_failed: usize,
+    pub last_scanned_at: Option<DateTime<Utc>>,
+    /// When the most recent stored session was last active
+    pub last_session_at: Option<DateTime<Utc>>,
+    pub last_synced_at: Option<DateTime<Utc>>,
+    pub last_skipped_at: Option<DateTime<Utc>>,
+    /// Latest skipped and failed files, newest first
+    pub recent_skips: Vec<SkippedSession>,
+}
+
+fn from_millis(millis: Option<i64>) -> Option<DateTime<Utc>> {
+    millis.and_then(|millis| Utc.timestamp_millis_opt(millis).single())
+}
+
+fn upsert(
+    conn: &Connection,
+    provider: &str,
+    source_path: &str,
+    session_id: Option<&str>,
+    reason: SkipReason,
+    detail: &str,
+    now: i64,
+) -> Result<usize> {
+    conn.execute(
+        "INSERT OR REPLACE INTO ingest_skips (
+            provider, source_path, session_id, reason, detail, skipped_at
+         ) VALUES (?, ?, ?, ?, ?, ?)",
+        params![
+            provider,
+            source_path,
+            session_id,
+            reason.as_str(),
+            detail,
+            now
+        ],
+    )
+}
+
+fn delete(conn: &Connection, provider: &str, source_path: &str) -> Result<usize> {
+    conn.execute(
+        "DELETE FROM ingest_skips WHERE provider = ? AND source_path = ?",
+        params![provider, source_path],
+    )
+}
+
+fn replace_scan(
+    conn: &mut Connection,
+    provider: &str,
+    parsed: &[PathBuf],
+    failed: &[(PathBuf, String)],
+    now: i64,
+) -> Result<()> {
+    let tx = conn.transaction()?;
+    for path in parsed {
+        delete(&tx, provider, &path.to_string_lossy())?;
+    }
+    for (path, error) in failed {
+        upsert(
+            &tx,
+            provider,
+            &path.to_string_lossy(),
+            None,
+            SkipReason::Failed,
+            error,
+            now,
+        )?;
+    }
+    tx.commit()
+}
+
+fn stats(conn: &Connection, provider: &str) -> Result<ProviderIngestStats> {
+    let (converted, partially_converted, synced, sync_failed, last_session_at, last_synced_at) =
+        conn.query_row(
+            "SELECT COUNT(*),
+                    COALESCE(SUM(conversion_skipped_lines > 0), 0),
+                    COALESCE(SUM(synced_to_server = 1), 0),
+                    COALESCE(SUM(sync_failed_reason IS NOT NULL), 0),
+                    MAX(COALESCE(session_end_time, session_start_time)),
+                    MAX(synced_at)
+             FROM agent_sessions WHERE provider = ?",
+            [provider],
+            |row| {
+                Ok((
+                    row.get::<_, i64>(0)? as usize,
+                    row.get::<_, i64>(1)? as usize,
+                    row.get::<_, i64>(2)? as usize,
+                    row.get::<_, i64>(3)? as usize,
+                    row.get::<_, Option<i64>>(4)?,
+                    row.get::<_, Option<i64>>(5)?,
+                ))
+            },
+        )?;
+    let (scanned, last_scanned_at) = conn.query_row(
+        "SELECT COUNT(*), MAX(scanned_at) FROM scan_cache WHERE provider = ?",
+        [provider],
+        |row| {
+            Ok((
+                row.get::<_, i64>(0)? as usize,
+                row.get::<_, Option<i64>>(1)?,
+            ))
+        },
+    )?;
+
+    let mut skipped = SkipCounts::default();
+    let mut failed = 0;
+    let mut stmt = conn
+        .prepare("SELECT reason, COUNT(*) FROM ingest_skips WHERE provider = ? GROUP BY reason")?;
+    let counts = stmt.query_map([provider], |row| {
+        Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)? as usize))
+    })?;
+    for count in counts {
+        let (reason, count) = count?;
+        match SkipReason::parse(&reason) {
+            Some(SkipReason::NoTimestamps) => skipped.no_timestamps = count,
+            Some(SkipReason::TooLarge) => skipped.too_large = count,
+            Some(SkipReason::FilteredProject) => skipped.filtered_project = count,
+            Some(SkipReason::Invalid) => skipped.invalid = count,
+            Some(SkipReason::LowSignal) => skipped.low_signal = count,
+            Some(SkipReason::Failed) => failed = count,
+            None => {}
+        }
+    }
+    let last_skipped_at: Option<i64> = conn.query_row(
+        "SELECT MAX(skipped_at) FROM ingest_skips WHERE provider = ?",
+        [provider],
+        |row| row.get(0),
+    )?;
+
+    let mut stmt = conn.prepare(
+        "SELECT source_path, session_id, reason, detail, skipped_at
+         FROM ingest_skips WHERE provider = ?
+         ORDER BY skipped_at DESC, source_path
+         LIMIT ?",
+    )?;
+    let recent_skips = stmt
+        .query_map(params![provider, RECENT_SKIPS as i64], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, Option<String>>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, String>(3)?,
+                row.get::<_, i64>(4)?,
+            ))
+        })?
+        .collect::<Result<Vec<_>>>()?
+        .into_iter()
+        .filter_map(|(source_path, session_id, reason, detail, skipped_at)| {
+            Some(SkippedSession {
+                source_path,
+                session_id,
+                reason: SkipReason::parse(&reason)?,
+                detail,
+                skipped_at: from_millis(Some(skipped_at))?,
+            })
+        })
+        .collect();
+
+    Ok(ProviderIngestStats {
+        provider: provider.to_string(),
+        discovered: scanned.max(converted) + failed,
+        converted,
+        partially_converted,
+        skipped,
+        failed,
+        synced,
+        sync_failed,
+        last_scanned_at: from_millis(last_scanned_at),
+        last_session_at: from_millis(last_session_at),
+        last_synced_at: from_millis(last_synced_at),
+        last_skipped_at: from_millis(last_skipped_at),
+        recent_skips,
+    })
+}
+
+/// Record that a session was left out, replacing any earlier reason
+///
+/// Like the quarantine, recording never fails the caller: without a
+/// database, as in tests, there is nowhere to keep it.
+pub fn record_skip(
+    provider: &str,
+    source_path: &Path,
+    session_id: Option<&str>,
+    reason: SkipReason,
+    detail: &str,
+) {
+    if is_previewing(provider) {
+        return;
+    }
+    let now = Utc::now().timestamp_millis();
+    let _ = with_connection_mut(|conn| {
+        upsert(
+            conn,
+            provider,
+            &source_path.to_string_lossy(),
+            session_id,
+            reason,
+            detail,
+            now,
+        )
+    });
+}
+
+/// Forget why a session was left out, now that it made it through
+pub fn clear_skip(provider: &str, source_path: &Path) {
+    if is_previewing(provider) {
+        return;
+    }
+    let _ = with_connection_mut(|conn| delete(conn, provider, &source_path.to_string_lossy()));
+}
+
+/// Forget a session was left out for `reason`, keeping other reasons
+pub fn clear_skip_reason(provider: &str, source_path: &Path, reason: SkipReason) {
+    if is_previewing(provider) {
+        return;
+    }
+    let _ = with_connection_mut(|conn| {
+        conn.execute(
+            "DELETE FROM ingest_skips WHERE provider = ? AND source_path = ? AND reason = ?",
+            params![provider, source_path.to_string_lossy(), reason.as_str()],
+        )
+    });
+}
+
+/// Record the files a scan failed to parse, clearing those it parsed
+pub fn record_scan(provider: &str, parsed: &[PathBuf], failed: &[(PathBuf, String)]) {
+    if is_previewing(provider) {
+        return;
+    }
+    let now = Utc::now().timestamp_millis();
+    let _ = with_connection_mut(|conn| replace_scan(conn, provider, parsed, failed, now));
+}
+
+/// How many of a provider's sessions were found, converted, skipped and
+/// failed, and when each last happened
+pub fn provider_stats(provider: &str) -> Result<ProviderIngestStats, String> {
+    with_connection_mut(|conn| stats(conn, provider))
+        .map_err(|e| format!("Failed to load ingest stats: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scan_replaces_failures() {
+        let mut conn = crate::database::test_connection();
+        let (a, b) = (PathBuf::from("/s/a.jsonl"), PathBuf::from("/s/b.jsonl"));
+        replace_scan(
+            &mut conn,
+            "codex",
+            &[],
+            &[(a.clone(), "bad".into()), (b.clone(), "bad".into())],
+            1,
+        )
+        .unwrap();
+        replace_scan(&mut conn, "codex", &[a], &[], 2).unwrap();
+
+        let stats = stats(&conn, "codex").unwrap();
+        assert_eq!(stats.failed, 1);
+        assert_eq!(stats.discovered, 1);
+        assert_eq!(stats.recent_skips[0].source_path, "/s/b.jsonl");
+        assert_eq!(stats.recent_skips[0].reason, SkipReason::Failed);
+    }
+
+    #[test]
+    fn test_stats_count_sessions_and_skips() {
+        let conn = crate::database::test_connection();
+        conn.execute_batch(
+            "INSERT INTO agent_sessions (id, provider, project_name, session_id, file_name,
+                file_path, file_size, session_end_time, synced_to_server, synced_at,
+                created_at, uploaded_at)
+             VALUES ('1', 'codex', 'p', 's1', 's1.jsonl', '/c/s1.jsonl', 1, 5000, 1, 6000, 0, 0),
+                    ('2', 'codex', 'p', 's2', 's2.jsonl', '/c/s2.jsonl', 1, 7000, 0, NULL, 0, 0),
+                    ('3', 'gemini-code', 'p', 's3', 's3.jsonl', '/c/s3.jsonl', 1, 9000, 0, NULL, 0, 0)",
+        )
+        .unwrap();
+        upsert(
+            &conn,
+            "codex",
+            "/c/s2.jsonl",
+            Some("s2"),
+            SkipReason::TooLarge,
+            "big",
+            10,
+        )
+        .unwrap();
+        upsert(
+            &conn,
+            "codex",
+            "/c/s2.jsonl",
+            Some("s2"),
+            SkipReason::NoTimestamps,
+            "none",
+            11,
+        )
+        .unwrap();
+        upsert(
+            &conn,
+            "codex",
+            "/c/s4.jsonl",
+            None,
+            SkipReason::FilteredProject,
+            "excluded",
+            12,
+        )
+        .unwrap();
+
+        let stats = stats(&conn, "codex").unwrap();
+        assert_eq!(stats.converted, 2);
+        assert_eq!(stats.synced, 1);
+        assert_eq!(
+            stats.skipped,
+            SkipCounts {
+                no_timestamps: 1,
+                too_large: 0,
+                filtered_project: 1,
+                invalid: 0,
+                low_signal: 0,
+            }
+        );
+        assert_eq!(stats.last_session_at, from_millis(Some(7000)));
+        assert_eq!(stats.last_synced_at, from_millis(Some(6000)));
+        assert_eq!(stats.last_skipped_at, from_millis(Some(12)));
+        assert_eq!(stats.recent_skips.len(), 2);
+    }
+}
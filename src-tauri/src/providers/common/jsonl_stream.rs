@@ -0,0 +1,281 @@
+//! Line-streaming JSONL input and output for the converters
+//!
+//! Session files can reach hundreds of megabytes, so converters read them one
+//! line at a time through `JsonlReader` and write canonical messages through
+//! `CanonicalWriter` as they go, instead of holding both files in memory.
+//!
+//! The canonical destination depends on the session's working directory,
+//! which may only show up partway through the source file. `CanonicalWriter`
+//! therefore writes to a hidden temporary file next to the provider's sessions
+//! and moves it into place once the conversion is done. Watchers skip hidden
+//! files, and an abandoned conversion removes its temporary file.
+//!
+//! Source lines a converter can't handle are handed to `quarantine` instead of
+//! being dropped; persisting stores them with the source file so the session
+//! can be converted again later.
+
+use crate::providers::canonical::validation::OutputCheck;
+use crate::providers::canonical::CanonicalMessage;
+use crate::providers::common::file_access::open_read_only;
+use crate::providers::common::quarantine::{self, QuarantinedLine};
+use crate::providers::common::timestamps::TimestampNormalizer;
+use chrono::{DateTime, Utc};
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// Reads a JSONL file one non-blank line at a time, reusing one buffer
+pub struct JsonlReader<R> {
+    reader: R,
+    buffer: String,
+    line_number: usize,
+}
+
+impl JsonlReader<BufReader<File>> {
+    pub fn open(path: &Path) -> io::Result<Self> {
+        Ok(Self::new(BufReader::new(open_read_only(path)?)))
+    }
+}
+
+impl<R: BufRead> JsonlReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            buffer: String::new(),
+            line_number: 0,
+        }
+    }
+
+    /// The next non-blank line, trimmed, with its 1-based line number
+    pub fn next_line(&mut self) -> io::Result<Option<(usize, &str)>> {
+        loop {
+            self.buffer.clear();
+            if self.reader.read_line(&mut self.buffer)? == 0 {
+                return Ok(None);
+            }
+            self.line_number += 1;
+            if !self.buffer.trim().is_empty() {
+                return Ok(Some((self.line_number, self.buffer.trim())));
+            }
+        }
+    }
+}
+
+/// Writes canonical messages to a temporary file as they are converted
+pub struct CanonicalWriter {
+    writer: BufWriter<File>,
+    temp_path: PathBuf,
+    check: OutputCheck,
+    timestamps: TimestampNormalizer,
+    message_count: usize,
+    source_path: Option<PathBuf>,
+    quarantined: Vec<QuarantinedLine>,
+    persisted: bool,
+}
+
+impl CanonicalWriter {
+    /// Start a canonical file in `dir`
+    pub fn create(dir: &Path) -> io::Result<Self> {
+        fs::create_dir_all(dir)?;
+        let temp_path = dir.join(format!(".{}.partial", uuid::Uuid::new_v4()));
+        Ok(Self {
+            writer: BufWriter::new(File::create(&temp_path)?),
+            temp_path,
+            check: OutputCheck::default(),
+            timestamps: TimestampNormalizer::default(),
+            message_count: 0,
+            source_path: None,
+            quarantined: Vec::new(),
+            persisted: false,
+        })
+    }
+
+    /// Start a canonical file in the session cache's {provider} directory
+    pub fn for_provider(provider_id: &str) -> io::Result<Self> {
+        Self::create(&sessions_dir()?.join(provider_id))
+    }
+
+    /// Start a canonical file for a conversion of `source_path`, whose
+    /// quarantined lines are recorded when the file is persisted
+    pub fn for_source(provider_id: &str, source_path: &Path) -> io::Result<Self> {
+        let mut writer = Self::for_provider(provider_id)?;
+        writer.source_path = Some(source_path.to_path_buf());
+        Ok(writer)
+    }
+
+    /// Remove temporary files that conversions interrupted by a crash left in
+    /// the providers' session directories, returning how many were removed
+    ///
+    /// Only safe before any watcher or scan has started converting.
+    pub fn remove_abandoned() -> io::Result<usize> {
+        let sessions_dir = sessions_dir()?;
+        if !sessions_dir.exists() {
+            return Ok(0);
+        }
+        let mut removed = 0;
+        for provider_dir in fs::read_dir(sessions_dir)?.flatten() {
+            removed += remove_partial_files(&provider_dir.path());
+        }
+        Ok(removed)
+    }
+
+    /// Append a message with its timestamp normalized; lines are
+    /// newline-separated with no trailing newline
+    pub fn write(&mut self, message: &CanonicalMessage) -> io::Result<()> {
+        let message = self.timestamps.normalize(message);
+        if self.message_count > 0 {
+            self.writer.write_all(b"\n")?;
+        }
+        serde_json::to_writer(&mut self.writer, &*message)?;
+        self.check.check(&message);
+        self.message_count += 1;
+        Ok(())
+    }
+
+    pub fn message_count(&self) -> usize {
+        self.message_count
+    }
+
+    /// Time of the latest main-thread message written so far
+    pub fn last_timestamp(&self) -> Option<DateTime<Utc>> {
+        self.timestamps.last_main()
+    }
+
+    /// Source lines set aside so far
+    pub fn quarantined(&self) -> &[QuarantinedLine] {
+        &self.quarantined
+    }
+
+    /// The canonical JSONL written so far
+    pub fn content(&mut self) -> io::Result<String> {
+        self.writer.flush()?;
+        fs::read_to_string(&self.temp_path)
+    }
+
+    /// Set aside a source line that couldn't be parsed or converted
+    pub fn quarantine(&mut self, line_number: usize, line: &str, error: impl ToString) {
+        self.quarantined
+            .push(QuarantinedLine::new(line_number, line, error));
+    }
+
+    /// Move the finished file to `destination`, log any validation issues and
+    /// record the quarantined lines
+    pub fn persist(
+        mut self,
+        provider_id: &str,
+        session_id: &str,
+        destination: &Path,
+    ) -> io::Result<()> {
+        self.writer.flush()?;
+        if let Some(parent) = destination.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::rename(&self.temp_path, destination)?;
+        self.persisted = true;
+
+        std::mem::take(&mut self.check).log(provider_id, session_id);
+        if let Some(source_path) = &self.source_path {
+            quarantine::record_conversion(provider_id, session_id, source_path, &self.quarantined);
+        }
+        Ok(())
+    }
+}
+
+fn sessions_dir() -> io::Result<PathBuf> {
+    crate::config::get_sessions_dir().map_err(|e| io::Error::other(e.to_string()))
+}
+
+fn remove_partial_files(dir: &Path) -> usize {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return 0;
+    };
+    entries
+        .flatten()
+        .filter(|entry| {
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            name.starts_with('.') && name.ends_with(".partial")
+        })
+        .filter(|entry| fs::remove_file(entry.path()).is_ok())
+        .count()
+}
+
+impl Drop for CanonicalWriter {
+    fn drop(&mut self) {
+        if !self.persisted {
+            let _ = fs::remove_file(&self.temp_path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn message(uuid: &str) -> CanonicalMessage {
+        serde_json::from_value(json!({
+            "uuid": uuid, "timestamp": "2025-01-01T10:00:00Z", "type": "user",
+            "sessionId": "s1", "provider": "claude-code",
+            "message": {"role": "user", "content": "hi"}
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_reader_skips_blank_lines() {
+        let mut reader = JsonlReader::new("{\"a\":1}\n\n  \n{\"b\":2}\r\n".as_bytes());
+        assert_eq!(reader.next_line().unwrap(), Some((1, "{\"a\":1}")));
+        assert_eq!(reader.next_line().unwrap(), Some((4, "{\"b\":2}")));
+        assert_eq!(reader.next_line().unwrap(), None);
+    }
+
+    #[test]
+    fn test_writer_collects_quarantined_lines() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut writer = CanonicalWriter::create(temp_dir.path()).unwrap();
+        writer.write(&message("u1")).unwrap();
+        writer.quarantine(2, "{\"type\":", "EOF while parsing a value");
+
+        assert_eq!(writer.message_count(), 1);
+        assert_eq!(
+            writer.quarantined,
+            &[QuarantinedLine::new(
+                2,
+                "{\"type\":",
+                "EOF while parsing a value"
+            )]
+        );
+    }
+
+    #[test]
+    fn test_writer_persists_or_cleans_up() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let destination = temp_dir.path().join("app").join("s1.jsonl");
+
+        let mut writer = CanonicalWriter::create(temp_dir.path()).unwrap();
+        writer.write(&message("u1")).unwrap();
+        writer.write(&message("u2")).unwrap();
+        assert_eq!(writer.message_count(), 2);
+        writer.persist("claude-code", "s1", &destination).unwrap();
+
+        let content = fs::read_to_string(&destination).unwrap();
+        assert_eq!(content.lines().count(), 2);
+        assert!(!content.ends_with('\n'));
+
+        let mut writer = CanonicalWriter::create(temp_dir.path()).unwrap();
+        writer.write(&message("u3")).unwrap();
+        drop(writer);
+
+        // A crash skips Drop; the leftover is cleaned up on the next launch
+        let writer = CanonicalWriter::create(temp_dir.path()).unwrap();
+        std::mem::forget(writer);
+        assert_eq!(remove_partial_files(temp_dir.path()), 1);
+        let leftovers: Vec<_> = fs::read_dir(temp_dir.path())
+            .unwrap()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_name().to_string_lossy().ends_with(".partial"))
+            .collect();
+        assert!(leftovers.is_empty());
+    }
+}
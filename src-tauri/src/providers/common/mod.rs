@@ -3,13 +3,26 @@
 // This module contains shared code that was previously duplicated
 // across Claude, Claude Code, Copilot, Cursor, and Gemini Code watchers.
 
+pub mod access;
 pub mod agent_merger;
 pub mod canonical_path;
 pub mod constants;
+pub mod converted;
 pub mod db_helpers;
+pub mod file_access;
 pub mod file_utils;
+pub mod file_watcher;
+pub mod ingest_log;
+pub mod jsonl_stream;
+pub mod models;
+pub mod preview;
+pub mod project_detection;
+pub mod quarantine;
+pub mod resumption;
+pub mod scan_cache;
 pub mod session_info;
 pub mod session_state;
+pub mod timestamps;
 pub mod timing;
 pub mod watcher_status;
 
@@ -17,7 +30,12 @@ pub mod watcher_status;
 pub use agent_merger::*;
 pub use canonical_path::*;
 pub use constants::*;
+pub use converted::{ConvertResult, ConvertedFile};
 pub use file_utils::*;
+pub use file_watcher::FileWatcher;
+pub use jsonl_stream::{CanonicalWriter, JsonlReader};
+pub use models::annotate_model;
+pub use scan_cache::ScanReport;
 pub use session_info::SessionInfo;
 pub use session_state::SessionStateManager;
 pub use watcher_status::WatcherStatus;
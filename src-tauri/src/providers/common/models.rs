@@ -0,0 +1,442 @@
+//! Model identifier normalization
+//!
+//! Providers report models in different shapes: `claude-sonnet-4-5-20250929`,
+//! `gpt-4.1`, `gemini-2.5-pro`, `anthropic/claude-opus-4@20250805` or just
+//! `default`. This module reduces them to a family (`claude-sonnet`, `gpt`,
+//! `gemini-pro`) and a dotted version (`4.5`, `4.1`, `2.5`) so sessions from
+//! different providers can be compared. Snapshot dates and suffixes such as
+//! `latest` or `preview` are dropped.
+
+use crate::providers::canonical::CanonicalMessage;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// Family reported when a provider only says which model *slot* it used
+pub const DEFAULT_MODEL_FAMILY: &str = "default";
+
+/// Raw identifiers that don't name a concrete model
+const PLACEHOLDER_MODELS: &[&str] = &["default", "auto", "unknown"];
+
+/// Tokens that carry no family or version information
+const NOISE_TOKENS: &[&str] = &["latest", "exp", "experimental", "preview"];
+
+/// First family token -> vendor
+const MODEL_VENDORS: &[(&str, &str)] = &[
+    ("claude", "anthropic"),
+    ("gpt", "openai"),
+    ("chatgpt", "openai"),
+    ("codex", "openai"),
+    ("o", "openai"),
+    ("gemini", "google"),
+    ("gemma", "google"),
+    ("grok", "xai"),
+    ("deepseek", "deepseek"),
+    ("qwen", "alibaba"),
+];
+
+/// Context window assumed when a model isn't in `CONTEXT_WINDOWS`
+pub const DEFAULT_CONTEXT_WINDOW: u64 = 200_000;
+
+/// (family prefix, version prefix) -> context window in tokens, most specific first
+///
+/// Family prefixes match whole tokens, so `o` matches `o-mini` but not `olmo`.
+const CONTEXT_WINDOWS: &[(&str, &str, u64)] = &[
+    ("gpt", "4.1", 1_047_576),
+    ("gpt", "4o", 128_000),
+    ("gpt", "4", 128_000),
+    ("gpt", "5", 400_000),
+    ("gpt-codex", "", 400_000),
+    ("codex", "", 400_000),
+    ("o", "1", 200_000),
+    ("o", "3", 200_000),
+    ("o", "4", 200_000),
+    ("gemini", "", 1_048_576),
+    ("claude", "", 200_000),
+    ("grok", "", 256_000),
+];
+
+/// Price per million tokens in USD
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ModelPricing {
+    pub input: f64,
+    pub output: f64,
+    pub cache_write: f64,
+    pub cache_read: f64,
+}
+
+impl ModelPricing {
+    const fn new(input: f64, output: f64, cache_write: f64, cache_read: f64) -> Self {
+        Self {
+            input,
+            output,
+            cache_write,
+            cache_read,
+        }
+    }
+
+    /// Cost in USD of one request's token usage
+    pub fn cost(&self, input: u64, output: u64, cache_write: u64, cache_read: u64) -> f64 {
+        (input as f64 * self.input
+            + output as f64 * self.output
+            + cache_write as f64 * self.cache_write
+            + cache_read as f64 * self.cache_read)
+            / 1_000_000.0
+    }
+}
+
+/// (family prefix, version prefix) -> list price, most specific first
+const MODEL_PRICES: &[(&str, &str, ModelPricing)] = &[
+    ("claude-opus", "4.5", ModelPricing::new(5.0, 25.0, 6.25, 0.5)),
+    ("claude-opus", "", ModelPricing::new(15.0, 75.0, 18.75, 1.5)),
+    ("claude-sonnet", "", ModelPricing::new(3.0, 15.0, 3.75, 0.3)),
+    ("claude-haiku", "3", ModelPricing::new(0.8, 4.0, 1.0, 0.08)),
+    ("claude-haiku", "", ModelPricing::new(1.0, 5.0, 1.25, 0.1)),
+    ("gpt-codex", "", ModelPricing::new(1.25, 10.0, 1.25, 0.125)),
+    ("gpt", "4.1", ModelPricing::new(2.0, 8.0, 2.0, 0.5)),
+    ("gpt", "4o", ModelPricing::new(2.5, 10.0, 2.5, 1.25)),
+    ("gpt", "5", ModelPricing::new(1.25, 10.0, 1.25, 0.125)),
+    ("codex", "", ModelPricing::new(1.25, 10.0, 1.25, 0.125)),
+    ("o", "1", ModelPricing::new(2.0, 8.0, 2.0, 0.5)),
+    ("o", "3", ModelPricing::new(2.0, 8.0, 2.0, 0.5)),
+    ("o", "4", ModelPricing::new(2.0, 8.0, 2.0, 0.5)),
+    ("gemini-pro", "", ModelPricing::new(1.25, 10.0, 1.25, 0.31)),
+    ("gemini-flash", "", ModelPricing::new(0.3, 2.5, 0.3, 0.075)),
+    ("grok", "", ModelPricing::new(3.0, 15.0, 3.0, 0.75)),
+];
+
+/// A provider model identifier split into family and version
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct NormalizedModel {
+    /// Model family, e.g. "claude-sonnet", "gpt", "gemini-flash"
+    pub family: String,
+    /// Dotted version, e.g. "4.5", "4o", "2.0"
+    pub version: Option<String>,
+    /// Vendor, when the family is recognised
+    pub vendor: Option<String>,
+    /// Identifier as reported by the provider
+    pub raw: String,
+}
+
+impl NormalizedModel {
+    /// Family and version joined, e.g. "claude-sonnet-4.5"
+    pub fn canonical_name(&self) -> String {
+        match &self.version {
+            Some(version) => format!("{}-{}", self.family, version),
+            None => self.family.clone(),
+        }
+    }
+}
+
+/// Context window of a model in tokens
+pub fn context_window_tokens(model: &NormalizedModel) -> u64 {
+    let version = model.version.as_deref().unwrap_or_default();
+    CONTEXT_WINDOWS
+        .iter()
+        .find(|(family, version_prefix, _)| {
+            family_matches(&model.family, family) && version.starts_with(version_prefix)
+        })
+        .map(|(_, _, window)| *window)
+        .unwrap_or(DEFAULT_CONTEXT_WINDOW)
+}
+
+/// List price of a model, if known
+pub fn model_pricing(model: &NormalizedModel) -> Option<ModelPricing> {
+    let version = model.version.as_deref().unwrap_or_default();
+    MODEL_PRICES
+        .iter()
+        .find(|(family, version_prefix, _)| {
+            family_matches(&model.family, family) && version.starts_with(version_prefix)
+        })
+        .map(|(_, _, pricing)| *pricing)
+}
+
+/// Whether a family starts with the given whole family tokens
+fn family_matches(family: &str, prefix: &str) -> bool {
+    match family.strip_prefix(prefix) {
+        Some(rest) => rest.is_empty() || rest.starts_with('-'),
+        None => false,
+    }
+}
+
+/// Normalize a provider model identifier
+pub fn normalize_model(raw: &str) -> NormalizedModel {
+    let trimmed = raw.trim();
+
+    // Drop routing prefixes ("anthropic/", "models/") and snapshot suffixes ("@20250805")
+    let name = trimmed.rsplit('/').next().unwrap_or(trimmed);
+    let name = name.split('@').next().unwrap_or(name).to_lowercase();
+
+    if name.is_empty() || PLACEHOLDER_MODELS.contains(&name.as_str()) {
+        return NormalizedModel {
+            family: DEFAULT_MODEL_FAMILY.to_string(),
+            version: None,
+            vendor: None,
+            raw: raw.to_string(),
+        };
+    }
+
+    let mut family_parts: Vec<String> = Vec::new();
+    let mut version_parts: Vec<String> = Vec::new();
+
+    for token in name.split(['-', '_', ':']) {
+        if token.is_empty() || NOISE_TOKENS.contains(&token) {
+            continue;
+        }
+        // A snapshot date ("20250929", "2024-08-06", "0613") ends the identifier
+        if token.len() >= 4 && token.chars().all(|c| c.is_ascii_digit()) {
+            break;
+        }
+
+        if token.starts_with(|c: char| c.is_ascii_digit()) {
+            version_parts.push(token.to_string());
+        } else if let Some(digits) = o_series_version(token) {
+            // OpenAI reasoning models: "o3" -> family "o", version "3"
+            family_parts.push("o".to_string());
+            version_parts.push(digits.to_string());
+        } else {
+            family_parts.push(token.to_string());
+        }
+    }
+
+    if family_parts.is_empty() {
+        // Nothing but a version, keep the identifier as the family
+        return NormalizedModel {
+            family: name,
+            version: None,
+            vendor: None,
+            raw: raw.to_string(),
+        };
+    }
+
+    let vendor = MODEL_VENDORS
+        .iter()
+        .find(|(prefix, _)| *prefix == family_parts[0])
+        .map(|(_, vendor)| vendor.to_string());
+
+    NormalizedModel {
+        family: family_parts.join("-"),
+        version: (!version_parts.is_empty()).then(|| version_parts.join(".")),
+        vendor,
+        raw: raw.to_string(),
+    }
+}
+
+fn o_series_version(token: &str) -> Option<&str> {
+    let digits = token.strip_prefix('o')?;
+    (!digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit())).then_some(digits)
+}
+
+/// Record the normalized model in a message's provider metadata
+///
+/// Adds `model_family`, `model_version` and `model_vendor` alongside any
+/// existing provider metadata. Messages without a model are left unchanged.
+pub fn annotate_model(message: &mut CanonicalMessage) {
+    let Some(raw) = message.message.model.as_deref() else {
+        return;
+    };
+    let normalized = normalize_model(raw);
+
+    let metadata = message
+        .provider_metadata
+        .get_or_insert_with(|| Value::Object(Default::default()));
+    // Leave non-object metadata from the provider untouched
+    let Some(object) = metadata.as_object_mut() else {
+        return;
+    };
+
+    object.insert("model_family".to_string(), Value::from(normalized.family));
+    if let Some(version) = normalized.version {
+        object.insert("model_version".to_string(), Value::from(version));
+    }
+    if let Some(vendor) = normalized.vendor {
+        object.insert("model_vendor".to_string(), Value::from(vendor));
+    }
+}
+
+/// Count assistant messages per normalized model in canonical JSONL content
+///
+/// Returns `None` when no message reports a model. The result is uploaded as
+/// `modelUsage` in the session's custom metrics.
+pub fn summarize_model_usage(content: &str) -> Option<Value> {
+    let mut counts: BTreeMap<String, u64> = BTreeMap::new();
+
+    for line in content.lines() {
+        let Ok(message) = serde_json::from_str::<Value>(line) else {
+            continue;
+        };
+        if message["type"] != "assistant" {
+            continue;
+        }
+        let Some(raw) = message["message"]["model"].as_str() else {
+            continue;
+        };
+        *counts
+            .entry(normalize_model(raw).canonical_name())
+            .or_default() += 1;
+    }
+
+    // Most used model first; BTreeMap order breaks ties deterministically
+    let primary = counts
+        .iter()
+        .max_by(|a, b| a.1.cmp(b.1).then_with(|| b.0.cmp(a.0)))
+        .map(|(name, _)| name.clone())?;
+
+    Some(serde_json::json!({
+        "primaryModel": primary,
+        "messagesByModel": counts,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::canonical::{
+        ContentValue, MessageContent, MessageType, CANONICAL_SCHEMA_VERSION,
+    };
+
+    fn normalized(raw: &str) -> (String, Option<String>, Option<String>) {
+        let model = normalize_model(raw);
+        (model.family, model.version, model.vendor)
+    }
+
+    #[test]
+    fn test_normalize_claude_models() {
+        assert_eq!(
+            normalized("claude-sonnet-4-5-20250929"),
+            (
+                "claude-sonnet".to_string(),
+                Some("4.5".to_string()),
+                Some("anthropic".to_string())
+            )
+        );
+        assert_eq!(
+            normalized("claude-3-5-sonnet-20241022").0,
+            "claude-sonnet".to_string()
+        );
+        assert_eq!(
+            normalized("anthropic/claude-opus-4-1@20250805").1,
+            Some("4.1".to_string())
+        );
+    }
+
+    #[test]
+    fn test_normalize_openai_and_gemini_models() {
+        assert_eq!(
+            normalized("gpt-4.1"),
+            (
+                "gpt".to_string(),
+                Some("4.1".to_string()),
+                Some("openai".to_string())
+            )
+        );
+        assert_eq!(normalized("gpt-4o-2024-08-06").1, Some("4o".to_string()));
+        assert_eq!(
+            normalized("o3-mini"),
+            (
+                "o-mini".to_string(),
+                Some("3".to_string()),
+                Some("openai".to_string())
+            )
+        );
+        assert_eq!(
+            normalized("gemini-2.0-flash-exp"),
+            (
+                "gemini-flash".to_string(),
+                Some("2.0".to_string()),
+                Some("google".to_string())
+            )
+        );
+    }
+
+    #[test]
+    fn test_context_window_tokens() {
+        let window = |raw: &str| context_window_tokens(&normalize_model(raw));
+
+        assert_eq!(window("claude-sonnet-4-5-20250929"), 200_000);
+        assert_eq!(window("gpt-4.1"), 1_047_576);
+        assert_eq!(window("gpt-4o-2024-08-06"), 128_000);
+        assert_eq!(window("gpt-5.1-codex"), 400_000);
+        assert_eq!(window("gemini-2.5-pro"), 1_048_576);
+        assert_eq!(window("o3-mini"), 200_000);
+        assert_eq!(window("default"), DEFAULT_CONTEXT_WINDOW);
+        assert_eq!(window("olmo-2-7b"), DEFAULT_CONTEXT_WINDOW);
+        assert_eq!(window("orca-mini"), DEFAULT_CONTEXT_WINDOW);
+    }
+
+    #[test]
+    fn test_model_pricing() {
+        let pricing = |raw: &str| model_pricing(&normalize_model(raw));
+
+        let sonnet = pricing("claude-sonnet-4-5-20250929").unwrap();
+        let cost = sonnet.cost(1_000_000, 100_000, 0, 2_000_000);
+        assert!((cost - 5.1).abs() < 1e-9);
+        assert_eq!(pricing("claude-opus-4-5").unwrap().input, 5.0);
+        assert_eq!(pricing("claude-opus-4-1").unwrap().input, 15.0);
+        assert_eq!(pricing("gpt-5.1-codex").unwrap().output, 10.0);
+        assert_eq!(pricing("gemini-2.5-flash").unwrap().input, 0.3);
+        assert_eq!(pricing("o4-mini").unwrap().input, 2.0);
+        assert_eq!(pricing("default"), None);
+        assert_eq!(pricing("orca-mini"), None);
+    }
+
+    #[test]
+    fn test_normalize_placeholder_models() {
+        assert_eq!(normalized("default"), ("default".to_string(), None, None));
+        assert_eq!(normalized("Auto").0, DEFAULT_MODEL_FAMILY);
+    }
+
+    #[test]
+    fn test_annotate_model_keeps_existing_metadata() {
+        let mut message = CanonicalMessage {
+            schema_version: CANONICAL_SCHEMA_VERSION,
+            uuid: "m1".to_string(),
+            timestamp: "2025-01-01T00:00:00Z".to_string(),
+            message_type: MessageType::Assistant,
+            session_id: "s1".to_string(),
+            provider: "gemini-code".to_string(),
+            cwd: None,
+            git_branch: None,
+            version: None,
+            parent_uuid: None,
+            is_sidechain: None,
+            user_type: None,
+            message: MessageContent {
+                role: "assistant".to_string(),
+                content: ContentValue::Text("hi".to_string()),
+                model: Some("gemini-2.5-pro".to_string()),
+                usage: None,
+            },
+            provider_metadata: Some(serde_json::json!({"gemini_type": "gemini"})),
+            is_meta: None,
+            request_id: None,
+            tool_use_result: None,
+        };
+
+        annotate_model(&mut message);
+
+        let metadata = message.provider_metadata.unwrap();
+        assert_eq!(metadata["gemini_type"], "gemini");
+        assert_eq!(metadata["model_family"], "gemini-pro");
+        assert_eq!(metadata["model_version"], "2.5");
+        assert_eq!(metadata["model_vendor"], "google");
+    }
+
+    #[test]
+    fn test_summarize_model_usage() {
+        let content = [
+            r#"{"type":"user","message":{"role":"user","content":"hi","model":"gpt-5"}}"#,
+            r#"{"type":"assistant","message":{"role":"assistant","content":"a","model":"claude-sonnet-4-5-20250929"}}"#,
+            r#"{"type":"assistant","message":{"role":"assistant","content":"b","model":"claude-sonnet-4-5"}}"#,
+            r#"{"type":"assistant","message":{"role":"assistant","content":"c","model":"claude-haiku-4-5"}}"#,
+        ]
+        .join("\n");
+
+        let usage = summarize_model_usage(&content).unwrap();
+        assert_eq!(usage["primaryModel"], "claude-sonnet-4.5");
+        assert_eq!(usage["messagesByModel"]["claude-sonnet-4.5"], 2);
+        assert_eq!(usage["messagesByModel"]["claude-haiku-4.5"], 1);
+
+        assert!(summarize_model_usage(r#"{"message":{"role":"user"}}"#).is_none());
+    }
+}
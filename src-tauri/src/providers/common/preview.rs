@@ -0,0 +1,91 @@
+//! Dry-run scans for sync previews
+//!
+//! While a provider is previewed its scans still write canonical files to the
+//! session cache, where the real sync reuses them, but leave the database
+//! alone: the scan cache isn't saved, Cursor project paths aren't cached and
+//! the lines converters skip are collected for the preview instead of being
+//! quarantined. Conversions by the provider's watcher during a preview are
+//! collected the same way.
+
+use super::quarantine::QuarantinedLine;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+/// Skipped lines per session, for each provider being previewed
+static PREVIEWS: Mutex<BTreeMap<String, BTreeMap<String, Vec<QuarantinedLine>>>> =
+    Mutex::new(BTreeMap::new());
+
+/// A provider's preview, ended when dropped
+#[derive(Debug)]
+pub struct PreviewGuard {
+    provider: String,
+}
+
+impl PreviewGuard {
+    /// Start previewing `provider`; only one preview per provider runs at a time
+    pub fn start(provider: &str) -> Result<Self, String> {
+        let mut previews = PREVIEWS.lock().unwrap();
+        if previews.contains_key(provider) {
+            return Err(format!("A sync preview of {} is already running", provider));
+        }
+        previews.insert(provider.to_string(), BTreeMap::new());
+        Ok(Self {
+            provider: provider.to_string(),
+        })
+    }
+
+    /// End the preview, returning the lines skipped per session
+    pub fn finish(self) -> BTreeMap<String, Vec<QuarantinedLine>> {
+        PREVIEWS
+            .lock()
+            .unwrap()
+            .remove(&self.provider)
+            .unwrap_or_default()
+    }
+}
+
+impl Drop for PreviewGuard {
+    fn drop(&mut self) {
+        PREVIEWS.lock().unwrap().remove(&self.provider);
+    }
+}
+
+/// Whether `provider`'s scans are dry runs
+pub fn is_previewing(provider: &str) -> bool {
+    PREVIEWS.lock().unwrap().contains_key(provider)
+}
+
+/// Keep a conversion's skipped lines for the preview of `provider`
+///
+/// Returns false when the provider isn't being previewed, in which case the
+/// lines should be quarantined as usual.
+pub fn collect(provider: &str, session_id: &str, lines: &[QuarantinedLine]) -> bool {
+    let mut previews = PREVIEWS.lock().unwrap();
+    let Some(sessions) = previews.get_mut(provider) else {
+        return false;
+    };
+    sessions.insert(session_id.to_string(), lines.to_vec());
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_preview_collects_skipped_lines() {
+        let lines = vec![QuarantinedLine::new(3, "{", "EOF while parsing")];
+        assert!(!collect("preview-test", "s1", &lines));
+
+        let guard = PreviewGuard::start("preview-test").unwrap();
+        assert!(PreviewGuard::start("preview-test").is_err());
+        assert!(is_previewing("preview-test"));
+        assert!(collect("preview-test", "s1", &lines));
+        assert!(collect("preview-test", "s2", &[]));
+
+        let skipped = guard.finish();
+        assert_eq!(skipped["s1"], lines);
+        assert!(skipped["s2"].is_empty());
+        assert!(!is_previewing("preview-test"));
+    }
+}
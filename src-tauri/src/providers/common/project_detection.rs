@@ -0,0 +1,167 @@
+//! Project detection from tool activity
+//!
+//! Some sessions don't record a working directory (some Cursor and Copilot
+//! sessions), so they couldn't be linked to a project. Their tools still name
+//! the files they read and edited, and tool results often print absolute
+//! paths, so the project is inferred by matching those paths against the
+//! roots of projects already known: the root containing most of them wins,
+//! the deepest one when roots are nested, and the first touched on a tie.
+
+use crate::metrics::compute::parse_messages;
+use crate::metrics::tools::touched_file_paths;
+use crate::providers::canonical::{CanonicalMessage, ContentBlock, ContentValue};
+use crate::providers::common::canonical_path::{is_absolute_path, SessionPath};
+
+/// Absolute paths printed in a tool result, without `:line` suffixes
+fn paths_in_result(content: &str) -> impl Iterator<Item = &str> {
+    content
+        .split(|c: char| c.is_whitespace() || matches!(c, '"' | '\'' | '`' | '(' | ')'))
+        .filter(|token| is_absolute_path(token))
+        .map(|token| {
+            // Keep a drive letter's colon, drop `:12:5` suffixes
+            let start = if token.starts_with('/') { 0 } else { 2 };
+            match token[start..].find(':') {
+                Some(colon) => &token[..start + colon],
+                None => token,
+            }
+        })
+        .map(|token| token.trim_end_matches(['.', ',', ';']))
+}
+
+/// Absolute file paths named by tool inputs and results, in order of first use
+pub fn mentioned_paths(messages: &[CanonicalMessage]) -> Vec<String> {
+    let mut paths: Vec<String> = touched_file_paths(messages)
+        .into_iter()
+        .filter(|path| is_absolute_path(path))
+        .collect();
+
+    for message in messages {
+        let ContentValue::Structured(blocks) = &message.message.content else {
+            continue;
+        };
+        for block in blocks {
+            let ContentBlock::ToolResult { content, .. } = block else {
+                continue;
+            };
+            for path in paths_in_result(content) {
+                if !paths.iter().any(|seen| seen == path) {
+                    paths.push(path.to_string());
+                }
+            }
+        }
+    }
+
+    paths
+}
+
+/// The known project root containing most of `paths`
+pub fn infer_project_root<'a>(paths: &[String], roots: &'a [String]) -> Option<&'a str> {
+    // Nested roots: the deepest one containing the path wins
+    let parsed: Vec<(&String, SessionPath)> = roots
+        .iter()
+        .map(|root| (root, SessionPath::parse(root)))
+        .collect();
+    let root_of = |path: &SessionPath| {
+        parsed
+            .iter()
+            .filter(|(_, root)| path.starts_with(root))
+            .max_by_key(|(_, root)| root.components().len())
+            .map(|(root, _)| *root)
+    };
+
+    let mut counts: Vec<(&String, usize)> = Vec::new();
+    for path in paths {
+        if let Some(root) = root_of(&SessionPath::parse(path)) {
+            match counts.iter_mut().find(|(seen, _)| *seen == root) {
+                Some((_, count)) => *count += 1,
+                None => counts.push((root, 1)),
+            }
+        }
+    }
+
+    let mut best: Option<(&String, usize)> = None;
+    for (root, count) in counts {
+        if best.is_none_or(|(_, best_count)| count > best_count) {
+            best = Some((root, count));
+        }
+    }
+    best.map(|(root, _)| root.as_str())
+}
+
+/// Working directory of a session that didn't record one, inferred from the
+/// files its tools touched
+pub fn infer_cwd(content: &str) -> Option<String> {
+    let paths = mentioned_paths(&parse_messages(content));
+    if paths.is_empty() {
+        return None;
+    }
+    let roots = crate::database::get_project_roots().ok()?;
+    infer_project_root(&paths, &roots).map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn roots() -> Vec<String> {
+        ["/work/app", "/work/app/packages/web", "/work/lib"]
+            .map(String::from)
+            .to_vec()
+    }
+
+    fn paths(paths: &[&str]) -> Vec<String> {
+        paths.iter().map(|path| path.to_string()).collect()
+    }
+
+    #[test]
+    fn test_most_touched_root_wins() {
+        let roots = roots();
+        let touched = paths(&["/work/lib/a.rs", "/work/app/b.ts", "/work/app/c.ts"]);
+        assert_eq!(infer_project_root(&touched, &roots), Some("/work/app"));
+
+        // Nested roots attribute to the deepest
+        let touched = paths(&["/work/app/packages/web/index.ts", "/work/lib/a.rs"]);
+        assert_eq!(
+            infer_project_root(&touched, &roots),
+            Some("/work/app/packages/web")
+        );
+
+        // Sibling directories sharing a prefix aren't inside the root
+        let touched = paths(&["/work/application/x.ts", "/tmp/y"]);
+        assert_eq!(infer_project_root(&touched, &roots), None);
+    }
+
+    #[test]
+    fn test_windows_roots_ignore_case_and_separators() {
+        let roots = paths(&[r"C:\Work\App"]);
+        let touched = paths(&["c:/work/app/src/main.ts", r"\\?\C:\WORK\APP\b.ts"]);
+        assert_eq!(infer_project_root(&touched, &roots), Some(r"C:\Work\App"));
+    }
+
+    #[test]
+    fn test_mentioned_paths_from_inputs_and_results() {
+        let content = json!({
+            "uuid": "a1", "timestamp": "2025-01-01T10:00:00Z", "type": "assistant",
+            "sessionId": "s1", "provider": "cursor",
+            "message": {"role": "assistant", "content": [
+                {"type": "tool_use", "id": "t1", "name": "Read",
+                 "input": {"file_path": "/work/app/src/main.ts"}},
+                {"type": "tool_use", "id": "t2", "name": "Edit",
+                 "input": {"file_path": "relative/only.ts"}},
+                {"type": "tool_result", "tool_use_id": "t3",
+                 "content": "/work/lib/src/a.rs:12:5: error\nsee \"C:\\repo\\b.rs\"."}
+            ]}
+        })
+        .to_string();
+
+        assert_eq!(
+            mentioned_paths(&parse_messages(&content)),
+            [
+                "/work/app/src/main.ts",
+                "/work/lib/src/a.rs",
+                "C:\\repo\\b.rs"
+            ]
+        );
+    }
+}
@@ -0,0 +1,373 @@
+//! Quarantine for source lines the converters couldn't handle
+//!
+//! A malformed or unrecognized line doesn't stop a conversion: the session is
+//! written without it and the line is stored in `conversion_errors` with the
+//! error, the app version and the provider's CLI version. Each conversion of a
+//! session replaces its quarantined lines, so a session that converts cleanly
+//! leaves none behind. Converters of JSON documents or databases quarantine
+//! messages instead of lines, numbered by their position in the source.
+//!
+//! The number of skipped lines is kept on the session as
+//! `agent_sessions.conversion_skipped_lines`, so metrics computed from a
+//! lossy conversion can be told apart from complete ones.
+//!
+//! Lines quarantined by an older app version are worth another try after an
+//! update, since the fix for a changed format usually ships in the converter.
+
+use crate::database::with_connection_mut;
+use crate::logging::log_warn;
+use crate::providers::common::preview;
+use crate::providers::versions;
+use chrono::{DateTime, TimeZone, Utc};
+use rusqlite::{params, Connection, Result};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Longest prefix of a source line kept in the quarantine
+const MAX_RAW_LINE_BYTES: usize = 4096;
+
+/// Version of the running app, recorded with every quarantined line
+pub const APP_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// A line that failed while its session was being converted
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuarantinedLine {
+    pub line_number: usize,
+    pub raw_line: String,
+    pub error: String,
+}
+
+impl QuarantinedLine {
+    pub fn new(line_number: usize, raw_line: &str, error: impl ToString) -> Self {
+        Self {
+            line_number,
+            raw_line: truncate(raw_line, MAX_RAW_LINE_BYTES).to_string(),
+            error: error.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConversionError {
+    pub provider: String,
+    pub session_id: String,
+    pub source_path: String,
+    pub line_number: usize,
+    pub raw_line: String,
+    pub error: String,
+    pub app_version: String,
+    pub provider_version: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A session with lines quarantined, identified well enough to convert again
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuarantinedSession {
+    pub provider: String,
+    pub session_id: String,
+    pub source_path: String,
+}
+
+/// Where a conversion's quarantined lines came from
+pub struct ConversionSource<'a> {
+    pub provider: &'a str,
+    pub session_id: &'a str,
+    pub source_path: &'a str,
+    pub provider_version: Option<&'a str>,
+}
+
+fn replace(
+    conn: &mut Connection,
+    source: &ConversionSource,
+    lines: &[QuarantinedLine],
+    now: i64,
+) -> Result<()> {
+    let tx = conn.transaction()?;
+    tx.execute(
+        "DELETE FROM conversion_errors WHERE provider = ? AND session_id = ?",
+        params![source.provider, source.session_id],
+    )?;
+    {
+        let mut stmt = tx.prepare(
+            "INSERT OR REPLACE INTO conversion_errors (
+                provider, session_id, source_path, line_number, raw_line,
+                error, app_version, provider_version, created_at
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        )?;
+        for line in lines {
+            stmt.execute(params![
+                source.provider,
+                source.session_id,
+                source.source_path,
+                line.line_number as i64,
+                line.raw_line,
+                line.error,
+                APP_VERSION,
+                source.provider_version,
+                now,
+            ])?;
+        }
+    }
+    tx.commit()
+}
+
+fn list(conn: &Connection, provider: Option<&str>) -> Result<Vec<ConversionError>> {
+    let mut stmt = conn.prepare(
+        "SELECT provider, session_id, source_path, line_number, raw_line,
+                error, app_version, provider_version, created_at
+         FROM conversion_errors
+         WHERE ?1 IS NULL OR provider = ?1
+         ORDER BY created_at DESC, provider, session_id, line_number",
+    )?;
+    let errors = stmt
+        .query_map(params![provider], |row| {
+            Ok(ConversionError {
+                provider: row.get(0)?,
+                session_id: row.get(1)?,
+                source_path: row.get(2)?,
+                line_number: row.get::<_, i64>(3)? as usize,
+                raw_line: row.get(4)?,
+                error: row.get(5)?,
+                app_version: row.get(6)?,
+                provider_version: row.get(7)?,
+                created_at: Utc
+                    .timestamp_millis_opt(row.get(8)?)
+                    .single()
+                    .unwrap_or_default(),
+            })
+        })?
+        .collect::<Result<Vec<_>>>()?;
+    Ok(errors)
+}
+
+/// Sessions with lines quarantined by any app version other than `app_version`
+fn stale_sessions(conn: &Connection, app_version: &str) -> Result<Vec<QuarantinedSession>> {
+    let mut stmt = conn.prepare(
+        "SELECT DISTINCT provider, session_id, source_path FROM conversion_errors
+         WHERE app_version != ?",
+    )?;
+    let sessions = stmt
+        .query_map(params![app_version], |row| {
+            Ok(QuarantinedSession {
+                provider: row.get(0)?,
+                session_id: row.get(1)?,
+                source_path: row.get(2)?,
+            })
+        })?
+        .collect::<Result<Vec<_>>>()?;
+    Ok(sessions)
+}
+
+/// Copy the session's quarantined line count onto its `agent_sessions` row
+fn update_skipped_lines(conn: &Connection, provider: &str, session_id: &str) -> Result<usize> {
+    conn.execute(
+        "UPDATE agent_sessions SET conversion_skipped_lines = (
+            SELECT COUNT(*) FROM conversion_errors WHERE provider = ?1 AND session_id = ?2
+         )
+         WHERE provider = ?1 AND session_id = ?2",
+        params![provider, session_id],
+    )
+}
+
+/// Replace a session's quarantined lines with those of its latest conversion
+pub fn record(source: &ConversionSource, lines: &[QuarantinedLine]) -> Result<(), String> {
+    let now = Utc::now().timestamp_millis();
+    with_connection_mut(|conn| {
+        replace(conn, source, lines, now)?;
+        update_skipped_lines(conn, source.provider, source.session_id)?;
+        Ok(())
+    })
+    .map_err(|e| format!("Failed to record conversion errors: {}", e))
+}
+
+/// Log a summary of a finished conversion's skipped lines and record them
+///
+/// Called by the converters after writing a canonical file, next to the
+/// output validation. Like validation, it never fails the conversion.
+pub fn record_conversion(
+    provider_id: &str,
+    session_id: &str,
+    source_path: &Path,
+    lines: &[QuarantinedLine],
+) {
+    if preview::collect(provider_id, session_id, lines) {
+        return;
+    }
+    if let Some(summary) = summarize(lines) {
+        log_warn(
+            provider_id,
+            &format!("⚠ Session {} converted partially: {}", session_id, summary),
+        )
+        .unwrap_or_default();
+    }
+
+    let provider_version = versions::detected_version(provider_id);
+    let source = ConversionSource {
+        provider: provider_id,
+        session_id,
+        source_path: &source_path.to_string_lossy(),
+        provider_version: provider_version.as_deref(),
+    };
+    // Without a database, as in tests, there is nowhere to keep them
+    if let Err(e) = record(&source, lines) {
+        if !lines.is_empty() {
+            log_warn(provider_id, &e).unwrap_or_default();
+        }
+    }
+}
+
+/// Set a newly inserted session's skipped line count from its quarantine,
+/// which the conversion recorded before the session had a row
+pub fn sync_skipped_lines(provider: &str, session_id: &str) -> Result<(), String> {
+    with_connection_mut(|conn| update_skipped_lines(conn, provider, session_id))
+        .map(|_| ())
+        .map_err(|e| format!("Failed to update skipped lines: {}", e))
+}
+
+/// "3 lines skipped: 2 × missing field `id`, 1 × EOF while parsing a value"
+pub fn summarize(lines: &[QuarantinedLine]) -> Option<String> {
+    if lines.is_empty() {
+        return None;
+    }
+    let mut reasons: BTreeMap<&str, usize> = BTreeMap::new();
+    for line in lines {
+        *reasons.entry(reason(&line.error)).or_default() += 1;
+    }
+    let mut reasons: Vec<(&str, usize)> = reasons.into_iter().collect();
+    reasons.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+
+    Some(format!(
+        "{} {} skipped: {}",
+        lines.len(),
+        if lines.len() == 1 { "line" } else { "lines" },
+        reasons
+            .iter()
+            .map(|(reason, count)| format!("{} × {}", count, reason))
+            .collect::<Vec<_>>()
+            .join(", ")
+    ))
+}
+
+/// An error without the position serde_json appends, so equal causes group
+fn reason(error: &str) -> &str {
+    match error.rfind(" at line ") {
+        Some(index) if error[index..].contains(" column ") => &error[..index],
+        _ => error,
+    }
+}
+
+/// Quarantined lines, newest first, optionally for one provider
+pub fn list_conversion_errors(provider: Option<&str>) -> Result<Vec<ConversionError>, String> {
+    with_connection_mut(|conn| list(conn, provider))
+        .map_err(|e| format!("Failed to load conversion errors: {}", e))
+}
+
+/// Sessions quarantined before this version of the app was installed
+pub fn sessions_to_retry() -> Result<Vec<QuarantinedSession>, String> {
+    with_connection_mut(|conn| stale_sessions(conn, APP_VERSION))
+        .map_err(|e| format!("Failed to load conversion errors: {}", e))
+}
+
+/// Every session with quarantined lines, optionally for one provider
+pub fn quarantined_sessions(provider: Option<&str>) -> Result<Vec<QuarantinedSession>, String> {
+    let mut sessions: Vec<QuarantinedSession> = list_conversion_errors(provider)?
+        .into_iter()
+        .map(|error| QuarantinedSession {
+            provider: error.provider,
+            session_id: error.session_id,
+            source_path: error.source_path,
+        })
+        .collect();
+    sessions.dedup();
+    Ok(sessions)
+}
+
+fn truncate(line: &str, max_bytes: usize) -> &str {
+    if line.len() <= max_bytes {
+        return line;
+    }
+    let mut end = max_bytes;
+    while !line.is_char_boundary(end) {
+        end -= 1;
+    }
+    &line[..end]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn source(session_id: &str) -> ConversionSource<'_> {
+        ConversionSource {
+            provider: "codex",
+            session_id,
+            source_path: "/home/me/.codex/sessions/rollout.jsonl",
+            provider_version: Some("0.50.0"),
+        }
+    }
+
+    #[test]
+    fn test_conversion_replaces_quarantined_lines() {
+        let mut conn = crate::database::test_connection();
+        let lines = [
+            QuarantinedLine::new(3, "{\"type\":", "EOF while parsing"),
+            QuarantinedLine::new(7, "{\"type\":\"new\"}", "unknown variant `new`"),
+        ];
+        replace(&mut conn, &source("s1"), &lines, 1).unwrap();
+        replace(&mut conn, &source("s2"), &lines[..1], 2).unwrap();
+
+        let errors = list(&conn, Some("codex")).unwrap();
+        assert_eq!(errors.len(), 3);
+        assert_eq!(errors[0].session_id, "s2");
+        assert_eq!(errors[0].app_version, APP_VERSION);
+        assert_eq!(errors[0].provider_version.as_deref(), Some("0.50.0"));
+        assert!(list(&conn, Some("claude-code")).unwrap().is_empty());
+
+        // A clean conversion leaves nothing quarantined for the session
+        replace(&mut conn, &source("s1"), &[], 3).unwrap();
+        let errors = list(&conn, None).unwrap();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].session_id, "s2");
+    }
+
+    #[test]
+    fn test_stale_sessions_come_from_other_versions() {
+        let mut conn = crate::database::test_connection();
+        replace(
+            &mut conn,
+            &source("s1"),
+            &[QuarantinedLine::new(1, "x", "expected value")],
+            1,
+        )
+        .unwrap();
+
+        assert!(stale_sessions(&conn, APP_VERSION).unwrap().is_empty());
+        let stale = stale_sessions(&conn, "99.0.0").unwrap();
+        assert_eq!(stale.len(), 1);
+        assert_eq!(stale[0].session_id, "s1");
+    }
+
+    #[test]
+    fn test_summary_groups_reasons() {
+        let lines = [
+            QuarantinedLine::new(1, "{", "EOF while parsing an object at line 1 column 1"),
+            QuarantinedLine::new(2, "{}", "missing field `type` at line 1 column 2"),
+            QuarantinedLine::new(5, "{\"a\":1}", "missing field `type` at line 1 column 7"),
+        ];
+        assert_eq!(
+            summarize(&lines).unwrap(),
+            "3 lines skipped: 2 × missing field `type`, 1 × EOF while parsing an object"
+        );
+        assert_eq!(summarize(&[]), None);
+    }
+
+    #[test]
+    fn test_long_lines_are_truncated_on_char_boundaries() {
+        let line = "é".repeat(MAX_RAW_LINE_BYTES);
+        let quarantined = QuarantinedLine::new(1, &line, "too long");
+        assert!(quarantined.raw_line.len() <= MAX_RAW_LINE_BYTES);
+        assert!(quarantined.raw_line.chars().all(|c| c == 'é'));
+    }
+}
@@ -0,0 +1,185 @@
+//! Resumed sessions
+//!
+//! Resuming a conversation (`claude --resume`, an OpenCode fork) starts a new
+//! session file that repeats the earlier conversation before continuing it.
+//! Each file stays as the provider wrote it; the new session records the one
+//! it continues in `resumed_from`, found in one of two ways:
+//! - messages at the start of the file that still carry another session's id
+//!   name the session they were copied from
+//! - otherwise, a session whose first message has the same id as an earlier
+//!   session's first message starts with that session's prefix
+
+use crate::database::with_connection_mut;
+use rusqlite::{params, Connection, OptionalExtension, Result};
+use serde::Deserialize;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MessageIds {
+    uuid: Option<String>,
+    session_id: Option<String>,
+}
+
+/// What the start of a canonical file says about the session it continues
+#[derive(Debug, Default, PartialEq)]
+struct ResumptionHints {
+    first_message_uuid: Option<String>,
+    /// The last session named by the copied messages the file starts with
+    parent_session_id: Option<String>,
+}
+
+fn read_hints(reader: impl BufRead, session_id: &str) -> ResumptionHints {
+    let mut hints = ResumptionHints::default();
+    let messages = reader
+        .lines()
+        .map_while(|line| line.ok())
+        .filter_map(|line| serde_json::from_str::<MessageIds>(&line).ok());
+    for message in messages {
+        if hints.first_message_uuid.is_none() {
+            hints.first_message_uuid = message.uuid;
+        }
+        match message.session_id {
+            Some(id) if id != session_id => hints.parent_session_id = Some(id),
+            // The session's own messages start; the copied prefix is over
+            _ => break,
+        }
+    }
+    hints
+}
+
+fn link(
+    conn: &Connection,
+    provider: &str,
+    session_id: &str,
+    hints: &ResumptionHints,
+) -> Result<Option<String>> {
+    conn.execute(
+        "UPDATE agent_sessions SET first_message_uuid = ? WHERE session_id = ?",
+        params![hints.first_message_uuid, session_id],
+    )?;
+
+    let named_parent = match &hints.parent_session_id {
+        Some(parent) => conn
+            .query_row(
+                "SELECT session_id FROM agent_sessions WHERE session_id = ?",
+                params![parent],
+                |row| row.get(0),
+            )
+            .optional()?,
+        None => None,
+    };
+    let parent = match (named_parent, &hints.first_message_uuid) {
+        (Some(parent), _) => Some(parent),
+        // The most recent earlier session this one shares its first message with
+        (None, Some(first_message_uuid)) => conn
+            .query_row(
+                "SELECT other.session_id FROM agent_sessions other, agent_sessions this
+                 WHERE this.session_id = ?1 AND other.provider = ?2
+                   AND other.first_message_uuid = ?3 AND other.session_id != ?1
+                   AND other.created_at < this.created_at
+                 ORDER BY other.created_at DESC
+                 LIMIT 1",
+                params![session_id, provider, first_message_uuid],
+                |row| row.get(0),
+            )
+            .optional()?,
+        (None, None) => None,
+    };
+
+    conn.execute(
+        "UPDATE agent_sessions SET resumed_from = ? WHERE session_id = ?",
+        params![parent, session_id],
+    )?;
+    Ok(parent)
+}
+
+/// Record which session `session_id` resumes, if any, returning it
+pub fn link_resumed_session(
+    provider: &str,
+    session_id: &str,
+    file_path: &Path,
+) -> std::result::Result<Option<String>, String> {
+    let file = File::open(file_path).map_err(|e| format!("Failed to open session: {}", e))?;
+    let hints = read_hints(BufReader::new(file), session_id);
+    with_connection_mut(|conn| link(conn, provider, session_id, &hints))
+        .map_err(|e| format!("Failed to link resumed session: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(conn: &Connection, session_id: &str, created_at: i64) {
+        conn.execute(
+            "INSERT INTO agent_sessions (
+                id, provider, project_name, session_id, file_name, file_path, file_size,
+                created_at, uploaded_at
+            ) VALUES (?1, 'claude-code', 'app', ?1, 'f.jsonl', '/f', 10, ?2, 0)",
+            params![session_id, created_at],
+        )
+        .unwrap();
+    }
+
+    fn hints(content: &str, session_id: &str) -> ResumptionHints {
+        read_hints(content.as_bytes(), session_id)
+    }
+
+    #[test]
+    fn test_copied_prefix_names_parent() {
+        let content = concat!(
+            "{\"uuid\":\"m1\",\"sessionId\":\"a\"}\n",
+            "{\"uuid\":\"m2\",\"sessionId\":\"b\"}\n",
+            "{\"uuid\":\"m3\",\"sessionId\":\"c\"}\n",
+            "{\"uuid\":\"m4\",\"sessionId\":\"a\"}\n",
+        );
+        assert_eq!(
+            hints(content, "c"),
+            ResumptionHints {
+                first_message_uuid: Some("m1".to_string()),
+                parent_session_id: Some("b".to_string()),
+            }
+        );
+        assert_eq!(hints(content, "a").parent_session_id, None);
+    }
+
+    #[test]
+    fn test_links_named_parent_or_shared_prefix() {
+        let conn = crate::database::test_connection();
+        session(&conn, "a", 1);
+        session(&conn, "b", 2);
+        session(&conn, "c", 3);
+
+        let first = |uuid: &str| ResumptionHints {
+            first_message_uuid: Some(uuid.to_string()),
+            parent_session_id: None,
+        };
+        assert_eq!(link(&conn, "claude-code", "a", &first("m1")).unwrap(), None);
+        assert_eq!(
+            link(&conn, "claude-code", "b", &first("m1")).unwrap(),
+            Some("a".to_string())
+        );
+        // The most recent earlier session wins, and later ones never do
+        assert_eq!(
+            link(&conn, "claude-code", "c", &first("m1")).unwrap(),
+            Some("b".to_string())
+        );
+        assert_eq!(link(&conn, "claude-code", "a", &first("m1")).unwrap(), None);
+
+        // A named parent must exist
+        let named = |parent: &str| ResumptionHints {
+            first_message_uuid: Some("other".to_string()),
+            parent_session_id: Some(parent.to_string()),
+        };
+        assert_eq!(
+            link(&conn, "claude-code", "c", &named("a")).unwrap(),
+            Some("a".to_string())
+        );
+        assert_eq!(
+            link(&conn, "claude-code", "c", &named("missing")).unwrap(),
+            None
+        );
+    }
+}
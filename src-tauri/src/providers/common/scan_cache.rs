@@ -0,0 +1,609 @@
+//! Incremental, parallel historical scans
+//!
+//! Parsing a provider file and rewriting its canonical copy dominates scan
+//! time, so `ScanCache` remembers what each source file produced along with
+//! its size, modification time and hash. A file whose size and mtime are
+//! unchanged, or that was touched without its content changing, reuses the
+//! cached session as long as its canonical copy still exists, the same
+//! converter produced it and the sidechain files merged into it are
+//! unchanged. Rows of source files that no longer exist are pruned.
+//!
+//! `scan_project_dirs` walks project directories in parallel and reports how long
+//! each one took, for the rescan progress events. A cancelled scan stops
+//! parsing and returns what it found so far; the cache keeps those files.
+
+use super::ingest_log;
+use super::preview::is_previewing;
+use super::quarantine::APP_VERSION;
+use super::SessionInfo;
+use crate::database::with_connection_mut;
+use crate::events::CancellationToken;
+use crate::logging::{log_info, log_warn};
+use rayon::prelude::*;
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Instant, UNIX_EPOCH};
+
+/// Sessions found by a scan, and which of them came from the cache
+#[derive(Debug, Default)]
+pub struct ScanReport {
+    pub sessions: Vec<SessionInfo>,
+    /// Session IDs whose source file hadn't changed since the last scan
+    pub unchanged: HashSet<String>,
+    pub project_timings: Vec<ProjectScanTiming>,
+}
+
+impl ScanReport {
+    /// Keep only the sessions matching `keep`
+    pub fn retain(&mut self, keep: impl Fn(&SessionInfo) -> bool) {
+        let (kept, dropped): (Vec<_>, Vec<_>) = self.sessions.drain(..).partition(|s| keep(s));
+        for session in dropped {
+            self.unchanged.remove(&session.session_id);
+        }
+        self.sessions = kept;
+    }
+
+    /// Report for a scanner that doesn't use the cache
+    pub fn uncached(project: &str, sessions: Vec<SessionInfo>, started: Instant) -> Self {
+        Self {
+            project_timings: vec![ProjectScanTiming {
+                project: project.to_string(),
+                files: sessions.len(),
+                cached: 0,
+                duration_ms: started.elapsed().as_millis() as u64,
+            }],
+            sessions,
+            unchanged: HashSet::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectScanTiming {
+    pub project: String,
+    /// Source files found in the project
+    pub files: usize,
+    /// Files reused from the cache instead of being parsed
+    pub cached: usize,
+    pub duration_ms: u64,
+}
+
+/// What scanning one project directory found
+struct ProjectScan {
+    /// Sessions, flagged when they came from the cache
+    sessions: Vec<(SessionInfo, bool)>,
+    timing: ProjectScanTiming,
+    /// Source files parsed, and those that failed to, for the ingest log
+    parsed: Vec<PathBuf>,
+    failed: Vec<(PathBuf, String)>,
+}
+
+/// Converter whose output is cached; converters ship with the app
+const CONVERTER_VERSION: &str = APP_VERSION;
+
+/// A file merged into a session besides its source, and its size and
+/// modification time when the session was converted (None if it was missing)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct SidechainStamp {
+    path: PathBuf,
+    stamp: Option<(i64, i64)>,
+}
+
+#[derive(Debug, Clone)]
+struct CacheEntry {
+    size: i64,
+    mtime_ms: i64,
+    content_hash: String,
+    session: SessionInfo,
+    sidechains: Vec<SidechainStamp>,
+}
+
+impl CacheEntry {
+    fn sidechains_unchanged(&self) -> bool {
+        self.sidechains
+            .iter()
+            .all(|sidechain| file_stamp(&sidechain.path) == sidechain.stamp)
+    }
+}
+
+/// Sidechain files a provider merges into the session of `path`, stamped
+fn sidechain_stamps(provider: &str, path: &Path) -> Vec<SidechainStamp> {
+    let files = match provider {
+        "claude-code" => crate::providers::claude::converter_utils::sidechain_files(path),
+        _ => Vec::new(),
+    };
+    files
+        .into_iter()
+        .map(|path| SidechainStamp {
+            stamp: file_stamp(&path),
+            path,
+        })
+        .collect()
+}
+
+/// Size and modification time of a file
+fn file_stamp(path: &Path) -> Option<(i64, i64)> {
+    let metadata = fs::metadata(path).ok()?;
+    let mtime_ms = metadata
+        .modified()
+        .ok()?
+        .duration_since(UNIX_EPOCH)
+        .ok()?
+        .as_millis() as i64;
+    Some((metadata.len() as i64, mtime_ms))
+}
+
+/// SHA256 of a file's content
+fn content_hash(path: &Path) -> Option<String> {
+    let mut file = fs::File::open(path).ok()?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher).ok()?;
+    Some(format!("{:x}", hasher.finalize()))
+}
+
+/// A provider's scan cache, loaded once per scan and saved at its end
+pub struct ScanCache {
+    provider: String,
+    entries: HashMap<PathBuf, CacheEntry>,
+    updates: Mutex<Vec<(PathBuf, CacheEntry)>>,
+}
+
+impl ScanCache {
+    fn empty(provider: &str) -> Self {
+        Self {
+            provider: provider.to_string(),
+            entries: HashMap::new(),
+            updates: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Load the provider's cache; scans without a database start from nothing
+    pub fn load(provider: &str) -> Self {
+        with_connection_mut(|conn| Self::load_from(conn, provider)).unwrap_or_else(|e| {
+            log_warn(provider, &format!("⚠ Scan cache unavailable: {}", e)).unwrap_or_default();
+            Self::empty(provider)
+        })
+    }
+
+    fn load_from(conn: &Connection, provider: &str) -> rusqlite::Result<Self> {
+        let mut stmt = conn.prepare(
+            "SELECT source_path, size, mtime_ms, content_hash, session, sidechains
+             FROM scan_cache WHERE provider = ? AND converter_version = ?",
+        )?;
+        let entries = stmt
+            .query_map(params![provider, CONVERTER_VERSION], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get(1)?,
+                    row.get(2)?,
+                    row.get(3)?,
+                    row.get::<_, String>(4)?,
+                    row.get::<_, String>(5)?,
+                ))
+            })?
+            .filter_map(|row| {
+                let (path, size, mtime_ms, content_hash, session, sidechains) = row.ok()?;
+                let session = serde_json::from_str(&session).ok()?;
+                let sidechains = serde_json::from_str(&sidechains).ok()?;
+                Some((
+                    PathBuf::from(path),
+                    CacheEntry {
+                        size,
+                        mtime_ms,
+                        content_hash,
+                        session,
+                        sidechains,
+                    },
+                ))
+            })
+            .collect();
+
+        let mut cache = Self::empty(provider);
+        cache.entries = entries;
+        Ok(cache)
+    }
+
+    /// The session a source file produces: from the cache if the file hasn't
+    /// changed, otherwise from `parse`. The flag is true for cached sessions.
+    pub fn scan_file(
+        &self,
+        path: &Path,
+        parse: impl FnOnce() -> Result<Option<SessionInfo>, String>,
+    ) -> (Result<Option<SessionInfo>, String>, bool) {
+        let Some((size, mtime_ms)) = file_stamp(path) else {
+            return (parse(), false);
+        };
+
+        let mut known_hash = None;
+        if let Some(entry) = self.entries.get(path).filter(|entry| {
+            entry.size == size && entry.session.file_path.exists() && entry.sidechains_unchanged()
+        }) {
+            if entry.mtime_ms == mtime_ms {
+                return (Ok(Some(entry.session.clone())), true);
+            }
+            // Touched: unchanged if the content hashes the same
+            let hash = content_hash(path);
+            if hash.as_deref() == Some(entry.content_hash.as_str()) {
+                self.record(
+                    path,
+                    CacheEntry {
+                        mtime_ms,
+                        ..entry.clone()
+                    },
+                );
+                return (Ok(Some(entry.session.clone())), true);
+            }
+            known_hash = hash;
+        }
+
+        let result = parse();
+        if let Ok(Some(session)) = &result {
+            let hash = known_hash.or_else(|| content_hash(path));
+            if let Some(content_hash) = hash {
+                self.record(
+                    path,
+                    CacheEntry {
+                        size,
+                        mtime_ms,
+                        content_hash,
+                        session: session.clone(),
+                        sidechains: sidechain_stamps(&self.provider, path),
+                    },
+                );
+            }
+        }
+        (result, false)
+    }
+
+    fn record(&self, path: &Path, entry: CacheEntry) {
+        self.updates
+            .lock()
+            .unwrap()
+            .push((path.to_path_buf(), entry));
+    }
+
+    /// Write the files parsed during the scan back to the cache, dropping rows
+    /// of other converters and of source files that no longer exist
+    ///
+    /// Previews leave the cache as it was.
+    pub fn save(&self) {
+        if is_previewing(&self.provider) {
+            return;
+        }
+        if let Err(e) = with_connection_mut(|conn| self.save_to(conn)) {
+            log_warn(
+                &self.provider,
+                &format!("⚠ Failed to save scan cache: {}", e),
+            )
+            .unwrap_or_default();
+        }
+    }
+
+    fn save_to(&self, conn: &mut Connection) -> rusqlite::Result<()> {
+        let updates = std::mem::take(&mut *self.updates.lock().unwrap());
+        let now = chrono::Utc::now().timestamp_millis();
+        let tx = conn.transaction()?;
+        tx.execute(
+            "DELETE FROM scan_cache WHERE provider = ? AND converter_version != ?",
+            params![self.provider, CONVERTER_VERSION],
+        )?;
+        {
+            let mut stmt =
+                tx.prepare("DELETE FROM scan_cache WHERE provider = ? AND source_path = ?")?;
+            for path in self.entries.keys().filter(|path| !path.exists()) {
+                stmt.execute(params![self.provider, path.to_string_lossy()])?;
+            }
+        }
+        {
+            let mut stmt = tx.prepare(
+                "INSERT OR REPLACE INTO scan_cache
+                    (provider, source_path, size, mtime_ms, content_hash, session, scanned_at,
+                     converter_version, sidechains)
+                 VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            )?;
+            for (path, entry) in updates {
+                let (Ok(session), Ok(sidechains)) = (
+                    serde_json::to_string(&entry.session),
+                    serde_json::to_string(&entry.sidechains),
+                ) else {
+                    continue;
+                };
+                stmt.execute(params![
+                    self.provider,
+                    path.to_string_lossy(),
+                    entry.size,
+                    entry.mtime_ms,
+                    entry.content_hash,
+                    session,
+                    now,
+                    CONVERTER_VERSION,
+                    sidechains
+                ])?;
+            }
+        }
+        tx.commit()
+    }
+}
+
+/// Forget the scans that produced these canonical files, e.g. after pruning
+/// them from the session cache, so the next scan converts their sources again
+pub fn forget_canonical_files(conn: &Connection, files: &[PathBuf]) -> rusqlite::Result<usize> {
+    let mut stmt =
+        conn.prepare("DELETE FROM scan_cache WHERE json_extract(session, '$.file_path') = ?")?;
+    let mut removed = 0;
+    for file in files {
+        removed += stmt.execute(params![file.to_string_lossy()])?;
+    }
+    Ok(removed)
+}
+
+/// Point cached scans at canonical files moved from under `old_prefix` to
+/// under `new_prefix`
+pub fn move_canonical_files(
+    conn: &Connection,
+    old_prefix: &str,
+    new_prefix: &str,
+) -> rusqlite::Result<usize> {
+    conn.execute(
+        "UPDATE scan_cache
+         SET session = json_set(session, '$.file_path',
+             ?2 || substr(json_extract(session, '$.file_path'), length(?1) + 1))
+         WHERE substr(json_extract(session, '$.file_path'), 1, length(?1)) = ?1",
+        params![old_prefix, new_prefix],
+    )
+}
+
+/// Scan project directories in parallel. `list_files` finds a project's
+/// source files and `parse` turns one into a session, given the project name;
+/// parse failures are logged, recorded in the ingest log and skipped. Files
+/// left when `cancel` is cancelled are skipped too.
+pub fn scan_project_dirs<L, P>(
+    provider: &str,
+    projects: Vec<(String, PathBuf)>,
+    cancel: &CancellationToken,
+    list_files: L,
+    parse: P,
+) -> ScanReport
+where
+    L: Fn(&Path) -> Vec<PathBuf> + Sync,
+    P: Fn(&Path, &str) -> Result<Option<SessionInfo>, String> + Sync,
+{
+    let cache = ScanCache::load(provider);
+
+    let results: Vec<ProjectScan> = projects
+        .par_iter()
+        .map(|(project, dir)| {
+            let started = Instant::now();
+            let files = list_files(dir);
+            let outcomes: Vec<_> = files
+                .par_iter()
+                .filter(|_| !cancel.is_cancelled())
+                .map(|file| {
+                    let (result, cached) = cache.scan_file(file, || parse(file, project));
+                    (file, result, cached)
+                })
+                .collect();
+
+            let mut sessions = Vec::new();
+            let mut parsed = Vec::new();
+            let mut failed = Vec::new();
+            for (file, result, cached) in outcomes {
+                match result {
+                    Ok(session) => {
+                        sessions.extend(session.map(|session| (session, cached)));
+                        parsed.push(file.clone());
+                    }
+                    Err(e) => {
+                        log_warn(
+                            provider,
+                            &format!("Failed to parse session {}: {}", file.display(), e),
+                        )
+                        .unwrap_or_default();
+                        failed.push((file.clone(), e));
+                    }
+                }
+            }
+
+            let timing = ProjectScanTiming {
+                project: project.clone(),
+                files: files.len(),
+                cached: sessions.iter().filter(|(_, cached)| *cached).count(),
+                duration_ms: started.elapsed().as_millis() as u64,
+            };
+            ProjectScan {
+                sessions,
+                timing,
+                parsed,
+                failed,
+            }
+        })
+        .collect();
+
+    cache.save();
+
+    let mut report = ScanReport::default();
+    let mut parsed = Vec::new();
+    let mut failed = Vec::new();
+    for scan in results {
+        for (session, cached) in scan.sessions {
+            if cached {
+                report.unchanged.insert(session.session_id.clone());
+            }
+            report.sessions.push(session);
+        }
+        report.project_timings.push(scan.timing);
+        parsed.extend(scan.parsed);
+        failed.extend(scan.failed);
+    }
+    ingest_log::record_scan(provider, &parsed, &failed);
+
+    log_info(
+        provider,
+        &format!(
+            "📊 Found {} sessions ({} unchanged) in {} projects",
+            report.sessions.len(),
+            report.unchanged.len(),
+            report.project_timings.len()
+        ),
+    )
+    .unwrap_or_default();
+
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    fn session(file_path: &Path) -> SessionInfo {
+        SessionInfo {
+            provider: "claude-code".to_string(),
+            project_name: "app".to_string(),
+            session_id: "s1".to_string(),
+            file_path: file_path.to_path_buf(),
+            file_name: "s1.jsonl".to_string(),
+            session_start_time: None,
+            session_end_time: None,
+            duration_ms: None,
+            file_size: 0,
+            content: None,
+            cwd: None,
+            project_hash: None,
+        }
+    }
+
+    #[test]
+    fn test_scan_file_skips_unchanged_files() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let source = temp_dir.path().join("s1.jsonl");
+        let canonical = temp_dir.path().join("canonical.jsonl");
+        fs::write(&source, "{}").unwrap();
+        fs::write(&canonical, "{}").unwrap();
+
+        let mut conn = crate::database::test_connection();
+
+        let parses = Cell::new(0);
+        let parse = || {
+            parses.set(parses.get() + 1);
+            Ok(Some(session(&canonical)))
+        };
+
+        let cache = ScanCache::load_from(&conn, "claude-code").unwrap();
+        assert!(!cache.scan_file(&source, parse).1);
+        cache.save_to(&mut conn).unwrap();
+
+        let cache = ScanCache::load_from(&conn, "claude-code").unwrap();
+        let (result, cached) = cache.scan_file(&source, parse);
+        assert!(cached);
+        assert_eq!(result.unwrap().unwrap().session_id, "s1");
+        assert_eq!(parses.get(), 1);
+
+        let write = |content: &str, mtime_secs: u64| {
+            fs::write(&source, content).unwrap();
+            fs::File::options()
+                .write(true)
+                .open(&source)
+                .unwrap()
+                .set_modified(UNIX_EPOCH + std::time::Duration::from_secs(mtime_secs))
+                .unwrap();
+        };
+
+        // Same size, different content
+        write("[]", 1_000);
+        assert!(!cache.scan_file(&source, parse).1);
+
+        // Touched without changing
+        write("{}", 2_000);
+        assert!(cache.scan_file(&source, parse).1);
+
+        // A missing canonical copy is rebuilt
+        fs::remove_file(&canonical).unwrap();
+        assert!(!cache.scan_file(&source, parse).1);
+        assert_eq!(parses.get(), 3);
+    }
+
+    #[test]
+    fn test_scan_file_follows_sidechains_and_prunes_missing_sources() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let source = temp_dir.path().join("s1.jsonl");
+        let agent = temp_dir.path().join("agent-a1.jsonl");
+        let canonical = temp_dir.path().join("canonical.jsonl");
+        fs::write(
+            &source,
+            r#"{"type":"user","uuid":"u1","toolUseResult":{"agentId":"a1"}}"#,
+        )
+        .unwrap();
+        fs::write(&canonical, "{}").unwrap();
+
+        let mut conn = crate::database::test_connection();
+        let parse = || Ok(Some(session(&canonical)));
+
+        let cache = ScanCache::load_from(&conn, "claude-code").unwrap();
+        assert!(!cache.scan_file(&source, parse).1);
+        cache.save_to(&mut conn).unwrap();
+
+        // The sidechain showing up makes the session stale
+        let cache = ScanCache::load_from(&conn, "claude-code").unwrap();
+        assert!(cache.entries[&source].sidechains_unchanged());
+        fs::write(&agent, "{}").unwrap();
+        assert!(!cache.scan_file(&source, parse).1);
+
+        // Another converter's rows aren't reused
+        conn.execute("UPDATE scan_cache SET converter_version = 'old'", [])
+            .unwrap();
+        assert!(ScanCache::load_from(&conn, "claude-code")
+            .unwrap()
+            .entries
+            .is_empty());
+        cache.save_to(&mut conn).unwrap();
+
+        let cache = ScanCache::load_from(&conn, "claude-code").unwrap();
+        assert_eq!(cache.entries.len(), 1);
+        fs::remove_file(&source).unwrap();
+        cache.save_to(&mut conn).unwrap();
+        let rows: i64 = conn
+            .query_row("SELECT COUNT(*) FROM scan_cache", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(rows, 0);
+    }
+
+    #[test]
+    fn test_moved_and_pruned_canonical_files() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let source = temp_dir.path().join("s1.jsonl");
+        let old_dir = temp_dir.path().join("old");
+        let new_dir = temp_dir.path().join("new");
+        fs::create_dir_all(&old_dir).unwrap();
+        fs::create_dir_all(&new_dir).unwrap();
+        fs::write(&source, "{}").unwrap();
+        fs::write(old_dir.join("s1.jsonl"), "{}").unwrap();
+
+        let mut conn = crate::database::test_connection();
+        let cache = ScanCache::load_from(&conn, "claude-code").unwrap();
+        cache.scan_file(&source, || Ok(Some(session(&old_dir.join("s1.jsonl")))));
+        cache.save_to(&mut conn).unwrap();
+
+        let prefix = |dir: &Path| format!("{}{}", dir.display(), std::path::MAIN_SEPARATOR);
+        let moved = move_canonical_files(&conn, &prefix(&old_dir), &prefix(&new_dir)).unwrap();
+        assert_eq!(moved, 1);
+        let cache = ScanCache::load_from(&conn, "claude-code").unwrap();
+        assert_eq!(
+            cache.entries[&source].session.file_path,
+            new_dir.join("s1.jsonl")
+        );
+
+        let forgotten = forget_canonical_files(&conn, &[new_dir.join("s1.jsonl")]).unwrap();
+        assert_eq!(forgotten, 1);
+        assert!(ScanCache::load_from(&conn, "claude-code")
+            .unwrap()
+            .entries
+            .is_empty());
+    }
+}
@@ -0,0 +1,301 @@
+//! Timestamp normalization for canonical messages
+//!
+//! Providers write timestamps in different shapes: UTC with `Z`, local time
+//! with an offset, local time without any offset, or Unix milliseconds. Mixed
+//! shapes in one session skew durations, so every canonical timestamp is
+//! rewritten as RFC 3339 UTC. Timestamps that are already UTC are left as
+//! written.
+//!
+//! A session's clock can also jump: the machine's time zone or clock changes
+//! mid-session, or a provider mixes local and UTC times. Messages on the main
+//! thread that go back in time by more than `CLOCK_SKEW_TOLERANCE`, or that
+//! lie in the future, are moved to the previous message's time. Sidechain
+//! messages are exempt, since merged sub-agent transcripts legitimately run
+//! alongside the main thread.
+//!
+//! Converters that append to a session carry the latest main-thread time over
+//! with `TimestampNormalizer::resume`, so appended messages are corrected the
+//! same way a full conversion would correct them.
+//!
+//! Messages whose time was assumed or corrected keep the original under
+//! `timestamp_original` in their provider metadata, with the reason under
+//! `timestamp_correction`.
+
+use crate::providers::canonical::CanonicalMessage;
+use chrono::{DateTime, Duration, Local, NaiveDateTime, SecondsFormat, TimeZone, Utc};
+use serde_json::Value;
+use std::borrow::Cow;
+
+/// How far a main-thread message may go back in time before it counts as a
+/// clock change rather than out-of-order logging
+const CLOCK_SKEW_TOLERANCE: Duration = Duration::minutes(1);
+
+/// How far ahead of now a timestamp may lie before it counts as a clock error
+const FUTURE_TOLERANCE: Duration = Duration::minutes(5);
+
+/// Why a message's timestamp was changed beyond reformatting it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimestampCorrection {
+    /// No offset given; read as the machine's local time
+    AssumedLocal,
+    /// Earlier than the message before it
+    ClockSkew,
+    /// Later than the time of conversion
+    Future,
+}
+
+impl TimestampCorrection {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::AssumedLocal => "assumed_local",
+            Self::ClockSkew => "clock_skew",
+            Self::Future => "future",
+        }
+    }
+}
+
+/// Parse a provider timestamp into UTC
+///
+/// Returns whether the time had to be assumed to be local, lacking an offset.
+pub fn parse_timestamp(raw: &str) -> Option<(DateTime<Utc>, bool)> {
+    let raw = raw.trim();
+    if let Ok(parsed) = DateTime::parse_from_rfc3339(raw) {
+        return Some((parsed.with_timezone(&Utc), false));
+    }
+    if let Ok(millis) = raw.parse::<i64>() {
+        // Seconds are too small to be milliseconds of any recent date
+        let millis = if millis < 100_000_000_000 {
+            millis.checked_mul(1000)?
+        } else {
+            millis
+        };
+        return Utc
+            .timestamp_millis_opt(millis)
+            .single()
+            .map(|dt| (dt, false));
+    }
+    ["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%d %H:%M:%S%.f"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(raw, format).ok())
+        .and_then(|naive| Local.from_local_datetime(&naive).earliest())
+        .map(|local| (local.with_timezone(&Utc), true))
+}
+
+/// Normalizes the timestamps of one session's messages, in order
+#[derive(Debug)]
+pub struct TimestampNormalizer {
+    now: DateTime<Utc>,
+    /// Time of the latest main-thread message
+    last_main: Option<DateTime<Utc>>,
+}
+
+impl Default for TimestampNormalizer {
+    fn default() -> Self {
+        Self::new(Utc::now())
+    }
+}
+
+impl TimestampNormalizer {
+    pub fn new(now: DateTime<Utc>) -> Self {
+        Self {
+            now,
+            last_main: None,
+        }
+    }
+
+    /// Continue a session whose earlier messages were normalized up to
+    /// `last_main`, so appended messages are corrected against them
+    pub fn resume(last_main: Option<DateTime<Utc>>) -> Self {
+        Self {
+            last_main,
+            ..Self::default()
+        }
+    }
+
+    /// Time of the latest main-thread message normalized so far
+    pub fn last_main(&self) -> Option<DateTime<Utc>> {
+        self.last_main
+    }
+
+    /// Normalize `message`, cloning it only if its timestamp changes
+    pub fn normalize<'a>(&mut self, message: &'a CanonicalMessage) -> Cow<'a, CanonicalMessage> {
+        match self.normalized_timestamp(message) {
+            None => Cow::Borrowed(message),
+            Some((timestamp, correction)) => {
+                let mut message = message.clone();
+                apply(&mut message, timestamp, correction);
+                Cow::Owned(message)
+            }
+        }
+    }
+
+    /// Normalize `message` in place
+    pub fn normalize_in_place(&mut self, message: &mut CanonicalMessage) {
+        if let Some((timestamp, correction)) = self.normalized_timestamp(message) {
+            apply(message, timestamp, correction);
+        }
+    }
+
+    /// The timestamp `message` should have, if it differs from its own
+    fn normalized_timestamp(
+        &mut self,
+        message: &CanonicalMessage,
+    ) -> Option<(String, Option<TimestampCorrection>)> {
+        // Unparseable timestamps are left to validation to report
+        let (mut time, assumed_local) = parse_timestamp(&message.timestamp)?;
+        let mut correction = assumed_local.then_some(TimestampCorrection::AssumedLocal);
+
+        if message.is_sidechain != Some(true) {
+            if time > self.now + FUTURE_TOLERANCE {
+                time = self.last_main.unwrap_or(self.now).min(self.now);
+                correction = Some(TimestampCorrection::Future);
+            } else if let Some(last) = self.last_main {
+                if time < last - CLOCK_SKEW_TOLERANCE {
+                    time = last;
+                    correction = Some(TimestampCorrection::ClockSkew);
+                }
+            }
+            self.last_main = Some(self.last_main.map_or(time, |last| last.max(time)));
+        }
+
+        let is_utc = message.timestamp.ends_with('Z') || message.timestamp.ends_with("+00:00");
+        if correction.is_none() && is_utc {
+            return None;
+        }
+        Some((
+            time.to_rfc3339_opts(SecondsFormat::Millis, true),
+            correction,
+        ))
+    }
+}
+
+fn apply(
+    message: &mut CanonicalMessage,
+    timestamp: String,
+    correction: Option<TimestampCorrection>,
+) {
+    let original = std::mem::replace(&mut message.timestamp, timestamp);
+    let Some(correction) = correction else {
+        return;
+    };
+
+    let metadata = message
+        .provider_metadata
+        .get_or_insert_with(|| Value::Object(Default::default()));
+    // Leave non-object metadata from the provider untouched
+    let Some(object) = metadata.as_object_mut() else {
+        return;
+    };
+    object.insert("timestamp_original".to_string(), Value::from(original));
+    object.insert(
+        "timestamp_correction".to_string(),
+        Value::from(correction.as_str()),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn message(timestamp: &str) -> CanonicalMessage {
+        serde_json::from_value(json!({
+            "uuid": "u1", "timestamp": timestamp, "type": "user",
+            "sessionId": "s1", "provider": "codex",
+            "message": {"role": "user", "content": "hi"}
+        }))
+        .unwrap()
+    }
+
+    fn normalizer() -> TimestampNormalizer {
+        TimestampNormalizer::new("2025-06-01T00:00:00Z".parse().unwrap())
+    }
+
+    fn correction(message: &CanonicalMessage) -> Option<&str> {
+        message.provider_metadata.as_ref()?["timestamp_correction"].as_str()
+    }
+
+    #[test]
+    fn test_offsets_and_epochs_become_utc() {
+        let mut normalizer = normalizer();
+
+        let utc = message("2025-01-01T10:00:00.123Z");
+        assert!(matches!(normalizer.normalize(&utc), Cow::Borrowed(_)));
+
+        let offset = message("2025-01-01T12:00:01+02:00");
+        let offset = normalizer.normalize(&offset);
+        assert_eq!(offset.timestamp, "2025-01-01T10:00:01.000Z");
+        assert_eq!(correction(&offset), None);
+
+        let epoch = message("1735725602000");
+        let epoch = normalizer.normalize(&epoch);
+        assert_eq!(epoch.timestamp, "2025-01-01T10:00:02.000Z");
+
+        // Out of range epochs are left for validation to report
+        let overflow = message("-9223372036854775807");
+        assert!(matches!(normalizer.normalize(&overflow), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn test_times_without_offset_are_local() {
+        let mut normalizer = normalizer();
+        let local = message("2025-01-01 10:00:00");
+        let local = normalizer.normalize(&local);
+
+        let expected = Local
+            .from_local_datetime(
+                &NaiveDateTime::parse_from_str("2025-01-01 10:00:00", "%Y-%m-%d %H:%M:%S").unwrap(),
+            )
+            .unwrap()
+            .with_timezone(&Utc)
+            .to_rfc3339_opts(SecondsFormat::Millis, true);
+        assert_eq!(local.timestamp, expected);
+        assert_eq!(correction(&local), Some("assumed_local"));
+        assert_eq!(
+            local.provider_metadata.as_ref().unwrap()["timestamp_original"],
+            "2025-01-01 10:00:00"
+        );
+    }
+
+    #[test]
+    fn test_resumed_sessions_keep_correcting() {
+        let mut normalizer = normalizer();
+        normalizer.normalize(&message("2025-01-01T10:00:00.000Z"));
+
+        // An append converted later picks up where the last conversion stopped
+        let mut resumed = TimestampNormalizer::resume(normalizer.last_main());
+        let appended = message("2025-01-01T09:00:00.000Z");
+        let appended = resumed.normalize(&appended);
+        assert_eq!(appended.timestamp, "2025-01-01T10:00:00.000Z");
+        assert_eq!(correction(&appended), Some("clock_skew"));
+    }
+
+    #[test]
+    fn test_clock_jumps_are_corrected() {
+        let mut messages = vec![
+            message("2025-01-01T10:00:00.000Z"),
+            // Out of order by a few seconds is normal logging jitter
+            message("2025-01-01T09:59:58.000Z"),
+            // The clock went back an hour
+            message("2025-01-01T09:00:05.000Z"),
+            message("2025-01-01T10:00:10.000Z"),
+            message("2030-01-01T00:00:00.000Z"),
+        ];
+        let mut sidechain = message("2025-01-01T09:30:00.000Z");
+        sidechain.is_sidechain = Some(true);
+        messages.insert(2, sidechain);
+
+        let mut normalizer = normalizer();
+        for message in &mut messages {
+            normalizer.normalize_in_place(message);
+        }
+
+        assert_eq!(correction(&messages[1]), None);
+        assert_eq!(messages[2].timestamp, "2025-01-01T09:30:00.000Z");
+        assert_eq!(messages[3].timestamp, "2025-01-01T10:00:00.000Z");
+        assert_eq!(correction(&messages[3]), Some("clock_skew"));
+        assert_eq!(correction(&messages[4]), None);
+        assert_eq!(messages[5].timestamp, "2025-01-01T10:00:10.000Z");
+        assert_eq!(correction(&messages[5]), Some("future"));
+    }
+}
@@ -8,4 +8,10 @@ pub struct WatcherStatus {
     pub pending_uploads: usize,
     pub processing_uploads: usize,
     pub failed_uploads: usize,
+    /// Directory the operating system won't let GuideMode read, so sessions
+    /// there aren't captured until access is granted
+    pub access_denied: Option<String>,
+    /// How to raise the system's file watch limit, when running out of
+    /// watches made this watcher fall back to polling
+    pub watch_limit: Option<String>,
 }
@@ -1,9 +1,16 @@
+use crate::providers::canonical::media::block_from_path;
 use crate::providers::canonical::{
     CanonicalMessage, ContentBlock, ContentValue, MessageContent, MessageType,
+    CANONICAL_SCHEMA_VERSION,
 };
-use super::parser::CopilotEvent;
+use crate::providers::common::{CanonicalWriter, ConvertResult, ConvertedFile};
+use super::parser::{CopilotEvent, CopilotParser, ParsedSession};
 use anyhow::Result;
 use serde_json::Value;
+use std::io;
+use std::path::Path;
+
+const PROVIDER_ID: &str = "github-copilot";
 
 /// Convert a Copilot event to one or more canonical messages
 ///
@@ -33,6 +40,42 @@ pub fn convert_event_to_canonical(
     }
 }
 
+/// Convert a Copilot session file to canonical format
+///
+/// Session files live in `{storage}/session-state/{session_id}.jsonl`.
+pub fn convert_file(session_file: &Path) -> ConvertResult<ConvertedFile> {
+    let storage_path = session_file
+        .parent()
+        .and_then(|p| p.parent())
+        .ok_or("Invalid file path")?;
+
+    let parser = CopilotParser::new(storage_path.to_path_buf());
+    let parsed = parser.parse_session(session_file)?;
+    Ok(convert_parsed_session(session_file, &parsed)?)
+}
+
+/// Write a parsed session's canonical messages out, setting aside the event
+/// lines it skipped
+pub fn convert_parsed_session(
+    session_file: &Path,
+    parsed: &ParsedSession,
+) -> io::Result<ConvertedFile> {
+    let mut writer = CanonicalWriter::for_source(PROVIDER_ID, session_file)?;
+    for skipped in &parsed.skipped_lines {
+        writer.quarantine(skipped.line_number, &skipped.raw_line, &skipped.error);
+    }
+    for message in &parsed.messages {
+        writer.write(message)?;
+    }
+
+    Ok(ConvertedFile::new(
+        PROVIDER_ID,
+        parsed.session_id.clone(),
+        parsed.cwd.clone(),
+        writer,
+    ))
+}
+
 /// Convert session.start event
 fn convert_session_start(
     event: &CopilotEvent,
@@ -40,6 +83,7 @@ fn convert_session_start(
     cwd: Option<&str>,
 ) -> Result<CanonicalMessage> {
     Ok(CanonicalMessage {
+        schema_version: CANONICAL_SCHEMA_VERSION,
         uuid: event.id.clone(),
         timestamp: event.timestamp.clone(),
         message_type: MessageType::Meta,
@@ -77,7 +121,27 @@ fn convert_user_message(
         .unwrap_or("")
         .to_string();
 
+    // Files attached with @-mentions are referenced by path (and hashed when readable)
+    let attachments: Vec<ContentBlock> = event
+        .data
+        .get("attachments")
+        .and_then(|v| v.as_array())
+        .map(|items| items.iter().filter_map(attachment_block).collect())
+        .unwrap_or_default();
+
+    let content = if attachments.is_empty() {
+        ContentValue::Text(text)
+    } else {
+        let mut blocks = Vec::with_capacity(attachments.len() + 1);
+        if !text.is_empty() {
+            blocks.push(ContentBlock::Text { text });
+        }
+        blocks.extend(attachments);
+        ContentValue::Structured(blocks)
+    };
+
     Ok(CanonicalMessage {
+        schema_version: CANONICAL_SCHEMA_VERSION,
         uuid: event.id.clone(),
         timestamp: event.timestamp.clone(),
         message_type: MessageType::User,
@@ -91,7 +155,7 @@ fn convert_user_message(
         user_type: Some("external".to_string()),
         message: MessageContent {
             role: "user".to_string(),
-            content: ContentValue::Text(text),
+            content,
             model: None,
             usage: None,
         },
@@ -104,6 +168,13 @@ fn convert_user_message(
     })
 }
 
+/// Convert a user.message attachment (`{type, path, displayName}`) to a canonical block
+fn attachment_block(attachment: &Value) -> Option<ContentBlock> {
+    let path = attachment.get("path").and_then(|v| v.as_str())?;
+    let name = attachment.get("displayName").and_then(|v| v.as_str());
+    Some(block_from_path(path, None, name))
+}
+
 /// Convert assistant.message event
 fn convert_assistant_message(
     event: &CopilotEvent,
@@ -122,6 +193,7 @@ fn convert_assistant_message(
         .map(String::from);
 
     Ok(CanonicalMessage {
+        schema_version: CANONICAL_SCHEMA_VERSION,
         uuid: id,
         timestamp,
         message_type: MessageType::Assistant,
@@ -182,6 +254,7 @@ fn convert_info_message(
     }
 
     Ok(CanonicalMessage {
+        schema_version: CANONICAL_SCHEMA_VERSION,
         uuid: id,
         timestamp,
         message_type: MessageType::Meta,
@@ -225,6 +298,7 @@ fn convert_abort_message(
     let message_text = format!("Session aborted: {}", reason);
 
     Ok(CanonicalMessage {
+        schema_version: CANONICAL_SCHEMA_VERSION,
         uuid: id,
         timestamp,
         message_type: MessageType::Meta,
@@ -313,6 +387,7 @@ fn convert_tool_use(
         .map(String::from);
 
     Ok(CanonicalMessage {
+        schema_version: CANONICAL_SCHEMA_VERSION,
         uuid: call_id,
         timestamp,
         message_type: MessageType::Assistant,
@@ -394,6 +469,7 @@ fn convert_tool_result(
     };
 
     Ok(CanonicalMessage {
+        schema_version: CANONICAL_SCHEMA_VERSION,
         uuid: format!("{}_result", id),
         timestamp,
         message_type: MessageType::User,  // Tool results are USER messages
@@ -432,6 +508,7 @@ fn convert_unknown_message(
     let event_type = &event.event_type;
 
     Ok(CanonicalMessage {
+        schema_version: CANONICAL_SCHEMA_VERSION,
         uuid: id,
         timestamp,
         message_type: MessageType::Meta,
@@ -513,6 +590,33 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_convert_user_message_with_attachments() {
+        let entry = create_test_event(
+            "user.message",
+            json!({
+                "content": "Explain @src/main.rs",
+                "attachments": [
+                    {"type": "file", "path": "/missing/src/main.rs", "displayName": "main.rs"},
+                    {"type": "selection"}
+                ]
+            }),
+        );
+        let result = convert_event_to_canonical(&entry, "session-1", None).unwrap();
+
+        match &result[0].message.content {
+            ContentValue::Structured(blocks) => {
+                assert_eq!(blocks.len(), 2);
+                assert!(matches!(
+                    &blocks[1],
+                    ContentBlock::Attachment { name: Some(name), path: Some(path), .. }
+                        if name == "main.rs" && path == "/missing/src/main.rs"
+                ));
+            }
+            _ => panic!("Expected structured content"),
+        }
+    }
+
     #[test]
     fn test_convert_assistant_message() {
         let entry = create_test_event("assistant.message", json!({ "content": "Hi there", "messageId": "msg-1", "toolRequests": [] }));
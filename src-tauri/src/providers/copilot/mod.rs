@@ -3,3 +3,5 @@ pub mod parser;
 pub mod scanner;
 pub mod utils;
 pub mod watcher;
+
+pub use converter::convert_file;
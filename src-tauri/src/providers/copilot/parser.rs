@@ -1,6 +1,10 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use shellexpand::tilde;
+use super::utils::attribute_session;
+use crate::providers::canonical::CanonicalMessage;
+use crate::providers::common::quarantine::QuarantinedLine;
+use crate::providers::common::expand_home;
+use crate::providers::common::file_access::ProviderRoot;
 use std::fs;
 use std::path::{Path, PathBuf};
 
@@ -52,8 +56,11 @@ pub struct ParsedSession {
     pub session_start_time: Option<DateTime<Utc>>,
     pub session_end_time: Option<DateTime<Utc>>,
     pub duration_ms: Option<i64>,
-    pub jsonl_content: String,
+    /// Canonical messages converted from the events, timestamps not yet normalized
+    pub messages: Vec<CanonicalMessage>,
     pub cwd: Option<String>,
+    /// Event lines left out because they couldn't be parsed or converted
+    pub skipped_lines: Vec<QuarantinedLine>,
 }
 
 pub struct CopilotParser {
@@ -63,8 +70,8 @@ pub struct CopilotParser {
 
 /// Load the Copilot config.json file to get trusted folders
 pub fn load_copilot_config() -> Result<CopilotConfig, String> {
-    let config_path = tilde("~/.copilot/config.json");
-    let config_path = Path::new(config_path.as_ref());
+    let config_path = expand_home("~/.copilot/config.json");
+    let config_path = config_path.as_path();
 
     if !config_path.exists() {
         return Ok(CopilotConfig {
@@ -81,99 +88,6 @@ pub fn load_copilot_config() -> Result<CopilotConfig, String> {
     Ok(config)
 }
 
-/// Detect project name AND cwd path from events by matching against trusted folders
-/// Returns (project_name, cwd_path)
-/// Scans ALL events and returns on first match with a trusted folder
-pub fn detect_project_and_cwd_from_events(
-    events: &[CopilotEvent],
-    trusted_folders: &[String],
-) -> Option<(String, String)> {
-    // First pass: Look for tool.execution_start events with paths in arguments
-    for event in events {
-        if event.event_type == "tool.execution_start" {
-            if let Some(args) = event.data.get("arguments") {
-                // Try to extract path from arguments
-                if let Some(path) = args.get("path").and_then(|p| p.as_str()) {
-                    // Match against trusted folders
-                    if let Some((project, cwd)) = match_trusted_folder_with_cwd(path, trusted_folders) {
-                        return Some((project, cwd));
-                    }
-                }
-
-                // Also check if command contains a path (for bash commands like cd /path)
-                if let Some(command) = args.get("command").and_then(|c| c.as_str()) {
-                    // Try to extract paths from common commands
-                    for word in command.split_whitespace() {
-                        if word.starts_with('/') || word.starts_with('~') {
-                            if let Some((project, cwd)) = match_trusted_folder_with_cwd(word, trusted_folders) {
-                                return Some((project, cwd));
-                            }
-                        }
-                    }
-                }
-            }
-        }
-    }
-
-    // Second pass: Look for tool.execution_complete events with paths in result output
-    // This catches cases where commands like "pnpm lint" output the working directory
-    for event in events {
-        if event.event_type == "tool.execution_complete" {
-            if let Some(result) = event.data.get("result") {
-                // Extract content from result (can be string or {content: "..."})
-                let result_content = if let Some(content_str) = result.as_str() {
-                    content_str
-                } else if let Some(content) = result.get("content").and_then(|c| c.as_str()) {
-                    content
-                } else {
-                    continue;
-                };
-
-                // Look for paths in the result output
-                // Common patterns: "> package@version command /path/to/project"
-                for line in result_content.lines().take(10) {  // Only check first 10 lines for performance
-                    for word in line.split_whitespace() {
-                        if word.starts_with('/') || word.starts_with('~') {
-                            if let Some((project, cwd)) = match_trusted_folder_with_cwd(word, trusted_folders) {
-                                return Some((project, cwd));
-                            }
-                        }
-                    }
-                }
-            }
-        }
-    }
-
-    None
-}
-
-/// Match a path against trusted folders, returning (folder_name, folder_path) if matched
-fn match_trusted_folder_with_cwd(
-    path: &str,
-    trusted_folders: &[String],
-) -> Option<(String, String)> {
-    for folder in trusted_folders {
-        let expanded_folder = tilde(folder);
-        let folder_path = expanded_folder.as_ref();
-
-        // Check if the path starts with this trusted folder
-        if path.starts_with(folder_path) {
-            // Extract the folder name (last component of the path)
-            if let Some(name) = Path::new(folder_path).file_name().and_then(|n| n.to_str()) {
-                return Some((name.to_string(), folder_path.to_string()));
-            }
-        }
-    }
-
-    None
-}
-
-/// Match a path against trusted folders, returning the folder name if matched
-#[allow(dead_code)]
-fn match_trusted_folder(path: &str, trusted_folders: &[String]) -> Option<String> {
-    match_trusted_folder_with_cwd(path, trusted_folders).map(|(name, _cwd)| name)
-}
-
 impl CopilotParser {
     #[allow(dead_code)]
     pub fn new(storage_path: PathBuf) -> Self {
@@ -183,19 +97,26 @@ impl CopilotParser {
     #[allow(dead_code)]
     pub fn parse_session(&self, session_file_path: &Path) -> Result<ParsedSession, String> {
         // Read the JSONL file
-        let content = fs::read_to_string(session_file_path)
+        let content = ProviderRoot::new("github-copilot", &self.storage_path)
+            .read_to_string(session_file_path)
             .map_err(|e| format!("Failed to read session file: {}", e))?;
 
-        // Parse JSONL - one event per line
+        // Parse JSONL - one event per line; unparseable lines are skipped
         let mut events = Vec::new();
-        for line in content.lines() {
+        let mut event_lines = Vec::new();
+        let mut skipped_lines = Vec::new();
+        for (index, line) in content.lines().enumerate() {
             if line.trim().is_empty() {
                 continue;
             }
 
-            let event: CopilotEvent = serde_json::from_str(line)
-                .map_err(|e| format!("Failed to parse event line: {}", e))?;
-            events.push(event);
+            match serde_json::from_str::<CopilotEvent>(line) {
+                Ok(event) => {
+                    events.push(event);
+                    event_lines.push((index + 1, line));
+                }
+                Err(e) => skipped_lines.push(QuarantinedLine::new(index + 1, line, e)),
+            }
         }
 
         if events.is_empty() {
@@ -229,15 +150,18 @@ impl CopilotParser {
             _ => None,
         };
 
-        // Detect project and cwd from events
-        let (project_name, cwd) = match load_copilot_config() {
-            Ok(config) if !config.trusted_folders.is_empty() => {
-                detect_project_and_cwd_from_events(&events, &config.trusted_folders)
-                    .map(|(name, cwd)| (name, Some(cwd)))
-                    .unwrap_or_else(|| ("copilot-sessions".to_string(), None))
-            }
-            _ => ("copilot-sessions".to_string(), None),
-        };
+        // Attribute the session to a project using its workspaces, trusted folders
+        // and any user-defined path mappings
+        let trusted_folders = load_copilot_config()
+            .map(|config| config.trusted_folders)
+            .unwrap_or_default();
+        let mappings = crate::config::load_provider_config("github-copilot")
+            .map(|config| config.project_mappings)
+            .unwrap_or_default();
+
+        let (project_name, cwd) = attribute_session(&events, &trusted_folders, &mappings)
+            .map(|(name, cwd)| (name, Some(cwd)))
+            .unwrap_or_else(|| ("copilot-sessions".to_string(), None));
 
         // Extract session ID from filename (UUID)
         let session_id = session_file_path
@@ -250,31 +174,25 @@ impl CopilotParser {
         use crate::providers::copilot::converter::convert_event_to_canonical;
 
         let mut canonical_messages = Vec::new();
-        for event in &events {
+        for (event, (line_number, line)) in events.iter().zip(&event_lines) {
             match convert_event_to_canonical(event, &session_id, cwd.as_deref()) {
                 Ok(mut messages) => canonical_messages.append(&mut messages),
                 Err(e) => {
-                    // Log error but continue processing other events
-                    eprintln!("Warning: Failed to convert event: {}", e);
+                    // Skip the event but continue processing other events
+                    skipped_lines.push(QuarantinedLine::new(*line_number, line, e));
                 }
             }
         }
 
-        // Convert canonical messages to JSONL
-        let jsonl_content = canonical_messages
-            .iter()
-            .filter_map(|msg| serde_json::to_string(msg).ok())
-            .collect::<Vec<_>>()
-            .join("\n");
-
         Ok(ParsedSession {
             session_id,
             project_name,
             session_start_time,
             session_end_time,
             duration_ms,
-            jsonl_content,
+            messages: canonical_messages,
             cwd,
+            skipped_lines,
         })
     }
 
@@ -345,16 +263,17 @@ mod tests {
 
         assert_eq!(result.session_id, "test-session-123");
         assert_eq!(result.project_name, "copilot-sessions");
-        assert!(!result.jsonl_content.is_empty());
         assert!(result.session_start_time.is_some());
         assert!(result.session_end_time.is_some());
 
-        // Verify it's canonical format (should have multiple lines, each a CanonicalMessage)
-        let lines: Vec<&str> = result.jsonl_content.lines().collect();
-        assert!(lines.len() >= 3, "Expected at least 3 canonical messages");
+        // Verify it's canonical format (should have multiple CanonicalMessages)
+        assert!(
+            result.messages.len() >= 3,
+            "Expected at least 3 canonical messages"
+        );
 
-        // Parse first line to verify it's canonical format (uses camelCase)
-        let first_msg: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+        // Serialize the first message to verify it's canonical format (uses camelCase)
+        let first_msg = serde_json::to_value(&result.messages[0]).unwrap();
         assert!(first_msg.get("uuid").is_some(), "Should have uuid field");
         assert!(first_msg.get("timestamp").is_some(), "Should have timestamp field");
         assert!(first_msg.get("type").is_some(), "Should have type field"); // message_type -> type
@@ -1,7 +1,9 @@
 //! GitHub Copilot session scanner - discovers and parses Copilot sessions from ~/.copilot/session-state/
 
-use crate::logging::{log_info, log_warn};
-use crate::providers::common::SessionInfo;
+use crate::events::CancellationToken;
+use crate::ignore_list::IgnoreList;
+use crate::providers::common::scan_cache::scan_project_dirs;
+use crate::providers::common::{ScanReport, SessionInfo};
 use std::fs;
 use std::path::Path;
 
@@ -9,66 +11,58 @@ use std::path::Path;
 pub fn scan_sessions_filtered(
     base_path: &Path,
     selected_projects: Option<&[String]>,
-) -> Result<Vec<SessionInfo>, String> {
+    cancel: &CancellationToken,
+) -> Result<ScanReport, String> {
     // Copilot uses ~/.copilot/session-state/{uuid}.jsonl
     let session_dir = base_path.join("session-state");
     if !session_dir.exists() {
-        return Ok(Vec::new());
+        return Ok(ScanReport::default());
     }
-
-    let mut sessions = Vec::new();
-    let entries = fs::read_dir(&session_dir)
+    // Fail the scan on an unreadable directory rather than finding nothing
+    fs::read_dir(&session_dir)
         .map_err(|e| format!("Failed to read Copilot session directory: {}", e))?;
 
-    for entry in entries.flatten() {
-        let file_path = entry.path();
-
-        // Only process JSONL session files (end with ".jsonl")
-        if file_path.extension().and_then(|ext| ext.to_str()) == Some("jsonl") {
-            if let Some(file_name) = file_path.file_name().and_then(|n| n.to_str()) {
-                // Skip hidden files
-                if !file_name.starts_with('.') {
-                    match parse_copilot_session(&file_path, selected_projects) {
-                        Ok(Some(session_info)) => {
-                            sessions.push(session_info);
-                        }
-                        Ok(None) => {
-                            // Session filtered out - skipped
-                        }
-                        Err(e) => {
-                            if let Err(log_err) = log_warn(
-                                "github-copilot",
-                                &format!(
-                                    "Failed to parse Copilot session {}: {}",
-                                    file_path.display(),
-                                    e
-                                ),
-                            ) {
-                                eprintln!("Logging error: {}", log_err);
-                            }
-                        }
-                    }
-                }
-            }
-        }
-    }
-
-    if let Err(e) = log_info(
+    // Sessions aren't grouped by project on disk, so the directory is one group
+    let mut report = scan_project_dirs(
         "github-copilot",
-        &format!("📊 Found {} GitHub Copilot sessions", sessions.len()),
-    ) {
-        eprintln!("Logging error: {}", e);
+        vec![("session-state".to_string(), session_dir)],
+        cancel,
+        |dir| {
+            fs::read_dir(dir)
+                .map(|entries| {
+                    entries
+                        .flatten()
+                        .map(|entry| entry.path())
+                        // Only process JSONL session files, skipping hidden files
+                        .filter(|path| {
+                            path.extension().and_then(|ext| ext.to_str()) == Some("jsonl")
+                        })
+                        .filter(|path| {
+                            path.file_name()
+                                .and_then(|n| n.to_str())
+                                .is_some_and(|file_name| !file_name.starts_with('.'))
+                        })
+                        .collect()
+                })
+                .unwrap_or_default()
+        },
+        |file_path, _| parse_copilot_session(file_path, selected_projects),
+    );
+
+    // Cached sessions skip parsing, and with it the project filter
+    if let Some(selected) = selected_projects {
+        report.retain(|session| selected.contains(&session.project_name));
     }
 
-    Ok(sessions)
+    Ok(report)
 }
 
 fn parse_copilot_session(
     file_path: &Path,
     selected_projects: Option<&[String]>,
 ) -> Result<Option<SessionInfo>, String> {
+    use super::converter::convert_parsed_session;
     use super::parser::CopilotParser;
-    use super::super::common::get_canonical_path;
 
     // Use CopilotParser to parse the new JSONL event format
     let storage_path = file_path
@@ -85,12 +79,14 @@ fn parse_copilot_session(
             return Ok(None); // Skip this session
         }
     }
+    if IgnoreList::current().is_ignored(&parsed.project_name, parsed.cwd.as_deref()) {
+        return Ok(None);
+    }
 
     // Write canonical format to project-organized path
-    let cache_path = get_canonical_path("github-copilot", parsed.cwd.as_deref(), &parsed.session_id)
-        .map_err(|e| format!("Failed to get canonical path: {}", e))?;
-
-    fs::write(&cache_path, &parsed.jsonl_content)
+    let cache_path = convert_parsed_session(file_path, &parsed)
+        .map_err(|e| format!("Failed to convert to canonical format: {}", e))?
+        .persist()
         .map_err(|e| format!("Failed to write canonical cache file: {}", e))?;
 
     // Get file size of canonical cache file
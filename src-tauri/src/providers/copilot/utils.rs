@@ -1,13 +1,13 @@
 use crate::config::ProjectInfo;
-use super::parser::load_copilot_config;
+use super::parser::{load_copilot_config, CopilotEvent};
 use chrono::{DateTime, Utc};
-use shellexpand::tilde;
+use crate::providers::common::expand_home;
+use std::collections::HashMap;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 pub fn scan_projects(home_directory: &str) -> Result<Vec<ProjectInfo>, String> {
-    let expanded = tilde(home_directory);
-    let primary_base = PathBuf::from(expanded.into_owned());
+    let primary_base = expand_home(home_directory);
 
     // Build candidate paths with fallbacks for cross-platform support
     let mut base_candidates = Vec::new();
@@ -43,8 +43,7 @@ pub fn scan_projects(home_directory: &str) -> Result<Vec<ProjectInfo>, String> {
     // If we have trusted folders, use them as projects
     if !copilot_config.trusted_folders.is_empty() {
         for folder in &copilot_config.trusted_folders {
-            let expanded_folder = tilde(folder);
-            let folder_path = PathBuf::from(expanded_folder.into_owned());
+            let folder_path = expand_home(folder);
 
             // Get the folder name (last component of the path)
             if let Some(name) = folder_path.file_name().and_then(|n| n.to_str()) {
@@ -112,3 +111,280 @@ pub fn scan_projects(home_directory: &str) -> Result<Vec<ProjectInfo>, String> {
 
     Ok(projects)
 }
+
+/// A directory that Copilot sessions can be attributed to
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProjectRoot {
+    pub path: PathBuf,
+    pub name: String,
+    /// Defined by the user in ProviderConfig.project_mappings
+    pub explicit: bool,
+}
+
+/// Build candidate project roots from the session header, trusted folders and user mappings
+///
+/// A mapping for the same path as a workspace or trusted folder overrides its name.
+pub fn build_project_roots(
+    workspaces: &[String],
+    trusted_folders: &[String],
+    mappings: &HashMap<String, String>,
+) -> Vec<ProjectRoot> {
+    let mut roots: Vec<ProjectRoot> = Vec::new();
+
+    for (path, name) in mappings {
+        roots.push(ProjectRoot {
+            path: expand_path(path),
+            name: name.clone(),
+            explicit: true,
+        });
+    }
+
+    for folder in workspaces.iter().chain(trusted_folders) {
+        let path = expand_path(folder);
+        if roots.iter().any(|root| root.path == path) {
+            continue;
+        }
+
+        if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
+            roots.push(ProjectRoot {
+                name: name.to_string(),
+                path,
+                explicit: false,
+            });
+        }
+    }
+
+    roots
+}
+
+/// Find the root a path belongs to
+///
+/// Matches whole path components, so `/work/app-admin` is not inside `/work/app`.
+/// User mappings win over other roots; otherwise the most specific root wins,
+/// so a session started in a parent directory is attributed to the nested workspace.
+pub fn find_project_root<'a>(path: &Path, roots: &'a [ProjectRoot]) -> Option<&'a ProjectRoot> {
+    roots
+        .iter()
+        .filter(|root| path.starts_with(&root.path))
+        .max_by_key(|root| (root.explicit, root.path.components().count()))
+}
+
+/// Workspace folders declared in the session.start header
+pub fn header_workspaces(events: &[CopilotEvent]) -> Vec<String> {
+    let Some(data) = session_start_data(events) else {
+        return Vec::new();
+    };
+
+    ["workspaceFolders", "workspaces"]
+        .iter()
+        .filter_map(|key| data.get(*key).and_then(|v| v.as_array()))
+        .flatten()
+        .filter_map(|workspace| {
+            workspace
+                .as_str()
+                .or_else(|| workspace.get("path").and_then(|p| p.as_str()))
+                .or_else(|| workspace.get("uri").and_then(|u| u.as_str()))
+        })
+        .map(|path| path.strip_prefix("file://").unwrap_or(path).to_string())
+        .collect()
+}
+
+/// Directory the session was started from, if the header records it
+pub fn header_cwd(events: &[CopilotEvent]) -> Option<String> {
+    let data = session_start_data(events)?;
+
+    data.get("cwd")
+        .or_else(|| data.get("workingDirectory"))
+        .or_else(|| data.get("context").and_then(|c| c.get("cwd")))
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string())
+}
+
+/// Attribute a session to a project, returning (project_name, cwd)
+///
+/// Paths touched by tools are the strongest signal and are checked first. When
+/// no tool path matches, the session's starting directory is used, and finally
+/// a single declared workspace.
+pub fn attribute_session(
+    events: &[CopilotEvent],
+    trusted_folders: &[String],
+    mappings: &HashMap<String, String>,
+) -> Option<(String, String)> {
+    let workspaces = header_workspaces(events);
+    let roots = build_project_roots(&workspaces, trusted_folders, mappings);
+    if roots.is_empty() {
+        return None;
+    }
+
+    let start_dir = header_cwd(events).map(|cwd| expand_path(&cwd));
+    let to_result = |root: &ProjectRoot| (root.name.clone(), root.path.to_string_lossy().to_string());
+
+    for path in observed_paths(events) {
+        let path = expand_path(&path);
+        // Relative tool paths are resolved against the starting directory
+        let path = match &start_dir {
+            Some(start) if path.is_relative() => start.join(path),
+            _ => path,
+        };
+
+        if let Some(root) = find_project_root(&path, &roots) {
+            return Some(to_result(root));
+        }
+    }
+
+    if let Some(root) = start_dir.and_then(|start| find_project_root(&start, &roots).cloned()) {
+        return Some(to_result(&root));
+    }
+
+    match workspaces.as_slice() {
+        [only] => find_project_root(&expand_path(only), &roots).map(to_result),
+        _ => None,
+    }
+}
+
+/// Paths mentioned by tool calls, in order of reliability
+///
+/// Tool arguments (path, then path-like words in commands) come before paths
+/// printed in the first lines of tool output.
+fn observed_paths(events: &[CopilotEvent]) -> Vec<String> {
+    let mut paths = Vec::new();
+
+    for event in events {
+        if event.event_type != "tool.execution_start" {
+            continue;
+        }
+        let Some(args) = event.data.get("arguments") else {
+            continue;
+        };
+
+        if let Some(path) = args.get("path").and_then(|p| p.as_str()) {
+            paths.push(path.to_string());
+        }
+
+        // Also check if command contains a path (for bash commands like cd /path)
+        if let Some(command) = args.get("command").and_then(|c| c.as_str()) {
+            paths.extend(absolute_words(command));
+        }
+    }
+
+    // Catches cases where commands like "pnpm lint" output the working directory
+    for event in events {
+        if event.event_type != "tool.execution_complete" {
+            continue;
+        }
+        let Some(result) = event.data.get("result") else {
+            continue;
+        };
+
+        // Result can be a string or {content: "..."}
+        let content = result
+            .as_str()
+            .or_else(|| result.get("content").and_then(|c| c.as_str()));
+
+        if let Some(content) = content {
+            // Only check first 10 lines for performance
+            for line in content.lines().take(10) {
+                paths.extend(absolute_words(line));
+            }
+        }
+    }
+
+    paths
+}
+
+fn absolute_words(text: &str) -> Vec<String> {
+    text.split_whitespace()
+        .filter(|word| word.starts_with('/') || word.starts_with('~'))
+        .map(|word| word.to_string())
+        .collect()
+}
+
+fn session_start_data(events: &[CopilotEvent]) -> Option<&serde_json::Value> {
+    events
+        .iter()
+        .find(|e| e.event_type == "session.start")
+        .map(|e| &e.data)
+}
+
+fn expand_path(path: &str) -> PathBuf {
+    expand_home(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(event_type: &str, data: serde_json::Value) -> CopilotEvent {
+        CopilotEvent {
+            event_type: event_type.to_string(),
+            data,
+            id: "event".to_string(),
+            timestamp: "2025-01-01T10:00:00.000Z".to_string(),
+            parent_id: None,
+        }
+    }
+
+    fn tool_path(path: &str) -> CopilotEvent {
+        event(
+            "tool.execution_start",
+            serde_json::json!({"toolName": "view", "arguments": {"path": path}}),
+        )
+    }
+
+    #[test]
+    fn test_shared_prefix_matches_whole_components() {
+        let trusted = vec!["/work/app".to_string(), "/work/app-admin".to_string()];
+        let events = vec![tool_path("/work/app-admin/src/main.rs")];
+
+        let (name, cwd) = attribute_session(&events, &trusted, &HashMap::new()).unwrap();
+        assert_eq!(name, "app-admin");
+        assert_eq!(cwd, "/work/app-admin");
+    }
+
+    #[test]
+    fn test_parent_directory_session_uses_nested_workspace() {
+        let trusted = vec!["/work".to_string()];
+        let events = vec![
+            event(
+                "session.start",
+                serde_json::json!({
+                    "cwd": "/work",
+                    "workspaceFolders": ["/work/api", {"uri": "file:///work/web"}]
+                }),
+            ),
+            tool_path("web/src/index.ts"),
+        ];
+
+        let (name, cwd) = attribute_session(&events, &trusted, &HashMap::new()).unwrap();
+        assert_eq!(name, "web");
+        assert_eq!(cwd, "/work/web");
+    }
+
+    #[test]
+    fn test_falls_back_to_header_cwd() {
+        let events = vec![event(
+            "session.start",
+            serde_json::json!({"cwd": "/work/api/src", "workspaces": ["/work/api", "/work/web"]}),
+        )];
+
+        let (name, _) = attribute_session(&events, &[], &HashMap::new()).unwrap();
+        assert_eq!(name, "api");
+    }
+
+    #[test]
+    fn test_user_mapping_overrides_attribution() {
+        let trusted = vec!["/work/mono/packages/ui".to_string()];
+        let mappings = HashMap::from([("/work/mono".to_string(), "monorepo".to_string())]);
+        let events = vec![tool_path("/work/mono/packages/ui/button.tsx")];
+
+        let (name, cwd) = attribute_session(&events, &trusted, &mappings).unwrap();
+        assert_eq!(name, "monorepo");
+        assert_eq!(cwd, "/work/mono");
+    }
+
+    #[test]
+    fn test_no_roots_returns_none() {
+        let events = vec![tool_path("/work/app/main.rs")];
+        assert!(attribute_session(&events, &[], &HashMap::new()).is_none());
+    }
+}
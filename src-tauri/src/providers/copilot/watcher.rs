@@ -1,14 +1,14 @@
 use crate::config::load_provider_config;
 use crate::events::{EventBus, SessionEventPayload};
+use crate::ignore_list::IgnoreList;
 use crate::logging::{log_error, log_info};
 use crate::providers::common::{
-    extract_session_id_from_filename, get_canonical_path, get_file_size, has_extension,
-    should_skip_file, SessionStateManager, WatcherStatus, EVENT_TIMEOUT, FILE_WATCH_POLL_INTERVAL,
-    MIN_SIZE_CHANGE_BYTES,
+    access, expand_home, extract_session_id_from_filename, file_watcher, get_file_size,
+    has_extension, should_skip_file, FileWatcher, SessionStateManager,
+    WatcherStatus, EVENT_TIMEOUT, FILE_WATCH_POLL_INTERVAL, MIN_SIZE_CHANGE_BYTES,
 };
 use crate::upload_queue::UploadQueue;
-use notify::{Config, Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
-use shellexpand::tilde;
+use notify::{Config, Event, EventKind, RecursiveMode};
 use std::path::{Path, PathBuf};
 use std::sync::{mpsc, Arc, Mutex};
 use std::thread;
@@ -25,7 +25,7 @@ pub struct FileChangeEvent {
 
 #[derive(Debug)]
 pub struct CopilotWatcher {
-    _watcher: RecommendedWatcher,
+    _watcher: FileWatcher,
     _thread_handle: thread::JoinHandle<()>,
     upload_queue: Arc<UploadQueue>,
     is_running: Arc<Mutex<bool>>,
@@ -50,8 +50,8 @@ impl CopilotWatcher {
         }
 
         let home_directory = config.home_directory;
-        let expanded_home = tilde(&home_directory);
-        let base_path = Path::new(expanded_home.as_ref());
+        let expanded_home = expand_home(&home_directory);
+        let base_path = expanded_home.as_path();
 
         if !base_path.exists() {
             return Err(format!(
@@ -84,7 +84,9 @@ impl CopilotWatcher {
         let (tx, rx) = mpsc::channel();
 
         // Create the file watcher
-        let mut watcher = RecommendedWatcher::new(
+        let watcher = FileWatcher::new(
+            PROVIDER_ID,
+            base_path,
             tx,
             Config::default().with_poll_interval(FILE_WATCH_POLL_INTERVAL),
         )?;
@@ -106,10 +108,13 @@ impl CopilotWatcher {
         let upload_queue_clone = Arc::clone(&upload_queue);
         let event_bus_clone = event_bus.clone();
 
+        let file_watcher = watcher.clone();
+
         // Start background thread to handle file events
         let thread_handle = thread::spawn(move || {
             Self::file_event_processor(
                 rx,
+                file_watcher,
                 session_dir,
                 upload_queue_clone,
                 event_bus_clone,
@@ -127,6 +132,7 @@ impl CopilotWatcher {
 
     fn file_event_processor(
         rx: mpsc::Receiver<Result<Event, notify::Error>>,
+        file_watcher: FileWatcher,
         session_dir: PathBuf,
         _upload_queue: Arc<UploadQueue>,
         event_bus: EventBus,
@@ -213,10 +219,12 @@ impl CopilotWatcher {
                     }
                 }
                 Ok(Err(error)) => {
-                    if let Err(e) =
-                        log_error(PROVIDER_ID, &format!("File watcher error: {:?}", error))
-                    {
-                        eprintln!("Logging error: {}", e);
+                    if !file_watcher.handle_error(&error) {
+                        if let Err(e) =
+                            log_error(PROVIDER_ID, &format!("File watcher error: {:?}", error))
+                        {
+                            eprintln!("Logging error: {}", e);
+                        }
                     }
                 }
                 Err(mpsc::RecvTimeoutError::Timeout) => {
@@ -236,12 +244,13 @@ impl CopilotWatcher {
         }
     }
 
+    /// Convert a Copilot session to canonical JSONL
+    /// Returns (canonical_path, project_name), or None when the project is ignored
     fn convert_to_canonical_file(
         copilot_file: &Path,
-        session_id: &str,
-    ) -> Result<PathBuf, Box<dyn std::error::Error + Send + Sync>> {
+    ) -> Result<Option<(PathBuf, String)>, Box<dyn std::error::Error + Send + Sync>> {
+        use super::converter::convert_parsed_session;
         use super::parser::CopilotParser;
-        use std::fs;
 
         // Parse and convert to canonical format using the parser
         let storage_path = copilot_file
@@ -251,15 +260,15 @@ impl CopilotWatcher {
 
         let parser = CopilotParser::new(storage_path.to_path_buf());
         let parsed = parser.parse_session(copilot_file)?;
+        if IgnoreList::current().is_ignored(&parsed.project_name, parsed.cwd.as_deref()) {
+            return Ok(None);
+        }
 
-        // Get project-organized canonical path using CWD from parsed session
-        // Uses ~/.guidemode/sessions/{provider}/{project}/{session_id}.jsonl
-        let canonical_path = get_canonical_path(PROVIDER_ID, parsed.cwd.as_deref(), session_id)?;
-
-        // Write canonical JSONL to project-organized path
-        fs::write(&canonical_path, parsed.jsonl_content)?;
+        // Move to the project-organized path, using the CWD from the parsed session
+        // ~/.guidemode/sessions/{provider}/{project}/{session_id}.jsonl
+        let canonical_path = convert_parsed_session(copilot_file, &parsed)?.persist()?;
 
-        Ok(canonical_path)
+        Ok(Some((canonical_path, parsed.project_name)))
     }
 
     fn process_file_event(event: &Event, session_dir: &Path) -> Option<FileChangeEvent> {
@@ -286,26 +295,25 @@ impl CopilotWatcher {
                     let session_id = extract_session_id_from_filename(path);
 
                     // Convert to canonical format and get cache path
-                    let canonical_path = match Self::convert_to_canonical_file(path, &session_id) {
-                        Ok(cache_path) => cache_path,
-                        Err(e) => {
-                            if let Err(log_err) = log_error(
-                                PROVIDER_ID,
-                                &format!("Failed to convert to canonical format: {}", e),
-                            ) {
-                                eprintln!("Logging error: {}", log_err);
+                    let (canonical_path, project_name) =
+                        match Self::convert_to_canonical_file(path) {
+                            Ok(Some(converted)) => converted,
+                            // Ignored projects never reach the canonical cache
+                            Ok(None) => continue,
+                            Err(e) => {
+                                if let Err(log_err) = log_error(
+                                    PROVIDER_ID,
+                                    &format!("Failed to convert to canonical format: {}", e),
+                                ) {
+                                    eprintln!("Logging error: {}", log_err);
+                                }
+                                continue;
                             }
-                            continue;
-                        }
-                    };
+                        };
 
                     // Get file size of canonical cache file
                     let file_size = get_file_size(&canonical_path).unwrap_or(0);
 
-                    // Use "copilot-sessions" as default project name
-                    // TODO: Could extract from file content if needed
-                    let project_name = "copilot-sessions".to_string();
-
                     return Some(FileChangeEvent {
                         path: canonical_path, // Use canonical cache path, not source path
                         project_name,
@@ -344,6 +352,8 @@ impl CopilotWatcher {
             pending_uploads: upload_status.pending,
             processing_uploads: upload_status.processing,
             failed_uploads: upload_status.failed,
+            access_denied: access::denied_path(PROVIDER_ID),
+            watch_limit: file_watcher::watch_limit_hint(PROVIDER_ID),
         }
     }
 }
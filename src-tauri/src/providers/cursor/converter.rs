@@ -1,51 +1,74 @@
 /// Converter from Cursor protobuf format to canonical JSONL
 use super::protobuf::{CursorBlob, ContentBlock as CursorContentBlock, CursorMessage};
+use super::timing::{SessionTiming, TimestampMethod};
 use crate::providers::canonical::{
     CanonicalMessage, ContentBlock, ContentValue, MessageContent, MessageType,
+    CANONICAL_SCHEMA_VERSION,
 };
 use crate::providers::canonical::converter::ToCanonical;
+use crate::providers::canonical::media::{block_from_base64, block_from_url};
 use anyhow::Result;
 use chrono::{DateTime, Utc};
 use serde_json::{json, Value};
 
-/// Wrapper for CursorMessage with session metadata for timestamp calculation
+/// Wrapper for CursorMessage with its resolved timestamp
 ///
-/// Since Cursor doesn't store per-message timestamps, we calculate them based on:
-/// - Session `createdAt` timestamp (from meta table)
-/// - Message index/ordering (from database rowid)
-/// - 1 second increment per message for realistic timing
+/// Cursor rarely stores per-message timestamps, so the timestamp comes from
+/// `SessionTiming::resolve`: a real timestamp from the blob when available,
+/// otherwise an estimate from the session's created/updated times and the
+/// message's position. The method used is recorded in `provider_metadata`.
 pub struct CursorMessageWithRaw<'a> {
     pub message: &'a CursorMessage,
     pub raw_data: &'a [u8],
-    pub session_created_at: i64, // Unix timestamp in milliseconds
-    pub message_index: usize,     // Index in the session for timestamp calculation
+    pub timestamp_ms: i64, // Unix timestamp in milliseconds
+    pub timestamp_method: TimestampMethod,
 }
 
 impl<'a> CursorMessageWithRaw<'a> {
     pub fn new(
         message: &'a CursorMessage,
         raw_data: &'a [u8],
-        session_created_at: i64,
+        timing: &SessionTiming,
         message_index: usize,
     ) -> Self {
+        let (timestamp_ms, timestamp_method) = timing.resolve(message, message_index);
+
         Self {
             message,
             raw_data,
-            session_created_at,
-            message_index,
+            timestamp_ms,
+            timestamp_method,
         }
     }
 
-    /// Calculate timestamp for this message based on session creation time and message index
+    /// Format the resolved timestamp for canonical messages
     fn calculate_timestamp(&self) -> String {
-        let base_timestamp = DateTime::from_timestamp_millis(self.session_created_at)
-            .unwrap_or_else(Utc::now);
-        let message_timestamp = base_timestamp + chrono::Duration::seconds(self.message_index as i64);
-        message_timestamp.to_rfc3339_opts(chrono::SecondsFormat::Millis, true)
+        DateTime::from_timestamp_millis(self.timestamp_ms)
+            .unwrap_or_else(Utc::now)
+            .to_rfc3339_opts(chrono::SecondsFormat::Millis, true)
+    }
+
+    /// Record how the timestamp was determined in provider_metadata
+    fn tag_timestamp_method(&self, mut messages: Vec<CanonicalMessage>) -> Vec<CanonicalMessage> {
+        for msg in &mut messages {
+            let metadata = msg.provider_metadata.get_or_insert_with(|| json!({}));
+            if let Some(obj) = metadata.as_object_mut() {
+                obj.insert(
+                    "timestamp_method".to_string(),
+                    json!(self.timestamp_method.as_str()),
+                );
+            }
+        }
+        messages
     }
 
     /// Convert to multiple canonical messages (split by content block type)
     pub fn to_canonical_split(&self) -> Result<Vec<CanonicalMessage>> {
+        self.split_messages()
+            .map(|messages| self.tag_timestamp_method(messages))
+    }
+
+    fn split_messages(&self) -> Result<Vec<CanonicalMessage>> {
         match self.message {
             CursorMessage::Protobuf(blob) => {
                 if blob.is_complex() {
@@ -137,6 +160,7 @@ fn split_json_message(
     let mut messages = Vec::new();
     let mut text_blocks = Vec::new();
     let mut thinking_blocks = Vec::new();
+    let mut media_blocks = Vec::new();
 
     // Determine message type and role
     let base_message_type = match json_msg.role.as_str() {
@@ -222,6 +246,9 @@ fn split_json_message(
                     thinking: thinking.clone(),
                 });
             }
+            ContentBlock::Image { .. } | ContentBlock::Attachment { .. } => {
+                media_blocks.push(block.clone());
+            }
         }
     }
 
@@ -230,7 +257,7 @@ fn split_json_message(
         messages.push(create_json_text_message(
             json_msg,
             timestamp,
-            base_message_type,
+            base_message_type.clone(),
             &base_role,
             text_blocks.join("\n"),
         )?);
@@ -247,6 +274,17 @@ fn split_json_message(
         )?);
     }
 
+    // Flush images and attachments
+    if !media_blocks.is_empty() {
+        messages.push(create_json_structured_message(
+            json_msg,
+            timestamp,
+            base_message_type,
+            &base_role,
+            media_blocks,
+        )?);
+    }
+
     Ok(messages)
 }
 
@@ -261,6 +299,7 @@ fn create_json_text_message(
     let unique_uuid = format!("{}-{}", json_msg.id, uuid::Uuid::new_v4());
 
     Ok(CanonicalMessage {
+        schema_version: CANONICAL_SCHEMA_VERSION,
         uuid: unique_uuid,
         timestamp: timestamp.to_string(),
         message_type,
@@ -307,6 +346,7 @@ fn create_json_structured_message(
     let unique_uuid = format!("{}-{}", json_msg.id, uuid::Uuid::new_v4());
 
     Ok(CanonicalMessage {
+        schema_version: CANONICAL_SCHEMA_VERSION,
         uuid: unique_uuid,
         timestamp: timestamp.to_string(),
         message_type,
@@ -487,6 +527,7 @@ fn create_text_message(
     let unique_uuid = format!("{}-{}", base_uuid, uuid::Uuid::new_v4());
 
     Ok(CanonicalMessage {
+        schema_version: CANONICAL_SCHEMA_VERSION,
         uuid: unique_uuid,
         timestamp: timestamp.to_string(),
         message_type,
@@ -532,6 +573,7 @@ fn create_structured_message(
     let unique_uuid = format!("{}-{}", base_uuid, uuid::Uuid::new_v4());
 
     Ok(CanonicalMessage {
+        schema_version: CANONICAL_SCHEMA_VERSION,
         uuid: unique_uuid,
         timestamp: timestamp.to_string(),
         message_type,
@@ -613,6 +655,7 @@ impl CursorBlob {
             .map(|_| "default".to_string());
 
         Ok(Some(CanonicalMessage {
+            schema_version: CANONICAL_SCHEMA_VERSION,
             uuid: self.uuid.clone().unwrap_or_else(|| uuid::Uuid::new_v4().to_string()),
             timestamp: timestamp.to_string(),
             message_type,
@@ -895,6 +938,9 @@ fn parse_content_array(content: &serde_json::Value, role: &str, message_id: &str
                         });
                     }
                 }
+                "image" | "file" | "document" => {
+                    blocks.push(media_block(block));
+                }
                 _ => {
                     blocks.push(ContentBlock::Text {
                         text: format!("[Unknown block type: {}]", block_type),
@@ -918,6 +964,47 @@ fn parse_content_array(content: &serde_json::Value, role: &str, message_id: &str
     }
 }
 
+/// Convert an image or file block to a hashed canonical block
+///
+/// Handles both Anthropic blocks (`source: {type, media_type, data | url}`)
+/// and AI SDK parts (`image` / `data` holding base64 or a URL, plus `mimeType`).
+fn media_block(block: &serde_json::Value) -> ContentBlock {
+    let name = block
+        .get("filename")
+        .or_else(|| block.get("title"))
+        .and_then(|n| n.as_str());
+
+    if let Some(source) = block.get("source") {
+        let media_type = source.get("media_type").and_then(|m| m.as_str());
+        if let Some(url) = source.get("url").and_then(|u| u.as_str()) {
+            return block_from_url(url, media_type, name);
+        }
+        let data = source.get("data").and_then(|d| d.as_str()).unwrap_or_default();
+        return block_from_base64(media_type.unwrap_or("application/octet-stream"), data, name, None);
+    }
+
+    let media_type = block
+        .get("mimeType")
+        .or_else(|| block.get("mediaType"))
+        .and_then(|m| m.as_str());
+    let data = block
+        .get("image")
+        .or_else(|| block.get("data"))
+        .and_then(|d| d.as_str())
+        .unwrap_or_default();
+
+    if data.starts_with("data:") || data.contains("://") {
+        block_from_url(data, media_type, name)
+    } else {
+        let default_type = if block.get("image").is_some() {
+            "image/png"
+        } else {
+            "application/octet-stream"
+        };
+        block_from_base64(media_type.unwrap_or(default_type), data, name, None)
+    }
+}
+
 /// Convert JSON message with timestamp
 fn convert_json_message_with_timestamp(
     json_msg: &super::protobuf::JsonMessage,
@@ -963,6 +1050,7 @@ fn convert_json_message_with_timestamp(
     };
 
     Ok(Some(CanonicalMessage {
+        schema_version: CANONICAL_SCHEMA_VERSION,
         uuid: if json_msg.id.is_empty() {
             uuid::Uuid::new_v4().to_string()
         } else {
@@ -1356,4 +1444,28 @@ mod tests {
             _ => panic!("Expected text content"),
         }
     }
+
+    #[test]
+    fn test_json_message_image_is_hashed() {
+        let content = json!([
+            {"type": "text", "text": "Match this mockup"},
+            {"type": "image", "image": "aGVsbG8=", "mimeType": "image/jpeg"}
+        ]);
+
+        match parse_content_array(&content, "user", "msg-1") {
+            ContentValue::Structured(blocks) => {
+                assert_eq!(blocks.len(), 2);
+                match &blocks[1] {
+                    ContentBlock::Image {
+                        media_type, hash, ..
+                    } => {
+                        assert_eq!(media_type, "image/jpeg");
+                        assert!(hash.is_some());
+                    }
+                    other => panic!("Expected image block, got {:?}", other),
+                }
+            }
+            _ => panic!("Expected structured content"),
+        }
+    }
 }
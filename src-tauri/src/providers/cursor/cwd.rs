@@ -0,0 +1,373 @@
+/// CWD resolution for Cursor sessions
+///
+/// Cursor names each chat hash directory with the MD5 of the workspace path, and
+/// keeps a matching folder in {base_path}/projects whose name is the path with
+/// the leading / removed and every / replaced with -. That folder name alone is
+/// ambiguous for paths that contain hyphens (`/Users/x/my-repo` and
+/// `/Users/x/my/repo` share a folder name), so resolution works in stages:
+/// On Windows the folder starts with the drive letter (`c-Users-x-repo` for
+/// `c:\Users\x\repo`), and the path may have been hashed with either
+/// separator or drive letter case.
+///
+///
+/// 1. Cached mapping from the local database
+/// 2. Workspace paths recorded in Cursor's own project metadata files
+/// 3. Candidate paths generated from every split point of the folder name,
+///    first pruned against the filesystem, then exhaustively; both passes
+///    stop after a fixed number of candidates per folder
+///
+/// Every candidate is verified against the session hash before it is accepted,
+/// and verified mappings are cached so the search only runs once per hash.
+use crate::providers::common::canonical_path::SessionPath;
+use crate::providers::common::file_access::ProviderRoot;
+use crate::providers::common::preview::is_previewing;
+use std::cell::Cell;
+use std::fs;
+use std::path::Path;
+
+/// Metadata keys that Cursor uses for the workspace path in project files
+const METADATA_PATH_KEYS: &[&str] = &["workspacePath", "workspaceRoot", "rootPath", "cwd", "path"];
+
+/// Skip metadata files larger than this (logs and caches are not metadata)
+const MAX_METADATA_FILE_SIZE: u64 = 1024 * 1024;
+
+/// Characters that Cursor folds into `-` besides the path separator
+const LITERAL_SEPARATORS: &[char] = &['-', '.', '_', ' '];
+
+/// Maximum hyphen count for the unpruned search (2^n candidates)
+const MAX_EXHAUSTIVE_SPLITS: usize = 16;
+
+/// Maximum search nodes visited per folder name in either pass
+const MAX_SEARCH_NODES: usize = 100_000;
+
+/// Find the CWD for a Cursor session by checking the projects directory
+pub fn find_cwd_for_session(session_hash: &str, projects_dir: &Path) -> Option<String> {
+    if let Ok(Some(cwd)) = crate::database::get_cursor_project_path(session_hash) {
+        return Some(cwd);
+    }
+
+    let (cwd, source) = resolve_cwd(session_hash, projects_dir)?;
+    if is_previewing("cursor") {
+        return Some(cwd);
+    }
+
+    if let Err(e) = crate::database::save_cursor_project_path(session_hash, &cwd, source) {
+        tracing::debug!(
+            "Could not cache Cursor project path for {}: {}",
+            session_hash,
+            e
+        );
+    }
+
+    Some(cwd)
+}
+
+/// Resolve the CWD without consulting the cache
+///
+/// Returns the verified path and the strategy that produced it.
+pub fn resolve_cwd(session_hash: &str, projects_dir: &Path) -> Option<(String, &'static str)> {
+    if !projects_dir.exists() {
+        return None;
+    }
+
+    let folders: Vec<(std::path::PathBuf, String)> = fs::read_dir(projects_dir)
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir())
+        .filter_map(|path| {
+            let name = path.file_name()?.to_str()?.to_string();
+            Some((path, name))
+        })
+        .collect();
+
+    // Cursor's own metadata is authoritative when present
+    for (project_path, _) in &folders {
+        for cwd in read_metadata_paths(project_path) {
+            if let Some(cwd) = matching_spelling(&cwd, session_hash) {
+                return Some((cwd, "metadata"));
+            }
+        }
+    }
+
+    // Candidates that exist on disk (cheap, handles hyphenated directories)
+    for (_, folder_name) in &folders {
+        if let Some(cwd) = search_candidates(folder_name, session_hash, true) {
+            return Some((cwd, "filesystem"));
+        }
+    }
+
+    // Workspace may have been moved or deleted; fall back to an unpruned search
+    for (_, folder_name) in &folders {
+        if folder_name.matches('-').count() > MAX_EXHAUSTIVE_SPLITS {
+            continue;
+        }
+        if let Some(cwd) = search_candidates(folder_name, session_hash, false) {
+            return Some((cwd, "candidate"));
+        }
+    }
+
+    None
+}
+
+/// Check whether a CWD hashes to the given Cursor session hash
+fn hash_matches(cwd: &str, session_hash: &str) -> bool {
+    format!("{:x}", md5::compute(cwd.as_bytes())) == session_hash
+}
+
+/// The spelling of a CWD that hashes to the session hash, if any does
+fn matching_spelling(cwd: &str, session_hash: &str) -> Option<String> {
+    if hash_matches(cwd, session_hash) {
+        return Some(cwd.to_string());
+    }
+    SessionPath::parse(cwd)
+        .spellings()
+        .into_iter()
+        .find(|spelling| hash_matches(spelling, session_hash))
+}
+
+/// Collect workspace paths from JSON metadata files in a project folder
+fn read_metadata_paths(project_path: &Path) -> Vec<String> {
+    let Ok(entries) = fs::read_dir(project_path) else {
+        return Vec::new();
+    };
+
+    let root = ProviderRoot::new("cursor", project_path);
+    let mut paths = Vec::new();
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("json") {
+            continue;
+        }
+
+        let too_large = entry
+            .metadata()
+            .map(|m| !m.is_file() || m.len() > MAX_METADATA_FILE_SIZE)
+            .unwrap_or(true);
+        if too_large {
+            continue;
+        }
+
+        let Ok(content) = root.read_to_string(&path) else {
+            continue;
+        };
+        let Ok(value) = serde_json::from_str::<serde_json::Value>(&content) else {
+            continue;
+        };
+
+        for key in METADATA_PATH_KEYS {
+            if let Some(cwd) = value.get(*key).and_then(|v| v.as_str()) {
+                paths.push(cwd.trim_end_matches(['/', '\\']).to_string());
+            }
+        }
+    }
+
+    paths
+}
+
+/// Try every interpretation of the hyphens in a project folder name
+///
+/// With `prune` set, a hyphen is only treated as a path separator when the
+/// directory before it exists. Without pruning, hyphens are either `/` or a
+/// literal `-`. A leading single letter is also tried as a Windows drive.
+/// Literal separators still branch on every hyphen, so the whole search for
+/// one folder name gives up after `MAX_SEARCH_NODES` nodes.
+fn search_candidates(folder_name: &str, session_hash: &str, prune: bool) -> Option<String> {
+    let segments: Vec<&str> = folder_name.split('-').collect();
+    let (first, rest) = segments.split_first()?;
+    let literals: &[char] = if prune { LITERAL_SEPARATORS } else { &['-'] };
+    let budget = Cell::new(MAX_SEARCH_NODES);
+
+    let unix = Search {
+        session_hash,
+        prune,
+        literals,
+        separator: '/',
+        budget: &budget,
+    };
+    let found = unix.find(rest, String::new(), (*first).to_string());
+    if found.is_some() {
+        return found;
+    }
+
+    if first.len() != 1 || !first.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        return None;
+    }
+    // The drive's colon may have become a hyphen of its own
+    let rest = match rest.split_first() {
+        Some((&"", rest)) => rest,
+        _ => rest,
+    };
+    let (component, rest) = rest.split_first()?;
+    let windows = Search {
+        separator: '\\',
+        ..unix
+    };
+    windows.find(rest, format!("{}:", first), (*component).to_string())
+}
+
+/// How candidates for one folder name are built and checked
+#[derive(Clone, Copy)]
+struct Search<'a> {
+    session_hash: &'a str,
+    prune: bool,
+    literals: &'a [char],
+    separator: char,
+    /// Nodes left before the search gives up, shared by both separators
+    budget: &'a Cell<usize>,
+}
+
+impl Search<'_> {
+    fn find(&self, segments: &[&str], prefix: String, component: String) -> Option<String> {
+        let remaining = self.budget.get();
+        if remaining == 0 {
+            return None;
+        }
+        self.budget.set(remaining - 1);
+
+        let Some((next, rest)) = segments.split_first() else {
+            let cwd = format!("{}{}{}", prefix, self.separator, component);
+            return matching_spelling(&cwd, self.session_hash);
+        };
+
+        // Treat the hyphen as a path separator
+        let dir = format!("{}{}{}", prefix, self.separator, component);
+        if !self.prune || Path::new(&dir).is_dir() {
+            let found = self.find(rest, dir, (*next).to_string());
+            if found.is_some() {
+                return found;
+            }
+        }
+
+        // Treat the hyphen as part of the directory name
+        for sep in self.literals {
+            let joined = format!("{}{}{}", component, sep, next);
+            let found = self.find(rest, prefix.clone(), joined);
+            if found.is_some() {
+                return found;
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn md5_hex(path: &str) -> String {
+        format!("{:x}", md5::compute(path.as_bytes()))
+    }
+
+    #[test]
+    fn test_resolves_simple_path() {
+        let temp = tempfile::tempdir().unwrap();
+        fs::create_dir(temp.path().join("Users-x-work-guidemode")).unwrap();
+
+        let hash = md5_hex("/Users/x/work/guidemode");
+        let (cwd, _) = resolve_cwd(&hash, temp.path()).unwrap();
+        assert_eq!(cwd, "/Users/x/work/guidemode");
+    }
+
+    #[test]
+    fn test_resolves_hyphenated_path_without_filesystem() {
+        let temp = tempfile::tempdir().unwrap();
+        fs::create_dir(temp.path().join("Users-x-my-repo")).unwrap();
+
+        let hash = md5_hex("/Users/x/my-repo");
+        let (cwd, source) = resolve_cwd(&hash, temp.path()).unwrap();
+        assert_eq!(cwd, "/Users/x/my-repo");
+        assert_eq!(source, "candidate");
+    }
+
+    #[test]
+    fn test_resolves_hyphenated_path_on_disk() {
+        let workspace = tempfile::tempdir().unwrap();
+        let repo = workspace.path().join("my-cool.repo");
+        fs::create_dir(&repo).unwrap();
+        let repo = repo.to_string_lossy().to_string();
+
+        let projects = tempfile::tempdir().unwrap();
+        let folder = repo.trim_start_matches('/').replace(['/', '.'], "-");
+        fs::create_dir(projects.path().join(folder)).unwrap();
+
+        let (cwd, source) = resolve_cwd(&md5_hex(&repo), projects.path()).unwrap();
+        assert_eq!(cwd, repo);
+        assert_eq!(source, "filesystem");
+    }
+
+    #[test]
+    fn test_resolves_from_metadata() {
+        let temp = tempfile::tempdir().unwrap();
+        let project = temp.path().join("Users-x-a-b-c");
+        fs::create_dir(&project).unwrap();
+        fs::write(
+            project.join("project.json"),
+            r#"{"workspacePath": "/Users/x/a-b-c"}"#,
+        )
+        .unwrap();
+
+        let (cwd, source) = resolve_cwd(&md5_hex("/Users/x/a-b-c"), temp.path()).unwrap();
+        assert_eq!(cwd, "/Users/x/a-b-c");
+        assert_eq!(source, "metadata");
+    }
+
+    #[test]
+    fn test_unknown_hash_returns_none() {
+        let temp = tempfile::tempdir().unwrap();
+        fs::create_dir(temp.path().join("Users-x-repo")).unwrap();
+
+        assert!(resolve_cwd("0123456789abcdef", temp.path()).is_none());
+    }
+
+    #[test]
+    fn test_resolves_windows_drive_paths() {
+        let temp = tempfile::tempdir().unwrap();
+        fs::create_dir(temp.path().join("c-Users-x-my-repo")).unwrap();
+        fs::create_dir(temp.path().join("D--work-app")).unwrap();
+
+        let (cwd, _) = resolve_cwd(&md5_hex(r"c:\Users\x\my-repo"), temp.path()).unwrap();
+        assert_eq!(cwd, r"c:\Users\x\my-repo");
+
+        // Hashed with the other drive letter case and separator
+        let (cwd, _) = resolve_cwd(&md5_hex("d:/work/app"), temp.path()).unwrap();
+        assert_eq!(cwd, "d:/work/app");
+    }
+
+    #[test]
+    fn test_long_folder_names_stay_bounded() {
+        let segments = vec!["a"; 40].join("-");
+        let budget = Cell::new(MAX_SEARCH_NODES);
+        let search = Search {
+            session_hash: "0123456789abcdef",
+            prune: false,
+            literals: LITERAL_SEPARATORS,
+            separator: '/',
+            budget: &budget,
+        };
+        let segments: Vec<&str> = segments.split('-').collect();
+
+        assert!(search
+            .find(&segments[1..], String::new(), "a".to_string())
+            .is_none());
+        assert_eq!(budget.get(), 0);
+    }
+
+    #[test]
+    fn test_windows_metadata_paths() {
+        let temp = tempfile::tempdir().unwrap();
+        let project = temp.path().join("c-work-app");
+        fs::create_dir(&project).unwrap();
+        fs::write(
+            project.join("project.json"),
+            r#"{"workspacePath": "C:\\work\\app\\"}"#,
+        )
+        .unwrap();
+
+        let (cwd, source) = resolve_cwd(&md5_hex(r"c:\work\app"), temp.path()).unwrap();
+        assert_eq!(cwd, r"c:\work\app");
+        assert_eq!(source, "metadata");
+    }
+}
@@ -83,6 +83,50 @@ pub fn get_decoded_messages(
     conn: &Connection,
 ) -> Result<Vec<(String, Vec<u8>, super::protobuf::CursorMessage)>, Box<dyn std::error::Error>> {
     let blobs = get_all_blobs(conn)?;
+    Ok(decode_messages(blobs))
+}
+
+/// Get blobs with rowid in (after_rowid, up_to_rowid], in insertion order
+pub fn get_blobs_in_range(
+    conn: &Connection,
+    after_rowid: i64,
+    up_to_rowid: i64,
+) -> Result<Vec<(String, Vec<u8>)>, rusqlite::Error> {
+    let mut stmt =
+        conn.prepare("SELECT id, data FROM blobs WHERE rowid > ? AND rowid <= ? ORDER BY rowid")?;
+
+    let blobs = stmt
+        .query_map([after_rowid, up_to_rowid], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(blobs)
+}
+
+/// Get messages decoded from blobs with rowid in (after_rowid, up_to_rowid]
+///
+/// Used for incremental conversion: callers remember the last rowid they
+/// converted and only decode blobs that Cursor appended since then.
+#[allow(clippy::type_complexity)]
+pub fn get_decoded_messages_in_range(
+    conn: &Connection,
+    after_rowid: i64,
+    up_to_rowid: i64,
+) -> Result<Vec<(String, Vec<u8>, super::protobuf::CursorMessage)>, Box<dyn std::error::Error>> {
+    let blobs = get_blobs_in_range(conn, after_rowid, up_to_rowid)?;
+    Ok(decode_messages(blobs))
+}
+
+/// Get the highest blob rowid (0 for an empty database)
+pub fn get_max_blob_rowid(conn: &Connection) -> Result<i64, rusqlite::Error> {
+    conn.query_row("SELECT COALESCE(MAX(rowid), 0) FROM blobs", [], |row| {
+        row.get(0)
+    })
+}
+
+/// Decode raw blobs, skipping tree/reference blobs that aren't messages
+fn decode_messages(
+    blobs: Vec<(String, Vec<u8>)>,
+) -> Vec<(String, Vec<u8>, super::protobuf::CursorMessage)> {
     let mut decoded = Vec::new();
     let total_blobs = blobs.len();
 
@@ -110,14 +154,16 @@ pub fn get_decoded_messages(
         }
     }
 
-    tracing::info!(
-        "Decode summary: {}/{} blobs successful ({:.1}% success rate)",
-        decoded.len(),
-        total_blobs,
-        (decoded.len() as f64 / total_blobs as f64) * 100.0
-    );
+    if total_blobs > 0 {
+        tracing::info!(
+            "Decode summary: {}/{} blobs successful ({:.1}% success rate)",
+            decoded.len(),
+            total_blobs,
+            (decoded.len() as f64 / total_blobs as f64) * 100.0
+        );
+    }
 
-    Ok(decoded)
+    decoded
 }
 
 /// Get the count of blobs in the database
@@ -163,4 +209,38 @@ mod tests {
         let version2 = get_data_version(&conn).unwrap();
         assert_eq!(version1, version2);
     }
+
+    #[test]
+    fn test_blob_rowid_range() {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+
+        {
+            let conn = Connection::open(&db_path).unwrap();
+            conn.execute_batch("CREATE TABLE blobs (id TEXT PRIMARY KEY, data BLOB);")
+                .unwrap();
+        }
+
+        let conn = open_cursor_db(&db_path).unwrap();
+        assert_eq!(get_max_blob_rowid(&conn).unwrap(), 0);
+        drop(conn);
+
+        {
+            let conn = Connection::open(&db_path).unwrap();
+            for id in ["a", "b", "c"] {
+                conn.execute("INSERT INTO blobs VALUES (?, x'00')", [id]).unwrap();
+            }
+        }
+
+        let conn = open_cursor_db(&db_path).unwrap();
+        assert_eq!(get_max_blob_rowid(&conn).unwrap(), 3);
+
+        let ids: Vec<String> = get_blobs_in_range(&conn, 1, 3)
+            .unwrap()
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect();
+        assert_eq!(ids, vec!["b".to_string(), "c".to_string()]);
+        assert!(get_blobs_in_range(&conn, 3, 3).unwrap().is_empty());
+    }
 }
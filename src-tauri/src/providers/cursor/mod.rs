@@ -8,16 +8,20 @@
 /// - Content-addressable storage with SHA-256 blob IDs
 /// - WAL mode for safe concurrent access
 pub mod converter;
+pub mod cwd;
 pub mod db;
 pub mod debug;
 pub mod protobuf;
 pub mod scanner;
+pub mod timing;
 pub mod types;
 pub mod watcher;
 
-pub use scanner::scan_existing_sessions;
+pub use cwd::find_cwd_for_session;
+pub use scanner::{convert_file, scan_existing_sessions};
 pub use types::CursorSession;
 
+use crate::providers::common::{expand_home, SessionPath};
 use std::fs;
 use std::path::{Path, PathBuf};
 
@@ -28,8 +32,8 @@ use std::path::{Path, PathBuf};
 /// 2. Finds session directories with store.db files
 /// 3. Attempts to read session metadata for project names
 pub fn scan_projects(home_directory: &str) -> Result<Vec<crate::config::ProjectInfo>, String> {
-    let base_path = shellexpand::tilde(home_directory).to_string();
-    let chats_dir = Path::new(&base_path).join("chats");
+    let base_path = expand_home(home_directory);
+    let chats_dir = base_path.join("chats");
 
     if !chats_dir.exists() {
         return Err(format!(
@@ -84,9 +88,8 @@ pub fn scan_projects(home_directory: &str) -> Result<Vec<crate::config::ProjectI
                         let project_name = cwd
                             .as_ref()
                             .and_then(|path| {
-                                Path::new(path)
+                                SessionPath::parse(path)
                                     .file_name()
-                                    .and_then(|name| name.to_str())
                                     .map(|s| s.to_string())
                             })
                             .unwrap_or_else(|| metadata.name.clone());
@@ -226,6 +229,40 @@ pub fn discover_sessions(base_path: &Path) -> Result<Vec<CursorSession>, Box<dyn
     Ok(sessions)
 }
 
+/// Load a single Cursor session from its store.db path
+///
+/// Expects the standard layout {base_path}/chats/{hash}/{uuid}/store.db.
+/// Cheaper than `discover_sessions` when the database path is already known.
+pub fn load_session(db_path: &Path, base_path: &Path) -> Result<CursorSession, Box<dyn std::error::Error>> {
+    let session_dir = db_path
+        .parent()
+        .ok_or_else(|| format!("Invalid Cursor database path: {}", db_path.display()))?;
+    let hash_dir = session_dir
+        .parent()
+        .ok_or_else(|| format!("Invalid Cursor database path: {}", db_path.display()))?;
+
+    let session_id = session_dir
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .ok_or_else(|| format!("Invalid Cursor session directory: {}", session_dir.display()))?;
+    let hash = hash_dir
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .ok_or_else(|| format!("Invalid Cursor hash directory: {}", hash_dir.display()))?;
+
+    let conn = db::open_cursor_db(db_path)?;
+    let metadata = db::get_session_metadata(&conn)?;
+    let cwd = find_cwd_for_session(&hash, &base_path.join("projects"));
+
+    Ok(CursorSession {
+        session_id,
+        db_path: db_path.to_path_buf(),
+        metadata,
+        hash,
+        cwd,
+    })
+}
+
 /// Get the Cursor database path from a session ID
 ///
 /// Note: This requires scanning to find which hash directory contains the session
@@ -239,42 +276,6 @@ pub fn get_db_path_for_session(session_id: &str, base_path: &Path) -> Result<Pat
         .ok_or_else(|| format!("Session not found: {}", session_id))
 }
 
-/// Find the CWD for a Cursor session by checking the projects directory
-///
-/// Cursor stores projects in {base_path}/projects with folder names that are
-/// the CWD path with the leading / removed and remaining / replaced with -
-/// Example: /Users/cliftonc/work/guidemode -> Users-cliftonc-work-guidemode
-pub fn find_cwd_for_session(session_hash: &str, projects_dir: &Path) -> Option<String> {
-    if !projects_dir.exists() {
-        return None;
-    }
-
-    // Try to find a matching project by computing hash for each CWD
-    if let Ok(entries) = fs::read_dir(projects_dir) {
-        for entry in entries.flatten() {
-            let project_path = entry.path();
-            if !project_path.is_dir() {
-                continue;
-            }
-
-            // Convert project folder name back to CWD
-            // Example: Users-cliftonc-work-guidemode -> /Users/cliftonc/work/guidemode
-            if let Some(folder_name) = project_path.file_name().and_then(|n| n.to_str()) {
-                let cwd = format!("/{}", folder_name.replace('-', "/"));
-
-                // Compute MD5 hash of CWD to see if it matches the session hash
-                let hash = format!("{:x}", md5::compute(cwd.as_bytes()));
-
-                if hash == session_hash {
-                    return Some(cwd);
-                }
-            }
-        }
-    }
-
-    None
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -283,8 +284,8 @@ mod tests {
     fn test_scan_projects() {
         // This test will only work if Cursor is installed
         // Skip if directory doesn't exist
-        let base_path = shellexpand::tilde("~/.cursor").to_string();
-        let chats_dir = Path::new(&base_path).join("chats");
+        let base_path = expand_home("~/.cursor");
+        let chats_dir = base_path.join("chats");
         if !chats_dir.exists() {
             return;
         }
@@ -296,8 +297,8 @@ mod tests {
     #[test]
     fn test_discover_sessions() {
         // This test will only work if Cursor is installed
-        let base_path = shellexpand::tilde("~/.cursor").to_string();
-        let chats_dir = Path::new(&base_path).join("chats");
+        let base_path = expand_home("~/.cursor");
+        let chats_dir = base_path.join("chats");
         if !chats_dir.exists() {
             return;
         }
@@ -146,6 +146,9 @@ pub struct JsonMessage {
     pub id: String,
     pub role: String,
     pub content: serde_json::Value,
+    /// Message timestamp when Cursor records one (millis, seconds or RFC3339)
+    #[serde(default, alias = "createdAt", skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<serde_json::Value>,
 }
 
 /// Hybrid message type that can be either protobuf or JSON
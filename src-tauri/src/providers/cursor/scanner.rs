@@ -2,12 +2,16 @@
 ///
 /// This runs on watcher initialization to find and process all existing
 /// Cursor sessions that may not have been previously imported.
-use super::{converter::CursorMessageWithRaw, db, discover_sessions, CursorSession};
-use crate::events::{EventBus, SessionEventPayload};
+use super::{
+    converter::CursorMessageWithRaw, db, discover_sessions, load_session, timing::SessionTiming,
+    CursorSession,
+};
+use crate::events::{CancellationToken, EventBus, SessionEventPayload};
 use crate::providers::canonical::CanonicalMessage;
-use crate::providers::common::get_canonical_path;
+use crate::providers::common::timestamps::TimestampNormalizer;
+use crate::providers::common::{annotate_model, CanonicalWriter, ConvertResult, ConvertedFile};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 const PROVIDER_ID: &str = "cursor";
 
@@ -21,23 +25,6 @@ pub struct ScanResult {
     pub messages_converted: usize,
 }
 
-/// Statistics for message conversion
-#[derive(Default)]
-struct MessageStats {
-    user_count: usize,
-    user_json: usize,
-    user_protobuf: usize,
-    assistant_count: usize,
-    assistant_json: usize,
-    assistant_protobuf: usize,
-    system_count: usize,
-    other_count: usize,
-    tool_use_count: usize,
-    tool_result_count: usize,
-    skipped_count: usize,
-    failed_count: usize,
-}
-
 /// Scan all existing Cursor sessions and convert them to canonical format
 ///
 /// This function:
@@ -111,136 +98,39 @@ pub fn scan_existing_sessions(
 /// Process a single Cursor session
 ///
 /// Steps:
-/// 1. Open database and get blobs
-/// 2. Decode protobuf messages
-/// 3. Convert to canonical format
-/// 4. Write canonical JSONL file
-/// 5. Publish SessionChanged event
+/// 1. Convert the session's blobs to canonical format
+/// 2. Write canonical JSONL file
+/// 3. Publish SessionChanged event
 ///
 /// Returns the number of messages converted
 fn process_session(
     session: &CursorSession,
     event_bus: &EventBus,
 ) -> Result<usize, Box<dyn std::error::Error>> {
-    // Open database
-    let conn = db::open_cursor_db(&session.db_path)?;
-
-    // Get decoded messages (supports both protobuf and JSON)
-    let decoded_messages = db::get_decoded_messages(&conn)?;
-
-    if decoded_messages.is_empty() {
-        return Ok(0); // Empty session, skip
-    }
-
-    // Convert to canonical messages
-    let mut canonical_messages: Vec<CanonicalMessage> = Vec::new();
-    let mut stats = MessageStats::default();
-
-    for (message_index, (_msg_id, raw_data, msg)) in decoded_messages.iter().enumerate() {
-        // Track message source type
-        let msg_source = match &msg {
-            super::protobuf::CursorMessage::Protobuf(_) => "protobuf",
-            super::protobuf::CursorMessage::Json(_) => "json",
-        };
-
-        // Wrap message with raw data and session metadata for timestamp calculation
-        let msg_with_raw = CursorMessageWithRaw::new(
-            msg,
-            raw_data,
-            session.metadata.created_at,
-            message_index,
-        );
-
-        // Use split conversion to prevent UUID collisions
-        match msg_with_raw.to_canonical_split() {
-            Ok(messages) => {
-                // Process each split message
-                for mut canonical in messages {
-                    // Track by role and source
-                    match canonical.message.role.as_str() {
-                        "user" => {
-                            stats.user_count += 1;
-                            if msg_source == "json" {
-                                stats.user_json += 1;
-                            } else {
-                            stats.user_protobuf += 1;
-                        }
-                    }
-                    "assistant" => {
-                        stats.assistant_count += 1;
-                        if msg_source == "json" {
-                            stats.assistant_json += 1;
-                        } else {
-                            stats.assistant_protobuf += 1;
-                        }
-                    }
-                    "system" => stats.system_count += 1,
-                    _ => stats.other_count += 1,
-                }
-
-                // Check for tool content
-                if let crate::providers::canonical::ContentValue::Structured(blocks) = &canonical.message.content {
-                    for block in blocks {
-                        match block {
-                            crate::providers::canonical::ContentBlock::ToolUse { .. } => stats.tool_use_count += 1,
-                            crate::providers::canonical::ContentBlock::ToolResult { .. } => stats.tool_result_count += 1,
-                            _ => {}
-                        }
-                    }
-                }
-
-                // Set session ID (ToCanonical doesn't know it)
-                canonical.session_id = session.session_id.clone();
-
-                // Set CWD from session if available
-                if canonical.cwd.is_none() {
-                    canonical.cwd = session.cwd.clone();
-                }
-
-                    canonical_messages.push(canonical);
-                }
-            }
-            Err(e) => {
-                stats.failed_count += 1;
-                tracing::warn!("Failed to convert blob in session {}: {:?}", session.session_id, e);
-            }
-        }
+    if session.is_ignored() {
+        return Ok(0);
     }
+    let max_rowid = db::get_max_blob_rowid(&db::open_cursor_db(&session.db_path)?)?;
+    let (converted, blob_count) =
+        convert_session(session, max_rowid).map_err(|e| e as Box<dyn std::error::Error>)?;
+    let message_count = converted.message_count();
 
     tracing::info!(
-        "Conversion stats for session {}: {} messages ({} user [{} JSON + {} protobuf], {} assistant [{} JSON + {} protobuf], {} system, {} tool_use, {} tool_result, {} skipped, {} failed)",
+        "Conversion stats for session {}: {} messages from {} blobs ({} failed)",
         session.session_id,
-        canonical_messages.len(),
-        stats.user_count,
-        stats.user_json,
-        stats.user_protobuf,
-        stats.assistant_count,
-        stats.assistant_json,
-        stats.assistant_protobuf,
-        stats.system_count,
-        stats.tool_use_count,
-        stats.tool_result_count,
-        stats.skipped_count,
-        stats.failed_count
+        message_count,
+        blob_count,
+        converted.skipped_lines().len()
     );
 
-    if canonical_messages.is_empty() {
+    if message_count == 0 {
         return Ok(0); // No valid messages, skip
     }
 
-    // Sort by timestamp (though Cursor blobs don't have timestamps, use database order)
-    // Messages are already in database order from the query
-
-    // Get canonical path (use CWD if available)
-    let canonical_path = get_canonical_path(
-        PROVIDER_ID,
-        session.cwd.as_deref(),
-        &session.session_id,
-    )
-    .map_err(|e| -> Box<dyn std::error::Error> { Box::new(std::io::Error::other(e.to_string())) })?;
-
-    // Write canonical JSONL
-    write_canonical_file(&canonical_path, &canonical_messages)?;
+    // Messages stay in database order; Cursor blobs don't have timestamps
+    let canonical_path = converted
+        .persist()
+        .map_err(|e| e as Box<dyn std::error::Error>)?;
 
     // Get file size
     let file_size = fs::metadata(&canonical_path)?.len();
@@ -255,32 +145,178 @@ fn process_session(
 
     event_bus.publish(PROVIDER_ID, payload)?;
 
-    Ok(canonical_messages.len())
+    Ok(message_count)
+}
+
+/// Convert a Cursor session's store.db to canonical format
+///
+/// Databases live in `{base_path}/chats/{hash}/{session_id}/store.db`.
+pub fn convert_file(db_path: &Path) -> ConvertResult<ConvertedFile> {
+    let base_path = db_path
+        .ancestors()
+        .nth(4)
+        .ok_or_else(|| format!("Invalid Cursor database path: {}", db_path.display()))?;
+    let session = load_session(db_path, base_path).map_err(|e| e.to_string())?;
+    let max_rowid = db::get_max_blob_rowid(&db::open_cursor_db(db_path)?)?;
+
+    convert_session(&session, max_rowid).map(|(converted, _)| converted)
 }
 
-/// Write canonical messages to a JSONL file
-pub fn write_canonical_file(
+/// Convert a Cursor session's blobs up to `max_rowid` to canonical format
+///
+/// Blobs that fail to convert are set aside. Also returns how many blobs were
+/// decoded, which positions blobs appended later for their timestamps.
+pub fn convert_session(
+    session: &CursorSession,
+    max_rowid: i64,
+) -> ConvertResult<(ConvertedFile, usize)> {
+    // Get decoded messages (supports both protobuf and JSON)
+    let conn = db::open_cursor_db(&session.db_path)?;
+    let decoded_messages =
+        db::get_decoded_messages_in_range(&conn, 0, max_rowid).map_err(|e| e.to_string())?;
+
+    let mut writer = CanonicalWriter::for_source(PROVIDER_ID, &session.db_path)?;
+    let timing = SessionTiming::for_session(session, decoded_messages.len());
+
+    for (message_index, (blob_id, raw_data, msg)) in decoded_messages.iter().enumerate() {
+        // Wrap message with raw data and session metadata for timestamp calculation
+        let msg_with_raw = CursorMessageWithRaw::new(msg, raw_data, &timing, message_index);
+
+        // Use split conversion to prevent UUID collisions
+        match msg_with_raw.to_canonical_split() {
+            Ok(messages) => {
+                for mut canonical in messages {
+                    attribute_to_session(&mut canonical, session);
+                    writer.write(&canonical)?;
+                }
+            }
+            Err(e) => {
+                tracing::warn!("Failed to convert blob in session {}: {:?}", session.session_id, e);
+                quarantine_blob(&mut writer, message_index, blob_id, raw_data, e);
+            }
+        }
+    }
+
+    let converted = ConvertedFile::new(
+        PROVIDER_ID,
+        session.session_id.clone(),
+        session.cwd.clone(),
+        writer,
+    );
+    Ok((converted, decoded_messages.len()))
+}
+
+/// Set aside a blob that failed to convert
+///
+/// Blobs are numbered by their position in the session's database. Protobuf
+/// blobs are kept as lossy UTF-8, which still shows their text fields.
+fn quarantine_blob(
+    writer: &mut CanonicalWriter,
+    index: usize,
+    blob_id: &str,
+    raw_data: &[u8],
+    error: impl std::fmt::Debug,
+) {
+    writer.quarantine(
+        index + 1,
+        &format!("{}: {}", blob_id, String::from_utf8_lossy(raw_data)),
+        format!("{:?}", error),
+    );
+}
+
+/// Append canonical messages to an existing JSONL file
+///
+/// Creates the file if it doesn't exist. Keeps the same layout as
+/// `CanonicalWriter` (newline-separated, no trailing newline).
+pub fn append_canonical_file(
     path: &PathBuf,
     messages: &[CanonicalMessage],
 ) -> Result<(), Box<dyn std::error::Error>> {
-    // Ensure parent directory exists
+    use std::io::Write;
+
+    if messages.is_empty() {
+        return Ok(());
+    }
+
     if let Some(parent) = path.parent() {
         fs::create_dir_all(parent)?;
     }
 
-    // Convert to JSONL (one JSON object per line)
+    let needs_separator = fs::metadata(path).map(|m| m.len() > 0).unwrap_or(false);
+
     let jsonl: Vec<String> = messages
         .iter()
         .filter_map(|msg| serde_json::to_string(msg).ok())
         .collect();
 
-    let content = jsonl.join("\n");
+    let mut content = String::new();
+    if needs_separator {
+        content.push('\n');
+    }
+    content.push_str(&jsonl.join("\n"));
 
-    fs::write(path, content)?;
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    file.write_all(content.as_bytes())?;
 
     Ok(())
 }
 
+/// Fill in what a converted blob doesn't know about its session: its ID,
+/// working directory and model
+fn attribute_to_session(canonical: &mut CanonicalMessage, session: &CursorSession) {
+    canonical.session_id = session.session_id.clone();
+    if canonical.cwd.is_none() {
+        canonical.cwd = session.cwd.clone();
+    }
+    apply_session_model(canonical, session);
+}
+
+/// Attribute an assistant message to the session's model and normalize it
+///
+/// Cursor blobs only record the "default" model slot; the model actually used
+/// is stored once per session in the meta table.
+fn apply_session_model(canonical: &mut CanonicalMessage, session: &CursorSession) {
+    let last_used_model = &session.metadata.last_used_model;
+    if !last_used_model.is_empty() && canonical.message.model.as_deref() == Some("default") {
+        canonical.message.model = Some(last_used_model.clone());
+    }
+    annotate_model(canonical);
+}
+
+/// Convert decoded Cursor messages to canonical messages for a session
+///
+/// `start_index` is the index of the first decoded message within the session,
+/// and `timestamps` resumes from the messages already converted, so incremental
+/// conversions keep the same timestamps as a full conversion.
+pub fn convert_decoded_messages(
+    session: &CursorSession,
+    decoded_messages: &[(String, Vec<u8>, super::protobuf::CursorMessage)],
+    start_index: usize,
+    timestamps: &mut TimestampNormalizer,
+) -> Vec<CanonicalMessage> {
+    let mut canonical_messages = Vec::new();
+    let timing = SessionTiming::for_session(session, start_index + decoded_messages.len());
+
+    for (offset, (_msg_id, raw_data, msg)) in decoded_messages.iter().enumerate() {
+        // Wrap message with raw data and session timing for timestamp calculation
+        let msg_with_raw = CursorMessageWithRaw::new(msg, raw_data, &timing, start_index + offset);
+
+        // Use split conversion to prevent UUID collisions
+        if let Ok(messages) = msg_with_raw.to_canonical_split() {
+            for mut canonical in messages {
+                attribute_to_session(&mut canonical, session);
+                timestamps.normalize_in_place(&mut canonical);
+                canonical_messages.push(canonical);
+            }
+        }
+    }
+
+    canonical_messages
+}
+
 /// Scan Cursor sessions with optional project filtering
 ///
 /// This function matches the API of other providers for use in session_scanner.rs dispatcher.
@@ -288,6 +324,7 @@ pub fn write_canonical_file(
 pub fn scan_sessions_filtered(
     base_path: &std::path::Path,
     selected_projects: Option<&[String]>,
+    cancel: &CancellationToken,
 ) -> Result<Vec<crate::providers::common::SessionInfo>, String> {
     use crate::logging::{log_info, log_warn};
 
@@ -308,6 +345,10 @@ pub fn scan_sessions_filtered(
     let mut session_infos = Vec::new();
 
     for session in sessions {
+        // A cancelled scan keeps the sessions already converted
+        if cancel.is_cancelled() {
+            break;
+        }
         match scan_single_cursor_session(&session, selected_projects) {
             Ok(Some(info)) => session_infos.push(info),
             Ok(None) => {
@@ -335,58 +376,28 @@ fn scan_single_cursor_session(
     session: &CursorSession,
     selected_projects: Option<&[String]>,
 ) -> Result<Option<crate::providers::common::SessionInfo>, String> {
+    use crate::providers::common::timing::extract_timing_from_jsonl;
     use crate::providers::common::SessionInfo;
-    use chrono::{DateTime, Utc};
-    use std::path::Path;
 
-    // Open database and get decoded messages (supports both protobuf and JSON)
-    let conn = db::open_cursor_db(&session.db_path).map_err(|e| e.to_string())?;
-    let decoded_messages = db::get_decoded_messages(&conn).map_err(|e| e.to_string())?;
-
-    if decoded_messages.is_empty() {
-        return Err("Empty session (no messages)".to_string());
+    if session.is_ignored() {
+        return Ok(None);
     }
 
-    // Convert messages to canonical format (decoded_messages is Vec<(String, Vec<u8>, CursorMessage)>)
-    let mut canonical_messages = Vec::new();
-
-    for (message_index, (_blob_id, raw_data, msg)) in decoded_messages.iter().enumerate() {
-        // Wrap message with raw data and session metadata for timestamp calculation
-        let msg_with_raw = CursorMessageWithRaw::new(
-            msg,
-            raw_data,
-            session.metadata.created_at,
-            message_index,
-        );
-
-        // Use to_canonical_split() to properly separate tool calls and tool results
-        if let Ok(messages) = msg_with_raw.to_canonical_split() {
-            for mut canonical_msg in messages {
-                // Set session ID (required for UI parser)
-                canonical_msg.session_id = session.session_id.clone();
-
-                // Use CWD from session (already discovered)
-                if canonical_msg.cwd.is_none() {
-                    canonical_msg.cwd = session.cwd.clone();
-                }
-                canonical_messages.push(canonical_msg);
-            }
-        }
+    let max_rowid = db::open_cursor_db(&session.db_path)
+        .and_then(|conn| db::get_max_blob_rowid(&conn))
+        .map_err(|e| e.to_string())?;
+    if max_rowid == 0 {
+        return Err("Empty session (no messages)".to_string());
     }
 
-    if canonical_messages.is_empty() {
+    // Convert messages to canonical format, in database order
+    let (converted, _) = convert_session(session, max_rowid).map_err(|e| e.to_string())?;
+    if converted.message_count() == 0 {
         return Err("No valid messages after conversion".to_string());
     }
 
-    // Sort by timestamp
-    canonical_messages.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
-
-    // Extract CWD from first message (now enriched with project directory lookup)
-    let cwd = canonical_messages
-        .first()
-        .and_then(|m| m.cwd.clone());
-
     // Derive project name from CWD (last path component) or fall back to session name
+    let cwd = converted.cwd.clone();
     let project_name = cwd
         .as_ref()
         .and_then(|path| {
@@ -404,36 +415,14 @@ fn scan_single_cursor_session(
         }
     }
 
-    // Get canonical path
-    let canonical_path = get_canonical_path("cursor", cwd.as_deref(), &session.session_id)
-        .map_err(|e| format!("Failed to get canonical path: {}", e))?;
-
     // Write canonical JSONL
-    let canonical_content = canonical_messages
-        .iter()
-        .filter_map(|msg| serde_json::to_string(msg).ok())
-        .collect::<Vec<_>>()
-        .join("\n");
-
-    fs::write(&canonical_path, canonical_content)
+    let canonical_path = converted
+        .persist()
         .map_err(|e| format!("Failed to write canonical file: {}", e))?;
 
     // Extract timing from messages
-    let session_start_time = canonical_messages
-        .first()
-        .and_then(|m| DateTime::parse_from_rfc3339(&m.timestamp).ok())
-        .map(|dt| dt.with_timezone(&Utc));
-
-    let session_end_time = canonical_messages
-        .last()
-        .and_then(|m| DateTime::parse_from_rfc3339(&m.timestamp).ok())
-        .map(|dt| dt.with_timezone(&Utc));
-
-    let duration_ms = if let (Some(start), Some(end)) = (session_start_time, session_end_time) {
-        Some((end - start).num_milliseconds())
-    } else {
-        None
-    };
+    let (session_start_time, session_end_time, duration_ms) =
+        extract_timing_from_jsonl(&canonical_path)?;
 
     // Get file size
     let file_size = fs::metadata(&canonical_path)
@@ -467,21 +456,23 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_write_canonical_file() {
-        use crate::providers::canonical::{ContentValue, MessageContent, MessageType};
-        use chrono::Utc;
+    fn test_append_canonical_file() {
+        use crate::providers::canonical::{
+            ContentValue, MessageContent, MessageType, CANONICAL_SCHEMA_VERSION,
+        };
         use tempfile::tempdir;
 
         let dir = tempdir().unwrap();
         let file_path = dir.path().join("test.jsonl");
 
-        let messages = vec![CanonicalMessage {
-            uuid: "test-1".to_string(),
-            timestamp: Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+        let message = |uuid: &str| CanonicalMessage {
+            schema_version: CANONICAL_SCHEMA_VERSION,
+            uuid: uuid.to_string(),
+            timestamp: "2025-01-01T00:00:00.000Z".to_string(),
             message_type: MessageType::User,
             session_id: "test-session".to_string(),
             provider: "cursor".to_string(),
-            cwd: Some("/test".to_string()),
+            cwd: None,
             git_branch: None,
             version: None,
             parent_uuid: None,
@@ -497,14 +488,17 @@ mod tests {
             is_meta: None,
             request_id: None,
             tool_use_result: None,
-        }];
-
-        write_canonical_file(&file_path, &messages).unwrap();
+        };
 
-        assert!(file_path.exists());
+        append_canonical_file(&file_path, &[message("a")]).unwrap();
+        append_canonical_file(&file_path, &[message("b"), message("c")]).unwrap();
+        append_canonical_file(&file_path, &[]).unwrap();
 
         let content = fs::read_to_string(&file_path).unwrap();
-        assert!(content.contains("Test message"));
-        assert!(content.contains("cursor"));
+        let uuids: Vec<String> = content
+            .lines()
+            .map(|line| serde_json::from_str::<CanonicalMessage>(line).unwrap().uuid)
+            .collect();
+        assert_eq!(uuids, vec!["a", "b", "c"]);
     }
 }
@@ -0,0 +1,229 @@
+/// Per-message timestamp resolution for Cursor sessions
+///
+/// Cursor does not reliably store per-message timestamps. We use, in order:
+/// 1. A timestamp embedded in the blob itself (protobuf metadata field or JSON message)
+/// 2. Linear interpolation between the session's created and last-updated times
+/// 3. Synthetic 1-second spacing from the session's created time
+///
+/// The method used is recorded in each message's `provider_metadata.timestamp_method`
+/// so downstream latency metrics can tell real timings from estimates.
+use super::protobuf::CursorMessage;
+use super::types::CursorSession;
+use chrono::DateTime;
+use serde_json::Value;
+use std::path::Path;
+
+/// Keys that may carry a timestamp inside blob metadata or JSON messages
+const TIMESTAMP_KEYS: &[&str] = &["timestamp", "createdAt", "created_at", "updatedAt"];
+
+/// Synthetic spacing used when no session end time is known
+const SYNTHETIC_STEP_MS: i64 = 1000;
+
+/// How a message timestamp was determined
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimestampMethod {
+    /// Timestamp stored by Cursor in the blob
+    BlobMetadata,
+    /// Interpolated between session created and updated times
+    Interpolated,
+    /// Session created time plus a fixed step per message
+    Synthetic,
+}
+
+impl TimestampMethod {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TimestampMethod::BlobMetadata => "blob_metadata",
+            TimestampMethod::Interpolated => "interpolated",
+            TimestampMethod::Synthetic => "synthetic",
+        }
+    }
+}
+
+/// Session-level timing bounds used to estimate message timestamps
+#[derive(Debug, Clone, Copy)]
+pub struct SessionTiming {
+    /// Session creation time (Unix milliseconds)
+    pub created_at: i64,
+    /// Last activity time (Unix milliseconds), if known
+    pub updated_at: Option<i64>,
+    /// Total decoded messages in the session at conversion time
+    pub total_messages: usize,
+}
+
+impl SessionTiming {
+    /// Build timing bounds for a session
+    ///
+    /// Uses the meta table's `updatedAt` when present, otherwise the latest
+    /// modification time of store.db and its WAL file.
+    pub fn for_session(session: &CursorSession, total_messages: usize) -> Self {
+        let updated_at = session
+            .metadata
+            .updated_at
+            .or_else(|| store_modified_at(&session.db_path))
+            .filter(|updated| *updated > session.metadata.created_at);
+
+        Self {
+            created_at: session.metadata.created_at,
+            updated_at,
+            total_messages,
+        }
+    }
+
+    /// Timestamp for a message, preferring a real timestamp from the blob
+    pub fn resolve(&self, message: &CursorMessage, message_index: usize) -> (i64, TimestampMethod) {
+        match extract_message_timestamp(message) {
+            Some(timestamp) => (timestamp, TimestampMethod::BlobMetadata),
+            None => self.estimate(message_index),
+        }
+    }
+
+    /// Estimate a timestamp for the message at `message_index`
+    pub fn estimate(&self, message_index: usize) -> (i64, TimestampMethod) {
+        if let Some(updated_at) = self.updated_at {
+            if self.total_messages > 1 && message_index < self.total_messages {
+                let span = (updated_at - self.created_at) as i128;
+                let offset = span * message_index as i128 / (self.total_messages - 1) as i128;
+                return (
+                    self.created_at + offset as i64,
+                    TimestampMethod::Interpolated,
+                );
+            }
+        }
+
+        (
+            self.created_at + message_index as i64 * SYNTHETIC_STEP_MS,
+            TimestampMethod::Synthetic,
+        )
+    }
+}
+
+/// Extract a real timestamp stored alongside a Cursor message
+pub fn extract_message_timestamp(message: &CursorMessage) -> Option<i64> {
+    match message {
+        CursorMessage::Protobuf(blob) => blob
+            .metadata
+            .as_deref()
+            .filter(|meta| !meta.is_empty())
+            .and_then(|meta| serde_json::from_str::<Value>(meta).ok())
+            .and_then(|value| timestamp_from_object(&value)),
+        CursorMessage::Json(json_msg) => json_msg.timestamp.as_ref().and_then(parse_timestamp),
+    }
+}
+
+fn timestamp_from_object(value: &Value) -> Option<i64> {
+    TIMESTAMP_KEYS
+        .iter()
+        .find_map(|key| value.get(*key).and_then(parse_timestamp))
+}
+
+/// Parse a timestamp given as Unix milliseconds/seconds or an RFC3339 string
+fn parse_timestamp(value: &Value) -> Option<i64> {
+    match value {
+        Value::Number(n) => {
+            let n = n.as_i64()?;
+            // Values below 10^11 are seconds (year ~5138 in millis)
+            let millis = if n < 100_000_000_000 {
+                n.checked_mul(1000)?
+            } else {
+                n
+            };
+            (millis > 0).then_some(millis)
+        }
+        Value::String(s) => DateTime::parse_from_rfc3339(s)
+            .ok()
+            .map(|dt| dt.timestamp_millis()),
+        _ => None,
+    }
+}
+
+/// Latest modification time of store.db and its WAL (Unix milliseconds)
+fn store_modified_at(db_path: &Path) -> Option<i64> {
+    let wal_path = db_path.with_extension("db-wal");
+
+    [db_path, wal_path.as_path()]
+        .iter()
+        .filter_map(|path| std::fs::metadata(path).ok()?.modified().ok())
+        .max()
+        .map(|modified| DateTime::<chrono::Utc>::from(modified).timestamp_millis())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::cursor::protobuf::{CursorBlob, JsonMessage};
+
+    fn blob_with_metadata(metadata: &str) -> CursorMessage {
+        CursorMessage::Protobuf(CursorBlob {
+            content_wrapper: None,
+            uuid: None,
+            metadata: Some(metadata.to_string()),
+            complex_data: None,
+            additional_content: None,
+            blob_references: None,
+        })
+    }
+
+    #[test]
+    fn test_interpolates_between_created_and_updated() {
+        let timing = SessionTiming {
+            created_at: 1_000_000,
+            updated_at: Some(1_010_000),
+            total_messages: 3,
+        };
+
+        assert_eq!(timing.estimate(0), (1_000_000, TimestampMethod::Interpolated));
+        assert_eq!(timing.estimate(1), (1_005_000, TimestampMethod::Interpolated));
+        assert_eq!(timing.estimate(2), (1_010_000, TimestampMethod::Interpolated));
+    }
+
+    #[test]
+    fn test_synthetic_without_updated_time() {
+        let timing = SessionTiming {
+            created_at: 1_000_000,
+            updated_at: None,
+            total_messages: 3,
+        };
+
+        assert_eq!(timing.estimate(2), (1_002_000, TimestampMethod::Synthetic));
+    }
+
+    #[test]
+    fn test_blob_metadata_timestamp_wins() {
+        let timing = SessionTiming {
+            created_at: 1_000_000,
+            updated_at: Some(1_010_000),
+            total_messages: 3,
+        };
+
+        let message = blob_with_metadata(r#"{"createdAt": 1762058138859}"#);
+        assert_eq!(
+            timing.resolve(&message, 1),
+            (1762058138859, TimestampMethod::BlobMetadata)
+        );
+
+        let message = blob_with_metadata("");
+        assert_eq!(timing.resolve(&message, 1).1, TimestampMethod::Interpolated);
+    }
+
+    #[test]
+    fn test_json_message_timestamp() {
+        let message = CursorMessage::Json(JsonMessage {
+            id: "m1".to_string(),
+            role: "user".to_string(),
+            content: serde_json::json!("hi"),
+            timestamp: Some(serde_json::json!("2025-01-01T00:00:01.500Z")),
+        });
+
+        assert_eq!(extract_message_timestamp(&message), Some(1735689601500));
+    }
+
+    #[test]
+    fn test_parse_timestamp_seconds() {
+        assert_eq!(
+            parse_timestamp(&serde_json::json!(1762058138)),
+            Some(1762058138000)
+        );
+        assert_eq!(parse_timestamp(&serde_json::json!(i64::MIN)), None);
+    }
+}
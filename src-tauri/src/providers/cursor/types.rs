@@ -1,4 +1,5 @@
 /// Cursor-specific type definitions
+use crate::ignore_list::IgnoreList;
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
@@ -11,6 +12,8 @@ pub struct SessionMetadata {
     pub name: String,
     pub mode: String,
     pub created_at: i64, // Unix timestamp in milliseconds
+    #[serde(default)]
+    pub updated_at: Option<i64>, // Unix timestamp in milliseconds, when present
     pub last_used_model: String,
 }
 
@@ -50,6 +53,11 @@ impl CursorSession {
             .unwrap_or_else(|| self.metadata.name.clone())
     }
 
+    /// Whether the session's project is in the ignore list
+    pub fn is_ignored(&self) -> bool {
+        IgnoreList::current().is_ignored(&self.project_name(), self.cwd.as_deref())
+    }
+
     /// Get created timestamp as DateTime
     #[allow(dead_code)] // Helper method, may be used in future
     pub fn created_at(&self) -> chrono::DateTime<chrono::Utc> {
@@ -5,13 +5,22 @@
 /// - Watches ~/.cursor/chats for new session directories
 /// - Polls active sessions (from our database) using PRAGMA data_version
 /// - Only polls sessions updated in last hour (automatic pruning)
+/// - Converts only blobs appended since the last conversion (tracked by rowid)
 use crate::config::load_provider_config;
 use crate::database::with_connection_mut;
 use crate::events::{EventBus, SessionEventPayload};
-use crate::providers::cursor::{db, discover_sessions, get_db_path_for_session, scan_existing_sessions};
-use crate::providers::common::get_canonical_path;
+use crate::providers::canonical::validation::check_converted_file;
+use crate::providers::cursor::{
+    db, discover_sessions, get_db_path_for_session, load_session, scan_existing_sessions,
+    scanner, CursorSession,
+};
+use crate::providers::common::timestamps::TimestampNormalizer;
+use crate::providers::common::{
+    access, expand_home, file_watcher, get_canonical_path, FileWatcher,
+};
+use chrono::{DateTime, Utc};
 use crate::upload_queue::UploadQueue;
-use notify::{Config, RecommendedWatcher, RecursiveMode, Watcher};
+use notify::{Config, RecursiveMode};
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::sync::{mpsc, Arc, Mutex};
@@ -29,11 +38,45 @@ struct SessionTracker {
     db_path: PathBuf,
     last_data_version: i64,
     last_checked: SystemTime,
+    /// Conversion progress, None until the session has been converted by this watcher
+    progress: Option<ConversionProgress>,
+}
+
+impl SessionTracker {
+    fn new(session_id: &str, db_path: PathBuf) -> Self {
+        // Phase 1 Fix: Initialize with current data_version to prevent false positives
+        let initial_version = if db_path.exists() {
+            db::open_cursor_db(&db_path)
+                .and_then(|conn| db::get_data_version(&conn))
+                .unwrap_or(0)
+        } else {
+            0
+        };
+
+        SessionTracker {
+            session_id: session_id.to_string(),
+            db_path,
+            last_data_version: initial_version,
+            last_checked: SystemTime::now(),
+            progress: None,
+        }
+    }
+}
+
+/// How far a session's store.db has been converted to canonical JSONL
+#[derive(Debug, Clone, Copy)]
+struct ConversionProgress {
+    /// Highest blob rowid included in the canonical file
+    last_blob_rowid: i64,
+    /// Number of decoded messages converted so far (positions new messages for timestamps)
+    messages_converted: usize,
+    /// Latest main-thread timestamp written, which appended messages are normalized against
+    last_timestamp: Option<DateTime<Utc>>,
 }
 
 #[derive(Debug)]
 pub struct CursorWatcher {
-    _watcher: RecommendedWatcher,
+    _watcher: FileWatcher,
     _poll_thread: thread::JoinHandle<()>,
     is_running: Arc<Mutex<bool>>,
 }
@@ -43,6 +86,10 @@ pub struct CursorWatcher {
 pub struct CursorWatcherStatus {
     pub is_running: bool,
     pub active_sessions: usize,
+    /// Directory the operating system won't let GuideMode read
+    pub access_denied: Option<String>,
+    /// How to raise the system's file watch limit, when Cursor fell back to polling
+    pub watch_limit: Option<String>,
 }
 
 
@@ -63,8 +110,8 @@ impl CursorWatcher {
         }
 
         // Get base path from config
-        let base_path = shellexpand::tilde(&config.home_directory).to_string();
-        let base_path = Path::new(&base_path);
+        let base_path = expand_home(&config.home_directory);
+        let base_path = base_path.as_path();
 
         // Part 1: Run initial scan of existing sessions
         tracing::info!("📊 Scanning existing Cursor sessions...");
@@ -91,7 +138,9 @@ impl CursorWatcher {
         tracing::info!("📁 Watching Cursor chats directory: {}", chats_dir.display());
 
         let (tx, rx) = mpsc::channel();
-        let mut watcher = RecommendedWatcher::new(
+        let watcher = FileWatcher::new(
+            PROVIDER_ID,
+            base_path,
             tx,
             Config::default().with_poll_interval(Duration::from_secs(2)),
         )?;
@@ -105,9 +154,10 @@ impl CursorWatcher {
         let upload_queue_clone = upload_queue.clone();
         let event_bus_clone = event_bus.clone();
         let base_path_clone = base_path.to_path_buf();
+        let file_watcher = watcher.clone();
 
         let poll_thread = thread::spawn(move || {
-            Self::hybrid_event_loop(rx, is_running_clone, upload_queue_clone, event_bus_clone, base_path_clone);
+            Self::hybrid_event_loop(rx, file_watcher, is_running_clone, upload_queue_clone, event_bus_clone, base_path_clone);
         });
 
         Ok(CursorWatcher {
@@ -120,6 +170,7 @@ impl CursorWatcher {
     /// Hybrid event loop: handles both filesystem events and database polling
     fn hybrid_event_loop(
         rx: mpsc::Receiver<notify::Result<notify::Event>>,
+        file_watcher: FileWatcher,
         is_running: Arc<Mutex<bool>>,
         _upload_queue: Arc<UploadQueue>,
         event_bus: EventBus,
@@ -141,10 +192,21 @@ impl CursorWatcher {
                     if let Some(session_id) = Self::handle_filesystem_event(event) {
                         tracing::info!("🆕 New Cursor session detected: {}", session_id);
 
+                        // Sessions we already track only need their new blobs converted
+                        if let Some(tracker) = session_trackers.get_mut(&session_id) {
+                            if let Err(e) = Self::update_session(tracker, &event_bus, &base_path) {
+                                tracing::warn!("Failed to update session {}: {:?}", session_id, e);
+                            }
+                            continue;
+                        }
+
                         // Try to process immediately
                         match Self::process_new_session(&session_id, &event_bus, &base_path) {
-                            Ok(()) => {
+                            Ok((db_path, progress)) => {
                                 tracing::debug!("✅ Processed new session: {}", session_id);
+                                let mut tracker = SessionTracker::new(&session_id, db_path);
+                                tracker.progress = progress;
+                                session_trackers.insert(session_id, tracker);
                             }
                             Err(e) => {
                                 tracing::warn!("❌ Failed to process new session {}: {:?}", session_id, e);
@@ -153,7 +215,9 @@ impl CursorWatcher {
                     }
                 }
                 Ok(Err(e)) => {
-                    tracing::error!("Filesystem watch error: {:?}", e);
+                    if !file_watcher.handle_error(&e) {
+                        tracing::error!("Filesystem watch error: {:?}", e);
+                    }
                 }
                 Err(mpsc::RecvTimeoutError::Timeout) => {
                     // No filesystem events, continue to polling
@@ -190,12 +254,13 @@ impl CursorWatcher {
         None
     }
 
-    /// Process a newly detected session
+    /// Process a newly detected session; sessions of ignored projects are
+    /// tracked without being converted
     fn process_new_session(
         session_id: &str,
         event_bus: &EventBus,
         base_path: &Path,
-    ) -> Result<(), Box<dyn std::error::Error>> {
+    ) -> Result<(PathBuf, Option<ConversionProgress>), Box<dyn std::error::Error>> {
         // Re-discover sessions to find the new one
         let sessions = discover_sessions(base_path)?;
 
@@ -204,51 +269,133 @@ impl CursorWatcher {
             .find(|s| s.session_id == session_id)
             .ok_or_else(|| format!("Session {} not found after discovery", session_id))?;
 
-        // Use scanner logic to process single session
-        use crate::providers::cursor::converter::CursorMessageWithRaw;
-        use crate::providers::cursor::scanner;
-        
+        if session.is_ignored() {
+            return Ok((session.db_path, None));
+        }
+        let progress = Self::convert_full(&session, event_bus)?;
+        Ok((session.db_path, Some(progress)))
+    }
+
+    /// Convert every blob in a session and rewrite its canonical file
+    fn convert_full(
+        session: &CursorSession,
+        event_bus: &EventBus,
+    ) -> Result<ConversionProgress, Box<dyn std::error::Error>> {
+        let max_rowid = db::get_max_blob_rowid(&db::open_cursor_db(&session.db_path)?)?;
+        let (converted, blob_count) = scanner::convert_session(session, max_rowid)
+            .map_err(|e| e as Box<dyn std::error::Error>)?;
+
+        let progress = ConversionProgress {
+            last_blob_rowid: max_rowid,
+            messages_converted: blob_count,
+            last_timestamp: converted.last_timestamp(),
+        };
+
+        if converted.message_count() == 0 {
+            return Ok(progress); // No messages yet, skip
+        }
+
+        let canonical_path = converted
+            .persist()
+            .map_err(|e| e as Box<dyn std::error::Error>)?;
+
+        Self::publish_session_changed(session, canonical_path, event_bus)?;
 
+        Ok(progress)
+    }
+
+    /// Convert only blobs appended since `progress` and append them to the canonical file
+    fn convert_incremental(
+        session: &CursorSession,
+        progress: ConversionProgress,
+        max_rowid: i64,
+        event_bus: &EventBus,
+    ) -> Result<ConversionProgress, Box<dyn std::error::Error>> {
         let conn = db::open_cursor_db(&session.db_path)?;
-        let decoded_messages = db::get_decoded_messages(&conn)?;
-
-        let mut canonical_messages = Vec::new();
-        for (message_index, (_msg_id, raw_data, msg)) in decoded_messages.iter().enumerate() {
-            // Wrap message with raw data and session metadata for timestamp calculation
-            let msg_with_raw = CursorMessageWithRaw::new(
-                msg,
-                raw_data,
-                session.metadata.created_at,
-                message_index,
+        let decoded_messages =
+            db::get_decoded_messages_in_range(&conn, progress.last_blob_rowid, max_rowid)?;
+
+        let mut timestamps = TimestampNormalizer::resume(progress.last_timestamp);
+        let canonical_messages = scanner::convert_decoded_messages(
+            session,
+            &decoded_messages,
+            progress.messages_converted,
+            &mut timestamps,
+        );
+
+        if !canonical_messages.is_empty() {
+            let canonical_path = Self::canonical_path_for(session)?;
+            scanner::append_canonical_file(&canonical_path, &canonical_messages)?;
+            check_converted_file(PROVIDER_ID, &session.session_id, &canonical_path);
+
+            tracing::debug!(
+                "➕ Appended {} messages to Cursor session {}",
+                canonical_messages.len(),
+                session.session_id
             );
 
-            // Use split conversion to prevent UUID collisions
-            if let Ok(messages) = msg_with_raw.to_canonical_split() {
-                for mut canonical in messages {
-                    canonical.session_id = session.session_id.clone();
-                    if canonical.cwd.is_none() {
-                        canonical.cwd = session.cwd.clone();
-                    }
-                    canonical_messages.push(canonical);
-                }
-            }
+            Self::publish_session_changed(session, canonical_path, event_bus)?;
         }
 
-        if canonical_messages.is_empty() {
-            return Ok(()); // No messages yet, skip
+        Ok(ConversionProgress {
+            last_blob_rowid: max_rowid,
+            messages_converted: progress.messages_converted + decoded_messages.len(),
+            last_timestamp: timestamps.last_main(),
+        })
+    }
+
+    /// Bring a tracked session's canonical file up to date
+    ///
+    /// Appends new blobs when possible and falls back to a full rebuild when the
+    /// canonical file is missing, nothing has been converted yet, or the blob
+    /// table no longer extends what was converted (e.g. Cursor compacted it).
+    fn update_session(
+        tracker: &mut SessionTracker,
+        event_bus: &EventBus,
+        base_path: &Path,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let session = load_session(&tracker.db_path, base_path)?;
+        if session.is_ignored() {
+            return Ok(());
         }
+        let canonical_exists = Self::canonical_path_for(&session)?.exists();
 
-        // Get canonical path and write (use CWD if available)
-        let canonical_path = get_canonical_path(
-            PROVIDER_ID,
-            session.cwd.as_deref(),
-            &session.session_id,
-        )
-        .map_err(|e| -> Box<dyn std::error::Error> { Box::new(std::io::Error::other(e.to_string())) })?;
+        let max_rowid = {
+            let conn = db::open_cursor_db(&session.db_path)?;
+            db::get_max_blob_rowid(&conn)?
+        };
+
+        let progress = match tracker.progress {
+            Some(progress) if canonical_exists && max_rowid == progress.last_blob_rowid => {
+                tracing::debug!(
+                    "Session {} data_version changed but no new blobs, skipping",
+                    session.session_id
+                );
+                return Ok(());
+            }
+            Some(progress) if canonical_exists && max_rowid > progress.last_blob_rowid => {
+                Self::convert_incremental(&session, progress, max_rowid, event_bus)?
+            }
+            _ => {
+                tracing::info!("🔄 Rebuilding canonical file for Cursor session {}", session.session_id);
+                Self::convert_full(&session, event_bus)?
+            }
+        };
 
-        scanner::write_canonical_file(&canonical_path, &canonical_messages)?;
+        tracker.progress = Some(progress);
+        Ok(())
+    }
 
-        // Get file size and publish event
+    fn canonical_path_for(session: &CursorSession) -> Result<PathBuf, Box<dyn std::error::Error>> {
+        get_canonical_path(PROVIDER_ID, session.cwd.as_deref(), &session.session_id)
+            .map_err(|e| -> Box<dyn std::error::Error> { Box::new(std::io::Error::other(e.to_string())) })
+    }
+
+    fn publish_session_changed(
+        session: &CursorSession,
+        canonical_path: PathBuf,
+        event_bus: &EventBus,
+    ) -> Result<(), Box<dyn std::error::Error>> {
         let file_size = std::fs::metadata(&canonical_path)?.len();
 
         event_bus.publish(
@@ -288,51 +435,19 @@ impl CursorWatcher {
         session_trackers.retain(|id, _| active_ids.contains(id));
 
         // Poll each active session
-        for (session_id, canonical_path) in active_sessions {
+        for (session_id, _canonical_path) in active_sessions {
             // Get or create tracker
             let tracker = session_trackers.entry(session_id.clone()).or_insert_with(|| {
                 // Try to get DB path for this session
                 let db_path = get_db_path_for_session(&session_id, base_path).unwrap_or_default();
-
-                // Phase 1 Fix: Initialize with current data_version to prevent false positives
-                let initial_version = if db_path.exists() {
-                    db::open_cursor_db(&db_path)
-                        .and_then(|conn| db::get_data_version(&conn))
-                        .unwrap_or(0)
-                } else {
-                    0
-                };
-
-                SessionTracker {
-                    session_id: session_id.clone(),
-                    db_path,
-                    last_data_version: initial_version,
-                    last_checked: SystemTime::now(),
-                }
+                SessionTracker::new(&session_id, db_path)
             });
 
             // Check for changes using PRAGMA data_version
             match Self::check_session_changed(tracker) {
                 Ok(true) => {
-                    // Phase 3 Enhancement: Verify content actually changed before reprocessing
-                    // Prevents redundant processing when only SQLite metadata changed
-                    let should_reprocess = match Self::verify_content_changed(&canonical_path, &tracker.db_path) {
-                        Ok(changed) => changed,
-                        Err(e) => {
-                            tracing::debug!("Could not verify content change for {}: {:?}, will reprocess", session_id, e);
-                            true // Default to reprocessing if verification fails
-                        }
-                    };
-
-                    if should_reprocess {
-                        tracing::info!("🔄 Session {} has content changes, reprocessing", session_id);
-
-                        // Reprocess session
-                        if let Err(e) = Self::process_new_session(&session_id, event_bus, base_path) {
-                            tracing::warn!("Failed to reprocess session {}: {:?}", session_id, e);
-                        }
-                    } else {
-                        tracing::debug!("Session {} data_version changed but content unchanged, skipping", session_id);
+                    if let Err(e) = Self::update_session(tracker, event_bus, base_path) {
+                        tracing::warn!("Failed to update session {}: {:?}", session_id, e);
                     }
                 }
                 Ok(false) => {
@@ -395,31 +510,6 @@ impl CursorWatcher {
         }
     }
 
-    /// Verify content actually changed by comparing message count
-    /// Returns true if content changed, false if unchanged
-    fn verify_content_changed(
-        canonical_path: &str,
-        db_path: &Path,
-    ) -> Result<bool, Box<dyn std::error::Error>> {
-        // If canonical file doesn't exist, definitely need to create it
-        if !std::path::Path::new(canonical_path).exists() {
-            return Ok(true);
-        }
-
-        // Count messages in Cursor database
-        let conn = db::open_cursor_db(db_path)?;
-        let db_message_count = db::get_decoded_messages(&conn)?.len();
-
-        // Count lines in canonical JSONL file (each line = 1 message)
-        let canonical_message_count = std::fs::read_to_string(canonical_path)?
-            .lines()
-            .filter(|line| !line.trim().is_empty())
-            .count();
-
-        // If counts differ, content changed
-        Ok(db_message_count != canonical_message_count)
-    }
-
     pub fn stop(&self) -> Result<(), String> {
         let mut is_running = self.is_running.lock().map_err(|e| e.to_string())?;
         *is_running = false;
@@ -437,6 +527,8 @@ impl CursorWatcher {
         Ok(CursorWatcherStatus {
             is_running,
             active_sessions,
+            access_denied: access::denied_path(PROVIDER_ID),
+            watch_limit: file_watcher::watch_limit_hint(PROVIDER_ID),
         })
     }
 }
@@ -0,0 +1,292 @@
+//! Detection of installed agents for first-run onboarding
+//!
+//! Each supported provider is probed three ways: its data directory, its CLI
+//! on PATH (plus the install locations GUI apps often lack on PATH) and the
+//! most recent change to its session files. An agent counts as installed if
+//! either its directory or its binary is found.
+
+use chrono::{DateTime, Utc};
+use rayon::prelude::*;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant, SystemTime};
+
+const VERSION_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How deep session directories are searched for recent activity
+/// (Codex nests sessions as sessions/YYYY/MM/DD/rollout.jsonl)
+const ACTIVITY_SCAN_DEPTH: usize = 4;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DetectedAgent {
+    pub provider_id: String,
+    pub installed: bool,
+    pub binary_path: Option<String>,
+    pub version: Option<String>,
+    /// Home directory to configure the provider with, `~/`-relative when under home
+    pub home_directory: Option<String>,
+    pub has_sessions: bool,
+    pub last_activity: Option<DateTime<Utc>>,
+}
+
+struct AgentSpec {
+    provider_id: &'static str,
+    binaries: &'static [&'static str],
+    /// Directory under the provider's home that holds its sessions
+    sessions_dir: &'static str,
+}
+
+const AGENTS: &[AgentSpec] = &[
+    AgentSpec {
+        provider_id: "claude-code",
+        binaries: &["claude"],
+        sessions_dir: "projects",
+    },
+    AgentSpec {
+        provider_id: "github-copilot",
+        binaries: &["copilot"],
+        sessions_dir: "session-state",
+    },
+    AgentSpec {
+        provider_id: "opencode",
+        binaries: &["opencode"],
+        sessions_dir: "storage",
+    },
+    AgentSpec {
+        provider_id: "codex",
+        binaries: &["codex"],
+        sessions_dir: "sessions",
+    },
+    AgentSpec {
+        provider_id: "gemini-code",
+        binaries: &["gemini"],
+        sessions_dir: "tmp",
+    },
+    AgentSpec {
+        provider_id: "cursor",
+        binaries: &["cursor-agent"],
+        sessions_dir: "chats",
+    },
+];
+
+/// Probe every supported provider
+pub fn detect_installed_agents() -> Vec<DetectedAgent> {
+    let home = dirs::home_dir();
+    AGENTS
+        .par_iter()
+        .map(|spec| detect(spec, home.as_deref()))
+        .collect()
+}
+
+/// The provider's data directory, if it exists where the provider keeps it by
+/// default
+pub fn default_home_directory(provider_id: &str) -> Option<PathBuf> {
+    let home = dirs::home_dir()?;
+    home_candidates(provider_id, &home)
+        .into_iter()
+        .find(|dir| dir.is_dir())
+}
+
+/// Version of a provider's CLI, if it's installed
+pub fn cli_version(provider_id: &str) -> Option<String> {
+    let spec = AGENTS.iter().find(|spec| spec.provider_id == provider_id)?;
+    let home = dirs::home_dir();
+    spec.binaries
+        .iter()
+        .find_map(|name| find_binary(name, home.as_deref()))
+        .as_deref()
+        .and_then(binary_version)
+}
+
+fn detect(spec: &AgentSpec, home: Option<&Path>) -> DetectedAgent {
+    let home_dir = home.and_then(|home| {
+        home_candidates(spec.provider_id, home)
+            .into_iter()
+            .find(|dir| dir.is_dir())
+    });
+    let binary = spec
+        .binaries
+        .iter()
+        .find_map(|name| find_binary(name, home));
+    let version = binary.as_deref().and_then(binary_version);
+    let last_activity = home_dir
+        .as_ref()
+        .and_then(|dir| last_activity(&dir.join(spec.sessions_dir)));
+
+    DetectedAgent {
+        provider_id: spec.provider_id.to_string(),
+        installed: home_dir.is_some() || binary.is_some(),
+        binary_path: binary.map(|path| path.to_string_lossy().to_string()),
+        version,
+        home_directory: home_dir.map(|dir| display_path(&dir, home)),
+        has_sessions: last_activity.is_some(),
+        last_activity,
+    }
+}
+
+/// Where each provider keeps its data, most likely first
+fn home_candidates(provider_id: &str, home: &Path) -> Vec<PathBuf> {
+    match provider_id {
+        "claude-code" => vec![home.join(".claude")],
+        "github-copilot" => vec![home.join(".copilot")],
+        "codex" => vec![home.join(".codex")],
+        "gemini-code" => vec![home.join(".gemini")],
+        "cursor" => vec![home.join(".cursor")],
+        "opencode" => {
+            let mut candidates = Vec::new();
+            if let Ok(xdg_data) = std::env::var("XDG_DATA_HOME") {
+                candidates.push(PathBuf::from(xdg_data).join("opencode"));
+            }
+            candidates.push(home.join(".local/share/opencode"));
+            if let Some(data_dir) = dirs::data_local_dir() {
+                candidates.push(data_dir.join("opencode"));
+            }
+            candidates
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// Find `name` on PATH or in common install locations that apps launched
+/// from the desktop don't inherit on PATH
+fn find_binary(name: &str, home: Option<&Path>) -> Option<PathBuf> {
+    let mut dirs: Vec<PathBuf> = std::env::var_os("PATH")
+        .map(|path| std::env::split_paths(&path).collect())
+        .unwrap_or_default();
+    if let Some(home) = home {
+        dirs.extend(
+            [
+                ".local/bin",
+                ".npm-global/bin",
+                ".bun/bin",
+                ".volta/bin",
+                ".claude/local",
+            ]
+            .iter()
+            .map(|dir| home.join(dir)),
+        );
+    }
+    dirs.extend(["/opt/homebrew/bin", "/usr/local/bin"].map(PathBuf::from));
+
+    let file_names: Vec<String> = if cfg!(windows) {
+        ["exe", "cmd"]
+            .iter()
+            .map(|ext| format!("{}.{}", name, ext))
+            .collect()
+    } else {
+        vec![name.to_string()]
+    };
+
+    dirs.iter()
+        .flat_map(|dir| file_names.iter().map(move |file| dir.join(file)))
+        .find(|path| path.is_file())
+}
+
+/// Run `binary --version`, giving up after `VERSION_TIMEOUT`
+fn binary_version(binary: &Path) -> Option<String> {
+    let mut child = Command::new(binary)
+        .arg("--version")
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .ok()?;
+
+    let started = Instant::now();
+    loop {
+        match child.try_wait().ok()? {
+            Some(_) => break,
+            None if started.elapsed() > VERSION_TIMEOUT => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return None;
+            }
+            None => std::thread::sleep(Duration::from_millis(50)),
+        }
+    }
+
+    let output = child.wait_with_output().ok()?;
+    parse_version(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// First version-looking token of `--version` output, such as `1.0.98` in
+/// "1.0.98 (Claude Code)" or `0.39.0` in "codex-cli 0.39.0"
+fn parse_version(output: &str) -> Option<String> {
+    output
+        .split_whitespace()
+        .map(|token| token.trim_start_matches('v').trim_end_matches([',', ')']))
+        .find(|token| {
+            token.contains('.')
+                && token.starts_with(|c: char| c.is_ascii_digit())
+                && token
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-')
+        })
+        .map(str::to_string)
+}
+
+/// Most recent modification among the files under `dir`
+fn last_activity(dir: &Path) -> Option<DateTime<Utc>> {
+    walkdir::WalkDir::new(dir)
+        .max_depth(ACTIVITY_SCAN_DEPTH)
+        .into_iter()
+        .flatten()
+        .filter(|entry| entry.file_type().is_file())
+        .filter_map(|entry| entry.metadata().ok()?.modified().ok())
+        .max()
+        .map(|modified: SystemTime| modified.into())
+}
+
+/// `path` as `~/...` when it's under home, matching the frontend's defaults
+fn display_path(path: &Path, home: Option<&Path>) -> String {
+    match home.and_then(|home| path.strip_prefix(home).ok()) {
+        Some(relative) => format!("~/{}", relative.to_string_lossy().replace('\\', "/")),
+        None => path.to_string_lossy().to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_parse_version() {
+        assert_eq!(
+            parse_version("1.0.98 (Claude Code)\n").as_deref(),
+            Some("1.0.98")
+        );
+        assert_eq!(parse_version("codex-cli 0.39.0").as_deref(), Some("0.39.0"));
+        assert_eq!(
+            parse_version("v2.1.0-beta.1").as_deref(),
+            Some("2.1.0-beta.1")
+        );
+        assert_eq!(parse_version("usage: gemini [options]"), None);
+    }
+
+    #[test]
+    fn test_detects_agent_from_its_directory() {
+        let home = tempfile::tempdir().unwrap();
+        let session = home.path().join(".claude/projects/-Users-me-app/s1.jsonl");
+        fs::create_dir_all(session.parent().unwrap()).unwrap();
+        fs::write(&session, "{}").unwrap();
+        fs::create_dir_all(home.path().join(".codex")).unwrap();
+
+        let spec = |id: &str| AGENTS.iter().find(|spec| spec.provider_id == id).unwrap();
+
+        let claude = detect(spec("claude-code"), Some(home.path()));
+        assert!(claude.installed && claude.has_sessions);
+        assert_eq!(claude.home_directory.as_deref(), Some("~/.claude"));
+        assert!(claude.last_activity.is_some());
+
+        // Installed but never used
+        let codex = detect(spec("codex"), Some(home.path()));
+        assert!(codex.installed && !codex.has_sessions);
+
+        let gemini = detect(spec("gemini-code"), Some(home.path()));
+        assert_eq!(gemini.home_directory, None);
+        assert!(!gemini.has_sessions);
+    }
+}
@@ -0,0 +1,138 @@
+//! Checkpoint and history rewrite handling for Gemini sessions
+//!
+//! Gemini CLI keeps each session as a single JSON document that it rewrites in
+//! place. Restoring a checkpoint (or resuming a saved chat) rewinds the history
+//! and then appends the restored messages again, so the same message IDs can
+//! appear more than once and messages from the abandoned branch may remain in
+//! between. Converting that array as-is double-counts messages.
+//!
+//! Reconciliation walks the messages in order and treats a repeated ID as a
+//! rewind point: everything recorded from the earlier copy onwards belongs to
+//! the abandoned branch and is replaced by what follows.
+
+use super::parser::GeminiMessage;
+use std::collections::HashMap;
+
+/// Messages with checkpoint rewinds resolved
+#[derive(Debug, Clone)]
+pub struct ReconciledMessages {
+    /// Messages on the active branch, in order
+    pub messages: Vec<GeminiMessage>,
+    /// Number of raw messages that were superseded or abandoned
+    pub dropped: usize,
+}
+
+impl ReconciledMessages {
+    /// IDs of the active messages, in order
+    pub fn message_ids(&self) -> Vec<String> {
+        self.messages.iter().map(|m| m.id.clone()).collect()
+    }
+}
+
+/// Resolve checkpoint rewinds so every message ID appears once
+pub fn reconcile_messages(messages: &[GeminiMessage]) -> ReconciledMessages {
+    let mut active: Vec<GeminiMessage> = Vec::with_capacity(messages.len());
+    let mut positions: HashMap<&str, usize> = HashMap::new();
+
+    for message in messages {
+        if let Some(&position) = positions.get(message.id.as_str()) {
+            // Rewind to the earlier copy and drop the branch recorded after it
+            for abandoned in &active[position..] {
+                positions.remove(abandoned.id.as_str());
+            }
+            active.truncate(position);
+        }
+
+        positions.insert(message.id.as_str(), active.len());
+        active.push(message.clone());
+    }
+
+    ReconciledMessages {
+        dropped: messages.len() - active.len(),
+        messages: active,
+    }
+}
+
+/// Check whether a session's history was rewritten since it was last converted
+///
+/// Appending messages is a normal update; anything else (messages removed,
+/// reordered or replaced by a restored checkpoint) is a rewrite.
+pub fn is_history_rewrite(previous_ids: &[String], current_ids: &[String]) -> bool {
+    !current_ids.starts_with(previous_ids)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(id: &str, content: &str) -> GeminiMessage {
+        GeminiMessage {
+            id: id.to_string(),
+            timestamp: "2025-10-11T00:00:00Z".to_string(),
+            message_type: "user".to_string(),
+            content: content.to_string(),
+            attachments: Vec::new(),
+            tool_calls: None,
+            thoughts: None,
+            tokens: None,
+            model: None,
+        }
+    }
+
+    fn ids(reconciled: &ReconciledMessages) -> Vec<&str> {
+        reconciled.messages.iter().map(|m| m.id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_linear_history_unchanged() {
+        let messages = vec![message("a", "1"), message("b", "2"), message("c", "3")];
+        let reconciled = reconcile_messages(&messages);
+
+        assert_eq!(ids(&reconciled), vec!["a", "b", "c"]);
+        assert_eq!(reconciled.dropped, 0);
+    }
+
+    #[test]
+    fn test_checkpoint_restore_drops_abandoned_branch() {
+        // Restored to after "b", abandoning "c", then continued with "d"
+        let messages = vec![
+            message("a", "1"),
+            message("b", "2"),
+            message("c", "abandoned"),
+            message("a", "1"),
+            message("b", "2 (restored)"),
+            message("d", "4"),
+        ];
+        let reconciled = reconcile_messages(&messages);
+
+        assert_eq!(ids(&reconciled), vec!["a", "b", "d"]);
+        assert_eq!(reconciled.messages[1].content, "2 (restored)");
+        assert_eq!(reconciled.dropped, 3);
+    }
+
+    #[test]
+    fn test_partial_replay_keeps_earlier_history() {
+        let messages = vec![
+            message("a", "1"),
+            message("b", "2"),
+            message("c", "3"),
+            message("b", "2"),
+        ];
+        let reconciled = reconcile_messages(&messages);
+
+        assert_eq!(ids(&reconciled), vec!["a", "b"]);
+    }
+
+    #[test]
+    fn test_is_history_rewrite() {
+        let previous = vec!["a".to_string(), "b".to_string()];
+
+        assert!(!is_history_rewrite(
+            &previous,
+            &["a".to_string(), "b".to_string(), "c".to_string()]
+        ));
+        assert!(is_history_rewrite(&previous, &["a".to_string(), "c".to_string()]));
+        assert!(is_history_rewrite(&previous, &["a".to_string()]));
+        assert!(!is_history_rewrite(&[], &previous));
+    }
+}
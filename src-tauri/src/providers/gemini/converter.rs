@@ -1,12 +1,19 @@
 use crate::providers::canonical::{
     converter::ToCanonical, CanonicalMessage, ContentBlock, ContentValue, MessageContent,
-    MessageType, TokenUsage,
+    MessageType, TokenUsage, CANONICAL_SCHEMA_VERSION,
 };
-use crate::providers::common::get_canonical_path;
-use super::parser::{GeminiMessage, GeminiSession};
+use crate::providers::canonical::media::{block_from_base64, block_from_url};
+use crate::ignore_list::IgnoreList;
+use crate::providers::common::quarantine::QuarantinedLine;
+use crate::providers::common::{
+    annotate_model, extract_session_id_from_filename, CanonicalWriter, ConvertedFile,
+};
+use super::checkpoint::{reconcile_messages, ReconciledMessages};
+use super::parser::{GeminiAttachment, GeminiMessage, GeminiSession};
 use anyhow::{Context, Result};
 use serde_json::Value;
 use std::fs;
+use std::io::BufReader;
 use std::path::{Path, PathBuf};
 
 /// Implement ToCanonical for GeminiMessage
@@ -31,12 +38,13 @@ impl ToCanonical for GeminiMessage {
         };
 
         // Build content value - combine thoughts and text into structured content if needed
-        let content = if self.thoughts.is_some() && !self.thoughts.as_ref().unwrap().is_empty() {
-            // Message has thoughts - create structured content with thinking blocks
+        let has_thoughts = self.thoughts.as_ref().is_some_and(|t| !t.is_empty());
+        let content = if has_thoughts || !self.attachments.is_empty() {
+            // Message has thoughts or attachments - create structured content
             let mut blocks = Vec::new();
 
             // Add thinking blocks for each thought
-            for thought in self.thoughts.as_ref().unwrap() {
+            for thought in self.thoughts.iter().flatten() {
                 blocks.push(ContentBlock::Thinking {
                     thinking: format!("{}: {}", thought.subject, thought.description),
                 });
@@ -49,6 +57,9 @@ impl ToCanonical for GeminiMessage {
                 });
             }
 
+            // Add images and files (hashed, never the raw data)
+            blocks.extend(self.attachments.iter().map(attachment_block));
+
             ContentValue::Structured(blocks)
         } else if !self.content.is_empty() {
             // No thoughts, just text
@@ -62,7 +73,12 @@ impl ToCanonical for GeminiMessage {
         // In Claude's model, thoughts and tool calls are part of output
         let usage = self.tokens.as_ref().map(|tokens| TokenUsage {
             input_tokens: Some(tokens.input),
-            output_tokens: Some(tokens.output + tokens.thoughts + tokens.tool),
+            output_tokens: Some(
+                tokens
+                    .output
+                    .saturating_add(tokens.thoughts)
+                    .saturating_add(tokens.tool),
+            ),
             cache_creation_input_tokens: None,
             cache_read_input_tokens: Some(tokens.cached),
         });
@@ -75,6 +91,7 @@ impl ToCanonical for GeminiMessage {
         });
 
         Ok(Some(CanonicalMessage {
+            schema_version: CANONICAL_SCHEMA_VERSION,
             uuid: self.id.clone(),
             timestamp: self.timestamp.clone(),
             message_type,
@@ -108,19 +125,36 @@ impl ToCanonical for GeminiMessage {
     }
 }
 
+/// Convert an inline or file attachment part to a canonical block
+fn attachment_block(attachment: &GeminiAttachment) -> ContentBlock {
+    match attachment {
+        GeminiAttachment::Inline { mime_type, data } => {
+            block_from_base64(mime_type, data, None, None)
+        }
+        GeminiAttachment::File {
+            mime_type,
+            file_uri,
+        } => block_from_url(file_uri, mime_type.as_deref(), None),
+    }
+}
+
 /// Convert a GeminiSession to canonical JSONL
 ///
 /// This handles:
 /// - Converting each message to canonical format
 /// - Extracting tool calls into separate tool_use/tool_result messages
 /// - Populating session_id and cwd for all messages
+///
+/// Messages are reconciled first, so a history rewritten by a checkpoint
+/// restore converts to the active branch only.
 pub fn convert_session_to_canonical(
     session: &GeminiSession,
     cwd: Option<String>,
 ) -> Result<Vec<CanonicalMessage>> {
     let mut canonical_messages = Vec::new();
+    let reconciled = reconcile_messages(&session.messages);
 
-    for message in &session.messages {
+    for message in &reconciled.messages {
         // First, handle tool calls if present
         if let Some(ref tool_calls) = message.tool_calls {
             for tool_call in tool_calls {
@@ -132,6 +166,7 @@ pub fn convert_session_to_canonical(
                 };
 
                 let tool_use_msg = CanonicalMessage {
+                    schema_version: CANONICAL_SCHEMA_VERSION,
                     uuid: tool_call.id.clone(),
                     timestamp: message.timestamp.clone(),
                     message_type: MessageType::Assistant,
@@ -200,6 +235,7 @@ pub fn convert_session_to_canonical(
                     };
 
                     let tool_result_msg = CanonicalMessage {
+                        schema_version: CANONICAL_SCHEMA_VERSION,
                         uuid: format!("{}_result", tool_call.id),
                         timestamp: message.timestamp.clone(),
                         message_type: MessageType::User,  // Tool results are USER messages
@@ -255,20 +291,31 @@ pub fn convert_session_to_canonical(
         }
     }
 
+    canonical_messages.iter_mut().for_each(annotate_model);
+
     Ok(canonical_messages)
 }
 
-/// Convert Gemini JSON file to canonical JSONL and cache it
-///
-/// This is the shared conversion function used by both the watcher and scanner.
-/// It ensures consistent behavior across live monitoring and historical rescans.
+/// Result of converting a Gemini session file
+#[derive(Debug, Clone)]
+pub struct ConvertedSession {
+    /// Path to the cached canonical JSONL file
+    pub canonical_path: PathBuf,
+    /// IDs of the messages on the active branch, in order
+    pub message_ids: Vec<String>,
+    /// Raw messages dropped while reconciling checkpoint rewrites
+    pub dropped_messages: usize,
+}
+
+/// Convert a Gemini JSON file to canonical JSONL and cache it
 ///
-/// # Arguments
-/// * `json_file_path` - Path to the original Gemini session JSON file
-/// * `session_id` - Session identifier (from filename)
+/// This is the conversion used by both the watcher and scanner, so live
+/// monitoring and historical rescans produce the same output. The session ID
+/// comes from the filename, not the sessionId field in the JSON.
 ///
-/// # Returns
-/// The path to the cached canonical JSONL file
+/// The canonical file is always rewritten in full, so a rewritten history
+/// replaces the previous output instead of appending to it. Sessions of
+/// ignored projects aren't converted and give None.
 ///
 /// # Errors
 /// Returns an error if:
@@ -276,47 +323,86 @@ pub fn convert_session_to_canonical(
 /// - JSON parsing fails
 /// - Canonical conversion fails
 /// - File write fails
-pub fn convert_to_canonical_file(
-    json_file_path: &Path,
-    session_id: &str,
-) -> Result<PathBuf> {
-    const PROVIDER_ID: &str = "gemini-code";
+pub fn convert_session_file(json_file_path: &Path) -> Result<Option<ConvertedSession>> {
+    let (session, skipped_messages) = read_session(json_file_path)?;
+    let cwd = infer_cwd_from_session(&session);
+    if IgnoreList::current().is_ignored(&project_name(&session, cwd.as_deref()), cwd.as_deref()) {
+        return Ok(None);
+    }
+
+    let (converted, reconciled) =
+        convert_reconciled(json_file_path, &session, skipped_messages, cwd)?;
+    let canonical_path = converted
+        .persist()
+        .map_err(|e| anyhow::anyhow!("Failed to write canonical JSONL: {}", e))?;
+
+    Ok(Some(ConvertedSession {
+        canonical_path,
+        message_ids: reconciled.message_ids(),
+        dropped_messages: reconciled.dropped,
+    }))
+}
+
+/// Convert a Gemini JSON file to canonical JSONL without caching it
+pub fn convert_file(json_file_path: &Path) -> Result<ConvertedFile> {
+    let (session, skipped_messages) = read_session(json_file_path)?;
+    let cwd = infer_cwd_from_session(&session);
+    convert_reconciled(json_file_path, &session, skipped_messages, cwd)
+        .map(|(converted, _)| converted)
+}
 
-    // Read the original Gemini JSON file
-    let content = fs::read_to_string(json_file_path)
+/// The project a session belongs to: its cwd's last component, or the
+/// shortened project hash when the cwd couldn't be inferred
+pub fn project_name(session: &GeminiSession, cwd: Option<&str>) -> String {
+    if let Some(cwd_path) = cwd {
+        Path::new(cwd_path)
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or(&session.project_hash)
+            .to_string()
+    } else {
+        format!("gemini-{}", &session.project_hash[..8])
+    }
+}
+
+/// Parse the original Gemini JSON file straight from disk
+fn read_session(json_file_path: &Path) -> Result<(GeminiSession, Vec<QuarantinedLine>)> {
+    let file = fs::File::open(json_file_path)
         .context(format!("Failed to read Gemini JSON file: {:?}", json_file_path))?;
+    GeminiSession::from_reader_lenient(BufReader::new(file))
+        .context("Failed to parse Gemini session JSON")
+}
 
-    // Parse the Gemini session
-    let session = GeminiSession::from_json(&content)
-        .context("Failed to parse Gemini session JSON")?;
+fn convert_reconciled(
+    json_file_path: &Path,
+    session: &GeminiSession,
+    skipped_messages: Vec<QuarantinedLine>,
+    cwd: Option<String>,
+) -> Result<(ConvertedFile, ReconciledMessages)> {
+    const PROVIDER_ID: &str = "gemini-code";
+    let session_id = extract_session_id_from_filename(json_file_path);
 
-    // Try to infer CWD from message content using shared utility
-    let cwd = infer_cwd_from_session(&session);
+    let reconciled = reconcile_messages(&session.messages);
 
     // Convert to canonical format
-    let canonical_messages = convert_session_to_canonical(&session, cwd.clone())?;
+    let canonical_messages = convert_session_to_canonical(session, cwd.clone())?;
 
-    // Serialize each message to JSONL
-    let mut canonical_lines = Vec::new();
+    // Write each message out as JSONL
+    let mut writer = CanonicalWriter::for_source(PROVIDER_ID, json_file_path)
+        .context("Failed to create canonical JSONL")?;
+    for skipped in skipped_messages {
+        writer.quarantine(skipped.line_number, &skipped.raw_line, skipped.error);
+    }
     for (line_num, msg) in canonical_messages.iter().enumerate() {
-        let line = serde_json::to_string(msg)
+        writer
+            .write(msg)
             .context(format!("Failed to serialize canonical message {} for session {}", line_num, session_id))?;
-        canonical_lines.push(line);
     }
 
-    // Join canonical lines into content
-    let canonical_content = canonical_lines.join("\n");
-
-    // Get project-organized canonical path using inferred CWD
-    // Uses ~/.guidemode/sessions/{provider}/{project}/{session_id}.jsonl
-    let canonical_path = get_canonical_path(PROVIDER_ID, cwd.as_deref(), session_id)
-        .map_err(|e| anyhow::anyhow!("Failed to get canonical path: {}", e))?;
-
-    // Write to project-organized path
-    fs::write(&canonical_path, canonical_content)
-        .context(format!("Failed to write canonical JSONL to {:?}", canonical_path))?;
-
-    Ok(canonical_path)
+    // Cached under ~/.guidemode/sessions/{provider}/{project}/{session_id}.jsonl
+    // once persisted, using the inferred CWD
+    let converted = ConvertedFile::new(PROVIDER_ID, session_id, cwd, writer);
+    Ok((converted, reconciled))
 }
 
 /// Infer working directory from Gemini session messages
@@ -339,6 +425,7 @@ mod tests {
             timestamp: "2025-01-01T00:00:00.000Z".to_string(),
             message_type: "user".to_string(),
             content: "Hello".to_string(),
+            attachments: Vec::new(),
             tool_calls: None,
             thoughts: None,
             tokens: None,
@@ -364,6 +451,7 @@ mod tests {
             timestamp: "2025-01-01T00:00:01.000Z".to_string(),
             message_type: "gemini".to_string(),
             content: "World".to_string(),
+            attachments: Vec::new(),
             tool_calls: None,
             thoughts: None,
             tokens: Some(GeminiTokenUsage {
@@ -389,6 +477,12 @@ mod tests {
         // output_tokens should include output + thoughts + tool
         assert_eq!(usage.output_tokens, Some(65)); // 50 + 10 + 5
         assert_eq!(usage.cache_read_input_tokens, Some(75));
+
+        // Corrupt counts saturate rather than overflow
+        let mut msg = msg;
+        msg.tokens.as_mut().unwrap().thoughts = u32::MAX;
+        let usage = msg.to_canonical().unwrap().unwrap().message.usage.unwrap();
+        assert_eq!(usage.output_tokens, Some(u32::MAX));
     }
 
     #[test]
@@ -403,6 +497,7 @@ mod tests {
                 timestamp: "2025-01-01T00:00:05.000Z".to_string(),
                 message_type: "gemini".to_string(),
                 content: String::new(),
+                attachments: Vec::new(),
                 tool_calls: Some(vec![ToolCall {
                     id: "call-1".to_string(),
                     name: "shell".to_string(),
@@ -478,6 +573,7 @@ mod tests {
             timestamp: "2025-01-01T00:00:00.000Z".to_string(),
             message_type: "gemini".to_string(),
             content: "Let me analyze this.".to_string(),
+            attachments: Vec::new(),
             tool_calls: None,
             thoughts: Some(vec![
                 Thought {
@@ -541,6 +637,7 @@ mod tests {
             timestamp: "2025-01-01T00:00:00.000Z".to_string(),
             message_type: "gemini".to_string(),
             content: String::new(), // No text content, only thoughts
+            attachments: Vec::new(),
             tool_calls: None,
             thoughts: Some(vec![Thought {
                 subject: "Thinking".to_string(),
@@ -568,4 +665,74 @@ mod tests {
             _ => panic!("Expected structured content"),
         }
     }
+
+    #[test]
+    fn test_multipart_content_with_image() {
+        let msg: GeminiMessage = serde_json::from_value(serde_json::json!({
+            "id": "msg-1",
+            "timestamp": "2025-01-01T00:00:00.000Z",
+            "type": "user",
+            "content": [
+                {"text": "Describe "},
+                {"text": "this image"},
+                {"inlineData": {"mimeType": "image/png", "data": "aGVsbG8="}},
+                {"fileData": {"mimeType": "application/pdf", "fileUri": "/tmp/missing/spec.pdf"}}
+            ]
+        }))
+        .unwrap();
+
+        assert_eq!(msg.content, "Describe this image");
+        assert_eq!(msg.attachments.len(), 2);
+
+        let canonical = msg.to_canonical().unwrap().unwrap();
+        match canonical.message.content {
+            ContentValue::Structured(blocks) => {
+                assert_eq!(blocks.len(), 3);
+                assert!(matches!(&blocks[0], ContentBlock::Text { text } if text == "Describe this image"));
+                assert!(matches!(
+                    &blocks[1],
+                    ContentBlock::Image { media_type, hash: Some(_), .. } if media_type == "image/png"
+                ));
+                assert!(matches!(
+                    &blocks[2],
+                    ContentBlock::Attachment { name: Some(name), path: Some(_), .. } if name == "spec.pdf"
+                ));
+            }
+            _ => panic!("Expected structured content"),
+        }
+    }
+
+    #[test]
+    fn test_rewritten_history_not_duplicated() {
+        let text_message = |id: &str, content: &str| GeminiMessage {
+            id: id.to_string(),
+            timestamp: "2025-01-01T00:00:00.000Z".to_string(),
+            message_type: "user".to_string(),
+            content: content.to_string(),
+            attachments: Vec::new(),
+            tool_calls: None,
+            thoughts: None,
+            tokens: None,
+            model: None,
+        };
+
+        // Checkpoint restored after msg-1: msg-2 is abandoned, msg-1 replayed
+        let session = GeminiSession {
+            session_id: "session-123".to_string(),
+            project_hash: "abc123".to_string(),
+            start_time: "2025-01-01T00:00:00.000Z".to_string(),
+            last_updated: "2025-01-01T00:00:10.000Z".to_string(),
+            messages: vec![
+                text_message("msg-1", "first"),
+                text_message("msg-2", "abandoned"),
+                text_message("msg-1", "first"),
+                text_message("msg-3", "continued"),
+            ],
+        };
+
+        let canonical = convert_session_to_canonical(&session, None).unwrap();
+        let uuids: Vec<&str> = canonical.iter().map(|m| m.uuid.as_str()).collect();
+
+        assert_eq!(uuids, vec!["msg-1", "msg-3"]);
+    }
 }
@@ -1,6 +1,9 @@
+pub mod checkpoint;
 pub mod converter;
 pub mod parser;
 pub mod registry;
 pub mod scanner;
 pub mod utils;
 pub mod watcher;
+
+pub use converter::convert_file;
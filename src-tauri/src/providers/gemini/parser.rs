@@ -1,3 +1,4 @@
+use crate::providers::common::quarantine::QuarantinedLine;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -37,6 +38,7 @@ pub struct ToolCall {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(from = "RawGeminiMessage")]
 pub struct GeminiMessage {
     pub id: String,
     pub timestamp: String,
@@ -44,8 +46,13 @@ pub struct GeminiMessage {
     #[serde(rename = "type")]
     pub message_type: String, // "user" or "gemini"
 
+    /// Text content (text parts of multi-part content are concatenated)
     pub content: String,
 
+    /// Inline data and file references from multi-part content
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub attachments: Vec<GeminiAttachment>,
+
     #[serde(rename = "toolCalls", skip_serializing_if = "Option::is_none")]
     pub tool_calls: Option<Vec<ToolCall>>,
 
@@ -59,6 +66,94 @@ pub struct GeminiMessage {
     pub model: Option<String>,
 }
 
+/// Non-text part of a Gemini message
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum GeminiAttachment {
+    /// `inlineData` part with base64 content
+    Inline { mime_type: String, data: String },
+    /// `fileData` part referencing a file by URI
+    File {
+        mime_type: Option<String>,
+        file_uri: String,
+    },
+}
+
+/// Message as stored on disk, where `content` may be a string, a single part
+/// or a list of parts
+#[derive(Deserialize)]
+struct RawGeminiMessage {
+    id: String,
+    timestamp: String,
+    #[serde(rename = "type")]
+    message_type: String,
+    #[serde(default)]
+    content: serde_json::Value,
+    #[serde(rename = "toolCalls")]
+    tool_calls: Option<Vec<ToolCall>>,
+    thoughts: Option<Vec<Thought>>,
+    tokens: Option<TokenUsage>,
+    model: Option<String>,
+    // Already-split attachments (when re-reading serialized messages)
+    #[serde(default)]
+    attachments: Vec<GeminiAttachment>,
+}
+
+impl From<RawGeminiMessage> for GeminiMessage {
+    fn from(raw: RawGeminiMessage) -> Self {
+        let mut content = String::new();
+        let mut attachments = raw.attachments;
+        collect_parts(&raw.content, &mut content, &mut attachments);
+
+        Self {
+            id: raw.id,
+            timestamp: raw.timestamp,
+            message_type: raw.message_type,
+            content,
+            attachments,
+            tool_calls: raw.tool_calls,
+            thoughts: raw.thoughts,
+            tokens: raw.tokens,
+            model: raw.model,
+        }
+    }
+}
+
+/// Split Gemini `PartListUnion` content into text and attachments
+fn collect_parts(
+    value: &serde_json::Value,
+    text: &mut String,
+    attachments: &mut Vec<GeminiAttachment>,
+) {
+    match value {
+        serde_json::Value::String(s) => text.push_str(s),
+        serde_json::Value::Array(parts) => {
+            for part in parts {
+                collect_parts(part, text, attachments);
+            }
+        }
+        serde_json::Value::Object(part) => {
+            if let Some(s) = part.get("text").and_then(|t| t.as_str()) {
+                text.push_str(s);
+            } else if let Some(inline) = part.get("inlineData") {
+                attachments.push(GeminiAttachment::Inline {
+                    mime_type: inline["mimeType"]
+                        .as_str()
+                        .unwrap_or("application/octet-stream")
+                        .to_string(),
+                    data: inline["data"].as_str().unwrap_or_default().to_string(),
+                });
+            } else if let Some(file) = part.get("fileData") {
+                attachments.push(GeminiAttachment::File {
+                    mime_type: file["mimeType"].as_str().map(str::to_string),
+                    file_uri: file["fileUri"].as_str().unwrap_or_default().to_string(),
+                });
+            }
+        }
+        _ => {}
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Thought {
     pub subject: String,
@@ -82,6 +177,38 @@ impl GeminiSession {
         serde_json::from_str(json_str)
     }
 
+    /// Parse a session straight from a reader, without buffering the whole
+    /// document in a string first
+    ///
+    /// Messages that don't parse are skipped; see `from_reader_lenient`.
+    pub fn from_reader(reader: impl std::io::Read) -> Result<Self, serde_json::Error> {
+        Self::from_reader_lenient(reader).map(|(session, _)| session)
+    }
+
+    /// Parse a session, skipping messages in a format the parser doesn't know
+    ///
+    /// Only the session itself must parse. Skipped messages are returned
+    /// numbered by their 1-based position in the file's `messages` array.
+    pub fn from_reader_lenient(
+        reader: impl std::io::Read,
+    ) -> Result<(Self, Vec<QuarantinedLine>), serde_json::Error> {
+        let mut value: serde_json::Value = serde_json::from_reader(reader)?;
+        let raw_messages = match value.get_mut("messages") {
+            Some(serde_json::Value::Array(messages)) => std::mem::take(messages),
+            _ => Vec::new(),
+        };
+        let mut session: Self = serde_json::from_value(value)?;
+
+        let mut skipped = Vec::new();
+        for (index, raw) in raw_messages.into_iter().enumerate() {
+            match GeminiMessage::deserialize(&raw) {
+                Ok(message) => session.messages.push(message),
+                Err(e) => skipped.push(QuarantinedLine::new(index + 1, &raw.to_string(), e)),
+            }
+        }
+        Ok((session, skipped))
+    }
+
     /// Get the total number of messages in the session
     #[allow(dead_code)]
     pub fn message_count(&self) -> usize {
@@ -182,6 +309,28 @@ impl TokenSummary {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_unparseable_messages_are_skipped() {
+        let json = r#"{
+            "sessionId": "s1", "projectHash": "abc",
+            "startTime": "2025-01-01T00:00:00Z", "lastUpdated": "2025-01-01T00:01:00Z",
+            "messages": [
+                {"id": "m1", "timestamp": "2025-01-01T00:00:00Z", "type": "user", "content": "Hi"},
+                {"id": "m2", "type": "gemini", "content": "no timestamp"},
+                {"id": "m3", "timestamp": "2025-01-01T00:00:30Z", "type": "gemini", "content": "Hello"}
+            ]
+        }"#;
+
+        let (session, skipped) = GeminiSession::from_reader_lenient(json.as_bytes()).unwrap();
+        assert_eq!(session.messages.len(), 2);
+        assert_eq!(skipped.len(), 1);
+        assert_eq!(skipped[0].line_number, 2);
+        assert!(skipped[0].error.contains("timestamp"));
+
+        // The session itself still has to parse
+        assert!(GeminiSession::from_reader(r#"{"sessionId": "s1"}"#.as_bytes()).is_err());
+    }
+
     #[test]
     fn test_parse_gemini_session() {
         let json = r#"{
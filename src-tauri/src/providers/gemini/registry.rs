@@ -1,3 +1,4 @@
+use crate::providers::common::file_access::ProviderRoot;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs;
@@ -36,7 +37,8 @@ impl GeminiProjectRegistry {
             return Ok(Self::default());
         }
 
-        let content = fs::read_to_string(&path)?;
+        let providers_dir = path.parent().unwrap_or(&path);
+        let content = ProviderRoot::new("gemini-code", providers_dir).read_to_string(&path)?;
         let registry: GeminiProjectRegistry = serde_json::from_str(&content)?;
         Ok(registry)
     }
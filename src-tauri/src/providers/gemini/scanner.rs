@@ -1,115 +1,94 @@
 //! Gemini session scanner - discovers and parses Gemini sessions from ~/.gemini/tmp/
 
-use crate::logging::{log_info, log_warn};
-use crate::providers::common::SessionInfo;
+use crate::events::CancellationToken;
+use crate::providers::common::scan_cache::scan_project_dirs;
+use crate::providers::common::{ScanReport, SessionInfo};
 use chrono::{DateTime, Utc};
 use std::fs;
+use std::io::BufReader;
 use std::path::Path;
 
 /// Scan all Gemini sessions from the base path
 pub fn scan_sessions_filtered(
     base_path: &Path,
     selected_projects: Option<&[String]>,
-) -> Result<Vec<SessionInfo>, String> {
+    cancel: &CancellationToken,
+) -> Result<ScanReport, String> {
     // Gemini uses ~/.gemini/tmp/{hash}/chats/session-*.json structure
     let tmp_path = base_path.join("tmp");
     if !tmp_path.exists() {
-        return Ok(Vec::new());
+        return Ok(ScanReport::default());
     }
 
-    let mut sessions = Vec::new();
-
-    // Recursively scan project hash directories
+    // Collect project hash directories
     let entries = fs::read_dir(&tmp_path)
         .map_err(|e| format!("Failed to read Gemini tmp directory: {}", e))?;
 
+    let mut projects = Vec::new();
     for entry in entries.flatten() {
         let project_path = entry.path();
-
-        // Skip the 'bin' directory
-        if let Some(name) = project_path.file_name().and_then(|n| n.to_str()) {
-            if name == "bin" {
-                continue;
-            }
-        }
-
         if !project_path.is_dir() {
             continue;
         }
 
         // Get project hash (folder name)
-        let project_hash = project_path
-            .file_name()
-            .and_then(|n| n.to_str())
-            .map(|s| s.to_string());
+        let Some(project_hash) = project_path.file_name().and_then(|n| n.to_str()) else {
+            continue; // Skip if we can't determine hash
+        };
 
-        // Skip projects not in the selected list (Gemini uses hashes for filtering)
-        if let Some(selected) = selected_projects {
-            if let Some(ref hash) = project_hash {
-                if !selected.contains(hash) {
-                    continue;
-                }
-            } else {
-                continue; // Skip if we can't determine hash
-            }
-        }
-
-        let chats_path = project_path.join("chats");
-        if !chats_path.exists() {
+        // Skip the 'bin' directory
+        if project_hash == "bin" {
             continue;
         }
 
-        // Scan all session files in the chats directory
-        if let Ok(chat_entries) = fs::read_dir(&chats_path) {
-            for chat_entry in chat_entries.flatten() {
-                let file_path = chat_entry.path();
-
-                // Only process session JSON files
-                if let Some(filename) = file_path.file_name().and_then(|n| n.to_str()) {
-                    if filename.starts_with("session-") && filename.ends_with(".json") {
-                        match parse_gemini_session(&file_path) {
-                            Ok(session_info) => {
-                                sessions.push(session_info);
-                            }
-                            Err(e) => {
-                                if let Err(log_err) = log_warn(
-                                    "gemini-code",
-                                    &format!(
-                                        "Failed to parse Gemini session {}: {}",
-                                        file_path.display(),
-                                        e
-                                    ),
-                                ) {
-                                    eprintln!("Logging error: {}", log_err);
-                                }
-                            }
-                        }
-                    }
-                }
+        // Skip projects not in the selected list (Gemini uses hashes for filtering)
+        if let Some(selected) = selected_projects {
+            if !selected.contains(&project_hash.to_string()) {
+                continue;
             }
         }
-    }
 
-    if let Err(e) = log_info(
-        "gemini-code",
-        &format!("📊 Found {} Gemini sessions", sessions.len()),
-    ) {
-        eprintln!("Logging error: {}", e);
+        projects.push((project_hash.to_string(), project_path));
     }
 
-    Ok(sessions)
+    Ok(scan_project_dirs(
+        "gemini-code",
+        projects,
+        cancel,
+        |project_path| {
+            // Scan all session files in the chats directory
+            let Ok(chat_entries) = fs::read_dir(project_path.join("chats")) else {
+                return Vec::new();
+            };
+            chat_entries
+                .flatten()
+                .map(|chat_entry| chat_entry.path())
+                .filter(|file_path| {
+                    // Only process session JSON files
+                    file_path
+                        .file_name()
+                        .and_then(|n| n.to_str())
+                        .is_some_and(|filename| {
+                            filename.starts_with("session-") && filename.ends_with(".json")
+                        })
+                })
+                .collect()
+        },
+        |file_path, _| parse_gemini_session(file_path),
+    ))
 }
 
-fn parse_gemini_session(file_path: &Path) -> Result<SessionInfo, String> {
+/// Parse a session and convert it into the canonical cache; None when its
+/// project is ignored
+pub(crate) fn parse_gemini_session(file_path: &Path) -> Result<Option<SessionInfo>, String> {
     use super::super::common::extract_session_id_from_filename;
-    use super::converter::convert_to_canonical_file;
+    use super::converter::{convert_session_file, project_name};
     use super::parser::GeminiSession;
 
-    let content =
-        fs::read_to_string(file_path).map_err(|e| format!("Failed to read file: {}", e))?;
+    let file = fs::File::open(file_path).map_err(|e| format!("Failed to read file: {}", e))?;
 
-    // Parse the Gemini session JSON
-    let session: GeminiSession = serde_json::from_str(&content)
+    // Parse the Gemini session JSON straight from disk
+    let session = GeminiSession::from_reader(BufReader::new(file))
         .map_err(|e| format!("Failed to parse Gemini session JSON: {}", e))?;
 
     // IMPORTANT: Use filename as session_id (not the sessionId field from JSON)
@@ -139,8 +118,12 @@ fn parse_gemini_session(file_path: &Path) -> Result<SessionInfo, String> {
     // - Serializing to JSONL
     // - Getting project-organized canonical path
     // - Writing to cache
-    let cached_file_path = convert_to_canonical_file(file_path, &session_id)
-        .map_err(|e| format!("Failed to convert to canonical format: {}", e))?;
+    let Some(converted) = convert_session_file(file_path)
+        .map_err(|e| format!("Failed to convert to canonical format: {}", e))?
+    else {
+        return Ok(None);
+    };
+    let cached_file_path = converted.canonical_path;
 
     let file_name = format!("{}.jsonl", session_id);
 
@@ -154,17 +137,9 @@ fn parse_gemini_session(file_path: &Path) -> Result<SessionInfo, String> {
     let cwd = extract_cwd_from_gemini_session(&session);
 
     // Determine project name from CWD or use hash
-    let project_name = if let Some(cwd_path) = &cwd {
-        Path::new(cwd_path)
-            .file_name()
-            .and_then(|n| n.to_str())
-            .unwrap_or(&session.project_hash)
-            .to_string()
-    } else {
-        format!("gemini-{}", &session.project_hash[..8])
-    };
+    let project_name = project_name(&session, cwd.as_deref());
 
-    Ok(SessionInfo {
+    Ok(Some(SessionInfo {
         provider: "gemini-code".to_string(),
         project_name,
         session_id, // Use filename-based ID, not session.session_id from JSON
@@ -177,7 +152,7 @@ fn parse_gemini_session(file_path: &Path) -> Result<SessionInfo, String> {
         content: None, // Now using cached file, not in-memory content
         cwd,
         project_hash: Some(session.project_hash), // Used for filtering Gemini sessions
-    })
+    }))
 }
 
 /// Extract CWD from Gemini session using shared extraction logic
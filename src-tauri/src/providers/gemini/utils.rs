@@ -1,17 +1,17 @@
 use crate::providers::sort_projects_by_modified;
 use crate::config::ProjectInfo;
 use super::parser::GeminiSession;
+use crate::providers::common::canonical_path::{expand_home, is_absolute_path, SessionPath};
+use crate::providers::common::file_access::ProviderRoot;
 use chrono::{DateTime, Utc};
 use sha2::{Digest, Sha256};
-use shellexpand::tilde;
 use std::fs;
-use std::path::{Path, PathBuf};
+use std::path::Path;
 
 pub fn scan_projects(home_directory: &str) -> Result<Vec<ProjectInfo>, String> {
     use super::registry::GeminiProjectRegistry;
 
-    let expanded = tilde(home_directory);
-    let base_path = PathBuf::from(expanded.into_owned());
+    let base_path = expand_home(home_directory);
 
     if !base_path.exists() {
         return Err(format!(
@@ -114,6 +114,7 @@ fn resolve_project_info(project_path: &Path, hash: &str) -> Result<(String, Stri
     }
 
     // Try to read the first session file
+    let root = ProviderRoot::new("gemini-code", project_path);
     let entries =
         fs::read_dir(&chats_path).map_err(|e| format!("Failed to read chats directory: {}", e))?;
 
@@ -130,7 +131,7 @@ fn resolve_project_info(project_path: &Path, hash: &str) -> Result<(String, Stri
         }
 
         // Try to parse the session and extract working directory
-        if let Ok(content) = fs::read_to_string(&path) {
+        if let Ok(content) = root.read_to_string(&path) {
             if let Ok(session) = GeminiSession::from_json(&content) {
                 // Verify the hash matches
                 if session.project_hash != hash {
@@ -151,9 +152,8 @@ fn resolve_project_info(project_path: &Path, hash: &str) -> Result<(String, Stri
 
 /// Get project name from working directory path
 fn get_project_name_from_path(workdir: &str) -> Result<String, String> {
-    Path::new(workdir)
+    SessionPath::parse(workdir)
         .file_name()
-        .and_then(|n| n.to_str())
         .map(|s| s.to_string())
         .ok_or_else(|| "Could not extract project name from path".to_string())
 }
@@ -166,7 +166,11 @@ pub fn extract_candidate_paths_from_content(content: &str) -> Vec<String> {
 
     for line in lines {
         // Look for absolute paths (Unix and Windows)
-        if line.contains("/Users/") || line.contains("/home/") || line.contains("C:\\") {
+        if line.contains("/Users/")
+            || line.contains("/home/")
+            || line.contains(":\\")
+            || line.contains(":/")
+        {
             // Prefer paths after '---' delimiter (common in tool output)
             let search_text = if let Some(delimiter_pos) = line.find("---") {
                 &line[delimiter_pos + 3..]
@@ -178,11 +182,7 @@ pub fn extract_candidate_paths_from_content(content: &str) -> Vec<String> {
             let parts: Vec<&str> = search_text.split_whitespace().collect();
             for part in parts {
                 // Unix paths or Windows paths
-                if part.starts_with('/')
-                    || (part.len() > 3
-                        && part.chars().nth(1) == Some(':')
-                        && part.chars().nth(2) == Some('\\'))
-                {
+                if is_absolute_path(part) {
                     paths.push(part.to_string());
                 }
             }
@@ -193,32 +193,16 @@ pub fn extract_candidate_paths_from_content(content: &str) -> Vec<String> {
 }
 
 /// Try progressively shorter paths until we find one matching the hash
+///
+/// Gemini hashes the working directory as its process saw it, so Windows
+/// paths are tried with each separator and drive letter case.
 pub fn find_matching_path(full_path: &str, expected_hash: &str) -> Option<String> {
-    let path_buf = Path::new(full_path);
-    let mut current_path = path_buf;
-
-    // Try the full path first, then progressively remove the last segment
-    loop {
-        if let Some(path_str) = current_path.to_str() {
-            // Skip root and empty paths
-            if !path_str.is_empty() && path_str != "/" && path_str != "\\" {
-                // Test if this path's hash matches
-                if verify_hash(path_str, expected_hash) {
-                    return Some(path_str.to_string());
-                }
-            }
-        }
-
-        // Move up to parent directory
-        match current_path.parent() {
-            Some(parent) if parent != current_path => {
-                current_path = parent;
-            }
-            _ => break, // No more parents or reached root
-        }
-    }
-
-    None
+    SessionPath::parse(full_path)
+        .ancestors()
+        // Skip the root itself
+        .filter(|path| !path.components().is_empty())
+        .flat_map(|path| path.spellings())
+        .find(|path| verify_hash(path, expected_hash))
 }
 
 /// Verify that SHA256(workdir) == hash
@@ -317,6 +301,28 @@ mod tests {
         let workdir = "/Users/cliftonc/work/guidemode";
         let name = get_project_name_from_path(workdir).unwrap();
         assert_eq!(name, "guidemode");
+        assert_eq!(
+            get_project_name_from_path(r"C:\work\guidemode\").unwrap(),
+            "guidemode"
+        );
+    }
+
+    #[test]
+    fn test_find_matching_windows_path() {
+        let hash = |path: &str| hex::encode(Sha256::digest(path.as_bytes()));
+        let expected = hash(r"C:\Users\dev\guidemode");
+        assert_eq!(
+            find_matching_path(r"c:/Users/dev/guidemode/src/main.ts", &expected),
+            Some(r"C:\Users\dev\guidemode".to_string())
+        );
+        assert_eq!(
+            find_matching_path(r"\\?\C:\Users\dev\guidemode\src", &expected),
+            Some(r"C:\Users\dev\guidemode".to_string())
+        );
+        assert_eq!(
+            find_matching_path(r"D:\Users\dev\guidemode", &expected),
+            None
+        );
     }
 
     // Tests for CWD extraction are now in tests/gemini_cwd_extraction.rs
@@ -2,14 +2,15 @@ use crate::config::load_provider_config;
 use crate::events::{EventBus, SessionEventPayload};
 use crate::logging::{log_error, log_info, log_warn};
 use crate::providers::common::{
-    extract_session_id_from_filename, get_file_size, has_extension,
-    should_skip_file, SessionStateManager, WatcherStatus, EVENT_TIMEOUT, FILE_WATCH_POLL_INTERVAL,
-    MIN_SIZE_CHANGE_BYTES,
+    access, expand_home, extract_session_id_from_filename, file_watcher, get_file_size,
+    has_extension, should_skip_file, FileWatcher, SessionStateManager, WatcherStatus, EVENT_TIMEOUT,
+    FILE_WATCH_POLL_INTERVAL, MIN_SIZE_CHANGE_BYTES,
 };
-use crate::providers::gemini::converter::convert_to_canonical_file;
+use crate::providers::gemini::checkpoint::is_history_rewrite;
+use crate::providers::gemini::converter::convert_session_file;
 use crate::upload_queue::UploadQueue;
-use notify::{Config, Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
-use shellexpand::tilde;
+use notify::{Config, Event, EventKind, RecursiveMode};
+use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::sync::{mpsc, Arc, Mutex};
@@ -26,7 +27,7 @@ pub struct FileChangeEvent {
 
 #[derive(Debug)]
 pub struct GeminiWatcher {
-    _watcher: RecommendedWatcher,
+    _watcher: FileWatcher,
     _thread_handle: thread::JoinHandle<()>,
     upload_queue: Arc<UploadQueue>,
     is_running: Arc<Mutex<bool>>,
@@ -51,8 +52,8 @@ impl GeminiWatcher {
         }
 
         let home_directory = config.home_directory;
-        let expanded_home = tilde(&home_directory);
-        let base_path = Path::new(expanded_home.as_ref());
+        let expanded_home = expand_home(&home_directory);
+        let base_path = expanded_home.as_path();
 
         if !base_path.exists() {
             return Err(format!(
@@ -88,7 +89,9 @@ impl GeminiWatcher {
         let (tx, rx) = mpsc::channel();
 
         // Create the file watcher
-        let mut watcher = RecommendedWatcher::new(
+        let watcher = FileWatcher::new(
+            PROVIDER_ID,
+            base_path,
             tx,
             Config::default().with_poll_interval(FILE_WATCH_POLL_INTERVAL),
         )?;
@@ -121,10 +124,13 @@ impl GeminiWatcher {
         let tmp_path_clone = tmp_path.clone();
         let event_bus_clone = event_bus.clone();
 
+        let file_watcher = watcher.clone();
+
         // Start background thread to handle file events
         let thread_handle = thread::spawn(move || {
             Self::file_event_processor(
                 rx,
+                file_watcher,
                 tmp_path_clone,
                 upload_queue_clone,
                 event_bus_clone,
@@ -174,12 +180,15 @@ impl GeminiWatcher {
 
     fn file_event_processor(
         rx: mpsc::Receiver<Result<Event, notify::Error>>,
+        file_watcher: FileWatcher,
         tmp_path: PathBuf,
         _upload_queue: Arc<UploadQueue>,
         event_bus: EventBus,
         is_running: Arc<Mutex<bool>>,
     ) {
         let mut session_states = SessionStateManager::new();
+        // Message IDs from the last conversion, used to spot checkpoint rewrites
+        let mut session_message_ids: HashMap<String, Vec<String>> = HashMap::new();
 
         loop {
             // Check if we should continue running
@@ -196,11 +205,10 @@ impl GeminiWatcher {
                 Ok(Ok(event)) => {
                     if let Some(file_event) = Self::process_file_event(&event, &tmp_path) {
                         // Convert Gemini JSON to canonical JSONL and cache it using shared function
-                        let canonical_path = match convert_to_canonical_file(
-                            &file_event.path,
-                            &file_event.session_id,
-                        ) {
-                            Ok(path) => path,
+                        let converted = match convert_session_file(&file_event.path) {
+                            Ok(Some(converted)) => converted,
+                            // Ignored projects never reach the canonical cache
+                            Ok(None) => continue,
                             Err(e) => {
                                 if let Err(log_err) = log_error(
                                     PROVIDER_ID,
@@ -212,6 +220,29 @@ impl GeminiWatcher {
                             }
                         };
 
+                        // The canonical file was regenerated from the reconciled history,
+                        // so a rewrite replaces earlier output rather than duplicating it
+                        if let Some(previous_ids) =
+                            session_message_ids.get(&file_event.session_id)
+                        {
+                            if is_history_rewrite(previous_ids, &converted.message_ids) {
+                                if let Err(e) = log_info(
+                                    PROVIDER_ID,
+                                    &format!(
+                                        "♻️ Gemini session history rewritten: {} ({} messages, {} superseded)",
+                                        file_event.session_id,
+                                        converted.message_ids.len(),
+                                        converted.dropped_messages
+                                    ),
+                                ) {
+                                    eprintln!("Logging error: {}", e);
+                                }
+                            }
+                        }
+                        session_message_ids
+                            .insert(file_event.session_id.clone(), converted.message_ids);
+                        let canonical_path = converted.canonical_path;
+
                         // Get file size of canonical JSONL
                         let canonical_size = get_file_size(&canonical_path).unwrap_or(0);
 
@@ -278,10 +309,12 @@ impl GeminiWatcher {
                     }
                 }
                 Ok(Err(error)) => {
-                    if let Err(e) =
-                        log_error(PROVIDER_ID, &format!("File watcher error: {:?}", error))
-                    {
-                        eprintln!("Logging error: {}", e);
+                    if !file_watcher.handle_error(&error) {
+                        if let Err(e) =
+                            log_error(PROVIDER_ID, &format!("File watcher error: {:?}", error))
+                        {
+                            eprintln!("Logging error: {}", e);
+                        }
                     }
                 }
                 Err(mpsc::RecvTimeoutError::Timeout) => {
@@ -406,6 +439,8 @@ impl GeminiWatcher {
             pending_uploads: upload_status.pending,
             processing_uploads: upload_status.processing,
             failed_uploads: upload_status.failed,
+            access_denied: access::denied_path(PROVIDER_ID),
+            watch_limit: file_watcher::watch_limit_hint(PROVIDER_ID),
         }
     }
 }
@@ -1,5 +1,7 @@
 use crate::config::ProjectInfo;
+use crate::error::GuideModeError;
 use chrono::{DateTime, Utc};
+use std::path::Path;
 
 pub mod canonical; // Canonical format types and converter trait
 pub mod claude; // Claude Code converter (public for canonical format migration)
@@ -7,9 +9,11 @@ pub mod codex; // Codex converter (public for canonical format migration)
 pub mod common;
 pub mod copilot; // Copilot converter (public for canonical format migration)
 pub mod cursor; // Cursor converter
+pub mod detection; // Installed agent detection for onboarding
 pub mod gemini; // Gemini converter (public for canonical format migration)
 pub mod opencode; // OpenCode converter (public for canonical format migration)
 mod session_scanner;
+pub mod versions; // CLI versions and converter compatibility
 
 // Re-export watchers from provider modules
 pub use claude::watcher::{ClaudeWatcher, ClaudeWatcherStatus};
@@ -21,15 +25,62 @@ pub use gemini::watcher::{GeminiWatcher, GeminiWatcherStatus};
 pub use opencode::watcher::{OpenCodeWatcher, OpenCodeWatcherStatus};
 pub use session_scanner::scan_all_sessions_filtered;
 
-pub fn scan_projects(provider_id: &str, home_directory: &str) -> Result<Vec<ProjectInfo>, String> {
-    match provider_id {
+/// Providers sessions are converted from; every canonical session names one
+pub const PROVIDER_IDS: [&str; 6] = [
+    "claude-code",
+    "github-copilot",
+    "opencode",
+    "codex",
+    "gemini-code",
+    "cursor",
+];
+
+pub fn scan_projects(
+    provider_id: &str,
+    home_directory: &str,
+) -> Result<Vec<ProjectInfo>, GuideModeError> {
+    common::access::ensure_readable(provider_id, &common::expand_home(home_directory))?;
+    let projects = match provider_id {
         "claude-code" => claude::scan_projects(home_directory),
         "github-copilot" => copilot::utils::scan_projects(home_directory),
         "opencode" => opencode::scan_projects(home_directory),
         "codex" => codex::scan_projects(home_directory),
         "gemini-code" => gemini::utils::scan_projects(home_directory),
         "cursor" => cursor::scan_projects(home_directory),
-        other => Err(format!("Unsupported provider: {}", other)),
+        other => {
+            return Err(GuideModeError::Validation(format!(
+                "Unsupported provider: {}",
+                other
+            )))
+        }
+    };
+    projects.map_err(|message| GuideModeError::Provider {
+        provider: provider_id.to_string(),
+        message,
+    })
+}
+
+/// Convert one of a provider's session files to canonical JSONL
+///
+/// Watchers, scans, the canonical format migration and the converter golden
+/// tests all convert through here. `path` is the session's transcript, except
+/// for OpenCode, whose sessions are a directory of message files, and Cursor,
+/// whose sessions are a store.db each.
+pub fn convert_file_to_canonical(
+    provider_id: &str,
+    path: &Path,
+) -> common::ConvertResult<common::ConvertedFile> {
+    match provider_id {
+        "claude-code" => {
+            let session_id = common::extract_session_id_from_filename(path);
+            claude::convert_file(path, &session_id, None)
+        }
+        "github-copilot" => copilot::convert_file(path),
+        "opencode" => opencode::convert_file(path),
+        "codex" => codex::convert_file(path),
+        "gemini-code" => Ok(gemini::convert_file(path)?),
+        "cursor" => cursor::convert_file(path),
+        other => Err(format!("Unsupported provider: {}", other).into()),
     }
 }
 
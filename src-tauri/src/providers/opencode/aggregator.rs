@@ -0,0 +1,380 @@
+//! Incremental aggregation of OpenCode sessions
+//!
+//! OpenCode stores every message and message part in its own JSON file, so a
+//! full parse of a long session reads thousands of files. The watcher keeps a
+//! `SessionAggregator` that caches parsed messages and parts per session and,
+//! on each change, only re-reads the messages whose files were touched (and,
+//! within those, only the part files whose modification time changed).
+//!
+//! Before applying an incremental update the cache is checked against the
+//! message directory listing. Missing or unexpected messages (events we never
+//! saw, deleted files) or a changed project worktree force a full rebuild, as
+//! does reaching `FULL_REBUILD_INTERVAL` incremental updates.
+
+use super::parser::{
+    aggregate_message, build_parsed_session, compare_messages, compare_parts, load_message_file,
+    load_part_file, MessageAggregate, OpenCodeMessage, OpenCodeParser, OpenCodePart,
+    OpenCodeProject, ParsedSession,
+};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Incremental updates allowed before a session is rebuilt from scratch
+const FULL_REBUILD_INTERVAL: usize = 100;
+
+/// How a session was aggregated
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AggregationMode {
+    /// Every message and part file was read
+    Full,
+    /// Only the listed number of changed messages were re-read
+    Incremental { messages_reloaded: usize },
+}
+
+impl fmt::Display for AggregationMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AggregationMode::Full => write!(f, "full rebuild"),
+            AggregationMode::Incremental { messages_reloaded } => {
+                write!(f, "incremental, {} messages reloaded", messages_reloaded)
+            }
+        }
+    }
+}
+
+struct CachedPart {
+    modified: Option<SystemTime>,
+    part: OpenCodePart,
+}
+
+struct CachedMessage {
+    message: OpenCodeMessage,
+    parts: HashMap<PathBuf, CachedPart>,
+    aggregate: MessageAggregate,
+}
+
+impl CachedMessage {
+    /// Build a cached message, reusing previously parsed parts whose files are unchanged
+    fn load(
+        parser: &OpenCodeParser,
+        session_id: &str,
+        message: OpenCodeMessage,
+        mut previous_parts: HashMap<PathBuf, CachedPart>,
+        cwd: Option<&str>,
+    ) -> Result<Self, String> {
+        let mut parts = HashMap::new();
+
+        for path in list_json_files(&parser.part_dir(&message.id)) {
+            let modified = fs::metadata(&path).and_then(|m| m.modified()).ok();
+            let cached = match previous_parts.remove(&path) {
+                Some(cached) if modified.is_some() && cached.modified == modified => cached,
+                _ => CachedPart {
+                    modified,
+                    part: load_part_file(&path)?,
+                },
+            };
+            parts.insert(path, cached);
+        }
+
+        let mut sorted: Vec<OpenCodePart> = parts.values().map(|c| c.part.clone()).collect();
+        sorted.sort_by(compare_parts);
+        let aggregate = aggregate_message(session_id, &message, sorted, cwd);
+
+        Ok(Self {
+            message,
+            parts,
+            aggregate,
+        })
+    }
+}
+
+struct SessionCache {
+    cwd: Option<String>,
+    messages: HashMap<String, CachedMessage>,
+    updates_since_rebuild: usize,
+}
+
+impl SessionCache {
+    /// Read every message and part file for a session
+    fn load(parser: &OpenCodeParser, session_id: &str, cwd: Option<&str>) -> Result<Self, String> {
+        let mut messages = HashMap::new();
+
+        for path in list_json_files(&parser.message_dir(session_id)) {
+            let Some(id) = file_stem(&path) else {
+                continue;
+            };
+            let message = load_message_file(&path)?;
+            let cached = CachedMessage::load(parser, session_id, message, HashMap::new(), cwd)?;
+            messages.insert(id, cached);
+        }
+
+        Ok(Self {
+            cwd: cwd.map(str::to_string),
+            messages,
+            updates_since_rebuild: 0,
+        })
+    }
+
+    /// Re-read only the messages affected by `changed_files`
+    ///
+    /// Returns the number of messages reloaded, or None when the cache no
+    /// longer matches the files on disk and a full rebuild is needed.
+    fn apply_changes(
+        &mut self,
+        parser: &OpenCodeParser,
+        session_id: &str,
+        changed_files: &HashSet<PathBuf>,
+        cwd: Option<&str>,
+    ) -> Result<Option<usize>, String> {
+        if self.cwd.as_deref() != cwd {
+            return Ok(None);
+        }
+
+        let message_dir = parser.message_dir(session_id);
+        let on_disk: HashSet<String> = list_json_files(&message_dir)
+            .iter()
+            .filter_map(|path| file_stem(path))
+            .collect();
+        let dirty: HashSet<String> = changed_files
+            .iter()
+            .filter_map(|path| changed_message_id(path))
+            .filter(|id| on_disk.contains(id))
+            .collect();
+
+        // Consistency check: every cached message still exists, and every
+        // message on disk is either cached or about to be loaded
+        let consistent = self.messages.keys().all(|id| on_disk.contains(id))
+            && on_disk
+                .iter()
+                .all(|id| self.messages.contains_key(id) || dirty.contains(id));
+        if !consistent {
+            return Ok(None);
+        }
+
+        for id in &dirty {
+            let message = load_message_file(&message_dir.join(format!("{}.json", id)))?;
+            let previous_parts = self
+                .messages
+                .remove(id)
+                .map(|cached| cached.parts)
+                .unwrap_or_default();
+            let cached = CachedMessage::load(parser, session_id, message, previous_parts, cwd)?;
+            self.messages.insert(id.clone(), cached);
+        }
+
+        self.updates_since_rebuild += 1;
+        Ok(Some(dirty.len()))
+    }
+
+    fn to_parsed_session(&self, session_id: &str, project: &OpenCodeProject) -> ParsedSession {
+        let mut messages: Vec<&CachedMessage> = self.messages.values().collect();
+        messages.sort_by(|a, b| compare_messages(&a.message, &b.message));
+
+        build_parsed_session(
+            session_id,
+            project,
+            messages.into_iter().map(|cached| &cached.aggregate),
+        )
+    }
+}
+
+/// Per-session cache of parsed OpenCode messages and parts
+#[derive(Default)]
+pub struct SessionAggregator {
+    sessions: HashMap<String, SessionCache>,
+}
+
+impl SessionAggregator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Aggregate a session, re-reading only what changed since the last call
+    ///
+    /// `changed_files` are the storage paths reported by the file watcher since
+    /// the session was last aggregated.
+    pub fn aggregate(
+        &mut self,
+        parser: &OpenCodeParser,
+        session_id: &str,
+        changed_files: &HashSet<PathBuf>,
+    ) -> Result<(ParsedSession, AggregationMode), String> {
+        let session = parser.load_session(session_id)?;
+        let project = parser.load_project_for(&session)?;
+        let cwd = Some(project.worktree.as_str());
+
+        let mut mode = AggregationMode::Full;
+        if let Some(cache) = self.sessions.get_mut(session_id) {
+            if cache.updates_since_rebuild < FULL_REBUILD_INTERVAL {
+                if let Some(messages_reloaded) =
+                    cache.apply_changes(parser, session_id, changed_files, cwd)?
+                {
+                    mode = AggregationMode::Incremental { messages_reloaded };
+                }
+            }
+        }
+
+        if mode == AggregationMode::Full {
+            let cache = SessionCache::load(parser, session_id, cwd)?;
+            self.sessions.insert(session_id.to_string(), cache);
+        }
+
+        let parsed = self.sessions[session_id].to_parsed_session(session_id, &project);
+        Ok((parsed, mode))
+    }
+
+    /// Drop cached state so the next aggregation is a full rebuild
+    pub fn invalidate(&mut self, session_id: &str) {
+        self.sessions.remove(session_id);
+    }
+}
+
+/// Message ID affected by a changed storage file, if any
+///
+/// Parts live at part/{messageId}/{partId}.json and messages at
+/// message/{sessionId}/{messageId}.json.
+fn changed_message_id(path: &Path) -> Option<String> {
+    let parent = path.parent()?;
+    let kind = parent.parent()?.file_name()?.to_str()?;
+
+    match kind {
+        "part" => Some(parent.file_name()?.to_str()?.to_string()),
+        "message" => file_stem(path),
+        _ => None,
+    }
+}
+
+fn file_stem(path: &Path) -> Option<String> {
+    path.file_stem()
+        .and_then(|stem| stem.to_str())
+        .map(|stem| stem.to_string())
+}
+
+fn list_json_files(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().and_then(|ext| ext.to_str()) == Some("json"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn write_json(path: PathBuf, value: serde_json::Value) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, value.to_string()).unwrap();
+    }
+
+    fn write_message(storage: &Path, id: &str, created: i64, text: &str) -> HashSet<PathBuf> {
+        let message_path = storage.join("message/ses_1").join(format!("{}.json", id));
+        write_json(
+            message_path.clone(),
+            serde_json::json!({
+                "id": id, "role": "user", "sessionID": "ses_1",
+                "time": {"created": created}
+            }),
+        );
+
+        let part_path = storage.join("part").join(id).join("prt_1.json");
+        write_json(
+            part_path.clone(),
+            serde_json::json!({
+                "id": "prt_1", "type": "text", "text": text,
+                "messageID": id, "sessionID": "ses_1"
+            }),
+        );
+
+        HashSet::from([message_path, part_path])
+    }
+
+    fn create_storage() -> (tempfile::TempDir, OpenCodeParser) {
+        let temp = tempdir().unwrap();
+        let storage = temp.path().join("storage");
+
+        write_json(
+            storage.join("project/proj_1.json"),
+            serde_json::json!({"id": "proj_1", "worktree": "/work/app", "time": {}}),
+        );
+        write_json(
+            storage.join("session/proj_1/ses_1.json"),
+            serde_json::json!({"id": "ses_1", "projectID": "proj_1", "time": {}}),
+        );
+        write_message(&storage, "msg_1", 1_700_000_000_000, "first");
+
+        let parser = OpenCodeParser::new(storage);
+        (temp, parser)
+    }
+
+    #[test]
+    fn test_incremental_matches_full_parse() {
+        let (temp, parser) = create_storage();
+        let storage = temp.path().join("storage");
+        let mut aggregator = SessionAggregator::new();
+
+        let (_, mode) = aggregator
+            .aggregate(&parser, "ses_1", &HashSet::new())
+            .unwrap();
+        assert_eq!(mode, AggregationMode::Full);
+
+        let changed = write_message(&storage, "msg_2", 1_700_000_001_000, "second");
+        let (parsed, mode) = aggregator.aggregate(&parser, "ses_1", &changed).unwrap();
+
+        assert_eq!(
+            mode,
+            AggregationMode::Incremental {
+                messages_reloaded: 1
+            }
+        );
+        assert_eq!(
+            parsed.jsonl_content,
+            parser.parse_session("ses_1").unwrap().jsonl_content
+        );
+        assert!(parsed.jsonl_content.contains("second"));
+    }
+
+    #[test]
+    fn test_missed_message_forces_full_rebuild() {
+        let (temp, parser) = create_storage();
+        let storage = temp.path().join("storage");
+        let mut aggregator = SessionAggregator::new();
+
+        aggregator
+            .aggregate(&parser, "ses_1", &HashSet::new())
+            .unwrap();
+
+        // A message written without a corresponding watcher event
+        write_message(&storage, "msg_2", 1_700_000_001_000, "missed");
+        let (parsed, mode) = aggregator
+            .aggregate(&parser, "ses_1", &HashSet::new())
+            .unwrap();
+
+        assert_eq!(mode, AggregationMode::Full);
+        assert!(parsed.jsonl_content.contains("missed"));
+    }
+
+    #[test]
+    fn test_changed_message_id() {
+        assert_eq!(
+            changed_message_id(Path::new("/s/part/msg_1/prt_1.json")).as_deref(),
+            Some("msg_1")
+        );
+        assert_eq!(
+            changed_message_id(Path::new("/s/message/ses_1/msg_2.json")).as_deref(),
+            Some("msg_2")
+        );
+        assert_eq!(
+            changed_message_id(Path::new("/s/session/proj_1/ses_1.json")),
+            None
+        );
+    }
+}
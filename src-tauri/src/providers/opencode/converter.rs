@@ -1,10 +1,17 @@
+use crate::providers::canonical::media::block_from_url;
 use crate::providers::canonical::{
     CanonicalMessage, ContentBlock, ContentValue, MessageContent, MessageType,
+    CANONICAL_SCHEMA_VERSION,
 };
-use super::parser::{OpenCodeJsonLContent, OpenCodeJsonLEntry};
+use crate::providers::common::{annotate_model, CanonicalWriter, ConvertResult, ConvertedFile};
+use super::parser::{OpenCodeJsonLContent, OpenCodeJsonLEntry, OpenCodeParser, ParsedSession};
 use anyhow::{Context, Result};
+use std::io;
+use std::path::Path;
 use uuid::Uuid;
 
+const PROVIDER_ID: &str = "opencode";
+
 /// Convert OpenCode aggregated JSONL entry to canonical format
 ///
 /// OpenCode's parser already does the hard work of aggregating session,
@@ -38,7 +45,8 @@ pub fn convert_entry_to_canonical(entry: &OpenCodeJsonLEntry) -> Result<Canonica
         format!("{}-{}", entry.session_id, entry.timestamp)
     };
 
-    Ok(CanonicalMessage {
+    let mut canonical = CanonicalMessage {
+        schema_version: CANONICAL_SCHEMA_VERSION,
         uuid,
         timestamp: entry.timestamp.clone(),
         message_type,
@@ -53,7 +61,7 @@ pub fn convert_entry_to_canonical(entry: &OpenCodeJsonLEntry) -> Result<Canonica
         message: MessageContent {
             role,
             content,
-            model: None,
+            model: entry.message.model.clone(),
             usage: None,
         },
         provider_metadata: Some(serde_json::json!({
@@ -62,7 +70,10 @@ pub fn convert_entry_to_canonical(entry: &OpenCodeJsonLEntry) -> Result<Canonica
         is_meta: None,
         request_id: None,
         tool_use_result: None,
-    })
+    };
+    annotate_model(&mut canonical);
+
+    Ok(canonical)
 }
 
 /// Convert OpenCode content blocks to canonical format
@@ -132,13 +143,9 @@ fn convert_content_block(block: &OpenCodeJsonLContent) -> Result<ContentBlock> {
             url,
             ..
         } => {
-            // Convert file reference to text with metadata
-            // Files are OpenCode-specific, so we preserve them as text
-            let text = format!(
-                "[File: {} ({})] URL: {}",
-                filename, mime, url
-            );
-            Ok(ContentBlock::Text { text })
+            // File parts carry a data: URL or a file:// reference; keep only the
+            // hash and local path, never the embedded data
+            Ok(block_from_url(url, Some(mime), Some(filename)))
         }
         OpenCodeJsonLContent::Patch { files, hash, .. } => {
             // Convert patch reference to text with metadata
@@ -154,28 +161,74 @@ fn convert_content_block(block: &OpenCodeJsonLContent) -> Result<ContentBlock> {
     }
 }
 
-/// Convert a complete OpenCode JSONL string to canonical JSONL
-pub fn convert_opencode_jsonl_to_canonical(opencode_jsonl: &str) -> Result<String> {
-    let mut canonical_lines = Vec::new();
+/// Convert an OpenCode session to canonical format
+///
+/// Sessions are read through their message directory,
+/// `{storage}/message/{session_id}`.
+pub fn convert_file(message_dir: &Path) -> ConvertResult<ConvertedFile> {
+    let session_id = message_dir
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or("Invalid message directory")?;
+    let storage_path = message_dir
+        .parent()
+        .and_then(|p| p.parent())
+        .ok_or("Invalid message directory")?;
+
+    let parser = OpenCodeParser::new(storage_path.to_path_buf());
+    let parsed = parser.parse_session(session_id)?;
+    Ok(convert_parsed_session(&parsed, message_dir)?)
+}
+
+/// Convert an aggregated OpenCode session to canonical format
+///
+/// Lines of its JSONL that can't be parsed or converted are set aside, so one
+/// bad message doesn't lose the rest of the session.
+pub fn convert_parsed_session(
+    parsed: &ParsedSession,
+    message_dir: &Path,
+) -> io::Result<ConvertedFile> {
+    let mut writer = CanonicalWriter::for_source(PROVIDER_ID, message_dir)?;
+    let cwd = write_canonical(&parsed.jsonl_content, &mut writer)?;
+    Ok(ConvertedFile::new(
+        PROVIDER_ID,
+        parsed.session_id.clone(),
+        cwd,
+        writer,
+    ))
+}
+
+/// Write each line of aggregated OpenCode JSONL in canonical format, returning
+/// the working directory of the first messages
+fn write_canonical(
+    opencode_jsonl: &str,
+    writer: &mut CanonicalWriter,
+) -> io::Result<Option<String>> {
+    let mut cwd = None;
 
-    for (line_num, line) in opencode_jsonl.lines().enumerate() {
+    for (index, line) in opencode_jsonl.lines().enumerate() {
         if line.trim().is_empty() {
             continue;
         }
 
-        let entry: OpenCodeJsonLEntry = serde_json::from_str(line)
-            .with_context(|| format!("Failed to parse OpenCode JSONL line {}", line_num))?;
-
-        let canonical = convert_entry_to_canonical(&entry)
-            .with_context(|| format!("Failed to convert OpenCode entry at line {}", line_num))?;
-
-        let canonical_line = serde_json::to_string(&canonical)
-            .with_context(|| format!("Failed to serialize canonical message at line {}", line_num))?;
+        let canonical = serde_json::from_str::<OpenCodeJsonLEntry>(line)
+            .context("Failed to parse OpenCode JSONL line")
+            .and_then(|entry| {
+                convert_entry_to_canonical(&entry).context("Failed to convert OpenCode entry")
+            });
 
-        canonical_lines.push(canonical_line);
+        match canonical {
+            Ok(canonical) => {
+                if cwd.is_none() && writer.message_count() < 50 {
+                    cwd = canonical.cwd.clone();
+                }
+                writer.write(&canonical)?;
+            }
+            Err(e) => writer.quarantine(index + 1, line, format!("{:#}", e)),
+        }
     }
 
-    Ok(canonical_lines.join("\n"))
+    Ok(cwd)
 }
 
 #[cfg(test)]
@@ -191,6 +244,7 @@ mod tests {
             entry_type: "user".to_string(),
             message: OpenCodeJsonLMessage {
                 role: "user".to_string(),
+                model: None,
                 content: vec![OpenCodeJsonLContent::Text {
                     content_type: "text".to_string(),
                     text: "Hello, world!".to_string(),
@@ -220,6 +274,7 @@ mod tests {
             entry_type: "tool_use".to_string(),
             message: OpenCodeJsonLMessage {
                 role: "tool".to_string(),
+                model: None,
                 content: vec![OpenCodeJsonLContent::ToolUse {
                     content_type: "tool_use".to_string(),
                     id: "call_123".to_string(),
@@ -257,6 +312,7 @@ mod tests {
             entry_type: "tool_result".to_string(),
             message: OpenCodeJsonLMessage {
                 role: "tool".to_string(),
+                model: None,
                 content: vec![OpenCodeJsonLContent::ToolResult {
                     content_type: "tool_result".to_string(),
                     tool_use_id: "call_123".to_string(),
@@ -297,6 +353,7 @@ mod tests {
             entry_type: "user".to_string(),
             message: OpenCodeJsonLMessage {
                 role: "user".to_string(),
+                model: None,
                 content: vec![OpenCodeJsonLContent::File {
                     content_type: "file".to_string(),
                     filename: "test.png".to_string(),
@@ -313,11 +370,13 @@ mod tests {
             ContentValue::Structured(blocks) => {
                 assert_eq!(blocks.len(), 1);
                 match &blocks[0] {
-                    ContentBlock::Text { text } => {
-                        assert!(text.contains("test.png"));
-                        assert!(text.contains("image/png"));
+                    ContentBlock::Image {
+                        media_type, path, ..
+                    } => {
+                        assert_eq!(media_type, "image/png");
+                        assert_eq!(path.as_deref(), Some("/test/test.png"));
                     }
-                    _ => panic!("Expected text block for file"),
+                    _ => panic!("Expected image block for file"),
                 }
             }
             _ => panic!("Expected structured content"),
@@ -332,6 +391,7 @@ mod tests {
             entry_type: "assistant".to_string(),
             message: OpenCodeJsonLMessage {
                 role: "assistant".to_string(),
+                model: None,
                 content: vec![OpenCodeJsonLContent::Patch {
                     content_type: "patch".to_string(),
                     files: vec!["file1.rs".to_string(), "file2.rs".to_string()],
@@ -367,6 +427,7 @@ mod tests {
             entry_type: "user".to_string(),
             message: OpenCodeJsonLMessage {
                 role: "user".to_string(),
+                model: None,
                 content: vec![
                     OpenCodeJsonLContent::Text {
                         content_type: "text".to_string(),
@@ -392,4 +453,21 @@ mod tests {
             _ => panic!("Expected structured content"),
         }
     }
+
+    #[test]
+    fn test_skips_unconvertible_lines() {
+        let good = r#"{"sessionId":"s1","timestamp":"2025-01-01T00:00:00.000Z","type":"user","message":{"role":"user","content":[{"type":"text","text":"Hi"}]}}"#;
+        let jsonl = format!("{}\n{{\"sessionId\":\n{}", good, good);
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut writer = CanonicalWriter::create(dir.path()).unwrap();
+        let cwd = write_canonical(&jsonl, &mut writer).unwrap();
+
+        assert_eq!(writer.content().unwrap().lines().count(), 2);
+        assert_eq!(cwd, None);
+        let skipped = writer.quarantined();
+        assert_eq!(skipped.len(), 1);
+        assert_eq!(skipped[0].line_number, 2);
+        assert!(skipped[0].error.starts_with("Failed to parse OpenCode JSONL line"));
+    }
 }
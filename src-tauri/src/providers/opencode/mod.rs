@@ -1,21 +1,22 @@
+pub mod aggregator;
 pub mod converter;
 pub mod parser;
 pub mod scanner;
 pub mod watcher;
 
+pub use converter::convert_file;
 pub use parser::OpenCodeParser;
 use super::sort_projects_by_modified;
 use crate::config::ProjectInfo;
 use chrono::{DateTime, Utc};
-use shellexpand::tilde;
+use crate::providers::common::expand_home;
 use std::ffi::OsStr;
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::time::SystemTime;
 
 pub fn scan_projects(home_directory: &str) -> Result<Vec<ProjectInfo>, String> {
-    let expanded = tilde(home_directory);
-    let primary_base = PathBuf::from(expanded.into_owned());
+    let primary_base = expand_home(home_directory);
 
     let mut base_candidates = Vec::new();
 
@@ -1,5 +1,7 @@
+use crate::providers::common::file_access::{self, ProviderRoot};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
 use std::fs;
 use std::path::{Path, PathBuf};
 
@@ -37,6 +39,9 @@ pub struct OpenCodeMessage {
     #[serde(rename = "sessionID")]
     pub session_id: String,
     pub time: OpenCodeTime,
+    /// Model that produced the message (assistant messages only)
+    #[serde(rename = "modelID", default)]
+    pub model_id: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -117,6 +122,8 @@ pub struct OpenCodeJsonLEntry {
 pub struct OpenCodeJsonLMessage {
     pub role: String,
     pub content: Vec<OpenCodeJsonLContent>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -177,281 +184,41 @@ pub struct ParsedSession {
 
 pub struct OpenCodeParser {
     storage_path: PathBuf,
+    /// IDs read from session files become paths, which must stay in storage
+    root: ProviderRoot,
 }
 
 impl OpenCodeParser {
     pub fn new(storage_path: PathBuf) -> Self {
-        Self { storage_path }
+        let root = ProviderRoot::new("opencode", &storage_path);
+        Self { storage_path, root }
     }
 
     pub fn parse_session(&self, session_id: &str) -> Result<ParsedSession, String> {
         // Load session metadata first to get project ID
         let session = self.load_session(session_id)?;
-        let project_id = session
-            .project_id
-            .ok_or_else(|| format!("Session {} has no project ID", session_id))?;
-        let project = self.load_project(&project_id)?;
-        let cwd = Some(project.worktree.clone());
+        let project = self.load_project_for(&session)?;
+        let cwd = Some(project.worktree.as_str());
 
-        // Load all messages for this session
+        // Load all messages for this session, with their parts, in chronological order
         let messages = self.load_messages_for_session(session_id)?;
-
-        // Track aggregated metrics
-        let mut total_input_tokens = 0i64;
-        let mut total_output_tokens = 0i64;
-        let mut total_reasoning_tokens = 0i64;
-        let mut total_cache_write = 0i64;
-        let mut total_cache_read = 0i64;
-        let mut total_cost = 0.0;
-        let mut tool_count = 0usize;
-        let mut file_count = 0usize;
-
-        // Load all parts for each message and organize chronologically
-        let mut session_entries = Vec::new();
+        let mut aggregates = Vec::with_capacity(messages.len());
 
         for message in messages {
             let parts = self.load_parts_for_message(&message.id)?;
-
-            // Process parts and create separate entries for tool use/results
-            let mut text_content: Vec<OpenCodeJsonLContent> = Vec::new();
-
-            let base_timestamp = message
-                .time
-                .created
-                .or(message.time.completed) // Messages use 'completed' not 'initialized/updated'
-                .or(message.time.initialized)
-                .or(message.time.updated)
-                .and_then(DateTime::from_timestamp_millis)
-                .unwrap_or_else(Utc::now);
-
-            for part in parts {
-                match part.part_type.as_str() {
-                    "text" => {
-                        if let Some(text) = part.text {
-                            text_content.push(OpenCodeJsonLContent::Text {
-                                content_type: "text".to_string(),
-                                text,
-                            });
-                        }
-                    }
-                    "tool" => {
-                        if let (Some(tool_name), Some(call_id), Some(state)) = (
-                            part.tool.as_ref(),
-                            part.call_id.as_ref(),
-                            part.state.as_ref(),
-                        ) {
-                            tool_count += 1;
-
-                            // Get timestamp from part if available
-                            // For tool parts, check state.time first, then part.time
-                            let part_timestamp = state
-                                .time
-                                .as_ref()
-                                .and_then(|t| t.start)
-                                .or_else(|| {
-                                    part.time
-                                        .as_ref()
-                                        .and_then(|t| t.start)
-                                })
-                                .and_then(DateTime::from_timestamp_millis)
-                                .unwrap_or(base_timestamp);
-
-                            // Create separate entry for tool use
-                            let tool_use_entry = OpenCodeJsonLEntry {
-                                session_id: session_id.to_string(),
-                                timestamp: part_timestamp.to_rfc3339(),
-                                entry_type: "tool_use".to_string(),
-                                message: OpenCodeJsonLMessage {
-                                    role: "tool".to_string(),
-                                    content: vec![OpenCodeJsonLContent::ToolUse {
-                                        content_type: "tool_use".to_string(),
-                                        id: call_id.clone(),
-                                        name: tool_name.clone(),
-                                        input: state
-                                            .input
-                                            .clone()
-                                            .unwrap_or(serde_json::Value::Null),
-                                    }],
-                                },
-                                cwd: cwd.clone(),
-                            };
-                            session_entries.push((part_timestamp, tool_use_entry));
-
-                            // Create separate entry for tool result if output exists
-                            if let Some(output) = state.output.as_ref() {
-                                // For tool parts, check state.time.end first, then part.time.end
-                                let result_timestamp = state
-                                    .time
-                                    .as_ref()
-                                    .and_then(|t| t.end)
-                                    .or_else(|| {
-                                        part.time
-                                            .as_ref()
-                                            .and_then(|t| t.end)
-                                    })
-                                    .and_then(DateTime::from_timestamp_millis)
-                                    .unwrap_or_else(|| {
-                                        part_timestamp + chrono::Duration::milliseconds(1)
-                                    });
-
-                                let tool_result_entry = OpenCodeJsonLEntry {
-                                    session_id: session_id.to_string(),
-                                    timestamp: result_timestamp.to_rfc3339(),
-                                    entry_type: "tool_result".to_string(),
-                                    message: OpenCodeJsonLMessage {
-                                        role: "tool".to_string(),
-                                        content: vec![OpenCodeJsonLContent::ToolResult {
-                                            content_type: "tool_result".to_string(),
-                                            tool_use_id: call_id.clone(),
-                                            content: output.clone(),
-                                            is_error: Some(state.status != "completed"),
-                                        }],
-                                    },
-                                    cwd: cwd.clone(),
-                                };
-                                session_entries.push((result_timestamp, tool_result_entry));
-                            }
-                        }
-                    }
-                    "file" => {
-                        if let (Some(filename), Some(mime), Some(url)) = (
-                            part.filename.as_ref(),
-                            part.mime.as_ref(),
-                            part.url.as_ref(),
-                        ) {
-                            file_count += 1;
-                            text_content.push(OpenCodeJsonLContent::File {
-                                content_type: "file".to_string(),
-                                filename: filename.clone(),
-                                mime: mime.clone(),
-                                url: url.clone(),
-                            });
-                        }
-                    }
-                    "patch" => {
-                        if let (Some(files), Some(hash)) = (part.files.as_ref(), part.hash.as_ref())
-                        {
-                            if !files.is_empty() {
-                                text_content.push(OpenCodeJsonLContent::Patch {
-                                    content_type: "patch".to_string(),
-                                    files: files.clone(),
-                                    hash: hash.clone(),
-                                });
-                            }
-                        }
-                    }
-                    "step-finish" => {
-                        // Aggregate token usage
-                        if let Some(tokens) = part.tokens.as_ref() {
-                            total_input_tokens += tokens.input.unwrap_or(0);
-                            total_output_tokens += tokens.output.unwrap_or(0);
-                            total_reasoning_tokens += tokens.reasoning.unwrap_or(0);
-                            if let Some(cache) = tokens.cache.as_ref() {
-                                total_cache_write += cache.write.unwrap_or(0);
-                                total_cache_read += cache.read.unwrap_or(0);
-                            }
-                        }
-                        if let Some(cost) = part.cost {
-                            total_cost += cost;
-                        }
-                    }
-                    _ => {
-                        // Skip other types (step-start, snapshot, etc.)
-                    }
-                }
-            }
-
-            // Create entry for text/file/patch content if any
-            if !text_content.is_empty() {
-                let entry = OpenCodeJsonLEntry {
-                    session_id: session_id.to_string(),
-                    timestamp: base_timestamp.to_rfc3339(),
-                    entry_type: message.role.clone(),
-                    message: OpenCodeJsonLMessage {
-                        role: message.role,
-                        content: text_content,
-                    },
-                    cwd: cwd.clone(),
-                };
-
-                session_entries.push((base_timestamp, entry));
-            }
+            aggregates.push(aggregate_message(session_id, &message, parts, cwd));
         }
 
-        // Sort entries by timestamp
-        session_entries.sort_by_key(|(timestamp, _)| *timestamp);
-
-        // Build JSONL content
-        let jsonl_content = session_entries
-            .iter()
-            .map(|(_, entry)| serde_json::to_string(entry).unwrap_or_default())
-            .filter(|line| !line.is_empty())
-            .collect::<Vec<_>>()
-            .join("\n");
-
-        // Calculate session timing
-        let session_start_time = session_entries.first().map(|(ts, _)| *ts);
-        let session_end_time = session_entries.last().map(|(ts, _)| *ts);
-        let duration_ms = match (session_start_time, session_end_time) {
-            (Some(start), Some(end)) => Some((end - start).num_milliseconds()),
-            _ => None,
-        };
-
-        // Extract project name from worktree path
-        let project_name = Path::new(&project.worktree)
-            .file_name()
-            .and_then(|name| name.to_str())
-            .unwrap_or("unknown")
-            .to_string();
-
-        // Build aggregated token data
-        let total_tokens = if total_input_tokens > 0 || total_output_tokens > 0 {
-            Some(OpenCodeTokens {
-                input: Some(total_input_tokens),
-                output: Some(total_output_tokens),
-                reasoning: if total_reasoning_tokens > 0 {
-                    Some(total_reasoning_tokens)
-                } else {
-                    None
-                },
-                cache: if total_cache_read > 0 || total_cache_write > 0 {
-                    Some(OpenCodeTokenCache {
-                        write: if total_cache_write > 0 {
-                            Some(total_cache_write)
-                        } else {
-                            None
-                        },
-                        read: if total_cache_read > 0 {
-                            Some(total_cache_read)
-                        } else {
-                            None
-                        },
-                    })
-                } else {
-                    None
-                },
-            })
-        } else {
-            None
-        };
+        Ok(build_parsed_session(session_id, &project, aggregates.iter()))
+    }
 
-        Ok(ParsedSession {
-            session_id: session_id.to_string(),
-            project_name,
-            session_start_time,
-            session_end_time,
-            duration_ms,
-            jsonl_content,
-            total_tokens,
-            total_cost: if total_cost > 0.0 {
-                Some(total_cost)
-            } else {
-                None
-            },
-            tool_count,
-            file_count,
-            cwd: Some(project.worktree.clone()),
-        })
+    /// Load the project a session belongs to
+    pub fn load_project_for(&self, session: &OpenCodeSession) -> Result<OpenCodeProject, String> {
+        let project_id = session
+            .project_id
+            .as_ref()
+            .ok_or_else(|| format!("Session {} has no project ID", session.id))?;
+        self.load_project(project_id)
     }
 
     pub fn get_sessions_for_project(&self, project_id: &str) -> Result<Vec<String>, String> {
@@ -501,7 +268,7 @@ impl OpenCodeParser {
         Ok(projects)
     }
 
-    fn load_session(&self, session_id: &str) -> Result<OpenCodeSession, String> {
+    pub fn load_session(&self, session_id: &str) -> Result<OpenCodeSession, String> {
         // We need to find the session file - it could be in any project directory
         let session_base_dir = self.storage_path.join("session");
 
@@ -513,8 +280,7 @@ impl OpenCodeParser {
             if project_session_dir.is_dir() {
                 let session_file = project_session_dir.join(format!("{}.json", session_id));
                 if session_file.exists() {
-                    let content = fs::read_to_string(&session_file)
-                        .map_err(|e| format!("Failed to read session file: {}", e))?;
+                    let content = self.root.read_to_string(&session_file)?;
 
                     let mut session: OpenCodeSession = serde_json::from_str(&content)
                         .map_err(|e| format!("Failed to parse session JSON: {}", e))?;
@@ -542,12 +308,13 @@ impl OpenCodeParser {
             .storage_path
             .join("project")
             .join(format!("{}.json", project_id));
+        self.root.check(&project_file)?;
         self.load_project_from_path(&project_file)
     }
 
     fn load_project_from_path(&self, path: &Path) -> Result<OpenCodeProject, String> {
-        let content =
-            fs::read_to_string(path).map_err(|e| format!("Failed to read project file: {}", e))?;
+        let content = file_access::read_to_string(path)
+            .map_err(|e| format!("Failed to read project file: {}", e))?;
 
         let project: OpenCodeProject = serde_json::from_str(&content)
             .map_err(|e| format!("Failed to parse project JSON: {}", e))?;
@@ -555,8 +322,19 @@ impl OpenCodeParser {
         Ok(project)
     }
 
+    /// Directory holding the message files for a session
+    pub fn message_dir(&self, session_id: &str) -> PathBuf {
+        self.storage_path.join("message").join(session_id)
+    }
+
+    /// Directory holding the part files for a message
+    pub fn part_dir(&self, message_id: &str) -> PathBuf {
+        self.storage_path.join("part").join(message_id)
+    }
+
     fn load_messages_for_session(&self, session_id: &str) -> Result<Vec<OpenCodeMessage>, String> {
-        let message_dir = self.storage_path.join("message").join(session_id);
+        let message_dir = self.message_dir(session_id);
+        self.root.check(&message_dir)?;
         if !message_dir.exists() {
             return Ok(Vec::new());
         }
@@ -568,31 +346,19 @@ impl OpenCodeParser {
         for entry in entries.flatten() {
             let path = entry.path();
             if path.extension().and_then(|ext| ext.to_str()) == Some("json") {
-                let content = fs::read_to_string(&path)
-                    .map_err(|e| format!("Failed to read message file: {}", e))?;
-
-                let message: OpenCodeMessage = serde_json::from_str(&content)
-                    .map_err(|e| format!("Failed to parse message JSON: {}", e))?;
-
-                messages.push(message);
+                messages.push(load_message_file(&path)?);
             }
         }
 
         // Sort messages by creation time
-        messages.sort_by_key(|msg| {
-            msg.time
-                .created
-                .or(msg.time.completed)
-                .or(msg.time.initialized)
-                .or(msg.time.updated)
-                .unwrap_or(0)
-        });
+        messages.sort_by(compare_messages);
 
         Ok(messages)
     }
 
     fn load_parts_for_message(&self, message_id: &str) -> Result<Vec<OpenCodePart>, String> {
-        let part_dir = self.storage_path.join("part").join(message_id);
+        let part_dir = self.part_dir(message_id);
+        self.root.check(&part_dir)?;
         if !part_dir.exists() {
             return Ok(Vec::new());
         }
@@ -604,18 +370,12 @@ impl OpenCodeParser {
         for entry in entries.flatten() {
             let path = entry.path();
             if path.extension().and_then(|ext| ext.to_str()) == Some("json") {
-                let content = fs::read_to_string(&path)
-                    .map_err(|e| format!("Failed to read part file: {}", e))?;
-
-                let part: OpenCodePart = serde_json::from_str(&content)
-                    .map_err(|e| format!("Failed to parse part JSON: {}", e))?;
-
-                parts.push(part);
+                parts.push(load_part_file(&path)?);
             }
         }
 
         // Sort parts by start time if available
-        parts.sort_by_key(|part| part.time.as_ref().and_then(|t| t.start).unwrap_or(0));
+        parts.sort_by(compare_parts);
 
         Ok(parts)
     }
@@ -645,7 +405,7 @@ impl OpenCodeParser {
 
                     if message_file.exists() {
                         // Found the message file - read it to get the session ID
-                        if let Ok(content) = fs::read_to_string(&message_file) {
+                        if let Ok(content) = self.root.read_to_string(&message_file) {
                             if let Ok(message) = serde_json::from_str::<OpenCodeMessage>(&content) {
                                 return Some(message.session_id);
                             }
@@ -667,6 +427,346 @@ impl OpenCodeParser {
     }
 }
 
+/// Read a single message file
+pub fn load_message_file(path: &Path) -> Result<OpenCodeMessage, String> {
+    let content = file_access::read_to_string(path)
+        .map_err(|e| format!("Failed to read message file: {}", e))?;
+
+    serde_json::from_str(&content).map_err(|e| format!("Failed to parse message JSON: {}", e))
+}
+
+/// Read a single part file
+pub fn load_part_file(path: &Path) -> Result<OpenCodePart, String> {
+    let content = file_access::read_to_string(path)
+        .map_err(|e| format!("Failed to read part file: {}", e))?;
+
+    serde_json::from_str(&content).map_err(|e| format!("Failed to parse part JSON: {}", e))
+}
+
+/// Chronological ordering for messages (ties broken by ID for stable output)
+pub fn compare_messages(a: &OpenCodeMessage, b: &OpenCodeMessage) -> Ordering {
+    let key = |message: &OpenCodeMessage| {
+        message
+            .time
+            .created
+            .or(message.time.completed)
+            .or(message.time.initialized)
+            .or(message.time.updated)
+            .unwrap_or(0)
+    };
+    key(a).cmp(&key(b)).then_with(|| a.id.cmp(&b.id))
+}
+
+/// Ordering for parts within a message by start time (ties broken by ID)
+pub fn compare_parts(a: &OpenCodePart, b: &OpenCodePart) -> Ordering {
+    let key = |part: &OpenCodePart| part.time.as_ref().and_then(|t| t.start).unwrap_or(0);
+    key(a).cmp(&key(b)).then_with(|| a.id.cmp(&b.id))
+}
+
+/// Entries and metrics produced from a single message and its parts
+#[derive(Debug, Clone, Default)]
+pub struct MessageAggregate {
+    pub entries: Vec<(DateTime<Utc>, OpenCodeJsonLEntry)>,
+    pub tool_count: usize,
+    pub file_count: usize,
+    pub input_tokens: i64,
+    pub output_tokens: i64,
+    pub reasoning_tokens: i64,
+    pub cache_write: i64,
+    pub cache_read: i64,
+    pub cost: f64,
+}
+
+/// Build the JSONL entries for one message
+///
+/// Parts must already be sorted by start time. Tool parts become separate
+/// tool_use/tool_result entries; text, file and patch parts are combined into
+/// a single entry for the message.
+pub fn aggregate_message(
+    session_id: &str,
+    message: &OpenCodeMessage,
+    parts: Vec<OpenCodePart>,
+    cwd: Option<&str>,
+) -> MessageAggregate {
+    let mut aggregate = MessageAggregate::default();
+
+    // Process parts and create separate entries for tool use/results
+    let mut text_content: Vec<OpenCodeJsonLContent> = Vec::new();
+
+    let base_timestamp = message
+        .time
+        .created
+        .or(message.time.completed) // Messages use 'completed' not 'initialized/updated'
+        .or(message.time.initialized)
+        .or(message.time.updated)
+        .and_then(DateTime::from_timestamp_millis)
+        .unwrap_or_else(Utc::now);
+
+    for part in parts {
+        match part.part_type.as_str() {
+            "text" => {
+                if let Some(text) = part.text {
+                    text_content.push(OpenCodeJsonLContent::Text {
+                        content_type: "text".to_string(),
+                        text,
+                    });
+                }
+            }
+            "tool" => {
+                if let (Some(tool_name), Some(call_id), Some(state)) = (
+                    part.tool.as_ref(),
+                    part.call_id.as_ref(),
+                    part.state.as_ref(),
+                ) {
+                    aggregate.tool_count += 1;
+
+                    // Get timestamp from part if available
+                    // For tool parts, check state.time first, then part.time
+                    let part_timestamp = state
+                        .time
+                        .as_ref()
+                        .and_then(|t| t.start)
+                        .or_else(|| {
+                            part.time
+                                .as_ref()
+                                .and_then(|t| t.start)
+                        })
+                        .and_then(DateTime::from_timestamp_millis)
+                        .unwrap_or(base_timestamp);
+
+                    // Create separate entry for tool use
+                    let tool_use_entry = OpenCodeJsonLEntry {
+                        session_id: session_id.to_string(),
+                        timestamp: part_timestamp.to_rfc3339(),
+                        entry_type: "tool_use".to_string(),
+                        message: OpenCodeJsonLMessage {
+                            role: "tool".to_string(),
+                            model: message.model_id.clone(),
+                            content: vec![OpenCodeJsonLContent::ToolUse {
+                                content_type: "tool_use".to_string(),
+                                id: call_id.clone(),
+                                name: tool_name.clone(),
+                                input: state
+                                    .input
+                                    .clone()
+                                    .unwrap_or(serde_json::Value::Null),
+                            }],
+                        },
+                        cwd: cwd.map(str::to_string),
+                    };
+                    aggregate.entries.push((part_timestamp, tool_use_entry));
+
+                    // Create separate entry for tool result if output exists
+                    if let Some(output) = state.output.as_ref() {
+                        // For tool parts, check state.time.end first, then part.time.end
+                        let result_timestamp = state
+                            .time
+                            .as_ref()
+                            .and_then(|t| t.end)
+                            .or_else(|| {
+                                part.time
+                                    .as_ref()
+                                    .and_then(|t| t.end)
+                            })
+                            .and_then(DateTime::from_timestamp_millis)
+                            .unwrap_or_else(|| {
+                                part_timestamp + chrono::Duration::milliseconds(1)
+                            });
+
+                        let tool_result_entry = OpenCodeJsonLEntry {
+                            session_id: session_id.to_string(),
+                            timestamp: result_timestamp.to_rfc3339(),
+                            entry_type: "tool_result".to_string(),
+                            message: OpenCodeJsonLMessage {
+                                role: "tool".to_string(),
+                                model: message.model_id.clone(),
+                                content: vec![OpenCodeJsonLContent::ToolResult {
+                                    content_type: "tool_result".to_string(),
+                                    tool_use_id: call_id.clone(),
+                                    content: output.clone(),
+                                    is_error: Some(state.status != "completed"),
+                                }],
+                            },
+                            cwd: cwd.map(str::to_string),
+                        };
+                        aggregate.entries.push((result_timestamp, tool_result_entry));
+                    }
+                }
+            }
+            "file" => {
+                if let (Some(filename), Some(mime), Some(url)) = (
+                    part.filename.as_ref(),
+                    part.mime.as_ref(),
+                    part.url.as_ref(),
+                ) {
+                    aggregate.file_count += 1;
+                    text_content.push(OpenCodeJsonLContent::File {
+                        content_type: "file".to_string(),
+                        filename: filename.clone(),
+                        mime: mime.clone(),
+                        url: url.clone(),
+                    });
+                }
+            }
+            "patch" => {
+                if let (Some(files), Some(hash)) = (part.files.as_ref(), part.hash.as_ref())
+                {
+                    if !files.is_empty() {
+                        text_content.push(OpenCodeJsonLContent::Patch {
+                            content_type: "patch".to_string(),
+                            files: files.clone(),
+                            hash: hash.clone(),
+                        });
+                    }
+                }
+            }
+            "step-finish" => {
+                // Aggregate token usage
+                if let Some(tokens) = part.tokens.as_ref() {
+                    aggregate.input_tokens += tokens.input.unwrap_or(0);
+                    aggregate.output_tokens += tokens.output.unwrap_or(0);
+                    aggregate.reasoning_tokens += tokens.reasoning.unwrap_or(0);
+                    if let Some(cache) = tokens.cache.as_ref() {
+                        aggregate.cache_write += cache.write.unwrap_or(0);
+                        aggregate.cache_read += cache.read.unwrap_or(0);
+                    }
+                }
+                if let Some(cost) = part.cost {
+                    aggregate.cost += cost;
+                }
+            }
+            _ => {
+                // Skip other types (step-start, snapshot, etc.)
+            }
+        }
+    }
+
+    // Create entry for text/file/patch content if any
+    if !text_content.is_empty() {
+        let entry = OpenCodeJsonLEntry {
+            session_id: session_id.to_string(),
+            timestamp: base_timestamp.to_rfc3339(),
+            entry_type: message.role.clone(),
+            message: OpenCodeJsonLMessage {
+                role: message.role.clone(),
+                model: message.model_id.clone(),
+                content: text_content,
+            },
+            cwd: cwd.map(str::to_string),
+        };
+
+        aggregate.entries.push((base_timestamp, entry));
+    }
+
+    aggregate
+}
+
+/// Combine per-message aggregates (in message order) into a parsed session
+pub fn build_parsed_session<'a>(
+    session_id: &str,
+    project: &OpenCodeProject,
+    aggregates: impl Iterator<Item = &'a MessageAggregate>,
+) -> ParsedSession {
+    // Track aggregated metrics
+    let mut total_input_tokens = 0i64;
+    let mut total_output_tokens = 0i64;
+    let mut total_reasoning_tokens = 0i64;
+    let mut total_cache_write = 0i64;
+    let mut total_cache_read = 0i64;
+    let mut total_cost = 0.0;
+    let mut tool_count = 0usize;
+    let mut file_count = 0usize;
+
+    let mut session_entries: Vec<&(DateTime<Utc>, OpenCodeJsonLEntry)> = Vec::new();
+
+    for aggregate in aggregates {
+        session_entries.extend(aggregate.entries.iter());
+        tool_count += aggregate.tool_count;
+        file_count += aggregate.file_count;
+        total_input_tokens += aggregate.input_tokens;
+        total_output_tokens += aggregate.output_tokens;
+        total_reasoning_tokens += aggregate.reasoning_tokens;
+        total_cache_write += aggregate.cache_write;
+        total_cache_read += aggregate.cache_read;
+        total_cost += aggregate.cost;
+    }
+
+    // Sort entries by timestamp
+    session_entries.sort_by_key(|(timestamp, _)| *timestamp);
+
+    // Build JSONL content
+    let jsonl_content = session_entries
+        .iter()
+        .map(|(_, entry)| serde_json::to_string(entry).unwrap_or_default())
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    // Calculate session timing
+    let session_start_time = session_entries.first().map(|(ts, _)| *ts);
+    let session_end_time = session_entries.last().map(|(ts, _)| *ts);
+    let duration_ms = match (session_start_time, session_end_time) {
+        (Some(start), Some(end)) => Some((end - start).num_milliseconds()),
+        _ => None,
+    };
+
+    // Extract project name from worktree path
+    let project_name = Path::new(&project.worktree)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or("unknown")
+        .to_string();
+
+    // Build aggregated token data
+    let total_tokens = if total_input_tokens > 0 || total_output_tokens > 0 {
+        Some(OpenCodeTokens {
+            input: Some(total_input_tokens),
+            output: Some(total_output_tokens),
+            reasoning: if total_reasoning_tokens > 0 {
+                Some(total_reasoning_tokens)
+            } else {
+                None
+            },
+            cache: if total_cache_read > 0 || total_cache_write > 0 {
+                Some(OpenCodeTokenCache {
+                    write: if total_cache_write > 0 {
+                        Some(total_cache_write)
+                    } else {
+                        None
+                    },
+                    read: if total_cache_read > 0 {
+                        Some(total_cache_read)
+                    } else {
+                        None
+                    },
+                })
+            } else {
+                None
+            },
+        })
+    } else {
+        None
+    };
+
+    ParsedSession {
+        session_id: session_id.to_string(),
+        project_name,
+        session_start_time,
+        session_end_time,
+        duration_ms,
+        jsonl_content,
+        total_tokens,
+        total_cost: if total_cost > 0.0 {
+            Some(total_cost)
+        } else {
+            None
+        },
+        tool_count,
+        file_count,
+        cwd: Some(project.worktree.clone()),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
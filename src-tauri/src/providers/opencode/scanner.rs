@@ -1,5 +1,7 @@
 //! OpenCode session scanner - discovers and parses OpenCode sessions from ~/.local/share/opencode/storage/
 
+use crate::events::CancellationToken;
+use crate::ignore_list::IgnoreList;
 use crate::logging::{log_info, log_warn};
 use crate::providers::common::SessionInfo;
 use std::fs;
@@ -9,6 +11,7 @@ use std::path::Path;
 pub fn scan_sessions_filtered(
     base_path: &Path,
     selected_projects: Option<&[String]>,
+    cancel: &CancellationToken,
 ) -> Result<Vec<SessionInfo>, String> {
     // Import the OpenCode parser
     use super::parser::OpenCodeParser;
@@ -27,6 +30,11 @@ pub fn scan_sessions_filtered(
         .map_err(|e| format!("Failed to get OpenCode projects: {}", e))?;
 
     for project in projects {
+        // A cancelled scan keeps the sessions already converted
+        if cancel.is_cancelled() {
+            break;
+        }
+
         // Extract project name from worktree path
         let project_name = Path::new(&project.worktree)
             .file_name()
@@ -47,8 +55,12 @@ pub fn scan_sessions_filtered(
             .map_err(|e| format!("Failed to get sessions for project {}: {}", project.id, e))?;
 
         for session_id in session_ids {
+            if cancel.is_cancelled() {
+                break;
+            }
             match parse_opencode_session(&parser, &session_id, &project) {
-                Ok(session_info) => sessions.push(session_info),
+                Ok(Some(session_info)) => sessions.push(session_info),
+                Ok(None) => {}
                 Err(e) => {
                     if let Err(log_err) = log_warn(
                         "opencode",
@@ -71,40 +83,39 @@ pub fn scan_sessions_filtered(
     Ok(sessions)
 }
 
+/// Parse a session and convert it into the canonical cache; None when its
+/// project is ignored
 fn parse_opencode_session(
     parser: &super::parser::OpenCodeParser,
     session_id: &str,
     _project: &super::parser::OpenCodeProject,
-) -> Result<SessionInfo, String> {
-    use super::super::common::{extract_cwd_from_canonical_content, get_canonical_path};
-    use super::converter::convert_opencode_jsonl_to_canonical;
+) -> Result<Option<SessionInfo>, String> {
+    use super::converter::convert_parsed_session;
 
     // Parse the session using the OpenCode parser
     // This aggregates session/message/part files into OpenCode JSONL format
     let parsed_session = parser
         .parse_session(session_id)
         .map_err(|e| format!("Failed to parse session with OpenCode parser: {}", e))?;
+    if IgnoreList::current().is_ignored(&parsed_session.project_name, parsed_session.cwd.as_deref())
+    {
+        return Ok(None);
+    }
 
-    // Convert aggregated OpenCode JSONL to canonical format
-    let canonical_jsonl = convert_opencode_jsonl_to_canonical(&parsed_session.jsonl_content)
-        .map_err(|e| format!("Failed to convert OpenCode JSONL to canonical: {}", e))?;
-
-    // Extract CWD from canonical content for project organization
-    let cwd = extract_cwd_from_canonical_content(&canonical_jsonl);
-
-    // Get project-organized canonical path
-    let cached_file_path = get_canonical_path("opencode", cwd.as_deref(), session_id)
-        .map_err(|e| format!("Failed to get canonical path: {}", e))?;
-
-    // Write canonical JSONL to project-organized path
-    fs::write(&cached_file_path, &canonical_jsonl)
+    // Convert aggregated OpenCode JSONL to canonical format, then move it to the
+    // project-organized path picked by its CWD
+    let cached_file_path = convert_parsed_session(&parsed_session, &parser.message_dir(session_id))
+        .map_err(|e| format!("Failed to convert to canonical format: {}", e))?
+        .persist()
         .map_err(|e| format!("Failed to write cached JSONL: {}", e))?;
 
     let file_name = format!("{}.jsonl", session_id);
 
-    let file_size = canonical_jsonl.len() as u64;
+    let file_size = fs::metadata(&cached_file_path)
+        .map(|m| m.len())
+        .unwrap_or(0);
 
-    Ok(SessionInfo {
+    Ok(Some(SessionInfo {
         provider: "opencode".to_string(),
         project_name: parsed_session.project_name,
         session_id: parsed_session.session_id,
@@ -117,5 +128,5 @@ fn parse_opencode_session(
         content: None, // Now using cached file, not in-memory content
         cwd: parsed_session.cwd,
         project_hash: None, // Not used for OpenCode
-    })
+    }))
 }
@@ -1,14 +1,16 @@
-use super::converter::convert_opencode_jsonl_to_canonical;
+use super::aggregator::SessionAggregator;
+use super::converter::convert_parsed_session;
 use super::parser::OpenCodeParser;
 use crate::config::load_provider_config;
 use crate::events::{EventBus, SessionEventPayload};
+use crate::ignore_list::IgnoreList;
 use crate::logging::{log_error, log_info};
-use crate::providers::common::{WatcherStatus, FILE_WATCH_POLL_INTERVAL};
+use crate::providers::common::{
+    access, expand_home, file_watcher, FileWatcher, WatcherStatus, FILE_WATCH_POLL_INTERVAL,
+};
 use crate::upload_queue::UploadQueue;
-use notify::{Config, Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
-use shellexpand::tilde;
+use notify::{Config, Event, EventKind, RecursiveMode};
 use std::collections::HashSet;
-use std::fs;
 use std::path::{Path, PathBuf};
 use std::sync::{mpsc, Arc, Mutex};
 use std::thread;
@@ -38,7 +40,7 @@ pub struct OpenCodeSessionState {
 
 #[derive(Debug)]
 pub struct OpenCodeWatcher {
-    _watcher: RecommendedWatcher,
+    _watcher: FileWatcher,
     _thread_handle: thread::JoinHandle<()>,
     upload_queue: Arc<UploadQueue>,
     is_running: Arc<Mutex<bool>>,
@@ -63,8 +65,8 @@ impl OpenCodeWatcher {
         }
 
         let home_directory = config.home_directory;
-        let expanded_home = tilde(&home_directory);
-        let base_path = Path::new(expanded_home.as_ref());
+        let expanded_home = expand_home(&home_directory);
+        let base_path = expanded_home.as_path();
 
         if !base_path.exists() {
             return Err(format!(
@@ -134,7 +136,9 @@ impl OpenCodeWatcher {
         let (tx, rx) = mpsc::channel();
 
         // Create the file watcher
-        let mut watcher = RecommendedWatcher::new(
+        let watcher = FileWatcher::new(
+            PROVIDER_ID,
+            base_path,
             tx,
             Config::default().with_poll_interval(FILE_WATCH_POLL_INTERVAL),
         )?;
@@ -154,10 +158,13 @@ impl OpenCodeWatcher {
         let upload_queue_clone = Arc::clone(&upload_queue);
         let event_bus_clone = event_bus.clone();
 
+        let file_watcher = watcher.clone();
+
         // Start background thread to handle file events
         let thread_handle = thread::spawn(move || {
             Self::file_event_processor(
                 rx,
+                file_watcher,
                 storage_path,
                 parser,
                 projects_to_watch,
@@ -176,34 +183,32 @@ impl OpenCodeWatcher {
     }
 
     /// Aggregate session into virtual JSONL and write to cache
-    /// Returns (jsonl_path, project_name)
+    /// Returns (jsonl_path, project_name), or None when the project is ignored
     fn aggregate_session(
         parser: &OpenCodeParser,
+        aggregator: &mut SessionAggregator,
         session_id: &str,
         _project_id: &str,
-    ) -> Result<(PathBuf, String), Box<dyn std::error::Error + Send + Sync>> {
-        use crate::providers::common::{extract_cwd_from_canonical_content, get_canonical_path};
-
-        // Parse session to create aggregated OpenCode JSONL
-        // This is the critical snapshot process that gathers all session, message, and part files
-        let parsed_session = parser
-            .parse_session(session_id)
+        changed_files: &HashSet<PathBuf>,
+    ) -> Result<Option<(PathBuf, String)>, Box<dyn std::error::Error + Send + Sync>> {
+        // Aggregate session, message, and part files into OpenCode JSONL
+        // Only files changed since the last aggregation are re-read
+        let (parsed_session, mode) = aggregator
+            .aggregate(parser, session_id, changed_files)
             .map_err(|e| format!("Failed to parse OpenCode session {}: {}", session_id, e))?;
+        if IgnoreList::current()
+            .is_ignored(&parsed_session.project_name, parsed_session.cwd.as_deref())
+        {
+            return Ok(None);
+        }
 
         // Convert aggregated OpenCode JSONL to canonical format
-        let canonical_jsonl = convert_opencode_jsonl_to_canonical(&parsed_session.jsonl_content)
-            .map_err(|e| format!("Failed to convert OpenCode JSONL to canonical: {}", e))?;
+        let converted = convert_parsed_session(&parsed_session, &parser.message_dir(session_id))?;
+        let message_count = converted.message_count();
 
-        // Extract CWD from canonical content for project organization
-        let cwd = extract_cwd_from_canonical_content(&canonical_jsonl);
-
-        // Get project-organized canonical path
+        // Move to the project-organized path picked by the session's CWD
         // Uses ~/.guidemode/sessions/{provider}/{project}/{session_id}.jsonl
-        let jsonl_path = get_canonical_path(PROVIDER_ID, cwd.as_deref(), session_id)
-            .map_err(|e| format!("Failed to get canonical path: {}", e))?;
-
-        // Write canonical JSONL to project-organized path
-        fs::write(&jsonl_path, &canonical_jsonl)?;
+        let jsonl_path = converted.persist()?;
 
         // Extract real project name from parsed session (not the GUID)
         let project_name = parsed_session.project_name.clone();
@@ -211,17 +216,18 @@ impl OpenCodeWatcher {
         if let Err(e) = log_info(
             PROVIDER_ID,
             &format!(
-                "📝 Aggregated session {} → {} ({} bytes, project: {})",
+                "📝 Aggregated session {} → {} ({} messages, project: {}, {})",
                 session_id,
                 jsonl_path.display(),
-                canonical_jsonl.len(),
-                project_name
+                message_count,
+                project_name,
+                mode
             ),
         ) {
             eprintln!("Logging error: {}", e);
         }
 
-        Ok((jsonl_path, project_name))
+        Ok(Some((jsonl_path, project_name)))
     }
 
     fn discover_all_projects(
@@ -245,8 +251,10 @@ impl OpenCodeWatcher {
         Ok(project_names)
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn file_event_processor(
         rx: mpsc::Receiver<Result<Event, notify::Error>>,
+        file_watcher: FileWatcher,
         storage_path: PathBuf,
         parser: OpenCodeParser,
         projects_to_watch: Vec<String>,
@@ -256,6 +264,7 @@ impl OpenCodeWatcher {
     ) {
         let mut session_states: std::collections::HashMap<String, OpenCodeSessionState> =
             std::collections::HashMap::new();
+        let mut aggregator = SessionAggregator::new();
 
         loop {
             // Check if we should continue running
@@ -278,11 +287,13 @@ impl OpenCodeWatcher {
                     }
                 }
                 Ok(Err(error)) => {
-                    if let Err(e) = log_error(
-                        PROVIDER_ID,
-                        &format!("OpenCode file watcher error: {:?}", error),
-                    ) {
-                        eprintln!("Logging error: {}", e);
+                    if !file_watcher.handle_error(&error) {
+                        if let Err(e) = log_error(
+                            PROVIDER_ID,
+                            &format!("OpenCode file watcher error: {:?}", error),
+                        ) {
+                            eprintln!("Logging error: {}", e);
+                        }
                     }
                 }
                 Err(mpsc::RecvTimeoutError::Timeout) => {
@@ -311,9 +322,28 @@ impl OpenCodeWatcher {
                 .collect();
 
             for (session_id, project_id) in sessions_to_aggregate {
+                // Files changed since the last aggregation drive the incremental update
+                let changed_files = session_states
+                    .get_mut(&session_id)
+                    .map(|state| std::mem::take(&mut state.affected_files))
+                    .unwrap_or_default();
+
                 // Aggregate session into virtual JSONL
-                match Self::aggregate_session(&parser, &session_id, &project_id) {
-                    Ok((jsonl_path, project_name)) => {
+                match Self::aggregate_session(
+                    &parser,
+                    &mut aggregator,
+                    &session_id,
+                    &project_id,
+                    &changed_files,
+                ) {
+                    // Ignored projects never reach the canonical cache
+                    Ok(None) => {
+                        if let Some(state) = session_states.get_mut(&session_id) {
+                            state.needs_aggregation = false;
+                            state.last_aggregated = Some(now);
+                        }
+                    }
+                    Ok(Some((jsonl_path, project_name))) => {
                         // Get file size
                         let file_size = jsonl_path.metadata().map(|m| m.len()).unwrap_or(0);
 
@@ -342,6 +372,9 @@ impl OpenCodeWatcher {
                         }
                     }
                     Err(e) => {
+                        // Changed files were consumed, so retry from a full rebuild
+                        aggregator.invalidate(&session_id);
+
                         if let Err(log_err) = log_error(
                             PROVIDER_ID,
                             &format!("Failed to aggregate session {}: {}", session_id, e),
@@ -543,6 +576,8 @@ impl OpenCodeWatcher {
             pending_uploads: upload_status.pending,
             processing_uploads: upload_status.processing,
             failed_uploads: upload_status.failed,
+            access_denied: access::denied_path(PROVIDER_ID),
+            watch_limit: file_watcher::watch_limit_hint(PROVIDER_ID),
         }
     }
 }
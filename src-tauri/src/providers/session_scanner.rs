@@ -3,37 +3,67 @@
 //! This module provides a unified interface for scanning sessions across all providers.
 //! Each provider has its own scanner module that handles provider-specific logic.
 
-use crate::providers::common::SessionInfo;
-use shellexpand::tilde;
-use std::path::Path;
+use crate::events::CancellationToken;
+use crate::ignore_list::IgnoreList;
+use crate::providers::common::{ScanReport, SessionInfo};
+use crate::providers::common::access;
+use crate::providers::common::expand_home;
+use std::time::Instant;
 
 #[allow(dead_code)] // Will be removed during provider file reorganization
 pub fn scan_all_sessions(
     provider_id: &str,
     home_directory: &str,
 ) -> Result<Vec<SessionInfo>, String> {
-    scan_all_sessions_filtered(provider_id, home_directory, None)
+    scan_all_sessions_filtered(
+        provider_id,
+        home_directory,
+        None,
+        &CancellationToken::default(),
+    )
+    .map(|report| report.sessions)
 }
 
+/// Scan a provider's sessions. File-based providers reuse sessions whose
+/// source file hasn't changed since the last scan; Cursor and OpenCode, whose
+/// sessions span several files, are scanned in full.
+///
+/// Once `cancel` is cancelled the scanners stop early and the report holds
+/// only the sessions found so far. Sessions of ignored projects are left out.
 pub fn scan_all_sessions_filtered(
     provider_id: &str,
     home_directory: &str,
     selected_projects: Option<&[String]>,
-) -> Result<Vec<SessionInfo>, String> {
-    let expanded = tilde(home_directory);
-    let base_path = Path::new(expanded.as_ref());
+    cancel: &CancellationToken,
+) -> Result<ScanReport, String> {
+    let expanded = expand_home(home_directory);
+    let base_path = expanded.as_path();
 
+    // A directory GuideMode may not read would scan as empty
+    access::ensure_readable(provider_id, base_path)?;
     if !base_path.exists() {
-        return Ok(Vec::new());
+        return Ok(ScanReport::default());
     }
 
-    match provider_id {
-        "claude-code" => super::claude::scanner::scan_sessions_filtered(base_path, selected_projects),
-        "github-copilot" => super::copilot::scanner::scan_sessions_filtered(base_path, selected_projects),
-        "opencode" => super::opencode::scanner::scan_sessions_filtered(base_path, selected_projects),
-        "codex" => super::codex::scanner::scan_sessions_filtered(base_path, selected_projects),
-        "gemini-code" => super::gemini::scanner::scan_sessions_filtered(base_path, selected_projects),
-        "cursor" => super::cursor::scanner::scan_sessions_filtered(base_path, selected_projects),
+    let started = Instant::now();
+    let mut report = match provider_id {
+        "claude-code" => super::claude::scanner::scan_sessions_filtered(base_path, selected_projects, cancel),
+        "github-copilot" => super::copilot::scanner::scan_sessions_filtered(base_path, selected_projects, cancel),
+        "opencode" => super::opencode::scanner::scan_sessions_filtered(base_path, selected_projects, cancel)
+            .map(|sessions| ScanReport::uncached(provider_id, sessions, started)),
+        "codex" => super::codex::scanner::scan_sessions_filtered(base_path, selected_projects, cancel),
+        "gemini-code" => super::gemini::scanner::scan_sessions_filtered(base_path, selected_projects, cancel),
+        "cursor" => super::cursor::scanner::scan_sessions_filtered(base_path, selected_projects, cancel)
+            .map(|sessions| ScanReport::uncached(provider_id, sessions, started)),
         _ => Err(format!("Unsupported provider: {}", provider_id)),
+    }?;
+
+    // Scanners skip ignored projects before converting them; sessions the
+    // scan cache still holds from before their project was ignored are left
+    // out here, and ones whose cwd isn't known yet are checked once it's read
+    let ignore_list = IgnoreList::current();
+    if !ignore_list.is_empty() {
+        report.retain(|session| !ignore_list.is_ignored(&session.project_name, session.cwd.as_deref()));
     }
+    Ok(report)
 }
@@ -0,0 +1,204 @@
+//! Provider CLI versions and converter compatibility
+//!
+//! Agents change their session formats between releases, and a converter
+//! meeting a format it doesn't know drops messages or whole sessions. Each
+//! CLI's version is detected at startup and kept in
+//! ~/.guidemode/providers/versions.json; a version newer than the one its
+//! converter was last tested with is logged to the provider's log and sent to
+//! the frontend as a `provider-version-warning` event. Validation failures
+//! logged afterwards mention the untested version, so a parse failure comes
+//! with its likely cause.
+
+use crate::config::{ensure_providers_dir, get_providers_dir};
+use crate::logging::log_warn;
+use crate::providers::detection::cli_version;
+use chrono::{DateTime, Utc};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fs;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter};
+
+/// Newest release of each provider the converters were tested with
+const TESTED_VERSIONS: &[(&str, &str)] = &[
+    ("claude-code", "2.0.14"),
+    ("github-copilot", "0.0.340"),
+    ("opencode", "0.15.0"),
+    ("codex", "0.46.0"),
+    ("gemini-code", "0.9.0"),
+    ("cursor", "2025.10.02"),
+];
+
+/// Latest check, for annotating validation failures
+static LATEST: Mutex<Option<HashMap<String, ProviderVersion>>> = Mutex::new(None);
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderVersion {
+    pub provider_id: String,
+    /// Installed CLI version, `None` when the CLI isn't on this machine
+    pub version: Option<String>,
+    pub tested_version: String,
+    pub newer_than_tested: bool,
+    pub detected_at: DateTime<Utc>,
+}
+
+impl ProviderVersion {
+    fn warning(&self) -> Option<String> {
+        if !self.newer_than_tested {
+            return None;
+        }
+        Some(format!(
+            "{} {} is newer than {}, the latest version GuideMode was tested with; \
+             sessions in a changed format may fail to convert",
+            self.provider_id,
+            self.version.as_deref().unwrap_or("unknown"),
+            self.tested_version
+        ))
+    }
+}
+
+/// Detect every provider's CLI version and store the results
+pub fn check_all() -> Vec<ProviderVersion> {
+    let versions: Vec<ProviderVersion> = TESTED_VERSIONS
+        .par_iter()
+        .map(|(provider_id, tested)| evaluate(provider_id, cli_version(provider_id), tested))
+        .collect();
+
+    for version in &versions {
+        if let Some(warning) = version.warning() {
+            log_warn(&version.provider_id, &format!("⚠ {}", warning)).unwrap_or_default();
+        }
+    }
+
+    if let Err(e) = save(&versions) {
+        log_warn(
+            "providers",
+            &format!("Failed to store provider versions: {}", e),
+        )
+        .unwrap_or_default();
+    }
+    if let Ok(mut latest) = LATEST.lock() {
+        *latest = Some(
+            versions
+                .iter()
+                .map(|v| (v.provider_id.clone(), v.clone()))
+                .collect(),
+        );
+    }
+    versions
+}
+
+/// Check versions in the background and tell the frontend about untested ones
+pub fn check_and_publish(app: AppHandle) {
+    tauri::async_runtime::spawn_blocking(move || {
+        let untested: Vec<ProviderVersion> = check_all()
+            .into_iter()
+            .filter(|version| version.newer_than_tested)
+            .collect();
+        if !untested.is_empty() {
+            let _ = app.emit("provider-version-warning", &untested);
+        }
+    });
+}
+
+/// Versions stored by the last check
+pub fn load() -> Vec<ProviderVersion> {
+    get_providers_dir()
+        .ok()
+        .and_then(|dir| fs::read_to_string(dir.join("versions.json")).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// Why conversions of `provider_id` may be failing, if its version is untested
+pub fn compatibility_note(provider_id: &str) -> Option<String> {
+    LATEST.lock().ok()?.as_ref()?.get(provider_id)?.warning()
+}
+
+/// Installed CLI version of `provider_id` found by the latest check
+pub fn detected_version(provider_id: &str) -> Option<String> {
+    LATEST
+        .lock()
+        .ok()?
+        .as_ref()?
+        .get(provider_id)?
+        .version
+        .clone()
+}
+
+fn save(versions: &[ProviderVersion]) -> Result<(), Box<dyn std::error::Error>> {
+    ensure_providers_dir()?;
+    let content = serde_json::to_string_pretty(versions)?;
+    fs::write(get_providers_dir()?.join("versions.json"), content)?;
+    Ok(())
+}
+
+fn evaluate(provider_id: &str, version: Option<String>, tested: &str) -> ProviderVersion {
+    let newer_than_tested = version
+        .as_deref()
+        .is_some_and(|version| compare_versions(version, tested) == Ordering::Greater);
+    ProviderVersion {
+        provider_id: provider_id.to_string(),
+        version,
+        tested_version: tested.to_string(),
+        newer_than_tested,
+        detected_at: Utc::now(),
+    }
+}
+
+/// Compare dotted versions numerically, ignoring pre-release and build suffixes
+fn compare_versions(a: &str, b: &str) -> Ordering {
+    fn parts(version: &str) -> Vec<u64> {
+        version
+            .split(['-', '+'])
+            .next()
+            .unwrap_or_default()
+            .split('.')
+            .map(|part| part.parse().unwrap_or(0))
+            .collect()
+    }
+    let (a, b) = (parts(a), parts(b));
+    let len = a.len().max(b.len());
+    (0..len)
+        .map(|i| a.get(i).unwrap_or(&0).cmp(b.get(i).unwrap_or(&0)))
+        .find(|ordering| ordering.is_ne())
+        .unwrap_or(Ordering::Equal)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compare_versions() {
+        assert_eq!(compare_versions("2.0.15", "2.0.14"), Ordering::Greater);
+        assert_eq!(compare_versions("2.0.9", "2.0.14"), Ordering::Less);
+        assert_eq!(compare_versions("2.0", "2.0.0"), Ordering::Equal);
+        assert_eq!(
+            compare_versions("0.47.0-alpha.1", "0.46.0"),
+            Ordering::Greater
+        );
+        assert_eq!(
+            compare_versions("2025.09.18-7ae6800", "2025.10.02"),
+            Ordering::Less
+        );
+    }
+
+    #[test]
+    fn test_only_newer_versions_warn() {
+        let newer = evaluate("codex", Some("0.50.1".to_string()), "0.46.0");
+        assert!(newer.newer_than_tested);
+        assert!(newer
+            .warning()
+            .unwrap()
+            .contains("codex 0.50.1 is newer than 0.46.0"));
+
+        assert!(evaluate("codex", Some("0.46.0".to_string()), "0.46.0")
+            .warning()
+            .is_none());
+        assert!(evaluate("codex", None, "0.46.0").warning().is_none());
+    }
+}
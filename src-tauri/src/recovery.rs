@@ -0,0 +1,240 @@
+//! Startup repair of session state left inconsistent by a crash
+//!
+//! The upload queue itself is in memory and rebuilt from unsynced sessions,
+//! but the flags it and the metrics handler write can be left contradicting
+//! each other when the app dies mid-way:
+//!
+//! - Sessions marked synced without a sync time, or marked both synced and
+//!   failed, are reset to unsynced. Re-uploading is cheap: the server's hash
+//!   check skips content it already has, so the server settles the mismatch.
+//! - Sessions whose core metrics are marked completed without a metrics row
+//!   are marked pending again.
+//! - Recently changed sessions still pending core metrics were waiting on the
+//!   metrics handler's debounce when the app stopped. Nothing will change
+//!   them again, so their metrics are computed in the background.
+//! - Temporary files from interrupted conversions are removed.
+//!
+//! `run` must be called before watchers start, while no conversion can be in
+//! progress.
+
+use crate::database::with_connection_mut;
+use crate::logging::{log_error, log_info};
+use crate::metrics::compute_session_metrics;
+use crate::providers::common::CanonicalWriter;
+use chrono::{Duration, Utc};
+use rusqlite::{params, Connection};
+
+/// How far back a pending session counts as interrupted rather than historical
+const PENDING_METRICS_WINDOW_HOURS: i64 = 24;
+
+/// What the startup recovery pass repaired
+#[derive(Debug, Default, PartialEq)]
+pub struct RecoveryReport {
+    pub synced_without_time: usize,
+    pub synced_and_failed: usize,
+    pub missing_metrics: usize,
+    pub pending_metrics: Vec<String>,
+    pub partial_files: usize,
+}
+
+impl RecoveryReport {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    fn summary(&self) -> String {
+        [
+            (
+                self.synced_without_time,
+                "marked synced without a sync time",
+            ),
+            (self.synced_and_failed, "marked both synced and failed"),
+            (
+                self.missing_metrics,
+                "marked with metrics that were never stored",
+            ),
+            (self.pending_metrics.len(), "left waiting for core metrics"),
+            (self.partial_files, "interrupted conversion files"),
+        ]
+        .iter()
+        .filter(|(count, _)| *count > 0)
+        .map(|(count, what)| format!("{} {}", count, what))
+        .collect::<Vec<_>>()
+        .join(", ")
+    }
+}
+
+/// Repair inconsistent session state, log a report and compute metrics for
+/// sessions that were interrupted waiting on them
+pub fn run() -> RecoveryReport {
+    let mut report = with_connection_mut(repair).unwrap_or_else(|e| {
+        log_error(
+            "recovery",
+            &format!("Failed to repair session state: {}", e),
+        )
+        .unwrap_or_default();
+        RecoveryReport::default()
+    });
+
+    report.partial_files = CanonicalWriter::remove_abandoned().unwrap_or_else(|e| {
+        log_error(
+            "recovery",
+            &format!("Failed to remove partial files: {}", e),
+        )
+        .unwrap_or_default();
+        0
+    });
+
+    if report.is_empty() {
+        log_info("recovery", "✓ Session state is consistent").unwrap_or_default();
+    } else {
+        log_info(
+            "recovery",
+            &format!("🔧 Repaired sessions: {}", report.summary()),
+        )
+        .unwrap_or_default();
+    }
+
+    let pending = report.pending_metrics.clone();
+    if !pending.is_empty() {
+        tauri::async_runtime::spawn_blocking(move || {
+            for session_id in pending {
+                if let Err(e) = compute_session_metrics(&session_id) {
+                    log_error(
+                        "recovery",
+                        &format!("Failed to compute core metrics for {}: {}", session_id, e),
+                    )
+                    .unwrap_or_default();
+                }
+            }
+        });
+    }
+
+    report
+}
+
+fn repair(conn: &mut Connection) -> rusqlite::Result<RecoveryReport> {
+    let tx = conn.transaction()?;
+
+    let synced_without_time = tx.execute(
+        "UPDATE agent_sessions SET synced_to_server = 0
+         WHERE synced_to_server = 1 AND synced_at IS NULL",
+        [],
+    )?;
+
+    // Keep the failure so the session shows up for retry
+    let synced_and_failed = tx.execute(
+        "UPDATE agent_sessions SET synced_to_server = 0
+         WHERE synced_to_server = 1 AND sync_failed_reason IS NOT NULL",
+        [],
+    )?;
+
+    let missing_metrics = tx.execute(
+        "UPDATE agent_sessions SET core_metrics_status = 'pending'
+         WHERE core_metrics_status = 'completed'
+           AND NOT EXISTS (
+               SELECT 1 FROM session_metrics m WHERE m.session_id = agent_sessions.session_id
+           )",
+        [],
+    )?;
+
+    let since = (Utc::now() - Duration::hours(PENDING_METRICS_WINDOW_HOURS)).timestamp_millis();
+    let pending_metrics = tx
+        .prepare(
+            "SELECT session_id FROM agent_sessions
+             WHERE COALESCE(core_metrics_status, 'pending') = 'pending'
+               AND session_end_time IS NOT NULL
+               AND uploaded_at >= ?
+             ORDER BY uploaded_at DESC",
+        )?
+        .query_map(params![since], |row| row.get(0))?
+        .collect::<rusqlite::Result<Vec<String>>>()?;
+
+    tx.commit()?;
+
+    Ok(RecoveryReport {
+        synced_without_time,
+        synced_and_failed,
+        missing_metrics,
+        pending_metrics,
+        partial_files: 0,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn insert_session(
+        conn: &Connection,
+        session_id: &str,
+        synced: bool,
+        synced_at: Option<i64>,
+        failed: Option<&str>,
+        metrics_status: &str,
+        changed_at: i64,
+    ) {
+        conn.execute(
+            "INSERT INTO agent_sessions (
+                id, provider, project_name, session_id, file_name, file_path, file_size,
+                session_end_time, synced_to_server, synced_at, sync_failed_reason,
+                core_metrics_status, created_at, uploaded_at
+            ) VALUES (?1, 'claude-code', 'app', ?1, ?1, '/s', 100, 1, ?2, ?3, ?4, ?5, 0, ?6)",
+            params![
+                session_id,
+                synced,
+                synced_at,
+                failed,
+                metrics_status,
+                changed_at
+            ],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_repair_inconsistent_sessions() {
+        let mut conn = crate::database::test_connection();
+
+        let now = Utc::now().timestamp_millis();
+        let old = now - Duration::days(30).num_milliseconds();
+        insert_session(&conn, "ok", true, Some(now), None, "pending", old);
+        insert_session(&conn, "no-time", true, None, None, "pending", old);
+        insert_session(
+            &conn,
+            "both",
+            true,
+            Some(now),
+            Some("HTTP 400"),
+            "pending",
+            old,
+        );
+        insert_session(&conn, "no-metrics", false, None, None, "completed", old);
+        insert_session(&conn, "interrupted", false, None, None, "pending", now);
+
+        let report = repair(&mut conn).unwrap();
+        assert_eq!(
+            report,
+            RecoveryReport {
+                synced_without_time: 1,
+                synced_and_failed: 1,
+                missing_metrics: 1,
+                pending_metrics: vec!["interrupted".to_string()],
+                partial_files: 0,
+            }
+        );
+
+        let synced: Vec<String> = conn
+            .prepare("SELECT session_id FROM agent_sessions WHERE synced_to_server = 1")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap();
+        assert_eq!(synced, vec!["ok"]);
+
+        // A second pass has nothing left to repair
+        let report = repair(&mut conn).unwrap();
+        assert_eq!(report.synced_without_time + report.missing_metrics, 0);
+    }
+}
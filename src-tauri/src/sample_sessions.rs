@@ -0,0 +1,675 @@
+//! Synthetic sessions for demos and tests
+//!
+//! Writes realistic sessions in a provider's own on-disk format under a
+//! sample root: the provider's home directory is `<root>/<provider>` and the
+//! projects the sessions ran in are empty directories under
+//! `<root>/workspaces`, so project detection resolves them. Pointing a
+//! provider's home directory at the sample home shows the app working
+//! without real data, and tests can drive scanners and watchers end-to-end.
+//!
+//! Cursor isn't supported: its sessions are SQLite databases of protobuf
+//! blobs written by Cursor itself.
+
+use crate::error::GuideModeError;
+use crate::logging::log_info;
+use chrono::{DateTime, Duration, SecondsFormat, Utc};
+use serde::Serialize;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+/// Most sessions one call generates
+pub const MAX_SAMPLE_SESSIONS: usize = 200;
+
+/// Providers that sample sessions can be generated for
+pub const SAMPLE_PROVIDERS: &[&str] = &[
+    "claude-code",
+    "codex",
+    "gemini-code",
+    "github-copilot",
+    "opencode",
+];
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SampleSessions {
+    pub provider_id: String,
+    /// Home directory to point the provider at
+    pub home_directory: String,
+    pub session_ids: Vec<String>,
+}
+
+/// One coding task: a prompt, a shell command the agent ran, and its answer
+struct Scenario {
+    project: &'static str,
+    prompt: &'static str,
+    command: &'static str,
+    output: &'static str,
+    reply: &'static str,
+}
+
+const SCENARIOS: &[Scenario] = &[
+    Scenario {
+        project: "todo-api",
+        prompt: "Add a health check endpoint that reports the database status",
+        command: "grep -rn \"router.get\" src/routes",
+        output: "src/routes/index.ts:12:router.get('/todos', listTodos)\nsrc/routes/index.ts:18:router.get('/todos/:id', getTodo)",
+        reply: "I added `GET /health` next to the todo routes. It returns 200 with the database status, or 503 when the connection check fails.",
+    },
+    Scenario {
+        project: "web-dashboard",
+        prompt: "Why does the revenue chart flicker when the filters change?",
+        command: "npm test -- Chart",
+        output: "PASS src/components/Chart.test.tsx\n  ✓ renders every series (24 ms)\nTests: 1 passed, 1 total",
+        reply: "The chart remounts because its `key` includes the filter object, which is recreated on every render. Memoizing the filters keeps the same chart instance.",
+    },
+    Scenario {
+        project: "data-pipeline",
+        prompt: "Speed up the nightly import, it takes over an hour",
+        command: "python -m cProfile -s cumtime import_job.py --dry-run | head -4",
+        output: "   ncalls  tottime  cumtime filename:lineno(function)\n     4120  812.301 3301.942 db.py:88(insert_row)",
+        reply: "Almost all of the time goes to row-by-row inserts in `insert_row`. Batching them with `executemany` should bring the import down to a few minutes.",
+    },
+    Scenario {
+        project: "release-cli",
+        prompt: "Add a --json flag to the status command",
+        command: "cargo test status",
+        output: "running 4 tests\ntest status::tests::prints_json ... ok\ntest result: ok. 4 passed; 0 failed",
+        reply: "`status --json` now prints the same fields as JSON, and a new test covers the output.",
+    },
+    Scenario {
+        project: "mobile-app",
+        prompt: "Fix the crash when opening a notification while logged out",
+        command: "git log --oneline -3 -- src/notifications",
+        output: "a41c9e2 Open deep links from notifications\n7d03b1f Add notification settings screen\n2c8e5aa Register push token on login",
+        reply: "Deep links from notifications assumed a signed-in user. They now go to the login screen first and resume the link after sign-in.",
+    },
+];
+
+/// A session being written: its scenario, working directory and clock
+struct Sample<'a> {
+    scenario: &'a Scenario,
+    session_id: String,
+    cwd: String,
+    started: DateTime<Utc>,
+}
+
+impl Sample<'_> {
+    /// Timestamp `seconds` into the session
+    fn at(&self, seconds: i64) -> String {
+        (self.started + Duration::seconds(seconds)).to_rfc3339_opts(SecondsFormat::Millis, true)
+    }
+
+    fn millis(&self, seconds: i64) -> i64 {
+        (self.started + Duration::seconds(seconds)).timestamp_millis()
+    }
+}
+
+fn new_id() -> String {
+    Uuid::new_v4().to_string()
+}
+
+/// A compact ID in the style of OpenCode's `ses_`, `msg_` and `prt_` IDs
+fn prefixed_id(prefix: &str) -> String {
+    format!("{}_{}", prefix, Uuid::new_v4().simple())
+}
+
+fn write_json(path: &Path, value: &Value) -> Result<(), GuideModeError> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, serde_json::to_string_pretty(value)?)?;
+    Ok(())
+}
+
+fn write_jsonl(path: &Path, lines: &[Value]) -> Result<(), GuideModeError> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut content = String::new();
+    for line in lines {
+        content.push_str(&serde_json::to_string(line)?);
+        content.push('\n');
+    }
+    fs::write(path, content)?;
+    Ok(())
+}
+
+/// Generate `count` sessions for `provider_id` under `root`, spread over the
+/// last hours, cycling through a handful of projects
+pub fn generate(
+    provider_id: &str,
+    count: usize,
+    root: &Path,
+) -> Result<SampleSessions, GuideModeError> {
+    if !SAMPLE_PROVIDERS.contains(&provider_id) {
+        return Err(GuideModeError::Validation(format!(
+            "Sample sessions aren't available for {}",
+            provider_id
+        )));
+    }
+    if count == 0 || count > MAX_SAMPLE_SESSIONS {
+        return Err(GuideModeError::Validation(format!(
+            "Generate between 1 and {} sample sessions",
+            MAX_SAMPLE_SESSIONS
+        )));
+    }
+
+    let home = root.join(provider_id);
+    let workspaces = root.join("workspaces");
+    fs::create_dir_all(&home)?;
+    for scenario in SCENARIOS {
+        fs::create_dir_all(workspaces.join(scenario.project))?;
+    }
+    // Canonicalized so the paths in the sessions match what scanners resolve
+    let workspaces = fs::canonicalize(&workspaces)?;
+
+    let now = Utc::now();
+    let mut session_ids = Vec::with_capacity(count);
+    for index in 0..count {
+        let scenario = &SCENARIOS[index % SCENARIOS.len()];
+        let sample = Sample {
+            scenario,
+            session_id: match provider_id {
+                "opencode" => prefixed_id("ses"),
+                _ => new_id(),
+            },
+            cwd: workspaces.join(scenario.project).display().to_string(),
+            started: now - Duration::minutes(37 * (count - index) as i64),
+        };
+        match provider_id {
+            "claude-code" => write_claude(&home, &sample)?,
+            "codex" => write_codex(&home, &sample)?,
+            "gemini-code" => write_gemini(&home, &sample)?,
+            "github-copilot" => write_copilot(&home, &sample)?,
+            "opencode" => write_opencode(&home, &sample)?,
+            _ => unreachable!("checked against SAMPLE_PROVIDERS"),
+        }
+        session_ids.push(sample.session_id);
+    }
+    if provider_id == "codex" {
+        write_codex_config(&home, &workspaces)?;
+    }
+
+    log_info(
+        provider_id,
+        &format!(
+            "🧪 Generated {} sample sessions in {}",
+            count,
+            home.display()
+        ),
+    )
+    .unwrap_or_default();
+
+    Ok(SampleSessions {
+        provider_id: provider_id.to_string(),
+        home_directory: home.display().to_string(),
+        session_ids,
+    })
+}
+
+/// `~/.claude/projects/<cwd with separators as dashes>/<session>.jsonl`
+fn write_claude(home: &Path, sample: &Sample) -> Result<(), GuideModeError> {
+    let s = sample.scenario;
+    let project_dir = sample.cwd.replace(['/', '\\', ':', '.'], "-");
+    let path = home
+        .join("projects")
+        .join(project_dir)
+        .join(format!("{}.jsonl", sample.session_id));
+
+    let ids: Vec<String> = (0..4).map(|_| new_id()).collect();
+    let entry = |index: usize, seconds: i64, entry_type: &str, message: Value| {
+        let mut entry = json!({
+            "parentUuid": index.checked_sub(1).map(|parent| ids[parent].clone()),
+            "isSidechain": false,
+            "userType": "external",
+            "cwd": sample.cwd,
+            "sessionId": sample.session_id,
+            "version": "2.0.31",
+            "gitBranch": "main",
+            "type": entry_type,
+            "message": message,
+            "uuid": ids[index],
+            "timestamp": sample.at(seconds),
+        });
+        if entry_type == "assistant" {
+            entry["requestId"] = json!(format!("req_{}", index));
+        }
+        entry
+    };
+    let assistant = |content: Value, output_tokens: u32| {
+        json!({
+            "model": "claude-sonnet-4-5-20250929",
+            "id": format!("msg_{}", Uuid::new_v4().simple()),
+            "type": "message",
+            "role": "assistant",
+            "content": content,
+            "stop_reason": null,
+            "stop_sequence": null,
+            "usage": {
+                "input_tokens": 12,
+                "cache_read_input_tokens": 9800,
+                "output_tokens": output_tokens,
+                "service_tier": "standard",
+            },
+        })
+    };
+
+    write_jsonl(
+        &path,
+        &[
+            entry(0, 0, "user", json!({"role": "user", "content": s.prompt})),
+            entry(
+                1,
+                6,
+                "assistant",
+                assistant(
+                    json!([{
+                        "type": "tool_use",
+                        "id": "toolu_01Bash",
+                        "name": "Bash",
+                        "input": {"command": s.command, "description": "Inspect the project"},
+                    }]),
+                    48,
+                ),
+            ),
+            entry(
+                2,
+                9,
+                "user",
+                json!({"role": "user", "content": [{
+                    "tool_use_id": "toolu_01Bash",
+                    "type": "tool_result",
+                    "content": s.output,
+                    "is_error": false,
+                }]}),
+            ),
+            entry(
+                3,
+                21,
+                "assistant",
+                assistant(json!([{"type": "text", "text": s.reply}]), 96),
+            ),
+        ],
+    )
+}
+
+/// `~/.codex/sessions/YYYY/MM/DD/rollout-<time>-<session>.jsonl`
+fn write_codex(home: &Path, sample: &Sample) -> Result<(), GuideModeError> {
+    let s = sample.scenario;
+    let path = home
+        .join("sessions")
+        .join(sample.started.format("%Y/%m/%d").to_string())
+        .join(format!(
+            "rollout-{}-{}.jsonl",
+            sample.started.format("%Y-%m-%dT%H-%M-%S"),
+            sample.session_id
+        ));
+    let arguments = json!({"command": ["bash", "-lc", s.command]}).to_string();
+    let item = |seconds: i64, item_type: &str, payload: Value| json!({"timestamp": sample.at(seconds), "type": item_type, "payload": payload});
+
+    write_jsonl(
+        &path,
+        &[
+            item(
+                0,
+                "session_meta",
+                json!({
+                    "id": sample.session_id,
+                    "timestamp": sample.at(0),
+                    "cwd": sample.cwd,
+                    "originator": "codex_cli_rs",
+                    "cli_version": "0.58.0",
+                    "instructions": null,
+                    "git": {"branch": "main"},
+                }),
+            ),
+            item(
+                0,
+                "turn_context",
+                json!({"cwd": sample.cwd, "approval_policy": "on-request", "model": "gpt-5-codex"}),
+            ),
+            item(
+                1,
+                "response_item",
+                json!({"type": "message", "role": "user", "content": [{"type": "input_text", "text": s.prompt}]}),
+            ),
+            item(
+                5,
+                "response_item",
+                json!({"type": "function_call", "name": "shell", "arguments": arguments, "call_id": "call_1"}),
+            ),
+            item(
+                8,
+                "response_item",
+                json!({"type": "function_call_output", "call_id": "call_1", "output": s.output}),
+            ),
+            item(
+                18,
+                "event_msg",
+                json!({"type": "token_count", "info": {"last_token_usage": {
+                    "input_tokens": 2400,
+                    "cached_input_tokens": 1800,
+                    "output_tokens": 120,
+                }}}),
+            ),
+            item(
+                19,
+                "response_item",
+                json!({"type": "message", "role": "assistant", "content": [{"type": "output_text", "text": s.reply}]}),
+            ),
+        ],
+    )
+}
+
+/// Codex lists the projects it trusts in `config.toml`; the scanner reads them
+fn write_codex_config(home: &Path, workspaces: &Path) -> Result<(), GuideModeError> {
+    let mut config = String::new();
+    for scenario in SCENARIOS {
+        let worktree = workspaces.join(scenario.project).display().to_string();
+        config.push_str(&format!(
+            "[projects.{}]\ntrust_level = \"trusted\"\n\n",
+            Value::String(worktree)
+        ));
+    }
+    fs::write(home.join("config.toml"), config)?;
+    Ok(())
+}
+
+/// `~/.gemini/tmp/<sha256 of cwd>/chats/session-<time>-<id>.json`
+fn write_gemini(home: &Path, sample: &Sample) -> Result<(), GuideModeError> {
+    let s = sample.scenario;
+    let project_hash = hex::encode(Sha256::digest(sample.cwd.as_bytes()));
+    let path = home
+        .join("tmp")
+        .join(&project_hash)
+        .join("chats")
+        .join(format!(
+            "session-{}-{}.json",
+            sample.started.format("%Y-%m-%dT%H-%M"),
+            &sample.session_id[..8]
+        ));
+    // Gemini doesn't record the cwd; the app infers it from absolute paths
+    // in tool arguments, like the one `list_directory` takes
+    let tool_call = |name: &str, display_name: &str, args: Value, output: &str| {
+        let id = format!("{}-{}-0", name, sample.millis(6));
+        json!({
+            "id": id,
+            "name": name,
+            "args": args,
+            "result": [{"functionResponse": {
+                "id": id,
+                "name": name,
+                "response": {"output": output},
+            }}],
+            "status": "success",
+            "timestamp": sample.at(9),
+            "displayName": display_name,
+        })
+    };
+    let tokens = |input: u32, output: u32| {
+        json!({
+            "input": input,
+            "output": output,
+            "cached": input / 2,
+            "thoughts": 0,
+            "tool": 0,
+            "total": input + output,
+        })
+    };
+
+    write_json(
+        &path,
+        &json!({
+            "sessionId": sample.session_id,
+            "projectHash": project_hash,
+            "startTime": sample.at(0),
+            "lastUpdated": sample.at(20),
+            "messages": [
+                {"id": new_id(), "timestamp": sample.at(0), "type": "user", "content": s.prompt},
+                {
+                    "id": new_id(),
+                    "timestamp": sample.at(6),
+                    "type": "gemini",
+                    "content": "",
+                    "tokens": tokens(8200, 40),
+                    "model": "gemini-2.5-pro",
+                    "toolCalls": [
+                        tool_call(
+                            "list_directory",
+                            "ReadFolder",
+                            json!({"path": sample.cwd}),
+                            "Listed 3 item(s).",
+                        ),
+                        tool_call(
+                            "run_shell_command",
+                            "Shell",
+                            json!({"command": s.command, "description": "Inspect the project"}),
+                            s.output,
+                        ),
+                    ],
+                },
+                {
+                    "id": new_id(),
+                    "timestamp": sample.at(20),
+                    "type": "gemini",
+                    "content": s.reply,
+                    "tokens": tokens(8600, 90),
+                    "model": "gemini-2.5-pro",
+                },
+            ],
+        }),
+    )
+}
+
+/// `~/.copilot/session-state/<session>.jsonl`
+fn write_copilot(home: &Path, sample: &Sample) -> Result<(), GuideModeError> {
+    let s = sample.scenario;
+    let path = home
+        .join("session-state")
+        .join(format!("{}.jsonl", sample.session_id));
+    let ids: Vec<String> = (0..6).map(|_| new_id()).collect();
+    let event = |index: usize, seconds: i64, event_type: &str, data: Value| {
+        json!({
+            "type": event_type,
+            "data": data,
+            "id": ids[index],
+            "timestamp": sample.at(seconds),
+            "parentId": index.checked_sub(1).map(|parent| ids[parent].clone()),
+        })
+    };
+    let arguments = json!({"command": s.command, "description": "Inspect the project"});
+
+    write_jsonl(
+        &path,
+        &[
+            event(
+                0,
+                0,
+                "session.start",
+                json!({
+                    "sessionId": sample.session_id,
+                    "version": 1,
+                    "producer": "copilot-agent",
+                    "copilotVersion": "0.0.354",
+                    "startTime": sample.at(0),
+                    "cwd": sample.cwd,
+                    "workspaceFolders": [sample.cwd],
+                }),
+            ),
+            event(
+                1,
+                2,
+                "user.message",
+                json!({"content": s.prompt, "transformedContent": s.prompt, "attachments": []}),
+            ),
+            event(
+                2,
+                6,
+                "assistant.message",
+                json!({
+                    "messageId": "msg-1",
+                    "content": "Let me look at the project first.",
+                    "toolRequests": [{
+                        "toolCallId": "toolu_01Bash",
+                        "name": "bash",
+                        "arguments": arguments,
+                        "type": "function",
+                    }],
+                }),
+            ),
+            event(
+                3,
+                6,
+                "tool.execution_start",
+                json!({"toolCallId": "toolu_01Bash", "toolName": "bash", "arguments": arguments}),
+            ),
+            event(
+                4,
+                9,
+                "tool.execution_complete",
+                json!({"toolCallId": "toolu_01Bash", "success": true, "result": {"content": s.output}}),
+            ),
+            event(
+                5,
+                20,
+                "assistant.message",
+                json!({"messageId": "msg-2", "content": s.reply, "toolRequests": []}),
+            ),
+        ],
+    )
+}
+
+/// OpenCode keeps one JSON file per project, session, message and part under
+/// `~/.local/share/opencode/storage`
+fn write_opencode(home: &Path, sample: &Sample) -> Result<(), GuideModeError> {
+    let s = sample.scenario;
+    let storage = home.join("storage");
+    let project_id = hex::encode(Sha256::digest(sample.cwd.as_bytes()));
+    let session_id = &sample.session_id;
+
+    write_json(
+        &storage.join("project").join(format!("{}.json", project_id)),
+        &json!({
+            "id": project_id,
+            "worktree": sample.cwd,
+            "vcs": "git",
+            "time": {"created": sample.millis(0)},
+        }),
+    )?;
+    write_json(
+        &storage
+            .join("session")
+            .join(&project_id)
+            .join(format!("{}.json", session_id)),
+        &json!({
+            "id": session_id,
+            "version": "0.15.8",
+            "projectID": project_id,
+            "directory": sample.cwd,
+            "title": s.prompt,
+            "time": {"created": sample.millis(0), "updated": sample.millis(20)},
+        }),
+    )?;
+
+    let write_message = |seconds: i64, role: &str, parts: Vec<Value>| {
+        let message_id = prefixed_id("msg");
+        let mut message = json!({
+            "id": message_id,
+            "role": role,
+            "sessionID": session_id,
+            "time": {"created": sample.millis(seconds), "completed": sample.millis(seconds + 1)},
+        });
+        if role == "assistant" {
+            message["modelID"] = json!("claude-sonnet-4-5");
+            message["providerID"] = json!("anthropic");
+        }
+        write_json(
+            &storage
+                .join("message")
+                .join(session_id)
+                .join(format!("{}.json", message_id)),
+            &message,
+        )?;
+        for (index, mut part) in parts.into_iter().enumerate() {
+            let part_id = prefixed_id("prt");
+            part["id"] = json!(part_id);
+            part["messageID"] = json!(message_id);
+            part["sessionID"] = json!(session_id);
+            let start = sample.millis(seconds) + index as i64;
+            part["time"] = json!({"start": start, "end": start + 1});
+            write_json(
+                &storage
+                    .join("part")
+                    .join(&message_id)
+                    .join(format!("{}.json", part_id)),
+                &part,
+            )?;
+        }
+        Ok::<(), GuideModeError>(())
+    };
+
+    write_message(0, "user", vec![json!({"type": "text", "text": s.prompt})])?;
+    write_message(
+        6,
+        "assistant",
+        vec![json!({
+            "type": "tool",
+            "tool": "bash",
+            "callID": "call_bash_01",
+            "state": {
+                "status": "completed",
+                "input": {"command": s.command},
+                "output": s.output,
+                "title": s.command,
+                "time": {"start": sample.millis(6), "end": sample.millis(9)},
+            },
+        })],
+    )?;
+    write_message(
+        20,
+        "assistant",
+        vec![json!({"type": "text", "text": s.reply})],
+    )
+}
+
+/// Where the app generates sample sessions, kept across runs so a provider
+/// can stay pointed at it
+pub fn default_root() -> PathBuf {
+    std::env::temp_dir().join("guidemode-samples")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_rejects_unsupported_requests() {
+        let dir = tempfile::tempdir().unwrap();
+        let err = generate("cursor", 1, dir.path()).unwrap_err();
+        assert_eq!(err.code(), "validation");
+        assert!(generate("claude-code", 0, dir.path()).is_err());
+        assert!(generate("claude-code", MAX_SAMPLE_SESSIONS + 1, dir.path()).is_err());
+    }
+
+    #[test]
+    fn test_generate_writes_provider_layouts() {
+        let dir = tempfile::tempdir().unwrap();
+        for provider_id in SAMPLE_PROVIDERS {
+            let samples = generate(provider_id, 3, dir.path()).unwrap();
+            assert_eq!(samples.session_ids.len(), 3);
+            assert!(Path::new(&samples.home_directory).is_dir());
+        }
+        let home = dir.path();
+        assert!(home.join("codex/config.toml").is_file());
+        assert_eq!(
+            fs::read_dir(home.join("github-copilot/session-state"))
+                .unwrap()
+                .count(),
+            3
+        );
+        assert_eq!(fs::read_dir(home.join("workspaces")).unwrap().count(), 5);
+    }
+}
@@ -0,0 +1,276 @@
+//! Server health checks and capability gating
+//!
+//! Self-hosted servers can lag behind the desktop app. `GET /api/health`
+//! (under the configured API base path, see `api_paths`)
+//! reports the server's version, the canonical schema versions it reads and
+//! the upload features it supports. The check runs at login and periodically;
+//! the latest result is pushed to the frontend as a `server-health` event and
+//! gates uploads: transcripts are only uploaded when the server can read the
+//! canonical schema this app writes; otherwise sessions fall back to
+//! metrics-only uploads. Results are kept per server URL, so switching servers
+//! doesn't carry one server's verdict over to another; until a check has
+//! reached the configured server, uploads proceed as before. Which upload API
+//! to use is negotiated from the capabilities in
+//! `upload_queue::upload::negotiation`.
+//!
+//! Servers that predate the endpoint answer 404; they are treated as
+//! compatible with the legacy feature set. Each check first fetches the
+//! server's discovery document, which can move its endpoints.
+
+use crate::api_paths;
+use crate::config::load_config;
+use crate::logging::{log_info, log_warn};
+use crate::providers::canonical::CANONICAL_SCHEMA_VERSION;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+
+/// Server accepts compressed session uploads at `/api/agent-sessions/upload-v2`
+pub const UPLOAD_V2: &str = "upload-v2";
+
+/// Server accepts appended transcript bytes at `/api/agent-sessions/upload-delta`
+pub const UPLOAD_DELTA: &str = "upload-delta";
+
+/// Capabilities of servers without a health endpoint
+const LEGACY_CAPABILITIES: &[&str] = &[UPLOAD_V2];
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How often the connected server is checked after startup
+const CHECK_INTERVAL: Duration = Duration::from_secs(15 * 60);
+
+/// Result of the most recent check that reached each server, by server URL
+static LAST_HEALTH: Mutex<BTreeMap<String, ServerHealth>> = Mutex::new(BTreeMap::new());
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerHealth {
+    pub server_url: String,
+    pub reachable: bool,
+    pub latency_ms: Option<u64>,
+    pub version: Option<String>,
+    pub capabilities: Vec<String>,
+    pub schema_compatible: bool,
+    /// Why the server is unreachable or incompatible
+    pub message: Option<String>,
+    pub checked_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HealthResponse {
+    version: Option<String>,
+    capabilities: Option<Vec<String>>,
+    canonical_schema: Option<SchemaRange>,
+}
+
+/// Canonical schema versions the server can read
+#[derive(Debug, Deserialize)]
+struct SchemaRange {
+    min: u32,
+    max: u32,
+}
+
+/// Check `server_url`'s health endpoint
+pub async fn check(server_url: &str) -> ServerHealth {
+    // Where the health endpoint is can depend on the discovery document
+    api_paths::discover(server_url).await;
+    let started = Instant::now();
+    let result = fetch_health(server_url).await;
+    let latency_ms = Some(started.elapsed().as_millis() as u64);
+
+    match result {
+        Ok(response) => from_response(server_url, response, latency_ms),
+        Err(e) => ServerHealth {
+            server_url: server_url.to_string(),
+            reachable: false,
+            latency_ms: None,
+            version: None,
+            capabilities: Vec::new(),
+            schema_compatible: true,
+            message: Some(e),
+            checked_at: Utc::now(),
+        },
+    }
+}
+
+/// Check the server, keep the result for gating and push it to the frontend
+pub async fn check_and_publish(app: &AppHandle, server_url: &str) -> ServerHealth {
+    let health = check(server_url).await;
+    record(&health);
+    let _ = app.emit("server-health", &health);
+    health
+}
+
+/// Check the configured server now and then every `CHECK_INTERVAL`
+pub fn start_periodic_checks(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+            let server_url = load_config().ok().and_then(|config| config.server_url);
+            if let Some(server_url) = server_url {
+                check_and_publish(&app, &server_url).await;
+            }
+        }
+    });
+}
+
+/// Whether `server_url` can read the canonical schema this app writes
+pub fn schema_compatible(server_url: &str) -> bool {
+    last_health(server_url).is_none_or(|health| health.schema_compatible)
+}
+
+fn last_health(server_url: &str) -> Option<ServerHealth> {
+    LAST_HEALTH.lock().ok()?.get(server_url).cloned()
+}
+
+/// Keep a check that reached the server, logging changes in compatibility
+fn record(health: &ServerHealth) {
+    if !health.reachable {
+        log_warn(
+            "server",
+            &format!(
+                "⚠ Server {} unreachable: {}",
+                health.server_url,
+                health.message.as_deref().unwrap_or("unknown error")
+            ),
+        )
+        .unwrap_or_default();
+        return;
+    }
+
+    let Ok(mut last) = LAST_HEALTH.lock() else {
+        return;
+    };
+    let was_compatible = last
+        .get(&health.server_url)
+        .is_none_or(|previous| previous.schema_compatible);
+    match (&health.message, was_compatible) {
+        (Some(message), true) => {
+            log_warn("server", &format!("⚠ {}", message)).unwrap_or_default();
+        }
+        (None, false) => {
+            log_info("server", "✓ Server is compatible again").unwrap_or_default();
+        }
+        _ => {}
+    }
+    last.insert(health.server_url.clone(), health.clone());
+}
+
+async fn fetch_health(server_url: &str) -> Result<Option<HealthResponse>, String> {
+    let client = reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+
+    let response = client
+        .get(api_paths::api_url(server_url, api_paths::HEALTH))
+        .send()
+        .await
+        .map_err(|e| format!("Health check failed: {}", e))?;
+
+    let status = response.status();
+    if status == reqwest::StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+    if !status.is_success() {
+        return Err(format!("Health check failed with status {}", status));
+    }
+
+    response
+        .json()
+        .await
+        .map(Some)
+        .map_err(|e| format!("Failed to parse health response: {}", e))
+}
+
+/// Health from a response, or from a legacy server's 404 when `None`
+fn from_response(
+    server_url: &str,
+    response: Option<HealthResponse>,
+    latency_ms: Option<u64>,
+) -> ServerHealth {
+    let legacy = response.is_none();
+    let response = response.unwrap_or_default();
+    let capabilities = match response.capabilities {
+        Some(capabilities) => capabilities,
+        None if legacy => LEGACY_CAPABILITIES.iter().map(|c| c.to_string()).collect(),
+        None => Vec::new(),
+    };
+    let message = schema_mismatch(response.canonical_schema.as_ref());
+
+    ServerHealth {
+        server_url: server_url.to_string(),
+        reachable: true,
+        latency_ms,
+        version: response.version,
+        capabilities,
+        schema_compatible: message.is_none(),
+        message,
+        checked_at: Utc::now(),
+    }
+}
+
+/// Why the server can't read this app's canonical schema, if it can't
+fn schema_mismatch(range: Option<&SchemaRange>) -> Option<String> {
+    let range = range?;
+    if CANONICAL_SCHEMA_VERSION > range.max {
+        Some(format!(
+            "The server reads canonical schema up to v{} but this app writes v{}; \
+             transcripts won't be uploaded until the server is updated",
+            range.max, CANONICAL_SCHEMA_VERSION
+        ))
+    } else if CANONICAL_SCHEMA_VERSION < range.min {
+        Some(format!(
+            "The server requires canonical schema v{} or later but this app writes v{}; \
+             update the app to upload transcripts",
+            range.min, CANONICAL_SCHEMA_VERSION
+        ))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(json: &str) -> ServerHealth {
+        from_response(
+            "https://guide.local",
+            Some(serde_json::from_str(json).unwrap()),
+            None,
+        )
+    }
+
+    #[test]
+    fn test_legacy_server_keeps_v2_uploads() {
+        let health = from_response("https://guide.local", None, Some(12));
+        assert!(health.reachable && health.schema_compatible);
+        assert_eq!(health.capabilities, vec![UPLOAD_V2]);
+        assert_eq!(health.version, None);
+    }
+
+    #[test]
+    fn test_capabilities_and_schema_range() {
+        let health = parse(
+            r#"{"version":"2.4.0","capabilities":["upload-v2"],"canonicalSchema":{"min":1,"max":1}}"#,
+        );
+        assert_eq!(health.version.as_deref(), Some("2.4.0"));
+        assert!(health.capabilities.iter().any(|c| c == UPLOAD_V2));
+        assert!(health.schema_compatible);
+        assert!(health.message.is_none());
+
+        // A server that only accepts a newer schema can't take this app's transcripts
+        let health = parse(r#"{"capabilities":[],"canonicalSchema":{"min":2,"max":3}}"#);
+        assert!(!health.schema_compatible);
+        assert!(health.message.unwrap().contains("update the app"));
+
+        // Servers with a health endpoint list every capability they have
+        assert!(parse(r#"{"version":"2.5.0"}"#).capabilities.is_empty());
+    }
+}
@@ -0,0 +1,355 @@
+//! Archive old sessions to cold storage
+//!
+//! Archiving writes the matching sessions to a zip archive under
+//! `~/.guidemode/archives`, in the same format as `session_export`, then
+//! deletes their canonical files and database rows to shrink the live
+//! database. Their usage stays in the usage rollups, so dashboards keep the
+//! history. Archived sessions are remembered in `archived_sessions` so scans
+//! and watchers don't save them again; restoring an archive forgets them and
+//! imports it like any export.
+//!
+//! Sessions still waiting to upload are left in place.
+
+use crate::config::get_config_dir;
+use crate::database::{session_data_tables, with_connection_mut};
+use crate::error::GuideModeError;
+use crate::events::{Progress, ProgressPhase};
+use crate::logging::{log_info, log_warn};
+use crate::session_export::{
+    query_sessions, write_archive, ExportFilters, ExportManifest, ExportedSession, SessionRow,
+    MANIFEST_FILE,
+};
+use crate::session_import::{import_sessions, ImportReport};
+use crate::workspace_roots::effective_sync_mode;
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, Result};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveReport {
+    pub path: String,
+    pub session_count: usize,
+    /// Bytes of canonical files deleted
+    pub freed_bytes: u64,
+    /// Sessions left in place because they haven't uploaded yet
+    pub pending_upload: Vec<String>,
+    /// Sessions whose canonical file no longer exists; they aren't archived
+    pub missing: Vec<String>,
+}
+
+/// Sessions that haven't synced, weren't skipped and whose sync mode uploads
+fn pending_uploads(conn: &Connection, sessions: &[ExportedSession]) -> Result<HashSet<String>> {
+    let mut stmt = conn.prepare(
+        "SELECT session_id FROM agent_sessions WHERE synced_to_server = 0 AND sync_skipped = 0",
+    )?;
+    let unsynced: HashSet<String> = stmt
+        .query_map([], |row| row.get(0))?
+        .collect::<Result<_>>()?;
+
+    Ok(sessions
+        .iter()
+        .filter(|session| unsynced.contains(&session.session_id))
+        .filter(|session| {
+            effective_sync_mode(&session.provider, session.cwd.as_deref())
+                .is_none_or(|sync_mode| sync_mode != "Nothing")
+        })
+        .map(|session| session.session_id.clone())
+        .collect())
+}
+
+/// Per-session tables whose rows outlive archiving, so dashboards keep the history
+const KEPT_ON_ARCHIVE: &[&str] = &["session_usage"];
+
+/// Delete archived sessions' rows, keeping their usage, and remember them
+fn remove_archived(
+    conn: &mut Connection,
+    sessions: &[ExportedSession],
+    archive_path: &str,
+) -> Result<()> {
+    let tx = conn.transaction()?;
+    let now = Utc::now().timestamp_millis();
+    let tables: Vec<String> = session_data_tables(&tx)?
+        .into_iter()
+        .filter(|table| table.has_session_id && !KEPT_ON_ARCHIVE.contains(&table.name.as_str()))
+        .map(|table| table.name)
+        .collect();
+    for session in sessions {
+        for table in &tables {
+            tx.execute(
+                &format!("DELETE FROM {} WHERE session_id = ?", table),
+                params![session.session_id],
+            )?;
+        }
+        tx.execute(
+            "DELETE FROM agent_sessions WHERE session_id = ?",
+            params![session.session_id],
+        )?;
+        tx.execute(
+            "INSERT OR REPLACE INTO archived_sessions (session_id, provider, archive_path, archived_at)
+             VALUES (?, ?, ?, ?)",
+            params![session.session_id, session.provider, archive_path, now],
+        )?;
+    }
+    tx.commit()
+}
+
+fn forget_archived(conn: &Connection, session_ids: &[String]) -> Result<usize> {
+    let mut forgotten = 0;
+    for session_id in session_ids {
+        forgotten += conn.execute(
+            "DELETE FROM archived_sessions WHERE session_id = ?",
+            params![session_id],
+        )?;
+    }
+    Ok(forgotten)
+}
+
+/// Create an empty archive file in `dir`, named after `now` to the
+/// millisecond; a counter is added when that name is taken, so an earlier
+/// archive is never overwritten
+fn create_archive_file(dir: &Path, now: DateTime<Utc>) -> std::result::Result<PathBuf, String> {
+    let stamp = now.format("%Y%m%d-%H%M%S-%3f");
+    let mut attempt = 1;
+    loop {
+        let path = match attempt {
+            1 => dir.join(format!("sessions-{}.zip", stamp)),
+            n => dir.join(format!("sessions-{}-{}.zip", stamp, n)),
+        };
+        match fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)
+        {
+            Ok(_) => return Ok(path),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => attempt += 1,
+            Err(e) => return Err(format!("Failed to create {}: {}", path.display(), e)),
+        }
+    }
+}
+
+/// Archive the sessions matching `filters` and delete them from the live
+/// database; `until` or `session_ids` must be set
+pub fn archive_sessions(
+    filters: &ExportFilters,
+    progress: &Progress,
+) -> Result<ArchiveReport, GuideModeError> {
+    if filters.until.is_none() && filters.session_ids.is_none() {
+        return Err(GuideModeError::Validation(
+            "Choose the sessions to archive by date or by ID".to_string(),
+        ));
+    }
+
+    let (rows, pending) = with_connection_mut(|conn| {
+        let rows = query_sessions(conn, filters)?;
+        let sessions: Vec<ExportedSession> = rows.iter().map(|row| row.session.clone()).collect();
+        Ok((rows, pending_uploads(conn, &sessions)?))
+    })
+    .map_err(|e: rusqlite::Error| format!("Failed to query sessions: {}", e))?;
+    let total = rows.len();
+    progress.report(
+        ProgressPhase::Starting,
+        0,
+        total,
+        format!("Archiving {} sessions...", total),
+    );
+
+    // Sessions still waiting to upload stay in the live database
+    let (pending_upload, rows): (Vec<SessionRow>, Vec<SessionRow>) = rows
+        .into_iter()
+        .partition(|row| pending.contains(&row.session.session_id));
+    let pending_upload: Vec<String> = pending_upload
+        .into_iter()
+        .map(|row| row.session.session_id)
+        .collect();
+    let no_sessions = || {
+        GuideModeError::Validation(
+            "No sessions to archive: none match, or they haven't uploaded yet".to_string(),
+        )
+    };
+    if rows.is_empty() {
+        return Err(no_sessions());
+    }
+
+    let archives_dir = get_config_dir()
+        .map_err(|e| e.to_string())?
+        .join("archives");
+    fs::create_dir_all(&archives_dir)
+        .map_err(|e| format!("Failed to create {}: {}", archives_dir.display(), e))?;
+    let archive_path = create_archive_file(&archives_dir, Utc::now())?;
+    // Cancelling is possible until the archive is written; then the sessions are removed
+    let (written, missing) = write_archive(&archive_path, rows, progress)?;
+    if written.is_empty() {
+        let _ = fs::remove_file(&archive_path);
+        return Err(no_sessions());
+    }
+    let archive_path = archive_path.to_string_lossy().to_string();
+
+    let (archived, files): (Vec<ExportedSession>, Vec<String>) = written
+        .into_iter()
+        .map(|row| (row.session, row.file_path))
+        .unzip();
+    with_connection_mut(|conn| remove_archived(conn, &archived, &archive_path))
+        .map_err(|e| format!("Failed to remove archived sessions: {}", e))?;
+
+    // Only canonical copies are deleted, never a provider's own files
+    let sessions_dir = crate::config::get_sessions_dir().map_err(|e| e.to_string())?;
+    let mut freed_bytes = 0;
+    for file_path in files {
+        let path = Path::new(&file_path);
+        if !path.starts_with(&sessions_dir) {
+            continue;
+        }
+        let size = fs::metadata(path)
+            .map(|metadata| metadata.len())
+            .unwrap_or(0);
+        match fs::remove_file(path) {
+            Ok(()) => freed_bytes += size,
+            Err(e) => log_warn(
+                "archive",
+                &format!("⚠ Failed to delete {}: {}", file_path, e),
+            )
+            .unwrap_or_default(),
+        }
+    }
+
+    log_info(
+        "archive",
+        &format!(
+            "✓ Archived {} sessions to {} ({} waiting to upload, {} missing)",
+            archived.len(),
+            archive_path,
+            pending_upload.len(),
+            missing.len()
+        ),
+    )
+    .unwrap_or_default();
+    progress.report(
+        ProgressPhase::Complete,
+        total,
+        total,
+        format!("Archived {} sessions", archived.len()),
+    );
+
+    Ok(ArchiveReport {
+        path: archive_path,
+        session_count: archived.len(),
+        freed_bytes,
+        pending_upload,
+        missing,
+    })
+}
+
+/// Restore an archive's sessions into the live database
+pub fn restore_archive(path: &str, progress: &Progress) -> Result<ImportReport, GuideModeError> {
+    let file = fs::File::open(path).map_err(|e| format!("Failed to open {}: {}", path, e))?;
+    let mut archive =
+        zip::ZipArchive::new(file).map_err(|e| format!("Failed to read archive: {}", e))?;
+    let manifest: ExportManifest = serde_json::from_reader(
+        archive
+            .by_name(MANIFEST_FILE)
+            .map_err(|_| format!("{} is not a session archive", path))?,
+    )
+    .map_err(|e| format!("Invalid archive manifest: {}", e))?;
+
+    let session_ids: Vec<String> = manifest
+        .sessions
+        .into_iter()
+        .map(|session| session.session_id)
+        .collect();
+    with_connection_mut(|conn| forget_archived(conn, &session_ids))
+        .map_err(|e| format!("Failed to restore archived sessions: {}", e))?;
+
+    import_sessions(path, None, progress)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn exported(session_id: &str) -> ExportedSession {
+        ExportedSession {
+            provider: "claude-code".to_string(),
+            session_id: session_id.to_string(),
+            project_name: "app".to_string(),
+            file: format!("sessions/claude-code/{}.jsonl", session_id),
+            cwd: None,
+            git_branch: None,
+            session_start_time: None,
+            session_end_time: None,
+            duration_ms: None,
+            rating: None,
+            project: None,
+            metrics: None,
+        }
+    }
+
+    fn count(conn: &Connection, table: &str) -> i64 {
+        conn.query_row(&format!("SELECT COUNT(*) FROM {}", table), [], |row| {
+            row.get(0)
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_archived_rows_are_removed_and_remembered() {
+        let mut conn = crate::database::test_connection();
+        for session_id in ["s1", "s2"] {
+            conn.execute(
+                "INSERT INTO agent_sessions (
+                    id, provider, project_name, session_id, file_name, file_path, file_size,
+                    created_at, uploaded_at
+                ) VALUES (?1, 'claude-code', 'app', ?1, 'f.jsonl', '/f', 10, 0, 0)",
+                params![session_id],
+            )
+            .unwrap();
+            conn.execute(
+                "INSERT INTO session_usage (session_id, provider, project_name, day, week, updated_at)
+                 VALUES (?, 'claude-code', 'app', '2025-01-01', '2024-12-30', 0)",
+                params![session_id],
+            )
+            .unwrap();
+        }
+        conn.execute(
+            "INSERT INTO session_metrics (id, session_id, provider, timestamp, created_at)
+             VALUES ('m1', 's1', 'claude-code', 0, 0)",
+            [],
+        )
+        .unwrap();
+
+        remove_archived(&mut conn, &[exported("s1")], "/archives/a.zip").unwrap();
+        assert_eq!(count(&conn, "agent_sessions"), 1);
+        assert_eq!(count(&conn, "session_metrics"), 0);
+        assert_eq!(count(&conn, "archived_sessions"), 1);
+        // Usage history stays
+        assert_eq!(count(&conn, "session_usage"), 2);
+
+        assert_eq!(
+            forget_archived(&conn, &["s1".to_string(), "s9".to_string()]).unwrap(),
+            1
+        );
+        assert_eq!(count(&conn, "archived_sessions"), 0);
+    }
+
+    #[test]
+    fn test_archives_in_the_same_millisecond_get_their_own_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let now: DateTime<Utc> = "2025-01-01T10:00:00.250Z".parse().unwrap();
+
+        let first = create_archive_file(dir.path(), now).unwrap();
+        let second = create_archive_file(dir.path(), now).unwrap();
+        assert_eq!(
+            first.file_name().unwrap(),
+            "sessions-20250101-100000-250.zip"
+        );
+        assert_eq!(
+            second.file_name().unwrap(),
+            "sessions-20250101-100000-250-2.zip"
+        );
+        assert!(first.exists() && second.exists());
+    }
+}
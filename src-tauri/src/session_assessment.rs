@@ -0,0 +1,566 @@
+//! Local assessments of completed sessions
+//!
+//! The server assesses sessions that sync to it. With the assessor enabled,
+//! `AssessmentHandler` does the same on this machine: once a session
+//! completes or goes quiet it's queued, and queued sessions are sent one at a
+//! time, no faster than the configured requests per minute, to an
+//! `AssessmentRunner` (by default the configured chat completions endpoint).
+//!
+//! The result is stored like a server assessment: a row in
+//! `session_assessments` with `survey_type = 'local'`, whose `responses` hold
+//! the model, summary and tips, and the tips in the category columns of
+//! `session_metrics`. A rating the user gave is never replaced, and local
+//! ratings aren't uploaded as the user's. A session is only assessed again
+//! once it has grown.
+
+use crate::config::{load_assessor_config, AssessorConfig};
+use crate::database::{get_full_session_by_id, with_connection_mut};
+use crate::events::{EventBus, SessionEventPayload};
+use crate::logging::{log_error, log_info};
+use crate::session_summary::{read_transcript, request_completion};
+use crate::shutdown::ShutdownCoordinator;
+use chrono::Utc;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::future::Future;
+use std::time::{Duration, Instant};
+use tokio::sync::{broadcast, mpsc};
+
+/// Quiet period after which a session that didn't report completing is
+/// considered done
+const ASSESSMENT_IDLE: Duration = Duration::from_secs(300);
+
+/// How often quiet sessions are checked
+const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// `session_assessments.survey_type` of local assessments
+const LOCAL_SURVEY_TYPE: &str = "local";
+
+const RATINGS: [&str; 3] = ["thumbs_up", "meh", "thumbs_down"];
+
+const SYSTEM_PROMPT: &str = "You assess sessions between a developer and an AI coding \
+agent. Reply with only a JSON object of this shape: {\"rating\": \"thumbs_up\" | \"meh\" | \
+\"thumbs_down\", \"summary\": one sentence on how the session went, \"tips\": {\"usage\": [], \
+\"error\": [], \"engagement\": [], \"quality\": [], \"performance\": []}}. Rate whether the \
+developer got what they wanted. Each tips list holds at most three short, concrete suggestions \
+for the developer in that category, or nothing.";
+
+/// Improvement tips by metrics category
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CategoryTips {
+    pub usage: Vec<String>,
+    pub error: Vec<String>,
+    pub engagement: Vec<String>,
+    pub quality: Vec<String>,
+    pub performance: Vec<String>,
+}
+
+impl CategoryTips {
+    /// `session_metrics` tips columns with their tips, newline-joined like
+    /// metrics processing writes them
+    fn columns(&self) -> [(&'static str, String); 5] {
+        [
+            ("usage_improvement_tips", self.usage.join("\n")),
+            ("error_improvement_tips", self.error.join("\n")),
+            ("engagement_improvement_tips", self.engagement.join("\n")),
+            ("quality_improvement_tips", self.quality.join("\n")),
+            ("performance_improvement_tips", self.performance.join("\n")),
+        ]
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LocalAssessment {
+    pub rating: String,
+    #[serde(default)]
+    pub summary: String,
+    #[serde(default)]
+    pub tips: CategoryTips,
+}
+
+/// What a local assessment's `responses` record
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LocalResponses {
+    model: String,
+    /// Size of the session file when it was assessed
+    file_size: i64,
+    summary: String,
+    tips: CategoryTips,
+}
+
+/// Something that can assess a session from its transcript
+pub trait AssessmentRunner {
+    /// Recorded with the assessments it makes
+    fn model(&self) -> &str;
+
+    fn assess(
+        &self,
+        transcript: &str,
+    ) -> impl Future<Output = Result<LocalAssessment, String>> + Send;
+}
+
+/// Runner asking the configured chat completions endpoint
+pub struct ChatCompletionsRunner {
+    config: AssessorConfig,
+}
+
+impl ChatCompletionsRunner {
+    pub fn new(config: AssessorConfig) -> Self {
+        Self { config }
+    }
+}
+
+impl AssessmentRunner for ChatCompletionsRunner {
+    fn model(&self) -> &str {
+        &self.config.model
+    }
+
+    async fn assess(&self, transcript: &str) -> Result<LocalAssessment, String> {
+        let reply = request_completion(
+            &self.config.endpoint,
+            &self.config.model,
+            self.config.api_key.as_deref(),
+            SYSTEM_PROMPT,
+            transcript,
+        )
+        .await?;
+        parse_assessment(&reply)
+    }
+}
+
+/// Read an assessment from a model's reply, which may wrap the JSON in prose
+/// or a code fence
+fn parse_assessment(reply: &str) -> Result<LocalAssessment, String> {
+    let json = match (reply.find('{'), reply.rfind('}')) {
+        (Some(start), Some(end)) if start < end => &reply[start..=end],
+        _ => return Err("Assessment reply has no JSON object".to_string()),
+    };
+    let mut assessment: LocalAssessment =
+        serde_json::from_str(json).map_err(|e| format!("Invalid assessment reply: {}", e))?;
+    assessment.rating = assessment.rating.trim().to_lowercase();
+    if !RATINGS.contains(&assessment.rating.as_str()) {
+        return Err(format!("Unknown assessment rating '{}'", assessment.rating));
+    }
+    Ok(assessment)
+}
+
+/// A session's stored assessment
+enum Existing {
+    None,
+    /// The user's own, which is never replaced
+    User,
+    Local(Option<LocalResponses>),
+}
+
+fn existing_assessment(conn: &Connection, session_id: &str) -> rusqlite::Result<Existing> {
+    let row: Option<(Option<String>, String)> = conn
+        .query_row(
+            "SELECT survey_type, responses FROM session_assessments WHERE session_id = ?",
+            params![session_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()?;
+    Ok(match row {
+        None => Existing::None,
+        Some((Some(survey_type), responses)) if survey_type == LOCAL_SURVEY_TYPE => {
+            Existing::Local(serde_json::from_str(&responses).ok())
+        }
+        Some(_) => Existing::User,
+    })
+}
+
+/// Whether a session of `file_size` should be assessed
+fn needs_assessment(conn: &Connection, session_id: &str, file_size: i64) -> rusqlite::Result<bool> {
+    Ok(match existing_assessment(conn, session_id)? {
+        Existing::None => true,
+        Existing::User => false,
+        Existing::Local(responses) => responses.is_none_or(|local| local.file_size != file_size),
+    })
+}
+
+fn store_assessment(
+    conn: &mut Connection,
+    session_id: &str,
+    provider: &str,
+    responses: &LocalResponses,
+    rating: &str,
+    now: i64,
+) -> rusqlite::Result<bool> {
+    let tx = conn.transaction()?;
+    let previous_tips = match existing_assessment(&tx, session_id)? {
+        // Assessed by the user in the meantime
+        Existing::User => return Ok(false),
+        Existing::Local(Some(previous)) => previous.tips,
+        Existing::Local(None) | Existing::None => CategoryTips::default(),
+    };
+
+    let responses_json = serde_json::to_string(responses)
+        .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+    let updated = tx.execute(
+        "UPDATE session_assessments SET responses = ?, rating = ?, completed_at = ?
+         WHERE session_id = ? AND survey_type = ?",
+        params![responses_json, rating, now, session_id, LOCAL_SURVEY_TYPE],
+    )?;
+    if updated == 0 {
+        tx.execute(
+            "INSERT INTO session_assessments (
+                id, session_id, provider, responses, survey_type, rating, completed_at, created_at
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+            params![
+                uuid::Uuid::new_v4().to_string(),
+                session_id,
+                provider,
+                responses_json,
+                LOCAL_SURVEY_TYPE,
+                rating,
+                now,
+                now
+            ],
+        )?;
+    }
+
+    // Tips from metrics processing are kept; ones from an earlier local
+    // assessment are replaced
+    for ((column, tips), (_, previous)) in
+        responses.tips.columns().iter().zip(previous_tips.columns())
+    {
+        if tips.is_empty() {
+            continue;
+        }
+        tx.execute(
+            &format!(
+                "UPDATE session_metrics SET {column} = ?
+                 WHERE session_id = ? AND ({column} IS NULL OR {column} = '' OR {column} = ?)"
+            ),
+            params![tips, session_id, previous],
+        )?;
+    }
+    tx.commit()?;
+    Ok(true)
+}
+
+/// Assess a session with `runner`. Unless `force` is set, sessions that were
+/// assessed at their current size, or rated by the user, return None.
+pub async fn assess_session_with(
+    runner: &impl AssessmentRunner,
+    session_id: &str,
+    max_transcript_chars: usize,
+    force: bool,
+) -> Result<Option<LocalAssessment>, String> {
+    let session = get_full_session_by_id(session_id)
+        .map_err(|e| format!("Failed to get session: {}", e))?
+        .ok_or_else(|| format!("Session {} not found", session_id))?;
+    let needed = with_connection_mut(|conn| needs_assessment(conn, session_id, session.file_size))
+        .map_err(|e| format!("Failed to check assessment: {}", e))?;
+    if !needed && !force {
+        return Ok(None);
+    }
+
+    let file_path = session.file_path.clone();
+    let (transcript, _) =
+        tokio::task::spawn_blocking(move || read_transcript(&file_path, max_transcript_chars))
+            .await
+            .map_err(|e| format!("Failed to read session: {}", e))??;
+
+    let assessment = runner.assess(&transcript).await?;
+    let responses = LocalResponses {
+        model: runner.model().to_string(),
+        file_size: session.file_size,
+        summary: assessment.summary.clone(),
+        tips: assessment.tips.clone(),
+    };
+    let now = Utc::now().timestamp_millis();
+    let stored = with_connection_mut(|conn| {
+        store_assessment(
+            conn,
+            session_id,
+            &session.provider,
+            &responses,
+            &assessment.rating,
+            now,
+        )
+    })
+    .map_err(|e| format!("Failed to store assessment: {}", e))?;
+    if !stored {
+        return Ok(None);
+    }
+
+    log_info(
+        "assessment",
+        &format!(
+            "✓ Assessed session {} with {}: {}",
+            session_id,
+            runner.model(),
+            assessment.rating
+        ),
+    )
+    .unwrap_or_default();
+
+    Ok(Some(assessment))
+}
+
+/// Assess a session with the configured endpoint
+pub async fn assess_session(
+    session_id: &str,
+    force: bool,
+) -> Result<Option<LocalAssessment>, String> {
+    let config =
+        load_assessor_config().map_err(|e| format!("Failed to load assessor config: {}", e))?;
+    let max_chars = config.max_transcript_chars;
+    assess_session_with(
+        &ChatCompletionsRunner::new(config),
+        session_id,
+        max_chars,
+        force,
+    )
+    .await
+}
+
+/// Spaces requests evenly so no more than a set number go out in a minute
+#[derive(Debug, Default)]
+struct RateLimiter {
+    next_at: Option<Instant>,
+}
+
+impl RateLimiter {
+    /// Take the next slot at or after `now`, returning when it starts
+    fn reserve(&mut self, now: Instant, requests_per_minute: u32) -> Instant {
+        let at = self.next_at.filter(|next_at| *next_at > now).unwrap_or(now);
+        self.next_at = Some(at + Duration::from_secs(60) / requests_per_minute.max(1));
+        at
+    }
+}
+
+/// Handler that assesses sessions once they complete or go quiet
+pub struct AssessmentHandler {
+    event_bus: EventBus,
+    shutdown: ShutdownCoordinator,
+}
+
+impl AssessmentHandler {
+    pub fn new(event_bus: EventBus, shutdown: ShutdownCoordinator) -> Self {
+        Self {
+            event_bus,
+            shutdown,
+        }
+    }
+
+    pub fn start(self) {
+        // Assessments are requested one at a time, within the rate limit
+        let (work_tx, mut work_rx) = mpsc::unbounded_channel::<String>();
+        tauri::async_runtime::spawn(async move {
+            let mut limiter = RateLimiter::default();
+            while let Some(session_id) = work_rx.recv().await {
+                let Ok(config) = load_assessor_config() else {
+                    continue;
+                };
+                let at = limiter.reserve(Instant::now(), config.requests_per_minute);
+                tokio::time::sleep_until(at.into()).await;
+
+                let max_chars = config.max_transcript_chars;
+                let runner = ChatCompletionsRunner::new(config);
+                if let Err(e) = assess_session_with(&runner, &session_id, max_chars, false).await {
+                    log_error(
+                        "assessment",
+                        &format!("Failed to assess {}: {}", session_id, e),
+                    )
+                    .unwrap_or_default();
+                }
+            }
+        });
+
+        tauri::async_runtime::spawn(async move {
+            let mut rx = self.event_bus.subscribe();
+            let mut shutdown_rx = self.shutdown.subscribe();
+            let mut interval = tokio::time::interval(CHECK_INTERVAL);
+
+            // Session ID -> when it's due to be assessed
+            let mut pending: HashMap<String, Instant> = HashMap::new();
+
+            loop {
+                tokio::select! {
+                    result = rx.recv() => {
+                        match result {
+                            Ok(event) => match &event.payload {
+                                SessionEventPayload::SessionChanged { session_id, .. } => {
+                                    let due_at = Instant::now() + ASSESSMENT_IDLE;
+                                    pending.insert(session_id.clone(), due_at);
+                                }
+                                SessionEventPayload::Completed { session_id, .. } => {
+                                    pending.insert(session_id.clone(), Instant::now());
+                                }
+                                _ => {}
+                            },
+                            Err(broadcast::error::RecvError::Closed) => {
+                                log_info("assessment", "Assessment handler stopped (event bus closed)")
+                                    .unwrap_or_default();
+                                break;
+                            }
+                            Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                        }
+                    }
+                    _ = interval.tick() => {
+                        let now = Instant::now();
+                        let ready: Vec<String> = pending
+                            .iter()
+                            .filter(|(_, due_at)| **due_at <= now)
+                            .map(|(session_id, _)| session_id.clone())
+                            .collect();
+                        if ready.is_empty() {
+                            continue;
+                        }
+                        for session_id in &ready {
+                            pending.remove(session_id);
+                        }
+
+                        // Checked when sessions are due so settings apply without a restart
+                        if !load_assessor_config().map(|config| config.enabled).unwrap_or(false) {
+                            continue;
+                        }
+                        for session_id in ready {
+                            let _ = work_tx.send(session_id);
+                        }
+                    }
+                    _ = shutdown_rx.recv() => {
+                        log_info("assessment", "Assessment handler gracefully shutting down")
+                            .unwrap_or_default();
+                        break;
+                    }
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn database() -> Connection {
+        let conn = crate::database::test_connection();
+        conn.execute(
+            "INSERT INTO agent_sessions (
+                id, provider, project_name, session_id, file_name, file_path, file_size,
+                created_at, uploaded_at
+            ) VALUES ('s1', 'claude-code', 'app', 's1', 's1', '/s1', 100, 0, 0)",
+            [],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO session_metrics (
+                id, session_id, provider, timestamp, created_at, error_improvement_tips
+            ) VALUES ('m1', 's1', 'claude-code', 0, 0, 'Read errors before retrying')",
+            [],
+        )
+        .unwrap();
+        conn
+    }
+
+    fn responses(file_size: i64, quality: &str) -> LocalResponses {
+        LocalResponses {
+            model: "llama3.2".to_string(),
+            file_size,
+            summary: "Fixed the parser".to_string(),
+            tips: CategoryTips {
+                error: vec!["Share the stack trace".to_string()],
+                quality: vec![quality.to_string()],
+                ..Default::default()
+            },
+        }
+    }
+
+    fn tips(conn: &Connection) -> (Option<String>, Option<String>) {
+        conn.query_row(
+            "SELECT error_improvement_tips, quality_improvement_tips FROM session_metrics",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_parse_assessment() {
+        let reply = "Here you go:\n```json\n{\"rating\": \"Thumbs_Up\", \"summary\": \"Done\", \
+                     \"tips\": {\"quality\": [\"Add tests\"]}}\n```";
+        let assessment = parse_assessment(reply).unwrap();
+        assert_eq!(assessment.rating, "thumbs_up");
+        assert_eq!(assessment.tips.quality, ["Add tests"]);
+        assert!(assessment.tips.usage.is_empty());
+
+        assert!(parse_assessment("{\"rating\": \"great\"}").is_err());
+        assert!(parse_assessment("I can't assess this").is_err());
+    }
+
+    #[test]
+    fn test_store_assessment_keeps_user_ratings_and_processed_tips() {
+        let mut conn = database();
+        assert!(needs_assessment(&conn, "s1", 100).unwrap());
+
+        assert!(store_assessment(
+            &mut conn,
+            "s1",
+            "claude-code",
+            &responses(100, "Add tests"),
+            "meh",
+            1
+        )
+        .unwrap());
+        assert!(!needs_assessment(&conn, "s1", 100).unwrap());
+        assert!(needs_assessment(&conn, "s1", 250).unwrap());
+        // Tips from processing stay
+        assert_eq!(
+            tips(&conn),
+            (
+                Some("Read errors before retrying".to_string()),
+                Some("Add tests".to_string())
+            )
+        );
+
+        // Assessing again replaces the local assessment and its tips
+        assert!(store_assessment(
+            &mut conn,
+            "s1",
+            "claude-code",
+            &responses(250, "Split the change"),
+            "thumbs_up",
+            2
+        )
+        .unwrap());
+        assert_eq!(tips(&conn).1, Some("Split the change".to_string()));
+        let count: i64 = conn
+            .query_row("SELECT COUNT(*) FROM session_assessments", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(count, 1);
+
+        // Once the user rates the session it's theirs
+        conn.execute("UPDATE session_assessments SET survey_type = NULL", [])
+            .unwrap();
+        assert!(!needs_assessment(&conn, "s1", 400).unwrap());
+        assert!(!store_assessment(
+            &mut conn,
+            "s1",
+            "claude-code",
+            &responses(400, "x"),
+            "meh",
+            3
+        )
+        .unwrap());
+    }
+
+    #[test]
+    fn test_rate_limiter_spaces_requests() {
+        let mut limiter = RateLimiter::default();
+        let start = Instant::now();
+        assert_eq!(limiter.reserve(start, 6), start);
+        assert_eq!(limiter.reserve(start, 6), start + Duration::from_secs(10));
+        assert_eq!(limiter.reserve(start, 6), start + Duration::from_secs(20));
+        // Idle time isn't saved up
+        let later = start + Duration::from_secs(60);
+        assert_eq!(limiter.reserve(later, 6), later);
+    }
+}
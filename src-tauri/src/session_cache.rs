@@ -0,0 +1,444 @@
+//! Canonical session cache management
+//!
+//! Converted sessions are cached as {provider}/{project}/{session_id}.jsonl
+//! under `config::get_sessions_dir`, which can be relocated through the
+//! `cacheDir` setting. This module reports the cache's disk usage, moves it
+//! when the setting changes and keeps it under `cacheMaxMb`.
+//!
+//! Pruning only removes sessions the server already has, least recently used
+//! first, and forgets the scans that produced them. A pruned session is
+//! converted again the next time its source file changes or is rescanned;
+//! until then its transcript can't be viewed locally.
+//!
+//! Watchers write into the cache, so they are stopped while it moves (see
+//! `commands::set_cache_settings`).
+
+use crate::config::{get_sessions_dir, load_config, save_config};
+use crate::database::with_connection_mut;
+use crate::logging::{log_error, log_info};
+use crate::providers::common::scan_cache;
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheSettings {
+    pub cache_dir: String,
+    pub max_mb: Option<u64>,
+}
+
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiskUsage {
+    pub root: String,
+    pub total_bytes: u64,
+    pub file_count: usize,
+    pub max_bytes: Option<u64>,
+    pub providers: Vec<ProviderUsage>,
+}
+
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderUsage {
+    pub provider: String,
+    pub total_bytes: u64,
+    pub file_count: usize,
+    pub projects: Vec<ProjectUsage>,
+}
+
+#[derive(Debug, Default, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectUsage {
+    pub project: String,
+    pub total_bytes: u64,
+    pub file_count: usize,
+}
+
+#[derive(Debug, Default, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PruneReport {
+    pub removed_files: usize,
+    pub freed_bytes: u64,
+}
+
+/// A cached session file
+struct CachedFile {
+    path: PathBuf,
+    provider: String,
+    project: String,
+    size: u64,
+    last_used: SystemTime,
+}
+
+pub fn load_settings() -> Result<CacheSettings, String> {
+    let config = load_config().map_err(|e| format!("Failed to load config: {}", e))?;
+    let cache_dir =
+        get_sessions_dir().map_err(|e| format!("Failed to resolve cache directory: {}", e))?;
+    Ok(CacheSettings {
+        cache_dir: cache_dir.to_string_lossy().to_string(),
+        max_mb: config.cache_max_mb,
+    })
+}
+
+/// Whether saving `settings` would move the cache to another directory
+pub fn moves_cache(settings: &CacheSettings) -> Result<bool, String> {
+    let current =
+        get_sessions_dir().map_err(|e| format!("Failed to resolve cache directory: {}", e))?;
+    Ok(PathBuf::from(settings.cache_dir.trim()) != current)
+}
+
+/// Save the cache settings, moving cached sessions to a new directory first
+///
+/// Watchers must be stopped while the cache moves; see `moves_cache`.
+pub fn save_settings(settings: &CacheSettings) -> Result<PruneReport, String> {
+    let current =
+        get_sessions_dir().map_err(|e| format!("Failed to resolve cache directory: {}", e))?;
+    let target = PathBuf::from(settings.cache_dir.trim());
+    if !target.is_absolute() {
+        return Err("Cache directory must be an absolute path".to_string());
+    }
+
+    if target != current {
+        if target.starts_with(&current) || current.starts_with(&target) {
+            return Err(
+                "Cache directory can't be inside the current one, or contain it".to_string(),
+            );
+        }
+        relocate(&current, &target)?;
+    }
+
+    let mut config = load_config().map_err(|e| format!("Failed to load config: {}", e))?;
+    let default_dir = crate::config::get_config_dir()
+        .map_err(|e| format!("Failed to resolve config directory: {}", e))?
+        .join("sessions");
+    config.cache_dir = (target != default_dir).then(|| target.to_string_lossy().to_string());
+    config.cache_max_mb = settings.max_mb.filter(|max_mb| *max_mb > 0);
+    save_config(&config).map_err(|e| format!("Failed to save config: {}", e))?;
+
+    prune_to_limit()
+}
+
+/// Disk usage of the session cache by provider and project
+pub fn disk_usage() -> Result<DiskUsage, String> {
+    let root =
+        get_sessions_dir().map_err(|e| format!("Failed to resolve cache directory: {}", e))?;
+    let max_mb = load_config().ok().and_then(|config| config.cache_max_mb);
+
+    let mut usage = summarize(&root, &list_files(&root));
+    usage.max_bytes = max_mb.map(|max_mb| max_mb * 1024 * 1024);
+    Ok(usage)
+}
+
+/// Prune the cache to `cacheMaxMb`, if set
+pub fn prune_to_limit() -> Result<PruneReport, String> {
+    let Some(max_mb) = load_config().ok().and_then(|config| config.cache_max_mb) else {
+        return Ok(PruneReport::default());
+    };
+    let root =
+        get_sessions_dir().map_err(|e| format!("Failed to resolve cache directory: {}", e))?;
+    let synced = with_connection_mut(synced_paths)
+        .map_err(|e| format!("Failed to load synced sessions: {}", e))?;
+
+    let (report, removed) = prune(list_files(&root), max_mb * 1024 * 1024, &synced);
+    if !removed.is_empty() {
+        with_connection_mut(|conn| scan_cache::forget_canonical_files(conn, &removed))
+            .map_err(|e| format!("Failed to forget pruned sessions' scans: {}", e))?;
+    }
+    if report.removed_files > 0 {
+        log_info(
+            "cache",
+            &format!(
+                "🧹 Pruned {} synced sessions ({} MB) from the cache",
+                report.removed_files,
+                report.freed_bytes / (1024 * 1024)
+            ),
+        )
+        .unwrap_or_default();
+    }
+    Ok(report)
+}
+
+/// Prune in the background, logging failures
+pub fn prune_in_background() {
+    tauri::async_runtime::spawn_blocking(|| {
+        if let Err(e) = prune_to_limit() {
+            log_error("cache", &format!("Failed to prune cache: {}", e)).unwrap_or_default();
+        }
+    });
+}
+
+fn synced_paths(conn: &mut rusqlite::Connection) -> rusqlite::Result<HashSet<PathBuf>> {
+    conn.prepare("SELECT file_path FROM agent_sessions WHERE synced_to_server = 1")?
+        .query_map([], |row| row.get::<_, String>(0))?
+        .map(|path| path.map(PathBuf::from))
+        .collect()
+}
+
+/// Cached session files, skipping in-progress conversions
+fn list_files(root: &Path) -> Vec<CachedFile> {
+    let mut files = Vec::new();
+    for provider in subdirectories(root) {
+        for project in subdirectories(&provider) {
+            let Ok(entries) = fs::read_dir(&project) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                let path = entry.path();
+                let is_session = path.extension().is_some_and(|ext| ext == "jsonl")
+                    && !entry.file_name().to_string_lossy().starts_with('.');
+                let Ok(metadata) = entry.metadata() else {
+                    continue;
+                };
+                if !is_session || !metadata.is_file() {
+                    continue;
+                }
+                let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+                files.push(CachedFile {
+                    provider: file_name(&provider),
+                    project: file_name(&project),
+                    size: metadata.len(),
+                    // Access times are often not updated; a write is a use too
+                    last_used: metadata
+                        .accessed()
+                        .map_or(modified, |accessed| accessed.max(modified)),
+                    path,
+                });
+            }
+        }
+    }
+    files
+}
+
+fn subdirectories(dir: &Path) -> Vec<PathBuf> {
+    fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|path| path.is_dir())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+fn summarize(root: &Path, files: &[CachedFile]) -> DiskUsage {
+    let mut by_provider: BTreeMap<&str, BTreeMap<&str, ProjectUsage>> = BTreeMap::new();
+    for file in files {
+        let project = by_provider
+            .entry(&file.provider)
+            .or_default()
+            .entry(&file.project)
+            .or_insert_with(|| ProjectUsage {
+                project: file.project.clone(),
+                ..Default::default()
+            });
+        project.total_bytes += file.size;
+        project.file_count += 1;
+    }
+
+    let mut providers: Vec<ProviderUsage> = by_provider
+        .into_iter()
+        .map(|(provider, projects)| {
+            let mut projects: Vec<ProjectUsage> = projects.into_values().collect();
+            projects.sort_by_key(|project| std::cmp::Reverse(project.total_bytes));
+            ProviderUsage {
+                provider: provider.to_string(),
+                total_bytes: projects.iter().map(|p| p.total_bytes).sum(),
+                file_count: projects.iter().map(|p| p.file_count).sum(),
+                projects,
+            }
+        })
+        .collect();
+    providers.sort_by_key(|provider| std::cmp::Reverse(provider.total_bytes));
+
+    DiskUsage {
+        root: root.to_string_lossy().to_string(),
+        total_bytes: files.iter().map(|f| f.size).sum(),
+        file_count: files.len(),
+        max_bytes: None,
+        providers,
+    }
+}
+
+/// Remove synced files, least recently used first, until the cache fits
+/// `max_bytes`, returning what was freed and the files removed
+fn prune(
+    mut files: Vec<CachedFile>,
+    max_bytes: u64,
+    synced: &HashSet<PathBuf>,
+) -> (PruneReport, Vec<PathBuf>) {
+    let mut total: u64 = files.iter().map(|f| f.size).sum();
+    let mut report = PruneReport::default();
+    let mut removed = Vec::new();
+    if total <= max_bytes {
+        return (report, removed);
+    }
+
+    files.retain(|file| synced.contains(&file.path));
+    files.sort_by_key(|file| file.last_used);
+    for file in files {
+        if total <= max_bytes {
+            break;
+        }
+        if fs::remove_file(&file.path).is_ok() {
+            total -= file.size;
+            report.removed_files += 1;
+            report.freed_bytes += file.size;
+            removed.push(file.path);
+        }
+    }
+    (report, removed)
+}
+
+/// Move the cache from `from` to `to` and point sessions at the new files
+fn relocate(from: &Path, to: &Path) -> Result<(), String> {
+    fs::create_dir_all(to).map_err(|e| format!("Failed to create cache directory: {}", e))?;
+
+    let files = list_files(from);
+    let mut moved = 0;
+    for file in &files {
+        let Ok(relative) = file.path.strip_prefix(from) else {
+            continue;
+        };
+        move_file(&file.path, &to.join(relative)).map_err(|e| {
+            format!(
+                "Failed to move {} to the new cache: {}",
+                file.path.display(),
+                e
+            )
+        })?;
+        moved += 1;
+    }
+
+    let old_prefix = format!("{}{}", from.to_string_lossy(), std::path::MAIN_SEPARATOR);
+    let new_prefix = format!("{}{}", to.to_string_lossy(), std::path::MAIN_SEPARATOR);
+    with_connection_mut(|conn| {
+        let tx = conn.transaction()?;
+        tx.execute(
+            "UPDATE agent_sessions SET file_path = ?2 || substr(file_path, length(?1) + 1)
+             WHERE substr(file_path, 1, length(?1)) = ?1",
+            params![old_prefix, new_prefix],
+        )?;
+        scan_cache::move_canonical_files(&tx, &old_prefix, &new_prefix)?;
+        tx.commit()
+    })
+    .map_err(|e| format!("Failed to update session paths: {}", e))?;
+
+    log_info(
+        "cache",
+        &format!(
+            "📦 Moved {} cached sessions from {} to {}",
+            moved,
+            from.display(),
+            to.display()
+        ),
+    )
+    .unwrap_or_default();
+    Ok(())
+}
+
+/// Rename, or copy and remove when the destination is on another filesystem
+fn move_file(from: &Path, to: &Path) -> io::Result<()> {
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    if fs::rename(from, to).is_err() {
+        fs::copy(from, to)?;
+        fs::remove_file(from)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(root: &Path, relative: &str, size: usize) -> PathBuf {
+        let path = root.join(relative);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, vec![b'x'; size]).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_usage_by_provider_and_project() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path();
+        write(root, "claude-code/app/a.jsonl", 300);
+        write(root, "claude-code/app/b.jsonl", 200);
+        write(root, "claude-code/web/c.jsonl", 100);
+        write(root, "codex/cli/d.jsonl", 50);
+        // Conversions in progress aren't counted
+        write(root, "codex/.e.jsonl.partial", 1000);
+
+        let usage = summarize(root, &list_files(root));
+        assert_eq!((usage.total_bytes, usage.file_count), (650, 4));
+        assert_eq!(usage.providers[0].provider, "claude-code");
+        assert_eq!(usage.providers[0].total_bytes, 600);
+        assert_eq!(
+            usage.providers[0].projects[0],
+            ProjectUsage {
+                project: "app".to_string(),
+                total_bytes: 500,
+                file_count: 2,
+            }
+        );
+        assert_eq!(usage.providers[1].total_bytes, 50);
+    }
+
+    #[test]
+    fn test_prune_removes_least_recently_used_synced_files() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path();
+        let oldest = write(root, "claude-code/app/oldest.jsonl", 100);
+        let unsynced = write(root, "claude-code/app/unsynced.jsonl", 100);
+        let older = write(root, "claude-code/app/older.jsonl", 100);
+        let newest = write(root, "claude-code/app/newest.jsonl", 100);
+
+        let now = SystemTime::now();
+        let mut files = list_files(root);
+        for file in &mut files {
+            let age = match file.path.file_stem().unwrap().to_str().unwrap() {
+                "oldest" | "unsynced" => 300,
+                "older" => 200,
+                _ => 100,
+            };
+            file.last_used = now - std::time::Duration::from_secs(age);
+        }
+        let synced: HashSet<PathBuf> = [&oldest, &older, &newest].into_iter().cloned().collect();
+
+        let (report, removed) = prune(files, 250, &synced);
+        assert_eq!(removed, vec![oldest.clone(), older.clone()]);
+        assert_eq!(
+            report,
+            PruneReport {
+                removed_files: 2,
+                freed_bytes: 200,
+            }
+        );
+        assert!(!oldest.exists() && !older.exists());
+        assert!(unsynced.exists() && newest.exists());
+    }
+
+    #[test]
+    fn test_move_file_creates_directories() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let from = write(temp_dir.path(), "old/codex/cli/s1.jsonl", 10);
+        let to = temp_dir.path().join("new/codex/cli/s1.jsonl");
+
+        move_file(&from, &to).unwrap();
+        assert!(!from.exists());
+        assert_eq!(fs::read(&to).unwrap().len(), 10);
+    }
+}
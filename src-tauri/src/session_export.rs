@@ -0,0 +1,377 @@
+//! Export sessions to a portable zip archive
+//!
+//! The archive holds each session's canonical JSONL under
+//! `sessions/<provider>/` and a `manifest.json` with what isn't in the session
+//! itself: project name and metadata, rating and computed metrics. Importing
+//! the archive with `session_import` restores the sessions, their project names
+//! and ratings; metrics are recomputed from the sessions on the importing
+//! machine and are included for reading the archive offline.
+
+use crate::database::{get_session_metrics, with_connection_mut, SessionMetrics};
+use crate::disk_space;
+use crate::error::GuideModeError;
+use crate::events::{Progress, ProgressPhase};
+use crate::logging::{log_info, log_warn};
+use chrono::Utc;
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+/// Name of the manifest at the root of an export archive
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+const MANIFEST_VERSION: u32 = 1;
+
+/// Which sessions to export; unset fields match every session
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ExportFilters {
+    pub provider: Option<String>,
+    pub project_name: Option<String>,
+    pub session_ids: Option<Vec<String>>,
+    /// Sessions that started at or after this time (Unix milliseconds)
+    pub since: Option<i64>,
+    /// Sessions that started before this time (Unix milliseconds)
+    pub until: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportManifest {
+    pub version: u32,
+    pub exported_at: String,
+    pub sessions: Vec<ExportedSession>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportedSession {
+    pub provider: String,
+    pub session_id: String,
+    pub project_name: String,
+    /// Path of the session's canonical JSONL within the archive
+    pub file: String,
+    pub cwd: Option<String>,
+    pub git_branch: Option<String>,
+    pub session_start_time: Option<i64>,
+    pub session_end_time: Option<i64>,
+    pub duration_ms: Option<i64>,
+    pub rating: Option<String>,
+    #[serde(default)]
+    pub project: Option<ExportedProject>,
+    #[serde(default)]
+    pub metrics: Option<SessionMetrics>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportedProject {
+    pub name: String,
+    pub github_repo: Option<String>,
+    #[serde(rename = "type")]
+    pub project_type: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportReport {
+    pub path: String,
+    pub session_count: usize,
+    /// Sessions whose canonical file no longer exists
+    pub missing: Vec<String>,
+}
+
+/// A matching session and where its canonical file lives
+pub(crate) struct SessionRow {
+    pub(crate) file_path: String,
+    pub(crate) session: ExportedSession,
+}
+
+pub(crate) fn query_sessions(conn: &Connection, filters: &ExportFilters) -> rusqlite::Result<Vec<SessionRow>> {
+    let mut stmt = conn.prepare(
+        "SELECT s.provider, s.session_id, s.project_name, s.file_path, s.cwd, s.git_branch,
+                s.session_start_time, s.session_end_time, s.duration_ms,
+                (SELECT rating FROM session_assessments a
+                 WHERE a.session_id = s.session_id LIMIT 1),
+                p.name, p.github_repo, p.type
+         FROM agent_sessions s
+         LEFT JOIN projects p ON p.id = s.project_id
+         WHERE (?1 IS NULL OR s.provider = ?1)
+           AND (?2 IS NULL OR s.project_name = ?2 COLLATE NOCASE)
+           AND (?3 IS NULL OR s.session_start_time >= ?3)
+           AND (?4 IS NULL OR s.session_start_time < ?4)
+         ORDER BY s.session_start_time, s.session_id",
+    )?;
+    let rows = stmt
+        .query_map(
+            params![
+                filters.provider,
+                filters.project_name,
+                filters.since,
+                filters.until
+            ],
+            |row| {
+                let provider: String = row.get(0)?;
+                let session_id: String = row.get(1)?;
+                let project = match row.get::<_, Option<String>>(10)? {
+                    Some(name) => Some(ExportedProject {
+                        name,
+                        github_repo: row.get(11)?,
+                        project_type: row.get(12)?,
+                    }),
+                    None => None,
+                };
+                Ok(SessionRow {
+                    file_path: row.get(3)?,
+                    session: ExportedSession {
+                        file: archive_file_name(&provider, &session_id),
+                        provider,
+                        session_id,
+                        project_name: row.get(2)?,
+                        cwd: row.get(4)?,
+                        git_branch: row.get(5)?,
+                        session_start_time: row.get(6)?,
+                        session_end_time: row.get(7)?,
+                        duration_ms: row.get(8)?,
+                        rating: row.get(9)?,
+                        project,
+                        metrics: None,
+                    },
+                })
+            },
+        )?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    Ok(match &filters.session_ids {
+        Some(session_ids) => rows
+            .into_iter()
+            .filter(|row| session_ids.contains(&row.session.session_id))
+            .collect(),
+        None => rows,
+    })
+}
+
+/// Path of a session within the archive
+fn archive_file_name(provider: &str, session_id: &str) -> String {
+    let sanitize = |value: &str| value.replace(['/', '\\'], "_");
+    format!(
+        "sessions/{}/{}.jsonl",
+        sanitize(provider),
+        sanitize(session_id)
+    )
+}
+
+/// Stream the sessions' canonical files into a zip archive at `path`
+///
+/// Sessions are read one at a time, so memory use doesn't grow with the
+/// number of sessions. Returns the sessions written, with their metrics, and
+/// the IDs of those whose canonical file no longer exists. A failed or
+/// cancelled archive is removed.
+pub(crate) fn write_archive(
+    path: &Path,
+    rows: Vec<SessionRow>,
+    progress: &Progress,
+) -> Result<(Vec<SessionRow>, Vec<String>), GuideModeError> {
+    disk_space::ensure_space(path, "archive")?;
+    let file = fs::File::create(path)
+        .map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+    let result = write_sessions(io::BufWriter::new(file), rows, progress);
+    if result.is_err() {
+        let _ = fs::remove_file(path);
+    }
+    result
+}
+
+fn write_sessions<W: Write + io::Seek>(
+    writer: W,
+    rows: Vec<SessionRow>,
+    progress: &Progress,
+) -> Result<(Vec<SessionRow>, Vec<String>), GuideModeError> {
+    let mut archive = zip::ZipWriter::new(writer);
+    let options = zip::write::SimpleFileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated);
+
+    let total = rows.len();
+    let mut written = Vec::with_capacity(total);
+    let mut missing = Vec::new();
+    for (index, mut row) in rows.into_iter().enumerate() {
+        progress.check_cancelled(index, total)?;
+        progress.report_item(index + 1, total, || {
+            format!("Writing session {} of {}...", index + 1, total)
+        });
+
+        let Ok(mut file) = fs::File::open(&row.file_path) else {
+            missing.push(row.session.session_id);
+            continue;
+        };
+        archive
+            .start_file(row.session.file.as_str(), options)
+            .map_err(archive_error)?;
+        io::copy(&mut file, &mut archive)
+            .map_err(|e| format!("Failed to write {}: {}", row.file_path, e))?;
+        row.session.metrics = get_session_metrics(&row.session.session_id).ok().flatten();
+        written.push(row);
+    }
+    progress.check_cancelled(total, total)?;
+
+    let manifest = ExportManifest {
+        version: MANIFEST_VERSION,
+        exported_at: Utc::now().to_rfc3339(),
+        sessions: written.iter().map(|row| row.session.clone()).collect(),
+    };
+    archive
+        .start_file(MANIFEST_FILE, options)
+        .map_err(archive_error)?;
+    serde_json::to_writer_pretty(&mut archive, &manifest)
+        .map_err(|e| archive_error(io::Error::from(e).into()))?;
+    archive.finish().map_err(archive_error)?;
+
+    Ok((written, missing))
+}
+
+fn archive_error(e: zip::result::ZipError) -> GuideModeError {
+    GuideModeError::from(format!("Failed to write archive: {}", e))
+}
+
+/// Read the manifest of an extracted archive, if it has one
+pub fn read_manifest(root: &Path) -> Option<ExportManifest> {
+    let content = fs::read_to_string(root.join(MANIFEST_FILE)).ok()?;
+    match serde_json::from_str(&content) {
+        Ok(manifest) => Some(manifest),
+        Err(e) => {
+            log_warn("import", &format!("⚠ Ignoring invalid manifest: {}", e)).unwrap_or_default();
+            None
+        }
+    }
+}
+
+/// Export the sessions matching `filters` to a zip archive at `destination`
+pub fn export_sessions(
+    filters: &ExportFilters,
+    destination: &str,
+    progress: &Progress,
+) -> Result<ExportReport, GuideModeError> {
+    let rows = with_connection_mut(|conn| query_sessions(conn, filters))
+        .map_err(|e| format!("Failed to query sessions: {}", e))?;
+    let total = rows.len();
+    progress.report(
+        ProgressPhase::Starting,
+        0,
+        total,
+        format!("Exporting {} sessions...", total),
+    );
+
+    let (sessions, missing) = write_archive(Path::new(destination), rows, progress)?;
+
+    log_info(
+        "export",
+        &format!(
+            "✓ Exported {} sessions to {} ({} missing)",
+            sessions.len(),
+            destination,
+            missing.len()
+        ),
+    )
+    .unwrap_or_default();
+    progress.report(
+        ProgressPhase::Complete,
+        total,
+        total,
+        format!("Exported {} sessions", sessions.len()),
+    );
+
+    Ok(ExportReport {
+        path: destination.to_string(),
+        session_count: sessions.len(),
+        missing,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn insert_session(conn: &Connection, session_id: &str, provider: &str, started_at: i64) {
+        conn.execute(
+            "INSERT INTO agent_sessions (
+                id, provider, project_name, session_id, file_name, file_path, file_size,
+                session_start_time, created_at, uploaded_at
+            ) VALUES (?1, ?2, 'app', ?1, ?1, '/sessions/' || ?1, 0, ?3, 0, 0)",
+            params![session_id, provider, started_at],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_query_sessions_applies_filters() {
+        let conn = crate::database::test_connection();
+        insert_session(&conn, "s1", "claude-code", 1_000);
+        insert_session(&conn, "s2", "codex", 2_000);
+        insert_session(&conn, "s3", "claude-code", 3_000);
+        conn.execute(
+            "INSERT INTO session_assessments (id, session_id, provider, responses, rating, completed_at)
+             VALUES ('a1', 's3', 'claude-code', '{}', 'thumbs_up', 0)",
+            [],
+        )
+        .unwrap();
+
+        let session_ids = |filters: ExportFilters| -> Vec<String> {
+            query_sessions(&conn, &filters)
+                .unwrap()
+                .into_iter()
+                .map(|row| row.session.session_id)
+                .collect()
+        };
+
+        assert_eq!(session_ids(ExportFilters::default()), ["s1", "s2", "s3"]);
+        assert_eq!(
+            session_ids(ExportFilters {
+                provider: Some("claude-code".to_string()),
+                since: Some(2_000),
+                ..Default::default()
+            }),
+            ["s3"]
+        );
+        assert_eq!(
+            session_ids(ExportFilters {
+                session_ids: Some(vec!["s2".to_string()]),
+                ..Default::default()
+            }),
+            ["s2"]
+        );
+
+        let rows = query_sessions(&conn, &ExportFilters::default()).unwrap();
+        assert_eq!(rows[2].session.rating.as_deref(), Some("thumbs_up"));
+        assert_eq!(rows[2].session.file, "sessions/claude-code/s3.jsonl");
+    }
+
+    #[test]
+    fn test_archive_round_trips_manifest() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let conn = crate::database::test_connection();
+        insert_session(&conn, "s1", "claude-code", 1_000);
+        insert_session(&conn, "s2", "claude-code", 2_000);
+        let mut rows = query_sessions(&conn, &ExportFilters::default()).unwrap();
+        let session_file = temp_dir.path().join("s1.jsonl");
+        fs::write(&session_file, "{}\n").unwrap();
+        rows[0].file_path = session_file.to_string_lossy().to_string();
+
+        let archive_path = temp_dir.path().join("export.zip");
+        let progress = Progress::silent(crate::events::OperationKind::Export, None);
+        let (written, missing) = write_archive(&archive_path, rows, &progress).unwrap();
+        assert_eq!(written.len(), 1);
+        assert_eq!(missing, ["s2"]);
+
+        let mut archive = zip::ZipArchive::new(fs::File::open(&archive_path).unwrap()).unwrap();
+        let extracted = temp_dir.path().join("extracted");
+        archive.extract(&extracted).unwrap();
+
+        let manifest = read_manifest(&extracted).unwrap();
+        assert_eq!(manifest.version, MANIFEST_VERSION);
+        assert_eq!(manifest.sessions.len(), 1);
+        assert_eq!(manifest.sessions[0].project_name, "app");
+        assert!(extracted.join(&manifest.sessions[0].file).is_file());
+    }
+}
@@ -0,0 +1,510 @@
+//! Import sessions from a directory or zip archive
+//!
+//! Accepts canonical JSONL, such as a backup of ~/.guidemode/sessions, or,
+//! with a provider hint, the raw session files of providers that keep one file
+//! per session (Claude Code, Codex, Gemini). Imported sessions are written to
+//! the canonical cache and inserted like sessions found by a historical scan,
+//! so the upload queue picks them up according to the provider's sync mode.
+//! Archives made by `session_export` also restore project names and ratings
+//! from their manifest.
+
+use crate::database::{quick_rate_session, update_session_project_name};
+use crate::error::GuideModeError;
+use crate::events::{Progress, ProgressPhase};
+use crate::logging::{log_info, log_warn};
+use crate::providers::canonical::validation::{validate_jsonl, validate_messages};
+use crate::providers::canonical::CanonicalMessage;
+use crate::providers::common::db_helpers::insert_session_immediately;
+use crate::providers::common::SessionInfo;
+use crate::providers::common::{extract_cwd_from_canonical_content, get_canonical_path};
+use crate::providers::PROVIDER_IDS;
+use crate::session_export::{read_manifest, ExportedSession, MANIFEST_FILE};
+use crate::validation::{validate_file_size, MAX_SESSION_FILE_SIZE};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+/// Providers whose raw session files can be imported with a provider hint
+pub const RAW_IMPORT_PROVIDERS: [&str; 3] = ["claude-code", "codex", "gemini-code"];
+
+/// Archives with more entries than this are rejected
+const MAX_ARCHIVE_ENTRIES: usize = 50_000;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportedSession {
+    pub provider: String,
+    pub session_id: String,
+    pub project_name: String,
+    /// Canonical file the session was written to
+    pub file_path: String,
+}
+
+/// A file that was not imported, and why
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportIssue {
+    /// Path relative to the imported directory or archive
+    pub path: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportReport {
+    pub imported: Vec<ImportedSession>,
+    /// Sessions already present with identical content
+    pub skipped: Vec<ImportIssue>,
+    pub failed: Vec<ImportIssue>,
+}
+
+enum FileOutcome {
+    Imported(ImportedSession),
+    Unchanged,
+}
+
+/// Temporary directory an archive is extracted to, removed on drop
+struct ExtractedArchive(PathBuf);
+
+impl Drop for ExtractedArchive {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Extract a zip archive's files into `destination`, refusing entries that
+/// would land outside it or exceed the session file size limit
+fn extract_archive(archive_path: &Path, destination: &Path) -> Result<(), String> {
+    let file =
+        fs::File::open(archive_path).map_err(|e| format!("Failed to open archive: {}", e))?;
+    let mut archive =
+        zip::ZipArchive::new(file).map_err(|e| format!("Failed to read archive: {}", e))?;
+    if archive.len() > MAX_ARCHIVE_ENTRIES {
+        return Err(format!(
+            "Archive has {} entries, more than the {} allowed",
+            archive.len(),
+            MAX_ARCHIVE_ENTRIES
+        ));
+    }
+
+    for index in 0..archive.len() {
+        let mut entry = archive
+            .by_index(index)
+            .map_err(|e| format!("Failed to read archive entry {}: {}", index, e))?;
+        if !entry.is_file() || entry.size() > MAX_SESSION_FILE_SIZE {
+            continue;
+        }
+        let Some(relative_path) = entry.enclosed_name() else {
+            continue;
+        };
+
+        let target = destination.join(relative_path);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+        }
+        let mut output = fs::File::create(&target)
+            .map_err(|e| format!("Failed to create {}: {}", target.display(), e))?;
+        // The declared size can lie; stop reading once the limit is passed
+        let written = io::copy(
+            &mut (&mut entry).take(MAX_SESSION_FILE_SIZE + 1),
+            &mut output,
+        )
+        .map_err(|e| format!("Failed to extract {}: {}", target.display(), e))?;
+        if written > MAX_SESSION_FILE_SIZE {
+            return Err(format!(
+                "Archive entry {} is larger than declared",
+                relative_path.display()
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Session files under a directory, sorted, leaving out an export manifest
+fn collect_session_files(root: &Path) -> Vec<PathBuf> {
+    let manifest_path = root.join(MANIFEST_FILE);
+    let mut files: Vec<PathBuf> = walkdir::WalkDir::new(root)
+        .follow_links(false)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file())
+        .map(|entry| entry.into_path())
+        .filter(|path| {
+            matches!(
+                path.extension().and_then(|ext| ext.to_str()),
+                Some("jsonl") | Some("json")
+            ) && *path != manifest_path
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+/// Parse content as canonical JSONL; None if any line isn't a canonical message
+fn parse_canonical(content: &str) -> Option<Vec<CanonicalMessage>> {
+    let messages = content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| serde_json::from_str::<CanonicalMessage>(line).ok())
+        .collect::<Option<Vec<_>>>()?;
+    (!messages.is_empty()).then_some(messages)
+}
+
+/// Project name used until the session is linked to a project by its cwd
+fn fallback_project_name(cwd: Option<&str>) -> String {
+    cwd.and_then(|cwd| Path::new(cwd).file_name())
+        .and_then(|name| name.to_str())
+        .unwrap_or("unknown")
+        .to_string()
+}
+
+/// Whether a session ID can name a file in the canonical cache: no path
+/// separators, parent references or drive prefixes
+fn is_safe_session_id(session_id: &str) -> bool {
+    !session_id.is_empty() && !session_id.contains(['/', '\\', ':']) && !session_id.contains("..")
+}
+
+/// Write a canonical session to the cache; Unchanged if it is already there
+fn import_canonical(
+    content: &str,
+    messages: &[CanonicalMessage],
+) -> Result<(SessionInfo, bool), String> {
+    let report = validate_messages(messages);
+    if !report.is_valid() {
+        return Err(format!("Invalid canonical session: {}", report.summary()));
+    }
+
+    let provider = messages[0].provider.clone();
+    let session_id = messages[0].session_id.clone();
+    if !PROVIDER_IDS.contains(&provider.as_str()) {
+        return Err(format!("Unknown provider \"{}\"", provider));
+    }
+    if !is_safe_session_id(&session_id) {
+        return Err(format!("Invalid session ID \"{}\"", session_id));
+    }
+    let cwd = extract_cwd_from_canonical_content(content);
+
+    let canonical_path = get_canonical_path(&provider, cwd.as_deref(), &session_id)
+        .map_err(|e| format!("Failed to get canonical path: {}", e))?;
+    let unchanged = fs::read_to_string(&canonical_path).is_ok_and(|existing| existing == content);
+    if !unchanged {
+        fs::write(&canonical_path, content)
+            .map_err(|e| format!("Failed to write canonical session: {}", e))?;
+    }
+
+    let file_name = format!("{}.jsonl", session_id);
+    Ok((
+        SessionInfo {
+            provider,
+            project_name: fallback_project_name(cwd.as_deref()),
+            session_id,
+            file_path: canonical_path,
+            file_name,
+            session_start_time: None,
+            session_end_time: None,
+            duration_ms: None,
+            file_size: content.len() as u64,
+            content: None,
+            cwd,
+            project_hash: None,
+        },
+        unchanged,
+    ))
+}
+
+/// Convert a raw provider session file into the canonical cache
+fn import_raw(path: &Path, provider: &str) -> Result<SessionInfo, String> {
+    use crate::providers::{claude, codex, gemini};
+
+    let session = match provider {
+        "claude-code" => {
+            // Claude Code names projects after the folder its sessions live in
+            let project_name = path
+                .parent()
+                .and_then(|parent| parent.file_name())
+                .and_then(|name| name.to_str())
+                .unwrap_or("unknown");
+            claude::scanner::parse_claude_session(path, project_name)
+        }
+        "codex" => codex::scanner::parse_codex_session(path, None),
+        "gemini-code" => gemini::scanner::parse_gemini_session(path),
+        other => Err(format!(
+            "Raw import is not supported for {}; import canonical JSONL instead",
+            other
+        )),
+    }?;
+    session.ok_or_else(|| "Session is in an ignored project".to_string())
+}
+
+fn import_file(path: &Path, provider_hint: Option<&str>) -> Result<FileOutcome, String> {
+    validate_file_size(path, MAX_SESSION_FILE_SIZE).map_err(|e| e.to_string())?;
+    let content = fs::read_to_string(path).map_err(|e| format!("Failed to read file: {}", e))?;
+
+    let (session, unchanged) = match (parse_canonical(&content), provider_hint) {
+        (Some(messages), _) => import_canonical(&content, &messages)?,
+        (None, Some(provider)) => (import_raw(path, provider)?, false),
+        (None, None) => {
+            return Err(format!(
+                "Not canonical JSONL ({}); choose a provider to import raw session files",
+                validate_jsonl(&content).summary()
+            ))
+        }
+    };
+    if unchanged {
+        return Ok(FileOutcome::Unchanged);
+    }
+    insert_imported(&session)?;
+
+    Ok(FileOutcome::Imported(ImportedSession {
+        provider: session.provider,
+        session_id: session.session_id,
+        project_name: session.project_name,
+        file_path: session.file_path.to_string_lossy().to_string(),
+    }))
+}
+
+/// Convert a provider's raw session file again, such as after a converter fix,
+/// and queue the result like an imported session
+pub fn reconvert_raw(path: &Path, provider: &str) -> Result<SessionInfo, String> {
+    let session = import_raw(path, provider)?;
+    insert_imported(&session)?;
+    Ok(session)
+}
+
+fn insert_imported(session: &SessionInfo) -> Result<(), String> {
+    let file_size = fs::metadata(&session.file_path)
+        .map(|metadata| metadata.len())
+        .unwrap_or(session.file_size);
+    insert_session_immediately(
+        &session.provider,
+        &session.project_name,
+        &session.session_id,
+        &session.file_path,
+        file_size,
+        None, // Hash will be calculated during upload
+        true, // is_historical - the machine's current git state doesn't apply
+    )
+    .map_err(|e| format!("Failed to insert session: {}", e))?;
+    crate::upload_queue::request_db_poll();
+    Ok(())
+}
+
+/// Restore what an export manifest recorded beyond the session itself
+fn restore_exported_details(exported: &ExportedSession) {
+    if let Err(e) = update_session_project_name(&exported.session_id, &exported.project_name) {
+        log_warn(
+            "import",
+            &format!(
+                "⚠ Failed to restore project name of {}: {}",
+                exported.session_id, e
+            ),
+        )
+        .unwrap_or_default();
+    }
+    if let Some(rating) = &exported.rating {
+        if let Err(e) = quick_rate_session(&exported.session_id, rating) {
+            log_warn(
+                "import",
+                &format!(
+                    "⚠ Failed to restore rating of {}: {}",
+                    exported.session_id, e
+                ),
+            )
+            .unwrap_or_default();
+        }
+    }
+}
+
+/// Import the sessions in a directory or zip archive
+///
+/// Raw provider files are only converted when `provider_hint` names one of
+/// `RAW_IMPORT_PROVIDERS`; canonical files are recognized on their own.
+pub fn import_sessions(
+    path: &str,
+    provider_hint: Option<&str>,
+    progress: &Progress,
+) -> Result<ImportReport, GuideModeError> {
+    if let Some(provider) = provider_hint {
+        if !RAW_IMPORT_PROVIDERS.contains(&provider) {
+            return Err(GuideModeError::Validation(format!(
+                "Raw import is not supported for {}; supported providers: {}",
+                provider,
+                RAW_IMPORT_PROVIDERS.join(", ")
+            )));
+        }
+    }
+
+    let source = Path::new(path);
+    let is_archive = source.is_file()
+        && source
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("zip"));
+
+    let _extracted;
+    let root = if is_archive {
+        let destination =
+            std::env::temp_dir().join(format!("guidemode-import-{}", uuid::Uuid::new_v4()));
+        _extracted = ExtractedArchive(destination.clone());
+        extract_archive(source, &destination)?;
+        destination
+    } else if source.is_dir() {
+        source.to_path_buf()
+    } else {
+        return Err(GuideModeError::Validation(format!(
+            "{} is not a directory or zip archive",
+            path
+        )));
+    };
+
+    let manifest = read_manifest(&root);
+    let exported: HashMap<&str, &ExportedSession> = manifest
+        .iter()
+        .flat_map(|manifest| &manifest.sessions)
+        .map(|session| (session.session_id.as_str(), session))
+        .collect();
+
+    let files = collect_session_files(&root);
+    let total = files.len();
+    progress.report(
+        ProgressPhase::Starting,
+        0,
+        total,
+        format!("Importing {} files...", total),
+    );
+
+    let mut report = ImportReport::default();
+    for (index, file) in files.into_iter().enumerate() {
+        progress.check_cancelled(index, total)?;
+        progress.report_item(index + 1, total, || {
+            format!("Importing file {} of {}...", index + 1, total)
+        });
+
+        let relative_path = file
+            .strip_prefix(&root)
+            .unwrap_or(&file)
+            .to_string_lossy()
+            .to_string();
+
+        match import_file(&file, provider_hint) {
+            Ok(FileOutcome::Imported(mut session)) => {
+                if let Some(exported) = exported.get(session.session_id.as_str()) {
+                    restore_exported_details(exported);
+                    session.project_name = exported.project_name.clone();
+                }
+                report.imported.push(session);
+            }
+            Ok(FileOutcome::Unchanged) => report.skipped.push(ImportIssue {
+                path: relative_path,
+                reason: "Already imported".to_string(),
+            }),
+            Err(reason) => {
+                log_warn(
+                    "import",
+                    &format!("⚠ Failed to import {}: {}", relative_path, reason),
+                )
+                .unwrap_or_default();
+                report.failed.push(ImportIssue {
+                    path: relative_path,
+                    reason,
+                });
+            }
+        }
+    }
+
+    log_info(
+        "import",
+        &format!(
+            "✓ Imported {} sessions from {} ({} skipped, {} failed)",
+            report.imported.len(),
+            path,
+            report.skipped.len(),
+            report.failed.len()
+        ),
+    )
+    .unwrap_or_default();
+    progress.report(
+        ProgressPhase::Complete,
+        total,
+        total,
+        format!("Imported {} sessions", report.imported.len()),
+    );
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::io::Write;
+
+    fn canonical_line(uuid: &str) -> String {
+        json!({
+            "uuid": uuid, "timestamp": "2025-01-01T10:00:00Z", "type": "user",
+            "sessionId": "s1", "provider": "claude-code", "cwd": "/work/app",
+            "message": {"role": "user", "content": "Hello"}
+        })
+        .to_string()
+    }
+
+    #[test]
+    fn test_parse_canonical() {
+        let content = format!("{}\n\n{}\n", canonical_line("u1"), canonical_line("u2"));
+        assert_eq!(parse_canonical(&content).map(|m| m.len()), Some(2));
+
+        // Raw provider lines (no provider field) are not canonical
+        let raw = format!(
+            "{}\n{}",
+            canonical_line("u1"),
+            json!({"type": "summary", "summary": "Chat"})
+        );
+        assert!(parse_canonical(&raw).is_none());
+        assert!(parse_canonical("").is_none());
+    }
+
+    #[test]
+    fn test_is_safe_session_id() {
+        assert!(is_safe_session_id("0d9f4c3e-7b1a-4e52-9c1d-3f6a2b8e5d10"));
+        assert!(is_safe_session_id("rollout-2025-01-01T10-00-00"));
+        assert!(!is_safe_session_id(""));
+        assert!(!is_safe_session_id("../../.ssh/authorized_keys"));
+        assert!(!is_safe_session_id("nested/session"));
+        assert!(!is_safe_session_id("nested\\session"));
+        assert!(!is_safe_session_id("C:session"));
+    }
+
+    #[test]
+    fn test_extract_archive() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let archive_path = temp_dir.path().join("backup.zip");
+
+        let mut writer = zip::ZipWriter::new(fs::File::create(&archive_path).unwrap());
+        let options = zip::write::SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Stored);
+        writer
+            .start_file("sessions/claude-code/app/s1.jsonl", options)
+            .unwrap();
+        writer.write_all(canonical_line("u1").as_bytes()).unwrap();
+        writer.start_file(MANIFEST_FILE, options).unwrap();
+        writer.write_all(b"{}").unwrap();
+        writer.start_file("notes.txt", options).unwrap();
+        writer.write_all(b"not a session").unwrap();
+        writer.start_file("../escape.jsonl", options).unwrap();
+        writer.write_all(b"{}").unwrap();
+        writer.finish().unwrap();
+
+        let destination = temp_dir.path().join("extracted");
+        extract_archive(&archive_path, &destination).unwrap();
+
+        assert_eq!(
+            collect_session_files(&destination),
+            vec![destination.join("sessions/claude-code/app/s1.jsonl")]
+        );
+        assert!(!temp_dir.path().join("escape.jsonl").exists());
+    }
+}
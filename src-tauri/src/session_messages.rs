@@ -0,0 +1,618 @@
+//! Paged and streamed access to a session's canonical messages
+//!
+//! Canonical files can be hundreds of megabytes, so the session viewer asks
+//! for pages of parsed messages instead of the whole file. Pages are addressed
+//! by line; an index of line offsets per file, extended as the file grows,
+//! lets each page be read with a single seek. Live sessions are followed over
+//! a channel: every `SessionChanged` event for the session sends the lines
+//! added since the last page. `LiveTailHandler` does the same for every
+//! session a watcher sees growing, publishing the new messages on the event
+//! bus at most once per `TAIL_INTERVAL` per session.
+
+use crate::database::get_full_session_by_id;
+use crate::events::{EventBus, SessionEventPayload};
+use crate::logging::{log_debug, log_error, log_info};
+use crate::providers::canonical::CanonicalMessage;
+use crate::shutdown::ShutdownCoordinator;
+use indexmap::IndexMap;
+use lazy_static::lazy_static;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs::{File, Metadata};
+use std::hash::{DefaultHasher, Hasher};
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime};
+use tokio::sync::{broadcast, oneshot};
+
+pub const DEFAULT_PAGE_SIZE: usize = 200;
+pub const MAX_PAGE_SIZE: usize = 1000;
+
+/// Line indexes kept in memory, least recently used evicted first
+const MAX_INDEXED_FILES: usize = 16;
+
+/// Shortest time between two batches of live messages for a session
+const TAIL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Sessions that haven't changed for this long stop being tailed
+const TAIL_IDLE_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// How often a stream without changes checks that its receiver is still there
+const STREAM_PROBE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Bytes at the end of the indexed part of a file that are checked for a
+/// rewrite before the index is extended
+const INDEX_CHECK_BYTES: u64 = 4096;
+
+lazy_static! {
+    static ref LINE_INDEXES: Mutex<IndexMap<PathBuf, LineIndex>> = Mutex::new(IndexMap::new());
+    static ref ACTIVE_STREAMS: Mutex<HashMap<String, oneshot::Sender<()>>> =
+        Mutex::new(HashMap::new());
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MessagePage {
+    pub messages: Vec<CanonicalMessage>,
+    /// Line of the first message in the page
+    pub offset: usize,
+    /// Line to request the following page from
+    pub next_offset: usize,
+    /// Lines in the file when the page was read
+    pub total_lines: usize,
+    /// Lines in the page that aren't valid canonical messages
+    pub skipped: usize,
+}
+
+/// Byte offsets of the lines of a file
+#[derive(Debug, Default)]
+struct LineIndex {
+    len: u64,
+    line_starts: Vec<u64>,
+    ends_with_newline: bool,
+    /// Inode of the indexed file, where the platform has one
+    file_id: Option<u64>,
+    modified: Option<SystemTime>,
+    /// Checksum of the last `INDEX_CHECK_BYTES` of the indexed part
+    checksum: u64,
+}
+
+#[cfg(unix)]
+fn file_id(metadata: &Metadata) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    Some(metadata.ino())
+}
+
+#[cfg(not(unix))]
+fn file_id(_metadata: &Metadata) -> Option<u64> {
+    None
+}
+
+/// Checksum of the `INDEX_CHECK_BYTES` before byte `end` of a file
+fn tail_checksum(file: &mut File, end: u64) -> std::io::Result<u64> {
+    let start = end.saturating_sub(INDEX_CHECK_BYTES);
+    file.seek(SeekFrom::Start(start))?;
+    let mut bytes = Vec::with_capacity((end - start) as usize);
+    file.take(end - start).read_to_end(&mut bytes)?;
+    let mut hasher = DefaultHasher::new();
+    hasher.write(&bytes);
+    Ok(hasher.finish())
+}
+
+impl LineIndex {
+    /// Bring the index up to date with the file, scanning only what was added.
+    /// Canonical files are rewritten with their earlier lines unchanged; a file
+    /// that shrank, or whose indexed part no longer matches, is indexed from
+    /// scratch.
+    fn update(&mut self, file: &mut File) -> std::io::Result<()> {
+        let metadata = file.metadata()?;
+        let len = metadata.len();
+        let (id, modified) = (file_id(&metadata), metadata.modified().ok());
+        if len == self.len && id == self.file_id && modified == self.modified {
+            return Ok(());
+        }
+        if len < self.len || (self.len > 0 && tail_checksum(file, self.len)? != self.checksum) {
+            *self = LineIndex::default();
+        }
+        self.file_id = id;
+        self.modified = modified;
+        if len == self.len {
+            return Ok(());
+        }
+
+        // An unterminated last line may have grown, so scan it again
+        let resume = if self.ends_with_newline || self.line_starts.is_empty() {
+            self.len
+        } else {
+            self.line_starts.pop().unwrap_or(0)
+        };
+        self.line_starts.push(resume);
+
+        file.seek(SeekFrom::Start(resume))?;
+        let mut reader = BufReader::new(file.take(len - resume));
+        let mut position = resume;
+        let mut last_byte = None;
+        loop {
+            let buffer = reader.fill_buf()?;
+            if buffer.is_empty() {
+                break;
+            }
+            for (i, byte) in buffer.iter().enumerate() {
+                let line_end = position + i as u64 + 1;
+                if *byte == b'\n' && line_end < len {
+                    self.line_starts.push(line_end);
+                }
+            }
+            last_byte = buffer.last().copied();
+            let consumed = buffer.len();
+            position += consumed as u64;
+            reader.consume(consumed);
+        }
+
+        self.len = len;
+        self.ends_with_newline = last_byte == Some(b'\n');
+        self.checksum = tail_checksum(file, len)?;
+        Ok(())
+    }
+
+    fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+}
+
+impl MessagePage {
+    /// A page without messages at line `offset`
+    fn empty(offset: usize) -> Self {
+        Self {
+            messages: Vec::new(),
+            offset,
+            next_offset: offset,
+            total_lines: offset,
+            skipped: 0,
+        }
+    }
+}
+
+/// Read up to `limit` messages starting at line `offset`
+pub fn read_message_page(path: &Path, offset: usize, limit: usize) -> Result<MessagePage, String> {
+    let limit = limit.clamp(1, MAX_PAGE_SIZE);
+    let mut file = File::open(path).map_err(|e| format!("Failed to open session file: {}", e))?;
+
+    let ((start_byte, end_byte), end_line, total_lines) = {
+        let mut indexes = LINE_INDEXES.lock().unwrap();
+        let mut index = indexes.shift_remove(path).unwrap_or_default();
+        index
+            .update(&mut file)
+            .map_err(|e| format!("Failed to index session file: {}", e))?;
+
+        let total_lines = index.line_count();
+        let start_line = offset.min(total_lines);
+        let end_line = (start_line + limit).min(total_lines);
+        let byte_range = (
+            index
+                .line_starts
+                .get(start_line)
+                .copied()
+                .unwrap_or(index.len),
+            index
+                .line_starts
+                .get(end_line)
+                .copied()
+                .unwrap_or(index.len),
+        );
+
+        indexes.insert(path.to_path_buf(), index);
+        if indexes.len() > MAX_INDEXED_FILES {
+            indexes.shift_remove_index(0);
+        }
+        (byte_range, end_line, total_lines)
+    };
+
+    file.seek(SeekFrom::Start(start_byte))
+        .map_err(|e| format!("Failed to read session file: {}", e))?;
+    let mut content = String::new();
+    file.take(end_byte - start_byte)
+        .read_to_string(&mut content)
+        .map_err(|e| format!("Failed to read session file: {}", e))?;
+
+    let mut messages = Vec::new();
+    let mut skipped = 0;
+    for line in content.lines().filter(|line| !line.trim().is_empty()) {
+        match serde_json::from_str::<CanonicalMessage>(line) {
+            Ok(message) => messages.push(message),
+            Err(_) => skipped += 1,
+        }
+    }
+
+    Ok(MessagePage {
+        messages,
+        offset: offset.min(total_lines),
+        next_offset: end_line,
+        total_lines,
+        skipped,
+    })
+}
+
+pub(crate) fn session_file_path(session_id: &str) -> Result<PathBuf, String> {
+    get_full_session_by_id(session_id)
+        .map_err(|e| format!("Failed to get session: {}", e))?
+        .map(|session| PathBuf::from(session.file_path))
+        .ok_or_else(|| format!("Session {} not found", session_id))
+}
+
+/// A page of a stored session's messages
+pub fn get_session_messages(
+    session_id: &str,
+    offset: usize,
+    limit: Option<usize>,
+) -> Result<MessagePage, String> {
+    let path = session_file_path(session_id)?;
+    read_message_page(&path, offset, limit.unwrap_or(DEFAULT_PAGE_SIZE))
+}
+
+/// Send every page from `offset` to the end of the file; returns where the
+/// next read starts, or None once `send` reports the receiver is gone
+fn send_new_pages(
+    path: &Path,
+    mut offset: usize,
+    send: &mut impl FnMut(MessagePage) -> bool,
+) -> Option<usize> {
+    loop {
+        let page = match read_message_page(path, offset, MAX_PAGE_SIZE) {
+            Ok(page) => page,
+            // The file may be mid-rewrite; try again on the next change
+            Err(_) => return Some(offset),
+        };
+        if page.next_offset == offset {
+            return Some(offset);
+        }
+        offset = page.next_offset;
+        if !send(page) {
+            return None;
+        }
+    }
+}
+
+/// `send_new_pages` on the blocking pool, handing `send` back with the next
+/// offset; None once the receiver is gone
+async fn send_new_pages_blocking<F>(path: PathBuf, offset: usize, mut send: F) -> Option<(usize, F)>
+where
+    F: FnMut(MessagePage) -> bool + Send + 'static,
+{
+    tokio::task::spawn_blocking(move || {
+        send_new_pages(&path, offset, &mut send).map(|next_offset| (next_offset, send))
+    })
+    .await
+    .ok()
+    .flatten()
+}
+
+fn is_change_of(payload: &SessionEventPayload, session_id: &str) -> bool {
+    matches!(
+        payload,
+        SessionEventPayload::SessionChanged { session_id: id, .. } if id == session_id
+    )
+}
+
+/// Follow a session, sending the messages after line `offset` now and as the
+/// session changes. Returns an id for `stop_session_stream`.
+///
+/// A stream without changes sends an empty page every `STREAM_PROBE_INTERVAL`
+/// and ends once `send` reports the receiver is gone.
+pub fn stream_session_messages(
+    event_bus: &EventBus,
+    session_id: &str,
+    offset: usize,
+    mut send: impl FnMut(MessagePage) -> bool + Send + 'static,
+) -> Result<String, String> {
+    let path = session_file_path(session_id)?;
+    let stream_id = uuid::Uuid::new_v4().to_string();
+    let (stop_tx, mut stop_rx) = oneshot::channel();
+    ACTIVE_STREAMS
+        .lock()
+        .unwrap()
+        .insert(stream_id.clone(), stop_tx);
+
+    let mut events = event_bus.subscribe();
+    let session_id = session_id.to_string();
+    let id = stream_id.clone();
+    tauri::async_runtime::spawn(async move {
+        let mut probe = tokio::time::interval(STREAM_PROBE_INTERVAL);
+        probe.tick().await;
+
+        let mut next = send_new_pages_blocking(path.clone(), offset, send).await;
+        while let Some((offset, mut send)) = next {
+            tokio::select! {
+                event = events.recv() => match event {
+                    Ok(event) if !is_change_of(&event.payload, &session_id) => {
+                        next = Some((offset, send));
+                    }
+                    // Missed events may include this session's; catch up anyway
+                    Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => {
+                        probe.reset();
+                        next = send_new_pages_blocking(path.clone(), offset, send).await;
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                },
+                // A window that closed without stopping its stream only shows
+                // up as a failed send
+                _ = probe.tick() => {
+                    next = send(MessagePage::empty(offset)).then_some((offset, send));
+                }
+                _ = &mut stop_rx => break,
+            }
+        }
+
+        ACTIVE_STREAMS.lock().unwrap().remove(&id);
+        log_debug("session-viewer", &format!("Stopped message stream {}", id)).unwrap_or_default();
+    });
+
+    Ok(stream_id)
+}
+
+/// Stop a stream started by `stream_session_messages`
+pub fn stop_session_stream(stream_id: &str) -> bool {
+    match ACTIVE_STREAMS.lock().unwrap().remove(stream_id) {
+        Some(stop) => {
+            let _ = stop.send(());
+            true
+        }
+        None => false,
+    }
+}
+
+/// New pages of a tailed session; a session seen for the first time starts
+/// at its current end, so only messages added while it's tailed are sent
+fn tail_pages(path: &Path, offset: Option<usize>) -> (usize, Vec<MessagePage>) {
+    let Some(offset) = offset else {
+        let end = read_message_page(path, usize::MAX, 1)
+            .map(|page| page.total_lines)
+            .unwrap_or(0);
+        return (end, Vec::new());
+    };
+
+    let mut pages = Vec::new();
+    let next_offset = send_new_pages(path, offset, &mut |page| {
+        pages.push(page);
+        true
+    })
+    .unwrap_or(offset);
+    (next_offset, pages)
+}
+
+/// A session followed by `LiveTailHandler`
+struct TailedSession {
+    provider: String,
+    file_path: PathBuf,
+    /// Line the next batch starts from, once the session's end is known
+    next_offset: Option<usize>,
+    changed_at: Instant,
+    /// Changed since its last batch
+    pending: bool,
+}
+
+/// Handler that publishes the messages appended to live sessions
+pub struct LiveTailHandler {
+    event_bus: EventBus,
+    shutdown: ShutdownCoordinator,
+}
+
+impl LiveTailHandler {
+    pub fn new(event_bus: EventBus, shutdown: ShutdownCoordinator) -> Self {
+        Self {
+            event_bus,
+            shutdown,
+        }
+    }
+
+    pub fn start(self) {
+        tauri::async_runtime::spawn(async move {
+            let mut rx = self.event_bus.subscribe();
+            let mut shutdown_rx = self.shutdown.subscribe();
+            let mut interval = tokio::time::interval(TAIL_INTERVAL);
+            let mut sessions: HashMap<String, TailedSession> = HashMap::new();
+
+            loop {
+                tokio::select! {
+                    result = rx.recv() => {
+                        match result {
+                            Ok(event) => {
+                                if let SessionEventPayload::SessionChanged { session_id, file_path, .. } =
+                                    &event.payload
+                                {
+                                    let session = sessions.entry(session_id.clone()).or_insert_with(|| {
+                                        TailedSession {
+                                            provider: event.provider.clone(),
+                                            file_path: file_path.clone(),
+                                            next_offset: None,
+                                            changed_at: Instant::now(),
+                                            pending: true,
+                                        }
+                                    });
+                                    session.file_path = file_path.clone();
+                                    session.changed_at = Instant::now();
+                                    session.pending = true;
+                                }
+                            }
+                            Err(broadcast::error::RecvError::Closed) => {
+                                log_info("session-viewer", "Live tail handler stopped (event bus closed)")
+                                    .unwrap_or_default();
+                                break;
+                            }
+                            // Missed changes are caught up by the next one
+                            Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                        }
+                    }
+                    _ = interval.tick() => {
+                        sessions.retain(|_, session| session.changed_at.elapsed() < TAIL_IDLE_TIMEOUT);
+
+                        let pending: Vec<(String, PathBuf, Option<usize>)> = sessions
+                            .iter_mut()
+                            .filter(|(_, session)| session.pending)
+                            .map(|(session_id, session)| {
+                                session.pending = false;
+                                (session_id.clone(), session.file_path.clone(), session.next_offset)
+                            })
+                            .collect();
+                        if pending.is_empty() {
+                            continue;
+                        }
+
+                        let Ok(batches) = tokio::task::spawn_blocking(move || {
+                            pending
+                                .into_iter()
+                                .map(|(session_id, path, offset)| (session_id, tail_pages(&path, offset)))
+                                .collect::<Vec<_>>()
+                        })
+                        .await
+                        else {
+                            continue;
+                        };
+
+                        for (session_id, (next_offset, pages)) in batches {
+                            let Some(session) = sessions.get_mut(&session_id) else {
+                                continue;
+                            };
+                            session.next_offset = Some(next_offset);
+
+                            for page in pages.into_iter().filter(|page| !page.messages.is_empty()) {
+                                let payload = SessionEventPayload::MessagesAppended {
+                                    session_id: session_id.clone(),
+                                    offset: page.offset,
+                                    next_offset: page.next_offset,
+                                    messages: page.messages,
+                                };
+                                if let Err(e) = self.event_bus.publish(&session.provider, payload) {
+                                    log_error("session-viewer", &format!("Failed to publish live messages: {}", e))
+                                        .unwrap_or_default();
+                                }
+                            }
+                        }
+                    }
+                    _ = shutdown_rx.recv() => {
+                        log_info("session-viewer", "Live tail handler gracefully shutting down").unwrap_or_default();
+                        break;
+                    }
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::io::Write;
+
+    fn line(uuid: &str) -> String {
+        json!({
+            "uuid": uuid, "timestamp": "2025-01-01T10:00:00Z", "type": "user",
+            "sessionId": "s1", "provider": "claude-code",
+            "message": {"role": "user", "content": "Hello"}
+        })
+        .to_string()
+    }
+
+    fn uuids(page: &MessagePage) -> Vec<&str> {
+        page.messages.iter().map(|m| m.uuid.as_str()).collect()
+    }
+
+    #[test]
+    fn test_pages_follow_a_growing_file() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("s1.jsonl");
+        // Canonical files don't end with a newline
+        std::fs::write(&path, format!("{}\nnot json\n{}", line("u1"), line("u2"))).unwrap();
+
+        let page = read_message_page(&path, 0, 2).unwrap();
+        assert_eq!(uuids(&page), ["u1"]);
+        assert_eq!(
+            (page.skipped, page.next_offset, page.total_lines),
+            (1, 2, 3)
+        );
+
+        let page = read_message_page(&path, page.next_offset, 2).unwrap();
+        assert_eq!(uuids(&page), ["u2"]);
+
+        let mut file = std::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap();
+        write!(file, "\n{}\n{}", line("u3"), line("u4")).unwrap();
+
+        let page = read_message_page(&path, 2, 10).unwrap();
+        assert_eq!(uuids(&page), ["u2", "u3", "u4"]);
+        assert_eq!(page.total_lines, 5);
+
+        // Rewritten shorter: indexed again from the start
+        std::fs::write(&path, line("u5")).unwrap();
+        let page = read_message_page(&path, 0, 10).unwrap();
+        assert_eq!(uuids(&page), ["u5"]);
+        assert_eq!(read_message_page(&path, 7, 10).unwrap().offset, 1);
+    }
+
+    #[test]
+    fn test_rewrite_of_same_length_is_indexed_again() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("s1.jsonl");
+        std::fs::write(&path, format!("{}\n{}", line("u10"), line("u2"))).unwrap();
+        assert_eq!(
+            uuids(&read_message_page(&path, 0, 10).unwrap()),
+            ["u10", "u2"]
+        );
+
+        // Same length, with the line break one byte earlier
+        std::fs::write(&path, format!("{}\n{}", line("u1"), line("u20"))).unwrap();
+        let page = read_message_page(&path, 0, 10).unwrap();
+        assert_eq!(uuids(&page), ["u1", "u20"]);
+        assert_eq!(page.skipped, 0);
+    }
+
+    #[test]
+    fn test_send_new_pages_stops_when_receiver_is_gone() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("s1.jsonl");
+        let lines: Vec<String> = (0..MAX_PAGE_SIZE + 5)
+            .map(|i| line(&format!("u{}", i)))
+            .collect();
+        std::fs::write(&path, lines.join("\n")).unwrap();
+
+        let mut sizes = Vec::new();
+        let next = send_new_pages(&path, 0, &mut |page| {
+            sizes.push(page.messages.len());
+            true
+        });
+        assert_eq!(sizes, [MAX_PAGE_SIZE, 5]);
+        assert_eq!(next, Some(MAX_PAGE_SIZE + 5));
+
+        assert_eq!(send_new_pages(&path, 3, &mut |_| false), None);
+    }
+
+    #[test]
+    fn test_tail_pages_starts_at_the_end() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("s1.jsonl");
+        std::fs::write(&path, format!("{}\n{}", line("u1"), line("u2"))).unwrap();
+
+        let (offset, pages) = tail_pages(&path, None);
+        assert_eq!(offset, 2);
+        assert!(pages.is_empty());
+
+        let mut file = std::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap();
+        write!(file, "\n{}", line("u3")).unwrap();
+
+        let (offset, pages) = tail_pages(&path, Some(offset));
+        assert_eq!(offset, 3);
+        assert_eq!(pages.len(), 1);
+        assert_eq!(uuids(&pages[0]), ["u3"]);
+
+        let (offset, pages) = tail_pages(&path, Some(offset));
+        assert_eq!(offset, 3);
+        assert!(pages.is_empty());
+    }
+}
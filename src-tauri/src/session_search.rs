@@ -0,0 +1,234 @@
+//! Find-in-transcript for a single session
+//!
+//! The session viewer searches on the Rust side so it never has to load the
+//! whole canonical file. Matches are reported per message part, addressed by
+//! the same line numbers as `session_messages` pages, with a short snippet
+//! around the first occurrence. Matching ignores ASCII case.
+
+use crate::metrics::tools::input_paths;
+use crate::providers::canonical::{CanonicalMessage, ContentBlock, ContentValue};
+use crate::session_messages::session_file_path;
+use serde::Serialize;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+/// Matches returned before the search stops
+pub const MAX_SEARCH_MATCHES: usize = 500;
+
+/// Characters of context kept on each side of a match in its snippet
+const SNIPPET_CONTEXT: usize = 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchField {
+    Text,
+    Thinking,
+    ToolName,
+    FilePath,
+    ToolResult,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionSearchMatch {
+    /// Line of the message, as used by `get_session_messages` offsets
+    pub line: usize,
+    pub uuid: String,
+    pub field: SearchField,
+    pub snippet: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionSearchResults {
+    pub matches: Vec<SessionSearchMatch>,
+    /// Whether the search stopped at `MAX_SEARCH_MATCHES`
+    pub truncated: bool,
+}
+
+/// Byte index of the first occurrence of `needle`, ignoring ASCII case
+fn find_ignore_ascii_case(haystack: &str, needle: &str) -> Option<usize> {
+    let (haystack, needle) = (haystack.as_bytes(), needle.as_bytes());
+    if needle.is_empty() || needle.len() > haystack.len() {
+        return None;
+    }
+    haystack
+        .windows(needle.len())
+        .position(|window| window.eq_ignore_ascii_case(needle))
+}
+
+/// Text around a match, on one line, with ellipses where it was cut
+fn snippet(text: &str, start: usize, len: usize) -> String {
+    let before = text[..start]
+        .char_indices()
+        .rev()
+        .nth(SNIPPET_CONTEXT - 1)
+        .map(|(index, _)| index)
+        .unwrap_or(0);
+    let after = text[start + len..]
+        .char_indices()
+        .nth(SNIPPET_CONTEXT)
+        .map(|(index, _)| start + len + index)
+        .unwrap_or(text.len());
+
+    let mut snippet = String::new();
+    if before > 0 {
+        snippet.push('…');
+    }
+    snippet.extend(
+        text[before..after]
+            .chars()
+            .map(|c| if c.is_whitespace() { ' ' } else { c }),
+    );
+    if after < text.len() {
+        snippet.push('…');
+    }
+    snippet
+}
+
+/// The searchable parts of a message
+fn searchable_parts(message: &CanonicalMessage) -> Vec<(SearchField, &str)> {
+    let blocks = match &message.message.content {
+        ContentValue::Text(text) => return vec![(SearchField::Text, text.as_str())],
+        ContentValue::Structured(blocks) => blocks,
+    };
+
+    let mut parts = Vec::new();
+    for block in blocks {
+        match block {
+            ContentBlock::Text { text } => parts.push((SearchField::Text, text.as_str())),
+            ContentBlock::Thinking { thinking } => {
+                parts.push((SearchField::Thinking, thinking.as_str()))
+            }
+            ContentBlock::ToolUse { name, input, .. } => {
+                parts.push((SearchField::ToolName, name.as_str()));
+                let mut paths = Vec::new();
+                input_paths(input, &mut paths);
+                parts.extend(paths.into_iter().map(|path| (SearchField::FilePath, path)));
+            }
+            ContentBlock::ToolResult { content, .. } => {
+                parts.push((SearchField::ToolResult, content.as_str()))
+            }
+            ContentBlock::Image {
+                path: Some(path), ..
+            }
+            | ContentBlock::Attachment {
+                path: Some(path), ..
+            } => parts.push((SearchField::FilePath, path.as_str())),
+            _ => {}
+        }
+    }
+    parts
+}
+
+/// Search a canonical file for `query`
+fn search_file(path: &Path, query: &str) -> Result<SessionSearchResults, String> {
+    let file = File::open(path).map_err(|e| format!("Failed to open session file: {}", e))?;
+    let query = query.trim();
+    let mut matches = Vec::new();
+
+    for (line_number, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.map_err(|e| format!("Failed to read session file: {}", e))?;
+        let Ok(message) = serde_json::from_str::<CanonicalMessage>(&line) else {
+            continue;
+        };
+
+        for (field, text) in searchable_parts(&message) {
+            let Some(start) = find_ignore_ascii_case(text, query) else {
+                continue;
+            };
+            if matches.len() == MAX_SEARCH_MATCHES {
+                return Ok(SessionSearchResults {
+                    matches,
+                    truncated: true,
+                });
+            }
+            matches.push(SessionSearchMatch {
+                line: line_number,
+                uuid: message.uuid.clone(),
+                field,
+                snippet: snippet(text, start, query.len()),
+            });
+        }
+    }
+
+    Ok(SessionSearchResults {
+        matches,
+        truncated: false,
+    })
+}
+
+/// Find the messages of a session that mention `query`
+pub fn search_in_session(session_id: &str, query: &str) -> Result<SessionSearchResults, String> {
+    let path = session_file_path(session_id)?;
+    search_file(&path, query)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+
+    fn message(uuid: &str, content: Value) -> String {
+        json!({
+            "uuid": uuid, "timestamp": "2025-01-01T10:00:00Z", "type": "assistant",
+            "sessionId": "s1", "provider": "claude-code",
+            "message": {"role": "assistant", "content": content}
+        })
+        .to_string()
+    }
+
+    #[test]
+    fn test_search_file_matches_parts() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("s1.jsonl");
+        let lines = [
+            message("u1", json!("Let me look at the Parser")),
+            "not json".to_string(),
+            message(
+                "u2",
+                json!([
+                    {"type": "thinking", "thinking": "the parser is slow"},
+                    {"type": "tool_use", "id": "t1", "name": "Read",
+                     "input": {"file_path": "/app/src/parser.rs"}},
+                    {"type": "tool_use", "id": "t2", "name": "Grep", "input": {"pattern": "parser"}}
+                ]),
+            ),
+        ];
+        std::fs::write(&path, lines.join("\n")).unwrap();
+
+        let results = search_file(&path, " PARSER ").unwrap();
+        let found: Vec<_> = results
+            .matches
+            .iter()
+            .map(|m| (m.line, m.uuid.as_str(), m.field))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (0, "u1", SearchField::Text),
+                (2, "u2", SearchField::Thinking),
+                (2, "u2", SearchField::FilePath),
+            ]
+        );
+        assert!(!results.truncated);
+        assert_eq!(results.matches[2].snippet, "/app/src/parser.rs");
+
+        let results = search_file(&path, "read").unwrap();
+        assert_eq!(results.matches[0].field, SearchField::ToolName);
+        assert!(search_file(&path, "  ").unwrap().matches.is_empty());
+    }
+
+    #[test]
+    fn test_snippet() {
+        let text = format!("{}needle\nin a haystack", "é".repeat(100));
+        let start = text.find("needle").unwrap();
+
+        let snippet = snippet(&text, start, "needle".len());
+        assert_eq!(
+            snippet,
+            format!("…{}needle in a haystack", "é".repeat(SNIPPET_CONTEXT))
+        );
+    }
+}
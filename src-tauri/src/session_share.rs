@@ -0,0 +1,264 @@
+//! Share a session as a server link or a Markdown excerpt
+//!
+//! A link points at the session on the GuideMode server; a session that hasn't
+//! synced yet is uploaded first, as long as its provider syncs transcripts and
+//! the processor would upload it: not excluded from sync, not under the
+//! provider's quality gate. It only counts as synced once the transcript is in.
+//! Only the active tenant's sessions can be shared as links.
+//! The excerpt is the session's Markdown transcript without thinking, with the
+//! home directory and anything that looks like a credential replaced, cut to a
+//! length that fits a pull request description.
+
+use crate::config::load_config;
+use crate::database::{get_full_session_by_id, mark_session_synced, with_connection_mut};
+use crate::logging::log_info;
+use crate::providers::canonical::CanonicalMessage;
+use crate::redaction::sanitize;
+use crate::transcript::{render_transcript, TranscriptFormat, TranscriptOptions};
+use crate::upload_queue::quality_gate;
+use crate::upload_queue::{UploadItem, UploadOutcome, UploadQueue};
+use crate::workspace_roots::effective_sync_mode;
+use chrono::Utc;
+use rusqlite::params;
+use serde::Serialize;
+use std::fs;
+use std::path::PathBuf;
+
+/// Excerpts longer than this are cut at a turn boundary
+pub const DEFAULT_EXCERPT_MAX_CHARS: usize = 20_000;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionExcerpt {
+    pub markdown: String,
+    /// Whether later turns were left out to respect the length limit
+    pub truncated: bool,
+}
+
+/// URL of a synced session on the server
+fn session_url(server_url: &str, session_id: &str, server_session_id: Option<&str>) -> String {
+    format!(
+        "{}/sessions/{}",
+        server_url.trim_end_matches('/'),
+        urlencoding::encode(server_session_id.unwrap_or(session_id))
+    )
+}
+
+/// Where a session stands with the server
+struct SyncState {
+    synced: bool,
+    /// The id the server gave the session
+    server_session_id: Option<String>,
+    /// Skipped by the quality gate, or in a project excluded from sync
+    skipped: bool,
+}
+
+fn sync_state(session_id: &str) -> Result<SyncState, String> {
+    with_connection_mut(|conn| {
+        conn.query_row(
+            "SELECT s.synced_to_server, s.server_session_id,
+                    COALESCE(s.sync_skipped, 0) = 1 OR COALESCE(p.sync_excluded, 0) = 1
+             FROM agent_sessions s
+             LEFT JOIN projects p ON p.id = s.project_id
+             WHERE s.session_id = ?",
+            params![session_id],
+            |row| {
+                Ok(SyncState {
+                    synced: row.get::<_, Option<i64>>(0)? == Some(1),
+                    server_session_id: row.get(1)?,
+                    skipped: row.get(2)?,
+                })
+            },
+        )
+    })
+    .map_err(|e| format!("Failed to get sync state: {}", e))
+}
+
+/// Server link to a session, uploading it first if it hasn't synced yet
+pub async fn share_session_link(
+    upload_queue: &UploadQueue,
+    session_id: &str,
+) -> Result<String, String> {
+    let config = load_config().map_err(|e| format!("Failed to load config: {}", e))?;
+    let server_url = config
+        .server_url
+        .clone()
+        .filter(|_| config.api_key.is_some())
+        .ok_or("Log in to GuideMode to share session links")?;
+    // Neither link to nor upload another tenant's session
+    if !crate::tenant::belongs_to_active_tenant(session_id) {
+        return Err(
+            "This session was recorded for another organization; log in to it to share the session"
+                .to_string(),
+        );
+    }
+
+    let state = sync_state(session_id)?;
+    if state.synced {
+        return Ok(session_url(
+            &server_url,
+            session_id,
+            state.server_session_id.as_deref(),
+        ));
+    }
+    // The processor wouldn't upload it either
+    if state.skipped {
+        return Err(
+            "This session isn't synced: its project is excluded from sync or it was skipped \
+             as too short; copy a Markdown excerpt instead"
+                .to_string(),
+        );
+    }
+
+    let session = get_full_session_by_id(session_id)
+        .map_err(|e| format!("Failed to get session: {}", e))?
+        .ok_or_else(|| format!("Session {} not found", session_id))?;
+    // A workspace root's sync mode overrides the provider's
+    let sync_mode = effective_sync_mode(&session.provider, session.cwd.as_deref())
+        .ok_or("Failed to load provider config")?;
+    if sync_mode != "Transcript and Metrics" {
+        return Err(format!(
+            "This {} session doesn't sync transcripts (sync mode: {}); copy a Markdown excerpt instead",
+            session.provider, sync_mode
+        ));
+    }
+
+    let item = UploadItem {
+        id: uuid::Uuid::new_v4().to_string(),
+        provider: session.provider.clone(),
+        project_name: session.project_name.clone(),
+        file_path: PathBuf::from(&session.file_path),
+        file_name: session.file_name.clone(),
+        queued_at: Utc::now(),
+        retry_count: 0,
+        next_retry_at: None,
+        last_error: None,
+        file_hash: None,
+        file_size: session.file_size as u64,
+        session_id: Some(session_id.to_string()),
+        content: None,
+        cwd: session.cwd.clone(),
+        priority: 0,
+        last_attempt_at: None,
+        superseded: false,
+        quality_checked: false,
+    };
+    let gated_item = item.clone();
+    let low_signal =
+        tauri::async_runtime::spawn_blocking(move || quality_gate::check_item(&gated_item))
+            .await
+            .map_err(|e| format!("Failed to check session quality: {}", e))?;
+    if let Some(low_signal) = low_signal {
+        return Err(format!(
+            "This session is below the {} quality gate ({}); copy a Markdown excerpt instead",
+            session.provider, low_signal
+        ));
+    }
+
+    let outcome = upload_queue.upload_now(&item).await?;
+    if outcome != UploadOutcome::Transcript {
+        return Err(
+            "The server can't take this session's transcript yet; try sharing it again later"
+                .to_string(),
+        );
+    }
+    mark_session_synced(session_id, None)
+        .map_err(|e| format!("Failed to mark session as synced: {}", e))?;
+
+    log_info(
+        "share",
+        &format!("✓ Uploaded session {} to share it", session_id),
+    )
+    .unwrap_or_default();
+
+    Ok(session_url(&server_url, session_id, None))
+}
+
+/// Cut Markdown before the turn that would exceed `max_chars`
+fn truncate_at_turn(markdown: &str, max_chars: usize) -> (String, bool) {
+    if markdown.len() <= max_chars {
+        return (markdown.to_string(), false);
+    }
+
+    let cut = markdown
+        .match_indices("\n## ")
+        .map(|(index, _)| index)
+        .take_while(|index| *index <= max_chars)
+        .last()
+        .unwrap_or_else(|| {
+            let mut index = max_chars;
+            while !markdown.is_char_boundary(index) {
+                index -= 1;
+            }
+            index
+        });
+    (
+        format!(
+            "{}\n\n_Transcript truncated; later turns left out._\n",
+            markdown[..cut].trim_end()
+        ),
+        true,
+    )
+}
+
+/// Build a sanitized Markdown excerpt of a session for pasting elsewhere
+pub fn session_markdown_excerpt(
+    session_id: &str,
+    max_chars: Option<usize>,
+) -> Result<SessionExcerpt, String> {
+    let session = get_full_session_by_id(session_id)
+        .map_err(|e| format!("Failed to get session: {}", e))?
+        .ok_or_else(|| format!("Session {} not found", session_id))?;
+    let content = fs::read_to_string(&session.file_path)
+        .map_err(|e| format!("Failed to read session file: {}", e))?;
+    let messages: Vec<CanonicalMessage> = content
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect();
+
+    let markdown = render_transcript(
+        &messages,
+        TranscriptFormat::Markdown,
+        TranscriptOptions::default(),
+    );
+    let home_dir = dirs::home_dir().map(|home| home.to_string_lossy().to_string());
+    let sanitized = sanitize(&markdown, home_dir.as_deref());
+    let (markdown, truncated) =
+        truncate_at_turn(&sanitized, max_chars.unwrap_or(DEFAULT_EXCERPT_MAX_CHARS));
+
+    Ok(SessionExcerpt {
+        markdown,
+        truncated,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_truncate_at_turn() {
+        let markdown = "# Session s1\n\n## User\n\nHello\n\n## Assistant\n\nA long answer";
+
+        assert_eq!(
+            truncate_at_turn(markdown, 1_000),
+            (markdown.to_string(), false)
+        );
+
+        let (truncated, was_truncated) = truncate_at_turn(markdown, 40);
+        assert!(was_truncated);
+        assert!(truncated.starts_with("# Session s1\n\n## User\n\nHello\n\n_Transcript truncated"));
+    }
+
+    #[test]
+    fn test_session_url() {
+        assert_eq!(
+            session_url("https://app.guidemode.dev/", "a b", None),
+            "https://app.guidemode.dev/sessions/a%20b"
+        );
+        assert_eq!(
+            session_url("https://app.guidemode.dev", "local", Some("srv-1")),
+            "https://app.guidemode.dev/sessions/srv-1"
+        );
+    }
+}
@@ -0,0 +1,503 @@
+//! Local summaries of completed sessions
+//!
+//! When summarization is enabled, `SummaryHandler` waits for a session to
+//! complete or go quiet, sends its transcript (without thinking, sanitized
+//! like share excerpts) to the configured chat completions endpoint and stores
+//! the one-paragraph reply in `ai_model_summary`. The files the session's
+//! tools touched most go in `ai_model_metadata` under `localSummary`, along
+//! with the file size that was summarized: a local summary is only redone once
+//! the session has grown, and a summary from AI processing is never replaced.
+
+use crate::config::{load_summarizer_config, SummarizerConfig};
+use crate::database::{get_full_session_by_id, with_connection_mut};
+use crate::events::{EventBus, SessionEventPayload};
+use crate::logging::{log_error, log_info};
+use crate::metrics::tools::input_paths;
+use crate::providers::canonical::{CanonicalMessage, ContentBlock, ContentValue};
+use crate::redaction::sanitize;
+use crate::shutdown::ShutdownCoordinator;
+use crate::transcript::{render_transcript, TranscriptFormat, TranscriptOptions};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use tokio::sync::{broadcast, mpsc};
+
+/// Quiet period after which a session that didn't report completing is
+/// considered done
+const SUMMARY_IDLE: Duration = Duration::from_secs(300);
+
+/// How often quiet sessions are checked
+const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Longest wait for the endpoint to answer
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Files kept in a summary's key files
+const MAX_KEY_FILES: usize = 10;
+
+const SYSTEM_PROMPT: &str = "You summarize sessions between a developer and an AI coding \
+agent. Reply with a single paragraph of plain text, at most 120 words, saying what the \
+developer wanted, what the agent did and how the session ended. No headings, lists or preamble.";
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionSummary {
+    pub summary: String,
+    pub key_files: Vec<String>,
+}
+
+/// What `ai_model_metadata.localSummary` records about a local summary
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LocalSummary {
+    key_files: Vec<String>,
+    model: String,
+    /// Size of the session file when it was summarized
+    file_size: i64,
+}
+
+/// Whether a session with this stored summary and metadata should be
+/// summarized at `file_size`
+fn needs_summary(summary: Option<&str>, metadata: Option<&str>, file_size: i64) -> bool {
+    if summary.is_none_or(|summary| summary.trim().is_empty()) {
+        return true;
+    }
+    let local = metadata
+        .and_then(|metadata| serde_json::from_str::<Value>(metadata).ok())
+        .and_then(|metadata| {
+            serde_json::from_value::<LocalSummary>(metadata["localSummary"].clone()).ok()
+        });
+    match local {
+        Some(local) => local.file_size != file_size,
+        // Written by AI processing
+        None => false,
+    }
+}
+
+/// Files most used by the session's tools, relative to the session's
+/// directory where they're inside it
+fn key_files(messages: &[CanonicalMessage]) -> Vec<String> {
+    let mut counts: HashMap<String, (usize, usize)> = HashMap::new();
+    for message in messages {
+        let ContentValue::Structured(blocks) = &message.message.content else {
+            continue;
+        };
+        for block in blocks {
+            let ContentBlock::ToolUse { input, .. } = block else {
+                continue;
+            };
+            let mut paths = Vec::new();
+            input_paths(input, &mut paths);
+            for path in paths {
+                let path = message
+                    .cwd
+                    .as_deref()
+                    .and_then(|cwd| Path::new(path).strip_prefix(cwd).ok())
+                    .map(|relative| relative.to_string_lossy().to_string())
+                    .unwrap_or_else(|| path.to_string());
+                let first_seen = counts.len();
+                counts.entry(path).or_insert((0, first_seen)).0 += 1;
+            }
+        }
+    }
+
+    let mut files: Vec<_> = counts.into_iter().collect();
+    files.sort_by(|(_, (count_a, first_a)), (_, (count_b, first_b))| {
+        count_b.cmp(count_a).then(first_a.cmp(first_b))
+    });
+    files
+        .into_iter()
+        .take(MAX_KEY_FILES)
+        .map(|(path, _)| path)
+        .collect()
+}
+
+/// Keep a transcript under `max_chars`: its first third, which states the
+/// goal, and its end, which has the outcome
+fn trim_transcript(transcript: &str, max_chars: usize) -> String {
+    if transcript.len() <= max_chars {
+        return transcript.to_string();
+    }
+    let floor = |mut index: usize| {
+        while !transcript.is_char_boundary(index) {
+            index -= 1;
+        }
+        index
+    };
+    let head = floor(max_chars / 3);
+    let tail = floor(transcript.len() - (max_chars - max_chars / 3));
+    format!(
+        "{}\n\n[… middle of the session left out …]\n\n{}",
+        &transcript[..head],
+        &transcript[tail..]
+    )
+}
+
+/// A session's transcript as sent to a local model: markdown without
+/// thinking, sanitized and trimmed to `max_chars`, with the messages it was
+/// rendered from
+pub(crate) fn read_transcript(
+    file_path: &str,
+    max_chars: usize,
+) -> Result<(String, Vec<CanonicalMessage>), String> {
+    let content = std::fs::read_to_string(file_path)
+        .map_err(|e| format!("Failed to read session file: {}", e))?;
+    let messages: Vec<CanonicalMessage> = content
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect();
+    let markdown = render_transcript(
+        &messages,
+        TranscriptFormat::Markdown,
+        TranscriptOptions::default(),
+    );
+    let home_dir = dirs::home_dir().map(|home| home.to_string_lossy().to_string());
+    let transcript = trim_transcript(&sanitize(&markdown, home_dir.as_deref()), max_chars);
+    Ok((transcript, messages))
+}
+
+/// Ask the endpoint for a summary of `transcript`
+async fn request_summary(config: &SummarizerConfig, transcript: &str) -> Result<String, String> {
+    request_completion(
+        &config.endpoint,
+        &config.model,
+        config.api_key.as_deref(),
+        SYSTEM_PROMPT,
+        transcript,
+    )
+    .await
+}
+
+/// Send `transcript` to a chat completions endpoint under `system_prompt`,
+/// returning the reply
+pub(crate) async fn request_completion(
+    endpoint: &str,
+    model: &str,
+    api_key: Option<&str>,
+    system_prompt: &str,
+    transcript: &str,
+) -> Result<String, String> {
+    let client = reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+
+    let mut request = client.post(endpoint).json(&json!({
+        "model": model,
+        "messages": [
+            {"role": "system", "content": system_prompt},
+            {"role": "user", "content": transcript},
+        ],
+        "temperature": 0.2,
+        "stream": false,
+    }));
+    if let Some(api_key) = api_key.filter(|key| !key.is_empty()) {
+        request = request.header("Authorization", format!("Bearer {}", api_key));
+    }
+
+    let response = request
+        .send()
+        .await
+        .map_err(|e| format!("Request to {} failed: {}", endpoint, e))?;
+    if !response.status().is_success() {
+        let status = response.status();
+        let error_text = response
+            .text()
+            .await
+            .unwrap_or_else(|_| "Unknown error".to_string());
+        return Err(format!(
+            "Request to {} failed with status {}: {}",
+            endpoint, status, error_text
+        ));
+    }
+
+    let body: Value = response
+        .json()
+        .await
+        .map_err(|e| format!("Invalid response from {}: {}", endpoint, e))?;
+    body["choices"][0]["message"]["content"]
+        .as_str()
+        .map(str::trim)
+        .filter(|reply| !reply.is_empty())
+        .map(str::to_string)
+        .ok_or_else(|| format!("Response from {} has no content", endpoint))
+}
+
+/// `ai_model_metadata` with `localSummary` set, keeping any other fields
+fn merged_metadata(existing: Option<&str>, local: &LocalSummary) -> String {
+    let mut metadata = existing
+        .and_then(|existing| serde_json::from_str::<Value>(existing).ok())
+        .filter(Value::is_object)
+        .unwrap_or_else(|| json!({}));
+    metadata["localSummary"] = json!(local);
+    metadata.to_string()
+}
+
+fn store_summary(
+    conn: &Connection,
+    session_id: &str,
+    summary: &str,
+    local: &LocalSummary,
+) -> rusqlite::Result<()> {
+    let existing: Option<String> = conn
+        .query_row(
+            "SELECT ai_model_metadata FROM agent_sessions WHERE session_id = ?",
+            params![session_id],
+            |row| row.get(0),
+        )
+        .optional()?
+        .flatten();
+    conn.execute(
+        "UPDATE agent_sessions SET ai_model_summary = ?, ai_model_metadata = ?
+         WHERE session_id = ?",
+        params![
+            summary,
+            merged_metadata(existing.as_deref(), local),
+            session_id
+        ],
+    )?;
+    Ok(())
+}
+
+/// Summarize a session with the configured endpoint. Unless `force` is set,
+/// sessions with an up-to-date summary are left alone and return None.
+pub async fn summarize_session(
+    session_id: &str,
+    force: bool,
+) -> Result<Option<SessionSummary>, String> {
+    let config =
+        load_summarizer_config().map_err(|e| format!("Failed to load summarizer config: {}", e))?;
+    let session = get_full_session_by_id(session_id)
+        .map_err(|e| format!("Failed to get session: {}", e))?
+        .ok_or_else(|| format!("Session {} not found", session_id))?;
+    if !force
+        && !needs_summary(
+            session.ai_model_summary.as_deref(),
+            session.ai_model_metadata.as_deref(),
+            session.file_size,
+        )
+    {
+        return Ok(None);
+    }
+
+    let max_chars = config.max_transcript_chars;
+    let file_path = session.file_path.clone();
+    let (transcript, key_files) = tokio::task::spawn_blocking(move || {
+        let (transcript, messages) = read_transcript(&file_path, max_chars)?;
+        Ok::<_, String>((transcript, key_files(&messages)))
+    })
+    .await
+    .map_err(|e| format!("Failed to read session: {}", e))??;
+
+    let summary = request_summary(&config, &transcript).await?;
+    let local = LocalSummary {
+        key_files: key_files.clone(),
+        model: config.model.clone(),
+        file_size: session.file_size,
+    };
+    with_connection_mut(|conn| store_summary(conn, session_id, &summary, &local))
+        .map_err(|e| format!("Failed to store summary: {}", e))?;
+
+    log_info(
+        "summary",
+        &format!("✓ Summarized session {} with {}", session_id, config.model),
+    )
+    .unwrap_or_default();
+
+    Ok(Some(SessionSummary { summary, key_files }))
+}
+
+/// Handler that summarizes sessions once they complete or go quiet
+pub struct SummaryHandler {
+    event_bus: EventBus,
+    shutdown: ShutdownCoordinator,
+}
+
+impl SummaryHandler {
+    pub fn new(event_bus: EventBus, shutdown: ShutdownCoordinator) -> Self {
+        Self {
+            event_bus,
+            shutdown,
+        }
+    }
+
+    pub fn start(self) {
+        // Summaries are requested one at a time, away from the event loop
+        let (work_tx, mut work_rx) = mpsc::unbounded_channel::<String>();
+        tauri::async_runtime::spawn(async move {
+            while let Some(session_id) = work_rx.recv().await {
+                if let Err(e) = summarize_session(&session_id, false).await {
+                    log_error(
+                        "summary",
+                        &format!("Failed to summarize {}: {}", session_id, e),
+                    )
+                    .unwrap_or_default();
+                }
+            }
+        });
+
+        tauri::async_runtime::spawn(async move {
+            let mut rx = self.event_bus.subscribe();
+            let mut shutdown_rx = self.shutdown.subscribe();
+            let mut interval = tokio::time::interval(CHECK_INTERVAL);
+
+            // Session ID -> when it's due to be summarized
+            let mut pending: HashMap<String, Instant> = HashMap::new();
+
+            loop {
+                tokio::select! {
+                    result = rx.recv() => {
+                        match result {
+                            Ok(event) => match &event.payload {
+                                SessionEventPayload::SessionChanged { session_id, .. } => {
+                                    let due_at = Instant::now() + SUMMARY_IDLE;
+                                    pending.insert(session_id.clone(), due_at);
+                                }
+                                SessionEventPayload::Completed { session_id, .. } => {
+                                    pending.insert(session_id.clone(), Instant::now());
+                                }
+                                _ => {}
+                            },
+                            Err(broadcast::error::RecvError::Closed) => {
+                                log_info("summary", "Summary handler stopped (event bus closed)")
+                                    .unwrap_or_default();
+                                break;
+                            }
+                            Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                        }
+                    }
+                    _ = interval.tick() => {
+                        let now = Instant::now();
+                        let ready: Vec<String> = pending
+                            .iter()
+                            .filter(|(_, due_at)| **due_at <= now)
+                            .map(|(session_id, _)| session_id.clone())
+                            .collect();
+                        if ready.is_empty() {
+                            continue;
+                        }
+                        for session_id in &ready {
+                            pending.remove(session_id);
+                        }
+
+                        // Checked when sessions are due so settings apply without a restart
+                        if !load_summarizer_config().map(|config| config.enabled).unwrap_or(false) {
+                            continue;
+                        }
+                        for session_id in ready {
+                            let _ = work_tx.send(session_id);
+                        }
+                    }
+                    _ = shutdown_rx.recv() => {
+                        log_info("summary", "Summary handler gracefully shutting down")
+                            .unwrap_or_default();
+                        break;
+                    }
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn local_summary(file_size: i64) -> LocalSummary {
+        LocalSummary {
+            key_files: vec!["src/main.rs".to_string()],
+            model: "llama3.2".to_string(),
+            file_size,
+        }
+    }
+
+    #[test]
+    fn test_needs_summary() {
+        let local = merged_metadata(Some(r#"{"intents":["fix"]}"#), &local_summary(100));
+
+        assert!(needs_summary(None, None, 100));
+        assert!(needs_summary(Some(" "), None, 100));
+        assert!(!needs_summary(Some("Fixed it"), Some(&local), 100));
+        assert!(needs_summary(Some("Fixed it"), Some(&local), 250));
+        // Summaries from AI processing are kept
+        assert!(!needs_summary(
+            Some("Fixed it"),
+            Some(r#"{"intents":[]}"#),
+            250
+        ));
+        assert!(!needs_summary(Some("Fixed it"), None, 250));
+    }
+
+    #[test]
+    fn test_key_files() {
+        let messages: Vec<CanonicalMessage> = [
+            ("/app/src/lib.rs", "Read"),
+            ("/app/src/main.rs", "Read"),
+            ("/app/src/main.rs", "Edit"),
+            ("/tmp/notes.md", "Write"),
+        ]
+        .iter()
+        .map(|(path, tool)| {
+            serde_json::from_value(json!({
+                "uuid": "u1", "timestamp": "2025-01-01T10:00:00Z", "type": "assistant",
+                "sessionId": "s1", "provider": "claude-code", "cwd": "/app",
+                "message": {"role": "assistant", "content": [
+                    {"type": "tool_use", "id": "t1", "name": tool, "input": {"file_path": path}}
+                ]}
+            }))
+            .unwrap()
+        })
+        .collect();
+
+        assert_eq!(
+            key_files(&messages),
+            ["src/main.rs", "src/lib.rs", "/tmp/notes.md"]
+        );
+    }
+
+    #[test]
+    fn test_trim_transcript_keeps_start_and_end() {
+        let transcript = format!("GOAL{}OUTCOME", "x".repeat(1_000));
+
+        assert_eq!(trim_transcript(&transcript, 2_000), transcript);
+        let trimmed = trim_transcript(&transcript, 300);
+        assert!(trimmed.starts_with("GOAL"));
+        assert!(trimmed.ends_with("OUTCOME"));
+        assert!(trimmed.len() < 400);
+    }
+
+    #[test]
+    fn test_store_summary_keeps_other_metadata() {
+        let conn = crate::database::test_connection();
+        conn.execute(
+            "INSERT INTO agent_sessions (
+                id, provider, project_name, session_id, file_name, file_path, file_size,
+                ai_model_metadata, created_at, uploaded_at
+            ) VALUES ('s1', 'claude-code', 'app', 's1', 's1', '/s1', 100, ?1, 0, 0)",
+            params![r#"{"intents":["fix"]}"#],
+        )
+        .unwrap();
+
+        store_summary(&conn, "s1", "Fixed the parser", &local_summary(100)).unwrap();
+
+        let (summary, metadata): (String, String) = conn
+            .query_row(
+                "SELECT ai_model_summary, ai_model_metadata FROM agent_sessions",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        let metadata: Value = serde_json::from_str(&metadata).unwrap();
+        assert_eq!(summary, "Fixed the parser");
+        assert_eq!(metadata["intents"], json!(["fix"]));
+        assert_eq!(metadata["localSummary"]["keyFiles"], json!(["src/main.rs"]));
+        assert!(!needs_summary(
+            Some(&summary),
+            Some(&metadata.to_string()),
+            100
+        ));
+    }
+}
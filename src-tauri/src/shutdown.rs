@@ -47,9 +47,7 @@ impl ShutdownCoordinator {
     /// Trigger graceful shutdown
     /// All subscribers will receive a shutdown signal
     ///
-    /// Note: Currently not exposed as a command, but available for future use
-    /// (e.g., graceful app shutdown, restart functionality).
-    #[allow(dead_code)]
+    /// Called when the app exits, before the upload queue is drained.
     pub fn shutdown(&self) {
         let _ = self.shutdown_tx.send(());
     }
@@ -0,0 +1,184 @@
+//! Dry-run sync previews
+//!
+//! Before a provider's first big sync, its sessions can be scanned, converted
+//! and validated without storing or uploading anything, so the user sees how
+//! many sessions would sync, how much would be uploaded and which sessions
+//! convert badly. See `providers::common::preview` for what a dry run skips.
+
+use crate::config::load_provider_config;
+use crate::database::with_connection_mut;
+use crate::events::CancellationToken;
+use crate::providers::canonical::validation::validate_jsonl;
+use crate::providers::common::preview::PreviewGuard;
+use crate::providers::common::quarantine::list_conversion_errors;
+use crate::providers::common::SessionInfo;
+use crate::providers::scan_all_sessions_filtered;
+use crate::upload_queue::compression::compress_file_content;
+use rayon::prelude::*;
+use rusqlite::params;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+
+/// Rough size of a session's metadata and metrics upload
+const METRICS_UPLOAD_BYTES: u64 = 4 * 1024;
+
+/// Validation errors listed per session; the count covers the rest
+const MAX_LISTED_ERRORS: usize = 5;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionPreview {
+    pub session_id: String,
+    pub project_name: String,
+    /// Size of the canonical file
+    pub file_size: u64,
+    pub message_count: usize,
+    /// Bytes the session's upload would send under the provider's sync mode
+    pub upload_bytes: u64,
+    pub already_synced: bool,
+    /// Source lines the conversion had to leave out
+    pub skipped_lines: usize,
+    pub validation_error_count: usize,
+    pub validation_errors: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncPreview {
+    pub provider: String,
+    pub sync_mode: String,
+    pub session_count: usize,
+    /// Sessions not uploaded yet
+    pub sessions_to_upload: usize,
+    pub total_bytes: u64,
+    pub estimated_upload_bytes: u64,
+    pub sessions_with_errors: usize,
+    /// Largest sessions first
+    pub sessions: Vec<SessionPreview>,
+}
+
+/// Scan, convert and validate a provider's sessions without syncing them
+pub fn preview_sync(provider_id: &str) -> Result<SyncPreview, String> {
+    let config = load_provider_config(provider_id)
+        .map_err(|e| format!("Failed to load provider config: {}", e))?;
+    let selected_projects =
+        (config.project_selection != "ALL").then_some(config.selected_projects.as_slice());
+
+    let guard = PreviewGuard::start(provider_id)?;
+    let report = scan_all_sessions_filtered(
+        provider_id,
+        &config.home_directory,
+        selected_projects,
+        &CancellationToken::default(),
+    )?;
+    let skipped = guard.finish();
+
+    // Sessions reused from the scan cache weren't converted again; their
+    // skipped lines are the ones quarantined by the last conversion
+    let mut quarantined: HashMap<String, usize> = HashMap::new();
+    for error in list_conversion_errors(Some(provider_id)).unwrap_or_default() {
+        *quarantined.entry(error.session_id).or_default() += 1;
+    }
+    let synced = synced_session_ids(provider_id).unwrap_or_default();
+
+    let mut sessions: Vec<SessionPreview> = report
+        .sessions
+        .par_iter()
+        .map(|session| {
+            let skipped_lines = skipped.get(&session.session_id).map_or_else(
+                || quarantined.get(&session.session_id).copied().unwrap_or(0),
+                |lines| lines.len(),
+            );
+            preview_session(
+                session,
+                &config.sync_mode,
+                synced.contains(&session.session_id),
+                skipped_lines,
+            )
+        })
+        .collect();
+    sessions.sort_by_key(|session| std::cmp::Reverse(session.file_size));
+
+    let to_upload = sessions.iter().filter(|session| !session.already_synced);
+    Ok(SyncPreview {
+        provider: provider_id.to_string(),
+        sync_mode: config.sync_mode.clone(),
+        session_count: sessions.len(),
+        sessions_to_upload: to_upload.clone().count(),
+        total_bytes: sessions.iter().map(|session| session.file_size).sum(),
+        estimated_upload_bytes: to_upload.map(|session| session.upload_bytes).sum(),
+        sessions_with_errors: sessions
+            .iter()
+            .filter(|session| session.validation_error_count > 0 || session.skipped_lines > 0)
+            .count(),
+        sessions,
+    })
+}
+
+fn preview_session(
+    session: &SessionInfo,
+    sync_mode: &str,
+    already_synced: bool,
+    skipped_lines: usize,
+) -> SessionPreview {
+    let content = match fs::read_to_string(&session.file_path) {
+        Ok(content) => content,
+        Err(e) => {
+            return SessionPreview {
+                session_id: session.session_id.clone(),
+                project_name: session.project_name.clone(),
+                file_size: session.file_size,
+                message_count: 0,
+                upload_bytes: 0,
+                already_synced,
+                skipped_lines,
+                validation_error_count: 1,
+                validation_errors: vec![format!("Failed to read canonical file: {}", e)],
+            }
+        }
+    };
+
+    let report = validate_jsonl(&content);
+    let errors: Vec<String> = report
+        .errors()
+        .map(|issue| format!("line {}: {}", issue.line, issue.message))
+        .collect();
+
+    SessionPreview {
+        session_id: session.session_id.clone(),
+        project_name: session.project_name.clone(),
+        file_size: content.len() as u64,
+        message_count: report.total_messages,
+        upload_bytes: upload_bytes(&content, sync_mode),
+        already_synced,
+        skipped_lines,
+        validation_error_count: errors.len(),
+        validation_errors: errors.into_iter().take(MAX_LISTED_ERRORS).collect(),
+    }
+}
+
+/// Estimated bytes uploaded for a session's canonical content
+fn upload_bytes(content: &str, sync_mode: &str) -> u64 {
+    match sync_mode {
+        "Metrics Only" => METRICS_UPLOAD_BYTES,
+        // Transcripts are uploaded gzipped
+        "Transcript and Metrics" => compress_file_content(content.as_bytes())
+            .map_or(content.len() as u64, |compressed| compressed.len() as u64)
+            .saturating_add(METRICS_UPLOAD_BYTES),
+        _ => 0,
+    }
+}
+
+fn synced_session_ids(provider_id: &str) -> Result<HashSet<String>, String> {
+    with_connection_mut(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT session_id FROM agent_sessions WHERE provider = ? AND synced_to_server = 1",
+        )?;
+        let ids = stmt
+            .query_map(params![provider_id], |row| row.get(0))?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(ids)
+    })
+    .map_err(|e| format!("Failed to load synced sessions: {}", e))
+}
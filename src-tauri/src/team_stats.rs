@@ -0,0 +1,422 @@
+//! Team aggregates from the server
+//!
+//! When logged in to a tenant, the dashboard compares the user's metrics with
+//! their team's. The server computes the team's percentiles per metric for a
+//! range of days and only answers members allowed to see them; the response
+//! is cached in `team_stats_cache` for an hour per tenant and range. A cached
+//! response is still shown, marked stale, while the server can't be reached.
+//! Losing access (403) drops the tenant's cached stats, as does logging out.
+//!
+//! The user's side of the comparison is computed locally from
+//! `session_metrics`, by session start day.
+
+use crate::config::load_config;
+use crate::database::with_connection_mut;
+use crate::logging::log_info;
+use crate::metrics::rollup::local_date;
+use crate::metrics::UsageRange;
+use chrono::{DateTime, Utc};
+use reqwest::StatusCode;
+use rusqlite::{params, Connection, OptionalExtension, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// How long fetched stats are used before asking the server again
+const CACHE_TTL: Duration = Duration::from_secs(60 * 60);
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Compared metrics, as the server names them, and their `session_metrics` columns
+const COMPARED_METRICS: [(&str, &str); 7] = [
+    ("responseLatencyMs", "response_latency_ms"),
+    ("taskCompletionTimeMs", "task_completion_time_ms"),
+    ("readWriteRatio", "read_write_ratio"),
+    ("inputClarityScore", "input_clarity_score"),
+    ("errorCount", "error_count"),
+    ("interruptionRate", "interruption_rate"),
+    ("sessionLengthMinutes", "session_length_minutes"),
+];
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MetricAggregate {
+    median: Option<f64>,
+    p25: Option<f64>,
+    p75: Option<f64>,
+}
+
+/// The server's answer for a tenant and range
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ServerTeamStats {
+    member_count: Option<i64>,
+    #[serde(default)]
+    metrics: HashMap<String, MetricAggregate>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MetricComparison {
+    pub metric: String,
+    pub my_median: Option<f64>,
+    /// Sessions in the range with this metric
+    pub my_session_count: i64,
+    pub team_median: Option<f64>,
+    pub team_p25: Option<f64>,
+    pub team_p75: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TeamComparison {
+    pub tenant_id: String,
+    pub tenant_name: Option<String>,
+    pub member_count: Option<i64>,
+    pub fetched_at: DateTime<Utc>,
+    /// The server couldn't be reached and these are older stats
+    pub stale: bool,
+    pub metrics: Vec<MetricComparison>,
+}
+
+enum FetchError {
+    Forbidden,
+    Failed(String),
+}
+
+fn load(
+    conn: &Connection,
+    tenant_id: &str,
+    range: &UsageRange,
+) -> Result<Option<(ServerTeamStats, i64)>> {
+    let cached: Option<(String, i64)> = conn
+        .query_row(
+            "SELECT stats_json, fetched_at FROM team_stats_cache
+             WHERE tenant_id = ? AND period_start = ? AND period_end = ?",
+            params![tenant_id, range.start.to_string(), range.end.to_string()],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()?;
+    Ok(cached.and_then(|(json, fetched_at)| {
+        serde_json::from_str(&json)
+            .ok()
+            .map(|stats| (stats, fetched_at))
+    }))
+}
+
+fn store(
+    conn: &Connection,
+    tenant_id: &str,
+    range: &UsageRange,
+    stats: &ServerTeamStats,
+    now: i64,
+) -> Result<()> {
+    let json = serde_json::to_string(stats)
+        .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+    conn.execute(
+        "INSERT OR REPLACE INTO team_stats_cache (
+            tenant_id, period_start, period_end, stats_json, fetched_at
+        ) VALUES (?, ?, ?, ?, ?)",
+        params![
+            tenant_id,
+            range.start.to_string(),
+            range.end.to_string(),
+            json,
+            now
+        ],
+    )?;
+    Ok(())
+}
+
+fn clear(conn: &Connection, tenant_id: Option<&str>) -> Result<usize> {
+    conn.execute(
+        "DELETE FROM team_stats_cache WHERE ?1 IS NULL OR tenant_id = ?1",
+        params![tenant_id],
+    )
+}
+
+/// Drop all cached team stats, e.g. when logging out
+pub fn clear_team_stats_cache() -> std::result::Result<(), String> {
+    with_connection_mut(|conn| clear(conn, None))
+        .map(|_| ())
+        .map_err(|e| format!("Failed to clear team stats: {}", e))
+}
+
+fn median(mut values: Vec<f64>) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_by(f64::total_cmp);
+    let middle = values.len() / 2;
+    Some(if values.len().is_multiple_of(2) {
+        (values[middle - 1] + values[middle]) / 2.0
+    } else {
+        values[middle]
+    })
+}
+
+/// The user's values of each compared metric in `range`, from `tenant_id`'s
+/// sessions only, since they're compared with that tenant's team
+fn my_values(
+    conn: &Connection,
+    tenant_id: &str,
+    range: &UsageRange,
+) -> Result<HashMap<&'static str, Vec<f64>>> {
+    let columns: Vec<&str> = COMPARED_METRICS.iter().map(|(_, column)| *column).collect();
+    let mut stmt = conn.prepare(&format!(
+        "SELECT s.session_start_time, {}
+         FROM session_metrics m
+         JOIN agent_sessions s ON s.session_id = m.session_id
+         WHERE s.session_start_time IS NOT NULL
+           AND s.tenant_id = ?",
+        columns
+            .iter()
+            .map(|column| format!("m.{}", column))
+            .collect::<Vec<_>>()
+            .join(", ")
+    ))?;
+    let mut rows = stmt.query(params![tenant_id])?;
+
+    let mut values: HashMap<&'static str, Vec<f64>> = HashMap::new();
+    while let Some(row) = rows.next()? {
+        let in_range =
+            local_date(row.get(0)?).is_some_and(|day| day >= range.start && day <= range.end);
+        if !in_range {
+            continue;
+        }
+        for (index, (metric, _)) in COMPARED_METRICS.iter().enumerate() {
+            if let Some(value) = row.get::<_, Option<f64>>(index + 1)? {
+                values.entry(metric).or_default().push(value);
+            }
+        }
+    }
+    Ok(values)
+}
+
+fn compare(
+    stats: &ServerTeamStats,
+    mut mine: HashMap<&'static str, Vec<f64>>,
+) -> Vec<MetricComparison> {
+    COMPARED_METRICS
+        .iter()
+        .filter_map(|(metric, _)| {
+            let team = stats.metrics.get(*metric);
+            let values = mine.remove(metric).unwrap_or_default();
+            if team.is_none() && values.is_empty() {
+                return None;
+            }
+            let team = team.cloned().unwrap_or_default();
+            Some(MetricComparison {
+                metric: metric.to_string(),
+                my_session_count: values.len() as i64,
+                my_median: median(values),
+                team_median: team.median,
+                team_p25: team.p25,
+                team_p75: team.p75,
+            })
+        })
+        .collect()
+}
+
+async fn fetch(
+    server_url: &str,
+    api_key: &str,
+    tenant_id: &str,
+    range: &UsageRange,
+) -> std::result::Result<ServerTeamStats, FetchError> {
+    let client = reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .map_err(|e| FetchError::Failed(format!("Failed to create HTTP client: {}", e)))?;
+    let response = client
+        .get(crate::api_paths::api_url(
+            server_url,
+            &format!("tenants/{}/metrics/aggregates", tenant_id),
+        ))
+        .query(&[
+            ("start", range.start.to_string()),
+            ("end", range.end.to_string()),
+        ])
+        .header("Authorization", format!("Bearer {}", api_key))
+        .send()
+        .await
+        .map_err(|e| FetchError::Failed(format!("Failed to fetch team stats: {}", e)))?;
+
+    let status = response.status();
+    if matches!(status, StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN) {
+        return Err(FetchError::Forbidden);
+    }
+    if !status.is_success() {
+        let error_text = response
+            .text()
+            .await
+            .unwrap_or_else(|_| "Unknown error".to_string());
+        return Err(FetchError::Failed(format!(
+            "Team stats request failed with status {}: {}",
+            status, error_text
+        )));
+    }
+    response
+        .json()
+        .await
+        .map_err(|e| FetchError::Failed(format!("Invalid team stats response: {}", e)))
+}
+
+/// The user's metrics next to their team's for `range`, from the cache while
+/// it's fresh unless `refresh` is set
+pub async fn get_team_comparison(
+    range: UsageRange,
+    refresh: bool,
+) -> std::result::Result<TeamComparison, String> {
+    let config = load_config().map_err(|e| format!("Failed to load config: {}", e))?;
+    let (Some(api_key), Some(server_url), Some(tenant_id)) =
+        (config.api_key, config.server_url, config.tenant_id)
+    else {
+        return Err("Log in to a team to compare with it".to_string());
+    };
+
+    let now = Utc::now().timestamp_millis();
+    let cached = with_connection_mut(|conn| load(conn, &tenant_id, &range))
+        .map_err(|e| format!("Failed to read cached team stats: {}", e))?;
+    let fresh = cached
+        .as_ref()
+        .filter(|(_, fetched_at)| now - fetched_at < CACHE_TTL.as_millis() as i64);
+
+    let (stats, fetched_at, stale) = match fresh {
+        Some((stats, fetched_at)) if !refresh => (stats.clone(), *fetched_at, false),
+        _ => match fetch(&server_url, &api_key, &tenant_id, &range).await {
+            Ok(stats) => {
+                with_connection_mut(|conn| store(conn, &tenant_id, &range, &stats, now))
+                    .map_err(|e| format!("Failed to cache team stats: {}", e))?;
+                (stats, now, false)
+            }
+            Err(FetchError::Forbidden) => {
+                with_connection_mut(|conn| clear(conn, Some(&tenant_id)))
+                    .map_err(|e| format!("Failed to clear team stats: {}", e))?;
+                log_info(
+                    "team-stats",
+                    &format!("Not allowed to view team stats of tenant {}", tenant_id),
+                )
+                .unwrap_or_default();
+                return Err("You don't have permission to view this team's stats".to_string());
+            }
+            Err(FetchError::Failed(e)) => match cached {
+                Some((stats, fetched_at)) => (stats, fetched_at, true),
+                None => return Err(e),
+            },
+        },
+    };
+
+    let mine = with_connection_mut(|conn| my_values(conn, &tenant_id, &range))
+        .map_err(|e| format!("Failed to read your metrics: {}", e))?;
+    Ok(TeamComparison {
+        tenant_id,
+        tenant_name: config.tenant_name,
+        member_count: stats.member_count,
+        fetched_at: DateTime::from_timestamp_millis(fetched_at).unwrap_or_default(),
+        stale,
+        metrics: compare(&stats, mine),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn range() -> UsageRange {
+        UsageRange {
+            start: NaiveDate::from_ymd_opt(2025, 3, 1).unwrap(),
+            end: NaiveDate::from_ymd_opt(2025, 3, 31).unwrap(),
+        }
+    }
+
+    fn team_stats() -> ServerTeamStats {
+        serde_json::from_value(serde_json::json!({
+            "memberCount": 4,
+            "metrics": {
+                "responseLatencyMs": {"median": 1200.0, "p25": 800.0, "p75": 2000.0},
+                "unknownMetric": {"median": 1.0}
+            }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_cache_is_per_tenant_and_range() {
+        let conn = crate::database::test_connection();
+
+        store(&conn, "t1", &range(), &team_stats(), 100).unwrap();
+        let (stats, fetched_at) = load(&conn, "t1", &range()).unwrap().unwrap();
+        assert_eq!(fetched_at, 100);
+        assert_eq!(stats.member_count, Some(4));
+        assert!(load(&conn, "t2", &range()).unwrap().is_none());
+
+        store(&conn, "t2", &range(), &team_stats(), 100).unwrap();
+        clear(&conn, Some("t1")).unwrap();
+        assert!(load(&conn, "t1", &range()).unwrap().is_none());
+        assert!(load(&conn, "t2", &range()).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_my_values_are_the_tenants() {
+        let conn = crate::database::test_connection();
+        // Midday, so the day is in range in any time zone
+        let started = NaiveDate::from_ymd_opt(2025, 3, 10)
+            .unwrap()
+            .and_hms_opt(12, 0, 0)
+            .unwrap()
+            .and_utc()
+            .timestamp_millis();
+        for (session_id, tenant_id, errors) in [("s1", "t1", 2), ("s2", "t2", 9)] {
+            conn.execute(
+                "INSERT INTO agent_sessions (
+                    id, provider, project_name, session_id, file_name, file_path, file_size,
+                    created_at, uploaded_at, tenant_id, session_start_time
+                ) VALUES (?1, 'claude-code', 'app', ?1, 'f.jsonl', '/f', 10, 0, 0, ?2, ?3)",
+                params![session_id, tenant_id, started],
+            )
+            .unwrap();
+            conn.execute(
+                "INSERT INTO session_metrics (id, session_id, provider, timestamp, error_count, created_at)
+                 VALUES (?1, ?1, 'claude-code', 0, ?2, 0)",
+                params![session_id, errors],
+            )
+            .unwrap();
+        }
+
+        let values = my_values(&conn, "t1", &range()).unwrap();
+        assert_eq!(values["errorCount"], vec![2.0]);
+    }
+
+    #[test]
+    fn test_compare_with_team() {
+        let mine = HashMap::from([
+            ("responseLatencyMs", vec![1000.0, 3000.0, 1500.0, 900.0]),
+            ("errorCount", vec![2.0]),
+        ]);
+        let metrics = compare(&team_stats(), mine);
+
+        assert_eq!(
+            metrics,
+            [
+                MetricComparison {
+                    metric: "responseLatencyMs".to_string(),
+                    my_median: Some(1250.0),
+                    my_session_count: 4,
+                    team_median: Some(1200.0),
+                    team_p25: Some(800.0),
+                    team_p75: Some(2000.0),
+                },
+                MetricComparison {
+                    metric: "errorCount".to_string(),
+                    my_median: Some(2.0),
+                    my_session_count: 1,
+                    team_median: None,
+                    team_p25: None,
+                    team_p75: None,
+                },
+            ]
+        );
+    }
+}
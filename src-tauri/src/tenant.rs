@@ -0,0 +1,140 @@
+//! Keeping each tenant's local state apart
+//!
+//! Switching accounts mustn't send one organization the sessions recorded
+//! for another, so local state is namespaced by the tenant that was logged in
+//! when it was created:
+//! - each session records its tenant in `agent_sessions.tenant_id`, and only
+//!   the active tenant's sessions are queued, retried, patched or counted as
+//!   already uploaded. Sessions recorded while logged out, or before tenants
+//!   were tracked, belong to the next tenant logged in to.
+//! - uploaded hashes record the tenant they were uploaded for in
+//!   `uploaded_hashes.tenant_id`, and only count as uploaded for that tenant.
+//! - provider configs live in `providers/<tenant_id>/`; a tenant without its
+//!   own config for a provider starts from the shared one.
+//!
+//! The active tenant is read on most database writes, so it's cached until
+//! config.json is saved or changes on disk.
+
+use crate::config::{config_file_stamp, load_config};
+use crate::database::with_connection_mut;
+use crate::logging::{log_info, log_warn};
+use rusqlite::{params, Connection, OptionalExtension, Result};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+/// The active tenant, and the size and modification time of the config
+/// file it was read from
+type CachedTenant = (Option<(u64, SystemTime)>, Option<String>);
+
+static ACTIVE_TENANT: Mutex<Option<CachedTenant>> = Mutex::new(None);
+
+/// The tenant logged in to, if any
+pub fn active_tenant_id() -> Option<String> {
+    let stamp = config_file_stamp();
+    let Ok(mut cached) = ACTIVE_TENANT.lock() else {
+        return load_config().ok().and_then(|config| config.tenant_id);
+    };
+    match cached.as_ref() {
+        Some((cached_stamp, tenant_id)) if stamp.is_some() && *cached_stamp == stamp => {
+            tenant_id.clone()
+        }
+        _ => {
+            let tenant_id = load_config().ok().and_then(|config| config.tenant_id);
+            *cached = Some((stamp, tenant_id.clone()));
+            tenant_id
+        }
+    }
+}
+
+/// Read the active tenant from config.json again on next use
+pub fn forget_active_tenant() {
+    if let Ok(mut cached) = ACTIVE_TENANT.lock() {
+        *cached = None;
+    }
+}
+
+/// The active tenant's directory name, if it's safe to use as one
+pub fn active_tenant_dir() -> Option<String> {
+    active_tenant_id().filter(|tenant_id| {
+        !tenant_id.is_empty()
+            && tenant_id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    })
+}
+
+fn claim(conn: &Connection, tenant_id: &str) -> Result<usize> {
+    conn.execute(
+        "UPDATE uploaded_hashes SET tenant_id = ? WHERE tenant_id IS NULL",
+        params![tenant_id],
+    )?;
+    conn.execute(
+        "UPDATE agent_sessions SET tenant_id = ? WHERE tenant_id IS NULL",
+        params![tenant_id],
+    )
+}
+
+fn owner(conn: &Connection, session_id: &str) -> Result<Option<Option<String>>> {
+    conn.query_row(
+        "SELECT tenant_id FROM agent_sessions WHERE session_id = ?",
+        params![session_id],
+        |row| row.get(0),
+    )
+    .optional()
+}
+
+/// Give `tenant_id` the sessions that don't belong to a tenant yet
+pub fn claim_unowned_sessions(tenant_id: &str) {
+    match with_connection_mut(|conn| claim(conn, tenant_id)) {
+        Ok(0) => {}
+        Ok(claimed) => log_info(
+            "tenant",
+            &format!("✓ {} sessions now belong to tenant {}", claimed, tenant_id),
+        )
+        .unwrap_or_default(),
+        Err(e) => log_warn(
+            "tenant",
+            &format!("⚠ Failed to assign sessions to tenant {}: {}", tenant_id, e),
+        )
+        .unwrap_or_default(),
+    }
+}
+
+/// Whether a session may be uploaded for the active tenant: it's new, or it
+/// belongs to that tenant or to none yet
+pub fn belongs_to_active_tenant(session_id: &str) -> bool {
+    let active = active_tenant_id();
+    match with_connection_mut(|conn| owner(conn, session_id)) {
+        Ok(Some(Some(tenant_id))) => active.as_deref() == Some(tenant_id.as_str()),
+        Ok(_) => true,
+        // Unknown ownership is never uploaded
+        Err(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unowned_sessions_are_claimed_once() {
+        let conn = crate::database::test_connection();
+        for session_id in ["s1", "s2"] {
+            conn.execute(
+                "INSERT INTO agent_sessions (
+                    id, provider, project_name, session_id, file_name, file_path, file_size,
+                    created_at, uploaded_at
+                ) VALUES (?1, 'claude-code', 'app', ?1, 'f.jsonl', '/f', 10, 0, 0)",
+                params![session_id],
+            )
+            .unwrap();
+        }
+
+        assert_eq!(owner(&conn, "s1").unwrap(), Some(None));
+        assert_eq!(claim(&conn, "acme").unwrap(), 2);
+        // Another tenant logging in later doesn't take them
+        assert_eq!(claim(&conn, "globex").unwrap(), 0);
+        assert_eq!(owner(&conn, "s2").unwrap(), Some(Some("acme".to_string())));
+        assert_eq!(owner(&conn, "missing").unwrap(), None);
+    }
+}
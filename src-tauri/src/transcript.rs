@@ -0,0 +1,487 @@
+//! Render canonical sessions as readable transcripts
+//!
+//! Produces Markdown or a standalone HTML page with the user and assistant
+//! turns in order. Tool calls and their results are collapsible sections, and
+//! thinking is either left out or, in HTML, hidden behind a toggle.
+
+use crate::database::get_full_session_by_id;
+use crate::providers::canonical::{CanonicalMessage, ContentBlock, ContentValue, MessageType};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::fs;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TranscriptFormat {
+    Markdown,
+    Html,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TranscriptOptions {
+    pub include_thinking: bool,
+}
+
+/// One piece of a transcript, independent of the output format
+#[derive(Debug, PartialEq)]
+enum Part<'a> {
+    /// Start of a user or assistant turn
+    Turn {
+        role: &'a str,
+        timestamp: &'a str,
+        model: Option<&'a str>,
+    },
+    Text(&'a str),
+    Thinking(&'a str),
+    ToolCall {
+        name: &'a str,
+        input: String,
+    },
+    ToolResult {
+        name: &'a str,
+        content: &'a str,
+        is_error: bool,
+    },
+    /// Image or attachment placeholder
+    File(String),
+}
+
+/// Flatten messages into transcript parts. Messages that only carry tool
+/// results continue the current turn instead of starting a user turn.
+fn transcript_parts<'a>(
+    messages: &'a [CanonicalMessage],
+    options: TranscriptOptions,
+) -> Vec<Part<'a>> {
+    let mut parts = Vec::new();
+    let mut tool_names: HashMap<&str, &str> = HashMap::new();
+    let mut current_role: Option<&str> = None;
+
+    for message in messages {
+        if message.message_type == MessageType::Meta || message.is_meta == Some(true) {
+            continue;
+        }
+
+        let blocks: Vec<&ContentBlock> = match &message.message.content {
+            ContentValue::Text(text) if text.trim().is_empty() => continue,
+            ContentValue::Text(_) => Vec::new(),
+            ContentValue::Structured(blocks) => blocks.iter().collect(),
+        };
+        let only_tool_results = !blocks.is_empty()
+            && blocks
+                .iter()
+                .all(|block| matches!(block, ContentBlock::ToolResult { .. }));
+
+        let role = message.message.role.as_str();
+        if current_role != Some(role) && !only_tool_results {
+            parts.push(Part::Turn {
+                role,
+                timestamp: &message.timestamp,
+                model: message.message.model.as_deref(),
+            });
+            current_role = Some(role);
+        }
+
+        if let ContentValue::Text(text) = &message.message.content {
+            parts.push(Part::Text(text));
+        }
+        for block in blocks {
+            match block {
+                ContentBlock::Text { text } if !text.trim().is_empty() => {
+                    parts.push(Part::Text(text))
+                }
+                ContentBlock::Text { .. } => {}
+                ContentBlock::Thinking { thinking } => {
+                    if options.include_thinking && !thinking.trim().is_empty() {
+                        parts.push(Part::Thinking(thinking));
+                    }
+                }
+                ContentBlock::ToolUse { id, name, input } => {
+                    tool_names.insert(id, name);
+                    parts.push(Part::ToolCall {
+                        name,
+                        input: serde_json::to_string_pretty(input).unwrap_or_default(),
+                    });
+                }
+                ContentBlock::ToolResult {
+                    tool_use_id,
+                    content,
+                    is_error,
+                } => parts.push(Part::ToolResult {
+                    name: tool_names
+                        .get(tool_use_id.as_str())
+                        .copied()
+                        .unwrap_or("tool"),
+                    content,
+                    is_error: is_error.unwrap_or(false),
+                }),
+                ContentBlock::Image {
+                    media_type, path, ..
+                } => parts.push(Part::File(match path {
+                    Some(path) => format!("Image ({}): {}", media_type, path),
+                    None => format!("Image ({})", media_type),
+                })),
+                ContentBlock::Attachment { name, path, .. } => {
+                    let name = name.as_deref().or(path.as_deref()).unwrap_or("unnamed");
+                    parts.push(Part::File(format!("Attachment: {}", name)));
+                }
+            }
+        }
+    }
+
+    parts
+}
+
+fn role_label(role: &str) -> &str {
+    match role {
+        "user" => "User",
+        "assistant" => "Assistant",
+        other => other,
+    }
+}
+
+/// A backtick fence longer than any backtick run in `content`
+fn code_fence(content: &str) -> String {
+    let longest_run = content.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    "`".repeat(longest_run.max(2) + 1)
+}
+
+fn render_markdown(messages: &[CanonicalMessage], parts: &[Part]) -> String {
+    let mut out = String::new();
+    let first = &messages[0];
+    let _ = writeln!(out, "# Session {}\n", first.session_id);
+    let _ = writeln!(out, "- Provider: {}", first.provider);
+    if let Some(cwd) = messages.iter().find_map(|m| m.cwd.as_deref()) {
+        let _ = writeln!(out, "- Project: {}", cwd);
+    }
+    let _ = writeln!(out, "- Started: {}", first.timestamp);
+
+    for part in parts {
+        match part {
+            Part::Turn {
+                role,
+                timestamp,
+                model,
+            } => {
+                let _ = write!(out, "\n## {} · {}", role_label(role), timestamp);
+                if let Some(model) = model {
+                    let _ = write!(out, " · {}", model);
+                }
+                out.push_str("\n\n");
+            }
+            Part::Text(text) => {
+                let _ = writeln!(out, "{}\n", text.trim_end());
+            }
+            Part::Thinking(thinking) => {
+                let _ = writeln!(out, "<details>\n<summary>Thinking</summary>\n");
+                for line in thinking.trim_end().lines() {
+                    let _ = writeln!(out, "> {}", line);
+                }
+                out.push_str("\n</details>\n\n");
+            }
+            Part::ToolCall { name, input } => {
+                let fence = code_fence(input);
+                let _ = writeln!(
+                    out,
+                    "<details>\n<summary>Tool call: {}</summary>\n\n{}json\n{}\n{}\n\n</details>\n",
+                    name, fence, input, fence
+                );
+            }
+            Part::ToolResult {
+                name,
+                content,
+                is_error,
+            } => {
+                let fence = code_fence(content);
+                let label = if *is_error {
+                    "Tool error"
+                } else {
+                    "Tool result"
+                };
+                let _ = writeln!(
+                    out,
+                    "<details>\n<summary>{}: {}</summary>\n\n{}\n{}\n{}\n\n</details>\n",
+                    label,
+                    name,
+                    fence,
+                    content.trim_end(),
+                    fence
+                );
+            }
+            Part::File(description) => {
+                let _ = writeln!(out, "_{}_\n", description);
+            }
+        }
+    }
+
+    out
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+const HTML_STYLE: &str = "
+body { font-family: -apple-system, BlinkMacSystemFont, 'Segoe UI', sans-serif; max-width: 860px; margin: 2rem auto; padding: 0 1rem; color: #1f2328; }
+header { border-bottom: 1px solid #d0d7de; margin-bottom: 1rem; }
+.turn { margin: 1.5rem 0 0.5rem; padding-top: 0.5rem; border-top: 1px solid #eaeef2; font-size: 0.9rem; color: #59636e; }
+.turn strong { color: #1f2328; }
+.text { white-space: pre-wrap; }
+details { margin: 0.5rem 0; border: 1px solid #d0d7de; border-radius: 6px; padding: 0.25rem 0.75rem; }
+summary { cursor: pointer; font-size: 0.9rem; }
+pre { overflow-x: auto; background: #f6f8fa; padding: 0.5rem; border-radius: 4px; }
+.error summary { color: #cf222e; }
+.thinking { color: #59636e; font-style: italic; }
+.file { color: #59636e; font-style: italic; }
+#show-thinking:not(:checked) ~ main .thinking { display: none; }
+";
+
+fn render_html(messages: &[CanonicalMessage], parts: &[Part]) -> String {
+    let mut out = String::new();
+    let first = &messages[0];
+    let title = escape_html(&format!("Session {}", first.session_id));
+    let _ = write!(
+        out,
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>{}</style>\n</head>\n<body>\n",
+        title, HTML_STYLE
+    );
+
+    let _ = write!(
+        out,
+        "<header>\n<h1>{}</h1>\n<p>{}",
+        title,
+        escape_html(&first.provider)
+    );
+    if let Some(cwd) = messages.iter().find_map(|m| m.cwd.as_deref()) {
+        let _ = write!(out, " · {}", escape_html(cwd));
+    }
+    let _ = writeln!(out, " · {}</p>\n</header>", escape_html(&first.timestamp));
+
+    if parts.iter().any(|part| matches!(part, Part::Thinking(_))) {
+        out.push_str(
+            "<input type=\"checkbox\" id=\"show-thinking\" checked>\n<label for=\"show-thinking\">Show thinking</label>\n",
+        );
+    }
+
+    out.push_str("<main>\n");
+    for part in parts {
+        match part {
+            Part::Turn {
+                role,
+                timestamp,
+                model,
+            } => {
+                let _ = write!(
+                    out,
+                    "<div class=\"turn\"><strong>{}</strong> · {}",
+                    escape_html(role_label(role)),
+                    escape_html(timestamp)
+                );
+                if let Some(model) = model {
+                    let _ = write!(out, " · {}", escape_html(model));
+                }
+                out.push_str("</div>\n");
+            }
+            Part::Text(text) => {
+                let _ = writeln!(
+                    out,
+                    "<div class=\"text\">{}</div>",
+                    escape_html(text.trim_end())
+                );
+            }
+            Part::Thinking(thinking) => {
+                let _ = writeln!(
+                    out,
+                    "<details class=\"thinking\"><summary>Thinking</summary><div class=\"text\">{}</div></details>",
+                    escape_html(thinking.trim_end())
+                );
+            }
+            Part::ToolCall { name, input } => {
+                let _ = writeln!(
+                    out,
+                    "<details class=\"tool\"><summary>Tool call: {}</summary><pre>{}</pre></details>",
+                    escape_html(name),
+                    escape_html(input)
+                );
+            }
+            Part::ToolResult {
+                name,
+                content,
+                is_error,
+            } => {
+                let (class, label) = if *is_error {
+                    ("tool error", "Tool error")
+                } else {
+                    ("tool", "Tool result")
+                };
+                let _ = writeln!(
+                    out,
+                    "<details class=\"{}\"><summary>{}: {}</summary><pre>{}</pre></details>",
+                    class,
+                    label,
+                    escape_html(name),
+                    escape_html(content.trim_end())
+                );
+            }
+            Part::File(description) => {
+                let _ = writeln!(
+                    out,
+                    "<div class=\"file\">{}</div>",
+                    escape_html(description)
+                );
+            }
+        }
+    }
+    out.push_str("</main>\n</body>\n</html>\n");
+
+    out
+}
+
+/// Render a session's canonical messages as a transcript
+pub fn render_transcript(
+    messages: &[CanonicalMessage],
+    format: TranscriptFormat,
+    options: TranscriptOptions,
+) -> String {
+    if messages.is_empty() {
+        return String::new();
+    }
+
+    let parts = transcript_parts(messages, options);
+    match format {
+        TranscriptFormat::Markdown => render_markdown(messages, &parts),
+        TranscriptFormat::Html => render_html(messages, &parts),
+    }
+}
+
+/// Render a stored session as a transcript and write it to `path`
+pub fn export_session_transcript(
+    session_id: &str,
+    format: TranscriptFormat,
+    path: &str,
+    options: TranscriptOptions,
+) -> Result<(), String> {
+    let session = get_full_session_by_id(session_id)
+        .map_err(|e| format!("Failed to get session: {}", e))?
+        .ok_or_else(|| format!("Session {} not found", session_id))?;
+
+    let content = fs::read_to_string(&session.file_path)
+        .map_err(|e| format!("Failed to read session file: {}", e))?;
+    // Lines that don't parse are left out rather than failing the export
+    let messages: Vec<CanonicalMessage> = content
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect();
+    if messages.is_empty() {
+        return Err(format!("Session {} has no messages", session_id));
+    }
+
+    fs::write(path, render_transcript(&messages, format, options))
+        .map_err(|e| format!("Failed to write transcript: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn messages() -> Vec<CanonicalMessage> {
+        [
+            json!({
+                "uuid": "u1", "timestamp": "2025-01-01T10:00:00Z", "type": "user",
+                "sessionId": "s1", "provider": "claude-code", "cwd": "/work/app",
+                "message": {"role": "user", "content": "Fix <the> bug"}
+            }),
+            json!({
+                "uuid": "u2", "timestamp": "2025-01-01T10:00:05Z", "type": "assistant",
+                "sessionId": "s1", "provider": "claude-code",
+                "message": {"role": "assistant", "model": "claude-sonnet-4", "content": [
+                    {"type": "thinking", "thinking": "Look at main.rs first"},
+                    {"type": "text", "text": "Reading the file."},
+                    {"type": "tool_use", "id": "t1", "name": "Read", "input": {"file_path": "main.rs"}}
+                ]}
+            }),
+            json!({
+                "uuid": "u3", "timestamp": "2025-01-01T10:00:06Z", "type": "user",
+                "sessionId": "s1", "provider": "claude-code",
+                "message": {"role": "user", "content": [
+                    {"type": "tool_result", "tool_use_id": "t1", "content": "fn main() {}\n```"}
+                ]}
+            }),
+            json!({
+                "uuid": "u4", "timestamp": "2025-01-01T10:00:07Z", "type": "assistant",
+                "sessionId": "s1", "provider": "claude-code",
+                "message": {"role": "assistant", "content": "Fixed."}
+            }),
+        ]
+        .into_iter()
+        .map(|value| serde_json::from_value(value).unwrap())
+        .collect()
+    }
+
+    #[test]
+    fn test_tool_results_continue_the_turn() {
+        let messages = messages();
+        let parts = transcript_parts(&messages, TranscriptOptions::default());
+
+        let turns: Vec<&str> = parts
+            .iter()
+            .filter_map(|part| match part {
+                Part::Turn { role, .. } => Some(*role),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(turns, ["user", "assistant"]);
+        assert!(parts.contains(&Part::ToolResult {
+            name: "Read",
+            content: "fn main() {}\n```",
+            is_error: false,
+        }));
+        assert!(!parts.iter().any(|part| matches!(part, Part::Thinking(_))));
+    }
+
+    #[test]
+    fn test_render_markdown() {
+        let markdown = render_transcript(
+            &messages(),
+            TranscriptFormat::Markdown,
+            TranscriptOptions {
+                include_thinking: true,
+            },
+        );
+
+        assert!(markdown.starts_with("# Session s1\n"));
+        assert!(markdown.contains("## Assistant · 2025-01-01T10:00:05Z · claude-sonnet-4"));
+        assert!(markdown.contains("> Look at main.rs first"));
+        assert!(markdown.contains("<summary>Tool call: Read</summary>"));
+        // Results containing a fence get a longer one
+        assert!(markdown.contains("````\nfn main() {}\n```\n````"));
+    }
+
+    #[test]
+    fn test_render_html_escapes_content() {
+        let html = render_transcript(
+            &messages(),
+            TranscriptFormat::Html,
+            TranscriptOptions {
+                include_thinking: true,
+            },
+        );
+
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("Fix &lt;the&gt; bug"));
+        assert!(html.contains("id=\"show-thinking\""));
+        assert!(html.contains("<summary>Tool result: Read</summary>"));
+    }
+}
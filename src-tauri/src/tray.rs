@@ -0,0 +1,34 @@
+//! Tray icon
+//!
+//! Keeps the app reachable while its window is hidden, such as after a
+//! minimized launch at login. Quitting from the tray goes through the normal
+//! exit path, so in-flight uploads are drained.
+
+use crate::windows::focus_main_window;
+use tauri::menu::{Menu, MenuItem};
+use tauri::tray::TrayIconBuilder;
+use tauri::App;
+
+const OPEN_ID: &str = "open";
+const QUIT_ID: &str = "quit";
+
+pub fn create(app: &App) -> tauri::Result<()> {
+    let open = MenuItem::with_id(app, OPEN_ID, "Open GuideMode", true, None::<&str>)?;
+    let quit = MenuItem::with_id(app, QUIT_ID, "Quit GuideMode", true, None::<&str>)?;
+    let menu = Menu::with_items(app, &[&open, &quit])?;
+
+    let mut tray = TrayIconBuilder::with_id("main")
+        .tooltip("GuideMode")
+        .menu(&menu)
+        .on_menu_event(|app, event| match event.id().as_ref() {
+            OPEN_ID => focus_main_window(app),
+            QUIT_ID => app.exit(0),
+            _ => {}
+        });
+    if let Some(icon) = app.default_window_icon() {
+        tray = tray.icon(icon.clone());
+    }
+    tray.build(app)?;
+
+    Ok(())
+}
@@ -0,0 +1,151 @@
+//! Adaptive upload concurrency.
+//!
+//! Uploads run up to a configured maximum at once (`maxConcurrentUploads`).
+//! Timeouts, server errors and rate limits halve the effective concurrency,
+//! and every `SUCCESSES_PER_STEP` successful uploads in a row raise it by one,
+//! back up to the maximum.
+
+use super::types::{DEFAULT_CONCURRENT_UPLOADS, MAX_CONCURRENT_UPLOADS};
+use crate::logging::log_info;
+use std::sync::Mutex;
+
+/// Successful uploads in a row before concurrency goes up by one
+const SUCCESSES_PER_STEP: u32 = 5;
+
+#[derive(Debug)]
+struct State {
+    max: usize,
+    current: usize,
+    successes: u32,
+}
+
+#[derive(Debug)]
+pub struct AdaptiveConcurrency {
+    state: Mutex<State>,
+}
+
+impl Default for AdaptiveConcurrency {
+    fn default() -> Self {
+        Self::new(DEFAULT_CONCURRENT_UPLOADS)
+    }
+}
+
+impl AdaptiveConcurrency {
+    pub fn new(max: usize) -> Self {
+        let max = max.clamp(1, MAX_CONCURRENT_UPLOADS);
+        Self {
+            state: Mutex::new(State {
+                max,
+                current: max,
+                successes: 0,
+            }),
+        }
+    }
+
+    /// Change the configured maximum, starting again from it
+    pub fn set_max(&self, max: usize) {
+        let max = max.clamp(1, MAX_CONCURRENT_UPLOADS);
+        if let Ok(mut state) = self.state.lock() {
+            if state.max != max {
+                *state = State {
+                    max,
+                    current: max,
+                    successes: 0,
+                };
+            }
+        }
+    }
+
+    /// Uploads that may run at once right now
+    pub fn current(&self) -> usize {
+        self.state.lock().map(|state| state.current).unwrap_or(1)
+    }
+
+    /// The configured maximum
+    pub fn max(&self) -> usize {
+        self.state.lock().map(|state| state.max).unwrap_or(1)
+    }
+
+    pub fn record_success(&self) {
+        let Ok(mut state) = self.state.lock() else {
+            return;
+        };
+        if state.current >= state.max {
+            return;
+        }
+        state.successes += 1;
+        if state.successes >= SUCCESSES_PER_STEP {
+            state.successes = 0;
+            state.current += 1;
+            log_info(
+                "upload-queue",
+                &format!(
+                    "📈 Uploads healthy, concurrency raised to {}/{}",
+                    state.current, state.max
+                ),
+            )
+            .unwrap_or_default();
+        }
+    }
+
+    /// Back off after a timeout, server error or rate limit
+    pub fn record_overload(&self) {
+        let Ok(mut state) = self.state.lock() else {
+            return;
+        };
+        state.successes = 0;
+        let reduced = (state.current / 2).max(1);
+        if reduced < state.current {
+            state.current = reduced;
+            log_info(
+                "upload-queue",
+                &format!(
+                    "📉 Server struggling, concurrency lowered to {}/{}",
+                    state.current, state.max
+                ),
+            )
+            .unwrap_or_default();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backs_off_and_recovers() {
+        let concurrency = AdaptiveConcurrency::new(4);
+        assert_eq!(concurrency.current(), 4);
+
+        concurrency.record_overload();
+        assert_eq!(concurrency.current(), 2);
+        concurrency.record_overload();
+        concurrency.record_overload();
+        assert_eq!(concurrency.current(), 1);
+
+        for _ in 0..SUCCESSES_PER_STEP - 1 {
+            concurrency.record_success();
+        }
+        assert_eq!(concurrency.current(), 1);
+        concurrency.record_success();
+        assert_eq!(concurrency.current(), 2);
+
+        // Never past the maximum
+        for _ in 0..SUCCESSES_PER_STEP * 10 {
+            concurrency.record_success();
+        }
+        assert_eq!(concurrency.current(), 4);
+    }
+
+    #[test]
+    fn test_max_is_clamped() {
+        assert_eq!(AdaptiveConcurrency::new(0).max(), 1);
+        assert_eq!(AdaptiveConcurrency::new(1000).max(), MAX_CONCURRENT_UPLOADS);
+
+        let concurrency = AdaptiveConcurrency::default();
+        concurrency.record_overload();
+        concurrency.set_max(5);
+        assert_eq!(concurrency.current(), 5);
+    }
+}
@@ -0,0 +1,120 @@
+//! Size cap for embedded binary data in uploaded sessions.
+//!
+//! Converters reduce images and attachments to hashed references, but raw data
+//! can still reach a canonical file through pass-through fields such as
+//! `toolUseResult` (e.g. a screenshot returned by a Read tool as base64).
+//! Before upload, any oversized string value that looks like base64 or a data
+//! URL is replaced with a placeholder recording its size and hash. Ordinary
+//! text is never truncated.
+
+use crate::upload_queue::hashing::calculate_content_hash_sha256;
+use serde_json::Value;
+use std::borrow::Cow;
+
+/// Largest embedded data value (in bytes) kept verbatim in an upload
+pub const MAX_EMBEDDED_DATA_BYTES: usize = 64 * 1024;
+
+/// Replace oversized embedded data in JSONL content
+///
+/// Returns the content unchanged (borrowed) when nothing needed capping.
+/// Lines that are not valid JSON are passed through as-is.
+pub fn cap_embedded_data(content: &str) -> Cow<'_, str> {
+    let mut capped_any = false;
+    let mut lines = Vec::new();
+
+    for line in content.split('\n') {
+        // A line shorter than the cap cannot contain an oversized value
+        if line.len() > MAX_EMBEDDED_DATA_BYTES {
+            if let Ok(mut value) = serde_json::from_str::<Value>(line) {
+                if cap_value(&mut value) {
+                    capped_any = true;
+                    lines.push(Cow::Owned(value.to_string()));
+                    continue;
+                }
+            }
+        }
+        lines.push(Cow::Borrowed(line));
+    }
+
+    if capped_any {
+        Cow::Owned(lines.join("\n"))
+    } else {
+        Cow::Borrowed(content)
+    }
+}
+
+/// Cap embedded data in a JSON value, returning true if anything was replaced
+fn cap_value(value: &mut Value) -> bool {
+    match value {
+        Value::String(s) if s.len() > MAX_EMBEDDED_DATA_BYTES && is_embedded_data(s) => {
+            *s = format!(
+                "[embedded data omitted: {} bytes, sha256:{}]",
+                s.len(),
+                calculate_content_hash_sha256(s)
+            );
+            true
+        }
+        Value::Array(items) => {
+            let mut capped = false;
+            for item in items {
+                capped |= cap_value(item);
+            }
+            capped
+        }
+        Value::Object(map) => {
+            let mut capped = false;
+            for item in map.values_mut() {
+                capped |= cap_value(item);
+            }
+            capped
+        }
+        _ => false,
+    }
+}
+
+/// Data URLs and strings made up entirely of base64 characters
+fn is_embedded_data(s: &str) -> bool {
+    s.starts_with("data:")
+        || s.bytes()
+            .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'+' | b'/' | b'=' | b'\n' | b'\r'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_small_content_is_borrowed() {
+        let content = "{\"a\":1}\n{\"b\":2}";
+        assert!(matches!(cap_embedded_data(content), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn test_large_base64_is_replaced() {
+        let data = "A".repeat(MAX_EMBEDDED_DATA_BYTES + 1);
+        let line = json!({
+            "uuid": "u1",
+            "toolUseResult": {"type": "image", "file": {"base64": data}}
+        })
+        .to_string();
+        let content = format!("{{\"uuid\":\"u0\"}}\n{}", line);
+
+        let capped = cap_embedded_data(&content);
+        assert!(capped.len() < MAX_EMBEDDED_DATA_BYTES);
+
+        let lines: Vec<&str> = capped.split('\n').collect();
+        assert_eq!(lines[0], "{\"uuid\":\"u0\"}");
+        let value: Value = serde_json::from_str(lines[1]).unwrap();
+        let placeholder = value["toolUseResult"]["file"]["base64"].as_str().unwrap();
+        assert!(placeholder.starts_with("[embedded data omitted:"));
+    }
+
+    #[test]
+    fn test_large_text_is_kept() {
+        let text = "log line with spaces\n".repeat(MAX_EMBEDDED_DATA_BYTES / 10);
+        let content = json!({"uuid": "u1", "message": {"content": text}}).to_string();
+
+        assert!(matches!(cap_embedded_data(&content), Cow::Borrowed(_)));
+    }
+}
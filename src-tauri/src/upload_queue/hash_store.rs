@@ -0,0 +1,222 @@
+//! Persistent record of uploaded content hashes.
+//!
+//! Backs the in-memory `uploaded_hashes` cache so deduplication survives
+//! restarts. Each session keeps only the hash of its latest upload: a newer
+//! upload supersedes the earlier ones, which are pruned as it is recorded.
+//!
+//! Transcript uploads also record their upload base, the hash and length of
+//! the exact bytes the server received, which delta uploads build on.
+
+use crate::database::with_connection_mut;
+use crate::logging::log_warn;
+use chrono::Utc;
+use rusqlite::{params, Connection, OptionalExtension};
+
+/// Whether content with this hash has been uploaded for the active tenant
+pub fn is_uploaded(hash: &str) -> bool {
+    let tenant_id = crate::tenant::active_tenant_id();
+    with_connection_mut(|conn| contains(conn, hash, tenant_id.as_deref())).unwrap_or(false)
+}
+
+/// Record a successful upload for the active tenant, pruning the session's
+/// superseded hashes
+pub fn record_upload(provider: &str, session_id: &str, hash: &str) {
+    let tenant_id = crate::tenant::active_tenant_id();
+    if let Err(e) =
+        with_connection_mut(|conn| record(conn, provider, session_id, hash, tenant_id.as_deref()))
+    {
+        log_warn(
+            "upload-queue",
+            &format!("⚠ Failed to record uploaded hash for {}: {}", session_id, e),
+        )
+        .unwrap_or_default();
+    }
+}
+
+/// The transcript the server last received for a session
+#[derive(Debug, Clone, PartialEq)]
+pub struct UploadBase {
+    pub content_hash: String,
+    pub content_length: u64,
+}
+
+/// The session's upload base, if its transcript was uploaded before
+pub fn upload_base(provider: &str, session_id: &str) -> Option<UploadBase> {
+    with_connection_mut(|conn| base(conn, provider, session_id))
+        .ok()
+        .flatten()
+}
+
+/// Record the bytes the server now holds for a session
+pub fn record_upload_base(provider: &str, session_id: &str, upload_base: &UploadBase) {
+    if let Err(e) = with_connection_mut(|conn| record_base(conn, provider, session_id, upload_base))
+    {
+        log_warn(
+            "upload-queue",
+            &format!("⚠ Failed to record upload base for {}: {}", session_id, e),
+        )
+        .unwrap_or_default();
+    }
+}
+
+/// Forget a provider's uploads so its sessions can be synced again
+pub fn clear_provider(provider: &str) {
+    if let Err(e) = with_connection_mut(|conn| {
+        conn.execute(
+            "DELETE FROM uploaded_hashes WHERE provider = ?",
+            params![provider],
+        )?;
+        conn.execute(
+            "DELETE FROM upload_bases WHERE provider = ?",
+            params![provider],
+        )
+    }) {
+        log_warn(
+            "upload-queue",
+            &format!("⚠ Failed to clear uploaded hashes for {}: {}", provider, e),
+        )
+        .unwrap_or_default();
+    }
+}
+
+fn contains(conn: &Connection, hash: &str, tenant_id: Option<&str>) -> rusqlite::Result<bool> {
+    // Uploads belong to the tenant they were made for
+    Ok(conn
+        .query_row(
+            "SELECT 1 FROM uploaded_hashes WHERE hash = ? AND tenant_id IS ? LIMIT 1",
+            params![hash, tenant_id],
+            |_| Ok(()),
+        )
+        .optional()?
+        .is_some())
+}
+
+fn record(
+    conn: &mut Connection,
+    provider: &str,
+    session_id: &str,
+    hash: &str,
+    tenant_id: Option<&str>,
+) -> rusqlite::Result<()> {
+    let tx = conn.transaction()?;
+    tx.execute(
+        "INSERT OR REPLACE INTO uploaded_hashes (provider, session_id, hash, uploaded_at, tenant_id)
+         VALUES (?, ?, ?, ?, ?)",
+        params![
+            provider,
+            session_id,
+            hash,
+            Utc::now().timestamp_millis(),
+            tenant_id
+        ],
+    )?;
+    tx.execute(
+        "DELETE FROM uploaded_hashes WHERE provider = ? AND session_id = ? AND hash != ?",
+        params![provider, session_id, hash],
+    )?;
+    tx.commit()
+}
+
+fn base(
+    conn: &Connection,
+    provider: &str,
+    session_id: &str,
+) -> rusqlite::Result<Option<UploadBase>> {
+    conn.query_row(
+        "SELECT content_hash, content_length FROM upload_bases
+         WHERE provider = ? AND session_id = ?",
+        params![provider, session_id],
+        |row| {
+            Ok(UploadBase {
+                content_hash: row.get(0)?,
+                content_length: row.get::<_, i64>(1)? as u64,
+            })
+        },
+    )
+    .optional()
+}
+
+fn record_base(
+    conn: &Connection,
+    provider: &str,
+    session_id: &str,
+    upload_base: &UploadBase,
+) -> rusqlite::Result<usize> {
+    conn.execute(
+        "INSERT OR REPLACE INTO upload_bases
+             (provider, session_id, content_hash, content_length, uploaded_at)
+         VALUES (?, ?, ?, ?, ?)",
+        params![
+            provider,
+            session_id,
+            upload_base.content_hash,
+            upload_base.content_length as i64,
+            Utc::now().timestamp_millis()
+        ],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_prunes_superseded_hashes() {
+        let mut conn = crate::database::test_connection();
+
+        record(&mut conn, "claude-code", "s1", "aaa", None).unwrap();
+        record(&mut conn, "claude-code", "s2", "bbb", None).unwrap();
+        assert!(contains(&conn, "aaa", None).unwrap());
+
+        // A newer upload of s1 supersedes its first one
+        record(&mut conn, "claude-code", "s1", "ccc", None).unwrap();
+        assert!(!contains(&conn, "aaa", None).unwrap());
+        assert!(contains(&conn, "bbb", None).unwrap());
+        assert!(contains(&conn, "ccc", None).unwrap());
+
+        let rows: i64 = conn
+            .query_row("SELECT COUNT(*) FROM uploaded_hashes", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(rows, 2);
+    }
+
+    #[test]
+    fn test_uploads_count_for_their_sessions_tenant() {
+        let mut conn = crate::database::test_connection();
+        conn.execute(
+            "INSERT INTO agent_sessions (
+                id, provider, project_name, session_id, file_name, file_path, file_size,
+                created_at, uploaded_at, tenant_id
+            ) VALUES ('s1', 'claude-code', 'app', 's1', 's1', '/s1', 10, 0, 0, 'acme')",
+            [],
+        )
+        .unwrap();
+
+        record(&mut conn, "claude-code", "s1", "aaa", Some("acme")).unwrap();
+        assert!(contains(&conn, "aaa", Some("acme")).unwrap());
+        assert!(!contains(&conn, "aaa", Some("globex")).unwrap());
+        assert!(!contains(&conn, "aaa", None).unwrap());
+
+        // Still only the uploading tenant's once the session is gone
+        conn.execute("DELETE FROM agent_sessions", []).unwrap();
+        assert!(contains(&conn, "aaa", Some("acme")).unwrap());
+        assert!(!contains(&conn, "aaa", Some("globex")).unwrap());
+    }
+
+    #[test]
+    fn test_upload_base_is_replaced() {
+        let conn = crate::database::crate::database::test_connection();
+
+        assert_eq!(base(&conn, "codex", "s1").unwrap(), None);
+        for (hash, length) in [("aaa", 100), ("bbb", 250)] {
+            let upload_base = UploadBase {
+                content_hash: hash.to_string(),
+                content_length: length,
+            };
+            record_base(&conn, "codex", "s1", &upload_base).unwrap();
+        }
+        let upload_base = base(&conn, "codex", "s1").unwrap().unwrap();
+        assert_eq!(upload_base.content_hash, "bbb");
+        assert_eq!(upload_base.content_length, 250);
+    }
+}
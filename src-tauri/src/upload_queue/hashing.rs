@@ -27,8 +27,13 @@ pub fn calculate_file_hash_sha256(file_path: &PathBuf) -> Result<String, String>
 
 /// Calculate SHA256 hash of content in memory (for v2 upload deduplication)
 pub fn calculate_content_hash_sha256(content: &str) -> String {
+    calculate_bytes_hash_sha256(content.as_bytes())
+}
+
+/// Calculate SHA256 hash of raw bytes (for delta upload bases)
+pub fn calculate_bytes_hash_sha256(content: &[u8]) -> String {
     let mut hasher = Sha256::new();
-    hasher.update(content.as_bytes());
+    hasher.update(content);
     let result = hasher.finalize();
     format!("{:x}", result)
 }
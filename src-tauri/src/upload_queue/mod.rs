@@ -1,21 +1,40 @@
 // Module declarations
-mod compression;
-mod hashing;
+pub(crate) mod compression;
+mod concurrency;
+mod embedded;
+mod hash_store;
+pub(crate) mod hashing;
+mod poll_schedule;
 mod processor;
+pub(crate) mod quality_gate;
 mod queue_manager;
+mod queue_state;
+mod transport;
+mod truncation;
 mod types;
 mod upload;
 mod validation;
 
 // Re-export types and constants from submodules
+pub use poll_schedule::request_db_poll;
+pub use transport::{HttpTransport, UploadTransport};
+#[allow(unused_imports)] // Used by integration tests
+pub use transport::TransportFuture;
 pub use types::*;
+pub use upload::UploadOutcome;
+pub use upload::inspect::{inspect_upload_payload, UploadPayloadInspection};
 
+use crate::error::GuideModeError;
+use crate::events::EventBus;
 use crate::config::GuideModeConfig;
+use concurrency::AdaptiveConcurrency;
+use crate::logging::{log_info, log_warn};
 use crate::project_metadata::ProjectMetadata;
 use crate::providers::SessionInfo;
 use indexmap::IndexSet;
 use std::collections::VecDeque;
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use tokio::sync::Semaphore;
 
 // Import validation function and PathBuf for tests only
@@ -36,6 +55,8 @@ pub struct UploadQueue {
     config: Arc<Mutex<Option<GuideModeConfig>>>,
     app_handle: Arc<Mutex<Option<tauri::AppHandle>>>,
     upload_semaphore: Arc<Semaphore>, // Limit concurrent uploads
+    concurrency: Arc<AdaptiveConcurrency>,
+    transport: Arc<dyn UploadTransport>,
 }
 
 impl std::fmt::Debug for UploadQueue {
@@ -49,6 +70,8 @@ impl std::fmt::Debug for UploadQueue {
             .field("config", &"<config>")
             .field("app_handle", &"<app handle>")
             .field("upload_semaphore", &"<semaphore>")
+            .field("concurrency", &self.concurrency)
+            .field("transport", &"<transport>")
             .finish()
     }
 }
@@ -61,6 +84,11 @@ impl Default for UploadQueue {
 
 impl UploadQueue {
     pub fn new() -> Self {
+        Self::with_transport(Arc::new(HttpTransport))
+    }
+
+    /// A queue that sends uploads through `transport`, e.g. a mock in tests
+    pub fn with_transport(transport: Arc<dyn UploadTransport>) -> Self {
         Self {
             queue: Arc::new(Mutex::new(VecDeque::new())),
             processing: Arc::new(Mutex::new(0)),
@@ -70,15 +98,50 @@ impl UploadQueue {
             config: Arc::new(Mutex::new(None)),
             app_handle: Arc::new(Mutex::new(None)),
             upload_semaphore: Arc::new(Semaphore::new(MAX_CONCURRENT_UPLOADS)),
+            concurrency: Arc::new(AdaptiveConcurrency::default()),
+            transport,
         }
     }
 
     pub fn set_config(&self, config: GuideModeConfig) {
+        self.concurrency.set_max(
+            config
+                .max_concurrent_uploads
+                .unwrap_or(DEFAULT_CONCURRENT_UPLOADS),
+        );
+        if let Some(tenant_id) = config.tenant_id.as_deref() {
+            crate::tenant::claim_unowned_sessions(tenant_id);
+        }
         if let Ok(mut config_guard) = self.config.lock() {
+            let switched_tenant = config_guard
+                .as_ref()
+                .is_some_and(|previous| previous.tenant_id != config.tenant_id);
+            if switched_tenant {
+                self.forget_tenant_state();
+            }
             *config_guard = Some(config);
         }
     }
 
+    /// Drop queued items and cached upload hashes of the previous tenant; the
+    /// next database poll queues the new tenant's sessions
+    fn forget_tenant_state(&self) {
+        if let Ok(mut queue) = self.queue.lock() {
+            queue.clear();
+        }
+        if let Ok(mut failed_items) = self.failed_items.lock() {
+            failed_items.clear();
+        }
+        if let Ok(mut uploaded_hashes) = self.uploaded_hashes.lock() {
+            uploaded_hashes.clear();
+        }
+        log_info(
+            "upload-queue",
+            "Tenant changed, cleared the upload queue of the previous tenant",
+        )
+        .unwrap_or_default();
+    }
+
     pub fn set_app_handle(&self, app_handle: tauri::AppHandle) {
         if let Ok(mut handle_guard) = self.app_handle.lock() {
             *handle_guard = Some(app_handle);
@@ -128,7 +191,8 @@ impl UploadQueue {
         )
     }
 
-    pub fn start_processing(&self) -> Result<(), String> {
+    /// Start uploading; `event_bus` wakes the processor as sessions are saved
+    pub fn start_processing(&self, event_bus: &EventBus) -> Result<(), String> {
         // Create processor and delegate to it
         let processor = processor::UploadProcessor::new(
             Arc::clone(&self.queue),
@@ -139,13 +203,56 @@ impl UploadQueue {
             Arc::clone(&self.config),
             Arc::clone(&self.app_handle),
             Arc::clone(&self.upload_semaphore),
+            Arc::clone(&self.concurrency),
+            Arc::clone(&self.transport),
+            event_bus.clone(),
         );
 
         processor.start()
     }
 
+    /// Stop taking items off the queue and wait up to `timeout` for in-flight
+    /// uploads to finish
+    ///
+    /// Uploads still running at the deadline are abandoned; their sessions stay
+    /// unsynced in the database and are queued again on the next launch.
+    pub async fn shutdown(&self, timeout: Duration) {
+        if let Ok(mut is_running) = self.is_running.lock() {
+            *is_running = false;
+        }
+
+        let all_permits = MAX_CONCURRENT_UPLOADS as u32;
+        match tokio::time::timeout(timeout, self.upload_semaphore.acquire_many(all_permits)).await {
+            Ok(Ok(permits)) => {
+                // Keep every permit so nothing new starts before exit
+                permits.forget();
+                log_info("upload-queue", "✓ In-flight uploads finished").unwrap_or_default();
+            }
+            _ => {
+                self.upload_semaphore.close();
+                log_warn(
+                    "upload-queue",
+                    &format!(
+                        "⚠ Gave up on {} in-flight uploads; they will resume on next launch",
+                        all_permits as usize - self.upload_semaphore.available_permits()
+                    ),
+                )
+                .unwrap_or_default();
+            }
+        }
+
+        let queued = self.queue.lock().map(|queue| queue.len()).unwrap_or(0);
+        if queued > 0 {
+            log_info(
+                "upload-queue",
+                &format!("{} queued uploads left for next launch", queued),
+            )
+            .unwrap_or_default();
+        }
+    }
+
     pub fn get_status(&self) -> UploadStatus {
-        queue_manager::get_status(&self.processing)
+        queue_manager::get_status(&self.processing, &self.concurrency)
     }
 
     #[allow(dead_code)]
@@ -161,14 +268,90 @@ impl UploadQueue {
         queue_manager::retry_failed();
     }
 
-    pub fn clear_uploaded_hashes(&self) {
+    /// Forget uploaded hashes so a provider's sessions can be synced again
+    ///
+    /// The in-memory cache doesn't track providers, so it is cleared entirely;
+    /// the persisted hashes are cleared for `provider` only.
+    pub fn clear_uploaded_hashes(&self, provider: &str) {
         if let Ok(mut uploaded_hashes) = self.uploaded_hashes.lock() {
             uploaded_hashes.clear();
         }
+        hash_store::clear_provider(provider);
     }
 
     pub fn get_all_items(&self) -> QueueItems {
-        queue_manager::get_all_items()
+        queue_manager::get_all_items(&self.queue)
+    }
+
+    /// Set a queue item's priority; higher priorities upload first
+    pub fn set_item_priority(&self, item_id: &str, priority: i64) -> Result<(), String> {
+        queue_state::set_priority(item_id, priority)?;
+        self.update_queued_item(item_id, |item| item.priority = priority);
+        Ok(())
+    }
+
+    /// Move a queue item to another project, returning the name it was given
+    pub fn set_item_project(&self, item_id: &str, project_name: &str) -> Result<String, String> {
+        let project_name = queue_state::set_project(item_id, project_name)?;
+        self.update_queued_item(item_id, |item| item.project_name = project_name.clone());
+        Ok(project_name)
+    }
+
+    /// Change a queue item's working directory, or clear it
+    pub fn set_item_cwd(&self, item_id: &str, cwd: Option<&str>) -> Result<(), String> {
+        queue_state::set_cwd(item_id, cwd)?;
+        let cwd = cwd.map(str::trim).filter(|cwd| !cwd.is_empty());
+        self.update_queued_item(item_id, |item| item.cwd = cwd.map(str::to_string));
+        Ok(())
+    }
+
+    /// Exclude a project from syncing, cancelling its queued items, or include it again
+    ///
+    /// Returns how many sessions were skipped or let through again.
+    pub fn set_project_sync_excluded(
+        &self,
+        project_id: &str,
+        excluded: bool,
+    ) -> Result<usize, String> {
+        let session_ids = crate::project_sync::set_project_sync_excluded(project_id, excluded)?;
+        if excluded {
+            if let Ok(mut queue) = self.queue.lock() {
+                queue.retain(|item| {
+                    item.session_id
+                        .as_ref()
+                        .is_none_or(|session_id| !session_ids.contains(session_id))
+                });
+            }
+        }
+        Ok(session_ids.len())
+    }
+
+    /// Merge duplicate projects into `target_id`, moving their queued items along
+    pub fn merge_projects(
+        &self,
+        source_ids: &[String],
+        target_id: &str,
+    ) -> Result<crate::project_merge::ProjectMerge, String> {
+        let merge = crate::project_merge::merge_projects(source_ids, target_id)?;
+        if let Ok(mut queue) = self.queue.lock() {
+            for item in queue.iter_mut().filter(|item| {
+                item.session_id
+                    .as_ref()
+                    .is_some_and(|session_id| merge.moved_sessions.contains(session_id))
+            }) {
+                item.project_name = merge.target_name.clone();
+            }
+        }
+        Ok(merge)
+    }
+
+    /// Apply an edit to the item if it's already in the in-memory queue
+    fn update_queued_item(&self, item_id: &str, update: impl FnOnce(&mut UploadItem)) {
+        if let Ok(mut queue) = self.queue.lock() {
+            if let Some(item) = queue.iter_mut().find(|item| item.id == item_id) {
+                update(item);
+            }
+        }
     }
 
     pub fn remove_item(&self, item_id: &str) -> Result<(), String> {
@@ -179,6 +362,12 @@ impl UploadQueue {
         queue_manager::retry_item(item_id)
     }
 
+    /// Upload an item right away instead of waiting for the processor
+    pub async fn upload_now(&self, item: &UploadItem) -> Result<UploadOutcome, GuideModeError> {
+        let config = self.config.lock().ok().and_then(|config| config.clone());
+        self.transport.upload(item, config).await
+    }
+
     /// Upload project metadata to the server
     ///
     /// **DEPRECATED**: Use embedded projectMetadata in upload payloads instead
@@ -209,7 +398,11 @@ impl UploadQueue {
 
         // Make GET request to check if project exists
         let client = reqwest::Client::new();
-        let url = format!("{}/api/projects/{}", server_url, project_name);
+        let url = format!(
+            "{}/{}",
+            crate::api_paths::api_url(&server_url, crate::api_paths::PROJECTS),
+            project_name
+        );
 
         let response = client
             .get(&url)
@@ -236,6 +429,26 @@ mod tests {
         assert_eq!(status.failed, 0);
     }
 
+    #[tokio::test]
+    async fn test_shutdown_waits_for_in_flight_uploads() {
+        let queue = UploadQueue::new();
+
+        // An in-flight upload that finishes shortly after shutdown starts
+        let permit = Arc::clone(&queue.upload_semaphore)
+            .try_acquire_owned()
+            .unwrap();
+        let upload = tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            drop(permit);
+        });
+
+        queue.shutdown(Duration::from_secs(5)).await;
+        assert!(upload.is_finished());
+        assert!(!*queue.is_running.lock().unwrap());
+        // Nothing new can start after shutdown
+        assert_eq!(queue.upload_semaphore.available_permits(), 0);
+    }
+
     #[test]
     fn test_add_item() {
         use std::fs;
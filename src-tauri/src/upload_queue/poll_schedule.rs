@@ -0,0 +1,98 @@
+//! When the processor polls the database.
+//!
+//! Polls run every `dbPollIntervalSecs` (10 if unset). Each poll that finds
+//! nothing to upload or patch doubles the interval, up to
+//! `MAX_IDLE_DB_POLL_INTERVAL_SECS`; one that finds work goes back to the
+//! configured interval. Changing a synced session's metadata, saving sessions
+//! outside the event bus (scans, imports) and a session changing or
+//! completing request a poll, which runs on the processor's next tick.
+//! Sessions the event bus reports as saved are queued without one.
+
+use super::types::{DB_POLL_INTERVAL_SECS, MAX_IDLE_DB_POLL_INTERVAL_SECS};
+use chrono::{DateTime, Duration, Utc};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Set when something may have been left for the processor to upload
+static POLL_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+/// Poll the database on the processor's next tick, without waiting out the
+/// interval
+pub fn request_db_poll() {
+    POLL_REQUESTED.store(true, Ordering::Relaxed);
+}
+
+/// Whether a poll was requested since the last call
+pub fn take_poll_request() -> bool {
+    POLL_REQUESTED.swap(false, Ordering::Relaxed)
+}
+
+#[derive(Debug)]
+pub struct PollSchedule {
+    last_poll: DateTime<Utc>,
+    interval_secs: u64,
+}
+
+impl PollSchedule {
+    /// First poll one configured interval from `now`
+    pub fn new(now: DateTime<Utc>, configured_secs: Option<u64>) -> Self {
+        Self {
+            last_poll: now,
+            interval_secs: base_interval(configured_secs),
+        }
+    }
+
+    /// Whether to poll at `now`; a requested poll is due at once
+    pub fn is_due(&self, now: DateTime<Utc>, requested: bool) -> bool {
+        requested || now - self.last_poll >= Duration::seconds(self.interval_secs as i64)
+    }
+
+    /// Schedule the next poll after one at `now` that did or didn't find work
+    pub fn record_poll(
+        &mut self,
+        now: DateTime<Utc>,
+        found_work: bool,
+        configured_secs: Option<u64>,
+    ) {
+        let base = base_interval(configured_secs);
+        self.last_poll = now;
+        self.interval_secs = if found_work {
+            base
+        } else {
+            (self.interval_secs * 2).clamp(base, base.max(MAX_IDLE_DB_POLL_INTERVAL_SECS))
+        };
+    }
+}
+
+fn base_interval(configured_secs: Option<u64>) -> u64 {
+    configured_secs.unwrap_or(DB_POLL_INTERVAL_SECS).max(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_idle_polls_back_off_until_work_is_found() {
+        let start = Utc::now();
+        let mut schedule = PollSchedule::new(start, None);
+        assert!(!schedule.is_due(start + Duration::seconds(9), false));
+        assert!(schedule.is_due(start + Duration::seconds(9), true));
+        assert!(schedule.is_due(start + Duration::seconds(10), false));
+
+        let mut intervals = Vec::new();
+        for _ in 0..7 {
+            schedule.record_poll(start, false, None);
+            intervals.push(schedule.interval_secs);
+        }
+        assert_eq!(intervals, [20, 40, 80, 160, 300, 300, 300]);
+
+        schedule.record_poll(start, true, None);
+        assert_eq!(schedule.interval_secs, 10);
+
+        // A configured interval longer than the cap isn't shortened
+        schedule.record_poll(start, false, Some(600));
+        assert_eq!(schedule.interval_secs, 600);
+        schedule.record_poll(start, false, Some(0));
+        assert_eq!(schedule.interval_secs, 300);
+    }
+}
@@ -4,22 +4,40 @@
 //! Refactored from 297-line monolithic function into focused methods.
 
 use crate::config::GuideModeConfig;
-use crate::database::{get_unsynced_sessions, mark_session_sync_failed, mark_session_synced};
+use crate::database::{
+    get_dirty_metadata_sessions, get_unsynced_session, get_unsynced_sessions,
+    mark_session_sync_failed, mark_session_sync_skipped, mark_session_synced,
+    mark_session_transcript_deferred, requeue_deferred_transcripts, UnsyncedSession,
+};
+use crate::error::GuideModeError;
+use crate::events::{EventBus, EventReceiver, SessionEventPayload};
 use crate::logging::{log_error, log_info, log_warn};
+use crate::providers::common::ingest_log::{self, SkipReason};
 use chrono::{DateTime, Utc};
 use indexmap::IndexSet;
-use std::collections::VecDeque;
+use std::cmp::Reverse;
+use std::collections::{HashSet, VecDeque};
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
 use tauri::Emitter;
-use tokio::sync::Semaphore;
+use tokio::sync::broadcast::error::{RecvError, TryRecvError};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
 use tokio::time::sleep;
 
+use super::concurrency::AdaptiveConcurrency;
+use super::hash_store;
+use super::poll_schedule::{request_db_poll, take_poll_request, PollSchedule};
+use super::quality_gate::{self, LowSignal, LOW_SIGNAL};
 use super::queue_manager;
-use super::types::{UploadItem, DB_POLL_INTERVAL_SECS, MAX_UPLOADED_HASHES};
+use super::queue_state;
+use super::transport::UploadTransport;
+use super::types::{
+    UploadItem, MAX_CONCURRENT_UPLOADS, MAX_UPLOADED_HASHES, METADATA_PATCHES_PER_POLL,
+};
+use super::upload::UploadOutcome;
 use super::upload::{
-    calculate_backoff, classify_error, process_upload_item, schedule_retry, should_retry, ErrorType,
+    calculate_backoff, classify_error, rate_limited_until, schedule_retry, should_retry, ErrorType,
 };
 
 /// Main upload processor that manages the processing loop
@@ -33,6 +51,11 @@ pub struct UploadProcessor {
     config: Arc<Mutex<Option<GuideModeConfig>>>,
     app_handle: Arc<Mutex<Option<tauri::AppHandle>>>,
     semaphore: Arc<Semaphore>,
+    concurrency: Arc<AdaptiveConcurrency>,
+    transport: Arc<dyn UploadTransport>,
+    event_bus: EventBus,
+    /// Sessions being uploaded right now
+    in_flight: Arc<Mutex<HashSet<String>>>,
 }
 
 impl UploadProcessor {
@@ -47,6 +70,9 @@ impl UploadProcessor {
         config: Arc<Mutex<Option<GuideModeConfig>>>,
         app_handle: Arc<Mutex<Option<tauri::AppHandle>>>,
         semaphore: Arc<Semaphore>,
+        concurrency: Arc<AdaptiveConcurrency>,
+        transport: Arc<dyn UploadTransport>,
+        event_bus: EventBus,
     ) -> Self {
         Self {
             queue,
@@ -57,6 +83,10 @@ impl UploadProcessor {
             config,
             app_handle,
             semaphore,
+            concurrency,
+            transport,
+            event_bus,
+            in_flight: Arc::new(Mutex::new(HashSet::new())),
         }
     }
 
@@ -80,7 +110,9 @@ impl UploadProcessor {
 
     /// Main processing loop
     async fn run_loop(&self) {
-        let mut last_db_poll = Utc::now();
+        let mut poll_schedule = PollSchedule::new(Utc::now(), self.configured_poll_interval());
+        let mut events = self.event_bus.subscribe();
+        let mut saved_sessions = Vec::new();
 
         loop {
             // Check if we should continue
@@ -94,8 +126,11 @@ impl UploadProcessor {
                 continue;
             }
 
+            // Queue sessions saved since the last tick
+            self.queue_saved_sessions(std::mem::take(&mut saved_sessions));
+
             // Poll database if needed
-            if let Err(e) = self.poll_database_if_needed(&mut last_db_poll).await {
+            if let Err(e) = self.poll_database_if_needed(&mut poll_schedule).await {
                 log_error("upload-queue", &format!("Database polling failed: {}", e))
                     .unwrap_or_default();
             }
@@ -103,8 +138,9 @@ impl UploadProcessor {
             // Process available items
             self.process_available_items().await;
 
-            // Brief sleep to avoid busy-waiting
-            sleep(Duration::from_millis(500)).await;
+            // Brief sleep to avoid busy-waiting, cut short by session events
+            wait_for_session_events(&mut events, &mut saved_sessions, Duration::from_millis(500))
+                .await;
         }
     }
 
@@ -129,29 +165,76 @@ impl UploadProcessor {
             .unwrap_or(false)
     }
 
-    /// Poll database for unsynced sessions if interval has elapsed
+    fn configured_poll_interval(&self) -> Option<u64> {
+        get_config(&self.config).and_then(|config| config.db_poll_interval_secs)
+    }
+
+    /// Poll database for unsynced sessions if the poll is due
     async fn poll_database_if_needed(
         &self,
-        last_db_poll: &mut DateTime<Utc>,
+        poll_schedule: &mut PollSchedule,
     ) -> Result<(), String> {
         let now = Utc::now();
-        let elapsed = (now - *last_db_poll).num_seconds();
-
-        if elapsed < DB_POLL_INTERVAL_SECS as i64 {
+        if !poll_schedule.is_due(now, take_poll_request()) {
             return Ok(());
         }
 
-        *last_db_poll = now;
-        self.fetch_and_queue_unsynced_sessions().await
+        let patched = self.patch_dirty_metadata().await;
+        let queued = self.fetch_and_queue_unsynced_sessions().await;
+        let found_work = patched || queued.as_ref().is_ok_and(|queued| *queued);
+        poll_schedule.record_poll(now, found_work, self.configured_poll_interval());
+        queued.map(|_| ())
+    }
+
+    /// Send rating and assessment changes of sessions that already synced;
+    /// whether there were any
+    async fn patch_dirty_metadata(&self) -> bool {
+        if rate_limited_until().is_some() {
+            return false;
+        }
+        let Some(config) = get_config(&self.config) else {
+            return false;
+        };
+        let sessions = match get_dirty_metadata_sessions(METADATA_PATCHES_PER_POLL) {
+            Ok(sessions) => sessions,
+            Err(e) => {
+                log_error(
+                    "upload-queue",
+                    &format!("Failed to get sessions with metadata changes: {}", e),
+                )
+                .unwrap_or_default();
+                return false;
+            }
+        };
+        let found = !sessions.is_empty();
+
+        for session in sessions {
+            if let Err(e) = self.transport.patch_metadata(&session, &config).await {
+                // Stays dirty and is sent again on the next poll
+                log_warn(
+                    "upload-queue",
+                    &format!(
+                        "⚠ Failed to patch metadata for {}: {}",
+                        session.session_id, e
+                    ),
+                )
+                .unwrap_or_default();
+                if rate_limited_until().is_some() {
+                    break;
+                }
+            }
+        }
+        found
     }
 
-    /// Fetch unsynced sessions from database and add to queue
-    async fn fetch_and_queue_unsynced_sessions(&self) -> Result<(), String> {
+    /// Fetch unsynced sessions from database and add to queue; whether there
+    /// were any
+    async fn fetch_and_queue_unsynced_sessions(&self) -> Result<bool, String> {
         let unsynced = get_unsynced_sessions()
             .map_err(|e| format!("Failed to get unsynced sessions: {}", e))?;
 
         if unsynced.is_empty() {
-            return Ok(());
+            return Ok(false);
         }
 
         log_info(
@@ -160,9 +243,39 @@ impl UploadProcessor {
         )
         .unwrap_or_default();
 
+        self.queue_sessions(unsynced);
+        Ok(true)
+    }
+
+    /// Queue the sessions saved since the last tick that are ready to upload
+    fn queue_saved_sessions(&self, session_ids: Vec<String>) {
+        let sessions: Vec<UnsyncedSession> = session_ids
+            .iter()
+            .filter_map(|session_id| match get_unsynced_session(session_id) {
+                Ok(session) => session,
+                Err(e) => {
+                    // The next poll finds it
+                    log_warn(
+                        "upload-queue",
+                        &format!("⚠ Failed to get saved session {}: {}", session_id, e),
+                    )
+                    .unwrap_or_default();
+                    None
+                }
+            })
+            .collect();
+        self.queue_sessions(sessions);
+    }
+
+    /// Add sessions that aren't queued yet to the queue
+    fn queue_sessions(&self, sessions: Vec<UnsyncedSession>) {
+        if sessions.is_empty() {
+            return;
+        }
         let mut queue = self.queue.lock().unwrap();
+        let mut queued = Vec::new();
 
-        for session in unsynced {
+        for session in sessions {
             if self.is_session_queued(&queue, &session.session_id) {
                 continue;
             }
@@ -182,34 +295,60 @@ impl UploadProcessor {
                 session_id: Some(session.session_id.clone()),
                 content: None,
                 cwd: session.cwd.clone(),
+                priority: session.upload.priority,
+                last_attempt_at: session
+                    .upload
+                    .last_attempt_at
+                    .and_then(DateTime::from_timestamp_millis),
+                superseded: session.upload.superseded_at.is_some(),
+                quality_checked: false,
             };
             queue.push_back(item);
+            queued.push(session.session_id);
         }
+        drop(queue);
 
-        Ok(())
+        queue_state::record_queued(&queued);
     }
 
-    /// Check if session is already in queue
+    /// Check if session is already in queue or being uploaded
     fn is_session_queued(&self, queue: &VecDeque<UploadItem>, session_id: &str) -> bool {
         queue
             .iter()
             .any(|item| item.session_id.as_ref() == Some(&session_id.to_string()))
+            || self
+                .in_flight
+                .lock()
+                .is_ok_and(|in_flight| in_flight.contains(session_id))
     }
 
-    /// Process available items up to semaphore limit
+    /// Process available items up to the current concurrency
+    ///
+    /// Permits are taken before spawning, so shutdown can wait for every
+    /// in-flight upload by acquiring all of them.
     async fn process_available_items(&self) {
-        let available_permits = self.semaphore.available_permits();
+        // The server asked the whole queue to back off
+        if rate_limited_until().is_some() {
+            return;
+        }
 
-        for _ in 0..available_permits {
-            // Try to get an item
-            let item = self.get_next_item();
+        while self.should_continue() {
+            let in_flight = MAX_CONCURRENT_UPLOADS - self.semaphore.available_permits();
+            if in_flight >= self.concurrency.current() {
+                break;
+            }
+
+            let Ok(permit) = Arc::clone(&self.semaphore).try_acquire_owned() else {
+                break; // At the concurrency limit, or shutting down
+            };
 
-            if item.is_none() {
+            // Try to get an item
+            let Some(item) = self.get_next_item() else {
                 break; // No more items
-            }
+            };
 
             // Spawn upload task
-            self.spawn_upload_task(item.unwrap());
+            self.spawn_upload_task(item, permit);
         }
     }
 
@@ -222,22 +361,57 @@ impl UploadProcessor {
             return Some(item);
         }
 
-        // Otherwise, get first item
-        queue.pop_front()
+        // Otherwise, the highest priority item not waiting for a retry, in queue order
+        let index = queue
+            .iter()
+            .enumerate()
+            .filter(|(_, item)| item.next_retry_at.is_none())
+            .max_by_key(|(index, item)| (item.priority, Reverse(*index)))
+            .map(|(index, _)| index)?;
+        queue.remove(index)
     }
 
     /// Spawn async task to upload an item
-    fn spawn_upload_task(&self, item: UploadItem) {
-        let semaphore = Arc::clone(&self.semaphore);
+    fn spawn_upload_task(&self, item: UploadItem, permit: OwnedSemaphorePermit) {
         let processing = Arc::clone(&self.processing);
         let config = Arc::clone(&self.config);
         let app_handle = Arc::clone(&self.app_handle);
         let queue = Arc::clone(&self.queue);
         let failed_items = Arc::clone(&self.failed_items);
         let uploaded_hashes = Arc::clone(&self.uploaded_hashes);
+        let concurrency = Arc::clone(&self.concurrency);
+        let transport = Arc::clone(&self.transport);
+        let in_flight = Arc::clone(&self.in_flight);
+
+        // Taken off the queue, so polls must still see it as queued
+        if let Some(ref session_id) = item.session_id {
+            if let Ok(mut in_flight) = in_flight.lock() {
+                in_flight.insert(session_id.clone());
+            }
+        }
 
         tauri::async_runtime::spawn(async move {
-            let _permit = semaphore.acquire().await.unwrap();
+            let _permit = permit;
+
+            // Sessions under the provider's quality gate are skipped, not uploaded;
+            // the gate reads the file, so it runs once and off the async runtime
+            let mut item = item;
+            if !item.quality_checked {
+                let gated_item = item.clone();
+                match tauri::async_runtime::spawn_blocking(move || {
+                    quality_gate::check_item(&gated_item)
+                })
+                .await
+                {
+                    Ok(Some(low_signal)) => {
+                        skip_low_signal(&item, &low_signal);
+                        finish_in_flight(&in_flight, &item);
+                        return;
+                    }
+                    Ok(None) => item.quality_checked = true,
+                    Err(_) => {} // Checked again on the next attempt
+                }
+            }
 
             // Increment processing counter
             increment_counter(&processing);
@@ -256,22 +430,31 @@ impl UploadProcessor {
             )
             .unwrap_or_default();
 
+            if let Some(ref session_id) = item.session_id {
+                queue_state::record_attempt(session_id);
+            }
+
             // Process upload
             let item_mut = item.clone();
-            let result = process_upload_item(&item_mut, upload_config).await;
+            let result = transport.upload(&item_mut, upload_config).await;
 
             // Handle result
             match result {
-                Ok(_) => {
-                    handle_upload_success(item_mut, &uploaded_hashes, &app_handle).await;
+                Ok(outcome) => {
+                    concurrency.record_success();
+                    handle_upload_success(item_mut, outcome, &uploaded_hashes, &app_handle).await;
                 }
                 Err(e) => {
+                    if is_overload(&e) {
+                        concurrency.record_overload();
+                    }
                     handle_upload_failure(item_mut, e, &queue, &failed_items, &app_handle).await;
                 }
             }
 
             // Decrement processing counter
             decrement_counter(&processing);
+            finish_in_flight(&in_flight, &item);
         });
     }
 
@@ -309,13 +492,71 @@ fn get_config(config: &Arc<Mutex<Option<GuideModeConfig>>>) -> Option<GuideModeC
     config.lock().ok().and_then(|c| c.clone())
 }
 
+fn finish_in_flight(in_flight: &Arc<Mutex<HashSet<String>>>, item: &UploadItem) {
+    if let Some(ref session_id) = item.session_id {
+        if let Ok(mut in_flight) = in_flight.lock() {
+            in_flight.remove(session_id);
+        }
+    }
+}
+
+/// Wait up to `timeout` for session events, collecting the sessions saved to
+/// the local database meanwhile
+///
+/// Saved sessions are queued directly. Changed and completed sessions, and
+/// events missed while lagging, request a database poll.
+async fn wait_for_session_events(
+    events: &mut EventReceiver,
+    saved_sessions: &mut Vec<String>,
+    timeout: Duration,
+) {
+    let mut event = tokio::select! {
+        event = events.recv() => event,
+        _ = sleep(timeout) => return,
+    };
+    loop {
+        match event {
+            Ok(event) => match event.payload {
+                SessionEventPayload::Saved { session_id } => {
+                    if !saved_sessions.contains(&session_id) {
+                        saved_sessions.push(session_id);
+                    }
+                }
+                SessionEventPayload::SessionChanged { .. }
+                | SessionEventPayload::Completed { .. } => request_db_poll(),
+                _ => {}
+            },
+            Err(RecvError::Lagged(_)) => request_db_poll(),
+            Err(RecvError::Closed) => {
+                sleep(timeout).await;
+                return;
+            }
+        }
+        // Take whatever else arrived, without waiting
+        event = match events.try_recv() {
+            Ok(event) => Ok(event),
+            Err(TryRecvError::Lagged(n)) => Err(RecvError::Lagged(n)),
+            Err(TryRecvError::Empty) | Err(TryRecvError::Closed) => return,
+        };
+    }
+}
+
 async fn handle_upload_success(
     item: UploadItem,
+    outcome: UploadOutcome,
     uploaded_hashes: &Arc<Mutex<IndexSet<String>>>,
     app_handle: &Arc<Mutex<Option<tauri::AppHandle>>>,
 ) {
+    if outcome == UploadOutcome::TranscriptDeferred {
+        handle_transcript_deferred(item, app_handle).await;
+        return;
+    }
+
     // Mark hash as uploaded
     if let Some(file_hash) = &item.file_hash {
+        let session_id = item.session_id.as_deref().unwrap_or(&item.file_name);
+        hash_store::record_upload(&item.provider, session_id, file_hash);
+
         if let Ok(mut hashes) = uploaded_hashes.lock() {
             hashes.insert(file_hash.clone());
 
@@ -363,19 +604,88 @@ async fn handle_upload_success(
         ),
     )
     .unwrap_or_default();
+
+    if outcome != UploadOutcome::Transcript {
+        return;
+    }
+    // The server takes transcripts again; send the ones it couldn't before
+    match requeue_deferred_transcripts() {
+        Ok(0) => {}
+        Ok(count) => {
+            log_info(
+                "upload-queue",
+                &format!(
+                    "🔁 Re-queued {} sessions whose transcript was deferred",
+                    count
+                ),
+            )
+            .unwrap_or_default();
+            request_db_poll();
+        }
+        Err(e) => log_warn(
+            "upload-queue",
+            &format!("⚠ Failed to re-queue deferred transcripts: {}", e),
+        )
+        .unwrap_or_default(),
+    }
+}
+
+/// Metrics went up without the transcript: the session counts as synced, but
+/// its hash isn't recorded so the transcript isn't taken for uploaded
+async fn handle_transcript_deferred(
+    item: UploadItem,
+    app_handle: &Arc<Mutex<Option<tauri::AppHandle>>>,
+) {
+    let Some(ref session_id) = item.session_id else {
+        return;
+    };
+    if let Err(e) = mark_session_transcript_deferred(session_id) {
+        log_error(
+            "upload-queue",
+            &format!("Failed to mark session {} as synced: {}", session_id, e),
+        )
+        .unwrap_or_default();
+        return;
+    }
+    emit_session_event(app_handle, "session-synced", session_id).await;
+
+    log_info(
+        "upload-queue",
+        &format!(
+            "✓ Uploaded metrics of {}; transcript deferred until the server accepts it",
+            item.file_name
+        ),
+    )
+    .unwrap_or_default();
+}
+
+/// Whether an upload error means the server can't keep up
+fn is_overload(error: &GuideModeError) -> bool {
+    match classify_error(error) {
+        ErrorType::Server | ErrorType::RateLimited => true,
+        ErrorType::Network => match error {
+            GuideModeError::Network(e) => e.is_timeout(),
+            GuideModeError::Status { status, .. } => {
+                *status == reqwest::StatusCode::REQUEST_TIMEOUT
+            }
+            GuideModeError::Io(e) => e.kind() == std::io::ErrorKind::TimedOut,
+            _ => false,
+        },
+        _ => false,
+    }
 }
 
 async fn handle_upload_failure(
     mut item: UploadItem,
-    error: String,
+    error: GuideModeError,
     queue: &Arc<Mutex<VecDeque<UploadItem>>>,
     failed_items: &Arc<Mutex<Vec<UploadItem>>>,
     app_handle: &Arc<Mutex<Option<tauri::AppHandle>>>,
 ) {
-    item.last_error = Some(error.clone());
-
     // Use retry module to classify error
     let error_type = classify_error(&error);
+    let error = error.to_string();
+    item.last_error = Some(error.clone());
 
     match error_type {
         ErrorType::Client => {
@@ -392,7 +702,23 @@ async fn handle_upload_failure(
             )
             .unwrap_or_default();
         }
-        ErrorType::Server | ErrorType::Network => {
+        ErrorType::RateLimited => {
+            // Retry once the cooldown ends, without using up a retry
+            let retry_at = rate_limited_until().unwrap_or_else(Utc::now);
+            item.next_retry_at = Some(retry_at);
+            requeue_item(item.clone(), queue);
+
+            log_warn(
+                "upload-queue",
+                &format!(
+                    "⏳ Upload rate limited, retrying {} after {}",
+                    item.file_name,
+                    retry_at.to_rfc3339()
+                ),
+            )
+            .unwrap_or_default();
+        }
+        ErrorType::Server | ErrorType::Network | ErrorType::ChecksumMismatch => {
             // Retry with backoff
             item.retry_count += 1;
 
@@ -429,6 +755,32 @@ async fn handle_upload_failure(
     }
 }
 
+fn skip_low_signal(item: &UploadItem, low_signal: &LowSignal) {
+    let reason = low_signal.to_string();
+    log_info(
+        "upload-queue",
+        &format!("⊘ Not uploading {}, {}", item.file_name, reason),
+    )
+    .unwrap_or_default();
+
+    if let Some(ref session_id) = item.session_id {
+        if let Err(e) = mark_session_sync_skipped(session_id, LOW_SIGNAL) {
+            log_error(
+                "upload-queue",
+                &format!("Failed to mark session {} as skipped: {}", session_id, e),
+            )
+            .unwrap_or_default();
+        }
+    }
+    ingest_log::record_skip(
+        &item.provider,
+        &item.file_path,
+        item.session_id.as_deref(),
+        SkipReason::LowSignal,
+        &reason,
+    );
+}
+
 fn requeue_item(item: UploadItem, queue: &Arc<Mutex<VecDeque<UploadItem>>>) {
     if let Ok(mut q) = queue.lock() {
         q.push_back(item);
@@ -0,0 +1,187 @@
+//! Quality gate for sessions about to upload.
+//!
+//! Opened-and-closed sessions, ones that never got an answer and ones that
+//! are nothing but meta messages add noise to the server's dashboards. A
+//! provider can enable a gate that skips them instead: before each upload
+//! the session's canonical content is counted, and one under the configured
+//! thresholds is marked `sync_skipped` with the reason `low_signal` and
+//! recorded in the ingest stats. A skipped session is gated again once its
+//! file changes, in case it was resumed.
+
+use super::types::UploadItem;
+use super::upload::metrics::read_canonical_content;
+use crate::config::{load_provider_config, QualityGate};
+use crate::providers::common::ingest_log::{self, SkipReason};
+use serde_json::Value;
+use std::fmt;
+
+/// `sync_skip_reason` of sessions the gate skipped
+pub const LOW_SIGNAL: &str = "low_signal";
+
+/// Thresholds a session must reach to upload
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QualityThresholds {
+    /// Fewest user and assistant messages, meta messages aside
+    pub min_messages: usize,
+    pub min_assistant_messages: usize,
+}
+
+impl Default for QualityThresholds {
+    fn default() -> Self {
+        Self {
+            min_messages: 2,
+            min_assistant_messages: 1,
+        }
+    }
+}
+
+impl QualityThresholds {
+    /// The provider's thresholds, or `None` when its gate is off
+    pub fn for_provider(provider: &str) -> Option<Self> {
+        let gate = load_provider_config(provider).ok()?.quality_gate;
+        gate.enabled.then(|| Self::default().with_overrides(&gate))
+    }
+
+    pub fn with_overrides(mut self, gate: &QualityGate) -> Self {
+        if let Some(min) = gate.min_messages {
+            self.min_messages = min;
+        }
+        if let Some(min) = gate.min_assistant_messages {
+            self.min_assistant_messages = min;
+        }
+        self
+    }
+
+    /// Why `content` is too trivial to upload, if it is
+    pub fn check(&self, content: &str) -> Option<LowSignal> {
+        let signal = SessionSignal::of(content);
+        let low =
+            signal.messages() < self.min_messages || signal.assistant < self.min_assistant_messages;
+        low.then_some(LowSignal {
+            signal,
+            thresholds: self.clone(),
+        })
+    }
+}
+
+/// Why `item` is too trivial to upload, when its provider gates quality
+///
+/// Reads the session's canonical content, so call it off the async runtime.
+pub fn check_item(item: &UploadItem) -> Option<LowSignal> {
+    let thresholds = QualityThresholds::for_provider(&item.provider)?;
+    let low_signal = thresholds.check(&read_canonical_content(item)?);
+    if low_signal.is_none() {
+        // It may have been skipped before it was resumed
+        ingest_log::clear_skip_reason(&item.provider, &item.file_path, SkipReason::LowSignal);
+    }
+    low_signal
+}
+
+/// Messages of a session, by kind
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SessionSignal {
+    pub user: usize,
+    pub assistant: usize,
+    /// System events and other meta messages
+    pub meta: usize,
+}
+
+impl SessionSignal {
+    /// Count the messages of canonical JSONL content, ignoring unparseable lines
+    pub fn of(content: &str) -> Self {
+        let mut signal = Self::default();
+        for line in content.lines().filter(|line| !line.trim().is_empty()) {
+            let Ok(message) = serde_json::from_str::<Value>(line) else {
+                continue;
+            };
+            let is_meta = message.get("isMeta").and_then(Value::as_bool) == Some(true);
+            match message.get("type").and_then(Value::as_str) {
+                Some("user") if !is_meta => signal.user += 1,
+                Some("assistant") if !is_meta => signal.assistant += 1,
+                _ => signal.meta += 1,
+            }
+        }
+        signal
+    }
+
+    /// User and assistant messages
+    pub fn messages(&self) -> usize {
+        self.user + self.assistant
+    }
+}
+
+/// A session under its provider's thresholds
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LowSignal {
+    pub signal: SessionSignal,
+    pub thresholds: QualityThresholds,
+}
+
+impl fmt::Display for LowSignal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let SessionSignal {
+            user,
+            assistant,
+            meta,
+        } = self.signal;
+        write!(
+            f,
+            "skipped: low signal ({} user, {} assistant and {} meta messages; needs {} messages, {} from the assistant)",
+            user,
+            assistant,
+            meta,
+            self.thresholds.min_messages,
+            self.thresholds.min_assistant_messages
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(message_type: &str, is_meta: bool) -> String {
+        serde_json::json!({ "type": message_type, "isMeta": is_meta }).to_string() + "\n"
+    }
+
+    #[test]
+    fn test_counts_messages_by_kind() {
+        let content = [
+            line("user", false),
+            line("user", true),
+            line("assistant", false),
+            line("meta", false),
+        ]
+        .concat()
+            + "not json\n";
+        assert_eq!(
+            SessionSignal::of(&content),
+            SessionSignal {
+                user: 1,
+                assistant: 1,
+                meta: 2,
+            }
+        );
+    }
+
+    #[test]
+    fn test_low_signal_sessions() {
+        let thresholds = QualityThresholds::default();
+        let exchange = line("user", false) + &line("assistant", false);
+        assert_eq!(thresholds.check(&exchange), None);
+
+        // No answer, or nothing but meta noise
+        let unanswered = line("user", false) + &line("user", false);
+        assert_eq!(thresholds.check(&unanswered).unwrap().signal.assistant, 0);
+        let meta_only = line("meta", false).repeat(10);
+        assert!(thresholds.check(&meta_only).is_some());
+
+        let stricter = thresholds.with_overrides(&QualityGate {
+            enabled: true,
+            min_messages: Some(4),
+            min_assistant_messages: None,
+        });
+        let low = stricter.check(&exchange).unwrap();
+        assert!(low.to_string().starts_with("skipped: low signal (1 user"));
+    }
+}
@@ -8,19 +8,28 @@ use crate::database::{
     remove_session_by_id, retry_failed_sessions, retry_session_by_id,
 };
 use crate::logging::{log_info, log_warn};
-use crate::project_metadata::extract_project_metadata;
+use crate::project_metadata::extract_session_file_project_metadata;
+use crate::project_sync::is_cwd_excluded;
+use crate::providers::common::ingest_log::{clear_skip, record_skip, SkipReason};
 use crate::providers::SessionInfo;
-use crate::validation::{validate_session_file, MAX_SESSION_FILE_SIZE};
-use chrono::Utc;
+#[cfg(test)]
+use crate::validation::validate_session_file;
+use crate::validation::{validate_file_path, MAX_SESSION_FILE_SIZE};
+use chrono::{DateTime, Utc};
 use indexmap::IndexSet;
-use std::collections::VecDeque;
-use std::path::PathBuf;
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 use uuid::Uuid;
 
+use super::concurrency::AdaptiveConcurrency;
+use super::hash_store;
 use super::hashing::{calculate_content_hash_sha256, calculate_file_hash_sha256};
 use super::types::{QueueItems, UploadItem, UploadStatus};
+use super::upload::rate_limited_until;
+#[cfg(test)]
 use super::validation::validate_jsonl_timestamps;
+use super::validation::EnqueueRules;
 
 /// Add a file-based upload item to the queue (test only)
 #[cfg(test)]
@@ -87,6 +96,10 @@ pub fn add_item(
         session_id: None,
         content: None,
         cwd: None,
+        priority: 0,
+        last_attempt_at: None,
+        superseded: false,
+        quality_checked: false,
     };
 
     if let Ok(mut queue) = queue.lock() {
@@ -103,73 +116,76 @@ pub fn add_historical_session(
     _config: &Arc<Mutex<Option<GuideModeConfig>>>,
     session: &SessionInfo,
 ) -> Result<(), String> {
-    // Handle sessions with in-memory content vs file-based sessions differently
-    let (file_hash, file_size, content): (String, u64, Option<String>) =
-        if let Some(ref content) = session.content {
-            // For sessions with in-memory content (like OpenCode), validate size and timestamps
-            let content_size = content.len() as u64;
-
-            // Check size limit
-            if content_size > MAX_SESSION_FILE_SIZE {
-                let reason = format!(
-                    "content size ({} bytes) exceeds maximum ({} bytes)",
-                    content_size, MAX_SESSION_FILE_SIZE
-                );
-                log_warn(
-                    "upload-queue",
-                    &format!(
-                        "⚠ Skipping historical upload: {} ({})",
-                        session.file_name, reason
-                    ),
-                )
-                .unwrap_or_default();
-                return Ok(());
-            }
+    // Left out sessions are recorded for the provider's ingest stats
+    let skip = |reason: SkipReason, detail: &str| {
+        record_skip(
+            &session.provider,
+            &session.file_path,
+            Some(&session.session_id),
+            reason,
+            detail,
+        )
+    };
 
-            let (is_valid, validation_error) = validate_jsonl_timestamps(content);
-            if !is_valid {
-                let reason =
-                    validation_error.unwrap_or_else(|| "no valid timestamps found".to_string());
-                log_warn(
-                    "upload-queue",
-                    &format!(
-                        "⚠ Skipping historical upload: {} ({})",
-                        session.file_name, reason
-                    ),
-                )
-                .unwrap_or_default();
-                return Ok(());
-            }
+    // Whether the content passes the provider's enqueue validation
+    let rules = EnqueueRules::for_provider(&session.provider);
+    if rules.bypass {
+        log_info(
+            "upload-queue",
+            &format!(
+                "⚠ Enqueue validation bypassed for {} ({})",
+                session.file_name, session.provider
+            ),
+        )
+        .unwrap_or_default();
+    }
+    let passes = |content: &str| match rules.check(content) {
+        Ok(()) => true,
+        Err(violation) => {
+            log_warn(
+                "upload-queue",
+                &format!(
+                    "⚠ Skipping historical upload: {} ({})",
+                    session.file_name, violation
+                ),
+            )
+            .unwrap_or_default();
+            skip(violation.skip_reason(), &violation.to_string());
+            false
+        }
+    };
 
-            let content_hash = calculate_content_hash_sha256(content);
-            (content_hash, content_size, Some(content.clone()))
-        } else {
-            // For file-based sessions, validate path and check file size
-            let (validated_path, file_size) =
-                validate_session_file(&session.file_path).map_err(|e| e.to_string())?;
-
-            // Read and validate content
-            let file_content = std::fs::read_to_string(&validated_path)
-                .map_err(|e| format!("Failed to read file: {}", e))?;
-
-            let (is_valid, validation_error) = validate_jsonl_timestamps(&file_content);
-            if !is_valid {
-                let reason =
-                    validation_error.unwrap_or_else(|| "no valid timestamps found".to_string());
-                log_warn(
-                    "upload-queue",
-                    &format!(
-                        "⚠ Skipping historical upload: {} ({})",
-                        session.file_name, reason
-                    ),
-                )
-                .unwrap_or_default();
-                return Ok(());
-            }
+    // Handle sessions with in-memory content vs file-based sessions differently
+    let (file_hash, file_size, content): (String, u64, Option<String>) = if let Some(ref content) =
+        session.content
+    {
+        // For sessions with in-memory content (like OpenCode), validate the content
+        let content_size = content.len() as u64;
+
+        if !passes(content) {
+            return Ok(());
+        }
 
-            let file_hash = calculate_file_hash_sha256(&validated_path)?;
-            (file_hash, file_size, None)
-        };
+        let content_hash = calculate_content_hash_sha256(content);
+        (content_hash, content_size, Some(content.clone()))
+    } else {
+        // For file-based sessions, validate path; oversized files are uploaded truncated
+        let validated_path = validate_file_path(&session.file_path).map_err(|e| e.to_string())?;
+        let file_size = std::fs::metadata(&validated_path)
+            .map_err(|e| format!("Failed to get file metadata: {}", e))?
+            .len();
+
+        // Read and validate content
+        let file_content = std::fs::read_to_string(&validated_path)
+            .map_err(|e| format!("Failed to read file: {}", e))?;
+
+        if !passes(&file_content) {
+            return Ok(());
+        }
+
+        let file_hash = calculate_file_hash_sha256(&validated_path)?;
+        (file_hash, file_size, None)
+    };
 
     // Check if this file has already been uploaded
     if is_file_already_uploaded(uploaded_hashes, &file_hash) {
@@ -184,6 +200,52 @@ pub fn add_historical_session(
         return Ok(());
     }
 
+    if !crate::tenant::belongs_to_active_tenant(&session.session_id) {
+        log_info(
+            "upload-queue",
+            &format!(
+                "⏭ Skipping historical upload: {} (belongs to another tenant)",
+                session.file_name
+            ),
+        )
+        .unwrap_or_default();
+        return Ok(());
+    }
+
+    if let Some(cwd) = session.cwd.as_deref().filter(|cwd| is_cwd_excluded(cwd)) {
+        log_info(
+            "upload-queue",
+            &format!(
+                "⏭ Skipping historical upload: {} (project at {} is excluded from sync)",
+                session.file_name, cwd
+            ),
+        )
+        .unwrap_or_default();
+        skip(
+            SkipReason::FilteredProject,
+            &format!("Project at {} is excluded from sync", cwd),
+        );
+        return Ok(());
+    }
+
+    if file_size > MAX_SESSION_FILE_SIZE {
+        let detail = format!(
+            "{} bytes, over the {} byte upload limit; uploaded as its first and last messages",
+            file_size, MAX_SESSION_FILE_SIZE
+        );
+        log_warn(
+            "upload-queue",
+            &format!(
+                "⚠ Historical session {} is too large to upload whole ({})",
+                session.file_name, detail
+            ),
+        )
+        .unwrap_or_default();
+        skip(SkipReason::TooLarge, &detail);
+    } else {
+        clear_skip(&session.provider, &session.file_path);
+    }
+
     // Extract project metadata if CWD is available (will be embedded in upload payload)
     let real_project_name = if let Some(ref cwd) = session.cwd {
         log_info(
@@ -195,7 +257,11 @@ pub fn add_historical_session(
         )
         .unwrap_or_default();
 
-        match extract_project_metadata(cwd) {
+        match extract_session_file_project_metadata(
+            &session.provider,
+            cwd,
+            Path::new(&session.file_path),
+        ) {
             Ok(metadata) => {
                 log_info("upload-queue", &format!("✓ Extracted project metadata: {} (type: {}, git: {}) - will embed in upload payload",
                     metadata.project_name,
@@ -222,7 +288,9 @@ pub fn add_historical_session(
     };
 
     // Use the real project name if available, otherwise fall back to Claude folder name
-    let project_name_for_upload = real_project_name.unwrap_or_else(|| session.project_name.clone());
+    let project_name_for_upload = crate::project_aliases::canonical_project_name(
+        &real_project_name.unwrap_or_else(|| session.project_name.clone()),
+    );
 
     log_info(
         "upload-queue",
@@ -248,6 +316,10 @@ pub fn add_historical_session(
         session_id: Some(session.session_id.clone()),
         content,
         cwd: session.cwd.clone(),
+        priority: 0,
+        last_attempt_at: None,
+        superseded: false,
+        quality_checked: false,
     };
 
     if let Ok(mut queue) = queue.lock() {
@@ -314,6 +386,10 @@ pub fn add_session_content(
         session_id: Some(session_id.to_string()),
         content: Some(content),
         cwd: None,
+        priority: 0,
+        last_attempt_at: None,
+        superseded: false,
+        quality_checked: false,
     };
 
     if let Ok(mut queue) = queue.lock() {
@@ -324,7 +400,10 @@ pub fn add_session_content(
 }
 
 /// Get upload queue status
-pub fn get_status(processing: &Arc<Mutex<usize>>) -> UploadStatus {
+pub fn get_status(
+    processing: &Arc<Mutex<usize>>,
+    concurrency: &AdaptiveConcurrency,
+) -> UploadStatus {
     // Get real-time stats from database instead of in-memory queue
     let db_stats = get_upload_stats().unwrap_or(crate::database::UploadStats {
         pending: 0,
@@ -351,30 +430,50 @@ pub fn get_status(processing: &Arc<Mutex<usize>>) -> UploadStatus {
         processing: processing_count,
         failed,
         recent_uploads,
+        rate_limited_until: rate_limited_until(),
+        concurrency: concurrency.current(),
+        max_concurrency: concurrency.max(),
     }
 }
 
 /// Get all queue items (pending and failed)
-pub fn get_all_items() -> QueueItems {
+///
+/// Retry times come from the in-memory queue; everything else is tracked in
+/// the database.
+pub fn get_all_items(queue: &Arc<Mutex<VecDeque<UploadItem>>>) -> QueueItems {
+    let retry_times: HashMap<String, DateTime<Utc>> = queue
+        .lock()
+        .map(|queue| {
+            queue
+                .iter()
+                .filter_map(|item| Some((item.id.clone(), item.next_retry_at?)))
+                .collect()
+        })
+        .unwrap_or_default();
+
     // Get pending items from database (unsynced sessions)
     let pending = if let Ok(unsynced_sessions) = get_unsynced_sessions() {
         unsynced_sessions
             .into_iter()
             .map(|session| UploadItem {
+                next_retry_at: retry_times.get(&session.id).copied(),
                 id: session.id,
                 provider: session.provider,
                 project_name: session.project_name,
                 file_path: PathBuf::from(&session.file_path),
                 file_name: session.file_name,
-                queued_at: Utc::now(), // Use current time as approximation
-                retry_count: 0,
-                next_retry_at: None,
+                queued_at: from_millis(session.upload.queued_at),
+                retry_count: session.upload.attempts,
                 last_error: None,
                 file_hash: None,
                 file_size: session.file_size as u64,
                 session_id: Some(session.session_id),
                 content: None,
                 cwd: session.cwd,
+                priority: session.upload.priority,
+                last_attempt_at: session.upload.last_attempt_at.map(from_millis),
+                superseded: session.upload.superseded_at.is_some(),
+                quality_checked: false,
             })
             .collect()
     } else {
@@ -391,8 +490,8 @@ pub fn get_all_items() -> QueueItems {
                 project_name: session.project_name,
                 file_path: PathBuf::from(&session.file_path),
                 file_name: session.file_name,
-                queued_at: Utc::now(), // Use current time as approximation
-                retry_count: 3,        // Max retries exceeded
+                queued_at: from_millis(session.upload.queued_at),
+                retry_count: session.upload.attempts,
                 next_retry_at: None,
                 last_error: Some(session.sync_failed_reason),
                 file_hash: None,
@@ -400,6 +499,10 @@ pub fn get_all_items() -> QueueItems {
                 session_id: Some(session.session_id),
                 content: None,
                 cwd: session.cwd,
+                priority: session.upload.priority,
+                last_attempt_at: session.upload.last_attempt_at.map(from_millis),
+                superseded: session.upload.superseded_at.is_some(),
+                quality_checked: false,
             })
             .collect()
     } else {
@@ -409,6 +512,10 @@ pub fn get_all_items() -> QueueItems {
     QueueItems { pending, failed }
 }
 
+fn from_millis(millis: i64) -> DateTime<Utc> {
+    DateTime::from_timestamp_millis(millis).unwrap_or_else(Utc::now)
+}
+
 /// Remove an item from the queue by ID
 pub fn remove_item(item_id: &str) -> Result<(), String> {
     // Remove session from database by ID
@@ -462,15 +569,18 @@ pub fn find_ready_item(queue: &mut VecDeque<UploadItem>) -> Option<UploadItem> {
 }
 
 /// Check if a file hash has already been uploaded
+///
+/// Consults the in-memory cache first, then the hashes persisted by earlier runs.
 pub fn is_file_already_uploaded(
     uploaded_hashes: &Arc<Mutex<IndexSet<String>>>,
     file_hash: &str,
 ) -> bool {
-    if let Ok(uploaded_hashes) = uploaded_hashes.lock() {
+    let cached = if let Ok(uploaded_hashes) = uploaded_hashes.lock() {
         uploaded_hashes.contains(file_hash)
     } else {
         false
-    }
+    };
+    cached || hash_store::is_uploaded(file_hash)
 }
 
 // DEPRECATED: This function is no longer used. Project metadata is now embedded in
@@ -497,7 +607,7 @@ async fn _upload_project_metadata_static_legacy(
 
     // Make HTTP POST request to server
     let client = reqwest::Client::new();
-    let url = format!("{}/api/projects", server_url);
+    let url = crate::api_paths::api_url(&server_url, crate::api_paths::PROJECTS);
 
     let response = client
         .post(&url)
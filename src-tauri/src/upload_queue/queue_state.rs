@@ -0,0 +1,182 @@
+//! Upload queue metadata kept per session.
+//!
+//! Each session's place in the queue lives in `agent_sessions`: when it first
+//! entered the queue, how many uploads were attempted and when the last one
+//! was, and its priority, which moves it ahead of sessions queued earlier.
+//!
+//! Queued sessions can also be reassigned to another project or working
+//! directory before they're retried, to fix misattributed sessions. Sessions
+//! with a working directory take their project from it at upload, so for
+//! those the working directory is what decides the uploaded project.
+
+use crate::database::with_connection_mut;
+use crate::logging::log_warn;
+use crate::metrics::store::record_session_usage;
+use crate::project_aliases;
+use chrono::Utc;
+use rusqlite::{params, Connection};
+
+/// Note that sessions entered the queue, keeping the time each first did
+pub fn record_queued(session_ids: &[String]) {
+    let now = Utc::now().timestamp_millis();
+    if let Err(e) = with_connection_mut(|conn| queued(conn, session_ids, now)) {
+        log_warn(
+            "upload-queue",
+            &format!("⚠ Failed to record queued sessions: {}", e),
+        )
+        .unwrap_or_default();
+    }
+}
+
+/// Count an upload attempt for a session
+pub fn record_attempt(session_id: &str) {
+    let now = Utc::now().timestamp_millis();
+    if let Err(e) = with_connection_mut(|conn| attempt(conn, session_id, now)) {
+        log_warn(
+            "upload-queue",
+            &format!(
+                "⚠ Failed to record upload attempt for {}: {}",
+                session_id, e
+            ),
+        )
+        .unwrap_or_default();
+    }
+}
+
+/// Set the priority of a queue item; higher priorities upload first
+pub fn set_priority(item_id: &str, priority: i64) -> Result<(), String> {
+    with_connection_mut(|conn| {
+        conn.execute(
+            "UPDATE agent_sessions SET upload_priority = ? WHERE id = ? AND synced_to_server = 0",
+            params![priority, item_id],
+        )
+    })
+    .map_err(|e| format!("Failed to set priority: {}", e))
+    .and_then(|rows| found(rows, item_id))
+}
+
+/// Move a queue item to another project, returning the name it was given
+///
+/// Aliased names are resolved to their canonical project, and the session's
+/// usage moves to that project.
+pub fn set_project(item_id: &str, project_name: &str) -> Result<String, String> {
+    let project_name = project_name.trim();
+    if project_name.is_empty() {
+        return Err("Project name can't be empty".to_string());
+    }
+
+    let (session_id, project_name) = with_connection_mut(|conn| {
+        let project_name = project_aliases::resolve(conn, project_name);
+        let rows = conn.execute(
+            "UPDATE agent_sessions SET project_name = ? WHERE id = ? AND synced_to_server = 0",
+            params![project_name, item_id],
+        )?;
+        let session_id = (rows > 0)
+            .then(|| {
+                conn.query_row(
+                    "SELECT session_id FROM agent_sessions WHERE id = ?",
+                    params![item_id],
+                    |row| row.get::<_, String>(0),
+                )
+            })
+            .transpose()?;
+        Ok((session_id, project_name))
+    })
+    .map_err(|e| format!("Failed to set project: {}", e))?;
+
+    let session_id = session_id.ok_or_else(|| not_found(item_id))?;
+    record_session_usage(&session_id, None)
+        .map_err(|e| format!("Failed to move session usage: {}", e))?;
+    Ok(project_name)
+}
+
+/// Change the working directory of a queue item, or clear it
+pub fn set_cwd(item_id: &str, cwd: Option<&str>) -> Result<(), String> {
+    let cwd = cwd.map(str::trim).filter(|cwd| !cwd.is_empty());
+    with_connection_mut(|conn| {
+        conn.execute(
+            "UPDATE agent_sessions SET cwd = ? WHERE id = ? AND synced_to_server = 0",
+            params![cwd, item_id],
+        )
+    })
+    .map_err(|e| format!("Failed to set working directory: {}", e))
+    .and_then(|rows| found(rows, item_id))
+}
+
+fn queued(conn: &mut Connection, session_ids: &[String], now: i64) -> rusqlite::Result<()> {
+    let tx = conn.transaction()?;
+    {
+        let mut stmt = tx.prepare(
+            "UPDATE agent_sessions SET upload_queued_at = ?
+             WHERE session_id = ? AND upload_queued_at IS NULL",
+        )?;
+        for session_id in session_ids {
+            stmt.execute(params![now, session_id])?;
+        }
+    }
+    tx.commit()
+}
+
+fn attempt(conn: &Connection, session_id: &str, now: i64) -> rusqlite::Result<usize> {
+    conn.execute(
+        "UPDATE agent_sessions
+         SET upload_attempts = upload_attempts + 1, upload_last_attempt_at = ?,
+             upload_queued_at = COALESCE(upload_queued_at, ?)
+         WHERE session_id = ?",
+        params![now, now, session_id],
+    )
+}
+
+fn found(rows: usize, item_id: &str) -> Result<(), String> {
+    if rows > 0 {
+        Ok(())
+    } else {
+        Err(not_found(item_id))
+    }
+}
+
+fn not_found(item_id: &str) -> String {
+    format!("Queue item {} not found or already synced", item_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_queue_state_is_tracked() {
+        let mut conn = crate::database::test_connection();
+        conn.execute(
+            "INSERT INTO agent_sessions (
+                id, provider, project_name, session_id, file_name, file_path, file_size,
+                created_at, uploaded_at
+            ) VALUES ('id1', 'claude-code', 'app', 's1', 's1.jsonl', '/s1', 10, 5, 0)",
+            [],
+        )
+        .unwrap();
+        let state = |conn: &Connection| {
+            conn.query_row(
+                "SELECT upload_queued_at, upload_attempts, upload_last_attempt_at
+                 FROM agent_sessions WHERE id = 'id1'",
+                [],
+                |row| {
+                    Ok((
+                        row.get::<_, Option<i64>>(0)?,
+                        row.get::<_, i64>(1)?,
+                        row.get::<_, Option<i64>>(2)?,
+                    ))
+                },
+            )
+            .unwrap()
+        };
+
+        // Queued again later keeps the time it was first queued
+        queued(&mut conn, &["s1".to_string()], 100).unwrap();
+        queued(&mut conn, &["s1".to_string()], 200).unwrap();
+        assert_eq!(state(&conn), (Some(100), 0, None));
+
+        attempt(&conn, "s1", 300).unwrap();
+        attempt(&conn, "s1", 400).unwrap();
+        assert_eq!(state(&conn), (Some(100), 2, Some(400)));
+    }
+}
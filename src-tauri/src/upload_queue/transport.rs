@@ -0,0 +1,57 @@
+//! How the processor reaches the server.
+//!
+//! The processor sends everything through an `UploadTransport`, so tests can
+//! run the queue against a mock instead of a live server. `HttpTransport` is
+//! the real one: it routes each item by sync mode and upload API (see
+//! `upload::process_upload_item`).
+
+use super::types::UploadItem;
+use super::upload::{process_upload_item, upload_metadata_patch, UploadOutcome};
+use crate::config::GuideModeConfig;
+use crate::database::DirtyMetadataSession;
+use crate::error::GuideModeError;
+use std::future::Future;
+use std::pin::Pin;
+
+/// What an `UploadTransport` returns; errors are classified for retries by
+/// variant, e.g. a `GuideModeError::Status` of 503 is retried with backoff
+pub type TransportFuture<'a, T = ()> =
+    Pin<Box<dyn Future<Output = Result<T, GuideModeError>> + Send + 'a>>;
+
+pub trait UploadTransport: Send + Sync {
+    /// Upload a queued session, reporting whether its transcript went along
+    fn upload<'a>(
+        &'a self,
+        item: &'a UploadItem,
+        config: Option<GuideModeConfig>,
+    ) -> TransportFuture<'a, UploadOutcome>;
+
+    /// Send rating and assessment changes of a session that already synced
+    fn patch_metadata<'a>(
+        &'a self,
+        session: &'a DirtyMetadataSession,
+        config: &'a GuideModeConfig,
+    ) -> TransportFuture<'a>;
+}
+
+/// Uploads to the configured server over HTTP
+#[derive(Debug, Default, Clone, Copy)]
+pub struct HttpTransport;
+
+impl UploadTransport for HttpTransport {
+    fn upload<'a>(
+        &'a self,
+        item: &'a UploadItem,
+        config: Option<GuideModeConfig>,
+    ) -> TransportFuture<'a, UploadOutcome> {
+        Box::pin(process_upload_item(item, config))
+    }
+
+    fn patch_metadata<'a>(
+        &'a self,
+        session: &'a DirtyMetadataSession,
+        config: &'a GuideModeConfig,
+    ) -> TransportFuture<'a> {
+        Box::pin(upload_metadata_patch(session, config))
+    }
+}
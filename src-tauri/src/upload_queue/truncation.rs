@@ -0,0 +1,112 @@
+//! Head and tail of transcripts too large to upload whole.
+//!
+//! Sessions larger than `MAX_SESSION_FILE_SIZE` used to be skipped. They are
+//! now uploaded as their first and last lines, up to half the limit each,
+//! with a record of what was left out between them. Metrics are computed
+//! locally from the whole session and sent as usual, and the upload is
+//! flagged as truncated so the server can tell the transcript is incomplete.
+//! Lines are kept whole, so every line sent is a complete message.
+
+use crate::validation::MAX_SESSION_FILE_SIZE;
+use serde::Serialize;
+
+/// What a truncated upload left out
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Truncation {
+    /// Size of the whole transcript, in bytes
+    pub original_size: u64,
+    pub head_lines: usize,
+    pub tail_lines: usize,
+    /// Lines between the head and the tail that weren't sent
+    pub omitted_lines: usize,
+    pub omitted_bytes: u64,
+}
+
+/// Whether a transcript is too large to upload whole
+pub fn is_oversized(content: &[u8]) -> bool {
+    content.len() as u64 > MAX_SESSION_FILE_SIZE
+}
+
+/// The first and last lines of `content` fitting in `max_bytes`, or `None`
+/// when it fits whole
+pub fn truncate_transcript(content: &[u8], max_bytes: usize) -> Option<(Vec<u8>, Truncation)> {
+    if content.len() <= max_bytes {
+        return None;
+    }
+    let lines: Vec<&[u8]> = content
+        .split(|&b| b == b'\n')
+        .filter(|line| !line.is_empty())
+        .collect();
+    let budget = max_bytes / 2;
+
+    // Lines that fit in the budget, counting the newline after each
+    let fitting = |lines: &mut dyn Iterator<Item = &&[u8]>| {
+        let mut used = 0;
+        lines
+            .take_while(|line| {
+                used += line.len() + 1;
+                used <= budget
+            })
+            .count()
+    };
+    let head_lines = fitting(&mut lines.iter());
+    let tail_lines = fitting(&mut lines[head_lines..].iter().rev());
+    let tail_start = lines.len() - tail_lines;
+
+    let mut kept = Vec::with_capacity(max_bytes);
+    for line in lines[..head_lines].iter().chain(&lines[tail_start..]) {
+        kept.extend_from_slice(line);
+        kept.push(b'\n');
+    }
+    let omitted = &lines[head_lines..tail_start];
+    let truncation = Truncation {
+        original_size: content.len() as u64,
+        head_lines,
+        tail_lines,
+        omitted_lines: omitted.len(),
+        omitted_bytes: omitted.iter().map(|line| line.len() as u64 + 1).sum(),
+    };
+    Some((kept, truncation))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_small_content_is_kept() {
+        assert!(truncate_transcript(b"{\"a\":1}\n{\"b\":2}\n", 100).is_none());
+    }
+
+    #[test]
+    fn test_keeps_whole_head_and_tail_lines() {
+        let content: String = (0..10).map(|i| format!("line-{}\n", i)).collect();
+        let (kept, truncation) = truncate_transcript(content.as_bytes(), 30).unwrap();
+
+        // Each line is 7 bytes with its newline, so two fit in each half
+        assert_eq!(
+            String::from_utf8(kept).unwrap(),
+            "line-0\nline-1\nline-8\nline-9\n"
+        );
+        assert_eq!(
+            truncation,
+            Truncation {
+                original_size: 70,
+                head_lines: 2,
+                tail_lines: 2,
+                omitted_lines: 6,
+                omitted_bytes: 42,
+            }
+        );
+    }
+
+    #[test]
+    fn test_line_larger_than_half_is_left_out() {
+        let content = format!("{}\nshort\n", "x".repeat(50));
+        let (kept, truncation) = truncate_transcript(content.as_bytes(), 40).unwrap();
+        assert_eq!(kept, b"short\n");
+        assert_eq!(truncation.head_lines, 0);
+        assert_eq!(truncation.omitted_lines, 1);
+    }
+}
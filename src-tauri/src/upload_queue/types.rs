@@ -6,11 +6,23 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
-// Database polling interval (10 seconds by default, configurable later)
+// Database polling interval when dbPollIntervalSecs isn't configured
 pub const DB_POLL_INTERVAL_SECS: u64 = 10;
 
-// Maximum number of concurrent uploads (can be tuned based on system performance)
-pub const MAX_CONCURRENT_UPLOADS: usize = 3;
+// Longest interval idle database polls back off to
+pub const MAX_IDLE_DB_POLL_INTERVAL_SECS: u64 = 300;
+
+// Metadata patches sent per database poll
+pub const METADATA_PATCHES_PER_POLL: usize = 20;
+
+// Concurrent uploads when maxConcurrentUploads isn't configured
+pub const DEFAULT_CONCURRENT_UPLOADS: usize = 3;
+
+// Most concurrent uploads allowed, whatever is configured
+pub const MAX_CONCURRENT_UPLOADS: usize = 10;
+
+// How long quitting waits for in-flight uploads before giving up on them
+pub const SHUTDOWN_UPLOAD_TIMEOUT_SECS: u64 = 10;
 
 // Maximum number of uploaded hashes to cache (prevents unbounded memory growth)
 // Each hash is ~64 bytes, so 10,000 hashes = ~640KB
@@ -35,6 +47,18 @@ pub struct UploadItem {
     pub content: Option<String>,
     // Working directory for project metadata extraction
     pub cwd: Option<String>,
+    // Higher priorities upload first
+    #[serde(default)]
+    pub priority: i64,
+    // When an upload of the session was last attempted, across restarts
+    #[serde(default)]
+    pub last_attempt_at: Option<DateTime<Utc>>,
+    // Whether this replaces an upload the session's file changed after
+    #[serde(default)]
+    pub superseded: bool,
+    // Whether the quality gate passed it, so retries don't read it again
+    #[serde(default)]
+    pub quality_checked: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -43,6 +67,11 @@ pub struct UploadStatus {
     pub processing: usize,
     pub failed: usize,
     pub recent_uploads: Vec<UploadItem>,
+    /// When the server's rate limit lifts, while uploads are paused for it
+    pub rate_limited_until: Option<DateTime<Utc>>,
+    /// Uploads allowed at once right now, lowered while the server struggles
+    pub concurrency: usize,
+    pub max_concurrency: usize,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -0,0 +1,95 @@
+//! Sessions uploaded from more than one machine.
+//!
+//! Machines that share a cloud-synced provider directory each convert and
+//! upload the same sessions. Identical copies hash the same, so the server's
+//! hash check already skips them. Copies differ when one machine hasn't
+//! received the latest sync yet; those conflicts are resolved here by keeping
+//! the more complete version: the one with more messages, or on a tie the one
+//! whose last message is newer. A full tie keeps the server's copy so two
+//! machines never keep replacing each other's uploads.
+
+use chrono::{DateTime, Utc};
+
+/// How complete a copy of a session is
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionVersion {
+    pub message_count: usize,
+    pub last_message_at: Option<DateTime<Utc>>,
+}
+
+impl SessionVersion {
+    /// Version of canonical JSONL content
+    pub fn of_content(content: &str) -> Self {
+        let mut lines = content.lines().filter(|line| !line.trim().is_empty());
+        let last_line = lines.next_back();
+        let last_message_at = last_line.and_then(|line| {
+            serde_json::from_str::<serde_json::Value>(line)
+                .ok()?
+                .get("timestamp")?
+                .as_str()
+                .and_then(|ts| DateTime::parse_from_rfc3339(ts).ok())
+                .map(|dt| dt.with_timezone(&Utc))
+        });
+        let message_count = lines.count() + usize::from(last_line.is_some());
+        Self {
+            message_count,
+            last_message_at,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resolution {
+    /// The local copy is more complete; upload it
+    Upload,
+    /// The server's copy is at least as complete; leave it
+    KeepRemote,
+}
+
+/// Decide between the local copy of a session and one another machine uploaded
+pub fn resolve(local: &SessionVersion, remote: &SessionVersion) -> Resolution {
+    let local_key = (local.message_count, local.last_message_at);
+    let remote_key = (remote.message_count, remote.last_message_at);
+    if local_key > remote_key {
+        Resolution::Upload
+    } else {
+        Resolution::KeepRemote
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version(message_count: usize, last_message_at: Option<&str>) -> SessionVersion {
+        SessionVersion {
+            message_count,
+            last_message_at: last_message_at.map(|ts| ts.parse().unwrap()),
+        }
+    }
+
+    #[test]
+    fn test_of_content() {
+        let content = "{\"timestamp\":\"2025-01-01T10:00:00Z\"}\n\n{\"timestamp\":\"2025-01-01T10:05:00Z\"}\n";
+        assert_eq!(
+            SessionVersion::of_content(content),
+            version(2, Some("2025-01-01T10:05:00Z"))
+        );
+        assert_eq!(SessionVersion::of_content(""), version(0, None));
+    }
+
+    #[test]
+    fn test_resolve_prefers_more_complete_copy() {
+        let partial = version(10, Some("2025-01-01T10:05:00Z"));
+        let complete = version(12, Some("2025-01-01T10:09:00Z"));
+        assert_eq!(resolve(&complete, &partial), Resolution::Upload);
+        assert_eq!(resolve(&partial, &complete), Resolution::KeepRemote);
+
+        // Same length, diverged: the newer last message wins
+        let newer = version(10, Some("2025-01-01T10:06:00Z"));
+        assert_eq!(resolve(&newer, &partial), Resolution::Upload);
+
+        // A full tie leaves the server's copy alone
+        assert_eq!(resolve(&partial, &partial.clone()), Resolution::KeepRemote);
+    }
+}
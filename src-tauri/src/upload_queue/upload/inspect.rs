@@ -0,0 +1,256 @@
+//! Inspecting upload payloads
+//!
+//! Builds the requests the queue would send for a session under its
+//! provider's sync mode, without sending anything, so users can see what
+//! leaves their machine. The encoded transcript is replaced by a summary and
+//! a preview of the text it encodes, and other long strings are truncated.
+//!
+//! The server isn't asked whether it already holds the transcript; a real
+//! upload leaves the content out when it does.
+
+use super::conflict::SessionVersion;
+use super::metrics::{build_metrics_request, build_session_request, read_canonical_content};
+use super::negotiation::{self, UploadApi};
+use super::v2::{build_upload_request, delta_base_for, upload_content, PreparedContent};
+use crate::api_paths;
+use crate::config::{get_machine_id, load_config};
+use crate::database::{get_full_session_by_id, get_session_metrics};
+use crate::server_health;
+use crate::upload_queue::embedded::cap_embedded_data;
+use crate::upload_queue::hashing::calculate_bytes_hash_sha256;
+use crate::upload_queue::types::UploadItem;
+use crate::workspace_roots::effective_sync_mode;
+use chrono::Utc;
+use serde::Serialize;
+use serde_json::Value;
+use std::path::PathBuf;
+
+/// Strings longer than this are truncated in the inspected bodies
+const MAX_STRING_CHARS: usize = 2000;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InspectedRequest {
+    pub endpoint: String,
+    pub body: Value,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UploadPayloadInspection {
+    pub session_id: String,
+    pub provider: String,
+    pub sync_mode: String,
+    /// Requests in the order they would be sent; none when nothing syncs
+    pub requests: Vec<InspectedRequest>,
+    /// How a real upload could differ from these requests
+    pub notes: Vec<String>,
+}
+
+/// Build the requests uploading `session_id` would send right now
+pub fn inspect_upload_payload(session_id: &str) -> Result<UploadPayloadInspection, String> {
+    let session = get_full_session_by_id(session_id)
+        .map_err(|e| format!("Failed to get session data: {}", e))?
+        .ok_or_else(|| format!("Session {} not found in database", session_id))?;
+    let sync_mode = effective_sync_mode(&session.provider, session.cwd.as_deref())
+        .ok_or("Failed to load provider config")?;
+
+    let item = UploadItem {
+        id: uuid::Uuid::new_v4().to_string(),
+        provider: session.provider.clone(),
+        project_name: session.project_name.clone(),
+        file_path: PathBuf::from(&session.file_path),
+        file_name: session.file_name.clone(),
+        queued_at: Utc::now(),
+        retry_count: 0,
+        next_retry_at: None,
+        last_error: None,
+        file_hash: None,
+        file_size: session.file_size as u64,
+        session_id: Some(session_id.to_string()),
+        content: None,
+        cwd: session.cwd.clone(),
+        priority: 0,
+        last_attempt_at: None,
+        superseded: false,
+        quality_checked: false,
+    };
+
+    // Only an API already negotiated is used; inspecting doesn't contact the server
+    let server_url = load_config()
+        .ok()
+        .and_then(|config| config.server_url)
+        .unwrap_or_default();
+    let api = negotiation::cached(&server_url);
+
+    let mut notes = Vec::new();
+    let requests = match sync_mode.as_str() {
+        "Metrics Only" => metrics_only_requests(&item, session_id)?,
+        "Transcript and Metrics" if !server_health::schema_compatible(&server_url) => {
+            notes.push(
+                "The server can't accept transcripts from this app, so only metrics are uploaded"
+                    .to_string(),
+            );
+            metrics_only_requests(&item, session_id)?
+        }
+        "Transcript and Metrics" if api == Some(UploadApi::V1) => {
+            notes.push(
+                "The server only accepts uploads without transcripts, so only metrics are uploaded"
+                    .to_string(),
+            );
+            metrics_only_requests(&item, session_id)?
+        }
+        "Transcript and Metrics" => {
+            notes.push(
+                "The transcript is left out when the server already holds this exact file"
+                    .to_string(),
+            );
+            if api.is_none() {
+                notes.push(
+                    "No upload API has been negotiated with the server yet; shown as a full upload"
+                        .to_string(),
+                );
+            }
+            vec![transcript_request(
+                &item,
+                session_id,
+                api == Some(UploadApi::Delta),
+            )?]
+        }
+        mode => {
+            notes.push(format!("Sync mode is '{}', so nothing is uploaded", mode));
+            Vec::new()
+        }
+    };
+
+    Ok(UploadPayloadInspection {
+        session_id: session_id.to_string(),
+        provider: session.provider,
+        sync_mode,
+        requests: requests
+            .into_iter()
+            .map(|(endpoint, mut body)| {
+                truncate_strings(&mut body);
+                InspectedRequest {
+                    endpoint: api_paths::api_path(&server_url, endpoint),
+                    body,
+                }
+            })
+            .collect(),
+        notes,
+    })
+}
+
+fn metrics_only_requests(
+    item: &UploadItem,
+    session_id: &str,
+) -> Result<Vec<(&'static str, Value)>, String> {
+    let metrics = get_session_metrics(session_id).ok().flatten();
+    let mut requests = vec![(
+        api_paths::SESSION_UPLOAD,
+        build_session_request(item, session_id, metrics.as_ref())?,
+    )];
+    if let Some(metrics) = metrics {
+        let canonical_content = read_canonical_content(item);
+        requests.push((
+            api_paths::METRICS_UPLOAD,
+            build_metrics_request(&metrics, canonical_content.as_deref()),
+        ));
+    }
+    Ok(requests)
+}
+
+fn transcript_request(
+    item: &UploadItem,
+    session_id: &str,
+    allow_delta: bool,
+) -> Result<(&'static str, Value), String> {
+    let file_content =
+        std::fs::read(&item.file_path).map_err(|e| format!("Failed to read file: {}", e))?;
+    let file_hash = calculate_bytes_hash_sha256(&file_content);
+    let file_content = upload_content(item.cwd.as_deref(), file_content);
+    let machine_id = get_machine_id().map_err(|e| format!("Failed to get machine ID: {}", e))?;
+
+    // Same delta base as the upload would use
+    let delta_base = delta_base_for(&item.provider, session_id, &file_content, allow_delta);
+    let appended = match &delta_base {
+        Some(base) => &file_content[base.content_length as usize..],
+        None => &file_content[..],
+    };
+
+    let version = SessionVersion::of_content(&String::from_utf8_lossy(&file_content));
+    let content = PreparedContent::new(appended, delta_base, version)?;
+    let mut body = build_upload_request(item, session_id, &file_hash, &machine_id, Some(&content))?;
+    body["content"] = summarize_content(&content.encoded, appended);
+
+    let endpoint = if content.delta_base.is_some() {
+        api_paths::SESSION_UPLOAD_DELTA
+    } else {
+        api_paths::SESSION_UPLOAD_V2
+    };
+    Ok((endpoint, body))
+}
+
+/// Stand-in for the encoded transcript: its sizes and the start of its text
+fn summarize_content(encoded: &str, transcript: &[u8]) -> Value {
+    let text = String::from_utf8_lossy(transcript);
+    serde_json::json!({
+        "encodedBytes": encoded.len(),
+        "transcriptBytes": transcript.len(),
+        "transcriptLines": text.lines().count(),
+        // Truncated with the rest of the body
+        "preview": cap_embedded_data(&text),
+    })
+}
+
+/// Truncate every long string in `value`
+fn truncate_strings(value: &mut Value) {
+    match value {
+        Value::String(s) => truncate(s),
+        Value::Array(items) => items.iter_mut().for_each(truncate_strings),
+        Value::Object(map) => map.values_mut().for_each(truncate_strings),
+        _ => {}
+    }
+}
+
+fn truncate(s: &mut String) {
+    let total = s.chars().count();
+    if total > MAX_STRING_CHARS {
+        let kept: String = s.chars().take(MAX_STRING_CHARS).collect();
+        *s = format!("{}… ({} more characters)", kept, total - MAX_STRING_CHARS);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_long_strings_are_truncated() {
+        let long = "x".repeat(MAX_STRING_CHARS + 5);
+        let mut body = serde_json::json!({
+            "sessionId": "s1",
+            "uncommittedChanges": {"patch": long, "filesChanged": 3},
+            "errorTypes": ["short", long],
+        });
+        truncate_strings(&mut body);
+
+        assert_eq!(body["sessionId"], "s1");
+        assert_eq!(body["uncommittedChanges"]["filesChanged"], 3);
+        let patch = body["uncommittedChanges"]["patch"].as_str().unwrap();
+        assert!(patch.ends_with("… (5 more characters)"));
+        assert_eq!(body["errorTypes"][0], "short");
+        assert_eq!(body["errorTypes"][1], patch);
+    }
+
+    #[test]
+    fn test_content_summary() {
+        let transcript = b"{\"a\":1}\n{\"b\":2}\n";
+        let version = SessionVersion::of_content("");
+        let content = PreparedContent::new(transcript, None, version).unwrap();
+        let summary = summarize_content(&content.encoded, transcript);
+        assert_eq!(summary["transcriptBytes"], 16);
+        assert_eq!(summary["transcriptLines"], 2);
+        assert_eq!(summary["preview"], "{\"a\":1}\n{\"b\":2}\n");
+    }
+}
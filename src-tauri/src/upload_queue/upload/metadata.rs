@@ -0,0 +1,94 @@
+//! Metadata patches for sessions that already synced.
+//!
+//! Rating a synced session marks its metadata dirty instead of queueing the
+//! whole session again. The processor sends the rating and assessment fields
+//! on their own; a server without the patch endpoint gets a full upload.
+
+use super::retry::record_rate_limit;
+use crate::api_paths;
+use crate::config::GuideModeConfig;
+use crate::database::{
+    clear_metadata_dirty, get_full_session_by_id, get_session_rating, requeue_dirty_metadata,
+    DirtyMetadataSession,
+};
+use crate::error::GuideModeError;
+use crate::logging::log_info;
+use chrono::DateTime;
+use reqwest::StatusCode;
+use serde_json::Value;
+
+/// Build the patch for a session's rating and assessment
+pub fn build_metadata_patch(session: &DirtyMetadataSession) -> Result<Value, String> {
+    let session_data = get_full_session_by_id(&session.session_id)
+        .map_err(|e| format!("Failed to get session data: {}", e))?
+        .ok_or_else(|| format!("Session {} not found in database", session.session_id))?;
+    let rating = get_session_rating(&session.session_id).ok().flatten();
+
+    Ok(serde_json::json!({
+        // Same keys the server deduplicates uploads on
+        "provider": session_data.provider,
+        "sessionId": session_data.session_id,
+        "fileName": session_data.file_name,
+        "serverSessionId": session.server_session_id,
+        "assessmentStatus": session_data.assessment_status,
+        "assessmentCompletedAt": session_data
+            .assessment_completed_at
+            .and_then(DateTime::from_timestamp_millis)
+            .map(|dt| dt.to_rfc3339()),
+        "assessmentRating": rating,
+    }))
+}
+
+/// Send a session's metadata changes, clearing them once the server has them
+pub async fn upload_metadata_patch(
+    session: &DirtyMetadataSession,
+    config: &GuideModeConfig,
+) -> Result<(), GuideModeError> {
+    let api_key = config.api_key.as_ref().ok_or("No API key configured")?;
+    let server_url = config
+        .server_url
+        .as_ref()
+        .ok_or("No server URL configured")?;
+    let patch = build_metadata_patch(session)?;
+
+    let client = reqwest::Client::new();
+    let url = api_paths::api_url(server_url, api_paths::SESSION_METADATA);
+    let response = client
+        .patch(&url)
+        .header("Authorization", format!("Bearer {}", api_key))
+        .header("Content-Type", "application/json")
+        .json(&patch)
+        .send()
+        .await?;
+
+    let status = response.status();
+    if matches!(
+        status,
+        StatusCode::NOT_FOUND | StatusCode::METHOD_NOT_ALLOWED
+    ) {
+        // The server predates metadata patches; upload the session again
+        log_info(
+            "upload-queue",
+            &format!(
+                "↻ Server doesn't accept metadata patches, re-uploading session {}",
+                session.session_id
+            ),
+        )
+        .unwrap_or_default();
+        return requeue_dirty_metadata(&session.session_id)
+            .map_err(|e| format!("Failed to queue session for re-upload: {}", e).into());
+    }
+    if !status.is_success() {
+        record_rate_limit(&response);
+        return Err(GuideModeError::from_response("Metadata patch", response).await);
+    }
+
+    clear_metadata_dirty(&session.session_id, session.dirty_at)
+        .map_err(|e| format!("Failed to clear metadata changes: {}", e))?;
+    log_info(
+        "upload-queue",
+        &format!("✓ Patched metadata for session {}", session.session_id),
+    )
+    .unwrap_or_default();
+    Ok(())
+}
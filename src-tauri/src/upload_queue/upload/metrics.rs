@@ -2,18 +2,28 @@
 //!
 //! Uploads session metadata and metrics, skipping JSONL transcript.
 
-use crate::config::GuideModeConfig;
+use crate::api_paths;
+use crate::config::{get_machine_id, GuideModeConfig};
+use crate::error::GuideModeError;
 use crate::database::{
     get_full_session_by_id, get_session_metrics, get_session_rating, SessionMetrics,
 };
 use crate::logging::{log_info, log_warn};
-use crate::project_metadata::extract_project_metadata;
+use crate::project_aliases::canonical_project_name;
+use crate::project_metadata::{detect_languages, extract_session_file_project_metadata};
+use crate::providers::common::models::summarize_model_usage;
 use crate::upload_queue::types::UploadItem;
+use crate::workspace_roots;
+use super::project::{embed_metadata, record_embedded_metadata};
+use super::retry::record_rate_limit;
 use chrono::DateTime;
 use serde_json::Value;
 
 /// Upload session metadata and metrics only (no JSONL transcript)
-pub async fn upload_metrics_only(item: &UploadItem, config: GuideModeConfig) -> Result<(), String> {
+pub async fn upload_metrics_only(
+    item: &UploadItem,
+    config: GuideModeConfig,
+) -> Result<(), GuideModeError> {
     let api_key = config.api_key.clone().ok_or("No API key configured")?;
     let server_url = config
         .server_url
@@ -26,6 +36,64 @@ pub async fn upload_metrics_only(item: &UploadItem, config: GuideModeConfig) ->
         .as_ref()
         .ok_or("Session ID required for metrics-only sync")?;
 
+    let metrics = get_session_metrics(session_id).ok().flatten();
+    let session_request = build_session_request(item, session_id, metrics.as_ref())?;
+
+    // Upload session metadata
+    let client = reqwest::Client::new();
+    let url = api_paths::api_url(&server_url, api_paths::SESSION_UPLOAD);
+
+    let response = client
+        .post(&url)
+        .header("Authorization", format!("Bearer {}", api_key))
+        .header("Content-Type", "application/json")
+        .json(&session_request)
+        .send()
+        .await?;
+
+    if !response.status().is_success() {
+        record_rate_limit(&response);
+        return Err(GuideModeError::from_response(
+            "Session metadata upload (metrics-only mode)",
+            response,
+        )
+        .await);
+    }
+    record_embedded_metadata(&session_request);
+
+    log_info(
+        "upload-queue",
+        &format!("✓ Uploaded session metadata for {}", session_id),
+    )
+    .unwrap_or_default();
+
+    // Upload metrics
+    if let Some(metrics) = metrics {
+        let canonical_content = read_canonical_content(item);
+        upload_session_metrics(
+            &metrics,
+            canonical_content.as_deref(),
+            &server_url,
+            &api_key,
+        )
+        .await?;
+    } else {
+        log_warn(
+            "upload-queue",
+            &format!("⚠ No metrics found for session {}", session_id),
+        )
+        .unwrap_or_default();
+    }
+
+    Ok(())
+}
+
+/// Build the metadata request of a metrics-only upload
+pub fn build_session_request(
+    item: &UploadItem,
+    session_id: &str,
+    metrics: Option<&SessionMetrics>,
+) -> Result<Value, String> {
     // Fetch full session data from database
     let session_data = get_full_session_by_id(session_id)
         .map_err(|e| format!("Failed to get session data: {}", e))?
@@ -39,7 +107,7 @@ pub async fn upload_metrics_only(item: &UploadItem, config: GuideModeConfig) ->
         )
         .unwrap_or_default();
 
-        match extract_project_metadata(cwd) {
+        match extract_session_file_project_metadata(&item.provider, cwd, &item.file_path) {
             Ok(metadata) => {
                 log_info(
                     "upload-queue",
@@ -90,7 +158,7 @@ pub async fn upload_metrics_only(item: &UploadItem, config: GuideModeConfig) ->
     // fileSize is included as a useful metric for session size analytics
     let mut session_request = serde_json::json!({
         "provider": session_data.provider,
-        "projectName": final_project_name,
+        "projectName": canonical_project_name(&final_project_name),
         "sessionId": session_data.session_id,
         "fileName": session_data.file_name,
         // filePath intentionally omitted for metrics-only uploads
@@ -98,6 +166,7 @@ pub async fn upload_metrics_only(item: &UploadItem, config: GuideModeConfig) ->
         "sessionStartTime": timestamp_to_iso(session_data.session_start_time),
         "sessionEndTime": timestamp_to_iso(session_data.session_end_time),
         "durationMs": session_data.duration_ms,
+        "activeDurationMs": metrics.and_then(|m| m.active_duration_ms()),
         "processingStatus": session_data.processing_status,
         "queuedAt": timestamp_to_iso(session_data.queued_at),
         "processedAt": timestamp_to_iso(session_data.processed_at),
@@ -110,68 +179,101 @@ pub async fn upload_metrics_only(item: &UploadItem, config: GuideModeConfig) ->
         "aiModelQualityScore": session_data.ai_model_quality_score,
         "aiModelMetadata": session_data.ai_model_metadata.and_then(|s| serde_json::from_str::<Value>(&s).ok()),
         "aiModelPhaseAnalysis": session_data.ai_model_phase_analysis.and_then(|s| serde_json::from_str::<Value>(&s).ok()),
+        "resumedFromSessionId": session_data.resumed_from,
+        "machineId": get_machine_id().ok(),
     });
 
     // Add project metadata if available
     if let Some(ref metadata) = project_metadata {
-        session_request["projectMetadata"] = serde_json::json!({
+        let metadata = serde_json::json!({
             "gitRemoteUrl": metadata.git_remote_url,
             "cwd": metadata.cwd,
             "detectedProjectType": metadata.detected_project_type,
+            "frameworks": metadata.frameworks,
+            "languages": detect_languages(&metadata.cwd),
         });
+        embed_metadata(&mut session_request, metadata);
     }
 
-    // Upload session metadata
+    let tags = workspace_roots::tags(item.cwd.as_deref());
+    if !tags.is_empty() {
+        session_request["tags"] = serde_json::json!(tags);
+    }
+
+    Ok(session_request)
+}
+
+/// Canonical content of the session being uploaded, if it can be read
+pub fn read_canonical_content(item: &UploadItem) -> Option<String> {
+    item.content
+        .clone()
+        .or_else(|| std::fs::read_to_string(&item.file_path).ok())
+}
+
+/// Session custom metrics with model usage merged in
+///
+/// Model usage is computed from the normalized models in the canonical
+/// content and stored under `modelUsage`, next to the custom metrics
+/// computed during session processing.
+pub fn custom_metrics_with_model_usage(
+    custom_metrics: Option<&str>,
+    canonical_content: Option<&str>,
+) -> Option<Value> {
+    let mut custom = custom_metrics.and_then(|s| serde_json::from_str::<Value>(s).ok());
+
+    let Some(model_usage) = canonical_content.and_then(summarize_model_usage) else {
+        return custom;
+    };
+
+    match custom {
+        Some(Value::Object(ref mut map)) => {
+            map.insert("modelUsage".to_string(), model_usage);
+        }
+        None => custom = Some(serde_json::json!({ "modelUsage": model_usage })),
+        // Keep unexpected custom metrics shapes as-is
+        Some(_) => {}
+    }
+
+    custom
+}
+
+/// Helper function to upload session metrics to server
+pub async fn upload_session_metrics(
+    metrics: &SessionMetrics,
+    canonical_content: Option<&str>,
+    server_url: &str,
+    api_key: &str,
+) -> Result<(), GuideModeError> {
+    let metrics_request = build_metrics_request(metrics, canonical_content);
+
+    // Upload metrics
     let client = reqwest::Client::new();
-    let url = format!("{}/api/agent-sessions/upload", server_url);
+    let url = api_paths::api_url(server_url, api_paths::METRICS_UPLOAD);
 
     let response = client
         .post(&url)
         .header("Authorization", format!("Bearer {}", api_key))
         .header("Content-Type", "application/json")
-        .json(&session_request)
+        .json(&metrics_request)
         .send()
-        .await
-        .map_err(|e| format!("Failed to upload session metadata: {}", e))?;
+        .await?;
 
     if !response.status().is_success() {
-        let status = response.status();
-        let error_text = response
-            .text()
-            .await
-            .unwrap_or_else(|_| "Unknown error".to_string());
-        return Err(format!(
-            "Session metadata upload failed (metrics-only mode) with status {}: {}",
-            status, error_text
-        ));
+        record_rate_limit(&response);
+        return Err(GuideModeError::from_response("Session metrics upload", response).await);
     }
 
     log_info(
         "upload-queue",
-        &format!("✓ Uploaded session metadata for {}", session_id),
+        &format!("✓ Uploaded metrics for session {}", metrics.session_id),
     )
     .unwrap_or_default();
 
-    // Fetch and upload metrics
-    if let Ok(Some(metrics)) = get_session_metrics(session_id) {
-        upload_session_metrics(&metrics, &server_url, &api_key).await?;
-    } else {
-        log_warn(
-            "upload-queue",
-            &format!("⚠ No metrics found for session {}", session_id),
-        )
-        .unwrap_or_default();
-    }
-
     Ok(())
 }
 
-/// Helper function to upload session metrics to server
-pub async fn upload_session_metrics(
-    metrics: &SessionMetrics,
-    server_url: &str,
-    api_key: &str,
-) -> Result<(), String> {
+/// Build the request uploading a session's metrics
+pub fn build_metrics_request(metrics: &SessionMetrics, canonical_content: Option<&str>) -> Value {
     // Helper to parse JSON array from comma-separated string
     let parse_array = |s: &Option<String>| -> Option<Vec<String>> {
         s.as_ref().map(|str_val| {
@@ -260,6 +362,14 @@ pub async fn upload_session_metrics(
         "sessionLengthMinutes".to_string(),
         serde_json::json!(metrics.session_length_minutes),
     );
+    metrics_obj.insert(
+        "activeLengthMinutes".to_string(),
+        serde_json::json!(metrics.active_length_minutes),
+    );
+    metrics_obj.insert(
+        "idleGapCount".to_string(),
+        serde_json::json!(metrics.idle_gap_count),
+    );
     metrics_obj.insert(
         "totalInterruptions".to_string(),
         serde_json::json!(metrics.total_interruptions),
@@ -364,46 +474,13 @@ pub async fn upload_session_metrics(
     // Custom metrics
     metrics_obj.insert(
         "customMetrics".to_string(),
-        serde_json::json!(metrics
-            .custom_metrics
-            .as_ref()
-            .and_then(|s| serde_json::from_str::<Value>(s).ok())),
+        serde_json::json!(custom_metrics_with_model_usage(
+            metrics.custom_metrics.as_deref(),
+            canonical_content
+        )),
     );
 
-    let metrics_request = serde_json::json!({
+    serde_json::json!({
         "metrics": [metrics_obj]
-    });
-
-    // Upload metrics
-    let client = reqwest::Client::new();
-    let url = format!("{}/api/session-metrics/upload", server_url);
-
-    let response = client
-        .post(&url)
-        .header("Authorization", format!("Bearer {}", api_key))
-        .header("Content-Type", "application/json")
-        .json(&metrics_request)
-        .send()
-        .await
-        .map_err(|e| format!("Failed to upload metrics: {}", e))?;
-
-    if !response.status().is_success() {
-        let status = response.status();
-        let error_text = response
-            .text()
-            .await
-            .unwrap_or_else(|_| "Unknown error".to_string());
-        return Err(format!(
-            "Session metrics upload failed with status {}: {}",
-            status, error_text
-        ));
-    }
-
-    log_info(
-        "upload-queue",
-        &format!("✓ Uploaded metrics for session {}", metrics.session_id),
-    )
-    .unwrap_or_default();
-
-    Ok(())
+    })
 }
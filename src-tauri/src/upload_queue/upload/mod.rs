@@ -3,46 +3,91 @@
 //! Routes upload requests to appropriate handlers (v2, metrics, project).
 
 // Upload submodules
+pub mod conflict;
+pub mod inspect;
+pub mod metadata;
 pub mod metrics;
+pub mod negotiation;
 pub mod project;
 pub mod retry;
 pub mod v2;
 
 // Re-export main functions
+pub use metadata::upload_metadata_patch;
 pub use metrics::upload_metrics_only;
-pub use v2::upload_v2;
+pub use v2::{upload_delta, upload_v2};
 
 // Re-export deprecated function for backward compatibility
 #[allow(deprecated)]
 pub use project::upload_project_metadata_static;
 
 // Re-export retry utilities
-pub use retry::{calculate_backoff, classify_error, schedule_retry, should_retry, ErrorType};
+pub use retry::{
+    calculate_backoff, classify_error, rate_limited_until, schedule_retry, should_retry, ErrorType,
+};
 
 use crate::config::GuideModeConfig;
+use crate::error::GuideModeError;
+use crate::ignore_list::IgnoreList;
+use crate::logging::log_warn;
+use crate::server_health;
 use crate::upload_queue::hashing::{calculate_content_hash_sha256, calculate_file_hash_sha256};
 use crate::upload_queue::types::UploadItem;
+use crate::workspace_roots;
+use negotiation::UploadApi;
+
+/// What an upload sent the server
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UploadOutcome {
+    /// The transcript with its metrics
+    Transcript,
+    /// Metrics only, as the session's sync mode asks
+    MetricsOnly,
+    /// Metrics only, because the server can't take the transcript yet
+    TranscriptDeferred,
+}
 
 /// Process an upload item by routing to the appropriate upload method based on sync mode
 pub async fn process_upload_item(
     item: &UploadItem,
     config: Option<GuideModeConfig>,
-) -> Result<(), String> {
+) -> Result<UploadOutcome, GuideModeError> {
     let config = config.ok_or("No configuration available")?;
 
-    // Check provider sync mode before uploading
-    use crate::config::load_provider_config;
-    let provider_config = load_provider_config(&item.provider)
-        .map_err(|e| format!("Failed to load provider config: {}", e))?;
+    if IgnoreList::new(&config.ignore_patterns).is_ignored(&item.project_name, item.cwd.as_deref()) {
+        return Err(GuideModeError::Config(format!(
+            "Project '{}' is on the ignore list, skipping upload",
+            item.project_name
+        )));
+    }
+
+    // Check the sync mode before uploading: the session's workspace root's, or its provider's
+    let sync_mode = workspace_roots::effective_sync_mode(&item.provider, item.cwd.as_deref())
+        .ok_or("Failed to load provider config")?;
 
     // Route to appropriate upload function based on sync mode
-    match provider_config.sync_mode.as_str() {
+    match sync_mode.as_str() {
         "Metrics Only" => {
             // Metrics-only sync: upload session metadata and metrics without JSONL
-            upload_metrics_only(item, config.clone()).await
+            upload_metrics_only(item, config.clone()).await?;
+            Ok(UploadOutcome::MetricsOnly)
+        }
+        "Transcript and Metrics"
+            if !server_health::schema_compatible(
+                config.server_url.as_deref().unwrap_or_default(),
+            ) =>
+        {
+            // The server can't take this transcript; send what it can read
+            log_warn(
+                "upload-queue",
+                "⚠ Server can't accept transcripts from this app, uploading metrics only",
+            )
+            .unwrap_or_default();
+            upload_metrics_only(item, config.clone()).await?;
+            Ok(UploadOutcome::TranscriptDeferred)
         }
         "Transcript and Metrics" => {
-            // Full sync: use v2 upload with compression and deduplication
+            // Full sync through the newest upload API the server supports
 
             // Extract session ID
             let session_id = item.session_id.as_ref()
@@ -60,14 +105,51 @@ pub async fn process_upload_item(
                 }
             };
 
-            // Use v2 upload endpoint
-            upload_v2(item, session_id, &file_hash, config.clone()).await
+            let server_url = config.server_url.clone().ok_or("No server URL configured")?;
+            let api = negotiation::upload_api(&server_url).await;
+            match upload_transcript(api, item, session_id, &file_hash, &config).await {
+                // The server dropped that API; renegotiate and try once more
+                Err(e) if negotiation::is_endpoint_gone(&e) => {
+                    negotiation::invalidate();
+                    let renegotiated = negotiation::upload_api(&server_url).await;
+                    if renegotiated == api {
+                        return Err(e);
+                    }
+                    upload_transcript(renegotiated, item, session_id, &file_hash, &config).await
+                }
+                // The server may have changed versions; probe it again next time
+                Err(e) if negotiation::is_version_error(&e) => {
+                    negotiation::invalidate();
+                    Err(e)
+                }
+                result => result,
+            }
         }
         _ => {
-            Err(format!(
+            Err(GuideModeError::Config(format!(
                 "Sync mode is '{}', skipping upload (expected 'Metrics Only' or 'Transcript and Metrics')",
-                provider_config.sync_mode
-            ))
+                sync_mode
+            )))
+        }
+    }
+}
+
+/// Upload a session's transcript through the negotiated API
+async fn upload_transcript(
+    api: UploadApi,
+    item: &UploadItem,
+    session_id: &str,
+    file_hash: &str,
+    config: &GuideModeConfig,
+) -> Result<UploadOutcome, GuideModeError> {
+    match api {
+        UploadApi::Delta => upload_delta(item, session_id, file_hash, config.clone()).await?,
+        UploadApi::V2 => upload_v2(item, session_id, file_hash, config.clone()).await?,
+        // The v1 endpoint takes no transcript
+        UploadApi::V1 => {
+            upload_metrics_only(item, config.clone()).await?;
+            return Ok(UploadOutcome::TranscriptDeferred);
         }
     }
+    Ok(UploadOutcome::Transcript)
 }
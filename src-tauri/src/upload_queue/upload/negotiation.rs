@@ -0,0 +1,154 @@
+//! Upload API version negotiation.
+//!
+//! Servers advertise the upload APIs they accept in their health check's
+//! capabilities. Transcripts go through the newest API both sides support:
+//!
+//! - `Delta`: only the bytes appended since the session's last upload, or the
+//!   whole session through v2 when there is no usable base
+//! - `V2`: the full compressed transcript with hash deduplication
+//! - `V1`: the original endpoint, which takes session metadata and metrics
+//!   but no transcript
+//!
+//! The result is cached per server for `NEGOTIATION_TTL`, so a server upgraded
+//! or downgraded in place is picked up without a restart. An upload answered
+//! with 404 or 410 means the server dropped that API, so the cache is
+//! refreshed and the upload is retried once through whatever the server offers
+//! now. Other client errors that may come from a version change (400, 415,
+//! 422, 426) drop the cache without retrying.
+
+use crate::error::GuideModeError;
+use crate::logging::log_info;
+use crate::server_health::{self, UPLOAD_DELTA, UPLOAD_V2};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// How long a negotiated API is trusted before the server is probed again
+const NEGOTIATION_TTL: Duration = Duration::from_secs(15 * 60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UploadApi {
+    V1,
+    V2,
+    Delta,
+}
+
+impl UploadApi {
+    fn from_capabilities(capabilities: &[String]) -> Self {
+        let has = |capability: &str| capabilities.iter().any(|c| c == capability);
+        if has(UPLOAD_DELTA) && has(UPLOAD_V2) {
+            UploadApi::Delta
+        } else if has(UPLOAD_V2) {
+            UploadApi::V2
+        } else {
+            UploadApi::V1
+        }
+    }
+}
+
+/// Negotiated API, the server it was negotiated with and when
+static NEGOTIATED: Mutex<Option<(String, UploadApi, Instant)>> = Mutex::new(None);
+
+/// The upload API to use with `server_url`, probing the server if needed
+pub async fn upload_api(server_url: &str) -> UploadApi {
+    if let Some(api) = cached(server_url) {
+        return api;
+    }
+
+    let health = server_health::check(server_url).await;
+    if !health.reachable {
+        // Can't tell yet; upload as before and negotiate on the next upload
+        return UploadApi::V2;
+    }
+
+    let api = UploadApi::from_capabilities(&health.capabilities);
+    log_info(
+        "upload-queue",
+        &format!("🤝 Negotiated {:?} uploads with {}", api, server_url),
+    )
+    .unwrap_or_default();
+    if let Ok(mut negotiated) = NEGOTIATED.lock() {
+        *negotiated = Some((server_url.to_string(), api, Instant::now()));
+    }
+    api
+}
+
+/// Forget the negotiated API so the next upload probes the server again
+pub fn invalidate() {
+    if let Ok(mut negotiated) = NEGOTIATED.lock() {
+        *negotiated = None;
+    }
+}
+
+/// Whether an upload error means the server no longer serves that endpoint
+pub fn is_endpoint_gone(error: &GuideModeError) -> bool {
+    matches!(
+        error.status(),
+        Some(reqwest::StatusCode::NOT_FOUND | reqwest::StatusCode::GONE)
+    )
+}
+
+/// Whether an upload error may mean the server changed which API versions it
+/// takes, so the negotiated API should be probed again
+pub fn is_version_error(error: &GuideModeError) -> bool {
+    matches!(
+        error.status(),
+        Some(
+            reqwest::StatusCode::BAD_REQUEST
+                | reqwest::StatusCode::UNSUPPORTED_MEDIA_TYPE
+                | reqwest::StatusCode::UNPROCESSABLE_ENTITY
+                | reqwest::StatusCode::UPGRADE_REQUIRED
+        )
+    )
+}
+
+/// The API already negotiated with `server_url`, if any and still fresh
+pub fn cached(server_url: &str) -> Option<UploadApi> {
+    let negotiated = NEGOTIATED.lock().ok()?;
+    match negotiated.as_ref() {
+        Some((url, api, at)) if url == server_url && at.elapsed() < NEGOTIATION_TTL => Some(*api),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn api(capabilities: &[&str]) -> UploadApi {
+        let capabilities: Vec<String> = capabilities.iter().map(|c| c.to_string()).collect();
+        UploadApi::from_capabilities(&capabilities)
+    }
+
+    #[test]
+    fn test_newest_shared_api_wins() {
+        assert_eq!(
+            api(&["upload-v1", "upload-v2", "upload-delta"]),
+            UploadApi::Delta
+        );
+        assert_eq!(api(&["upload-v2"]), UploadApi::V2);
+        assert_eq!(api(&["upload-v1"]), UploadApi::V1);
+        assert_eq!(api(&[]), UploadApi::V1);
+
+        // Deltas fall back to v2, so they need it too
+        assert_eq!(api(&["upload-delta"]), UploadApi::V1);
+    }
+
+    #[test]
+    fn test_endpoint_gone() {
+        let failed = |status: u16| GuideModeError::Status {
+            action: "Upload v2".to_string(),
+            status: reqwest::StatusCode::from_u16(status).unwrap(),
+            body: String::new(),
+        };
+        assert!(is_endpoint_gone(&failed(404)));
+        assert!(is_endpoint_gone(&failed(410)));
+        assert!(!is_endpoint_gone(&failed(400)));
+        assert!(is_version_error(&failed(400)));
+        assert!(is_version_error(&failed(426)));
+        assert!(!is_version_error(&failed(401)));
+        assert!(!is_version_error(&failed(429)));
+        assert!(!is_endpoint_gone(&GuideModeError::from(
+            "Upload v2 failed with status 404 Not Found: "
+        )));
+    }
+}
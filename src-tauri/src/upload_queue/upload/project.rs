@@ -4,12 +4,158 @@
 //! (v2 and metrics uploads) instead of being uploaded separately. This reduces API calls
 //! and ensures atomic session+project updates.
 //!
-//! This module is kept for backward compatibility and legacy code paths only.
+//! The separate upload is kept for backward compatibility and legacy code
+//! paths only; the embedding itself goes through `embed_metadata`.
+//!
+//! The metadata last uploaded for each project is remembered per server and
+//! tenant, so unchanged metadata isn't sent again for every session:
+//! - session uploads leave `projectMetadata` out when the server received the
+//!   same metadata for the project within `EMBEDDED_METADATA_REFRESH`
+//!   (`embed_metadata`), and remember what it accepted
+//!   (`record_embedded_metadata`).
+//! - the legacy POST is skipped when unchanged. When it has changed, the
+//!   request carries the server's last ETag for the project in
+//!   `If-None-Match`; a server that already holds the same metadata answers
+//!   304 or 412 without rewriting it.
 
-use crate::config::GuideModeConfig;
-use crate::logging::log_info;
+use crate::config::{load_config, GuideModeConfig};
+use crate::database::with_connection_mut;
+use crate::logging::{log_debug, log_info};
 use crate::project_metadata::ProjectMetadata;
+use crate::upload_queue::hashing::calculate_bytes_hash_sha256;
 use crate::upload_queue::types::ProjectUploadRequest;
+use chrono::Utc;
+use reqwest::header::{ETAG, IF_NONE_MATCH};
+use reqwest::StatusCode;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde_json::Value;
+use std::time::Duration;
+
+/// How long a server is trusted to keep embedded project metadata before
+/// it's sent again, in case the server lost it
+const EMBEDDED_METADATA_REFRESH: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// The project metadata a server last received
+#[derive(Debug, Clone, PartialEq)]
+struct UploadedMetadata {
+    body_hash: String,
+    etag: Option<String>,
+}
+
+fn uploaded(
+    conn: &Connection,
+    server_url: &str,
+    tenant_id: &str,
+    project_name: &str,
+) -> rusqlite::Result<Option<UploadedMetadata>> {
+    conn.query_row(
+        "SELECT body_hash, etag FROM project_metadata_uploads
+         WHERE server_url = ? AND tenant_id = ? AND project_name = ?",
+        params![server_url, tenant_id, project_name],
+        |row| {
+            Ok(UploadedMetadata {
+                body_hash: row.get(0)?,
+                etag: row.get(1)?,
+            })
+        },
+    )
+    .optional()
+}
+
+/// Hash of the metadata a server received for a project since `since` (Unix seconds)
+fn uploaded_hash_since(
+    conn: &Connection,
+    server_url: &str,
+    tenant_id: &str,
+    project_name: &str,
+    since: i64,
+) -> rusqlite::Result<Option<String>> {
+    conn.query_row(
+        "SELECT body_hash FROM project_metadata_uploads
+         WHERE server_url = ? AND tenant_id = ? AND project_name = ? AND uploaded_at >= ?",
+        params![server_url, tenant_id, project_name, since],
+        |row| row.get(0),
+    )
+    .optional()
+}
+
+fn record_uploaded(
+    conn: &Connection,
+    server_url: &str,
+    tenant_id: &str,
+    project_name: &str,
+    metadata: &UploadedMetadata,
+) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT OR REPLACE INTO project_metadata_uploads
+         (server_url, tenant_id, project_name, body_hash, etag, uploaded_at)
+         VALUES (?, ?, ?, ?, ?, ?)",
+        params![
+            server_url,
+            tenant_id,
+            project_name,
+            metadata.body_hash,
+            metadata.etag,
+            Utc::now().timestamp()
+        ],
+    )?;
+    Ok(())
+}
+
+/// The server and tenant uploads currently go to
+fn upload_target() -> Option<(String, String)> {
+    let config = load_config().ok()?;
+    Some((config.server_url?, config.tenant_id.unwrap_or_default()))
+}
+
+/// Embed `metadata` in a session upload's `request` as `projectMetadata`,
+/// unless the server recently received the same metadata for its project
+pub fn embed_metadata(request: &mut Value, metadata: Value) {
+    let project_name = request["projectName"].as_str().unwrap_or_default();
+    let body_hash = calculate_bytes_hash_sha256(metadata.to_string().as_bytes());
+    let since = Utc::now().timestamp() - EMBEDDED_METADATA_REFRESH.as_secs() as i64;
+    let unchanged = upload_target().is_some_and(|(server_url, tenant_id)| {
+        with_connection_mut(|conn| {
+            uploaded_hash_since(conn, &server_url, &tenant_id, project_name, since)
+        })
+        .ok()
+        .flatten()
+        .is_some_and(|uploaded| uploaded == body_hash)
+    });
+    if unchanged {
+        log_debug(
+            "upload-queue",
+            &format!(
+                "Project metadata unchanged, not embedding it: {}",
+                project_name
+            ),
+        )
+        .unwrap_or_default();
+        return;
+    }
+    request["projectMetadata"] = metadata;
+}
+
+/// Remember the `projectMetadata` a server accepted with a session upload
+pub fn record_embedded_metadata(request: &Value) {
+    let (Some(project_name), Some(metadata)) = (
+        request["projectName"].as_str(),
+        request.get("projectMetadata"),
+    ) else {
+        return;
+    };
+    let Some((server_url, tenant_id)) = upload_target() else {
+        return;
+    };
+    let uploaded = UploadedMetadata {
+        body_hash: calculate_bytes_hash_sha256(metadata.to_string().as_bytes()),
+        etag: None,
+    };
+    // Not remembering it only costs a repeated upload
+    let _ = with_connection_mut(|conn| {
+        record_uploaded(conn, &server_url, &tenant_id, project_name, &uploaded)
+    });
+}
 
 /// Upload project metadata to the server (static version for use in async tasks)
 ///
@@ -37,28 +183,79 @@ pub async fn upload_project_metadata_static(
         detected_project_type: metadata.detected_project_type.clone(),
     };
 
+    let body = serde_json::to_vec(&upload_request)
+        .map_err(|e| format!("Failed to serialize project metadata: {}", e))?;
+    let body_hash = calculate_bytes_hash_sha256(&body);
+    let tenant_id = config.tenant_id.unwrap_or_default();
+    let previous =
+        with_connection_mut(|conn| uploaded(conn, &server_url, &tenant_id, &metadata.project_name))
+            .ok()
+            .flatten();
+    if previous
+        .as_ref()
+        .is_some_and(|previous| previous.body_hash == body_hash)
+    {
+        log_debug(
+            "upload-queue",
+            &format!(
+                "Project metadata unchanged, not uploading: {}",
+                metadata.project_name
+            ),
+        )
+        .unwrap_or_default();
+        return Ok(());
+    }
+
     // Make HTTP POST request to server
     let client = reqwest::Client::new();
-    let url = format!("{}/api/projects", server_url);
+    let url = crate::api_paths::api_url(&server_url, crate::api_paths::PROJECTS);
 
-    let response = client
+    let mut request = client
         .post(&url)
         .header("Authorization", format!("Bearer {}", api_key))
-        .header("Content-Type", "application/json")
-        .json(&upload_request)
+        .header("Content-Type", "application/json");
+    let previous_etag = previous.and_then(|previous| previous.etag);
+    if let Some(etag) = &previous_etag {
+        request = request.header(IF_NONE_MATCH, etag);
+    }
+    let response = request
+        .body(body)
         .send()
         .await
         .map_err(|e| format!("HTTP request failed: {}", e))?;
 
-    if response.status().is_success() {
+    let status = response.status();
+    let unchanged = status == StatusCode::NOT_MODIFIED || status == StatusCode::PRECONDITION_FAILED;
+    if status.is_success() || unchanged {
+        let etag = response
+            .headers()
+            .get(ETAG)
+            .and_then(|etag| etag.to_str().ok())
+            .map(str::to_string)
+            .or(previous_etag);
+        let uploaded = UploadedMetadata { body_hash, etag };
+        // Not remembering it only costs a repeated upload
+        let _ = with_connection_mut(|conn| {
+            record_uploaded(
+                conn,
+                &server_url,
+                &tenant_id,
+                &metadata.project_name,
+                &uploaded,
+            )
+        });
+        let message = if unchanged {
+            "📦 Project metadata already up to date on the server"
+        } else {
+            "📦 Project metadata uploaded"
+        };
         log_info(
             "upload-queue",
-            &format!("📦 Project metadata uploaded: {}", metadata.project_name),
+            &format!("{}: {}", message, metadata.project_name),
         )
         .unwrap_or_default();
         Ok(())
     } else {
-        let status = response.status();
         let error_text = response
             .text()
             .await
@@ -69,3 +266,50 @@ pub async fn upload_project_metadata_static(
         ))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_uploaded_metadata_is_kept_per_server_and_tenant() {
+        let conn = crate::database::test_connection();
+        let server = "https://app.guidemode.dev";
+
+        assert_eq!(uploaded(&conn, server, "acme", "api").unwrap(), None);
+        let first = UploadedMetadata {
+            body_hash: "h1".to_string(),
+            etag: Some("\"v1\"".to_string()),
+        };
+        record_uploaded(&conn, server, "acme", "api", &first).unwrap();
+        assert_eq!(uploaded(&conn, server, "acme", "api").unwrap(), Some(first));
+
+        // Newer metadata replaces the earlier
+        let second = UploadedMetadata {
+            body_hash: "h2".to_string(),
+            etag: None,
+        };
+        record_uploaded(&conn, server, "acme", "api", &second).unwrap();
+        assert_eq!(
+            uploaded(&conn, server, "acme", "api").unwrap(),
+            Some(second)
+        );
+
+        // Only metadata received recently counts as still on the server
+        let now = Utc::now().timestamp();
+        assert_eq!(
+            uploaded_hash_since(&conn, server, "acme", "api", now - 60).unwrap(),
+            Some("h2".to_string())
+        );
+        assert_eq!(
+            uploaded_hash_since(&conn, server, "acme", "api", now + 60).unwrap(),
+            None
+        );
+
+        assert_eq!(uploaded(&conn, server, "globex", "api").unwrap(), None);
+        assert_eq!(
+            uploaded(&conn, "https://guide.example.com", "acme", "api").unwrap(),
+            None
+        );
+    }
+}
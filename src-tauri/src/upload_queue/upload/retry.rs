@@ -2,9 +2,28 @@
 //!
 //! Handles retry strategy, error classification (client/server/network),
 //! and backoff calculation. Extracted from processor.rs in Phase 5.
+//!
+//! A 429 answer puts the whole queue on a cooldown until the time given by
+//! the server's `Retry-After` or rate limit reset headers, since every
+//! upload goes to the same server. Rate limited uploads are retried once the
+//! cooldown ends without counting against their retries.
 
 use super::super::types::UploadItem;
-use chrono::Utc;
+use crate::error::GuideModeError;
+use crate::logging::log_warn;
+use chrono::{DateTime, Utc};
+use reqwest::header::HeaderMap;
+use reqwest::StatusCode;
+use std::sync::Mutex;
+
+/// Cooldown after a 429 that doesn't say when to retry
+const DEFAULT_RATE_LIMIT_COOLDOWN_SECS: i64 = 60;
+
+/// Longest cooldown honored, in case a header is off by orders of magnitude
+const MAX_RATE_LIMIT_COOLDOWN_SECS: i64 = 60 * 60;
+
+/// When the server's rate limit lifts
+static RATE_LIMITED_UNTIL: Mutex<Option<DateTime<Utc>>> = Mutex::new(None);
 
 /// Error classification for determining retry behavior
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -15,6 +34,10 @@ pub enum ErrorType {
     Server,
     /// Network errors - connection issues, retry with backoff
     Network,
+    /// Rate limited (429) - retry once the cooldown ends
+    RateLimited,
+    /// The server received different content than was sent - resend in full
+    ChecksumMismatch,
 }
 
 /// Retry strategy configuration
@@ -46,9 +69,12 @@ impl RetryStrategy {
 
     /// Check if we should retry an item based on current retry count
     pub fn should_retry(&self, item: &UploadItem, error_type: ErrorType) -> bool {
-        // Never retry client errors
-        if error_type == ErrorType::Client {
-            return false;
+        match error_type {
+            // Never retry client errors
+            ErrorType::Client => return false,
+            // Rate limits say nothing about the upload itself
+            ErrorType::RateLimited => return true,
+            ErrorType::Server | ErrorType::Network | ErrorType::ChecksumMismatch => {}
         }
 
         // Retry server/network errors up to max_retries
@@ -69,34 +95,22 @@ impl RetryStrategy {
     }
 }
 
-/// Classify an error message into an ErrorType
-pub fn classify_error(error: &str) -> ErrorType {
-    // Check for client errors (4xx)
-    if error.contains("status 400")
-        || error.contains("Bad Request")
-        || error.contains("status 401")
-        || error.contains("Unauthorized")
-        || error.contains("status 403")
-        || error.contains("Forbidden")
-        || error.contains("status 404")
-        || error.contains("Not Found")
-        || error.contains("validation failed")
-        || error.contains("invalid input")
-    {
-        return ErrorType::Client;
+/// Classify an upload error into an ErrorType
+pub fn classify_error(error: &GuideModeError) -> ErrorType {
+    match error {
+        GuideModeError::ChecksumMismatch { .. } => ErrorType::ChecksumMismatch,
+        GuideModeError::Status { status, .. } => match *status {
+            StatusCode::TOO_MANY_REQUESTS => ErrorType::RateLimited,
+            // A request the server gave up waiting for may go through next time
+            StatusCode::REQUEST_TIMEOUT => ErrorType::Network,
+            status if status.is_client_error() => ErrorType::Client,
+            status if status.is_server_error() => ErrorType::Server,
+            _ => ErrorType::Network,
+        },
+        GuideModeError::Validation(_) => ErrorType::Client,
+        // Connection issues, timeouts, etc.
+        _ => ErrorType::Network,
     }
-
-    // Check for server errors (5xx)
-    if error.contains("status 5")
-        || error.contains("Internal Server Error")
-        || error.contains("Service Unavailable")
-        || error.contains("Gateway Timeout")
-    {
-        return ErrorType::Server;
-    }
-
-    // Default to network error (connection issues, timeouts, etc.)
-    ErrorType::Network
 }
 
 /// Helper function to check if we should retry (uses default strategy)
@@ -114,40 +128,200 @@ pub fn calculate_backoff(retry_count: u32) -> u64 {
     RetryStrategy::default().calculate_backoff(retry_count)
 }
 
+/// When a rate limited response says to retry
+///
+/// Reads `Retry-After` (seconds or an HTTP date), then the reset time of the
+/// `RateLimit-Reset` or `X-RateLimit-Reset` headers (seconds, or a Unix time).
+pub fn retry_after(headers: &HeaderMap, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    let header = |name: &str| headers.get(name).and_then(|value| value.to_str().ok());
+    // Delays are capped before they're added, so huge values can't overflow
+    let after = |seconds: i64| {
+        now + chrono::Duration::seconds(seconds.clamp(0, MAX_RATE_LIMIT_COOLDOWN_SECS))
+    };
+
+    let until = if let Some(value) = header("retry-after") {
+        match value.trim().parse::<i64>() {
+            Ok(seconds) => after(seconds),
+            Err(_) => DateTime::parse_from_rfc2822(value.trim())
+                .ok()?
+                .with_timezone(&Utc),
+        }
+    } else {
+        let reset = header("ratelimit-reset")
+            .or_else(|| header("x-ratelimit-reset"))?
+            .trim()
+            .parse::<i64>()
+            .ok()?;
+        // Resets past a billion seconds are Unix times rather than delays
+        if reset > 1_000_000_000 {
+            DateTime::from_timestamp(reset, 0)?
+        } else {
+            after(reset)
+        }
+    };
+
+    Some(until.clamp(
+        now,
+        now + chrono::Duration::seconds(MAX_RATE_LIMIT_COOLDOWN_SECS),
+    ))
+}
+
+/// Start the queue's cooldown if `response` is a rate limit
+pub fn record_rate_limit(response: &reqwest::Response) {
+    if response.status() != StatusCode::TOO_MANY_REQUESTS {
+        return;
+    }
+
+    let now = Utc::now();
+    let until = retry_after(response.headers(), now)
+        .unwrap_or(now + chrono::Duration::seconds(DEFAULT_RATE_LIMIT_COOLDOWN_SECS));
+    if let Ok(mut rate_limited_until) = RATE_LIMITED_UNTIL.lock() {
+        if rate_limited_until.is_some_and(|current| current >= until) {
+            return;
+        }
+        *rate_limited_until = Some(until);
+    }
+    log_warn(
+        "upload-queue",
+        &format!(
+            "⏳ Server is rate limiting uploads, pausing the queue until {}",
+            until.to_rfc3339()
+        ),
+    )
+    .unwrap_or_default();
+}
+
+/// When the current rate limit cooldown ends, if one is active
+pub fn rate_limited_until() -> Option<DateTime<Utc>> {
+    RATE_LIMITED_UNTIL
+        .lock()
+        .ok()
+        .and_then(|until| *until)
+        .filter(|until| *until > Utc::now())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn failed(status: u16) -> GuideModeError {
+        GuideModeError::Status {
+            action: "Upload v2".to_string(),
+            status: StatusCode::from_u16(status).unwrap(),
+            body: "Unknown error".to_string(),
+        }
+    }
+
     #[test]
     fn test_classify_client_errors() {
-        assert_eq!(classify_error("status 400"), ErrorType::Client);
-        assert_eq!(classify_error("Bad Request"), ErrorType::Client);
-        assert_eq!(classify_error("status 401"), ErrorType::Client);
-        assert_eq!(classify_error("Unauthorized"), ErrorType::Client);
-        assert_eq!(classify_error("status 403"), ErrorType::Client);
-        assert_eq!(classify_error("Forbidden"), ErrorType::Client);
-        assert_eq!(classify_error("status 404"), ErrorType::Client);
-        assert_eq!(classify_error("Not Found"), ErrorType::Client);
-        assert_eq!(classify_error("validation failed"), ErrorType::Client);
-        assert_eq!(classify_error("invalid input"), ErrorType::Client);
+        assert_eq!(classify_error(&failed(400)), ErrorType::Client);
+        assert_eq!(classify_error(&failed(401)), ErrorType::Client);
+        assert_eq!(classify_error(&failed(403)), ErrorType::Client);
+        assert_eq!(classify_error(&failed(404)), ErrorType::Client);
+        assert_eq!(
+            classify_error(&GuideModeError::Validation("invalid input".to_string())),
+            ErrorType::Client
+        );
     }
 
     #[test]
     fn test_classify_server_errors() {
-        assert_eq!(classify_error("status 500"), ErrorType::Server);
-        assert_eq!(classify_error("status 502"), ErrorType::Server);
-        assert_eq!(classify_error("status 503"), ErrorType::Server);
-        assert_eq!(classify_error("Internal Server Error"), ErrorType::Server);
-        assert_eq!(classify_error("Service Unavailable"), ErrorType::Server);
-        assert_eq!(classify_error("Gateway Timeout"), ErrorType::Server);
+        assert_eq!(classify_error(&failed(500)), ErrorType::Server);
+        assert_eq!(classify_error(&failed(502)), ErrorType::Server);
+        assert_eq!(classify_error(&failed(503)), ErrorType::Server);
+        assert_eq!(classify_error(&failed(504)), ErrorType::Server);
+    }
+
+    #[test]
+    fn test_classify_rate_limit_errors() {
+        assert_eq!(classify_error(&failed(429)), ErrorType::RateLimited);
+        assert!(should_retry(
+            &UploadItem {
+                retry_count: 10,
+                ..create_test_item()
+            },
+            ErrorType::RateLimited
+        ));
+    }
+
+    #[test]
+    fn test_retry_after_headers() {
+        use reqwest::header::HeaderValue;
+
+        let now: DateTime<Utc> = "2025-01-01T10:00:00Z".parse().unwrap();
+        let headers = |name: &'static str, value: &'static str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(name, HeaderValue::from_static(value));
+            headers
+        };
+
+        let at = |until: Option<DateTime<Utc>>| until.map(|until| until.to_rfc3339());
+        assert_eq!(
+            at(retry_after(&headers("retry-after", "30"), now)),
+            Some("2025-01-01T10:00:30+00:00".to_string())
+        );
+        assert_eq!(
+            at(retry_after(
+                &headers("retry-after", "Wed, 01 Jan 2025 10:02:00 GMT"),
+                now
+            )),
+            Some("2025-01-01T10:02:00+00:00".to_string())
+        );
+        assert_eq!(
+            at(retry_after(
+                &headers("x-ratelimit-reset", "1735725720"),
+                now
+            )),
+            Some("2025-01-01T10:02:00+00:00".to_string())
+        );
+        assert_eq!(
+            at(retry_after(&headers("ratelimit-reset", "5"), now)),
+            Some("2025-01-01T10:00:05+00:00".to_string())
+        );
+        // Capped at an hour
+        assert_eq!(
+            at(retry_after(&headers("retry-after", "86400"), now)),
+            Some("2025-01-01T11:00:00+00:00".to_string())
+        );
+        assert_eq!(
+            at(retry_after(
+                &headers("retry-after", "9223372036854775807"),
+                now
+            )),
+            Some("2025-01-01T11:00:00+00:00".to_string())
+        );
+        assert_eq!(
+            at(retry_after(
+                &headers("ratelimit-reset", "-9223372036854775808"),
+                now
+            )),
+            Some("2025-01-01T10:00:00+00:00".to_string())
+        );
+        assert_eq!(retry_after(&HeaderMap::new(), now), None);
+    }
+
+    #[test]
+    fn test_classify_checksum_mismatch() {
+        let mismatch = GuideModeError::ChecksumMismatch {
+            session_id: "s1".to_string(),
+            received: "aa".to_string(),
+            sent: "bb".to_string(),
+        };
+        assert_eq!(classify_error(&mismatch), ErrorType::ChecksumMismatch);
     }
 
     #[test]
     fn test_classify_network_errors() {
-        assert_eq!(classify_error("Connection refused"), ErrorType::Network);
-        assert_eq!(classify_error("Timeout"), ErrorType::Network);
-        assert_eq!(classify_error("DNS resolution failed"), ErrorType::Network);
-        assert_eq!(classify_error("Unknown error"), ErrorType::Network);
+        assert_eq!(classify_error(&failed(408)), ErrorType::Network);
+        // Errors that never reached the server, or aren't recognised
+        assert_eq!(
+            classify_error(&GuideModeError::from("Connection refused")),
+            ErrorType::Network
+        );
+        assert_eq!(
+            classify_error(&GuideModeError::Io(std::io::ErrorKind::TimedOut.into())),
+            ErrorType::Network
+        );
     }
 
     #[test]
@@ -333,6 +507,10 @@ mod tests {
             session_id: Some("test-session".to_string()),
             content: None,
             cwd: None,
+            priority: 0,
+            last_attempt_at: None,
+            superseded: false,
+            quality_checked: false,
         }
     }
 }
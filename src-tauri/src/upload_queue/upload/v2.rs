@@ -1,52 +1,94 @@
 //! V2 upload with compression and deduplication.
 //!
 //! Uploads full session content with gzip compression and hash-based deduplication.
+//!
+//! Delta uploads use the same payload, but when the server already holds a
+//! prefix of the transcript (the session's upload base) only the appended
+//! bytes are sent, with the base's hash and length so the server can check it
+//! is extending the copy it has. A server whose copy differs answers 409 and
+//! the session is uploaded in full.
+//!
+//! Servers that report a hash of the content they received after decoding it
+//! have it checked against the bytes sent. A mismatch means the content was
+//! mangled on the way, by a proxy or a truncated request, so the upload
+//! fails instead of the session being marked synced, and its retries skip
+//! the hash check and delta, since the server may now hold the mangled copy.
+//!
+//! Transcripts over the size limit are sent as their head and tail, flagged
+//! `truncated`, and never as deltas: the server holds no full prefix of them
+//! to extend.
 
-use crate::config::GuideModeConfig;
-use crate::database::{get_full_session_by_id, get_session_metrics, get_session_rating};
-use crate::logging::{log_debug, log_info};
-use crate::project_metadata::extract_project_metadata;
+use crate::config::{get_machine_id, load_provider_config, GuideModeConfig, RedactionLevel};
+use crate::database::{
+    get_full_session_by_id, get_session_metrics, get_session_rating, get_uncommitted_snapshot,
+};
+use crate::api_paths;
+use crate::error::GuideModeError;
+use crate::logging::{log_debug, log_info, log_warn};
+use crate::project_aliases::canonical_project_name;
+use crate::project_metadata::{detect_languages, extract_session_file_project_metadata};
+use crate::redaction::redact_jsonl;
 use crate::upload_queue::compression::compress_file_content;
+use crate::upload_queue::embedded::cap_embedded_data;
+use crate::upload_queue::hash_store::{self, UploadBase};
+use crate::upload_queue::hashing::calculate_bytes_hash_sha256;
+use crate::upload_queue::truncation::{is_oversized, truncate_transcript, Truncation};
 use crate::upload_queue::types::UploadItem;
-use chrono::DateTime;
+use crate::validation::MAX_SESSION_FILE_SIZE;
+use crate::workspace_roots;
+use super::conflict::{resolve, Resolution, SessionVersion};
+use super::metrics::{custom_metrics_with_model_usage, read_canonical_content};
+use super::project::{embed_metadata, record_embedded_metadata};
+use super::retry::record_rate_limit;
+use chrono::{DateTime, Utc};
 use serde_json::Value;
 
+/// Server's answer to a hash check
+pub struct HashCheck {
+    pub needs_upload: bool,
+    /// The copy the server holds, when another machine uploaded it
+    pub remote: Option<SessionVersion>,
+}
+
 /// Check if file hash exists on server (v2 upload optimization)
 pub async fn check_file_hash(
     session_id: &str,
     file_hash: &str,
+    machine_id: &str,
     server_url: &str,
     api_key: &str,
-) -> Result<bool, String> {
+) -> Result<HashCheck, GuideModeError> {
     let client = reqwest::Client::new();
     let url = format!(
-        "{}/api/agent-sessions/check-hash?sessionId={}&fileHash={}",
-        server_url, session_id, file_hash
+        "{}?sessionId={}&fileHash={}&machineId={}",
+        api_paths::api_url(server_url, api_paths::CHECK_HASH),
+        session_id,
+        file_hash,
+        machine_id
     );
 
     let response = client
         .get(&url)
         .header("Authorization", format!("Bearer {}", api_key))
         .send()
-        .await
-        .map_err(|e| format!("Failed to check hash: {}", e))?;
+        .await?;
 
     if !response.status().is_success() {
-        let status = response.status();
-        let error_text = response
-            .text()
-            .await
-            .unwrap_or_else(|_| "Unknown error".to_string());
-        return Err(format!(
-            "Hash check failed with status {}: {}",
-            status, error_text
-        ));
+        record_rate_limit(&response);
+        return Err(GuideModeError::from_response("Hash check", response).await);
     }
 
+    // Servers that track machines also describe the copy they hold
     #[derive(serde::Deserialize)]
+    #[serde(rename_all = "camelCase")]
     struct HashCheckResponse {
-        #[serde(rename = "needsUpload")]
         needs_upload: bool,
+        #[serde(default)]
+        machine_id: Option<String>,
+        #[serde(default)]
+        message_count: Option<usize>,
+        #[serde(default)]
+        last_message_at: Option<DateTime<Utc>>,
     }
 
     let hash_response: HashCheckResponse = response
@@ -54,7 +96,21 @@ pub async fn check_file_hash(
         .await
         .map_err(|e| format!("Failed to parse hash check response: {}", e))?;
 
-    Ok(hash_response.needs_upload)
+    let from_other_machine = hash_response
+        .machine_id
+        .is_some_and(|remote_machine| remote_machine != machine_id);
+    let remote = match hash_response.message_count {
+        Some(message_count) if from_other_machine => Some(SessionVersion {
+            message_count,
+            last_message_at: hash_response.last_message_at,
+        }),
+        _ => None,
+    };
+
+    Ok(HashCheck {
+        needs_upload: hash_response.needs_upload,
+        remote,
+    })
 }
 
 /// Upload session using v2 endpoint with compression and deduplication
@@ -63,31 +119,225 @@ pub async fn upload_v2(
     session_id: &str,
     file_hash: &str,
     config: GuideModeConfig,
-) -> Result<(), String> {
+) -> Result<(), GuideModeError> {
+    upload_session(item, session_id, file_hash, config, false).await
+}
+
+/// Upload only what the session appended since its last upload, or the whole
+/// session through v2 when there is no usable base
+pub async fn upload_delta(
+    item: &UploadItem,
+    session_id: &str,
+    file_hash: &str,
+    config: GuideModeConfig,
+) -> Result<(), GuideModeError> {
+    match upload_session(item, session_id, file_hash, config.clone(), true).await {
+        Err(e) if e.status() == Some(reqwest::StatusCode::CONFLICT) => {
+            log_info(
+                "upload-queue",
+                &format!(
+                    "⚠ Server copy of {} differs from the upload base, uploading in full",
+                    session_id
+                ),
+            )
+            .unwrap_or_default();
+            upload_session(item, session_id, file_hash, config, false).await
+        }
+        result => result,
+    }
+}
+
+/// Start of a `GuideModeError::ChecksumMismatch` message, to recognise one
+/// recorded as an item's last error
+pub const CHECKSUM_MISMATCH: &str = "Upload checksum mismatch";
+
+/// Transcript content prepared for an upload
+pub struct PreparedContent {
+    /// Gzipped, base64 encoded content, or only its appended bytes for a delta
+    pub encoded: String,
+    /// SHA256 of the content encoded, as the server should decode it
+    pub content_hash: String,
+    /// The copy on the server a delta extends
+    pub delta_base: Option<UploadBase>,
+    pub version: SessionVersion,
+    /// What was left out of a transcript too large to send whole
+    pub truncation: Option<Truncation>,
+}
+
+impl PreparedContent {
+    /// Cut `content` (as returned by `upload_content`) down to its head and
+    /// tail if it's over the size limit, then gzip and base64 encode it
+    pub fn new(
+        content: &[u8],
+        delta_base: Option<UploadBase>,
+        version: SessionVersion,
+    ) -> Result<Self, String> {
+        let truncated = truncate_transcript(content, MAX_SESSION_FILE_SIZE as usize);
+        let (content, truncation) = match &truncated {
+            Some((kept, truncation)) => (kept.as_slice(), Some(truncation.clone())),
+            None => (content, None),
+        };
+
+        // Compress the file content
+        let compressed = compress_file_content(content)?;
+
+        // Encode compressed content to base64
+        use base64::Engine;
+        Ok(Self {
+            encoded: base64::engine::general_purpose::STANDARD.encode(&compressed),
+            content_hash: calculate_bytes_hash_sha256(content),
+            delta_base,
+            version,
+            truncation,
+        })
+    }
+}
+
+/// The copy on the server that `content` can be sent as a delta of, if any
+pub fn delta_base_for(
+    provider: &str,
+    session_id: &str,
+    content: &[u8],
+    allow_delta: bool,
+) -> Option<UploadBase> {
+    if !allow_delta || is_oversized(content) {
+        return None;
+    }
+    hash_store::upload_base(provider, session_id).filter(|base| extends_base(content, base))
+}
+
+/// Check the hash the server reports for the content it received, if any,
+/// against the hash of the content sent
+fn verify_checksum(
+    session_id: &str,
+    response_body: &str,
+    sent_hash: &str,
+) -> Result<(), GuideModeError> {
+    let received_hash = serde_json::from_str::<Value>(response_body)
+        .ok()
+        .and_then(|body| body["contentHash"].as_str().map(str::to_string));
+    match received_hash {
+        Some(received_hash) if !received_hash.eq_ignore_ascii_case(sent_hash) => {
+            Err(GuideModeError::ChecksumMismatch {
+                session_id: session_id.to_string(),
+                received: received_hash,
+                sent: sent_hash.to_string(),
+            })
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Whether a recorded upload error is a checksum mismatch
+pub fn is_checksum_mismatch(error: &str) -> bool {
+    error.starts_with(CHECKSUM_MISMATCH)
+}
+
+/// Whether `content` extends the bytes recorded in `base`
+pub fn extends_base(content: &[u8], base: &UploadBase) -> bool {
+    let base_length = base.content_length as usize;
+    content.len() > base_length
+        && calculate_bytes_hash_sha256(&content[..base_length]) == base.content_hash
+}
+
+async fn upload_session(
+    item: &UploadItem,
+    session_id: &str,
+    file_hash: &str,
+    config: GuideModeConfig,
+    allow_delta: bool,
+) -> Result<(), GuideModeError> {
     let api_key = config.api_key.clone().ok_or("No API key configured")?;
     let server_url = config
         .server_url
         .clone()
         .ok_or("No server URL configured")?;
 
+    let machine_id =
+        get_machine_id().map_err(|e| format!("Failed to get machine ID: {}", e))?;
+
+    // The server may hold a mangled copy after a checksum mismatch; send it all again
+    let resend = item.last_error.as_deref().is_some_and(is_checksum_mismatch);
+    let allow_delta = allow_delta && !resend;
+
     // Check if server already has this file
-    let needs_upload = check_file_hash(session_id, file_hash, &server_url, &api_key).await?;
+    let hash_check = if resend {
+        HashCheck {
+            needs_upload: true,
+            remote: None,
+        }
+    } else {
+        check_file_hash(session_id, file_hash, &machine_id, &server_url, &api_key).await?
+    };
 
     // Prepare content only if needed
-    let compressed_content = if needs_upload {
+    let mut uploaded_base = None;
+    let content = if hash_check.needs_upload {
         // Read file content
         let file_content = if let Some(ref content) = item.content {
             content.as_bytes().to_vec()
         } else {
             std::fs::read(&item.file_path).map_err(|e| format!("Failed to read file: {}", e))?
         };
+        let file_content = upload_content(item.cwd.as_deref(), file_content);
 
-        // Compress the file content
-        let compressed = compress_file_content(&file_content)?;
+        // Another machine may hold a more complete copy of a cloud-synced session
+        let version = SessionVersion::of_content(&String::from_utf8_lossy(&file_content));
+        if let Some(remote) = &hash_check.remote {
+            if resolve(&version, remote) == Resolution::KeepRemote {
+                log_info(
+                    "upload-queue",
+                    &format!(
+                        "⚡ Another machine uploaded a more complete copy of {} ({} vs {} messages), skipping",
+                        session_id, remote.message_count, version.message_count
+                    ),
+                )
+                .unwrap_or_default();
+                return Ok(());
+            }
+        }
 
-        // Encode compressed content to base64
-        use base64::Engine;
-        Some(base64::engine::general_purpose::STANDARD.encode(&compressed))
+        // Send only the appended bytes when the server holds a prefix of this
+        // file; a truncated upload leaves the server nothing to extend
+        if !is_oversized(&file_content) {
+            uploaded_base = Some(UploadBase {
+                content_hash: calculate_bytes_hash_sha256(&file_content),
+                content_length: file_content.len() as u64,
+            });
+        }
+        let delta_base = delta_base_for(&item.provider, session_id, &file_content, allow_delta);
+        let appended = match &delta_base {
+            Some(base) => &file_content[base.content_length as usize..],
+            None => &file_content[..],
+        };
+        if item.superseded {
+            let upload = if delta_base.is_some() {
+                "the appended messages"
+            } else {
+                "it in full"
+            };
+            log_info(
+                "upload-queue",
+                &format!(
+                    "↻ Session {} changed after it synced, uploading {}",
+                    session_id, upload
+                ),
+            )
+            .unwrap_or_default();
+        }
+
+        let content = PreparedContent::new(appended, delta_base, version)?;
+        if let Some(truncation) = &content.truncation {
+            log_warn(
+                "upload-queue",
+                &format!(
+                    "⚠ Session {} is too large to upload whole ({} bytes), uploading its first {} and last {} messages",
+                    session_id, truncation.original_size, truncation.head_lines, truncation.tail_lines
+                ),
+            )
+            .unwrap_or_default();
+        }
+        Some(content)
     } else {
         log_info(
             "upload-queue",
@@ -100,6 +350,100 @@ pub async fn upload_v2(
         None
     };
 
+    let upload_request =
+        build_upload_request(item, session_id, file_hash, &machine_id, content.as_ref())?;
+
+    // Make HTTP request to the v2 or delta endpoint
+    let client = reqwest::Client::new();
+    let is_delta = content
+        .as_ref()
+        .is_some_and(|content| content.delta_base.is_some());
+    let (endpoint, upload_kind) = if is_delta {
+        (api_paths::SESSION_UPLOAD_DELTA, "delta")
+    } else {
+        (api_paths::SESSION_UPLOAD_V2, "v2")
+    };
+    let url = api_paths::api_url(&server_url, endpoint);
+
+    let response = client
+        .post(&url)
+        .header("Authorization", format!("Bearer {}", api_key))
+        .header("Content-Type", "application/json")
+        .json(&upload_request)
+        .send()
+        .await?;
+
+    if !response.status().is_success() {
+        record_rate_limit(&response);
+        let action = format!("Upload {}", upload_kind);
+        return Err(GuideModeError::from_response(&action, response).await);
+    }
+
+    if let Some(ref content) = content {
+        let response_body = response.text().await.unwrap_or_default();
+        verify_checksum(session_id, &response_body, &content.content_hash)?;
+    }
+
+    if let Some(ref base) = uploaded_base {
+        hash_store::record_upload_base(&item.provider, session_id, base);
+    }
+    record_embedded_metadata(&upload_request);
+
+    log_debug(
+        "upload-queue",
+        &format!("✓ Uploaded session via {} for {}", upload_kind, session_id),
+    )
+    .unwrap_or_default();
+
+    Ok(())
+}
+
+/// The transcript bytes an upload sends: redacted, with oversized embedded
+/// data (base64 images etc.) replaced by hashed placeholders
+///
+/// Upload bases are hashed over these bytes rather than the file's, so they
+/// match the copy the server holds.
+pub fn upload_content(cwd: Option<&str>, content: Vec<u8>) -> Vec<u8> {
+    let content = redacted_content(cwd, content);
+    let capped = match std::str::from_utf8(&content) {
+        Ok(text) => match cap_embedded_data(text) {
+            std::borrow::Cow::Owned(capped) => Some(capped.into_bytes()),
+            std::borrow::Cow::Borrowed(_) => None,
+        },
+        Err(_) => None,
+    };
+    capped.unwrap_or(content)
+}
+
+/// The transcript with what its workspace root asks to scrub redacted
+///
+/// Redaction is line by line and deterministic, so redacted transcripts
+/// still extend the prefix the server holds and can upload as deltas.
+fn redacted_content(cwd: Option<&str>, content: Vec<u8>) -> Vec<u8> {
+    match workspace_roots::redaction_level(cwd) {
+        RedactionLevel::None => content,
+        level => {
+            let home_dir = dirs::home_dir().map(|home| home.to_string_lossy().to_string());
+            redact_jsonl(
+                &String::from_utf8_lossy(&content),
+                level,
+                home_dir.as_deref(),
+            )
+            .into_bytes()
+        }
+    }
+}
+
+/// Build the body of a v2 or delta upload
+///
+/// `content` is left out when the server already holds the file.
+pub fn build_upload_request(
+    item: &UploadItem,
+    session_id: &str,
+    file_hash: &str,
+    machine_id: &str,
+    content: Option<&PreparedContent>,
+) -> Result<Value, String> {
     // Get full session data from database
     let session_data = get_full_session_by_id(session_id)
         .map_err(|e| format!("Failed to get session data: {}", e))?
@@ -122,7 +466,7 @@ pub async fn upload_v2(
 
     // Extract project metadata if CWD is available (will be embedded in payload)
     let (final_project_name, project_metadata) = if let Some(ref cwd) = item.cwd {
-        match extract_project_metadata(cwd) {
+        match extract_session_file_project_metadata(&item.provider, cwd, &item.file_path) {
             Ok(metadata) => {
                 // Project metadata will be embedded in the upload payload
                 let project_name = metadata.project_name.clone();
@@ -137,7 +481,7 @@ pub async fn upload_v2(
     // Prepare upload request with embedded metrics and project metadata
     let mut upload_request = serde_json::json!({
         "provider": session_data.provider,
-        "projectName": final_project_name,
+        "projectName": canonical_project_name(&final_project_name),
         "sessionId": session_data.session_id,
         "fileName": session_data.file_name,
         "filePath": session_data.file_path,
@@ -146,6 +490,7 @@ pub async fn upload_v2(
         "sessionStartTime": timestamp_to_iso(session_data.session_start_time),
         "sessionEndTime": timestamp_to_iso(session_data.session_end_time),
         "durationMs": session_data.duration_ms,
+        "activeDurationMs": metrics.as_ref().and_then(|m| m.active_duration_ms()),
         "processingStatus": session_data.processing_status,
         "queuedAt": timestamp_to_iso(session_data.queued_at),
         "processedAt": timestamp_to_iso(session_data.processed_at),
@@ -161,21 +506,64 @@ pub async fn upload_v2(
         "gitBranch": session_data.git_branch,
         "firstCommitHash": session_data.first_commit_hash,
         "latestCommitHash": session_data.latest_commit_hash,
+        "resumedFromSessionId": session_data.resumed_from,
+        "machineId": machine_id,
     });
 
     // Add project metadata if available
     if let Some(ref metadata) = project_metadata {
-        upload_request["projectMetadata"] = serde_json::json!({
+        let metadata = serde_json::json!({
             "gitRemoteUrl": metadata.git_remote_url,
             "cwd": metadata.cwd,
             "detectedProjectType": metadata.detected_project_type,
+            "frameworks": metadata.frameworks,
+            "languages": detect_languages(&metadata.cwd),
         });
+        embed_metadata(&mut upload_request, metadata);
     }
 
-    // Add compressed content if needed
-    if let Some(content) = compressed_content {
-        upload_request["content"] = serde_json::json!(content);
+    let tags = workspace_roots::tags(item.cwd.as_deref());
+    if !tags.is_empty() {
+        upload_request["tags"] = serde_json::json!(tags);
+    }
+
+    // Attach the working tree patch of sessions that never committed, if enabled
+    let attach_patch = load_provider_config(&session_data.provider)
+        .map(|provider_config| provider_config.attach_uncommitted_patch)
+        .unwrap_or(false);
+    if attach_patch {
+        if let Some(snapshot) = get_uncommitted_snapshot(session_id).ok().flatten() {
+            upload_request["uncommittedChanges"] = serde_json::json!({
+                "headCommit": snapshot.head_commit,
+                "filesChanged": snapshot.files_changed,
+                "linesAdded": snapshot.lines_added,
+                "linesRemoved": snapshot.lines_removed,
+                "patch": snapshot.patch,
+                "patchTruncated": snapshot.patch_truncated,
+                "capturedAt": timestamp_to_iso(Some(snapshot.captured_at)),
+            });
+        }
+    }
+
+    if let Some(content) = content {
+        // Add compressed content
+        upload_request["content"] = serde_json::json!(content.encoded);
         upload_request["contentEncoding"] = serde_json::json!("gzip");
+
+        if let Some(ref base) = content.delta_base {
+            upload_request["baseHash"] = serde_json::json!(base.content_hash);
+            upload_request["baseLength"] = serde_json::json!(base.content_length);
+        }
+
+        if let Some(ref truncation) = content.truncation {
+            upload_request["truncated"] = serde_json::json!(true);
+            upload_request["truncation"] = serde_json::json!(truncation);
+        }
+
+        // Describe the uploaded copy so other machines can compare theirs against it
+        upload_request["messageCount"] = serde_json::json!(content.version.message_count);
+        upload_request["lastMessageAt"] =
+            serde_json::json!(content.version.last_message_at.map(|dt| dt.to_rfc3339()));
     }
 
     // Add metrics if available
@@ -197,6 +585,8 @@ pub async fn upload_v2(
                 .and_then(|str_val| serde_json::from_str::<Vec<i64>>(str_val).ok())
         };
 
+        let canonical_content = read_canonical_content(item);
+
         upload_request["metrics"] = serde_json::json!({
             "sessionId": m.session_id,
             "provider": m.provider,
@@ -219,6 +609,8 @@ pub async fn upload_v2(
             // Engagement metrics
             "interruptionRate": m.interruption_rate,
             "sessionLengthMinutes": m.session_length_minutes,
+            "activeLengthMinutes": m.active_length_minutes,
+            "idleGapCount": m.idle_gap_count,
             "totalInterruptions": m.total_interruptions,
             "engagementTotalResponses": m.engagement_total_responses,
             // Quality metrics
@@ -257,42 +649,72 @@ pub async fn upload_v2(
             "compactEventSteps": parse_compact_steps(&m.compact_event_steps),
             "avgTokensPerMessage": m.avg_tokens_per_message,
             "messagesUntilFirstCompact": m.messages_until_first_compact,
+            "peakContextUtilizationPercent": m.peak_context_utilization_percent,
+            "avgContextUtilizationPercent": m.avg_context_utilization_percent,
+            "contextCeilingHits": m.context_ceiling_hits,
             "contextImprovementTips": parse_array(&m.context_improvement_tips),
             // Custom metrics
-            "customMetrics": m.custom_metrics.as_ref().and_then(|s| serde_json::from_str::<Value>(s).ok()),
+            "customMetrics": custom_metrics_with_model_usage(m.custom_metrics.as_deref(), canonical_content.as_deref()),
         });
     }
 
-    // Make HTTP request to v2 endpoint
-    let client = reqwest::Client::new();
-    let url = format!("{}/api/agent-sessions/upload-v2", server_url);
+    Ok(upload_request)
+}
 
-    let response = client
-        .post(&url)
-        .header("Authorization", format!("Bearer {}", api_key))
-        .header("Content-Type", "application/json")
-        .json(&upload_request)
-        .send()
-        .await
-        .map_err(|e| format!("HTTP request failed: {}", e))?;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::upload_queue::embedded::MAX_EMBEDDED_DATA_BYTES;
 
-    if !response.status().is_success() {
-        let status = response.status();
-        let error_text = response
-            .text()
-            .await
-            .unwrap_or_else(|_| "Unknown error".to_string());
-        return Err(format!(
-            "Upload v2 failed with status {}: {}",
-            status, error_text
-        ));
+    #[test]
+    fn test_extends_base() {
+        let base = UploadBase {
+            content_hash: calculate_bytes_hash_sha256(b"{\"a\":1}"),
+            content_length: 7,
+        };
+        assert!(extends_base(b"{\"a\":1}\n{\"b\":2}", &base));
+        // Unchanged, rewritten or truncated files need a full upload
+        assert!(!extends_base(b"{\"a\":1}", &base));
+        assert!(!extends_base(b"{\"a\":9}\n{\"b\":2}", &base));
+        assert!(!extends_base(b"{", &base));
     }
 
-    log_debug(
-        "upload-queue",
-        &format!("✓ Uploaded session via v2 for {}", session_id),
-    )
-    .unwrap_or_default();
+    #[test]
+    fn test_base_covers_capped_content() {
+        let image = serde_json::json!({
+            "toolUseResult": {"file": {"base64": "A".repeat(MAX_EMBEDDED_DATA_BYTES + 1)}}
+        });
+        let first = format!("{}\n", image);
+        let sent = upload_content(None, first.clone().into_bytes());
+        assert!(sent.len() < first.len());
+        let base = UploadBase {
+            content_hash: calculate_bytes_hash_sha256(&sent),
+            content_length: sent.len() as u64,
+        };
 
-    Ok(())
+        // The file grows; what it would send next still extends what was sent
+        let grown = upload_content(None, format!("{}{{\"b\":2}}\n", first).into_bytes());
+        assert!(extends_base(&grown, &base));
+        assert_eq!(&grown[base.content_length as usize..], b"{\"b\":2}\n");
+    }
+
+    #[test]
+    fn test_verify_checksum() {
+        let sent = calculate_bytes_hash_sha256(b"{\"a\":1}");
+        let ok = format!(r#"{{"success":true,"contentHash":"{}"}}"#, sent.to_uppercase());
+        assert!(verify_checksum("s1", &ok, &sent).is_ok());
+        // Servers that don't report a hash aren't checked
+        assert!(verify_checksum("s1", r#"{"success":true}"#, &sent).is_ok());
+        assert!(verify_checksum("s1", "", &sent).is_ok());
+
+        let truncated = calculate_bytes_hash_sha256(b"{\"a\"");
+        let error = verify_checksum(
+            "s1",
+            &format!(r#"{{"contentHash":"{}"}}"#, truncated),
+            &sent,
+        )
+        .unwrap_err();
+        assert!(matches!(error, GuideModeError::ChecksumMismatch { .. }));
+        assert!(is_checksum_mismatch(&error.to_string()));
+    }
 }
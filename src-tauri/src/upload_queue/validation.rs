@@ -1,70 +1,203 @@
-//! Validation utilities for JSONL content and file checks.
+//! Validation of sessions before they're queued for upload.
 //!
-//! Ensures upload items meet quality requirements before processing.
+//! A session is queued only if it passes its provider's `EnqueueRules`: by
+//! default, at least one message with a timestamp. Each provider's config can
+//! override the required fields, a maximum size and a minimum message count,
+//! for exports the defaults wrongly reject, or bypass the checks entirely
+//! while debugging.
 
+use crate::config::{load_provider_config, EnqueueValidation};
 use crate::logging::log_warn;
+use crate::providers::common::ingest_log::SkipReason;
 use serde_json::Value;
+use std::fmt;
 
-/// Validate that JSONL content contains at least one entry with a timestamp field
-pub fn validate_jsonl_timestamps(content: &str) -> (bool, Option<String>) {
-    let lines: Vec<&str> = content
-        .lines()
-        .filter(|line| !line.trim().is_empty())
-        .collect();
-
-    if lines.is_empty() {
-        return (
-            false,
-            Some("File is empty or contains only whitespace".to_string()),
-        );
+/// Checks a session must pass to be queued for upload
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnqueueRules {
+    /// Fields at least one message must have
+    pub required_fields: Vec<String>,
+    /// Largest session queued, in bytes; larger ones are uploaded truncated
+    /// when unset
+    pub max_size_bytes: Option<u64>,
+    pub min_messages: usize,
+    /// Queue sessions without checking them
+    pub bypass: bool,
+}
+
+impl Default for EnqueueRules {
+    fn default() -> Self {
+        Self {
+            required_fields: vec!["timestamp".to_string()],
+            max_size_bytes: None,
+            min_messages: 1,
+            bypass: false,
+        }
     }
+}
 
-    let mut has_valid_json = false;
-    let mut parse_errors = 0;
+/// Why a session can't be queued
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RuleViolation {
+    Empty,
+    NoValidJson {
+        parse_errors: usize,
+    },
+    MissingField {
+        field: String,
+        lines: usize,
+        valid: usize,
+    },
+    TooFewMessages {
+        messages: usize,
+        min: usize,
+    },
+    TooLarge {
+        size: u64,
+        max: u64,
+    },
+}
 
-    // Check if at least one line has a timestamp field
-    for (index, line) in lines.iter().enumerate() {
-        if let Ok(entry) = serde_json::from_str::<Value>(line) {
-            has_valid_json = true;
-            // Look for timestamp field (common across providers)
-            if entry.get("timestamp").is_some() {
-                return (true, None);
+impl fmt::Display for RuleViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RuleViolation::Empty => write!(f, "File is empty or contains only whitespace"),
+            RuleViolation::NoValidJson { parse_errors } => write!(
+                f,
+                "No valid JSON lines found ({} parse errors)",
+                parse_errors
+            ),
+            RuleViolation::MissingField {
+                field,
+                lines,
+                valid,
+            } => write!(
+                f,
+                "No {} field found in any of {} lines ({} valid JSON entries)",
+                field, lines, valid
+            ),
+            RuleViolation::TooFewMessages { messages, min } => write!(
+                f,
+                "{} messages, fewer than the minimum of {}",
+                messages, min
+            ),
+            RuleViolation::TooLarge { size, max } => {
+                write!(f, "{} bytes, over the maximum of {}", size, max)
             }
-        } else {
-            parse_errors += 1;
-            if index < 3 {
-                // Log first few parse errors for debugging
-                log_warn(
-                    "upload-queue",
-                    &format!(
-                        "  Line {} failed to parse as JSON: {}",
-                        index + 1,
-                        &line[..line.len().min(100)]
-                    ),
-                )
-                .unwrap_or_default();
+        }
+    }
+}
+
+impl RuleViolation {
+    /// How the ingest stats count the session
+    pub fn skip_reason(&self) -> SkipReason {
+        match self {
+            RuleViolation::MissingField { field, .. } if field == "timestamp" => {
+                SkipReason::NoTimestamps
             }
+            _ => SkipReason::Invalid,
         }
     }
+}
 
-    if !has_valid_json {
-        return (
-            false,
-            Some(format!(
-                "No valid JSON lines found ({} parse errors)",
-                parse_errors
-            )),
-        );
+impl EnqueueRules {
+    /// The defaults with the provider's configured overrides
+    pub fn for_provider(provider: &str) -> Self {
+        let overrides = load_provider_config(provider)
+            .map(|config| config.enqueue_validation)
+            .unwrap_or_default();
+        Self::default().with_overrides(&overrides)
     }
 
-    (
-        false,
-        Some(format!(
-            "No timestamp field found in any of {} lines ({} valid JSON entries)",
-            lines.len(),
-            lines.len() - parse_errors
-        )),
-    )
+    pub fn with_overrides(mut self, overrides: &EnqueueValidation) -> Self {
+        if let Some(fields) = &overrides.required_fields {
+            self.required_fields = fields.clone();
+        }
+        if let Some(max) = overrides.max_size_bytes {
+            self.max_size_bytes = Some(max);
+        }
+        if let Some(min) = overrides.min_messages {
+            self.min_messages = min;
+        }
+        self.bypass = overrides.bypass;
+        self
+    }
+
+    /// Check JSONL content against the rules
+    pub fn check(&self, content: &str) -> Result<(), RuleViolation> {
+        if self.bypass {
+            return Ok(());
+        }
+        if let Some(max) = self.max_size_bytes {
+            let size = content.len() as u64;
+            if size > max {
+                return Err(RuleViolation::TooLarge { size, max });
+            }
+        }
+
+        let lines: Vec<&str> = content
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .collect();
+        if lines.is_empty() {
+            return Err(RuleViolation::Empty);
+        }
+
+        let mut missing: Vec<&str> = self.required_fields.iter().map(String::as_str).collect();
+        let mut valid = 0;
+        let mut parse_errors = 0;
+        for (index, line) in lines.iter().enumerate() {
+            // Every field found and enough messages counted: nothing left to check
+            if missing.is_empty() && valid >= self.min_messages {
+                return Ok(());
+            }
+            if let Ok(entry) = serde_json::from_str::<Value>(line) {
+                valid += 1;
+                missing.retain(|field| entry.get(field).is_none());
+            } else {
+                parse_errors += 1;
+                if index < 3 {
+                    // Log first few parse errors for debugging
+                    log_warn(
+                        "upload-queue",
+                        &format!(
+                            "  Line {} failed to parse as JSON: {}",
+                            index + 1,
+                            &line[..line.len().min(100)]
+                        ),
+                    )
+                    .unwrap_or_default();
+                }
+            }
+        }
+
+        if valid == 0 {
+            return Err(RuleViolation::NoValidJson { parse_errors });
+        }
+        if let Some(field) = missing.first() {
+            return Err(RuleViolation::MissingField {
+                field: field.to_string(),
+                lines: lines.len(),
+                valid,
+            });
+        }
+        if valid < self.min_messages {
+            return Err(RuleViolation::TooFewMessages {
+                messages: valid,
+                min: self.min_messages,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Validate that JSONL content contains at least one entry with a timestamp field
+#[cfg(test)]
+pub fn validate_jsonl_timestamps(content: &str) -> (bool, Option<String>) {
+    match EnqueueRules::default().check(content) {
+        Ok(()) => (true, None),
+        Err(violation) => (false, Some(violation.to_string())),
+    }
 }
 
 #[cfg(test)]
@@ -102,4 +235,48 @@ mod tests {
         assert!(is_valid);
         assert!(error.is_none());
     }
+
+    #[test]
+    fn test_overridden_rules() {
+        let content = "{\"ts\":1,\"type\":\"user\"}\n{\"type\":\"assistant\"}";
+        let default_violation = EnqueueRules::default().check(content).unwrap_err();
+        assert_eq!(default_violation.skip_reason(), SkipReason::NoTimestamps);
+
+        let rules = EnqueueRules::default().with_overrides(&EnqueueValidation {
+            required_fields: Some(vec!["ts".to_string(), "type".to_string()]),
+            ..Default::default()
+        });
+        assert_eq!(rules.check(content), Ok(()));
+
+        let rules = rules.with_overrides(&EnqueueValidation {
+            min_messages: Some(3),
+            max_size_bytes: Some(1000),
+            ..Default::default()
+        });
+        assert_eq!(
+            rules.check(content),
+            Err(RuleViolation::TooFewMessages {
+                messages: 2,
+                min: 3
+            })
+        );
+
+        let rules = rules.with_overrides(&EnqueueValidation {
+            max_size_bytes: Some(10),
+            ..Default::default()
+        });
+        let violation = rules.check(content).unwrap_err();
+        assert!(matches!(violation, RuleViolation::TooLarge { max: 10, .. }));
+        assert_eq!(violation.skip_reason(), SkipReason::Invalid);
+    }
+
+    #[test]
+    fn test_bypass_accepts_anything() {
+        let rules = EnqueueRules::default().with_overrides(&EnqueueValidation {
+            bypass: true,
+            ..Default::default()
+        });
+        assert_eq!(rules.check(""), Ok(()));
+        assert_eq!(rules.check("not json"), Ok(()));
+    }
 }
@@ -15,6 +15,13 @@ fn get_allowed_directories() -> Result<Vec<PathBuf>, GuideModeError> {
         home_dir.join(".codex"),   // Codex sessions
     ];
 
+    // Relocated canonical session cache
+    if let Ok(sessions_dir) = crate::config::get_sessions_dir() {
+        if !sessions_dir.starts_with(home_dir.join(".guidemode")) {
+            allowed.push(sessions_dir);
+        }
+    }
+
     // OpenCode path (platform-specific)
     #[cfg(target_os = "macos")]
     allowed.push(home_dir.join(".local/share/opencode"));
@@ -106,6 +113,7 @@ pub fn validate_file_size(path: &Path, max_size: u64) -> Result<u64, GuideModeEr
 }
 
 /// Validate both path and size for session files
+#[cfg(test)]
 pub fn validate_session_file(path: &Path) -> Result<(PathBuf, u64), GuideModeError> {
     let canonical_path = validate_file_path(path)?;
     let size = validate_file_size(&canonical_path, MAX_SESSION_FILE_SIZE)?;
@@ -0,0 +1,98 @@
+//! Native windows beyond the main one
+//!
+//! A session can be opened in its own window, labelled `session-{id}`, which
+//! loads the frontend's standalone `/session-window/{id}` route. Background
+//! processing only runs in the main window; session windows just display.
+//!
+//! Session events go to the main window and to the window showing that
+//! session, if one is open, rather than to every window.
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager, WebviewUrl, WebviewWindowBuilder};
+
+pub const MAIN_WINDOW: &str = "main";
+
+/// Prefix of session window labels, matched by the read-only session window
+/// capability in tauri.conf.json
+const SESSION_WINDOW_PREFIX: &str = "session-";
+
+/// Label of the window showing `session_id`
+///
+/// Window labels only allow alphanumerics, `-`, `/`, `:` and `_`.
+fn session_window_label(session_id: &str) -> String {
+    let id: String = session_id
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("{}{}", SESSION_WINDOW_PREFIX, id)
+}
+
+/// Bring the main window to the front
+pub fn focus_main_window(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window(MAIN_WINDOW) {
+        let _ = window.unminimize();
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+}
+
+/// Open `session_id` in its own window, or focus the window already showing it
+pub fn open_session_window(app: &AppHandle, session_id: &str) -> Result<(), String> {
+    let label = session_window_label(session_id);
+    if let Some(window) = app.get_webview_window(&label) {
+        window
+            .show()
+            .and_then(|_| window.set_focus())
+            .map_err(|e| format!("Failed to focus session window: {}", e))?;
+        return Ok(());
+    }
+
+    let route = format!("session-window/{}", urlencoding::encode(session_id));
+    let short_id: String = session_id.chars().take(8).collect();
+    WebviewWindowBuilder::new(app, &label, WebviewUrl::App(route.into()))
+        .title(format!("GuideMode · Session {}", short_id))
+        .inner_size(960.0, 800.0)
+        .min_inner_size(480.0, 400.0)
+        .build()
+        .map_err(|e| format!("Failed to open session window: {}", e))?;
+    Ok(())
+}
+
+/// Emit a session's event to the main window and that session's window
+pub fn emit_session_event<S: Serialize + Clone>(
+    app: &AppHandle,
+    event: &str,
+    session_id: &str,
+    payload: S,
+) {
+    let _ = app.emit_to(MAIN_WINDOW, event, payload.clone());
+
+    let label = session_window_label(session_id);
+    if app.get_webview_window(&label).is_some() {
+        let _ = app.emit_to(label.as_str(), event, payload);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session_window_label() {
+        assert_eq!(
+            session_window_label("0199a3f2-7c1e-4b7a"),
+            "session-0199a3f2-7c1e-4b7a"
+        );
+        // Gemini and Cursor IDs can carry characters labels don't allow
+        assert_eq!(
+            session_window_label("chat.2025/01 x"),
+            "session-chat_2025_01_x"
+        );
+    }
+}
@@ -0,0 +1,151 @@
+//! Workspace roots: default policies by directory
+//!
+//! The config can declare roots as globs over directories (`~/work/**`,
+//! `/src/*/client`), each with the sync mode, redaction level and tags its
+//! sessions get. A session's policy is that of the first root its working
+//! directory matches; the root's sync mode overrides the provider's, so a
+//! client's repositories can stay local while everything else syncs.
+
+use crate::config::{load_config, load_provider_config, RedactionLevel, WorkspaceRoot};
+use crate::providers::common::canonical_path::{expand_home, SessionPath};
+
+fn parse(path: &str) -> SessionPath {
+    SessionPath::parse(&expand_home(path).to_string_lossy())
+}
+
+/// Path components with `~` expanded, led by the drive or share on Windows
+pub(crate) fn components(path: &str) -> Vec<String> {
+    parse(path).rooted_components()
+}
+
+/// Components lower-cased for Windows paths, whose case doesn't matter
+pub(crate) fn fold_case(components: Vec<String>, windows: bool) -> Vec<String> {
+    if !windows {
+        return components;
+    }
+    components
+        .into_iter()
+        .map(|component| component.to_lowercase())
+        .collect()
+}
+
+/// Whether a component matches a pattern component with `*` wildcards
+pub(crate) fn matches_component(pattern: &str, component: &str) -> bool {
+    match pattern.split_once('*') {
+        None => pattern == component,
+        Some((prefix, rest)) => {
+            let Some(remaining) = component.strip_prefix(prefix) else {
+                return false;
+            };
+            (0..=remaining.len())
+                .filter(|&start| remaining.is_char_boundary(start))
+                .any(|start| matches_component(rest, &remaining[start..]))
+        }
+    }
+}
+
+fn matches_components(pattern: &[String], path: &[String]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((first, rest)) if first == "**" => {
+            (0..=path.len()).any(|skip| matches_components(rest, &path[skip..]))
+        }
+        Some((first, rest)) => match path.split_first() {
+            Some((component, path_rest)) => {
+                matches_component(first, component) && matches_components(rest, path_rest)
+            }
+            None => false,
+        },
+    }
+}
+
+/// Whether `cwd` matches a root's glob
+pub fn matches(pattern: &str, cwd: &str) -> bool {
+    let cwd = parse(cwd);
+    let windows = cwd.is_windows();
+    matches_components(
+        &fold_case(components(pattern), windows),
+        &fold_case(cwd.rooted_components(), windows),
+    )
+}
+
+/// The first root `cwd` matches
+pub fn policy_for<'a>(roots: &'a [WorkspaceRoot], cwd: &str) -> Option<&'a WorkspaceRoot> {
+    roots.iter().find(|root| matches(&root.pattern, cwd))
+}
+
+/// The configured policy of sessions run in `cwd`
+pub fn current_policy(cwd: Option<&str>) -> Option<WorkspaceRoot> {
+    let cwd = cwd?;
+    let config = load_config().ok()?;
+    policy_for(&config.workspace_roots, cwd).cloned()
+}
+
+/// The sync mode of a session: its workspace root's, or else its provider's
+pub fn effective_sync_mode(provider: &str, cwd: Option<&str>) -> Option<String> {
+    match current_policy(cwd).and_then(|root| root.sync_mode) {
+        Some(sync_mode) => Some(sync_mode),
+        None => load_provider_config(provider)
+            .ok()
+            .map(|config| config.sync_mode),
+    }
+}
+
+/// The redaction level of sessions run in `cwd`
+pub fn redaction_level(cwd: Option<&str>) -> RedactionLevel {
+    current_policy(cwd)
+        .map(|root| root.redaction)
+        .unwrap_or_default()
+}
+
+/// The tags sent with uploads of sessions run in `cwd`
+pub fn tags(cwd: Option<&str>) -> Vec<String> {
+    current_policy(cwd)
+        .map(|root| root.tags)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn root(pattern: &str, sync_mode: Option<&str>) -> WorkspaceRoot {
+        WorkspaceRoot {
+            pattern: pattern.to_string(),
+            sync_mode: sync_mode.map(str::to_string),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_glob_matching() {
+        assert!(matches("/work/**", "/work"));
+        assert!(matches("/work/**", "/work/client/app/"));
+        assert!(!matches("/work/**", "/workshop/app"));
+        assert!(matches("/src/*/client", "/src/acme/client"));
+        assert!(!matches("/src/*/client", "/src/acme/web/client"));
+        assert!(matches("/src/**/client", "/src/acme/web/client"));
+        assert!(matches("/src/acme-*", "/src/acme-api"));
+        assert!(!matches("/src/acme-*", "/src/other"));
+        assert!(matches("C:/work/**", "C:\\work\\app"));
+        assert!(matches("C:/Work/**", "c:\\work\\App"));
+        assert!(matches("C:/work/*", "\\\\?\\C:\\work\\app"));
+        assert!(matches("//nas/work/**", "\\\\NAS\\work\\app"));
+        assert!(!matches("/Work/**", "/work/app"));
+
+        let home = dirs::home_dir().unwrap().to_string_lossy().to_string();
+        assert!(matches("~/work/**", &format!("{}/work/app", home)));
+    }
+
+    #[test]
+    fn test_first_matching_root_wins() {
+        let roots = [
+            root("/work/client/**", Some("Nothing")),
+            root("/work/**", Some("Metrics Only")),
+        ];
+        let policy = |cwd| policy_for(&roots, cwd).and_then(|root| root.sync_mode.as_deref());
+        assert_eq!(policy("/work/client/app"), Some("Nothing"));
+        assert_eq!(policy("/work/oss/lib"), Some("Metrics Only"));
+        assert_eq!(policy("/home/dev/app"), None);
+    }
+}
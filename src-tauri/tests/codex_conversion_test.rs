@@ -2,6 +2,9 @@
 ///
 /// This test validates the conversion logic without needing the Tauri command layer.
 
+use guidemode_desktop::providers::canonical::converter::ToCanonical;
+use guidemode_desktop::providers::canonical::{CanonicalMessage, ContentBlock, ContentValue, MessageType};
+use guidemode_desktop::providers::codex::CodexMessage;
 use std::fs;
 use std::path::PathBuf;
 
@@ -104,3 +107,122 @@ fn test_parse_codex_session_sample() {
     }
 }
 
+/// Parse and convert a fixture rollout, failing on any line that cannot be parsed
+fn convert_fixture(name: &str) -> (Vec<CodexMessage>, Vec<CanonicalMessage>) {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/codex")
+        .join(name);
+    let content = fs::read_to_string(&path).expect("Failed to read fixture");
+
+    let messages: Vec<CodexMessage> = content
+        .lines()
+        .filter(|l| !l.trim().is_empty())
+        .map(|line| serde_json::from_str(line).expect("Fixture line should parse"))
+        .collect();
+
+    let canonical = messages
+        .iter()
+        .filter_map(|msg| msg.to_canonical().expect("Fixture line should convert"))
+        .collect();
+
+    (messages, canonical)
+}
+
+fn tool_blocks(canonical: &[CanonicalMessage]) -> Vec<&ContentBlock> {
+    canonical
+        .iter()
+        .filter_map(|msg| match &msg.message.content {
+            ContentValue::Structured(blocks) => Some(blocks.iter()),
+            _ => None,
+        })
+        .flatten()
+        .collect()
+}
+
+#[test]
+fn test_rollout_v1_fixture() {
+    let (messages, canonical) = convert_fixture("rollout_v1.jsonl");
+
+    let meta = &messages[0];
+    assert_eq!(
+        meta.get_session_id().as_deref(),
+        Some("019a005e-c8fc-7512-8e78-c2322cbf0875")
+    );
+    assert_eq!(meta.get_cwd().as_deref(), Some("/Users/dev/work/my-app"));
+    assert_eq!(meta.get_git_branch().as_deref(), Some("main"));
+    assert_eq!(meta.get_version().as_deref(), Some("0.45.0"));
+
+    let blocks = tool_blocks(&canonical);
+    assert!(matches!(blocks[0], ContentBlock::ToolUse { name, .. } if name == "shell"));
+    assert!(matches!(
+        blocks[1],
+        ContentBlock::ToolResult { is_error: Some(false), .. }
+    ));
+
+    let usage = canonical
+        .iter()
+        .find_map(|msg| msg.message.usage.as_ref())
+        .expect("token_count should produce usage");
+    assert_eq!(usage.input_tokens, Some(1200));
+}
+
+#[test]
+fn test_rollout_v2_fixture() {
+    let (messages, canonical) = convert_fixture("rollout_v2.jsonl");
+
+    // Renamed and nested session_meta fields
+    let meta = &messages[0];
+    assert_eq!(
+        meta.get_session_id().as_deref(),
+        Some("019a7a3b-1111-7222-8333-944455556666")
+    );
+    assert_eq!(meta.get_cwd().as_deref(), Some("/Users/dev/work/my-app"));
+    assert_eq!(meta.get_git_branch().as_deref(), Some("feature/v2"));
+    assert_eq!(meta.get_version().as_deref(), Some("0.58.0"));
+
+    // New response_item tool types become tool_use/tool_result blocks
+    let blocks = tool_blocks(&canonical);
+    let tool_names: Vec<&str> = blocks
+        .iter()
+        .filter_map(|block| match block {
+            ContentBlock::ToolUse { name, .. } => Some(name.as_str()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(tool_names, vec!["apply_patch", "local_shell", "web_search"]);
+
+    let result_errors: Vec<Option<bool>> = blocks
+        .iter()
+        .filter_map(|block| match block {
+            ContentBlock::ToolResult { is_error, .. } => Some(*is_error),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(result_errors, vec![Some(false), Some(true)]);
+
+    // Unknown item and line types are passed through as meta messages
+    let passthrough: Vec<&CanonicalMessage> = canonical
+        .iter()
+        .filter(|msg| {
+            msg.provider_metadata
+                .as_ref()
+                .and_then(|m| m.get("warning"))
+                .is_some()
+        })
+        .collect();
+    assert_eq!(passthrough.len(), 2);
+    assert!(passthrough
+        .iter()
+        .all(|msg| msg.message_type == MessageType::Meta));
+    assert_eq!(
+        passthrough[0].provider_metadata.as_ref().unwrap()["payload"]["ghost_commit"]["id"],
+        "abc123"
+    );
+
+    // Conversation content survives
+    let last = canonical.last().unwrap();
+    assert!(matches!(
+        &last.message.content,
+        ContentValue::Text(text) if text.contains("Patch applied")
+    ));
+}
@@ -0,0 +1,214 @@
+//! Golden tests for the session converters
+//!
+//! Every session in `tests/fixtures/<provider>/` is converted through
+//! `convert_file_to_canonical`, the conversion the watchers, scans and
+//! migration use, minus moving the output into the session cache. The
+//! canonical output is compared with `<name>.canonical.jsonl` next to it, and
+//! the source lines set aside as unconvertible with `<name>.skipped`, one line
+//! number per line (no file means none are). Run with `UPDATE_GOLDEN=1` to
+//! rewrite the expected files after an intended converter change, then review
+//! the diff.
+//!
+//! Fixtures are sanitized real sessions. OpenCode sessions are a storage tree
+//! (`storage/message/<session>` is what gets converted), and Cursor sessions a
+//! `chats/<hash>/<session>/store.db` database with JSON message blobs.
+
+use guidemode_desktop::providers::convert_file_to_canonical;
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+const PROVIDERS: &[&str] = &[
+    "claude-code",
+    "codex",
+    "cursor",
+    "gemini-code",
+    "github-copilot",
+    "opencode",
+];
+
+const EXPECTED_SUFFIX: &str = ".canonical.jsonl";
+const SKIPPED_SUFFIX: &str = ".skipped";
+
+/// Conversions write into `~/.guidemode`, and the Copilot parser reads its
+/// trusted folders and project mappings from `~`, so point both at an empty
+/// temporary home
+fn use_temp_home() {
+    static HOME: OnceLock<tempfile::TempDir> = OnceLock::new();
+    HOME.get_or_init(|| {
+        let home = tempfile::tempdir().unwrap();
+        std::env::set_var("HOME", home.path());
+        home
+    });
+}
+
+fn fixture_dir(provider: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(provider)
+}
+
+fn sorted_entries(dir: &Path) -> Vec<PathBuf> {
+    let mut entries: Vec<PathBuf> = std::fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    entries.sort();
+    entries
+}
+
+fn file_name(path: &Path) -> &str {
+    path.file_name().unwrap().to_str().unwrap()
+}
+
+/// Paths `convert_file_to_canonical` takes for a provider's sessions, with the
+/// name of each session's expected files
+fn fixture_sessions(provider: &str) -> Vec<(String, PathBuf)> {
+    let dir = fixture_dir(provider);
+    match provider {
+        "opencode" => sorted_entries(&dir.join("storage/message"))
+            .into_iter()
+            .map(|message_dir| (file_name(&message_dir).to_string(), message_dir))
+            .collect(),
+        "cursor" => sorted_entries(&dir.join("chats"))
+            .into_iter()
+            .flat_map(|hash_dir| sorted_entries(&hash_dir))
+            .map(|session_dir| {
+                let db_path = session_dir.join("store.db");
+                (file_name(&session_dir).to_string(), db_path)
+            })
+            .collect(),
+        _ => sorted_entries(&dir)
+            .into_iter()
+            .filter(|path| {
+                let name = file_name(path);
+                // Claude Code agent sidechains are merged into their session
+                !name.ends_with(EXPECTED_SUFFIX)
+                    && !name.ends_with(SKIPPED_SUFFIX)
+                    && !name.starts_with("agent-")
+            })
+            .map(|path| {
+                let stem = path.file_stem().unwrap().to_str().unwrap().to_string();
+                (stem, path)
+            })
+            .collect(),
+    }
+}
+
+fn parse_lines(jsonl: &str) -> Vec<Value> {
+    jsonl
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect()
+}
+
+fn parse_line_numbers(skipped: &str) -> Vec<usize> {
+    skipped
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.trim().parse().unwrap())
+        .collect()
+}
+
+/// Where `actual` first differs from `expected`, if it does
+fn first_difference(expected: &[Value], actual: &[Value]) -> Option<String> {
+    let line = expected
+        .iter()
+        .zip(actual)
+        .position(|(expected, actual)| expected != actual);
+    match line {
+        Some(index) => Some(format!(
+            "line {} differs\n  expected: {}\n  actual:   {}",
+            index + 1,
+            expected[index],
+            actual[index]
+        )),
+        None if expected.len() != actual.len() => Some(format!(
+            "expected {} lines, got {}",
+            expected.len(),
+            actual.len()
+        )),
+        None => None,
+    }
+}
+
+#[test]
+fn test_converters_match_golden_output() {
+    use_temp_home();
+    let update = std::env::var_os("UPDATE_GOLDEN").is_some();
+    let mut failures = Vec::new();
+
+    for provider in PROVIDERS {
+        let sessions = fixture_sessions(provider);
+        assert!(!sessions.is_empty(), "No {} fixtures", provider);
+
+        for (name, session) in sessions {
+            let converted = convert_file_to_canonical(provider, &session)
+                .and_then(|mut converted| Ok((converted.content()?, converted)));
+            let (content, converted) = match converted {
+                Ok(converted) => converted,
+                Err(e) => {
+                    failures.push(format!("{}: conversion failed: {}", session.display(), e));
+                    continue;
+                }
+            };
+            let actual = parse_lines(&content);
+            if actual.is_empty() {
+                failures.push(format!("{}: converted to nothing", session.display()));
+                continue;
+            }
+            let skipped: Vec<usize> = converted
+                .skipped_lines()
+                .iter()
+                .map(|line| line.line_number)
+                .collect();
+
+            let dir = fixture_dir(provider);
+            let expected_path = dir.join(format!("{}{}", name, EXPECTED_SUFFIX));
+            let skipped_path = dir.join(format!("{}{}", name, SKIPPED_SUFFIX));
+
+            if update {
+                let lines: Vec<String> = actual.iter().map(Value::to_string).collect();
+                std::fs::write(&expected_path, lines.join("\n") + "\n").unwrap();
+                if skipped.is_empty() {
+                    let _ = std::fs::remove_file(&skipped_path);
+                } else {
+                    let lines: Vec<String> = skipped.iter().map(usize::to_string).collect();
+                    std::fs::write(&skipped_path, lines.join("\n") + "\n").unwrap();
+                }
+                continue;
+            }
+
+            let expected = match std::fs::read_to_string(&expected_path) {
+                Ok(expected) => parse_lines(&expected),
+                Err(_) => {
+                    failures.push(format!("{}: no expected output", session.display()));
+                    continue;
+                }
+            };
+            if let Some(difference) = first_difference(&expected, &actual) {
+                failures.push(format!("{}: {}", session.display(), difference));
+            }
+
+            let expected_skipped = std::fs::read_to_string(&skipped_path)
+                .map(|skipped| parse_line_numbers(&skipped))
+                .unwrap_or_default();
+            if expected_skipped != skipped {
+                failures.push(format!(
+                    "{}: expected lines {:?} skipped, got {:?}",
+                    session.display(),
+                    expected_skipped,
+                    skipped
+                ));
+            }
+        }
+    }
+
+    assert!(
+        failures.is_empty(),
+        "Converter output drifted from the golden files (rerun with UPDATE_GOLDEN=1 \
+         if the change is intended):\n\n{}",
+        failures.join("\n\n")
+    );
+}
@@ -102,6 +102,7 @@ fn test_infer_cwd_from_session_with_thoughts() {
                 timestamp: "2025-10-19T03:18:28.380Z".to_string(),
                 message_type: "user".to_string(),
                 content: "Review the rust code in apps/desktop".to_string(),
+                attachments: Vec::new(),
                 tool_calls: None,
                 thoughts: None,
                 tokens: None,
@@ -113,6 +114,7 @@ fn test_infer_cwd_from_session_with_thoughts() {
                 timestamp: "2025-10-19T03:21:52.867Z".to_string(),
                 message_type: "gemini".to_string(),
                 content: "I will review the code.".to_string(),
+                attachments: Vec::new(),
                 tool_calls: None,
                 thoughts: Some(vec![
                     Thought {
@@ -151,6 +153,7 @@ fn test_infer_cwd_from_session_with_content() {
             timestamp: "2025-10-19T00:01:00.000Z".to_string(),
             message_type: "user".to_string(),
             content: "Reading file /Users/cliftonc/work/guidemode/CLAUDE.md".to_string(),
+            attachments: Vec::new(),
             tool_calls: None,
             thoughts: None,
             tokens: None,
@@ -177,6 +180,7 @@ fn test_infer_cwd_from_session_no_match() {
             timestamp: "2025-10-19T00:01:00.000Z".to_string(),
             message_type: "user".to_string(),
             content: "No file paths here!".to_string(),
+            attachments: Vec::new(),
             tool_calls: None,
             thoughts: None,
             tokens: None,
@@ -214,6 +218,7 @@ fn test_infer_cwd_from_tool_calls() {
             timestamp: "2025-10-19T04:15:00.000Z".to_string(),
             message_type: "gemini".to_string(),
             content: "I'll read the file".to_string(),
+            attachments: Vec::new(),
             tool_calls: Some(vec![tool_call]),
             thoughts: None,
             tokens: None,
@@ -254,6 +259,7 @@ fn test_infer_cwd_from_tool_calls_paths_array() {
             timestamp: "2025-10-19T04:20:00.000Z".to_string(),
             message_type: "gemini".to_string(),
             content: "Reading multiple files".to_string(),
+            attachments: Vec::new(),
             tool_calls: Some(vec![tool_call]),
             thoughts: None,
             tokens: None,
@@ -292,6 +298,7 @@ fn test_cwd_priority_tool_calls_over_thoughts() {
             timestamp: "2025-10-19T05:00:30.000Z".to_string(),
             message_type: "gemini".to_string(),
             content: "Some unrelated path /wrong/path/file.txt".to_string(),
+            attachments: Vec::new(),
             tool_calls: Some(vec![tool_call]),
             thoughts: Some(vec![Thought {
                 subject: "Reading".to_string(),
@@ -0,0 +1,279 @@
+// Integration tests for git_diff against fixture repositories
+//
+// Sessions don't always run at the root of a plain checkout: they may run in a
+// subdirectory, a linked worktree or a submodule. Each test builds such a
+// layout in a temp directory and checks the diff comes from the right checkout.
+
+use git2::{Repository, Signature};
+use guidemode_desktop::git_diff::{
+    capture_uncommitted_snapshot, get_commit_diff, get_diff_file, list_diff_files, open_repository,
+    FileDiff, MAX_FILE_CONTENT_BYTES, MAX_FILE_DIFF_BYTES,
+};
+use guidemode_desktop::project_metadata::extract_git_branch;
+use std::fs;
+use std::path::Path;
+
+fn write(root: &Path, path: &str, content: &str) {
+    let full_path = root.join(path);
+    fs::create_dir_all(full_path.parent().unwrap()).unwrap();
+    fs::write(full_path, content).unwrap();
+}
+
+/// Stage everything in the working directory and commit it, returning the hash
+fn commit_all(repo: &Repository, message: &str) -> String {
+    let mut index = repo.index().unwrap();
+    index
+        .add_all(["*"].iter(), git2::IndexAddOption::DEFAULT, None)
+        .unwrap();
+    index.write().unwrap();
+
+    let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+    let signature = Signature::now("Dev", "dev@example.com").unwrap();
+    let parent = repo.head().ok().and_then(|head| head.peel_to_commit().ok());
+    let parents: Vec<&git2::Commit> = parent.iter().collect();
+
+    repo.commit(
+        Some("HEAD"),
+        &signature,
+        &signature,
+        message,
+        &tree,
+        &parents,
+    )
+    .unwrap()
+    .to_string()
+}
+
+fn init_repo(root: &Path) -> (Repository, String) {
+    let repo = Repository::init(root).unwrap();
+    write(root, "src/lib.rs", "fn main() {}\n");
+    write(root, "README.md", "# Fixture\n");
+    let hash = commit_all(&repo, "Initial commit");
+    (repo, hash)
+}
+
+fn find<'a>(diffs: &'a [FileDiff], path: &str) -> &'a FileDiff {
+    diffs
+        .iter()
+        .find(|diff| diff.new_path == path)
+        .unwrap_or_else(|| panic!("{} not in diff", path))
+}
+
+#[test]
+fn test_diff_from_subdirectory() {
+    let dir = tempfile::tempdir().unwrap();
+    let (_repo, hash) = init_repo(dir.path());
+
+    write(dir.path(), "src/lib.rs", "fn main() { run() }\n");
+    write(dir.path(), "src/new.rs", "pub fn run() {}\n");
+
+    let cwd = dir.path().join("src");
+    let diffs = get_commit_diff(cwd.to_str().unwrap(), &hash, &hash, true, None, None).unwrap();
+
+    assert_eq!(diffs.len(), 2);
+    assert_eq!(find(&diffs, "src/lib.rs").change_type, "modified");
+    assert_eq!(
+        find(&diffs, "src/lib.rs").new_content.as_deref(),
+        Some("fn main() { run() }\n")
+    );
+    // Untracked files are read relative to the checkout root, not the cwd
+    assert_eq!(
+        find(&diffs, "src/new.rs").new_content.as_deref(),
+        Some("pub fn run() {}\n")
+    );
+}
+
+#[test]
+fn test_diff_in_linked_worktree() {
+    let dir = tempfile::tempdir().unwrap();
+    let main_path = dir.path().join("main");
+    let worktree_path = dir.path().join("feature");
+    let (repo, base_hash) = init_repo(&main_path);
+
+    repo.worktree("feature", &worktree_path, None).unwrap();
+    assert!(worktree_path.join(".git").is_file());
+
+    let worktree_repo = open_repository(worktree_path.to_str().unwrap()).unwrap();
+    assert_eq!(
+        worktree_repo.workdir().unwrap().canonicalize().unwrap(),
+        worktree_path.canonicalize().unwrap()
+    );
+    assert_eq!(
+        extract_git_branch(worktree_path.to_str().unwrap()).as_deref(),
+        Some("feature")
+    );
+
+    write(&worktree_path, "src/lib.rs", "fn main() { feature() }\n");
+    let feature_hash = commit_all(&worktree_repo, "Add feature");
+
+    let diffs = get_commit_diff(
+        worktree_path.to_str().unwrap(),
+        &base_hash,
+        &feature_hash,
+        false,
+        None,
+        None,
+    )
+    .unwrap();
+
+    assert_eq!(diffs.len(), 1);
+    assert_eq!(
+        find(&diffs, "src/lib.rs").new_content.as_deref(),
+        Some("fn main() { feature() }\n")
+    );
+
+    // The main checkout is untouched and still on its own branch
+    let main_diffs = get_commit_diff(
+        main_path.to_str().unwrap(),
+        &base_hash,
+        &base_hash,
+        true,
+        None,
+        None,
+    )
+    .unwrap();
+    assert!(main_diffs.is_empty());
+}
+
+#[test]
+fn test_diff_in_submodule() {
+    let dir = tempfile::tempdir().unwrap();
+    let library_path = dir.path().join("library");
+    let parent_path = dir.path().join("app");
+
+    let library = Repository::init(&library_path).unwrap();
+    write(&library_path, "lib.rs", "pub fn helper() {}\n");
+    let library_hash = commit_all(&library, "Library");
+
+    let (parent, _) = init_repo(&parent_path);
+    let mut submodule = parent
+        .submodule(
+            library_path.to_str().unwrap(),
+            Path::new("vendor/library"),
+            true,
+        )
+        .unwrap();
+    submodule.clone(None).unwrap();
+    submodule.add_finalize().unwrap();
+    commit_all(&parent, "Add library submodule");
+
+    let submodule_path = parent_path.join("vendor/library");
+    assert!(submodule_path.join(".git").is_file());
+
+    // Commits in the submodule live in the superproject's modules/ object store
+    write(&submodule_path, "lib.rs", "pub fn helper() { todo!() }\n");
+    let diffs = get_commit_diff(
+        submodule_path.to_str().unwrap(),
+        &library_hash,
+        &library_hash,
+        true,
+        None,
+        None,
+    )
+    .unwrap();
+
+    assert_eq!(diffs.len(), 1);
+    assert_eq!(find(&diffs, "lib.rs").change_type, "modified");
+    assert_eq!(
+        find(&diffs, "lib.rs").new_content.as_deref(),
+        Some("pub fn helper() { todo!() }\n")
+    );
+}
+
+#[test]
+fn test_paginated_diff() {
+    let dir = tempfile::tempdir().unwrap();
+    let (_repo, hash) = init_repo(dir.path());
+    let cwd = dir.path().to_str().unwrap();
+
+    for i in 0..5 {
+        write(dir.path(), &format!("gen/file{}.rs", i), "line\n");
+    }
+    write(dir.path(), "src/lib.rs", "fn main() {}\nfn run() {}\n");
+
+    let first_page = list_diff_files(cwd, &hash, &hash, true, None, 0, 4).unwrap();
+    assert_eq!(first_page.total_files, 6);
+    assert_eq!(first_page.files.len(), 4);
+    assert!(first_page.has_more);
+
+    let last_page = list_diff_files(cwd, &hash, &hash, true, None, 4, 4).unwrap();
+    assert_eq!(last_page.files.len(), 2);
+    assert!(!last_page.has_more);
+
+    let lib = last_page
+        .files
+        .iter()
+        .chain(&first_page.files)
+        .find(|file| file.new_path == "src/lib.rs")
+        .unwrap();
+    assert_eq!(lib.change_type, "modified");
+    assert_eq!((lib.stats.additions, lib.stats.deletions), (1, 0));
+
+    let file = get_diff_file(cwd, &hash, &hash, true, None, "src/lib.rs")
+        .unwrap()
+        .unwrap();
+    assert_eq!(file.stats.additions, 1);
+    assert!(file.hunks[0].contains("+fn run() {}"));
+    assert!(!file.truncated);
+
+    let untracked = get_diff_file(cwd, &hash, &hash, true, None, "gen/file3.rs")
+        .unwrap()
+        .unwrap();
+    assert_eq!(untracked.change_type, "added");
+    assert!(get_diff_file(cwd, &hash, &hash, true, None, "README.md")
+        .unwrap()
+        .is_none());
+}
+
+#[test]
+fn test_large_file_diff_truncated() {
+    let dir = tempfile::tempdir().unwrap();
+    let (_repo, hash) = init_repo(dir.path());
+
+    let line = "x".repeat(99);
+    let lines = (MAX_FILE_CONTENT_BYTES as usize / 100) * 2;
+    let content: String = (0..lines).map(|_| format!("{}\n", line)).collect();
+    write(dir.path(), "src/lib.rs", &content);
+
+    let diffs =
+        get_commit_diff(dir.path().to_str().unwrap(), &hash, &hash, true, None, None).unwrap();
+    let lib = find(&diffs, "src/lib.rs");
+
+    assert!(lib.truncated);
+    assert!(lib.new_content.is_none());
+    assert_eq!(lib.stats.additions as usize, lines);
+    assert!(lib.hunks[0].len() < MAX_FILE_DIFF_BYTES + 1024);
+    assert!(lib.hunks[0].contains("\\ Diff truncated"));
+}
+
+#[test]
+fn test_uncommitted_snapshot() {
+    let dir = tempfile::tempdir().unwrap();
+    let (repo, hash) = init_repo(dir.path());
+
+    write(dir.path(), "src/lib.rs", "fn main() { run() }\n");
+    write(
+        dir.path(),
+        "src/run.rs",
+        "pub fn run() {}\npub fn stop() {}\n",
+    );
+
+    let snapshot = capture_uncommitted_snapshot(&repo).unwrap();
+
+    assert_eq!(snapshot.head_commit, hash);
+    assert_eq!(snapshot.files_changed, 2);
+    assert_eq!(snapshot.lines_added, 3);
+    assert_eq!(snapshot.lines_removed, 1);
+    assert!(!snapshot.patch_truncated);
+    assert!(snapshot
+        .patch
+        .contains("-fn main() {}\n+fn main() { run() }\n"));
+    assert!(snapshot.patch.contains("+pub fn stop() {}\n"));
+}
+
+#[test]
+fn test_open_repository_outside_git() {
+    let dir = tempfile::tempdir().unwrap();
+
+    assert!(open_repository(dir.path().to_str().unwrap()).is_err());
+}
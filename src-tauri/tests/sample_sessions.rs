@@ -0,0 +1,123 @@
+//! Sample sessions read back by the providers' own scanners and parsers
+//!
+//! `sample_sessions::generate` writes sessions in each provider's on-disk
+//! format; this checks that the code reading real provider directories finds
+//! their projects and converts every session, so the samples stay usable for
+//! demos as the formats evolve.
+
+use guidemode_desktop::providers::canonical::converter::ToCanonical;
+use guidemode_desktop::providers::claude::types::ClaudeEntry;
+use guidemode_desktop::providers::codex::CodexMessage;
+use guidemode_desktop::providers::copilot::parser::CopilotParser;
+use guidemode_desktop::providers::gemini::converter::convert_session_to_canonical;
+use guidemode_desktop::providers::gemini::parser::GeminiSession;
+use guidemode_desktop::providers::gemini::utils::infer_cwd_from_session;
+use guidemode_desktop::providers::opencode::parser::OpenCodeParser;
+use guidemode_desktop::providers::scan_projects;
+use guidemode_desktop::sample_sessions::{generate, SAMPLE_PROVIDERS};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// The Copilot parser reads its trusted folders from `~`, so point it at an
+/// empty temporary home
+fn use_temp_home() {
+    static HOME: OnceLock<tempfile::TempDir> = OnceLock::new();
+    HOME.get_or_init(|| {
+        let home = tempfile::tempdir().unwrap();
+        std::env::set_var("HOME", home.path());
+        home
+    });
+}
+
+/// Files under `dir` with the given extension, recursively
+fn files_with_extension(dir: &Path, extension: &str) -> Vec<PathBuf> {
+    walkdir::WalkDir::new(dir)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.into_path())
+        .filter(|path| path.extension().and_then(|e| e.to_str()) == Some(extension))
+        .collect()
+}
+
+/// Canonical messages parsed from the JSONL transcript of `ToCanonical` lines
+fn count_canonical<T>(path: &Path) -> usize
+where
+    T: ToCanonical + serde::de::DeserializeOwned,
+{
+    std::fs::read_to_string(path)
+        .unwrap()
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| {
+            serde_json::from_str::<T>(line)
+                .unwrap()
+                .to_canonical()
+                .unwrap()
+        })
+        .count()
+}
+
+#[test]
+fn test_samples_are_scanned_and_converted() {
+    use_temp_home();
+    let root = tempfile::tempdir().unwrap();
+
+    for provider in SAMPLE_PROVIDERS {
+        let samples = generate(provider, 5, root.path()).unwrap();
+        let home = Path::new(&samples.home_directory);
+
+        if *provider != "github-copilot" {
+            // Copilot's projects come from its global config, not its sessions
+            let projects = scan_projects(provider, &samples.home_directory).unwrap();
+            assert_eq!(projects.len(), 5, "{} projects: {:?}", provider, projects);
+        }
+
+        let converted: Vec<usize> = match *provider {
+            "claude-code" => files_with_extension(home, "jsonl")
+                .iter()
+                .map(|path| count_canonical::<ClaudeEntry>(path))
+                .collect(),
+            "codex" => files_with_extension(home, "jsonl")
+                .iter()
+                .map(|path| count_canonical::<CodexMessage>(path))
+                .collect(),
+            "gemini-code" => files_with_extension(home, "json")
+                .iter()
+                .map(|path| {
+                    let content = std::fs::read_to_string(path).unwrap();
+                    let session = GeminiSession::from_json(&content).unwrap();
+                    let cwd = infer_cwd_from_session(&session, &session.project_hash);
+                    assert!(cwd.is_some(), "No cwd for {}", path.display());
+                    convert_session_to_canonical(&session, cwd).unwrap().len()
+                })
+                .collect(),
+            "github-copilot" => files_with_extension(home, "jsonl")
+                .iter()
+                .map(|path| {
+                    let parsed = CopilotParser::new(home.to_path_buf())
+                        .parse_session(path)
+                        .unwrap();
+                    parsed.messages.len()
+                })
+                .collect(),
+            "opencode" => samples
+                .session_ids
+                .iter()
+                .map(|session_id| {
+                    let parsed = OpenCodeParser::new(home.join("storage"))
+                        .parse_session(session_id)
+                        .unwrap();
+                    assert!(parsed.cwd.is_some());
+                    parsed.jsonl_content.lines().count()
+                })
+                .collect(),
+            other => panic!("No reader for {}", other),
+        };
+
+        assert_eq!(converted.len(), 5, "{} sessions", provider);
+        for messages in converted {
+            // The prompt, a tool call, its result and the reply
+            assert!(messages >= 3, "{}: only {} messages", provider, messages);
+        }
+    }
+}
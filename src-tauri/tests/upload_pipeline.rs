@@ -0,0 +1,282 @@
+//! End-to-end upload queue tests against a mock server
+//!
+//! The queue runs its real processor, with an in-memory database and a mock
+//! `UploadTransport` standing in for the server. Each provider has a fixture
+//! session in canonical JSONL under `tests/fixtures/sessions/`.
+
+use guidemode_desktop::config::{
+    save_config, save_provider_config, GuideModeConfig, ProviderConfig,
+};
+use guidemode_desktop::database::{
+    get_failed_sessions, get_unsynced_session, init_in_memory_database, DirtyMetadataSession,
+};
+use guidemode_desktop::error::GuideModeError;
+use guidemode_desktop::events::EventBus;
+use guidemode_desktop::providers::canonical::CanonicalMessage;
+use guidemode_desktop::providers::common::db_helpers::insert_session_immediately;
+use guidemode_desktop::upload_queue::{
+    TransportFuture, UploadItem, UploadOutcome, UploadQueue, UploadTransport,
+};
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+use tokio::sync::MutexGuard;
+
+const PROVIDERS: &[&str] = &[
+    "claude-code",
+    "codex",
+    "cursor",
+    "gemini-code",
+    "github-copilot",
+    "opencode",
+];
+
+const TENANT_ID: &str = "test-tenant";
+
+/// Records uploads and answers with scripted results, uploaded transcripts
+/// once they run out
+#[derive(Default)]
+struct MockServer {
+    uploads: Mutex<Vec<(String, String)>>,
+    responses: Mutex<HashMap<String, VecDeque<Result<UploadOutcome, GuideModeError>>>>,
+}
+
+impl MockServer {
+    fn respond(&self, session_id: &str, responses: Vec<Result<UploadOutcome, GuideModeError>>) {
+        self.responses
+            .lock()
+            .unwrap()
+            .insert(session_id.to_string(), responses.into());
+    }
+
+    /// (provider, session ID) of every upload attempt, in order
+    fn uploads(&self) -> Vec<(String, String)> {
+        self.uploads.lock().unwrap().clone()
+    }
+
+    fn attempts(&self, session_id: &str) -> usize {
+        self.uploads()
+            .iter()
+            .filter(|(_, uploaded)| uploaded == session_id)
+            .count()
+    }
+}
+
+impl UploadTransport for MockServer {
+    fn upload<'a>(
+        &'a self,
+        item: &'a UploadItem,
+        _config: Option<GuideModeConfig>,
+    ) -> TransportFuture<'a, UploadOutcome> {
+        Box::pin(async move {
+            let session_id = item.session_id.clone().unwrap_or_default();
+            self.uploads
+                .lock()
+                .unwrap()
+                .push((item.provider.clone(), session_id.clone()));
+            self.responses
+                .lock()
+                .unwrap()
+                .get_mut(&session_id)
+                .and_then(VecDeque::pop_front)
+                .unwrap_or(Ok(UploadOutcome::Transcript))
+        })
+    }
+
+    fn patch_metadata<'a>(
+        &'a self,
+        _session: &'a DirtyMetadataSession,
+        _config: &'a GuideModeConfig,
+    ) -> TransportFuture<'a> {
+        Box::pin(async { Ok(()) })
+    }
+}
+
+/// Point the app at a temporary home with an active tenant syncing every
+/// provider's transcripts, and an empty in-memory database
+///
+/// Processors share the sessions saved in this process, so tests hold the
+/// returned guard to run one at a time.
+async fn setup() -> MutexGuard<'static, ()> {
+    static HOME: OnceLock<tempfile::TempDir> = OnceLock::new();
+    static SERIAL: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+    HOME.get_or_init(|| {
+        let home = tempfile::tempdir().unwrap();
+        std::env::set_var("HOME", home.path());
+
+        save_config(&config()).unwrap();
+        for provider in PROVIDERS {
+            let provider_config = ProviderConfig {
+                enabled: true,
+                sync_mode: "Transcript and Metrics".to_string(),
+                ..Default::default()
+            };
+            save_provider_config(provider, &provider_config).unwrap();
+        }
+        init_in_memory_database().unwrap();
+        home
+    });
+    SERIAL.lock().await
+}
+
+fn config() -> GuideModeConfig {
+    GuideModeConfig {
+        api_key: Some("test-key".to_string()),
+        server_url: Some("https://guidemode.test".to_string()),
+        tenant_id: Some(TENANT_ID.to_string()),
+        ..Default::default()
+    }
+}
+
+fn fixture(provider: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/sessions")
+        .join(format!("{}.jsonl", provider))
+}
+
+/// Save a provider's fixture session under `session_id`, as its watcher would
+fn save_fixture_session(provider: &str, session_id: &str) {
+    let dir = dirs::home_dir()
+        .unwrap()
+        .join(".guidemode/sessions")
+        .join(provider)
+        .join("fixture-app");
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(format!("{}.jsonl", session_id));
+    std::fs::copy(fixture(provider), &path).unwrap();
+    let size = std::fs::metadata(&path).unwrap().len();
+
+    insert_session_immediately(provider, "fixture-app", session_id, &path, size, None, true)
+        .unwrap();
+}
+
+fn start_queue(server: &Arc<MockServer>) -> UploadQueue {
+    let queue = UploadQueue::with_transport(Arc::clone(server) as Arc<dyn UploadTransport>);
+    queue.set_config(config());
+    queue.start_processing(&EventBus::new(16)).unwrap();
+    queue
+}
+
+/// Wait up to `timeout` for `done`
+async fn wait_for(timeout: Duration, done: impl Fn() -> bool) -> bool {
+    let deadline = tokio::time::Instant::now() + timeout;
+    while !done() {
+        if tokio::time::Instant::now() >= deadline {
+            return false;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    true
+}
+
+fn is_synced(session_id: &str) -> bool {
+    get_unsynced_session(session_id).unwrap().is_none()
+}
+
+#[test]
+fn test_fixtures_are_canonical_jsonl() {
+    for provider in PROVIDERS {
+        let content = std::fs::read_to_string(fixture(provider)).unwrap();
+        for line in content.lines() {
+            let message: CanonicalMessage = serde_json::from_str(line)
+                .unwrap_or_else(|e| panic!("{} fixture line isn't canonical: {}", provider, e));
+            assert_eq!(message.provider, *provider);
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_saved_sessions_upload_for_every_provider() {
+    let _serial = setup().await;
+    let server = Arc::new(MockServer::default());
+    let queue = start_queue(&server);
+
+    let session_ids: Vec<String> = PROVIDERS
+        .iter()
+        .map(|provider| format!("every-provider-{}", provider))
+        .collect();
+    for (provider, session_id) in PROVIDERS.iter().zip(&session_ids) {
+        save_fixture_session(provider, session_id);
+    }
+
+    let all_synced = wait_for(Duration::from_secs(20), || {
+        session_ids.iter().all(|session_id| is_synced(session_id))
+    })
+    .await;
+    queue.shutdown(Duration::from_secs(5)).await;
+    assert!(all_synced, "uploaded: {:?}", server.uploads());
+
+    let mut uploads = server.uploads();
+    uploads.sort();
+    let expected: Vec<(String, String)> = PROVIDERS
+        .iter()
+        .map(|provider| provider.to_string())
+        .zip(session_ids)
+        .collect();
+    assert_eq!(uploads, expected);
+}
+
+fn failed(status: reqwest::StatusCode) -> GuideModeError {
+    GuideModeError::Status {
+        action: "Upload v2".to_string(),
+        status,
+        body: String::new(),
+    }
+}
+
+#[tokio::test]
+async fn test_server_errors_retry_and_client_errors_fail() {
+    let _serial = setup().await;
+    let server = Arc::new(MockServer::default());
+    server.respond(
+        "flaky",
+        vec![Err(failed(reqwest::StatusCode::SERVICE_UNAVAILABLE))],
+    );
+    server.respond(
+        "rejected",
+        vec![Err(failed(reqwest::StatusCode::BAD_REQUEST))],
+    );
+    let queue = start_queue(&server);
+
+    save_fixture_session("claude-code", "flaky");
+    save_fixture_session("codex", "rejected");
+
+    let settled = wait_for(Duration::from_secs(20), || {
+        is_synced("flaky")
+            && get_failed_sessions()
+                .unwrap()
+                .iter()
+                .any(|failed| failed.session_id == "rejected")
+    })
+    .await;
+    queue.shutdown(Duration::from_secs(5)).await;
+    assert!(settled, "uploaded: {:?}", server.uploads());
+
+    // The 503 was retried after its backoff; the 400 never was
+    assert_eq!(server.attempts("flaky"), 2);
+    assert_eq!(server.attempts("rejected"), 1);
+}
+
+#[tokio::test]
+async fn test_deferred_transcripts_upload_once_the_server_takes_them() {
+    let _serial = setup().await;
+    let server = Arc::new(MockServer::default());
+    server.respond("deferred", vec![Ok(UploadOutcome::TranscriptDeferred)]);
+    let queue = start_queue(&server);
+
+    save_fixture_session("claude-code", "deferred");
+    let deferred = wait_for(Duration::from_secs(20), || is_synced("deferred")).await;
+    assert!(deferred, "uploaded: {:?}", server.uploads());
+    assert_eq!(server.attempts("deferred"), 1);
+
+    // A transcript going through sends the deferred one too
+    save_fixture_session("codex", "transcript");
+    let resent = wait_for(Duration::from_secs(20), || {
+        server.attempts("deferred") == 2 && is_synced("deferred")
+    })
+    .await;
+    queue.shutdown(Duration::from_secs(5)).await;
+    assert!(resent, "uploaded: {:?}", server.uploads());
+}
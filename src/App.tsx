@@ -6,9 +6,10 @@ import { OnboardingTour } from './components/Onboarding/OnboardingTour'
 import { ToastContainer } from './components/ToastContainer'
 import { useAuth } from './hooks/useAuth'
 import { useDatabase } from './hooks/useDatabase'
-import { useDebouncedCoreMetrics } from './hooks/useDebouncedCoreMetrics'
 import { useDelayedAiProcessing } from './hooks/useDelayedAiProcessing'
+import { useDiskSpaceWarnings } from './hooks/useDiskSpaceWarnings'
 import { useOnboarding } from './hooks/useOnboarding'
+import { useProviderVersionWarnings } from './hooks/useProviderVersionWarnings'
 import { useSessionIngest } from './hooks/useSessionIngest'
 import { useTheme } from './hooks/useTheme'
 import DashboardPage from './pages/DashboardPage'
@@ -21,6 +22,7 @@ import SessionDetailPage from './pages/SessionDetailPage'
 import SessionsPage from './pages/SessionsPage'
 import SettingsPage from './pages/SettingsPage'
 import UploadQueuePage from './pages/UploadQueuePage'
+import { invoke } from './services/commands'
 
 function AppContent() {
   const navigate = useNavigate()
@@ -29,14 +31,17 @@ function AppContent() {
   // Start listening for session detection events
   useSessionIngest()
 
-  // Process core metrics with debouncing (waits for file activity to settle)
-  useDebouncedCoreMetrics()
-
   // Process AI metrics with configurable delay (default 10min after session ends)
   useDelayedAiProcessing()
 
+  // Warn about agents newer than their converters were tested with
+  useProviderVersionWarnings()
+
+  // Tell the user when session writes pause for lack of disk space
+  useDiskSpaceWarnings()
+
   useEffect(() => {
-    // Listen for navigation events from the menubar window
+    // Listen for navigation events from the menubar window and deep links
     let unlisten: (() => void) | undefined
 
     listen('navigate', event => {
@@ -46,6 +51,11 @@ function AppContent() {
       unlisten = fn
     })
 
+    // Open the guidemode:// link the app was launched with, if any
+    invoke<string | null>('take_deep_link_route').then(route => {
+      if (route) navigate(route)
+    })
+
     return () => {
       unlisten?.()
     }
@@ -81,6 +91,18 @@ function AppContent() {
   )
 }
 
+// Session windows opened with `open_session_window` only show the session;
+// background processing keeps running in the main window alone
+function SessionWindowContent() {
+  return (
+    <main className="min-h-screen bg-base-100 p-6">
+      <Routes>
+        <Route path="/session-window/:sessionId" element={<SessionDetailPage />} />
+      </Routes>
+    </main>
+  )
+}
+
 function App() {
   const { isLoading } = useAuth()
   const { isReady: isDbReady, error: dbError } = useDatabase()
@@ -120,11 +142,13 @@ function App() {
     )
   }
 
+  const isSessionWindow = window.location.pathname.startsWith('/session-window/')
+
   return (
     <Router>
-      <AppContent />
+      {isSessionWindow ? <SessionWindowContent /> : <AppContent />}
       <ToastContainer />
-      <OnboardingTour />
+      {!isSessionWindow && <OnboardingTour />}
     </Router>
   )
 }
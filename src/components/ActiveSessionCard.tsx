@@ -15,10 +15,10 @@ import {
   XCircleIcon,
 } from '@heroicons/react/24/outline'
 import { useQuery, useQueryClient } from '@tanstack/react-query'
-import { invoke } from '@tauri-apps/api/core'
 import { listen } from '@tauri-apps/api/event'
 import { useEffect } from 'react'
 import { useLocalSessionContent } from '../hooks/useLocalSessionContent'
+import { invoke } from '../services/commands'
 
 interface ActiveSessionCardProps {
   session: {
@@ -41,6 +41,7 @@ interface ActiveSessionCardProps {
     assessmentRating?: string | null
     syncedToServer?: boolean
     syncFailedReason?: string | null
+    conversionSkippedLines?: number
   }
   isActive: boolean
   isProcessing?: boolean
@@ -422,6 +423,15 @@ export function ActiveSessionCard({
               </span>
             )}
 
+            {!!session.conversionSkippedLines && (
+              <span
+                className="tooltip tooltip-left badge badge-warning badge-sm"
+                data-tip={`${session.conversionSkippedLines} source lines couldn't be converted; metrics are incomplete`}
+              >
+                Incomplete
+              </span>
+            )}
+
             {/* Processing Status */}
             <div
               className={`tooltip tooltip-left flex items-center justify-center w-8 h-8 rounded-md ${processingInfo.bgColor} ${
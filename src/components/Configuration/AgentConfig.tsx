@@ -1,5 +1,4 @@
 import { useQueryClient } from '@tanstack/react-query'
-import { invoke } from '@tauri-apps/api/core'
 import { open } from '@tauri-apps/plugin-dialog'
 import { formatDistanceToNow } from 'date-fns'
 import { useEffect, useState } from 'react'
@@ -26,6 +25,7 @@ import {
   useStopCursorWatcher,
 } from '../../hooks/useCursorWatcher'
 import { useDirectoryExists } from '../../hooks/useDirectoryExists'
+import { useOpenPrivacySettings, useProviderAccess } from '../../hooks/useProviderAccess'
 import {
   useOpenCodeWatcherStatus,
   useStartOpenCodeWatcher,
@@ -38,12 +38,15 @@ import {
 } from '../../hooks/useProviderConfig'
 import { useRescanProgress } from '../../hooks/useRescanProgress'
 import { useSetupInstructions } from '../../hooks/useSetupInstructions'
+import { useSyncPreview } from '../../hooks/useSyncPreview'
 import { useToast } from '../../hooks/useToast'
+import { errorCode, invoke } from '../../services/commands'
 import type { CodingAgent, ProviderConfig } from '../../types/providers'
 import ConfirmDialog from '../ConfirmDialog'
 import RescanProgress from '../RescanProgress'
 import ProviderIcon from '../icons/ProviderIcon'
 import SetupInstructions from './SetupInstructions'
+import SyncPreview from './SyncPreview'
 
 interface AgentConfigProps {
   agent: CodingAgent
@@ -73,7 +76,8 @@ function AgentConfig({ agent, headerActions }: AgentConfigProps) {
   const [confirmClear, setConfirmClear] = useState(false)
   const [isScanning, setIsScanning] = useState(false)
   const toast = useToast()
-  const { progress: rescanProgress } = useRescanProgress(agent.id)
+  const { progress: rescanProgress, cancel: cancelRescan } = useRescanProgress(agent.id)
+  const syncPreview = useSyncPreview()
 
   // Watcher hooks - conditional based on provider
   const { data: claudeWatcherStatus } = useClaudeWatcherStatus()
@@ -175,6 +179,14 @@ function AgentConfig({ agent, headerActions }: AgentConfigProps) {
   // Check if home directory exists
   const { data: directoryExists } = useDirectoryExists(effectiveHomeDirectory)
 
+  // Check the directory can be read, not just that it exists (macOS privacy protection)
+  const {
+    data: accessDenied,
+    refetch: recheckAccess,
+    isFetching: checkingAccess,
+  } = useProviderAccess(agent.id, localConfig.enabled && directoryExists === true)
+  const { mutate: openPrivacySettings } = useOpenPrivacySettings()
+
   const { data: projects = [], isLoading: projectsLoading } = useScanProjects(
     agent.id,
     localConfig.enabled ? effectiveHomeDirectory : ''
@@ -212,7 +224,7 @@ function AgentConfig({ agent, headerActions }: AgentConfigProps) {
 
   // Reset scanning state when rescan completes
   useEffect(() => {
-    if (rescanProgress?.phase === 'complete') {
+    if (rescanProgress?.phase === 'complete' || rescanProgress?.phase === 'cancelled') {
       setIsScanning(false)
       // Refresh sessions list
       queryClient.invalidateQueries({ queryKey: ['sessions'] })
@@ -338,7 +350,11 @@ function AgentConfig({ agent, headerActions }: AgentConfigProps) {
         ? projects.map(p => p.name)
         : localConfig.selectedProjects
 
-    startWatcher(projectsToWatch)
+    startWatcher(projectsToWatch, {
+      onError: err => {
+        if (errorCode(err) === 'access_denied') recheckAccess()
+      },
+    })
   }
 
   const handleStopWatcher = () => {
@@ -352,6 +368,7 @@ function AgentConfig({ agent, headerActions }: AgentConfigProps) {
       setIsScanning(true)
       await invoke('scan_historical_sessions', { providerId: agent.id })
     } catch (err) {
+      if (errorCode(err) === 'cancelled') return
       console.error('Failed to start rescan:', err)
       const errorMessage = (err as Error).message || String(err) || 'Unknown error occurred'
       toast.error(`Failed to start rescan: ${errorMessage}`)
@@ -359,6 +376,12 @@ function AgentConfig({ agent, headerActions }: AgentConfigProps) {
     }
   }
 
+  const handlePreviewSync = () => {
+    syncPreview.mutate(agent.id, {
+      onError: err => toast.error(`Failed to preview sync: ${String(err)}`),
+    })
+  }
+
   const handleClearAndRescan = async () => {
     setConfirmClear(false)
     try {
@@ -370,6 +393,7 @@ function AgentConfig({ agent, headerActions }: AgentConfigProps) {
         try {
           await invoke('scan_historical_sessions', { providerId: agent.id })
         } catch (err) {
+          if (errorCode(err) === 'cancelled') return
           console.error('Failed to start rescan:', err)
           const errorMessage = (err as Error).message || String(err) || 'Unknown error occurred'
           toast.error(`Failed to start rescan: ${errorMessage}`)
@@ -402,9 +426,11 @@ function AgentConfig({ agent, headerActions }: AgentConfigProps) {
 
   const isConfigLoading = configLoading || saving
   const isWatcherBusy = startingWatcher || stoppingWatcher
+  const deniedDirectory = watcherStatus?.access_denied ?? accessDenied
   const canStartWatcher =
     localConfig.enabled &&
     directoryExists &&
+    !deniedDirectory &&
     startWatcher !== undefined &&
     (localConfig.projectSelection === 'ALL' || localConfig.selectedProjects.length > 0)
 
@@ -500,6 +526,81 @@ function AgentConfig({ agent, headerActions }: AgentConfigProps) {
             </div>
           )}
 
+          {/* Access Denied Note */}
+          {deniedDirectory && (
+            <div className="bg-warning/10 border border-warning/30 rounded-lg p-4">
+              <div className="flex items-start gap-3">
+                <svg
+                  className="w-5 h-5 text-warning flex-shrink-0 mt-0.5"
+                  fill="none"
+                  stroke="currentColor"
+                  viewBox="0 0 24 24"
+                >
+                  <path
+                    strokeLinecap="round"
+                    strokeLinejoin="round"
+                    strokeWidth={2}
+                    d="M12 15v2m-6 4h12a2 2 0 002-2v-6a2 2 0 00-2-2H6a2 2 0 00-2 2v6a2 2 0 002 2zm10-10V7a4 4 0 00-8 0v4h8z"
+                  />
+                </svg>
+                <div className="flex-1">
+                  <div className="font-medium text-base-content/80 text-sm">Access Needed</div>
+                  <div className="text-sm text-base-content/60 mt-1">
+                    Your system won't let GuideMode read{' '}
+                    <code className="bg-base-300 px-1 rounded text-xs">{deniedDirectory}</code>, so
+                    no {agent.name} sessions are captured. Grant GuideMode Full Disk Access in System
+                    Settings, then check again.
+                  </div>
+                  <div className="flex gap-2 mt-3">
+                    <button className="btn btn-sm btn-primary" onClick={() => openPrivacySettings()}>
+                      Grant Access
+                    </button>
+                    <button
+                      className="btn btn-sm btn-ghost"
+                      onClick={() => recheckAccess()}
+                      disabled={checkingAccess}
+                    >
+                      {checkingAccess ? (
+                        <span className="loading loading-spinner loading-xs" />
+                      ) : (
+                        'Check Again'
+                      )}
+                    </button>
+                  </div>
+                </div>
+              </div>
+            </div>
+          )}
+
+          {/* Watch Limit Note */}
+          {watcherStatus?.is_running && watcherStatus.watch_limit && (
+            <div className="bg-warning/10 border border-warning/30 rounded-lg p-4">
+              <div className="flex items-start gap-3">
+                <svg
+                  className="w-5 h-5 text-warning flex-shrink-0 mt-0.5"
+                  fill="none"
+                  stroke="currentColor"
+                  viewBox="0 0 24 24"
+                >
+                  <path
+                    strokeLinecap="round"
+                    strokeLinejoin="round"
+                    strokeWidth={2}
+                    d="M12 9v2m0 4h.01m-6.938 4h13.856c1.54 0 2.502-1.667 1.732-3L13.732 4c-.77-1.333-2.694-1.333-3.464 0L3.34 16c-.77 1.333.192 3 1.732 3z"
+                  />
+                </svg>
+                <div className="flex-1">
+                  <div className="font-medium text-base-content/80 text-sm">
+                    Polling for Changes
+                  </div>
+                  <div className="text-sm text-base-content/60 mt-1">
+                    {watcherStatus.watch_limit}
+                  </div>
+                </div>
+              </div>
+            </div>
+          )}
+
           <div className="space-y-6">
             {/* Home Directory - Always enabled */}
             <div className="form-control w-full" data-tour="home-directory">
@@ -591,12 +692,14 @@ function AgentConfig({ agent, headerActions }: AgentConfigProps) {
                     <div className="text-xs text-base-content/60">
                       {!localConfig.enabled
                         ? 'Enable the provider to start file watching'
-                        : directoryExists === false
-                          ? 'Cannot start watcher - directory does not exist'
-                          : localConfig.projectSelection === 'SELECTED' &&
-                              localConfig.selectedProjects.length === 0
-                            ? 'Select at least one project to watch'
-                            : 'Configure your projects above to start watching'}
+                        : deniedDirectory
+                          ? 'Cannot start watcher - GuideMode needs access to the directory'
+                          : directoryExists === false
+                            ? 'Cannot start watcher - directory does not exist'
+                            : localConfig.projectSelection === 'SELECTED' &&
+                                localConfig.selectedProjects.length === 0
+                              ? 'Select at least one project to watch'
+                              : 'Configure your projects above to start watching'}
                     </div>
                   )}
                 </div>
@@ -779,10 +882,15 @@ function AgentConfig({ agent, headerActions }: AgentConfigProps) {
                     current={rescanProgress.current}
                     total={rescanProgress.total}
                     message={rescanProgress.message}
+                    onCancel={cancelRescan}
                   />
                 </div>
               )}
 
+              {syncPreview.data && (
+                <SyncPreview preview={syncPreview.data} onClose={() => syncPreview.reset()} />
+              )}
+
               {/* Action Buttons */}
               <div className="flex gap-2 items-start">
                 <div className="flex-1">
@@ -819,6 +927,21 @@ function AgentConfig({ agent, headerActions }: AgentConfigProps) {
                     Scan directory for new sessions
                   </p>
                 </div>
+                <div className="flex-1">
+                  <button
+                    onClick={handlePreviewSync}
+                    className="btn btn-outline btn-sm w-full"
+                    disabled={isScanning || syncPreview.isPending || directoryExists === false}
+                  >
+                    {syncPreview.isPending && (
+                      <span className="loading loading-spinner loading-xs" />
+                    )}
+                    Preview Sync
+                  </button>
+                  <p className="text-xs text-base-content/60 mt-1">
+                    Dry run without storing or uploading
+                  </p>
+                </div>
                 <div className="flex-1">
                   <button
                     onClick={() => setConfirmClear(true)}
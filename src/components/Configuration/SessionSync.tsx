@@ -19,8 +19,17 @@ function SessionSync({ agent }: SessionSyncProps) {
   const navigate = useNavigate()
   const location = useLocation()
   const { data: config } = useProviderConfig(agent.id)
-  const { scanSessions, syncSessions, resetProgress, progress, isScanning, isSyncing, error } =
-    useSessionSync(agent.id)
+  const {
+    scanSessions,
+    syncSessions,
+    cancelOperation,
+    resetProgress,
+    progress,
+    isScanning,
+    isSyncing,
+    isCancelling,
+    error,
+  } = useSessionSync(agent.id)
 
   const [showDetails, setShowDetails] = useState(false)
 
@@ -136,6 +145,15 @@ function SessionSync({ agent }: SessionSyncProps) {
                   {isSyncing && !progress.current_project && 'Queueing sessions...'}
                   {isUploading && 'Uploading sessions...'}
                 </span>
+                {(isScanning || isSyncing) && (
+                  <button
+                    onClick={cancelOperation}
+                    className="btn btn-ghost btn-xs ml-auto"
+                    disabled={isCancelling}
+                  >
+                    Cancel
+                  </button>
+                )}
               </div>
             )}
 
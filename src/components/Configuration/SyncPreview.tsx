@@ -0,0 +1,71 @@
+import type { SyncPreview as SyncPreviewData } from '../../hooks/useSyncPreview'
+
+function formatBytes(bytes: number): string {
+  if (bytes < 1024) return `${bytes} B`
+  if (bytes < 1024 * 1024) return `${(bytes / 1024).toFixed(1)} KB`
+  if (bytes < 1024 * 1024 * 1024) return `${(bytes / (1024 * 1024)).toFixed(1)} MB`
+  return `${(bytes / (1024 * 1024 * 1024)).toFixed(2)} GB`
+}
+
+interface SyncPreviewProps {
+  preview: SyncPreviewData
+  onClose: () => void
+}
+
+// What a sync of the provider would store and upload, from a dry run
+function SyncPreview({ preview, onClose }: SyncPreviewProps) {
+  const problems = preview.sessions.filter(
+    session => session.validationErrorCount > 0 || session.skippedLines > 0,
+  )
+
+  return (
+    <div className="bg-base-200 rounded-lg p-3 mb-4 text-sm">
+      <div className="flex items-center justify-between mb-2">
+        <span className="font-semibold">Sync preview</span>
+        <button onClick={onClose} className="btn btn-ghost btn-xs">
+          Close
+        </button>
+      </div>
+      <div className="grid grid-cols-2 gap-x-4 gap-y-1">
+        <span className="text-base-content/70">Sessions found</span>
+        <span>
+          {preview.sessionCount} ({formatBytes(preview.totalBytes)})
+        </span>
+        <span className="text-base-content/70">To upload</span>
+        <span>{preview.sessionsToUpload}</span>
+        <span className="text-base-content/70">Estimated upload</span>
+        <span>
+          {preview.syncMode === 'Nothing'
+            ? 'Nothing (sync is off)'
+            : `${formatBytes(preview.estimatedUploadBytes)} (${preview.syncMode})`}
+        </span>
+        <span className="text-base-content/70">With problems</span>
+        <span className={preview.sessionsWithErrors > 0 ? 'text-warning' : ''}>
+          {preview.sessionsWithErrors}
+        </span>
+      </div>
+
+      {problems.length > 0 && (
+        <div className="mt-3 space-y-1 max-h-48 overflow-y-auto">
+          {problems.map(session => (
+            <details key={session.sessionId} className="bg-base-100 rounded px-2 py-1">
+              <summary className="cursor-pointer text-xs">
+                <span className="font-mono">{session.sessionId}</span> · {session.projectName}
+                {session.skippedLines > 0 && ` · ${session.skippedLines} lines skipped`}
+                {session.validationErrorCount > 0 &&
+                  ` · ${session.validationErrorCount} validation errors`}
+              </summary>
+              <ul className="text-xs text-error mt-1 space-y-0.5">
+                {session.validationErrors.map(error => (
+                  <li key={error}>{error}</li>
+                ))}
+              </ul>
+            </details>
+          ))}
+        </div>
+      )}
+    </div>
+  )
+}
+
+export default SyncPreview
@@ -0,0 +1,90 @@
+import { useState } from 'react'
+import { useConversionErrors, useRetryConversionErrors } from '../hooks/useConversionErrors'
+
+// Source lines the converters couldn't handle, grouped by session, with a way
+// to convert the sessions again
+export function ConversionErrors() {
+  const { data: errors = [], refetch } = useConversionErrors()
+  const retry = useRetryConversionErrors()
+  const [expanded, setExpanded] = useState<string | null>(null)
+
+  const sessions = new Map<string, typeof errors>()
+  for (const error of errors) {
+    const key = `${error.provider}/${error.sessionId}`
+    sessions.set(key, [...(sessions.get(key) ?? []), error])
+  }
+
+  return (
+    <div className="card bg-base-100 shadow-sm border border-base-300">
+      <div className="card-body">
+        <div className="flex items-center justify-between">
+          <h2 className="card-title">Conversion Errors</h2>
+          <button onClick={() => refetch()} className="btn btn-ghost btn-sm">
+            Refresh
+          </button>
+        </div>
+        <p className="text-sm text-base-content/70 mb-4">
+          Lines an agent wrote in a format GuideMode couldn't read are left out of the session and
+          kept here. Sessions are converted again automatically after an app update.
+        </p>
+
+        {sessions.size === 0 ? (
+          <p className="text-sm text-base-content/60">No conversion errors</p>
+        ) : (
+          <div className="space-y-2">
+            {[...sessions.entries()].map(([key, lines]) => (
+              <div key={key} className="bg-base-200 rounded-lg">
+                <button
+                  className="w-full flex justify-between p-2 text-sm"
+                  onClick={() => setExpanded(expanded === key ? null : key)}
+                >
+                  <span className="font-medium truncate">{key}</span>
+                  <span className="text-base-content/70">
+                    {lines.length} {lines.length === 1 ? 'line' : 'lines'} · v{lines[0].appVersion}
+                  </span>
+                </button>
+                {expanded === key && (
+                  <div className="px-4 pb-2 space-y-2">
+                    <div className="text-xs text-base-content/60 font-mono truncate">
+                      {lines[0].sourcePath}
+                    </div>
+                    {lines.map(line => (
+                      <div key={line.lineNumber} className="text-xs">
+                        <div>
+                          Line {line.lineNumber}: <span className="text-error">{line.error}</span>
+                        </div>
+                        <pre className="bg-base-300 rounded p-1 overflow-x-auto whitespace-pre-wrap break-all">
+                          {line.rawLine}
+                        </pre>
+                      </div>
+                    ))}
+                  </div>
+                )}
+              </div>
+            ))}
+          </div>
+        )}
+
+        {retry.error && (
+          <div className="alert alert-error text-sm mt-2">{String(retry.error)}</div>
+        )}
+        {retry.data && (
+          <div className="alert alert-info text-sm mt-2">
+            Retried {retry.data.retried} sessions: {retry.data.recovered} recovered,{' '}
+            {retry.data.stillFailing} still failing, {retry.data.failed} failed
+          </div>
+        )}
+
+        <div className="card-actions justify-end mt-2">
+          <button
+            onClick={() => retry.mutate(undefined)}
+            className="btn btn-primary btn-sm"
+            disabled={sessions.size === 0 || retry.isPending}
+          >
+            {retry.isPending ? 'Retrying...' : 'Retry all'}
+          </button>
+        </div>
+      </div>
+    </div>
+  )
+}
@@ -231,6 +231,8 @@ function ClaudeFileCard({ file, expanded, onToggle }: ClaudeFileCardProps) {
         return <div className="badge badge-primary badge-sm">Command</div>
       case 'skill':
         return <div className="badge badge-secondary badge-sm">Skill</div>
+      case 'agent':
+        return <div className="badge badge-accent badge-sm">Agent</div>
       case 'config':
         return <div className="badge badge-ghost badge-sm">Config</div>
       default:
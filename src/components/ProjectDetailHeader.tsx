@@ -1,16 +1,26 @@
-import { CalendarIcon, CodeBracketIcon, FolderIcon } from '@heroicons/react/24/outline'
+import {
+  ArrowPathIcon,
+  CalendarIcon,
+  CodeBracketIcon,
+  FolderIcon,
+  NoSymbolIcon,
+} from '@heroicons/react/24/outline'
 import type { LocalProject } from '../hooks/useLocalProjects'
 
 interface ProjectDetailHeaderProps {
   project: LocalProject
   onOpenFolder?: () => void
   onViewGithub?: () => void
+  onToggleSync?: () => void
+  syncTogglePending?: boolean
 }
 
 export function ProjectDetailHeader({
   project,
   onOpenFolder,
   onViewGithub,
+  onToggleSync,
+  syncTogglePending,
 }: ProjectDetailHeaderProps) {
   const formatDate = (timestamp: number) => {
     return new Date(timestamp).toLocaleDateString('en-US', {
@@ -30,6 +40,9 @@ export function ProjectDetailHeader({
             <div className="flex items-center gap-3">
               <h2 className="text-2xl font-bold text-base-content">{project.name}</h2>
               <div className="badge badge-primary badge-outline">{project.type}</div>
+              {project.syncExcluded && (
+                <div className="badge badge-warning badge-outline">Not synced</div>
+              )}
             </div>
 
             {/* CWD */}
@@ -86,6 +99,26 @@ export function ProjectDetailHeader({
                 View on GitHub
               </button>
             )}
+            {onToggleSync && (
+              <button
+                type="button"
+                onClick={onToggleSync}
+                disabled={syncTogglePending}
+                className="btn btn-sm btn-outline"
+                title={
+                  project.syncExcluded
+                    ? 'Sync this project again, including sessions skipped while it was excluded'
+                    : 'Stop syncing this project and cancel its queued uploads'
+                }
+              >
+                {project.syncExcluded ? (
+                  <ArrowPathIcon className="w-4 h-4" />
+                ) : (
+                  <NoSymbolIcon className="w-4 h-4" />
+                )}
+                {project.syncExcluded ? 'Resume Sync' : 'Exclude from Sync'}
+              </button>
+            )}
           </div>
         </div>
       </div>
@@ -1,11 +1,11 @@
 import { DateFilter, SessionCard } from '@guidemode/session-processing/ui'
 import type { DateFilterValue, SessionRating } from '@guidemode/session-processing/ui'
-import { invoke } from '@tauri-apps/api/core'
 import { useCallback, useState } from 'react'
 import { useNavigate } from 'react-router-dom'
 import { useLocalSessions } from '../hooks/useLocalSessions'
 import { useQuickRating } from '../hooks/useQuickRating'
 import { useToast } from '../hooks/useToast'
+import { invoke } from '../services/commands'
 import { useSessionActivityStore } from '../stores/sessionActivityStore'
 import ProviderIcon from './icons/ProviderIcon'
 
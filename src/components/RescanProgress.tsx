@@ -9,6 +9,7 @@ interface RescanProgressProps {
 function RescanProgress({ phase, current, total, message, onCancel }: RescanProgressProps) {
   const percentage = total > 0 ? Math.round((current / total) * 100) : 0
   const isComplete = phase === 'complete'
+  const isCancelled = phase === 'cancelled'
   const isScanning = phase === 'scanning'
 
   return (
@@ -16,7 +17,7 @@ function RescanProgress({ phase, current, total, message, onCancel }: RescanProg
       {/* Status indicator */}
       <div className="flex items-center justify-between">
         <div className="flex items-center gap-2">
-          {!isComplete ? (
+          {!isComplete && !isCancelled ? (
             <span className="loading loading-spinner loading-sm text-primary" />
           ) : (
             <svg
@@ -36,12 +37,14 @@ function RescanProgress({ phase, current, total, message, onCancel }: RescanProg
           <span className="text-sm font-medium">
             {isComplete
               ? 'Scan Complete'
+              : isCancelled
+                ? 'Scan Cancelled'
               : isScanning
                 ? 'Scanning Directory...'
                 : 'Processing Sessions...'}
           </span>
         </div>
-        {onCancel && !isComplete && (
+        {onCancel && !isComplete && !isCancelled && (
           <button onClick={onCancel} className="btn btn-ghost btn-xs">
             Cancel
           </button>
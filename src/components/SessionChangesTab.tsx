@@ -1,6 +1,5 @@
 import { DiffModeEnum, DiffView } from '@git-diff-view/react'
 import { useQuery } from '@tanstack/react-query'
-import { invoke } from '@tauri-apps/api/core'
 import { useState } from 'react'
 import './git-diff-scoped.css'
 import {
@@ -11,6 +10,7 @@ import {
   MinusIcon,
   PlusIcon,
 } from '@heroicons/react/24/outline'
+import { invoke } from '../services/commands'
 
 interface FileDiff {
   oldPath: string
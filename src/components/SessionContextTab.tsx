@@ -5,9 +5,9 @@ import {
   DocumentTextIcon,
 } from '@heroicons/react/24/outline'
 import { useQuery } from '@tanstack/react-query'
-import { invoke } from '@tauri-apps/api/core'
 import { useEffect, useState } from 'react'
 import { type FileUsageStats, useContextFileUsage } from '../hooks/useContextFileUsage'
+import { invoke } from '../services/commands'
 
 // Dynamic import types for syntax highlighter
 interface SyntaxHighlighterDeps {
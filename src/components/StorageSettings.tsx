@@ -0,0 +1,151 @@
+import { useEffect, useState } from 'react'
+import { useCacheSettings, useDiskUsage, useSaveCacheSettings } from '../hooks/useSessionCache'
+
+function formatBytes(bytes: number): string {
+  if (bytes < 1024) return `${bytes} B`
+  if (bytes < 1024 * 1024) return `${(bytes / 1024).toFixed(1)} KB`
+  if (bytes < 1024 * 1024 * 1024) return `${(bytes / (1024 * 1024)).toFixed(1)} MB`
+  return `${(bytes / (1024 * 1024 * 1024)).toFixed(2)} GB`
+}
+
+// Where converted sessions are cached, how much space they take and the
+// size synced sessions are pruned back to
+export function StorageSettings() {
+  const { data: usage, refetch } = useDiskUsage()
+  const { data: settings } = useCacheSettings()
+  const saveSettings = useSaveCacheSettings()
+  const [cacheDir, setCacheDir] = useState('')
+  const [maxMb, setMaxMb] = useState('')
+  const [expanded, setExpanded] = useState<string | null>(null)
+
+  useEffect(() => {
+    if (settings) {
+      setCacheDir(settings.cacheDir)
+      setMaxMb(settings.maxMb ? String(settings.maxMb) : '')
+    }
+  }, [settings])
+
+  const isDirty =
+    !!settings &&
+    (cacheDir.trim() !== settings.cacheDir || (maxMb ? Number(maxMb) : null) !== settings.maxMb)
+
+  const handleSave = () => {
+    saveSettings.mutate({
+      cacheDir: cacheDir.trim(),
+      maxMb: maxMb ? Number.parseInt(maxMb) : null,
+    })
+  }
+
+  return (
+    <div className="card bg-base-100 shadow-sm border border-base-300">
+      <div className="card-body">
+        <div className="flex items-center justify-between">
+          <h2 className="card-title">Storage</h2>
+          <button onClick={() => refetch()} className="btn btn-ghost btn-sm">
+            Refresh
+          </button>
+        </div>
+        <p className="text-sm text-base-content/70 mb-4">
+          Converted sessions are cached locally. Sessions already synced to the server are removed,
+          least recently used first, when the cache grows past its limit.
+        </p>
+
+        {usage && (
+          <div className="space-y-2 mb-4">
+            <div className="flex justify-between">
+              <span className="text-base-content/70">Cache size</span>
+              <span>
+                {formatBytes(usage.totalBytes)}
+                {usage.maxBytes !== null && ` of ${formatBytes(usage.maxBytes)}`} ·{' '}
+                {usage.fileCount} sessions
+              </span>
+            </div>
+            {usage.maxBytes !== null && (
+              <progress
+                className="progress progress-primary w-full"
+                value={Math.min(usage.totalBytes, usage.maxBytes)}
+                max={usage.maxBytes}
+              />
+            )}
+            {usage.providers.map(provider => (
+              <div key={provider.provider} className="bg-base-200 rounded-lg">
+                <button
+                  className="w-full flex justify-between p-2 text-sm"
+                  onClick={() =>
+                    setExpanded(expanded === provider.provider ? null : provider.provider)
+                  }
+                >
+                  <span className="font-medium">{provider.provider}</span>
+                  <span className="text-base-content/70">
+                    {formatBytes(provider.totalBytes)} · {provider.fileCount}
+                  </span>
+                </button>
+                {expanded === provider.provider && (
+                  <div className="px-4 pb-2 space-y-1">
+                    {provider.projects.map(project => (
+                      <div key={project.project} className="flex justify-between text-xs">
+                        <span className="truncate">{project.project}</span>
+                        <span className="text-base-content/60">
+                          {formatBytes(project.totalBytes)} · {project.fileCount}
+                        </span>
+                      </div>
+                    ))}
+                  </div>
+                )}
+              </div>
+            ))}
+          </div>
+        )}
+
+        <div className="form-control">
+          <label className="label">
+            <span className="label-text font-medium">Cache directory</span>
+          </label>
+          <input
+            type="text"
+            className="input input-bordered input-sm font-mono"
+            value={cacheDir}
+            onChange={e => setCacheDir(e.target.value)}
+          />
+          <label className="label">
+            <span className="label-text-alt">Changing it moves the cached sessions</span>
+          </label>
+        </div>
+
+        <div className="form-control">
+          <label className="label">
+            <span className="label-text font-medium">Size limit (MB)</span>
+          </label>
+          <input
+            type="number"
+            min="1"
+            placeholder="Unlimited"
+            className="input input-bordered input-sm"
+            value={maxMb}
+            onChange={e => setMaxMb(e.target.value)}
+          />
+        </div>
+
+        {saveSettings.error && (
+          <div className="alert alert-error text-sm mt-2">{String(saveSettings.error)}</div>
+        )}
+        {saveSettings.data && saveSettings.data.removedFiles > 0 && (
+          <div className="alert alert-success text-sm mt-2">
+            Freed {formatBytes(saveSettings.data.freedBytes)} by removing{' '}
+            {saveSettings.data.removedFiles} synced sessions
+          </div>
+        )}
+
+        <div className="card-actions justify-end mt-2">
+          <button
+            onClick={handleSave}
+            className="btn btn-primary btn-sm"
+            disabled={!isDirty || saveSettings.isPending}
+          >
+            {saveSettings.isPending ? 'Saving...' : 'Save'}
+          </button>
+        </div>
+      </div>
+    </div>
+  )
+}
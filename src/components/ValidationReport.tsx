@@ -1,8 +1,8 @@
 import { type JSONLValidationResult, validateJSONL } from '@guidemode/session-processing/validation'
 import { XCircleIcon } from '@heroicons/react/24/outline'
-import { invoke } from '@tauri-apps/api/core'
 import type React from 'react'
 import { useCallback, useEffect, useState } from 'react'
+import { invoke } from '../services/commands'
 import { ValidationBadge } from './ValidationBadge'
 
 interface ValidationReportProps {
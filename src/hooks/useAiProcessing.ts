@@ -10,9 +10,9 @@ import {
   SessionSummaryTask,
 } from '@guidemode/session-processing/ai-models'
 import type { ParsedSession } from '@guidemode/session-processing/processors'
-import { invoke } from '@tauri-apps/api/core'
 import { fetch as tauriFetch } from '@tauri-apps/plugin-http'
 import { useCallback, useState } from 'react'
+import { invoke } from '../services/commands'
 import { useConfigStore } from '../stores/configStore'
 import type { AiProcessingStep } from './useAiProcessingProgress'
 import { useAuth } from './useAuth'
@@ -488,29 +488,17 @@ async function storeAiResults(sessionId: string, results: AiProcessingResult): P
   try {
     // Update agent_sessions table with AI results, mark processing as completed,
     // and reset synced_to_server to trigger a new upload with AI data
-    await invoke('execute_sql', {
-      sql: `
-        UPDATE agent_sessions
-        SET
-          ai_model_summary = ?,
-          ai_model_quality_score = ?,
-          ai_model_metadata = ?,
-          ai_model_phase_analysis = ?,
-          processing_status = 'completed',
-          processed_at = ?,
-          synced_to_server = 0
-        WHERE session_id = ?
-      `,
-      params: [
-        results.summary || null,
-        results.qualityScore ?? null,
-        results.metadata && Object.keys(results.metadata).length > 0
-          ? JSON.stringify(results.metadata)
-          : null,
-        results.phaseAnalysis ? JSON.stringify(results.phaseAnalysis) : null,
-        Date.now(),
-        sessionId,
-      ],
+    await invoke('store_ai_results', {
+      sessionId,
+      results: {
+        summary: results.summary || null,
+        qualityScore: results.qualityScore ?? null,
+        metadata:
+          results.metadata && Object.keys(results.metadata).length > 0
+            ? JSON.stringify(results.metadata)
+            : null,
+        phaseAnalysis: results.phaseAnalysis ? JSON.stringify(results.phaseAnalysis) : null,
+      },
     })
   } catch (err) {
     console.error('Failed to store AI results:', err)
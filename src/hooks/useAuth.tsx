@@ -1,7 +1,7 @@
 import { useMutation, useQuery, useQueryClient } from '@tanstack/react-query'
-import { invoke } from '@tauri-apps/api/core'
 import { listen } from '@tauri-apps/api/event'
 import { useEffect } from 'react'
+import { invoke } from '../services/commands'
 
 export interface User {
   username: string
@@ -20,6 +20,14 @@ export interface GuideModeConfig {
   avatarUrl?: string
   tenantId?: string
   tenantName?: string
+  launchAtLogin?: boolean
+  startMinimized?: boolean
+  cacheDir?: string
+  cacheMaxMb?: number
+  idleThresholdMinutes?: number
+  maxConcurrentUploads?: number
+  dbPollIntervalSecs?: number
+  apiBasePath?: string
 }
 
 export function useAuth() {
@@ -1,6 +1,7 @@
-import { invoke } from '@tauri-apps/api/core'
 import { listen } from '@tauri-apps/api/event'
 import { useEffect } from 'react'
+import { getDatabase } from '../db/client'
+import { invoke } from '../services/commands'
 import { useSessionProcessing } from './useSessionProcessing'
 
 // SQL result type for session query
@@ -35,13 +36,13 @@ export function useAutoSessionProcessing() {
 
         try {
           // Fetch session details from database (returns snake_case SQL column names)
-          const sessionResult = await invoke<SessionRow[]>('execute_sql', {
-            sql: `SELECT provider, file_path, session_id
+          const sessionResult = await getDatabase().select<SessionRow[]>(
+            `SELECT provider, file_path, session_id
                   FROM agent_sessions
                   WHERE session_id = ?
                   LIMIT 1`,
-            params: [sessionId],
-          })
+            [sessionId]
+          )
 
           if (sessionResult.length === 0) {
             console.error(`Session ${sessionId} not found in database`)
@@ -1,5 +1,6 @@
-import { invoke } from '@tauri-apps/api/core'
 import { useCallback, useEffect, useRef, useState } from 'react'
+import { getDatabase } from '../db/client'
+import { invoke } from '../services/commands'
 import { useSessionProcessing } from './useSessionProcessing'
 
 // SQL result type for unprocessed sessions query
@@ -30,8 +31,8 @@ export function useBackgroundProcessing() {
       setIsProcessing(true)
 
       // Find sessions without metrics (returns snake_case SQL column names)
-      const unprocessedSessions = await invoke<UnprocessedSessionRow[]>('execute_sql', {
-        sql: `
+      const unprocessedSessions = await getDatabase().select<UnprocessedSessionRow[]>(
+        `
           SELECT s.session_id, s.provider, s.file_path
           FROM agent_sessions s
           LEFT JOIN session_metrics m ON s.session_id = m.session_id
@@ -39,8 +40,8 @@ export function useBackgroundProcessing() {
           ORDER BY s.created_at DESC
           LIMIT 5
         `,
-        params: [],
-      })
+        []
+      )
 
       if (unprocessedSessions.length > 0) {
         console.log(`Processing ${unprocessedSessions.length} unprocessed sessions...`)
@@ -1,7 +1,7 @@
 import { useQuery } from '@tanstack/react-query'
-import { invoke } from '@tauri-apps/api/core'
+import { invoke } from '../services/commands'
 
-export type ClaudeFileType = 'command' | 'skill' | 'config' | 'other'
+export type ClaudeFileType = 'command' | 'skill' | 'agent' | 'config' | 'other'
 
 export interface ClaudeMetadata {
   name?: string
@@ -1,11 +1,15 @@
 import { useMutation, useQuery, useQueryClient } from '@tanstack/react-query'
-import { invoke } from '@tauri-apps/api/core'
+import { invoke } from '../services/commands'
 
 export interface CodexWatcherStatus {
   is_running: boolean
   pending_uploads: number
   processing_uploads: number
   failed_uploads: number
+  /** Directory GuideMode isn't allowed to read until access is granted */
+  access_denied?: string | null
+  /** How to raise the file watch limit, when the watcher fell back to polling */
+  watch_limit?: string | null
 }
 
 export function useCodexWatcherStatus() {
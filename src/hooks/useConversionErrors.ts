@@ -0,0 +1,44 @@
+import { useMutation, useQuery, useQueryClient } from '@tanstack/react-query'
+import { invoke } from '../services/commands'
+
+export interface ConversionError {
+  provider: string
+  sessionId: string
+  sourcePath: string
+  lineNumber: number
+  rawLine: string
+  error: string
+  appVersion: string
+  providerVersion: string | null
+  createdAt: string
+}
+
+export interface RetryReport {
+  retried: number
+  recovered: number
+  stillFailing: number
+  failed: number
+}
+
+export function useConversionErrors(provider?: string) {
+  return useQuery({
+    queryKey: ['conversion-errors', provider ?? null],
+    queryFn: () => invoke<ConversionError[]>('list_conversion_errors', { provider }),
+    staleTime: 60_000,
+  })
+}
+
+/**
+ * Convert quarantined sessions again from their source files. Lines the
+ * converters now handle drop out of the quarantine.
+ */
+export function useRetryConversionErrors() {
+  const queryClient = useQueryClient()
+
+  return useMutation({
+    mutationFn: (provider?: string) => invoke<RetryReport>('retry_conversion_errors', { provider }),
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: ['conversion-errors'] })
+    },
+  })
+}
@@ -1,7 +1,8 @@
 import { ProcessorRegistry } from '@guidemode/session-processing/processors'
 import { useQueryClient } from '@tanstack/react-query'
-import { invoke } from '@tauri-apps/api/core'
 import { useEffect, useRef } from 'react'
+import { getDatabase } from '../db/client'
+import { invoke } from '../services/commands'
 import { useConfigStore } from '../stores/configStore'
 import { useAiProcessing } from './useAiProcessing'
 
@@ -49,8 +50,8 @@ export function useDelayedAiProcessing() {
         // - session_end_time IS NOT NULL (session has ended)
         // - (now - session_end_time) > delay (minimum wait time)
         // - session_end_time > (now - 1 hour) (maximum age window)
-        const eligibleSessions = await invoke<EligibleSessionRow[]>('execute_sql', {
-          sql: `
+        const eligibleSessions = await getDatabase().select<EligibleSessionRow[]>(
+          `
             SELECT session_id, provider, file_path, session_end_time
             FROM agent_sessions
             WHERE core_metrics_status = 'completed'
@@ -60,8 +61,8 @@ export function useDelayedAiProcessing() {
               AND session_end_time > ?
             LIMIT 10
           `,
-          params: [nowMs, delayMs, minSessionEndTime],
-        })
+          [nowMs, delayMs, minSessionEndTime]
+        )
 
         if (eligibleSessions.length === 0) {
           return
@@ -119,10 +120,7 @@ export function useDelayedAiProcessing() {
 
             if (isAuthError) {
               // Mark as failed so we don't keep retrying with bad credentials
-              await invoke('execute_sql', {
-                sql: `UPDATE agent_sessions SET processing_status = 'failed' WHERE session_id = ?`,
-                params: [sessionRow.session_id],
-              })
+              await invoke('fail_ai_processing', { sessionId: sessionRow.session_id })
             }
           } finally {
             processingQueue.current.delete(sessionRow.session_id)
@@ -1,5 +1,5 @@
 import { useQuery } from '@tanstack/react-query'
-import { invoke } from '@tauri-apps/api/core'
+import { invoke } from '../services/commands'
 
 export function useDirectoryExists(path: string | undefined, enabled = true) {
   return useQuery({
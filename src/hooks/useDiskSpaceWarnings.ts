@@ -0,0 +1,51 @@
+import { listen } from '@tauri-apps/api/event'
+import { useEffect } from 'react'
+import { useToastStore } from '../stores/toastStore'
+
+export interface DiskSpace {
+  path: string
+  availableBytes: number
+  minFreeBytes: number
+  /** Session files and archives aren't written until space is freed */
+  low: boolean
+  checkedAt: string
+}
+
+const megabytes = (bytes: number) => Math.floor(bytes / 1_000_000)
+
+/**
+ * Tell the user when session file writes pause because the disk is nearly
+ * full, and when they resume
+ */
+export function useDiskSpaceWarnings() {
+  const addToast = useToastStore(state => state.addToast)
+
+  useEffect(() => {
+    let unlisten: (() => void) | undefined
+
+    listen<DiskSpace>('disk-space', event => {
+      const space = event.payload
+      addToast(
+        space.low
+          ? {
+              type: 'error',
+              message: `Only ${megabytes(space.availableBytes)} MB of disk space left. Sessions aren't being saved until at least ${megabytes(space.minFreeBytes)} MB is free.`,
+              duration: 15000,
+            }
+          : {
+              type: 'success',
+              message: 'Disk space freed. Sessions are being saved again.',
+              duration: 5000,
+            }
+      )
+    })
+      .then(fn => {
+        unlisten = fn
+      })
+      .catch(error => console.error('Failed to set up disk space listener:', error))
+
+    return () => {
+      unlisten?.()
+    }
+  }, [addToast])
+}
@@ -1,5 +1,5 @@
-import { invoke } from '@tauri-apps/api/core'
 import { useCallback, useEffect, useState } from 'react'
+import { invoke } from '../services/commands'
 
 export interface LocalProject {
   id: string
@@ -10,6 +10,7 @@ export interface LocalProject {
   createdAt: number
   updatedAt: number
   sessionCount: number
+  syncExcluded: boolean
 }
 
 /**
@@ -5,7 +5,7 @@ import {
   parserRegistry,
 } from '@guidemode/session-processing/ui'
 import { useQuery } from '@tanstack/react-query'
-import { invoke } from '@tauri-apps/api/core'
+import { invoke } from '../services/commands'
 
 interface UseLocalSessionContentResult {
   messages: BaseSessionMessage[]
@@ -1,6 +1,6 @@
 import type { SessionMetricsUI } from '@guidemode/session-processing/ui'
 import { useQuery } from '@tanstack/react-query'
-import { invoke } from '@tauri-apps/api/core'
+import { getDatabase } from '../db/client'
 
 interface UseLocalSessionMetricsResult {
   metrics: SessionMetricsUI | null
@@ -10,16 +10,16 @@ interface UseLocalSessionMetricsResult {
 
 async function fetchSessionMetrics(sessionId: string): Promise<SessionMetricsUI | null> {
   // Fetch metrics from local database
-  const result: any[] = await invoke('execute_sql', {
-    sql: `
+  const result = await getDatabase().select<any[]>(
+    `
       SELECT *
       FROM session_metrics
       WHERE session_id = ?
       ORDER BY created_at DESC
       LIMIT 1
     `,
-    params: [sessionId],
-  })
+    [sessionId]
+  )
 
   if (result.length === 0) {
     return null
@@ -1,12 +1,15 @@
 import type { AgentSession } from '@guidemode/types'
 import { useQuery, useQueryClient } from '@tanstack/react-query'
-import { invoke } from '@tauri-apps/api/core'
 import { useCallback, useEffect, useState } from 'react'
+import { getDatabase } from '../db/client'
+import { invoke } from '../services/commands'
 
 interface SessionWithMetrics extends AgentSession {
   filePath: string | null
   syncedToServer?: boolean
   syncFailedReason?: string | null
+  /** Source lines left out of the converted session; its metrics are incomplete */
+  conversionSkippedLines?: number
   cwd?: string | null
   metrics?: {
     // Performance
@@ -195,10 +198,7 @@ async function fetchSessions(filters: SessionFilters = {}): Promise<SessionWithM
     ORDER BY s.session_end_time DESC NULLS LAST
   `
 
-  const result: any[] = await invoke('execute_sql', {
-    sql: query,
-    params,
-  })
+  const result = await getDatabase().select<any[]>(query, params)
 
   console.log(`[useLocalSessions] Loaded ${result.length} sessions from database`)
 
@@ -273,6 +273,7 @@ async function fetchSessions(filters: SessionFilters = {}): Promise<SessionWithM
         : new Date().toISOString(),
       syncedToServer: row.synced_to_server === 1,
       syncFailedReason: row.sync_failed_reason || null,
+      conversionSkippedLines: row.conversion_skipped_lines || 0,
       cwd: row.cwd || null,
       gitBranch: row.git_branch || null,
       firstCommitHash: row.first_commit_hash || null,
@@ -327,8 +328,8 @@ export function useLocalSession(sessionId: string) {
 
     try {
       // Load session with metrics
-      const sessionResult: any[] = await invoke('execute_sql', {
-        sql: `
+      const sessionResult = await getDatabase().select<any[]>(
+        `
           SELECT
             s.*,
             COALESCE(p.name, s.project_name) as project_name,
@@ -351,8 +352,8 @@ export function useLocalSession(sessionId: string) {
           LEFT JOIN projects p ON s.project_id = p.id
           WHERE s.session_id = ?
         `,
-        params: [sessionId],
-      })
+        [sessionId]
+      )
 
       if (sessionResult.length === 0) {
         throw new Error('Session not found')
@@ -423,6 +424,7 @@ export function useLocalSession(sessionId: string) {
           : new Date().toISOString(),
         syncedToServer: row.synced_to_server === 1,
         syncFailedReason: row.sync_failed_reason || null,
+        conversionSkippedLines: row.conversion_skipped_lines || 0,
         filePath: row.file_path,
         cwd: row.cwd || null,
         gitBranch: row.git_branch || null,
@@ -0,0 +1,30 @@
+import { listen } from '@tauri-apps/api/event'
+import { useCallback, useEffect, useMemo, useState } from 'react'
+import { cancelOperation, type OperationProgress } from '../services/commands'
+
+/**
+ * Progress of one run of a long operation (export, import, migration,
+ * historical sync, maintenance), tracked by the operation ID passed to its
+ * command so it can be cancelled while the command is running
+ */
+export function useOperationProgress() {
+  const operationId = useMemo(() => crypto.randomUUID(), [])
+  const [progress, setProgress] = useState<OperationProgress | null>(null)
+
+  useEffect(() => {
+    const unlisten = listen<OperationProgress>('operation-progress', event => {
+      if (event.payload.operationId === operationId) {
+        setProgress(event.payload)
+      }
+    })
+
+    return () => {
+      unlisten.then(fn => fn())
+    }
+  }, [operationId])
+
+  const cancel = useCallback(() => cancelOperation(operationId), [operationId])
+  const reset = () => setProgress(null)
+
+  return { operationId, progress, cancel, reset }
+}
@@ -0,0 +1,24 @@
+import { useMutation, useQuery } from '@tanstack/react-query'
+import { invoke } from '../services/commands'
+
+/**
+ * The provider directory the operating system won't let GuideMode read
+ * (macOS privacy protection), or null when it can be read
+ */
+export function useProviderAccess(providerId: string, enabled = true) {
+  return useQuery({
+    queryKey: ['provider-access', providerId],
+    queryFn: () => invoke<string | null>('check_provider_access', { providerId }),
+    enabled,
+    staleTime: 30 * 1000, // 30 seconds
+  })
+}
+
+/**
+ * Open the system settings where GuideMode is granted access
+ */
+export function useOpenPrivacySettings() {
+  return useMutation({
+    mutationFn: () => invoke<void>('open_privacy_settings'),
+  })
+}
@@ -1,5 +1,5 @@
 import { useMutation, useQuery, useQueryClient } from '@tanstack/react-query'
-import { invoke } from '@tauri-apps/api/core'
+import { invoke } from '../services/commands'
 import type { Project, ProviderConfig } from '../types/providers'
 
 export function useProviderConfig(providerId: string) {
@@ -0,0 +1,41 @@
+import { listen } from '@tauri-apps/api/event'
+import { useEffect } from 'react'
+import { useToastStore } from '../stores/toastStore'
+
+export interface ProviderVersion {
+  providerId: string
+  version: string | null
+  testedVersion: string
+  newerThanTested: boolean
+  detectedAt: string
+}
+
+/**
+ * Warn when an installed agent is newer than the version its converter was
+ * tested with, since its sessions may fail to convert
+ */
+export function useProviderVersionWarnings() {
+  const addToast = useToastStore(state => state.addToast)
+
+  useEffect(() => {
+    let unlisten: (() => void) | undefined
+
+    listen<ProviderVersion[]>('provider-version-warning', event => {
+      for (const provider of event.payload) {
+        addToast({
+          type: 'warning',
+          message: `${provider.providerId} ${provider.version} is newer than the tested ${provider.testedVersion}. If its sessions stop syncing, check the provider logs.`,
+          duration: 10000,
+        })
+      }
+    })
+      .then(fn => {
+        unlisten = fn
+      })
+      .catch(error => console.error('Failed to set up provider version listener:', error))
+
+    return () => {
+      unlisten?.()
+    }
+  }, [addToast])
+}
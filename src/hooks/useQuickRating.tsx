@@ -1,6 +1,6 @@
 import type { SessionRating } from '@guidemode/session-processing/ui'
 import { useMutation, useQueryClient } from '@tanstack/react-query'
-import { invoke } from '@tauri-apps/api/core'
+import { invoke } from '../services/commands'
 
 interface QuickRatingParams {
   sessionId: string
@@ -1,26 +1,19 @@
 import { listen } from '@tauri-apps/api/event'
-import { useEffect, useState } from 'react'
-
-interface RescanProgressData {
-  provider: string
-  phase: string
-  current: number
-  total: number
-  message: string
-}
+import { useCallback, useEffect, useState } from 'react'
+import { cancelOperation, type OperationProgress } from '../services/commands'
 
 export function useRescanProgress(providerId: string) {
-  const [progress, setProgress] = useState<RescanProgressData | null>(null)
+  const [progress, setProgress] = useState<OperationProgress | null>(null)
 
   useEffect(() => {
     // Listen for rescan progress events
-    const unlisten = listen<RescanProgressData>('rescan-progress', event => {
+    const unlisten = listen<OperationProgress>('rescan-progress', event => {
       // Only update if this event is for our provider
       if (event.payload.provider === providerId) {
         setProgress(event.payload)
 
-        // Clear progress after completion (after 3 seconds)
-        if (event.payload.phase === 'complete') {
+        // Clear progress after the scan ends (after 3 seconds)
+        if (event.payload.phase === 'complete' || event.payload.phase === 'cancelled') {
           setTimeout(() => {
             setProgress(null)
           }, 3000)
@@ -35,5 +28,12 @@ export function useRescanProgress(providerId: string) {
 
   const reset = () => setProgress(null)
 
-  return { progress, reset }
+  const operationId = progress?.operationId
+  const cancel = useCallback(async () => {
+    if (operationId) {
+      await cancelOperation(operationId)
+    }
+  }, [operationId])
+
+  return { progress, reset, cancel }
 }
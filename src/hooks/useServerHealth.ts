@@ -0,0 +1,51 @@
+import { useQuery, useQueryClient } from '@tanstack/react-query'
+import { listen } from '@tauri-apps/api/event'
+import { useEffect } from 'react'
+import { invoke } from '../services/commands'
+
+export interface ServerHealth {
+  serverUrl: string
+  reachable: boolean
+  latencyMs: number | null
+  version: string | null
+  capabilities: string[]
+  schemaCompatible: boolean
+  message: string | null
+  checkedAt: string
+}
+
+/**
+ * Health of the connected server: version, latency and whether it can read
+ * this app's sessions. Checked on mount and refreshed by the backend's
+ * periodic `server-health` events.
+ */
+export function useServerHealth(enabled: boolean) {
+  const queryClient = useQueryClient()
+
+  const query = useQuery({
+    queryKey: ['server-health'],
+    queryFn: async (): Promise<ServerHealth> => await invoke('check_server_health'),
+    enabled,
+    staleTime: 60_000,
+  })
+
+  useEffect(() => {
+    let unlisten: (() => void) | undefined
+
+    listen<ServerHealth>('server-health', event => {
+      queryClient.setQueryData(['server-health'], event.payload)
+    })
+      .then(fn => {
+        unlisten = fn
+      })
+      .catch(error => console.error('Failed to set up server health listener:', error))
+
+    return () => {
+      if (unlisten) {
+        unlisten()
+      }
+    }
+  }, [queryClient])
+
+  return query
+}
@@ -0,0 +1,65 @@
+import { useMutation, useQuery, useQueryClient } from '@tanstack/react-query'
+import { invoke } from '../services/commands'
+
+export interface ProjectUsage {
+  project: string
+  totalBytes: number
+  fileCount: number
+}
+
+export interface ProviderUsage {
+  provider: string
+  totalBytes: number
+  fileCount: number
+  projects: ProjectUsage[]
+}
+
+export interface DiskUsage {
+  root: string
+  totalBytes: number
+  fileCount: number
+  maxBytes: number | null
+  providers: ProviderUsage[]
+}
+
+export interface CacheSettings {
+  cacheDir: string
+  maxMb: number | null
+}
+
+export interface PruneReport {
+  removedFiles: number
+  freedBytes: number
+}
+
+export function useDiskUsage() {
+  return useQuery({
+    queryKey: ['disk-usage'],
+    queryFn: () => invoke<DiskUsage>('get_disk_usage'),
+    staleTime: 60_000,
+  })
+}
+
+export function useCacheSettings() {
+  return useQuery({
+    queryKey: ['cache-settings'],
+    queryFn: () => invoke<CacheSettings>('get_cache_settings'),
+  })
+}
+
+/**
+ * Save the cache location and size limit. A new location moves the cached
+ * sessions there; the cache is then pruned to the limit.
+ */
+export function useSaveCacheSettings() {
+  const queryClient = useQueryClient()
+
+  return useMutation({
+    mutationFn: (settings: CacheSettings) =>
+      invoke<PruneReport>('set_cache_settings', { settings }),
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: ['cache-settings'] })
+      queryClient.invalidateQueries({ queryKey: ['disk-usage'] })
+    },
+  })
+}
@@ -8,8 +8,9 @@ import type {
   QualityMetrics,
   UsageMetrics,
 } from '@guidemode/types'
-import { invoke } from '@tauri-apps/api/core'
 import { useCallback, useState } from 'react'
+import { getDatabase } from '../db/client'
+import { invoke } from '../services/commands'
 import type { SessionMetricsRow } from '../utils/dbMappers'
 
 // Extended session metrics row with additional desktop-specific fields
@@ -102,10 +103,10 @@ export function useSessionProcessing() {
         let gitDiffData = undefined
         try {
           // Get session details from database to get cwd and commit hashes
-          const sessionDetails = await invoke<any[]>('execute_sql', {
-            sql: 'SELECT cwd, first_commit_hash, latest_commit_hash, session_start_time, session_end_time FROM agent_sessions WHERE session_id = ?',
-            params: [sessionId],
-          })
+          const sessionDetails = await getDatabase().select<any[]>(
+            'SELECT cwd, first_commit_hash, latest_commit_hash, session_start_time, session_end_time FROM agent_sessions WHERE session_id = ?',
+            [sessionId]
+          )
 
           if (sessionDetails[0]?.cwd && sessionDetails[0]?.first_commit_hash) {
             const session = sessionDetails[0]
@@ -294,7 +295,12 @@ function mapResultsToRow(
 }
 
 /**
- * Store metrics in local SQLite database
+ * Store processor-only metrics in local SQLite database
+ *
+ * Core metrics (latency, operations, errors, engagement, tokens, context) are
+ * computed and written by the backend engine once a session settles, so only
+ * the columns it doesn't compute are written here. The backend's values are
+ * left untouched on conflict.
  */
 async function storeMetrics(
   sessionId: string,
@@ -303,150 +309,7 @@ async function storeMetrics(
 ): Promise<void> {
   const row = mapResultsToRow(sessionId, provider, results)
 
-  const sql = `
-      INSERT OR REPLACE INTO session_metrics (
-        id, session_id, provider, timestamp,
-        response_latency_ms, task_completion_time_ms, performance_total_responses,
-        read_write_ratio, input_clarity_score, read_operations, write_operations, total_user_messages,
-        error_count, error_types, last_error_message, recovery_attempts, fatal_errors,
-        interruption_rate, session_length_minutes, total_interruptions, engagement_total_responses,
-        task_success_rate, iteration_count, process_quality_score,
-        used_plan_mode, used_todo_tracking, over_top_affirmations,
-        successful_operations, total_operations, exit_plan_mode_count, todo_write_count,
-        over_top_affirmations_phrases,
-        usage_improvement_tips, error_improvement_tips, engagement_improvement_tips,
-        quality_improvement_tips, performance_improvement_tips, improvement_tips,
-        custom_metrics,
-        git_total_files_changed, git_lines_added, git_lines_removed, git_lines_modified,
-        git_net_lines_changed, git_lines_read_per_line_changed, git_reads_per_file_changed,
-        git_lines_changed_per_minute, git_lines_changed_per_tool_use, total_lines_read,
-        git_diff_improvement_tips,
-        total_input_tokens, total_output_tokens, total_cache_created, total_cache_read,
-        context_length, context_window_size, context_utilization_percent,
-        compact_event_count, compact_event_steps, avg_tokens_per_message,
-        messages_until_first_compact, context_improvement_tips,
-        created_at
-      ) VALUES (
-        ?, ?, ?, ?,
-        ?, ?, ?,
-        ?, ?, ?, ?, ?,
-        ?, ?, ?, ?, ?,
-        ?, ?, ?, ?,
-        ?, ?, ?,
-        ?, ?, ?,
-        ?, ?, ?, ?,
-        ?,
-        ?, ?, ?,
-        ?, ?, ?,
-        ?,
-        ?, ?, ?, ?,
-        ?, ?, ?,
-        ?, ?, ?,
-        ?,
-        ?, ?, ?, ?,
-        ?, ?, ?,
-        ?, ?, ?,
-        ?, ?,
-        ?
-      )
-    `
-
-  const params = [
-    row.id,
-    row.session_id,
-    row.provider,
-    row.timestamp,
-    row.response_latency_ms ?? null,
-    row.task_completion_time_ms ?? null,
-    row.performance_total_responses ?? null,
-    row.read_write_ratio ?? null,
-    row.input_clarity_score ?? null,
-    row.read_operations ?? null,
-    row.write_operations ?? null,
-    row.total_user_messages ?? null,
-    row.error_count ?? null,
-    row.error_types ?? null,
-    row.last_error_message ?? null,
-    row.recovery_attempts ?? null,
-    row.fatal_errors ?? null,
-    row.interruption_rate ?? null,
-    row.session_length_minutes ?? null,
-    row.total_interruptions ?? null,
-    row.engagement_total_responses ?? null,
-    row.task_success_rate ?? null,
-    row.iteration_count ?? null,
-    row.process_quality_score ?? null,
-    row.used_plan_mode ?? null,
-    row.used_todo_tracking ?? null,
-    row.over_top_affirmations ?? null,
-    row.successful_operations ?? null,
-    row.total_operations ?? null,
-    row.exit_plan_mode_count ?? null,
-    row.todo_write_count ?? null,
-    row.over_top_affirmations_phrases ?? null,
-    row.usage_improvement_tips ?? null,
-    row.error_improvement_tips ?? null,
-    row.engagement_improvement_tips ?? null,
-    row.quality_improvement_tips ?? null,
-    row.performance_improvement_tips ?? null,
-    row.improvement_tips ?? null,
-    row.custom_metrics ?? null,
-    row.git_total_files_changed ?? null,
-    row.git_lines_added ?? null,
-    row.git_lines_removed ?? null,
-    row.git_lines_modified ?? null,
-    row.git_net_lines_changed ?? null,
-    row.git_lines_read_per_line_changed ?? null,
-    row.git_reads_per_file_changed ?? null,
-    row.git_lines_changed_per_minute ?? null,
-    row.git_lines_changed_per_tool_use ?? null,
-    row.total_lines_read ?? null,
-    row.git_diff_improvement_tips ?? null,
-    row.total_input_tokens ?? null,
-    row.total_output_tokens ?? null,
-    row.total_cache_created ?? null,
-    row.total_cache_read ?? null,
-    row.context_length ?? null,
-    row.context_window_size ?? null,
-    row.context_utilization_percent ?? null,
-    row.compact_event_count ?? null,
-    row.compact_event_steps ?? null,
-    row.avg_tokens_per_message ?? null,
-    row.messages_until_first_compact ?? null,
-    row.context_improvement_tips ?? null,
-    row.created_at,
-  ]
-
-  // Debug logging
-  const columnMatches = sql.match(/INSERT OR REPLACE INTO session_metrics \(([\s\S]*?)\) VALUES/)
-  if (columnMatches) {
-    const columns = columnMatches[1]
-      .split(',')
-      .map(s => s.trim())
-      .filter(Boolean)
-    console.log('[DEBUG] Column count:', columns.length)
-    console.log('[DEBUG] Param count:', params.length)
-    if (columns.length !== params.length) {
-      console.error('[ERROR] Column/param mismatch!')
-      console.error('[ERROR] Columns:', columns)
-    }
-  }
-
-  // Use Tauri SQL plugin to insert or replace metrics (upsert based on session_id unique constraint)
-  await invoke('execute_sql', {
-    sql,
-    params,
-  })
-
-  // Update the core_metrics_status in agent_sessions table and reset sync flag to trigger upload
-  await invoke('execute_sql', {
-    sql: `
-      UPDATE agent_sessions
-      SET core_metrics_status = 'completed',
-          core_metrics_processed_at = ?,
-          synced_to_server = 0
-      WHERE session_id = ?
-    `,
-    params: [Date.now(), sessionId],
-  })
+  // The backend also resets the sync flag so the new tips are uploaded;
+  // core_metrics_status is owned by the backend engine
+  await invoke('store_processor_metrics', { sessionId, provider, metrics: row })
 }
@@ -1,6 +1,6 @@
 import { useMutation, useQuery, useQueryClient } from '@tanstack/react-query'
-import { invoke } from '@tauri-apps/api/core'
 import { useCallback, useState } from 'react'
+import { errorCode, invoke } from '../services/commands'
 
 export interface SessionInfo {
   provider: string
@@ -47,6 +47,8 @@ export function useSessionSync(providerId: string) {
       setError(null)
     },
     onError: err => {
+      // A cancelled scan isn't a failure
+      if (errorCode(err) === 'cancelled') return
       setError(err instanceof Error ? err.message : 'Failed to scan sessions')
     },
   })
@@ -63,6 +65,11 @@ export function useSessionSync(providerId: string) {
       queryClient.invalidateQueries({ queryKey: ['upload-queue', 'status'] })
     },
     onError: err => {
+      if (errorCode(err) === 'cancelled') {
+        // Sessions queued before the cancel still upload
+        refetchProgress()
+        return
+      }
       setError(err instanceof Error ? err.message : 'Failed to sync sessions')
     },
   })
@@ -79,6 +86,14 @@ export function useSessionSync(providerId: string) {
     },
   })
 
+  // Stop a running scan or sync
+  const cancelMutation = useMutation({
+    mutationFn: () => invoke<boolean>('cancel_historical_operation', { providerId }),
+    onError: err => {
+      setError(err instanceof Error ? err.message : 'Failed to cancel')
+    },
+  })
+
   const scanSessions = useCallback(async () => {
     setError(null)
     try {
@@ -97,6 +112,14 @@ export function useSessionSync(providerId: string) {
     }
   }, [syncMutation])
 
+  const cancelOperation = useCallback(async () => {
+    try {
+      await cancelMutation.mutateAsync()
+    } catch (_err) {
+      // Error handling is done in mutation callbacks
+    }
+  }, [cancelMutation])
+
   const resetProgress = useCallback(async () => {
     setError(null)
     try {
@@ -115,10 +138,12 @@ export function useSessionSync(providerId: string) {
     isScanning: scanMutation.isPending || (progress?.is_scanning ?? false),
     isSyncing: syncMutation.isPending || (progress?.is_syncing ?? false),
     isResetting: resetMutation.isPending,
+    isCancelling: cancelMutation.isPending,
 
     // Actions
     scanSessions,
     syncSessions,
+    cancelOperation,
     resetProgress,
 
     // Utilities
@@ -1,5 +1,5 @@
 import { useQuery } from '@tanstack/react-query'
-import { invoke } from '@tauri-apps/api/core'
+import { invoke } from '../services/commands'
 
 /**
  * Hook to load provider setup instructions from markdown files
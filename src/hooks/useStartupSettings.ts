@@ -0,0 +1,29 @@
+import { useMutation, useQuery, useQueryClient } from '@tanstack/react-query'
+import { invoke } from '../services/commands'
+
+export interface StartupSettings {
+  launchAtLogin: boolean
+  startMinimized: boolean
+}
+
+/**
+ * Whether the app launches at login and whether those launches start hidden
+ * in the tray. Saving also updates the OS login item.
+ */
+export function useStartupSettings() {
+  return useQuery({
+    queryKey: ['startup-settings'],
+    queryFn: () => invoke<StartupSettings>('get_startup_settings'),
+  })
+}
+
+export function useSaveStartupSettings() {
+  const queryClient = useQueryClient()
+
+  return useMutation({
+    mutationFn: (settings: StartupSettings) => invoke('set_startup_settings', { settings }),
+    onSuccess: (_, settings) => {
+      queryClient.setQueryData(['startup-settings'], settings)
+    },
+  })
+}
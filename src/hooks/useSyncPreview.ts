@@ -0,0 +1,35 @@
+import { useMutation } from '@tanstack/react-query'
+import { invoke } from '../services/commands'
+
+export interface SessionPreview {
+  sessionId: string
+  projectName: string
+  fileSize: number
+  messageCount: number
+  uploadBytes: number
+  alreadySynced: boolean
+  skippedLines: number
+  validationErrorCount: number
+  validationErrors: string[]
+}
+
+export interface SyncPreview {
+  provider: string
+  syncMode: string
+  sessionCount: number
+  sessionsToUpload: number
+  totalBytes: number
+  estimatedUploadBytes: number
+  sessionsWithErrors: number
+  sessions: SessionPreview[]
+}
+
+/**
+ * Scan, convert and validate a provider's sessions without storing or
+ * uploading them, to check what a sync would do
+ */
+export function useSyncPreview() {
+  return useMutation({
+    mutationFn: (providerId: string) => invoke<SyncPreview>('preview_sync', { providerId }),
+  })
+}
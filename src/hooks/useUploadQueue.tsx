@@ -1,5 +1,5 @@
 import { useMutation, useQuery, useQueryClient } from '@tanstack/react-query'
-import { invoke } from '@tauri-apps/api/core'
+import { invoke } from '../services/commands'
 
 export interface UploadItem {
   id: string
@@ -15,6 +15,10 @@ export interface UploadItem {
   file_size: number
   session_id?: string
   content?: string
+  cwd?: string | null
+  priority: number
+  last_attempt_at?: string | null
+  superseded?: boolean
 }
 
 export interface QueueItems {
@@ -27,6 +31,9 @@ export interface UploadStatus {
   processing: number
   failed: number
   recent_uploads: UploadItem[]
+  rate_limited_until?: string | null
+  concurrency: number
+  max_concurrency: number
 }
 
 export function useUploadQueueItems() {
@@ -75,6 +82,45 @@ export function useRemoveQueueItem() {
   })
 }
 
+export function useSetQueueItemPriority() {
+  const queryClient = useQueryClient()
+
+  return useMutation({
+    mutationFn: async ({ itemId, priority }: { itemId: string; priority: number }) => {
+      return await invoke('set_queue_item_priority', { itemId, priority })
+    },
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: ['upload-queue'] })
+    },
+  })
+}
+
+export function useSetQueueItemProject() {
+  const queryClient = useQueryClient()
+
+  return useMutation({
+    mutationFn: async ({ itemId, projectName }: { itemId: string; projectName: string }) => {
+      return await invoke<string>('set_queue_item_project', { itemId, projectName })
+    },
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: ['upload-queue'] })
+    },
+  })
+}
+
+export function useSetQueueItemCwd() {
+  const queryClient = useQueryClient()
+
+  return useMutation({
+    mutationFn: async ({ itemId, cwd }: { itemId: string; cwd: string | null }) => {
+      return await invoke('set_queue_item_cwd', { itemId, cwd })
+    },
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: ['upload-queue'] })
+    },
+  })
+}
+
 export function useRetryAllFailed() {
   const queryClient = useQueryClient()
 
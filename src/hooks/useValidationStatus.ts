@@ -1,6 +1,6 @@
 import { type JSONLValidationResult, validateJSONL } from '@guidemode/session-processing/validation'
-import { invoke } from '@tauri-apps/api/core'
 import { useCallback, useEffect, useState } from 'react'
+import { invoke } from '../services/commands'
 
 export type ValidationStatus = 'valid' | 'errors' | 'warnings' | 'unknown' | 'loading'
 
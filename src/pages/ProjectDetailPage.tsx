@@ -1,6 +1,5 @@
 import { ArrowLeftIcon, ClockIcon, DocumentTextIcon } from '@heroicons/react/24/outline'
-import { useQuery } from '@tanstack/react-query'
-import { invoke } from '@tauri-apps/api/core'
+import { useMutation, useQuery, useQueryClient } from '@tanstack/react-query'
 import { open } from '@tauri-apps/plugin-shell'
 import { useState } from 'react'
 import { useNavigate, useParams } from 'react-router-dom'
@@ -12,6 +11,7 @@ import { SessionContextTab } from '../components/SessionContextTab'
 import ProviderIcon from '../components/icons/ProviderIcon'
 import { useClaudeFiles } from '../hooks/useClaudeFiles'
 import type { LocalProject } from '../hooks/useLocalProjects'
+import { invoke } from '../services/commands'
 
 type TabType = 'sessions' | 'context' | 'changes' | 'claude'
 
@@ -39,11 +39,23 @@ export default function ProjectDetailPage() {
         createdAt: result.createdAt,
         updatedAt: result.updatedAt,
         sessionCount: result.sessionCount || 0,
+        syncExcluded: !!result.syncExcluded,
       }
     },
     enabled: !!projectId,
   })
 
+  const queryClient = useQueryClient()
+  const syncToggle = useMutation({
+    mutationFn: async (excluded: boolean) => {
+      return await invoke<number>('set_project_sync_excluded', { projectId, excluded })
+    },
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: ['project', projectId] })
+      queryClient.invalidateQueries({ queryKey: ['upload-queue'] })
+    },
+  })
+
   // Check if project has .claude folder
   const { data: claudeFiles = [] } = useClaudeFiles(project?.cwd, !!project)
   const hasClaude = claudeFiles.length > 0
@@ -110,6 +122,8 @@ export default function ProjectDetailPage() {
         project={project}
         onOpenFolder={handleOpenFolder}
         onViewGithub={project.githubRepo ? handleViewGithub : undefined}
+        onToggleSync={() => syncToggle.mutate(!project.syncExcluded)}
+        syncTogglePending={syncToggle.isPending}
       />
 
       {/* Tabs */}
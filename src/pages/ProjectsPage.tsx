@@ -5,16 +5,26 @@ import { useLocalProjects } from '../hooks/useLocalProjects'
 const PROJECT_TYPE_LABELS: Record<string, string> = {
   nodejs: 'Node.js',
   rust: 'Rust',
+  'rust-workspace': 'Rust Workspace',
   python: 'Python',
   go: 'Go',
+  jvm: 'JVM',
+  terraform: 'Terraform',
+  flutter: 'Flutter',
+  dart: 'Dart',
   generic: 'Generic',
 }
 
 const PROJECT_TYPE_COLORS: Record<string, string> = {
   nodejs: 'badge-success',
   rust: 'badge-error',
+  'rust-workspace': 'badge-error',
   python: 'badge-info',
   go: 'badge-primary',
+  jvm: 'badge-warning',
+  terraform: 'badge-secondary',
+  flutter: 'badge-accent',
+  dart: 'badge-accent',
   generic: 'badge-neutral',
 }
 
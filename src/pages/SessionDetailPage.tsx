@@ -14,6 +14,7 @@ import type { SessionRating } from '@guidemode/session-processing/ui'
 import {
   ArrowDownIcon,
   ArrowUpIcon,
+  ArrowUturnLeftIcon,
   BugAntIcon,
   ChartBarIcon,
   ChatBubbleLeftRightIcon,
@@ -24,15 +25,15 @@ import {
   DocumentTextIcon,
 } from '@heroicons/react/24/outline'
 import { useQuery, useQueryClient } from '@tanstack/react-query'
-import { invoke } from '@tauri-apps/api/core'
 import { listen } from '@tauri-apps/api/event'
 import { useCallback, useEffect, useMemo, useState } from 'react'
-import { useNavigate, useParams } from 'react-router-dom'
+import { useMatch, useNavigate, useParams } from 'react-router-dom'
 import { JsonBlock } from '../components/JsonBlock'
 import { SessionChangesTab } from '../components/SessionChangesTab'
 import { SessionContextTab } from '../components/SessionContextTab'
 import { ValidationReport } from '../components/ValidationReport'
 import ProviderIcon from '../components/icons/ProviderIcon'
+import { getDatabase } from '../db/client'
 import { useAiProcessing } from '../hooks/useAiProcessing'
 import { useAiProcessingProgress } from '../hooks/useAiProcessingProgress'
 import { useAuth } from '../hooks/useAuth'
@@ -43,6 +44,7 @@ import { useSessionActivity } from '../hooks/useSessionActivity'
 import { useSessionProcessing } from '../hooks/useSessionProcessing'
 import { useToast } from '../hooks/useToast'
 import { useValidationStatus } from '../hooks/useValidationStatus'
+import { invoke } from '../services/commands'
 import { useSessionActivityStore } from '../stores/sessionActivityStore'
 import {
   type AgentSessionRow,
@@ -66,6 +68,7 @@ interface AgentSessionWithRating extends AgentSessionRow {
   ai_model_summary: string | null
   ai_model_quality_score: number | null
   ai_model_metadata: string | null
+  resumed_from: string | null
 }
 
 interface ProjectRowExtended extends ProjectRow {
@@ -108,17 +111,18 @@ interface DesktopSession {
   aiModelMetadata?: string | null
   errorMessage?: string | null
   coreMetricsStatus?: string | null
+  resumedFrom?: string | null
 }
 
 // Fetch function for session metadata
 async function fetchSessionMetadata(sessionId: string): Promise<DesktopSession | null> {
-  const result = await invoke<AgentSessionWithRating[]>('execute_sql', {
-    sql: `SELECT s.*, a.rating as assessment_rating
+  const result = await getDatabase().select<AgentSessionWithRating[]>(
+    `SELECT s.*, a.rating as assessment_rating
           FROM agent_sessions s
           LEFT JOIN session_assessments a ON s.session_id = a.session_id
           WHERE s.session_id = ? LIMIT 1`,
-    params: [sessionId],
-  })
+    [sessionId]
+  )
 
   if (result.length === 0) {
     throw new Error('Session not found')
@@ -154,17 +158,18 @@ async function fetchSessionMetadata(sessionId: string): Promise<DesktopSession |
     aiModelSummary: row.ai_model_summary,
     aiModelQualityScore: row.ai_model_quality_score,
     aiModelMetadata: row.ai_model_metadata,
+    resumedFrom: row.resumed_from,
   }
 }
 
 // Fetch project for session
 async function fetchSessionProject(sessionId: string): Promise<LocalProject | null> {
-  const result = await invoke<ProjectRowExtended[]>('execute_sql', {
-    sql: `SELECT p.* FROM projects p
+  const result = await getDatabase().select<ProjectRowExtended[]>(
+    `SELECT p.* FROM projects p
           JOIN agent_sessions s ON p.id = s.project_id
           WHERE s.session_id = ? LIMIT 1`,
-    params: [sessionId],
-  })
+    [sessionId]
+  )
 
   if (result.length === 0) {
     return null
@@ -186,6 +191,7 @@ async function fetchSessionProject(sessionId: string): Promise<LocalProject | nu
 export default function SessionDetailPage() {
   const { sessionId } = useParams<{ sessionId: string }>()
   const navigate = useNavigate()
+  const isSessionWindow = useMatch('/session-window/:sessionId') !== null
   const queryClient = useQueryClient()
   const { user } = useAuth()
   const [reverseOrder, setReverseOrder] = useState(() => {
@@ -356,6 +362,15 @@ export default function SessionDetailPage() {
     enabled: !!session?.cwd && activeTab !== 'context',
   })
 
+  const handleOpenInWindow = async () => {
+    if (!sessionId) return
+    try {
+      await invoke('open_session_window', { sessionId })
+    } catch (err) {
+      toast.error(`Failed to open session window: ${err}`)
+    }
+  }
+
   // Handle sync session click
   const handleSyncSession = async () => {
     if (!user) {
@@ -367,10 +382,7 @@ export default function SessionDetailPage() {
 
     try {
       // Clear the sync_failed_reason to allow retry
-      await invoke('execute_sql', {
-        sql: 'UPDATE agent_sessions SET sync_failed_reason = NULL WHERE session_id = ?',
-        params: [sessionId],
-      })
+      await invoke('clear_session_sync_failure', { sessionId })
 
       toast.success('Session queued for upload. Check the Upload Queue page for status.')
     } catch (err) {
@@ -515,10 +527,10 @@ export default function SessionDetailPage() {
       }
 
       // Reload session to show AI results
-      await invoke<any[]>('execute_sql', {
-        sql: 'SELECT * FROM agent_sessions WHERE session_id = ? LIMIT 1',
-        params: [sessionId],
-      })
+      await getDatabase().select<any[]>(
+        'SELECT * FROM agent_sessions WHERE session_id = ? LIMIT 1',
+        [sessionId]
+      )
 
       // Invalidate all relevant queries to refresh the UI
       await queryClient.invalidateQueries({ queryKey: ['session-metadata', sessionId] })
@@ -718,17 +730,36 @@ export default function SessionDetailPage() {
               </span>
             )}
         </div>
-        <button onClick={() => navigate('/sessions')} className="btn btn-sm btn-ghost">
-          <svg className="w-5 h-5" fill="none" stroke="currentColor" viewBox="0 0 24 24">
-            <path
-              strokeLinecap="round"
-              strokeLinejoin="round"
-              strokeWidth={2}
-              d="M15 19l-7-7 7-7"
-            />
-          </svg>
-          Back to Sessions
-        </button>
+        {!isSessionWindow && (
+          <div className="flex items-center gap-2">
+            <button
+              onClick={handleOpenInWindow}
+              className="btn btn-sm btn-ghost"
+              title="Open this session in its own window"
+            >
+              <svg className="w-5 h-5" fill="none" stroke="currentColor" viewBox="0 0 24 24">
+                <path
+                  strokeLinecap="round"
+                  strokeLinejoin="round"
+                  strokeWidth={2}
+                  d="M10 6H6a2 2 0 00-2 2v10a2 2 0 002 2h10a2 2 0 002-2v-4M14 4h6m0 0v6m0-6L10 14"
+                />
+              </svg>
+              New Window
+            </button>
+            <button onClick={() => navigate('/sessions')} className="btn btn-sm btn-ghost">
+              <svg className="w-5 h-5" fill="none" stroke="currentColor" viewBox="0 0 24 24">
+                <path
+                  strokeLinecap="round"
+                  strokeLinejoin="round"
+                  strokeWidth={2}
+                  d="M15 19l-7-7 7-7"
+                />
+              </svg>
+              Back to Sessions
+            </button>
+          </div>
+        )}
       </div>
 
       {/* Session Detail Header */}
@@ -783,6 +814,20 @@ export default function SessionDetailPage() {
         />
       )}
 
+      {session?.resumedFrom && (
+        <div className="alert alert-info py-2">
+          <ArrowUturnLeftIcon className="w-4 h-4" />
+          <span className="text-sm">This session resumes an earlier conversation.</span>
+          <button
+            type="button"
+            className="btn btn-ghost btn-xs"
+            onClick={() => navigate(`/sessions/${session.resumedFrom}`)}
+          >
+            View earlier session
+          </button>
+        </div>
+      )}
+
       {/* Tabs Navigation with Controls */}
       <div className="card bg-base-200 border border-base-300 border-b-2 rounded-lg">
         <div className="flex items-stretch">
@@ -1,7 +1,6 @@
 import { DateFilter, SessionCard } from '@guidemode/session-processing/ui'
 import type { SessionRating } from '@guidemode/session-processing/ui'
 import type { DateFilterValue } from '@guidemode/session-processing/ui'
-import { invoke } from '@tauri-apps/api/core'
 import { listen } from '@tauri-apps/api/event'
 import { useCallback, useEffect, useRef, useState } from 'react'
 import { flushSync } from 'react-dom'
@@ -10,6 +9,7 @@ import { ActiveSessionCard } from '../components/ActiveSessionCard'
 import ConfirmDialog from '../components/ConfirmDialog'
 import ProcessingModeDialog from '../components/ProcessingModeDialog'
 import ProviderIcon from '../components/icons/ProviderIcon'
+import { getDatabase } from '../db/client'
 import { useAiProcessing } from '../hooks/useAiProcessing'
 import { useAuth } from '../hooks/useAuth'
 import { useLocalProjects } from '../hooks/useLocalProjects'
@@ -18,6 +18,7 @@ import { useQuickRating } from '../hooks/useQuickRating'
 import { useSessionActivity } from '../hooks/useSessionActivity'
 import { useSessionProcessing } from '../hooks/useSessionProcessing'
 import { useToast } from '../hooks/useToast'
+import { invoke } from '../services/commands'
 import { useSessionActivityStore } from '../stores/sessionActivityStore'
 
 const SESSIONS_PER_PAGE = 50
@@ -172,6 +173,7 @@ export default function SessionsPage() {
     let unlistenSynced: (() => void) | undefined
     let unlistenFailed: (() => void) | undefined
     let unlistenUpdated: (() => void) | undefined
+    let unlistenRescan: (() => void) | undefined
 
     listen('session-synced', () => {
       invalidateSessions()
@@ -191,10 +193,18 @@ export default function SessionsPage() {
       unlistenUpdated = fn
     })
 
+    // Historical scans save sessions in batches without per-session events
+    listen('rescan-progress', () => {
+      invalidateSessions()
+    }).then(fn => {
+      unlistenRescan = fn
+    })
+
     return () => {
       unlistenSynced?.()
       unlistenFailed?.()
       unlistenUpdated?.()
+      unlistenRescan?.()
     }
   }, [invalidateSessions])
 
@@ -493,10 +503,10 @@ export default function SessionsPage() {
     // Trigger manual upload for this session
     try {
       // Get session details to trigger upload
-      const sessionResult: any[] = await invoke('execute_sql', {
-        sql: 'SELECT provider FROM agent_sessions WHERE session_id = ?',
-        params: [sessionId],
-      })
+      const sessionResult = await getDatabase().select<any[]>(
+        'SELECT provider FROM agent_sessions WHERE session_id = ?',
+        [sessionId]
+      )
 
       if (sessionResult.length === 0) {
         toast.error('Session not found')
@@ -515,10 +525,7 @@ export default function SessionsPage() {
       }
 
       // Clear the sync_failed_reason to allow retry
-      await invoke('execute_sql', {
-        sql: 'UPDATE agent_sessions SET sync_failed_reason = NULL WHERE session_id = ?',
-        params: [sessionId],
-      })
+      await invoke('clear_session_sync_failure', { sessionId })
 
       // The upload queue will pick it up automatically on next poll
       toast.success('Session queued for upload. Check the Upload Queue page for status.')
@@ -806,6 +813,7 @@ export default function SessionsPage() {
                       assessmentRating: session.assessmentRating,
                       syncedToServer: session.syncedToServer,
                       syncFailedReason: session.syncFailedReason,
+                      conversionSkippedLines: session.conversionSkippedLines,
                     }}
                     isActive={isActive}
                     isProcessing={
@@ -7,9 +7,13 @@ import {
 import { fetch as tauriFetch } from '@tauri-apps/plugin-http'
 import { useCallback, useEffect, useState } from 'react'
 import { useLocation, useNavigate } from 'react-router-dom'
+import { ConversionErrors } from '../components/ConversionErrors'
 import Login from '../components/Login'
+import { StorageSettings } from '../components/StorageSettings'
 import { useAuth } from '../hooks/useAuth'
 import { useOnboarding } from '../hooks/useOnboarding'
+import { useServerHealth } from '../hooks/useServerHealth'
+import { useSaveStartupSettings, useStartupSettings } from '../hooks/useStartupSettings'
 import { useUpdater } from '../hooks/useUpdater'
 import { useConfigStore } from '../stores/configStore'
 
@@ -17,6 +21,7 @@ function SettingsPage() {
   const navigate = useNavigate()
   const location = useLocation()
   const { user, logout } = useAuth()
+  const { data: serverHealth } = useServerHealth(!!user)
   const {
     aiApiKeys,
     setAiApiKey,
@@ -28,6 +33,8 @@ function SettingsPage() {
     getAiModel,
   } = useConfigStore()
   const { resetTour, hasCompletedTour } = useOnboarding()
+  const { data: startupSettings } = useStartupSettings()
+  const saveStartupSettings = useSaveStartupSettings()
   const {
     hasUpdate,
     currentVersion,
@@ -254,10 +261,34 @@ function SettingsPage() {
                   <div className="flex justify-between items-center">
                     <span className="text-base-content/70">Connected Server</span>
                     <div className="flex items-center gap-2">
-                      <div className="w-2 h-2 bg-success rounded-full" />
+                      <div
+                        className={`w-2 h-2 rounded-full ${
+                          !serverHealth || (serverHealth.reachable && serverHealth.schemaCompatible)
+                            ? 'bg-success'
+                            : serverHealth.reachable
+                              ? 'bg-warning'
+                              : 'bg-error'
+                        }`}
+                      />
                       <span className="text-sm font-mono">{user.serverUrl}</span>
                     </div>
                   </div>
+                  {serverHealth?.reachable && (
+                    <div className="flex justify-between">
+                      <span className="text-base-content/70">Server Version</span>
+                      <span className="text-sm">
+                        {serverHealth.version ?? 'Unknown'}
+                        {serverHealth.latencyMs !== null && (
+                          <span className="text-base-content/50"> · {serverHealth.latencyMs} ms</span>
+                        )}
+                      </span>
+                    </div>
+                  )}
+                  {serverHealth?.message && (
+                    <div role="alert" className="alert alert-warning text-sm">
+                      <span>{serverHealth.message}</span>
+                    </div>
+                  )}
                   {user.tenantName && (
                     <div className="flex justify-between">
                       <span className="text-base-content/70">Organization</span>
@@ -727,6 +758,72 @@ function SettingsPage() {
           </div>
         </div>
 
+        {/* Startup Section */}
+        <div className="card bg-base-100 shadow-sm border border-base-300">
+          <div className="card-body">
+            <h2 className="card-title">Startup</h2>
+            <p className="text-sm text-base-content/70 mb-4">
+              Launch GuideMode when you log in so sessions are captured without opening the app.
+            </p>
+
+            <div className="space-y-4">
+              <label className="flex items-center justify-between cursor-pointer">
+                <div>
+                  <div className="font-medium">Launch at login</div>
+                  <div className="text-sm text-base-content/60">
+                    Start GuideMode when you log in to this computer
+                  </div>
+                </div>
+                <input
+                  type="checkbox"
+                  className="toggle toggle-primary"
+                  checked={startupSettings?.launchAtLogin ?? false}
+                  disabled={!startupSettings || saveStartupSettings.isPending}
+                  onChange={e =>
+                    startupSettings &&
+                    saveStartupSettings.mutate({
+                      ...startupSettings,
+                      launchAtLogin: e.target.checked,
+                    })
+                  }
+                />
+              </label>
+
+              <label className="flex items-center justify-between cursor-pointer">
+                <div>
+                  <div className="font-medium">Start minimized to tray</div>
+                  <div className="text-sm text-base-content/60">
+                    Keep the window hidden when launched at login; open it from the tray icon
+                  </div>
+                </div>
+                <input
+                  type="checkbox"
+                  className="toggle toggle-primary"
+                  checked={startupSettings?.startMinimized ?? false}
+                  disabled={!startupSettings?.launchAtLogin || saveStartupSettings.isPending}
+                  onChange={e =>
+                    startupSettings &&
+                    saveStartupSettings.mutate({
+                      ...startupSettings,
+                      startMinimized: e.target.checked,
+                    })
+                  }
+                />
+              </label>
+
+              {saveStartupSettings.error && (
+                <div className="alert alert-error text-sm">
+                  {String(saveStartupSettings.error)}
+                </div>
+              )}
+            </div>
+          </div>
+        </div>
+
+        <StorageSettings />
+
+        <ConversionErrors />
+
         {/* Help & Tour Section */}
         <div className="card bg-base-100 shadow-sm border border-base-300">
           <div className="card-body">
@@ -769,39 +866,6 @@ function SettingsPage() {
             </p>
 
             <div className="space-y-6">
-              {/* Core Metrics Debounce */}
-              <div className="form-control">
-                <label className="label">
-                  <span className="label-text font-medium">Core Metrics Debounce</span>
-                  <span className="label-text-alt text-xs">
-                    {systemConfig.coreMetricsDebounceSeconds}s
-                  </span>
-                </label>
-                <input
-                  type="range"
-                  min="5"
-                  max="60"
-                  value={systemConfig.coreMetricsDebounceSeconds}
-                  onChange={e =>
-                    updateSystemConfig({
-                      coreMetricsDebounceSeconds: Number.parseInt(e.target.value),
-                    })
-                  }
-                  className="range range-primary"
-                  step="5"
-                />
-                <div className="w-full flex justify-between text-xs px-2 text-base-content/50">
-                  <span>5s</span>
-                  <span>30s</span>
-                  <span>60s</span>
-                </div>
-                <label className="label">
-                  <span className="label-text-alt">
-                    Wait time after file activity stops before processing core metrics
-                  </span>
-                </label>
-              </div>
-
               {/* AI Processing Delay */}
               <div className="form-control">
                 <label className="label">
@@ -77,6 +77,14 @@ function UploadQueuePage() {
                   {displayId}
                 </span>
                 <span className="badge badge-ghost badge-sm shrink-0">{item.provider}</span>
+                {item.superseded && (
+                  <span
+                    className="badge badge-outline badge-sm shrink-0"
+                    title="The session changed after it synced; this uploads the new version"
+                  >
+                    Re-upload
+                  </span>
+                )}
                 <span className="text-xs text-base-content/60 shrink-0">
                   {formatFileSize(item.file_size)}
                 </span>
@@ -230,6 +238,11 @@ function UploadQueuePage() {
           <div className="card-body p-4">
             <div className="text-xs text-base-content/70 uppercase">Processing</div>
             <div className="text-2xl font-bold">{status?.processing || 0}</div>
+            {status && status.concurrency < status.max_concurrency && (
+              <div className="text-xs text-warning">
+                Limited to {status.concurrency} of {status.max_concurrency} at once
+              </div>
+            )}
           </div>
         </div>
         <div className="card bg-base-100 shadow-sm border border-base-300">
@@ -240,6 +253,13 @@ function UploadQueuePage() {
         </div>
       </div>
 
+      {status?.rate_limited_until && (
+        <div className="alert alert-warning text-sm">
+          Rate limited until {new Date(status.rate_limited_until).toLocaleTimeString()}. Uploads
+          resume automatically.
+        </div>
+      )}
+
       {/* Queue Items Table */}
       <div className="card bg-base-100 shadow-sm border border-base-300">
         <div className="card-body">
@@ -7,6 +7,7 @@ import userEvent from '@testing-library/user-event'
 import { beforeAll, beforeEach, describe, expect, it, vi } from 'vitest'
 
 const invokeMock = vi.fn()
+const selectMock = vi.fn()
 const listenMock = vi.fn()
 const openMock = vi.fn()
 const toast = {
@@ -42,6 +43,10 @@ vi.mock('@tauri-apps/api/core', () => ({
   invoke: (...args: unknown[]) => invokeMock(...args),
 }))
 
+vi.mock('../../db/client', () => ({
+  getDatabase: () => ({ select: (...args: unknown[]) => selectMock(...args) }),
+}))
+
 vi.mock('@tauri-apps/api/event', () => ({
   listen: (...args: unknown[]) => listenMock(...args),
 }))
@@ -184,6 +189,7 @@ function renderPage(route = '/sessions/session-1', ui?: ReactNode) {
 describe('SessionDetailPage integration', () => {
   beforeEach(() => {
     invokeMock.mockReset()
+    selectMock.mockReset()
     listenMock.mockResolvedValue(() => {})
     openMock.mockResolvedValue(undefined)
     toast.success.mockReset()
@@ -194,24 +200,15 @@ describe('SessionDetailPage integration', () => {
 
 
   function setupInvokeForMetadata(config: { apiKey?: string; username?: string } = { apiKey: 'abc', username: 'tester' }) {
-    invokeMock.mockImplementation(async (command: string, args: any) => {
+    invokeMock.mockImplementation(async (command: string) => {
       if (command === 'load_config_command') {
         return config
       }
       if (command === 'get_all_projects') {
         return []
       }
-      if (command === 'execute_sql' && args.sql.includes('FROM agent_sessions')) {
-        return [sessionRow]
-      }
-      if (command === 'execute_sql' && args.sql.includes('FROM projects')) {
-        return []
-      }
-      if (command === 'execute_sql' && args.sql.includes('UPDATE agent_sessions')) {
-        return []
-      }
-      if (command === 'execute_sql' && args.sql.includes('FROM session_metrics')) {
-        return []
+      if (command === 'clear_session_sync_failure') {
+        return undefined
       }
       if (command === 'get_session_content') {
         return '{"timestamp":"2025-01-01T10:00:00Z","type":"message","message":{"role":"user","content":[{"type":"text","text":"Hello"}]}}'
@@ -219,11 +216,11 @@ describe('SessionDetailPage integration', () => {
       if (command === 'quick_rate_session') {
         return {}
       }
-      if (command === 'execute_sql') {
-        return []
-      }
       throw new Error(`Unexpected command ${command}`)
     })
+    selectMock.mockImplementation(async (sql: string) =>
+      sql.includes('FROM agent_sessions') ? [sessionRow] : []
+    )
   }
 
   it('queues upload when sync button clicked', async () => {
@@ -235,13 +232,9 @@ describe('SessionDetailPage integration', () => {
     await userEvent.click(syncButton)
 
     await waitFor(() =>
-      expect(invokeMock).toHaveBeenCalledWith(
-        'execute_sql',
-        expect.objectContaining({
-          sql: expect.stringContaining('UPDATE agent_sessions SET sync_failed_reason = NULL'),
-          params: ['session-1'],
-        })
-      )
+      expect(invokeMock).toHaveBeenCalledWith('clear_session_sync_failure', {
+        sessionId: 'session-1',
+      })
     )
     await waitFor(() =>
       expect(toast.success).toHaveBeenCalledWith(
@@ -6,6 +6,7 @@ import userEvent from '@testing-library/user-event'
 import { beforeAll, beforeEach, describe, expect, it, vi } from 'vitest'
 
 const invokeMock = vi.fn()
+const selectMock = vi.fn()
 const listenMock = vi.fn()
 const toast = {
   success: vi.fn(),
@@ -38,6 +39,10 @@ vi.mock('@tauri-apps/api/core', () => ({
   invoke: (...args: unknown[]) => invokeMock(...args),
 }))
 
+vi.mock('../../db/client', () => ({
+  getDatabase: () => ({ select: (...args: unknown[]) => selectMock(...args) }),
+}))
+
 vi.mock('@tauri-apps/api/event', () => ({
   listen: (...args: unknown[]) => listenMock(...args),
 }))
@@ -150,6 +155,7 @@ function renderPage(ui: ReactNode) {
 describe('SessionsPage integration', () => {
   beforeEach(() => {
     invokeMock.mockReset()
+    selectMock.mockReset()
     listenMock.mockResolvedValue(() => {})
     toast.success.mockReset()
     toast.error.mockReset()
@@ -161,47 +167,24 @@ describe('SessionsPage integration', () => {
 
 
   it('queues session upload when provider allows transcripts', async () => {
-    invokeMock.mockImplementation(async (command: string, args: any) => {
+    invokeMock.mockImplementation(async (command: string) => {
       if (command === 'load_config_command') {
         return { apiKey: 'abc', username: 'tester', serverUrl: 'http://server' }
       }
       if (command === 'get_all_projects') {
         return []
       }
-      if (command === 'execute_sql' && args.sql.includes('FROM agent_sessions')) {
-        return [
-          {
-            id: '1',
-            session_id: 'session-1',
-            provider: 'claude-code',
-            file_name: 'session-1.jsonl',
-            file_path: '/tmp/session-1.jsonl',
-            project_name: 'Project A',
-            duration_ms: 1000,
-            session_start_time: Date.now(),
-            session_end_time: Date.now(),
-            created_at: Date.now(),
-            uploaded_at: Date.now(),
-            processing_status: 'pending',
-            assessment_status: 'not_started',
-            synced_to_server: 0,
-          },
-        ]
-      }
-      if (command === 'execute_sql' && args.sql.includes('SELECT provider')) {
-        return [{ provider: 'claude-code' }]
-      }
-      if (command === 'execute_sql' && args.sql.includes('UPDATE agent_sessions')) {
-        return []
+      if (command === 'clear_session_sync_failure') {
+        return undefined
       }
       if (command === 'load_provider_config_command') {
         return { syncMode: 'Transcript and Metrics' }
       }
-      if (command === 'execute_sql') {
-        return []
-      }
       throw new Error(`Unexpected command ${command}`)
     })
+    selectMock.mockImplementation(async (sql: string) =>
+      sql.includes('SELECT provider') ? [{ provider: 'claude-code' }] : []
+    )
 
     renderPage(<SessionsPageComponent />)
 
@@ -212,10 +195,9 @@ describe('SessionsPage integration', () => {
       'load_provider_config_command',
       expect.objectContaining({ providerId: 'claude-code' })
     )
-    expect(invokeMock).toHaveBeenCalledWith(
-      'execute_sql',
-      expect.objectContaining({ sql: expect.stringContaining('UPDATE agent_sessions') })
-    )
+    expect(invokeMock).toHaveBeenCalledWith('clear_session_sync_failure', {
+      sessionId: 'session-1',
+    })
     expect(toast.success).toHaveBeenCalledWith(
       'Session queued for upload. Check the Upload Queue page for status.'
     )
@@ -0,0 +1,106 @@
+import { invoke as tauriInvoke, type InvokeArgs } from '@tauri-apps/api/core'
+
+/**
+ * How a failed command rejects: the serialized `GuideModeError`
+ */
+export interface CommandErrorPayload {
+  code: string
+  message: string
+  retryable: boolean
+  /** HTTP status, when the server answered with an error */
+  status?: number
+}
+
+/**
+ * A rejected Tauri command, with the error code the backend reported
+ */
+export class CommandError extends Error {
+  readonly code: string
+  readonly retryable: boolean
+  readonly status?: number
+
+  constructor(payload: CommandErrorPayload) {
+    super(payload.message)
+    this.name = 'CommandError'
+    this.code = payload.code
+    this.retryable = payload.retryable
+    this.status = payload.status
+  }
+
+  /** Just the message, so `String(error)` and template strings read as before */
+  override toString(): string {
+    return this.message
+  }
+}
+
+function isCommandErrorPayload(value: unknown): value is CommandErrorPayload {
+  return (
+    typeof value === 'object' &&
+    value !== null &&
+    typeof (value as CommandErrorPayload).code === 'string' &&
+    typeof (value as CommandErrorPayload).message === 'string'
+  )
+}
+
+/**
+ * Invoke a Tauri command, rejecting with a `CommandError` when the backend
+ * returns a `GuideModeError`
+ */
+export async function invoke<T>(command: string, args?: InvokeArgs): Promise<T> {
+  try {
+    return await tauriInvoke<T>(command, args)
+  } catch (error) {
+    if (isCommandErrorPayload(error)) {
+      throw new CommandError(error)
+    }
+    throw error
+  }
+}
+
+/**
+ * Error code of a rejected command, if it reported one
+ */
+export function errorCode(error: unknown): string | undefined {
+  return error instanceof CommandError ? error.code : undefined
+}
+
+export type OperationKind =
+  | 'rescan'
+  | 'historical_sync'
+  | 'migration'
+  | 'export'
+  | 'import'
+  | 'archive'
+  | 'maintenance'
+
+export type ProgressPhase =
+  | 'starting'
+  | 'scanning'
+  | 'processing'
+  | 'complete'
+  | 'cancelled'
+  | 'failed'
+
+/**
+ * A `rescan-progress` or `operation-progress` event
+ */
+export interface OperationProgress {
+  operationId: string
+  kind: OperationKind
+  provider?: string
+  phase: ProgressPhase
+  current: number
+  total: number
+  message: string
+  /** How long each project took to scan, once a rescan has scanned */
+  projectTimings?: { project: string; files: number; cached: number; durationMs: number }[]
+  /** Sessions a rescan found unchanged since the last scan */
+  unchanged?: number
+}
+
+/**
+ * Ask a long operation to stop; false if it already finished
+ */
+export function cancelOperation(operationId: string): Promise<boolean> {
+  return invoke<boolean>('cancel_operation', { operationId })
+}
@@ -1,6 +1,6 @@
-import { invoke } from '@tauri-apps/api/core'
 import { create } from 'zustand'
 import { persist } from 'zustand/middleware'
+import { invoke } from '../services/commands'
 import type { ProviderConfig } from '../types/providers'
 
 interface AiApiKeys {
@@ -71,7 +71,7 @@ export const useConfigStore = create<ConfigState>()(
             isLoading: false,
           }))
         } catch (error) {
-          set({ error: error as string, isLoading: false })
+          set({ error: String(error), isLoading: false })
         }
       },
 
@@ -84,7 +84,7 @@ export const useConfigStore = create<ConfigState>()(
             isLoading: false,
           }))
         } catch (error) {
-          set({ error: error as string, isLoading: false })
+          set({ error: String(error), isLoading: false })
         }
       },
 
@@ -98,7 +98,7 @@ export const useConfigStore = create<ConfigState>()(
             return { providerConfigs: newConfigs, isLoading: false }
           })
         } catch (error) {
-          set({ error: error as string, isLoading: false })
+          set({ error: String(error), isLoading: false })
         }
       },
 
@@ -8,6 +8,8 @@ export interface Project {
   lastModified: string
 }
 
+export type MonorepoGranularity = 'repo' | 'package'
+
 export interface ProviderConfig {
   enabled: boolean
   homeDirectory: string
@@ -15,6 +17,32 @@ export interface ProviderConfig {
   selectedProjects: string[]
   lastScanned: string | null
   syncMode: SyncMode
+  projectMappings?: Record<string, string>
+  attachUncommittedPatch?: boolean
+  monorepoGranularity?: Record<string, MonorepoGranularity>
+  /** Overrides of the checks sessions must pass to be queued for upload */
+  enqueueValidation?: EnqueueValidation
+  /** Skip low-signal sessions instead of uploading them */
+  qualityGate?: QualityGate
+}
+
+export interface EnqueueValidation {
+  /** Fields at least one message must have (default: timestamp) */
+  requiredFields?: string[]
+  /** Largest session queued, in bytes */
+  maxSizeBytes?: number
+  /** Fewest messages a session must have (default: 1) */
+  minMessages?: number
+  /** Queue sessions without checking them, for debugging */
+  bypass?: boolean
+}
+
+export interface QualityGate {
+  enabled: boolean
+  /** Fewest user and assistant messages, meta messages aside (default: 2) */
+  minMessages?: number
+  /** Fewest assistant messages (default: 1) */
+  minAssistantMessages?: number
 }
 
 export interface CodingAgent {
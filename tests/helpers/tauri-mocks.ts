@@ -7,8 +7,6 @@ import { vi } from 'vitest'
 export function createMockInvoke() {
 	return vi.fn().mockImplementation((command: string, args?: any) => {
 		switch (command) {
-			case 'execute_sql':
-				return Promise.resolve([])
 			case 'read_session_file':
 				return Promise.resolve('{"type":"user","content":"test"}')
 			case 'load_config_command':
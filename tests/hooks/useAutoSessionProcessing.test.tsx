@@ -4,6 +4,7 @@ import { useAutoSessionProcessing } from '../../src/hooks/useAutoSessionProcessi
 
 const listen = vi.fn()
 const invoke = vi.fn()
+const select = vi.fn()
 const processSession = vi.fn()
 
 vi.mock('@tauri-apps/api/event', () => ({
@@ -14,6 +15,10 @@ vi.mock('@tauri-apps/api/core', () => ({
   invoke: (...args: unknown[]) => invoke(...args),
 }))
 
+vi.mock('../../src/db/client', () => ({
+  getDatabase: () => ({ select: (...args: unknown[]) => select(...args) }),
+}))
+
 vi.mock('../../src/hooks/useSessionProcessing', () => ({
   useSessionProcessing: () => ({
     processSession: (...args: unknown[]) => processSession(...args),
@@ -46,6 +51,7 @@ describe('useAutoSessionProcessing', () => {
     })
 
     invoke.mockReset()
+    select.mockReset()
     processSession.mockReset()
   })
 
@@ -60,15 +66,8 @@ describe('useAutoSessionProcessing', () => {
       session_id: 'session-1',
     }
 
+    select.mockResolvedValue([sessionRow])
     invoke.mockImplementation(async (command, args) => {
-      if (command === 'execute_sql') {
-        expect(args).toEqual({
-          sql: expect.stringContaining('FROM agent_sessions'),
-          params: ['session-1'],
-        })
-        return [sessionRow]
-      }
-
       if (command === 'get_session_content') {
         expect(args).toEqual({
           provider: 'claude-code',
@@ -92,6 +91,9 @@ describe('useAutoSessionProcessing', () => {
       await eventHandler?.({ payload: 'session-1' })
     })
 
+    expect(select).toHaveBeenCalledWith(expect.stringContaining('FROM agent_sessions'), [
+      'session-1',
+    ])
     expect(processSession).toHaveBeenCalledWith('session-1', 'claude-code', 'session-content', 'local')
 
     unmount()
@@ -105,10 +107,8 @@ describe('useAutoSessionProcessing', () => {
       session_id: 'session-2',
     }
 
+    select.mockResolvedValue([sessionRow])
     invoke.mockImplementation(async (command) => {
-      if (command === 'execute_sql') {
-        return [sessionRow]
-      }
       if (command === 'get_session_content') {
         return 'content-2'
       }
@@ -142,10 +142,8 @@ describe('useAutoSessionProcessing', () => {
   it('logs when session is missing and does not invoke processor', async () => {
     const consoleSpy = vi.spyOn(console, 'error').mockImplementation(() => {})
 
+    select.mockResolvedValue([])
     invoke.mockImplementation(async (command) => {
-      if (command === 'execute_sql') {
-        return []
-      }
       throw new Error(`Unexpected command ${command}`)
     })
 
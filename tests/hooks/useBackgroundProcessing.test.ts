@@ -3,12 +3,17 @@ import { afterEach, beforeEach, describe, expect, it, vi } from 'vitest'
 import { useBackgroundProcessing } from '../../src/hooks/useBackgroundProcessing'
 
 const invoke = vi.fn()
+const select = vi.fn()
 const processSession = vi.fn()
 
 vi.mock('@tauri-apps/api/core', () => ({
   invoke: (...args: unknown[]) => invoke(...args),
 }))
 
+vi.mock('../../src/db/client', () => ({
+  getDatabase: () => ({ select: (...args: unknown[]) => select(...args) }),
+}))
+
 vi.mock('../../src/hooks/useSessionProcessing', () => ({
   useSessionProcessing: () => ({
     processSession: (...args: unknown[]) => processSession(...args),
@@ -19,6 +24,7 @@ describe('useBackgroundProcessing', () => {
   beforeEach(() => {
     vi.useFakeTimers()
     invoke.mockReset()
+    select.mockReset()
     processSession.mockReset()
   })
 
@@ -29,26 +35,19 @@ describe('useBackgroundProcessing', () => {
   })
 
   it('processes unprocessed sessions when processNow is invoked', async () => {
+    select.mockResolvedValue([
+      {
+        session_id: 'session-1',
+        provider: 'claude',
+        file_path: '/tmp/a.jsonl',
+      },
+      {
+        session_id: 'session-2',
+        provider: 'copilot',
+        file_path: '/tmp/b.jsonl',
+      },
+    ])
     invoke.mockImplementation(async (command, args) => {
-      if (command === 'execute_sql') {
-        expect(args).toEqual({
-          sql: expect.stringContaining('LEFT JOIN session_metrics'),
-          params: [],
-        })
-        return [
-          {
-            session_id: 'session-1',
-            provider: 'claude',
-            file_path: '/tmp/a.jsonl',
-          },
-          {
-            session_id: 'session-2',
-            provider: 'copilot',
-            file_path: '/tmp/b.jsonl',
-          },
-        ]
-      }
-
       if (command === 'get_session_content') {
         return `content-for-${(args as { sessionId: string }).sessionId}`
       }
@@ -64,6 +63,7 @@ describe('useBackgroundProcessing', () => {
       await result.current.processNow()
     })
 
+    expect(select).toHaveBeenCalledWith(expect.stringContaining('LEFT JOIN session_metrics'), [])
     expect(processSession).toHaveBeenCalledTimes(2)
     expect(processSession).toHaveBeenNthCalledWith(
       1,
@@ -83,7 +83,7 @@ describe('useBackgroundProcessing', () => {
   })
 
   it('toggles enabled state via helpers', () => {
-    invoke.mockResolvedValue([])
+    select.mockResolvedValue([])
 
     const { result } = renderHook(() => useBackgroundProcessing())
 
@@ -101,16 +101,14 @@ describe('useBackgroundProcessing', () => {
   })
 
   it('guards against concurrent processing', async () => {
+    select.mockResolvedValue([
+      {
+        session_id: 'session-3',
+        provider: 'claude',
+        file_path: '/tmp/c.jsonl',
+      },
+    ])
     invoke.mockImplementation(async (command) => {
-      if (command === 'execute_sql') {
-        return [
-          {
-            session_id: 'session-3',
-            provider: 'claude',
-            file_path: '/tmp/c.jsonl',
-          },
-        ]
-      }
       if (command === 'get_session_content') {
         return 'content'
       }
@@ -4,10 +4,10 @@ import { QueryClient, QueryClientProvider } from '@tanstack/react-query'
 import { beforeEach, describe, expect, it, vi } from 'vitest'
 import { useLocalSessionMetrics } from '../../src/hooks/useLocalSessionMetrics'
 
-const invoke = vi.fn()
+const select = vi.fn()
 
-vi.mock('@tauri-apps/api/core', () => ({
-  invoke: (...args: unknown[]) => invoke(...args),
+vi.mock('../../src/db/client', () => ({
+  getDatabase: () => ({ select: (...args: unknown[]) => select(...args) }),
 }))
 
 const createQueryClient = () =>
@@ -23,11 +23,11 @@ const withProvider = (client: QueryClient) =>
 
 describe('useLocalSessionMetrics', () => {
   beforeEach(() => {
-    invoke.mockReset()
+    select.mockReset()
   })
 
   it('returns null when no metrics exist', async () => {
-    invoke.mockResolvedValue([])
+    select.mockResolvedValue([])
 
     const client = createQueryClient()
 
@@ -37,14 +37,16 @@ describe('useLocalSessionMetrics', () => {
 
     await waitFor(() => expect(result.current.loading).toBe(false))
 
-    expect(invoke).toHaveBeenCalledWith('execute_sql', expect.any(Object))
+    expect(select).toHaveBeenCalledWith(expect.stringContaining('FROM session_metrics'), [
+      'session-1',
+    ])
     expect(result.current.metrics).toBeNull()
 
     client.clear()
   })
 
   it('maps database row to UI metrics format', async () => {
-    invoke.mockResolvedValue([
+    select.mockResolvedValue([
       {
         created_at: 1700000000000,
         read_write_ratio: 1.5,
@@ -5,11 +5,16 @@ import { beforeEach, describe, expect, it, vi } from 'vitest'
 import { useInvalidateSessions, useLocalSession, useLocalSessions } from '../../src/hooks/useLocalSessions'
 
 const invoke = vi.fn()
+const select = vi.fn()
 
 vi.mock('@tauri-apps/api/core', () => ({
   invoke: (...args: unknown[]) => invoke(...args),
 }))
 
+vi.mock('../../src/db/client', () => ({
+  getDatabase: () => ({ select: (...args: unknown[]) => select(...args) }),
+}))
+
 const createQueryClient = () =>
   new QueryClient({
     defaultOptions: {
@@ -24,7 +29,7 @@ const withProvider = (client: QueryClient) =>
 
 describe('useLocalSessions', () => {
   beforeEach(() => {
-    invoke.mockReset()
+    select.mockReset()
   })
 
   it('loads sessions and transforms metrics', async () => {
@@ -69,7 +74,7 @@ describe('useLocalSessions', () => {
       duration_minutes: 1,
     }
 
-    invoke.mockResolvedValue([row])
+    select.mockResolvedValue([row])
 
     const client = createQueryClient()
 
@@ -80,12 +85,7 @@ describe('useLocalSessions', () => {
 
       await waitFor(() => expect(result.current.loading).toBe(false))
 
-      expect(invoke).toHaveBeenCalledWith(
-        'execute_sql',
-        expect.objectContaining({
-          params: [],
-        })
-      )
+      expect(select).toHaveBeenCalledWith(expect.stringContaining('FROM agent_sessions'), [])
 
       expect(result.current.sessions).toHaveLength(1)
       const session = result.current.sessions[0]
@@ -123,7 +123,7 @@ describe('useLocalSessions', () => {
   })
 
   it('applies filters to query parameters', async () => {
-    invoke.mockResolvedValue([])
+    select.mockResolvedValue([])
 
     const client = createQueryClient()
 
@@ -148,8 +148,8 @@ describe('useLocalSessions', () => {
 
       await waitFor(() => expect(result.current.loading).toBe(false))
 
-      expect(invoke).toHaveBeenCalledTimes(1)
-      const params = invoke.mock.calls[0][1].params as Array<string | number>
+      expect(select).toHaveBeenCalledTimes(1)
+      const params = select.mock.calls[0][1] as Array<string | number>
       const expectedFrom = new Date(from)
       expectedFrom.setHours(0, 0, 0, 0)
       const expectedTo = new Date(to)
@@ -192,52 +192,50 @@ describe('useInvalidateSessions', () => {
 describe('useLocalSession', () => {
   beforeEach(() => {
     invoke.mockReset()
+    select.mockReset()
   })
 
   it('loads a single session and its content', async () => {
     const now = Date.now()
 
+    select.mockResolvedValue([
+      {
+        id: 'row-9',
+        session_id: 'session-9',
+        provider: 'claude-code',
+        file_name: 'session.jsonl',
+        project_name: 'Project Z',
+        session_start_time: now - 1000,
+        session_end_time: now,
+        file_size: 1024,
+        duration_ms: 1000,
+        processing_status: 'completed',
+        processed_at: now,
+        assessment_status: 'done',
+        assessment_completed_at: now,
+        ai_model_summary: null,
+        ai_model_quality_score: null,
+        ai_model_metadata: null,
+        created_at: now,
+        uploaded_at: now,
+        file_path: '/tmp/session.jsonl',
+        response_latency_ms: 100,
+        task_completion_time_ms: 200,
+        read_write_ratio: 1.5,
+        input_clarity_score: 0.6,
+        task_success_rate: 0.9,
+        iteration_count: 2,
+        process_quality_score: 0.8,
+        used_plan_mode: 1,
+        used_todo_tracking: 0,
+        interruption_rate: 0.2,
+        session_length_minutes: 20,
+        error_count: 1,
+        fatal_errors: 0,
+        improvement_tips: 'tip1\ntip2',
+      },
+    ])
     invoke.mockImplementation(async (command, args) => {
-      if (command === 'execute_sql') {
-        return [
-          {
-            id: 'row-9',
-            session_id: 'session-9',
-            provider: 'claude-code',
-            file_name: 'session.jsonl',
-            project_name: 'Project Z',
-            session_start_time: now - 1000,
-            session_end_time: now,
-            file_size: 1024,
-            duration_ms: 1000,
-            processing_status: 'completed',
-            processed_at: now,
-            assessment_status: 'done',
-            assessment_completed_at: now,
-            ai_model_summary: null,
-            ai_model_quality_score: null,
-            ai_model_metadata: null,
-            created_at: now,
-            uploaded_at: now,
-            file_path: '/tmp/session.jsonl',
-            response_latency_ms: 100,
-            task_completion_time_ms: 200,
-            read_write_ratio: 1.5,
-            input_clarity_score: 0.6,
-            task_success_rate: 0.9,
-            iteration_count: 2,
-            process_quality_score: 0.8,
-            used_plan_mode: 1,
-            used_todo_tracking: 0,
-            interruption_rate: 0.2,
-            session_length_minutes: 20,
-            error_count: 1,
-            fatal_errors: 0,
-            improvement_tips: 'tip1\ntip2',
-          },
-        ]
-      }
-
       if (command === 'read_session_file') {
         expect(args).toEqual({ filePath: '/tmp/session.jsonl' })
         return 'file-content'
@@ -264,10 +262,8 @@ describe('useLocalSession', () => {
   })
 
   it('reports error when session is missing', async () => {
+    select.mockResolvedValue([])
     invoke.mockImplementation(async (command) => {
-      if (command === 'execute_sql') {
-        return []
-      }
       throw new Error(`Unexpected command ${command}`)
     })
 
@@ -3,6 +3,7 @@ import { afterAll, beforeAll, beforeEach, describe, expect, it, vi } from 'vites
 import { useSessionProcessing } from '../../src/hooks/useSessionProcessing'
 
 const mockInvoke = vi.fn()
+const mockSelect = vi.fn()
 const mockGetProcessor = vi.fn()
 const mockProcessMetrics = vi.fn()
 
@@ -10,6 +11,10 @@ vi.mock('@tauri-apps/api/core', () => ({
   invoke: (...args: unknown[]) => mockInvoke(...args),
 }))
 
+vi.mock('../../src/db/client', () => ({
+  getDatabase: () => ({ select: (...args: unknown[]) => mockSelect(...args) }),
+}))
+
 vi.mock('@guidemode/session-processing/processors', () => ({
   ProcessorRegistry: class {
     getProcessor(provider: string) {
@@ -45,6 +50,7 @@ describe('useSessionProcessing', () => {
 
   beforeEach(() => {
     mockInvoke.mockReset()
+    mockSelect.mockReset()
     mockGetProcessor.mockReset()
     mockProcessMetrics.mockReset()
     mockInvoke.mockClear()
@@ -117,7 +123,7 @@ describe('useSessionProcessing', () => {
     ]
 
     // Mock session details query to return empty (no git diff will be fetched)
-    mockInvoke.mockResolvedValueOnce([])
+    mockSelect.mockResolvedValueOnce([])
 
     mockProcessMetrics.mockResolvedValue(metricsResults)
     mockGetProcessor.mockReturnValue({ processMetrics: mockProcessMetrics })
@@ -147,90 +153,23 @@ describe('useSessionProcessing', () => {
       provider: 'claude-code',
     })
 
-    expect(mockInvoke).toHaveBeenCalledTimes(3)
-
-    // First call is to fetch session details for git diff
-    const sessionDetailsCall = mockInvoke.mock.calls[0]
-    expect(sessionDetailsCall[0]).toBe('execute_sql')
-    expect(sessionDetailsCall[1].sql).toContain('SELECT cwd, first_commit_hash')
-
-    const insertCall = mockInvoke.mock.calls[1]
-    expect(insertCall[0]).toBe('execute_sql')
-    expect(insertCall[1]).toMatchObject({
-      sql: expect.stringContaining('INSERT OR REPLACE INTO session_metrics'),
-    })
-    expect(insertCall[1].params).toEqual([
-      '00000000-0000-0000-0000-000000000123',
+    // Session details are read to fetch the git diff
+    expect(mockSelect).toHaveBeenCalledWith(expect.stringContaining('SELECT cwd, first_commit_hash'), [
       'session-1',
-      'claude-code',
-      FIXED_TIME.getTime(),
-      120,
-      450,
-      3,
-      1.5,
-      0.7,
-      5,
-      2,
-      4,
-      2,
-      'timeout,api',
-      'request failed',
-      1,
-      0,
-      0.1,
-      15,
-      2,
-      6,
-      0.8,
-      3,
-      0.9,
-      1,
-      0,
-      2,
-      5,
-      7,
-      1,
-      2,
-      'phrase-a,phrase-b',
-      null,
-      null,
-      null,
-      'tip1\ntip2',
-      null,
-      'tip1\ntip2',
-      null,
-      null,
-      null,
-      null,
-      null,
-      null,
-      null,
-      null,
-      null,
-      null,
-      null,
-      null, // git_diff_improvement_tips (11th git metric)
-      // Context management metrics (added in migrations 017-020)
-      null, // total_input_tokens
-      null, // total_output_tokens
-      null, // total_cache_created
-      null, // total_cache_read
-      null, // context_length
-      null, // context_window_size
-      null, // context_utilization_percent
-      null, // compact_event_count
-      null, // compact_event_steps
-      null, // avg_tokens_per_message
-      null, // messages_until_first_compact
-      null, // context_improvement_tips
-      FIXED_TIME.getTime(),
     ])
 
-    const updateCall = mockInvoke.mock.calls[2]
-    expect(updateCall[0]).toBe('execute_sql')
-    expect(updateCall[1]).toEqual({
-      sql: expect.stringContaining('UPDATE agent_sessions'),
-      params: [FIXED_TIME.getTime(), 'session-1'],
+    expect(mockInvoke).toHaveBeenCalledTimes(1)
+    const [command, args] = mockInvoke.mock.calls[0]
+    expect(command).toBe('store_processor_metrics')
+    expect(args).toMatchObject({
+      sessionId: 'session-1',
+      provider: 'claude-code',
+      metrics: {
+        input_clarity_score: 0.7,
+        process_quality_score: 0.9,
+        quality_improvement_tips: 'tip1\ntip2',
+        improvement_tips: 'tip1\ntip2',
+      },
     })
   })
 
@@ -253,5 +192,6 @@ describe('useSessionProcessing', () => {
     expect(result.current.error).toBe('No processor found for provider: unknown')
     expect(result.current.processing).toBe(false)
     expect(mockInvoke).not.toHaveBeenCalled()
+    expect(mockSelect).not.toHaveBeenCalled()
   })
 })